@@ -8,10 +8,10 @@ use crate::{
 use actix::prelude::*;
 use serde_json::json;
 use std::{fmt, time::Duration};
-use witnet_config::defaults::PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
 use witnet_data_structures::{
-    chain::{tapi::current_active_wips, DataRequestOutput, Hashable},
+    chain::{tapi::current_active_wips, ConsensusConstants, DataRequestOutput, Hashable},
     error::TransactionError,
+    get_environment,
     proto::ProtobufConvert,
     radon_error::RadonErrors,
     transaction::DRTransaction,
@@ -274,11 +274,10 @@ fn deserialize_and_validate_dr_bytes(
     match DataRequestOutput::from_pb_bytes(dr_bytes) {
         Err(e) => Err(DrSenderError::Deserialization { msg: e.to_string() }),
         Ok(dr_output) => {
-            // TODO: read from consensus constants
-            let collateral_minimum = 1_000_000_000;
-            // TODO: read from consensus_constants
+            let consensus_constants = ConsensusConstants::for_environment(get_environment());
+            let collateral_minimum = consensus_constants.collateral_minimum;
             let required_reward_collateral_ratio =
-                PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
+                ConsensusConstants::minimum_reward_collateral_ratio();
             validate_data_request_output(
                 &dr_output,
                 collateral_minimum,
@@ -40,14 +40,19 @@
 //! ```
 use std::convert::TryFrom;
 use std::{
-    collections::HashSet, fmt, marker::PhantomData, net::SocketAddr, path::PathBuf, time::Duration,
+    collections::{BTreeMap, HashSet},
+    fmt,
+    marker::PhantomData,
+    net::SocketAddr,
+    path::{Path, PathBuf},
+    time::Duration,
 };
 
 use partial_struct::PartialStruct;
 use serde::{de, Deserialize, Deserializer, Serialize};
-use witnet_crypto::hash::HashFunction;
+use witnet_crypto::{hash::HashFunction, key::KeySpec};
 use witnet_data_structures::chain::{ConsensusConstants, Environment, PartialConsensusConstants};
-use witnet_data_structures::witnessing::WitnessingConfig;
+use witnet_data_structures::witnessing::{RetrievalSecret, RetrievalSecrets, WitnessingConfig};
 use witnet_protected::ProtectedString;
 
 use crate::{
@@ -150,7 +155,8 @@ pub struct Connections {
     /// peers should bind to
     pub server_addr: SocketAddr,
 
-    /// Public address
+    /// Public address, advertised to other peers as the address of our server. If it is not set,
+    /// the address at which enough peers see us is advertised instead
     #[partial_struct(skip)]
     #[partial_struct(serde(default))]
     pub public_addr: Option<SocketAddr>,
@@ -197,6 +203,16 @@ pub struct Connections {
     ))]
     pub discovery_peers_period: Duration,
 
+    /// Minimum time between two "get peers" messages sent to the same peer. Outbound sessions
+    /// ask for peers as soon as they are consolidated, and then every `discovery_peers_period`
+    #[partial_struct(serde(
+        default,
+        serialize_with = "to_secs",
+        deserialize_with = "from_secs",
+        rename = "get_peers_min_interval_seconds"
+    ))]
+    pub get_peers_min_interval: Duration,
+
     /// Period of the peers melt task
     #[partial_struct(serde(
         default,
@@ -257,6 +273,75 @@ pub struct Connections {
 
     /// Limit the number of requested blocks that will be processed as one batch
     pub requested_blocks_batch_limit: u32,
+
+    /// Period of the outbound rotation task, which replaces the worst scoring outbound peer.
+    /// Set to 0 to disable outbound rotation
+    #[partial_struct(serde(
+        default,
+        serialize_with = "to_secs",
+        deserialize_with = "from_secs",
+        rename = "outbound_rotation_period_seconds"
+    ))]
+    pub outbound_rotation_period: Duration,
+
+    /// Outbound sessions younger than this are never rotated
+    #[partial_struct(serde(
+        default,
+        serialize_with = "to_secs",
+        deserialize_with = "from_secs",
+        rename = "outbound_rotation_min_lifetime_seconds"
+    ))]
+    pub outbound_rotation_min_lifetime: Duration,
+
+    /// Outbound rotation never leaves fewer consolidated outbound sessions than this
+    pub outbound_rotation_min_outbound: u16,
+
+    /// When more than this many bytes are pending to be sent to a peer, low priority messages
+    /// (inventory announcements) to that peer are dropped
+    pub write_queue_high_water_mark: usize,
+
+    /// Sessions whose pending bytes would exceed this limit even with protocol-critical messages
+    /// only are closed
+    pub write_queue_hard_limit: usize,
+
+    /// Compress the messages sent to the peers that support it. If disabled, the support is not
+    /// advertised in the handshake and compressed messages are refused
+    pub compression: bool,
+
+    /// Messages smaller than this many bytes are sent uncompressed
+    pub compression_threshold: usize,
+
+    /// Period after which the scores earned by peers with the outcomes of their connections are
+    /// halved
+    #[partial_struct(serde(
+        default,
+        serialize_with = "to_secs",
+        deserialize_with = "from_secs",
+        rename = "peer_score_half_life_seconds"
+    ))]
+    pub peer_score_half_life: Duration,
+
+    /// Percentage of the outbound peers that are chosen among the untried or low scoring ones
+    /// regardless of their score, so that new peers keep being explored
+    pub peer_exploration_percentage: u8,
+
+    /// Number of peers that must see us at the same IP address for it to be advertised as ours,
+    /// together with the port of `server_addr`. Only used if `public_addr` is not set. Set to 0
+    /// to disable the discovery of our external address
+    pub external_address_quorum: u16,
+
+    /// Only the addresses that we had a session with in this period are shared with the peers
+    /// that ask for ours
+    #[partial_struct(serde(
+        default,
+        serialize_with = "to_secs",
+        deserialize_with = "from_secs",
+        rename = "shared_peers_max_age_seconds"
+    ))]
+    pub shared_peers_max_age: Duration,
+
+    /// Maximum number of addresses shared with a peer every time that it asks for ours
+    pub shared_peers_limit: usize,
 }
 
 /// Witnessing-specific configuration.
@@ -280,6 +365,15 @@ pub struct Witnessing {
     /// and we are taking as small of a risk as possible when committing to specially crafted data
     /// requests that may be potentially ill-intended.
     pub proxies: Vec<String>,
+
+    /// Path to a TOML file with the secrets that the URLs and headers of retrievals can refer to,
+    /// such as API keys. A secret in the `[api_key.coinlayer]` table replaces the
+    /// `{{api_key:coinlayer}}` placeholder, and has a `value` and the `hosts` that it can be sent
+    /// to. Retrievals with placeholders that have no secret here fail with a "missing secret"
+    /// error, and those to other hosts with a "secret host not allowed" one.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub secrets_file: Option<PathBuf>,
 }
 
 /// Available storage backends
@@ -292,6 +386,18 @@ pub enum StorageBackend {
     RocksDB,
 }
 
+/// What to do when unlocking a wallet would exceed the number of allowed sessions
+#[derive(Debug, Default, Deserialize, Serialize, Clone, Copy, PartialEq, Eq)]
+pub enum SessionLimitPolicy {
+    /// Refuse to create the new session
+    #[default]
+    #[serde(rename = "reject")]
+    Reject,
+    /// Close the least recently active session to make room for the new one
+    #[serde(rename = "evict-least-recently-active")]
+    EvictLeastRecentlyActive,
+}
+
 /// Storage-specific configuration
 #[derive(PartialStruct, Debug, Clone, PartialEq, Eq)]
 #[partial_struct(derive(Deserialize, Serialize, Default, Debug, Clone, PartialEq, Eq))]
@@ -668,6 +774,10 @@ impl Connections {
                 .discovery_peers_period
                 .to_owned()
                 .unwrap_or_else(|| defaults.connections_discovery_peers_period()),
+            get_peers_min_interval: config
+                .get_peers_min_interval
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_get_peers_min_interval()),
             check_melted_peers_period: config
                 .check_melted_peers_period
                 .to_owned()
@@ -711,6 +821,54 @@ impl Connections {
                 .requested_blocks_batch_limit
                 .to_owned()
                 .unwrap_or_else(|| defaults.connections_requested_blocks_batch_limit()),
+            outbound_rotation_period: config
+                .outbound_rotation_period
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_outbound_rotation_period()),
+            outbound_rotation_min_lifetime: config
+                .outbound_rotation_min_lifetime
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_outbound_rotation_min_lifetime()),
+            outbound_rotation_min_outbound: config
+                .outbound_rotation_min_outbound
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_outbound_rotation_min_outbound()),
+            write_queue_high_water_mark: config
+                .write_queue_high_water_mark
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_write_queue_high_water_mark()),
+            write_queue_hard_limit: config
+                .write_queue_hard_limit
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_write_queue_hard_limit()),
+            compression: config
+                .compression
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_compression()),
+            compression_threshold: config
+                .compression_threshold
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_compression_threshold()),
+            peer_score_half_life: config
+                .peer_score_half_life
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_peer_score_half_life()),
+            peer_exploration_percentage: config
+                .peer_exploration_percentage
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_peer_exploration_percentage()),
+            external_address_quorum: config
+                .external_address_quorum
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_external_address_quorum()),
+            shared_peers_max_age: config
+                .shared_peers_max_age
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_shared_peers_max_age()),
+            shared_peers_limit: config
+                .shared_peers_limit
+                .to_owned()
+                .unwrap_or_else(|| defaults.connections_shared_peers_limit()),
         }
     }
 
@@ -724,6 +882,7 @@ impl Connections {
             bootstrap_peers_period: Some(self.bootstrap_peers_period),
             storage_peers_period: Some(self.storage_peers_period),
             discovery_peers_period: Some(self.discovery_peers_period),
+            get_peers_min_interval: Some(self.get_peers_min_interval),
             check_melted_peers_period: Some(self.check_melted_peers_period),
             feeler_peers_period: Some(self.feeler_peers_period),
             handshake_timeout: Some(self.handshake_timeout),
@@ -735,6 +894,18 @@ impl Connections {
             reject_sybil_inbounds: Some(self.reject_sybil_inbounds),
             reject_sybil_inbounds_range_limit: Some(self.reject_sybil_inbounds_range_limit),
             requested_blocks_batch_limit: Some(self.requested_blocks_batch_limit),
+            outbound_rotation_period: Some(self.outbound_rotation_period),
+            outbound_rotation_min_lifetime: Some(self.outbound_rotation_min_lifetime),
+            outbound_rotation_min_outbound: Some(self.outbound_rotation_min_outbound),
+            write_queue_high_water_mark: Some(self.write_queue_high_water_mark),
+            write_queue_hard_limit: Some(self.write_queue_hard_limit),
+            compression: Some(self.compression),
+            compression_threshold: Some(self.compression_threshold),
+            peer_score_half_life: Some(self.peer_score_half_life),
+            peer_exploration_percentage: Some(self.peer_exploration_percentage),
+            external_address_quorum: Some(self.external_address_quorum),
+            shared_peers_max_age: Some(self.shared_peers_max_age),
+            shared_peers_limit: Some(self.shared_peers_limit),
         }
     }
 }
@@ -914,8 +1085,23 @@ pub struct Wallet {
     pub testnet: bool,
     /// Websockets server address.
     pub server_addr: SocketAddr,
+    /// HTTP server address, for JSON-RPC clients that cannot use WebSockets. The HTTP server is
+    /// only started if this is set.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub http_server_addr: Option<SocketAddr>,
+    /// Browser origins that are allowed to call the JSON-RPC servers, such as
+    /// `https://wallet.example.com`, or `*` for any. When empty, every browser is rejected.
+    /// Origins allowed through `*` never get credentials, and are not echoed back.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub cors_allowed_origins: Vec<String>,
+    /// Whether browsers may send credentials along with cross-origin requests coming from an
+    /// explicitly listed origin.
+    pub cors_allow_credentials: bool,
     /// Witnet node server address.
-    /// If more than one address is provided, will choose one at random.
+    /// If more than one address is provided, the first one is used, and the others are failed over
+    /// to in order whenever the one in use stops answering health checks.
     #[partial_struct(skip)]
     #[partial_struct(serde(default))]
     #[partial_struct(serde(deserialize_with = "deserialize_one_or_many"))]
@@ -944,6 +1130,10 @@ pub struct Wallet {
     pub id_hash_iterations: u32,
     /// Master Key-generation hash function. Default `Sha256`.
     pub id_hash_function: HashFunction,
+    /// Key spec of the derivation paths of the wallets created from now on. Default `wip3`.
+    pub key_spec: KeySpec,
+    /// Coin type of the derivation paths of the wallets created from now on. Default `4919`.
+    pub coin_type: u32,
     /// Lifetime in seconds of an unlocked wallet session id.
     pub session_expires_in: u64,
     /// Duration in milliseconds after which outgoing request should timeout.
@@ -956,6 +1146,155 @@ pub struct Wallet {
     /// If a transaction has not been included in a block for this amount of seconds, mark the UTXOs
     /// as available again.
     pub pending_transactions_timeout_seconds: u64,
+    /// Enable the stateless `derive_addresses` method. As it receives seed phrases and master keys
+    /// from clients, it is disabled by default.
+    pub enable_derive_addresses: bool,
+    /// Maximum number of notifications that can be queued for a subscribed client that is not
+    /// reading them. When the queue is full, the overflow policy of the subscription applies.
+    pub notifications_queue_size: usize,
+    /// How many of the last notifications of each session are kept, so that clients that
+    /// resubscribe after a disconnection can get the ones they missed.
+    pub notifications_replay_size: usize,
+    /// How many health checks in a row have to fail before switching to the next node in
+    /// `node_url`.
+    pub node_failover_threshold: u32,
+    /// Minimum time in seconds between two automatic switches of node, so as to avoid flapping.
+    pub node_failover_min_interval: u64,
+    /// Number of connections to the node that requests are spread over, so that slow requests do
+    /// not hold back the rest. Subscriptions always use the first connection.
+    pub node_pool_size: usize,
+    /// How many epochs can pass without the node notifying blocks or reporting a newer epoch
+    /// before clients are alerted that it is stalled.
+    pub node_stall_epochs: u32,
+    /// How many epochs the current epoch reported by the node can be ahead of the one given by the
+    /// clock of the wallet before clients are alerted about the skew between their clocks.
+    pub node_clock_skew_epochs: u32,
+    /// Execution deadlines in milliseconds of long-running methods, by method name
+    /// (`run_rad_request`, `export_master_key`, `unlock_wallet` for the synchronization it starts,
+    /// `resync_wallet` and `pin_sync`), and of the jobs run on notifications from the node
+    /// (`consolidate_blocks` for superblocks, and `node_sync` for the synchronization started when
+    /// the node is synced). `run_rad_request` and `export_master_key` default to
+    /// `requests_timeout`, and the rest have no deadline unless one is configured here.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub execution_timeouts: BTreeMap<String, u64>,
+    /// Cache the responses of data sources when running data requests locally with
+    /// `run_rad_request`. Never used for anything consensus-related.
+    pub retrieval_cache_enabled: bool,
+    /// Seconds after which a cached response is revalidated with the data source.
+    pub retrieval_cache_ttl: u64,
+    /// Maximum total size in bytes of the cached responses.
+    pub retrieval_cache_max_size: u64,
+    /// Maximum number of concurrent sessions of the same wallet.
+    pub max_sessions_per_wallet: usize,
+    /// Maximum number of concurrent sessions across all wallets.
+    pub max_sessions: usize,
+    /// What to do when unlocking a wallet would exceed `max_sessions_per_wallet` or
+    /// `max_sessions`.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub session_limit_policy: SessionLimitPolicy,
+    /// Check the internal consistency of every block received from the node (merkle roots,
+    /// signature and eligibility proof structure) before applying it, so that a compromised node
+    /// cannot feed the wallet forged blocks.
+    pub verify_block_headers: bool,
+    /// Lock every unlocked wallet after this many seconds without any authenticated request,
+    /// regardless of session expiration. Disabled if not set.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub idle_lock_after: Option<u64>,
+    /// How many times the words of a seed backup can be wrongly confirmed before the wallet
+    /// waiting for that confirmation is deleted.
+    pub seed_backup_confirmation_attempts: u32,
+    /// Maximum number of recipients of a value transfer transaction created by the wallet. Batches
+    /// are also bounded by the weight limit of value transfers, whichever allows fewer outputs.
+    pub max_vtt_outputs: u32,
+    /// Check the integrity of the records of every wallet when it is unlocked, repairing the ones
+    /// that can be derived from others, e.g. after a power loss interrupted writing them.
+    pub check_integrity_on_unlock: bool,
+    /// Estimated cost of executing the scripts of a data request, in the units of the RADON cost
+    /// table, above which `estimate_data_req_cost` warns that the request is expensive for
+    /// witnesses.
+    pub data_request_cost_warning_threshold: u64,
+    /// Maximum number of addresses of every unlocked wallet that are kept in memory to tell which
+    /// outputs of a block belong to it. The addresses beyond it are looked up in the database.
+    pub address_index_max_entries: u32,
+    /// Minimum fee in nanowits of the transactions created by the wallet, unless the fee policy is
+    /// explicitly overridden when creating them.
+    pub minimum_fee: u64,
+    /// Minimum fee in nanowits per thousand weight units of the transactions created by the wallet,
+    /// unless the fee policy is explicitly overridden when creating them.
+    pub minimum_fee_per_kilo_weight: u64,
+    /// Value in nanowits below which an output is considered dust. Recipients cannot be paid less
+    /// than this, and smaller change is added to the fee instead of creating a change output.
+    pub dust_threshold: u64,
+    /// Value in nanowits by which the inputs of a transaction may exceed its outputs and fee when
+    /// the wallet looks for inputs that need no change output. The excess is added to the fee.
+    pub changeless_tolerance: u64,
+    /// Nodes that the wallet visits at most when looking for inputs that need no change output,
+    /// before falling back to the usual selection. Zero disables the search.
+    pub changeless_search_budget: u32,
+    /// Refuse to generate external addresses that are further ahead of the last one that received
+    /// a payment than the number of addresses scanned ahead when synchronizing, instead of only
+    /// warning about them. Other wallets recovering from the same seed could miss payments to them.
+    pub refuse_addresses_beyond_gap_limit: bool,
+    /// Oldest JSON-RPC API version that clients can declare when unlocking a wallet. Clients that
+    /// declare an older one are told to upgrade, while clients that declare a version between this
+    /// one and the current one get sessions in compatibility mode.
+    pub min_client_api_version: u32,
+    /// Seconds during which the responses of the requests made with an idempotency key are kept
+    /// to answer their retries. Retries made later run the request again.
+    pub idempotency_key_retention: u64,
+    /// Maximum number of events waiting to be delivered to the webhook of each wallet, and also of
+    /// dead letters kept for it. Events that do not fit are dead-lettered right away.
+    pub webhook_outbox_size: u32,
+    /// Attempts to deliver an event to a webhook before it is dead-lettered.
+    pub webhook_max_attempts: u32,
+    /// Delay in milliseconds before retrying a failed webhook delivery, which is doubled after
+    /// every failed attempt.
+    pub webhook_retry_delay: u64,
+    /// Epochs grouped into every entry of the balance history of a wallet. Only the last balance of
+    /// the epochs in a group is kept.
+    pub balance_history_granularity: u32,
+    /// Days after which the entries of the balance history of a wallet are downsampled from one
+    /// per hour to one per day.
+    pub balance_history_daily_after: u32,
+    /// Path to a credentials file with the passwords of the wallets to unlock at startup, in a
+    /// service session that never expires. The file must not be accessible by other users.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub unlock_credentials_path: Option<PathBuf>,
+    /// Prune the old movement metadata of every unlocked wallet that has a retention policy every
+    /// this many seconds. Wallets are only pruned on demand if not set.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub prune_interval: Option<u64>,
+    /// Directory to which the details removed from pruned movements are archived, encrypted with
+    /// the key of their wallet. Defaults to a `pruned` directory inside `db_path`.
+    #[partial_struct(skip)]
+    #[partial_struct(serde(default))]
+    pub prune_archive_path: Option<PathBuf>,
+    /// Fail the requests to the node whose responses do not have the fields that the wallet
+    /// expects, instead of just warning about it. Meant for CI environments that need to catch
+    /// version skew between the node and the wallet.
+    pub strict_node_schema: bool,
+    /// Requests of clients that can be handled at the same time. Requests beyond this are refused
+    /// with an `Overloaded` error instead of being queued.
+    pub interactive_queue_limit: usize,
+    /// Blocks and superblocks that can be queued for processing. Blocks notified beyond this are
+    /// dropped, and the wallets synchronized once the queue drains.
+    pub block_queue_limit: usize,
+    /// Synchronizations and periodic jobs that can be queued. Periodic jobs beyond this are
+    /// skipped until their next turn.
+    pub maintenance_queue_limit: usize,
+    /// Light requests that each session can have in flight at the same time. Requests beyond
+    /// this are refused with a `TooManyRequests` error.
+    pub session_request_limit: usize,
+    /// Heavy requests, such as running data requests or resynchronizing a wallet, that each
+    /// session can have in flight at the same time.
+    pub session_heavy_request_limit: usize,
+    /// Heavy requests that all the sessions together can have in flight at the same time.
+    pub heavy_request_limit: usize,
 }
 
 impl Wallet {
@@ -967,6 +1306,11 @@ impl Wallet {
             server_addr: config
                 .server_addr
                 .unwrap_or_else(|| defaults.wallet_server_addr()),
+            http_server_addr: config.http_server_addr,
+            cors_allowed_origins: config.cors_allowed_origins.clone(),
+            cors_allow_credentials: config
+                .cors_allow_credentials
+                .unwrap_or_else(|| defaults.wallet_cors_allow_credentials()),
             node_url: config.node_url.clone(),
             node_sync_batch_size: config.node_sync_batch_size.unwrap_or(50),
             concurrency: config.concurrency,
@@ -999,6 +1343,12 @@ impl Wallet {
                 .id_hash_function
                 .clone()
                 .unwrap_or_else(|| defaults.wallet_id_hash_function()),
+            key_spec: config
+                .key_spec
+                .unwrap_or_else(|| defaults.wallet_key_spec()),
+            coin_type: config
+                .coin_type
+                .unwrap_or_else(|| defaults.wallet_coin_type()),
             sync_address_batch_length: config
                 .sync_address_batch_length
                 .unwrap_or_else(|| defaults.wallet_sync_address_batch_length()),
@@ -1008,6 +1358,129 @@ impl Wallet {
             pending_transactions_timeout_seconds: config
                 .pending_transactions_timeout_seconds
                 .unwrap_or_else(|| defaults.wallet_pending_transactions_timeout_seconds()),
+            enable_derive_addresses: config
+                .enable_derive_addresses
+                .unwrap_or_else(|| defaults.wallet_enable_derive_addresses()),
+            notifications_queue_size: config
+                .notifications_queue_size
+                .unwrap_or_else(|| defaults.wallet_notifications_queue_size()),
+            notifications_replay_size: config
+                .notifications_replay_size
+                .unwrap_or_else(|| defaults.wallet_notifications_replay_size()),
+            node_failover_threshold: config
+                .node_failover_threshold
+                .unwrap_or_else(|| defaults.wallet_node_failover_threshold()),
+            node_failover_min_interval: config
+                .node_failover_min_interval
+                .unwrap_or_else(|| defaults.wallet_node_failover_min_interval()),
+            node_pool_size: config
+                .node_pool_size
+                .unwrap_or_else(|| defaults.wallet_node_pool_size()),
+            node_stall_epochs: config
+                .node_stall_epochs
+                .unwrap_or_else(|| defaults.wallet_node_stall_epochs()),
+            node_clock_skew_epochs: config
+                .node_clock_skew_epochs
+                .unwrap_or_else(|| defaults.wallet_node_clock_skew_epochs()),
+            execution_timeouts: config.execution_timeouts.clone(),
+            retrieval_cache_enabled: config
+                .retrieval_cache_enabled
+                .unwrap_or_else(|| defaults.wallet_retrieval_cache_enabled()),
+            retrieval_cache_ttl: config
+                .retrieval_cache_ttl
+                .unwrap_or_else(|| defaults.wallet_retrieval_cache_ttl()),
+            retrieval_cache_max_size: config
+                .retrieval_cache_max_size
+                .unwrap_or_else(|| defaults.wallet_retrieval_cache_max_size()),
+            max_sessions_per_wallet: config
+                .max_sessions_per_wallet
+                .unwrap_or_else(|| defaults.wallet_max_sessions_per_wallet()),
+            max_sessions: config
+                .max_sessions
+                .unwrap_or_else(|| defaults.wallet_max_sessions()),
+            session_limit_policy: config.session_limit_policy,
+            verify_block_headers: config
+                .verify_block_headers
+                .unwrap_or_else(|| defaults.wallet_verify_block_headers()),
+            idle_lock_after: config.idle_lock_after,
+            seed_backup_confirmation_attempts: config
+                .seed_backup_confirmation_attempts
+                .unwrap_or_else(|| defaults.wallet_seed_backup_confirmation_attempts()),
+            max_vtt_outputs: config
+                .max_vtt_outputs
+                .unwrap_or_else(|| defaults.wallet_max_vtt_outputs()),
+            check_integrity_on_unlock: config
+                .check_integrity_on_unlock
+                .unwrap_or_else(|| defaults.wallet_check_integrity_on_unlock()),
+            data_request_cost_warning_threshold: config
+                .data_request_cost_warning_threshold
+                .unwrap_or_else(|| defaults.wallet_data_request_cost_warning_threshold()),
+            address_index_max_entries: config
+                .address_index_max_entries
+                .unwrap_or_else(|| defaults.wallet_address_index_max_entries()),
+            minimum_fee: config
+                .minimum_fee
+                .unwrap_or_else(|| defaults.wallet_minimum_fee()),
+            minimum_fee_per_kilo_weight: config
+                .minimum_fee_per_kilo_weight
+                .unwrap_or_else(|| defaults.wallet_minimum_fee_per_kilo_weight()),
+            dust_threshold: config
+                .dust_threshold
+                .unwrap_or_else(|| defaults.wallet_dust_threshold()),
+            changeless_tolerance: config
+                .changeless_tolerance
+                .unwrap_or_else(|| defaults.wallet_changeless_tolerance()),
+            changeless_search_budget: config
+                .changeless_search_budget
+                .unwrap_or_else(|| defaults.wallet_changeless_search_budget()),
+            refuse_addresses_beyond_gap_limit: config
+                .refuse_addresses_beyond_gap_limit
+                .unwrap_or_else(|| defaults.wallet_refuse_addresses_beyond_gap_limit()),
+            min_client_api_version: config
+                .min_client_api_version
+                .unwrap_or_else(|| defaults.wallet_min_client_api_version()),
+            idempotency_key_retention: config
+                .idempotency_key_retention
+                .unwrap_or_else(|| defaults.wallet_idempotency_key_retention()),
+            webhook_outbox_size: config
+                .webhook_outbox_size
+                .unwrap_or_else(|| defaults.wallet_webhook_outbox_size()),
+            webhook_max_attempts: config
+                .webhook_max_attempts
+                .unwrap_or_else(|| defaults.wallet_webhook_max_attempts()),
+            webhook_retry_delay: config
+                .webhook_retry_delay
+                .unwrap_or_else(|| defaults.wallet_webhook_retry_delay()),
+            balance_history_granularity: config
+                .balance_history_granularity
+                .unwrap_or_else(|| defaults.wallet_balance_history_granularity()),
+            balance_history_daily_after: config
+                .balance_history_daily_after
+                .unwrap_or_else(|| defaults.wallet_balance_history_daily_after()),
+            unlock_credentials_path: config.unlock_credentials_path.clone(),
+            prune_interval: config.prune_interval,
+            prune_archive_path: config.prune_archive_path.clone(),
+            strict_node_schema: config
+                .strict_node_schema
+                .unwrap_or_else(|| defaults.wallet_strict_node_schema()),
+            interactive_queue_limit: config
+                .interactive_queue_limit
+                .unwrap_or_else(|| defaults.wallet_interactive_queue_limit()),
+            block_queue_limit: config
+                .block_queue_limit
+                .unwrap_or_else(|| defaults.wallet_block_queue_limit()),
+            maintenance_queue_limit: config
+                .maintenance_queue_limit
+                .unwrap_or_else(|| defaults.wallet_maintenance_queue_limit()),
+            session_request_limit: config
+                .session_request_limit
+                .unwrap_or_else(|| defaults.wallet_session_request_limit()),
+            session_heavy_request_limit: config
+                .session_heavy_request_limit
+                .unwrap_or_else(|| defaults.wallet_session_heavy_request_limit()),
+            heavy_request_limit: config
+                .heavy_request_limit
+                .unwrap_or_else(|| defaults.wallet_heavy_request_limit()),
         }
     }
 
@@ -1015,6 +1488,9 @@ impl Wallet {
         PartialWallet {
             testnet: self.testnet,
             server_addr: Some(self.server_addr),
+            http_server_addr: self.http_server_addr,
+            cors_allowed_origins: self.cors_allowed_origins.clone(),
+            cors_allow_credentials: Some(self.cors_allow_credentials),
             node_url: self.node_url.clone(),
             node_sync_batch_size: Some(self.node_sync_batch_size),
             concurrency: self.concurrency,
@@ -1027,11 +1503,58 @@ impl Wallet {
             master_key_salt: None, // master_key_salt should not be exported
             id_hash_iterations: Some(self.id_hash_iterations),
             id_hash_function: Some(self.id_hash_function.clone()),
+            key_spec: Some(self.key_spec),
+            coin_type: Some(self.coin_type),
             session_expires_in: Some(self.session_expires_in),
             requests_timeout: Some(self.requests_timeout),
             sync_address_batch_length: Some(self.sync_address_batch_length),
             use_unconfirmed_utxos: Some(self.use_unconfirmed_utxos),
             pending_transactions_timeout_seconds: Some(self.pending_transactions_timeout_seconds),
+            enable_derive_addresses: Some(self.enable_derive_addresses),
+            notifications_queue_size: Some(self.notifications_queue_size),
+            notifications_replay_size: Some(self.notifications_replay_size),
+            node_failover_threshold: Some(self.node_failover_threshold),
+            node_failover_min_interval: Some(self.node_failover_min_interval),
+            node_pool_size: Some(self.node_pool_size),
+            node_stall_epochs: Some(self.node_stall_epochs),
+            node_clock_skew_epochs: Some(self.node_clock_skew_epochs),
+            execution_timeouts: self.execution_timeouts.clone(),
+            retrieval_cache_enabled: Some(self.retrieval_cache_enabled),
+            retrieval_cache_ttl: Some(self.retrieval_cache_ttl),
+            retrieval_cache_max_size: Some(self.retrieval_cache_max_size),
+            max_sessions_per_wallet: Some(self.max_sessions_per_wallet),
+            max_sessions: Some(self.max_sessions),
+            session_limit_policy: self.session_limit_policy,
+            verify_block_headers: Some(self.verify_block_headers),
+            idle_lock_after: self.idle_lock_after,
+            seed_backup_confirmation_attempts: Some(self.seed_backup_confirmation_attempts),
+            max_vtt_outputs: Some(self.max_vtt_outputs),
+            check_integrity_on_unlock: Some(self.check_integrity_on_unlock),
+            data_request_cost_warning_threshold: Some(self.data_request_cost_warning_threshold),
+            address_index_max_entries: Some(self.address_index_max_entries),
+            minimum_fee: Some(self.minimum_fee),
+            minimum_fee_per_kilo_weight: Some(self.minimum_fee_per_kilo_weight),
+            dust_threshold: Some(self.dust_threshold),
+            changeless_tolerance: Some(self.changeless_tolerance),
+            changeless_search_budget: Some(self.changeless_search_budget),
+            refuse_addresses_beyond_gap_limit: Some(self.refuse_addresses_beyond_gap_limit),
+            min_client_api_version: Some(self.min_client_api_version),
+            idempotency_key_retention: Some(self.idempotency_key_retention),
+            webhook_outbox_size: Some(self.webhook_outbox_size),
+            webhook_max_attempts: Some(self.webhook_max_attempts),
+            webhook_retry_delay: Some(self.webhook_retry_delay),
+            balance_history_granularity: Some(self.balance_history_granularity),
+            balance_history_daily_after: Some(self.balance_history_daily_after),
+            unlock_credentials_path: self.unlock_credentials_path.clone(),
+            prune_interval: self.prune_interval,
+            prune_archive_path: self.prune_archive_path.clone(),
+            strict_node_schema: Some(self.strict_node_schema),
+            interactive_queue_limit: Some(self.interactive_queue_limit),
+            block_queue_limit: Some(self.block_queue_limit),
+            maintenance_queue_limit: Some(self.maintenance_queue_limit),
+            session_request_limit: Some(self.session_request_limit),
+            session_heavy_request_limit: Some(self.session_heavy_request_limit),
+            heavy_request_limit: Some(self.heavy_request_limit),
         }
     }
 }
@@ -1120,6 +1643,7 @@ impl Witnessing {
                 .proxies
                 .clone()
                 .unwrap_or_else(|| defaults.witnessing_proxies()),
+            secrets_file: config.secrets_file.clone(),
         }
     }
 
@@ -1128,6 +1652,7 @@ impl Witnessing {
             allow_unproxied: Some(self.allow_unproxied),
             paranoid_percentage: Some(self.paranoid_percentage),
             proxies: Some(self.proxies.clone()),
+            secrets_file: self.secrets_file.clone(),
         }
     }
 
@@ -1164,13 +1689,52 @@ impl Witnessing {
 
         let paranoid = f32::from(self.paranoid_percentage) / 100.0;
 
+        let secrets = match &self.secrets_file {
+            Some(path) => {
+                let secrets = load_retrieval_secrets(path).unwrap_or_else(|e| {
+                    panic!(
+                        "Failed to load the retrieval secrets from {}: {}",
+                        path.display(),
+                        e
+                    )
+                });
+                log::info!(
+                    "Loaded {} retrieval secrets: {:?}",
+                    secrets.len(),
+                    secrets.keys()
+                );
+
+                RetrievalSecrets::new(secrets)
+            }
+            None => RetrievalSecrets::default(),
+        };
+
         WitnessingConfig {
             paranoid_threshold: paranoid,
             transports,
+            secrets,
         }
     }
 }
 
+/// Read a file of retrieval secrets, where each table is a kind of secret, and name the secrets
+/// after the placeholders that refer to them, e.g. `api_key:coinlayer`.
+fn load_retrieval_secrets(
+    path: &Path,
+) -> Result<BTreeMap<String, RetrievalSecret>, failure::Error> {
+    let contents = std::fs::read_to_string(path)?;
+    let tables: BTreeMap<String, BTreeMap<String, RetrievalSecret>> = toml::from_str(&contents)?;
+
+    Ok(tables
+        .into_iter()
+        .flat_map(|(kind, secrets)| {
+            secrets
+                .into_iter()
+                .map(move |(name, value)| (format!("{}:{}", kind, name), value))
+        })
+        .collect())
+}
+
 // Serialization helpers
 
 fn as_log_filter_string<S>(
@@ -1355,6 +1919,7 @@ mod tests {
             bootstrap_peers_period: Some(Duration::from_secs(10)),
             storage_peers_period: Some(Duration::from_secs(60)),
             discovery_peers_period: Some(Duration::from_secs(100)),
+            get_peers_min_interval: Some(Duration::from_secs(20)),
             check_melted_peers_period: Some(Duration::from_secs(112)),
             feeler_peers_period: Some(Duration::from_secs(1)),
             handshake_timeout: Some(Duration::from_secs(3)),
@@ -1366,6 +1931,18 @@ mod tests {
             reject_sybil_inbounds: Some(true),
             reject_sybil_inbounds_range_limit: Some(14),
             requested_blocks_batch_limit: Some(99),
+            outbound_rotation_period: Some(Duration::from_secs(600)),
+            outbound_rotation_min_lifetime: Some(Duration::from_secs(1200)),
+            outbound_rotation_min_outbound: Some(2),
+            write_queue_high_water_mark: Some(1024),
+            write_queue_hard_limit: Some(4096),
+            compression: Some(false),
+            compression_threshold: Some(512),
+            peer_score_half_life: Some(Duration::from_secs(7200)),
+            peer_exploration_percentage: Some(25),
+            external_address_quorum: Some(5),
+            shared_peers_max_age: Some(Duration::from_secs(3600)),
+            shared_peers_limit: Some(50),
         };
         let config = Connections::from_partial(&partial_config, &Testnet);
 
@@ -1377,6 +1954,7 @@ mod tests {
         assert_eq!(config.bootstrap_peers_period, Duration::from_secs(10));
         assert_eq!(config.storage_peers_period, Duration::from_secs(60));
         assert_eq!(config.discovery_peers_period, Duration::from_secs(100));
+        assert_eq!(config.get_peers_min_interval, Duration::from_secs(20));
         assert_eq!(config.check_melted_peers_period, Duration::from_secs(112));
         assert_eq!(config.feeler_peers_period, Duration::from_secs(1));
         assert_eq!(config.handshake_timeout, Duration::from_secs(3));
@@ -1388,6 +1966,21 @@ mod tests {
         assert!(config.reject_sybil_inbounds);
         assert_eq!(config.reject_sybil_inbounds_range_limit, 14);
         assert_eq!(config.requested_blocks_batch_limit, 99);
+        assert_eq!(config.outbound_rotation_period, Duration::from_secs(600));
+        assert_eq!(
+            config.outbound_rotation_min_lifetime,
+            Duration::from_secs(1200)
+        );
+        assert_eq!(config.outbound_rotation_min_outbound, 2);
+        assert_eq!(config.write_queue_high_water_mark, 1024);
+        assert_eq!(config.write_queue_hard_limit, 4096);
+        assert!(!config.compression);
+        assert_eq!(config.compression_threshold, 512);
+        assert_eq!(config.peer_score_half_life, Duration::from_secs(7200));
+        assert_eq!(config.peer_exploration_percentage, 25);
+        assert_eq!(config.external_address_quorum, 5);
+        assert_eq!(config.shared_peers_max_age, Duration::from_secs(3600));
+        assert_eq!(config.shared_peers_limit, 50);
     }
 
     #[test]
@@ -1396,6 +1989,7 @@ mod tests {
             allow_unproxied: Some(true),
             paranoid_percentage: Some(51),
             proxies: Some(Vec::<String>::new()),
+            secrets_file: None,
         };
         let config = Witnessing::from_partial(&partial, &Testnet);
 
@@ -1404,6 +1998,41 @@ mod tests {
         assert_eq!(config.proxies, Vec::<String>::new());
     }
 
+    #[test]
+    fn test_witnessing_loads_retrieval_secrets() {
+        let path = std::env::temp_dir().join(format!(
+            "witnet-retrieval-secrets-{}.toml",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            "[api_key.coinlayer]\nvalue = \"s3cr3t\"\nhosts = [\"api.coinlayer.com\"]\n\n\
+             [token.coinlayer]\nvalue = \"other\"\nhosts = []\n",
+        )
+        .unwrap();
+        let config = Witnessing {
+            allow_unproxied: true,
+            paranoid_percentage: 51,
+            proxies: vec![],
+            secrets_file: Some(path.clone()),
+        }
+        .into_config();
+        std::fs::remove_file(&path).unwrap();
+
+        let api_key = config.secrets.get("api_key:coinlayer").unwrap();
+        assert_eq!(api_key.value, "s3cr3t");
+        assert!(api_key.allows("api.coinlayer.com"));
+        assert!(!api_key.allows("coinlayer.com"));
+        let token = config.secrets.get("token:coinlayer").unwrap();
+        assert_eq!(token.value, "other");
+        assert!(!token.allows("api.coinlayer.com"));
+        assert!(config.secrets.get("coinlayer").is_none());
+        // Only the names of the secrets are ever printed
+        let debug = format!("{:?}", config);
+        assert!(debug.contains("api_key:coinlayer"));
+        assert!(!debug.contains("s3cr3t"));
+    }
+
     #[test]
     fn test_jsonrpc_default_from_partial() {
         let partial_config = PartialJsonRPC::default();
@@ -7,8 +7,8 @@ use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use std::path::PathBuf;
 use std::time::Duration;
 
-use witnet_crypto::hash::HashFunction;
-use witnet_data_structures::chain::Hash;
+use witnet_crypto::{hash::HashFunction, key::KeySpec};
+use witnet_data_structures::chain::{ConsensusConstants, Environment, Hash};
 use witnet_protected::ProtectedString;
 
 // When changing the defaults, remember to update the documentation!
@@ -74,6 +74,12 @@ pub trait Defaults {
         Duration::from_secs(30)
     }
 
+    /// Peers are asked for peers at most once every 10 seconds, so that the ones asked on
+    /// consolidation are not asked again by the next discovery
+    fn connections_get_peers_min_interval(&self) -> Duration {
+        Duration::from_secs(10)
+    }
+
     /// Default period for melt peers
     fn connections_check_melted_peers_period(&self) -> Duration {
         Duration::from_secs(300)
@@ -124,6 +130,66 @@ pub trait Defaults {
         500
     }
 
+    /// Default period for replacing the worst scoring outbound peer
+    fn connections_outbound_rotation_period(&self) -> Duration {
+        Duration::from_secs(30 * 60)
+    }
+
+    /// Outbound sessions are kept for at least one hour before being considered for rotation
+    fn connections_outbound_rotation_min_lifetime(&self) -> Duration {
+        Duration::from_secs(60 * 60)
+    }
+
+    /// Outbound rotation never leaves fewer consolidated outbound sessions than this
+    fn connections_outbound_rotation_min_outbound(&self) -> u16 {
+        4
+    }
+
+    /// Inventory announcements to a peer are dropped while more than 4 MiB are pending to be sent
+    fn connections_write_queue_high_water_mark(&self) -> usize {
+        4 * 1024 * 1024
+    }
+
+    /// Sessions are closed when more than 64 MiB are pending to be sent, twice the maximum size of
+    /// a message
+    fn connections_write_queue_hard_limit(&self) -> usize {
+        64 * 1024 * 1024
+    }
+
+    /// Compress the messages sent to the peers that support it
+    fn connections_compression(&self) -> bool {
+        true
+    }
+
+    /// Messages of less than 4 KiB are sent uncompressed, as they barely shrink
+    fn connections_compression_threshold(&self) -> usize {
+        4 * 1024
+    }
+
+    /// The scores of peers are halved every day
+    fn connections_peer_score_half_life(&self) -> Duration {
+        Duration::from_secs(24 * 60 * 60)
+    }
+
+    /// One in ten outbound peers is chosen regardless of its score
+    fn connections_peer_exploration_percentage(&self) -> u8 {
+        10
+    }
+
+    /// Three peers must agree on our external address before it is advertised
+    fn connections_external_address_quorum(&self) -> u16 {
+        3
+    }
+
+    /// Addresses that we have not had a session with for a day are not shared
+    fn connections_shared_peers_max_age(&self) -> Duration {
+        Duration::from_secs(24 * 60 * 60)
+    }
+
+    fn connections_shared_peers_limit(&self) -> usize {
+        100
+    }
+
     /// Let the default unproxied HTTP transport be enabled by default
     fn witnessing_allow_unproxied(&self) -> bool {
         true
@@ -146,58 +212,47 @@ pub trait Defaults {
 
     /// Default period between epochs
     fn consensus_constants_checkpoints_period(&self) -> u16 {
-        45
+        canonical_consensus_constants().checkpoints_period
     }
 
     /// Default period between superblocks
     fn consensus_constants_superblock_period(&self) -> u16 {
-        10
+        canonical_consensus_constants().superblock_period
     }
 
     /// Default period (in superblock periods) after which the committee should be reduced
     fn consensus_constants_superblock_committee_decreasing_period(&self) -> u32 {
-        5
+        canonical_consensus_constants().superblock_committee_decreasing_period
     }
 
     /// Default committee reduction step
     fn consensus_constants_superblock_committee_decreasing_step(&self) -> u32 {
-        5
+        canonical_consensus_constants().superblock_committee_decreasing_step
     }
 
     /// Default initial block reward
     fn consensus_constants_initial_block_reward(&self) -> u64 {
-        // 250 wits
-        250 * 1_000_000_000
+        canonical_consensus_constants().initial_block_reward
     }
 
     /// Default halving period
     fn consensus_constants_halving_period(&self) -> u32 {
-        // 3.5M epochs * (45 secs/epoch) ~> 5 years
-        // This will be the first timestamp with halved reward:
-        // 3_500_000 * 45 + 1_602_666_000 = 1_760_166_000
-        // 2025-10-11 @ 7:00am (UTC)
-
-        3_500_000
+        canonical_consensus_constants().halving_period
     }
 
     /// Default Hash value for the auxiliary bootstrap block
     fn consensus_constants_bootstrap_hash(&self) -> Hash {
-        // Brrr
-        "666564676f6573627272727c2f3030312f3738392f3432382f6130312e676966"
-            .parse()
-            .unwrap()
+        canonical_consensus_constants().bootstrap_hash
     }
 
     /// Default Hash value for the genesis block
     fn consensus_constants_genesis_hash(&self) -> Hash {
-        "6ca267d9accde3336739331d42d63509b799c6431e8d02b2d2cc9d3943d7ab02"
-            .parse()
-            .unwrap()
+        canonical_consensus_constants().genesis_hash
     }
 
     /// Default size of the superblock signing committee
     fn consensus_constants_superblock_signing_committee_size(&self) -> u32 {
-        100
+        canonical_consensus_constants().superblock_signing_committee_size
     }
 
     /// JSON-RPC server enabled by default
@@ -276,14 +331,14 @@ pub trait Defaults {
     /// The absolute minimum accepted by the network should be defined as a consensus constant,
     /// but to avoid affecting the magic number, it's a "pseudoconsensus constant" for now.
     fn consensus_constants_minimum_reward_collateral_ratio(&self) -> u64 {
-        PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO
+        ConsensusConstants::minimum_reward_collateral_ratio()
     }
 
     fn consensus_constants_max_vt_weight(&self) -> u32 {
-        20_000
+        canonical_consensus_constants().max_vt_weight
     }
     fn consensus_constants_max_dr_weight(&self) -> u32 {
-        80_000
+        canonical_consensus_constants().max_dr_weight
     }
 
     /// Default number of seconds before giving up waiting for requested blocks: `400`.
@@ -299,89 +354,67 @@ pub trait Defaults {
 
     /// An identity is considered active if it participated in the witnessing protocol at least once in the last `activity_period` epochs
     fn consensus_constants_activity_period(&self) -> u32 {
-        // 2000 epochs = 50 hours with an epoch of 90 secs
-        // 2000
-
-        // Testnet configuration
-        2000
+        canonical_consensus_constants().activity_period
     }
 
     /// Reputation will expire after N witnessing acts
     fn consensus_constants_reputation_expire_alpha_diff(&self) -> u32 {
-        // 20_000 witnessing acts
-        20_000
+        canonical_consensus_constants().reputation_expire_alpha_diff
     }
 
     /// Reputation issuance
     fn consensus_constants_reputation_issuance(&self) -> u32 {
-        // Issue 1 reputation point per witnessing act
-        1
+        canonical_consensus_constants().reputation_issuance
     }
 
     /// Minimum difficulty
     fn consensus_constants_minimum_difficulty(&self) -> u32 {
-        2000
+        canonical_consensus_constants().minimum_difficulty
     }
 
     /// Epochs with minimum difficulty
     fn consensus_constants_epochs_with_minimum_difficulty(&self) -> u32 {
-        2000
+        canonical_consensus_constants().epochs_with_minimum_difficulty
     }
 
     /// When to stop issuing new reputation
     fn consensus_constants_reputation_issuance_stop(&self) -> u32 {
-        // Issue reputation points for the first 2^20 witnessing acts
-        1 << 20
+        canonical_consensus_constants().reputation_issuance_stop
     }
 
     /// Penalization factor: fraction of reputation lost by liars for out of consensus claims
     fn consensus_constants_reputation_penalization_factor(&self) -> f64 {
-        // Lose half of the total reputation for every lie
-        0.5
+        canonical_consensus_constants().reputation_penalization_factor
     }
 
     /// Backup factor for mining: valid VRFs under this factor will result in broadcasting a block
     fn consensus_constants_mining_backup_factor(&self) -> u32 {
-        8
+        canonical_consensus_constants().mining_backup_factor
     }
 
     /// Replication factor for mining: valid VRFs under this factor will have priority
     fn consensus_constants_mining_replication_factor(&self) -> u32 {
-        3
+        canonical_consensus_constants().mining_replication_factor
     }
 
     /// Minimum value in nanowits for a collateral value
     fn consensus_constants_collateral_minimum(&self) -> u64 {
-        // 1 wit = 1_000_000_000 nanowits
-        1_000_000_000
+        canonical_consensus_constants().collateral_minimum
     }
 
     /// Minimum input age of an UTXO for being a valid collateral
     fn consensus_constants_collateral_age(&self) -> u32 {
-        1000
+        canonical_consensus_constants().collateral_age
     }
 
     /// Number of extra rounds for commitments and reveals
     fn consensus_constants_extra_rounds(&self) -> u16 {
-        3
+        canonical_consensus_constants().extra_rounds
     }
 
     /// First superblocks signing committee
     fn consensus_constants_bootstrapping_committee(&self) -> Vec<String> {
-        vec![
-            "wit1g0rkajsgwqux9rnmkfca5tz6djg0f87x7ms5qx".to_string(),
-            "wit1cyrlc64hyu0rux7hclmg9rxwxpa0v9pevyaj2c".to_string(),
-            "wit1asdpcspwysf0hg5kgwvgsp2h6g65y5kg9gj5dz".to_string(),
-            "wit13l337znc5yuualnxfg9s2hu9txylntq5pyazty".to_string(),
-            "wit17nnjuxmfuu92l6rxhque2qc3u2kvmx2fske4l9".to_string(),
-            "wit1etherz02v4fvqty6jhdawefd0pl33qtevy7s4z".to_string(),
-            "wit1drcpu0xc2akfcqn8r69vw70pj8fzjhjypdcfsq".to_string(),
-            "wit1gxf0ca67vxtg27kkmgezg7dd84hwmzkxn7c62x".to_string(),
-            "wit1hujx8v0y8rzqchmmagh8yw95r943cdddnegtgc".to_string(),
-            "wit1yd97y52ezvhq4kzl6rph6d3v6e9yya3n0kwjyr".to_string(),
-            "wit1fn5yxmgkphnnuu6347s2dlqpyrm4am280s6s9t".to_string(),
-            "wit12khyjjk0s2hyuzyyhv5v2d5y5snws7l58z207g".to_string(),
-        ]
+        canonical_consensus_constants().bootstrapping_committee
     }
 
     /// Wallet server address
@@ -389,6 +422,10 @@ pub trait Defaults {
         SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 11212)
     }
 
+    fn wallet_cors_allow_credentials(&self) -> bool {
+        false
+    }
+
     /// Wallet db file name
     fn wallet_db_file_name(&self) -> String {
         "witnet_wallet.db".to_string()
@@ -422,6 +459,14 @@ pub trait Defaults {
         HashFunction::Sha256
     }
 
+    fn wallet_key_spec(&self) -> KeySpec {
+        KeySpec::Wip3
+    }
+
+    fn wallet_coin_type(&self) -> u32 {
+        4919
+    }
+
     fn wallet_sync_address_batch_length(&self) -> u16 {
         20
     }
@@ -435,6 +480,170 @@ pub trait Defaults {
         10 * u64::from(self.consensus_constants_checkpoints_period())
     }
 
+    fn wallet_enable_derive_addresses(&self) -> bool {
+        false
+    }
+
+    fn wallet_notifications_queue_size(&self) -> usize {
+        128
+    }
+
+    fn wallet_notifications_replay_size(&self) -> usize {
+        100
+    }
+
+    fn wallet_node_failover_threshold(&self) -> u32 {
+        3
+    }
+
+    fn wallet_node_failover_min_interval(&self) -> u64 {
+        300
+    }
+
+    fn wallet_node_pool_size(&self) -> usize {
+        1
+    }
+
+    fn wallet_node_stall_epochs(&self) -> u32 {
+        10
+    }
+
+    fn wallet_node_clock_skew_epochs(&self) -> u32 {
+        2
+    }
+
+    fn wallet_retrieval_cache_enabled(&self) -> bool {
+        false
+    }
+
+    fn wallet_retrieval_cache_ttl(&self) -> u64 {
+        60
+    }
+
+    fn wallet_retrieval_cache_max_size(&self) -> u64 {
+        // 16 MiB
+        16 * 1024 * 1024
+    }
+
+    fn wallet_max_sessions_per_wallet(&self) -> usize {
+        8
+    }
+
+    fn wallet_max_sessions(&self) -> usize {
+        256
+    }
+
+    fn wallet_verify_block_headers(&self) -> bool {
+        false
+    }
+
+    fn wallet_seed_backup_confirmation_attempts(&self) -> u32 {
+        3
+    }
+
+    fn wallet_max_vtt_outputs(&self) -> u32 {
+        50
+    }
+
+    fn wallet_check_integrity_on_unlock(&self) -> bool {
+        false
+    }
+
+    fn wallet_data_request_cost_warning_threshold(&self) -> u64 {
+        1_000_000
+    }
+
+    fn wallet_address_index_max_entries(&self) -> u32 {
+        1_000_000
+    }
+
+    fn wallet_minimum_fee(&self) -> u64 {
+        1
+    }
+
+    /// Half of the priority of the "Low" tier of the fee estimation of the node
+    fn wallet_minimum_fee_per_kilo_weight(&self) -> u64 {
+        100
+    }
+
+    /// Roughly what spending an output costs in fees at the priority of the "Low" tier
+    fn wallet_dust_threshold(&self) -> u64 {
+        1_000
+    }
+
+    /// The same as the dust threshold, as smaller change is added to the fee anyway
+    fn wallet_changeless_tolerance(&self) -> u64 {
+        1_000
+    }
+
+    /// Visiting this many nodes takes a few milliseconds
+    fn wallet_changeless_search_budget(&self) -> u32 {
+        100_000
+    }
+
+    fn wallet_refuse_addresses_beyond_gap_limit(&self) -> bool {
+        false
+    }
+
+    /// Clients of every version of the API are accepted
+    fn wallet_min_client_api_version(&self) -> u32 {
+        1
+    }
+
+    /// One day
+    fn wallet_idempotency_key_retention(&self) -> u64 {
+        24 * 60 * 60
+    }
+
+    fn wallet_webhook_outbox_size(&self) -> u32 {
+        10_000
+    }
+
+    /// With the default retry delay, the last attempt happens about 17 minutes after the first one
+    fn wallet_webhook_max_attempts(&self) -> u32 {
+        10
+    }
+
+    fn wallet_webhook_retry_delay(&self) -> u64 {
+        2_000
+    }
+
+    fn wallet_balance_history_granularity(&self) -> u32 {
+        1
+    }
+
+    fn wallet_balance_history_daily_after(&self) -> u32 {
+        30
+    }
+
+    fn wallet_strict_node_schema(&self) -> bool {
+        false
+    }
+
+    fn wallet_interactive_queue_limit(&self) -> usize {
+        256
+    }
+
+    fn wallet_block_queue_limit(&self) -> usize {
+        64
+    }
+
+    fn wallet_maintenance_queue_limit(&self) -> usize {
+        64
+    }
+
+    fn wallet_session_request_limit(&self) -> usize {
+        64
+    }
+
+    fn wallet_session_heavy_request_limit(&self) -> usize {
+        4
+    }
+
+    fn wallet_heavy_request_limit(&self) -> usize {
+        16
+    }
+
     fn rocksdb_create_if_missing(&self) -> bool {
         true
     }
@@ -477,14 +686,16 @@ pub trait Defaults {
     }
 }
 
-/// Allow setting a reward to collateral percentage for a data request to be included in a block
-/// The absolute minimum accepted by the network is defined as a consensus constant
-// TODO: move this into ConsensusConstants
-pub const PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO: u64 = 125;
+pub use witnet_data_structures::chain::{
+    PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO,
+    PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE,
+};
 
-/// Modified value of callateral_age after WIP0027 (1 week)
-// TODO: modify the value directly in ConsensusConstants
-pub const PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE: u32 = 13440;
+/// The consensus constants defaults are the canonical mainnet values, which only differ from the
+/// testnet ones in the timestamp of checkpoint 0.
+fn canonical_consensus_constants() -> ConsensusConstants {
+    ConsensusConstants::for_environment(Environment::Mainnet)
+}
 
 /// Struct that will implement all the development defaults
 pub struct Development;
@@ -501,8 +712,7 @@ impl Defaults for Development {
     }
 
     fn consensus_constants_checkpoint_zero_timestamp(&self) -> i64 {
-        // Wednesday, 23-Sept-2020, 09:00 UTC
-        1_600_851_600
+        ConsensusConstants::for_environment(Environment::Development).checkpoint_zero_timestamp
     }
 
     fn connections_reject_sybil_inbounds(&self) -> bool {
@@ -520,8 +730,7 @@ impl Defaults for Mainnet {
     }
 
     fn consensus_constants_checkpoint_zero_timestamp(&self) -> i64 {
-        // Wednesday, 14-Oct-2020, 09:00 UTC
-        1_602_666_000
+        ConsensusConstants::for_environment(Environment::Mainnet).checkpoint_zero_timestamp
     }
 }
 
@@ -531,7 +740,6 @@ impl Defaults for Testnet {
     }
 
     fn consensus_constants_checkpoint_zero_timestamp(&self) -> i64 {
-        // Wednesday, 23-Sept-2020, 09:00 UTC
-        1_600_851_600
+        ConsensusConstants::for_environment(Environment::Testnet).checkpoint_zero_timestamp
     }
 }
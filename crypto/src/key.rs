@@ -13,6 +13,7 @@ use std::{
     fmt,
     io::{self, Read as _, Write as _},
     slice,
+    str::FromStr,
 };
 
 use bech32::{FromBase32, ToBase32 as _};
@@ -36,6 +37,22 @@ pub enum MasterKeyGenError {
     InvalidSeedLength,
 }
 
+/// Error type for errors ocurring when parsing a key path from its string representation.
+#[derive(Debug, PartialEq, Eq, Fail)]
+pub enum KeyPathParseError {
+    /// The path does not start with the `m` master key marker
+    #[fail(display = "Key path must start with 'm'")]
+    MissingMasterPrefix,
+    /// One of the path levels is not a valid index
+    #[fail(display = "Invalid key path index: {}", _0)]
+    InvalidIndex(String),
+}
+
+/// Error type for key spec names that are not known to this version.
+#[derive(Debug, PartialEq, Eq, Fail)]
+#[fail(display = "Unknown key spec: {}", _0)]
+pub struct UnknownKeySpec(pub String);
+
 /// Error type for errors ocurring when serializing an extended secret
 /// key.
 #[derive(Debug, Fail)]
@@ -389,7 +406,8 @@ pub struct KeyPath {
 }
 
 impl KeyPath {
-    const HARDENED_KEY_INDEX: u32 = 0x8000_0000;
+    /// First index of the hardened range. Indices below this value are non-hardened.
+    pub const HARDENED_KEY_INDEX: u32 = 0x8000_0000;
 
     /// Add a hardened-index index to the current path.
     ///
@@ -448,6 +466,38 @@ impl fmt::Display for KeyPath {
     }
 }
 
+impl FromStr for KeyPath {
+    type Err = KeyPathParseError;
+
+    /// Parse a key path such as `m/3'/4919'/0'/0/5`.
+    ///
+    /// Hardened indices can be marked with either `'` or `h`.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut levels = s.trim().split('/');
+        if levels.next() != Some("m") {
+            return Err(KeyPathParseError::MissingMasterPrefix);
+        }
+
+        levels.try_fold(KeyPath::default(), |path, level| {
+            let (number, hardened) = match level.strip_suffix(|c: char| c == '\'' || c == 'h') {
+                Some(number) => (number, true),
+                None => (level, false),
+            };
+            let index = number
+                .parse::<u32>()
+                .ok()
+                .filter(|index| *index < Self::HARDENED_KEY_INDEX)
+                .ok_or_else(|| KeyPathParseError::InvalidIndex(level.to_string()))?;
+
+            Ok(if hardened {
+                path.hardened(index)
+            } else {
+                path.index(index)
+            })
+        })
+    }
+}
+
 impl From<Vec<u32>> for KeyPath {
     fn from(path: Vec<u32>) -> Self {
         Self {
@@ -456,6 +506,52 @@ impl From<Vec<u32>> for KeyPath {
     }
 }
 
+/// Specification of the key paths under which the accounts of an HD wallet are derived, which
+/// have the form `m/purpose'/coin_type'/account'/keychain/index`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(Serialize, Deserialize),
+    serde(rename_all = "lowercase")
+)]
+pub enum KeySpec {
+    /// Witnet HD wallets as defined in WIP-0003, with purpose 3
+    Wip3,
+    /// Multi-account hierarchy of BIP-44, with purpose 44
+    Bip44,
+}
+
+impl KeySpec {
+    /// Purpose level of the key paths of this spec.
+    pub fn purpose(self) -> u32 {
+        match self {
+            KeySpec::Wip3 => 3,
+            KeySpec::Bip44 => 44,
+        }
+    }
+}
+
+impl fmt::Display for KeySpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeySpec::Wip3 => write!(f, "wip3"),
+            KeySpec::Bip44 => write!(f, "bip44"),
+        }
+    }
+}
+
+impl FromStr for KeySpec {
+    type Err = UnknownKeySpec;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "wip3" => Ok(KeySpec::Wip3),
+            "bip44" => Ok(KeySpec::Bip44),
+            _ => Err(UnknownKeySpec(s.to_string())),
+        }
+    }
+}
+
 #[inline]
 fn get_chain_code_and_secret(
     seed: &[u8],
@@ -567,6 +663,52 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_key_path_from_str() {
+        let path: KeyPath = "m/3'/4919'/0'/0/5".parse().unwrap();
+        let expected = KeyPath::default()
+            .hardened(3)
+            .hardened(4919)
+            .hardened(0)
+            .index(0)
+            .index(5);
+
+        assert_eq!(path, expected);
+        assert_eq!("m/3'/4919'/0'/0/5", path.to_string());
+        assert_eq!(
+            "m/44h/0h".parse::<KeyPath>().unwrap().to_string(),
+            "m/44'/0'"
+        );
+        assert!("m".parse::<KeyPath>().unwrap().is_master());
+    }
+
+    #[test]
+    fn test_key_spec_from_str() {
+        for spec in [KeySpec::Wip3, KeySpec::Bip44] {
+            assert_eq!(spec.to_string().parse::<KeySpec>(), Ok(spec));
+        }
+        assert_eq!(
+            "wip99".parse::<KeySpec>(),
+            Err(UnknownKeySpec("wip99".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_key_path_from_str_invalid() {
+        assert_eq!(
+            "3'/4919'".parse::<KeyPath>(),
+            Err(KeyPathParseError::MissingMasterPrefix)
+        );
+        assert_eq!(
+            "m/3'/x".parse::<KeyPath>(),
+            Err(KeyPathParseError::InvalidIndex("x".to_string()))
+        );
+        assert_eq!(
+            "m/2147483648".parse::<KeyPath>(),
+            Err(KeyPathParseError::InvalidIndex("2147483648".to_string()))
+        );
+    }
+
     #[test]
     fn test_slip32() {
         let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
@@ -0,0 +1,22 @@
+//! Feed arbitrary bytes into the protocol message decoder.
+//!
+//! Run with `cargo +nightly fuzz run message_from_bytes -- -malloc_limit_mb=128` so that any
+//! allocation that is disproportionate to the size of the input is reported as a crash.
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+
+use witnet_data_structures::{builders::MAX_MESSAGE_SIZE, proto::ProtobufConvert, types::Message};
+
+fuzz_target!(|data: &[u8]| {
+    // Bigger messages are rejected by the P2P codec before reaching the decoder
+    if data.len() > MAX_MESSAGE_SIZE {
+        return;
+    }
+
+    if let Ok(msg) = Message::from_pb_bytes(data) {
+        // Anything that decodes must encode again
+        msg.to_pb_bytes()
+            .expect("decoded messages should always be serializable");
+    }
+});
@@ -26,6 +26,14 @@ pub const PROTOCOL_VERSION: u32 = 0x0000_0001;
 /// Capabilities
 pub const CAPABILITIES: u64 = 0x0000_0000_0000_0001;
 
+/// Capability of receiving compressed messages, advertised along `CAPABILITIES` by the nodes that
+/// have compression enabled
+pub const CAPABILITY_COMPRESSION: u64 = 0x0000_0000_0000_0002;
+
+/// Maximum size in bytes of a serialized protocol message. Peers announcing bigger messages are
+/// disconnected before the message is buffered.
+pub const MAX_MESSAGE_SIZE: usize = 32 * 1024 * 1024;
+
 ////////////////////////////////////////////////////////////////////////////////////////
 // BUILDER PUBLIC FUNCTIONS
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -52,11 +60,15 @@ impl Message {
     }
 
     /// Function to build Version messages
+    ///
+    /// `sender_addr` is the address at which we can be reached, and `receiver_addr` is the address
+    /// at which we see the receiver, which lets it learn its own external address
     pub fn build_version(
         magic: u16,
         sender_addr: Option<SocketAddr>,
         receiver_addr: SocketAddr,
         beacon: LastBeacon,
+        capabilities: u64,
     ) -> Message {
         let addr = sender_addr.map(to_address);
         Message::build_message(
@@ -64,7 +76,7 @@ impl Message {
             Command::Version(Version {
                 version: PROTOCOL_VERSION,
                 timestamp: get_timestamp(),
-                capabilities: CAPABILITIES,
+                capabilities,
                 sender_address: addr.unwrap_or_default(),
                 receiver_address: to_address(receiver_addr),
                 user_agent: user_agent(),
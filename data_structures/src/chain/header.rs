@@ -0,0 +1,297 @@
+use witnet_crypto::{
+    hash::Sha256,
+    merkle::merkle_tree_root,
+    secp256k1::{ecdsa::Signature as Secp256k1_Signature, PublicKey as Secp256k1_PublicKey},
+    signature::verify,
+};
+
+use crate::{
+    chain::{
+        BlockHeader, BlockMerkleRoots, BlockTransactions, CheckpointBeacon, Hash, Hashable,
+        KeyedSignature,
+    },
+    error::BlockError,
+};
+
+/// Hashes of the transactions of a block, which is all that is needed for recomputing the merkle
+/// roots of its header.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct BlockTransactionHashes {
+    /// Hash of the mint transaction
+    pub mint: Hash,
+    /// Hashes of the value transfer transactions, in block order
+    pub value_transfer: Vec<Hash>,
+    /// Hashes of the data request transactions, in block order
+    pub data_request: Vec<Hash>,
+    /// Hashes of the commit transactions, in block order
+    pub commit: Vec<Hash>,
+    /// Hashes of the reveal transactions, in block order
+    pub reveal: Vec<Hash>,
+    /// Hashes of the tally transactions, in block order
+    pub tally: Vec<Hash>,
+}
+
+impl From<&BlockTransactions> for BlockTransactionHashes {
+    fn from(txns: &BlockTransactions) -> Self {
+        fn hashes<T: Hashable>(transactions: &[T]) -> Vec<Hash> {
+            transactions.iter().map(Hashable::hash).collect()
+        }
+
+        BlockTransactionHashes {
+            mint: txns.mint.hash(),
+            value_transfer: hashes(&txns.value_transfer_txns),
+            data_request: hashes(&txns.data_request_txns),
+            commit: hashes(&txns.commit_txns),
+            reveal: hashes(&txns.reveal_txns),
+            tally: hashes(&txns.tally_txns),
+        }
+    }
+}
+
+impl BlockTransactionHashes {
+    /// Merkle roots that a block header with these transactions must commit to.
+    pub fn merkle_roots(&self) -> BlockMerkleRoots {
+        fn root(hashes: &[Hash]) -> Hash {
+            let hashes: Vec<Sha256> = hashes.iter().map(|Hash::SHA256(x)| Sha256(*x)).collect();
+
+            Hash::from(merkle_tree_root(&hashes))
+        }
+
+        BlockMerkleRoots {
+            mint_hash: self.mint,
+            vt_hash_merkle_root: root(&self.value_transfer),
+            dr_hash_merkle_root: root(&self.data_request),
+            commit_hash_merkle_root: root(&self.commit),
+            reveal_hash_merkle_root: root(&self.reveal),
+            tally_hash_merkle_root: root(&self.tally),
+        }
+    }
+}
+
+/// Verify the internal consistency of a block header without any chain state.
+///
+/// This checks that:
+/// * The block builds on top of `previous_beacon`, if provided. As in the chain manager, this
+///   beacon holds the epoch and the hash of the previous block.
+/// * The merkle roots match the ones recomputed from `transaction_hashes`.
+/// * The VRF proof of eligibility is well formed. The proof itself is not verified, because that
+///   needs the VRF input of the epoch.
+/// * The block is signed by the same identity that claims the eligibility, and the signature over
+///   the block hash is valid.
+pub fn verify_block_header(
+    block_header: &BlockHeader,
+    block_sig: &KeyedSignature,
+    transaction_hashes: &BlockTransactionHashes,
+    previous_beacon: Option<CheckpointBeacon>,
+) -> Result<(), BlockError> {
+    let block_beacon = block_header.beacon;
+    if let Some(previous_beacon) = previous_beacon {
+        if previous_beacon.checkpoint > block_beacon.checkpoint {
+            return Err(BlockError::BlockOlderThanTip {
+                chain_epoch: previous_beacon.checkpoint,
+                block_epoch: block_beacon.checkpoint,
+            });
+        }
+        if previous_beacon.hash_prev_block != block_beacon.hash_prev_block {
+            return Err(BlockError::PreviousHashMismatch {
+                block_hash: block_beacon.hash_prev_block,
+                our_hash: previous_beacon.hash_prev_block,
+            });
+        }
+    }
+
+    if transaction_hashes.merkle_roots() != block_header.merkle_roots {
+        return Err(BlockError::NotValidMerkleTree);
+    }
+
+    if !block_header.proof.proof.is_well_formed() {
+        return Err(BlockError::NotValidPoe);
+    }
+
+    let proof_pkh = block_header.proof.proof.pkh();
+    let signature_pkh = block_sig.public_key.pkh();
+    if proof_pkh != signature_pkh {
+        return Err(BlockError::PublicKeyHashMismatch {
+            proof_pkh,
+            signature_pkh,
+        });
+    }
+
+    let hash = block_header.hash();
+    let Hash::SHA256(message) = hash;
+    let public_key: Result<Secp256k1_PublicKey, _> = block_sig.public_key.clone().try_into();
+    let signature: Result<Secp256k1_Signature, _> = block_sig.signature.clone().try_into();
+    match (public_key, signature) {
+        (Ok(public_key), Ok(signature)) if verify(&public_key, &message, &signature).is_ok() => {
+            Ok(())
+        }
+        _ => Err(BlockError::VerifySignatureFail { hash }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use witnet_crypto::{
+        secp256k1::{PublicKey as Secp256k1_PublicKey, SecretKey as Secp256k1_SecretKey},
+        signature::sign,
+    };
+    use witnet_protected::Protected;
+
+    use crate::{
+        chain::{Block, CheckpointVRF, PublicKey, SecretKey, Signature},
+        transaction::{MintTransaction, VTTransaction},
+        vrf::{BlockEligibilityClaim, VrfCtx},
+    };
+
+    use super::*;
+
+    const PRIV_KEY: [u8; 32] = [0xcd; 32];
+
+    fn sign_header(block_header: &BlockHeader, key: [u8; 32]) -> KeyedSignature {
+        let Hash::SHA256(data) = block_header.hash();
+        let secret_key = Secp256k1_SecretKey::from_slice(&key).unwrap();
+        let public_key = PublicKey::from(Secp256k1_PublicKey::from_secret_key_global(&secret_key));
+        let signature = sign(secret_key, &data).unwrap();
+
+        KeyedSignature {
+            signature: Signature::from(signature),
+            public_key,
+        }
+    }
+
+    /// A block signed by the identity that claims its eligibility, on top of `previous_beacon()`.
+    fn valid_block() -> Block {
+        let vrf = &mut VrfCtx::secp256k1().unwrap();
+        let secret_key = SecretKey {
+            bytes: Protected::from(PRIV_KEY.to_vec()),
+        };
+        let proof =
+            BlockEligibilityClaim::create(vrf, &secret_key, CheckpointVRF::default()).unwrap();
+        let txns = BlockTransactions {
+            mint: MintTransaction::new(2, vec![]),
+            value_transfer_txns: vec![VTTransaction::default()],
+            ..BlockTransactions::default()
+        };
+        let block_header = BlockHeader {
+            beacon: CheckpointBeacon {
+                checkpoint: 2,
+                hash_prev_block: previous_beacon().hash_prev_block,
+            },
+            merkle_roots: BlockMerkleRoots::from_transactions(&txns),
+            proof,
+            ..BlockHeader::default()
+        };
+        let block_sig = sign_header(&block_header, PRIV_KEY);
+
+        Block::new(block_header, block_sig, txns)
+    }
+
+    fn previous_beacon() -> CheckpointBeacon {
+        CheckpointBeacon {
+            checkpoint: 1,
+            hash_prev_block: Hash::SHA256([1; 32]),
+        }
+    }
+
+    fn verify_block(block: &Block) -> Result<(), BlockError> {
+        verify_block_header(
+            &block.block_header,
+            &block.block_sig,
+            &BlockTransactionHashes::from(&block.txns),
+            Some(previous_beacon()),
+        )
+    }
+
+    #[test]
+    fn valid_block_header() {
+        let block = valid_block();
+
+        verify_block(&block).unwrap();
+        // Without a previous beacon, only the internal consistency is checked
+        verify_block_header(
+            &block.block_header,
+            &block.block_sig,
+            &BlockTransactionHashes::from(&block.txns),
+            None,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn transaction_hashes_merkle_roots() {
+        let block = valid_block();
+
+        assert_eq!(
+            BlockTransactionHashes::from(&block.txns).merkle_roots(),
+            BlockMerkleRoots::from_transactions(&block.txns)
+        );
+    }
+
+    #[test]
+    fn previous_hash_mismatch() {
+        let mut block = valid_block();
+        block.block_header.beacon.hash_prev_block = Hash::SHA256([2; 32]);
+
+        assert!(matches!(
+            verify_block(&block),
+            Err(BlockError::PreviousHashMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn block_older_than_previous() {
+        let mut block = valid_block();
+        block.block_header.beacon.checkpoint = 0;
+
+        assert!(matches!(
+            verify_block(&block),
+            Err(BlockError::BlockOlderThanTip {
+                chain_epoch: 1,
+                block_epoch: 0,
+            })
+        ));
+    }
+
+    #[test]
+    fn not_valid_merkle_tree() {
+        let mut block = valid_block();
+        block.txns.value_transfer_txns.clear();
+
+        assert!(matches!(
+            verify_block(&block),
+            Err(BlockError::NotValidMerkleTree)
+        ));
+    }
+
+    #[test]
+    fn malformed_vrf_proof() {
+        let mut block = valid_block();
+        block.block_header.proof = BlockEligibilityClaim::default();
+
+        assert!(matches!(verify_block(&block), Err(BlockError::NotValidPoe)));
+    }
+
+    #[test]
+    fn public_key_hash_mismatch() {
+        let mut block = valid_block();
+        block.block_sig = sign_header(&block.block_header, [0x43; 32]);
+
+        assert!(matches!(
+            verify_block(&block),
+            Err(BlockError::PublicKeyHashMismatch { .. })
+        ));
+    }
+
+    #[test]
+    fn verify_signature_fail() {
+        let mut block = valid_block();
+        // A signature made before changing the header is no longer valid
+        block.block_header.signals = 1;
+        let hash = block.block_header.hash();
+
+        assert!(matches!(
+            verify_block(&block),
+            Err(BlockError::VerifySignatureFail { hash: h }) if h == hash
+        ));
+    }
+}
@@ -1,3 +1,5 @@
+/// Verification of block headers without the chain state, for light clients
+pub mod header;
 /// Keeps track of priority being used by transactions included in recent blocks, and provides
 /// methods for estimating sensible priority values for future transactions.
 pub mod priority;
@@ -35,7 +37,10 @@ use witnet_protected::Protected;
 use witnet_reputation::{ActiveReputationSet, TotalReputationSet};
 
 use crate::{
-    chain::{tapi::TapiEngine, Signature::Secp256k1},
+    chain::{
+        tapi::{ActiveWips, TapiEngine},
+        Signature::Secp256k1,
+    },
     data_request::{calculate_reward_collateral_ratio, DataRequestPool},
     error::{
         DataRequestError, EpochCalculationError, OutputPointerParseError, Secp256k1ConversionError,
@@ -240,7 +245,134 @@ pub struct ConsensusConstants {
     pub halving_period: u32,
 }
 
+/// Reward to collateral percentage required for a data request to be included in a block after
+/// WIP0022.
+///
+/// It is not part of `ConsensusConstants` because that would change the magic number.
+pub const PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO: u64 = 125;
+
+/// Value of `collateral_age` after WIP0027 (1 week).
+///
+/// It is not part of `ConsensusConstants` because that would change the magic number.
+pub const PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE: u32 = 13440;
+
+/// Maximum size in bytes of the body of the HTTP response to a retrieval after WIP0042 (4 MiB).
+///
+/// It is not part of `ConsensusConstants` because that would change the magic number.
+pub const PSEUDO_CONSENSUS_CONSTANTS_WIP0042_MAX_HTTP_RESPONSE_SIZE: usize = 4 * 1024 * 1024;
+
 impl ConsensusConstants {
+    /// Canonical consensus constants of an environment.
+    ///
+    /// This is the single source of truth for these values: the configuration defaults, the
+    /// validations and the wallet estimations all derive from it.
+    pub fn for_environment(environment: Environment) -> Self {
+        let checkpoint_zero_timestamp = match environment {
+            // Wednesday, 14-Oct-2020, 09:00 UTC
+            Environment::Mainnet => 1_602_666_000,
+            // Wednesday, 23-Sept-2020, 09:00 UTC
+            Environment::Testnet | Environment::Development => 1_600_851_600,
+        };
+
+        ConsensusConstants {
+            checkpoint_zero_timestamp,
+            checkpoints_period: 45,
+            // Brrr
+            bootstrap_hash: "666564676f6573627272727c2f3030312f3738392f3432382f6130312e676966"
+                .parse()
+                .unwrap(),
+            genesis_hash: "6ca267d9accde3336739331d42d63509b799c6431e8d02b2d2cc9d3943d7ab02"
+                .parse()
+                .unwrap(),
+            max_vt_weight: 20_000,
+            max_dr_weight: 80_000,
+            activity_period: 2000,
+            // 20_000 witnessing acts
+            reputation_expire_alpha_diff: 20_000,
+            // Issue 1 reputation point per witnessing act
+            reputation_issuance: 1,
+            // Issue reputation points for the first 2^20 witnessing acts
+            reputation_issuance_stop: 1 << 20,
+            // Lose half of the total reputation for every lie
+            reputation_penalization_factor: 0.5,
+            mining_backup_factor: 8,
+            mining_replication_factor: 3,
+            // 1 wit = 1_000_000_000 nanowits
+            collateral_minimum: 1_000_000_000,
+            collateral_age: 1000,
+            superblock_period: 10,
+            extra_rounds: 3,
+            minimum_difficulty: 2000,
+            epochs_with_minimum_difficulty: 2000,
+            bootstrapping_committee: vec![
+                "wit1g0rkajsgwqux9rnmkfca5tz6djg0f87x7ms5qx".to_string(),
+                "wit1cyrlc64hyu0rux7hclmg9rxwxpa0v9pevyaj2c".to_string(),
+                "wit1asdpcspwysf0hg5kgwvgsp2h6g65y5kg9gj5dz".to_string(),
+                "wit13l337znc5yuualnxfg9s2hu9txylntq5pyazty".to_string(),
+                "wit17nnjuxmfuu92l6rxhque2qc3u2kvmx2fske4l9".to_string(),
+                "wit1etherz02v4fvqty6jhdawefd0pl33qtevy7s4z".to_string(),
+                "wit1drcpu0xc2akfcqn8r69vw70pj8fzjhjypdcfsq".to_string(),
+                "wit1gxf0ca67vxtg27kkmgezg7dd84hwmzkxn7c62x".to_string(),
+                "wit1hujx8v0y8rzqchmmagh8yw95r943cdddnegtgc".to_string(),
+                "wit1yd97y52ezvhq4kzl6rph6d3v6e9yya3n0kwjyr".to_string(),
+                "wit1fn5yxmgkphnnuu6347s2dlqpyrm4am280s6s9t".to_string(),
+                "wit12khyjjk0s2hyuzyyhv5v2d5y5snws7l58z207g".to_string(),
+            ],
+            superblock_signing_committee_size: 100,
+            superblock_committee_decreasing_period: 5,
+            superblock_committee_decreasing_step: 5,
+            // 250 wits
+            initial_block_reward: 250 * 1_000_000_000,
+            // 3.5M epochs * (45 secs/epoch) ~> 5 years
+            // This will be the first timestamp with halved reward:
+            // 3_500_000 * 45 + 1_602_666_000 = 1_760_166_000
+            // 2025-10-11 @ 7:00am (UTC)
+            halving_period: 3_500_000,
+        }
+    }
+
+    /// Minimum input age of an UTXO for being a valid collateral, taking WIP0027 into account.
+    pub fn collateral_age_with_wips(&self, active_wips: &ActiveWips) -> u32 {
+        if active_wips.wip0027() {
+            PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE
+        } else {
+            self.collateral_age
+        }
+    }
+
+    /// Maximum size in bytes of the body of the HTTP response to a retrieval, which is only
+    /// limited after WIP0042.
+    pub fn max_http_response_size_with_wips(active_wips: &ActiveWips) -> Option<usize> {
+        Some(PSEUDO_CONSENSUS_CONSTANTS_WIP0042_MAX_HTTP_RESPONSE_SIZE)
+            .filter(|_| active_wips.wip0042())
+    }
+
+    /// Minimum reward to collateral percentage of a data request, which is the same in every
+    /// environment.
+    pub fn minimum_reward_collateral_ratio() -> u64 {
+        PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO
+    }
+
+    /// Names of the constants whose value differs between `self` and `other`, sorted
+    /// alphabetically.
+    pub fn differences(&self, other: &ConsensusConstants) -> Vec<String> {
+        let (ours, theirs) = match (serde_json::to_value(self), serde_json::to_value(other)) {
+            (Ok(serde_json::Value::Object(ours)), Ok(serde_json::Value::Object(theirs))) => {
+                (ours, theirs)
+            }
+            _ => return vec![],
+        };
+
+        let mut differences: Vec<String> = ours
+            .iter()
+            .filter(|(name, value)| theirs.get(*name) != Some(value))
+            .map(|(name, _)| name.clone())
+            .collect();
+        differences.sort();
+
+        differences
+    }
+
     /// Return the "magic number" for this consensus constants.
     /// The magic number is the first 16 bits of the hash of the consensus constants serialized
     /// using `ProtocolBuffers`.
@@ -486,24 +618,25 @@ impl Block {
         )
     }
 
+    /// Aggregated weight of the data request transactions in this block. The weight reserved by
+    /// unsolved data requests, see `DataRequestPool::unsolved_dr_weight`, is not included.
     pub fn dr_weight(&self) -> u32 {
-        let mut dr_weight = 0;
-        for dr_txn in self.txns.data_request_txns.iter() {
-            dr_weight += dr_txn.weight();
-        }
-        dr_weight
+        self.txns
+            .data_request_txns
+            .iter()
+            .fold(0, |weight, dr_txn| weight.saturating_add(dr_txn.weight()))
     }
 
+    /// Aggregated weight of the value transfer transactions in this block.
     pub fn vt_weight(&self) -> u32 {
-        let mut vt_weight = 0;
-        for vt_txn in self.txns.value_transfer_txns.iter() {
-            vt_weight += vt_txn.weight();
-        }
-        vt_weight
+        self.txns
+            .value_transfer_txns
+            .iter()
+            .fold(0, |weight, vt_txn| weight.saturating_add(vt_txn.weight()))
     }
 
     pub fn weight(&self) -> u32 {
-        self.dr_weight() + self.vt_weight()
+        self.dr_weight().saturating_add(self.vt_weight())
     }
 }
 
@@ -1434,6 +1567,31 @@ pub struct SupplyInfo {
     pub maximum_supply: u64,
 }
 
+/// Outcome of validating a transaction against the chain state of a node, without adding it to the
+/// transactions pool nor broadcasting it
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "verdict", rename_all = "lowercase")]
+pub enum TransactionValidation {
+    /// The node would accept the transaction
+    Valid {
+        /// Fee paid by the transaction
+        fee: u64,
+    },
+    /// The node would reject the transaction
+    Invalid(TransactionRejection),
+}
+
+/// Why a node would reject a transaction
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TransactionRejection {
+    /// Type of the validation error, e.g. `TransactionError` or `BlockError`
+    pub kind: String,
+    /// Variant of the validation error, e.g. `NegativeFee`
+    pub reason: String,
+    /// Description of the validation error
+    pub message: String,
+}
+
 /// Keyed signature data structure
 #[derive(Debug, Default, Eq, PartialEq, Clone, Hash, Serialize, Deserialize, ProtobufConvert)]
 #[protobuf_convert(pb = "witnet::KeyedSignature")]
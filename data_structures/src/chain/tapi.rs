@@ -124,6 +124,21 @@ pub fn all_wips_active() -> ActiveWips {
     active_wips.active_wips.insert("WIP0025".to_string(), 0);
     active_wips.active_wips.insert("WIP0026".to_string(), 0);
     active_wips.active_wips.insert("WIP0027".to_string(), 0);
+    active_wips.active_wips.insert("WIP0028".to_string(), 0);
+    active_wips.active_wips.insert("WIP0029".to_string(), 0);
+    active_wips.active_wips.insert("WIP0030".to_string(), 0);
+    active_wips.active_wips.insert("WIP0031".to_string(), 0);
+    active_wips.active_wips.insert("WIP0032".to_string(), 0);
+    active_wips.active_wips.insert("WIP0033".to_string(), 0);
+    active_wips.active_wips.insert("WIP0034".to_string(), 0);
+    active_wips.active_wips.insert("WIP0035".to_string(), 0);
+    active_wips.active_wips.insert("WIP0036".to_string(), 0);
+    active_wips.active_wips.insert("WIP0037".to_string(), 0);
+    active_wips.active_wips.insert("WIP0038".to_string(), 0);
+    active_wips.active_wips.insert("WIP0039".to_string(), 0);
+    active_wips.active_wips.insert("WIP0040".to_string(), 0);
+    active_wips.active_wips.insert("WIP0041".to_string(), 0);
+    active_wips.active_wips.insert("WIP0042".to_string(), 0);
 
     active_wips
 }
@@ -577,6 +592,66 @@ impl ActiveWips {
         self.wip_active("WIP0027")
     }
 
+    pub fn wip0028(&self) -> bool {
+        self.wip_active("WIP0028")
+    }
+
+    pub fn wip0029(&self) -> bool {
+        self.wip_active("WIP0029")
+    }
+
+    pub fn wip0030(&self) -> bool {
+        self.wip_active("WIP0030")
+    }
+
+    pub fn wip0031(&self) -> bool {
+        self.wip_active("WIP0031")
+    }
+
+    pub fn wip0032(&self) -> bool {
+        self.wip_active("WIP0032")
+    }
+
+    pub fn wip0033(&self) -> bool {
+        self.wip_active("WIP0033")
+    }
+
+    pub fn wip0034(&self) -> bool {
+        self.wip_active("WIP0034")
+    }
+
+    pub fn wip0035(&self) -> bool {
+        self.wip_active("WIP0035")
+    }
+
+    pub fn wip0036(&self) -> bool {
+        self.wip_active("WIP0036")
+    }
+
+    pub fn wip0037(&self) -> bool {
+        self.wip_active("WIP0037")
+    }
+
+    pub fn wip0038(&self) -> bool {
+        self.wip_active("WIP0038")
+    }
+
+    pub fn wip0039(&self) -> bool {
+        self.wip_active("WIP0039")
+    }
+
+    pub fn wip0040(&self) -> bool {
+        self.wip_active("WIP0040")
+    }
+
+    pub fn wip0041(&self) -> bool {
+        self.wip_active("WIP0041")
+    }
+
+    pub fn wip0042(&self) -> bool {
+        self.wip_active("WIP0042")
+    }
+
     /// Convenience method for inserting WIPs.
     pub fn insert_wip(&mut self, wip: &str, activation_epoch: Epoch) {
         self.active_wips.insert(String::from(wip), activation_epoch);
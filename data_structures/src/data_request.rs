@@ -12,7 +12,9 @@ use crate::{
     },
     error::{DataRequestError, TransactionError},
     radon_report::{RadonReport, Stage, TypeLike},
-    transaction::{CommitTransaction, DRTransaction, RevealTransaction, TallyTransaction},
+    transaction::{
+        dr_weight, CommitTransaction, DRTransaction, RevealTransaction, TallyTransaction,
+    },
 };
 use witnet_crypto::hash::calculate_sha256;
 
@@ -50,6 +52,29 @@ impl DataRequestPool {
             .collect()
     }
 
+    /// Weight reserved in a block of the given epoch by the data requests that are still waiting
+    /// for commitments, that is, all the active ones except `solved_dr_pointers`.
+    pub fn unsolved_dr_weight<'a>(
+        &self,
+        epoch: Epoch,
+        solved_dr_pointers: impl IntoIterator<Item = &'a Hash>,
+    ) -> u32 {
+        let mut dr_pointers: HashSet<Hash> = self
+            .get_dr_output_pointers_by_epoch(epoch)
+            .into_iter()
+            .collect();
+        for dr_pointer in solved_dr_pointers {
+            dr_pointers.remove(dr_pointer);
+        }
+
+        dr_pointers
+            .iter()
+            .filter_map(|dr_pointer| self.data_request_pool.get(dr_pointer))
+            .fold(0, |weight, dr_state| {
+                weight.saturating_add(dr_weight(0, 0, &dr_state.data_request))
+            })
+    }
+
     /// Get a `DataRequestOuput` for a DRTransaction `Hash`
     pub fn get_dr_output(&self, dr_pointer: &Hash) -> Option<DataRequestOutput> {
         self.data_request_pool
@@ -958,6 +983,15 @@ mod tests {
         add_data_requests();
     }
 
+    #[test]
+    fn test_unsolved_dr_weight() {
+        let (epoch, _fake_block_hash, p, dr_pointer) = add_data_requests();
+
+        // The data request has no witnesses, so it only reserves the weight of its output and tally
+        assert_eq!(p.unsolved_dr_weight(epoch, &[]), 146);
+        assert_eq!(p.unsolved_dr_weight(epoch, &[dr_pointer]), 0);
+    }
+
     #[test]
     fn test_add_data_requests_with_3_reveal_stages() {
         add_data_requests_with_3_reveal_stages();
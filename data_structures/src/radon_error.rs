@@ -29,11 +29,19 @@ pub enum RadonErrors {
     // Operator errors
     /// The operator does not exist.
     UnsupportedOperator = 0x20,
+    /// A regular expression given as operator argument is invalid or too complex.
+    InvalidRegex = 0x21,
     // Retrieval-specific errors
     /// At least one of the sources could not be retrieved, but returned HTTP error.
     HTTPError = 0x30,
     /// Al least one of the sources could not be retrieved, timeout reached.
     RetrieveTimeout = 0x31,
+    /// At least one of the sources refers to a secret that is not available in this node.
+    MissingSecret = 0x32,
+    /// At least one of the sources returned a response larger than the limit.
+    HTTPResponseTooLarge = 0x33,
+    /// At least one of the sources returned a response whose content type cannot be retrieved.
+    HTTPContentTypeMismatch = 0x34,
     // Math errors
     /// Math operator caused an underflow.
     Underflow = 0x40,
@@ -41,6 +49,18 @@ pub enum RadonErrors {
     Overflow = 0x41,
     /// Tried to divide by zero.
     DivisionByZero = 0x42,
+    /// Math operator caused an infinite or NaN float.
+    NonFiniteFloat = 0x43,
+    /// Raised a number to a power whose result is not a finite real number.
+    UndefinedPower = 0x44,
+    /// Raised a number to a power with an exponent too large in magnitude.
+    ExponentOutOfRange = 0x45,
+    /// Tried to convert a NaN float into an integer.
+    FloatIsNaN = 0x46,
+    /// Tried to convert an infinite float into an integer.
+    FloatIsInfinite = 0x47,
+    /// Tried to convert a JSON number that is too large to be exact.
+    JsonPrecisionLoss = 0x48,
     // Other errors
     /// Received zero reveals
     NoReveals = 0x50,
@@ -59,6 +79,12 @@ pub enum RadonErrors {
     ArrayIndexOutOfBounds = 0x70,
     /// Tried to access a value from a map using a key that does not exist
     MapKeyNotFound = 0x71,
+    /// Tried to extract a regular expression capture from a string that does not match it
+    RegexNoMatch = 0x72,
+    /// Tried to rename more than one key of a map to the same name
+    MapKeyCollision = 0x73,
+    /// Tried to join into a string an array with an element that is not a string
+    ArrayJoinTypeMismatch = 0x74,
     // Bridge errors: errors that only belong in inter-client communication
     /// Requests that cannot be parsed must always get this error as their result.
     /// However, this is not a valid result in a Tally transaction, because invalid requests
@@ -156,7 +182,7 @@ where
     IE: ErrorLike,
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "RadonError({:?})", self.inner)
+        write!(f, "RadonError({})", self.inner)
     }
 }
 
@@ -3,7 +3,10 @@ use std::time::{Duration, SystemTime};
 
 use serde::Serialize;
 
-use crate::{chain::tapi::ActiveWips, radon_error::ErrorLike};
+use crate::{
+    chain::{tapi::ActiveWips, Epoch, Hash},
+    radon_error::ErrorLike,
+};
 
 /// A high level data structure aimed to be used as the return type of RAD executor methods:
 ///
@@ -99,6 +102,50 @@ pub trait TypeLike: Clone + Sized {
     fn intercept(result: Result<Self, Self::Error>) -> Self;
 }
 
+/// Whether a RADON script is executed as consensus does, or for the convenience of a local user.
+///
+/// Results and errors must be the same in both modes. Local executions may additionally record
+/// traces of what happened, like the partial results of the calls or the elements removed by each
+/// filter, which consensus executions never do, so that a mistake in those conveniences cannot
+/// make a node compute different tallies than the rest of the network.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecutionMode {
+    /// Execution of data requests by witnesses and of tallies by miners and validators.
+    #[default]
+    Consensus,
+    /// Execution in a wallet or a toolkit, e.g. to try out a data request before deploying it.
+    Local,
+}
+
+/// The metadata of a data request that the retrievals of its sources can refer to, e.g. to append
+/// it as a query parameter of their URL.
+///
+/// Only metadata that every witness of a commitment round knows and agrees on is included, so that
+/// all of them send the same requests. The index of the witness is left out on purpose, as it is
+/// different for every witness.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct DataRequestMetadata {
+    /// The hash of the data request transaction.
+    pub dr_hash: Hash,
+    /// The epoch of the commitment round in which the data request is resolved.
+    pub epoch: Epoch,
+    /// Whether these are placeholders for trying out a data request locally, rather than the
+    /// metadata of a data request that is actually being resolved.
+    pub simulated: bool,
+}
+
+impl DataRequestMetadata {
+    /// Placeholder metadata for local executions of data requests that have not been broadcast:
+    /// the zero hash and epoch.
+    pub fn simulated() -> Self {
+        Self {
+            simulated: true,
+            ..Default::default()
+        }
+    }
+}
+
 /// A generic structure for bubbling up any kind of metadata that may be generated during the
 /// execution of a RADON script. This is specially useful for tracing errors back to specific calls
 /// in scripts.
@@ -133,6 +180,13 @@ where
     pub script_index: Option<usize>,
     /// Active WIPs
     pub active_wips: Option<ActiveWips>,
+    /// The elements removed by each of the filters that have been processed, if enabled.
+    pub filter_breakdown: Option<FilterBreakdown>,
+    /// Whether the execution is the one of consensus or a local one.
+    pub mode: ExecutionMode,
+    /// The data request whose retrievals are executed, which the URL and headers of the sources
+    /// can refer to through variables.
+    pub data_request: Option<DataRequestMetadata>,
 }
 
 impl<RT> Default for ReportContext<RT>
@@ -150,6 +204,9 @@ where
             start_time: None,
             script_index: None,
             active_wips: None,
+            filter_breakdown: None,
+            mode: ExecutionMode::Consensus,
+            data_request: None,
         }
     }
 }
@@ -199,6 +256,108 @@ where
             ..Default::default()
         }
     }
+
+    /// Include the metadata of the data request whose retrievals are executed.
+    pub fn set_data_request(&mut self, data_request: DataRequestMetadata) {
+        self.data_request = Some(data_request);
+    }
+
+    /// Record which elements of its input a filter removed, if the filter breakdown is enabled.
+    /// `removed` has one item per element in the input of the filter.
+    ///
+    /// The removed elements are identified by their position in the array that was input to the
+    /// first filter. In the tally stage, that is the position of the reveal, as the reveals that
+    /// were already classified as liars never make it to the filters.
+    pub fn record_removals(&mut self, removed: &[bool], reason: RemovalReason) {
+        let breakdown = match self.filter_breakdown.as_mut() {
+            Some(breakdown) => breakdown,
+            None => return,
+        };
+        debug_assert_eq!(
+            self.mode,
+            ExecutionMode::Local,
+            "The filter breakdown is only recorded by local executions"
+        );
+
+        // A filter whose input does not match the output of the previous one is applied to a
+        // different array
+        if breakdown.positions.len() != removed.len() {
+            breakdown.positions = match &self.stage {
+                Stage::Tally(metadata)
+                    if metadata.liars.iter().filter(|liar| !**liar).count() == removed.len() =>
+                {
+                    metadata
+                        .liars
+                        .iter()
+                        .enumerate()
+                        .filter(|(_, liar)| !**liar)
+                        .map(|(position, _)| position)
+                        .collect()
+                }
+                _ => (0..removed.len()).collect(),
+            };
+        }
+
+        let mut kept = Vec::with_capacity(removed.len());
+        let mut stage = FilterStage {
+            call_index: self.call_index,
+            removed: vec![],
+        };
+        for (&position, &removed) in breakdown.positions.iter().zip(removed) {
+            if removed {
+                stage.removed.push(RemovedElement {
+                    index: position,
+                    reason,
+                });
+            } else {
+                kept.push(position);
+            }
+        }
+        breakdown.positions = kept;
+        breakdown.stages.push(stage);
+    }
+}
+
+/// Why a filter removed an element from an array.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RemovalReason {
+    /// The element does not satisfy the subscript of the filter, e.g. it is out of the range
+    /// checked by the subscript.
+    OutOfRange,
+    /// The element deviates from the mean of the array more than the filter allows.
+    DeviationExceeded,
+    /// The element is not the mode of the array.
+    NotMode,
+}
+
+/// An element removed by a filter.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RemovedElement {
+    /// Position of the element in the array that was input to the first filter.
+    pub index: usize,
+    /// Why the element was removed.
+    pub reason: RemovalReason,
+}
+
+/// The elements removed by one filter.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FilterStage {
+    /// The index of the filter call in the script, if breakpoints are enabled.
+    pub call_index: Option<usize>,
+    /// The removed elements, in the order of the array.
+    pub removed: Vec<RemovedElement>,
+}
+
+/// The elements removed by each of the filters applied to an array, for debugging filters that
+/// have multiple stages. This is only recorded in local executions, never in consensus.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct FilterBreakdown {
+    /// One item per filter that has been applied, in order of application.
+    pub stages: Vec<FilterStage>,
+    /// Positions of the elements that passed every filter so far.
+    #[serde(skip)]
+    positions: Vec<usize>,
 }
 
 /// Tell different stage-specific metadata structures from each other.
@@ -244,6 +403,10 @@ where
     /// * `element_index` is the index of the element inside the array that serves as the input of
     ///     the subscript.
     pub subscript_partial_results: Vec<Vec<Vec<RT>>>,
+    /// Size in bytes of the body of the HTTP response, if the source was retrieved over HTTP.
+    pub response_size: Option<usize>,
+    /// Content type of the HTTP response, if the source was retrieved over HTTP and it had one.
+    pub content_type: Option<String>,
 }
 
 impl<RT> Default for RetrievalMetadata<RT>
@@ -253,6 +416,8 @@ where
     fn default() -> Self {
         Self {
             subscript_partial_results: vec![],
+            response_size: None,
+            content_type: None,
         }
     }
 }
@@ -430,4 +595,60 @@ mod tests {
         metadata.update_liars(vec![]);
         assert_eq!(metadata.liars, expected);
     }
+
+    #[test]
+    fn test_record_removals_in_tally() {
+        let mut metadata = TallyMetaData::<DummyType>::default();
+        // The reveals at positions 0 and 3 did not make it to the filters
+        metadata.update_liars(vec![true, false, false, true, false]);
+        let mut context = ReportContext {
+            stage: Stage::Tally(metadata),
+            filter_breakdown: Some(FilterBreakdown::default()),
+            mode: ExecutionMode::Local,
+            ..ReportContext::default()
+        };
+
+        for (call_index, removed, reason) in [
+            (
+                0,
+                vec![false, true, false],
+                RemovalReason::DeviationExceeded,
+            ),
+            (1, vec![true, false], RemovalReason::NotMode),
+        ] {
+            context.call_index = Some(call_index);
+            context.record_removals(&removed, reason);
+            if let Stage::Tally(metadata) = &mut context.stage {
+                metadata.update_liars(removed);
+            }
+        }
+
+        assert_eq!(
+            context.filter_breakdown.unwrap().stages,
+            vec![
+                FilterStage {
+                    call_index: Some(0),
+                    removed: vec![RemovedElement {
+                        index: 2,
+                        reason: RemovalReason::DeviationExceeded,
+                    }],
+                },
+                FilterStage {
+                    call_index: Some(1),
+                    removed: vec![RemovedElement {
+                        index: 1,
+                        reason: RemovalReason::NotMode,
+                    }],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_record_removals_disabled() {
+        let mut context = ReportContext::<DummyType>::default();
+        context.record_removals(&[true, false], RemovalReason::OutOfRange);
+
+        assert!(context.filter_breakdown.is_none());
+    }
 }
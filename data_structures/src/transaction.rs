@@ -30,6 +30,45 @@ pub const BETA: u32 = 1;
 /// to many UTXOs
 pub const GAMMA: u32 = 10;
 
+/// Weight of a value transfer transaction with the given number of inputs and outputs:
+///
+/// ```text
+/// VT_weight = N*INPUT_SIZE + M*OUTPUT_SIZE*gamma
+/// ```
+pub fn vt_weight(inputs_count: usize, outputs_count: usize) -> u32 {
+    let inputs_len = u32::try_from(inputs_count).unwrap_or(u32::MAX);
+    let outputs_len = u32::try_from(outputs_count).unwrap_or(u32::MAX);
+
+    let inputs_weight = inputs_len.saturating_mul(INPUT_SIZE);
+    let outputs_weight = outputs_len
+        .saturating_mul(OUTPUT_SIZE)
+        .saturating_mul(GAMMA);
+
+    inputs_weight.saturating_add(outputs_weight)
+}
+
+/// Weight of a data request transaction with the given number of inputs and outputs:
+///
+/// ```text
+/// DR_weight = DR_output_size*alpha + W*COMMIT + W*REVEAL*beta + TALLY*beta + N*INPUT_SIZE + (W + M)*OUTPUT_SIZE
+/// ```
+///
+/// The weight of a data request that has not been solved yet, which is still reserved in every
+/// block, is the one of a data request transaction without inputs and outputs.
+pub fn dr_weight(inputs_count: usize, outputs_count: usize, dr_output: &DataRequestOutput) -> u32 {
+    let inputs_len = u32::try_from(inputs_count).unwrap_or(u32::MAX);
+    let outputs_len = u32::try_from(outputs_count).unwrap_or(u32::MAX);
+    let dr_output_size = dr_output.weight().saturating_mul(ALPHA);
+    let dr_extra_weight = dr_output.extra_weight();
+    let inputs_weight = inputs_len.saturating_mul(INPUT_SIZE);
+    let outputs_weight = outputs_len.saturating_mul(OUTPUT_SIZE);
+
+    dr_output_size
+        .saturating_add(dr_extra_weight)
+        .saturating_add(inputs_weight)
+        .saturating_add(outputs_weight)
+}
+
 pub trait MemoizedHashable {
     fn hashable_bytes(&self) -> Vec<u8>;
     fn memoized_hash(&self) -> &MemoHash;
@@ -249,21 +288,9 @@ impl VTTransactionBody {
         }
     }
 
-    /// Value Transfer transaction weight. It is calculated as:
-    ///
-    /// ```text
-    /// VT_weight = N*INPUT_SIZE + M*OUTPUT_SIZE*gamma
-    /// ```
+    /// Value Transfer transaction weight, see [`vt_weight`].
     pub fn weight(&self) -> u32 {
-        let inputs_len = u32::try_from(self.inputs.len()).unwrap_or(u32::MAX);
-        let outputs_len = u32::try_from(self.outputs.len()).unwrap_or(u32::MAX);
-
-        let inputs_weight = inputs_len.saturating_mul(INPUT_SIZE);
-        let outputs_weight = outputs_len
-            .saturating_mul(OUTPUT_SIZE)
-            .saturating_mul(GAMMA);
-
-        inputs_weight.saturating_add(outputs_weight)
+        vt_weight(self.inputs.len(), self.outputs.len())
     }
 }
 
@@ -386,23 +413,9 @@ impl DRTransactionBody {
         }
     }
 
-    /// Data Request Transaction weight. It is calculated as:
-    ///
-    /// ```text
-    /// DR_weight = DR_output_size*alpha + W*COMMIT + W*REVEAL*beta + TALLY*beta + N*INPUT_SIZE + (W + M)*OUTPUT_SIZE
-    /// ```
+    /// Data Request Transaction weight, see [`dr_weight`].
     pub fn weight(&self) -> u32 {
-        let inputs_len = u32::try_from(self.inputs.len()).unwrap_or(u32::MAX);
-        let outputs_len = u32::try_from(self.outputs.len()).unwrap_or(u32::MAX);
-        let dr_output_size = self.dr_output.weight().saturating_mul(ALPHA);
-        let dr_extra_weight = self.dr_output.extra_weight();
-        let inputs_weight = inputs_len.saturating_mul(INPUT_SIZE);
-        let outputs_weight = outputs_len.saturating_mul(OUTPUT_SIZE);
-
-        dr_output_size
-            .saturating_add(dr_extra_weight)
-            .saturating_add(inputs_weight)
-            .saturating_add(outputs_weight)
+        dr_weight(self.inputs.len(), self.outputs.len(), &self.dr_output)
     }
 
     /// Specified data to be divided in a new level in the proof of inclusion
@@ -795,6 +808,8 @@ where
 
 #[cfg(test)]
 mod tests {
+    use proptest::prelude::*;
+
     use crate::{
         chain::{
             DataRequestOutput, Hashable, Input, KeyedSignature, PublicKeyHash, ValueTransferOutput,
@@ -1009,4 +1024,101 @@ mod tests {
         );
         assert_eq!(3495, dr_tx.weight());
     }
+
+    #[test]
+    fn test_vt_weight_fixtures() {
+        // (inputs, outputs, weight)
+        let fixtures = [
+            (0, 0, 0),
+            (1, 0, 133),
+            (0, 1, 360),
+            (1, 1, 493),
+            (2, 1, 626),
+            (1, 2, 853),
+            (3, 2, 1_119),
+            (10, 10, 4_930),
+        ];
+
+        for (inputs, outputs, weight) in fixtures {
+            assert_eq!(
+                vt_weight(inputs, outputs),
+                weight,
+                "{} inputs, {} outputs",
+                inputs,
+                outputs
+            );
+
+            let vt_body = VTTransactionBody::new(
+                vec![Input::default(); inputs],
+                vec![ValueTransferOutput::default(); outputs],
+            );
+            let vt_tx = Transaction::ValueTransfer(VTTransaction::new(vt_body, vec![]));
+            assert_eq!(vt_tx.weight(), weight);
+        }
+
+        assert_eq!(vt_weight(usize::MAX, 0), u32::MAX);
+        assert_eq!(vt_weight(0, usize::MAX), u32::MAX);
+    }
+
+    #[test]
+    fn test_dr_weight_fixtures() {
+        // (inputs, outputs, witnesses, weight)
+        let fixtures = [
+            (0, 0, 0, 146),
+            (0, 0, 1, 782),
+            (1, 0, 1, 915),
+            (1, 1, 2, 1_587),
+            (1, 1, 5, 3_495),
+            (4, 1, 3, 2_622),
+            (2, 3, 100, 64_120),
+            (1, 1, u16::MAX, 41_680_575),
+        ];
+
+        for (inputs, outputs, witnesses, weight) in fixtures {
+            let dro = DataRequestOutput {
+                witnesses,
+                ..Default::default()
+            };
+            assert_eq!(
+                dr_weight(inputs, outputs, &dro),
+                weight,
+                "{} inputs, {} outputs, {} witnesses",
+                inputs,
+                outputs,
+                witnesses
+            );
+
+            let dr_body = DRTransactionBody::new(
+                vec![Input::default(); inputs],
+                vec![ValueTransferOutput::default(); outputs],
+                dro,
+            );
+            let dr_tx = Transaction::DataRequest(DRTransaction::new(dr_body, vec![]));
+            assert_eq!(dr_tx.weight(), weight);
+        }
+
+        assert_eq!(
+            dr_weight(usize::MAX, 0, &DataRequestOutput::default()),
+            u32::MAX
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn weight_is_monotonic_in_inputs_and_outputs(
+            inputs in 0..10_000usize,
+            outputs in 0..10_000usize,
+            witnesses in any::<u16>(),
+        ) {
+            let dro = DataRequestOutput {
+                witnesses,
+                ..Default::default()
+            };
+
+            prop_assert!(vt_weight(inputs + 1, outputs) > vt_weight(inputs, outputs));
+            prop_assert!(vt_weight(inputs, outputs + 1) > vt_weight(inputs, outputs));
+            prop_assert!(dr_weight(inputs + 1, outputs, &dro) > dr_weight(inputs, outputs, &dro));
+            prop_assert!(dr_weight(inputs, outputs + 1, &dro) > dr_weight(inputs, outputs, &dro));
+        }
+    }
 }
@@ -13,7 +13,7 @@ use crate::{
     },
     error::TransactionError,
     fee::{AbsoluteFee, Fee},
-    transaction::{DRTransactionBody, VTTransactionBody, INPUT_SIZE},
+    transaction::{dr_weight, vt_weight, DRTransactionBody, VTTransactionBody, INPUT_SIZE},
     utxo_pool::{
         NodeUtxos, NodeUtxosRef, OwnUnspentOutputsPool, UnspentOutputsPool, UtxoDiff,
         UtxoSelectionStrategy,
@@ -250,35 +250,25 @@ pub fn calculate_weight(
     dro: Option<&DataRequestOutput>,
     max_weight: u32,
 ) -> Result<u32, TransactionError> {
-    let inputs = vec![Input::default(); inputs_count];
-    let outputs = vec![ValueTransferOutput::default(); outputs_count];
-
-    let weight = if let Some(dr_output) = dro {
-        let drt = DRTransactionBody::new(inputs, outputs, dr_output.clone());
-        let dr_weight = drt.weight();
-        if dr_weight > max_weight {
+    if let Some(dr_output) = dro {
+        let weight = dr_weight(inputs_count, outputs_count, dr_output);
+        if weight > max_weight {
             return Err(TransactionError::DataRequestWeightLimitExceeded {
-                weight: dr_weight,
+                weight,
                 max_weight,
                 dr_output: Box::new(dr_output.clone()),
             });
-        } else {
-            dr_weight
         }
+
+        Ok(weight)
     } else {
-        let vtt = VTTransactionBody::new(inputs, outputs);
-        let vt_weight = vtt.weight();
-        if vt_weight > max_weight {
-            return Err(TransactionError::ValueTransferWeightLimitExceeded {
-                weight: vt_weight,
-                max_weight,
-            });
-        } else {
-            vt_weight
+        let weight = vt_weight(inputs_count, outputs_count);
+        if weight > max_weight {
+            return Err(TransactionError::ValueTransferWeightLimitExceeded { weight, max_weight });
         }
-    };
 
-    Ok(weight)
+        Ok(weight)
+    }
 }
 
 /// Get total balance
@@ -7,11 +7,17 @@ use vrf::{
     VRF,
 };
 
+use witnet_crypto::secp256k1::PublicKey as Secp256k1_PublicKey;
+
 use crate::{
     chain::{CheckpointVRF, Hash, HashParseError, PublicKey, PublicKeyHash, SecretKey},
     proto::{schema::witnet, ProtobufConvert},
 };
 
+/// Length in bytes of a SECP256K1_SHA256_TAI proof: a compressed curve point, a 16-byte challenge
+/// and a 32-byte scalar.
+pub const VRF_PROOF_LENGTH: usize = 33 + 16 + 32;
+
 /// VRF context using SECP256K1 curve
 #[derive(Debug)]
 pub struct VrfCtx(ECVRF);
@@ -75,6 +81,14 @@ impl VrfProof {
         PublicKeyHash::from_public_key(&self.public_key)
     }
 
+    /// Check the structure of the proof without verifying it: it must have the right length and
+    /// its public key must be a valid curve point.
+    pub fn is_well_formed(&self) -> bool {
+        let public_key: Result<Secp256k1_PublicKey, _> = self.public_key.clone().try_into();
+
+        self.proof.len() == VRF_PROOF_LENGTH && public_key.is_ok()
+    }
+
     /// Getter for the VRF proof
     pub fn get_proof(&self) -> Vec<u8> {
         self.proof.clone()
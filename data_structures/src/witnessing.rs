@@ -1,3 +1,7 @@
+use std::{collections::BTreeMap, fmt, sync::Arc};
+
+use serde::Deserialize;
+
 /// Holds witnessing configuration after it has been validated.
 ///
 /// This is ready to use with `witnet_node::actors::RadManager::from_config` or in
@@ -9,6 +13,8 @@ where
 {
     pub transports: Vec<Option<T>>,
     pub paranoid_threshold: f32,
+    /// Secrets that the URLs and headers of retrievals can refer to
+    pub secrets: RetrievalSecrets,
 }
 
 impl<T> Default for WitnessingConfig<T>
@@ -19,6 +25,7 @@ where
         Self {
             transports: vec![None],
             paranoid_threshold: 0.51,
+            secrets: RetrievalSecrets::default(),
         }
     }
 }
@@ -43,3 +50,62 @@ where
         Ok(transports)
     }
 }
+
+/// Values that the URLs and headers of retrievals can refer to with placeholders such as
+/// `{{api_key:coinlayer}}`, so that API keys can be used without making them public on chain.
+///
+/// Secrets are only known to the local node. Their `Debug` representation only lists their names,
+/// so that they never end up in logs.
+#[derive(Clone, Default, PartialEq, Eq)]
+pub struct RetrievalSecrets(Arc<BTreeMap<String, RetrievalSecret>>);
+
+impl RetrievalSecrets {
+    /// Create a set of secrets by name. The name of a secret is the text between the braces of
+    /// its placeholders, e.g. `api_key:coinlayer`.
+    pub fn new(secrets: BTreeMap<String, RetrievalSecret>) -> Self {
+        Self(Arc::new(secrets))
+    }
+
+    /// The secret with the given name.
+    pub fn get(&self, name: &str) -> Option<&RetrievalSecret> {
+        self.0.get(name)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+impl fmt::Debug for RetrievalSecrets {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.0.keys()).finish()
+    }
+}
+
+/// A retrieval secret, along with the hosts that it can be sent to.
+///
+/// Anyone can post a data request, so secrets are only substituted into requests to the hosts of
+/// the API they belong to, rather than to any host that a data request happens to name.
+#[derive(Clone, PartialEq, Eq, Deserialize)]
+pub struct RetrievalSecret {
+    pub value: String,
+    /// Hosts that the secret can be sent to, which must match the host of the URL exactly
+    pub hosts: Vec<String>,
+}
+
+impl RetrievalSecret {
+    /// Whether the secret can be sent to `host`.
+    pub fn allows(&self, host: &str) -> bool {
+        self.hosts
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+}
+
+impl fmt::Debug for RetrievalSecret {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetrievalSecret")
+            .field("hosts", &self.hosts)
+            .finish_non_exhaustive()
+    }
+}
@@ -151,6 +151,7 @@ fn builders_build_version() {
         Some(sender_sock_addr),
         receiver_sock_addr,
         hardcoded_beacon.clone(),
+        CAPABILITIES,
     );
 
     // Check that the build_version function builds the expected message
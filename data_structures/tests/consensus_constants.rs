@@ -0,0 +1,88 @@
+use serde_json::json;
+use witnet_data_structures::chain::{ConsensusConstants, Environment};
+
+/// Mainnet consensus constants, pinned so that any accidental edit is caught.
+fn mainnet_fixture() -> serde_json::Value {
+    json!({
+        "checkpoint_zero_timestamp": 1_602_666_000,
+        "checkpoints_period": 45,
+        "bootstrap_hash": "666564676f6573627272727c2f3030312f3738392f3432382f6130312e676966",
+        "genesis_hash": "6ca267d9accde3336739331d42d63509b799c6431e8d02b2d2cc9d3943d7ab02",
+        "max_vt_weight": 20_000,
+        "max_dr_weight": 80_000,
+        "activity_period": 2000,
+        "reputation_expire_alpha_diff": 20_000,
+        "reputation_issuance": 1,
+        "reputation_issuance_stop": 1_048_576,
+        "reputation_penalization_factor": 0.5,
+        "mining_backup_factor": 8,
+        "mining_replication_factor": 3,
+        "collateral_minimum": 1_000_000_000,
+        "collateral_age": 1000,
+        "superblock_period": 10,
+        "extra_rounds": 3,
+        "minimum_difficulty": 2000,
+        "epochs_with_minimum_difficulty": 2000,
+        "bootstrapping_committee": [
+            "wit1g0rkajsgwqux9rnmkfca5tz6djg0f87x7ms5qx",
+            "wit1cyrlc64hyu0rux7hclmg9rxwxpa0v9pevyaj2c",
+            "wit1asdpcspwysf0hg5kgwvgsp2h6g65y5kg9gj5dz",
+            "wit13l337znc5yuualnxfg9s2hu9txylntq5pyazty",
+            "wit17nnjuxmfuu92l6rxhque2qc3u2kvmx2fske4l9",
+            "wit1etherz02v4fvqty6jhdawefd0pl33qtevy7s4z",
+            "wit1drcpu0xc2akfcqn8r69vw70pj8fzjhjypdcfsq",
+            "wit1gxf0ca67vxtg27kkmgezg7dd84hwmzkxn7c62x",
+            "wit1hujx8v0y8rzqchmmagh8yw95r943cdddnegtgc",
+            "wit1yd97y52ezvhq4kzl6rph6d3v6e9yya3n0kwjyr",
+            "wit1fn5yxmgkphnnuu6347s2dlqpyrm4am280s6s9t",
+            "wit12khyjjk0s2hyuzyyhv5v2d5y5snws7l58z207g"
+        ],
+        "superblock_signing_committee_size": 100,
+        "superblock_committee_decreasing_period": 5,
+        "superblock_committee_decreasing_step": 5,
+        "initial_block_reward": 250_000_000_000u64,
+        "halving_period": 3_500_000
+    })
+}
+
+#[test]
+fn mainnet_consensus_constants_match_fixture() {
+    let mainnet = ConsensusConstants::for_environment(Environment::Mainnet);
+
+    assert_eq!(serde_json::to_value(&mainnet).unwrap(), mainnet_fixture());
+
+    let fixture: ConsensusConstants = serde_json::from_value(mainnet_fixture()).unwrap();
+    assert_eq!(mainnet.get_magic(), fixture.get_magic());
+}
+
+#[test]
+fn testnet_only_differs_in_checkpoint_zero_timestamp() {
+    let mainnet = ConsensusConstants::for_environment(Environment::Mainnet);
+    let testnet = ConsensusConstants::for_environment(Environment::Testnet);
+
+    assert_eq!(
+        mainnet.differences(&testnet),
+        vec!["checkpoint_zero_timestamp".to_string()]
+    );
+    assert!(mainnet.differences(&mainnet.clone()).is_empty());
+}
+
+#[test]
+fn differences_are_sorted_by_name() {
+    let mainnet = ConsensusConstants::for_environment(Environment::Mainnet);
+    let other = ConsensusConstants {
+        superblock_period: 20,
+        collateral_minimum: 1,
+        max_vt_weight: 1,
+        ..mainnet.clone()
+    };
+
+    assert_eq!(
+        mainnet.differences(&other),
+        vec![
+            "collateral_minimum".to_string(),
+            "max_vt_weight".to_string(),
+            "superblock_period".to_string(),
+        ]
+    );
+}
@@ -0,0 +1,602 @@
+//! Property tests asserting that every protocol message survives a round trip through its protobuf
+//! serialization, i.e. `decode(encode(msg)) == msg`.
+use std::net::{IpAddr, SocketAddr};
+
+use proptest::prelude::*;
+
+use witnet_data_structures::{
+    builders::from_address,
+    chain::*,
+    proto::{schema::witnet, ProtobufConvert},
+    transaction::*,
+    types::*,
+    vrf::*,
+};
+
+/// Maximum length of the generated vectors. Kept small so that nested structures such as blocks
+/// do not explode in size.
+const MAX_LEN: usize = 4;
+
+fn edge_u32() -> impl Strategy<Value = u32> {
+    prop_oneof![Just(0), Just(1), Just(u32::MAX), any::<u32>()]
+}
+
+fn edge_u64() -> impl Strategy<Value = u64> {
+    prop_oneof![Just(0), Just(1), Just(u64::MAX), any::<u64>()]
+}
+
+fn edge_u16() -> impl Strategy<Value = u16> {
+    prop_oneof![Just(0), Just(u16::MAX), any::<u16>()]
+}
+
+fn timestamp() -> impl Strategy<Value = i64> {
+    prop_oneof![
+        Just(i64::MIN),
+        Just(-1),
+        Just(0),
+        Just(i64::MAX),
+        any::<i64>()
+    ]
+}
+
+fn bytes() -> impl Strategy<Value = Vec<u8>> {
+    prop_oneof![
+        Just(vec![]),
+        prop::collection::vec(any::<u8>(), 0..64),
+        // Something longer than a single-byte varint length prefix
+        prop::collection::vec(any::<u8>(), 128..300),
+    ]
+}
+
+fn non_empty_bytes() -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec(any::<u8>(), 1..100)
+}
+
+fn vec_of<S: Strategy>(element: S) -> impl Strategy<Value = Vec<S::Value>> {
+    prop::collection::vec(element, 0..=MAX_LEN)
+}
+
+fn hash() -> impl Strategy<Value = Hash> {
+    prop_oneof![
+        Just(Hash::default()),
+        any::<[u8; 32]>().prop_map(Hash::SHA256)
+    ]
+}
+
+fn pkh() -> impl Strategy<Value = PublicKeyHash> {
+    any::<[u8; 20]>().prop_map(|bytes| PublicKeyHash::from_bytes(&bytes).unwrap())
+}
+
+fn public_key() -> impl Strategy<Value = PublicKey> {
+    (any::<u8>(), any::<[u8; 32]>()).prop_map(|(compressed, bytes)| PublicKey { compressed, bytes })
+}
+
+fn keyed_signature() -> impl Strategy<Value = KeyedSignature> {
+    (bytes(), public_key()).prop_map(|(der, public_key)| KeyedSignature {
+        signature: Signature::Secp256k1(Secp256k1Signature { der }),
+        public_key,
+    })
+}
+
+fn vrf_proof_pb() -> impl Strategy<Value = witnet::VrfProof> {
+    (bytes(), public_key()).prop_map(|(proof, public_key)| {
+        let mut pb = witnet::VrfProof::new();
+        pb.set_proof(proof);
+        pb.set_public_key(public_key.to_pb());
+
+        pb
+    })
+}
+
+/// `Option<T>` fields are encoded as `T::default()` when `None`, so `Some(T::default())` is not
+/// representable on the wire. Only `None` or non-default values are generated.
+fn bn256_public_key() -> impl Strategy<Value = Option<Bn256PublicKey>> {
+    prop_oneof![
+        Just(None),
+        non_empty_bytes().prop_map(|public_key| {
+            let mut pb = witnet::Bn256PublicKey::new();
+            pb.set_public_key(public_key);
+
+            Some(Bn256PublicKey::from_pb(pb).unwrap())
+        }),
+    ]
+}
+
+fn checkpoint_beacon() -> impl Strategy<Value = CheckpointBeacon> {
+    (edge_u32(), hash()).prop_map(|(checkpoint, hash_prev_block)| CheckpointBeacon {
+        checkpoint,
+        hash_prev_block,
+    })
+}
+
+fn last_beacon() -> impl Strategy<Value = LastBeacon> {
+    (checkpoint_beacon(), checkpoint_beacon()).prop_map(
+        |(highest_block_checkpoint, highest_superblock_checkpoint)| LastBeacon {
+            highest_block_checkpoint,
+            highest_superblock_checkpoint,
+        },
+    )
+}
+
+fn address() -> impl Strategy<Value = Address> {
+    let ip = prop_oneof![
+        any::<u32>().prop_map(|ip| IpAddress::Ipv4 { ip }),
+        any::<[u32; 4]>().prop_map(|[ip0, ip1, ip2, ip3]| IpAddress::Ipv6 { ip0, ip1, ip2, ip3 }),
+    ];
+
+    (ip, edge_u16()).prop_map(|(ip, port)| Address { ip, port })
+}
+
+fn inventory_entry() -> impl Strategy<Value = InventoryEntry> {
+    prop_oneof![
+        hash().prop_map(InventoryEntry::Tx),
+        hash().prop_map(InventoryEntry::Block),
+        edge_u32().prop_map(InventoryEntry::SuperBlock),
+    ]
+}
+
+fn output_pointer() -> impl Strategy<Value = Input> {
+    (hash(), edge_u32()).prop_map(|(transaction_id, output_index)| {
+        Input::new(OutputPointer {
+            transaction_id,
+            output_index,
+        })
+    })
+}
+
+fn vt_output() -> impl Strategy<Value = ValueTransferOutput> {
+    (pkh(), edge_u64(), edge_u64()).prop_map(|(pkh, value, time_lock)| ValueTransferOutput {
+        pkh,
+        value,
+        time_lock,
+    })
+}
+
+fn rad_filters() -> impl Strategy<Value = Vec<RADFilter>> {
+    vec_of((edge_u32(), bytes()).prop_map(|(op, args)| RADFilter { op, args }))
+}
+
+fn rad_request() -> impl Strategy<Value = RADRequest> {
+    let kind = prop_oneof![
+        Just(RADType::Unknown),
+        Just(RADType::HttpGet),
+        Just(RADType::Rng),
+        Just(RADType::HttpPost),
+    ];
+    let retrieve = (kind, ".*", bytes(), bytes(), vec_of((".*", ".*"))).prop_map(
+        |(kind, url, script, body, headers)| RADRetrieve {
+            kind,
+            url,
+            script,
+            body,
+            headers,
+        },
+    );
+
+    (
+        edge_u64(),
+        vec_of(retrieve),
+        (rad_filters(), edge_u32()),
+        (rad_filters(), edge_u32()),
+    )
+        .prop_map(
+            |(time_lock, retrieve, (aggregate_filters, aggregate), (tally_filters, tally))| {
+                RADRequest {
+                    time_lock,
+                    retrieve,
+                    aggregate: RADAggregate {
+                        filters: aggregate_filters,
+                        reducer: aggregate,
+                    },
+                    tally: RADTally {
+                        filters: tally_filters,
+                        reducer: tally,
+                    },
+                }
+            },
+        )
+}
+
+fn dr_output() -> impl Strategy<Value = DataRequestOutput> {
+    (
+        rad_request(),
+        edge_u64(),
+        edge_u16(),
+        edge_u64(),
+        edge_u32(),
+        edge_u64(),
+    )
+        .prop_map(
+            |(
+                data_request,
+                witness_reward,
+                witnesses,
+                commit_and_reveal_fee,
+                min_consensus_percentage,
+                collateral,
+            )| DataRequestOutput {
+                data_request,
+                witness_reward,
+                witnesses,
+                commit_and_reveal_fee,
+                min_consensus_percentage,
+                collateral,
+            },
+        )
+}
+
+fn vt_transaction() -> impl Strategy<Value = VTTransaction> {
+    (
+        vec_of(output_pointer()),
+        vec_of(vt_output()),
+        vec_of(keyed_signature()),
+    )
+        .prop_map(|(inputs, outputs, signatures)| {
+            VTTransaction::new(VTTransactionBody::new(inputs, outputs), signatures)
+        })
+}
+
+fn dr_transaction() -> impl Strategy<Value = DRTransaction> {
+    (
+        vec_of(output_pointer()),
+        vec_of(vt_output()),
+        dr_output(),
+        vec_of(keyed_signature()),
+    )
+        .prop_map(|(inputs, outputs, dr_output, signatures)| {
+            DRTransaction::new(
+                DRTransactionBody::new(inputs, outputs, dr_output),
+                signatures,
+            )
+        })
+}
+
+fn commit_transaction() -> impl Strategy<Value = CommitTransaction> {
+    (
+        hash(),
+        hash(),
+        vrf_proof_pb(),
+        vec_of(output_pointer()),
+        vec_of(vt_output()),
+        bn256_public_key(),
+        vec_of(keyed_signature()),
+    )
+        .prop_map(
+            |(dr_pointer, commitment, proof, collateral, outputs, bn256_public_key, signatures)| {
+                let mut proof_pb = witnet::DataRequestEligibilityClaim::new();
+                proof_pb.set_proof(proof);
+                let proof = DataRequestEligibilityClaim::from_pb(proof_pb).unwrap();
+                let body = CommitTransactionBody::new(
+                    dr_pointer,
+                    commitment,
+                    proof,
+                    collateral,
+                    outputs,
+                    bn256_public_key,
+                );
+
+                CommitTransaction::new(body, signatures)
+            },
+        )
+}
+
+fn reveal_transaction() -> impl Strategy<Value = RevealTransaction> {
+    (hash(), bytes(), pkh(), vec_of(keyed_signature())).prop_map(
+        |(dr_pointer, reveal, pkh, signatures)| {
+            RevealTransaction::new(
+                RevealTransactionBody::new(dr_pointer, reveal, pkh),
+                signatures,
+            )
+        },
+    )
+}
+
+fn tally_transaction() -> impl Strategy<Value = TallyTransaction> {
+    (
+        hash(),
+        bytes(),
+        vec_of(vt_output()),
+        vec_of(pkh()),
+        vec_of(pkh()),
+    )
+        .prop_map(
+            |(dr_pointer, tally, outputs, out_of_consensus, error_committers)| {
+                TallyTransaction::new(
+                    dr_pointer,
+                    tally,
+                    outputs,
+                    out_of_consensus,
+                    error_committers,
+                )
+            },
+        )
+}
+
+fn mint_transaction() -> impl Strategy<Value = MintTransaction> {
+    (edge_u32(), vec_of(vt_output()))
+        .prop_map(|(epoch, outputs)| MintTransaction::new(epoch, outputs))
+}
+
+fn transaction() -> impl Strategy<Value = Transaction> {
+    prop_oneof![
+        vt_transaction().prop_map(Transaction::ValueTransfer),
+        dr_transaction().prop_map(Transaction::DataRequest),
+        commit_transaction().prop_map(Transaction::Commit),
+        reveal_transaction().prop_map(Transaction::Reveal),
+        tally_transaction().prop_map(Transaction::Tally),
+        mint_transaction().prop_map(Transaction::Mint),
+    ]
+}
+
+fn block() -> impl Strategy<Value = Block> {
+    let merkle_roots = prop::array::uniform6(hash()).prop_map(
+        |[mint_hash, vt_hash_merkle_root, dr_hash_merkle_root, commit_hash_merkle_root, reveal_hash_merkle_root, tally_hash_merkle_root]| {
+            BlockMerkleRoots {
+                mint_hash,
+                vt_hash_merkle_root,
+                dr_hash_merkle_root,
+                commit_hash_merkle_root,
+                reveal_hash_merkle_root,
+                tally_hash_merkle_root,
+            }
+        },
+    );
+    let block_header = (
+        edge_u32(),
+        checkpoint_beacon(),
+        merkle_roots,
+        vrf_proof_pb(),
+        bn256_public_key(),
+    )
+        .prop_map(|(signals, beacon, merkle_roots, proof, bn256_public_key)| {
+            let mut proof_pb = witnet::Block_BlockEligibilityClaim::new();
+            proof_pb.set_proof(proof);
+
+            BlockHeader {
+                signals,
+                beacon,
+                merkle_roots,
+                proof: BlockEligibilityClaim::from_pb(proof_pb).unwrap(),
+                bn256_public_key,
+            }
+        });
+    let txns = (
+        mint_transaction(),
+        vec_of(vt_transaction()),
+        vec_of(dr_transaction()),
+        vec_of(commit_transaction()),
+        vec_of(reveal_transaction()),
+        vec_of(tally_transaction()),
+    )
+        .prop_map(
+            |(
+                mint,
+                value_transfer_txns,
+                data_request_txns,
+                commit_txns,
+                reveal_txns,
+                tally_txns,
+            )| {
+                BlockTransactions {
+                    mint,
+                    value_transfer_txns,
+                    data_request_txns,
+                    commit_txns,
+                    reveal_txns,
+                    tally_txns,
+                }
+            },
+        );
+
+    (block_header, keyed_signature(), txns)
+        .prop_map(|(block_header, block_sig, txns)| Block::new(block_header, block_sig, txns))
+}
+
+fn superblock() -> impl Strategy<Value = SuperBlock> {
+    (edge_u32(), edge_u32(), prop::array::uniform5(hash())).prop_map(
+        |(signing_committee_length, index, [ars_root, data_request_root, last_block, last_block_in_previous_superblock, tally_root])| {
+            SuperBlock::new(
+                signing_committee_length,
+                ars_root,
+                data_request_root,
+                index,
+                last_block,
+                last_block_in_previous_superblock,
+                tally_root,
+            )
+        },
+    )
+}
+
+fn superblock_vote() -> impl Strategy<Value = SuperBlockVote> {
+    (bytes(), keyed_signature(), hash(), edge_u32()).prop_map(
+        |(signature, secp256k1_signature, superblock_hash, superblock_index)| SuperBlockVote {
+            bn256_signature: Bn256Signature { signature },
+            secp256k1_signature,
+            superblock_hash,
+            superblock_index,
+        },
+    )
+}
+
+fn version() -> impl Strategy<Value = Version> {
+    (
+        edge_u32(),
+        timestamp(),
+        edge_u64(),
+        address(),
+        address(),
+        prop_oneof![Just(String::new()), ".*"],
+        edge_u64(),
+        last_beacon(),
+    )
+        .prop_map(
+            |(
+                version,
+                timestamp,
+                capabilities,
+                sender_address,
+                receiver_address,
+                user_agent,
+                nonce,
+                beacon,
+            )| Version {
+                version,
+                timestamp,
+                capabilities,
+                sender_address,
+                receiver_address,
+                user_agent,
+                nonce,
+                beacon,
+            },
+        )
+}
+
+fn command() -> impl Strategy<Value = Command> {
+    // More than 10 alternatives need to share the same strategy type
+    prop_oneof![
+        Just(Command::GetPeers(GetPeers)).boxed(),
+        vec_of(address())
+            .prop_map(|peers| Command::Peers(Peers { peers }))
+            .boxed(),
+        Just(Command::Verack(Verack)).boxed(),
+        version().prop_map(Command::Version).boxed(),
+        block().prop_map(Command::Block).boxed(),
+        transaction().prop_map(Command::Transaction).boxed(),
+        superblock().prop_map(Command::SuperBlock).boxed(),
+        vec_of(inventory_entry())
+            .prop_map(
+                |inventory| Command::InventoryAnnouncement(InventoryAnnouncement { inventory })
+            )
+            .boxed(),
+        vec_of(inventory_entry())
+            .prop_map(|inventory| Command::InventoryRequest(InventoryRequest { inventory }))
+            .boxed(),
+        last_beacon().prop_map(Command::LastBeacon).boxed(),
+        superblock_vote().prop_map(Command::SuperBlockVote).boxed(),
+    ]
+}
+
+fn message() -> impl Strategy<Value = Message> {
+    (command(), edge_u16()).prop_map(|(kind, magic)| Message { kind, magic })
+}
+
+fn socket_addr() -> impl Strategy<Value = SocketAddr> {
+    let ip = prop_oneof![
+        any::<[u8; 4]>().prop_map(IpAddr::from),
+        any::<[u8; 16]>().prop_map(IpAddr::from),
+    ];
+
+    (ip, any::<u16>()).prop_map(|(ip, port)| SocketAddr::new(ip, port))
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(2000))]
+
+    #[test]
+    fn message_round_trip(msg in message()) {
+        let bytes = msg.to_pb_bytes().unwrap();
+        let decoded = Message::from_pb_bytes(&bytes).unwrap();
+
+        prop_assert_eq!(&decoded, &msg);
+        // Encoding must also be deterministic, as hashes are computed over it
+        prop_assert_eq!(decoded.to_pb_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn built_peers_round_trip(
+        magic in any::<u16>(),
+        peers in prop::collection::vec(socket_addr(), 0..100),
+    ) {
+        let msg = Message::build_peers(magic, &peers);
+        let decoded = Message::from_pb_bytes(&msg.to_pb_bytes().unwrap()).unwrap();
+
+        match decoded.kind {
+            Command::Peers(Peers { peers: decoded_peers }) => {
+                let decoded_peers: Vec<SocketAddr> =
+                    decoded_peers.iter().map(from_address).collect();
+                prop_assert_eq!(decoded_peers, peers);
+            }
+            other => prop_assert!(false, "expected peers, got {:?}", other),
+        }
+        prop_assert_eq!(decoded.magic, magic);
+    }
+
+    #[test]
+    fn built_version_round_trip(
+        magic in any::<u16>(),
+        sender in prop::option::of(socket_addr()),
+        receiver in socket_addr(),
+        beacon in last_beacon(),
+        capabilities in any::<u64>(),
+    ) {
+        let msg = Message::build_version(magic, sender, receiver, beacon, capabilities);
+        let decoded = Message::from_pb_bytes(&msg.to_pb_bytes().unwrap()).unwrap();
+
+        prop_assert_eq!(decoded, msg);
+    }
+
+    #[test]
+    fn decoding_arbitrary_bytes_does_not_panic(bytes in prop::collection::vec(any::<u8>(), 0..512)) {
+        let _ = Message::from_pb_bytes(&bytes);
+    }
+}
+
+#[test]
+fn empty_collections_round_trip() {
+    let msgs = vec![
+        Message::build_peers(0, &[]),
+        Message {
+            kind: Command::InventoryAnnouncement(InventoryAnnouncement { inventory: vec![] }),
+            magic: u16::MAX,
+        },
+        Message::build_block(
+            1,
+            BlockHeader::default(),
+            KeyedSignature::default(),
+            BlockTransactions::default(),
+        ),
+        Message::build_transaction(2, Transaction::Mint(MintTransaction::new(0, vec![]))),
+    ];
+
+    for msg in msgs {
+        let decoded = Message::from_pb_bytes(&msg.to_pb_bytes().unwrap()).unwrap();
+        assert_eq!(decoded, msg);
+    }
+}
+
+/// Regression: an empty BN256 public key is indistinguishable from a missing one on the wire, so
+/// it is always decoded as `None`.
+#[test]
+fn empty_bn256_public_key_decodes_as_none() {
+    let header = BlockHeader {
+        bn256_public_key: Some(Bn256PublicKey::from_pb(witnet::Bn256PublicKey::new()).unwrap()),
+        ..BlockHeader::default()
+    };
+    let msg = Message::build_block(0, header, KeyedSignature::default(), Default::default());
+
+    let decoded = Message::from_pb_bytes(&msg.to_pb_bytes().unwrap()).unwrap();
+
+    match decoded.kind {
+        Command::Block(block) => assert_eq!(block.block_header.bn256_public_key, None),
+        other => panic!("expected block, got {:?}", other),
+    }
+}
+
+/// Regression: IPv6 flow info and scope id are not part of `Address`, so peers built from them are
+/// decoded without them.
+#[test]
+fn built_peers_drop_ipv6_scope() {
+    let peer: SocketAddr = "[fe80::1%2]:21337".parse().unwrap();
+    let msg = Message::build_peers(0, &[peer]);
+
+    let decoded = Message::from_pb_bytes(&msg.to_pb_bytes().unwrap()).unwrap();
+
+    match decoded.kind {
+        Command::Peers(Peers { peers }) => {
+            assert_eq!(from_address(&peers[0]), "[fe80::1]:21337".parse().unwrap())
+        }
+        other => panic!("expected peers, got {:?}", other),
+    }
+}
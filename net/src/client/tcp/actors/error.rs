@@ -23,6 +23,12 @@ pub enum Error {
     /// The error ocurred when serializaing the request params to json.
     #[fail(display = "request params failed to serialize to json")]
     SerializeFailed(#[cause] JsonError),
+    /// The server does not implement the requested method.
+    #[fail(display = "the server does not implement method {}", method)]
+    MethodNotFound {
+        /// Name of the method.
+        method: String,
+    },
     /// The request timed out after the given duration.
     #[fail(display = "request timed out after {} milliseconds", _0)]
     RequestTimedOut(u128),
@@ -31,6 +37,24 @@ pub enum Error {
     Mailbox(#[cause] actix::MailboxError),
 }
 
+impl Error {
+    /// Whether the error means that the connection to the server is broken, rather than that the
+    /// server rejected the request.
+    pub fn is_connection_error(&self) -> bool {
+        match self {
+            Error::RequestFailed { error_kind, .. } => {
+                matches!(
+                    error_kind,
+                    TransportErrorKind::Transport(_) | TransportErrorKind::Unreachable
+                )
+            }
+            Error::RequestTimedOut(_) => true,
+            Error::Mailbox(_) => true,
+            _ => false,
+        }
+    }
+}
+
 impl From<actix::MailboxError> for Error {
     fn from(err: actix::MailboxError) -> Self {
         Error::Mailbox(err)
@@ -11,7 +11,7 @@ use std::{
 use actix::prelude::*;
 use async_jsonrpc_client::{
     transports::{shared::EventLoopHandle, tcp::TcpSocket},
-    DuplexTransport, ErrorKind as TransportErrorKind, Transport as _,
+    DuplexTransport, Transport as _,
 };
 use futures::StreamExt;
 use futures_util::compat::Compat01As03;
@@ -112,8 +112,20 @@ impl JsonRpcClient {
             .map_err(|e| log::error!("Reconnection error: {}", e))
             .expect("TCP socket reconnection should not panic, as the only possible error is malformed URL");
 
+        self.replace_connection(_handle, socket, url, timestamp, ctx);
+    }
+
+    /// Start using a new TCP connection, and recover the subscriptions on it.
+    fn replace_connection(
+        &mut self,
+        handle: EventLoopHandle,
+        socket: TcpSocket,
+        url: String,
+        timestamp: Instant,
+        ctx: &mut <Self as Actor>::Context,
+    ) {
         // Update connection info
-        self._handle = _handle;
+        self._handle = handle;
         self.connection.socket = socket;
         self.connection.timestamp = timestamp;
         self.connection.url = url;
@@ -182,8 +194,19 @@ impl JsonRpcClient {
 
         res.map_err(|err| {
             log::trace!(">> Received error: {}", err);
+            let message = err.to_string();
+            // Servers answer methods they do not implement with a JSON-RPC error of their own, which
+            // the transport only reports through its message
+            if message.contains(
+                jsonrpc_core::ErrorCode::MethodNotFound
+                    .description()
+                    .as_str(),
+            ) {
+                return Error::MethodNotFound { method };
+            }
+
             Error::RequestFailed {
-                message: err.to_string(),
+                message,
                 error_kind: err.0,
             }
         })
@@ -289,6 +312,16 @@ impl Request {
         self.timeout = duration;
         self
     }
+
+    /// Get the method of the request.
+    pub fn get_method(&self) -> &str {
+        &self.method
+    }
+
+    /// Get the params of the request.
+    pub fn get_params(&self) -> &Value {
+        &self.params
+    }
 }
 
 impl Message for Request {
@@ -327,7 +360,7 @@ impl Handler<Request> for JsonRpcClient {
                 })
                 .map_err(|err| {
                     log::error!("JSONRPC Request error: {:?}", err);
-                    if is_connection_error(&err) {
+                    if err.is_connection_error() {
                         // Backoff time is increased
                         act.increase_backoff_time();
                         act.reconnect(ctx);
@@ -431,6 +464,33 @@ impl Handler<GetCurrentNodeUrl> for JsonRpcClient {
     }
 }
 
+/// Stop using any other URL and connect to the given one right away, recovering the active
+/// subscriptions on the new connection.
+///
+/// This is meant for clients that want to decide by themselves which server to connect to. Any
+/// later reconnection will keep using this URL.
+#[derive(Clone, Debug)]
+pub struct SwitchUrl(pub String);
+
+impl Message for SwitchUrl {
+    type Result = Result<(), Error>;
+}
+
+impl Handler<SwitchUrl> for JsonRpcClient {
+    type Result = <SwitchUrl as Message>::Result;
+
+    fn handle(&mut self, SwitchUrl(url): SwitchUrl, ctx: &mut Self::Context) -> Self::Result {
+        log::info!("Switching TCP client to {}", url);
+        let (_handle, socket) = TcpSocket::new(&url).map_err(|_| Error::InvalidUrl)?;
+
+        self.urls = vec![url.clone()];
+        self.reset_backoff_time();
+        self.replace_connection(_handle, socket, url, Instant::now(), ctx);
+
+        Ok(())
+    }
+}
+
 impl StreamHandler<Result<NotifySubscriptionId, Error>> for JsonRpcClient {
     fn handle(&mut self, res: Result<NotifySubscriptionId, Error>, _ctx: &mut Self::Context) {
         match res {
@@ -455,20 +515,6 @@ impl StreamHandler<Result<NotifySubscriptionId, Error>> for JsonRpcClient {
     }
 }
 
-fn is_connection_error(err: &Error) -> bool {
-    match err {
-        Error::RequestFailed { error_kind, .. } => {
-            matches!(
-                error_kind,
-                TransportErrorKind::Transport(_) | TransportErrorKind::Unreachable
-            )
-        }
-        Error::RequestTimedOut(_) => true,
-        Error::Mailbox(_) => true,
-        _ => false,
-    }
-}
-
 /// Extract a subscription topic from a JSONRPC request
 fn subscription_topic_from_request(request: &Request) -> String {
     request
@@ -0,0 +1,142 @@
+//! Cross-origin resource sharing (CORS) policy shared by the server transports.
+
+/// Which browser origins are allowed to talk to a server.
+///
+/// Browsers are denied unless their origin is explicitly listed, so that any website opened in
+/// the same machine cannot call the server on behalf of the user.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Cors {
+    /// Allowed origins, such as `https://wallet.example.com`, or `*` for any origin.
+    ///
+    /// When empty, requests coming from a browser (that is, having an `Origin` header) are
+    /// rejected. Origins allowed through `*` are never allowed to send credentials.
+    pub allowed_origins: Vec<String>,
+    /// Whether browsers may send credentials (cookies or HTTP authentication) along with
+    /// cross-origin requests coming from an explicitly listed origin.
+    pub allow_credentials: bool,
+}
+
+impl Cors {
+    /// Whether any browser origin is allowed at all.
+    pub fn is_enabled(&self) -> bool {
+        !self.allowed_origins.is_empty()
+    }
+
+    /// Whether a request with the given `Origin` header is allowed.
+    ///
+    /// Requests without an `Origin` header do not come from a browser, so they are always allowed.
+    pub fn is_allowed(&self, origin: Option<&str>) -> bool {
+        match origin {
+            Some(origin) => self.is_listed(origin) || self.allows_any_origin(),
+            None => true,
+        }
+    }
+
+    /// Headers to add to the response to an allowed request coming from `origin`.
+    ///
+    /// Only explicitly listed origins are echoed back, and only those may send credentials. Any
+    /// other origin allowed through `*` gets a literal `*`, which browsers refuse to combine
+    /// with credentials.
+    pub fn response_headers(&self, origin: &str) -> Vec<(&'static str, String)> {
+        if self.is_listed(origin) {
+            let mut headers = vec![
+                ("Access-Control-Allow-Origin", origin.to_string()),
+                ("Vary", "Origin".to_string()),
+            ];
+            if self.allow_credentials {
+                headers.push(("Access-Control-Allow-Credentials", "true".to_string()));
+            }
+
+            headers
+        } else if self.allows_any_origin() {
+            vec![("Access-Control-Allow-Origin", "*".to_string())]
+        } else {
+            vec![]
+        }
+    }
+
+    fn is_listed(&self, origin: &str) -> bool {
+        self.allowed_origins.iter().any(|allowed| allowed == origin)
+    }
+
+    fn allows_any_origin(&self) -> bool {
+        self.allowed_origins.iter().any(|allowed| allowed == "*")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cors(allowed_origins: &[&str]) -> Cors {
+        Cors {
+            allowed_origins: allowed_origins.iter().map(ToString::to_string).collect(),
+            allow_credentials: false,
+        }
+    }
+
+    #[test]
+    fn disabled_cors_denies_browsers() {
+        let cors = cors(&[]);
+
+        assert!(!cors.is_allowed(Some("https://evil.example.com")));
+        assert!(!cors.is_allowed(Some("null")));
+        // Not a browser
+        assert!(cors.is_allowed(None));
+        assert!(cors.response_headers("https://evil.example.com").is_empty());
+    }
+
+    #[test]
+    fn only_allowed_origins_are_accepted() {
+        let cors = cors(&["https://wallet.example.com"]);
+
+        assert!(cors.is_allowed(Some("https://wallet.example.com")));
+        assert!(!cors.is_allowed(Some("https://evil.example.com")));
+        assert!(!cors.is_allowed(Some("null")));
+        // Not a browser
+        assert!(cors.is_allowed(None));
+    }
+
+    #[test]
+    fn wildcard_allows_any_origin() {
+        let mut cors = cors(&["*"]);
+        cors.allow_credentials = true;
+
+        assert!(cors.is_allowed(Some("https://evil.example.com")));
+        // The origin is not echoed back, and credentials are not allowed
+        assert_eq!(
+            cors.response_headers("https://evil.example.com"),
+            vec![("Access-Control-Allow-Origin", "*".to_string())]
+        );
+    }
+
+    #[test]
+    fn listed_origins_take_precedence_over_wildcard() {
+        let mut cors = cors(&["*", "https://wallet.example.com"]);
+        cors.allow_credentials = true;
+
+        let headers = cors.response_headers("https://wallet.example.com");
+        assert!(headers.contains(&(
+            "Access-Control-Allow-Origin",
+            "https://wallet.example.com".to_string()
+        )));
+        assert!(headers.contains(&("Access-Control-Allow-Credentials", "true".to_string())));
+    }
+
+    #[test]
+    fn credentials_header() {
+        let mut cors = cors(&["https://wallet.example.com"]);
+        let headers = cors.response_headers("https://wallet.example.com");
+        assert!(headers.contains(&(
+            "Access-Control-Allow-Origin",
+            "https://wallet.example.com".to_string()
+        )));
+        assert!(!headers
+            .iter()
+            .any(|(name, _)| *name == "Access-Control-Allow-Credentials"));
+
+        cors.allow_credentials = true;
+        let headers = cors.response_headers("https://wallet.example.com");
+        assert!(headers.contains(&("Access-Control-Allow-Credentials", "true".to_string())));
+    }
+}
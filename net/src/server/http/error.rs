@@ -0,0 +1,13 @@
+//! Error type definition
+use failure::Fail;
+
+/// Errors when starting the HTTP server
+#[derive(Debug, Fail)]
+pub enum Error {
+    /// Failed to bind the socket or to start the runtime of the server
+    #[fail(display = "{}", _0)]
+    Io(#[cause] std::io::Error),
+    /// Failed to set up the HTTP server on the bound socket
+    #[fail(display = "{}", _0)]
+    Hyper(#[cause] hyper::Error),
+}
@@ -0,0 +1,359 @@
+//! Plain HTTP server implementation, for clients that cannot use WebSockets.
+//!
+//! Every request is a JSON-RPC request POSTed to any path. As there is no way of pushing
+//! notifications to the client, subscriptions are not available through this transport.
+use std::{convert::Infallible, net, sync::Arc, thread};
+
+use futures::channel::oneshot;
+use futures_util::compat::Compat01As03;
+use hyper::{
+    header::{self, HeaderValue},
+    service::{make_service_fn, service_fn},
+    Body, Method, Request, Response, StatusCode,
+};
+use jsonrpc_core::IoHandler;
+
+use super::Cors;
+
+mod error;
+
+pub use error::Error;
+
+/// How long in seconds browsers can cache the result of a preflight request.
+const PREFLIGHT_MAX_AGE: u32 = 3600;
+
+/// A running JSON-RPC HTTP server. It is stopped when dropped.
+pub struct Server {
+    addr: net::SocketAddr,
+    _shutdown: oneshot::Sender<()>,
+}
+
+impl Server {
+    /// Create a builder for configuring and starting a server.
+    pub fn build() -> ServerBuilder {
+        ServerBuilder::default()
+    }
+
+    /// Address the server is listening on.
+    pub fn local_addr(&self) -> net::SocketAddr {
+        self.addr
+    }
+}
+
+/// Server configuration builder.
+pub struct ServerBuilder {
+    handler: IoHandler,
+    addr: net::SocketAddr,
+    cors: Cors,
+}
+
+impl Default for ServerBuilder {
+    fn default() -> Self {
+        Self {
+            handler: IoHandler::default(),
+            addr: net::SocketAddr::V4(net::SocketAddrV4::new(
+                net::Ipv4Addr::new(127, 0, 0, 1),
+                3201,
+            )),
+            cors: Cors::default(),
+        }
+    }
+}
+
+impl ServerBuilder {
+    /// Set handler
+    pub fn handler(mut self, handler: IoHandler) -> Self {
+        self.handler = handler;
+        self
+    }
+
+    /// Set the socket address to bind to.
+    pub fn addr(mut self, addr: net::SocketAddr) -> Self {
+        self.addr = addr;
+        self
+    }
+
+    /// Set which browser origins are allowed to call the server.
+    pub fn cors(mut self, cors: Cors) -> Self {
+        self.cors = cors;
+        self
+    }
+
+    /// Starts a JsonRPC HTTP server in its own thread.
+    pub fn start(self) -> Result<Server, Box<Error>> {
+        let Self {
+            handler,
+            addr,
+            cors,
+        } = self;
+
+        let listener = net::TcpListener::bind(addr).map_err(|e| Box::new(Error::Io(e)))?;
+        listener
+            .set_nonblocking(true)
+            .map_err(|e| Box::new(Error::Io(e)))?;
+        let addr = listener.local_addr().map_err(|e| Box::new(Error::Io(e)))?;
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_io()
+            .build()
+            .map_err(|e| Box::new(Error::Io(e)))?;
+        let builder = {
+            let _guard = runtime.enter();
+            hyper::Server::from_tcp(listener).map_err(|e| Box::new(Error::Hyper(e)))?
+        };
+
+        let handler = Arc::new(handler);
+        let cors = Arc::new(cors);
+        let make_service = make_service_fn(move |_| {
+            let handler = handler.clone();
+            let cors = cors.clone();
+
+            async move {
+                Ok::<_, Infallible>(service_fn(move |request| {
+                    handle_request(handler.clone(), cors.clone(), request)
+                }))
+            }
+        });
+        let (shutdown, shutdown_signal) = oneshot::channel::<()>();
+        let server = builder
+            .serve(make_service)
+            .with_graceful_shutdown(async move {
+                // Dropping the sender also shuts the server down
+                let _ = shutdown_signal.await;
+            });
+
+        thread::Builder::new()
+            .name(format!("jsonrpc-http-{}", addr))
+            .spawn(move || {
+                if let Err(e) = runtime.block_on(server) {
+                    log::error!("JSON-RPC HTTP server on {} failed: {}", addr, e);
+                }
+            })
+            .map_err(|e| Box::new(Error::Io(e)))?;
+
+        Ok(Server {
+            addr,
+            _shutdown: shutdown,
+        })
+    }
+}
+
+async fn handle_request(
+    handler: Arc<IoHandler>,
+    cors: Arc<Cors>,
+    request: Request<Body>,
+) -> Result<Response<Body>, Infallible> {
+    let origin = request
+        .headers()
+        .get(header::ORIGIN)
+        .map(|origin| String::from_utf8_lossy(origin.as_bytes()).into_owned());
+    if !cors.is_allowed(origin.as_deref()) {
+        log::warn!(
+            "Rejected JSON-RPC HTTP request from origin {}",
+            origin.unwrap_or_default()
+        );
+
+        return Ok(text_response(
+            StatusCode::FORBIDDEN,
+            "Origin of the request is not allowed",
+        ));
+    }
+
+    let mut response = match *request.method() {
+        Method::OPTIONS => preflight_response(&cors, &request),
+        Method::POST => call(&handler, request).await,
+        _ => {
+            let mut response = text_response(
+                StatusCode::METHOD_NOT_ALLOWED,
+                "JSON-RPC requests must be POSTed",
+            );
+            response
+                .headers_mut()
+                .insert(header::ALLOW, HeaderValue::from_static("POST, OPTIONS"));
+
+            response
+        }
+    };
+
+    if let Some(origin) = origin {
+        for (name, value) in cors.response_headers(&origin) {
+            if let Ok(value) = HeaderValue::from_str(&value) {
+                response.headers_mut().insert(name, value);
+            }
+        }
+    }
+
+    Ok(response)
+}
+
+/// Answer a CORS preflight request. The allowed origin headers are added by the caller.
+fn preflight_response(cors: &Cors, request: &Request<Body>) -> Response<Body> {
+    let mut response = Response::new(Body::empty());
+    *response.status_mut() = StatusCode::NO_CONTENT;
+    if cors.is_enabled() {
+        let allowed_headers = request
+            .headers()
+            .get(header::ACCESS_CONTROL_REQUEST_HEADERS)
+            .cloned()
+            .unwrap_or_else(|| HeaderValue::from_static("Content-Type"));
+        let headers = response.headers_mut();
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_METHODS,
+            HeaderValue::from_static("POST, OPTIONS"),
+        );
+        headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, allowed_headers);
+        headers.insert(header::ACCESS_CONTROL_MAX_AGE, PREFLIGHT_MAX_AGE.into());
+    }
+
+    response
+}
+
+/// Pass the body of the request to the JSON-RPC handler.
+async fn call(handler: &IoHandler, request: Request<Body>) -> Response<Body> {
+    let body = match hyper::body::to_bytes(request.into_body()).await {
+        Ok(body) => body,
+        Err(e) => {
+            return text_response(StatusCode::BAD_REQUEST, &e.to_string());
+        }
+    };
+    let body = match std::str::from_utf8(&body) {
+        Ok(body) => body,
+        Err(_) => {
+            return text_response(StatusCode::BAD_REQUEST, "Request body is not valid UTF-8");
+        }
+    };
+
+    match Compat01As03::new(handler.handle_request(body)).await {
+        Ok(Some(output)) => {
+            let mut response = Response::new(Body::from(output));
+            response.headers_mut().insert(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            );
+
+            response
+        }
+        // Notifications have no response
+        Ok(None) => {
+            let mut response = Response::new(Body::empty());
+            *response.status_mut() = StatusCode::NO_CONTENT;
+
+            response
+        }
+        Err(()) => text_response(StatusCode::INTERNAL_SERVER_ERROR, "Internal error"),
+    }
+}
+
+fn text_response(status: StatusCode, text: &str) -> Response<Body> {
+    let mut response = Response::new(Body::from(format!("{}\n", text)));
+    *response.status_mut() = status;
+    response.headers_mut().insert(
+        header::CONTENT_TYPE,
+        HeaderValue::from_static("text/plain; charset=utf-8"),
+    );
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use isahc::ReadResponseExt;
+    use jsonrpc_core::{Params, Value};
+    use serde_json::json;
+
+    use super::*;
+
+    const ALLOWED_ORIGIN: &str = "https://wallet.example.com";
+
+    fn start_server() -> Server {
+        let mut handler = IoHandler::default();
+        handler.add_method("get_wallet_infos", |_params: Params| {
+            jsonrpc_core::futures::future::ok(json!({ "infos": [] }))
+        });
+
+        Server::build()
+            .handler(handler)
+            .addr("127.0.0.1:0".parse().unwrap())
+            .cors(Cors {
+                allowed_origins: vec![ALLOWED_ORIGIN.to_string()],
+                allow_credentials: true,
+            })
+            .start()
+            .unwrap()
+    }
+
+    fn get_wallet_infos(server: &Server, origin: &str) -> isahc::Response<isahc::Body> {
+        let request = isahc::Request::post(format!("http://{}", server.local_addr()))
+            .header("Origin", origin)
+            .header("Content-Type", "application/json")
+            .body(r#"{"jsonrpc":"2.0","method":"get_wallet_infos","id":"1"}"#)
+            .unwrap();
+
+        isahc::send(request).unwrap()
+    }
+
+    #[test]
+    fn preflight_from_allowed_origin() {
+        let server = start_server();
+        let request = isahc::Request::builder()
+            .method("OPTIONS")
+            .uri(format!("http://{}", server.local_addr()))
+            .header("Origin", ALLOWED_ORIGIN)
+            .header("Access-Control-Request-Method", "POST")
+            .header("Access-Control-Request-Headers", "content-type")
+            .body(())
+            .unwrap();
+        let response = isahc::send(request).unwrap();
+
+        assert_eq!(response.status().as_u16(), 204);
+        let headers = response.headers();
+        assert_eq!(headers["access-control-allow-origin"], ALLOWED_ORIGIN);
+        assert_eq!(headers["access-control-allow-methods"], "POST, OPTIONS");
+        assert_eq!(headers["access-control-allow-headers"], "content-type");
+        assert_eq!(headers["access-control-allow-credentials"], "true");
+    }
+
+    #[test]
+    fn call_from_allowed_origin() {
+        let server = start_server();
+        let mut response = get_wallet_infos(&server, ALLOWED_ORIGIN);
+
+        assert_eq!(response.status().as_u16(), 200);
+        assert_eq!(
+            response.headers()["access-control-allow-origin"],
+            ALLOWED_ORIGIN
+        );
+        let body: Value = serde_json::from_str(&response.text().unwrap()).unwrap();
+        assert_eq!(body["result"], json!({ "infos": [] }));
+    }
+
+    #[test]
+    fn call_from_disallowed_origin() {
+        let server = start_server();
+        let response = get_wallet_infos(&server, "https://evil.example.com");
+
+        assert_eq!(response.status().as_u16(), 403);
+        assert!(response
+            .headers()
+            .get("access-control-allow-origin")
+            .is_none());
+    }
+
+    #[test]
+    fn preflight_from_disallowed_origin() {
+        let server = start_server();
+        let request = isahc::Request::builder()
+            .method("OPTIONS")
+            .uri(format!("http://{}", server.local_addr()))
+            .header("Origin", "https://evil.example.com")
+            .header("Access-Control-Request-Method", "POST")
+            .body(())
+            .unwrap();
+        let response = isahc::send(request).unwrap();
+
+        assert_eq!(response.status().as_u16(), 403);
+        assert!(response
+            .headers()
+            .get("access-control-allow-methods")
+            .is_none());
+    }
+}
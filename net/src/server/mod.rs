@@ -1,3 +1,7 @@
 //! Server implementations.
 
+pub mod cors;
+pub mod http;
 pub mod ws;
+
+pub use cors::Cors;
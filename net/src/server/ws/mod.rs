@@ -5,6 +5,8 @@ use std::sync::Arc;
 use jsonrpc_pubsub as pubsub;
 use jsonrpc_ws_server as server;
 
+use super::Cors;
+
 mod error;
 
 pub use error::Error;
@@ -25,6 +27,7 @@ impl Server {
 pub struct ServerBuilder {
     handler: PubSubHandler,
     addr: net::SocketAddr,
+    cors: Cors,
 }
 
 impl Default for ServerBuilder {
@@ -35,6 +38,7 @@ impl Default for ServerBuilder {
                 net::Ipv4Addr::new(127, 0, 0, 1),
                 3200,
             )),
+            cors: Cors::default(),
         }
     }
 }
@@ -52,13 +56,45 @@ impl ServerBuilder {
         self
     }
 
+    /// Set which browser origins are allowed to open a connection.
+    pub fn cors(mut self, cors: Cors) -> Self {
+        self.cors = cors;
+        self
+    }
+
     /// Starts a JsonRPC Websockets server.
     pub fn start(self) -> Result<Server, Box<Error>> {
-        let Self { handler, addr } = self;
+        let Self {
+            handler,
+            addr,
+            cors,
+        } = self;
 
         server::ServerBuilder::with_meta_extractor(handler, |context: &server::RequestContext| {
             Arc::new(pubsub::Session::new(context.sender()))
         })
+        .request_middleware(move |request: &server::ws::Request| {
+            // Browsers do not apply CORS to WebSockets, so the origin is checked on the upgrade
+            let origin = request.origin().ok().flatten();
+            if cors.is_allowed(origin) {
+                server::MiddlewareAction::Proceed
+            } else {
+                log::warn!(
+                    "Rejected WebSocket connection from origin {}",
+                    origin.unwrap_or_default()
+                );
+
+                server::MiddlewareAction::Respond {
+                    response: server::ws::Response::new(
+                        403,
+                        "Forbidden",
+                        b"Origin of the request is not allowed".to_vec(),
+                    ),
+                    validate_origin: false,
+                    validate_hosts: false,
+                }
+            }
+        })
         .start(&addr)
         .map(Server)
         .map_err(|err| Box::new(Error(err)))
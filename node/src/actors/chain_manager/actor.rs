@@ -5,11 +5,11 @@ use actix::{ActorTryFutureExt, ContextFutureSpawner, WrapFuture};
 use futures::future::{BoxFuture, Either};
 use futures_util::StreamExt;
 use itertools::Itertools;
-use witnet_config::defaults::PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
 use witnet_data_structures::{
     chain::{
         Block, ChainImport, ChainInfo, ChainState, CheckpointBeacon, CheckpointVRF,
-        GenesisBlockInfo, PublicKeyHash, ReputationEngine, StateMachine, SuperBlock,
+        ConsensusConstants, GenesisBlockInfo, PublicKeyHash, ReputationEngine, StateMachine,
+        SuperBlock,
     },
     data_request::DataRequestPool,
     get_environment,
@@ -148,7 +148,7 @@ impl ChainManager {
                 act.transactions_pool.set_collateral_minimum(config.consensus_constants.collateral_minimum);
 
                 // Minimum data request reward to collateral percentage required to include a data request into a block
-                let required_reward_collateral_ratio =PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
+                let required_reward_collateral_ratio = ConsensusConstants::minimum_reward_collateral_ratio();
                 act.transactions_pool.set_required_reward_collateral_ratio(required_reward_collateral_ratio);
 
                 // Store settings for Threshold Activation of Protocol Improvements
@@ -9,7 +9,6 @@ use std::{
 use actix::{prelude::*, ActorFutureExt, WrapFuture};
 use futures::future::Either;
 
-use witnet_config::defaults::PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE;
 use witnet_data_structures::{
     chain::{
         tapi::ActiveWips, Block, ChainState, CheckpointBeacon, DataRequestInfo, Epoch, Hash,
@@ -36,6 +35,7 @@ use crate::{
             GetSuperBlockVotes, GetSupplyInfo, GetUtxoInfo, IsConfirmedBlock, PeersBeacons,
             ReputationStats, Rewind, SendLastBeacon, SessionUnitResult, SetLastBeacon,
             SetPeersLimits, SignalingInfo, SnapshotExport, SnapshotImport, TryMineBlock,
+            ValidateTransaction,
         },
         sessions_manager::SessionsManager,
     },
@@ -88,6 +88,10 @@ impl Handler<EpochNotification<EveryEpochPayload>> for ChainManager {
             current_timestamp
         );
 
+        for summary in self.storage_errors.summaries() {
+            log::warn!("{}", summary);
+        }
+
         let last_checked_epoch = self.current_epoch;
         let current_epoch = msg.checkpoint;
         self.current_epoch = Some(current_epoch);
@@ -693,6 +697,28 @@ impl Handler<AddTransaction> for ChainManager {
     }
 }
 
+/// Handler for ValidateTransaction message
+impl Handler<ValidateTransaction> for ChainManager {
+    type Result = ResponseActFuture<Self, Result<u64, failure::Error>>;
+
+    fn handle(&mut self, msg: ValidateTransaction, _ctx: &mut Context<Self>) -> Self::Result {
+        // The unspent outputs of a node that is not synced may already have been spent
+        if !matches!(
+            self.sm_state,
+            StateMachine::Synced | StateMachine::AlmostSynced
+        ) {
+            return Box::pin(actix::fut::err(
+                ChainManagerError::NotSynced {
+                    current_state: self.sm_state,
+                }
+                .into(),
+            ));
+        }
+
+        self.validate_transaction(&msg.transaction)
+    }
+}
+
 /// Handler for GetBlocksEpochRange
 impl Handler<GetBlocksEpochRange> for ChainManager {
     type Result = Result<Vec<(Epoch, Hash)>, ChainManagerError>;
@@ -1557,11 +1583,9 @@ impl Handler<GetUtxoInfo> for ChainManager {
             active_wips: self.chain_state.tapi_engine.wip_activation.clone(),
             block_epoch: self.current_epoch.unwrap(),
         };
-        let collateral_age = if active_wips.wip0027() {
-            PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE
-        } else {
-            chain_info.consensus_constants.collateral_age
-        };
+        let collateral_age = chain_info
+            .consensus_constants
+            .collateral_age_with_wips(&active_wips);
         let block_number_limit = self
             .chain_state
             .block_number()
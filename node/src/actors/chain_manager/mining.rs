@@ -15,7 +15,6 @@ use actix::{
 };
 use ansi_term::Color::{White, Yellow};
 use futures::future::{try_join_all, FutureExt};
-use witnet_config::defaults::PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE;
 use witnet_data_structures::{
     chain::{
         tapi::{after_second_hard_fork, ActiveWips},
@@ -30,7 +29,7 @@ use witnet_data_structures::{
     error::TransactionError,
     get_environment,
     radon_error::RadonError,
-    radon_report::{RadonReport, ReportContext, TypeLike},
+    radon_report::{DataRequestMetadata, RadonReport, ReportContext, TypeLike},
     transaction::{
         CommitTransaction, CommitTransactionBody, DRTransactionBody, MintTransaction,
         RevealTransaction, RevealTransactionBody, TallyTransaction, VTTransactionBody,
@@ -340,11 +339,8 @@ impl ChainManager {
                 .data_request_state(&dr_pointer)
                 .map(|dr_state| (dr_pointer, dr_state.clone()))
         }) {
-            let (collateral_age, checkpoint_period) = match &self.chain_state.chain_info {
-                Some(x) => (
-                    x.consensus_constants.collateral_age,
-                    x.consensus_constants.checkpoints_period,
-                ),
+            let checkpoint_period = match &self.chain_state.chain_info {
+                Some(x) => x.consensus_constants.checkpoints_period,
                 None => {
                     log::error!("ChainInfo is None");
                     return;
@@ -362,11 +358,7 @@ impl ChainManager {
                 active_wips: self.chain_state.tapi_engine.wip_activation.clone(),
                 block_epoch: current_epoch,
             };
-            let collateral_age = if active_wips.wip0027() {
-                PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE
-            } else {
-                collateral_age
-            };
+            let collateral_age = consensus_constants.collateral_age_with_wips(&active_wips);
             let (target_hash, probability) = calculate_reppoe_threshold(
                 rep_eng,
                 &own_pkh,
@@ -561,6 +553,11 @@ impl ChainManager {
                             rad_request,
                             timeout: data_request_timeout,
                             active_wips,
+                            data_request: DataRequestMetadata {
+                                dr_hash: dr_pointer,
+                                epoch: current_epoch,
+                                simulated: false,
+                            },
                         })
                         .map(move |res|
                             res.map(move |result| match result {
@@ -928,22 +925,7 @@ pub fn build_block(
     transaction_fees += reveals_fees;
 
     // Calculate data request not solved weight
-    let mut dr_pointers: HashSet<Hash> = dr_pool
-        .get_dr_output_pointers_by_epoch(epoch)
-        .into_iter()
-        .collect();
-    for dr in solved_dr_pointers {
-        dr_pointers.remove(&dr);
-    }
-
-    for dr in dr_pointers {
-        let unsolved_dro = dr_pool.get_dr_output(&dr);
-        if let Some(dro) = unsolved_dro {
-            dr_weight = dr_weight
-                .saturating_add(dro.weight())
-                .saturating_add(dro.extra_weight());
-        }
-    }
+    dr_weight = dr_weight.saturating_add(dr_pool.unsolved_dr_weight(epoch, &solved_dr_pointers));
 
     let dro = DataRequestOutput {
         witnesses: 1,
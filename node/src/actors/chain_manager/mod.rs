@@ -47,13 +47,7 @@ use futures::future::{try_join_all, FutureExt};
 use glob::glob;
 use itertools::Itertools;
 use rand::Rng;
-use witnet_config::{
-    config::Tapi,
-    defaults::{
-        PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO,
-        PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE,
-    },
-};
+use witnet_config::config::Tapi;
 use witnet_crypto::hash::calculate_sha256;
 use witnet_data_structures::{
     chain::{
@@ -80,7 +74,7 @@ use witnet_data_structures::{
     vrf::VrfCtx,
 };
 use witnet_rad::types::RadonTypes;
-use witnet_util::timestamp::seconds_to_human_string;
+use witnet_util::{log_throttle::LogThrottle, timestamp::seconds_to_human_string};
 use witnet_validations::validations::{
     compare_block_candidates, validate_block, validate_block_transactions,
     validate_new_transaction, validate_rad_request, verify_signatures, VrfSlots,
@@ -246,6 +240,8 @@ pub struct ChainManager {
     import: Force<ChainImport<ImportError>>,
     /// Signals that a chain snapshot export is due.
     export: Force<PathBuf>,
+    /// Storage errors, which can repeat for every block while synchronizing
+    storage_errors: LogThrottle<&'static str>,
 }
 
 impl ChainManager {
@@ -631,8 +627,10 @@ impl ChainManager {
         let kvs_len = kvs.len();
         storage_mngr::put_batch(&kvs)
             .into_actor(self)
-            .map_err(|e, _, _| {
-                log::error!("Failed to persist data request report into storage: {}", e)
+            .map_err(|e, act, _| {
+                if act.storage_errors.record("storage", "data request report") {
+                    log::error!("Failed to persist data request report into storage: {}", e)
+                }
             })
             .and_then(move |_, _, _| {
                 log::trace!(
@@ -1388,17 +1386,9 @@ impl ChainManager {
             }
         }
 
-        if let (
-            Some(chain_info),
-            Some(reputation_engine),
-            Some(current_epoch),
-            Some(epoch_constants),
-        ) = (
-            self.chain_state.chain_info.as_ref(),
-            self.chain_state.reputation_engine.as_ref(),
-            self.current_epoch,
-            self.epoch_constants,
-        ) {
+        if let (Some(current_epoch), Some(epoch_constants)) =
+            (self.current_epoch, self.epoch_constants)
+        {
             if let Transaction::Commit(_) | Transaction::Reveal(_) = &msg.transaction {
                 let timestamp_mining = epoch_constants
                     .block_mining_timestamp(current_epoch)
@@ -1412,7 +1402,57 @@ impl ChainManager {
                     return Box::pin(actix::fut::ok(()));
                 }
             }
+        }
+
+        let fut =
+            self.validate_transaction(&msg.transaction)
+                .then(move |res, act, _ctx| match res {
+                    Ok(fee) => {
+                        // Broadcast valid transaction
+                        if msg.broadcast_flag {
+                            act.broadcast_item(InventoryItem::Transaction(msg.transaction.clone()));
+                        }
+
+                        // Add valid transaction to transactions_pool
+                        let tx_hash = msg.transaction.hash();
+                        let removed_transactions =
+                            act.transactions_pool.insert(msg.transaction, fee);
+                        log_removed_transactions(&removed_transactions, tx_hash);
+
+                        actix::fut::ok(())
+                    }
+                    Err(e) => {
+                        log::warn!(
+                            "Error when validating transaction {}: {}",
+                            msg.transaction.hash(),
+                            e
+                        );
+
+                        actix::fut::err(e)
+                    }
+                });
 
+        Box::pin(fut)
+    }
+
+    /// Validate a transaction as if it was going to be included in the next block, including its
+    /// signatures, without adding it to the transactions pool. Resolves to its fee.
+    #[must_use]
+    fn validate_transaction(
+        &self,
+        transaction: &Transaction,
+    ) -> ResponseActFuture<Self, Result<u64, failure::Error>> {
+        if let (
+            Some(chain_info),
+            Some(reputation_engine),
+            Some(current_epoch),
+            Some(epoch_constants),
+        ) = (
+            self.chain_state.chain_info.as_ref(),
+            self.chain_state.reputation_engine.as_ref(),
+            self.current_epoch,
+            self.epoch_constants,
+        ) {
             let mut signatures_to_verify = vec![];
             let mut vrf_input = chain_info.highest_vrf_output;
             vrf_input.checkpoint = current_epoch;
@@ -1422,15 +1462,13 @@ impl ChainManager {
                 // than or equal to the current epoch
                 block_epoch: current_epoch,
             };
-            let collateral_age = if active_wips.wip0027() {
-                PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE
-            } else {
-                chain_info.consensus_constants.collateral_age
-            };
+            let collateral_age = chain_info
+                .consensus_constants
+                .collateral_age_with_wips(&active_wips);
             let required_reward_collateral_ratio =
-                PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
+                ConsensusConstants::minimum_reward_collateral_ratio();
             let fut = future::ready(validate_new_transaction(
-                &msg.transaction,
+                transaction,
                 (
                     reputation_engine,
                     &self.chain_state.unspent_outputs_pool,
@@ -1455,30 +1493,6 @@ impl ChainManager {
                 signature_mngr::verify_signatures(signatures_to_verify)
                     .map(move |res| res.map(|()| fee))
                     .into_actor(act)
-            })
-            .then(move |res, act, _ctx| match res {
-                Ok(fee) => {
-                    // Broadcast valid transaction
-                    if msg.broadcast_flag {
-                        act.broadcast_item(InventoryItem::Transaction(msg.transaction.clone()));
-                    }
-
-                    // Add valid transaction to transactions_pool
-                    let tx_hash = msg.transaction.hash();
-                    let removed_transactions = act.transactions_pool.insert(msg.transaction, fee);
-                    log_removed_transactions(&removed_transactions, tx_hash);
-
-                    actix::fut::ok(())
-                }
-                Err(e) => {
-                    log::warn!(
-                        "Error when validating transaction {}: {}",
-                        msg.transaction.hash(),
-                        e
-                    );
-
-                    actix::fut::err(e)
-                }
             });
 
             Box::pin(fut)
@@ -6,9 +6,25 @@ use byteorder::{BigEndian, ReadBytesExt};
 
 use actix::Message;
 use bytes::{Buf, BufMut, BytesMut};
+use miniz_oxide::{deflate::compress_to_vec, inflate::decompress_to_vec_with_limit};
 use tokio_util::codec::{Decoder, Encoder};
 
-const HEADER_SIZE: usize = 4; // bytes
+use witnet_data_structures::builders::MAX_MESSAGE_SIZE;
+
+pub const HEADER_SIZE: usize = 4; // bytes
+
+/// Bit of the first byte of the header that tells that it is a flags byte.
+const FLAGS_MARKER: u8 = 0x80;
+
+/// Flags of the messages compressed with deflate.
+const FLAGS_DEFLATE: u8 = FLAGS_MARKER | 0x01;
+
+/// Maximum size of the messages that are sent with a flags byte, which leaves 3 bytes of the header
+/// for their size.
+const MAX_FLAGGED_MESSAGE_SIZE: usize = 0x00FF_FFFF;
+
+/// Compression level used by deflate, from 0 to 10.
+const DEFLATE_LEVEL: u8 = 6;
 
 /// Codec for client -> server transport
 ///
@@ -18,16 +34,51 @@ const HEADER_SIZE: usize = 4; // bytes
 /// Message: [u8; Message size]
 /// ```
 ///
+/// If the most significant bit of the header is set, the first byte of the header holds flags
+/// telling how the message is compressed, and only the other 3 bytes hold its size:
+/// ```ignore
+/// Flags: u8 (0x81: deflate)
+/// Message size: u24
+/// Message: [u8; Message size]
+/// ```
+/// As messages are never bigger than `MAX_MESSAGE_SIZE`, the header of uncompressed messages never
+/// has that bit set. Compressed messages are only sent to the peers that advertise support for
+/// them in the `Version` message, and only if they are at least `compression_threshold` bytes long
+/// and compressing them makes them smaller.
+///
 /// The message format is described in the file [schemas/protocol.fbs][protocol]
 ///
 /// [protocol]: https://github.com/witnet/witnet-rust/blob/master/schemas/protocol.fbs
 #[derive(Debug, Eq, PartialEq, Clone)]
-pub struct P2PCodec;
+pub struct P2PCodec {
+    /// Size in bytes from which messages are compressed, or `None` if compression is disabled, in
+    /// which case compressed messages are refused
+    compression_threshold: Option<usize>,
+}
+
+impl P2PCodec {
+    /// Create a codec that compresses the messages of at least `compression_threshold` bytes, or
+    /// that does not compress them at all if it is `None`.
+    pub fn new(compression_threshold: Option<usize>) -> Self {
+        Self {
+            compression_threshold,
+        }
+    }
+}
 
 impl Message for P2PCodec {
     type Result = ();
 }
 
+/// Message to be sent through the P2P codec.
+#[derive(Debug, Eq, PartialEq, Clone)]
+pub struct Frame {
+    /// Serialized message
+    pub bytes: BytesMut,
+    /// Whether the peer supports compressed messages
+    pub compress: bool,
+}
+
 /// Implement decoder trait for P2P codec
 impl Decoder for P2PCodec {
     type Item = BytesMut;
@@ -39,10 +90,43 @@ impl Decoder for P2PCodec {
         let msg_len = src.len();
         if msg_len >= HEADER_SIZE {
             let mut header_vec = Cursor::new(&src[0..HEADER_SIZE]);
-            let msg_size = usize::try_from(header_vec.read_u32::<BigEndian>().unwrap()).unwrap();
+            let header = header_vec.read_u32::<BigEndian>().unwrap();
+            let flags = header.to_be_bytes()[0];
+            let (flags, msg_size) = if flags & FLAGS_MARKER == 0 {
+                (None, usize::try_from(header).unwrap())
+            } else {
+                (Some(flags), usize::try_from(header & 0x00FF_FFFF).unwrap())
+            };
+            if msg_size > MAX_MESSAGE_SIZE {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!(
+                        "Message size {} bytes exceeds the maximum of {} bytes",
+                        msg_size, MAX_MESSAGE_SIZE
+                    ),
+                ));
+            }
+            if let Some(flags) = flags {
+                if self.compression_threshold.is_none() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "Received a compressed message, but compression is disabled",
+                    ));
+                }
+                if flags != FLAGS_DEFLATE {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("Unknown message flags {:#04x}", flags),
+                    ));
+                }
+            }
             if msg_len - HEADER_SIZE >= msg_size {
                 src.advance(HEADER_SIZE);
-                ftb = Some(src.split_to(msg_size));
+                let msg = src.split_to(msg_size);
+                ftb = Some(match flags {
+                    Some(_) => decompress(&msg)?,
+                    None => msg,
+                });
             }
         }
         // If the message is incomplete, return without consuming anything.
@@ -53,11 +137,30 @@ impl Decoder for P2PCodec {
 }
 
 /// Implement encoder trait for P2P codec
-impl Encoder<BytesMut> for P2PCodec {
+impl Encoder<Frame> for P2PCodec {
     type Error = io::Error;
 
     /// Method to encode a response into bytes
-    fn encode(&mut self, bytes: BytesMut, dst: &mut BytesMut) -> Result<(), Self::Error> {
+    fn encode(&mut self, frame: Frame, dst: &mut BytesMut) -> Result<(), Self::Error> {
+        let compressed = match self.compression_threshold {
+            Some(threshold) if frame.compress && frame.bytes.len() >= threshold => {
+                Some(compress_to_vec(&frame.bytes, DEFLATE_LEVEL))
+                    .filter(|compressed| compressed.len() < frame.bytes.len())
+                    .filter(|compressed| compressed.len() <= MAX_FLAGGED_MESSAGE_SIZE)
+            }
+            _ => None,
+        };
+        if let Some(compressed) = compressed {
+            // The size fits in the 3 bytes that follow the flags
+            let header = u32::try_from(compressed.len()).unwrap();
+            dst.put_u8(FLAGS_DEFLATE);
+            dst.put_slice(&header.to_be_bytes()[1..]);
+            dst.put_slice(&compressed);
+
+            return Ok(());
+        }
+
+        let bytes = frame.bytes;
         let header: u32 = u32::try_from(bytes.len()).map_err(|_| {
             log::error!("Maximum message size exceeded");
 
@@ -73,3 +176,19 @@ impl Encoder<BytesMut> for P2PCodec {
         Ok(())
     }
 }
+
+/// Decompress a message compressed with deflate, refusing to inflate it beyond `MAX_MESSAGE_SIZE`
+/// bytes.
+fn decompress(compressed: &[u8]) -> Result<BytesMut, io::Error> {
+    decompress_to_vec_with_limit(compressed, MAX_MESSAGE_SIZE)
+        .map(|bytes| BytesMut::from(bytes.as_slice()))
+        .map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Cannot decompress message into at most {} bytes: {:?}",
+                    MAX_MESSAGE_SIZE, e.status
+                ),
+            )
+        })
+}
@@ -24,7 +24,7 @@ use witnet_crypto::key::KeyPath;
 use witnet_data_structures::{
     chain::{
         tapi::ActiveWips, Block, DataRequestOutput, Epoch, Hash, Hashable, PublicKeyHash, RADType,
-        StateMachine, SyncStatus,
+        StateMachine, SyncStatus, TransactionRejection, TransactionValidation,
     },
     transaction::Transaction,
     vrf::VrfMessage,
@@ -43,7 +43,7 @@ use crate::{
             GetItemBlock, GetItemSuperblock, GetItemTransaction, GetKnownPeers,
             GetMemoryTransaction, GetMempool, GetNodeStats, GetReputation, GetSignalingInfo,
             GetState, GetSupplyInfo, GetUtxoInfo, InitializePeers, IsConfirmedBlock, Rewind,
-            SnapshotExport, SnapshotImport,
+            SnapshotExport, SnapshotImport, ValidateTransaction,
         },
         peers_manager::PeersManager,
         sessions_manager::SessionsManager,
@@ -89,6 +89,9 @@ pub fn attach_regular_methods<H>(
     server.add_actix_method(system, "inventory", |params: Params| {
         Box::pin(inventory(params.parse()))
     });
+    server.add_actix_method(system, "validateTransaction", |params: Params| {
+        Box::pin(validate_transaction(params.parse()))
+    });
     server.add_actix_method(system, "getBlockChain", |params: Params| {
         Box::pin(get_block_chain(params.parse()))
     });
@@ -526,6 +529,58 @@ pub async fn inventory(params: Result<InventoryItem, Error>) -> JsonRpcResult {
     }
 }
 
+/// Validate a transaction against the chain state of the node, without adding it to the
+/// transactions pool nor broadcasting it.
+///
+/// Input: the JSON serialization of a transaction
+///
+/// Returns whether the node would accept the transaction, along with its fee, or which validation
+/// error it would reject it with. Fails if the node cannot validate transactions yet, e.g. because
+/// it is not synced.
+pub async fn validate_transaction(params: Result<Transaction, Error>) -> JsonRpcResult {
+    let transaction = match params {
+        Ok(x) => x,
+        Err(e) => return Err(e),
+    };
+
+    let chain_manager_addr = ChainManager::from_registry();
+    let res = chain_manager_addr
+        .send(ValidateTransaction { transaction })
+        .await
+        .map_err(internal_error)?;
+    let validation = match res {
+        Ok(fee) => TransactionValidation::Valid { fee },
+        Err(e) if e.downcast_ref::<ChainManagerError>().is_some() => {
+            return Err(internal_error_s(e));
+        }
+        Err(e) => TransactionValidation::Invalid(transaction_rejection(&e)),
+    };
+
+    serde_json::to_value(validation).map_err(internal_error)
+}
+
+/// Describe a validation error by the names of its type and variant.
+fn transaction_rejection(error: &failure::Error) -> TransactionRejection {
+    let fail = error.as_fail();
+    let kind = fail
+        .name()
+        .and_then(|name| name.rsplit("::").next())
+        .unwrap_or("Error")
+        .to_string();
+    let debug = format!("{:?}", fail);
+    let reason = debug
+        .split(|c: char| !c.is_alphanumeric() && c != '_')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+
+    TransactionRejection {
+        kind,
+        reason,
+        message: error.to_string(),
+    }
+}
+
 /// Params of getBlockChain method
 #[derive(Debug, Default, Deserialize, Serialize)]
 pub struct GetBlockChainParams {
@@ -1493,6 +1548,10 @@ pub struct AddrType {
     /// "new" | "tried" when asking for all the known peers
     #[serde(rename = "type")]
     pub type_: String,
+    /// Score earned by the peer with the outcomes of the connections with it, only when asking
+    /// for all the known peers, and only if any connection outcome was recorded for it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f64>,
 }
 
 /// Result of the `peers` method
@@ -1515,6 +1574,7 @@ pub async fn peers() -> JsonRpcResult {
                             .map(|p| AddrType {
                                 address: p.to_string(),
                                 type_: "inbound".to_string(),
+                                score: None,
                             })
                             .chain(
                                 x.outbound
@@ -1523,6 +1583,7 @@ pub async fn peers() -> JsonRpcResult {
                                     .map(|p| AddrType {
                                         address: p.to_string(),
                                         type_: "outbound".to_string(),
+                                        score: None,
                                     }),
                             )
                             .collect();
@@ -1551,6 +1612,7 @@ pub async fn known_peers() -> JsonRpcResult {
             res.map_err(internal_error)
                 .and_then(|known_peers| match known_peers {
                     Ok(x) => {
+                        let scores = x.scores;
                         let peers: Vec<_> = x
                             .new
                             .into_iter()
@@ -1558,6 +1620,7 @@ pub async fn known_peers() -> JsonRpcResult {
                             .map(|p| AddrType {
                                 address: p.to_string(),
                                 type_: "new".to_string(),
+                                score: scores.get(&p).copied(),
                             })
                             .chain(
                                 x.tried
@@ -1566,6 +1629,7 @@ pub async fn known_peers() -> JsonRpcResult {
                                     .map(|p| AddrType {
                                         address: p.to_string(),
                                         type_: "tried".to_string(),
+                                        score: scores.get(&p).copied(),
                                     }),
                             )
                             .collect();
@@ -2037,6 +2101,45 @@ mod tests {
         assert_eq!(response, Some(expected));
     }
 
+    #[test]
+    fn validate_transaction_method() {
+        // The chain manager cannot be reached from the tests, so the node cannot validate anything
+        let transaction = Transaction::ValueTransfer(VTTransaction::default());
+        let msg = format!(
+            r#"{{"jsonrpc":"2.0","method":"validateTransaction","params":{},"id":1}}"#,
+            serde_json::to_string(&transaction).unwrap()
+        );
+        let expected = r#"{"jsonrpc":"2.0","error":{"code":-32603,"message":"MailboxError(Mailbox has closed)"},"id":1}"#.to_string();
+        let mut server = WittyMultiServer::new();
+        attach_api(&mut server, true, Subscriptions::default(), &None);
+        let response = server.handle_request_sync(&msg, Default::default());
+        assert_eq!(response, Some(expected));
+    }
+
+    #[test]
+    fn transaction_rejections_name_the_validation_error() {
+        use witnet_data_structures::{chain::OutputPointer, error::TransactionError};
+
+        let rejection = transaction_rejection(&TransactionError::NegativeFee.into());
+        assert_eq!(
+            rejection,
+            TransactionRejection {
+                kind: "TransactionError".to_string(),
+                reason: "NegativeFee".to_string(),
+                message: "Transaction creates value (its fee is negative)".to_string(),
+            }
+        );
+
+        let output = OutputPointer::default();
+        let rejection = transaction_rejection(&TransactionError::OutputNotFound { output }.into());
+        assert_eq!(rejection.kind, "TransactionError");
+        assert_eq!(rejection.reason, "OutputNotFound");
+        assert_eq!(
+            serde_json::to_value(TransactionValidation::Invalid(rejection)).unwrap()["verdict"],
+            "invalid"
+        );
+    }
+
     #[test]
     fn get_block_chain_abs_overflow() {
         // Ensure that the get_block_chain method does not panic when passed i64::MIN as argument
@@ -2258,6 +2361,7 @@ mod tests {
                 "signalingInfo",
                 "syncStatus",
                 "tryRequest",
+                "validateTransaction",
                 "witnet_subscribe",
                 "witnet_unsubscribe",
             ]
@@ -26,7 +26,7 @@ use witnet_data_structures::{
         SuperBlockVote, SupplyInfo, ValueTransferOutput,
     },
     fee::{deserialize_fee_backwards_compatible, Fee},
-    radon_report::RadonReport,
+    radon_report::{DataRequestMetadata, RadonReport},
     transaction::{
         CommitTransaction, DRTransaction, RevealTransaction, Transaction, VTTransaction,
     },
@@ -36,7 +36,8 @@ use witnet_data_structures::{
 };
 use witnet_p2p::{
     error::SessionsError,
-    sessions::{GetConsolidatedPeersResult, SessionStatus, SessionType},
+    peers::PeerOutcome,
+    sessions::{rotation::SessionActivity, GetConsolidatedPeersResult, SessionStatus, SessionType},
 };
 use witnet_rad::{error::RadError, types::RadonTypes};
 
@@ -121,6 +122,18 @@ impl Message for AddTransaction {
     type Result = Result<(), failure::Error>;
 }
 
+/// Validate a transaction against the current chain state without adding it to the transactions
+/// pool nor broadcasting it
+pub struct ValidateTransaction {
+    /// Transaction
+    pub transaction: Transaction,
+}
+
+impl Message for ValidateTransaction {
+    /// Fee of the transaction if it is valid
+    type Result = Result<u64, failure::Error>;
+}
+
 /// Ask for a block identified by its hash
 pub struct GetBlock {
     /// Block hash
@@ -821,6 +834,29 @@ impl Message for RemoveAddressesFromTried {
     type Result = PeersSocketAddrsResult;
 }
 
+/// Message to record the outcome of a connection with a peer, which updates its score
+pub struct ReportPeerOutcome {
+    /// Address of the peer, the one to which we connected to
+    pub address: SocketAddr,
+    /// Outcome of the connection
+    pub outcome: PeerOutcome,
+}
+
+impl Message for ReportPeerOutcome {
+    type Result = ();
+}
+
+/// Message to set the address at which other peers can reach us, so that it is never added to
+/// the buckets
+pub struct SetPublicAddress {
+    /// Address at which other peers can reach us
+    pub address: SocketAddr,
+}
+
+impl Message for SetPublicAddress {
+    type Result = ();
+}
+
 /// Message to get a (random) peer address from the list
 pub struct GetRandomPeers {
     /// Number of random peers
@@ -831,8 +867,11 @@ impl Message for GetRandomPeers {
     type Result = PeersSocketAddrsResult;
 }
 
-/// Message to get all the peer addresses from the tried list
-pub struct RequestPeers;
+/// Message to get the peer addresses from the tried list to share with a peer that asked for them
+pub struct RequestPeers {
+    /// Address of the peer that asked for them
+    pub requester: SocketAddr,
+}
 
 impl Message for RequestPeers {
     type Result = PeersSocketAddrsResult;
@@ -858,6 +897,8 @@ pub struct PeersNewTried {
     pub new: Vec<SocketAddr>,
     /// Peers in tried bucket
     pub tried: Vec<SocketAddr>,
+    /// Current scores of the peers for which any connection outcome was recorded
+    pub scores: HashMap<SocketAddr, f64>,
 }
 
 ////////////////////////////////////////////////////////////////////////////////////////
@@ -875,6 +916,8 @@ pub struct ResolveRA {
     /// Active Witnet protocol improvements as of the current epoch.
     /// Used to select the correct version of the validation logic.
     pub active_wips: ActiveWips,
+    /// Metadata of the data request, which the URL and headers of its sources can refer to.
+    pub data_request: DataRequestMetadata,
 }
 
 /// Message for running the tally step of a data request.
@@ -1088,6 +1131,10 @@ pub struct Consolidate {
     /// is a potential peer that should try to be added
     pub potential_new_peer: Option<SocketAddr>,
 
+    /// Address at which the peer sees us, as it communicated it in the `receiver_address` of its
+    /// `Version` message
+    pub observed_address: Option<SocketAddr>,
+
     /// Session type
     pub session_type: SessionType,
 }
@@ -1181,6 +1228,40 @@ impl Message for LogMessage {
     type Result = SessionsUnitResult;
 }
 
+/// Report an error caused by the peer of a session. Only the first errors of every category from
+/// the same peer are logged in full, and the rest are summarized periodically. The peers that we
+/// connected to are also scored with how many errors they caused lately
+#[derive(Debug)]
+pub struct ReportSessionError {
+    /// Address of the peer
+    pub address: SocketAddr,
+    /// Type of the session
+    pub session_type: SessionType,
+    /// Category of the error
+    pub category: &'static str,
+    /// Error, which is only formatted if it is logged
+    pub error: failure::Error,
+}
+
+impl Message for ReportSessionError {
+    /// Number of errors of the same category from the same peer, including the ones that were not
+    /// logged
+    type Result = u64;
+}
+
+/// Report some activity of an outbound peer, used for scoring its session
+#[derive(Clone, Debug)]
+pub struct ReportSessionActivity {
+    /// Address of the peer
+    pub address: SocketAddr,
+    /// Activity of the peer
+    pub activity: SessionActivity,
+}
+
+impl Message for ReportSessionActivity {
+    type Result = ();
+}
+
 /// Drop outbound peers
 #[derive(Clone, Debug)]
 pub struct DropOutboundPeers {
@@ -1,3 +1,5 @@
+use std::{collections::HashMap, net::SocketAddr};
+
 use actix::prelude::*;
 
 use super::PeersManager;
@@ -10,7 +12,7 @@ use crate::{
     config_mngr, storage_mngr,
 };
 
-use witnet_p2p::peers::Peers;
+use witnet_p2p::peers::{PeerScore, Peers};
 
 /// Make actor from PeersManager
 impl Actor for PeersManager {
@@ -64,6 +66,18 @@ impl Actor for PeersManager {
                     })
                     .spawn(ctx);
 
+                storage_mngr::get::<_, HashMap<SocketAddr, PeerScore>>(
+                    &storage_keys::peer_scores_key(magic),
+                )
+                .into_actor(act)
+                .map(|res, act, _ctx| match res {
+                    Ok(Some(scores)) => act.peers.scores.extend(scores),
+                    // Nothing was persisted yet, or it was by a version without scores
+                    Ok(None) => {}
+                    Err(e) => log::error!("Couldn't get peer scores from storage: {}", e),
+                })
+                .spawn(ctx);
+
                 // Ask EpochManager for current epoch so that `Peers` knows about the bootstrapping
                 // status. If there is no current epoch, subscribe to first epoch so that the
                 // `bootstrapped` flag can be later set to `true` once actually bootstrapped.
@@ -1,10 +1,12 @@
+use std::convert::TryFrom;
+
 use actix::{Context, Handler};
 
 use super::PeersManager;
 use crate::actors::messages::{
     AddConsolidatedPeer, AddPeers, ClearPeers, EpochNotification, GetKnownPeers, GetRandomPeers,
     InitializePeers, PeersNewTried, PeersSocketAddrResult, PeersSocketAddrsResult,
-    RemoveAddressesFromTried, RequestPeers,
+    RemoveAddressesFromTried, ReportPeerOutcome, RequestPeers, SetPublicAddress,
 };
 use witnet_util::timestamp::get_timestamp;
 
@@ -98,6 +100,24 @@ impl Handler<RemoveAddressesFromTried> for PeersManager {
     }
 }
 
+/// Handler for ReportPeerOutcome message
+impl Handler<ReportPeerOutcome> for PeersManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: ReportPeerOutcome, _: &mut Context<Self>) -> Self::Result {
+        self.peers.record_outcome(msg.address, msg.outcome);
+    }
+}
+
+/// Handler for SetPublicAddress message
+impl Handler<SetPublicAddress> for PeersManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetPublicAddress, _: &mut Context<Self>) -> Self::Result {
+        self.peers.public_address = Some(msg.address);
+    }
+}
+
 /// Handler for GetRandomPeer message
 impl Handler<GetRandomPeers> for PeersManager {
     type Result = PeersSocketAddrsResult;
@@ -122,9 +142,13 @@ impl Handler<GetRandomPeers> for PeersManager {
 impl Handler<RequestPeers> for PeersManager {
     type Result = PeersSocketAddrsResult;
 
-    fn handle(&mut self, _msg: RequestPeers, _: &mut Context<Self>) -> Self::Result {
-        log::debug!("Get all peers");
-        self.peers.get_all_from_tried()
+    fn handle(&mut self, msg: RequestPeers, _: &mut Context<Self>) -> Self::Result {
+        log::debug!("Get peers to share with {}", msg.requester);
+        let max_age = i64::try_from(self.shared_peers_max_age.as_secs()).unwrap_or(i64::MAX);
+
+        Ok(self
+            .peers
+            .get_peers_to_share(&msg.requester, max_age, self.shared_peers_limit))
     }
 }
 
@@ -136,6 +160,7 @@ impl Handler<GetKnownPeers> for PeersManager {
         Ok(PeersNewTried {
             new: self.peers.get_all_from_new()?,
             tried: self.peers.get_all_from_tried()?,
+            scores: self.peers.current_scores(),
         })
     }
 }
@@ -44,6 +44,10 @@ pub struct PeersManager {
     pub bucketing_update_period: i64,
     /// Period in seconds for checking melted peers in the "ice" bucket
     pub check_melted_peers_period: Duration,
+    /// Only the peers seen in this period are shared with the peers that ask for them
+    pub shared_peers_max_age: Duration,
+    /// Maximum number of peers shared with a peer every time that it asks for them
+    pub shared_peers_limit: usize,
     /// Magic number from ConsensusConstants
     magic: u16,
 }
@@ -62,6 +66,8 @@ impl PeersManager {
             peers: Peers::from_config(config),
             bucketing_update_period: config.connections.bucketing_update_period,
             check_melted_peers_period: config.connections.check_melted_peers_period,
+            shared_peers_max_age: config.connections.shared_peers_max_age,
+            shared_peers_limit: config.connections.shared_peers_limit,
             magic: config.consensus_constants.get_magic(),
         }
     }
@@ -79,13 +85,37 @@ impl PeersManager {
                     }
                 })
                 .spawn(ctx);
+            storage_mngr::put(
+                &storage_keys::peer_scores_key(act.get_magic()),
+                &act.peers.scores,
+            )
+            .into_actor(act)
+            .map(|res, _act, _ctx| match res {
+                Ok(_) => log::trace!("PeersManager successfully persisted peer scores to storage"),
+                Err(err) => {
+                    log::error!(
+                        "Peers manager persist peer scores to storage failed: {}",
+                        err
+                    )
+                }
+            })
+            .spawn(ctx);
 
             act.persist_peers(ctx, storage_peers_period);
         });
     }
 
     fn import_peers(&mut self, peers: Peers, known_peers: Vec<SocketAddr>) {
-        self.peers = peers;
+        // The settings taken from the configuration are not persisted with the peers
+        self.peers = Peers {
+            ice_period: self.peers.ice_period,
+            public_address: self.peers.public_address,
+            score_half_life: self.peers.score_half_life,
+            exploration_percentage: self.peers.exploration_percentage,
+            // Scores are loaded on their own, maybe before the rest of the peers
+            scores: std::mem::take(&mut self.peers.scores),
+            ..peers
+        };
 
         match self.peers.add_to_new(known_peers, None) {
             Ok(_duplicated_peers) => {}
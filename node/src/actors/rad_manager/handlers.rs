@@ -59,6 +59,7 @@ impl Handler<ResolveRA> for RadManager {
                         settings,
                         active_wips.clone(),
                         witnessing.clone(),
+                        Some(msg.data_request),
                     )
                 })
                 .map(|fut| {
@@ -145,8 +146,9 @@ impl Handler<RunTally> for RadManager {
 #[cfg(test)]
 mod tests {
     use actix::{Actor, MailboxError, Message};
-    use witnet_data_structures::chain::{
-        tapi::all_wips_active, RADAggregate, RADRequest, RADRetrieve, RADTally, RADType,
+    use witnet_data_structures::{
+        chain::{tapi::all_wips_active, RADAggregate, RADRequest, RADRetrieve, RADTally, RADType},
+        radon_report::DataRequestMetadata,
     };
     use witnet_rad::reducers::RadonReducers;
 
@@ -273,6 +275,7 @@ mod tests {
                     rad_request,
                     timeout: None,
                     active_wips,
+                    data_request: DataRequestMetadata::default(),
                 })
                 .await
                 .unwrap()
@@ -312,6 +315,7 @@ mod tests {
                     rad_request,
                     timeout: None,
                     active_wips,
+                    data_request: DataRequestMetadata::default(),
                 })
                 .await
                 .unwrap()
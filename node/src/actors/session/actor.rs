@@ -4,7 +4,7 @@ use actix::{
 };
 
 use witnet_data_structures::types::Message as WitnetMessage;
-use witnet_p2p::sessions::{SessionStatus, SessionType};
+use witnet_p2p::sessions::SessionType;
 
 use super::{handlers::EveryEpochPayload, Session};
 use crate::actors::{
@@ -23,22 +23,10 @@ impl Actor for Session {
 
     /// Method to be executed when the actor is started
     fn started(&mut self, ctx: &mut Self::Context) {
-        // Set Handshake timeout for stopping actor if session is still unconsolidated after given period of time
-        ctx.run_later(self.config.connections.handshake_timeout, |act, ctx| {
-            if act.status != SessionStatus::Consolidated {
-                log::debug!(
-                    "Handshake timeout expired, disconnecting session with peer {:?}",
-                    act.remote_addr
-                );
-
-                if act.session_type == SessionType::Outbound {
-                    // Remove this address from tried bucket and ice it
-                    act.remove_and_ice_peer();
-                }
+        self.address = Some(ctx.address().downgrade());
 
-                ctx.stop();
-            }
-        });
+        // Set Handshake timeout for stopping actor if session is still unconsolidated after given period of time
+        self.start_handshake_timeout(ctx);
 
         // Peer registered if it is not come from feeler
         if self.session_type == SessionType::Feeler {
@@ -47,6 +35,7 @@ impl Actor for Session {
                 self.public_addr,
                 self.remote_addr,
                 self.last_beacon.clone(),
+                self.capabilities(),
             );
             self.send_message(version_msg);
             // Set HandshakeFlag of sent version message
@@ -91,6 +80,7 @@ impl Actor for Session {
                             act.public_addr,
                             act.remote_addr,
                             act.last_beacon.clone(),
+                            act.capabilities(),
                         );
                         act.send_message(version_msg);
                         // Set HandshakeFlag of sent version message
@@ -106,6 +96,9 @@ impl Actor for Session {
 
     /// Method to be executed when the actor is stopping
     fn stopping(&mut self, _: &mut Self::Context) -> Running {
+        // Score the peer with how the connection went
+        self.report_outcome_on_stop();
+
         // Get session manager address
         let session_manager_addr = SessionsManager::from_registry();
 
@@ -1,8 +1,8 @@
-use std::{cmp::Ordering, convert::TryFrom, io::Error, net::SocketAddr};
+use std::{cmp::Ordering, convert::TryFrom, io::Error, net::SocketAddr, time::Instant};
 
 use actix::{
     io::WriteHandler, ActorContext, ActorFutureExt, ActorTryFutureExt, Context,
-    ContextFutureSpawner, Handler, StreamHandler, SystemService, WrapFuture,
+    ContextFutureSpawner, Handler, MailboxError, Running, StreamHandler, SystemService, WrapFuture,
 };
 use bytes::BytesMut;
 use failure::Fail;
@@ -21,19 +21,22 @@ use witnet_data_structures::{
         Message as WitnetMessage, Peers, Version,
     },
 };
-use witnet_p2p::sessions::{SessionStatus, SessionType};
+use witnet_p2p::{
+    peers::{DisconnectReason, PeerOutcome},
+    sessions::{rotation::SessionActivity, SessionStatus, SessionType},
+};
 
-use super::Session;
+use super::{requests_peers_on_consolidation, Session};
 use crate::actors::{
     chain_manager::ChainManager,
-    inventory_manager::InventoryManager,
+    inventory_manager::{InventoryManager, InventoryManagerError},
     messages::{
         AddBlocks, AddCandidates, AddConsolidatedPeer, AddPeers, AddSuperBlock, AddSuperBlockVote,
         AddTransaction, CloseSession, Consolidate, EpochNotification, GetBlocksEpochRange,
-        GetHighestCheckpointBeacon, GetItem, GetSuperBlockVotes, PeerBeacon,
-        RemoveAddressesFromTried, RequestPeers, SendGetPeers, SendInventoryAnnouncement,
-        SendInventoryItem, SendInventoryRequest, SendLastBeacon, SendSuperBlockVote,
-        SessionUnitResult,
+        GetHighestCheckpointBeacon, GetItem, GetMemoryTransaction, GetSuperBlockVotes, PeerBeacon,
+        RemoveAddressesFromTried, ReportSessionError, RequestPeers, SendGetPeers,
+        SendInventoryAnnouncement, SendInventoryItem, SendInventoryRequest, SendLastBeacon,
+        SendSuperBlockVote, SessionUnitResult,
     },
     peers_manager::PeersManager,
     sessions_manager::SessionsManager,
@@ -78,7 +81,19 @@ enum HandshakeError {
 }
 
 /// Implement WriteHandler for Session
-impl WriteHandler<Error> for Session {}
+impl WriteHandler<Error> for Session {
+    /// A failed write means that the peer will not receive any more messages, so the session is
+    /// stopped, which also unregisters it from the SessionsManager
+    fn error(&mut self, err: Error, _ctx: &mut Self::Context) -> Running {
+        log::warn!(
+            "Error writing to session {:?}, closing it: {}",
+            self.remote_addr,
+            err
+        );
+
+        Running::Stop
+    }
+}
 
 /// Payload for the notification for a specific epoch
 #[derive(Debug)]
@@ -103,6 +118,7 @@ impl Handler<EpochNotification<EveryEpochPayload>> for Session {
         );
 
         self.current_epoch = msg.checkpoint;
+        self.report_activity(SessionActivity::PendingWrites(self.write_queue.pending()));
 
         if self.blocks_timestamp != 0
             && current_timestamp - self.blocks_timestamp > self.config.connections.blocks_timeout
@@ -112,6 +128,7 @@ impl Handler<EpochNotification<EveryEpochPayload>> for Session {
 
             // Remove this address from tried bucket and ice it
             self.remove_and_ice_peer();
+            self.disconnect_reason = DisconnectReason::Timeout;
 
             chain_manager_addr.do_send(AddBlocks {
                 blocks: vec![],
@@ -127,6 +144,11 @@ impl Handler<EpochNotification<EveryEpochPayload>> for Session {
 impl StreamHandler<Result<BytesMut, Error>> for Session {
     /// This is main event loop for client requests
     fn handle(&mut self, res: Result<BytesMut, Error>, ctx: &mut Self::Context) {
+        // The session is closing because the peer did not complete the handshake in time
+        if self.handshake_expired {
+            return;
+        }
+
         if res.is_err() {
             // TODO: how to handle this error?
             return;
@@ -137,10 +159,19 @@ impl StreamHandler<Result<BytesMut, Error>> for Session {
 
         match result {
             Err(err) => {
-                log::error!("Error decoding message: {:?}", err);
+                // Peers sending garbage can fail thousands of times per minute, so most of their
+                // errors are only counted, both for logging them
+                // and scored by the sessions manager
+                SessionsManager::from_registry().do_send(ReportSessionError {
+                    address: self.remote_addr,
+                    session_type: self.session_type,
+                    category: "decode",
+                    error: err,
+                });
 
                 // Remove this address from tried bucket and ice it
                 self.remove_and_ice_peer();
+                self.disconnect_reason = DisconnectReason::Misbehaved;
 
                 ctx.stop();
             }
@@ -158,6 +189,7 @@ impl StreamHandler<Result<BytesMut, Error>> for Session {
 
                     // Remove this address from tried bucket and ice it
                     self.remove_and_ice_peer();
+                    self.disconnect_reason = DisconnectReason::Misbehaved;
 
                     // Stop this session
                     ctx.stop();
@@ -251,62 +283,29 @@ impl StreamHandler<Result<BytesMut, Error>> for Session {
                         SessionStatus::Consolidated,
                         Command::InventoryRequest(InventoryRequest { inventory }),
                     ) => {
-                        let inventory_mngr = InventoryManager::from_registry();
-                        let item_requests: Vec<_> = inventory
-                            .iter()
-                            .map(|item| inventory_mngr.send(GetItem { item: item.clone() }))
-                            .collect();
+                        let item_requests: Vec<_> =
+                            inventory.iter().cloned().map(get_inventory_item).collect();
 
                         futures::future::try_join_all(item_requests)
                             .into_actor(self)
                             .map_err(|e, _, _| log::error!("Inventory request error: {}", e))
                             .and_then(move |item_responses, session, _| {
-                                let mut send_superblock_votes = false;
-                                for (i, item_response) in item_responses.into_iter().enumerate() {
-                                    match item_response {
-                                        Ok(item) => {
-                                            if let InventoryItem::Block(block) = &item {
-                                                if block.block_header.beacon.checkpoint
-                                                    == session
-                                                        .last_beacon
-                                                        .highest_block_checkpoint
-                                                        .checkpoint
-                                                {
-                                                    send_superblock_votes = true;
-                                                }
-                                            }
-
-                                            send_inventory_item_msg(session, item)
+                                let replies = inventory_replies(
+                                    session.magic_number,
+                                    &inventory,
+                                    item_responses,
+                                );
+                                let last_checkpoint =
+                                    session.last_beacon.highest_block_checkpoint.checkpoint;
+                                let send_superblock_votes =
+                                    replies.iter().any(|reply| match &reply.kind {
+                                        Command::Block(block) => {
+                                            block.block_header.beacon.checkpoint == last_checkpoint
                                         }
-                                        Err(e) => {
-                                            // Failed to retrieve item from inventory manager
-                                            match inventory[i] {
-                                                InventoryEntry::Block(hash) => {
-                                                    log::warn!(
-                                                        "Inventory request: {}: block {}",
-                                                        e,
-                                                        hash
-                                                    );
-                                                }
-                                                InventoryEntry::Tx(hash) => {
-                                                    log::warn!(
-                                                        "Inventory request: {}: transaction {}",
-                                                        e,
-                                                        hash
-                                                    );
-                                                }
-                                                InventoryEntry::SuperBlock(index) => {
-                                                    log::warn!(
-                                                        "Inventory request: {}: superblock {}",
-                                                        e,
-                                                        index
-                                                    );
-                                                }
-                                            }
-                                            // Stop block sending if an error occurs
-                                            break;
-                                        }
-                                    }
+                                        _ => false,
+                                    });
+                                for reply in replies {
+                                    session.send_message(reply);
                                 }
 
                                 actix::fut::ok(send_superblock_votes)
@@ -423,13 +422,7 @@ impl Handler<SendGetPeers> for Session {
     type Result = SessionUnitResult;
 
     fn handle(&mut self, _msg: SendGetPeers, _: &mut Context<Self>) {
-        log::trace!("Sending GetPeers message to peer at {:?}", self.remote_addr);
-
-        self.expected_peers_msg = self.expected_peers_msg.saturating_add(1);
-        // Create get peers message
-        let get_peers_msg = WitnetMessage::build_get_peers(self.magic_number);
-        // Write get peers message in session
-        self.send_message(get_peers_msg);
+        self.request_peers();
     }
 }
 
@@ -519,6 +512,8 @@ impl Handler<CloseSession> for Session {
 fn try_consolidate_session(session: &mut Session, ctx: &mut Context<Session>) {
     // Check if HandshakeFlags are all set to true
     if session.handshake_flags.all_true() {
+        session.cancel_handshake_timeout(ctx);
+
         // Update session to consolidate status
         update_consolidate(session, ctx);
     }
@@ -528,6 +523,8 @@ fn try_consolidate_session(session: &mut Session, ctx: &mut Context<Session>) {
 fn update_consolidate(session: &Session, ctx: &mut Context<Session>) {
     // First evaluate Feeler case
     if session.session_type == SessionType::Feeler {
+        session.report_outcome(PeerOutcome::Consolidated);
+
         // Get peer manager address
         let peers_manager_addr = PeersManager::from_registry();
 
@@ -560,6 +557,7 @@ fn update_consolidate(session: &Session, ctx: &mut Context<Session>) {
             .send(Consolidate {
                 address: session.remote_addr,
                 potential_new_peer,
+                observed_address: session.remote_observed_addr,
                 session_type: session.session_type,
             })
             .into_actor(session)
@@ -572,6 +570,13 @@ fn update_consolidate(session: &Session, ctx: &mut Context<Session>) {
                         );
                         // Set status to consolidate
                         act.status = SessionStatus::Consolidated;
+                        act.consolidated_at = Some(Instant::now());
+
+                        // Learn about the network from outbound peers right away instead of
+                        // waiting for the next peers discovery
+                        if requests_peers_on_consolidation(act.session_type) {
+                            act.request_peers();
+                        }
                     }
                     _ => {
                         log::debug!(
@@ -601,7 +606,9 @@ fn peer_discovery_get_peers(session: &mut Session, ctx: &mut Context<Session>) {
     peers_manager_addr
         // Send RequestPeers message to PeersManager actor
         // This returns a Request Future, representing an asynchronous message sending process
-        .send(RequestPeers)
+        .send(RequestPeers {
+            requester: session.remote_addr,
+        })
         // Convert a normal future into an ActorFuture
         .into_actor(session)
         // Process the response from PeersManager
@@ -640,6 +647,9 @@ fn peer_discovery_peers(
 
     if peers_requested {
         session.expected_peers_msg -= 1;
+        if let Some(sent_at) = session.get_peers_sent_at.take() {
+            session.report_activity(SessionActivity::ResponseTime(sent_at.elapsed()));
+        }
 
         // Convert array of address to vector of socket addresses
         let addresses: Vec<SocketAddr> = peers.iter().map(from_address).collect();
@@ -663,6 +673,7 @@ fn peer_discovery_peers(
             addresses: vec![src_address],
             ice: true,
         });
+        session.disconnect_reason = DisconnectReason::Misbehaved;
 
         // And stop the actor
         ctx.stop()
@@ -675,6 +686,8 @@ fn inventory_process_block(session: &mut Session, _ctx: &mut Context<Session>, b
     let chain_manager_addr = ChainManager::from_registry();
     let block_hash = block.hash();
 
+    session.report_activity(SessionActivity::Delivered(1));
+
     if session.requested_block_hashes.contains(&block_hash) {
         // Add block to requested_blocks
         session.requested_blocks.insert(block_hash, block);
@@ -720,10 +733,12 @@ fn inventory_process_block(session: &mut Session, _ctx: &mut Context<Session>, b
 
 /// Function called when Transaction message is received
 fn inventory_process_transaction(
-    _session: &mut Session,
+    session: &mut Session,
     _ctx: &mut Context<Session>,
     transaction: Transaction,
 ) {
+    session.report_activity(SessionActivity::Delivered(1));
+
     // Get ChainManager address
     let chain_manager_addr = ChainManager::from_registry();
 
@@ -931,6 +946,11 @@ fn handshake_version(
     }
 
     session.remote_sender_addr = Some(from_address(&command_version.sender_address));
+    session.remote_observed_addr = Some(from_address(&command_version.receiver_address));
+    session.compress_messages = compression_negotiated(
+        session.config.connections.compression,
+        command_version.capabilities,
+    );
 
     // Set version_rx flag, indicating reception of a version message from the peer
     flags.version_rx = true;
@@ -948,6 +968,7 @@ fn handshake_version(
             session.public_addr,
             session.remote_addr,
             session.last_beacon.clone(),
+            session.capabilities(),
         );
         responses.push(version);
     }
@@ -956,33 +977,77 @@ fn handshake_version(
 }
 
 fn send_inventory_item_msg(session: &mut Session, item: InventoryItem) {
+    let msg = inventory_item_msg(session.magic_number, item);
+    session.send_message(msg);
+}
+
+/// Build the message that carries an inventory item.
+fn inventory_item_msg(magic: u16, item: InventoryItem) -> WitnetMessage {
     match item {
         InventoryItem::Block(Block {
             block_header,
             block_sig,
             txns,
             ..
-        }) => {
-            // Build Block msg
-            let block_msg =
-                WitnetMessage::build_block(session.magic_number, block_header, block_sig, txns);
-            // Send Block msg
-            session.send_message(block_msg);
-        }
+        }) => WitnetMessage::build_block(magic, block_header, block_sig, txns),
         InventoryItem::Transaction(transaction) => {
-            // Build Transaction msg
-            let transaction_msg =
-                WitnetMessage::build_transaction(session.magic_number, transaction);
-            // Send Transaction msg
-            session.send_message(transaction_msg);
+            WitnetMessage::build_transaction(magic, transaction)
         }
-        InventoryItem::SuperBlock(superblock) => {
-            // Build SuperBlock msg
-            let superblock_msg = WitnetMessage::build_superblock(session.magic_number, superblock);
-            // Send SuperBlock msg
-            session.send_message(superblock_msg);
+        InventoryItem::SuperBlock(superblock) => WitnetMessage::build_superblock(magic, superblock),
+    }
+}
+
+/// Retrieve a requested inventory item. Transactions that are not in any block yet are looked up
+/// in the mempool.
+async fn get_inventory_item(
+    item: InventoryEntry,
+) -> Result<Result<InventoryItem, InventoryManagerError>, MailboxError> {
+    let pending_tx_hash = match item {
+        InventoryEntry::Tx(hash) => Some(hash),
+        _ => None,
+    };
+    let res = InventoryManager::from_registry()
+        .send(GetItem { item })
+        .await?;
+
+    match (res, pending_tx_hash) {
+        (Err(InventoryManagerError::ItemNotFound), Some(hash)) => Ok(ChainManager::from_registry()
+            .send(GetMemoryTransaction { hash })
+            .await?
+            .map(InventoryItem::Transaction)
+            .map_err(|()| InventoryManagerError::ItemNotFound)),
+        (res, _) => Ok(res),
+    }
+}
+
+/// Build the replies to an inventory request, in the order of the requested entries.
+///
+/// Missing transactions are skipped. A missing block or superblock stops the replies, because the
+/// peer could not consolidate the blocks that follow it anyway.
+fn inventory_replies(
+    magic: u16,
+    inventory: &[InventoryEntry],
+    item_responses: Vec<Result<InventoryItem, InventoryManagerError>>,
+) -> Vec<WitnetMessage> {
+    let mut replies = vec![];
+    for (entry, item_response) in inventory.iter().zip(item_responses) {
+        match (item_response, entry) {
+            (Ok(item), _) => replies.push(inventory_item_msg(magic, item)),
+            (Err(e), InventoryEntry::Tx(hash)) => {
+                log::warn!("Inventory request: {}: transaction {}", e, hash);
+            }
+            (Err(e), InventoryEntry::Block(hash)) => {
+                log::warn!("Inventory request: {}: block {}", e, hash);
+                break;
+            }
+            (Err(e), InventoryEntry::SuperBlock(index)) => {
+                log::warn!("Inventory request: {}: superblock {}", e, index);
+                break;
+            }
         }
     }
+
+    replies
 }
 
 // FIXME(#1366): handle superblock_beacon.
@@ -1106,7 +1171,7 @@ fn process_superblock_vote(_session: &mut Session, superblock_vote: SuperBlockVo
 #[cfg(test)]
 mod tests {
     use super::*;
-    use witnet_data_structures::chain::Hash;
+    use witnet_data_structures::{chain::Hash, transaction::MintTransaction};
 
     #[test]
     fn handshake_bootstrap_before_epoch_zero() {
@@ -1404,4 +1469,65 @@ mod tests {
             received_ts
         );
     }
+
+    fn mint_block(epoch: Epoch) -> Block {
+        let mut block = Block::default();
+        block.block_header.beacon.checkpoint = epoch;
+        block.txns.mint = MintTransaction::new(epoch, vec![]);
+
+        block
+    }
+
+    #[test]
+    fn inventory_replies_follow_requested_order() {
+        let magic = 0xABCD;
+        let block = mint_block(1);
+        let transaction = Transaction::Mint(mint_block(2).txns.mint);
+        let missing_hash = Hash::from(vec![0xFF; 32]);
+        let inventory = vec![
+            InventoryEntry::Tx(transaction.hash()),
+            InventoryEntry::Tx(missing_hash),
+            InventoryEntry::Block(block.hash()),
+        ];
+        let item_responses = vec![
+            Ok(InventoryItem::Transaction(transaction.clone())),
+            Err(InventoryManagerError::ItemNotFound),
+            Ok(InventoryItem::Block(block.clone())),
+        ];
+
+        // The missing transaction is skipped, and the items after it are still sent
+        let replies = inventory_replies(magic, &inventory, item_responses);
+        assert_eq!(replies.len(), 2);
+        assert!(replies.iter().all(|reply| reply.magic == magic));
+        match &replies[0].kind {
+            Command::Transaction(tx) => assert_eq!(tx.hash(), transaction.hash()),
+            kind => panic!("Expected a transaction, got {}", kind),
+        }
+        match &replies[1].kind {
+            Command::Block(b) => assert_eq!(b.hash(), block.hash()),
+            kind => panic!("Expected a block, got {}", kind),
+        }
+    }
+
+    #[test]
+    fn inventory_replies_stop_at_missing_block() {
+        let blocks: Vec<_> = (1..=3).map(mint_block).collect();
+        let inventory: Vec<_> = blocks
+            .iter()
+            .map(|block| InventoryEntry::Block(block.hash()))
+            .collect();
+        let item_responses = vec![
+            Ok(InventoryItem::Block(blocks[0].clone())),
+            Err(InventoryManagerError::ItemNotFound),
+            Ok(InventoryItem::Block(blocks[2].clone())),
+        ];
+
+        // Blocks after a missing one could not be consolidated by the peer
+        let replies = inventory_replies(0, &inventory, item_responses);
+        assert_eq!(replies.len(), 1);
+        assert!(matches!(
+            &replies[0].kind,
+            Command::Block(block) if block.hash() == blocks[0].hash()
+        ));
+    }
 }
@@ -1,30 +1,53 @@
-use std::{collections::HashMap, net::SocketAddr, sync::Arc};
+use std::{
+    collections::HashMap,
+    net::SocketAddr,
+    sync::Arc,
+    time::{Duration, Instant},
+};
 
-use actix::{io::FramedWrite, SystemService};
+use actix::{
+    io::FramedWrite, ActorContext, AsyncContext, Context, Recipient, SpawnHandle, SystemService,
+    WeakAddr,
+};
 
 use ansi_term::Color::Green;
 
 use witnet_config::config::Config;
 use witnet_data_structures::{
+    builders::{CAPABILITIES, CAPABILITY_COMPRESSION},
     chain::{Block, CheckpointBeacon, Epoch, Hash},
     proto::ProtobufConvert,
     types::{Command, LastBeacon, Message as WitnetMessage},
 };
-use witnet_p2p::sessions::{SessionStatus, SessionType};
+use witnet_p2p::{
+    peers::{DisconnectReason, PeerOutcome},
+    sessions::{rotation::SessionActivity, SessionStatus, SessionType},
+};
 
 use crate::actors::{
-    codec::P2PCodec,
-    messages::{LogMessage, RemoveAddressesFromTried},
+    codec::{Frame, P2PCodec, HEADER_SIZE},
+    messages::{
+        CloseSession, LogMessage, RemoveAddressesFromTried, ReportPeerOutcome,
+        ReportSessionActivity,
+    },
     peers_manager::PeersManager,
     sessions_manager::SessionsManager,
 };
-use bytes::BytesMut;
 use tokio::net::tcp::OwnedWriteHalf;
 
 mod actor;
 
 mod handlers;
 
+mod write_queue;
+
+use write_queue::{Admission, MessagePriority};
+pub use write_queue::{CountingWriter, WriteQueue};
+
+/// Time given to a session whose handshake timed out for sending what it already wrote before
+/// closing the connection anyway
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(1);
+
 /// HandshakeFlags
 #[derive(Default)]
 struct HandshakeFlags {
@@ -58,7 +81,16 @@ pub struct Session {
     session_type: SessionType,
 
     /// Framed wrapper to send messages through the TCP connection
-    framed: FramedWrite<BytesMut, OwnedWriteHalf, P2PCodec>,
+    framed: FramedWrite<Frame, CountingWriter<OwnedWriteHalf>, P2PCodec>,
+
+    /// Whether the messages sent to the peer can be compressed, because both ends support it
+    compress_messages: bool,
+
+    /// Bytes written to `framed` that have not been sent yet
+    write_queue: WriteQueue,
+
+    /// Address of this session, used for closing it when its write queue overflows
+    address: Option<WeakAddr<Session>>,
 
     /// Session status
     status: SessionStatus,
@@ -69,6 +101,9 @@ pub struct Session {
     /// Remote sender address
     remote_sender_addr: Option<SocketAddr>,
 
+    /// Address at which the remote peer sees us
+    remote_observed_addr: Option<SocketAddr>,
+
     /// Magic number
     magic_number: u16,
 
@@ -93,8 +128,81 @@ pub struct Session {
     /// Expected number of "peers" message from this peer
     expected_peers_msg: u8,
 
+    /// When the oldest unanswered "get peers" message was sent, used for measuring the response
+    /// time of the peer
+    get_peers_sent_at: Option<Instant>,
+
+    /// When the last "get peers" message was sent, used for rate limiting them
+    last_get_peers_sent_at: Option<Instant>,
+
     /// Superblock beacon target
     superblock_beacon_target: Option<CheckpointBeacon>,
+
+    /// When the session was consolidated
+    consolidated_at: Option<Instant>,
+
+    /// Reason to report for the peer when the session stops
+    disconnect_reason: DisconnectReason,
+
+    /// Timer that closes the session if the handshake is not completed in time
+    handshake_timeout: Option<SpawnHandle>,
+
+    /// Whether the handshake timed out, in which case the session is closing and ignores any
+    /// message from the peer
+    handshake_expired: bool,
+
+    /// Recipient of the outcomes of the connection, the PeersManager unless replaced
+    outcome_recipient: Option<Recipient<ReportPeerOutcome>>,
+}
+
+/// Whether a "get peers" message can be sent at `now` to a peer that was last sent one at
+/// `last_sent_at`
+fn get_peers_allowed(last_sent_at: Option<Instant>, now: Instant, min_interval: Duration) -> bool {
+    match last_sent_at {
+        Some(last_sent_at) => now.saturating_duration_since(last_sent_at) >= min_interval,
+        None => true,
+    }
+}
+
+/// Capabilities advertised in the `Version` message, depending on whether compression is enabled
+fn advertised_capabilities(compression: bool) -> u64 {
+    if compression {
+        CAPABILITIES | CAPABILITY_COMPRESSION
+    } else {
+        CAPABILITIES
+    }
+}
+
+/// Whether compressed messages can be sent to a peer that advertised `peer_capabilities`, which
+/// requires compression to be enabled on both ends
+fn compression_negotiated(compression: bool, peer_capabilities: u64) -> bool {
+    compression && peer_capabilities & CAPABILITY_COMPRESSION != 0
+}
+
+/// Outcome to report for the peer of a session that stops after having been consolidated for
+/// `uptime`, if it was ever consolidated. Sessions that completed the handshake but were not
+/// consolidated are not reported, as those are feelers or sessions refused by our own limits.
+/// Sessions that timed out before completing the handshake are reported as such.
+fn outcome_on_stop(
+    uptime: Option<Duration>,
+    handshake_completed: bool,
+    reason: DisconnectReason,
+) -> Option<PeerOutcome> {
+    match uptime {
+        Some(uptime) => Some(PeerOutcome::Disconnected {
+            reason,
+            uptime: uptime.as_secs(),
+        }),
+        None if handshake_completed => None,
+        None if reason == DisconnectReason::Timeout => Some(PeerOutcome::HandshakeTimeout),
+        None => Some(PeerOutcome::HandshakeFailed),
+    }
+}
+
+/// Whether a session asks the peer for its peers as soon as it is consolidated. Only outbound
+/// sessions do, as inbound peers are the ones that want to learn about the network from us.
+fn requests_peers_on_consolidation(session_type: SessionType) -> bool {
+    session_type == SessionType::Outbound
 }
 
 impl Drop for Session {
@@ -114,7 +222,8 @@ impl Session {
         public_addr: Option<SocketAddr>,
         remote_addr: SocketAddr,
         session_type: SessionType,
-        framed: FramedWrite<BytesMut, OwnedWriteHalf, P2PCodec>,
+        framed: FramedWrite<Frame, CountingWriter<OwnedWriteHalf>, P2PCodec>,
+        write_queue: WriteQueue,
         magic_number: u16,
         current_epoch: Epoch,
         last_beacon: LastBeacon,
@@ -126,9 +235,13 @@ impl Session {
             remote_addr,
             session_type,
             framed,
+            compress_messages: false,
+            write_queue,
+            address: None,
             status: SessionStatus::Unconsolidated,
             handshake_flags: HandshakeFlags::default(),
             remote_sender_addr: None,
+            remote_observed_addr: None,
             magic_number,
             current_epoch,
             last_beacon,
@@ -137,15 +250,58 @@ impl Session {
             blocks_timestamp: 0,
             config,
             expected_peers_msg: 0,
+            get_peers_sent_at: None,
+            last_get_peers_sent_at: None,
             superblock_beacon_target,
+            consolidated_at: None,
+            disconnect_reason: DisconnectReason::Closed,
+            handshake_timeout: None,
+            handshake_expired: false,
+            outcome_recipient: None,
         }
     }
 
+    /// Capabilities advertised to the peer in the `Version` message
+    fn capabilities(&self) -> u64 {
+        advertised_capabilities(self.config.connections.compression)
+    }
+
     /// Method to send a Witnet message to the remote peer
+    ///
+    /// Low priority messages are dropped if the peer is not keeping up with the messages sent to
+    /// it, and the session is closed if not even protocol-critical messages can be sent.
     fn send_message(&mut self, msg: WitnetMessage) {
         // Convert WitnetMessage into a vector of bytes
         match ProtobufConvert::to_pb_bytes(&msg) {
             Ok(bytes) => {
+                let priority = MessagePriority::of(&msg.kind);
+                match self.write_queue.admit(priority, bytes.len() + HEADER_SIZE) {
+                    Admission::Write => {}
+                    Admission::Drop => {
+                        log::debug!(
+                            "Dropping {} message to session {:?}: {} bytes pending to be sent",
+                            msg.kind,
+                            self.remote_addr,
+                            self.write_queue.pending(),
+                        );
+
+                        return;
+                    }
+                    Admission::Disconnect => {
+                        self.disconnect_reason = DisconnectReason::Timeout;
+                        log::warn!(
+                            "Closing session {:?} because it cannot keep up: {} bytes pending to be sent",
+                            self.remote_addr,
+                            self.write_queue.pending(),
+                        );
+                        if let Some(address) = self.address.as_ref().and_then(WeakAddr::upgrade) {
+                            address.do_send(CloseSession);
+                        }
+
+                        return;
+                    }
+                }
+
                 match msg.kind {
                     Command::Transaction(_) | Command::Block(_) | Command::SuperBlockVote(_) => {
                         let log_data = format!(
@@ -170,7 +326,10 @@ impl Session {
                     }
                 }
                 log::trace!("\t{:?}", msg);
-                self.framed.write(bytes.as_slice().into());
+                self.framed.write(Frame {
+                    bytes: bytes.as_slice().into(),
+                    compress: self.compress_messages,
+                });
             }
             Err(e) => {
                 log::error!(
@@ -183,6 +342,34 @@ impl Session {
             }
         }
     }
+    /// Ask the peer for its peers, unless it was already asked less than
+    /// `get_peers_min_interval` ago
+    fn request_peers(&mut self) {
+        let now = Instant::now();
+        if !get_peers_allowed(
+            self.last_get_peers_sent_at,
+            now,
+            self.config.connections.get_peers_min_interval,
+        ) {
+            log::trace!(
+                "Not sending GetPeers message to peer at {:?}, it was asked recently",
+                self.remote_addr
+            );
+
+            return;
+        }
+
+        log::trace!("Sending GetPeers message to peer at {:?}", self.remote_addr);
+
+        self.last_get_peers_sent_at = Some(now);
+        self.expected_peers_msg = self.expected_peers_msg.saturating_add(1);
+        self.get_peers_sent_at.get_or_insert(now);
+        // Create get peers message
+        let get_peers_msg = WitnetMessage::build_get_peers(self.magic_number);
+        // Write get peers message in session
+        self.send_message(get_peers_msg);
+    }
+
     // This method is useful to align the logs from receive_message with logs from send_message
     fn log_received_message(&self, msg: &WitnetMessage, bytes: &[u8]) {
         match msg.kind {
@@ -212,6 +399,86 @@ impl Session {
         log::trace!("\t{:?}", msg);
     }
 
+    // Report activity of outbound peers to the sessions manager, which uses it for rotating them
+    fn report_activity(&self, activity: SessionActivity) {
+        if self.session_type == SessionType::Outbound {
+            SessionsManager::from_registry().do_send(ReportSessionActivity {
+                address: self.remote_addr,
+                activity,
+            });
+        }
+    }
+
+    // Report the outcome of the connection to the peers manager, which uses it for scoring the
+    // peer. Only the addresses that we connected to are scored
+    fn report_outcome(&self, outcome: PeerOutcome) {
+        if self.session_type != SessionType::Inbound {
+            let msg = ReportPeerOutcome {
+                address: self.remote_addr,
+                outcome,
+            };
+            match &self.outcome_recipient {
+                Some(recipient) => recipient.do_send(msg),
+                None => PeersManager::from_registry().do_send(msg),
+            }
+        }
+    }
+
+    // Report the outcome of the connection when the session stops
+    fn report_outcome_on_stop(&self) {
+        let uptime = self.consolidated_at.map(|at| at.elapsed());
+        let outcome = outcome_on_stop(
+            uptime,
+            self.handshake_flags.all_true(),
+            self.disconnect_reason,
+        );
+        if let Some(outcome) = outcome {
+            self.report_outcome(outcome);
+        }
+    }
+
+    // Close the session if it is still unconsolidated after the handshake timeout
+    fn start_handshake_timeout(&mut self, ctx: &mut Context<Session>) {
+        let handle = ctx.run_later(self.config.connections.handshake_timeout, |act, ctx| {
+            act.handshake_timeout = None;
+            act.handshake_timeout_expired(ctx);
+        });
+        self.handshake_timeout = Some(handle);
+    }
+
+    // Stop the handshake timer once the handshake is completed
+    fn cancel_handshake_timeout(&mut self, ctx: &mut Context<Session>) {
+        if let Some(handle) = self.handshake_timeout.take() {
+            ctx.cancel_future(handle);
+        }
+    }
+
+    fn handshake_timeout_expired(&mut self, ctx: &mut Context<Session>) {
+        if self.status == SessionStatus::Consolidated {
+            return;
+        }
+
+        log::debug!(
+            "Handshake timeout expired, disconnecting {:?} session with peer {:?} (version received: {}, verack received: {})",
+            self.session_type,
+            self.remote_addr,
+            self.handshake_flags.version_rx,
+            self.handshake_flags.verack_rx,
+        );
+
+        if self.session_type == SessionType::Outbound {
+            // Remove this address from tried bucket and ice it
+            self.remove_and_ice_peer();
+        }
+        self.handshake_expired = true;
+        self.disconnect_reason = DisconnectReason::Timeout;
+
+        // Send what was already written before closing the connection. Once closed, the session
+        // stops (see `WriteHandler::finished`), or anyway after a while if the peer does not read
+        self.framed.close();
+        ctx.run_later(CLOSE_TIMEOUT, |_act, ctx| ctx.stop());
+    }
+
     // Remove this address from tried bucket and move to the ice bucket
     fn remove_and_ice_peer(&self) {
         let peers_manager_addr = PeersManager::from_registry();
@@ -221,3 +488,341 @@ impl Session {
         });
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use actix::{Actor, Addr, Handler, StreamHandler};
+    use bytes::BytesMut;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::{TcpListener, TcpStream},
+    };
+    use tokio_util::codec::{Decoder, Encoder, FramedRead};
+    use witnet_config::config::StorageBackend;
+    use witnet_p2p::sessions::GetConsolidatedPeersResult;
+
+    use super::*;
+    use crate::{
+        actors::messages::GetConsolidatedPeers, config_mngr, storage_mngr, utils::test_actix_system,
+    };
+
+    const MAGIC: u16 = 1;
+
+    #[test]
+    fn only_outbound_sessions_request_peers_on_consolidation() {
+        assert!(requests_peers_on_consolidation(SessionType::Outbound));
+        assert!(!requests_peers_on_consolidation(SessionType::Inbound));
+        assert!(!requests_peers_on_consolidation(SessionType::Feeler));
+    }
+
+    #[test]
+    fn compression_is_only_used_if_both_ends_support_it() {
+        let supporting_peer = advertised_capabilities(true);
+        let legacy_peer = CAPABILITIES;
+
+        assert!(compression_negotiated(true, supporting_peer));
+        assert!(!compression_negotiated(true, legacy_peer));
+        assert!(!compression_negotiated(false, supporting_peer));
+        assert_eq!(advertised_capabilities(false), CAPABILITIES);
+    }
+
+    #[test]
+    fn outcome_on_stop_depends_on_how_far_the_session_went() {
+        assert_eq!(
+            outcome_on_stop(None, false, DisconnectReason::Closed),
+            Some(PeerOutcome::HandshakeFailed)
+        );
+        assert_eq!(
+            outcome_on_stop(None, false, DisconnectReason::Timeout),
+            Some(PeerOutcome::HandshakeTimeout)
+        );
+        assert_eq!(outcome_on_stop(None, true, DisconnectReason::Closed), None);
+        assert_eq!(
+            outcome_on_stop(
+                Some(Duration::from_secs(7200)),
+                true,
+                DisconnectReason::Timeout
+            ),
+            Some(PeerOutcome::Disconnected {
+                reason: DisconnectReason::Timeout,
+                uptime: 7200,
+            })
+        );
+    }
+
+    #[test]
+    fn get_peers_messages_are_rate_limited() {
+        let min_interval = Duration::from_secs(10);
+        let consolidated_at = Instant::now();
+
+        // The first one is always sent
+        assert!(get_peers_allowed(None, consolidated_at, min_interval));
+        assert!(!get_peers_allowed(
+            Some(consolidated_at),
+            consolidated_at + Duration::from_secs(9),
+            min_interval
+        ));
+        assert!(get_peers_allowed(
+            Some(consolidated_at),
+            consolidated_at + min_interval,
+            min_interval
+        ));
+    }
+
+    /// Records the outcomes reported by a session instead of scoring the peer with them
+    struct OutcomeRecorder(Arc<Mutex<Vec<PeerOutcome>>>);
+
+    impl Actor for OutcomeRecorder {
+        type Context = Context<Self>;
+    }
+
+    impl Handler<ReportPeerOutcome> for OutcomeRecorder {
+        type Result = ();
+
+        fn handle(&mut self, msg: ReportPeerOutcome, _ctx: &mut Context<Self>) {
+            self.0.lock().unwrap().push(msg.outcome);
+        }
+    }
+
+    fn last_beacon() -> LastBeacon {
+        LastBeacon {
+            highest_block_checkpoint: CheckpointBeacon::default(),
+            highest_superblock_checkpoint: CheckpointBeacon::default(),
+        }
+    }
+
+    fn test_config() -> Config {
+        let mut config = Config::default();
+        config.storage.backend = StorageBackend::HashMap;
+        config.connections.handshake_timeout = Duration::from_millis(100);
+
+        config
+    }
+
+    /// Start a feeler session connected to the returned peer socket, which records the outcomes
+    /// of the connection in `outcomes`
+    async fn feeler_session(outcomes: Arc<Mutex<Vec<PeerOutcome>>>) -> (Addr<Session>, TcpStream) {
+        start_session(SessionType::Feeler, outcomes).await
+    }
+
+    /// Start a session of the given type connected to the returned peer socket, which records the
+    /// outcomes of the connection in `outcomes`
+    async fn start_session(
+        session_type: SessionType,
+        outcomes: Arc<Mutex<Vec<PeerOutcome>>>,
+    ) -> (Addr<Session>, TcpStream) {
+        let config = Arc::new(test_config());
+        config_mngr::start(config.clone());
+        storage_mngr::start();
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let stream = TcpStream::connect(listener.local_addr().unwrap())
+            .await
+            .unwrap();
+        let (peer, _) = listener.accept().await.unwrap();
+        let remote_addr = stream.peer_addr().unwrap();
+        let recorder = OutcomeRecorder(outcomes).start();
+
+        let session = Session::create(move |ctx| {
+            let (r, w) = stream.into_split();
+            let codec = P2PCodec::new(None);
+            Session::add_stream(FramedRead::new(r, codec.clone()), ctx);
+            let write_queue = WriteQueue::new(
+                config.connections.write_queue_high_water_mark,
+                config.connections.write_queue_hard_limit,
+            );
+            let mut session = Session::new(
+                None,
+                remote_addr,
+                session_type,
+                FramedWrite::new(CountingWriter::new(w, write_queue.clone()), codec, ctx),
+                write_queue,
+                MAGIC,
+                0,
+                last_beacon(),
+                config,
+                None,
+            );
+            session.outcome_recipient = Some(recorder.recipient());
+
+            session
+        });
+
+        (session, peer)
+    }
+
+    /// Send a message to a session from its peer
+    async fn send_from_peer(peer: &mut TcpStream, msg: WitnetMessage) {
+        let mut buf = BytesMut::new();
+        P2PCodec::new(None)
+            .encode(
+                Frame {
+                    bytes: msg.to_pb_bytes().unwrap().as_slice().into(),
+                    compress: false,
+                },
+                &mut buf,
+            )
+            .unwrap();
+        peer.write_all(&buf).await.unwrap();
+    }
+
+    /// Complete the handshake of a session from its peer
+    async fn complete_handshake(peer: &mut TcpStream) {
+        let version = WitnetMessage::build_version(
+            MAGIC,
+            None,
+            peer.peer_addr().unwrap(),
+            last_beacon(),
+            CAPABILITIES,
+        );
+        send_from_peer(peer, version).await;
+        send_from_peer(peer, WitnetMessage::build_verack(MAGIC)).await;
+    }
+
+    /// Commands of the messages that a session sent to its peer until it went quiet
+    async fn commands_received(peer: &mut TcpStream) -> Vec<Command> {
+        let mut buf = BytesMut::new();
+        while let Ok(Ok(read)) =
+            tokio::time::timeout(Duration::from_millis(300), peer.read_buf(&mut buf)).await
+        {
+            if read == 0 {
+                break;
+            }
+        }
+
+        let mut codec = P2PCodec::new(None);
+        let mut commands = vec![];
+        while let Some(bytes) = codec.decode(&mut buf).unwrap() {
+            commands.push(WitnetMessage::from_pb_bytes(&bytes).unwrap().kind);
+        }
+
+        commands
+    }
+
+    /// Sessions that the sessions manager considers consolidated
+    async fn consolidated_peers() -> GetConsolidatedPeersResult {
+        SessionsManager::from_registry()
+            .send(GetConsolidatedPeers)
+            .await
+            .unwrap()
+            .unwrap()
+    }
+
+    /// Whether the commands include a "get peers" message
+    fn asks_for_peers(commands: &[Command]) -> bool {
+        commands
+            .iter()
+            .any(|command| matches!(command, Command::GetPeers(_)))
+    }
+
+    /// Wait until the handshake timeout of the test sessions has long expired
+    async fn wait_past_handshake_timeout() {
+        tokio::time::sleep(Duration::from_millis(500)).await;
+    }
+
+    #[test]
+    fn handshake_timeout_stops_silent_sessions() {
+        test_actix_system(|| async {
+            let outcomes = Arc::new(Mutex::new(vec![]));
+            let (session, mut peer) = feeler_session(outcomes.clone()).await;
+
+            wait_past_handshake_timeout().await;
+
+            assert!(!session.connected());
+            assert_eq!(
+                *outcomes.lock().unwrap(),
+                vec![PeerOutcome::HandshakeTimeout]
+            );
+            // The version message of the session was flushed before closing the connection
+            let mut received = vec![];
+            peer.read_to_end(&mut received).await.unwrap();
+            assert!(!received.is_empty());
+        });
+    }
+
+    #[test]
+    fn handshake_timeout_stops_sessions_missing_verack() {
+        test_actix_system(|| async {
+            let outcomes = Arc::new(Mutex::new(vec![]));
+            let (session, mut peer) = feeler_session(outcomes.clone()).await;
+            let version = WitnetMessage::build_version(
+                MAGIC,
+                None,
+                peer.peer_addr().unwrap(),
+                last_beacon(),
+                CAPABILITIES,
+            );
+            send_from_peer(&mut peer, version).await;
+
+            wait_past_handshake_timeout().await;
+
+            assert!(!session.connected());
+            assert_eq!(
+                *outcomes.lock().unwrap(),
+                vec![PeerOutcome::HandshakeTimeout]
+            );
+        });
+    }
+
+    #[test]
+    fn completing_the_handshake_cancels_the_handshake_timeout() {
+        test_actix_system(|| async {
+            let outcomes = Arc::new(Mutex::new(vec![]));
+            let (_session, mut peer) = feeler_session(outcomes.clone()).await;
+            let version = WitnetMessage::build_version(
+                MAGIC,
+                None,
+                peer.peer_addr().unwrap(),
+                last_beacon(),
+                CAPABILITIES,
+            );
+            send_from_peer(&mut peer, version).await;
+            send_from_peer(&mut peer, WitnetMessage::build_verack(MAGIC)).await;
+
+            wait_past_handshake_timeout().await;
+
+            // Feelers are done once consolidated, and the timeout is not reported for them
+            assert_eq!(*outcomes.lock().unwrap(), vec![PeerOutcome::Consolidated]);
+        });
+    }
+
+    #[test]
+    fn outbound_sessions_ask_for_peers_once_consolidated() {
+        test_actix_system(|| async {
+            let outcomes = Arc::new(Mutex::new(vec![]));
+            let (session, mut peer) = start_session(SessionType::Outbound, outcomes).await;
+
+            complete_handshake(&mut peer).await;
+            let commands = commands_received(&mut peer).await;
+
+            assert!(session.connected());
+            assert_eq!(
+                consolidated_peers().await.outbound,
+                vec![peer.local_addr().unwrap()]
+            );
+            assert!(matches!(commands.first(), Some(Command::Version(_))));
+            assert!(asks_for_peers(&commands));
+        });
+    }
+
+    #[test]
+    fn inbound_sessions_do_not_ask_for_peers_once_consolidated() {
+        test_actix_system(|| async {
+            let outcomes = Arc::new(Mutex::new(vec![]));
+            let (session, mut peer) = start_session(SessionType::Inbound, outcomes).await;
+
+            complete_handshake(&mut peer).await;
+            let commands = commands_received(&mut peer).await;
+
+            assert!(session.connected());
+            assert_eq!(
+                consolidated_peers().await.inbound,
+                vec![peer.local_addr().unwrap()]
+            );
+            assert!(matches!(commands.first(), Some(Command::Verack(_))));
+            assert!(!asks_for_peers(&commands));
+        });
+    }
+}
@@ -0,0 +1,303 @@
+//! Accounting of the messages written to a session that have not been sent to the peer yet
+
+use std::{
+    cell::Cell,
+    io,
+    pin::Pin,
+    rc::Rc,
+    task::{Context, Poll},
+};
+
+use tokio::io::AsyncWrite;
+
+use witnet_data_structures::types::Command;
+
+/// How important it is for an outgoing message to reach the peer
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum MessagePriority {
+    /// Messages that can be dropped when the peer is not keeping up, because they are sent again
+    /// or the peer can ask for them later (inventory announcements)
+    Low,
+    /// Messages the protocol relies on, such as the handshake and requested blocks
+    Critical,
+}
+
+impl MessagePriority {
+    /// Priority of a message with the given command
+    pub fn of(command: &Command) -> Self {
+        match command {
+            Command::InventoryAnnouncement(_) => MessagePriority::Low,
+            _ => MessagePriority::Critical,
+        }
+    }
+}
+
+/// What to do with an outgoing message, depending on the bytes that are still pending
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Admission {
+    /// Write the message
+    Write,
+    /// Drop the message, the queue is above its high-water mark
+    Drop,
+    /// Close the session, not even critical messages fit in the queue
+    Disconnect,
+}
+
+/// Number of bytes written to a session that have not been sent to the peer yet.
+///
+/// It is shared between the session, which adds the size of the messages it writes, and the
+/// `CountingWriter` that sends them, which subtracts the bytes accepted by the socket.
+#[derive(Clone, Debug)]
+pub struct WriteQueue {
+    pending: Rc<Cell<usize>>,
+    high_water_mark: usize,
+    hard_limit: usize,
+}
+
+impl WriteQueue {
+    /// Create an empty queue. Low priority messages are dropped while more than
+    /// `high_water_mark` bytes are pending, and the session is closed when a critical message would
+    /// leave more than `hard_limit` bytes pending.
+    pub fn new(high_water_mark: usize, hard_limit: usize) -> Self {
+        Self {
+            pending: Rc::new(Cell::new(0)),
+            high_water_mark,
+            hard_limit,
+        }
+    }
+
+    /// Bytes that have not been sent yet
+    pub fn pending(&self) -> usize {
+        self.pending.get()
+    }
+
+    /// Decide what to do with a message of `len` bytes with the given priority. If it is to be
+    /// written, its bytes are counted as pending.
+    pub fn admit(&self, priority: MessagePriority, len: usize) -> Admission {
+        let pending = self.pending.get();
+        let after = pending.saturating_add(len);
+        let admission = match priority {
+            MessagePriority::Low if pending >= self.high_water_mark || after > self.hard_limit => {
+                Admission::Drop
+            }
+            MessagePriority::Critical if after > self.hard_limit => Admission::Disconnect,
+            _ => Admission::Write,
+        };
+        if admission == Admission::Write {
+            self.pending.set(after);
+        }
+
+        admission
+    }
+
+    fn sent(&self, len: usize) {
+        self.pending.set(self.pending.get().saturating_sub(len));
+    }
+}
+
+/// Writer that keeps a `WriteQueue` up to date with the bytes that are actually sent
+pub struct CountingWriter<W> {
+    inner: W,
+    queue: WriteQueue,
+}
+
+impl<W> CountingWriter<W> {
+    /// Wrap a writer, subtracting the bytes written to it from `queue`
+    pub fn new(inner: W, queue: WriteQueue) -> Self {
+        Self { inner, queue }
+    }
+}
+
+impl<W: AsyncWrite + Unpin> AsyncWrite for CountingWriter<W> {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let res = Pin::new(&mut self.inner).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = res {
+            self.queue.sent(written);
+        }
+
+        res
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.inner).poll_shutdown(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::task::noop_waker_ref;
+
+    use witnet_data_structures::{
+        chain::{InventoryEntry, SuperBlockVote},
+        types::{InventoryAnnouncement, Verack},
+    };
+
+    use super::*;
+
+    /// Sink whose socket buffer is full, so it never accepts any bytes
+    struct StalledSink;
+
+    impl AsyncWrite for StalledSink {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            _: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            Poll::Pending
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Pending
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Pending
+        }
+    }
+
+    /// Sink that accepts at most `chunk` bytes per write, or fails if the peer closed the
+    /// connection
+    struct Sink {
+        chunk: usize,
+        closed: bool,
+    }
+
+    impl AsyncWrite for Sink {
+        fn poll_write(
+            self: Pin<&mut Self>,
+            _: &mut Context<'_>,
+            buf: &[u8],
+        ) -> Poll<io::Result<usize>> {
+            if self.closed {
+                Poll::Ready(Err(io::ErrorKind::BrokenPipe.into()))
+            } else {
+                Poll::Ready(Ok(buf.len().min(self.chunk)))
+            }
+        }
+
+        fn poll_flush(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn poll_shutdown(self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<io::Result<()>> {
+            Poll::Ready(Ok(()))
+        }
+    }
+
+    fn poll_write<W: AsyncWrite + Unpin>(
+        writer: &mut CountingWriter<W>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let mut cx = Context::from_waker(noop_waker_ref());
+
+        Pin::new(writer).poll_write(&mut cx, buf)
+    }
+
+    #[test]
+    fn message_priorities() {
+        let announcement = Command::InventoryAnnouncement(InventoryAnnouncement {
+            inventory: vec![InventoryEntry::Block(Default::default())],
+        });
+        let vote = Command::SuperBlockVote(SuperBlockVote::new_unsigned(Default::default(), 0));
+
+        assert_eq!(MessagePriority::of(&announcement), MessagePriority::Low);
+        assert_eq!(
+            MessagePriority::of(&Command::Verack(Verack)),
+            MessagePriority::Critical
+        );
+        assert_eq!(MessagePriority::of(&vote), MessagePriority::Critical);
+    }
+
+    #[test]
+    fn stalled_sink_drops_low_priority_then_disconnects() {
+        let queue = WriteQueue::new(100, 250);
+        let mut writer = CountingWriter::new(StalledSink, queue.clone());
+
+        assert_eq!(queue.admit(MessagePriority::Low, 60), Admission::Write);
+        assert_eq!(queue.admit(MessagePriority::Critical, 60), Admission::Write);
+        assert!(poll_write(&mut writer, &[0; 120]).is_pending());
+        assert_eq!(queue.pending(), 120);
+
+        // Above the high-water mark only critical messages are written
+        assert_eq!(queue.admit(MessagePriority::Low, 1), Admission::Drop);
+        assert_eq!(
+            queue.admit(MessagePriority::Critical, 100),
+            Admission::Write
+        );
+        assert_eq!(queue.pending(), 220);
+
+        // Even critical messages cannot go beyond the hard limit
+        assert_eq!(queue.admit(MessagePriority::Low, 1), Admission::Drop);
+        assert_eq!(
+            queue.admit(MessagePriority::Critical, 31),
+            Admission::Disconnect
+        );
+        assert_eq!(queue.pending(), 220);
+    }
+
+    #[test]
+    fn low_priority_messages_do_not_exceed_hard_limit() {
+        let queue = WriteQueue::new(100, 150);
+
+        assert_eq!(queue.admit(MessagePriority::Low, 90), Admission::Write);
+        assert_eq!(queue.admit(MessagePriority::Low, 70), Admission::Drop);
+        assert_eq!(queue.admit(MessagePriority::Critical, 60), Admission::Write);
+        assert_eq!(queue.pending(), 150);
+    }
+
+    #[test]
+    fn sent_bytes_drain_the_queue() {
+        let queue = WriteQueue::new(100, 250);
+        let mut writer = CountingWriter::new(
+            Sink {
+                chunk: 80,
+                closed: false,
+            },
+            queue.clone(),
+        );
+        assert_eq!(
+            queue.admit(MessagePriority::Critical, 120),
+            Admission::Write
+        );
+        assert_eq!(queue.admit(MessagePriority::Low, 1), Admission::Drop);
+
+        assert!(matches!(
+            poll_write(&mut writer, &[0; 120]),
+            Poll::Ready(Ok(80))
+        ));
+        assert_eq!(queue.pending(), 40);
+        assert_eq!(queue.admit(MessagePriority::Low, 1), Admission::Write);
+        assert!(matches!(
+            poll_write(&mut writer, &[0; 41]),
+            Poll::Ready(Ok(41))
+        ));
+        assert_eq!(queue.pending(), 0);
+    }
+
+    #[test]
+    fn write_errors_are_propagated() {
+        let queue = WriteQueue::new(100, 250);
+        let mut writer = CountingWriter::new(
+            Sink {
+                chunk: 80,
+                closed: true,
+            },
+            queue.clone(),
+        );
+        assert_eq!(queue.admit(MessagePriority::Critical, 10), Admission::Write);
+
+        match poll_write(&mut writer, &[0; 10]) {
+            Poll::Ready(Err(e)) => assert_eq!(e.kind(), io::ErrorKind::BrokenPipe),
+            other => panic!("Expected a write error, got {:?}", other),
+        }
+        assert_eq!(queue.pending(), 10);
+    }
+}
@@ -28,6 +28,10 @@ impl Actor for SessionsManager {
                 // Set server address, connections limits, handshake timeout and optional features
                 act.sessions
                     .set_public_address(config.connections.public_addr);
+                act.sessions.set_external_address_discovery(
+                    usize::from(config.connections.external_address_quorum),
+                    config.connections.server_addr.port(),
+                );
                 act.sessions.set_limits(
                     config.connections.inbound_limit,
                     config.connections.outbound_limit,
@@ -62,6 +66,15 @@ impl Actor for SessionsManager {
                 // The peers discovery process begins upon SessionsManager's start
                 act.discovery_peers(ctx, discovery_peers_period);
 
+                // Outbound rotation is disabled by setting its period to 0
+                let outbound_rotation_period = config.connections.outbound_rotation_period;
+                if !outbound_rotation_period.is_zero() {
+                    act.rotate_outbound_peers(ctx, outbound_rotation_period);
+                }
+
+                // Errors caused by peers that were not logged in full are summarized periodically
+                act.summarize_session_errors(ctx);
+
                 fut::ok(())
             })
             .map_err(|err, _, _| log::error!("Sessions manager startup error: {}", err))
@@ -18,14 +18,16 @@ use crate::actors::{
     messages::{
         AddConsolidatedPeer, AddPeers, Anycast, Broadcast, Consolidate, Create, DropAllPeers,
         DropOutboundPeers, EpochNotification, GetConsolidatedPeers, LogMessage, NumSessions,
-        NumSessionsResult, PeerBeacon, Register, RemoveAddressesFromTried, SessionsUnitResult,
-        SetLastBeacon, SetPeersLimits, SetSuperBlockTargetBeacon, TryMineBlock, Unregister,
+        NumSessionsResult, PeerBeacon, Register, RemoveAddressesFromTried, ReportPeerOutcome,
+        ReportSessionActivity, ReportSessionError, SessionsUnitResult, SetLastBeacon,
+        SetPeersLimits, SetPublicAddress, SetSuperBlockTargetBeacon, TryMineBlock, Unregister,
     },
     peers_manager::PeersManager,
-    session::Session,
+    session::{CountingWriter, Session, WriteQueue},
 };
 use witnet_p2p::{
     error::SessionsError,
+    peers::PeerOutcome,
     sessions::{ip_range_string, SessionType},
 };
 use witnet_util::timestamp::{duration_until_timestamp, get_timestamp};
@@ -35,8 +37,8 @@ impl Handler<Create> for SessionsManager {
     type Result = ();
 
     fn handle(&mut self, msg: Create, _ctx: &mut Context<Self>) {
-        // Get server address
-        let public_address = self.sessions.public_address;
+        // Get server address, either configured or discovered from the peers
+        let public_address = self.sessions.advertised_address();
 
         // Get magic number
         let magic_number = self.sessions.magic_number;
@@ -91,15 +93,29 @@ impl Handler<Create> for SessionsManager {
             // Split TCP stream into read and write parts
             let (r, w) = msg.stream.into_split();
 
-            // Add stream in session actor from the read part of the tcp stream
-            Session::add_stream(FramedRead::new(r, P2PCodec), ctx);
+            // Messages are only compressed if compression is enabled
+            let codec = P2PCodec::new(
+                config
+                    .connections
+                    .compression
+                    .then_some(config.connections.compression_threshold),
+            );
 
-            // Create the session actor and store in its state the write part of the tcp stream
+            // Add stream in session actor from the read part of the tcp stream
+            Session::add_stream(FramedRead::new(r, codec.clone()), ctx);
+
+            // Create the session actor and store in its state the write part of the tcp stream,
+            // keeping track of the bytes pending to be sent through it
+            let write_queue = WriteQueue::new(
+                config.connections.write_queue_high_water_mark,
+                config.connections.write_queue_hard_limit,
+            );
             Session::new(
                 public_addr,
                 remote_addr,
                 msg.session_type,
-                FramedWrite::new(w, P2PCodec, ctx),
+                FramedWrite::new(CountingWriter::new(w, write_queue.clone()), codec, ctx),
+                write_queue,
                 magic_number,
                 current_epoch,
                 last_beacon,
@@ -236,9 +252,27 @@ impl Handler<Consolidate> for SessionsManager {
                     msg.session_type,
                     msg.address
                 );
+                if let Some(observed_address) = msg.observed_address {
+                    if let Some(address) = self
+                        .sessions
+                        .observe_external_address(msg.address, observed_address)
+                    {
+                        log::info!(
+                            "Discovered our external address from the peers: {}",
+                            address
+                        );
+                        peers_manager_addr.do_send(SetPublicAddress { address });
+                    }
+                }
                 if msg.session_type == SessionType::Outbound {
                     // Add outbound peer to the list of peers that should send us a beacon
                     self.beacons.also_wait_for(msg.address);
+
+                    // Score the peer for completing the handshake
+                    peers_manager_addr.do_send(ReportPeerOutcome {
+                        address: msg.address,
+                        outcome: PeerOutcome::Consolidated,
+                    });
                 }
             }
             Err(error @ SessionsError::AddressAlreadyRegistered)
@@ -468,6 +502,37 @@ impl Handler<LogMessage> for SessionsManager {
     }
 }
 
+impl Handler<ReportSessionError> for SessionsManager {
+    type Result = u64;
+
+    fn handle(&mut self, msg: ReportSessionError, _ctx: &mut Context<Self>) -> Self::Result {
+        if let Some(log_data) = self.session_error_log(&msg, get_timestamp()) {
+            log::error!("{}", log_data);
+        }
+        let errors = self.session_errors.count(&msg.address.ip(), msg.category);
+
+        // Only the addresses that we connected to are scored
+        if msg.session_type != SessionType::Inbound {
+            PeersManager::from_registry().do_send(ReportPeerOutcome {
+                address: msg.address,
+                outcome: PeerOutcome::DecodeError { errors },
+            });
+        }
+
+        errors
+    }
+}
+
+impl Handler<ReportSessionActivity> for SessionsManager {
+    type Result = ();
+
+    fn handle(&mut self, msg: ReportSessionActivity, _ctx: &mut Context<Self>) -> Self::Result {
+        self.sessions
+            .outbound_rotation
+            .record(msg.address, msg.activity);
+    }
+}
+
 impl Handler<SetLastBeacon> for SessionsManager {
     type Result = ();
 
@@ -1,4 +1,9 @@
-use std::{collections::HashSet, net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::HashSet,
+    net::{IpAddr, SocketAddr},
+    sync::Arc,
+    time::Duration,
+};
 
 use actix::{
     ActorFutureExt, Addr, AsyncContext, Context, ContextFutureSpawner, MailboxError, SystemService,
@@ -7,7 +12,10 @@ use actix::{
 
 use ansi_term::Color::Cyan;
 
-use witnet_p2p::sessions::{SessionType, Sessions};
+use witnet_p2p::sessions::{
+    rotation::{RotationAction, RotationParams},
+    SessionType, Sessions,
+};
 
 use self::beacons::Beacons;
 use crate::{
@@ -17,7 +25,7 @@ use crate::{
         epoch_manager::EpochManager,
         messages::{
             Anycast, CloseSession, GetEpochConstants, GetRandomPeers, OutboundTcpConnect,
-            PeersBeacons, PeersSocketAddrsResult, SendGetPeers, Subscribe,
+            PeersBeacons, PeersSocketAddrsResult, ReportSessionError, SendGetPeers, Subscribe,
         },
         peers_manager::PeersManager,
         session::Session,
@@ -30,6 +38,7 @@ use witnet_data_structures::{
     chain::{CheckpointBeacon, Epoch, EpochConstants},
     types::LastBeacon,
 };
+use witnet_util::{log_throttle::LogThrottle, timestamp::get_timestamp};
 
 mod actor;
 mod beacons;
@@ -51,6 +60,9 @@ pub struct SessionsManager {
     last_beacon: Option<LastBeacon>,
     /// Logging message hashset
     logging_messages: HashSet<String>,
+    /// Errors caused by the peers of the sessions, by peer IP so that reconnecting does not reset
+    /// them
+    session_errors: LogThrottle<IpAddr>,
     /// Reference to config
     config: Option<Arc<Config>>,
     /// SuperBlock target.
@@ -115,7 +127,9 @@ impl SessionsManager {
                     // This returns a FutureResult containing the socket address if present
                     .then(|res, act, _ctx| {
                         // Process the response from peers manager
-                        let addresses = act.process_get_peer_response(res);
+                        // Take at most as many peers as missing outbounds
+                        let limit = act.sessions.num_missing_outbound();
+                        let addresses = act.process_get_peer_response(res, limit);
                         log::debug!(
                             "Trying to create a new outbound connection to {:?}",
                             addresses
@@ -153,10 +167,80 @@ impl SessionsManager {
         });
     }
 
+    /// Method to periodically replace the worst scoring outbound peer with a new one
+    fn rotate_outbound_peers(&self, ctx: &mut Context<Self>, outbound_rotation_period: Duration) {
+        // Schedule the rotation with a given period
+        ctx.run_later(outbound_rotation_period, move |act, ctx| {
+            let connections = &act
+                .config
+                .as_ref()
+                .expect("Config should be set")
+                .connections;
+            let params = RotationParams {
+                min_lifetime: i64::try_from(connections.outbound_rotation_min_lifetime.as_secs())
+                    .unwrap_or(i64::MAX),
+                min_outbound: usize::from(connections.outbound_rotation_min_outbound),
+            };
+
+            for action in act.sessions.rotate_outbound(get_timestamp(), &params) {
+                match action {
+                    RotationAction::Disconnect(address) => {
+                        let pending_write_bytes = act
+                            .sessions
+                            .outbound_rotation
+                            .stats(&address)
+                            .map(|stats| stats.pending_write_bytes)
+                            .unwrap_or_default();
+                        log::debug!(
+                            "Rotating outbound peer {} ({} bytes pending to be sent)",
+                            address,
+                            pending_write_bytes
+                        );
+                        act.drop_outbound_peers(&[address]);
+                    }
+                    RotationAction::Dial => act.dial_replacement_peer(ctx),
+                }
+            }
+
+            // Reschedule the rotation task
+            act.rotate_outbound_peers(ctx, outbound_rotation_period);
+        });
+    }
+
+    /// Ask ConnectionsManager to open a new outbound session with a peer we are not connected to
+    ///
+    /// Unlike bootstrapping, this does not wait for an outbound slot to be free, because the
+    /// session being replaced may not have been unregistered yet.
+    fn dial_replacement_peer(&self, ctx: &mut Context<Self>) {
+        PeersManager::from_registry()
+            .send(GetRandomPeers {
+                n: usize::from(self.sessions.outbound_consolidated.limit.unwrap_or(1)),
+            })
+            .into_actor(self)
+            .then(|res, act, _ctx| {
+                let addresses = act.process_get_peer_response(res, 1);
+                log::debug!(
+                    "Trying to create a replacement outbound connection to {:?}",
+                    addresses
+                );
+
+                for address in addresses {
+                    ConnectionsManager::from_registry().do_send(OutboundTcpConnect {
+                        address,
+                        session_type: SessionType::Outbound,
+                    });
+                }
+
+                actix::fut::ready(())
+            })
+            .wait(ctx);
+    }
+
     /// Method to process peers manager RequestPeer response
     fn process_get_peer_response(
         &mut self,
         response: Result<PeersSocketAddrsResult, MailboxError>,
+        limit: usize,
     ) -> Vec<SocketAddr> {
         response
             // Unwrap the Result<PeersSocketAddrResult, MailboxError>
@@ -175,8 +259,8 @@ impl SessionsManager {
             // Filter the result checking if outbound address is eligible as new peer
             .into_iter()
             .filter(|address| self.sessions.is_outbound_address_eligible(*address))
-            // Take at most as many peers as missing outbounds
-            .take(self.sessions.num_missing_outbound())
+            // Take at most as many peers as requested
+            .take(limit)
             .collect()
     }
 
@@ -313,9 +397,76 @@ impl SessionsManager {
             a.reference.do_send(CloseSession);
         }
     }
+
+    /// Count an error caused by the peer of a session, returning what to log unless the errors of
+    /// the same category from the same peer have already been logged in full enough times
+    fn session_error_log(
+        &mut self,
+        msg: &ReportSessionError,
+        current_timestamp: i64,
+    ) -> Option<String> {
+        self.session_errors
+            .record_pure(msg.address.ip(), msg.category, current_timestamp)
+            .then(|| {
+                format!(
+                    "Error in session {} ({}): {}",
+                    msg.address, msg.category, msg.error
+                )
+            })
+    }
+
+    /// Method to periodically log how many errors caused by each peer were not logged in full
+    fn summarize_session_errors(&self, ctx: &mut Context<Self>) {
+        ctx.run_interval(self.session_errors.period(), |act, _ctx| {
+            for summary in act.session_errors.summaries() {
+                log::warn!("{}", summary);
+            }
+        });
+    }
 }
 
 /// Required traits for being able to retrieve SessionsManager address from registry
 impl actix::Supervised for SessionsManager {}
 
 impl SystemService for SessionsManager {}
+
+#[cfg(test)]
+mod tests {
+    use witnet_util::log_throttle::DEFAULT_FULL_DETAIL;
+
+    use super::*;
+
+    #[test]
+    fn repeated_session_errors_are_summarized() {
+        let mut manager = SessionsManager::default();
+        let ip: IpAddr = "10.0.0.1".parse().unwrap();
+        let mut logged = vec![];
+        for port in 0..1000 {
+            // The peer reconnects from another port after every failure
+            let msg = ReportSessionError {
+                address: SocketAddr::new(ip, 20000 + port),
+                session_type: SessionType::Outbound,
+                category: "decode",
+                error: failure::format_err!("invalid protobuf"),
+            };
+            logged.extend(manager.session_error_log(&msg, 0));
+        }
+
+        assert_eq!(logged.len(), DEFAULT_FULL_DETAIL as usize);
+        assert_eq!(
+            logged[0],
+            "Error in session 10.0.0.1:20000 (decode): invalid protobuf"
+        );
+        assert_eq!(manager.session_errors.count(&ip, "decode"), 1000);
+        let summaries: Vec<_> = manager
+            .session_errors
+            .summaries_pure(60)
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            summaries,
+            vec!["suppressed 995 similar decode errors from 10.0.0.1 in the last 60s"]
+        );
+    }
+}
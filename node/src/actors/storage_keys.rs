@@ -15,3 +15,9 @@ pub fn chain_state_key(magic: u16) -> String {
 pub fn peers_key(magic: u16) -> String {
     format!("peers-{}-key", magic)
 }
+
+/// Function to create a peer scores key for the storage
+#[inline]
+pub fn peer_scores_key(magic: u16) -> String {
+    format!("peer-scores-{}-key", magic)
+}
@@ -1,6 +1,54 @@
 use bytes::BytesMut;
+use miniz_oxide::deflate::compress_to_vec;
 use tokio_util::codec::{Decoder, Encoder};
-use witnet_node::actors::codec::P2PCodec;
+use witnet_data_structures::{
+    builders::MAX_MESSAGE_SIZE,
+    chain::{BlockHeader, BlockTransactions, KeyedSignature, PublicKeyHash, ValueTransferOutput},
+    proto::ProtobufConvert,
+    transaction::{VTTransaction, VTTransactionBody},
+    types::Message,
+};
+use witnet_node::actors::codec::{Frame, P2PCodec, HEADER_SIZE};
+
+const COMPRESSION_THRESHOLD: usize = 4 * 1024;
+
+/// A serialized block message of about 1 MB.
+fn large_block_message() -> BytesMut {
+    let vtt = |i: u8| {
+        let outputs = (0..100)
+            .map(|value| ValueTransferOutput {
+                pkh: PublicKeyHash::from_bytes(&[i; 20]).unwrap(),
+                value,
+                time_lock: 0,
+            })
+            .collect();
+
+        VTTransaction::new(VTTransactionBody::new(vec![], outputs), vec![])
+    };
+    let txns = BlockTransactions {
+        value_transfer_txns: (0..=255).map(vtt).collect(),
+        ..Default::default()
+    };
+    let msg = Message::build_block(
+        0xABCD,
+        BlockHeader::default(),
+        KeyedSignature::default(),
+        txns,
+    );
+
+    BytesMut::from(msg.to_pb_bytes().unwrap().as_slice())
+}
+
+fn encode(codec: &mut P2PCodec, bytes: &BytesMut, compress: bool) -> BytesMut {
+    let mut dst = BytesMut::new();
+    let frame = Frame {
+        bytes: bytes.clone(),
+        compress,
+    };
+    codec.encode(frame, &mut dst).unwrap();
+
+    dst
+}
 
 #[test]
 fn node_actors_codec_p2p_decoder() {
@@ -19,7 +67,7 @@ fn node_actors_codec_p2p_decoder() {
 
     assert_eq!(
         msg,
-        P2PCodec::decode(&mut P2PCodec {}, &mut buf)
+        P2PCodec::decode(&mut P2PCodec::new(None), &mut buf)
             .unwrap()
             .unwrap()
     );
@@ -42,6 +90,91 @@ fn node_actors_codec_p2p_encoder() {
     );
 
     let mut dst = BytesMut::with_capacity(1024);
-    P2PCodec::encode(&mut P2PCodec {}, decoded, &mut dst).unwrap();
+    let frame = Frame {
+        bytes: decoded,
+        compress: false,
+    };
+    P2PCodec::encode(&mut P2PCodec::new(None), frame, &mut dst).unwrap();
     assert_eq!(dst, encoded);
 }
+
+#[test]
+fn node_actors_codec_p2p_large_block_round_trip() {
+    let msg = large_block_message();
+    let mut codec = P2PCodec::new(Some(COMPRESSION_THRESHOLD));
+
+    // Uncompressed
+    let mut uncompressed = encode(&mut codec, &msg, false);
+    assert_eq!(uncompressed.len(), HEADER_SIZE + msg.len());
+    assert_eq!(codec.decode(&mut uncompressed).unwrap(), Some(msg.clone()));
+    assert!(uncompressed.is_empty());
+
+    // Compressed, and only complete once every byte has arrived
+    let compressed = encode(&mut codec, &msg, true);
+    assert_eq!(compressed[0], 0x81);
+    assert!(compressed.len() < msg.len() / 10);
+    let mut partial = BytesMut::from(&compressed[..compressed.len() - 1]);
+    assert_eq!(codec.decode(&mut partial).unwrap(), None);
+    partial.extend_from_slice(&compressed[compressed.len() - 1..]);
+    let decoded = codec.decode(&mut partial).unwrap().unwrap();
+    assert_eq!(decoded, msg);
+    assert_eq!(
+        Message::from_pb_bytes(&decoded).unwrap(),
+        Message::from_pb_bytes(&msg).unwrap()
+    );
+}
+
+#[test]
+fn node_actors_codec_p2p_compression_threshold() {
+    let mut codec = P2PCodec::new(Some(COMPRESSION_THRESHOLD));
+
+    // Messages below the threshold are sent as is, even if they would compress well
+    let small = BytesMut::from(&[0; COMPRESSION_THRESHOLD - 1][..]);
+    let encoded = encode(&mut codec, &small, true);
+    assert_eq!(&encoded[..HEADER_SIZE], &[0, 0, 0x0f, 0xff]);
+    assert_eq!(encoded.len(), HEADER_SIZE + small.len());
+
+    let at_threshold = BytesMut::from(&[0; COMPRESSION_THRESHOLD][..]);
+    let encoded = encode(&mut codec, &at_threshold, true);
+    assert_eq!(encoded[0], 0x81);
+    assert!(encoded.len() < at_threshold.len());
+
+    // Nothing is compressed if compression is disabled
+    let encoded = encode(&mut P2PCodec::new(None), &at_threshold, true);
+    assert_eq!(encoded.len(), HEADER_SIZE + at_threshold.len());
+}
+
+#[test]
+fn node_actors_codec_p2p_over_limit_decompression() {
+    // A message that inflates to more than the maximum size of a message
+    let bomb = compress_to_vec(&vec![0; MAX_MESSAGE_SIZE + 1], 10);
+    let size = u32::try_from(bomb.len()).unwrap().to_be_bytes();
+    let mut buf = BytesMut::from(&[0x81, size[1], size[2], size[3]][..]);
+    buf.extend_from_slice(&bomb);
+
+    let err = P2PCodec::new(Some(COMPRESSION_THRESHOLD))
+        .decode(&mut buf)
+        .unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
+
+#[test]
+fn node_actors_codec_p2p_interop_with_peer_without_compression() {
+    let msg = large_block_message();
+    let mut codec = P2PCodec::new(Some(COMPRESSION_THRESHOLD));
+    // A peer that does not support compression, or has it disabled
+    let mut legacy_peer = P2PCodec::new(None);
+
+    // Messages to peers that did not advertise compression are understood by them
+    let mut encoded = encode(&mut codec, &msg, false);
+    assert_eq!(legacy_peer.decode(&mut encoded).unwrap(), Some(msg.clone()));
+
+    // Their messages are understood as well
+    let mut encoded = encode(&mut legacy_peer, &msg, true);
+    assert_eq!(codec.decode(&mut encoded).unwrap(), Some(msg.clone()));
+
+    // And compressed messages sent to them anyway are refused
+    let mut encoded = encode(&mut codec, &msg, true);
+    let err = legacy_peer.decode(&mut encoded).unwrap_err();
+    assert_eq!(err.kind(), std::io::ErrorKind::InvalidData);
+}
@@ -11,6 +11,7 @@ fn from_config_test_success_helper(
         allow_unproxied,
         paranoid_percentage: 51,
         proxies,
+        secrets_file: None,
     }
     .into_config();
     let config = validate_witnessing_config::<String, witnet_rad::Uri>(&config).unwrap();
@@ -35,6 +36,7 @@ fn from_config_test_error_helper(
             allow_unproxied,
             paranoid_percentage: 51,
             proxies,
+            secrets_file: None,
         }
         .into_config();
         let config = validate_witnessing_config::<String, witnet_rad::Uri>(&config).unwrap();
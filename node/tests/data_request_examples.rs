@@ -2,8 +2,9 @@ use std::{collections::HashMap, convert::TryFrom, fs};
 
 use serde::{Deserialize, Serialize};
 
-use witnet_config::defaults::PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
-use witnet_data_structures::chain::{tapi::all_wips_active, DataRequestOutput};
+use witnet_data_structures::chain::{
+    tapi::all_wips_active, ConsensusConstants, DataRequestOutput, Environment,
+};
 use witnet_node::actors::messages::BuildDrt;
 use witnet_rad::{
     script::RadonScriptExecutionSettings,
@@ -48,10 +49,9 @@ fn run_dr_locally_with_data(
     // Validate RADON: if the dr cannot be included in a witnet block, this should fail.
     validate_rad_request(&dr.data_request, &all_wips_active())?;
     // Validate other parameters such as collateral and reward
-    // TODO: read this values from ConsensusConstants
-    let collateral_minimum = 1_000_000_000;
-    let required_reward_collateral_ratio =
-        PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
+    let consensus_constants = ConsensusConstants::for_environment(Environment::Mainnet);
+    let collateral_minimum = consensus_constants.collateral_minimum;
+    let required_reward_collateral_ratio = ConsensusConstants::minimum_reward_collateral_ratio();
     validate_data_request_output(
         dr,
         collateral_minimum,
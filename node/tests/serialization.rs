@@ -2,6 +2,7 @@ use bincode::{deserialize, serialize};
 use serde::{de::DeserializeOwned, Serialize};
 use std::fmt::Debug;
 use witnet_data_structures::chain::*;
+use witnet_p2p::peers::{PeerOutcome, Peers};
 
 fn t<T>(al: T)
 where
@@ -184,3 +185,34 @@ fn deserialize_rad_retrieve_old_version_rng() {
 
     t(retrieve_rng);
 }
+
+#[test]
+fn deserialize_peers_old_version() {
+    // Written before peers had scores
+    let peers: Peers = deserialize(&[
+        1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 127, 0, 0, 1, 89, 83, 42, 0,
+        0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0,
+    ])
+    .unwrap();
+
+    assert!(peers.bootstrapped);
+    assert_eq!(peers.server_address, "127.0.0.1:21337".parse().unwrap());
+    assert_eq!(peers.sk, 42);
+    assert!(peers.scores.is_empty());
+}
+
+#[test]
+fn peers_are_serialized_without_their_scores() {
+    let mut peers = Peers {
+        server_address: "127.0.0.1:21337".parse().unwrap(),
+        sk: 42,
+        ..Peers::default()
+    };
+    let bytes_without_scores = serialize(&peers).unwrap();
+    peers.record_outcome(
+        "127.0.0.1:21338".parse().unwrap(),
+        PeerOutcome::Consolidated,
+    );
+
+    assert_eq!(serialize(&peers).unwrap(), bytes_without_scores);
+}
@@ -11,7 +11,10 @@ use std::{
     time::Duration,
 };
 
-use rand::seq::IteratorRandom;
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    seq::{IteratorRandom, SliceRandom},
+};
 use witnet_config::config::Config;
 use witnet_crypto::hash::calculate_sha256;
 use witnet_util::timestamp::get_timestamp;
@@ -25,6 +28,106 @@ pub struct PeerInfo {
     pub timestamp: i64,
 }
 
+/// Repeated decode errors from the same IP beyond which the penalty of a peer stops growing
+pub const MAX_DECODE_ERRORS_PENALIZED: u64 = 5;
+
+/// Outcome of a connection with a peer, which raises or lowers the score of its address
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum PeerOutcome {
+    /// The handshake succeeded and the session was consolidated
+    Consolidated,
+    /// The handshake failed
+    HandshakeFailed,
+    /// The peer did not complete the handshake in time, holding a connection slot meanwhile
+    HandshakeTimeout,
+    /// The peer sent a message that could not be decoded
+    DecodeError {
+        /// Messages that could not be decoded from the same IP lately, including this one
+        errors: u64,
+    },
+    /// A consolidated session was closed
+    Disconnected {
+        /// Why the session was closed
+        reason: DisconnectReason,
+        /// Seconds that the session was consolidated for
+        uptime: u64,
+    },
+}
+
+/// Reason why a consolidated session was closed
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DisconnectReason {
+    /// The session was closed by either end without any fault of the peer
+    Closed,
+    /// The peer did not answer in time
+    Timeout,
+    /// The peer broke the protocol
+    Misbehaved,
+}
+
+impl PeerOutcome {
+    /// Amount by which this outcome changes the score of a peer. Sessions are rewarded for every
+    /// hour of uptime, up to a day, and peers that keep sending garbage are penalized harder for
+    /// every error, up to `MAX_DECODE_ERRORS_PENALIZED` errors.
+    pub fn score_delta(&self) -> f64 {
+        match self {
+            PeerOutcome::Consolidated => 1.0,
+            PeerOutcome::HandshakeFailed => -1.0,
+            PeerOutcome::HandshakeTimeout => -1.5,
+            PeerOutcome::DecodeError { errors } => {
+                -2.0 * (*errors).clamp(1, MAX_DECODE_ERRORS_PENALIZED) as f64
+            }
+            PeerOutcome::Disconnected { reason, uptime } => {
+                let uptime_hours = cmp::min(*uptime, 24 * 3600) as f64 / 3600.0;
+                let penalty = match reason {
+                    DisconnectReason::Closed => 0.0,
+                    DisconnectReason::Timeout => 1.0,
+                    DisconnectReason::Misbehaved => 2.0,
+                };
+
+                uptime_hours - penalty
+            }
+        }
+    }
+}
+
+/// Score of a peer address, built from the outcomes of the connections with it
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize)]
+pub struct PeerScore {
+    /// Score at the time of the last update
+    pub score: f64,
+    /// Last time that the score was updated
+    pub timestamp: i64,
+}
+
+impl PeerScore {
+    /// Score at `current_timestamp`, after halving it for every `half_life` seconds since the
+    /// last update
+    pub fn decayed(&self, current_timestamp: i64, half_life: i64) -> f64 {
+        let elapsed = current_timestamp.saturating_sub(self.timestamp).max(0);
+        if half_life <= 0 {
+            return self.score;
+        }
+
+        self.score * 0.5f64.powf(elapsed as f64 / half_life as f64)
+    }
+}
+
+/// Address of the tried bucket as considered for sharing it with the peers that ask for ours
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PeerRecord {
+    /// The socket address of the peer
+    pub address: SocketAddr,
+    /// Last time that a session with the address was consolidated or closed
+    pub last_seen: i64,
+    /// Peer that sent us the address, if it was not added manually
+    pub source: Option<SocketAddr>,
+    /// Whether the address is iced
+    pub iced: bool,
+    /// Whether the address is ours
+    pub own: bool,
+}
+
 /// Peers TBD
 #[derive(Deserialize, Serialize)]
 pub struct Peers {
@@ -39,12 +142,34 @@ pub struct Peers {
     pub ice_period: Duration,
     /// Bucket for new addresses
     pub new_bucket: HashMap<u16, PeerInfo>,
+    /// Peer that sent us each of the addresses in the new bucket, so that the addresses fed by a
+    /// misbehaving peer can be told apart. Addresses that were added manually have no source.
+    #[serde(skip)]
+    pub new_bucket_sources: HashMap<SocketAddr, SocketAddr>,
     /// Server SocketAddress
     pub server_address: SocketAddr,
+    /// Address at which other peers can reach us, either configured or learnt from the peers
+    #[serde(skip)]
+    pub public_address: Option<SocketAddr>,
     /// Nonce value
     pub sk: u64,
     /// Bucket for tried addresses
     pub tried_bucket: HashMap<u16, PeerInfo>,
+    /// Scores of the addresses that we have connected to
+    ///
+    /// They are persisted apart from the rest of the peers, so that snapshots of the peers written
+    /// by older versions can still be read.
+    #[serde(skip)]
+    pub scores: HashMap<SocketAddr, PeerScore>,
+    /// Period after which the score of a peer is halved
+    #[serde(skip)]
+    #[serde(default = "score_half_life_default")]
+    pub score_half_life: Duration,
+    /// Percentage of the peers selected by `get_random_peers` that are taken from the untried or
+    /// low scoring ones regardless of their score, so that new peers keep being explored
+    #[serde(skip)]
+    #[serde(default = "exploration_percentage_default")]
+    pub exploration_percentage: u8,
 }
 
 impl Default for Peers {
@@ -54,9 +179,14 @@ impl Default for Peers {
             ice_bucket: Default::default(),
             ice_period: safe_ice_period_default(),
             new_bucket: Default::default(),
+            new_bucket_sources: Default::default(),
             server_address: SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 0),
+            public_address: None,
             sk: thread_rng().gen(),
             tried_bucket: Default::default(),
+            scores: Default::default(),
+            score_half_life: score_half_life_default(),
+            exploration_percentage: exploration_percentage_default(),
         }
     }
 }
@@ -67,6 +197,12 @@ impl Peers {
         Peers {
             ice_period: config.connections.bucketing_ice_period,
             server_address: config.connections.server_addr,
+            public_address: config
+                .connections
+                .public_addr
+                .filter(|address| !address.ip().is_unspecified()),
+            score_half_life: config.connections.peer_score_half_life,
+            exploration_percentage: config.connections.peer_exploration_percentage,
             ..Default::default()
         }
     }
@@ -178,9 +314,10 @@ impl Peers {
         self.tried_bucket.get(&index).map(|p| p.address)
     }
 
-    /// Returns true if the address is the server address
+    /// Returns true if the address is the server address, or the address at which other peers
+    /// reach us
     pub fn is_server_address(&self, addr: &SocketAddr) -> bool {
-        *addr == self.server_address
+        *addr == self.server_address || Some(*addr) == self.public_address
     }
 
     /// Add multiple peer addresses and save timestamp in the new addresses bucket
@@ -212,10 +349,11 @@ impl Peers {
                         // If the source address that sent us this peer addresses is None, use the same address
                         // that we want to add. This will make all the peer addresses that were added using manual methods
                         // go to the same bucket that if it was announced by that address.
-                        let src_address = src_address.unwrap_or(address);
-                        let index = self.new_bucket_index(&address, &src_address);
+                        let index =
+                            self.new_bucket_index(&address, &src_address.unwrap_or(address));
 
-                        self.new_bucket
+                        let overwritten = self
+                            .new_bucket
                             .insert(
                                 index,
                                 PeerInfo {
@@ -223,7 +361,15 @@ impl Peers {
                                     timestamp: get_timestamp(), //msg.timestamp,
                                 },
                             )
-                            .map(|v| v.address)
+                            .map(|v| v.address);
+                        if let Some(overwritten) = overwritten {
+                            self.new_bucket_sources.remove(&overwritten);
+                        }
+                        if let Some(src_address) = src_address {
+                            self.new_bucket_sources.insert(address, src_address);
+                        }
+
+                        overwritten
                     } else {
                         None
                     }
@@ -301,11 +447,14 @@ impl Peers {
     /// Remove a peer given an index from new addresses bucket
     /// Returns the removed addresses
     pub fn remove_from_new_with_index(&mut self, indexes: &[u16]) -> Vec<SocketAddr> {
-        let v = indexes
+        let v: Vec<SocketAddr> = indexes
             .iter()
             .filter_map(|index| self.new_bucket.remove(index))
             .map(|info| info.address)
             .collect();
+        for address in &v {
+            self.new_bucket_sources.remove(address);
+        }
 
         log::trace!("Removed new peers: \n{}", self);
 
@@ -313,9 +462,13 @@ impl Peers {
     }
 
     /// Get a random socket address from the peers list
-    /// This method provides the same probability to tried and new bucket peers
+    /// This method provides the same probability to tried and new bucket peers. Within each
+    /// bucket, peers are more likely to be chosen the higher their score, except for
+    /// `exploration_percentage` of the choices, which are made uniformly among the peers that have
+    /// no score or a negative one
     pub fn get_random_peers(&self, n: usize) -> Result<Vec<SocketAddr>, failure::Error> {
         let mut rng = rand::thread_rng();
+        let current_ts = get_timestamp();
 
         let tried_len = self.tried_bucket.len();
         let new_len = self.new_bucket.len();
@@ -339,20 +492,155 @@ impl Peers {
         let index_new_peers = index_new_peers.clamp(min_new_required, new_len);
 
         // Obtains random peers from each bucket
-        v_peers.extend(
-            self.new_bucket
-                .values()
-                .map(|p| p.address)
-                .choose_multiple(&mut rng, index_new_peers),
+        v_peers.extend(self.choose_by_score(
+            &mut rng,
+            self.new_bucket.values().map(|p| p.address),
+            index_new_peers,
+            current_ts,
+        ));
+        v_peers.extend(self.choose_by_score(
+            &mut rng,
+            self.tried_bucket.values().map(|p| p.address),
+            n_peers - index_new_peers,
+            current_ts,
+        ));
+
+        Ok(v_peers)
+    }
+
+    /// Choose `n` different addresses out of `candidates`, with a probability that grows
+    /// exponentially with their score at `current_timestamp`
+    fn choose_by_score<R, I>(
+        &self,
+        rng: &mut R,
+        candidates: I,
+        n: usize,
+        current_timestamp: i64,
+    ) -> Vec<SocketAddr>
+    where
+        R: Rng,
+        I: Iterator<Item = SocketAddr>,
+    {
+        // Without any scores, all the candidates are equally likely
+        if self.scores.is_empty() {
+            return candidates.choose_multiple(rng, n);
+        }
+
+        let mut candidates: Vec<(SocketAddr, f64)> = candidates
+            .map(|address| {
+                let score = self.score_pure(&address, current_timestamp);
+
+                (address, score.unwrap_or(0.0))
+            })
+            .collect();
+        let mut chosen = Vec::with_capacity(n);
+        while chosen.len() < n && !candidates.is_empty() {
+            let explore = rng.gen_range(0, 100) < self.exploration_percentage;
+            let unexplored: Vec<usize> = if explore {
+                (0..candidates.len())
+                    .filter(|i| candidates[*i].1 <= 0.0)
+                    .collect()
+            } else {
+                vec![]
+            };
+
+            let index = match unexplored.choose(rng) {
+                Some(index) => *index,
+                None => {
+                    WeightedIndex::new(candidates.iter().map(|(_, score)| score_weight(*score)))
+                        .map(|distribution| distribution.sample(rng))
+                        .unwrap_or_else(|_| rng.gen_range(0, candidates.len()))
+                }
+            };
+
+            chosen.push(candidates.swap_remove(index).0);
+        }
+
+        chosen
+    }
+
+    /// Record the outcome of a connection with a peer address using the current timestamp as a
+    /// reference for decaying its previous score.
+    /// Returns the updated score
+    pub fn record_outcome(&mut self, address: SocketAddr, outcome: PeerOutcome) -> f64 {
+        self.record_outcome_pure(address, outcome, get_timestamp())
+    }
+
+    /// Record the outcome of a connection with a peer address using the provided timestamp as a
+    /// reference for decaying its previous score. The scores that have decayed to almost nothing
+    /// are forgotten, so that they do not accumulate forever.
+    /// Returns the updated score
+    pub fn record_outcome_pure(
+        &mut self,
+        address: SocketAddr,
+        outcome: PeerOutcome,
+        current_timestamp: i64,
+    ) -> f64 {
+        let half_life = self.score_half_life_secs();
+        let score =
+            self.score_pure(&address, current_timestamp).unwrap_or(0.0) + outcome.score_delta();
+
+        // Closing a session proves that the address was alive until then
+        if let PeerOutcome::Disconnected { .. } = outcome {
+            let index = self.tried_bucket_index(&address);
+            if let Some(info) = self
+                .tried_bucket
+                .get_mut(&index)
+                .filter(|info| info.address == address)
+            {
+                info.timestamp = cmp::max(info.timestamp, current_timestamp);
+            }
+        }
+        self.scores
+            .retain(|_, s| s.decayed(current_timestamp, half_life).abs() >= MIN_SCORE);
+        self.scores.insert(
+            address,
+            PeerScore {
+                score,
+                timestamp: current_timestamp,
+            },
         );
-        v_peers.extend(
-            self.tried_bucket
-                .values()
-                .map(|p| p.address)
-                .choose_multiple(&mut rng, n_peers - index_new_peers),
+
+        log::debug!(
+            "Peer address {} scored {:.2} after {:?}",
+            address,
+            score,
+            outcome
         );
 
-        Ok(v_peers)
+        score
+    }
+
+    /// Get the current score of a peer address, or `None` if no connection outcomes were recorded
+    /// for it
+    pub fn score(&self, address: &SocketAddr) -> Option<f64> {
+        self.score_pure(address, get_timestamp())
+    }
+
+    /// Get the score of a peer address at the provided timestamp, or `None` if no connection
+    /// outcomes were recorded for it
+    pub fn score_pure(&self, address: &SocketAddr, current_timestamp: i64) -> Option<f64> {
+        let half_life = self.score_half_life_secs();
+
+        self.scores
+            .get(address)
+            .map(|score| score.decayed(current_timestamp, half_life))
+    }
+
+    /// Get the current scores of all the peer addresses for which any connection outcome was
+    /// recorded
+    pub fn current_scores(&self) -> HashMap<SocketAddr, f64> {
+        let current_ts = get_timestamp();
+        let half_life = self.score_half_life_secs();
+
+        self.scores
+            .iter()
+            .map(|(address, score)| (*address, score.decayed(current_ts, half_life)))
+            .collect()
+    }
+
+    fn score_half_life_secs(&self) -> i64 {
+        i64::try_from(self.score_half_life.as_secs()).unwrap_or(i64::MAX)
     }
 
     /// Get a random socket address from the new peers list
@@ -364,11 +652,55 @@ impl Peers {
             .map(|(k, v)| (*k, v.address))
     }
 
+    /// Choose up to `limit` addresses of the tried bucket to share with the peer at `requester`,
+    /// among those seen in the last `max_age` seconds. See `select_peers_to_share`.
+    pub fn get_peers_to_share(
+        &self,
+        requester: &SocketAddr,
+        max_age: i64,
+        limit: usize,
+    ) -> Vec<SocketAddr> {
+        let current_ts = get_timestamp();
+        let ice_period = self.current_ice_period();
+        let records = self.tried_bucket.values().map(|info| PeerRecord {
+            address: info.address,
+            last_seen: info.timestamp,
+            source: self.new_bucket_source(&info.address),
+            iced: self
+                .ice_bucket
+                .get(&info.address)
+                .map_or(false, |ts| *ts >= current_ts.saturating_sub(ice_period)),
+            own: self.is_server_address(&info.address),
+        });
+
+        select_peers_to_share(
+            &mut rand::thread_rng(),
+            records,
+            requester,
+            current_ts.saturating_sub(max_age),
+            limit,
+        )
+    }
+
     /// Get all the peers from the tried bucket
     pub fn get_all_from_tried(&self) -> Result<Vec<SocketAddr>, failure::Error> {
         Ok(self.tried_bucket.values().map(|v| v.address).collect())
     }
 
+    /// Get the peer that sent us an address of the new bucket, if it was not added manually
+    pub fn new_bucket_source(&self, address: &SocketAddr) -> Option<SocketAddr> {
+        self.new_bucket_sources.get(address).copied()
+    }
+
+    /// Get all the addresses of the new bucket that were sent to us by a peer
+    pub fn get_all_from_new_by_source(&self, src_address: &SocketAddr) -> Vec<SocketAddr> {
+        self.new_bucket_sources
+            .iter()
+            .filter(|(_, source)| *source == src_address)
+            .map(|(address, _)| *address)
+            .collect()
+    }
+
     /// Get all the peers from the tried bucket
     pub fn get_all_from_new(&self) -> Result<Vec<SocketAddr>, failure::Error> {
         Ok(self.new_bucket.values().map(|v| v.address).collect())
@@ -384,6 +716,7 @@ impl Peers {
     /// Clear new addresses bucket
     pub fn clear_new_bucket(&mut self) {
         self.new_bucket.clear();
+        self.new_bucket_sources.clear();
 
         log::trace!("Cleared new bucket: \n{}", self);
     }
@@ -466,6 +799,58 @@ fn safe_ice_period_default() -> Duration {
     Duration::from_secs(60)
 }
 
+/// Generate a default for the `Peers::score_half_life` field
+fn score_half_life_default() -> Duration {
+    Duration::from_secs(24 * 3600)
+}
+
+/// Generate a default for the `Peers::exploration_percentage` field
+fn exploration_percentage_default() -> u8 {
+    10
+}
+
+/// Scores closer to zero than this are forgotten
+const MIN_SCORE: f64 = 0.01;
+
+/// Scores beyond this magnitude do not make a peer any more or less likely to be chosen, so that
+/// their weights do not overflow
+const MAX_SCORE_WEIGHT_EXPONENT: f64 = 20.0;
+
+/// Weight of a peer with the given score when choosing peers at random
+fn score_weight(score: f64) -> f64 {
+    score
+        .clamp(-MAX_SCORE_WEIGHT_EXPONENT, MAX_SCORE_WEIGHT_EXPONENT)
+        .exp()
+}
+
+/// Choose up to `limit` addresses to share with the peer at `requester`, uniformly at random
+/// among the ones that were seen since `seen_since`, so that we do not always reveal the same
+/// ones. The addresses that are iced or ours are never shared, and neither are the requester and
+/// the addresses that it sent us, which it already knows.
+pub fn select_peers_to_share<R, I>(
+    rng: &mut R,
+    records: I,
+    requester: &SocketAddr,
+    seen_since: i64,
+    limit: usize,
+) -> Vec<SocketAddr>
+where
+    R: Rng,
+    I: IntoIterator<Item = PeerRecord>,
+{
+    records
+        .into_iter()
+        .filter(|record| {
+            record.last_seen >= seen_since
+                && !record.iced
+                && !record.own
+                && record.address != *requester
+                && record.source != Some(*requester)
+        })
+        .map(|record| record.address)
+        .choose_multiple(rng, limit)
+}
+
 /// Returns the ip and ip split
 pub fn split_socket_addresses(socket_addr: &SocketAddr) -> (Vec<u8>, Vec<u8>, Vec<u8>) {
     match socket_addr {
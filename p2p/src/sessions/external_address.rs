@@ -0,0 +1,96 @@
+//! Discovery of the external address of the node, as seen by its peers
+
+use std::{
+    collections::{HashMap, HashSet},
+    net::{IpAddr, SocketAddr},
+};
+
+/// Tally of the IP addresses at which the consolidated peers see us. Once enough peers agree on
+/// one of them, it is adopted as our external address, together with the port on which we listen
+/// for peers
+#[derive(Debug, Default)]
+pub struct ExternalAddressTally {
+    /// IP address at which each peer sees us, by the address of its session
+    observations: HashMap<SocketAddr, IpAddr>,
+    /// Number of peers that must agree on an IP address for it to be adopted. Zero disables the
+    /// discovery
+    quorum: usize,
+    /// Port on which we listen for peers
+    port: u16,
+    /// Adopted external address
+    adopted: Option<SocketAddr>,
+}
+
+impl ExternalAddressTally {
+    /// Create a tally that adopts an IP address once `quorum` peers agree on it
+    pub fn new(quorum: usize, port: u16) -> Self {
+        Self {
+            quorum,
+            port,
+            ..Default::default()
+        }
+    }
+
+    /// Record the address at which the peer of a session sees us, replacing any previous
+    /// observation from that session. Returns the new external address if this observation
+    /// changed it
+    pub fn observe(&mut self, peer: SocketAddr, observed: SocketAddr) -> Option<SocketAddr> {
+        // Peers that do not know our address send an unspecified one
+        if self.quorum == 0 || observed.ip().is_unspecified() {
+            return None;
+        }
+        self.observations.insert(peer, observed.ip());
+
+        self.update()
+    }
+
+    /// Forget the observation from a session that was closed. The adopted address is kept until
+    /// the peers agree on a different one
+    pub fn forget(&mut self, peer: SocketAddr) {
+        self.observations.remove(&peer);
+    }
+
+    /// Adopted external address, if enough peers have ever agreed on one
+    pub fn external_address(&self) -> Option<SocketAddr> {
+        self.adopted
+    }
+
+    /// Number of distinct peers that see us at `ip`. Several sessions with peers sharing an IP
+    /// address count as a single one
+    pub fn votes(&self, ip: IpAddr) -> usize {
+        self.voters().get(&ip).map_or(0, HashSet::len)
+    }
+
+    fn voters(&self) -> HashMap<IpAddr, HashSet<IpAddr>> {
+        let mut voters: HashMap<IpAddr, HashSet<IpAddr>> = HashMap::new();
+        for (peer, observed) in &self.observations {
+            voters.entry(*observed).or_default().insert(peer.ip());
+        }
+
+        voters
+    }
+
+    /// Adopt the IP address seen by most peers if it reaches the quorum and it is seen by more
+    /// peers than the one adopted so far
+    fn update(&mut self) -> Option<SocketAddr> {
+        let adopted_votes = self.adopted.map_or(0, |address| self.votes(address.ip()));
+        // Ties are broken in favour of the lowest IP address, so that the result does not depend
+        // on the order of the observations
+        let (ip, votes) = self
+            .voters()
+            .into_iter()
+            .map(|(ip, voters)| (ip, voters.len()))
+            .max_by(|(ip_a, votes_a), (ip_b, votes_b)| {
+                votes_a.cmp(votes_b).then_with(|| ip_b.cmp(ip_a))
+            })?;
+
+        if votes < self.quorum || votes <= adopted_votes {
+            return None;
+        }
+
+        let address = SocketAddr::new(ip, self.port);
+        self.adopted = Some(address);
+
+        Some(address)
+    }
+}
@@ -2,14 +2,26 @@
 
 /// Bounded sessions module
 pub mod bounded_sessions;
+/// External address discovery module
+pub mod external_address;
+/// Outbound sessions rotation module
+pub mod rotation;
 
 use std::net::SocketAddr;
 
 use rand::{thread_rng, Rng};
 
-use super::{error::SessionsError, sessions::bounded_sessions::BoundedSessions};
+use super::{
+    error::SessionsError,
+    sessions::{
+        bounded_sessions::BoundedSessions,
+        external_address::ExternalAddressTally,
+        rotation::{OutboundRotation, RotationAction, RotationParams},
+    },
+};
 use crate::peers::get_range_address;
 use std::collections::HashSet;
+use witnet_util::timestamp::get_timestamp;
 
 /// Session type
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -59,8 +71,13 @@ where
     /// Outbound unconsolidated sessions: __known__ peer sessions that the node is connected to
     /// (in unconsolidated status)
     pub outbound_unconsolidated: BoundedSessions<T>,
-    /// Server public address listening to incoming connections
+    /// Statistics of the consolidated outbound sessions, used for rotating them
+    pub outbound_rotation: OutboundRotation,
+    /// Server public address listening to incoming connections, as configured
     pub public_address: Option<SocketAddr>,
+    /// Tally of the addresses at which the consolidated peers see us, used if the public address
+    /// is not configured
+    pub external_address: ExternalAddressTally,
 }
 
 /// Default trait implementation
@@ -77,7 +94,9 @@ where
             outbound_consolidated: BoundedSessions::default(),
             outbound_consolidated_consensus: BoundedSessions::default(),
             outbound_unconsolidated: BoundedSessions::default(),
+            outbound_rotation: OutboundRotation::default(),
             public_address: None,
+            external_address: ExternalAddressTally::default(),
         }
     }
 }
@@ -108,6 +127,38 @@ where
     pub fn set_public_address(&mut self, public_address: Option<SocketAddr>) {
         self.public_address = public_address;
     }
+    /// Method to set the number of peers that must agree on our external address before it is
+    /// advertised, and the port on which we listen for peers
+    pub fn set_external_address_discovery(&mut self, quorum: usize, port: u16) {
+        self.external_address = ExternalAddressTally::new(quorum, port);
+    }
+    /// Method to get the address advertised to peers as our own: the public address if it is
+    /// configured, or else the external address discovered from the peers
+    pub fn advertised_address(&self) -> Option<SocketAddr> {
+        self.configured_public_address()
+            .or_else(|| self.external_address.external_address())
+    }
+    /// Method to record the address at which the peer of a consolidated session sees us. Returns
+    /// the new advertised address if it changed
+    pub fn observe_external_address(
+        &mut self,
+        peer: SocketAddr,
+        observed: SocketAddr,
+    ) -> Option<SocketAddr> {
+        let adopted = self.external_address.observe(peer, observed)?;
+
+        // The configured public address takes precedence over the discovered one
+        if self.configured_public_address().is_some() {
+            None
+        } else {
+            Some(adopted)
+        }
+    }
+    /// The configured public address, unless it has an unspecified IP address (i.e. 0.0.0.0)
+    fn configured_public_address(&self) -> Option<SocketAddr> {
+        self.public_address
+            .filter(|address| !address.ip().is_unspecified())
+    }
     /// Method to set the sessions limits
     pub fn set_limits(&mut self, inbound_limit: u16, outbound_consolidated_limit: u16) {
         self.inbound_consolidated.set_limit(inbound_limit);
@@ -142,7 +193,7 @@ where
 
         // Check if address is the server address
         if self
-            .public_address
+            .advertised_address()
             .map(|address| address == candidate_addr)
             .unwrap_or(false)
         {
@@ -260,6 +311,12 @@ where
             // Explicitly ignore the result because we have no guarantees that this session was
             // inside the consensus map
             let _ = self.unconsensus_session(address);
+            self.outbound_rotation.unregister(address);
+        }
+
+        // The peer of a closed session no longer counts towards our external address
+        if status == SessionStatus::Consolidated {
+            self.external_address.forget(address);
         }
 
         // Get map to insert session to
@@ -294,6 +351,11 @@ where
         // Register session into consolidated collection
         cons_sessions.register_session(address, session_info.reference)?;
 
+        // Start scoring outbound sessions from the moment they are consolidated
+        if session_type == SessionType::Outbound {
+            self.outbound_rotation.register(address, get_timestamp());
+        }
+
         Ok(())
     }
     /// Method to mark a session as consensus safe
@@ -317,6 +379,22 @@ where
         cons_sessions.unregister_session(address).map(|_| ())
     }
 
+    /// Method to run an outbound rotation tick: select the worst scoring consolidated outbound
+    /// session to be replaced by a new one
+    pub fn rotate_outbound(&mut self, now: i64, params: &RotationParams) -> Vec<RotationAction> {
+        let selected = self.outbound_rotation.select(
+            self.outbound_consolidated.collection.keys(),
+            now,
+            params,
+        );
+        self.outbound_rotation.decay();
+
+        match selected {
+            Some(address) => vec![RotationAction::Disconnect(address), RotationAction::Dial],
+            None => vec![],
+        }
+    }
+
     /// Get all the consolidated sessions addresses
     pub fn get_consolidated_sessions_addr(&self) -> GetConsolidatedPeersResult {
         GetConsolidatedPeersResult {
@@ -0,0 +1,178 @@
+//! Scoring of outbound sessions, used for periodically replacing the least valuable one
+
+use std::{collections::HashMap, net::SocketAddr, time::Duration};
+
+/// Weight of a new sample in the smoothed response time of a session
+const RESPONSE_TIME_ALPHA: f64 = 0.3;
+
+/// Uptime (in seconds) after which a session gets the full uptime score
+const FULL_UPTIME_SCORE_SECS: i64 = 24 * 60 * 60;
+
+/// Response time (in milliseconds) that gets half of the full latency score
+const HALF_LATENCY_SCORE_MS: f64 = 1_000.0;
+
+/// Latency score of a session whose response time has not been measured yet
+const UNKNOWN_LATENCY_SCORE: f64 = 0.5;
+
+/// Something an outbound peer did that is taken into account when scoring its session
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionActivity {
+    /// The peer answered one of our requests after this long
+    ResponseTime(Duration),
+    /// The peer delivered this many blocks or transactions
+    Delivered(u32),
+    /// This many bytes written to the session are pending to be sent to the peer
+    PendingWrites(usize),
+}
+
+/// Statistics of a consolidated outbound session
+#[derive(Clone, Debug, PartialEq)]
+pub struct OutboundSessionStats {
+    /// Timestamp of the consolidation of the session
+    pub connected_at: i64,
+    /// Smoothed response time in milliseconds, if it has ever been measured
+    pub response_time_ms: Option<f64>,
+    /// Number of blocks and transactions delivered recently. It is halved on every rotation tick
+    pub delivered: u32,
+    /// Bytes pending to be sent to the peer when last reported. It does not affect the score
+    pub pending_write_bytes: usize,
+}
+
+impl OutboundSessionStats {
+    /// Create statistics for a session consolidated at `connected_at`
+    pub fn new(connected_at: i64) -> Self {
+        Self {
+            connected_at,
+            response_time_ms: None,
+            delivered: 0,
+            pending_write_bytes: 0,
+        }
+    }
+
+    /// Update the statistics with some activity of the peer
+    pub fn record(&mut self, activity: SessionActivity) {
+        match activity {
+            SessionActivity::ResponseTime(response_time) => {
+                let sample = response_time.as_secs_f64() * 1_000.0;
+                self.response_time_ms = Some(match self.response_time_ms {
+                    Some(previous) => {
+                        RESPONSE_TIME_ALPHA * sample + (1.0 - RESPONSE_TIME_ALPHA) * previous
+                    }
+                    None => sample,
+                });
+            }
+            SessionActivity::Delivered(items) => {
+                self.delivered = self.delivered.saturating_add(items);
+            }
+            SessionActivity::PendingWrites(bytes) => {
+                self.pending_write_bytes = bytes;
+            }
+        }
+    }
+
+    /// Seconds since the session was consolidated
+    pub fn lifetime(&self, now: i64) -> i64 {
+        now.saturating_sub(self.connected_at).max(0)
+    }
+
+    /// Score of the session, the higher the better.
+    ///
+    /// This is the sum of three scores between 0 and 1, one for each of uptime, latency and
+    /// usefulness (blocks and transactions delivered).
+    pub fn score(&self, now: i64) -> f64 {
+        let uptime = (self.lifetime(now) as f64 / FULL_UPTIME_SCORE_SECS as f64).min(1.0);
+        let latency = self
+            .response_time_ms
+            .map(|ms| HALF_LATENCY_SCORE_MS / (HALF_LATENCY_SCORE_MS + ms))
+            .unwrap_or(UNKNOWN_LATENCY_SCORE);
+        let delivered = f64::from(self.delivered);
+        let usefulness = delivered / (delivered + 1.0);
+
+        uptime + latency + usefulness
+    }
+}
+
+/// Parameters of a rotation tick
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RotationParams {
+    /// Sessions consolidated less than this many seconds ago are never rotated
+    pub min_lifetime: i64,
+    /// Rotation never leaves fewer consolidated outbound sessions than this
+    pub min_outbound: usize,
+}
+
+/// Action resulting from a rotation tick
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RotationAction {
+    /// Close the session with this peer
+    Disconnect(SocketAddr),
+    /// Open a new outbound session with a peer we are not connected to
+    Dial,
+}
+
+/// Keeps track of the statistics of the consolidated outbound sessions
+#[derive(Debug, Default)]
+pub struct OutboundRotation {
+    stats: HashMap<SocketAddr, OutboundSessionStats>,
+}
+
+impl OutboundRotation {
+    /// Start tracking a session consolidated at `connected_at`
+    pub fn register(&mut self, address: SocketAddr, connected_at: i64) {
+        self.stats
+            .insert(address, OutboundSessionStats::new(connected_at));
+    }
+
+    /// Stop tracking a session
+    pub fn unregister(&mut self, address: SocketAddr) {
+        self.stats.remove(&address);
+    }
+
+    /// Record some activity of a session. Activity of untracked sessions is ignored
+    pub fn record(&mut self, address: SocketAddr, activity: SessionActivity) {
+        if let Some(stats) = self.stats.get_mut(&address) {
+            stats.record(activity);
+        }
+    }
+
+    /// Get the statistics of a session
+    pub fn stats(&self, address: &SocketAddr) -> Option<&OutboundSessionStats> {
+        self.stats.get(address)
+    }
+
+    /// Select the worst scoring session among `candidates` that has lived at least
+    /// `params.min_lifetime`, as long as rotating it does not leave fewer than
+    /// `params.min_outbound` sessions.
+    ///
+    /// Ties are broken by address, so that the selection is deterministic.
+    pub fn select<'a, I>(
+        &self,
+        candidates: I,
+        now: i64,
+        params: &RotationParams,
+    ) -> Option<SocketAddr>
+    where
+        I: IntoIterator<Item = &'a SocketAddr>,
+    {
+        let candidates: Vec<&SocketAddr> = candidates.into_iter().collect();
+        if candidates.len() <= params.min_outbound {
+            return None;
+        }
+
+        candidates
+            .into_iter()
+            .filter_map(|address| self.stats.get(address).map(|stats| (address, stats)))
+            .filter(|(_, stats)| stats.lifetime(now) >= params.min_lifetime)
+            .map(|(address, stats)| (address, stats.score(now)))
+            .min_by(|(a, a_score), (b, b_score)| a_score.total_cmp(b_score).then(a.cmp(b)))
+            .map(|(address, _)| *address)
+    }
+
+    /// Halve the number of delivered items of every session, so that usefulness reflects recent
+    /// activity
+    pub fn decay(&mut self) {
+        for stats in self.stats.values_mut() {
+            stats.delivered /= 2;
+        }
+    }
+}
@@ -0,0 +1,149 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+
+use witnet_p2p::sessions::{external_address::*, *};
+
+const PORT: u16 = 21337;
+
+fn peer(last_byte: u8) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, last_byte)), 40000)
+}
+
+fn seen_at(last_byte: u8) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(203, 0, 113, last_byte)), 50000)
+}
+
+fn external(last_byte: u8) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(203, 0, 113, last_byte)), PORT)
+}
+
+/// The address seen by a quorum of peers is adopted, with the port on which we listen
+#[test]
+fn external_address_quorum_is_adopted() {
+    let mut tally = ExternalAddressTally::new(3, PORT);
+
+    assert_eq!(tally.observe(peer(1), seen_at(7)), None);
+    assert_eq!(tally.observe(peer(2), seen_at(9)), None);
+    assert_eq!(tally.observe(peer(3), seen_at(7)), None);
+    assert_eq!(tally.external_address(), None);
+
+    assert_eq!(tally.observe(peer(4), seen_at(7)), Some(external(7)));
+    assert_eq!(tally.external_address(), Some(external(7)));
+    assert_eq!(tally.votes(external(7).ip()), 3);
+    assert_eq!(tally.votes(external(9).ip()), 1);
+
+    // Further agreement does not change the address
+    assert_eq!(tally.observe(peer(5), seen_at(7)), None);
+    assert_eq!(tally.external_address(), Some(external(7)));
+}
+
+/// Sessions with peers that share an IP address count as a single vote
+#[test]
+fn external_address_votes_by_peer_ip() {
+    let mut tally = ExternalAddressTally::new(2, PORT);
+    let same_host = SocketAddr::new(peer(1).ip(), 40001);
+
+    assert_eq!(tally.observe(peer(1), seen_at(7)), None);
+    assert_eq!(tally.observe(same_host, seen_at(7)), None);
+    assert_eq!(tally.votes(external(7).ip()), 1);
+
+    assert_eq!(tally.observe(peer(2), seen_at(7)), Some(external(7)));
+}
+
+/// The adopted address only changes when more peers agree on a different one
+#[test]
+fn external_address_changes_only_with_more_votes() {
+    let mut tally = ExternalAddressTally::new(2, PORT);
+    tally.observe(peer(1), seen_at(7));
+    tally.observe(peer(2), seen_at(7));
+    assert_eq!(tally.external_address(), Some(external(7)));
+
+    // A tie keeps the adopted address, even if the other one is lower
+    assert_eq!(tally.observe(peer(3), seen_at(5)), None);
+    assert_eq!(tally.observe(peer(4), seen_at(5)), None);
+    assert_eq!(tally.external_address(), Some(external(7)));
+
+    assert_eq!(tally.observe(peer(5), seen_at(5)), Some(external(5)));
+    assert_eq!(tally.external_address(), Some(external(5)));
+}
+
+/// Closed sessions stop counting, but the adopted address is kept
+#[test]
+fn external_address_forget() {
+    let mut tally = ExternalAddressTally::new(2, PORT);
+    tally.observe(peer(1), seen_at(7));
+    tally.observe(peer(2), seen_at(7));
+
+    tally.forget(peer(1));
+    assert_eq!(tally.votes(external(7).ip()), 1);
+    assert_eq!(tally.external_address(), Some(external(7)));
+
+    // Another address is adopted as soon as it reaches the quorum
+    tally.observe(peer(3), seen_at(5));
+    assert_eq!(tally.external_address(), Some(external(7)));
+    assert_eq!(tally.observe(peer(4), seen_at(5)), Some(external(5)));
+}
+
+/// Unspecified addresses are sent by peers that do not know ours, and are ignored
+#[test]
+fn external_address_ignores_unspecified() {
+    let mut tally = ExternalAddressTally::new(1, PORT);
+    let unspecified = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), 0);
+
+    assert_eq!(tally.observe(peer(1), unspecified), None);
+    assert_eq!(tally.external_address(), None);
+}
+
+/// A quorum of zero disables the discovery
+#[test]
+fn external_address_disabled() {
+    let mut tally = ExternalAddressTally::new(0, PORT);
+
+    assert_eq!(tally.observe(peer(1), seen_at(7)), None);
+    assert_eq!(tally.external_address(), None);
+}
+
+/// The discovered address is advertised unless a public address is configured
+#[test]
+fn sessions_advertised_address() {
+    let mut sessions = Sessions::<String>::default();
+    sessions.set_external_address_discovery(1, PORT);
+    assert_eq!(sessions.advertised_address(), None);
+
+    // An unspecified public address counts as not configured
+    let unspecified = SocketAddr::new(IpAddr::V4(Ipv4Addr::UNSPECIFIED), PORT);
+    sessions.set_public_address(Some(unspecified));
+    assert_eq!(sessions.advertised_address(), None);
+    assert_eq!(
+        sessions.observe_external_address(peer(1), seen_at(7)),
+        Some(external(7))
+    );
+    assert_eq!(sessions.advertised_address(), Some(external(7)));
+
+    let configured = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(198, 51, 100, 1)), PORT);
+    sessions.set_public_address(Some(configured));
+    assert_eq!(sessions.advertised_address(), Some(configured));
+    assert_eq!(sessions.observe_external_address(peer(2), seen_at(5)), None);
+    assert_eq!(sessions.observe_external_address(peer(3), seen_at(5)), None);
+    assert_eq!(sessions.advertised_address(), Some(configured));
+}
+
+/// The peers of closed sessions no longer count towards the external address
+#[test]
+fn sessions_unregister_forgets_observation() {
+    let mut sessions = Sessions::<String>::default();
+    sessions.set_limits(8, 8);
+    sessions.set_external_address_discovery(2, PORT);
+    sessions
+        .register_session(SessionType::Inbound, peer(1), peer(1).to_string())
+        .unwrap();
+    sessions
+        .consolidate_session(SessionType::Inbound, peer(1))
+        .unwrap();
+    sessions.observe_external_address(peer(1), seen_at(7));
+    assert_eq!(sessions.external_address.votes(external(7).ip()), 1);
+
+    sessions
+        .unregister_session(SessionType::Inbound, SessionStatus::Consolidated, peer(1))
+        .unwrap();
+    assert_eq!(sessions.external_address.votes(external(7).ip()), 0);
+}
@@ -1,4 +1,8 @@
-use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
 
 use witnet_p2p::peers::*;
 
@@ -162,6 +166,70 @@ fn p2p_peers_remove_from_new_with_index() {
     assert_eq!(peers.remove_from_new_with_index(&[index]), vec![]);
 }
 
+#[test]
+fn p2p_peers_new_bucket_sources() {
+    // Create peers struct
+    let server_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(192, 168, 2, 20)), 8080);
+    let mut peers = Peers {
+        server_address,
+        ..Default::default()
+    };
+
+    // Add addresses sent by two different peers, and one added manually
+    let honest = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(168, 0, 0, 12)), 8080);
+    let junk_feeder = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(172, 16, 0, 1)), 8080);
+    let address_1 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let address_2 = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 8080);
+    let manual = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 1, 0, 3)), 8080);
+    peers.add_to_new(vec![address_1], Some(honest)).unwrap();
+    peers
+        .add_to_new(vec![address_2], Some(junk_feeder))
+        .unwrap();
+    peers.add_to_new(vec![manual], None).unwrap();
+
+    assert_eq!(peers.new_bucket_source(&address_1), Some(honest));
+    assert_eq!(peers.new_bucket_source(&address_2), Some(junk_feeder));
+    assert_eq!(peers.new_bucket_source(&manual), None);
+    assert_eq!(
+        peers.get_all_from_new_by_source(&junk_feeder),
+        vec![address_2]
+    );
+
+    // Removed addresses are no longer attributed to anyone
+    let index = peers.new_bucket_index(&address_2, &junk_feeder);
+    assert_eq!(peers.remove_from_new_with_index(&[index]), vec![address_2]);
+    assert_eq!(peers.new_bucket_source(&address_2), None);
+    assert_eq!(peers.get_all_from_new_by_source(&junk_feeder), vec![]);
+
+    peers.clear_new_bucket();
+    assert_eq!(peers.new_bucket_source(&address_1), None);
+}
+
+#[test]
+fn p2p_peers_own_addresses_are_not_added() {
+    // Create peers struct
+    let server_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(0, 0, 0, 0)), 21337);
+    let public_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(203, 0, 113, 7)), 21337);
+    let mut peers = Peers {
+        server_address,
+        public_address: Some(public_address),
+        ..Default::default()
+    };
+
+    // Peers gossip our own address back to us
+    let src_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(168, 0, 0, 12)), 21337);
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, 2)), 21337);
+    peers
+        .add_to_new(
+            vec![server_address, public_address, address],
+            Some(src_address),
+        )
+        .unwrap();
+
+    assert!(peers.is_server_address(&public_address));
+    assert_eq!(peers.get_all_from_new().unwrap(), vec![address]);
+}
+
 #[test]
 fn p2p_peers_get_all_from_new() {
     // Create peers struct
@@ -215,3 +283,186 @@ fn p2p_add_2_peers_in_collision() {
 
     assert_eq!(peers.get_all_from_tried().unwrap().len(), 1);
 }
+
+/// Peers whose tried bucket holds the given addresses and nothing else
+fn peers_with_tried(addresses: &[SocketAddr]) -> Peers {
+    let mut peers = Peers {
+        sk: 0,
+        ..Default::default()
+    };
+    for address in addresses {
+        peers.add_to_tried(*address).unwrap();
+    }
+    assert_eq!(peers.get_all_from_tried().unwrap().len(), addresses.len());
+
+    peers
+}
+
+#[test]
+fn p2p_peers_selection_favours_good_peers() {
+    let good = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let flaky = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    let broken = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 3)), 8080);
+    let unexplored = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 4)), 8080);
+    let mut peers = peers_with_tried(&[good, flaky, broken, unexplored]);
+    assert_eq!(peers.exploration_percentage, 10);
+
+    // The good peer kept long lived sessions, the others failed to complete the handshake
+    for _ in 0..3 {
+        peers.record_outcome(good, PeerOutcome::Consolidated);
+        peers.record_outcome(
+            good,
+            PeerOutcome::Disconnected {
+                reason: DisconnectReason::Closed,
+                uptime: 2 * 3600,
+            },
+        );
+        peers.record_outcome(flaky, PeerOutcome::HandshakeFailed);
+    }
+    peers.record_outcome(broken, PeerOutcome::DecodeError { errors: 1 });
+    peers.record_outcome(broken, PeerOutcome::HandshakeFailed);
+    assert!(peers.score(&good).unwrap() > 8.0);
+    assert!(peers.score(&flaky).unwrap() < 0.0);
+    assert!(peers.score(&broken).unwrap() < 0.0);
+    assert_eq!(peers.score(&unexplored), None);
+
+    let mut counts = HashMap::new();
+    for _ in 0..10_000 {
+        for address in peers.get_random_peers(1).unwrap() {
+            *counts.entry(address).or_insert(0) += 1;
+        }
+    }
+
+    // About 90% of the selections exploit the good peer, and the other 10% are spread among
+    // the untried and low scoring ones
+    let count = |address| counts.get(&address).copied().unwrap_or(0);
+    assert!(count(good) > 8_000, "{:?}", counts);
+    assert!(count(unexplored) > 100, "{:?}", counts);
+    assert!(count(flaky) > 0, "{:?}", counts);
+    assert!(count(broken) > 0, "{:?}", counts);
+}
+
+#[test]
+fn p2p_peers_selection_explores_untried_peers() {
+    let good = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let unexplored = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 4)), 8080);
+    let mut peers = Peers {
+        exploration_percentage: 100,
+        ..peers_with_tried(&[good, unexplored])
+    };
+    peers.record_outcome(good, PeerOutcome::Consolidated);
+
+    for _ in 0..1_000 {
+        assert_eq!(peers.get_random_peers(1).unwrap(), vec![unexplored]);
+    }
+    // Asking for more peers than there are unexplored ones also returns the good ones
+    assert_eq!(peers.get_random_peers(5).unwrap().len(), 2);
+}
+
+#[test]
+fn p2p_peers_scores_decay() {
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let other_address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    let mut peers = Peers {
+        score_half_life: Duration::from_secs(100),
+        ..Default::default()
+    };
+
+    assert_eq!(
+        peers.record_outcome_pure(address, PeerOutcome::Consolidated, 1_000),
+        1.0
+    );
+    assert_eq!(peers.score_pure(&address, 1_100), Some(0.5));
+    assert_eq!(peers.score_pure(&address, 1_200), Some(0.25));
+    assert_eq!(
+        peers.record_outcome_pure(address, PeerOutcome::HandshakeFailed, 1_100),
+        -0.5
+    );
+
+    // Scores that decayed to almost nothing are forgotten
+    peers.record_outcome_pure(other_address, PeerOutcome::Consolidated, 2_000);
+    assert_eq!(peers.score_pure(&address, 2_000), None);
+    assert_eq!(peers.score_pure(&other_address, 2_000), Some(1.0));
+}
+
+#[test]
+fn p2p_peers_shared_are_fresh_and_unknown_to_the_requester() {
+    let address = |i: u8| SocketAddr::new(IpAddr::V4(Ipv4Addr::new(10, 0, 0, i)), 21337);
+    let requester = address(200);
+    let record = |i: u8, last_seen: i64| PeerRecord {
+        address: address(i),
+        last_seen,
+        source: None,
+        iced: false,
+        own: false,
+    };
+
+    // Peers 1 to 20 are fresh, 21 to 30 were last seen too long ago
+    let mut records: Vec<PeerRecord> = (1..=20).map(|i| record(i, 1_000)).collect();
+    records.extend((21..=30).map(|i| record(i, 899)));
+    records.push(PeerRecord {
+        source: Some(requester),
+        ..record(31, 1_000)
+    });
+    records.push(PeerRecord {
+        iced: true,
+        ..record(32, 1_000)
+    });
+    records.push(PeerRecord {
+        own: true,
+        ..record(33, 1_000)
+    });
+    records.push(record(200, 1_000));
+
+    let mut rng = rand::thread_rng();
+    let mut counts = HashMap::new();
+    for _ in 0..1_000 {
+        let shared = select_peers_to_share(&mut rng, records.clone(), &requester, 900, 5);
+        assert_eq!(shared.len(), 5);
+        for peer in shared {
+            *counts.entry(peer).or_insert(0) += 1;
+        }
+    }
+
+    // Every fresh peer is shared at some point, and nothing else ever is
+    let mut shared: Vec<SocketAddr> = counts.keys().copied().collect();
+    shared.sort();
+    assert_eq!(shared, (1..=20).map(address).collect::<Vec<_>>());
+
+    // Without enough qualifying peers, all of them are shared
+    let shared = select_peers_to_share(&mut rng, records, &requester, 900, 50);
+    assert_eq!(shared.len(), 20);
+}
+
+#[test]
+fn p2p_peers_closed_sessions_refresh_their_address() {
+    let address = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 1)), 8080);
+    let requester = SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, 2)), 8080);
+    let mut peers = peers_with_tried(&[address]);
+    let index = peers.tried_bucket_index(&address);
+    peers.tried_bucket.get_mut(&index).unwrap().timestamp = 0;
+    assert_eq!(peers.get_peers_to_share(&requester, 3600, 10), vec![]);
+
+    peers.record_outcome(
+        address,
+        PeerOutcome::Disconnected {
+            reason: DisconnectReason::Closed,
+            uptime: 3600,
+        },
+    );
+    assert_eq!(
+        peers.get_peers_to_share(&requester, 3600, 10),
+        vec![address]
+    );
+    assert_eq!(peers.get_peers_to_share(&address, 3600, 10), vec![]);
+}
+
+#[test]
+fn p2p_peers_repeated_decode_errors_are_penalized_harder() {
+    let penalty = |errors| PeerOutcome::DecodeError { errors }.score_delta();
+
+    assert_eq!(penalty(1), -2.0);
+    assert!(penalty(3) < penalty(1));
+    // The penalty of a single outcome is bounded
+    assert_eq!(penalty(1000), penalty(MAX_DECODE_ERRORS_PENALIZED));
+}
@@ -0,0 +1,208 @@
+use std::{
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    time::Duration,
+};
+
+use witnet_p2p::sessions::{rotation::*, *};
+
+const NOW: i64 = 1_000_000;
+const HOUR: i64 = 60 * 60;
+
+fn address(last_byte: u8) -> SocketAddr {
+    SocketAddr::new(IpAddr::V4(Ipv4Addr::new(127, 0, 0, last_byte)), 21337)
+}
+
+fn params() -> RotationParams {
+    RotationParams {
+        min_lifetime: HOUR,
+        min_outbound: 2,
+    }
+}
+
+/// Register and consolidate an outbound session with the given statistics
+fn register_scored_session(
+    sessions: &mut Sessions<String>,
+    address: SocketAddr,
+    connected_at: i64,
+    activity: &[SessionActivity],
+) {
+    sessions
+        .register_session(SessionType::Outbound, address, address.to_string())
+        .unwrap();
+    sessions
+        .consolidate_session(SessionType::Outbound, address)
+        .unwrap();
+
+    sessions.outbound_rotation.register(address, connected_at);
+    for activity in activity {
+        sessions.outbound_rotation.record(address, *activity);
+    }
+}
+
+/// Sessions with one fast and useful peer, one slow peer that never delivered anything, one
+/// slower peer that is too young to be rotated and one peer of unknown latency
+fn scored_sessions() -> Sessions<String> {
+    let mut sessions = Sessions::default();
+    sessions.set_limits(8, 8);
+    register_scored_session(
+        &mut sessions,
+        address(1),
+        NOW - 10 * HOUR,
+        &[
+            SessionActivity::ResponseTime(Duration::from_millis(50)),
+            SessionActivity::Delivered(20),
+        ],
+    );
+    register_scored_session(
+        &mut sessions,
+        address(2),
+        NOW - 10 * HOUR,
+        &[SessionActivity::ResponseTime(Duration::from_secs(3))],
+    );
+    register_scored_session(
+        &mut sessions,
+        address(3),
+        NOW - 10 * 60,
+        &[SessionActivity::ResponseTime(Duration::from_secs(10))],
+    );
+    register_scored_session(
+        &mut sessions,
+        address(4),
+        NOW - 10 * HOUR,
+        &[SessionActivity::Delivered(1)],
+    );
+
+    sessions
+}
+
+#[test]
+fn p2p_rotation_stats_score() {
+    let mut stats = OutboundSessionStats::new(NOW - 2 * HOUR);
+    let unknown = stats.score(NOW);
+
+    // Fast responses and delivered items improve the score
+    stats.record(SessionActivity::ResponseTime(Duration::from_millis(100)));
+    let fast = stats.score(NOW);
+    assert!(fast > unknown);
+    stats.record(SessionActivity::Delivered(3));
+    assert!(stats.score(NOW) > fast);
+
+    // Response times are smoothed
+    stats.record(SessionActivity::ResponseTime(Duration::from_millis(1100)));
+    assert_eq!(stats.response_time_ms, Some(400.0));
+
+    // Uptime improves the score, up to a day
+    assert!(stats.score(NOW + HOUR) > stats.score(NOW));
+    assert_eq!(
+        stats.score(NOW + 30 * 24 * HOUR),
+        stats.score(NOW + 60 * 24 * HOUR)
+    );
+}
+
+#[test]
+fn p2p_rotation_stats_pending_writes() {
+    let mut stats = OutboundSessionStats::new(NOW - 2 * HOUR);
+    let score = stats.score(NOW);
+
+    stats.record(SessionActivity::PendingWrites(4096));
+    stats.record(SessionActivity::PendingWrites(1024));
+
+    assert_eq!(stats.pending_write_bytes, 1024);
+    assert_eq!(stats.score(NOW), score);
+}
+
+#[test]
+fn p2p_rotation_tick_selects_worst_scoring_session() {
+    let mut sessions = scored_sessions();
+
+    let actions = sessions.rotate_outbound(NOW, &params());
+
+    // Peer 3 is the slowest but it is younger than the minimum lifetime
+    assert_eq!(
+        actions,
+        vec![RotationAction::Disconnect(address(2)), RotationAction::Dial]
+    );
+}
+
+#[test]
+fn p2p_rotation_tick_respects_min_lifetime() {
+    let mut sessions = scored_sessions();
+    let params = RotationParams {
+        min_lifetime: 20 * HOUR,
+        ..params()
+    };
+
+    assert_eq!(sessions.rotate_outbound(NOW, &params), vec![]);
+}
+
+#[test]
+fn p2p_rotation_tick_respects_min_outbound() {
+    let mut sessions = scored_sessions();
+    let params = RotationParams {
+        min_outbound: 4,
+        ..params()
+    };
+    assert_eq!(sessions.rotate_outbound(NOW, &params), vec![]);
+
+    let params = RotationParams {
+        min_outbound: 3,
+        ..params
+    };
+    assert_eq!(
+        sessions.rotate_outbound(NOW, &params),
+        vec![RotationAction::Disconnect(address(2)), RotationAction::Dial]
+    );
+}
+
+#[test]
+fn p2p_rotation_tick_decays_usefulness() {
+    let mut sessions = scored_sessions();
+
+    sessions.rotate_outbound(NOW, &params());
+    assert_eq!(
+        sessions
+            .outbound_rotation
+            .stats(&address(1))
+            .unwrap()
+            .delivered,
+        10
+    );
+    sessions.rotate_outbound(NOW, &params());
+    assert_eq!(
+        sessions
+            .outbound_rotation
+            .stats(&address(1))
+            .unwrap()
+            .delivered,
+        5
+    );
+}
+
+#[test]
+fn p2p_rotation_tracks_consolidated_outbound_sessions() {
+    let mut sessions = Sessions::<String>::default();
+    let outbound = address(1);
+    let inbound = address(2);
+
+    sessions
+        .register_session(SessionType::Outbound, outbound, outbound.to_string())
+        .unwrap();
+    sessions
+        .register_session(SessionType::Inbound, inbound, inbound.to_string())
+        .unwrap();
+    assert!(sessions.outbound_rotation.stats(&outbound).is_none());
+
+    sessions
+        .consolidate_session(SessionType::Outbound, outbound)
+        .unwrap();
+    sessions
+        .consolidate_session(SessionType::Inbound, inbound)
+        .unwrap();
+    assert!(sessions.outbound_rotation.stats(&outbound).is_some());
+    assert!(sessions.outbound_rotation.stats(&inbound).is_none());
+
+    sessions
+        .unregister_session(SessionType::Outbound, SessionStatus::Consolidated, outbound)
+        .unwrap();
+    assert!(sessions.outbound_rotation.stats(&outbound).is_none());
+}
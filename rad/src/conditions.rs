@@ -1,5 +1,6 @@
 use std::{cmp::Ordering, convert::TryFrom};
 
+use serde::Serialize;
 use witnet_data_structures::{
     chain::{tapi::ActiveWips, RADTally},
     radon_error::RadonError,
@@ -147,10 +148,8 @@ pub fn evaluate_tally_precondition_clause(
                             Err(RadError::ModeTie { values, max_count })
                         }
                     }
-                    Err(e) => panic!(
-                        "Unexpected error when applying filter_mode on array of errors: {}",
-                        e
-                    ),
+                    // E.g. `RadError::Overflow` if the error counter wrapped around in a tie
+                    Err(e) => Err(e),
                 }
             }
             // Majority of values, compute and filter liars
@@ -231,6 +230,7 @@ pub fn construct_report_from_clause_result(
     clause_result: Result<TallyPreconditionClauseResult, RadError>,
     script: &RADTally,
     reports_len: usize,
+    settings: RadonScriptExecutionSettings,
     active_wips: &ActiveWips,
 ) -> RadonReport<RadonTypes> {
     // This TallyMetadata would be included in case of Error Result, in that case,
@@ -254,7 +254,7 @@ pub fn construct_report_from_clause_result(
                 script,
                 Some(liars),
                 Some(errors),
-                RadonScriptExecutionSettings::all_but_partial_results(),
+                settings,
                 active_wips,
             ) {
                 (Ok(x), _) => x,
@@ -325,6 +325,101 @@ pub fn radon_report_from_error(rad_error: RadError, num_reveals: usize) -> Radon
     )
 }
 
+/// Run the tally stage of a data request exactly as consensus does: the precondition clause, the
+/// tally script and, if enabled, the postcondition clause.
+///
+/// Consensus runs the tally script with `RadonScriptExecutionSettings::all_but_partial_results()`,
+/// other settings only change the metadata collected in the report.
+pub fn run_tally_with_conditions(
+    results: Vec<RadonReport<RadonTypes>>,
+    tally: &RADTally,
+    non_error_min: f64,
+    commits_count: usize,
+    settings: RadonScriptExecutionSettings,
+    active_wips: &ActiveWips,
+) -> RadonReport<RadonTypes> {
+    let results_len = results.len();
+    let clause_result =
+        evaluate_tally_precondition_clause(results, non_error_min, commits_count, active_wips);
+    let mut report = construct_report_from_clause_result(
+        clause_result,
+        tally,
+        results_len,
+        settings,
+        active_wips,
+    );
+    if active_wips.wips_0009_0011_0012() {
+        report = evaluate_tally_postcondition_clause(report, non_error_min, commits_count);
+    }
+    if active_wips.wip0018() {
+        // If the result of a tally transaction is RadonError::UnhandledIntercept, this will
+        // remove the message field, as specified in WIP0018.
+        report
+            .result
+            .remove_message_from_error_unhandled_intercept();
+    }
+
+    report
+}
+
+/// How the tally of a data request classified a reveal, which decides whether its witness is
+/// rewarded or penalized.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RevealClassification {
+    /// In consensus, so the witness is rewarded.
+    Honest,
+    /// A value out of consensus, so the collateral of the witness is slashed.
+    Liar,
+    /// An error out of consensus, so the collateral of the witness is refunded.
+    Error,
+}
+
+/// Classify the reveals of a data request according to the metadata of its tally report, as the
+/// tally transaction does when rewarding and penalizing witnesses.
+pub fn classify_reveals(report: &RadonReport<RadonTypes>) -> Vec<RevealClassification> {
+    match &report.context.stage {
+        Stage::Tally(metadata) => metadata
+            .liars
+            .iter()
+            .zip(metadata.errors.iter())
+            .map(|(liar, error)| match (liar, error) {
+                (true, true) => RevealClassification::Error,
+                (true, false) => RevealClassification::Liar,
+                (false, _) => RevealClassification::Honest,
+            })
+            .collect(),
+        _ => vec![],
+    }
+}
+
+/// Run the tally of the given reveals as consensus does, assuming that every witness that
+/// committed also revealed. Returns the tally report along with the classification of each
+/// reveal.
+pub fn run_tally_and_classify(
+    reveals: Vec<RadonTypes>,
+    tally: &RADTally,
+    non_error_min: f64,
+    active_wips: &ActiveWips,
+) -> (RadonReport<RadonTypes>, Vec<RevealClassification>) {
+    let commits_count = reveals.len();
+    let results = reveals
+        .into_iter()
+        .map(|reveal| RadonReport::from_result(Ok(reveal), &ReportContext::default()))
+        .collect();
+    let report = run_tally_with_conditions(
+        results,
+        tally,
+        non_error_min,
+        commits_count,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        active_wips,
+    );
+    let classifications = classify_reveals(&report);
+
+    (report, classifications)
+}
+
 /// An histogram-like counter that helps counting occurrences of different numeric categories.
 struct Counter {
     /// Tracks the position inside `values` of the category that appears the most.
@@ -375,7 +470,68 @@ impl Counter {
 
 #[cfg(test)]
 mod tests {
+    use witnet_data_structures::chain::{tapi::current_active_wips, RADFilter};
+
     use super::*;
+    use crate::{
+        filters::RadonFilters,
+        reducers::RadonReducers,
+        types::{float::RadonFloat, string::RadonString},
+    };
+
+    /// Mean of the reveals within one standard deviation
+    fn deviation_tally() -> RADTally {
+        RADTally {
+            filters: vec![RADFilter {
+                op: RadonFilters::DeviationStandard as u32,
+                args: vec![249, 60, 0],
+            }],
+            reducer: RadonReducers::AverageMean as u32,
+        }
+    }
+
+    fn floats(values: &[f64]) -> Vec<RadonTypes> {
+        values
+            .iter()
+            .map(|value| RadonFloat::from(*value).into())
+            .collect()
+    }
+
+    #[test]
+    fn test_classify_reveals_at_deviation_boundary() {
+        // The mean is 12.0 and the standard deviation is 2.0008, so 14.0 is just inside the
+        // filter and 14.1 is just outside
+        let reveals = floats(&[10.0, 10.0, 10.0, 13.9, 14.0, 14.1]);
+
+        let (report, classifications) =
+            run_tally_and_classify(reveals, &deviation_tally(), 0.51, &current_active_wips());
+
+        use RevealClassification::*;
+        assert_eq!(
+            classifications,
+            vec![Honest, Honest, Honest, Honest, Honest, Liar]
+        );
+        match report.result {
+            RadonTypes::Float(mean) => assert!((mean.value() - 11.58).abs() < 1e-9),
+            other => panic!("Expected a float, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_classify_error_reveals() {
+        let mut reveals = floats(&[10.0, 10.0, 10.0]);
+        reveals.push(RadonString::from("not a number").into());
+        reveals.push(RadonTypes::RadonError(
+            RadonError::try_from(RadError::HttpStatus { status_code: 404 }).unwrap(),
+        ));
+
+        let (report, classifications) =
+            run_tally_and_classify(reveals, &deviation_tally(), 0.51, &current_active_wips());
+
+        use RevealClassification::*;
+        assert_eq!(classifications, vec![Honest, Honest, Honest, Liar, Error]);
+        assert_eq!(report.result, RadonFloat::from(10.0).into());
+    }
 
     #[test]
     fn test_counter() {
@@ -0,0 +1,404 @@
+//! Static estimation of how costly it is for witnesses to execute the RADON scripts of a data
+//! request, so that request authors know whether their scripts are cheap or expensive, and so that
+//! requests can be priced proportionally to their complexity.
+//!
+//! Costs are abstract units. Every call costs a base amount, plus an amount per item of the
+//! collection and per byte of the value it is applied to, as given by the `CostTable`. Subscripts
+//! of `ArrayFilter`, `ArrayMap` and `ArraySort` are charged once per item.
+//!
+//! Nothing is retrieved: the sizes of the values are assumptions taken from `InputSizes`. Every
+//! data source responds with `response_bytes` bytes, every parsed or nested collection has
+//! `collection_items` items, and the bytes of a collection are evenly split among its items.
+use std::convert::TryFrom;
+
+use serde::{Deserialize, Serialize};
+use serde_cbor::Value;
+use witnet_data_structures::chain::{tapi::all_wips_active, RADRequest, RADType};
+
+use crate::{
+    error::RadError,
+    filters::RadonFilters,
+    operators::RadonOpCodes,
+    reducers::RadonReducers,
+    script::{
+        create_radon_script_from_filters_and_reducer, unpack_radon_script, unpack_subscript,
+        RadonCall,
+    },
+};
+
+/// Cost table used by `estimate_cost`.
+pub const LATEST_COST_TABLE: CostTable = CostTable::V1;
+
+/// Size of the values produced by `RNG` retrievals and by `BytesHash`.
+const DIGEST_BYTES: u64 = 32;
+
+/// Size of numbers and booleans.
+const SCALAR_BYTES: u64 = 8;
+
+/// Cost of applying an operator, filter or reducer to a value.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct UnitCost {
+    /// Cost of every application
+    pub base: u64,
+    /// Cost per item of the collection it is applied to
+    pub per_element: u64,
+    /// Cost per byte of the value it is applied to
+    pub per_byte: u64,
+}
+
+impl UnitCost {
+    const fn new(base: u64, per_element: u64, per_byte: u64) -> Self {
+        Self {
+            base,
+            per_element,
+            per_byte,
+        }
+    }
+
+    fn apply(&self, value: Shape) -> u64 {
+        self.base
+            .saturating_add(self.per_element.saturating_mul(value.elements))
+            .saturating_add(self.per_byte.saturating_mul(value.bytes))
+    }
+}
+
+/// Assumed sizes of the values that the scripts of a data request are applied to.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct InputSizes {
+    /// Bytes of the response of every HTTP data source
+    pub response_bytes: u64,
+    /// Items of every array or map obtained by parsing a response or by getting it from another
+    /// collection
+    pub collection_items: u64,
+    /// Reveals that the tally stage is applied to, i.e. the witnesses of the request
+    pub reveals: u64,
+}
+
+impl Default for InputSizes {
+    fn default() -> Self {
+        Self {
+            response_bytes: 1024,
+            collection_items: 10,
+            reveals: 10,
+        }
+    }
+}
+
+/// Cost of a data request, by stage.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct CostBreakdown {
+    /// Version of the cost table
+    pub version: u32,
+    /// Cost of every retrieval, including its script
+    pub retrieve: Vec<u64>,
+    /// Cost of the aggregation stage
+    pub aggregate: u64,
+    /// Cost of the tally stage
+    pub tally: u64,
+    /// Sum of the costs of all the stages
+    pub total: u64,
+}
+
+/// Estimate the cost of a data request with the latest cost table.
+pub fn estimate_cost(request: &RADRequest, sizes: &InputSizes) -> Result<CostBreakdown, RadError> {
+    LATEST_COST_TABLE.estimate(request, sizes)
+}
+
+/// Size of a value, as far as the cost of operating on it is concerned.
+#[derive(Clone, Copy, Debug)]
+struct Shape {
+    /// Items, if the value is a collection
+    elements: u64,
+    bytes: u64,
+}
+
+impl Shape {
+    const SCALAR: Shape = Shape {
+        elements: 0,
+        bytes: SCALAR_BYTES,
+    };
+
+    /// A value without items, such as a string, of `bytes` bytes.
+    fn flat(bytes: u64) -> Self {
+        Self { elements: 0, bytes }
+    }
+
+    /// Shape of the items of this collection, which are assumed to be collections too if `nested`.
+    fn item(self, sizes: &InputSizes, nested: bool) -> Self {
+        Self {
+            elements: if nested { sizes.collection_items } else { 0 },
+            bytes: self.bytes / self.elements.max(1),
+        }
+    }
+}
+
+/// Versions of the table of costs of every RADON operation.
+///
+/// Costs may become consensus-critical, so a released version must never change: new costs go
+/// into a new version.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum CostTable {
+    V1,
+}
+
+impl CostTable {
+    /// The cost table with the given version number, if there is one.
+    pub fn from_version(version: u32) -> Option<Self> {
+        match version {
+            1 => Some(CostTable::V1),
+            _ => None,
+        }
+    }
+
+    /// Version number of this cost table.
+    pub fn version(self) -> u32 {
+        match self {
+            CostTable::V1 => 1,
+        }
+    }
+
+    /// Cost of a retrieval, applied to its response.
+    pub fn retrieval(self, kind: &RADType) -> UnitCost {
+        match self {
+            CostTable::V1 => match kind {
+                RADType::HttpGet | RADType::HttpPost => UnitCost::new(100, 0, 1),
+                RADType::Rng => UnitCost::new(10, 0, 0),
+                RADType::Unknown => UnitCost::new(0, 0, 0),
+            },
+        }
+    }
+
+    /// Cost of an operator, not including its subscript, filter or reducer.
+    pub fn operator(self, op: RadonOpCodes) -> UnitCost {
+        use RadonOpCodes::*;
+
+        match self {
+            CostTable::V1 => match op {
+                Fail | Identity => UnitCost::new(1, 0, 0),
+                ArrayCount => UnitCost::new(1, 0, 0),
+                ArrayFilter | ArrayMap => UnitCost::new(5, 1, 0),
+                ArrayGetArray | ArrayGetBoolean | ArrayGetBytes | ArrayGetFloat
+                | ArrayGetInteger | ArrayGetMap | ArrayGetString => UnitCost::new(2, 0, 0),
+                ArrayGetPath | MapGetPath => UnitCost::new(5, 0, 0),
+                ArrayJoin => UnitCost::new(2, 1, 1),
+                ArrayReduce => UnitCost::new(5, 0, 0),
+                ArraySort => UnitCost::new(5, 4, 0),
+                BooleanAsString | BooleanNegate => UnitCost::new(1, 0, 0),
+                BytesAsString | BytesSlice => UnitCost::new(1, 0, 1),
+                BytesHash => UnitCost::new(10, 0, 2),
+                BytesLength => UnitCost::new(1, 0, 0),
+                BytesAsInteger => UnitCost::new(2, 0, 0),
+                IntegerAbsolute | IntegerAsFloat | IntegerGreaterThan | IntegerLessThan
+                | IntegerModulo | IntegerMultiply | IntegerNegate => UnitCost::new(1, 0, 0),
+                IntegerAsString => UnitCost::new(2, 0, 0),
+                IntegerPower => UnitCost::new(5, 0, 0),
+                FloatAbsolute | FloatCeiling | FloatGreaterThan | FloatFloor | FloatLessThan
+                | FloatModulo | FloatMultiply | FloatNegate | FloatRound | FloatTruncate
+                | FloatEquals | FloatAsInteger => UnitCost::new(1, 0, 0),
+                FloatAsString => UnitCost::new(2, 0, 0),
+                FloatPower => UnitCost::new(5, 0, 0),
+                MapGetArray | MapGetBoolean | MapGetBytes | MapGetFloat | MapGetInteger
+                | MapGetMap | MapGetString => UnitCost::new(2, 0, 0),
+                MapKeys | MapValues | MapPick | MapRename => UnitCost::new(2, 1, 0),
+                StringAsBoolean | StringAsFloat | StringAsInteger => UnitCost::new(2, 0, 1),
+                StringLength => UnitCost::new(1, 0, 0),
+                StringMatch => UnitCost::new(2, 0, 1),
+                StringParseJSONArray | StringParseJSONMap => UnitCost::new(10, 0, 4),
+                StringParseXMLMap => UnitCost::new(20, 0, 8),
+                StringToLowerCase | StringToUpperCase => UnitCost::new(1, 0, 1),
+                StringExtract => UnitCost::new(20, 0, 4),
+            },
+        }
+    }
+
+    /// Cost of a filter applied by `ArrayFilter` or by the aggregation and tally stages.
+    pub fn filter(self, filter: RadonFilters) -> UnitCost {
+        match self {
+            CostTable::V1 => match filter {
+                RadonFilters::DeviationStandard => UnitCost::new(5, 3, 0),
+                RadonFilters::Mode => UnitCost::new(5, 2, 0),
+                // Not implemented, so they fail right away
+                _ => UnitCost::new(1, 0, 0),
+            },
+        }
+    }
+
+    /// Cost of a reducer applied by `ArrayReduce` or by the aggregation and tally stages.
+    pub fn reducer(self, reducer: RadonReducers) -> UnitCost {
+        match self {
+            CostTable::V1 => match reducer {
+                RadonReducers::Mode => UnitCost::new(5, 2, 0),
+                RadonReducers::AverageMean => UnitCost::new(5, 1, 0),
+                RadonReducers::AverageMedian => UnitCost::new(5, 4, 0),
+                RadonReducers::DeviationStandard => UnitCost::new(5, 2, 0),
+                RadonReducers::HashConcatenate => UnitCost::new(10, 1, 2),
+                RadonReducers::AverageMeanTrimmed => UnitCost::new(5, 4, 0),
+                // Not implemented, so they fail right away
+                _ => UnitCost::new(1, 0, 0),
+            },
+        }
+    }
+
+    /// Estimate the cost of a data request with this cost table.
+    ///
+    /// Fails if any of its scripts cannot be decoded, or if the aggregation or tally stages use
+    /// filters or reducers that are not allowed there. Calls with wrong arguments are only charged
+    /// their base cost, as their execution fails right away.
+    pub fn estimate(
+        self,
+        request: &RADRequest,
+        sizes: &InputSizes,
+    ) -> Result<CostBreakdown, RadError> {
+        let active_wips = all_wips_active();
+
+        let mut retrieve = Vec::with_capacity(request.retrieve.len());
+        let mut sources_bytes = 0u64;
+        for retrieval in &request.retrieve {
+            let response = match retrieval.kind {
+                RADType::Rng => Shape::flat(DIGEST_BYTES),
+                _ => Shape::flat(sizes.response_bytes),
+            };
+            let script = if retrieval.script.is_empty() {
+                vec![]
+            } else {
+                unpack_radon_script(&retrieval.script)?
+            };
+            let (script_cost, source) = self.script_cost(&script, response, sizes)?;
+            retrieve.push(
+                self.retrieval(&retrieval.kind)
+                    .apply(response)
+                    .saturating_add(script_cost),
+            );
+            sources_bytes = sources_bytes.saturating_add(source.bytes);
+        }
+
+        let sources = Shape {
+            elements: u64::try_from(request.retrieve.len()).unwrap_or(u64::MAX),
+            bytes: sources_bytes,
+        };
+        let aggregate_script = create_radon_script_from_filters_and_reducer(
+            &request.aggregate.filters,
+            request.aggregate.reducer,
+            &active_wips,
+        )?;
+        let (aggregate, aggregated) = self.script_cost(&aggregate_script, sources, sizes)?;
+
+        let reveals = Shape {
+            elements: sizes.reveals,
+            bytes: aggregated.bytes.saturating_mul(sizes.reveals),
+        };
+        let tally_script = create_radon_script_from_filters_and_reducer(
+            &request.tally.filters,
+            request.tally.reducer,
+            &active_wips,
+        )?;
+        let (tally, _) = self.script_cost(&tally_script, reveals, sizes)?;
+
+        let total = retrieve
+            .iter()
+            .fold(aggregate.saturating_add(tally), |total, cost| {
+                total.saturating_add(*cost)
+            });
+
+        Ok(CostBreakdown {
+            version: self.version(),
+            retrieve,
+            aggregate,
+            tally,
+            total,
+        })
+    }
+
+    /// Cost of a script applied to a value, and the shape of its result.
+    fn script_cost(
+        self,
+        script: &[RadonCall],
+        input: Shape,
+        sizes: &InputSizes,
+    ) -> Result<(u64, Shape), RadError> {
+        script
+            .iter()
+            .try_fold((0u64, input), |(cost, value), call| {
+                let (call_cost, output) = self.call_cost(call, value, sizes)?;
+
+                Ok((cost.saturating_add(call_cost), output))
+            })
+    }
+
+    /// Cost of a call applied to a value, and the shape of its result.
+    fn call_cost(
+        self,
+        (op, args): &RadonCall,
+        input: Shape,
+        sizes: &InputSizes,
+    ) -> Result<(u64, Shape), RadError> {
+        use RadonOpCodes::*;
+
+        let first_arg = args.as_ref().and_then(|args| args.first());
+        let (extra_cost, output) =
+            match op {
+                ArrayFilter => match first_arg {
+                    Some(Value::Integer(code)) => {
+                        let cost = u8::try_from(*code)
+                            .ok()
+                            .and_then(|code| RadonFilters::try_from(code).ok())
+                            .map_or(0, |filter| self.filter(filter).apply(input));
+
+                        (cost, input)
+                    }
+                    Some(subscript) => (self.subscript_cost(subscript, input, sizes)?, input),
+                    None => (0, input),
+                },
+                ArrayMap | ArraySort => match first_arg {
+                    Some(subscript) => (self.subscript_cost(subscript, input, sizes)?, input),
+                    None => (0, input),
+                },
+                ArrayReduce => {
+                    let cost = match first_arg {
+                        Some(Value::Integer(code)) => u8::try_from(*code)
+                            .ok()
+                            .and_then(|code| RadonReducers::try_from(code).ok())
+                            .map_or(0, |reducer| self.reducer(reducer).apply(input)),
+                        _ => 0,
+                    };
+
+                    (cost, input.item(sizes, false))
+                }
+                ArrayGetArray | ArrayGetMap | ArrayGetPath | MapGetArray | MapGetMap
+                | MapGetPath => (0, input.item(sizes, true)),
+                ArrayGetBoolean | ArrayGetBytes | ArrayGetFloat | ArrayGetInteger
+                | ArrayGetString | MapGetBoolean | MapGetBytes | MapGetFloat | MapGetInteger
+                | MapGetString => (0, input.item(sizes, false)),
+                StringParseJSONArray | StringParseJSONMap | StringParseXMLMap => (
+                    0,
+                    Shape {
+                        elements: sizes.collection_items,
+                        bytes: input.bytes,
+                    },
+                ),
+                Fail | Identity | MapKeys | MapValues | MapPick | MapRename => (0, input),
+                ArrayJoin | BytesAsString | BytesSlice | StringExtract | StringToLowerCase
+                | StringToUpperCase => (0, Shape::flat(input.bytes)),
+                BytesHash => (0, Shape::flat(DIGEST_BYTES)),
+                _ => (0, Shape::SCALAR),
+            };
+
+        Ok((
+            self.operator(*op).apply(input).saturating_add(extra_cost),
+            output,
+        ))
+    }
+
+    /// Cost of applying a subscript to every item of a collection.
+    fn subscript_cost(
+        self,
+        subscript: &Value,
+        collection: Shape,
+        sizes: &InputSizes,
+    ) -> Result<u64, RadError> {
+        let subscript = unpack_subscript(subscript)?;
+        let (cost, _) = self.script_cost(&subscript, collection.item(sizes, true), sizes)?;
+
+        Ok(cost.saturating_mul(collection.elements))
+    }
+}
@@ -3,13 +3,14 @@
 use std::convert::TryFrom;
 
 use failure::{self, Fail};
-use serde::{Serialize, Serializer};
+use serde::{ser::SerializeMap, Serialize, Serializer};
 use serde_cbor::value::Value as SerdeCborValue;
+use serde_json::{json, Map as JsonMap, Value as JsonValue};
 
 use witnet_data_structures::radon_error::{ErrorLike, RadonError, RadonErrors};
 
-use crate::types::RadonTypes;
-use crate::{operators::RadonOpCodes, types::array::RadonArray};
+use crate::types::{bounded::items_to_string, RadonTypes};
+use crate::{operators::RadonOpCodes, request_json::value_to_json, types::array::RadonArray};
 
 /// RAD errors.
 #[derive(Clone, Debug, Fail, PartialEq)]
@@ -38,6 +39,13 @@ pub enum RadError {
         description
     )]
     JsonParse { description: String },
+    /// A JSON number given by a user is integral but too large for it to be exact, so its value
+    /// may differ from the one written
+    #[fail(
+        display = "JSON number {} is too large to be converted without losing precision",
+        number
+    )]
+    JsonPrecisionLoss { number: String },
     /// Failed to parse an object from a XML buffer
     #[fail(
         display = "Failed to parse an object from a XML buffer: {:?}",
@@ -53,6 +61,42 @@ pub enum RadError {
     /// The given key is not present in a RadonMap
     #[fail(display = "Failed to get key `{}` from RadonMap", key)]
     MapKeyNotFound { key: String },
+    /// A key in the path given to `ArrayGetPath` or `MapGetPath` is not present
+    ///
+    /// The path is not part of the encoded RADON error, so it is lost when decoding it.
+    #[fail(display = "Failed to get key `{}` after path `{}`", key, path)]
+    PathKeyNotFound { path: String, key: String },
+    /// An index in the path given to `ArrayGetPath` or `MapGetPath` is out of bounds
+    ///
+    /// The path is not part of the encoded RADON error, so it is lost when decoding it.
+    #[fail(
+        display = "Failed to get item at index `{}` after path `{}`",
+        index, path
+    )]
+    PathIndexOutOfBounds { path: String, index: i32 },
+    /// The value found when following the path given to `ArrayGetPath` or `MapGetPath` is not of
+    /// the expected type
+    #[fail(
+        display = "Expected {} after path `{}`, found {}",
+        expected, path, found
+    )]
+    PathTypeMismatch {
+        path: String,
+        expected: &'static str,
+        found: &'static str,
+    },
+    /// Two keys of the input of `MapRename` would be given the same name
+    #[fail(
+        display = "More than one key of RadonMap would be renamed to `{}`",
+        key
+    )]
+    MapKeyCollision { key: String },
+    /// An element of the input of `ArrayJoin` is of a type that cannot be joined into a string
+    #[fail(
+        display = "Element {} of RadonArray cannot be joined into a string, found {}",
+        index, found
+    )]
+    ArrayJoinTypeMismatch { index: usize, found: String },
     /// The given subscript does not return RadonBoolean in an ArrayFilter
     #[fail(
         display = "ArrayFilter subscript output was not RadonBoolean (was `{}`)",
@@ -107,18 +151,18 @@ pub enum RadError {
     },
     /// The given reducer is not implemented for the type of the input Array
     #[fail(
-        display = "Reducer `{}` is not implemented for Array `{:?}`",
+        display = "Reducer `{}` is not implemented for Array `{}`",
         reducer, array
     )]
     UnsupportedReducer { array: RadonArray, reducer: String },
     /// The given filter is not implemented for the type of the input Array
     #[fail(
-        display = "Filter `{}` is not implemented for Array `{:?}`",
+        display = "Filter `{}` is not implemented for Array `{}`",
         filter, array
     )]
     UnsupportedFilter { array: RadonArray, filter: String },
     /// The sort operator is not implemented for non-string arrays
-    #[fail(display = "ArraySort is not supported for RadonArray `{:?}`", array)]
+    #[fail(display = "ArraySort is not supported for RadonArray `{}`", array)]
     UnsupportedSortOp { array: RadonArray },
     /// The operator is not implemented for non-homogeneous arrays
     #[fail(
@@ -143,7 +187,7 @@ pub enum RadError {
     UnsupportedReducerInAT { operator: u8 },
     /// There was a tie after applying the mode reducer
     #[fail(
-        display = "There was a tie after applying the mode reducer on values: `{:?}`",
+        display = "There was a tie after applying the mode reducer on values: `{}`",
         values
     )]
     ModeTie { values: RadonArray, max_count: u16 },
@@ -188,8 +232,79 @@ pub enum RadError {
     )]
     ParseBool { message: String },
     /// Overflow error
-    #[fail(display = "Overflow error")]
-    Overflow,
+    ///
+    /// The operator and operands are not part of the encoded RADON error, so they are lost when
+    /// decoding it.
+    #[fail(
+        display = "Overflow error in `{}` with operands `{:?}`",
+        operator, operands
+    )]
+    Overflow {
+        operator: String,
+        operands: Vec<SerdeCborValue>,
+    },
+    /// A float operator produced an infinite or NaN result
+    ///
+    /// The operator and result are not part of the encoded RADON error, so they are lost when
+    /// decoding it.
+    #[fail(display = "`{}` produced a non-finite float: {}", operator, result)]
+    NonFiniteFloat { operator: String, result: String },
+    /// A float that is NaN was converted into an integer
+    ///
+    /// The operator is not part of the encoded RADON error, so it is lost when decoding it.
+    #[fail(display = "`{}` cannot convert NaN into an integer", operator)]
+    FloatIsNaN { operator: String },
+    /// A float that is positive or negative infinity was converted into an integer
+    ///
+    /// The operator and value are not part of the encoded RADON error, so they are lost when
+    /// decoding it.
+    #[fail(display = "`{}` cannot convert {} into an integer", operator, value)]
+    FloatIsInfinite { operator: String, value: String },
+    /// A power operator was given a base and exponent whose power is not a finite real number,
+    /// such as a negative base with a fractional exponent, or zero with a negative exponent
+    ///
+    /// The operator, base and exponent are not part of the encoded RADON error, so they are lost
+    /// when decoding it.
+    #[fail(
+        display = "`{}` of {} to the power of {} is not a finite real number",
+        operator, base, exponent
+    )]
+    UndefinedPower {
+        operator: String,
+        base: String,
+        exponent: String,
+    },
+    /// A power operator was given an exponent larger in magnitude than allowed
+    ///
+    /// The operator, exponent and limit are not part of the encoded RADON error, so they are lost
+    /// when decoding it.
+    #[fail(
+        display = "`{}` exponent {} is out of range, its magnitude cannot exceed {}",
+        operator, exponent, max
+    )]
+    ExponentOutOfRange {
+        operator: String,
+        exponent: String,
+        max: u32,
+    },
+    /// A regular expression given as operator argument cannot be compiled
+    ///
+    /// The pattern and message are not part of the encoded RADON error, so they are lost when
+    /// decoding it.
+    #[fail(display = "Invalid regular expression `{}`: {}", pattern, message)]
+    InvalidRegex { pattern: String, message: String },
+    /// A regular expression given as operator argument exceeds the complexity limits
+    #[fail(
+        display = "Regular expression of {} bytes exceeds the complexity limits",
+        length
+    )]
+    RegexTooComplex { length: usize },
+    /// A string does not match a regular expression, or the requested capture group did not
+    /// participate in the match
+    ///
+    /// The pattern is not part of the encoded RADON error, so it is lost when decoding it.
+    #[fail(display = "No match for regular expression `{}`", pattern)]
+    RegexNoMatch { pattern: String },
     /// Mismatching types
     #[fail(
         display = "Mismatching types in {}. Expected: {}, found: {}",
@@ -200,6 +315,9 @@ pub enum RadError {
         expected: &'static str,
         found: &'static str,
     },
+    /// The result of a reducer is not an integer, and its return policy forbids rounding it
+    #[fail(display = "Result of {} is not an integer: {}", reducer, value)]
+    FractionalResult { reducer: String, value: f64 },
     /// Arrays to be reduced have different sizes
     #[fail(
         display = "Arrays to be reduced in {} have different sizes. {} != {}",
@@ -233,6 +351,39 @@ pub enum RadError {
     /// Timeout during retrieval phase
     #[fail(display = "Timeout during retrieval phase")]
     RetrieveTimeout,
+    /// A placeholder in the URL or headers of a source refers to a secret that this node does not
+    /// have. The name of the secret is lost when encoding this error.
+    #[fail(display = "No value for retrieval secret `{}` in this node", name)]
+    MissingSecret { name: String },
+    /// A placeholder in the URL or headers of a source refers to a secret that this node does not
+    /// send to the host of the source. It is encoded as `MissingSecret`, as far as the data request
+    /// is concerned there is no secret for it.
+    #[fail(
+        display = "Retrieval secret `{}` cannot be sent to host `{}`",
+        name, host
+    )]
+    SecretHostNotAllowed { name: String, host: String },
+    /// A placeholder in the URL or headers of a source refers to a variable that does not exist.
+    ///
+    /// It has no RADON error code, so it cannot be encoded, as data requests that refer to unknown
+    /// variables are rejected when they are validated, before they are ever retrieved.
+    #[fail(display = "Unknown retrieval variable `{}`", name)]
+    UnknownRetrievalVariable { name: String },
+    /// The body of the HTTP response is larger than the limit of this node. `size` is the one
+    /// announced by the response, or the bytes received until the download was aborted. Sizes are
+    /// lost when encoding this error, as the limit depends on the configuration of each node.
+    #[fail(
+        display = "HTTP response of at least {} bytes is larger than the limit of {} bytes",
+        size, limit
+    )]
+    HttpResponseTooLarge { limit: usize, size: usize },
+    /// The content type of the HTTP response does not match the kind of the retrieval, e.g. binary
+    /// content for a retrieval that expects text. The content type is lost when encoding this error.
+    #[fail(
+        display = "HTTP response has content type `{}`, which is not text",
+        content_type
+    )]
+    HttpContentTypeMismatch { content_type: String },
     /// Invalid script
     #[fail(
         display = "CBOR value cannot be translated into a proper RADON script: {:?}",
@@ -433,7 +584,38 @@ impl RadError {
         Ok(RadonError::new(match kind {
             RadonErrors::RequestTooManySources => RadError::RequestTooManySources,
             RadonErrors::ScriptTooManyCalls => RadError::ScriptTooManyCalls,
-            RadonErrors::Overflow => RadError::Overflow,
+            RadonErrors::Overflow => RadError::Overflow {
+                operator: String::new(),
+                operands: vec![],
+            },
+            RadonErrors::NonFiniteFloat => RadError::NonFiniteFloat {
+                operator: String::new(),
+                result: String::new(),
+            },
+            RadonErrors::FloatIsNaN => RadError::FloatIsNaN {
+                operator: String::new(),
+            },
+            RadonErrors::FloatIsInfinite => RadError::FloatIsInfinite {
+                operator: String::new(),
+                value: String::new(),
+            },
+            RadonErrors::UndefinedPower => RadError::UndefinedPower {
+                operator: String::new(),
+                base: String::new(),
+                exponent: String::new(),
+            },
+            RadonErrors::ExponentOutOfRange => RadError::ExponentOutOfRange {
+                operator: String::new(),
+                exponent: String::new(),
+                max: 0,
+            },
+            RadonErrors::InvalidRegex => RadError::InvalidRegex {
+                pattern: String::new(),
+                message: String::new(),
+            },
+            RadonErrors::RegexNoMatch => RadError::RegexNoMatch {
+                pattern: String::new(),
+            },
             RadonErrors::InsufficientCommits => RadError::InsufficientCommits,
             RadonErrors::NoReveals => RadError::NoReveals,
             RadonErrors::SourceScriptNotCBOR => RadError::SourceScriptNotCBOR,
@@ -442,6 +624,15 @@ impl RadError {
             RadonErrors::Underflow => RadError::Underflow,
             RadonErrors::DivisionByZero => RadError::DivisionByZero,
             RadonErrors::RetrieveTimeout => RadError::RetrieveTimeout,
+            RadonErrors::MissingSecret => RadError::MissingSecret {
+                name: String::new(),
+            },
+            RadonErrors::HTTPResponseTooLarge => {
+                RadError::HttpResponseTooLarge { limit: 0, size: 0 }
+            }
+            RadonErrors::HTTPContentTypeMismatch => RadError::HttpContentTypeMismatch {
+                content_type: String::new(),
+            },
             RadonErrors::MalformedReveal => RadError::MalformedReveal,
             RadonErrors::EncodeReveal => RadError::EncodeReveal,
             RadonErrors::ArrayIndexOutOfBounds => {
@@ -452,6 +643,18 @@ impl RadError {
                 let (key,) = deserialize_args(error_args)?;
                 RadError::MapKeyNotFound { key }
             }
+            RadonErrors::MapKeyCollision => {
+                let (key,) = deserialize_args(error_args)?;
+                RadError::MapKeyCollision { key }
+            }
+            RadonErrors::ArrayJoinTypeMismatch => {
+                let (index, found) = deserialize_args(error_args)?;
+                RadError::ArrayJoinTypeMismatch { index, found }
+            }
+            RadonErrors::JsonPrecisionLoss => {
+                let (number,) = deserialize_args(error_args)?;
+                RadError::JsonPrecisionLoss { number }
+            }
             RadonErrors::UnsupportedOperator => {
                 let (input_type, operator, args) = deserialize_args(error_args)?;
                 RadError::UnsupportedOperator {
@@ -528,8 +731,15 @@ impl RadError {
                 };
                 Some(serialize_args((message,))?)
             }
-            RadError::ArrayIndexOutOfBounds { index } => Some(serialize_args((index,))?),
-            RadError::MapKeyNotFound { key } => Some(serialize_args((key,))?),
+            RadError::ArrayIndexOutOfBounds { index }
+            | RadError::PathIndexOutOfBounds { index, .. } => Some(serialize_args((index,))?),
+            RadError::MapKeyNotFound { key }
+            | RadError::PathKeyNotFound { key, .. }
+            | RadError::MapKeyCollision { key } => Some(serialize_args((key,))?),
+            RadError::ArrayJoinTypeMismatch { index, found } => {
+                Some(serialize_args((index, found))?)
+            }
+            RadError::JsonPrecisionLoss { number } => Some(serialize_args((number,))?),
             RadError::UnhandledIntercept { inner, message } => {
                 let message = match (inner, message) {
                     // Only serialize the message
@@ -564,7 +774,9 @@ impl RadError {
                 // This can only happen if `serialize_args` is called with a non-tuple argument
                 // For example:
                 // `serialize_args(x)` is invalid, it should be `serialize_args((x,))`
-                panic!("Args should be an array, is {:?}", value);
+                return Err(RadError::EncodeRadonErrorArguments {
+                    error_args: format!("Args should be an array, is {:?}", value),
+                });
             }
         }
 
@@ -582,11 +794,21 @@ impl RadError {
             RadError::UnsupportedOperator { .. } => RadonErrors::UnsupportedOperator,
             RadError::HttpStatus { .. } => RadonErrors::HTTPError,
             RadError::Underflow => RadonErrors::Underflow,
-            RadError::Overflow => RadonErrors::Overflow,
+            RadError::Overflow { .. } => RadonErrors::Overflow,
+            RadError::NonFiniteFloat { .. } => RadonErrors::NonFiniteFloat,
+            RadError::FloatIsNaN { .. } => RadonErrors::FloatIsNaN,
+            RadError::FloatIsInfinite { .. } => RadonErrors::FloatIsInfinite,
+            RadError::UndefinedPower { .. } => RadonErrors::UndefinedPower,
+            RadError::ExponentOutOfRange { .. } => RadonErrors::ExponentOutOfRange,
             RadError::DivisionByZero => RadonErrors::DivisionByZero,
             RadError::InsufficientCommits => RadonErrors::InsufficientCommits,
             RadError::NoReveals => RadonErrors::NoReveals,
             RadError::RetrieveTimeout => RadonErrors::RetrieveTimeout,
+            RadError::MissingSecret { .. } | RadError::SecretHostNotAllowed { .. } => {
+                RadonErrors::MissingSecret
+            }
+            RadError::HttpResponseTooLarge { .. } => RadonErrors::HTTPResponseTooLarge,
+            RadError::HttpContentTypeMismatch { .. } => RadonErrors::HTTPContentTypeMismatch,
             RadError::InsufficientConsensus { .. } => RadonErrors::InsufficientConsensus,
             RadError::TallyExecution { .. } => RadonErrors::TallyExecution,
             RadError::UnhandledIntercept { .. } | RadError::UnhandledInterceptV2 { .. } => {
@@ -594,8 +816,19 @@ impl RadError {
             }
             RadError::MalformedReveal => RadonErrors::MalformedReveal,
             RadError::EncodeReveal => RadonErrors::EncodeReveal,
-            RadError::ArrayIndexOutOfBounds { .. } => RadonErrors::ArrayIndexOutOfBounds,
-            RadError::MapKeyNotFound { .. } => RadonErrors::MapKeyNotFound,
+            RadError::ArrayIndexOutOfBounds { .. } | RadError::PathIndexOutOfBounds { .. } => {
+                RadonErrors::ArrayIndexOutOfBounds
+            }
+            RadError::MapKeyNotFound { .. } | RadError::PathKeyNotFound { .. } => {
+                RadonErrors::MapKeyNotFound
+            }
+            RadError::InvalidRegex { .. } | RadError::RegexTooComplex { .. } => {
+                RadonErrors::InvalidRegex
+            }
+            RadError::RegexNoMatch { .. } => RadonErrors::RegexNoMatch,
+            RadError::MapKeyCollision { .. } => RadonErrors::MapKeyCollision,
+            RadError::ArrayJoinTypeMismatch { .. } => RadonErrors::ArrayJoinTypeMismatch,
+            RadError::JsonPrecisionLoss { .. } => RadonErrors::JsonPrecisionLoss,
             // The `InconsistentSource` error is mapped here for the sake of backwards
             // compatibility. Namely, to enable paranoid retrieval without having to immediately
             // introduce a breaking change that may jeopardize oracle queries. The point of making
@@ -610,6 +843,344 @@ impl RadError {
         })
     }
 
+    /// Stable, machine-readable name of the kind of error, which API clients can rely on instead
+    /// of the `Display` message. The names of all the kinds are pinned by the
+    /// `rad_error_kinds.json` fixture, so new kinds must also be added there.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            RadError::Unknown => "unknown",
+            RadError::Decode { .. } => "decode",
+            RadError::Encode { .. } => "encode",
+            RadError::Hash => "hash",
+            RadError::JsonParse { .. } => "json_parse",
+            RadError::JsonPrecisionLoss { .. } => "json_precision_loss",
+            RadError::XmlParse { .. } => "xml_parse",
+            RadError::XmlParseOverflow => "xml_parse_overflow",
+            RadError::ArrayIndexOutOfBounds { .. } => "array_index_out_of_bounds",
+            RadError::MapKeyNotFound { .. } => "map_key_not_found",
+            RadError::PathKeyNotFound { .. } => "path_key_not_found",
+            RadError::PathIndexOutOfBounds { .. } => "path_index_out_of_bounds",
+            RadError::PathTypeMismatch { .. } => "path_type_mismatch",
+            RadError::MapKeyCollision { .. } => "map_key_collision",
+            RadError::ArrayJoinTypeMismatch { .. } => "array_join_type_mismatch",
+            RadError::ArrayFilterWrongSubscript { .. } => "array_filter_wrong_subscript",
+            RadError::BufferIsNotValue { .. } => "buffer_is_not_value",
+            RadError::NoOperatorInCompoundCall => "no_operator_in_compound_call",
+            RadError::NotIntegerOperator => "not_integer_operator",
+            RadError::NotNaturalOperator { .. } => "not_natural_operator",
+            RadError::ScriptNotArray { .. } => "script_not_array",
+            RadError::UnknownOperator { .. } => "unknown_operator",
+            RadError::UnknownFilter { .. } => "unknown_filter",
+            RadError::UnknownReducer { .. } => "unknown_reducer",
+            RadError::UnknownRetrieval => "unknown_retrieval",
+            RadError::UnsupportedHashFunction { .. } => "unsupported_hash_function",
+            RadError::UnsupportedOperator { .. } => "unsupported_operator",
+            RadError::UnsupportedReducer { .. } => "unsupported_reducer",
+            RadError::UnsupportedFilter { .. } => "unsupported_filter",
+            RadError::UnsupportedSortOp { .. } => "unsupported_sort_op",
+            RadError::UnsupportedOpNonHomogeneous { .. } => "unsupported_op_non_homogeneous",
+            RadError::UnsupportedOperatorInTally { .. } => "unsupported_operator_in_tally",
+            RadError::UnsupportedFilterInAT { .. } => "unsupported_filter_in_aggregation_or_tally",
+            RadError::UnsupportedReducerInAT { .. } => {
+                "unsupported_reducer_in_aggregation_or_tally"
+            }
+            RadError::ModeTie { .. } => "mode_tie",
+            RadError::EmptyArray => "empty_array",
+            RadError::WrongArguments { .. } => "wrong_arguments",
+            RadError::HttpStatus { .. } => "http_status",
+            RadError::HttpOther { .. } => "http_other",
+            RadError::ParseFloat { .. } => "parse_float",
+            RadError::ParseInt { .. } => "parse_int",
+            RadError::ParseBool { .. } => "parse_bool",
+            RadError::Overflow { .. } => "overflow",
+            RadError::NonFiniteFloat { .. } => "non_finite_float",
+            RadError::FloatIsNaN { .. } => "float_is_nan",
+            RadError::FloatIsInfinite { .. } => "float_is_infinite",
+            RadError::UndefinedPower { .. } => "undefined_power",
+            RadError::ExponentOutOfRange { .. } => "exponent_out_of_range",
+            RadError::InvalidRegex { .. } => "invalid_regex",
+            RadError::RegexTooComplex { .. } => "regex_too_complex",
+            RadError::RegexNoMatch { .. } => "regex_no_match",
+            RadError::MismatchingTypes { .. } => "mismatching_types",
+            RadError::FractionalResult { .. } => "fractional_result",
+            RadError::DifferentSizeArrays { .. } => "different_size_arrays",
+            RadError::BadSubscriptFormat { .. } => "bad_subscript_format",
+            RadError::Subscript { .. } => "subscript",
+            RadError::UrlParseError { .. } => "url_parse_error",
+            RadError::RetrieveTimeout => "retrieve_timeout",
+            RadError::MissingSecret { .. } => "missing_secret",
+            RadError::SecretHostNotAllowed { .. } => "secret_host_not_allowed",
+            RadError::UnknownRetrievalVariable { .. } => "unknown_retrieval_variable",
+            RadError::HttpResponseTooLarge { .. } => "http_response_too_large",
+            RadError::HttpContentTypeMismatch { .. } => "http_content_type_mismatch",
+            RadError::InvalidScript { .. } => "invalid_script",
+            RadError::EncodeRadonErrorArguments { .. } => "encode_radon_error_arguments",
+            RadError::DecodeRadonErrorNotArray { .. } => "decode_radon_error_not_array",
+            RadError::DecodeRadonErrorEmptyArray => "decode_radon_error_empty_array",
+            RadError::DecodeRadonErrorBadCode { .. } => "decode_radon_error_bad_code",
+            RadError::DecodeRadonErrorUnknownCode { .. } => "decode_radon_error_unknown_code",
+            RadError::DecodeRadonErrorMissingArguments => "decode_radon_error_missing_arguments",
+            RadError::DecodeRadonErrorWrongArguments { .. } => "decode_radon_error_wrong_arguments",
+            RadError::DecodeRadonErrorArgumentsRadonTypesFail { .. } => {
+                "decode_radon_error_arguments_radon_types_fail"
+            }
+            RadError::InsufficientCommits => "insufficient_commits",
+            RadError::NoReveals => "no_reveals",
+            RadError::InsufficientConsensus { .. } => "insufficient_consensus",
+            RadError::RequestTooManySources => "request_too_many_sources",
+            RadError::ScriptTooManyCalls => "script_too_many_calls",
+            RadError::SourceScriptNotCBOR => "source_script_not_cbor",
+            RadError::SourceScriptNotArray => "source_script_not_array",
+            RadError::SourceScriptNotRADON => "source_script_not_radon",
+            RadError::Underflow => "underflow",
+            RadError::DivisionByZero => "division_by_zero",
+            RadError::EncodeRadonErrorUnknownCode => "encode_radon_error_unknown_code",
+            RadError::TallyExecution { .. } => "tally_execution",
+            RadError::UnhandledIntercept { .. } => "unhandled_intercept",
+            RadError::UnhandledInterceptV2 { .. } => "unhandled_intercept_v2",
+            RadError::MalformedReveal => "malformed_reveal",
+            RadError::EncodeReveal => "encode_reveal",
+            RadError::InvalidHttpHeader { .. } => "invalid_http_header",
+            RadError::InconsistentSource => "inconsistent_source",
+        }
+    }
+
+    /// Fields of the error, named as in its variant. RADON arrays are rendered as in `Display`,
+    /// CBOR values as in the canonical JSON representation of data requests, and other values
+    /// that are not plain numbers or strings as strings.
+    fn fields(&self) -> Vec<(&'static str, JsonValue)> {
+        match self {
+            RadError::Decode { from, to } | RadError::Encode { from, to } => {
+                vec![("from", json!(from)), ("to", json!(to))]
+            }
+            RadError::JsonParse { description }
+            | RadError::XmlParse { description }
+            | RadError::BufferIsNotValue { description } => {
+                vec![("description", json!(description))]
+            }
+            RadError::ArrayIndexOutOfBounds { index } => vec![("index", json!(index))],
+            RadError::MapKeyNotFound { key } => vec![("key", json!(key))],
+            RadError::PathKeyNotFound { path, key } => {
+                vec![("path", json!(path)), ("key", json!(key))]
+            }
+            RadError::PathIndexOutOfBounds { path, index } => {
+                vec![("path", json!(path)), ("index", json!(index))]
+            }
+            RadError::PathTypeMismatch {
+                path,
+                expected,
+                found,
+            } => vec![
+                ("path", json!(path)),
+                ("expected", json!(expected)),
+                ("found", json!(found)),
+            ],
+            RadError::MapKeyCollision { key } => vec![("key", json!(key))],
+            RadError::JsonPrecisionLoss { number } => vec![("number", json!(number))],
+            RadError::ArrayJoinTypeMismatch { index, found } => {
+                vec![("index", json!(index)), ("found", json!(found))]
+            }
+            RadError::ArrayFilterWrongSubscript { value } => vec![("value", json!(value))],
+            RadError::NotNaturalOperator { code }
+            | RadError::UnknownOperator { code }
+            | RadError::UnknownFilter { code }
+            | RadError::UnknownReducer { code } => vec![("code", integer_to_json(*code))],
+            RadError::ScriptNotArray { input_type } => vec![("input_type", json!(input_type))],
+            RadError::UnsupportedHashFunction { function } => vec![("function", json!(function))],
+            RadError::UnsupportedOperator {
+                input_type,
+                operator,
+                args,
+            } => vec![
+                ("input_type", json!(input_type)),
+                ("operator", json!(operator)),
+                (
+                    "args",
+                    args.as_deref().map_or(JsonValue::Null, cbor_values_to_json),
+                ),
+            ],
+            RadError::UnsupportedReducer { array, reducer } => {
+                vec![("array", array_to_json(array)), ("reducer", json!(reducer))]
+            }
+            RadError::UnsupportedFilter { array, filter } => {
+                vec![("array", array_to_json(array)), ("filter", json!(filter))]
+            }
+            RadError::UnsupportedSortOp { array } => vec![("array", array_to_json(array))],
+            RadError::UnsupportedOpNonHomogeneous { operator } => {
+                vec![("operator", json!(operator))]
+            }
+            RadError::UnsupportedOperatorInTally { operator } => {
+                vec![("operator", json!(format!("{:?}", operator)))]
+            }
+            RadError::UnsupportedFilterInAT { operator }
+            | RadError::UnsupportedReducerInAT { operator } => {
+                vec![("operator", json!(operator))]
+            }
+            RadError::ModeTie { values, max_count } => vec![
+                ("values", array_to_json(values)),
+                ("max_count", json!(max_count)),
+            ],
+            RadError::WrongArguments {
+                input_type,
+                operator,
+                args,
+            } => vec![
+                ("input_type", json!(input_type)),
+                ("operator", json!(operator)),
+                ("args", cbor_values_to_json(args)),
+            ],
+            RadError::HttpStatus { status_code } => vec![("status_code", json!(status_code))],
+            RadError::HttpOther { message }
+            | RadError::ParseFloat { message }
+            | RadError::ParseInt { message }
+            | RadError::ParseBool { message } => vec![("message", json!(message))],
+            RadError::Overflow { operator, operands } => vec![
+                ("operator", json!(operator)),
+                ("operands", cbor_values_to_json(operands)),
+            ],
+            RadError::NonFiniteFloat { operator, result } => {
+                vec![("operator", json!(operator)), ("result", json!(result))]
+            }
+            RadError::FloatIsNaN { operator } => vec![("operator", json!(operator))],
+            RadError::FloatIsInfinite { operator, value } => {
+                vec![("operator", json!(operator)), ("value", json!(value))]
+            }
+            RadError::UndefinedPower {
+                operator,
+                base,
+                exponent,
+            } => vec![
+                ("operator", json!(operator)),
+                ("base", json!(base)),
+                ("exponent", json!(exponent)),
+            ],
+            RadError::ExponentOutOfRange {
+                operator,
+                exponent,
+                max,
+            } => vec![
+                ("operator", json!(operator)),
+                ("exponent", json!(exponent)),
+                ("max", json!(max)),
+            ],
+            RadError::InvalidRegex { pattern, message } => {
+                vec![("pattern", json!(pattern)), ("message", json!(message))]
+            }
+            RadError::RegexTooComplex { length } => vec![("length", json!(length))],
+            RadError::RegexNoMatch { pattern } => vec![("pattern", json!(pattern))],
+            RadError::MismatchingTypes {
+                method,
+                expected,
+                found,
+            } => vec![
+                ("method", json!(method)),
+                ("expected", json!(expected)),
+                ("found", json!(found)),
+            ],
+            RadError::FractionalResult { reducer, value } => {
+                vec![("reducer", json!(reducer)), ("value", json!(value))]
+            }
+            RadError::DifferentSizeArrays {
+                method,
+                first,
+                second,
+            } => vec![
+                ("method", json!(method)),
+                ("first", json!(first)),
+                ("second", json!(second)),
+            ],
+            RadError::BadSubscriptFormat { value } | RadError::InvalidScript { value } => {
+                vec![("value", cbor_to_json(value))]
+            }
+            RadError::Subscript {
+                input_type,
+                operator,
+                inner,
+            } => vec![
+                ("input_type", json!(input_type)),
+                ("operator", json!(operator)),
+                ("inner", json!(inner)),
+            ],
+            RadError::UrlParseError { inner, url } => {
+                vec![("inner", json!(inner.to_string())), ("url", json!(url))]
+            }
+            RadError::MissingSecret { name } | RadError::UnknownRetrievalVariable { name } => {
+                vec![("name", json!(name))]
+            }
+            RadError::SecretHostNotAllowed { name, host } => {
+                vec![("name", json!(name)), ("host", json!(host))]
+            }
+            RadError::HttpResponseTooLarge { limit, size } => {
+                vec![("limit", json!(limit)), ("size", json!(size))]
+            }
+            RadError::HttpContentTypeMismatch { content_type } => {
+                vec![("content_type", json!(content_type))]
+            }
+            RadError::EncodeRadonErrorArguments { error_args } => {
+                vec![("error_args", json!(error_args))]
+            }
+            RadError::DecodeRadonErrorNotArray { actual_type }
+            | RadError::DecodeRadonErrorBadCode { actual_type } => {
+                vec![("actual_type", json!(actual_type))]
+            }
+            RadError::DecodeRadonErrorUnknownCode { error_code } => {
+                vec![("error_code", json!(error_code))]
+            }
+            RadError::DecodeRadonErrorWrongArguments { arguments, message } => vec![
+                (
+                    "arguments",
+                    arguments.as_ref().map_or(JsonValue::Null, cbor_to_json),
+                ),
+                ("message", json!(message)),
+            ],
+            RadError::DecodeRadonErrorArgumentsRadonTypesFail { arguments, message } => vec![
+                (
+                    "arguments",
+                    arguments
+                        .as_deref()
+                        .map_or(JsonValue::Null, cbor_values_to_json),
+                ),
+                ("message", json!(message)),
+            ],
+            RadError::InsufficientConsensus { achieved, required } => {
+                vec![("achieved", json!(achieved)), ("required", json!(required))]
+            }
+            RadError::TallyExecution { inner, message }
+            | RadError::UnhandledIntercept { inner, message } => {
+                vec![("inner", json!(inner)), ("message", json!(message))]
+            }
+            RadError::UnhandledInterceptV2 { inner } => vec![("inner", json!(inner))],
+            RadError::InvalidHttpHeader { name, value, error } => vec![
+                ("name", json!(name)),
+                ("value", json!(value)),
+                ("error", json!(error)),
+            ],
+            RadError::Unknown
+            | RadError::Hash
+            | RadError::XmlParseOverflow
+            | RadError::NoOperatorInCompoundCall
+            | RadError::NotIntegerOperator
+            | RadError::UnknownRetrieval
+            | RadError::EmptyArray
+            | RadError::RetrieveTimeout
+            | RadError::DecodeRadonErrorEmptyArray
+            | RadError::DecodeRadonErrorMissingArguments
+            | RadError::InsufficientCommits
+            | RadError::NoReveals
+            | RadError::RequestTooManySources
+            | RadError::ScriptTooManyCalls
+            | RadError::SourceScriptNotCBOR
+            | RadError::SourceScriptNotArray
+            | RadError::SourceScriptNotRADON
+            | RadError::Underflow
+            | RadError::DivisionByZero
+            | RadError::EncodeRadonErrorUnknownCode
+            | RadError::MalformedReveal
+            | RadError::EncodeReveal
+            | RadError::InconsistentSource => vec![],
+        }
+    }
+
     /// Replaces the `from` field in instances of `RadError::Decode`
     pub fn replace_decode_from(self, from: &'static str) -> Self {
         match self {
@@ -640,15 +1211,53 @@ impl std::default::Default for RadError {
     }
 }
 
+/// RAD errors are serialized as their `kind`, their `Display` message and their `fields`:
+/// ```json
+/// {"kind": "http_status", "message": "HTTP GET response was an HTTP error code: 404", "fields": {"status_code": 404}}
+/// ```
+///
+/// Compatibility note: RAD errors used to be serialized as their `Display` message alone, so
+/// `RadonError` values in the JSON of execution reports went from `{"RadonError": "<message>"}`
+/// to `{"RadonError": {"kind": .., "message": "<message>", "fields": {..}}}`. Clients reading the
+/// old string can read `message` instead, which is unchanged.
 impl Serialize for RadError {
     fn serialize<S>(&self, serializer: S) -> Result<<S as Serializer>::Ok, <S as Serializer>::Error>
     where
         S: Serializer,
     {
-        serializer.serialize_str(&self.to_string())
+        let fields: JsonMap<String, JsonValue> = self
+            .fields()
+            .into_iter()
+            .map(|(name, value)| (name.to_string(), value))
+            .collect();
+
+        let mut map = serializer.serialize_map(Some(3))?;
+        map.serialize_entry("kind", self.kind())?;
+        map.serialize_entry("message", &self.to_string())?;
+        map.serialize_entry("fields", &fields)?;
+        map.end()
     }
 }
 
+/// Integers that do not fit in JSON numbers are rendered as strings.
+fn integer_to_json(integer: i128) -> JsonValue {
+    i64::try_from(integer)
+        .map(JsonValue::from)
+        .unwrap_or_else(|_| JsonValue::from(integer.to_string()))
+}
+
+fn cbor_to_json(value: &SerdeCborValue) -> JsonValue {
+    value_to_json(value).unwrap_or_else(|_| JsonValue::from(format!("{:?}", value)))
+}
+
+fn cbor_values_to_json(values: &[SerdeCborValue]) -> JsonValue {
+    JsonValue::Array(values.iter().map(cbor_to_json).collect())
+}
+
+fn array_to_json(array: &RadonArray) -> JsonValue {
+    JsonValue::from(items_to_string(array.as_slice()))
+}
+
 impl From<std::num::ParseFloatError> for RadError {
     fn from(err: std::num::ParseFloatError) -> Self {
         RadError::ParseFloat {
@@ -749,6 +1358,16 @@ mod tests {
             RadonErrors::MapKeyNotFound => RadError::MapKeyNotFound {
                 key: String::from("value"),
             },
+            RadonErrors::MapKeyCollision => RadError::MapKeyCollision {
+                key: String::from("value"),
+            },
+            RadonErrors::ArrayJoinTypeMismatch => RadError::ArrayJoinTypeMismatch {
+                index: 1,
+                found: String::from("RadonBoolean"),
+            },
+            RadonErrors::JsonPrecisionLoss => RadError::JsonPrecisionLoss {
+                number: String::from("18446744073709551616"),
+            },
             RadonErrors::UnhandledIntercept => RadError::UnhandledIntercept {
                 inner: None,
                 message: Some("Only the message field is serialized".to_string()),
@@ -882,4 +1501,221 @@ mod tests {
 
         assert_eq!(deserialized_rad_error.unwrap(), expected_rad_error);
     }
+
+    #[test]
+    fn encoded_errors_keep_the_values_that_are_not_displayed() {
+        use crate::{types::bounded::MAX_DISPLAY_ITEMS, RadonString};
+
+        let items: Vec<String> = (0..20).map(|i| format!("item {}", i)).collect();
+        let values = RadonArray::from(
+            items
+                .iter()
+                .map(|item| RadonTypes::String(RadonString::from(item.as_str())))
+                .collect::<Vec<_>>(),
+        );
+        let rad_error = RadError::UnhandledIntercept {
+            inner: Some(Box::new(RadError::ModeTie {
+                values: values.clone(),
+                max_count: 1,
+            })),
+            message: None,
+        };
+
+        // Displaying the tie only shows the first items
+        let tie = RadError::ModeTie {
+            values,
+            max_count: 1,
+        };
+        assert!(tie
+            .to_string()
+            .ends_with(&format!("…{} more])`", items.len() - MAX_DISPLAY_ITEMS)));
+
+        // But the message encoded into the RADON error still has all of them
+        let message = format!(
+            "inner: ModeTie {{ values: RadonArray {{ value: [{}], is_homogeneous: true }}, max_count: 1 }}",
+            items
+                .iter()
+                .map(|item| format!("String(RadonString {{ value: \"{}\" }})", item))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        let encoded =
+            Vec::<u8>::try_from(RadonTypes::RadonError(RadonError::new(rad_error))).unwrap();
+        // Tag 39, array of 2 items, error code 0xFF and a text string with a 2 bytes length
+        let mut expected = vec![0xD8, 0x27, 0x82, 0x18, 0xFF, 0x79];
+        expected.extend_from_slice(&u16::try_from(message.len()).unwrap().to_be_bytes());
+        expected.extend_from_slice(message.as_bytes());
+        assert_eq!(encoded, expected);
+    }
+
+    #[test]
+    fn math_errors_are_encoded_without_arguments() {
+        let overflow = RadError::Overflow {
+            operator: "IntegerMultiply".to_string(),
+            operands: vec![Value::Integer(i128::MAX), Value::Integer(2)],
+        };
+        assert_eq!(
+            overflow.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x41)]
+        );
+
+        let non_finite = RadError::NonFiniteFloat {
+            operator: "FloatPower".to_string(),
+            result: "inf".to_string(),
+        };
+        assert_eq!(
+            non_finite.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x43)]
+        );
+
+        let nan = RadError::FloatIsNaN {
+            operator: "FloatAsInteger".to_string(),
+        };
+        assert_eq!(
+            nan.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x46)]
+        );
+
+        let infinite = RadError::FloatIsInfinite {
+            operator: "FloatAsInteger".to_string(),
+            value: "-inf".to_string(),
+        };
+        assert_eq!(
+            infinite.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x47)]
+        );
+
+        let undefined = RadError::UndefinedPower {
+            operator: "FloatPower".to_string(),
+            base: "-8".to_string(),
+            exponent: "0.5".to_string(),
+        };
+        assert_eq!(
+            undefined.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x44)]
+        );
+
+        let out_of_range = RadError::ExponentOutOfRange {
+            operator: "IntegerPower".to_string(),
+            exponent: "-2000".to_string(),
+            max: 1024,
+        };
+        assert_eq!(
+            out_of_range.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x45)]
+        );
+    }
+
+    #[test]
+    fn regex_errors_are_encoded_without_arguments() {
+        let invalid = RadError::InvalidRegex {
+            pattern: "(".to_string(),
+            message: "unclosed group".to_string(),
+        };
+        assert_eq!(
+            invalid.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x21)]
+        );
+
+        let too_complex = RadError::RegexTooComplex { length: 1000 };
+        assert_eq!(
+            too_complex.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x21)]
+        );
+
+        let no_match = RadError::RegexNoMatch {
+            pattern: "a+".to_string(),
+        };
+        assert_eq!(
+            no_match.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x72)]
+        );
+    }
+
+    #[test]
+    fn missing_secret_is_encoded_without_name() {
+        let missing = RadError::MissingSecret {
+            name: "api_key:coinlayer".to_string(),
+        };
+        assert_eq!(
+            missing.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x32)]
+        );
+    }
+
+    #[test]
+    fn http_response_errors_are_encoded_without_details() {
+        let too_large = RadError::HttpResponseTooLarge {
+            limit: 1_024,
+            size: 1_025,
+        };
+        assert_eq!(
+            too_large.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x33)]
+        );
+
+        let mismatch = RadError::HttpContentTypeMismatch {
+            content_type: "image/png".to_string(),
+        };
+        assert_eq!(
+            mismatch.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x34)]
+        );
+    }
+
+    #[test]
+    fn path_errors_are_encoded_without_path() {
+        let key_not_found = RadError::PathKeyNotFound {
+            path: "data.items[0]".to_string(),
+            key: "quote".to_string(),
+        };
+        assert_eq!(
+            key_not_found.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x71), Value::Text("quote".to_string())]
+        );
+
+        let index_out_of_bounds = RadError::PathIndexOutOfBounds {
+            path: "data.items".to_string(),
+            index: 3,
+        };
+        assert_eq!(
+            index_out_of_bounds.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x70), Value::Integer(3)]
+        );
+    }
+
+    #[test]
+    fn operator_errors_are_encoded_with_the_offending_value() {
+        let collision = RadError::MapKeyCollision {
+            key: "volume".to_string(),
+        };
+        assert_eq!(
+            collision.try_into_cbor_array().unwrap(),
+            vec![Value::Integer(0x73), Value::Text("volume".to_string())]
+        );
+
+        let join_mismatch = RadError::ArrayJoinTypeMismatch {
+            index: 1,
+            found: "RadonBoolean".to_string(),
+        };
+        assert_eq!(
+            join_mismatch.try_into_cbor_array().unwrap(),
+            vec![
+                Value::Integer(0x74),
+                Value::Integer(1),
+                Value::Text("RadonBoolean".to_string())
+            ]
+        );
+
+        let precision_loss = RadError::JsonPrecisionLoss {
+            number: "18446744073709551616".to_string(),
+        };
+        assert_eq!(
+            precision_loss.try_into_cbor_array().unwrap(),
+            vec![
+                Value::Integer(0x48),
+                Value::Text("18446744073709551616".to_string())
+            ]
+        );
+    }
 }
@@ -3,11 +3,14 @@ use crate::{
     filters::RadonFilters,
     operators::array::transpose,
     reducers,
-    types::{array::RadonArray, boolean::RadonBoolean, float::RadonFloat, RadonType, RadonTypes},
+    types::{
+        array::RadonArray, boolean::RadonBoolean, float::RadonFloat, integer::RadonInteger,
+        RadonType, RadonTypes,
+    },
 };
 use serde_cbor::Value;
 use std::convert::TryFrom;
-use witnet_data_structures::radon_report::{ReportContext, Stage};
+use witnet_data_structures::radon_report::{RemovalReason, ReportContext, Stage};
 
 // FIXME: Allow for now, wait for https://github.com/rust-lang/rust/issues/67058 to reach stable
 #[allow(clippy::cast_precision_loss)]
@@ -66,20 +69,20 @@ pub fn standard_filter(
             }
             let bool_matrix = boolean_standard_filter(input, sigmas_float)?;
 
-            Ok(keep_rows(input, &bool_matrix, context))
+            keep_rows(input, &bool_matrix, context)
         }
         Some(_rad_types) => {
             // 1D array
             let bool_array = boolean_standard_filter(input, sigmas_float)?;
 
-            let bool_vec: Vec<bool> = bool_array
+            let bool_vec = bool_array
                 .value()
                 .iter()
                 .map(|b| match b {
-                    RadonTypes::Boolean(rad_bool) => !rad_bool.value(),
-                    _ => panic!("Expected RadonArray of RadonBoolean"),
+                    RadonTypes::Boolean(rad_bool) => Ok(!rad_bool.value()),
+                    other => Err(mismatching_types(RadonBoolean::radon_type_name(), other)),
                 })
-                .collect();
+                .collect::<Result<Vec<bool>, RadError>>()?;
 
             let mut result = vec![];
             for (item, &b) in input.value().into_iter().zip(bool_vec.iter()) {
@@ -88,6 +91,7 @@ pub fn standard_filter(
                 }
             }
 
+            context.record_removals(&bool_vec, RemovalReason::DeviationExceeded);
             if let Stage::Tally(ref mut metadata) = context.stage {
                 metadata.update_liars(bool_vec);
             }
@@ -103,23 +107,31 @@ fn keep_rows(
     input: &RadonArray,
     keep: &RadonArray,
     context: &mut ReportContext<RadonTypes>,
-) -> RadonTypes {
+) -> Result<RadonTypes, RadError> {
     let mut result = vec![];
     let mut bool_vec = vec![];
     for (item, keep_array) in input.value().into_iter().zip(keep.value()) {
         let keep_array = if let RadonTypes::Array(a) = keep_array {
             a
         } else {
-            panic!("Expected 2D array");
+            return Err(mismatching_types(
+                RadonArray::radon_type_name(),
+                &keep_array,
+            ));
         };
 
-        let row_true = keep_array.value().iter().all(|x| {
+        // Only 2D arrays are supported
+        let mut row_true = true;
+        for x in keep_array.value() {
             if let RadonTypes::Boolean(b) = x {
-                b.value()
+                if !b.value() {
+                    row_true = false;
+                    break;
+                }
             } else {
-                panic!("Only 2D arrays are supported");
+                return Err(mismatching_types(RadonBoolean::radon_type_name(), &x));
             }
-        });
+        }
 
         if row_true {
             result.push(item);
@@ -127,11 +139,20 @@ fn keep_rows(
         bool_vec.push(!row_true);
     }
 
+    context.record_removals(&bool_vec, RemovalReason::DeviationExceeded);
     if let Stage::Tally(ref mut metadata) = context.stage {
         metadata.update_liars(bool_vec);
     }
 
-    RadonTypes::from(RadonArray::from(result))
+    Ok(RadonTypes::from(RadonArray::from(result)))
+}
+
+fn mismatching_types(expected: &'static str, found: &RadonTypes) -> RadError {
+    RadError::MismatchingTypes {
+        method: RadonFilters::DeviationStandard.to_string(),
+        expected,
+        found: found.radon_type_name(),
+    }
 }
 
 // Return an array with the same dimensions as the input, with a boolean indicating
@@ -189,7 +210,7 @@ fn boolean_standard_filter(input: &RadonArray, sigmas_float: f64) -> Result<Rado
                 let xv = if let RadonTypes::Integer(i) = item {
                     i.value() as f64
                 } else {
-                    unreachable!()
+                    return Err(mismatching_types(RadonInteger::radon_type_name(), &item));
                 };
                 let keep = xv >= keep_min && xv <= keep_max;
                 result.push(RadonTypes::Boolean(RadonBoolean::from(keep)));
@@ -208,7 +229,7 @@ fn boolean_standard_filter(input: &RadonArray, sigmas_float: f64) -> Result<Rado
                         sigmas_float,
                     )?));
                 } else {
-                    unreachable!()
+                    return Err(mismatching_types(RadonArray::radon_type_name(), &v2mean));
                 }
             }
 
@@ -3,7 +3,7 @@ use crate::{
     reducers::mode::mode,
     types::{array::RadonArray, RadonType, RadonTypes},
 };
-use witnet_data_structures::radon_report::{ReportContext, Stage};
+use witnet_data_structures::radon_report::{RemovalReason, ReportContext, Stage};
 
 pub fn mode_filter(
     input: &RadonArray,
@@ -22,6 +22,7 @@ pub fn mode_filter(
         })
         .collect();
 
+    context.record_removals(&liars, RemovalReason::NotMode);
     if let Stage::Tally(ref mut metadata) = context.stage {
         metadata.update_liars(liars);
     }
@@ -2,7 +2,7 @@
 
 extern crate witnet_data_structures;
 
-use futures::{executor::block_on, future::join_all, AsyncReadExt};
+use futures::{executor::block_on, future::join_all};
 use serde::Serialize;
 pub use serde_cbor::{to_vec as cbor_to_vec, Value as CborValue};
 #[cfg(test)]
@@ -10,10 +10,12 @@ use witnet_data_structures::chain::tapi::all_wips_active;
 use witnet_data_structures::{
     chain::{
         tapi::{current_active_wips, ActiveWips},
-        RADAggregate, RADRequest, RADRetrieve, RADTally, RADType,
+        ConsensusConstants, RADAggregate, RADRequest, RADRetrieve, RADTally, RADType,
     },
-    radon_report::{RadonReport, ReportContext, RetrievalMetadata, Stage, TallyMetaData},
-    witnessing::WitnessingConfig,
+    radon_report::{
+        DataRequestMetadata, RadonReport, ReportContext, RetrievalMetadata, Stage, TallyMetaData,
+    },
+    witnessing::{RetrievalSecrets, WitnessingConfig},
 };
 use witnet_net::client::http::WitnetHttpClient;
 pub use witnet_net::Uri;
@@ -25,21 +27,28 @@ use crate::{
         create_radon_script_from_filters_and_reducer, execute_radon_script, unpack_radon_script,
         RadonScriptExecutionSettings,
     },
+    secrets::{substitute_secrets, substitute_url_secrets},
     types::{array::RadonArray, bytes::RadonBytes, string::RadonString, RadonTypes},
     user_agents::UserAgent,
+    variables::substitute_variables,
 };
 use core::convert::From;
 use witnet_net::client::http::{WitnetHttpBody, WitnetHttpRequest};
 
 pub mod conditions;
+pub mod cost;
 pub mod error;
 pub mod filters;
 pub mod hash_functions;
 pub mod operators;
 pub mod reducers;
+pub mod request_json;
+pub mod response;
 pub mod script;
+pub mod secrets;
 pub mod types;
 pub mod user_agents;
+pub mod variables;
 
 pub type Result<T> = std::result::Result<T, RadError>;
 
@@ -58,11 +67,30 @@ pub struct RADRequestExecutionReport {
 /// The `inputs_injection` allows for disabling the actual retrieval of the data sources and
 /// the provided strings will be fed to the retrieval scripts instead. It is therefore expected that
 /// the length of `sources_injection` matches that of `request.retrieve`.
+///
+/// The request has not been broadcast, so the variables in the URL and headers of its sources are
+/// substituted with placeholders, which the retrieval reports mark as simulated.
 pub fn try_data_request(
     request: &RADRequest,
     settings: RadonScriptExecutionSettings,
     inputs_injection: Option<&[&str]>,
     witnessing: Option<WitnessingConfig<witnet_net::Uri>>,
+) -> RADRequestExecutionReport {
+    block_on(try_data_request_async(
+        request,
+        settings,
+        inputs_injection,
+        witnessing,
+    ))
+}
+
+/// Asynchronous version of `try_data_request`, which stops retrieving the data sources as soon as
+/// it is dropped.
+pub async fn try_data_request_async(
+    request: &RADRequest,
+    settings: RadonScriptExecutionSettings,
+    inputs_injection: Option<&[&str]>,
+    witnessing: Option<WitnessingConfig<witnet_net::Uri>>,
 ) -> RADRequestExecutionReport {
     #[cfg(not(test))]
     let active_wips = current_active_wips();
@@ -70,6 +98,8 @@ pub fn try_data_request(
     let active_wips = all_wips_active();
     let mut retrieval_context =
         ReportContext::from_stage(Stage::Retrieval(RetrievalMetadata::default()));
+    retrieval_context.mode = settings.mode();
+    retrieval_context.set_data_request(DataRequestMetadata::simulated());
     let retrieve_responses = if let Some(inputs) = inputs_injection {
         assert_eq!(inputs.len(), request.retrieve.len(), "Tried to locally run a data request with a number of injected sources different than the number of retrieval paths ({} != {})", inputs.len(), request.retrieve.len());
 
@@ -82,7 +112,7 @@ pub fn try_data_request(
             })
             .collect()
     } else {
-        block_on(join_all(
+        join_all(
             request
                 .retrieve
                 .iter()
@@ -93,10 +123,12 @@ pub fn try_data_request(
                         settings,
                         active_wips.clone(),
                         witnessing.clone().unwrap_or_default(),
+                        Some(DataRequestMetadata::simulated()),
                     )
                 })
                 .collect::<Vec<_>>(),
-        ))
+        )
+        .await
     };
 
     let retrieval_reports: Vec<RadonReport<RadonTypes>> = retrieve_responses
@@ -210,19 +242,52 @@ pub fn run_retrieval_with_data(
 ) -> Result<RadonTypes> {
     let context = &mut ReportContext::from_stage(Stage::Retrieval(RetrievalMetadata::default()));
     context.set_active_wips(active_wips);
+    context.mode = settings.mode();
     run_retrieval_with_data_report(retrieve, response, context, settings)
         .map(RadonReport::into_inner)
 }
 
 /// Handle generic HTTP (GET/POST) response
+///
+/// Variables and secrets are only substituted into the request that is actually sent: errors and
+/// logs keep using `retrieve.url` and the header templates so that no secret value ever leaves
+/// this node.
 async fn http_response(
     retrieve: &RADRetrieve,
     context: &mut ReportContext<RadonTypes>,
     settings: RadonScriptExecutionSettings,
     client: Option<WitnetHttpClient>,
+    secrets: &RetrievalSecrets,
 ) -> Result<RadonReport<RadonTypes>> {
+    // Variables are only substituted after WIP0040, and secrets after WIP0041, before which their
+    // placeholders were sent as they are
+    let wip_active =
+        |wip: fn(&ActiveWips) -> bool| context.active_wips.as_ref().map(wip).unwrap_or(true);
+    let data_request = context
+        .data_request
+        .filter(|_| wip_active(ActiveWips::wip0040));
+    let secrets = Some(secrets).filter(|_| wip_active(ActiveWips::wip0041));
+    // Responses are only limited in size and content type after WIP0042
+    let max_response_size = match &context.active_wips {
+        Some(active_wips) => ConsensusConstants::max_http_response_size_with_wips(active_wips),
+        None => ConsensusConstants::max_http_response_size_with_wips(&all_wips_active()),
+    };
+    let with_variables = |template: &str| match &data_request {
+        Some(data_request) => substitute_variables(template, data_request),
+        None => template.to_string(),
+    };
+    // Secrets are only sent to the host of the URL, so the headers get those that it allows
+    let (url, host) = match secrets {
+        Some(secrets) => substitute_url_secrets(&with_variables(&retrieve.url), secrets)?,
+        None => (with_variables(&retrieve.url), None),
+    };
+    let substitute = |template: &str| match secrets {
+        Some(secrets) => substitute_secrets(&with_variables(template), secrets, host.as_deref()),
+        None => Ok(with_variables(template)),
+    };
+
     // Validate URL to make sure that we handle malformed URLs nicely before they hit any library
-    if let Err(err) = url::Url::parse(&retrieve.url) {
+    if let Err(err) = url::Url::parse(&url) {
         Err(RadError::UrlParseError {
             inner: err,
             url: retrieve.url.clone(),
@@ -248,14 +313,11 @@ async fn http_response(
     let request = WitnetHttpRequest::build(|builder| {
         // Populate the builder and generate the body for different types of retrievals
         let (builder, body) = match retrieve.kind {
-            RADType::HttpGet => (
-                builder.method("GET").uri(&retrieve.url),
-                WitnetHttpBody::empty(),
-            ),
+            RADType::HttpGet => (builder.method("GET").uri(&url), WitnetHttpBody::empty()),
             RADType::HttpPost => {
                 // Using `Vec<u8>` as the body sets the content type header to `application/octet-stream`
                 (
-                    builder.method("POST").uri(&retrieve.url),
+                    builder.method("POST").uri(&url),
                     WitnetHttpBody::from(retrieve.body.clone()),
                 )
             }
@@ -272,8 +334,15 @@ async fn http_response(
         for (name, value) in &retrieve.headers {
             // Handle invalid header names and values with a specific and friendly error message
             validate_header(name, value)?;
-
-            builder = builder.header(name, value);
+            // The substituted value is checked again, but reported using its template
+            let substituted = substitute(value)?;
+            validate_header(name, &substituted).map_err(|_| RadError::InvalidHttpHeader {
+                name: name.to_string(),
+                value: value.to_string(),
+                error: "invalid HTTP header value after substituting secrets".to_string(),
+            })?;
+
+            builder = builder.header(name, substituted);
         }
 
         // Finally attach the body to complete building the HTTP request
@@ -286,7 +355,8 @@ async fn http_response(
         .send(request)
         .await
         .map_err(|x| RadError::HttpOther {
-            message: x.to_string(),
+            // Transport errors may quote the URL, which must not reveal any secret
+            message: x.to_string().replace(&url, &retrieve.url),
         })?
         .inner();
 
@@ -296,17 +366,13 @@ async fn http_response(
         });
     }
 
-    // If at some point we want to support the retrieval of non-UTF8 data (e.g. raw bytes), this is
-    // where we need to decide how to read the response body
-    let (_parts, mut body) = response.into_parts();
-    let mut response_string = String::default();
-    body.read_to_string(&mut response_string)
-        .await
-        .map_err(|x| RadError::HttpOther {
-            message: x.to_string(),
-        })?;
+    let body = response::read_response(&retrieve.kind, response, max_response_size).await?;
+    if let Stage::Retrieval(metadata) = &mut context.stage {
+        metadata.response_size = Some(body.size);
+        metadata.content_type = body.content_type;
+    }
 
-    let result = run_retrieval_with_data_report(retrieve, &response_string, context, settings);
+    let result = run_retrieval_with_data_report(retrieve, &body.text, context, settings);
 
     match &result {
         Ok(report) => {
@@ -344,19 +410,27 @@ async fn rng_response(
 }
 
 /// Run retrieval stage of a data request, return `Result<RadonReport>`.
+///
+/// The variables in the URL and headers of the source are substituted with the metadata of
+/// `data_request`, or left as they are if there is none.
 pub async fn run_retrieval_report(
     retrieve: &RADRetrieve,
     settings: RadonScriptExecutionSettings,
     active_wips: ActiveWips,
     client: Option<WitnetHttpClient>,
+    secrets: &RetrievalSecrets,
+    data_request: Option<DataRequestMetadata>,
 ) -> Result<RadonReport<RadonTypes>> {
     let context = &mut ReportContext::from_stage(Stage::Retrieval(RetrievalMetadata::default()));
     context.set_active_wips(active_wips);
+    context.mode = settings.mode();
+    context.data_request = data_request;
 
     match retrieve.kind {
-        RADType::HttpGet => http_response(retrieve, context, settings, client).await,
+        RADType::HttpGet | RADType::HttpPost => {
+            http_response(retrieve, context, settings, client, secrets).await
+        }
         RADType::Rng => rng_response(context, settings).await,
-        RADType::HttpPost => http_response(retrieve, context, settings, client).await,
         _ => Err(RadError::UnknownRetrieval),
     }
 }
@@ -369,6 +443,8 @@ pub async fn run_retrieval(retrieve: &RADRetrieve, active_wips: ActiveWips) -> R
         RadonScriptExecutionSettings::disable_all(),
         active_wips,
         None,
+        &RetrievalSecrets::default(),
+        None,
     )
     .await
     .map(RadonReport::into_inner)
@@ -386,10 +462,19 @@ pub async fn run_paranoid_retrieval(
     settings: RadonScriptExecutionSettings,
     active_wips: ActiveWips,
     witnessing: WitnessingConfig<witnet_net::Uri>,
+    data_request: Option<DataRequestMetadata>,
 ) -> Result<RadonReport<RadonTypes>> {
     // We can skip paranoid checks for retrieval types that don't use networking (e.g. RNG)
     if !retrieve.kind.is_http() {
-        return run_retrieval_report(retrieve, settings, active_wips, None).await;
+        return run_retrieval_report(
+            retrieve,
+            settings,
+            active_wips,
+            None,
+            &witnessing.secrets,
+            data_request,
+        )
+        .await;
     }
 
     let futures: Result<Vec<_>> = witnessing
@@ -406,7 +491,14 @@ pub async fn run_paranoid_retrieval(
                     message: err.to_string(),
                 })
                 .map(|client| {
-                    run_retrieval_report(retrieve, settings, active_wips.clone(), Some(client))
+                    run_retrieval_report(
+                        retrieve,
+                        settings,
+                        active_wips.clone(),
+                        Some(client),
+                        &witnessing.secrets,
+                        data_request,
+                    )
                 })
         })
         .collect();
@@ -463,6 +555,7 @@ fn evaluate_paranoid_retrieval(
     // debugging data sources through `witnet_toolkit`), we can refactor `AggregateMetaData` and
     // avoid these tricks here.
     let mut context = ReportContext::from_stage(Stage::Tally(TallyMetaData::default()));
+    context.mode = settings.mode();
     let consensus = RADTally::from(aggregate);
     let tally = run_tally_with_context_report(values, &consensus, &mut context, settings)
         // Case 3
@@ -499,6 +592,7 @@ pub fn run_aggregation_report(
 ) -> (Result<RadonReport<RadonTypes>>, ReportContext<RadonTypes>) {
     let mut context = ReportContext::from_stage(Stage::Aggregation);
     context.set_active_wips(active_wips.clone());
+    context.mode = settings.mode();
 
     let aux =
         run_aggregation_with_context_report(radon_types_vec, aggregate, &mut context, settings);
@@ -570,6 +664,7 @@ pub fn run_tally_report(
     let mut context = ReportContext {
         stage: Stage::Tally(metadata),
         active_wips: Some(active_wips.clone()),
+        mode: settings.mode(),
         ..Default::default()
     };
 
@@ -1526,6 +1621,38 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_try_data_request_reports_simulated_data_request() {
+        let request = RADRequest {
+            time_lock: 0,
+            retrieve: vec![RADRetrieve {
+                kind: RADType::Rng,
+                url: String::from(""),
+                script: vec![128],
+                body: vec![],
+                headers: vec![],
+            }],
+            aggregate: RADAggregate {
+                filters: vec![],
+                reducer: RadonReducers::Mode as u32,
+            },
+            tally: RADTally {
+                filters: vec![],
+                reducer: RadonReducers::HashConcatenate as u32,
+            },
+        };
+        let report = try_data_request(
+            &request,
+            RadonScriptExecutionSettings::enable_all(),
+            None,
+            None,
+        );
+        let data_request = report.retrieve[0].context.data_request.unwrap();
+
+        assert!(data_request.simulated);
+        assert_eq!(data_request, DataRequestMetadata::simulated());
+    }
+
     #[test]
     fn test_try_data_request_http_post_non_ascii_header_key() {
         let script_r = Value::Array(vec![]);
@@ -1,17 +1,17 @@
 use std::{
     clone::Clone,
+    cmp::Ordering,
     convert::{TryFrom, TryInto},
-    iter,
 };
 
 use serde_cbor::value::{from_value, Value};
-use witnet_data_structures::radon_report::{RadonReport, ReportContext, Stage};
+use witnet_data_structures::radon_report::{RadonReport, RemovalReason, ReportContext, Stage};
 
 use crate::{
     error::RadError,
     filters::{self, RadonFilters},
-    operators::{string, RadonOpCodes},
-    reducers::{self, RadonReducers},
+    operators::{float as float_operators, integer as integer_operators, path, string},
+    reducers::{self, average::MeanReturnPolicy, RadonReducers},
     script::{execute_radon_script, unpack_subscript, RadonCall, RadonScriptExecutionSettings},
     types::{array::RadonArray, integer::RadonInteger, string::RadonString, RadonType, RadonTypes},
 };
@@ -31,15 +31,29 @@ pub fn reduce(
         args: args.to_vec(),
     };
 
-    if args.len() != 1 {
-        return Err(wrong_args());
-    }
-
-    let arg = args[0].to_owned();
-    let reducer_integer = from_value::<u8>(arg).map_err(|_| wrong_args())?;
+    let (reducer_arg, reducer_args) = args.split_first().ok_or_else(wrong_args)?;
+    let reducer_integer = from_value::<u8>(reducer_arg.to_owned()).map_err(|_| wrong_args())?;
     let reducer_code = RadonReducers::try_from(reducer_integer).map_err(|_| wrong_args())?;
 
-    reducers::reduce(input, reducer_code, context)
+    // After WIP-0035, every reducer validates its own arguments. Before that, the only argument
+    // that can follow the reducer code is the return policy, which is allowed after WIP-0028 and
+    // ignored by the reducers that do not use it
+    let legacy_max_args = match &context.active_wips {
+        Some(active_wips) if active_wips.wip0035() => None,
+        Some(active_wips) if active_wips.wip0028() => Some(1),
+        _ => Some(0),
+    };
+    if let Some(max_args) = legacy_max_args {
+        if reducer_args.len() > max_args {
+            return Err(wrong_args());
+        }
+        if let Some(arg) = reducer_args.first() {
+            let policy_integer = from_value::<u8>(arg.to_owned()).map_err(|_| wrong_args())?;
+            MeanReturnPolicy::try_from(policy_integer).map_err(|_| wrong_args())?;
+        }
+    }
+
+    reducers::reduce(input, reducer_code, reducer_args, context)
 }
 
 fn inner_get(input: &RadonArray, args: &[Value]) -> Result<RadonTypes, RadError> {
@@ -53,19 +67,16 @@ fn inner_get(input: &RadonArray, args: &[Value]) -> Result<RadonTypes, RadError>
         return Err(wrong_args());
     }
 
-    let not_found = |index: usize| RadError::ArrayIndexOutOfBounds {
-        index: i32::try_from(index).unwrap(),
-    };
-
     let arg = args[0].to_owned();
     let index = from_value::<i32>(arg).map_err(|_| wrong_args())?;
-    let index = usize::try_from(index).map_err(|_| RadError::ArrayIndexOutOfBounds { index })?;
+    let not_found = || RadError::ArrayIndexOutOfBounds { index };
+    let position = usize::try_from(index).map_err(|_| not_found())?;
 
     input
         .value()
-        .get(index)
+        .get(position)
         .map(Clone::clone)
-        .ok_or_else(|| not_found(index))
+        .ok_or_else(not_found)
 }
 
 /// Try to get any kind of `RadonType` from an entry in the input `RadonArray`, as specified
@@ -99,8 +110,10 @@ where
 /// This simply assumes that the element in that position is a number (i.e., `RadonFloat` or
 /// `RadonInteger`). If it is not, it will fail with a `RadError` because of `replace_separators`.
 fn get_numeric_string(input: &RadonArray, args: &[Value]) -> Result<RadonString, RadError> {
-    let item = get::<RadonString, _>(input, &args[..1])?.value();
-    let (thousands_separator, decimal_separator) = string::read_separators_from_args(&args[1..]);
+    let (index_args, separator_args) = args.split_at(args.len().min(1));
+    let item = get::<RadonString, _>(input, index_args)?.value();
+    let (thousands_separator, decimal_separator) =
+        string::read_separators_from_args(separator_args);
 
     Ok(RadonString::from(string::replace_separators(
         item,
@@ -109,6 +122,63 @@ fn get_numeric_string(input: &RadonArray, args: &[Value]) -> Result<RadonString,
     )))
 }
 
+/// Get a value nested in arrays and maps inside the input `RadonArray`, as specified by a path of
+/// indices and keys, coerced into the requested type. See `path::get_path` for the arguments.
+pub fn get_path(input: &RadonArray, args: &[Value]) -> Result<RadonTypes, RadError> {
+    path::get_path(RadonTypes::from(input.clone()), args)
+}
+
+/// Join the strings in the input `RadonArray` into a single `RadonString`, putting the separator
+/// given as the first argument between them, or nothing if it is omitted.
+///
+/// Every item must be a `RadonString`, unless the second argument is `true`, in which case
+/// integers and floats are also joined, in the same form as `IntegerAsString` and `FloatAsString`
+/// give them.
+pub fn join(input: &RadonArray, args: &[Value]) -> Result<RadonString, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonArray::radon_type_name(),
+        operator: "Join".to_string(),
+        args: args.to_vec(),
+    };
+
+    if args.len() > 2 {
+        return Err(wrong_args());
+    }
+    let separator = args
+        .first()
+        .map(|arg| from_value::<String>(arg.to_owned()))
+        .transpose()
+        .map_err(|_| wrong_args())?
+        .unwrap_or_default();
+    let convert_numbers = args
+        .get(1)
+        .map(|arg| from_value::<bool>(arg.to_owned()))
+        .transpose()
+        .map_err(|_| wrong_args())?
+        .unwrap_or(false);
+
+    let strings = input
+        .value()
+        .into_iter()
+        .enumerate()
+        .map(|(index, item)| match item {
+            RadonTypes::String(string) => Ok(string.value()),
+            RadonTypes::Integer(integer) if convert_numbers => {
+                integer_operators::to_string(integer).map(|string| string.value())
+            }
+            RadonTypes::Float(float) if convert_numbers => {
+                float_operators::to_string(float).map(|string| string.value())
+            }
+            other => Err(RadError::ArrayJoinTypeMismatch {
+                index,
+                found: other.radon_type_name().to_string(),
+            }),
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(RadonString::from(strings.join(&separator)))
+}
+
 pub fn map(
     input: &RadonArray,
     args: &[Value],
@@ -134,7 +204,8 @@ pub fn map(
     let mut reports = vec![];
     let mut results = vec![];
 
-    let settings = RadonScriptExecutionSettings::tailored_to_stage(&context.stage);
+    let settings =
+        RadonScriptExecutionSettings::tailored_to_stage(&context.stage).restricted_to(context.mode);
     for item in input.value() {
         let report = execute_radon_script(item.clone(), subscript.as_slice(), context, settings)?;
 
@@ -149,7 +220,7 @@ pub fn map(
     }
 
     // Extract the partial results from the reports and put them in the execution context if needed
-    partial_results_extract(&subscript, &reports, context);
+    partial_results_extract(&subscript, &reports, context)?;
 
     Ok(RadonArray::from(results).into())
 }
@@ -179,8 +250,12 @@ pub fn filter(
 
             let mut reports = vec![];
             let mut results = vec![];
+            // The subscript moves the index of the call, which is needed for the filter breakdown
+            let call_index = context.call_index;
+            let mut removed = vec![];
 
-            let settings = RadonScriptExecutionSettings::tailored_to_stage(&context.stage);
+            let settings = RadonScriptExecutionSettings::tailored_to_stage(&context.stage)
+                .restricted_to(context.mode);
             for item in input.value() {
                 let report =
                     execute_radon_script(item.clone(), subscript.as_slice(), context, settings)?;
@@ -195,6 +270,9 @@ pub fn filter(
                     if boolean.value() {
                         results.push(item.clone());
                     }
+                    if context.filter_breakdown.is_some() {
+                        removed.push(!boolean.value());
+                    }
                 } else {
                     return Err(RadError::ArrayFilterWrongSubscript {
                         value: report.result.to_string(),
@@ -205,7 +283,12 @@ pub fn filter(
             }
 
             // Extract the partial results from the reports and put them in the execution context if needed
-            partial_results_extract(&subscript, &reports, context);
+            partial_results_extract(&subscript, &reports, context)?;
+
+            if context.filter_breakdown.is_some() {
+                context.call_index = call_index;
+                context.record_removals(&removed, RemovalReason::OutOfRange);
+            }
 
             Ok(RadonArray::from(results).into())
         }
@@ -256,7 +339,13 @@ pub fn sort(
             }
             return Err(error.inner().clone());
         }
-        _ => unreachable!(),
+        other => {
+            return Err(RadError::MismatchingTypes {
+                method: "RadonArray::sort".to_string(),
+                expected: RadonArray::radon_type_name(),
+                found: other.radon_type_name(),
+            })
+        }
     };
 
     let mapped_array_value = mapped_array.value();
@@ -275,16 +364,26 @@ pub fn sort(
 
     // Distinguish depending the type
     match &mapped_array_value.first() {
+        // The values the array is sorted by must be homogeneous as well
+        Some(RadonTypes::String(_)) | Some(RadonTypes::Integer(_))
+            if !mapped_array.is_homogeneous() =>
+        {
+            return Err(RadError::UnsupportedOpNonHomogeneous {
+                operator: "ArraySort".to_string(),
+            })
+        }
         Some(RadonTypes::String(_)) => {
             tuple_array.sort_by(|a, b| match (a.1, b.1) {
                 (RadonTypes::String(a), RadonTypes::String(b)) => a.cmp(b),
-                _ => unreachable!(),
+                // Cannot happen, as the mapped array is homogeneous
+                _ => Ordering::Equal,
             });
         }
         Some(RadonTypes::Integer(_)) => {
             tuple_array.sort_by(|a, b| match (a.1, b.1) {
                 (RadonTypes::Integer(a), RadonTypes::Integer(b)) => a.cmp(b),
-                _ => unreachable!(),
+                // Cannot happen, as the mapped array is homogeneous
+                _ => Ordering::Equal,
             });
         }
         _ => {
@@ -303,21 +402,32 @@ fn partial_results_extract(
     subscript: &[RadonCall],
     reports: &[RadonReport<RadonTypes>],
     context: &mut ReportContext<RadonTypes>,
-) {
+) -> Result<(), RadError> {
     if let Stage::Retrieval(metadata) = &mut context.stage {
-        metadata.subscript_partial_results.push(subscript.iter().chain(iter::once(&(RadonOpCodes::Fail, None))).enumerate().map(|(index, _)|
-            reports
+        let mut subscript_partial_results = vec![];
+        // There is one partial result for the input, and then one for each of the calls
+        for index in 0..=subscript.len() {
+            // Execution reports from applying the same subscript on multiple values are expected
+            // to contain the same number of partial results
+            let partial_results = reports
                 .iter()
-                .map(|report|
-                report.partial_results
-                    .as_ref()
-                    .expect("Execution reports from applying subscripts are expected to contain partial results")
-                    .get(index)
-                    .expect("Execution reports from applying same subscript on multiple values should contain the same number of partial results")
-                    .clone()
-            ).collect::<Vec<RadonTypes>>()
-        ).collect::<Vec<Vec<RadonTypes>>>());
+                .map(|report| {
+                    report
+                        .partial_results
+                        .as_ref()
+                        .and_then(|partial_results| partial_results.get(index))
+                        .cloned()
+                        .ok_or(RadError::Unknown)
+                })
+                .collect::<Result<Vec<RadonTypes>, RadError>>()?;
+            subscript_partial_results.push(partial_results);
+        }
+        metadata
+            .subscript_partial_results
+            .push(subscript_partial_results);
     }
+
+    Ok(())
 }
 
 pub fn transpose(input: &RadonArray) -> Result<RadonArray, RadError> {
@@ -409,12 +519,13 @@ mod tests {
     use witnet_data_structures::radon_report::RetrievalMetadata;
 
     use crate::{
+        all_wips_active, current_active_wips,
         error::RadError,
         operators::{
             Operable,
             RadonOpCodes::{
-                IntegerGreaterThan, IntegerMultiply, MapGetBoolean, MapGetFloat, MapGetInteger,
-                MapGetString,
+                self, IntegerGreaterThan, IntegerMultiply, MapGetBoolean, MapGetFloat,
+                MapGetInteger, MapGetString,
             },
         },
         types::{
@@ -486,6 +597,115 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_reduce_return_policy() {
+        let input = &RadonArray::from(vec![
+            RadonInteger::from(1).into(),
+            RadonInteger::from(2).into(),
+        ]);
+        let mut context = ReportContext {
+            active_wips: Some(all_wips_active()),
+            ..Default::default()
+        };
+        let reduce_with_policy = |policy: u8, context: &mut ReportContext<RadonTypes>| {
+            let args = &[
+                Value::Integer(RadonReducers::AverageMean as i128),
+                Value::Integer(i128::from(policy)),
+            ];
+            reduce(input, args, context)
+        };
+
+        assert_eq!(
+            reduce_with_policy(0x00, &mut context),
+            Ok(RadonTypes::from(RadonInteger::from(2)))
+        );
+        assert_eq!(
+            reduce_with_policy(0x01, &mut context),
+            Ok(RadonTypes::from(RadonFloat::from(1.5)))
+        );
+        assert_eq!(
+            reduce_with_policy(0x02, &mut context),
+            Err(RadError::FractionalResult {
+                reducer: RadonReducers::AverageMean.to_string(),
+                value: 1.5,
+            })
+        );
+        assert_eq!(
+            &reduce_with_policy(0x03, &mut context)
+                .unwrap_err()
+                .to_string(),
+            "Wrong `RadonArray::Reduce()` arguments: `[Integer(3), Integer(3)]`"
+        );
+
+        // Without a policy argument, the result is rounded as before
+        let args = &[Value::Integer(RadonReducers::AverageMean as i128)];
+        assert_eq!(
+            reduce(input, args, &mut context),
+            Ok(RadonTypes::from(RadonInteger::from(2)))
+        );
+    }
+
+    #[test]
+    fn test_reduce_return_policy_before_wip0028() {
+        let input = &RadonArray::from(vec![
+            RadonInteger::from(1).into(),
+            RadonInteger::from(2).into(),
+        ]);
+        let mut context = ReportContext {
+            active_wips: Some(current_active_wips()),
+            ..Default::default()
+        };
+
+        let args = &[Value::Integer(RadonReducers::AverageMean as i128)];
+        assert_eq!(
+            reduce(input, args, &mut context),
+            Ok(RadonTypes::from(RadonInteger::from(2)))
+        );
+
+        let args = &[
+            Value::Integer(RadonReducers::AverageMean as i128),
+            Value::Integer(0x01),
+        ];
+        assert_eq!(
+            &reduce(input, args, &mut context).unwrap_err().to_string(),
+            "Wrong `RadonArray::Reduce()` arguments: `[Integer(3), Integer(1)]`"
+        );
+    }
+
+    #[test]
+    fn test_reduce_stray_arguments() {
+        let input = &RadonArray::from(vec![
+            RadonInteger::from(1).into(),
+            RadonInteger::from(2).into(),
+            RadonInteger::from(2).into(),
+        ]);
+        let args = &[
+            Value::Integer(RadonReducers::Mode as i128),
+            Value::Integer(0x01),
+        ];
+
+        // Before WIP-0035, the return policy is ignored by the reducers that do not use it
+        let mut active_wips = all_wips_active();
+        active_wips.active_wips.remove("WIP0035");
+        let mut context = ReportContext {
+            active_wips: Some(active_wips),
+            ..Default::default()
+        };
+        assert_eq!(
+            reduce(input, args, &mut context),
+            Ok(RadonTypes::from(RadonInteger::from(2)))
+        );
+
+        let mut context = ReportContext {
+            active_wips: Some(all_wips_active()),
+            ..Default::default()
+        };
+        assert_eq!(
+            &reduce(input, args, &mut context).unwrap_err().to_string(),
+            "Wrong `RadonArray::Reduce()` arguments: `[Integer(2), Integer(1)]`"
+        );
+    }
+
     #[test]
     fn test_transpose() {
         let array_1 = RadonTypes::from(RadonArray::from(vec![
@@ -682,6 +902,7 @@ mod tests {
             ]),
         ])];
         let mut context = ReportContext::from_stage(Stage::Retrieval(RetrievalMetadata::default()));
+        context.mode = witnet_data_structures::radon_report::ExecutionMode::Local;
         map(&input, &script, &mut context).unwrap();
 
         if let Stage::Retrieval(metadata) = context.stage {
@@ -814,6 +1035,7 @@ mod tests {
             ]),
         ])];
         let mut context = ReportContext::from_stage(Stage::Retrieval(RetrievalMetadata::default()));
+        context.mode = witnet_data_structures::radon_report::ExecutionMode::Local;
         filter(&input, &script, &mut context).unwrap();
 
         if let Stage::Retrieval(metadata) = context.stage {
@@ -885,6 +1107,95 @@ mod tests {
         assert_eq!(output, expected)
     }
 
+    #[test]
+    fn test_join_default_separator() {
+        let input = RadonArray::from(vec![
+            RadonString::from("Hello").into(),
+            RadonString::from("world!").into(),
+        ]);
+
+        assert_eq!(join(&input, &[]), Ok(RadonString::from("Helloworld!")));
+    }
+
+    #[test]
+    fn test_join_custom_separator() {
+        let input = RadonArray::from(vec![
+            RadonString::from("a").into(),
+            RadonString::from("b").into(),
+            RadonString::from("c").into(),
+        ]);
+        let args = [Value::Text(", ".to_string())];
+
+        assert_eq!(join(&input, &args), Ok(RadonString::from("a, b, c")));
+    }
+
+    #[test]
+    fn test_join_mixed_types() {
+        let input = RadonArray::from(vec![
+            RadonString::from("price").into(),
+            RadonInteger::from(42).into(),
+            RadonFloat::from(1.5).into(),
+        ]);
+
+        assert_eq!(
+            join(&input, &[Value::Text("=".to_string())]),
+            Err(RadError::ArrayJoinTypeMismatch {
+                index: 1,
+                found: "RadonInteger".to_string(),
+            })
+        );
+
+        let args = [Value::Text("=".to_string()), Value::Bool(true)];
+        assert_eq!(join(&input, &args), Ok(RadonString::from("price=42=1.5")));
+    }
+
+    #[test]
+    fn test_join_not_convertible_types() {
+        let input = RadonArray::from(vec![
+            RadonString::from("a").into(),
+            RadonBoolean::from(true).into(),
+        ]);
+        let args = [Value::Text("".to_string()), Value::Bool(true)];
+
+        assert_eq!(
+            join(&input, &args),
+            Err(RadError::ArrayJoinTypeMismatch {
+                index: 1,
+                found: "RadonBoolean".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn test_join_wrong_args() {
+        let input = RadonArray::from(vec![RadonString::from("a").into()]);
+
+        for args in [
+            vec![Value::Integer(1)],
+            vec![Value::Text("".to_string()), Value::Integer(1)],
+            vec![
+                Value::Text("".to_string()),
+                Value::Bool(true),
+                Value::Bool(true),
+            ],
+        ]
+        .iter()
+        {
+            assert!(matches!(
+                join(&input, args),
+                Err(RadError::WrongArguments { .. })
+            ));
+        }
+    }
+
+    #[test]
+    fn test_join_empty_array() {
+        let input = RadonArray::from(vec![]);
+        let args = [Value::Text(", ".to_string())];
+
+        assert_eq!(join(&input, &args), Ok(RadonString::from("")));
+    }
+
     #[test]
     fn test_sort_map_string_values() {
         let mut map1 = BTreeMap::new();
@@ -1211,6 +1522,26 @@ mod tests {
         assert_eq!(output, expected_err);
     }
 
+    #[test]
+    fn test_sort_by_non_homogeneous_values() {
+        // Both items are arrays, but the mode of the first one is an integer and the mode of the
+        // second one is a string
+        let input = RadonArray::from(vec![
+            RadonArray::from(vec![RadonInteger::from(1).into()]).into(),
+            RadonArray::from(vec![RadonString::from("a").into()]).into(),
+        ]);
+        let script = vec![Value::Array(vec![Value::Array(vec![
+            Value::Integer(RadonOpCodes::ArrayReduce as i128),
+            Value::Integer(RadonReducers::Mode as i128),
+        ])])];
+        let output = sort(&input, &script, &mut ReportContext::default()).unwrap_err();
+        let expected_err = RadError::UnsupportedOpNonHomogeneous {
+            operator: "ArraySort".to_string(),
+        };
+
+        assert_eq!(output, expected_err);
+    }
+
     #[test]
     fn test_sort_empty_array() {
         let input = RadonArray::from(vec![]);
@@ -1427,6 +1758,18 @@ mod tests {
         assert_eq!(output, expected_err);
     }
 
+    #[test]
+    fn test_get_number_without_arguments() {
+        let (input, _index, _item) = radon_array_of_integers();
+        let output = get_number::<RadonInteger>(&input, &[]).unwrap_err();
+        let expected_err = RadError::WrongArguments {
+            input_type: RadonArray::radon_type_name(),
+            operator: "Get".to_string(),
+            args: vec![],
+        };
+        assert_eq!(output, expected_err);
+    }
+
     #[test]
     fn test_get_float() {
         let (input, index, item) = radon_array_of_floats();
@@ -4,7 +4,7 @@ use std::convert::TryFrom;
 use crate::{
     error::RadError,
     hash_functions::{self, RadonHashFunctions},
-    types::{bytes::RadonBytes, string::RadonString, RadonType},
+    types::{bytes::RadonBytes, integer::RadonInteger, string::RadonString, RadonType},
 };
 
 pub fn to_string(input: &RadonBytes) -> Result<RadonString, RadError> {
@@ -27,6 +27,59 @@ pub fn hash(input: &RadonBytes, args: &[Value]) -> Result<RadonBytes, RadError>
 
     Ok(RadonBytes::from(digest))
 }
+
+pub fn length(input: &RadonBytes) -> RadonInteger {
+    RadonInteger::from(input.value().len() as i128)
+}
+
+/// Bytes from offset `start` up to offset `end`, or up to the last byte if there is no `end`.
+/// Offsets beyond the bounds of the input are clamped to them.
+pub fn slice(input: &RadonBytes, args: &[Value]) -> Result<RadonBytes, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonBytes::radon_type_name(),
+        operator: "Slice".to_string(),
+        args: args.to_vec(),
+    };
+
+    let offset = |arg: &Value| from_value::<i128>(arg.to_owned()).map_err(|_| wrong_args());
+    let (start, end) = match args {
+        [start] => (offset(start)?, None),
+        [start, end] => (offset(start)?, Some(offset(end)?)),
+        _ => return Err(wrong_args()),
+    };
+    if matches!(end, Some(end) if end < start) {
+        return Err(wrong_args());
+    }
+
+    let bytes = input.value();
+    let clamp = |offset: i128| {
+        usize::try_from(offset.max(0)).map_or(bytes.len(), |offset| offset.min(bytes.len()))
+    };
+    let start = clamp(start);
+    let end = end.map_or(bytes.len(), clamp);
+
+    Ok(RadonBytes::from(bytes[start..end].to_vec()))
+}
+
+/// Decode the input as a big-endian unsigned integer.
+pub fn to_integer(input: &RadonBytes) -> Result<RadonInteger, RadError> {
+    let bytes = input.value();
+    let overflow = || RadError::Overflow {
+        operator: "AsInteger".to_string(),
+        operands: vec![Value::Bytes(bytes.clone())],
+    };
+
+    let unsigned = bytes.iter().try_fold(0u128, |acc, byte| {
+        acc.checked_mul(256)
+            .map(|acc| acc + u128::from(*byte))
+            .ok_or_else(overflow)
+    })?;
+
+    i128::try_from(unsigned)
+        .map(RadonInteger::from)
+        .map_err(|_| overflow())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -64,4 +117,98 @@ mod tests {
             "Hash function `RadonHashFunctions::Fail` is not implemented"
         );
     }
+
+    #[test]
+    fn test_bytes_length() {
+        let input = RadonBytes::from(vec![0x01, 0x02, 0x03]);
+
+        assert_eq!(length(&input), RadonInteger::from(3));
+        assert_eq!(length(&RadonBytes::default()), RadonInteger::from(0));
+    }
+
+    #[test]
+    fn test_bytes_slice() {
+        let input = RadonBytes::from(vec![0x01, 0x02, 0x03, 0x04]);
+        let slice = |args: &[Value]| slice(&input, args).map(|bytes| bytes.value());
+
+        assert_eq!(slice(&[Value::from(1)]).unwrap(), vec![0x02, 0x03, 0x04]);
+        assert_eq!(
+            slice(&[Value::from(1), Value::from(3)]).unwrap(),
+            vec![0x02, 0x03]
+        );
+        assert_eq!(
+            slice(&[Value::from(2), Value::from(2)]).unwrap(),
+            Vec::<u8>::new()
+        );
+    }
+
+    #[test]
+    fn test_bytes_slice_clamps_out_of_range_offsets() {
+        let input = RadonBytes::from(vec![0x01, 0x02, 0x03, 0x04]);
+        let slice = |args: &[Value]| slice(&input, args).map(|bytes| bytes.value());
+
+        assert_eq!(
+            slice(&[Value::from(-5), Value::from(2)]).unwrap(),
+            vec![0x01, 0x02]
+        );
+        assert_eq!(
+            slice(&[Value::from(2), Value::from(100)]).unwrap(),
+            vec![0x03, 0x04]
+        );
+        assert_eq!(slice(&[Value::from(10)]).unwrap(), Vec::<u8>::new());
+    }
+
+    #[test]
+    fn test_bytes_slice_wrong_arguments() {
+        let input = RadonBytes::from(vec![0x01, 0x02, 0x03, 0x04]);
+
+        for args in [
+            vec![],
+            vec![Value::from(3), Value::from(1)],
+            vec![Value::from(10), Value::from(5)],
+            vec![Value::Text("1".to_string())],
+            vec![Value::from(1), Value::from(2), Value::from(3)],
+        ] {
+            assert!(
+                matches!(slice(&input, &args), Err(RadError::WrongArguments { .. })),
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_bytes_to_integer() {
+        let integer = |bytes: Vec<u8>| to_integer(&RadonBytes::from(bytes));
+
+        assert_eq!(integer(vec![]).unwrap(), RadonInteger::from(0));
+        assert_eq!(
+            integer(vec![0x01, 0x02, 0x03]).unwrap(),
+            RadonInteger::from(0x01_02_03)
+        );
+        assert_eq!(
+            integer(vec![0x7F; 16]).unwrap(),
+            RadonInteger::from(0x7F7F_7F7F_7F7F_7F7F_7F7F_7F7F_7F7F_7F7F)
+        );
+        assert!(matches!(
+            integer(vec![0x80; 16]),
+            Err(RadError::Overflow { .. })
+        ));
+        assert!(matches!(
+            integer(vec![0x01; 17]),
+            Err(RadError::Overflow { .. })
+        ));
+    }
+
+    #[test]
+    fn test_bytes_slice_and_to_integer() {
+        // Binary payload with a 4-byte big-endian price at offset 8
+        let payload = RadonBytes::from(vec![
+            0xCA, 0xFE, 0x00, 0x01, 0x00, 0x00, 0x00, 0x10, 0x00, 0x01, 0xE2, 0x40, 0xFF, 0xFF,
+        ]);
+
+        let price = slice(&payload, &[Value::from(8), Value::from(12)]).unwrap();
+        assert_eq!(length(&price), RadonInteger::from(4));
+        assert_eq!(to_integer(&price).unwrap(), RadonInteger::from(123_456));
+    }
 }
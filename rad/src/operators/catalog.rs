@@ -0,0 +1,806 @@
+//! Machine-readable description of the RADON operators, meant for tools such as request editors
+//! that need to know which operators exist and which arguments they take.
+
+use std::{convert::TryFrom, fmt::Debug};
+
+use serde::Serialize;
+
+use crate::{
+    filters::RadonFilters,
+    hash_functions::RadonHashFunctions,
+    operators::{
+        float::FloatRoundingMode, map::MissingKeyPolicy, path::RadonTypeCode,
+        string::StringLengthUnit, RadonOpCodes,
+    },
+    reducers::{average::MeanReturnPolicy, RadonReducers},
+    types::{
+        array::RadonArray, boolean::RadonBoolean, bytes::RadonBytes, float::RadonFloat,
+        integer::RadonInteger, map::RadonMap, string::RadonString, RadonType,
+    },
+};
+
+/// Output type of the operators whose result type depends on their input or arguments.
+pub const ANY_TYPE_NAME: &str = "RadonTypes";
+
+/// Description of an operator as applied to one input type.
+///
+/// Operators without arguments are called with their bare code, while the rest are called as an
+/// array containing the code followed by the arguments, even if all of them are omitted.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct OperatorSpec {
+    /// Numeric code of the operator
+    pub code: u8,
+    /// Name of the operator, as in `RadonOpCodes`
+    pub name: String,
+    /// Type of the values the operator applies to
+    pub input_type: &'static str,
+    /// Type of the result, or `ANY_TYPE_NAME` if it depends on the input or the arguments
+    pub output_type: &'static str,
+    /// Arguments of the operator, in order. Optional arguments always come last
+    pub arguments: Vec<ArgumentSpec>,
+    /// WIP that must be active for the operator to be available, if any
+    pub wip: Option<&'static str>,
+}
+
+/// Description of an argument of an operator.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ArgumentSpec {
+    /// Name of the argument
+    pub name: &'static str,
+    /// CBOR types accepted for the argument
+    pub kinds: Vec<ArgumentKind>,
+    /// Whether the argument can be omitted
+    pub optional: bool,
+    /// Codes accepted for the argument, if it only takes a closed set of integers
+    pub allowed_values: Vec<AllowedValue>,
+    /// WIP that must be active for the argument to be accepted, if any
+    pub wip: Option<&'static str>,
+}
+
+/// CBOR type of an argument.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgumentKind {
+    Integer,
+    Float,
+    Boolean,
+    Text,
+    Array,
+    Map,
+    /// A RADON script, i.e. an array of calls
+    Script,
+    /// Any CBOR value
+    Any,
+}
+
+/// A code accepted by an argument that only takes a closed set of integers, such as a reducer.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct AllowedValue {
+    pub code: u8,
+    pub name: String,
+    /// WIP that must be active for the value to be accepted, if any
+    pub wip: Option<&'static str>,
+    /// Arguments that follow this value, e.g. the number of sigmas of a deviation filter
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub arguments: Vec<ArgumentSpec>,
+}
+
+impl OperatorSpec {
+    fn new(
+        code: RadonOpCodes,
+        input_type: &'static str,
+        output_type: &'static str,
+        arguments: Vec<ArgumentSpec>,
+    ) -> Self {
+        Self {
+            code: code as u8,
+            name: code.to_string(),
+            input_type,
+            output_type,
+            arguments,
+            wip: None,
+        }
+    }
+
+    fn wip(self, wip: &'static str) -> Self {
+        Self {
+            wip: Some(wip),
+            ..self
+        }
+    }
+}
+
+impl ArgumentSpec {
+    fn new(name: &'static str, kinds: &[ArgumentKind]) -> Self {
+        Self {
+            name,
+            kinds: kinds.to_vec(),
+            optional: false,
+            allowed_values: vec![],
+            wip: None,
+        }
+    }
+
+    fn optional(self) -> Self {
+        Self {
+            optional: true,
+            ..self
+        }
+    }
+
+    fn allowed(self, allowed_values: Vec<AllowedValue>) -> Self {
+        Self {
+            allowed_values,
+            ..self
+        }
+    }
+
+    fn wip(self, wip: &'static str) -> Self {
+        Self {
+            wip: Some(wip),
+            ..self
+        }
+    }
+}
+
+impl AllowedValue {
+    fn new<E>(value: E) -> Self
+    where
+        E: Debug + Into<u8>,
+    {
+        Self {
+            name: format!("{:?}", value),
+            code: value.into(),
+            wip: None,
+            arguments: vec![],
+        }
+    }
+
+    fn wip(self, wip: &'static str) -> Self {
+        Self {
+            wip: Some(wip),
+            ..self
+        }
+    }
+
+    fn arguments(self, arguments: Vec<ArgumentSpec>) -> Self {
+        Self { arguments, ..self }
+    }
+}
+
+/// Every value of an enumeration whose values are all implemented.
+fn all_values<E>() -> Vec<AllowedValue>
+where
+    E: Debug + TryFrom<u8>,
+{
+    (0..=u8::MAX)
+        .filter_map(|code| {
+            E::try_from(code).ok().map(|value| AllowedValue {
+                code,
+                name: format!("{:?}", value),
+                wip: None,
+                arguments: vec![],
+            })
+        })
+        .collect()
+}
+
+fn reducers() -> Vec<AllowedValue> {
+    use ArgumentKind::*;
+
+    vec![
+        AllowedValue::new(RadonReducers::Mode),
+        AllowedValue::new(RadonReducers::AverageMean).arguments(vec![return_policy()]),
+        AllowedValue::new(RadonReducers::AverageMedian)
+            .wip("WIP0017")
+            .arguments(vec![return_policy()]),
+        AllowedValue::new(RadonReducers::DeviationStandard),
+        AllowedValue::new(RadonReducers::HashConcatenate).wip("WIP0019"),
+        AllowedValue::new(RadonReducers::AverageMeanTrimmed)
+            .wip("WIP0035")
+            .arguments(vec![ArgumentSpec::new("trim", &[Integer]), return_policy()]),
+    ]
+}
+
+/// Argument of the reducers that average integers, which decides the type of their result since
+/// WIP-0028.
+fn return_policy() -> ArgumentSpec {
+    ArgumentSpec::new("return_policy", &[ArgumentKind::Integer])
+        .optional()
+        .allowed(all_values::<MeanReturnPolicy>())
+        .wip("WIP0028")
+}
+
+/// Argument of `StringLength` that selects the unit in which the length is counted since WIP-0036.
+fn length_unit() -> ArgumentSpec {
+    ArgumentSpec::new("unit", &[ArgumentKind::Integer])
+        .optional()
+        .allowed(all_values::<StringLengthUnit>())
+        .wip("WIP0036")
+}
+
+/// Argument of `FloatAsInteger` that selects how the float is rounded.
+fn rounding_mode() -> ArgumentSpec {
+    ArgumentSpec::new("rounding", &[ArgumentKind::Integer])
+        .optional()
+        .allowed(all_values::<FloatRoundingMode>())
+}
+
+fn filters() -> Vec<AllowedValue> {
+    use ArgumentKind::*;
+
+    vec![
+        AllowedValue::new(RadonFilters::DeviationStandard)
+            .arguments(vec![ArgumentSpec::new("sigmas", &[Float, Integer])]),
+        AllowedValue::new(RadonFilters::Mode),
+    ]
+}
+
+fn hash_functions() -> Vec<AllowedValue> {
+    vec![AllowedValue::new(RadonHashFunctions::SHA2_256)]
+}
+
+/// Arguments of the getters that read a number out of a string, which accept custom separators
+/// since WIP-0024.
+fn separators() -> Vec<ArgumentSpec> {
+    use ArgumentKind::*;
+
+    vec![
+        ArgumentSpec::new("thousands_separator", &[Text])
+            .optional()
+            .wip("WIP0024"),
+        ArgumentSpec::new("decimal_separator", &[Text])
+            .optional()
+            .wip("WIP0024"),
+    ]
+}
+
+/// Arguments of the `GetPath` operators.
+fn path() -> Vec<ArgumentSpec> {
+    use ArgumentKind::*;
+
+    vec![
+        ArgumentSpec::new("path", &[Array]),
+        ArgumentSpec::new("type", &[Integer]).allowed(all_values::<RadonTypeCode>()),
+    ]
+}
+
+fn identity(input_type: &'static str) -> OperatorSpec {
+    OperatorSpec::new(RadonOpCodes::Identity, input_type, input_type, vec![])
+}
+
+/// Description of every operator implemented for every input type, ordered by input type and
+/// operator code.
+///
+/// The tests of this module execute every entry with arguments that follow its description, so
+/// any change in the operators must be reflected here.
+pub fn operator_catalog() -> Vec<OperatorSpec> {
+    use ArgumentKind::*;
+    use RadonOpCodes::*;
+
+    let array = RadonArray::radon_type_name();
+    let boolean = RadonBoolean::radon_type_name();
+    let bytes = RadonBytes::radon_type_name();
+    let float = RadonFloat::radon_type_name();
+    let integer = RadonInteger::radon_type_name();
+    let map = RadonMap::radon_type_name();
+    let string = RadonString::radon_type_name();
+
+    let index = || ArgumentSpec::new("index", &[Integer]);
+    let key = || ArgumentSpec::new("key", &[Text]);
+    let integer_operand = |name| ArgumentSpec::new(name, &[Integer]);
+    let float_operand = |name| ArgumentSpec::new(name, &[Float]);
+    let epsilon = || ArgumentSpec::new("epsilon", &[Float]);
+
+    vec![
+        // Array
+        identity(array),
+        OperatorSpec::new(ArrayCount, array, integer, vec![]),
+        OperatorSpec::new(
+            ArrayFilter,
+            array,
+            array,
+            vec![ArgumentSpec::new("filter", &[Script, Integer]).allowed(filters())],
+        ),
+        OperatorSpec::new(
+            ArrayJoin,
+            array,
+            string,
+            vec![
+                ArgumentSpec::new("separator", &[Text]).optional(),
+                ArgumentSpec::new("convert_numbers", &[Boolean]).optional(),
+            ],
+        )
+        .wip("WIP0039"),
+        OperatorSpec::new(ArrayGetArray, array, array, vec![index()]),
+        OperatorSpec::new(ArrayGetBoolean, array, boolean, vec![index()]),
+        OperatorSpec::new(ArrayGetBytes, array, bytes, vec![index()]),
+        OperatorSpec::new(
+            ArrayGetFloat,
+            array,
+            float,
+            [vec![index()], separators()].concat(),
+        ),
+        OperatorSpec::new(
+            ArrayGetInteger,
+            array,
+            integer,
+            [vec![index()], separators()].concat(),
+        ),
+        OperatorSpec::new(ArrayGetMap, array, map, vec![index()]),
+        OperatorSpec::new(ArrayGetString, array, string, vec![index()]),
+        OperatorSpec::new(
+            ArrayMap,
+            array,
+            array,
+            vec![ArgumentSpec::new("script", &[Script])],
+        ),
+        OperatorSpec::new(
+            ArrayReduce,
+            array,
+            ANY_TYPE_NAME,
+            vec![ArgumentSpec::new("reducer", &[Integer]).allowed(reducers())],
+        ),
+        OperatorSpec::new(
+            ArraySort,
+            array,
+            array,
+            vec![ArgumentSpec::new("script", &[Script]).optional()],
+        ),
+        OperatorSpec::new(ArrayGetPath, array, ANY_TYPE_NAME, path()).wip("WIP0031"),
+        // Boolean
+        identity(boolean),
+        OperatorSpec::new(BooleanAsString, boolean, string, vec![]),
+        OperatorSpec::new(BooleanNegate, boolean, boolean, vec![]),
+        // Bytes
+        identity(bytes),
+        OperatorSpec::new(BytesAsString, bytes, string, vec![]),
+        OperatorSpec::new(
+            BytesHash,
+            bytes,
+            bytes,
+            vec![ArgumentSpec::new("hash_function", &[Integer]).allowed(hash_functions())],
+        ),
+        OperatorSpec::new(BytesLength, bytes, integer, vec![]).wip("WIP0033"),
+        OperatorSpec::new(
+            BytesSlice,
+            bytes,
+            bytes,
+            vec![integer_operand("start"), integer_operand("end").optional()],
+        )
+        .wip("WIP0033"),
+        OperatorSpec::new(BytesAsInteger, bytes, integer, vec![]).wip("WIP0033"),
+        // Integer
+        identity(integer),
+        OperatorSpec::new(IntegerAbsolute, integer, integer, vec![]),
+        OperatorSpec::new(IntegerAsFloat, integer, float, vec![]),
+        OperatorSpec::new(IntegerAsString, integer, string, vec![]),
+        OperatorSpec::new(
+            IntegerGreaterThan,
+            integer,
+            boolean,
+            vec![integer_operand("value")],
+        ),
+        OperatorSpec::new(
+            IntegerLessThan,
+            integer,
+            boolean,
+            vec![integer_operand("value")],
+        ),
+        OperatorSpec::new(
+            IntegerModulo,
+            integer,
+            integer,
+            vec![integer_operand("modulus")],
+        ),
+        OperatorSpec::new(
+            IntegerMultiply,
+            integer,
+            integer,
+            vec![integer_operand("multiplier")],
+        ),
+        OperatorSpec::new(IntegerNegate, integer, integer, vec![]),
+        // Negative exponents result in a float after WIP-0034
+        OperatorSpec::new(
+            IntegerPower,
+            integer,
+            ANY_TYPE_NAME,
+            vec![integer_operand("exponent")],
+        ),
+        // Float
+        identity(float),
+        OperatorSpec::new(FloatAbsolute, float, float, vec![]),
+        OperatorSpec::new(FloatAsString, float, string, vec![]),
+        OperatorSpec::new(FloatCeiling, float, integer, vec![]),
+        OperatorSpec::new(
+            FloatGreaterThan,
+            float,
+            boolean,
+            vec![float_operand("value"), epsilon().optional().wip("WIP0032")],
+        ),
+        OperatorSpec::new(FloatFloor, float, integer, vec![]),
+        OperatorSpec::new(
+            FloatLessThan,
+            float,
+            boolean,
+            vec![float_operand("value"), epsilon().optional().wip("WIP0032")],
+        ),
+        OperatorSpec::new(FloatModulo, float, float, vec![float_operand("modulus")]),
+        OperatorSpec::new(
+            FloatMultiply,
+            float,
+            float,
+            vec![float_operand("multiplier")],
+        ),
+        OperatorSpec::new(FloatNegate, float, float, vec![]),
+        OperatorSpec::new(FloatPower, float, float, vec![float_operand("exponent")]),
+        OperatorSpec::new(FloatRound, float, integer, vec![]),
+        OperatorSpec::new(FloatTruncate, float, integer, vec![]),
+        OperatorSpec::new(
+            FloatEquals,
+            float,
+            boolean,
+            vec![float_operand("value"), epsilon()],
+        )
+        .wip("WIP0032"),
+        OperatorSpec::new(FloatAsInteger, float, integer, vec![rounding_mode()]).wip("WIP0038"),
+        // Map
+        identity(map),
+        OperatorSpec::new(MapGetArray, map, array, vec![key()]),
+        OperatorSpec::new(MapGetBoolean, map, boolean, vec![key()]),
+        OperatorSpec::new(MapGetBytes, map, bytes, vec![key()]),
+        OperatorSpec::new(
+            MapGetFloat,
+            map,
+            float,
+            [vec![key()], separators()].concat(),
+        ),
+        OperatorSpec::new(
+            MapGetInteger,
+            map,
+            integer,
+            [vec![key()], separators()].concat(),
+        ),
+        OperatorSpec::new(MapGetMap, map, map, vec![key()]),
+        OperatorSpec::new(MapGetString, map, string, vec![key()]),
+        OperatorSpec::new(MapKeys, map, array, vec![]),
+        OperatorSpec::new(MapValues, map, array, vec![]),
+        OperatorSpec::new(MapGetPath, map, ANY_TYPE_NAME, path()).wip("WIP0031"),
+        OperatorSpec::new(
+            MapPick,
+            map,
+            map,
+            vec![
+                ArgumentSpec::new("keys", &[Array]),
+                ArgumentSpec::new("missing_keys", &[Integer])
+                    .optional()
+                    .allowed(all_values::<MissingKeyPolicy>()),
+            ],
+        )
+        .wip("WIP0037"),
+        OperatorSpec::new(
+            MapRename,
+            map,
+            map,
+            vec![ArgumentSpec::new("renames", &[Map])],
+        )
+        .wip("WIP0037"),
+        // String
+        identity(string),
+        OperatorSpec::new(StringAsBoolean, string, boolean, vec![]),
+        OperatorSpec::new(StringAsFloat, string, float, separators()),
+        OperatorSpec::new(StringAsInteger, string, integer, separators()),
+        OperatorSpec::new(StringLength, string, integer, vec![length_unit()]),
+        OperatorSpec::new(
+            StringMatch,
+            string,
+            ANY_TYPE_NAME,
+            vec![
+                ArgumentSpec::new("cases", &[Map]),
+                ArgumentSpec::new("default", &[Any]),
+            ],
+        ),
+        OperatorSpec::new(StringParseJSONArray, string, array, vec![]),
+        OperatorSpec::new(StringParseJSONMap, string, map, vec![]),
+        OperatorSpec::new(StringParseXMLMap, string, map, vec![]),
+        OperatorSpec::new(StringToLowerCase, string, string, vec![]),
+        OperatorSpec::new(StringToUpperCase, string, string, vec![]),
+        OperatorSpec::new(
+            StringExtract,
+            string,
+            string,
+            vec![
+                ArgumentSpec::new("pattern", &[Text]),
+                ArgumentSpec::new("group", &[Integer]).optional(),
+            ],
+        )
+        .wip("WIP0030"),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use serde_cbor::Value;
+    use witnet_data_structures::{
+        chain::tapi::{all_wips_active, ActiveWips},
+        radon_report::ReportContext,
+    };
+
+    use crate::{error::RadError, operators::operate_in_context, types::RadonTypes};
+
+    use super::*;
+
+    /// Values of every input type to execute the operators on. Arrays of several types are needed
+    /// because some reducers and filters only support some of them.
+    fn samples(input_type: &str) -> Vec<RadonTypes> {
+        let values = vec![
+            RadonArray::from(vec![
+                RadonFloat::from(1.0).into(),
+                RadonFloat::from(2.0).into(),
+            ])
+            .into(),
+            RadonArray::from(vec![
+                RadonBytes::from(vec![0x01]).into(),
+                RadonBytes::from(vec![0x02]).into(),
+            ])
+            .into(),
+            RadonBoolean::from(true).into(),
+            RadonBytes::from(vec![0x01, 0x02, 0x03]).into(),
+            RadonInteger::from(2).into(),
+            RadonFloat::from(1.5).into(),
+            RadonMap::from(BTreeMap::from([(
+                "key".to_string(),
+                RadonInteger::from(1).into(),
+            )]))
+            .into(),
+            RadonString::from("1").into(),
+        ];
+
+        values
+            .into_iter()
+            .filter(|value: &RadonTypes| value.radon_type_name() == input_type)
+            .collect()
+    }
+
+    fn input_types() -> Vec<&'static str> {
+        vec![
+            RadonArray::radon_type_name(),
+            RadonBoolean::radon_type_name(),
+            RadonBytes::radon_type_name(),
+            RadonFloat::radon_type_name(),
+            RadonInteger::radon_type_name(),
+            RadonMap::radon_type_name(),
+            RadonString::radon_type_name(),
+        ]
+    }
+
+    fn dummy_value(kind: ArgumentKind) -> Value {
+        match kind {
+            ArgumentKind::Integer | ArgumentKind::Any => Value::Integer(1),
+            ArgumentKind::Float => Value::Float(1.0),
+            ArgumentKind::Boolean => Value::Bool(true),
+            ArgumentKind::Text => Value::Text(String::new()),
+            ArgumentKind::Array | ArgumentKind::Script => Value::Array(vec![]),
+            ArgumentKind::Map => Value::Map(BTreeMap::new()),
+        }
+    }
+
+    fn allowed_value_args(value: &AllowedValue) -> Vec<Value> {
+        let mut args = vec![Value::Integer(i128::from(value.code))];
+        args.extend(value.arguments.iter().flat_map(dummy_arg));
+
+        args
+    }
+
+    /// Arguments that follow the description of an argument, using its first allowed value if
+    /// it takes a closed set of integers.
+    fn dummy_arg(argument: &ArgumentSpec) -> Vec<Value> {
+        match (argument.kinds[0], argument.allowed_values.first()) {
+            (ArgumentKind::Integer, Some(value)) => allowed_value_args(value),
+            (kind, _) => vec![dummy_value(kind)],
+        }
+    }
+
+    /// Arguments for the first `count` arguments of `spec`, replacing the one at `position` with
+    /// `replacement`.
+    fn dummy_args(
+        spec: &OperatorSpec,
+        count: usize,
+        replacement: Option<(usize, Vec<Value>)>,
+    ) -> Vec<Value> {
+        spec.arguments[..count]
+            .iter()
+            .enumerate()
+            .flat_map(|(position, argument)| match &replacement {
+                Some((replaced, args)) if *replaced == position => args.clone(),
+                _ => dummy_arg(argument),
+            })
+            .collect()
+    }
+
+    /// Every combination of arguments that follows the description of an operator: with and
+    /// without each optional argument, and with every allowed value of each argument.
+    fn valid_args(spec: &OperatorSpec) -> Vec<Vec<Value>> {
+        let required = spec.arguments.iter().filter(|arg| !arg.optional).count();
+        let mut combinations = vec![];
+        for count in required..=spec.arguments.len() {
+            combinations.push(dummy_args(spec, count, None));
+            for (position, argument) in spec.arguments[..count].iter().enumerate() {
+                for value in &argument.allowed_values {
+                    let args = allowed_value_args(value);
+                    combinations.push(dummy_args(spec, count, Some((position, args))));
+                }
+            }
+        }
+
+        combinations
+    }
+
+    fn call(spec: &OperatorSpec, args: Vec<Value>) -> (RadonOpCodes, Option<Vec<Value>>) {
+        let op_code = RadonOpCodes::try_from(spec.code).unwrap();
+        if spec.arguments.is_empty() {
+            (op_code, None)
+        } else {
+            (op_code, Some(args))
+        }
+    }
+
+    fn execute(
+        input: RadonTypes,
+        call: &(RadonOpCodes, Option<Vec<Value>>),
+        active_wips: ActiveWips,
+    ) -> Result<RadonTypes, RadError> {
+        let mut context = ReportContext::default();
+        context.set_active_wips(active_wips);
+
+        operate_in_context(input, call, &mut context)
+    }
+
+    /// Whether an error means that the call does not follow the description of the operator, as
+    /// opposed to the operator failing on that particular input.
+    fn is_rejection(error: &RadError) -> bool {
+        matches!(
+            error,
+            RadError::UnsupportedOperator { .. }
+                | RadError::WrongArguments { .. }
+                | RadError::UnsupportedReducer { .. }
+                | RadError::UnsupportedFilter { .. }
+                | RadError::UnknownFilter { .. }
+                | RadError::UnsupportedHashFunction { .. }
+        )
+    }
+
+    #[test]
+    fn optional_arguments_come_last() {
+        for spec in operator_catalog() {
+            let first_optional = spec.arguments.iter().position(|arg| arg.optional);
+            if let Some(first_optional) = first_optional {
+                assert!(
+                    spec.arguments[first_optional..]
+                        .iter()
+                        .all(|arg| arg.optional),
+                    "{:?}",
+                    spec
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn catalog_entries_are_supported() {
+        for spec in operator_catalog() {
+            for args in valid_args(&spec) {
+                let call = call(&spec, args);
+                let results: Vec<_> = samples(spec.input_type)
+                    .into_iter()
+                    .map(|input| execute(input, &call, all_wips_active()))
+                    .collect();
+
+                assert!(
+                    results
+                        .iter()
+                        .any(|result| !matches!(result, Err(error) if is_rejection(error))),
+                    "{} on {} rejected call {:?}: {:?}",
+                    spec.name,
+                    spec.input_type,
+                    call,
+                    results
+                );
+                if spec.output_type != ANY_TYPE_NAME {
+                    for output in results.into_iter().flatten() {
+                        assert_eq!(output.radon_type_name(), spec.output_type, "{:?}", call);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn operators_missing_from_catalog_are_unsupported() {
+        let catalog = operator_catalog();
+
+        for op_code in (0..=u8::MAX).filter_map(|code| RadonOpCodes::try_from(code).ok()) {
+            for input_type in input_types() {
+                let listed = catalog
+                    .iter()
+                    .any(|spec| spec.code == op_code as u8 && spec.input_type == input_type);
+                if listed {
+                    continue;
+                }
+
+                for call in [(op_code, None), (op_code, Some(vec![Value::Integer(1)]))] {
+                    for input in samples(input_type) {
+                        assert!(
+                            matches!(
+                                execute(input, &call, all_wips_active()),
+                                Err(RadError::UnsupportedOperator { .. })
+                            ),
+                            "{:?} is not in the catalog for {}",
+                            call,
+                            input_type
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn values_missing_from_catalog_are_rejected() {
+        for spec in operator_catalog() {
+            for (position, argument) in spec.arguments.iter().enumerate() {
+                let allowed: Vec<u8> = argument.allowed_values.iter().map(|v| v.code).collect();
+                if allowed.is_empty() {
+                    continue;
+                }
+
+                for code in (0..=u8::MAX).filter(|code| !allowed.contains(code)) {
+                    let replacement = vec![Value::Integer(i128::from(code))];
+                    let args = dummy_args(&spec, position + 1, Some((position, replacement)));
+                    let call = call(&spec, args);
+
+                    for input in samples(spec.input_type) {
+                        assert!(
+                            matches!(
+                                execute(input, &call, all_wips_active()),
+                                Err(error) if is_rejection(&error)
+                            ),
+                            "{:?} is not in the catalog for {}",
+                            call,
+                            spec.input_type
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn gated_operators_are_unsupported_before_their_wip() {
+        for spec in operator_catalog() {
+            let wip = match spec.wip {
+                Some(wip) => wip,
+                None => continue,
+            };
+            let mut active_wips = all_wips_active();
+            active_wips.active_wips.remove(wip);
+
+            for args in valid_args(&spec) {
+                let call = call(&spec, args);
+                for input in samples(spec.input_type) {
+                    assert!(
+                        matches!(
+                            execute(input, &call, active_wips.clone()),
+                            Err(RadError::UnsupportedOperator { .. })
+                        ),
+                        "{:?} should not be available before {}",
+                        call,
+                        wip
+                    );
+                }
+            }
+        }
+    }
+}
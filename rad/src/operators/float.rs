@@ -1,9 +1,11 @@
 use std::{borrow::ToOwned, convert::TryFrom};
 
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 use serde_cbor::value::{from_value, Value};
 
 use crate::{
     error::RadError,
+    operators::{integer::MAX_POWER_EXPONENT, RadonOpCodes},
     types::{
         boolean::RadonBoolean, float::RadonFloat, integer::RadonInteger, string::RadonString,
         RadonType,
@@ -60,6 +62,95 @@ pub fn less_than(input: &RadonFloat, args: &[Value]) -> Result<RadonBoolean, Rad
     Ok(RadonBoolean::from(input.value() < other))
 }
 
+/// Read the threshold and the absolute epsilon of a comparison. The epsilon is optional unless
+/// `require_epsilon` is set, and defaults to 0, that is, to an exact comparison.
+fn comparison_args(
+    input: &RadonFloat,
+    args: &[Value],
+    operator: &str,
+    require_epsilon: bool,
+) -> Result<(f64, f64), RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonFloat::radon_type_name(),
+        operator: operator.to_string(),
+        args: args.to_vec(),
+    };
+
+    let (other, epsilon) = match args {
+        [other] if !require_epsilon => (other, None),
+        [other, epsilon] => (other, Some(epsilon)),
+        _ => return Err(wrong_args()),
+    };
+    let other = from_value::<f64>(other.to_owned()).map_err(|_| wrong_args())?;
+    let epsilon = match epsilon {
+        Some(epsilon) => from_value::<f64>(epsilon.to_owned()).map_err(|_| wrong_args())?,
+        None => 0.0,
+    };
+    if epsilon.is_nan() || epsilon < 0.0 {
+        return Err(wrong_args());
+    }
+
+    for operand in [input.value(), other] {
+        if operand.is_nan() {
+            return Err(RadError::NonFiniteFloat {
+                operator: operator.to_string(),
+                result: operand.to_string(),
+            });
+        }
+    }
+
+    Ok((other, epsilon))
+}
+
+/// Whether two operands are within `epsilon` of each other. Equal infinities are always within.
+// Exact equality is needed for infinities, whose difference is NaN
+#[allow(clippy::float_cmp)]
+fn within_epsilon(a: f64, b: f64, epsilon: f64) -> bool {
+    a == b || (a - b).abs() <= epsilon
+}
+
+/// `FloatGreaterThan` after WIP-0032, which takes an optional absolute epsilon as second argument.
+///
+/// Values within epsilon of the threshold are never greater than it. NaN operands are an error.
+pub fn greater_than_with_epsilon(
+    input: &RadonFloat,
+    args: &[Value],
+) -> Result<RadonBoolean, RadError> {
+    let (other, epsilon) = comparison_args(input, args, "GreaterThan", false)?;
+    let value = input.value();
+
+    Ok(RadonBoolean::from(
+        value > other && !within_epsilon(value, other, epsilon),
+    ))
+}
+
+/// `FloatLessThan` after WIP-0032, which takes an optional absolute epsilon as second argument.
+///
+/// Values within epsilon of the threshold are never less than it. NaN operands are an error.
+pub fn less_than_with_epsilon(
+    input: &RadonFloat,
+    args: &[Value],
+) -> Result<RadonBoolean, RadError> {
+    let (other, epsilon) = comparison_args(input, args, "LessThan", false)?;
+    let value = input.value();
+
+    Ok(RadonBoolean::from(
+        value < other && !within_epsilon(value, other, epsilon),
+    ))
+}
+
+/// Whether the input is within an absolute epsilon of the first argument. The epsilon is
+/// mandatory, so that exact float equality is never used by accident.
+pub fn equals(input: &RadonFloat, args: &[Value]) -> Result<RadonBoolean, RadError> {
+    let (other, epsilon) = comparison_args(input, args, "Equals", true)?;
+
+    Ok(RadonBoolean::from(within_epsilon(
+        input.value(),
+        other,
+        epsilon,
+    )))
+}
+
 pub fn modulo(input: &RadonFloat, args: &[Value]) -> Result<RadonFloat, RadError> {
     let wrong_args = || RadError::WrongArguments {
         input_type: RadonFloat::radon_type_name(),
@@ -89,6 +180,47 @@ pub fn power(input: &RadonFloat, args: &[Value]) -> Result<RadonFloat, RadError>
     Ok(RadonFloat::from(input.value().powf(exp)))
 }
 
+/// Raise a float to a float power, failing instead of producing infinite or NaN results.
+///
+/// This applies after WIP-0034, instead of `power` followed by `check_finite`. The magnitude of
+/// the exponent cannot exceed `MAX_POWER_EXPONENT`. Powers too large to be represented overflow,
+/// while a negative base with a fractional exponent, or zero with a negative exponent, result in
+/// a power that is undefined. Zero to the power of zero is one.
+pub fn checked_power(input: &RadonFloat, args: &[Value]) -> Result<RadonFloat, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonFloat::radon_type_name(),
+        operator: "Power".to_string(),
+        args: args.to_vec(),
+    };
+
+    let arg = args.first().ok_or_else(wrong_args)?.to_owned();
+    let exp = from_value::<f64>(arg).map_err(|_| wrong_args())?;
+    if exp.abs() > f64::from(MAX_POWER_EXPONENT) {
+        return Err(RadError::ExponentOutOfRange {
+            operator: RadonOpCodes::FloatPower.to_string(),
+            exponent: exp.to_string(),
+            max: MAX_POWER_EXPONENT,
+        });
+    }
+    let base = input.value();
+    let result = base.powf(exp);
+
+    if result.is_finite() {
+        Ok(RadonFloat::from(result))
+    } else if result.is_infinite() && base != 0.0 {
+        Err(RadError::Overflow {
+            operator: RadonOpCodes::FloatPower.to_string(),
+            operands: vec![Value::Float(base), Value::Float(exp)],
+        })
+    } else {
+        Err(RadError::UndefinedPower {
+            operator: RadonOpCodes::FloatPower.to_string(),
+            base: base.to_string(),
+            exponent: exp.to_string(),
+        })
+    }
+}
+
 // FIXME: Allow for now, wait for https://github.com/rust-lang/rust/issues/67058 to reach stable
 #[allow(clippy::cast_possible_truncation)]
 pub fn floor(input: &RadonFloat) -> RadonInteger {
@@ -107,6 +239,128 @@ pub fn truncate(input: &RadonFloat) -> RadonInteger {
     RadonInteger::from(input.value().trunc() as i128)
 }
 
+/// Fail if the result of a float operator is infinite or NaN, instead of letting it propagate into
+/// later operators and comparisons.
+///
+/// This applies after WIP-0029.
+pub fn check_finite(result: RadonFloat, operator: RadonOpCodes) -> Result<RadonFloat, RadError> {
+    if result.value().is_finite() {
+        Ok(result)
+    } else {
+        Err(RadError::NonFiniteFloat {
+            operator: operator.to_string(),
+            result: result.value().to_string(),
+        })
+    }
+}
+
+/// Convert an already rounded float into an integer, failing if it is not finite or if it does not
+/// fit into an i128, instead of saturating.
+///
+/// This applies after WIP-0029 to the `FloatCeiling`, `FloatFloor`, `FloatRound` and
+/// `FloatTruncate` operators.
+// The range is checked right before casting
+#[allow(clippy::cast_possible_truncation, clippy::cast_precision_loss)]
+pub fn checked_to_integer(
+    input: &RadonFloat,
+    rounded: f64,
+    operator: RadonOpCodes,
+) -> Result<RadonInteger, RadError> {
+    if !rounded.is_finite() {
+        return Err(RadError::NonFiniteFloat {
+            operator: operator.to_string(),
+            result: rounded.to_string(),
+        });
+    }
+
+    // Both bounds are powers of two, so they are exactly representable as f64. The upper one is
+    // exclusive because i128::MAX + 1 is the float that i128::MAX gets rounded to.
+    if rounded >= i128::MIN as f64 && rounded < i128::MAX as f64 {
+        Ok(RadonInteger::from(rounded as i128))
+    } else {
+        Err(RadError::Overflow {
+            operator: operator.to_string(),
+            operands: vec![Value::Float(input.value())],
+        })
+    }
+}
+
+/// Rounding mode used by `FloatAsInteger`, selected by its optional argument since WIP-0038.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum FloatRoundingMode {
+    /// Round towards zero, which is how the float is rounded when no mode is given.
+    Truncate = 0x00,
+    /// Round towards negative infinity.
+    Floor = 0x01,
+    /// Round towards positive infinity.
+    Ceiling = 0x02,
+    /// Round to the nearest integer, and halfway values to the even one.
+    HalfEven = 0x03,
+}
+
+impl Default for FloatRoundingMode {
+    fn default() -> Self {
+        FloatRoundingMode::Truncate
+    }
+}
+
+/// Round a float to the nearest integer, and halfway values to the even one.
+fn round_half_even(value: f64) -> f64 {
+    let rounded = value.round();
+    // `round` takes halfway values away from zero, so odd results need to be taken back by one
+    if (value - value.trunc()).abs() == 0.5 && rounded % 2.0 != 0.0 {
+        rounded - value.signum()
+    } else {
+        rounded
+    }
+}
+
+/// Converts a float into an integer using the rounding mode given by the only optional argument,
+/// which is the code of a `FloatRoundingMode`.
+///
+/// Unlike the older rounding operators, this always fails if the float is NaN or infinite, each
+/// with its own error, or if the result does not fit into an i128.
+pub fn to_integer(input: &RadonFloat, args: &[Value]) -> Result<RadonInteger, RadError> {
+    let operator = RadonOpCodes::FloatAsInteger;
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonFloat::radon_type_name(),
+        operator: operator.to_string(),
+        args: args.to_vec(),
+    };
+
+    let mode = match args {
+        [] => FloatRoundingMode::default(),
+        [arg] => from_value::<u8>(arg.clone())
+            .ok()
+            .and_then(|code| FloatRoundingMode::try_from(code).ok())
+            .ok_or_else(wrong_args)?,
+        _ => return Err(wrong_args()),
+    };
+
+    let value = input.value();
+    if value.is_nan() {
+        return Err(RadError::FloatIsNaN {
+            operator: operator.to_string(),
+        });
+    }
+    if value.is_infinite() {
+        return Err(RadError::FloatIsInfinite {
+            operator: operator.to_string(),
+            value: value.to_string(),
+        });
+    }
+
+    let rounded = match mode {
+        FloatRoundingMode::Truncate => value.trunc(),
+        FloatRoundingMode::Floor => value.floor(),
+        FloatRoundingMode::Ceiling => value.ceil(),
+        FloatRoundingMode::HalfEven => round_half_even(value),
+    };
+
+    checked_to_integer(input, rounded, operator)
+}
+
 #[test]
 fn test_float_absolute() {
     let positive_integer = RadonFloat::from(10.0);
@@ -177,6 +431,141 @@ fn test_float_less() {
     );
 }
 
+#[test]
+fn test_float_compare_with_epsilon() {
+    // 0.1 + 0.2 is slightly greater than 0.3
+    let input = RadonFloat::from(0.1 + 0.2);
+    let threshold = Value::Float(0.3);
+    let epsilon = Value::Float(1e-9);
+
+    // Without epsilon, comparisons are exact, as before WIP-0032
+    for args in [
+        &[threshold.clone()][..],
+        &[threshold.clone(), Value::Float(0.0)],
+    ] {
+        assert_eq!(
+            greater_than_with_epsilon(&input, args).unwrap(),
+            greater_than(&input, args).unwrap()
+        );
+        assert_eq!(
+            greater_than_with_epsilon(&input, args).unwrap(),
+            RadonBoolean::from(true)
+        );
+        assert_eq!(
+            less_than_with_epsilon(&input, args).unwrap(),
+            less_than(&input, args).unwrap()
+        );
+    }
+
+    let args = [threshold.clone(), epsilon.clone()];
+    assert_eq!(
+        greater_than_with_epsilon(&input, &args).unwrap(),
+        RadonBoolean::from(false)
+    );
+    assert_eq!(
+        less_than_with_epsilon(&input, &args).unwrap(),
+        RadonBoolean::from(false)
+    );
+    assert_eq!(equals(&input, &args).unwrap(), RadonBoolean::from(true));
+
+    // Values just outside epsilon of the threshold still compare
+    let args = [Value::Float(0.29), Value::Float(0.005)];
+    assert_eq!(
+        greater_than_with_epsilon(&input, &args).unwrap(),
+        RadonBoolean::from(true)
+    );
+    assert_eq!(equals(&input, &args).unwrap(), RadonBoolean::from(false));
+    let args = [Value::Float(0.31), Value::Float(0.005)];
+    assert_eq!(
+        less_than_with_epsilon(&input, &args).unwrap(),
+        RadonBoolean::from(true)
+    );
+
+    // Integer thresholds and epsilons are accepted too
+    let input = RadonFloat::from(10.0);
+    let args = [Value::Integer(11), Value::Integer(1)];
+    assert_eq!(
+        less_than_with_epsilon(&input, &args).unwrap(),
+        RadonBoolean::from(false)
+    );
+    assert_eq!(equals(&input, &args).unwrap(), RadonBoolean::from(true));
+
+    // Equal infinities are equal, whatever the epsilon
+    let input = RadonFloat::from(f64::INFINITY);
+    let args = [Value::Float(f64::INFINITY), Value::Float(0.0)];
+    assert_eq!(equals(&input, &args).unwrap(), RadonBoolean::from(true));
+    assert_eq!(
+        greater_than_with_epsilon(&input, &args).unwrap(),
+        RadonBoolean::from(false)
+    );
+}
+
+#[test]
+fn test_float_compare_wrong_epsilon() {
+    let input = RadonFloat::from(10.0);
+    let wrong = [
+        vec![Value::Float(10.0), Value::Float(-0.1)],
+        vec![Value::Float(10.0), Value::Float(f64::NAN)],
+        vec![Value::Float(10.0), Value::Text("0.1".to_string())],
+        vec![Value::Float(10.0), Value::Float(0.1), Value::Float(0.1)],
+        vec![],
+    ];
+
+    for args in &wrong {
+        for result in [
+            greater_than_with_epsilon(&input, args),
+            less_than_with_epsilon(&input, args),
+            equals(&input, args),
+        ] {
+            assert!(
+                matches!(result, Err(RadError::WrongArguments { .. })),
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    // The epsilon of `FloatEquals` is mandatory
+    assert_eq!(
+        equals(&input, &[Value::Float(10.0)]),
+        Err(RadError::WrongArguments {
+            input_type: RadonFloat::radon_type_name(),
+            operator: "Equals".to_string(),
+            args: vec![Value::Float(10.0)],
+        })
+    );
+}
+
+#[test]
+fn test_float_compare_nan() {
+    let nan_input = RadonFloat::from(f64::NAN);
+    let args = [Value::Float(1.0), Value::Float(0.1)];
+    assert_eq!(
+        greater_than_with_epsilon(&nan_input, &args),
+        Err(RadError::NonFiniteFloat {
+            operator: "GreaterThan".to_string(),
+            result: "NaN".to_string(),
+        })
+    );
+
+    let input = RadonFloat::from(1.0);
+    let args = [Value::Float(f64::NAN), Value::Float(0.1)];
+    assert_eq!(
+        less_than_with_epsilon(&input, &args),
+        Err(RadError::NonFiniteFloat {
+            operator: "LessThan".to_string(),
+            result: "NaN".to_string(),
+        })
+    );
+    assert_eq!(
+        equals(&input, &args),
+        Err(RadError::NonFiniteFloat {
+            operator: "Equals".to_string(),
+            result: "NaN".to_string(),
+        })
+    );
+}
+
 #[test]
 fn test_float_negate() {
     let positive_integer = RadonFloat::from(10.0);
@@ -212,6 +601,62 @@ fn test_float_power() {
     let value = Value::Float(3.0);
 
     assert_eq!(power(&rad_int, &[value]).unwrap(), RadonFloat::from(1000.0));
+
+    // Results are not checked
+    let root = power(&RadonFloat::from(-8.0), &[Value::Float(0.5)]).unwrap();
+    assert!(root.value().is_nan());
+    let reciprocal = power(&RadonFloat::from(0.0), &[Value::Float(-1.0)]).unwrap();
+    assert_eq!(reciprocal, RadonFloat::from(f64::INFINITY));
+}
+
+#[test]
+fn test_float_checked_power() {
+    let power = |base: f64, exp: f64| checked_power(&RadonFloat::from(base), &[Value::Float(exp)]);
+    let undefined = |base: &str, exp: &str| {
+        Err(RadError::UndefinedPower {
+            operator: "FloatPower".to_string(),
+            base: base.to_string(),
+            exponent: exp.to_string(),
+        })
+    };
+
+    assert_eq!(power(10.0, 3.0), Ok(RadonFloat::from(1000.0)));
+    assert_eq!(power(4.0, 0.5), Ok(RadonFloat::from(2.0)));
+    assert_eq!(power(2.0, -2.0), Ok(RadonFloat::from(0.25)));
+    assert_eq!(power(-2.0, 3.0), Ok(RadonFloat::from(-8.0)));
+    assert_eq!(power(-2.0, -2.0), Ok(RadonFloat::from(0.25)));
+    assert_eq!(power(0.0, 0.0), Ok(RadonFloat::from(1.0)));
+
+    // Negative bases with fractional exponents, and zero with negative exponents
+    assert_eq!(
+        power(-8.0, 1.0 / 3.0),
+        undefined("-8", "0.3333333333333333")
+    );
+    assert_eq!(power(-1.0, 0.5), undefined("-1", "0.5"));
+    assert_eq!(power(0.0, -1.0), undefined("0", "-1"));
+
+    assert_eq!(
+        power(10.0, 400.0),
+        Err(RadError::Overflow {
+            operator: "FloatPower".to_string(),
+            operands: vec![Value::Float(10.0), Value::Float(400.0)],
+        })
+    );
+    // Underflowing to zero is fine
+    assert_eq!(power(10.0, -400.0), Ok(RadonFloat::from(0.0)));
+
+    // The magnitude of the exponent is limited, even for trivial bases
+    assert_eq!(power(1.0, 1024.0), Ok(RadonFloat::from(1.0)));
+    for exp in [1024.5, -1025.0, f64::INFINITY] {
+        assert_eq!(
+            power(1.0, exp),
+            Err(RadError::ExponentOutOfRange {
+                operator: "FloatPower".to_string(),
+                exponent: exp.to_string(),
+                max: MAX_POWER_EXPONENT,
+            })
+        );
+    }
 }
 
 #[test]
@@ -257,3 +702,185 @@ fn test_float_trunc() {
     assert_eq!(truncate(&float2), RadonInteger::from(10));
     assert_eq!(truncate(&float3), RadonInteger::from(-10));
 }
+
+#[test]
+fn test_float_check_finite() {
+    assert_eq!(
+        check_finite(RadonFloat::from(1.5), RadonOpCodes::FloatMultiply),
+        Ok(RadonFloat::from(1.5))
+    );
+
+    let product = multiply(&RadonFloat::from(f64::MAX), &[Value::Float(2.0)]).unwrap();
+    assert_eq!(
+        check_finite(product, RadonOpCodes::FloatMultiply),
+        Err(RadError::NonFiniteFloat {
+            operator: "FloatMultiply".to_string(),
+            result: "inf".to_string(),
+        })
+    );
+
+    let remainder = modulo(&RadonFloat::from(5.0), &[Value::Float(0.0)]).unwrap();
+    assert_eq!(
+        check_finite(remainder, RadonOpCodes::FloatModulo),
+        Err(RadError::NonFiniteFloat {
+            operator: "FloatModulo".to_string(),
+            result: "NaN".to_string(),
+        })
+    );
+
+    let root = power(&RadonFloat::from(-8.0), &[Value::Float(1.0 / 3.0)]).unwrap();
+    assert_eq!(
+        check_finite(root, RadonOpCodes::FloatPower),
+        Err(RadError::NonFiniteFloat {
+            operator: "FloatPower".to_string(),
+            result: "NaN".to_string(),
+        })
+    );
+
+    let negation = negate(&RadonFloat::from(f64::INFINITY));
+    assert_eq!(
+        check_finite(negation, RadonOpCodes::FloatNegate),
+        Err(RadError::NonFiniteFloat {
+            operator: "FloatNegate".to_string(),
+            result: "-inf".to_string(),
+        })
+    );
+}
+
+#[test]
+fn test_float_checked_to_integer() {
+    let input = RadonFloat::from(-10.5);
+    assert_eq!(
+        checked_to_integer(&input, input.value().floor(), RadonOpCodes::FloatFloor),
+        Ok(RadonInteger::from(-11))
+    );
+
+    let input = RadonFloat::from(f64::NAN);
+    assert_eq!(
+        checked_to_integer(&input, input.value().round(), RadonOpCodes::FloatRound),
+        Err(RadError::NonFiniteFloat {
+            operator: "FloatRound".to_string(),
+            result: "NaN".to_string(),
+        })
+    );
+
+    let input = RadonFloat::from(f64::NEG_INFINITY);
+    assert_eq!(
+        checked_to_integer(&input, input.value().ceil(), RadonOpCodes::FloatCeiling),
+        Err(RadError::NonFiniteFloat {
+            operator: "FloatCeiling".to_string(),
+            result: "-inf".to_string(),
+        })
+    );
+
+    let input = RadonFloat::from(1e39);
+    assert_eq!(
+        checked_to_integer(&input, input.value().trunc(), RadonOpCodes::FloatTruncate),
+        Err(RadError::Overflow {
+            operator: "FloatTruncate".to_string(),
+            operands: vec![Value::Float(1e39)],
+        })
+    );
+
+    // The smallest i128 is a power of two, so it can be converted exactly
+    let input = RadonFloat::from(i128::MIN as f64);
+    assert_eq!(
+        checked_to_integer(&input, input.value().trunc(), RadonOpCodes::FloatTruncate),
+        Ok(RadonInteger::from(i128::MIN))
+    );
+
+    // But the largest i128 cannot be represented as a f64, and rounds up to 2^127
+    let input = RadonFloat::from(i128::MAX as f64);
+    assert!(matches!(
+        checked_to_integer(&input, input.value().trunc(), RadonOpCodes::FloatTruncate),
+        Err(RadError::Overflow { .. })
+    ));
+}
+
+#[test]
+fn test_float_as_integer_rounding_modes() {
+    let as_integer = |value: f64, mode: FloatRoundingMode| {
+        to_integer(
+            &RadonFloat::from(value),
+            &[Value::Integer(u8::from(mode).into())],
+        )
+        .unwrap()
+        .value()
+    };
+
+    let cases = [
+        (FloatRoundingMode::Truncate, [2, -2, 3, -3]),
+        (FloatRoundingMode::Floor, [2, -3, 3, -4]),
+        (FloatRoundingMode::Ceiling, [3, -2, 4, -3]),
+        (FloatRoundingMode::HalfEven, [2, -2, 4, -4]),
+    ];
+    for (mode, expected) in cases.iter() {
+        let results: Vec<i128> = [2.5, -2.5, 3.5, -3.5]
+            .iter()
+            .map(|value| as_integer(*value, *mode))
+            .collect();
+        assert_eq!(&results, expected, "{:?}", mode);
+    }
+
+    // Values that are not halfway are rounded to the nearest integer
+    assert_eq!(as_integer(2.6, FloatRoundingMode::HalfEven), 3);
+    assert_eq!(as_integer(-2.4, FloatRoundingMode::HalfEven), -2);
+    assert_eq!(as_integer(-0.5, FloatRoundingMode::HalfEven), 0);
+}
+
+#[test]
+fn test_float_as_integer_default_mode() {
+    assert_eq!(
+        to_integer(&RadonFloat::from(-2.7), &[]),
+        Ok(RadonInteger::from(-2))
+    );
+}
+
+#[test]
+fn test_float_as_integer_errors() {
+    assert_eq!(
+        to_integer(&RadonFloat::from(f64::NAN), &[Value::Integer(3)]),
+        Err(RadError::FloatIsNaN {
+            operator: "FloatAsInteger".to_string(),
+        })
+    );
+    for value in [f64::INFINITY, f64::NEG_INFINITY].iter() {
+        assert_eq!(
+            to_integer(&RadonFloat::from(*value), &[Value::Integer(3)]),
+            Err(RadError::FloatIsInfinite {
+                operator: "FloatAsInteger".to_string(),
+                value: value.to_string(),
+            })
+        );
+    }
+
+    assert_eq!(
+        to_integer(&RadonFloat::from(-1e40), &[Value::Integer(1)]),
+        Err(RadError::Overflow {
+            operator: "FloatAsInteger".to_string(),
+            operands: vec![Value::Float(-1e40)],
+        })
+    );
+}
+
+#[test]
+fn test_float_as_integer_wrong_args() {
+    let input = RadonFloat::from(2.5);
+
+    for args in [
+        vec![Value::Integer(4)],
+        vec![Value::Text("floor".to_string())],
+        vec![Value::Integer(0), Value::Integer(0)],
+    ]
+    .iter()
+    {
+        assert_eq!(
+            to_integer(&input, args),
+            Err(RadError::WrongArguments {
+                input_type: RadonFloat::radon_type_name(),
+                operator: "FloatAsInteger".to_string(),
+                args: args.clone(),
+            })
+        );
+    }
+}
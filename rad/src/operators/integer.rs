@@ -4,19 +4,32 @@ use serde_cbor::value::{from_value, Value};
 
 use crate::{
     error::RadError,
+    operators::RadonOpCodes,
     types::{
         boolean::RadonBoolean, float::RadonFloat, integer::RadonInteger, string::RadonString,
-        RadonType,
+        RadonType, RadonTypes,
     },
 };
 
+/// Largest magnitude of the exponent of `IntegerPower` and `FloatPower` after WIP-0034, so that
+/// the cost of computing a power is bounded regardless of the base.
+pub const MAX_POWER_EXPONENT: u32 = 1024;
+
+/// Build the error for an integer operator whose result does not fit into an i128.
+fn overflow(operator: RadonOpCodes, operands: &[i128]) -> RadError {
+    RadError::Overflow {
+        operator: operator.to_string(),
+        operands: operands.iter().map(|x| Value::Integer(*x)).collect(),
+    }
+}
+
 pub fn absolute(input: &RadonInteger) -> Result<RadonInteger, RadError> {
     let result = input.value().checked_abs();
 
     if let Some(result) = result {
         Ok(RadonInteger::from(result))
     } else {
-        Err(RadError::Overflow)
+        Err(overflow(RadonOpCodes::IntegerAbsolute, &[input.value()]))
     }
 }
 
@@ -42,7 +55,10 @@ pub fn multiply(input: &RadonInteger, args: &[Value]) -> Result<RadonInteger, Ra
     if let Some(result) = result {
         Ok(RadonInteger::from(result))
     } else {
-        Err(RadError::Overflow)
+        Err(overflow(
+            RadonOpCodes::IntegerMultiply,
+            &[input.value(), multiplier],
+        ))
     }
 }
 
@@ -80,9 +96,13 @@ pub fn modulo(input: &RadonInteger, args: &[Value]) -> Result<RadonInteger, RadE
     let arg = args.first().ok_or_else(wrong_args)?.to_owned();
     let modulo = from_value::<i128>(arg).map_err(|_| wrong_args())?;
 
+    // Note that a zero modulo is also reported as an overflow
     match input.value().checked_rem(modulo) {
         Some(x) => Ok(RadonInteger::from(x)),
-        None => Err(RadError::Overflow),
+        None => Err(overflow(
+            RadonOpCodes::IntegerModulo,
+            &[input.value(), modulo],
+        )),
     }
 }
 
@@ -92,7 +112,7 @@ pub fn negate(input: &RadonInteger) -> Result<RadonInteger, RadError> {
     if let Some(result) = result {
         Ok(RadonInteger::from(result))
     } else {
-        Err(RadError::Overflow)
+        Err(overflow(RadonOpCodes::IntegerNegate, &[input.value()]))
     }
 }
 
@@ -110,7 +130,56 @@ pub fn power(input: &RadonInteger, args: &[Value]) -> Result<RadonInteger, RadEr
     if let Some(result) = result {
         Ok(RadonInteger::from(result))
     } else {
-        Err(RadError::Overflow)
+        Err(overflow(
+            RadonOpCodes::IntegerPower,
+            &[input.value(), i128::from(exp)],
+        ))
+    }
+}
+
+/// Raise an integer to an integer power, resulting in a float if the exponent is negative.
+///
+/// This applies after WIP-0034, instead of `power`, which rejects negative exponents. The
+/// magnitude of the exponent cannot exceed `MAX_POWER_EXPONENT`. A negative exponent results in
+/// the reciprocal of the power with the opposite exponent, which is computed as an integer before
+/// converting it into a float, so that the result is deterministic. That power overflowing is
+/// reported as an underflow, and zero to a negative power is undefined. Zero to the power of zero
+/// is one.
+// The power is converted into a float on purpose
+#[allow(clippy::cast_precision_loss)]
+pub fn power_with_negative_exponent(
+    input: &RadonInteger,
+    args: &[Value],
+) -> Result<RadonTypes, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonInteger::radon_type_name(),
+        operator: "Power".to_string(),
+        args: args.to_vec(),
+    };
+
+    let arg = args.first().ok_or_else(wrong_args)?.to_owned();
+    let exp = from_value::<i128>(arg).map_err(|_| wrong_args())?;
+    let magnitude = u32::try_from(exp.unsigned_abs())
+        .ok()
+        .filter(|magnitude| *magnitude <= MAX_POWER_EXPONENT)
+        .ok_or_else(|| RadError::ExponentOutOfRange {
+            operator: RadonOpCodes::IntegerPower.to_string(),
+            exponent: exp.to_string(),
+            max: MAX_POWER_EXPONENT,
+        })?;
+    let base = input.value();
+    let power = base.checked_pow(magnitude);
+
+    match power {
+        Some(power) if exp >= 0 => Ok(RadonInteger::from(power).into()),
+        Some(0) => Err(RadError::UndefinedPower {
+            operator: RadonOpCodes::IntegerPower.to_string(),
+            base: base.to_string(),
+            exponent: exp.to_string(),
+        }),
+        Some(power) => Ok(RadonFloat::from(1.0 / power as f64).into()),
+        None if exp >= 0 => Err(overflow(RadonOpCodes::IntegerPower, &[base, exp])),
+        None => Err(RadError::Underflow),
     }
 }
 
@@ -122,10 +191,11 @@ fn test_integer_absolute() {
     assert_eq!(absolute(&positive_integer).unwrap(), positive_integer);
     assert_eq!(absolute(&negative_integer).unwrap(), positive_integer);
     assert_eq!(
-        absolute(&RadonInteger::from(i128::min_value()))
-            .unwrap_err()
-            .to_string(),
-        "Overflow error".to_string(),
+        absolute(&RadonInteger::from(i128::min_value())).unwrap_err(),
+        RadError::Overflow {
+            operator: "IntegerAbsolute".to_string(),
+            operands: vec![Value::Integer(i128::MIN)],
+        },
     );
 }
 
@@ -157,10 +227,20 @@ fn test_integer_multiply() {
 
     let value = Value::Integer(3);
     assert_eq!(
-        multiply(&RadonInteger::from(i128::max_value()), &[value])
-            .unwrap_err()
-            .to_string(),
-        "Overflow error".to_string(),
+        multiply(&RadonInteger::from(i128::max_value()), &[value]).unwrap_err(),
+        RadError::Overflow {
+            operator: "IntegerMultiply".to_string(),
+            operands: vec![Value::Integer(i128::MAX), Value::Integer(3)],
+        },
+    );
+
+    let value = Value::Integer(-1);
+    assert_eq!(
+        multiply(&RadonInteger::from(i128::MIN), &[value]).unwrap_err(),
+        RadError::Overflow {
+            operator: "IntegerMultiply".to_string(),
+            operands: vec![Value::Integer(i128::MIN), Value::Integer(-1)],
+        },
     );
 }
 
@@ -215,10 +295,11 @@ fn test_integer_negate() {
     assert_eq!(negate(&negative_integer).unwrap(), positive_integer);
 
     assert_eq!(
-        negate(&RadonInteger::from(i128::min_value()))
-            .unwrap_err()
-            .to_string(),
-        "Overflow error".to_string(),
+        negate(&RadonInteger::from(i128::min_value())).unwrap_err(),
+        RadError::Overflow {
+            operator: "IntegerNegate".to_string(),
+            operands: vec![Value::Integer(i128::MIN)],
+        },
     );
 }
 
@@ -243,12 +324,18 @@ fn test_integer_modulo() {
 
     assert_eq!(
         modulo(&RadonInteger::from(5), &[Value::Integer(0)]).unwrap_err(),
-        RadError::Overflow,
+        RadError::Overflow {
+            operator: "IntegerModulo".to_string(),
+            operands: vec![Value::Integer(5), Value::Integer(0)],
+        },
     );
 
     assert_eq!(
         modulo(&RadonInteger::from(i128::MIN), &[Value::Integer(-1)]).unwrap_err(),
-        RadError::Overflow,
+        RadError::Overflow {
+            operator: "IntegerModulo".to_string(),
+            operands: vec![Value::Integer(i128::MIN), Value::Integer(-1)],
+        },
     );
 }
 
@@ -262,7 +349,105 @@ fn test_integer_power() {
     let rad_int = RadonInteger::from(i128::max_value());
     let value = Value::Integer(3);
     assert_eq!(
-        power(&rad_int, &[value]).unwrap_err().to_string(),
-        "Overflow error".to_string(),
+        power(&rad_int, &[value]).unwrap_err(),
+        RadError::Overflow {
+            operator: "IntegerPower".to_string(),
+            operands: vec![Value::Integer(i128::MAX), Value::Integer(3)],
+        },
+    );
+
+    // Large exponents overflow even for small bases
+    let value = Value::Integer(i128::from(u32::MAX));
+    assert_eq!(
+        power(&RadonInteger::from(2), &[value]).unwrap_err(),
+        RadError::Overflow {
+            operator: "IntegerPower".to_string(),
+            operands: vec![Value::Integer(2), Value::Integer(i128::from(u32::MAX))],
+        },
     );
+
+    // Trivial bases never overflow
+    let value = Value::Integer(i128::from(u32::MAX));
+    assert_eq!(
+        power(&RadonInteger::from(1), &[value]).unwrap(),
+        RadonInteger::from(1)
+    );
+
+    // Exponents that do not fit into an u32 are rejected
+    let value = Value::Integer(i128::from(u32::MAX) + 1);
+    assert!(matches!(
+        power(&RadonInteger::from(1), &[value]).unwrap_err(),
+        RadError::WrongArguments { .. }
+    ));
+
+    // So are negative exponents
+    let value = Value::Integer(-2);
+    assert!(matches!(
+        power(&RadonInteger::from(2), &[value]).unwrap_err(),
+        RadError::WrongArguments { .. }
+    ));
+
+    // Zero to the power of zero is one
+    let value = Value::Integer(0);
+    assert_eq!(
+        power(&RadonInteger::from(0), &[value]).unwrap(),
+        RadonInteger::from(1)
+    );
+}
+
+#[test]
+fn test_integer_power_with_negative_exponent() {
+    let power = |base: i128, exp: i128| {
+        power_with_negative_exponent(&RadonInteger::from(base), &[Value::Integer(exp)])
+    };
+
+    assert_eq!(power(10, 3), Ok(RadonInteger::from(1000).into()));
+    assert_eq!(power(-2, 3), Ok(RadonInteger::from(-8).into()));
+    assert_eq!(power(0, 0), Ok(RadonInteger::from(1).into()));
+    assert_eq!(power(5, 0), Ok(RadonInteger::from(1).into()));
+
+    // Negative exponents result in floats
+    assert_eq!(power(2, -2), Ok(RadonFloat::from(0.25).into()));
+    assert_eq!(power(-2, -3), Ok(RadonFloat::from(-0.125).into()));
+    assert_eq!(power(10, -1), Ok(RadonFloat::from(0.1).into()));
+    assert_eq!(power(1, -1024), Ok(RadonFloat::from(1.0).into()));
+
+    // Zero to a negative power is undefined
+    assert_eq!(
+        power(0, -1),
+        Err(RadError::UndefinedPower {
+            operator: "IntegerPower".to_string(),
+            base: "0".to_string(),
+            exponent: "-1".to_string(),
+        })
+    );
+
+    // Powers that do not fit into an i128 overflow, and their reciprocals underflow
+    assert_eq!(
+        power(10, 39),
+        Err(RadError::Overflow {
+            operator: "IntegerPower".to_string(),
+            operands: vec![Value::Integer(10), Value::Integer(39)],
+        })
+    );
+    assert_eq!(power(10, -39), Err(RadError::Underflow));
+
+    // The magnitude of the exponent is limited, even for trivial bases
+    assert_eq!(power(1, 1024), Ok(RadonInteger::from(1).into()));
+    for exp in [1025, -1025, i128::from(u32::MAX) + 1, i128::MIN] {
+        assert_eq!(
+            power(1, exp),
+            Err(RadError::ExponentOutOfRange {
+                operator: "IntegerPower".to_string(),
+                exponent: exp.to_string(),
+                max: MAX_POWER_EXPONENT,
+            })
+        );
+    }
+
+    // Exponents must be integers
+    assert!(matches!(
+        power_with_negative_exponent(&RadonInteger::from(2), &[Value::Float(0.5)]),
+        Err(RadError::WrongArguments { .. })
+    ));
 }
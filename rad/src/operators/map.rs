@@ -1,10 +1,11 @@
-use std::convert::TryInto;
+use std::{collections::BTreeMap, convert::TryInto};
 
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 use serde_cbor::value::{from_value, Value};
 
 use crate::{
     error::RadError,
-    operators::string,
+    operators::{path, string},
     types::{array::RadonArray, map::RadonMap, string::RadonString, RadonType, RadonTypes},
 };
 
@@ -57,8 +58,10 @@ where
 /// This simply assumes that the element in that position is a number (i.e., `RadonFloat` or
 /// `RadonInteger`). If it is not, it will fail with a `RadError` because of `replace_separators`.
 fn get_numeric_string(input: &RadonMap, args: &[Value]) -> Result<RadonString, RadError> {
-    let item = get::<RadonString, _>(input, &args[..1])?.value();
-    let (thousands_separator, decimal_separator) = string::read_separators_from_args(&args[1..]);
+    let (key_args, separator_args) = args.split_at(args.len().min(1));
+    let item = get::<RadonString, _>(input, key_args)?.value();
+    let (thousands_separator, decimal_separator) =
+        string::read_separators_from_args(separator_args);
 
     Ok(RadonString::from(string::replace_separators(
         item,
@@ -67,6 +70,12 @@ fn get_numeric_string(input: &RadonMap, args: &[Value]) -> Result<RadonString, R
     )))
 }
 
+/// Get a value nested in maps and arrays inside the input `RadonMap`, as specified by a path of
+/// keys and indices, coerced into the requested type. See `path::get_path` for the arguments.
+pub fn get_path(input: &RadonMap, args: &[Value]) -> Result<RadonTypes, RadError> {
+    path::get_path(RadonTypes::from(input.clone()), args)
+}
+
 pub fn keys(input: &RadonMap) -> RadonArray {
     let v: Vec<RadonTypes> = input
         .value()
@@ -81,6 +90,94 @@ pub fn values(input: &RadonMap) -> RadonArray {
     RadonArray::from(v)
 }
 
+/// What `MapPick` does with the keys that are not present in its input, as given by its optional
+/// second argument since WIP-0037.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum MissingKeyPolicy {
+    /// Leave them out of the output, which is what happens when no policy is given.
+    Drop = 0x00,
+    /// Fail with `MapKeyNotFound` on the first of them, in the order they were given.
+    Fail = 0x01,
+}
+
+impl Default for MissingKeyPolicy {
+    fn default() -> Self {
+        MissingKeyPolicy::Drop
+    }
+}
+
+/// Keep only the entries of the input `RadonMap` whose keys are in the array given as the first
+/// argument. The second argument is the code of a `MissingKeyPolicy`.
+pub fn pick(input: &RadonMap, args: &[Value]) -> Result<RadonMap, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonMap::radon_type_name(),
+        operator: "MapPick".to_string(),
+        args: args.to_vec(),
+    };
+
+    let (keys, policy) = match args {
+        [keys] => (keys, MissingKeyPolicy::default()),
+        [keys, policy] => {
+            let policy = from_value::<u8>(policy.clone())
+                .ok()
+                .and_then(|code| MissingKeyPolicy::try_from(code).ok())
+                .ok_or_else(wrong_args)?;
+
+            (keys, policy)
+        }
+        _ => return Err(wrong_args()),
+    };
+    let keys = from_value::<Vec<String>>(keys.clone()).map_err(|_| wrong_args())?;
+
+    let mut picked = BTreeMap::new();
+    for key in keys {
+        match input.as_map().get(&key) {
+            Some(value) => {
+                picked.insert(key, value.clone());
+            }
+            None if policy == MissingKeyPolicy::Fail => {
+                return Err(RadError::MapKeyNotFound { key });
+            }
+            None => {}
+        }
+    }
+
+    Ok(RadonMap::from(picked))
+}
+
+/// Rename the keys of the input `RadonMap` as given by the only argument, a map from old names to
+/// new names. Keys that are not in the argument keep their name, and names in the argument that
+/// are not keys of the input are ignored.
+///
+/// Every key is renamed at once, so two keys can swap their names, but if two keys end up with the
+/// same name this fails with `MapKeyCollision`. Keys are renamed in order, so the error always
+/// reports the same name.
+pub fn rename(input: &RadonMap, args: &[Value]) -> Result<RadonMap, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonMap::radon_type_name(),
+        operator: "MapRename".to_string(),
+        args: args.to_vec(),
+    };
+
+    let renames = match args {
+        [renames] => {
+            from_value::<BTreeMap<String, String>>(renames.clone()).map_err(|_| wrong_args())?
+        }
+        _ => return Err(wrong_args()),
+    };
+
+    let mut renamed = BTreeMap::new();
+    for (key, value) in input.as_map() {
+        let key = renames.get(key).unwrap_or(key);
+        if renamed.insert(key.clone(), value.clone()).is_some() {
+            return Err(RadError::MapKeyCollision { key: key.clone() });
+        }
+    }
+
+    Ok(RadonMap::from(renamed))
+}
+
 /// This module was introduced for encapsulating the interim legacy logic before WIP-0024 is
 /// introduced, for the sake of maintainability.
 ///
@@ -209,6 +306,157 @@ mod tests {
         assert_eq!(values, RadonArray::from(vec![value1, value2, value0]));
     }
 
+    fn ticker() -> RadonMap {
+        RadonMap::from(BTreeMap::from([
+            ("last".to_string(), RadonFloat::from(1.5).into()),
+            ("time".to_string(), RadonInteger::from(1_600_000_000).into()),
+            ("volume".to_string(), RadonInteger::from(42).into()),
+        ]))
+    }
+
+    fn text_array(items: &[&str]) -> Value {
+        Value::Array(
+            items
+                .iter()
+                .map(|item| Value::Text(item.to_string()))
+                .collect(),
+        )
+    }
+
+    fn text_map(entries: &[(&str, &str)]) -> Value {
+        Value::Map(
+            entries
+                .iter()
+                .map(|(key, value)| (Value::Text(key.to_string()), Value::Text(value.to_string())))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_map_pick_drops_missing_keys() {
+        let expected = RadonMap::from(BTreeMap::from([
+            ("last".to_string(), RadonFloat::from(1.5).into()),
+            ("volume".to_string(), RadonInteger::from(42).into()),
+        ]));
+
+        let keys = text_array(&["volume", "price", "last"]);
+        assert_eq!(pick(&ticker(), &[keys.clone()]).unwrap(), expected);
+        assert_eq!(
+            pick(&ticker(), &[keys, Value::Integer(0)]).unwrap(),
+            expected
+        );
+        assert_eq!(
+            pick(&ticker(), &[text_array(&[])]).unwrap(),
+            RadonMap::default()
+        );
+    }
+
+    #[test]
+    fn test_map_pick_fails_on_missing_keys() {
+        let output = pick(
+            &ticker(),
+            &[text_array(&["last", "volume"]), Value::Integer(1)],
+        )
+        .unwrap();
+        assert_eq!(output.as_map().len(), 2);
+
+        // The first missing key in the order they were given is reported
+        let result = pick(
+            &ticker(),
+            &[text_array(&["last", "price", "close"]), Value::Integer(1)],
+        );
+        assert_eq!(
+            result,
+            Err(RadError::MapKeyNotFound {
+                key: "price".to_string()
+            })
+        );
+    }
+
+    #[test]
+    fn test_map_pick_wrong_arguments() {
+        for args in [
+            vec![],
+            vec![Value::Text("last".to_string())],
+            vec![Value::Array(vec![Value::Integer(0)])],
+            vec![text_array(&["last"]), Value::Integer(2)],
+            vec![text_array(&["last"]), Value::Integer(0), Value::Integer(0)],
+        ] {
+            assert!(
+                matches!(pick(&ticker(), &args), Err(RadError::WrongArguments { .. })),
+                "{:?}",
+                args
+            );
+        }
+    }
+
+    #[test]
+    fn test_map_rename() {
+        let renames = text_map(&[("last", "price"), ("close", "price"), ("time", "timestamp")]);
+        let output = rename(&ticker(), &[renames]).unwrap();
+
+        assert_eq!(
+            output,
+            RadonMap::from(BTreeMap::from([
+                ("price".to_string(), RadonFloat::from(1.5).into()),
+                (
+                    "timestamp".to_string(),
+                    RadonInteger::from(1_600_000_000).into()
+                ),
+                ("volume".to_string(), RadonInteger::from(42).into()),
+            ]))
+        );
+
+        // Keys are renamed at once, so they can swap their names
+        let swap = text_map(&[("last", "volume"), ("volume", "last")]);
+        let output = rename(&ticker(), &[swap]).unwrap();
+        assert_eq!(
+            output.as_map()["last"],
+            RadonTypes::from(RadonInteger::from(42))
+        );
+        assert_eq!(
+            output.as_map()["volume"],
+            RadonTypes::from(RadonFloat::from(1.5))
+        );
+    }
+
+    #[test]
+    fn test_map_rename_collision() {
+        let collision = RadError::MapKeyCollision {
+            key: "volume".to_string(),
+        };
+
+        // Renamed to the name of a key that keeps it
+        let renames = text_map(&[("last", "volume")]);
+        assert_eq!(rename(&ticker(), &[renames]), Err(collision.clone()));
+
+        // Two keys renamed to the same name
+        let renames = text_map(&[("last", "volume"), ("volume", "last"), ("time", "volume")]);
+        assert_eq!(rename(&ticker(), &[renames]), Err(collision));
+    }
+
+    #[test]
+    fn test_map_rename_wrong_arguments() {
+        for args in [
+            vec![],
+            vec![text_array(&["last"])],
+            vec![Value::Map(BTreeMap::from([(
+                Value::Text("last".to_string()),
+                Value::Integer(0),
+            )]))],
+            vec![text_map(&[]), text_map(&[])],
+        ] {
+            assert!(
+                matches!(
+                    rename(&ticker(), &args),
+                    Err(RadError::WrongArguments { .. })
+                ),
+                "{:?}",
+                args
+            );
+        }
+    }
+
     // Auxiliar functions
 
     fn radon_map_of_arrays() -> (RadonMap, String, RadonArray) {
@@ -639,4 +887,23 @@ mod tests {
             .unwrap();
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_get_number_without_arguments() {
+        let map = RadonMap::from(BTreeMap::from([(
+            String::from("foo"),
+            RadonTypes::from(RadonString::from("1234")),
+        )]));
+        let output = map
+            .operate(&(RadonOpCodes::MapGetInteger, Some(vec![])))
+            .unwrap_err();
+        assert_eq!(
+            output,
+            RadError::WrongArguments {
+                input_type: RadonMap::radon_type_name(),
+                operator: "Get".to_string(),
+                args: vec![],
+            }
+        );
+    }
 }
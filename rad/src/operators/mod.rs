@@ -9,9 +9,11 @@ use crate::{error::RadError, script::RadonCall, types::RadonTypes};
 pub mod array;
 pub mod boolean;
 pub mod bytes;
+pub mod catalog;
 pub mod float;
 pub mod integer;
 pub mod map;
+pub mod path;
 pub mod string;
 
 /// List of RADON operators.
@@ -29,7 +31,7 @@ pub enum RadonOpCodes {
     // Array operator codes (start at 0x10)
     ArrayCount = 0x10,
     ArrayFilter = 0x11,
-    //    ArrayFlatten = 0x12,
+    ArrayJoin = 0x12,
     ArrayGetArray = 0x13,
     ArrayGetBoolean = 0x14,
     ArrayGetBytes = 0x15,
@@ -42,6 +44,7 @@ pub enum RadonOpCodes {
     //    ArraySome = 0x1C,
     ArraySort = 0x1D,
     //    ArrayTake = 0x1E,
+    ArrayGetPath = 0x1F,
     ///////////////////////////////////////////////////////////////////////
     // Boolean operator codes (start at 0x20)
     BooleanAsString = 0x20,
@@ -51,6 +54,9 @@ pub enum RadonOpCodes {
     // Bytes operator codes (start at 0x30)
     BytesAsString = 0x30,
     BytesHash = 0x31,
+    BytesLength = 0x32,
+    BytesSlice = 0x33,
+    BytesAsInteger = 0x34,
     ///////////////////////////////////////////////////////////////////////
     // Integer operator codes (start at 0x40)
     IntegerAbsolute = 0x40,
@@ -81,6 +87,8 @@ pub enum RadonOpCodes {
     FloatRound = 0x5B,
     //    FloatSum = 0x5C,
     FloatTruncate = 0x5D,
+    FloatEquals = 0x5E,
+    FloatAsInteger = 0x5F,
     ///////////////////////////////////////////////////////////////////////
     // Map operator codes (start at 0x60)
     //    MapEntries = 0x60,
@@ -93,6 +101,9 @@ pub enum RadonOpCodes {
     MapGetString = 0x67,
     MapKeys = 0x68,
     MapValues = 0x69,
+    MapGetPath = 0x6A,
+    MapPick = 0x6B,
+    MapRename = 0x6C,
     ///////////////////////////////////////////////////////////////////////
     // String operator codes (start at 0x70)
     StringAsBoolean = 0x70,
@@ -106,6 +117,7 @@ pub enum RadonOpCodes {
     StringParseXMLMap = 0x78,
     StringToLowerCase = 0x79,
     StringToUpperCase = 0x7A,
+    StringExtract = 0x7B,
 }
 
 impl fmt::Display for RadonOpCodes {
@@ -125,7 +137,10 @@ pub trait Operable {
 }
 
 pub fn operate(input: RadonTypes, call: &RadonCall) -> Result<RadonTypes, RadError> {
-    input.as_operable().operate(call)
+    input
+        .as_operable()
+        .ok_or_else(|| not_operable(&input, call))?
+        .operate(call)
 }
 
 /// This is bound to be a replacement for the original `operate` method.
@@ -136,7 +151,19 @@ pub fn operate_in_context(
     call: &RadonCall,
     context: &mut ReportContext<RadonTypes>,
 ) -> Result<RadonTypes, RadError> {
-    input.as_operable().operate_in_context(call, context)
+    input
+        .as_operable()
+        .ok_or_else(|| not_operable(&input, call))?
+        .operate_in_context(call, context)
+}
+
+/// Error for calls applied on a value that does not support any operator, i.e. a `RadonError`.
+fn not_operable(input: &RadonTypes, call: &RadonCall) -> RadError {
+    RadError::UnsupportedOperator {
+        input_type: input.radon_type_name().to_string(),
+        operator: call.0.to_string(),
+        args: call.1.clone(),
+    }
 }
 
 pub fn identity(input: RadonTypes) -> Result<RadonTypes, RadError> {
@@ -525,12 +552,14 @@ mod legacy {
                 );
                 let result_before = input
                     .as_operable()
+                    .unwrap()
                     .operate_in_context(&call_before, &mut before_context);
                 assert_eq!(result_before, expected_before[index]);
 
                 let call_after = (operator, Some(args));
                 let result_after = input
                     .as_operable()
+                    .unwrap()
                     .operate_in_context(&call_after, &mut after_context);
                 assert_eq!(result_after, expected_after[index]);
             }
@@ -562,4 +591,22 @@ mod tests {
 
         assert_eq!(output, expected);
     }
+
+    #[test]
+    pub fn test_operate_on_error() {
+        let input = RadonTypes::RadonError(
+            witnet_data_structures::radon_error::RadonError::try_from(RadError::NoReveals).unwrap(),
+        );
+        let call = (RadonOpCodes::Identity, None);
+        let output = operate(input, &call).unwrap_err();
+
+        assert_eq!(
+            output,
+            RadError::UnsupportedOperator {
+                input_type: "RadonError".to_string(),
+                operator: RadonOpCodes::Identity.to_string(),
+                args: None,
+            }
+        );
+    }
 }
@@ -0,0 +1,399 @@
+use std::convert::TryFrom;
+
+use num_enum::TryFromPrimitive;
+use serde_cbor::value::Value;
+
+use crate::{
+    error::RadError,
+    types::{
+        array::RadonArray, boolean::RadonBoolean, bytes::RadonBytes, float::RadonFloat,
+        integer::RadonInteger, map::RadonMap, string::RadonString, RadonType, RadonTypes,
+    },
+};
+
+/// Types that the value found at the end of a path can be coerced into. They follow the same
+/// order as the typed getters (e.g. `MapGetArray` to `MapGetString`).
+/// **WARNING: these codes are consensus-critical.**
+#[derive(Copy, Clone, Debug, PartialEq, Eq, TryFromPrimitive)]
+#[repr(u8)]
+pub enum RadonTypeCode {
+    Array = 0x00,
+    Boolean = 0x01,
+    Bytes = 0x02,
+    Float = 0x03,
+    Integer = 0x04,
+    Map = 0x05,
+    String = 0x06,
+}
+
+/// A single step of a path: a key of a `RadonMap` or an index of a `RadonArray`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PathComponent {
+    Key(String),
+    Index(i128),
+}
+
+/// Traverse nested maps and arrays starting from `input`, and coerce the value found at the end
+/// into the requested type.
+///
+/// The first argument is an array of keys (strings) and indices (integers) to follow, and the
+/// second one is a `RadonTypeCode`. For example, `[["data", "items", 0, "price"], 3]` reads
+/// `data.items[0].price` as a `RadonFloat`.
+///
+/// Failures report the part of the path that could be followed before the failing component.
+pub fn get_path(input: RadonTypes, args: &[Value]) -> Result<RadonTypes, RadError> {
+    let (components, type_code) = read_path_args(&input, args)?;
+
+    let mut path = String::new();
+    let mut current = input;
+    for component in components {
+        current = match (current, &component) {
+            (RadonTypes::Map(map), PathComponent::Key(key)) => {
+                map.value()
+                    .remove(key)
+                    .ok_or_else(|| RadError::PathKeyNotFound {
+                        path: path.clone(),
+                        key: key.clone(),
+                    })?
+            }
+            (RadonTypes::Array(array), PathComponent::Index(index)) => {
+                let out_of_bounds = || RadError::PathIndexOutOfBounds {
+                    path: path.clone(),
+                    index: i32::try_from(*index).unwrap_or(i32::MAX),
+                };
+                let position = usize::try_from(*index).map_err(|_| out_of_bounds())?;
+
+                array
+                    .value()
+                    .into_iter()
+                    .nth(position)
+                    .ok_or_else(out_of_bounds)?
+            }
+            (other, PathComponent::Key(_)) => {
+                return Err(RadError::PathTypeMismatch {
+                    path,
+                    expected: RadonMap::radon_type_name(),
+                    found: other.radon_type_name(),
+                });
+            }
+            (other, PathComponent::Index(_)) => {
+                return Err(RadError::PathTypeMismatch {
+                    path,
+                    expected: RadonArray::radon_type_name(),
+                    found: other.radon_type_name(),
+                });
+            }
+        };
+
+        match component {
+            PathComponent::Key(key) if path.is_empty() => path = key,
+            PathComponent::Key(key) => path = format!("{}.{}", path, key),
+            PathComponent::Index(index) => path = format!("{}[{}]", path, index),
+        }
+    }
+
+    match type_code {
+        RadonTypeCode::Array => coerce::<RadonArray, _>(current, path),
+        RadonTypeCode::Boolean => coerce::<RadonBoolean, _>(current, path),
+        RadonTypeCode::Bytes => coerce::<RadonBytes, _>(current, path),
+        RadonTypeCode::Float => coerce::<RadonFloat, _>(current, path),
+        RadonTypeCode::Integer => coerce::<RadonInteger, _>(current, path),
+        RadonTypeCode::Map => coerce::<RadonMap, _>(current, path),
+        RadonTypeCode::String => coerce::<RadonString, _>(current, path),
+    }
+}
+
+fn read_path_args(
+    input: &RadonTypes,
+    args: &[Value],
+) -> Result<(Vec<PathComponent>, RadonTypeCode), RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: input.radon_type_name(),
+        operator: "GetPath".to_string(),
+        args: args.to_vec(),
+    };
+
+    let (components, type_code) = match args {
+        [Value::Array(components), Value::Integer(type_code)] => (components, type_code),
+        _ => return Err(wrong_args()),
+    };
+    let components = components
+        .iter()
+        .map(|component| match component {
+            Value::Text(key) => Ok(PathComponent::Key(key.clone())),
+            Value::Integer(index) => Ok(PathComponent::Index(*index)),
+            _ => Err(wrong_args()),
+        })
+        .collect::<Result<_, _>>()?;
+    let type_code = u8::try_from(*type_code)
+        .ok()
+        .and_then(|code| RadonTypeCode::try_from(code).ok())
+        .ok_or_else(wrong_args)?;
+
+    Ok((components, type_code))
+}
+
+fn coerce<O, T>(value: RadonTypes, path: String) -> Result<RadonTypes, RadError>
+where
+    O: RadonType<T>,
+    T: std::fmt::Debug,
+    RadonTypes: From<O>,
+{
+    let found = value.radon_type_name();
+
+    O::try_from(value)
+        .map(RadonTypes::from)
+        .map_err(|_| RadError::PathTypeMismatch {
+            path,
+            expected: O::radon_type_name(),
+            found,
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::operators::string::parse_json;
+
+    use super::*;
+
+    /// A CoinGecko-like response, with maps and arrays nested several levels deep.
+    const COINS_JSON: &str = r#"{
+        "data": {
+            "items": [
+                {
+                    "id": "bitcoin",
+                    "symbol": "btc",
+                    "quote": {
+                        "USD": { "price": 27123.45, "volume_24h": 13467992651 },
+                        "EUR": { "price": 25630.8, "volume_24h": 12726452218 }
+                    },
+                    "tags": ["pow", "sha-256"]
+                },
+                {
+                    "id": "witnet",
+                    "symbol": "wit",
+                    "quote": {
+                        "USD": { "price": 0.00541, "volume_24h": 46220 }
+                    },
+                    "tags": []
+                }
+            ],
+            "last_updated": "2023-09-25T10:00:00Z"
+        }
+    }"#;
+
+    fn coins() -> RadonTypes {
+        parse_json(&RadonString::from(COINS_JSON)).unwrap()
+    }
+
+    fn path_args(components: Vec<Value>, type_code: RadonTypeCode) -> Vec<Value> {
+        vec![
+            Value::Array(components),
+            Value::Integer(type_code as u8 as i128),
+        ]
+    }
+
+    fn key(key: &str) -> Value {
+        Value::Text(key.to_string())
+    }
+
+    #[test]
+    fn get_nested_float() {
+        let args = path_args(
+            vec![
+                key("data"),
+                key("items"),
+                Value::Integer(0),
+                key("quote"),
+                key("USD"),
+                key("price"),
+            ],
+            RadonTypeCode::Float,
+        );
+
+        assert_eq!(
+            get_path(coins(), &args).unwrap(),
+            RadonTypes::from(RadonFloat::from(27123.45))
+        );
+    }
+
+    #[test]
+    fn get_nested_values_of_every_kind() {
+        let item = vec![key("data"), key("items"), Value::Integer(1)];
+        let with = |extra: &[Value]| [item.as_slice(), extra].concat();
+
+        let symbol = get_path(
+            coins(),
+            &path_args(with(&[key("symbol")]), RadonTypeCode::String),
+        );
+        assert_eq!(symbol.unwrap(), RadonTypes::from(RadonString::from("wit")));
+
+        let volume = get_path(
+            coins(),
+            &path_args(
+                with(&[key("quote"), key("USD"), key("volume_24h")]),
+                RadonTypeCode::Integer,
+            ),
+        );
+        assert_eq!(volume.unwrap(), RadonTypes::from(RadonInteger::from(46220)));
+
+        let tags = get_path(
+            coins(),
+            &path_args(with(&[key("tags")]), RadonTypeCode::Array),
+        );
+        assert_eq!(
+            tags.unwrap(),
+            RadonTypes::from(RadonArray::from(Vec::<RadonTypes>::new()))
+        );
+
+        let quote = get_path(
+            coins(),
+            &path_args(with(&[key("quote")]), RadonTypeCode::Map),
+        );
+        assert!(matches!(quote, Ok(RadonTypes::Map(_))));
+    }
+
+    #[test]
+    fn get_from_array_input() {
+        let items = match get_path(
+            coins(),
+            &path_args(vec![key("data"), key("items")], RadonTypeCode::Array),
+        ) {
+            Ok(items) => items,
+            other => panic!("Expected an array, got {:?}", other),
+        };
+        let args = path_args(
+            vec![Value::Integer(0), key("tags"), Value::Integer(1)],
+            RadonTypeCode::String,
+        );
+
+        assert_eq!(
+            get_path(items, &args).unwrap(),
+            RadonTypes::from(RadonString::from("sha-256"))
+        );
+    }
+
+    #[test]
+    fn empty_path_coerces_input() {
+        let args = path_args(vec![], RadonTypeCode::Map);
+
+        assert_eq!(get_path(coins(), &args).unwrap(), coins());
+    }
+
+    #[test]
+    fn missing_key() {
+        let args = path_args(
+            vec![
+                key("data"),
+                key("items"),
+                Value::Integer(1),
+                key("quote"),
+                key("EUR"),
+                key("price"),
+            ],
+            RadonTypeCode::Float,
+        );
+
+        assert_eq!(
+            get_path(coins(), &args),
+            Err(RadError::PathKeyNotFound {
+                path: "data.items[1].quote".to_string(),
+                key: "EUR".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn index_out_of_bounds() {
+        let args = path_args(
+            vec![key("data"), key("items"), Value::Integer(2), key("id")],
+            RadonTypeCode::String,
+        );
+        assert_eq!(
+            get_path(coins(), &args),
+            Err(RadError::PathIndexOutOfBounds {
+                path: "data.items".to_string(),
+                index: 2,
+            })
+        );
+
+        let args = path_args(
+            vec![key("data"), key("items"), Value::Integer(-1), key("id")],
+            RadonTypeCode::String,
+        );
+        assert_eq!(
+            get_path(coins(), &args),
+            Err(RadError::PathIndexOutOfBounds {
+                path: "data.items".to_string(),
+                index: -1,
+            })
+        );
+    }
+
+    #[test]
+    fn type_mismatch_while_traversing() {
+        // Indexing a map
+        let args = path_args(vec![key("data"), Value::Integer(0)], RadonTypeCode::String);
+        assert_eq!(
+            get_path(coins(), &args),
+            Err(RadError::PathTypeMismatch {
+                path: "data".to_string(),
+                expected: RadonArray::radon_type_name(),
+                found: RadonMap::radon_type_name(),
+            })
+        );
+
+        // Looking up a key in a string
+        let args = path_args(
+            vec![key("data"), key("last_updated"), key("date")],
+            RadonTypeCode::String,
+        );
+        assert_eq!(
+            get_path(coins(), &args),
+            Err(RadError::PathTypeMismatch {
+                path: "data.last_updated".to_string(),
+                expected: RadonMap::radon_type_name(),
+                found: RadonString::radon_type_name(),
+            })
+        );
+    }
+
+    #[test]
+    fn type_mismatch_at_the_end() {
+        let args = path_args(
+            vec![key("data"), key("items"), Value::Integer(0), key("tags")],
+            RadonTypeCode::Float,
+        );
+
+        assert_eq!(
+            get_path(coins(), &args),
+            Err(RadError::PathTypeMismatch {
+                path: "data.items[0].tags".to_string(),
+                expected: RadonFloat::radon_type_name(),
+                found: RadonArray::radon_type_name(),
+            })
+        );
+    }
+
+    #[test]
+    fn wrong_arguments() {
+        let wrong = [
+            vec![],
+            vec![Value::Array(vec![key("data")])],
+            vec![Value::Array(vec![Value::Bool(true)]), Value::Integer(0)],
+            vec![Value::Array(vec![key("data")]), Value::Integer(7)],
+            vec![key("data"), Value::Integer(0)],
+        ];
+
+        for args in wrong {
+            assert!(
+                matches!(
+                    get_path(coins(), &args),
+                    Err(RadError::WrongArguments { .. })
+                ),
+                "{:?}",
+                args
+            );
+        }
+    }
+}
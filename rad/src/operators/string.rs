@@ -4,8 +4,11 @@ use std::{
     str::FromStr,
 };
 
+use num_enum::{IntoPrimitive, TryFromPrimitive};
+use regex::RegexBuilder;
 use serde_cbor::value::{from_value, Value};
 use serde_json::Value as JsonValue;
+use unicode_segmentation::UnicodeSegmentation;
 
 use crate::{
     error::RadError,
@@ -19,6 +22,12 @@ use crate::{
 const MAX_DEPTH: u8 = 20;
 const DEFAULT_THOUSANDS_SEPARATOR: &str = ",";
 const DEFAULT_DECIMAL_SEPARATOR: &str = ".";
+/// Maximum length in bytes of the regular expressions used by `StringExtract`.
+const MAX_REGEX_PATTERN_LENGTH: usize = 256;
+/// Maximum size in bytes of a compiled `StringExtract` regular expression, and of its lazy DFA
+/// cache. The regex engine always runs in linear time, so bounding its size is enough to bound the
+/// cost of matching.
+const MAX_REGEX_SIZE: usize = 1 << 16;
 
 /// Parse `RadonTypes` from a JSON-encoded `RadonString`.
 pub fn parse_json(input: &RadonString) -> Result<RadonTypes, RadError> {
@@ -191,10 +200,60 @@ pub fn as_numeric_string(input: &RadonString, args: &[Value]) -> String {
     replace_separators(str_value, thousands_separator, decimal_separator)
 }
 
+/// Unit in which `StringLength` counts the length of a string, selected by its optional argument
+/// since WIP-0036.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
+pub enum StringLengthUnit {
+    /// Bytes of the UTF-8 encoding of the string, which is how the length is counted when no unit
+    /// is given.
+    Bytes = 0x00,
+    /// Unicode scalar values.
+    Chars = 0x01,
+    /// Extended grapheme clusters, as segmented by the `unicode-segmentation` crate. Its version
+    /// is pinned in `Cargo.toml`, because segmentation depends on the version of Unicode, and every
+    /// node must count the same graphemes.
+    Graphemes = 0x02,
+}
+
+impl Default for StringLengthUnit {
+    fn default() -> Self {
+        StringLengthUnit::Bytes
+    }
+}
+
 pub fn length(input: &RadonString) -> RadonInteger {
     RadonInteger::from(input.value().len() as i128)
 }
 
+/// Counts the length of a string in the unit given by the only optional argument, which is
+/// the code of a `StringLengthUnit`.
+pub fn length_in_units(input: &RadonString, args: &[Value]) -> Result<RadonInteger, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonString::radon_type_name(),
+        operator: "StringLength".to_string(),
+        args: args.to_vec(),
+    };
+
+    let unit = match args {
+        [] => StringLengthUnit::default(),
+        [arg] => from_value::<u8>(arg.clone())
+            .ok()
+            .and_then(|code| StringLengthUnit::try_from(code).ok())
+            .ok_or_else(wrong_args)?,
+        _ => return Err(wrong_args()),
+    };
+
+    let value = input.value();
+    let length = match unit {
+        StringLengthUnit::Bytes => value.len(),
+        StringLengthUnit::Chars => value.chars().count(),
+        StringLengthUnit::Graphemes => value.graphemes(true).count(),
+    };
+
+    Ok(RadonInteger::from(length as i128))
+}
+
 pub fn to_lowercase(input: &RadonString) -> RadonString {
     RadonString::from(input.value().as_str().to_lowercase())
 }
@@ -271,16 +330,69 @@ pub fn string_match(input: &RadonString, args: &[Value]) -> Result<RadonTypes, R
                     Ok(RadonTypes::from(RadonInteger::try_from(res.clone())?))
                 }
                 RadonTypes::Map(_) => Ok(RadonTypes::from(RadonMap::try_from(res.clone())?)),
-                RadonTypes::RadonError(_) => unreachable!(),
-                RadonTypes::String(_) => {
-                    // Handled above
-                    unreachable!();
-                }
+                // Strings are handled above, and defaults are never decoded as errors
+                RadonTypes::RadonError(_) | RadonTypes::String(_) => Err(RadError::Decode {
+                    from: "serde_cbor::value::Value",
+                    to: default.radon_type_name(),
+                }),
             }
         })
         .unwrap_or(Ok(temp_def))
 }
 
+/// Extract a capture group of the first match of a regular expression.
+///
+/// The first argument is the pattern, and the optional second argument is the index of the capture
+/// group to extract, which defaults to `0` (the whole match), so that patterns without groups can
+/// be used. Index `1` extracts the first group.
+pub fn string_extract(input: &RadonString, args: &[Value]) -> Result<RadonString, RadError> {
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonString::radon_type_name(),
+        operator: "StringExtract".to_string(),
+        args: args.to_vec(),
+    };
+
+    let pattern = match args.first() {
+        Some(Value::Text(pattern)) => pattern,
+        _ => return Err(wrong_args()),
+    };
+    let group = match args.get(1) {
+        Some(arg) => from_value::<usize>(arg.clone()).map_err(|_| wrong_args())?,
+        None => 0,
+    };
+    if args.len() > 2 {
+        return Err(wrong_args());
+    }
+
+    if pattern.len() > MAX_REGEX_PATTERN_LENGTH {
+        return Err(RadError::RegexTooComplex {
+            length: pattern.len(),
+        });
+    }
+    let regex = RegexBuilder::new(pattern)
+        .size_limit(MAX_REGEX_SIZE)
+        .dfa_size_limit(MAX_REGEX_SIZE)
+        .build()
+        .map_err(|err| match err {
+            regex::Error::CompiledTooBig(_) => RadError::RegexTooComplex {
+                length: pattern.len(),
+            },
+            err => RadError::InvalidRegex {
+                pattern: pattern.clone(),
+                message: err.to_string(),
+            },
+        })?;
+
+    let value = input.value();
+    regex
+        .captures(&value)
+        .and_then(|captures| captures.get(group))
+        .map(|capture| RadonString::from(capture.as_str()))
+        .ok_or_else(|| RadError::RegexNoMatch {
+            pattern: pattern.clone(),
+        })
+}
+
 /// Replace thousands and decimals separators in a `String`.
 #[inline]
 pub fn replace_separators(
@@ -741,6 +853,63 @@ mod tests {
         assert_eq!(length(&rad_string), RadonInteger::from(5));
     }
 
+    #[test]
+    fn test_string_length_in_units() {
+        // A waving hand with a skin tone modifier, which is one grapheme made of two chars
+        let rad_string = RadonString::from("Hi 👋🏽");
+        let length_in = |unit: StringLengthUnit| {
+            length_in_units(&rad_string, &[Value::Integer(u8::from(unit).into())])
+        };
+
+        assert_eq!(
+            length_in(StringLengthUnit::Bytes),
+            Ok(RadonInteger::from(11))
+        );
+        assert_eq!(
+            length_in(StringLengthUnit::Chars),
+            Ok(RadonInteger::from(5))
+        );
+        assert_eq!(
+            length_in(StringLengthUnit::Graphemes),
+            Ok(RadonInteger::from(4))
+        );
+
+        // Without a unit, the length is counted in bytes, as it was before WIP-0036
+        assert_eq!(
+            length_in_units(&rad_string, &[]),
+            Ok(RadonInteger::from(11))
+        );
+        assert_eq!(length(&rad_string), RadonInteger::from(11));
+    }
+
+    #[test]
+    fn test_string_length_wrong_units() {
+        let rad_string = RadonString::from("Hello");
+
+        for args in [
+            vec![Value::Integer(3)],
+            vec![Value::Integer(-1)],
+            vec![Value::Text("bytes".to_string())],
+            vec![Value::Integer(0), Value::Integer(0)],
+        ] {
+            assert_eq!(
+                length_in_units(&rad_string, &args),
+                Err(RadError::WrongArguments {
+                    input_type: RadonString::radon_type_name(),
+                    operator: "StringLength".to_string(),
+                    args,
+                })
+            );
+        }
+    }
+
+    #[test]
+    fn test_string_length_unicode_version() {
+        // Updating `unicode-segmentation` may change how many graphemes are counted, so it must
+        // be done along with a new WIP
+        assert_eq!(unicode_segmentation::UNICODE_VERSION, (15, 1, 0));
+    }
+
     #[test]
     fn test_string_to_lowercase() {
         let rad_string: RadonString = RadonString::from("HeLlO");
@@ -1253,4 +1422,84 @@ mod tests {
         let expected = (String::from("x"), String::from("y"));
         assert_eq!(separators, expected);
     }
+
+    #[test]
+    fn test_string_extract() {
+        let input = RadonString::from("<span class=\"price\">1,234.56 USD</span>");
+
+        let args = vec![
+            Value::Text(r"price.>([0-9,.]+) ([A-Z]+)<".to_string()),
+            Value::Integer(1),
+        ];
+        let result = string_extract(&input, &args);
+        assert_eq!(result, Ok(RadonString::from("1,234.56")));
+
+        let args = vec![
+            Value::Text(r"price.>([0-9,.]+) ([A-Z]+)<".to_string()),
+            Value::Integer(2),
+        ];
+        let result = string_extract(&input, &args);
+        assert_eq!(result, Ok(RadonString::from("USD")));
+
+        let args = vec![Value::Text(r"[0-9]+".to_string()), Value::Integer(0)];
+        let result = string_extract(&input, &args);
+        assert_eq!(result, Ok(RadonString::from("1")));
+
+        // Without a group, the whole match is extracted
+        let args = vec![Value::Text(r"[0-9,.]+ [A-Z]+".to_string())];
+        let result = string_extract(&input, &args);
+        assert_eq!(result, Ok(RadonString::from("1,234.56 USD")));
+    }
+
+    #[test]
+    fn test_string_extract_no_match() {
+        let input = RadonString::from("temperature: unknown");
+        let args = vec![Value::Text(r"temperature: (-?[0-9]+)".to_string())];
+
+        let result = string_extract(&input, &args);
+        assert_eq!(
+            result,
+            Err(RadError::RegexNoMatch {
+                pattern: r"temperature: (-?[0-9]+)".to_string(),
+            })
+        );
+
+        // A capture group that does not exist never matches
+        let args = vec![Value::Text(r"temperature".to_string()), Value::Integer(1)];
+        let result = string_extract(&input, &args);
+        assert!(matches!(result, Err(RadError::RegexNoMatch { .. })));
+    }
+
+    #[test]
+    fn test_string_extract_invalid_pattern() {
+        let input = RadonString::from("abc");
+
+        let args = vec![Value::Text("(abc".to_string())];
+        let result = string_extract(&input, &args);
+        assert!(matches!(result, Err(RadError::InvalidRegex { .. })));
+
+        let args = vec![Value::Integer(1)];
+        let result = string_extract(&input, &args);
+        assert!(matches!(result, Err(RadError::WrongArguments { .. })));
+    }
+
+    #[test]
+    fn test_string_extract_complexity_cap() {
+        let input = RadonString::from("aaaa");
+
+        // Too long pattern
+        let args = vec![Value::Text("a".repeat(MAX_REGEX_PATTERN_LENGTH + 1))];
+        let result = string_extract(&input, &args);
+        assert_eq!(
+            result,
+            Err(RadError::RegexTooComplex {
+                length: MAX_REGEX_PATTERN_LENGTH + 1,
+            })
+        );
+
+        // Short pattern whose compiled program exceeds the size limit
+        let args = vec![Value::Text(r"(\w{100}){100}".to_string())];
+        let result = string_extract(&input, &args);
+        assert!(matches!(result, Err(RadError::RegexTooComplex { .. })));
+    }
 }
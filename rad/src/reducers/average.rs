@@ -1,4 +1,6 @@
-use std::ops::Div;
+use std::{convert::TryFrom, ops::Div};
+
+use num_enum::{IntoPrimitive, TryFromPrimitive};
 
 use crate::{
     error::RadError,
@@ -9,12 +11,25 @@ use crate::{
 
 /// Different available policies regarding what to do with the resulting Float after applying the
 /// average mean.
-#[derive(Clone, Copy, PartialEq, Eq)]
+///
+/// After WIP-0028, the policy used by the `AverageMean` and `AverageMedian` reducers can be chosen
+/// by passing its code as the second argument of the `ArrayReduce` operator, and that of the
+/// `AverageMeanTrimmed` reducer as the third one.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[repr(u8)]
 pub enum MeanReturnPolicy {
-    /// Pure `Fn(Array<T>) -> Float` behavior.
-    ReturnFloat,
     /// Enforces `Fn(Array<T>) -> T` behavior (if needed).
-    RoundToInteger,
+    RoundToInteger = 0x00,
+    /// Pure `Fn(Array<T>) -> Float` behavior, i.e. integers are promoted to float.
+    ReturnFloat = 0x01,
+    /// Enforces `Fn(Array<T>) -> T` behavior, failing if the result is not an exact integer.
+    ErrorOnFraction = 0x02,
+}
+
+impl Default for MeanReturnPolicy {
+    fn default() -> Self {
+        MeanReturnPolicy::RoundToInteger
+    }
 }
 
 /// Computes the average mean of the values found in a `RadonArray`.
@@ -25,7 +40,9 @@ pub enum MeanReturnPolicy {
 ///
 /// Depending on the `return_policy` parameter, it will enforce the reducer contract, i.e.
 /// `Fn(Array<T>) -> T`. In other words, if the input was `Integer`, and the policy is
-/// `RoundToInteger` it will round the final `Float` so the input and output types do match.
+/// `RoundToInteger` it will round the final `Float` so the input and output types do match. With
+/// `ErrorOnFraction`, the input and output types also match, but instead of rounding the final
+/// `Float`, a `FractionalResult` error is returned if it has a fractional part.
 ///
 /// The standard behavior for the `AverageMean` reducer is with `RoundToInteger`. However, the
 /// `DeviationStandard` reducer should call it with `ReturnFloat`.
@@ -95,14 +112,20 @@ pub fn mean(input: &RadonArray, return_policy: MeanReturnPolicy) -> Result<Radon
             let float_mean = RadonFloat::from(sum.div(value_len as f64));
 
             // In `RoundToInteger` mode, round the float so as to satisfy the reducers' inherent
-            // contract.
-            let mean = if return_policy == MeanReturnPolicy::RoundToInteger {
-                RadonTypes::from(float_operators::round(&float_mean))
-            } else {
-                RadonTypes::from(float_mean)
-            };
-
-            Ok(mean)
+            // contract. In `ErrorOnFraction` mode, only exact integers satisfy it.
+            match return_policy {
+                MeanReturnPolicy::RoundToInteger => {
+                    Ok(RadonTypes::from(float_operators::round(&float_mean)))
+                }
+                MeanReturnPolicy::ReturnFloat => Ok(RadonTypes::from(float_mean)),
+                MeanReturnPolicy::ErrorOnFraction if float_mean.value().fract() == 0.0 => {
+                    Ok(RadonTypes::from(float_operators::round(&float_mean)))
+                }
+                MeanReturnPolicy::ErrorOnFraction => Err(RadError::FractionalResult {
+                    reducer: RadonReducers::AverageMean.to_string(),
+                    value: float_mean.value(),
+                }),
+            }
         }
         Some(RadonTypes::Array(_)) => {
             let v = array_operators::transpose(input)?;
@@ -112,7 +135,11 @@ pub fn mean(input: &RadonArray, return_policy: MeanReturnPolicy) -> Result<Radon
                 if let RadonTypes::Array(v2mean) = v2mean {
                     mean_v.push(mean(&v2mean, return_policy)?);
                 } else {
-                    unreachable!()
+                    return Err(RadError::MismatchingTypes {
+                        method: RadonReducers::AverageMean.to_string(),
+                        expected: RadonArray::radon_type_name(),
+                        found: v2mean.radon_type_name(),
+                    });
                 }
             }
 
@@ -125,6 +152,44 @@ pub fn mean(input: &RadonArray, return_policy: MeanReturnPolicy) -> Result<Radon
     }
 }
 
+/// Computes the average mean of the values found in a `RadonArray`, ignoring the `trim` lowest
+/// and the `trim` highest of them.
+///
+/// The input must be an array of RadonIntegers or RadonFloats, and the `return_policy` applies as
+/// in `mean`. If there are no values left after trimming, the result is the mean of an empty
+/// array, that is, NaN.
+pub fn trimmed_mean(
+    input: &RadonArray,
+    trim: u32,
+    return_policy: MeanReturnPolicy,
+) -> Result<RadonTypes, RadError> {
+    let mut sorted = input.value();
+    match sorted.first() {
+        None | Some(RadonTypes::Float(_)) | Some(RadonTypes::Integer(_)) => {}
+        Some(_rad_types) => {
+            return Err(RadError::UnsupportedReducer {
+                array: input.clone(),
+                reducer: RadonReducers::AverageMeanTrimmed.to_string(),
+            })
+        }
+    }
+    // Mismatching items are left unsorted, as `mean` rejects them anyway
+    sorted.sort_by(|a, b| match (a, b) {
+        (RadonTypes::Float(a), RadonTypes::Float(b)) => a.value().total_cmp(&b.value()),
+        (RadonTypes::Integer(a), RadonTypes::Integer(b)) => a.value().cmp(&b.value()),
+        _ => std::cmp::Ordering::Equal,
+    });
+
+    let trim = usize::try_from(trim).unwrap_or(usize::MAX);
+    let kept = if sorted.len() > trim.saturating_mul(2) {
+        sorted[trim..sorted.len() - trim].to_vec()
+    } else {
+        vec![]
+    };
+
+    mean(&RadonArray::from(kept), return_policy)
+}
+
 #[cfg(test)]
 mod tests {
     use crate::types::{float::RadonFloat, integer::RadonInteger, string::RadonString};
@@ -320,4 +385,147 @@ mod tests {
 
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_average_mean_integer_policies_exact() {
+        let input = RadonArray::from(vec![
+            RadonInteger::from(1i128).into(),
+            RadonInteger::from(3i128).into(),
+        ]);
+
+        assert_eq!(
+            mean(&input, MeanReturnPolicy::RoundToInteger).unwrap(),
+            RadonTypes::from(RadonInteger::from(2))
+        );
+        assert_eq!(
+            mean(&input, MeanReturnPolicy::ReturnFloat).unwrap(),
+            RadonTypes::from(RadonFloat::from(2f64))
+        );
+        assert_eq!(
+            mean(&input, MeanReturnPolicy::ErrorOnFraction).unwrap(),
+            RadonTypes::from(RadonInteger::from(2))
+        );
+    }
+
+    #[test]
+    fn test_average_mean_integer_policies_fraction() {
+        let input = RadonArray::from(vec![
+            RadonInteger::from(1i128).into(),
+            RadonInteger::from(2i128).into(),
+        ]);
+
+        assert_eq!(
+            mean(&input, MeanReturnPolicy::RoundToInteger).unwrap(),
+            RadonTypes::from(RadonInteger::from(2))
+        );
+        assert_eq!(
+            mean(&input, MeanReturnPolicy::ReturnFloat).unwrap(),
+            RadonTypes::from(RadonFloat::from(1.5f64))
+        );
+        assert_eq!(
+            mean(&input, MeanReturnPolicy::ErrorOnFraction).unwrap_err(),
+            RadError::FractionalResult {
+                reducer: RadonReducers::AverageMean.to_string(),
+                value: 1.5,
+            }
+        );
+    }
+
+    #[test]
+    fn test_average_mean_float_ignores_policy() {
+        let input = RadonArray::from(vec![
+            RadonFloat::from(1f64).into(),
+            RadonFloat::from(2f64).into(),
+        ]);
+        let expected = RadonTypes::from(RadonFloat::from(1.5f64));
+
+        assert_eq!(
+            mean(&input, MeanReturnPolicy::ErrorOnFraction).unwrap(),
+            expected
+        );
+    }
+
+    #[test]
+    fn test_mean_return_policy_codes() {
+        use std::convert::TryFrom;
+
+        assert_eq!(
+            MeanReturnPolicy::default(),
+            MeanReturnPolicy::RoundToInteger
+        );
+        assert_eq!(
+            MeanReturnPolicy::try_from(0u8),
+            Ok(MeanReturnPolicy::RoundToInteger)
+        );
+        assert_eq!(
+            MeanReturnPolicy::try_from(1u8),
+            Ok(MeanReturnPolicy::ReturnFloat)
+        );
+        assert_eq!(
+            MeanReturnPolicy::try_from(2u8),
+            Ok(MeanReturnPolicy::ErrorOnFraction)
+        );
+        assert!(MeanReturnPolicy::try_from(3u8).is_err());
+    }
+
+    #[test]
+    fn test_trimmed_mean() {
+        let input = RadonArray::from(vec![
+            RadonInteger::from(100).into(),
+            RadonInteger::from(1).into(),
+            RadonInteger::from(-50).into(),
+            RadonInteger::from(2).into(),
+            RadonInteger::from(4).into(),
+        ]);
+
+        assert_eq!(
+            trimmed_mean(&input, 1, MeanReturnPolicy::ReturnFloat),
+            Ok(RadonTypes::from(RadonFloat::from(7f64 / 3f64)))
+        );
+        assert_eq!(
+            trimmed_mean(&input, 2, MeanReturnPolicy::RoundToInteger),
+            Ok(RadonTypes::from(RadonInteger::from(2)))
+        );
+        assert_eq!(
+            trimmed_mean(&input, 0, MeanReturnPolicy::RoundToInteger),
+            mean(&input, MeanReturnPolicy::RoundToInteger)
+        );
+
+        let input = RadonArray::from(vec![
+            RadonFloat::from(1.5).into(),
+            RadonFloat::from(-1000f64).into(),
+            RadonFloat::from(2.5).into(),
+            RadonFloat::from(1000f64).into(),
+        ]);
+        assert_eq!(
+            trimmed_mean(&input, 1, MeanReturnPolicy::RoundToInteger),
+            Ok(RadonTypes::from(RadonFloat::from(2f64)))
+        );
+    }
+
+    #[test]
+    fn test_trimmed_mean_trims_everything() {
+        let input = RadonArray::from(vec![
+            RadonFloat::from(1f64).into(),
+            RadonFloat::from(2f64).into(),
+        ]);
+
+        match trimmed_mean(&input, 1, MeanReturnPolicy::RoundToInteger).unwrap() {
+            RadonTypes::Float(float) => assert!(float.value().is_nan()),
+            other => panic!("Expected a float, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_trimmed_mean_unsupported_type() {
+        let input = RadonArray::from(vec![RadonString::from("foo").into()]);
+
+        assert_eq!(
+            trimmed_mean(&input, 0, MeanReturnPolicy::RoundToInteger),
+            Err(RadError::UnsupportedReducer {
+                array: input.clone(),
+                reducer: RadonReducers::AverageMeanTrimmed.to_string(),
+            })
+        );
+    }
 }
@@ -10,6 +10,10 @@ use crate::{
 use std::ops::Div;
 
 /// Population standard deviation
+///
+/// The result is always promoted to `Float`, even for arrays of `Integer` with an exact integer
+/// deviation, so this reducer is not affected by the `MeanReturnPolicy` of the `ArrayReduce`
+/// operator.
 // FIXME: Allow for now, since there is no safe cast function from a usize to float yet
 #[allow(clippy::cast_precision_loss)]
 pub fn standard(input: &RadonArray) -> Result<RadonTypes, RadError> {
@@ -23,7 +27,11 @@ pub fn standard(input: &RadonArray) -> Result<RadonTypes, RadError> {
             let mean_float = if let RadonTypes::Float(f) = mean_value {
                 f
             } else {
-                unreachable!()
+                return Err(RadError::MismatchingTypes {
+                    method: RadonReducers::AverageMean.to_string(),
+                    expected: RadonFloat::radon_type_name(),
+                    found: mean_value.radon_type_name(),
+                });
             };
 
             let sum_of_deviations_squared =
@@ -51,7 +59,11 @@ pub fn standard(input: &RadonArray) -> Result<RadonTypes, RadError> {
             let mean_float = if let RadonTypes::Float(f) = mean_value {
                 f
             } else {
-                unreachable!()
+                return Err(RadError::MismatchingTypes {
+                    method: RadonReducers::AverageMean.to_string(),
+                    expected: RadonFloat::radon_type_name(),
+                    found: mean_value.radon_type_name(),
+                });
             };
 
             let sum_of_deviations_squared =
@@ -82,7 +94,11 @@ pub fn standard(input: &RadonArray) -> Result<RadonTypes, RadError> {
                 if let RadonTypes::Array(v2std) = v2std {
                     mean_v.push(standard(&v2std)?);
                 } else {
-                    unreachable!()
+                    return Err(RadError::MismatchingTypes {
+                        method: RadonReducers::DeviationStandard.to_string(),
+                        expected: RadonArray::radon_type_name(),
+                        found: v2std.radon_type_name(),
+                    });
                 }
             }
 
@@ -258,6 +274,19 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_deviation_standard_integer_exact_is_float() {
+        let input = &RadonArray::from(vec![
+            RadonInteger::from(1i128).into(),
+            RadonInteger::from(3i128).into(),
+        ]);
+        let expected = RadonTypes::from(RadonFloat::from(1f64));
+
+        let output = standard(input).unwrap();
+
+        assert_eq!(output, expected);
+    }
+
     #[test]
     fn test_deviation_standard_integer_arrays() {
         let array_1 = RadonTypes::from(RadonArray::from(vec![
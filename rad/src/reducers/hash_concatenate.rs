@@ -43,8 +43,7 @@ pub fn hash_concatenate(input: &RadonArray) -> Result<RadonTypes, RadError> {
 
             let hash_function = [Value::from(u8::from(RadonHashFunctions::SHA2_256))];
             let radon_bytes =
-                bytes_operators::hash(&RadonBytes::from(concatenated_bytes), &hash_function)
-                    .unwrap();
+                bytes_operators::hash(&RadonBytes::from(concatenated_bytes), &hash_function)?;
 
             Ok(RadonTypes::from(radon_bytes))
         }
@@ -13,7 +13,13 @@ use ordered_float::NotNan;
 /// The input will be sorted using the ArraySort operator.
 /// The input must be an array of RadonIntegers or RadonFloats. Any RadonFloats set to NaN will be
 /// ignored for this operation.
-pub fn median(input: &RadonArray) -> Result<RadonTypes, RadError> {
+///
+/// The `return_policy` only applies to arrays of RadonIntegers, and decides the type of the
+/// result as in the `AverageMean` reducer: `ReturnFloat` always returns a RadonFloat, even if the
+/// median is one of the elements of the input.
+// FIXME: Allow for now, since there is no safe cast function from an i128 to float yet
+#[allow(clippy::cast_precision_loss)]
+pub fn median(input: &RadonArray, return_policy: MeanReturnPolicy) -> Result<RadonTypes, RadError> {
     let value = input.value();
     let value_len = value.len();
 
@@ -71,7 +77,13 @@ pub fn median(input: &RadonArray) -> Result<RadonTypes, RadError> {
             let sorted_input =
                 match array_operators::sort(input, &[], &mut ReportContext::default()) {
                     Ok(RadonTypes::Array(arr)) => arr.value(),
-                    Ok(_different_type) => unreachable!(),
+                    Ok(different_type) => {
+                        return Err(RadError::MismatchingTypes {
+                            method: RadonReducers::AverageMedian.to_string(),
+                            expected: RadonArray::radon_type_name(),
+                            found: different_type.radon_type_name(),
+                        })
+                    }
                     Err(e) => return Err(e),
                 };
 
@@ -82,7 +94,12 @@ pub fn median(input: &RadonArray) -> Result<RadonTypes, RadError> {
                 // Odd number of elements: take element at floor(N/2):
                 let median_pos = sorted_input.len() / 2;
 
-                Ok(sorted_input[median_pos].clone())
+                match (&sorted_input[median_pos], return_policy) {
+                    (RadonTypes::Integer(i), MeanReturnPolicy::ReturnFloat) => {
+                        Ok(RadonTypes::Float(RadonFloat::from(i.value() as f64)))
+                    }
+                    (median_elem, _) => Ok(median_elem.clone()),
+                }
             } else {
                 // Even number of elements: take average of element at (N/2 - 1) and N/2
                 let right_pos = sorted_input.len() / 2;
@@ -95,7 +112,13 @@ pub fn median(input: &RadonArray) -> Result<RadonTypes, RadError> {
                 ]);
                 // RoundToInteger means that when the average is not an integer, it will be rounded to an
                 // integer. For example, the average of 1 and 2, which is 1.5, will be rounded to 2.
-                mean(&rl, MeanReturnPolicy::RoundToInteger)
+                mean(&rl, return_policy).map_err(|e| match e {
+                    RadError::FractionalResult { value, .. } => RadError::FractionalResult {
+                        reducer: RadonReducers::AverageMedian.to_string(),
+                        value,
+                    },
+                    e => e,
+                })
             }
         }
         Some(_rad_types) => Err(RadError::UnsupportedReducer {
@@ -117,7 +140,7 @@ mod tests {
     #[test]
     fn test_operate_reduce_median_empty() {
         let input = RadonArray::from(vec![]);
-        let output = median(&input).unwrap_err();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap_err();
         let expected_error = EmptyArray;
         assert_eq!(output, expected_error);
     }
@@ -130,7 +153,7 @@ mod tests {
             RadonFloat::from(2f64).into(),
         ]);
         let expected = RadonTypes::from(RadonFloat::from(2f64));
-        let output = median(&input).unwrap();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap();
         assert_eq!(output, expected);
     }
 
@@ -142,7 +165,7 @@ mod tests {
         ]);
 
         let expected = RadonTypes::from(RadonFloat::from(1.5f64));
-        let output = median(&input).unwrap();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap();
         assert_eq!(output, expected);
     }
 
@@ -155,7 +178,7 @@ mod tests {
         ]);
 
         let expected = RadonTypes::from(RadonFloat::from(1.5f64));
-        let output = median(&input).unwrap();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap();
         assert_eq!(output, expected);
     }
 
@@ -166,7 +189,7 @@ mod tests {
             RadonFloat::from(f64::NAN).into(),
         ]);
 
-        let output = median(&input).unwrap_err();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap_err();
         let expected_error = EmptyArray;
         assert_eq!(output, expected_error);
     }
@@ -179,7 +202,7 @@ mod tests {
             RadonInteger::from(2i128).into(),
         ]);
         let expected = RadonTypes::from(RadonInteger::from(2i128));
-        let output = median(&input).unwrap();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap();
         assert_eq!(output, expected);
     }
 
@@ -190,7 +213,7 @@ mod tests {
             RadonInteger::from(1i128).into(),
             RadonInteger::from(2i128).into(),
         ]);
-        let output = median(&input).unwrap();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap();
         let expected = RadonTypes::from(RadonInteger::from(2i128));
         assert_eq!(output, expected);
     }
@@ -205,7 +228,7 @@ mod tests {
             RadonInteger::from(3i128).into(),
         ]);
         let expected = RadonTypes::from(RadonInteger::from(3i128));
-        let output = median(&input).unwrap();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap();
         assert_eq!(output, expected);
     }
 
@@ -216,7 +239,7 @@ mod tests {
             RadonString::from("Hello world!").into(),
             RadonString::from("Hello world!").into(),
         ]);
-        let output = median(&input).unwrap_err();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap_err();
         let expected_error = RadError::UnsupportedReducer {
             array: input,
             reducer: "RadonReducers::AverageMedian".to_string(),
@@ -230,7 +253,7 @@ mod tests {
             RadonString::from("Bye world!").into(),
             RadonString::from("Hello world!").into(),
         ]);
-        let output = median(&input).unwrap_err();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap_err();
         let expected_error = RadError::UnsupportedReducer {
             array: input,
             reducer: "RadonReducers::AverageMedian".to_string(),
@@ -265,7 +288,7 @@ mod tests {
             array3.into(),
         ]);
 
-        let output = median(&input).unwrap_err();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap_err();
         let expected_error = RadError::UnsupportedReducer {
             array: input,
             reducer: "RadonReducers::AverageMedian".to_string(),
@@ -282,7 +305,59 @@ mod tests {
         ]);
 
         let expected = RadonTypes::from(RadonInteger::from(18446744073709551616));
-        let output = median(&input).unwrap();
+        let output = median(&input, MeanReturnPolicy::RoundToInteger).unwrap();
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_operate_reduce_median_int_policies_exact() {
+        let odd = RadonArray::from(vec![
+            RadonInteger::from(1i128).into(),
+            RadonInteger::from(2i128).into(),
+            RadonInteger::from(5i128).into(),
+        ]);
+        let even = RadonArray::from(vec![
+            RadonInteger::from(1i128).into(),
+            RadonInteger::from(3i128).into(),
+        ]);
+
+        for input in &[odd, even] {
+            assert_eq!(
+                median(input, MeanReturnPolicy::RoundToInteger).unwrap(),
+                RadonTypes::from(RadonInteger::from(2i128))
+            );
+            assert_eq!(
+                median(input, MeanReturnPolicy::ReturnFloat).unwrap(),
+                RadonTypes::from(RadonFloat::from(2f64))
+            );
+            assert_eq!(
+                median(input, MeanReturnPolicy::ErrorOnFraction).unwrap(),
+                RadonTypes::from(RadonInteger::from(2i128))
+            );
+        }
+    }
+
+    #[test]
+    fn test_operate_reduce_median_int_policies_fraction() {
+        let input = RadonArray::from(vec![
+            RadonInteger::from(1i128).into(),
+            RadonInteger::from(2i128).into(),
+        ]);
+
+        assert_eq!(
+            median(&input, MeanReturnPolicy::RoundToInteger).unwrap(),
+            RadonTypes::from(RadonInteger::from(2i128))
+        );
+        assert_eq!(
+            median(&input, MeanReturnPolicy::ReturnFloat).unwrap(),
+            RadonTypes::from(RadonFloat::from(1.5f64))
+        );
+        assert_eq!(
+            median(&input, MeanReturnPolicy::ErrorOnFraction).unwrap_err(),
+            RadError::FractionalResult {
+                reducer: RadonReducers::AverageMedian.to_string(),
+                value: 1.5,
+            }
+        );
+    }
 }
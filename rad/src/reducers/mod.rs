@@ -1,6 +1,7 @@
-use std::fmt;
+use std::{convert::TryFrom, fmt, iter};
 
 use num_enum::{IntoPrimitive, TryFromPrimitive};
+use serde_cbor::value::{from_value, Value};
 
 use crate::{
     error::RadError,
@@ -14,7 +15,7 @@ pub mod hash_concatenate;
 pub mod median;
 pub mod mode;
 
-#[derive(Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromPrimitive, IntoPrimitive)]
 #[repr(u8)]
 pub enum RadonReducers {
     // Implemented
@@ -23,6 +24,7 @@ pub enum RadonReducers {
     AverageMedian = 0x05,
     DeviationStandard = 0x07,
     HashConcatenate = 0x0b,
+    AverageMeanTrimmed = 0x0c,
 
     // Not implemented
     Min = 0x00,
@@ -40,9 +42,21 @@ impl fmt::Display for RadonReducers {
     }
 }
 
+/// Applies a reducer to a `RadonArray`.
+///
+/// The `args` are the arguments that follow the reducer code in the `ArrayReduce` operator:
+/// - `AverageMean` and `AverageMedian` take an optional `MeanReturnPolicy` that decides the type
+///   of their result when applied to arrays of integers. Before WIP-0028, it is always
+///   `RoundToInteger`.
+/// - `AverageMeanTrimmed` takes the number of values to discard from each end of the sorted input,
+///   followed by an optional `MeanReturnPolicy`.
+/// - The other reducers take no arguments.
+///
+/// Before WIP-0035, unexpected arguments are ignored instead of rejected.
 pub fn reduce(
     input: &RadonArray,
     reducer_code: RadonReducers,
+    args: &[Value],
     context: &mut ReportContext<RadonTypes>,
 ) -> Result<RadonTypes, RadError> {
     let error = || {
@@ -51,24 +65,69 @@ pub fn reduce(
             reducer: reducer_code.to_string(),
         })
     };
+    let wrong_args = || RadError::WrongArguments {
+        input_type: RadonArray::radon_type_name(),
+        operator: "Reduce".to_string(),
+        args: iter::once(Value::Integer(u8::from(reducer_code).into()))
+            .chain(args.iter().cloned())
+            .collect(),
+    };
+
+    let strict = matches!(&context.active_wips, Some(active_wips) if active_wips.wip0035());
+    let max_args = |max: usize| {
+        if strict && args.len() > max {
+            Err(wrong_args())
+        } else {
+            Ok(())
+        }
+    };
+    let return_policy = |position: usize| match args.get(position) {
+        Some(arg) => {
+            let policy_integer = from_value::<u8>(arg.to_owned()).map_err(|_| wrong_args())?;
+            average::MeanReturnPolicy::try_from(policy_integer).map_err(|_| wrong_args())
+        }
+        None => Ok(average::MeanReturnPolicy::default()),
+    };
 
     if input.is_homogeneous() || input.value().is_empty() {
         match reducer_code {
             RadonReducers::AverageMean => {
-                average::mean(input, average::MeanReturnPolicy::RoundToInteger)
+                max_args(1)?;
+                average::mean(input, return_policy(0)?)
+            }
+            RadonReducers::Mode => {
+                max_args(0)?;
+                mode::mode(input)
+            }
+            RadonReducers::DeviationStandard => {
+                max_args(0)?;
+                deviation::standard(input)
             }
-            RadonReducers::Mode => mode::mode(input),
-            RadonReducers::DeviationStandard => deviation::standard(input),
             RadonReducers::AverageMedian => match &context.active_wips {
-                Some(active_wips) if active_wips.wip0017() => median::median(input),
+                Some(active_wips) if active_wips.wip0017() => {
+                    max_args(1)?;
+                    median::median(input, return_policy(0)?)
+                }
                 _ => error(),
             },
             RadonReducers::HashConcatenate => match &context.active_wips {
                 Some(active_wips) if active_wips.wip0019() => {
+                    max_args(0)?;
                     hash_concatenate::hash_concatenate(input)
                 }
                 _ => error(),
             },
+            RadonReducers::AverageMeanTrimmed => match &context.active_wips {
+                Some(active_wips) if active_wips.wip0035() => {
+                    if args.is_empty() {
+                        return Err(wrong_args());
+                    }
+                    max_args(2)?;
+                    let trim = from_value::<u32>(args[0].to_owned()).map_err(|_| wrong_args())?;
+                    average::trimmed_mean(input, trim, return_policy(1)?)
+                }
+                _ => error(),
+            },
             _ => error(),
         }
     } else {
@@ -81,11 +140,12 @@ pub fn reduce(
 #[cfg(test)]
 mod tests {
     use crate::{
-        current_active_wips,
+        all_wips_active, current_active_wips,
         error::RadError,
         reducers::{reduce, RadonReducers},
-        types::{array::RadonArray, float::RadonFloat, RadonTypes},
+        types::{array::RadonArray, float::RadonFloat, integer::RadonInteger, RadonTypes},
     };
+    use serde_cbor::Value;
     use witnet_data_structures::radon_report::ReportContext;
 
     #[test]
@@ -99,6 +159,7 @@ mod tests {
         let output = reduce(
             input,
             RadonReducers::AverageMean,
+            &[],
             &mut ReportContext::default(),
         )
         .unwrap();
@@ -117,6 +178,7 @@ mod tests {
         let output = reduce(
             input,
             RadonReducers::DeviationStandard,
+            &[],
             &mut ReportContext::default(),
         )
         .unwrap();
@@ -137,7 +199,7 @@ mod tests {
         ]);
 
         let expected = RadonTypes::from(RadonFloat::from(2f64));
-        let output = reduce(input, RadonReducers::AverageMedian, &mut context).unwrap();
+        let output = reduce(input, RadonReducers::AverageMedian, &[], &mut context).unwrap();
 
         assert_eq!(output, expected);
     }
@@ -156,7 +218,7 @@ mod tests {
         ]);
 
         let expected = RadonTypes::from(RadonFloat::from(2f64));
-        let output = reduce(input, RadonReducers::AverageMedian, &mut context).unwrap();
+        let output = reduce(input, RadonReducers::AverageMedian, &[], &mut context).unwrap();
 
         assert_eq!(output, expected);
 
@@ -170,7 +232,7 @@ mod tests {
             array: input.clone(),
             reducer: "RadonReducers::AverageMedian".to_string(),
         };
-        let output = reduce(input, RadonReducers::AverageMedian, &mut context).unwrap_err();
+        let output = reduce(input, RadonReducers::AverageMedian, &[], &mut context).unwrap_err();
 
         assert_eq!(output, expected_err);
     }
@@ -183,7 +245,162 @@ mod tests {
             RadonFloat::from(2f64).into(),
         ]);
         let expected = RadonTypes::from(RadonFloat::from(2f64));
-        let output = reduce(input, RadonReducers::Mode, &mut ReportContext::default()).unwrap();
+        let output = reduce(
+            input,
+            RadonReducers::Mode,
+            &[],
+            &mut ReportContext::default(),
+        )
+        .unwrap();
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_reduce_integer_return_policies() {
+        let mut context = ReportContext {
+            active_wips: Some(current_active_wips()),
+            ..Default::default()
+        };
+        let input = &RadonArray::from(vec![
+            RadonInteger::from(1i128).into(),
+            RadonInteger::from(2i128).into(),
+        ]);
+
+        for reducer in [RadonReducers::AverageMean, RadonReducers::AverageMedian] {
+            let output = reduce(input, reducer, &[], &mut context);
+            assert_eq!(output, Ok(RadonTypes::from(RadonInteger::from(2i128))));
+
+            let output = reduce(input, reducer, &[Value::Integer(1)], &mut context);
+            assert_eq!(output, Ok(RadonTypes::from(RadonFloat::from(1.5f64))));
+
+            let output = reduce(input, reducer, &[Value::Integer(2)], &mut context);
+            assert!(matches!(output, Err(RadError::FractionalResult { .. })));
+        }
+
+        // The standard deviation is always a float
+        let output = reduce(
+            input,
+            RadonReducers::DeviationStandard,
+            &[Value::Integer(2)],
+            &mut context,
+        );
+        assert_eq!(output, Ok(RadonTypes::from(RadonFloat::from(0.5f64))));
+    }
+
+    #[test]
+    fn test_reduce_unexpected_arguments() {
+        let input = &RadonArray::from(vec![
+            RadonInteger::from(1i128).into(),
+            RadonInteger::from(2i128).into(),
+        ]);
+        let reducer_args = [
+            (RadonReducers::Mode, vec![Value::Integer(0)]),
+            (RadonReducers::DeviationStandard, vec![Value::Integer(0)]),
+            (
+                RadonReducers::AverageMean,
+                vec![Value::Integer(0), Value::Integer(0)],
+            ),
+            (
+                RadonReducers::AverageMedian,
+                vec![Value::Integer(0), Value::Integer(0)],
+            ),
+            (
+                RadonReducers::AverageMeanTrimmed,
+                vec![Value::Integer(0), Value::Integer(0), Value::Integer(0)],
+            ),
+        ];
+
+        // After WIP-0035, every reducer rejects the arguments that it does not expect
+        let mut context = ReportContext {
+            active_wips: Some(all_wips_active()),
+            ..Default::default()
+        };
+        for (reducer, args) in &reducer_args {
+            let output = reduce(input, *reducer, args, &mut context);
+            assert!(
+                matches!(output, Err(RadError::WrongArguments { .. })),
+                "{} accepted {:?}: {:?}",
+                reducer,
+                args,
+                output
+            );
+        }
+
+        // Before that, they are ignored
+        let mut context = ReportContext {
+            active_wips: Some(current_active_wips()),
+            ..Default::default()
+        };
+        for (reducer, args) in &reducer_args[..4] {
+            let output = reduce(input, *reducer, args, &mut context);
+            assert!(
+                output.is_ok(),
+                "{} rejected {:?}: {:?}",
+                reducer,
+                args,
+                output
+            );
+        }
+    }
+
+    #[test]
+    fn test_reduce_average_mean_trimmed() {
+        let mut active_wips = all_wips_active();
+        let mut context = ReportContext {
+            active_wips: Some(active_wips.clone()),
+            ..Default::default()
+        };
+        let input = &RadonArray::from(vec![
+            RadonInteger::from(1i128).into(),
+            RadonInteger::from(2i128).into(),
+            RadonInteger::from(3i128).into(),
+            RadonInteger::from(100i128).into(),
+            RadonInteger::from(-50i128).into(),
+        ]);
+
+        let output = reduce(
+            input,
+            RadonReducers::AverageMeanTrimmed,
+            &[Value::Integer(1)],
+            &mut context,
+        );
+        assert_eq!(output, Ok(RadonTypes::from(RadonInteger::from(2i128))));
+
+        let output = reduce(
+            input,
+            RadonReducers::AverageMeanTrimmed,
+            &[Value::Integer(0), Value::Integer(1)],
+            &mut context,
+        );
+        assert_eq!(output, Ok(RadonTypes::from(RadonFloat::from(11.2f64))));
+
+        // The number of values to trim is mandatory and cannot be negative
+        for args in [vec![], vec![Value::Integer(-1)], vec![Value::Float(1.0)]] {
+            let output = reduce(
+                input,
+                RadonReducers::AverageMeanTrimmed,
+                &args,
+                &mut context,
+            );
+            assert!(matches!(output, Err(RadError::WrongArguments { .. })));
+        }
+
+        // Deactivate WIP-0035
+        active_wips.active_wips.remove(&"WIP0035".to_string());
+        context.active_wips = Some(active_wips);
+
+        let output = reduce(
+            input,
+            RadonReducers::AverageMeanTrimmed,
+            &[Value::Integer(1)],
+            &mut context,
+        );
+        assert_eq!(
+            output,
+            Err(RadError::UnsupportedReducer {
+                array: input.clone(),
+                reducer: "RadonReducers::AverageMeanTrimmed".to_string(),
+            })
+        );
+    }
 }
@@ -10,8 +10,11 @@ pub fn mode(input: &RadonArray) -> Result<RadonTypes, RadError> {
     let mut counter: HashMap<RadonTypes, i8> = HashMap::new();
 
     // Count how many times does each different item appear in the input array
+    // The counter is an `i8` that wraps around, which is what release builds have always done. It
+    // must keep doing so, as tally results computed by older nodes depend on it.
     for item in value {
-        *counter.entry(item).or_insert(0) += 1;
+        let count = counter.entry(item).or_insert(0);
+        *count = count.wrapping_add(1);
     }
 
     let temp_counter = counter.clone();
@@ -28,12 +31,19 @@ pub fn mode(input: &RadonArray) -> Result<RadonTypes, RadError> {
 
     // Returns the mode or an error if there is a tie
     if mode_vector.len() > 1 {
+        // A negative count means that the counter wrapped around, which cannot be reported as a
+        // tie count
+        let max_count = u16::try_from(*max_count).map_err(|_| RadError::Overflow {
+            operator: "Mode".to_string(),
+            operands: vec![],
+        })?;
+
         Err(RadError::ModeTie {
             values: input.clone(),
-            max_count: u16::try_from(*max_count).unwrap(),
+            max_count,
         })
     } else {
-        Ok(mode_vector.into_iter().next().unwrap())
+        mode_vector.into_iter().next().ok_or(RadError::EmptyArray)
     }
 }
 
@@ -177,4 +187,32 @@ mod tests {
         let output = mode(&input).unwrap();
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_mode_counter_wraps_around() {
+        // 130 appearances wrap around to -126, so the single appearance of `2` wins
+        let mut items = vec![RadonInteger::from(1).into(); 130];
+        items.push(RadonInteger::from(2).into());
+        let input = RadonArray::from(items);
+
+        let expected = RadonTypes::from(RadonInteger::from(2));
+        let output = mode(&input).unwrap();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_mode_tie_with_wrapped_counter() {
+        let mut items = vec![RadonInteger::from(1).into(); 128];
+        items.extend(vec![RadonInteger::from(2).into(); 128]);
+        let input = RadonArray::from(items);
+
+        let output = mode(&input).unwrap_err();
+        assert_eq!(
+            output,
+            RadError::Overflow {
+                operator: "Mode".to_string(),
+                operands: vec![],
+            }
+        );
+    }
 }
@@ -0,0 +1,708 @@
+//! Canonical JSON representation of data requests, meant for exchanging them with request editors.
+//!
+//! The serialization of `RADRequest` itself keeps scripts and filter arguments as the CBOR byte
+//! strings that go on chain. This representation shows every RADON call instead as the name of its
+//! operator, or an array with the name followed by the arguments:
+//! ```json
+//! {
+//!   "version": 1,
+//!   "time_lock": 0,
+//!   "retrieve": [{
+//!     "kind": "HTTP-GET",
+//!     "url": "https://api.coindesk.com/v1/bpi/currentprice.json",
+//!     "script": ["StringParseJSONMap", ["MapGetMap", "bpi"], ["MapGetFloat", "rate_float"]],
+//!     "body": "",
+//!     "headers": []
+//!   }],
+//!   "aggregate": { "filters": [{ "op": "DeviationStandard", "args": 1.5 }], "reducer": "AverageMean" },
+//!   "tally": { "filters": [{ "op": "Mode" }], "reducer": "Mode" }
+//! }
+//! ```
+//! Operators, filters and reducers can also be given by their numeric code, which is how those
+//! without a name are exported. Arguments are plain JSON values, except for the CBOR values that
+//! JSON cannot tell apart, which are objects with a single key, or two for tags:
+//! - `{"script": [..]}`: the subscript of an `ArrayFilter`, `ArrayMap` or `ArraySort` call
+//! - `{"bytes": "<hex>"}`
+//! - `{"float": "NaN"}`, `{"float": "inf"}` or `{"float": "-inf"}`
+//! - `{"integer": "<decimal>"}`: an integer that does not fit in 64 bits
+//! - `{"map": [[key, value], ..]}`
+//! - `{"tag": 1, "value": ..}`
+//!
+//! Retrieval bodies are strings, or `{"bytes": "<hex>"}` if they are not UTF-8, and empty scripts
+//! are `null`.
+//!
+//! Exporting a request and importing it back always gives the same request: requests whose
+//! scripts or filter arguments would be encoded differently when imported are not exported.
+use std::{collections::BTreeMap, fmt::Debug};
+
+use failure::Fail;
+use serde::{Deserialize, Deserializer, Serialize};
+use serde_cbor::Value as CborValue;
+use serde_json::{json, Map, Value as JsonValue};
+use witnet_data_structures::chain::{
+    RADAggregate, RADFilter, RADRequest, RADRetrieve, RADTally, RADType,
+};
+
+use crate::{filters::RadonFilters, operators::RadonOpCodes, reducers::RadonReducers};
+
+/// Version of the representation produced by `to_json`, which is the only one `from_json` accepts.
+pub const VERSION: u32 = 1;
+
+/// A `RADRequest` in its canonical JSON representation.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RADRequestJson {
+    pub version: u32,
+    pub time_lock: u64,
+    pub retrieve: Vec<RADRetrieveJson>,
+    pub aggregate: RADStageJson,
+    pub tally: RADStageJson,
+}
+
+/// A retrieval of a `RADRequestJson`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RADRetrieveJson {
+    pub kind: RADType,
+    #[serde(default)]
+    pub url: String,
+    /// RADON calls, or `None` if the script is empty
+    pub script: Option<Vec<JsonValue>>,
+    #[serde(default)]
+    pub body: BodyJson,
+    #[serde(default)]
+    pub headers: Vec<(String, String)>,
+}
+
+/// Body of a retrieval.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum BodyJson {
+    Text(String),
+    /// Hexadecimal bytes of a body that is not UTF-8
+    Bytes {
+        bytes: String,
+    },
+}
+
+impl Default for BodyJson {
+    fn default() -> Self {
+        BodyJson::Text(String::new())
+    }
+}
+
+/// The aggregation or the tally stage of a `RADRequestJson`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RADStageJson {
+    pub filters: Vec<RADFilterJson>,
+    pub reducer: CodeJson,
+}
+
+/// A filter of the aggregation or the tally stage.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RADFilterJson {
+    pub op: CodeJson,
+    /// Argument of the filter, which is absent if the filter has none. A `null` argument is the
+    /// CBOR null value.
+    #[serde(
+        default,
+        deserialize_with = "present_value",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub args: Option<JsonValue>,
+}
+
+/// An operator, filter or reducer, by name or by numeric code.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum CodeJson {
+    Name(String),
+    Code(u32),
+}
+
+/// Errors converting a request from or to its JSON representation. They point at the part of the
+/// request that could not be converted.
+#[derive(Clone, Debug, PartialEq, Eq, Fail)]
+pub enum RequestJsonError {
+    #[fail(
+        display = "Unsupported version {} of the data request JSON format, the supported one is {}",
+        version, supported
+    )]
+    UnsupportedVersion { version: u32, supported: u32 },
+    #[fail(display = "Retrieval #{}: {}", retrieval, message)]
+    Retrieval { retrieval: usize, message: String },
+    #[fail(display = "Retrieval #{}, call #{}: {}", retrieval, call, message)]
+    Call {
+        retrieval: usize,
+        call: usize,
+        message: String,
+    },
+    #[fail(display = "Filter #{} of the {} stage: {}", filter, stage, message)]
+    Filter {
+        stage: &'static str,
+        filter: usize,
+        message: String,
+    },
+    #[fail(display = "Reducer of the {} stage: {}", stage, message)]
+    Reducer {
+        stage: &'static str,
+        message: String,
+    },
+}
+
+/// Convert a request to its canonical JSON representation.
+pub fn to_json(request: &RADRequest) -> Result<RADRequestJson, RequestJsonError> {
+    let retrieve = request
+        .retrieve
+        .iter()
+        .enumerate()
+        .map(|(retrieval, retrieve)| retrieve_to_json(retrieval, retrieve))
+        .collect::<Result<_, _>>()?;
+
+    Ok(RADRequestJson {
+        version: VERSION,
+        time_lock: request.time_lock,
+        retrieve,
+        aggregate: stage_to_json(
+            "aggregate",
+            &request.aggregate.filters,
+            request.aggregate.reducer,
+        )?,
+        tally: stage_to_json("tally", &request.tally.filters, request.tally.reducer)?,
+    })
+}
+
+/// Convert a request from its canonical JSON representation.
+pub fn from_json(json: &RADRequestJson) -> Result<RADRequest, RequestJsonError> {
+    if json.version != VERSION {
+        return Err(RequestJsonError::UnsupportedVersion {
+            version: json.version,
+            supported: VERSION,
+        });
+    }
+
+    let retrieve = json
+        .retrieve
+        .iter()
+        .enumerate()
+        .map(|(retrieval, retrieve)| retrieve_from_json(retrieval, retrieve))
+        .collect::<Result<_, _>>()?;
+    let (filters, reducer) = stage_from_json("aggregate", &json.aggregate)?;
+    let aggregate = RADAggregate { filters, reducer };
+    let (filters, reducer) = stage_from_json("tally", &json.tally)?;
+    let tally = RADTally { filters, reducer };
+
+    Ok(RADRequest {
+        time_lock: json.time_lock,
+        retrieve,
+        aggregate,
+        tally,
+    })
+}
+
+fn retrieve_to_json(
+    retrieval: usize,
+    retrieve: &RADRetrieve,
+) -> Result<RADRetrieveJson, RequestJsonError> {
+    let retrieval_error = |message: String| RequestJsonError::Retrieval { retrieval, message };
+
+    let script = if retrieve.script.is_empty() {
+        None
+    } else {
+        let calls = match serde_cbor::from_slice(&retrieve.script) {
+            Ok(CborValue::Array(calls)) => calls,
+            Ok(_) => return Err(retrieval_error("the script is not an array".to_string())),
+            Err(e) => return Err(retrieval_error(format!("the script is not CBOR: {}", e))),
+        };
+        let calls = calls_to_json(&calls).map_err(|(call, message)| RequestJsonError::Call {
+            retrieval,
+            call,
+            message,
+        })?;
+        if script_from_json(&calls).ok().as_ref() != Some(&retrieve.script) {
+            return Err(retrieval_error(
+                "the script is not encoded canonically, so it would change when imported"
+                    .to_string(),
+            ));
+        }
+
+        Some(calls)
+    };
+    let body = match String::from_utf8(retrieve.body.clone()) {
+        Ok(text) => BodyJson::Text(text),
+        Err(_) => BodyJson::Bytes {
+            bytes: hex::encode(&retrieve.body),
+        },
+    };
+
+    Ok(RADRetrieveJson {
+        kind: retrieve.kind.clone(),
+        url: retrieve.url.clone(),
+        script,
+        body,
+        headers: retrieve.headers.clone(),
+    })
+}
+
+fn retrieve_from_json(
+    retrieval: usize,
+    json: &RADRetrieveJson,
+) -> Result<RADRetrieve, RequestJsonError> {
+    let script = match &json.script {
+        None => vec![],
+        Some(calls) => {
+            script_from_json(calls).map_err(|(call, message)| RequestJsonError::Call {
+                retrieval,
+                call,
+                message,
+            })?
+        }
+    };
+    let body = match &json.body {
+        BodyJson::Text(text) => text.clone().into_bytes(),
+        BodyJson::Bytes { bytes } => {
+            hex::decode(bytes).map_err(|e| RequestJsonError::Retrieval {
+                retrieval,
+                message: format!("the body is not hexadecimal: {}", e),
+            })?
+        }
+    };
+
+    Ok(RADRetrieve {
+        kind: json.kind.clone(),
+        url: json.url.clone(),
+        script,
+        body,
+        headers: json.headers.clone(),
+    })
+}
+
+fn stage_to_json(
+    stage: &'static str,
+    filters: &[RADFilter],
+    reducer: u32,
+) -> Result<RADStageJson, RequestJsonError> {
+    let filters = filters
+        .iter()
+        .enumerate()
+        .map(|(index, filter)| {
+            let filter_error = |message: String| RequestJsonError::Filter {
+                stage,
+                filter: index,
+                message,
+            };
+            let args = if filter.args.is_empty() {
+                None
+            } else {
+                let args = serde_cbor::from_slice(&filter.args)
+                    .map_err(|e| filter_error(format!("the arguments are not CBOR: {}", e)))?;
+                let json = value_to_json(&args).map_err(filter_error)?;
+                if value_from_json(&json)
+                    .ok()
+                    .and_then(|value| serde_cbor::to_vec(&value).ok())
+                    .as_ref()
+                    != Some(&filter.args)
+                {
+                    return Err(filter_error(
+                        "the arguments are not encoded canonically, so they would change when \
+                         imported"
+                            .to_string(),
+                    ));
+                }
+
+                Some(json)
+            };
+
+            Ok(RADFilterJson {
+                op: code_to_json::<RadonFilters>(filter.op),
+                args,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok(RADStageJson {
+        filters,
+        reducer: code_to_json::<RadonReducers>(reducer),
+    })
+}
+
+fn stage_from_json(
+    stage: &'static str,
+    json: &RADStageJson,
+) -> Result<(Vec<RADFilter>, u32), RequestJsonError> {
+    let filters = json
+        .filters
+        .iter()
+        .enumerate()
+        .map(|(index, filter)| {
+            let filter_error = |message: String| RequestJsonError::Filter {
+                stage,
+                filter: index,
+                message,
+            };
+            let op = code_from_json::<RadonFilters>(&filter.op).map_err(filter_error)?;
+            let args = match &filter.args {
+                None => vec![],
+                Some(args) => value_from_json(args)
+                    .and_then(|args| serde_cbor::to_vec(&args).map_err(|e| e.to_string()))
+                    .map_err(filter_error)?,
+            };
+
+            Ok(RADFilter { op, args })
+        })
+        .collect::<Result<_, _>>()?;
+    let reducer = code_from_json::<RadonReducers>(&json.reducer)
+        .map_err(|message| RequestJsonError::Reducer { stage, message })?;
+
+    Ok((filters, reducer))
+}
+
+/// Render the calls of a script, failing with the index of the first one that cannot be rendered.
+fn calls_to_json(calls: &[CborValue]) -> Result<Vec<JsonValue>, (usize, String)> {
+    calls
+        .iter()
+        .enumerate()
+        .map(|(index, call)| call_to_json(call).map_err(|message| (index, message)))
+        .collect()
+}
+
+fn call_to_json(call: &CborValue) -> Result<JsonValue, String> {
+    let items = match call {
+        CborValue::Array(items) => items,
+        operator => return operator_to_json(operator).map(|(name, _)| name),
+    };
+    let (operator, args) = items
+        .split_first()
+        .ok_or("a call must start with an operator")?;
+    let (name, operator) = operator_to_json(operator)?;
+    let takes_subscript = matches!(
+        operator,
+        Some(RadonOpCodes::ArrayFilter | RadonOpCodes::ArrayMap | RadonOpCodes::ArraySort)
+    );
+
+    let mut json = vec![name];
+    for (index, arg) in args.iter().enumerate() {
+        let subscript = match arg {
+            CborValue::Array(calls) if index == 0 && takes_subscript => calls_to_json(calls).ok(),
+            _ => None,
+        };
+        json.push(match subscript {
+            Some(calls) => json!({ "script": calls }),
+            None => value_to_json(arg)?,
+        });
+    }
+
+    Ok(JsonValue::Array(json))
+}
+
+/// Name of an operator, or its code if it has none, along with the operator itself if known.
+fn operator_to_json(operator: &CborValue) -> Result<(JsonValue, Option<RadonOpCodes>), String> {
+    let code = match operator {
+        CborValue::Integer(code) => {
+            u8::try_from(*code).map_err(|_| format!("{} is not an operator code", code))?
+        }
+        _ => return Err("operators must be integers".to_string()),
+    };
+
+    Ok(match RadonOpCodes::try_from(code) {
+        Ok(operator) => (JsonValue::from(format!("{:?}", operator)), Some(operator)),
+        Err(_) => (JsonValue::from(code), None),
+    })
+}
+
+/// Encode the calls of a script, failing with the index of the first one that cannot be encoded.
+fn script_from_json(calls: &[JsonValue]) -> Result<Vec<u8>, (usize, String)> {
+    let calls = calls_from_json(calls)?;
+
+    serde_cbor::to_vec(&CborValue::Array(calls)).map_err(|e| (0, e.to_string()))
+}
+
+fn calls_from_json(calls: &[JsonValue]) -> Result<Vec<CborValue>, (usize, String)> {
+    calls
+        .iter()
+        .enumerate()
+        .map(|(index, call)| call_from_json(call).map_err(|message| (index, message)))
+        .collect()
+}
+
+fn call_from_json(call: &JsonValue) -> Result<CborValue, String> {
+    let items = match call {
+        JsonValue::Array(items) => items,
+        operator => return operator_from_json(operator),
+    };
+    let (operator, args) = items
+        .split_first()
+        .ok_or("a call must start with an operator")?;
+
+    std::iter::once(operator_from_json(operator))
+        .chain(args.iter().map(value_from_json))
+        .collect::<Result<_, _>>()
+        .map(CborValue::Array)
+}
+
+fn operator_from_json(operator: &JsonValue) -> Result<CborValue, String> {
+    let code = serde_json::from_value::<CodeJson>(operator.clone())
+        .map_err(|_| format!("operator {} is neither a name nor a code", operator))
+        .and_then(|operator| code_from_json::<RadonOpCodes>(&operator))?;
+    if code > u32::from(u8::MAX) {
+        return Err(format!("{} is not an operator code", code));
+    }
+
+    Ok(CborValue::Integer(code.into()))
+}
+
+/// Name of a code of `T`, or the code itself if it has none.
+fn code_to_json<T>(code: u32) -> CodeJson
+where
+    T: TryFrom<u8> + Debug,
+{
+    u8::try_from(code)
+        .ok()
+        .and_then(|code| T::try_from(code).ok())
+        .map(|named| CodeJson::Name(format!("{:?}", named)))
+        .unwrap_or(CodeJson::Code(code))
+}
+
+fn code_from_json<T>(json: &CodeJson) -> Result<u32, String>
+where
+    T: TryFrom<u8> + Debug,
+{
+    match json {
+        CodeJson::Code(code) => Ok(*code),
+        CodeJson::Name(name) => (0..=u8::MAX)
+            .find(|code| T::try_from(*code).map_or(false, |named| format!("{:?}", named) == *name))
+            .map(u32::from)
+            .ok_or_else(|| format!("unknown name `{}`", name)),
+    }
+}
+
+/// Render a CBOR argument as JSON.
+pub(crate) fn value_to_json(value: &CborValue) -> Result<JsonValue, String> {
+    Ok(match value {
+        CborValue::Null => JsonValue::Null,
+        CborValue::Bool(boolean) => JsonValue::from(*boolean),
+        CborValue::Integer(integer) => i64::try_from(*integer)
+            .map(JsonValue::from)
+            .or_else(|_| u64::try_from(*integer).map(JsonValue::from))
+            .unwrap_or_else(|_| json!({ "integer": integer.to_string() })),
+        CborValue::Float(float) if float.is_finite() => JsonValue::from(*float),
+        CborValue::Float(float) => json!({ "float": float.to_string() }),
+        CborValue::Bytes(bytes) => json!({ "bytes": hex::encode(bytes) }),
+        CborValue::Text(text) => JsonValue::from(text.clone()),
+        CborValue::Array(values) => values
+            .iter()
+            .map(value_to_json)
+            .collect::<Result<_, _>>()
+            .map(JsonValue::Array)?,
+        CborValue::Map(map) => {
+            let entries = map
+                .iter()
+                .map(|(key, value)| Ok(json!([value_to_json(key)?, value_to_json(value)?])))
+                .collect::<Result<Vec<_>, String>>()?;
+
+            json!({ "map": entries })
+        }
+        CborValue::Tag(tag, value) => json!({ "tag": tag, "value": value_to_json(value)? }),
+        _ => return Err("unsupported CBOR value".to_string()),
+    })
+}
+
+/// Encode a JSON argument as CBOR.
+fn value_from_json(value: &JsonValue) -> Result<CborValue, String> {
+    Ok(match value {
+        JsonValue::Null => CborValue::Null,
+        JsonValue::Bool(boolean) => CborValue::Bool(*boolean),
+        JsonValue::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(integer), _) => CborValue::Integer(integer.into()),
+            (None, Some(integer)) => CborValue::Integer(integer.into()),
+            (None, None) => CborValue::Float(number.as_f64().unwrap_or_default()),
+        },
+        JsonValue::String(text) => CborValue::Text(text.clone()),
+        JsonValue::Array(values) => values
+            .iter()
+            .map(value_from_json)
+            .collect::<Result<_, _>>()
+            .map(CborValue::Array)?,
+        JsonValue::Object(object) => tagged_value_from_json(object)?,
+    })
+}
+
+/// Encode one of the objects that stand for CBOR values without a JSON counterpart.
+fn tagged_value_from_json(object: &Map<String, JsonValue>) -> Result<CborValue, String> {
+    let mut keys: Vec<&str> = object.keys().map(String::as_str).collect();
+    keys.sort_unstable();
+    let text = |key: &str| {
+        object[key]
+            .as_str()
+            .ok_or_else(|| format!("`{}` must be a string", key))
+    };
+
+    match keys.as_slice() {
+        ["bytes"] => hex::decode(text("bytes")?)
+            .map(CborValue::Bytes)
+            .map_err(|e| format!("`bytes` must be hexadecimal: {}", e)),
+        ["float"] => text("float")?
+            .parse()
+            .map(CborValue::Float)
+            .map_err(|e| format!("`float` must be a number: {}", e)),
+        ["integer"] => text("integer")?
+            .parse()
+            .map(CborValue::Integer)
+            .map_err(|e| format!("`integer` must be an integer: {}", e)),
+        ["map"] => {
+            let entries = object["map"]
+                .as_array()
+                .ok_or("`map` must be an array of entries")?;
+            entries
+                .iter()
+                .map(|entry| match entry.as_array().map(Vec::as_slice) {
+                    Some([key, value]) => Ok((value_from_json(key)?, value_from_json(value)?)),
+                    _ => Err("map entries must be arrays of a key and a value".to_string()),
+                })
+                .collect::<Result<BTreeMap<_, _>, _>>()
+                .map(CborValue::Map)
+        }
+        ["script"] => {
+            let calls = object["script"]
+                .as_array()
+                .ok_or("`script` must be an array of calls")?;
+            calls_from_json(calls)
+                .map(CborValue::Array)
+                .map_err(|(call, message)| format!("subscript call #{}: {}", call, message))
+        }
+        ["tag", "value"] => {
+            let tag = object["tag"]
+                .as_u64()
+                .ok_or("`tag` must be an unsigned integer")?;
+
+            Ok(CborValue::Tag(
+                tag,
+                Box::new(value_from_json(&object["value"])?),
+            ))
+        }
+        _ => Err(format!(
+            "objects must stand for a script, bytes, a float, an integer, a map or a tag, found \
+             one with keys {:?}",
+            keys
+        )),
+    }
+}
+
+/// Deserialize a value that is present, even if it is `null`.
+fn present_value<'de, D>(deserializer: D) -> Result<Option<JsonValue>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    JsonValue::deserialize(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(script: Vec<u8>) -> RADRequest {
+        RADRequest {
+            retrieve: vec![
+                RADRetrieve {
+                    kind: RADType::Rng,
+                    script: vec![0x80],
+                    ..RADRetrieve::default()
+                },
+                RADRetrieve {
+                    kind: RADType::HttpGet,
+                    url: "https://example.com".to_string(),
+                    script,
+                    ..RADRetrieve::default()
+                },
+            ],
+            ..RADRequest::default()
+        }
+    }
+
+    fn request_json(script: JsonValue) -> RADRequestJson {
+        serde_json::from_value(json!({
+            "version": 1,
+            "time_lock": 0,
+            "retrieve": [
+                { "kind": "RNG", "script": [] },
+                { "kind": "HTTP-GET", "url": "https://example.com", "script": script },
+            ],
+            "aggregate": { "filters": [], "reducer": "Mode" },
+            "tally": { "filters": [{ "op": "DeviationStandard", "args": 1.5 }], "reducer": 2 },
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_import_errors_name_the_retrieval_and_the_call() {
+        let json = request_json(json!(["StringParseJSONMap", ["MapGetFlaot", "price"]]));
+        assert_eq!(
+            from_json(&json).unwrap_err().to_string(),
+            "Retrieval #1, call #1: unknown name `MapGetFlaot`"
+        );
+
+        let json = request_json(json!([
+            "StringParseJSONArray",
+            "StringParseJSONMap",
+            ["ArrayMap", { "script": ["MapGetFloat", ["FloatPower", { "float": 1.5 }]] }],
+        ]));
+        assert_eq!(
+            from_json(&json).unwrap_err(),
+            RequestJsonError::Call {
+                retrieval: 1,
+                call: 2,
+                message: "subscript call #1: `float` must be a string".to_string(),
+            }
+        );
+
+        let mut json = request_json(json!(["StringParseJSONMap"]));
+        json.tally.filters[0].op = CodeJson::Name("Median".to_string());
+        assert_eq!(
+            from_json(&json).unwrap_err(),
+            RequestJsonError::Filter {
+                stage: "tally",
+                filter: 0,
+                message: "unknown name `Median`".to_string(),
+            }
+        );
+
+        let mut json = request_json(json!([]));
+        json.version = 2;
+        assert_eq!(
+            from_json(&json).unwrap_err(),
+            RequestJsonError::UnsupportedVersion {
+                version: 2,
+                supported: VERSION,
+            }
+        );
+    }
+
+    #[test]
+    fn test_requests_that_would_not_round_trip_are_not_exported() {
+        // The operator 0x77 written with a needlessly long encoding
+        let error = to_json(&request(vec![0x81, 0x18, 0x77])).unwrap_err();
+        assert!(matches!(
+            error,
+            RequestJsonError::Retrieval { retrieval: 1, .. }
+        ));
+
+        // A text argument that is shorter than its declared length
+        let error = to_json(&request(vec![0x82, 0x77, 0x82, 0x66, 0x63])).unwrap_err();
+        assert!(matches!(
+            error,
+            RequestJsonError::Retrieval { retrieval: 1, .. }
+        ));
+
+        let error = to_json(&request(vec![0x82, 0x77, 0x82, 0x61, 0x61])).unwrap_err();
+        assert_eq!(
+            error,
+            RequestJsonError::Call {
+                retrieval: 1,
+                call: 1,
+                message: "operators must be integers".to_string(),
+            }
+        );
+    }
+}
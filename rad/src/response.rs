@@ -0,0 +1,278 @@
+//! Limits on the HTTP responses to retrievals.
+//!
+//! Bodies are read while they are being downloaded, and the download is aborted as soon as a body
+//! goes over the limit, so that a source cannot make a node buffer an arbitrarily large response.
+//! The content type is checked before reading the body at all.
+//!
+//! Both limits change the outcome of retrievals, so they are only enforced after WIP0042, using
+//! the same maximum size in every node.
+use futures::{AsyncRead, AsyncReadExt};
+use witnet_data_structures::chain::RADType;
+
+use crate::{error::RadError, Result};
+
+/// Content types that are binary, or families of them if they end with `/`. HTTP retrievals read
+/// the body of their responses as text, so these can never be retrieved.
+const BINARY_CONTENT_TYPES: &[&str] = &[
+    "application/octet-stream",
+    "application/pdf",
+    "application/zip",
+    "application/gzip",
+    "application/x-protobuf",
+    "image/",
+    "audio/",
+    "video/",
+    "font/",
+];
+
+/// Body of the HTTP response to a retrieval, with what was received along with it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResponseBody {
+    pub text: String,
+    /// Size of the body in bytes
+    pub size: usize,
+    /// Value of the `Content-Type` header of the response, if any
+    pub content_type: Option<String>,
+}
+
+/// Check the content type of a response against the one expected by the kind of the retrieval.
+///
+/// Responses without a content type are accepted, as many sources do not send one.
+pub fn check_content_type(kind: &RADType, content_type: Option<&str>) -> Result<()> {
+    let content_type = match content_type {
+        Some(content_type) if kind.is_http() => content_type,
+        _ => return Ok(()),
+    };
+    // Parameters such as `; charset=utf-8` do not change the type
+    let mime = content_type
+        .split(';')
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_ascii_lowercase();
+    let is_binary = BINARY_CONTENT_TYPES.iter().any(|binary| {
+        if binary.ends_with('/') {
+            mime.starts_with(binary)
+        } else {
+            mime == *binary
+        }
+    });
+
+    if is_binary {
+        Err(RadError::HttpContentTypeMismatch {
+            content_type: content_type.to_string(),
+        })
+    } else {
+        Ok(())
+    }
+}
+
+/// Read the body of a response, failing as soon as it goes over `limit` bytes. A response that
+/// announces a larger body through its `Content-Length` is not read at all.
+pub async fn read_body<B>(body: B, content_length: Option<u64>, limit: usize) -> Result<Vec<u8>>
+where
+    B: AsyncRead + Unpin,
+{
+    let limit_u64 = u64::try_from(limit).unwrap_or(u64::MAX);
+    if let Some(content_length) = content_length.filter(|length| *length > limit_u64) {
+        return Err(RadError::HttpResponseTooLarge {
+            limit,
+            size: usize::try_from(content_length).unwrap_or(usize::MAX),
+        });
+    }
+
+    // Reading one byte past the limit tells a body that is exactly at the limit from a larger one
+    let mut bytes = vec![];
+    body.take(limit_u64.saturating_add(1))
+        .read_to_end(&mut bytes)
+        .await
+        .map_err(|e| RadError::HttpOther {
+            message: e.to_string(),
+        })?;
+    if bytes.len() > limit {
+        return Err(RadError::HttpResponseTooLarge {
+            limit,
+            size: bytes.len(),
+        });
+    }
+
+    Ok(bytes)
+}
+
+/// Check and read the response to an HTTP retrieval, whose body must be text of up to `limit`
+/// bytes. Without a limit, neither the size nor the content type of the response are checked.
+pub async fn read_response<B>(
+    kind: &RADType,
+    response: http::Response<B>,
+    limit: Option<usize>,
+) -> Result<ResponseBody>
+where
+    B: AsyncRead + Unpin,
+{
+    let (parts, body) = response.into_parts();
+    let content_type = parts
+        .headers
+        .get(http::header::CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from);
+    let content_length = parts
+        .headers
+        .get(http::header::CONTENT_LENGTH)
+        .and_then(|value| value.to_str().ok())
+        .and_then(|value| value.parse().ok());
+
+    // If at some point we want to support the retrieval of non-UTF8 data (e.g. raw bytes), this is
+    // where we need to decide how to read the response body
+    let bytes = match limit {
+        Some(limit) => {
+            check_content_type(kind, content_type.as_deref())?;
+            read_body(body, content_length, limit).await?
+        }
+        None => {
+            let mut bytes = vec![];
+            let mut body = body;
+            body.read_to_end(&mut bytes)
+                .await
+                .map_err(|e| RadError::HttpOther {
+                    message: e.to_string(),
+                })?;
+
+            bytes
+        }
+    };
+    let size = bytes.len();
+    let text = String::from_utf8(bytes).map_err(|e| RadError::HttpOther {
+        message: e.to_string(),
+    })?;
+
+    Ok(ResponseBody {
+        text,
+        size,
+        content_type,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::executor::block_on;
+
+    use super::*;
+
+    #[test]
+    fn binary_content_types_are_rejected() {
+        let kind = RADType::HttpGet;
+        for content_type in [
+            "application/octet-stream",
+            "image/png",
+            "Video/MP4",
+            "application/pdf; name=report.pdf",
+        ] {
+            assert_eq!(
+                check_content_type(&kind, Some(content_type)),
+                Err(RadError::HttpContentTypeMismatch {
+                    content_type: content_type.to_string(),
+                })
+            );
+        }
+
+        for content_type in [
+            "application/json",
+            "application/json; charset=utf-8",
+            "text/html",
+            "application/xml",
+        ] {
+            assert_eq!(check_content_type(&kind, Some(content_type)), Ok(()));
+        }
+        assert_eq!(check_content_type(&kind, None), Ok(()));
+        assert_eq!(
+            check_content_type(&RADType::Rng, Some("application/octet-stream")),
+            Ok(())
+        );
+    }
+
+    #[test]
+    fn bodies_up_to_the_limit_are_read() {
+        let body = b"{\"price\": 42}".to_vec();
+
+        assert_eq!(
+            block_on(read_body(&body[..], None, body.len())),
+            Ok(body.clone())
+        );
+        assert_eq!(
+            block_on(read_body(&body[..], None, body.len() - 1)),
+            Err(RadError::HttpResponseTooLarge {
+                limit: body.len() - 1,
+                size: body.len(),
+            })
+        );
+    }
+
+    #[test]
+    fn endless_bodies_are_aborted_at_the_limit() {
+        // This would never finish if the whole body was read
+        let endless = futures::io::repeat(b' ');
+
+        assert_eq!(
+            block_on(read_body(endless, None, 1_024)),
+            Err(RadError::HttpResponseTooLarge {
+                limit: 1_024,
+                size: 1_025,
+            })
+        );
+    }
+
+    #[test]
+    fn bodies_announced_as_too_large_are_not_read() {
+        let endless = futures::io::repeat(b' ');
+
+        assert_eq!(
+            block_on(read_body(endless, Some(1 << 30), 1_024)),
+            Err(RadError::HttpResponseTooLarge {
+                limit: 1_024,
+                size: 1 << 30,
+            })
+        );
+    }
+
+    #[test]
+    fn responses_report_their_size_and_content_type() {
+        let response = http::Response::builder()
+            .header("Content-Type", "application/json")
+            .body(&b"[1, 2, 3]"[..])
+            .unwrap();
+
+        assert_eq!(
+            block_on(read_response(&RADType::HttpGet, response, Some(1_024))),
+            Ok(ResponseBody {
+                text: "[1, 2, 3]".to_string(),
+                size: 9,
+                content_type: Some("application/json".to_string()),
+            })
+        );
+    }
+
+    #[test]
+    fn responses_are_not_checked_without_a_limit() {
+        let response = || {
+            http::Response::builder()
+                .header("Content-Type", "image/png")
+                .body(&b"[1, 2, 3]"[..])
+                .unwrap()
+        };
+
+        assert_eq!(
+            block_on(read_response(&RADType::HttpGet, response(), Some(1_024))),
+            Err(RadError::HttpContentTypeMismatch {
+                content_type: "image/png".to_string(),
+            })
+        );
+        assert_eq!(
+            block_on(read_response(&RADType::HttpGet, response(), None)),
+            Ok(ResponseBody {
+                text: "[1, 2, 3]".to_string(),
+                size: 9,
+                content_type: Some("image/png".to_string()),
+            })
+        );
+    }
+}
@@ -1,4 +1,8 @@
-use std::convert::TryFrom;
+use std::{
+    any::Any,
+    convert::TryFrom,
+    panic::{self, AssertUnwindSafe},
+};
 
 use serde_cbor::{
     self as cbor,
@@ -6,7 +10,7 @@ use serde_cbor::{
 };
 use witnet_data_structures::{
     chain::{tapi::ActiveWips, RADFilter},
-    radon_report::{RadonReport, ReportContext, Stage},
+    radon_report::{ExecutionMode, FilterBreakdown, RadonReport, ReportContext, Stage},
 };
 
 use crate::{
@@ -22,6 +26,9 @@ pub type RadonScript = Vec<RadonCall>;
 
 /// A set of flags for telling the RADON executor what execution features to enable and what
 /// metadata to collect.
+///
+/// `partial_results` and `filter_breakdown` are local-only, and are ignored by executions in
+/// `ExecutionMode::Consensus`.
 #[derive(Clone, Copy)]
 pub struct RadonScriptExecutionSettings {
     /// Keep track of which call index is being executed in every moment, so that any error can be
@@ -32,9 +39,11 @@ pub struct RadonScriptExecutionSettings {
     pub partial_results: bool,
     /// Measure total execution time for the script.
     pub timing: bool,
+    /// Keep track of which elements are removed by each filter in the script, and why.
+    pub filter_breakdown: bool,
 }
 
-/// Default to enabling all execution features except `partial_results`.
+/// Default to enabling all execution features except `partial_results` and `filter_breakdown`.
 impl Default for RadonScriptExecutionSettings {
     fn default() -> Self {
         Self::all_but_partial_results()
@@ -42,11 +51,12 @@ impl Default for RadonScriptExecutionSettings {
 }
 
 impl RadonScriptExecutionSettings {
-    /// Enable all execution features except `partial_results`. This is the default for
-    /// `witnet_node`.
+    /// Enable all execution features except `partial_results` and `filter_breakdown`. This is the
+    /// default for `witnet_node`, and the one used for consensus.
     pub fn all_but_partial_results() -> Self {
         Self {
             partial_results: false,
+            filter_breakdown: false,
             ..Self::enable_all()
         }
     }
@@ -57,6 +67,7 @@ impl RadonScriptExecutionSettings {
             partial_results: false,
             timing: false,
             breakpoints: false,
+            filter_breakdown: false,
         }
     }
 
@@ -66,32 +77,75 @@ impl RadonScriptExecutionSettings {
             partial_results: true,
             timing: true,
             breakpoints: true,
+            filter_breakdown: true,
         }
     }
 
     /// Only enable the execution features that are suitable for a specific data request stage.
+    ///
+    /// The filter breakdown is never enabled, as it is decided by the script that runs the
+    /// subscripts.
     pub fn tailored_to_stage(stage: &Stage<RadonTypes>) -> Self {
         match stage {
-            Stage::Retrieval(_) => Self::enable_all(),
+            Stage::Retrieval(_) => Self {
+                filter_breakdown: false,
+                ..Self::enable_all()
+            },
             _ => Self::all_but_partial_results(),
         }
     }
+
+    /// Mode of the executions that these settings are meant for, which is local if any of the
+    /// local-only features is enabled.
+    pub fn mode(&self) -> ExecutionMode {
+        if self.partial_results || self.filter_breakdown {
+            ExecutionMode::Local
+        } else {
+            ExecutionMode::Consensus
+        }
+    }
+
+    /// Disable the execution features that are not available in `mode`.
+    pub fn restricted_to(self, mode: ExecutionMode) -> Self {
+        if mode == ExecutionMode::Local {
+            self
+        } else {
+            Self {
+                partial_results: false,
+                filter_breakdown: false,
+                ..self
+            }
+        }
+    }
 }
 
 /// Run any RADON script on given input data, and return `RadonReport`.
 /// By enabling or disabling each of the specific flags in the settings argument, we can adjust how
 /// much execution metadata we want to track, e.g. execution time, partial results, etc.
+///
+/// The local-only settings are only honored if the context is in `ExecutionMode::Local`.
 pub fn execute_radon_script(
     input: RadonTypes,
     script: &[RadonCall],
     context: &mut ReportContext<RadonTypes>,
     settings: RadonScriptExecutionSettings,
 ) -> Result<RadonReport<RadonTypes>, RadError> {
+    debug_assert!(
+        context.mode == ExecutionMode::Local || settings.mode() == ExecutionMode::Consensus,
+        "Local-only execution features were enabled for an execution in consensus mode"
+    );
+    let settings = settings.restricted_to(context.mode);
+
     // Set the execution start timestamp, if enabled by `timing` setting
     if settings.timing {
         context.start();
     }
 
+    // Start recording the elements removed by filters, if enabled by `filter_breakdown` setting
+    if settings.filter_breakdown && context.filter_breakdown.is_none() {
+        context.filter_breakdown = Some(FilterBreakdown::default());
+    }
+
     // Initialize a vector for storing the partial results, if enabled by `partial_results` setting
     let mut partial_results = if settings.partial_results {
         Some(vec![Ok(input.clone())])
@@ -110,7 +164,7 @@ pub fn execute_radon_script(
             }
 
             // Apply the call
-            let partial_result = operate_in_context(input, call, context);
+            let partial_result = guard(call, || operate_in_context(input, call, context));
 
             // Keep partial result, if enabled by `partial_results` setting
             if let Some(partial_results) = partial_results.as_mut() {
@@ -140,7 +194,36 @@ pub fn execute_contextfree_radon_script(
     input: RadonTypes,
     script: &[RadonCall],
 ) -> Result<RadonTypes, RadError> {
-    script.iter().try_fold(input, operate)
+    script
+        .iter()
+        .try_fold(input, |input, call| guard(call, || operate(input, call)))
+}
+
+/// Apply a single call, turning any panic that happens in the operator into `RadError::Unknown`.
+///
+/// Operators are expected to never panic, but this guard keeps a malformed script or input that
+/// slips through from bringing the whole node down.
+fn guard<F>(call: &RadonCall, operation: F) -> Result<RadonTypes, RadError>
+where
+    F: FnOnce() -> Result<RadonTypes, RadError>,
+{
+    panic::catch_unwind(AssertUnwindSafe(operation)).unwrap_or_else(|payload| {
+        log::error!(
+            "Panic while applying RADON call {:?}: {}",
+            call,
+            panic_message(payload.as_ref())
+        );
+
+        Err(RadError::Unknown)
+    })
+}
+
+fn panic_message(payload: &(dyn Any + Send)) -> &str {
+    payload
+        .downcast_ref::<&str>()
+        .copied()
+        .or_else(|| payload.downcast_ref::<String>().map(String::as_str))
+        .unwrap_or("unknown panic")
 }
 
 pub fn unpack_radon_script(packed: &[u8]) -> Result<RadonScript, RadError> {
@@ -316,7 +399,10 @@ mod tests {
         assert_eq!(output, expected);
 
         // Test contextful execution
-        let mut context = ReportContext::default();
+        let mut context = ReportContext {
+            mode: ExecutionMode::Local,
+            ..ReportContext::default()
+        };
         let output = execute_radon_script(
             input.clone(),
             &script,
@@ -528,6 +614,90 @@ mod tests {
         assert_eq!(output.partial_results, Some(partial_expected));
     }
 
+    #[test]
+    fn test_filter_breakdown() {
+        use crate::types::{array::RadonArray, float::RadonFloat};
+        use witnet_data_structures::radon_report::{FilterStage, RemovalReason, RemovedElement};
+
+        let input = RadonTypes::from(RadonArray::from(
+            [10.0, 11.0, 500.0, 12.0, 10.0, 30.0, 11.0]
+                .iter()
+                .map(|x| RadonFloat::from(*x).into())
+                .collect::<Vec<_>>(),
+        ));
+        // Keep the values below 100, and then those within one standard deviation
+        let script = vec![
+            (
+                RadonOpCodes::ArrayFilter,
+                Some(vec![Value::Array(vec![Value::Array(vec![
+                    Value::Integer(RadonOpCodes::FloatLessThan as i128),
+                    Value::Float(100.0),
+                ])])]),
+            ),
+            (
+                RadonOpCodes::ArrayFilter,
+                Some(vec![
+                    Value::Integer(RadonFilters::DeviationStandard as i128),
+                    Value::Float(1.0),
+                ]),
+            ),
+        ];
+
+        let mut context = ReportContext {
+            mode: ExecutionMode::Local,
+            ..ReportContext::default()
+        };
+        let report = execute_radon_script(
+            input.clone(),
+            &script,
+            &mut context,
+            RadonScriptExecutionSettings::enable_all(),
+        )
+        .unwrap();
+
+        let expected_result = RadonTypes::from(RadonArray::from(
+            [10.0, 11.0, 12.0, 10.0, 11.0]
+                .iter()
+                .map(|x| RadonFloat::from(*x).into())
+                .collect::<Vec<_>>(),
+        ));
+        assert_eq!(report.result, expected_result);
+        assert_eq!(
+            report.context.filter_breakdown.unwrap().stages,
+            vec![
+                FilterStage {
+                    call_index: Some(0),
+                    removed: vec![RemovedElement {
+                        index: 2,
+                        reason: RemovalReason::OutOfRange,
+                    }],
+                },
+                FilterStage {
+                    call_index: Some(1),
+                    removed: vec![RemovedElement {
+                        index: 5,
+                        reason: RemovalReason::DeviationExceeded,
+                    }],
+                },
+            ]
+        );
+
+        // Nothing is recorded with the settings used for consensus, even in local mode
+        let mut context = ReportContext {
+            mode: ExecutionMode::Local,
+            ..ReportContext::default()
+        };
+        let report = execute_radon_script(
+            input,
+            &script,
+            &mut context,
+            RadonScriptExecutionSettings::all_but_partial_results(),
+        )
+        .unwrap();
+        assert_eq!(report.result, expected_result);
+        assert!(report.context.filter_breakdown.is_none());
+    }
+
     #[test]
     fn test_floats_as_integers() {
         use crate::types::{integer::RadonInteger, string::RadonString};
@@ -587,6 +757,87 @@ mod tests {
         assert_eq!(output, expected)
     }
 
+    #[test]
+    fn test_unpack_and_execute_bytes_script() {
+        use crate::types::{bytes::RadonBytes, integer::RadonInteger};
+
+        // Binary payload with a 4-byte big-endian price at offset 8
+        let payload = hex::decode("cafe0001000000100001e240ffff").unwrap();
+        let cbor_vec = Value::Array(vec![
+            Value::Array(vec![
+                Value::Integer(RadonOpCodes::BytesSlice as i128),
+                Value::Integer(8),
+                Value::Integer(12),
+            ]),
+            Value::Integer(RadonOpCodes::BytesAsInteger as i128),
+        ]);
+        let packed = serde_cbor::to_vec(&cbor_vec).unwrap();
+
+        let script = unpack_radon_script(&packed).unwrap();
+        assert_eq!(
+            script,
+            vec![
+                (
+                    RadonOpCodes::BytesSlice,
+                    Some(vec![Value::Integer(8), Value::Integer(12)]),
+                ),
+                (RadonOpCodes::BytesAsInteger, None),
+            ]
+        );
+
+        let input = RadonTypes::from(RadonBytes::from(payload));
+        let output = execute_contextfree_radon_script(input.clone(), &script).unwrap();
+        assert_eq!(output, RadonTypes::from(RadonInteger::from(123_456)));
+
+        let length = execute_contextfree_radon_script(input, &[(RadonOpCodes::BytesLength, None)]);
+        assert_eq!(length.unwrap(), RadonTypes::from(RadonInteger::from(14)));
+    }
+
+    #[test]
+    fn test_unpack_and_execute_reducer_with_arguments() {
+        use crate::{
+            all_wips_active,
+            reducers::RadonReducers,
+            types::{integer::RadonInteger, string::RadonString},
+        };
+
+        // The arguments that follow the reducer code reach the reducer: discard the lowest and the
+        // highest values before averaging
+        let cbor_vec = Value::Array(vec![
+            Value::Integer(RadonOpCodes::StringParseJSONArray as i128),
+            Value::Array(vec![
+                Value::Integer(RadonOpCodes::ArrayReduce as i128),
+                Value::Integer(RadonReducers::AverageMeanTrimmed as i128),
+                Value::Integer(1),
+            ]),
+        ]);
+        let packed = serde_cbor::to_vec(&cbor_vec).unwrap();
+
+        let script = unpack_radon_script(&packed).unwrap();
+        assert_eq!(
+            script,
+            vec![
+                (RadonOpCodes::StringParseJSONArray, None),
+                (
+                    RadonOpCodes::ArrayReduce,
+                    Some(vec![Value::Integer(0x0c), Value::Integer(1)]),
+                ),
+            ]
+        );
+
+        let input = RadonTypes::from(RadonString::from("[1, 2, 3, 100, -50]"));
+        let mut context = ReportContext::default();
+        context.set_active_wips(all_wips_active());
+        let output = execute_radon_script(
+            input,
+            &script,
+            &mut context,
+            RadonScriptExecutionSettings::disable_all(),
+        )
+        .unwrap();
+        assert_eq!(output.result, RadonTypes::from(RadonInteger::from(2)));
+    }
+
     #[test]
     fn test_create_radon_script() {
         let expected = vec![
@@ -681,4 +932,15 @@ mod tests {
         let expected = RadError::UnknownReducer { code: 99 };
         assert_eq!(output, expected);
     }
+
+    #[test]
+    fn test_guard_turns_panics_into_errors() {
+        let call = (RadonOpCodes::Identity, None);
+
+        let output = guard(&call, || panic!("Operator panicked"));
+        assert_eq!(output, Err(RadError::Unknown));
+
+        let output = guard(&call, || Err(RadError::EmptyArray));
+        assert_eq!(output, Err(RadError::EmptyArray));
+    }
 }
@@ -11,8 +11,8 @@ use crate::{
     operators::{array as array_operators, identity, Operable, RadonOpCodes},
     script::RadonCall,
     types::{
-        boolean::RadonBoolean, bytes::RadonBytes, float::RadonFloat, integer::RadonInteger,
-        map::RadonMap, string::RadonString, RadonType, RadonTypes,
+        boolean::RadonBoolean, bounded, bytes::RadonBytes, float::RadonFloat,
+        integer::RadonInteger, map::RadonMap, string::RadonString, RadonType, RadonTypes,
     },
 };
 
@@ -28,6 +28,11 @@ impl RadonArray {
     pub fn is_homogeneous(&self) -> bool {
         self.is_homogeneous
     }
+
+    /// The items of the array, without cloning them.
+    pub fn as_slice(&self) -> &[RadonTypes] {
+        &self.value
+    }
 }
 
 impl RadonType<Vec<RadonTypes>> for RadonArray {
@@ -115,7 +120,9 @@ impl TryInto<Value> for RadonArray {
 
 impl fmt::Display for RadonArray {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}({:?})", RADON_ARRAY_TYPE_NAME, self.value)
+        write!(f, "{}(", RADON_ARRAY_TYPE_NAME)?;
+        bounded::fmt_items(f, &self.value)?;
+        write!(f, ")")
     }
 }
 
@@ -134,6 +141,16 @@ impl Operable for RadonArray {
             .as_ref()
             .map(ActiveWips::wip0024)
             .unwrap_or(true);
+        let wip0031 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0031)
+            .unwrap_or(true);
+        let wip0039 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0039)
+            .unwrap_or(true);
 
         match call {
             (RadonOpCodes::Identity, None) => identity(RadonTypes::from(self.clone())),
@@ -165,7 +182,16 @@ impl Operable for RadonArray {
             (RadonOpCodes::ArrayGetString, Some(args)) => {
                 array_operators::get::<RadonString, _>(self, args).map(RadonTypes::from)
             }
+            (RadonOpCodes::ArrayGetPath, Some(args)) if wip0031 => {
+                array_operators::get_path(self, args.as_slice())
+            }
             (RadonOpCodes::ArrayFilter, Some(args)) => array_operators::filter(self, args, context),
+            (RadonOpCodes::ArrayJoin, None) if wip0039 => {
+                array_operators::join(self, &[]).map(RadonTypes::from)
+            }
+            (RadonOpCodes::ArrayJoin, Some(args)) if wip0039 => {
+                array_operators::join(self, args).map(RadonTypes::from)
+            }
             (RadonOpCodes::ArrayMap, Some(args)) => array_operators::map(self, args, context),
             (RadonOpCodes::ArrayReduce, Some(args)) => array_operators::reduce(self, args, context),
             (RadonOpCodes::ArraySort, Some(args)) => array_operators::sort(self, args, context),
@@ -327,6 +353,60 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_operate_get_path_before_wip0031() {
+        let mut inner = BTreeMap::new();
+        inner.insert("price".to_string(), RadonFloat::from(1.5).into());
+        let input = RadonArray::from(vec![RadonMap::from(inner).into()]);
+        let call = (
+            RadonOpCodes::ArrayGetPath,
+            Some(vec![
+                Value::Array(vec![Value::Integer(0), Value::Text("price".to_string())]),
+                Value::Integer(3),
+            ]),
+        );
+
+        let output = input.operate(&call).unwrap();
+        assert_eq!(output, RadonTypes::from(RadonFloat::from(1.5)));
+
+        let mut context = ReportContext {
+            active_wips: Some(ActiveWips::default()),
+            ..ReportContext::default()
+        };
+        let result = input.operate_in_context(&call, &mut context);
+        assert!(matches!(result, Err(RadError::UnsupportedOperator { .. })));
+    }
+
+    #[test]
+    fn test_operate_join_before_wip0039() {
+        use witnet_data_structures::chain::tapi::all_wips_active;
+
+        let input = RadonArray::from(vec![
+            RadonString::from("Hello").into(),
+            RadonString::from("world!").into(),
+        ]);
+        let join = (
+            RadonOpCodes::ArrayJoin,
+            Some(vec![Value::Text(" ".to_string())]),
+        );
+
+        assert_eq!(
+            input.operate(&join),
+            Ok(RadonTypes::from(RadonString::from("Hello world!")))
+        );
+
+        let mut active_wips = all_wips_active();
+        active_wips.active_wips.remove("WIP0039");
+        let mut context = ReportContext {
+            active_wips: Some(active_wips),
+            ..ReportContext::default()
+        };
+        for call in [join, (RadonOpCodes::ArrayJoin, None)].iter() {
+            let result = input.operate_in_context(call, &mut context);
+            assert!(matches!(result, Err(RadError::UnsupportedOperator { .. })));
+        }
+    }
+
     #[test]
     fn test_operate_unimplemented() {
         let input = RadonArray::from(vec![]);
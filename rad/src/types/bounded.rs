@@ -0,0 +1,248 @@
+//! Size-bounded rendering of RADON values.
+//!
+//! Values parsed from the responses of data sources can be arbitrarily large, and they end up in
+//! error messages and reports through their `Display` implementations. These only show up to
+//! `MAX_DISPLAY_ITEMS` items of every array or map, `MAX_DISPLAY_STRING_LENGTH` characters of every
+//! string, and `MAX_DISPLAY_DEPTH` levels of nested arrays and maps, and count whatever they leave
+//! out:
+//! ```text
+//! RadonArray([1, 2.5, "three", h'04', {"five": [[…2 more]]}, …3 more])
+//! ```
+//!
+//! Only the rendering is bounded. Values keep all their contents, which are available through
+//! `RadonType::value`, and neither their encoding nor their `Debug` rendering, which is part of
+//! the encoding of some errors, depend on these bounds.
+use std::{collections::BTreeMap, fmt};
+
+use crate::types::{RadonType, RadonTypes};
+
+/// Items of an array or map that are rendered before counting the rest.
+pub const MAX_DISPLAY_ITEMS: usize = 8;
+/// Characters of a string, or hexadecimal digits of a byte string, that are rendered.
+pub const MAX_DISPLAY_STRING_LENGTH: usize = 64;
+/// Levels of nested arrays and maps that are rendered. Deeper ones only show how many items they
+/// have.
+pub const MAX_DISPLAY_DEPTH: usize = 3;
+
+const ELLIPSIS: &str = "…";
+
+/// Write the items of an array between brackets.
+pub(crate) fn fmt_items(f: &mut fmt::Formatter<'_>, items: &[RadonTypes]) -> fmt::Result {
+    write_items(f, items, 0)
+}
+
+/// Write the entries of a map between braces.
+pub(crate) fn fmt_entries(
+    f: &mut fmt::Formatter<'_>,
+    entries: &BTreeMap<String, RadonTypes>,
+) -> fmt::Result {
+    write_entries(f, entries, 0)
+}
+
+/// Write a string between quotes.
+pub(crate) fn fmt_string(f: &mut fmt::Formatter<'_>, string: &str) -> fmt::Result {
+    write!(f, "\"")?;
+    for c in string.chars().take(MAX_DISPLAY_STRING_LENGTH) {
+        write!(f, "{}", c.escape_debug())?;
+    }
+    if string.chars().nth(MAX_DISPLAY_STRING_LENGTH).is_some() {
+        write!(f, "{}", ELLIPSIS)?;
+    }
+    write!(f, "\"")
+}
+
+/// Write bytes in hexadecimal.
+pub(crate) fn fmt_hex(f: &mut fmt::Formatter<'_>, bytes: &[u8]) -> fmt::Result {
+    let shown = bytes.len().min(MAX_DISPLAY_STRING_LENGTH / 2);
+    write!(f, "{}", hex::encode(&bytes[..shown]))?;
+    if shown < bytes.len() {
+        write!(f, "{}", ELLIPSIS)?;
+    }
+
+    Ok(())
+}
+
+/// Render the items of an array between brackets, as in `fmt_items`.
+pub(crate) fn items_to_string(items: &[RadonTypes]) -> String {
+    struct Items<'a>(&'a [RadonTypes]);
+
+    impl fmt::Display for Items<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            fmt_items(f, self.0)
+        }
+    }
+
+    Items(items).to_string()
+}
+
+/// Write an item of an array or map that is `depth` levels deep into the rendered value, in a
+/// compact notation similar to the CBOR diagnostic one.
+fn write_item(f: &mut fmt::Formatter<'_>, item: &RadonTypes, depth: usize) -> fmt::Result {
+    match item {
+        RadonTypes::Array(array) => write_items(f, array.as_slice(), depth + 1),
+        RadonTypes::Boolean(boolean) => write!(f, "{}", boolean.value()),
+        RadonTypes::Bytes(bytes) => {
+            write!(f, "h'")?;
+            fmt_hex(f, bytes.as_bytes())?;
+            write!(f, "'")
+        }
+        RadonTypes::Float(float) => write!(f, "{:?}", float.value()),
+        RadonTypes::Integer(integer) => write!(f, "{}", integer.value()),
+        RadonTypes::Map(map) => write_entries(f, map.as_map(), depth + 1),
+        RadonTypes::RadonError(error) => write!(f, "{}", error),
+        RadonTypes::String(string) => fmt_string(f, string.as_str()),
+    }
+}
+
+fn write_items(f: &mut fmt::Formatter<'_>, items: &[RadonTypes], depth: usize) -> fmt::Result {
+    write!(f, "[")?;
+    write_bounded(f, items.iter(), items.len(), depth, |f, item| {
+        write_item(f, item, depth)
+    })?;
+    write!(f, "]")
+}
+
+fn write_entries(
+    f: &mut fmt::Formatter<'_>,
+    entries: &BTreeMap<String, RadonTypes>,
+    depth: usize,
+) -> fmt::Result {
+    write!(f, "{{")?;
+    write_bounded(
+        f,
+        entries.iter(),
+        entries.len(),
+        depth,
+        |f, (key, value)| {
+            fmt_string(f, key)?;
+            write!(f, ": ")?;
+            write_item(f, value, depth)
+        },
+    )?;
+    write!(f, "}}")
+}
+
+/// Write the items of a container separated by commas, up to `MAX_DISPLAY_ITEMS` of them, followed
+/// by how many were left out. Containers that are too deep only show how many items they have.
+fn write_bounded<I, F>(
+    f: &mut fmt::Formatter<'_>,
+    items: I,
+    len: usize,
+    depth: usize,
+    mut write_one: F,
+) -> fmt::Result
+where
+    I: Iterator,
+    F: FnMut(&mut fmt::Formatter<'_>, I::Item) -> fmt::Result,
+{
+    let shown = if depth < MAX_DISPLAY_DEPTH {
+        len.min(MAX_DISPLAY_ITEMS)
+    } else {
+        0
+    };
+    for (index, item) in items.take(shown).enumerate() {
+        if index > 0 {
+            write!(f, ", ")?;
+        }
+        write_one(f, item)?;
+    }
+
+    match (len - shown, shown) {
+        (0, _) => Ok(()),
+        (more, 0) => write!(f, "{}{} more", ELLIPSIS, more),
+        (more, _) => write!(f, ", {}{} more", ELLIPSIS, more),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::convert::TryFrom;
+
+    use witnet_data_structures::radon_error::RadonError;
+
+    use super::*;
+    use crate::{
+        error::RadError,
+        types::{
+            array::RadonArray, boolean::RadonBoolean, bytes::RadonBytes, float::RadonFloat,
+            integer::RadonInteger, map::RadonMap, string::RadonString,
+        },
+    };
+
+    fn integers(range: std::ops::Range<i128>) -> Vec<RadonTypes> {
+        range.map(|i| RadonInteger::from(i).into()).collect()
+    }
+
+    /// An array with more items than are rendered, holding values of every type, too long strings
+    /// and byte strings, and arrays nested too deep.
+    fn large_array() -> RadonArray {
+        let mut nested = BTreeMap::new();
+        nested.insert(
+            "nested".to_string(),
+            RadonArray::from(vec![RadonArray::from(integers(0..2)).into()]).into(),
+        );
+        let mut items = vec![
+            RadonInteger::from(1).into(),
+            RadonFloat::from(2.5).into(),
+            RadonString::from("a".repeat(100)).into(),
+            RadonBytes::from(vec![0xab; 40]).into(),
+            RadonBoolean::from(true).into(),
+            RadonMap::from(nested).into(),
+            RadonString::from(r#"say "hi""#).into(),
+            RadonError::try_from(RadError::RetrieveTimeout)
+                .unwrap()
+                .into(),
+        ];
+        items.extend(integers(0..1000));
+
+        RadonArray::from(items)
+    }
+
+    #[test]
+    fn test_large_array_rendering_is_bounded() {
+        let array = large_array();
+        let expected = format!(
+            r#"RadonArray([1, 2.5, "{}…", h'{}…', true, {{"nested": [[…2 more]]}}, "say \"hi\"", RadonError(Timeout during retrieval phase), …1000 more])"#,
+            "a".repeat(MAX_DISPLAY_STRING_LENGTH),
+            "ab".repeat(MAX_DISPLAY_STRING_LENGTH / 2),
+        );
+
+        assert_eq!(array.to_string(), expected);
+        assert_eq!(
+            RadonTypes::from(array).to_string(),
+            format!("RadonTypes::{}", expected)
+        );
+    }
+
+    #[test]
+    fn test_small_values_are_rendered_whole() {
+        let mut map = BTreeMap::new();
+        map.insert("b".to_string(), RadonBytes::from(vec![1, 2, 3]).into());
+        map.insert("a".to_string(), RadonArray::from(integers(0..3)).into());
+
+        assert_eq!(
+            RadonMap::from(map).to_string(),
+            r#"RadonMap({"a": [0, 1, 2], "b": h'010203'})"#
+        );
+        assert_eq!(
+            RadonArray::from(integers(0..MAX_DISPLAY_ITEMS as i128)).to_string(),
+            "RadonArray([0, 1, 2, 3, 4, 5, 6, 7])"
+        );
+        assert_eq!(RadonArray::from(vec![]).to_string(), "RadonArray([])");
+    }
+
+    #[test]
+    fn test_errors_render_bounded_arrays() {
+        let error = RadError::UnsupportedReducer {
+            array: large_array(),
+            reducer: "AverageMean".to_string(),
+        };
+        let expected = format!(
+            "Reducer `AverageMean` is not implemented for Array `{}`",
+            large_array()
+        );
+
+        assert_eq!(error.to_string(), expected);
+        assert!(expected.len() < 400);
+    }
+}
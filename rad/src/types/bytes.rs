@@ -2,14 +2,14 @@ use crate::{
     error::RadError,
     operators::{bytes as bytes_operators, identity, Operable, RadonOpCodes},
     script::RadonCall,
-    types::{RadonType, RadonTypes},
+    types::{bounded, RadonType, RadonTypes},
 };
 use serde_cbor::value::Value;
 use std::{
     convert::{TryFrom, TryInto},
     fmt,
 };
-use witnet_data_structures::radon_report::ReportContext;
+use witnet_data_structures::{chain::tapi::ActiveWips, radon_report::ReportContext};
 
 const RADON_BYTES_TYPE_NAME: &str = "RadonBytes";
 
@@ -18,6 +18,13 @@ pub struct RadonBytes {
     value: Vec<u8>,
 }
 
+impl RadonBytes {
+    /// The bytes, without cloning them.
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.value
+    }
+}
+
 impl RadonType<Vec<u8>> for RadonBytes {
     fn value(&self) -> Vec<u8> {
         self.value.clone()
@@ -74,13 +81,28 @@ impl From<Vec<u8>> for RadonBytes {
 
 impl fmt::Display for RadonBytes {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let hex_value = hex::encode(&self.value);
-        write!(f, "{}({:?})", RADON_BYTES_TYPE_NAME, hex_value)
+        write!(f, "{}(\"", RADON_BYTES_TYPE_NAME)?;
+        bounded::fmt_hex(f, &self.value)?;
+        write!(f, "\")")
     }
 }
 
 impl Operable for RadonBytes {
     fn operate(&self, call: &RadonCall) -> Result<RadonTypes, RadError> {
+        self.operate_in_context(call, &mut ReportContext::default())
+    }
+
+    fn operate_in_context(
+        &self,
+        call: &RadonCall,
+        context: &mut ReportContext<RadonTypes>,
+    ) -> Result<RadonTypes, RadError> {
+        let wip0033 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0033)
+            .unwrap_or(true);
+
         match call {
             // Identity
             (RadonOpCodes::Identity, None) => identity(RadonTypes::from(self.clone())),
@@ -90,6 +112,15 @@ impl Operable for RadonBytes {
             (RadonOpCodes::BytesHash, Some(args)) => bytes_operators::hash(self, args.as_slice())
                 .map(RadonTypes::from)
                 .map_err(Into::into),
+            (RadonOpCodes::BytesLength, None) if wip0033 => {
+                Ok(RadonTypes::from(bytes_operators::length(self)))
+            }
+            (RadonOpCodes::BytesSlice, Some(args)) if wip0033 => {
+                bytes_operators::slice(self, args.as_slice()).map(RadonTypes::from)
+            }
+            (RadonOpCodes::BytesAsInteger, None) if wip0033 => {
+                bytes_operators::to_integer(self).map(RadonTypes::from)
+            }
             // Unsupported / unimplemented
             (op_code, args) => Err(RadError::UnsupportedOperator {
                 input_type: RADON_BYTES_TYPE_NAME.to_string(),
@@ -98,12 +129,43 @@ impl Operable for RadonBytes {
             }),
         }
     }
+}
 
-    fn operate_in_context(
-        &self,
+#[cfg(test)]
+mod tests {
+    use witnet_data_structures::chain::tapi::all_wips_active;
+
+    use super::*;
+
+    fn operate(
+        input: &RadonBytes,
         call: &RadonCall,
-        _context: &mut ReportContext<RadonTypes>,
+        active_wips: ActiveWips,
     ) -> Result<RadonTypes, RadError> {
-        self.operate(call)
+        let mut context = ReportContext {
+            active_wips: Some(active_wips),
+            ..ReportContext::default()
+        };
+
+        input.operate_in_context(call, &mut context)
+    }
+
+    #[test]
+    fn test_operate_bytes_operators_before_wip0033() {
+        let input = RadonBytes::from(vec![0x01, 0x02, 0x03]);
+
+        for call in [
+            (RadonOpCodes::BytesLength, None),
+            (RadonOpCodes::BytesSlice, Some(vec![Value::from(1)])),
+            (RadonOpCodes::BytesAsInteger, None),
+        ] {
+            assert!(operate(&input, &call, all_wips_active()).is_ok());
+            let result = operate(&input, &call, ActiveWips::default());
+            assert!(
+                matches!(result, Err(RadError::UnsupportedOperator { .. })),
+                "{:?}",
+                call
+            );
+        }
     }
 }
@@ -5,13 +5,13 @@ use std::{
 };
 
 use serde_cbor::value::Value;
-use witnet_data_structures::radon_report::ReportContext;
+use witnet_data_structures::{chain::tapi::ActiveWips, radon_report::ReportContext};
 
 use crate::{
     error::RadError,
     operators::{float as float_operators, identity, Operable, RadonOpCodes},
     script::RadonCall,
-    types::{string::RadonString, RadonType, RadonTypes},
+    types::{integer::RadonInteger, string::RadonString, RadonType, RadonTypes},
 };
 
 const RADON_FLOAT_TYPE_NAME: &str = "RadonFloat";
@@ -110,42 +110,128 @@ impl TryFrom<&str> for RadonFloat {
 
 impl Operable for RadonFloat {
     fn operate(&self, call: &RadonCall) -> Result<RadonTypes, RadError> {
+        self.operate_in_context(call, &mut ReportContext::default())
+    }
+
+    fn operate_in_context(
+        &self,
+        call: &RadonCall,
+        context: &mut ReportContext<RadonTypes>,
+    ) -> Result<RadonTypes, RadError> {
+        let wip0029 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0029)
+            .unwrap_or(true);
+        // After WIP-0029, float operators fail instead of producing infinite or NaN results
+        let finite = |result: RadonFloat, operator: RadonOpCodes| {
+            if wip0029 {
+                float_operators::check_finite(result, operator)
+            } else {
+                Ok(result)
+            }
+        };
+        // After WIP-0032, comparisons take an optional epsilon and `FloatEquals` is available
+        let wip0032 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0032)
+            .unwrap_or(true);
+        // After WIP-0034, `FloatPower` fails with specific errors and exponents are bounded
+        let wip0034 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0034)
+            .unwrap_or(true);
+        // After WIP-0038, `FloatAsInteger` is available
+        let wip0038 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0038)
+            .unwrap_or(true);
+        // After WIP-0029, rounding operators fail instead of saturating
+        let to_integer = |rounded: f64, legacy: fn(&RadonFloat) -> RadonInteger, operator| {
+            if wip0029 {
+                float_operators::checked_to_integer(self, rounded, operator)
+            } else {
+                Ok(legacy(self))
+            }
+        };
+
         match call {
             // Identity
             (RadonOpCodes::Identity, None) => identity(RadonTypes::from(self.clone())),
             (RadonOpCodes::FloatAbsolute, None) => {
-                Ok(RadonTypes::from(float_operators::absolute(self)))
+                finite(float_operators::absolute(self), RadonOpCodes::FloatAbsolute).map(Into::into)
             }
             (RadonOpCodes::FloatAsString, None) => float_operators::to_string(self.clone())
                 .map(RadonTypes::from)
                 .map_err(Into::into),
-            (RadonOpCodes::FloatCeiling, None) => {
-                Ok(RadonTypes::from(float_operators::ceiling(self)))
+            (RadonOpCodes::FloatAsInteger, None) if wip0038 => {
+                float_operators::to_integer(self, &[]).map(Into::into)
+            }
+            (RadonOpCodes::FloatAsInteger, Some(args)) if wip0038 => {
+                float_operators::to_integer(self, args.as_slice()).map(Into::into)
+            }
+            (RadonOpCodes::FloatCeiling, None) => to_integer(
+                self.value().ceil(),
+                float_operators::ceiling,
+                RadonOpCodes::FloatCeiling,
+            )
+            .map(Into::into),
+            (RadonOpCodes::FloatGreaterThan, Some(args)) => if wip0032 {
+                float_operators::greater_than_with_epsilon(self, args)
+            } else {
+                float_operators::greater_than(self, args)
             }
-            (RadonOpCodes::FloatGreaterThan, Some(args)) => {
-                float_operators::greater_than(self, args).map(Into::into)
+            .map(Into::into),
+            (RadonOpCodes::FloatLessThan, Some(args)) => if wip0032 {
+                float_operators::less_than_with_epsilon(self, args)
+            } else {
+                float_operators::less_than(self, args)
             }
-            (RadonOpCodes::FloatLessThan, Some(args)) => {
-                float_operators::less_than(self, args).map(Into::into)
+            .map(Into::into),
+            (RadonOpCodes::FloatEquals, Some(args)) if wip0032 => {
+                float_operators::equals(self, args).map(Into::into)
             }
             (RadonOpCodes::FloatMultiply, Some(args)) => {
-                float_operators::multiply(self, args.as_slice()).map(Into::into)
+                float_operators::multiply(self, args.as_slice())
+                    .and_then(|result| finite(result, RadonOpCodes::FloatMultiply))
+                    .map(Into::into)
             }
             (RadonOpCodes::FloatModulo, Some(args)) => {
-                float_operators::modulo(self, args.as_slice()).map(Into::into)
+                float_operators::modulo(self, args.as_slice())
+                    .and_then(|result| finite(result, RadonOpCodes::FloatModulo))
+                    .map(Into::into)
             }
-            (RadonOpCodes::FloatFloor, None) => Ok(RadonTypes::from(float_operators::floor(self))),
-
+            (RadonOpCodes::FloatFloor, None) => to_integer(
+                self.value().floor(),
+                float_operators::floor,
+                RadonOpCodes::FloatFloor,
+            )
+            .map(Into::into),
             (RadonOpCodes::FloatNegate, None) => {
-                Ok(RadonTypes::from(float_operators::negate(self)))
-            }
-            (RadonOpCodes::FloatPower, Some(args)) => {
-                float_operators::power(self, args.as_slice()).map(Into::into)
+                finite(float_operators::negate(self), RadonOpCodes::FloatNegate).map(Into::into)
             }
-            (RadonOpCodes::FloatRound, None) => Ok(RadonTypes::from(float_operators::round(self))),
-            (RadonOpCodes::FloatTruncate, None) => {
-                Ok(RadonTypes::from(float_operators::truncate(self)))
+            (RadonOpCodes::FloatPower, Some(args)) => if wip0034 {
+                float_operators::checked_power(self, args.as_slice())
+            } else {
+                float_operators::power(self, args.as_slice())
+                    .and_then(|result| finite(result, RadonOpCodes::FloatPower))
             }
+            .map(Into::into),
+            (RadonOpCodes::FloatRound, None) => to_integer(
+                self.value().round(),
+                float_operators::round,
+                RadonOpCodes::FloatRound,
+            )
+            .map(Into::into),
+            (RadonOpCodes::FloatTruncate, None) => to_integer(
+                self.value().trunc(),
+                float_operators::truncate,
+                RadonOpCodes::FloatTruncate,
+            )
+            .map(Into::into),
             // Unsupported / unimplemented
             (op_code, args) => Err(RadError::UnsupportedOperator {
                 input_type: RADON_FLOAT_TYPE_NAME.to_string(),
@@ -154,14 +240,6 @@ impl Operable for RadonFloat {
             }),
         }
     }
-
-    fn operate_in_context(
-        &self,
-        call: &RadonCall,
-        _context: &mut ReportContext<RadonTypes>,
-    ) -> Result<RadonTypes, RadError> {
-        self.operate(call)
-    }
 }
 
 impl fmt::Display for RadonFloat {
@@ -189,3 +267,207 @@ fn test_from_vector() {
 
     assert_eq!(result, expected);
 }
+
+#[test]
+fn test_operate_non_finite_results() {
+    let cases = vec![
+        (
+            RadonFloat::from(f64::MAX),
+            (RadonOpCodes::FloatMultiply, Some(vec![Value::Float(10.0)])),
+            RadError::NonFiniteFloat {
+                operator: "FloatMultiply".to_string(),
+                result: "inf".to_string(),
+            },
+        ),
+        (
+            RadonFloat::from(10.0),
+            (RadonOpCodes::FloatPower, Some(vec![Value::Float(400.0)])),
+            RadError::Overflow {
+                operator: "FloatPower".to_string(),
+                operands: vec![Value::Float(10.0), Value::Float(400.0)],
+            },
+        ),
+        (
+            RadonFloat::from(-8.0),
+            (RadonOpCodes::FloatPower, Some(vec![Value::Float(0.5)])),
+            RadError::UndefinedPower {
+                operator: "FloatPower".to_string(),
+                base: "-8".to_string(),
+                exponent: "0.5".to_string(),
+            },
+        ),
+        (
+            RadonFloat::from(f64::NAN),
+            (RadonOpCodes::FloatAbsolute, None),
+            RadError::NonFiniteFloat {
+                operator: "FloatAbsolute".to_string(),
+                result: "NaN".to_string(),
+            },
+        ),
+        (
+            RadonFloat::from(f64::NAN),
+            (RadonOpCodes::FloatRound, None),
+            RadError::NonFiniteFloat {
+                operator: "FloatRound".to_string(),
+                result: "NaN".to_string(),
+            },
+        ),
+        (
+            RadonFloat::from(1e40),
+            (RadonOpCodes::FloatFloor, None),
+            RadError::Overflow {
+                operator: "FloatFloor".to_string(),
+                operands: vec![Value::Float(1e40)],
+            },
+        ),
+        (
+            RadonFloat::from(-1e40),
+            (RadonOpCodes::FloatCeiling, None),
+            RadError::Overflow {
+                operator: "FloatCeiling".to_string(),
+                operands: vec![Value::Float(-1e40)],
+            },
+        ),
+    ];
+
+    for (input, call, expected) in cases {
+        assert_eq!(input.operate(&call), Err(expected), "{:?}", call);
+    }
+}
+
+#[test]
+fn test_operate_non_finite_results_before_wip0029() {
+    let mut context = ReportContext {
+        active_wips: Some(ActiveWips::default()),
+        ..ReportContext::default()
+    };
+
+    let input = RadonFloat::from(10.0);
+    let call = (RadonOpCodes::FloatPower, Some(vec![Value::Float(400.0)]));
+    assert_eq!(
+        input.operate_in_context(&call, &mut context),
+        Ok(RadonTypes::from(RadonFloat::from(f64::INFINITY)))
+    );
+
+    // Rounding saturates
+    let input = RadonFloat::from(1e40);
+    let call = (RadonOpCodes::FloatFloor, None);
+    assert_eq!(
+        input.operate_in_context(&call, &mut context),
+        Ok(RadonTypes::from(RadonInteger::from(i128::MAX)))
+    );
+
+    let input = RadonFloat::from(f64::NAN);
+    let call = (RadonOpCodes::FloatRound, None);
+    assert_eq!(
+        input.operate_in_context(&call, &mut context),
+        Ok(RadonTypes::from(RadonInteger::from(0)))
+    );
+}
+
+#[test]
+fn test_operate_power_before_wip0034() {
+    use witnet_data_structures::chain::tapi::all_wips_active;
+
+    let mut active_wips = all_wips_active();
+    active_wips.active_wips.remove("WIP0034");
+    let mut context = ReportContext {
+        active_wips: Some(active_wips),
+        ..ReportContext::default()
+    };
+    let power = |exp: f64| (RadonOpCodes::FloatPower, Some(vec![Value::Float(exp)]));
+
+    // Non-finite results are reported as such, and large exponents are not limited
+    assert_eq!(
+        RadonFloat::from(-8.0).operate_in_context(&power(0.5), &mut context),
+        Err(RadError::NonFiniteFloat {
+            operator: "FloatPower".to_string(),
+            result: "NaN".to_string(),
+        })
+    );
+    assert_eq!(
+        RadonFloat::from(0.0).operate_in_context(&power(-1.0), &mut context),
+        Err(RadError::NonFiniteFloat {
+            operator: "FloatPower".to_string(),
+            result: "inf".to_string(),
+        })
+    );
+    assert_eq!(
+        RadonFloat::from(1.0).operate_in_context(&power(1e6), &mut context),
+        Ok(RadonTypes::from(RadonFloat::from(1.0)))
+    );
+    assert_eq!(
+        RadonFloat::from(0.0).operate_in_context(&power(0.0), &mut context),
+        Ok(RadonTypes::from(RadonFloat::from(1.0)))
+    );
+
+    // Before WIP-0029, they are not even checked
+    let mut context = ReportContext {
+        active_wips: Some(ActiveWips::default()),
+        ..ReportContext::default()
+    };
+    let result = RadonFloat::from(-8.0).operate_in_context(&power(0.5), &mut context);
+    assert!(matches!(result, Ok(RadonTypes::Float(root)) if root.value().is_nan()));
+}
+
+#[test]
+fn test_operate_as_integer_before_wip0038() {
+    use witnet_data_structures::chain::tapi::all_wips_active;
+
+    let input = RadonFloat::from(-2.5);
+    let as_integer = (RadonOpCodes::FloatAsInteger, Some(vec![Value::Integer(3)]));
+
+    assert_eq!(
+        input.operate(&as_integer),
+        Ok(RadonTypes::from(RadonInteger::from(-2)))
+    );
+
+    let mut active_wips = all_wips_active();
+    active_wips.active_wips.remove("WIP0038");
+    let mut context = ReportContext {
+        active_wips: Some(active_wips),
+        ..ReportContext::default()
+    };
+    for call in [as_integer, (RadonOpCodes::FloatAsInteger, None)].iter() {
+        let result = input.operate_in_context(call, &mut context);
+        assert!(matches!(result, Err(RadError::UnsupportedOperator { .. })));
+    }
+}
+
+#[test]
+fn test_operate_compare_with_epsilon_before_wip0032() {
+    use crate::types::boolean::RadonBoolean;
+
+    let input = RadonFloat::from(0.1 + 0.2);
+    let greater = (
+        RadonOpCodes::FloatGreaterThan,
+        Some(vec![Value::Float(0.3), Value::Float(1e-9)]),
+    );
+    let equals = (
+        RadonOpCodes::FloatEquals,
+        Some(vec![Value::Float(0.3), Value::Float(1e-9)]),
+    );
+
+    assert_eq!(
+        input.operate(&greater),
+        Ok(RadonTypes::from(RadonBoolean::from(false)))
+    );
+    assert_eq!(
+        input.operate(&equals),
+        Ok(RadonTypes::from(RadonBoolean::from(true)))
+    );
+
+    // The epsilon is ignored and `FloatEquals` does not exist
+    let mut context = ReportContext {
+        active_wips: Some(ActiveWips::default()),
+        ..ReportContext::default()
+    };
+    assert_eq!(
+        input.operate_in_context(&greater, &mut context),
+        Ok(RadonTypes::from(RadonBoolean::from(true)))
+    );
+    assert!(matches!(
+        input.operate_in_context(&equals, &mut context),
+        Err(RadError::UnsupportedOperator { .. })
+    ));
+}
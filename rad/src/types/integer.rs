@@ -12,7 +12,7 @@ use crate::{
     script::RadonCall,
     types::{string::RadonString, RadonType, RadonTypes},
 };
-use witnet_data_structures::radon_report::ReportContext;
+use witnet_data_structures::{chain::tapi::ActiveWips, radon_report::ReportContext};
 
 const RADON_INTEGER_TYPE_NAME: &str = "RadonInteger";
 
@@ -99,6 +99,21 @@ impl TryFrom<&str> for RadonInteger {
 
 impl Operable for RadonInteger {
     fn operate(&self, call: &RadonCall) -> Result<RadonTypes, RadError> {
+        self.operate_in_context(call, &mut ReportContext::default())
+    }
+
+    fn operate_in_context(
+        &self,
+        call: &RadonCall,
+        context: &mut ReportContext<RadonTypes>,
+    ) -> Result<RadonTypes, RadError> {
+        // After WIP-0034, negative exponents result in a float and exponents are bounded
+        let wip0034 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0034)
+            .unwrap_or(true);
+
         match call {
             // Identity
             (RadonOpCodes::Identity, None) => identity(RadonTypes::from(self.clone())),
@@ -127,7 +142,11 @@ impl Operable for RadonInteger {
                 .map(RadonTypes::from)
                 .map_err(Into::into),
             (RadonOpCodes::IntegerPower, Some(args)) => {
-                integer_operators::power(self, args.as_slice()).map(Into::into)
+                if wip0034 {
+                    integer_operators::power_with_negative_exponent(self, args.as_slice())
+                } else {
+                    integer_operators::power(self, args.as_slice()).map(Into::into)
+                }
             }
             // Unsupported / unimplemented
             (op_code, args) => Err(RadError::UnsupportedOperator {
@@ -137,14 +156,6 @@ impl Operable for RadonInteger {
             }),
         }
     }
-
-    fn operate_in_context(
-        &self,
-        call: &RadonCall,
-        _context: &mut ReportContext<RadonTypes>,
-    ) -> Result<RadonTypes, RadError> {
-        self.operate(call)
-    }
 }
 
 impl fmt::Display for RadonInteger {
@@ -172,3 +183,82 @@ fn test_from_vector() {
 
     assert_eq!(result, expected);
 }
+
+#[test]
+fn test_operate_power() {
+    use crate::types::float::RadonFloat;
+
+    let power = |base: i128, exp: i128| {
+        (
+            RadonInteger::from(base),
+            (RadonOpCodes::IntegerPower, Some(vec![Value::Integer(exp)])),
+        )
+    };
+    let cases = vec![
+        (power(2, 10), Ok(RadonTypes::from(RadonInteger::from(1024)))),
+        (power(0, 0), Ok(RadonTypes::from(RadonInteger::from(1)))),
+        (power(2, -1), Ok(RadonTypes::from(RadonFloat::from(0.5)))),
+        (
+            power(0, -2),
+            Err(RadError::UndefinedPower {
+                operator: "IntegerPower".to_string(),
+                base: "0".to_string(),
+                exponent: "-2".to_string(),
+            }),
+        ),
+        (
+            power(3, 81),
+            Err(RadError::Overflow {
+                operator: "IntegerPower".to_string(),
+                operands: vec![Value::Integer(3), Value::Integer(81)],
+            }),
+        ),
+        (
+            power(1, 1025),
+            Err(RadError::ExponentOutOfRange {
+                operator: "IntegerPower".to_string(),
+                exponent: "1025".to_string(),
+                max: integer_operators::MAX_POWER_EXPONENT,
+            }),
+        ),
+    ];
+
+    for ((input, call), expected) in cases {
+        assert_eq!(input.operate(&call), expected, "{:?}", call);
+    }
+}
+
+#[test]
+fn test_operate_power_before_wip0034() {
+    let mut context = ReportContext {
+        active_wips: Some(ActiveWips::default()),
+        ..ReportContext::default()
+    };
+    let power = |exp: i128| (RadonOpCodes::IntegerPower, Some(vec![Value::Integer(exp)]));
+
+    // Negative exponents are rejected, large ones are not limited, and zero to the power of zero
+    // is one
+    assert!(matches!(
+        RadonInteger::from(2).operate_in_context(&power(-1), &mut context),
+        Err(RadError::WrongArguments { .. })
+    ));
+    assert!(matches!(
+        RadonInteger::from(0).operate_in_context(&power(-2), &mut context),
+        Err(RadError::WrongArguments { .. })
+    ));
+    assert_eq!(
+        RadonInteger::from(1).operate_in_context(&power(1025), &mut context),
+        Ok(RadonTypes::from(RadonInteger::from(1)))
+    );
+    assert_eq!(
+        RadonInteger::from(0).operate_in_context(&power(0), &mut context),
+        Ok(RadonTypes::from(RadonInteger::from(1)))
+    );
+    assert_eq!(
+        RadonInteger::from(3).operate_in_context(&power(81), &mut context),
+        Err(RadError::Overflow {
+            operator: "IntegerPower".to_string(),
+            operands: vec![Value::Integer(3), Value::Integer(81)],
+        })
+    );
+}
@@ -12,7 +12,7 @@ use crate::{
     operators::{identity, map as map_operators, Operable, RadonOpCodes},
     script::RadonCall,
     types::{
-        array::RadonArray, boolean::RadonBoolean, bytes::RadonBytes, float::RadonFloat,
+        array::RadonArray, boolean::RadonBoolean, bounded, bytes::RadonBytes, float::RadonFloat,
         integer::RadonInteger, string::RadonString, RadonType, RadonTypes,
     },
 };
@@ -24,6 +24,13 @@ pub struct RadonMap {
     value: BTreeMap<String, RadonTypes>,
 }
 
+impl RadonMap {
+    /// The entries of the map, without cloning them.
+    pub fn as_map(&self) -> &BTreeMap<String, RadonTypes> {
+        &self.value
+    }
+}
+
 impl RadonType<BTreeMap<String, RadonTypes>> for RadonMap {
     fn value(&self) -> BTreeMap<String, RadonTypes> {
         self.value.clone()
@@ -110,7 +117,9 @@ impl TryInto<Value> for RadonMap {
 
 impl fmt::Display for RadonMap {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}({:?})", RADON_MAP_TYPE_NAME, self.value)
+        write!(f, "{}(", RADON_MAP_TYPE_NAME)?;
+        bounded::fmt_entries(f, &self.value)?;
+        write!(f, ")")
     }
 }
 
@@ -129,6 +138,16 @@ impl Operable for RadonMap {
             .as_ref()
             .map(ActiveWips::wip0024)
             .unwrap_or(true);
+        let wip0031 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0031)
+            .unwrap_or(true);
+        let wip0037 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0037)
+            .unwrap_or(true);
 
         match call {
             (RadonOpCodes::Identity, None) => identity(RadonTypes::from(self.clone())),
@@ -159,8 +178,17 @@ impl Operable for RadonMap {
             (RadonOpCodes::MapGetString, Some(args)) => {
                 map_operators::get::<RadonString, _>(self, args.as_slice()).map(RadonTypes::from)
             }
+            (RadonOpCodes::MapGetPath, Some(args)) if wip0031 => {
+                map_operators::get_path(self, args.as_slice())
+            }
             (RadonOpCodes::MapKeys, None) => Ok(RadonTypes::from(map_operators::keys(self))),
             (RadonOpCodes::MapValues, None) => Ok(RadonTypes::from(map_operators::values(self))),
+            (RadonOpCodes::MapPick, Some(args)) if wip0037 => {
+                map_operators::pick(self, args.as_slice()).map(RadonTypes::from)
+            }
+            (RadonOpCodes::MapRename, Some(args)) if wip0037 => {
+                map_operators::rename(self, args.as_slice()).map(RadonTypes::from)
+            }
             (op_code, args) => Err(RadError::UnsupportedOperator {
                 input_type: RADON_MAP_TYPE_NAME.to_string(),
                 operator: op_code.to_string(),
@@ -172,7 +200,7 @@ impl Operable for RadonMap {
 
 #[cfg(test)]
 mod tests {
-    use witnet_data_structures::radon_report::TypeLike;
+    use witnet_data_structures::{chain::tapi::all_wips_active, radon_report::TypeLike};
 
     use crate::types::integer::RadonInteger;
 
@@ -193,6 +221,79 @@ mod tests {
         assert_eq!(output, expected);
     }
 
+    #[test]
+    fn test_operate_get_path_before_wip0031() {
+        let mut inner = BTreeMap::new();
+        inner.insert("price".to_string(), RadonInteger::from(42).into());
+        let mut map = BTreeMap::new();
+        map.insert(
+            "items".to_string(),
+            RadonArray::from(vec![RadonMap::from(inner).into()]).into(),
+        );
+        let input = RadonMap::from(map);
+        let call = (
+            RadonOpCodes::MapGetPath,
+            Some(vec![
+                Value::Array(vec![
+                    Value::Text("items".to_string()),
+                    Value::Integer(0),
+                    Value::Text("price".to_string()),
+                ]),
+                Value::Integer(4),
+            ]),
+        );
+
+        let output = input.operate(&call).unwrap();
+        assert_eq!(output, RadonTypes::from(RadonInteger::from(42)));
+
+        let mut context = ReportContext {
+            active_wips: Some(ActiveWips::default()),
+            ..ReportContext::default()
+        };
+        let result = input.operate_in_context(&call, &mut context);
+        assert!(matches!(result, Err(RadError::UnsupportedOperator { .. })));
+    }
+
+    #[test]
+    fn test_operate_pick_and_rename_before_wip0037() {
+        let mut map = BTreeMap::new();
+        map.insert("last".to_string(), RadonInteger::from(42).into());
+        map.insert("volume".to_string(), RadonInteger::from(7).into());
+        let input = RadonMap::from(map);
+        let pick = (
+            RadonOpCodes::MapPick,
+            Some(vec![Value::Array(vec![Value::Text("last".to_string())])]),
+        );
+        let rename = (
+            RadonOpCodes::MapRename,
+            Some(vec![Value::Map(BTreeMap::from([(
+                Value::Text("last".to_string()),
+                Value::Text("price".to_string()),
+            )]))]),
+        );
+
+        let picked = input.operate(&pick).unwrap();
+        let mut expected = BTreeMap::new();
+        expected.insert("last".to_string(), RadonInteger::from(42).into());
+        assert_eq!(picked, RadonTypes::from(RadonMap::from(expected)));
+        let renamed = input.operate(&rename).unwrap();
+        let mut expected = BTreeMap::new();
+        expected.insert("price".to_string(), RadonInteger::from(42).into());
+        expected.insert("volume".to_string(), RadonInteger::from(7).into());
+        assert_eq!(renamed, RadonTypes::from(RadonMap::from(expected)));
+
+        let mut active_wips = all_wips_active();
+        active_wips.active_wips.remove("WIP0037");
+        let mut context = ReportContext {
+            active_wips: Some(active_wips),
+            ..ReportContext::default()
+        };
+        for call in [pick, rename] {
+            let result = input.operate_in_context(&call, &mut context);
+            assert!(matches!(result, Err(RadError::UnsupportedOperator { .. })));
+        }
+    }
+
     #[test]
     fn test_operate_unimplemented() {
         let mut map = BTreeMap::new();
@@ -26,6 +26,7 @@ use crate::{
 
 pub mod array;
 pub mod boolean;
+pub mod bounded;
 pub mod bytes;
 pub mod float;
 pub mod integer;
@@ -95,16 +96,17 @@ impl RadonTypes {
         8
     }
 
-    pub fn as_operable(&self) -> &dyn Operable {
+    /// Operators can be applied to any of the RADON types, except for `RadonTypes::RadonError`.
+    pub fn as_operable(&self) -> Option<&dyn Operable> {
         match self {
-            RadonTypes::Array(inner) => inner,
-            RadonTypes::Boolean(inner) => inner,
-            RadonTypes::Bytes(inner) => inner,
-            RadonTypes::Float(inner) => inner,
-            RadonTypes::Integer(inner) => inner,
-            RadonTypes::Map(inner) => inner,
-            RadonTypes::RadonError(_) => panic!("`RadonTypes::RadonError` is not operable"),
-            RadonTypes::String(inner) => inner,
+            RadonTypes::Array(inner) => Some(inner),
+            RadonTypes::Boolean(inner) => Some(inner),
+            RadonTypes::Bytes(inner) => Some(inner),
+            RadonTypes::Float(inner) => Some(inner),
+            RadonTypes::Integer(inner) => Some(inner),
+            RadonTypes::Map(inner) => Some(inner),
+            RadonTypes::RadonError(_) => None,
+            RadonTypes::String(inner) => Some(inner),
         }
     }
 
@@ -331,9 +333,11 @@ impl TryFrom<RadonTypes> for Value {
             RadonTypes::Array(radon_array) => radon_array.try_into(),
             RadonTypes::Boolean(radon_boolean) => radon_boolean.try_into(),
             RadonTypes::Bytes(radon_bytes) => radon_bytes.try_into(),
-            RadonTypes::RadonError(error) => panic!(
-                "Should never try to build a `serde_cbor::Value` from `RadonTypes::RadonError`. Error was: {:?}", error
-            ),
+            // A `RadonError` can only be encoded as tagged CBOR, see `encode_tagged_bytes`
+            RadonTypes::RadonError(_) => Err(RadError::Encode {
+                from: "RadonError",
+                to: "serde_cbor::Value",
+            }),
             RadonTypes::Float(radon_float) => radon_float.try_into(),
             RadonTypes::Integer(radon_integer) => radon_integer.try_into(),
             RadonTypes::Map(radon_map) => radon_map.try_into(),
@@ -459,58 +463,162 @@ impl TryFrom<CborValue> for RadonTypes {
     }
 }
 
-/// Allow JSON decoding of any variant of `RadonTypes`.
-impl TryFrom<JsonValue> for RadonTypes {
-    type Error = RadError;
+/// Largest magnitude up to which every integral number can be represented exactly by a float.
+const MAX_EXACT_FLOAT_INTEGER: f64 = 9_007_199_254_740_992.0;
+
+/// How JSON values are converted into `RadonTypes`.
+///
+/// Both conversions follow the rules of the `ParseJSON` operators, which cannot change without
+/// breaking consensus:
+/// - booleans and strings map to `RadonBoolean` and `RadonString`
+/// - numbers written as integers that fit in `i64` or `u64` map to `RadonInteger`
+/// - other numbers map to `RadonInteger` if they are positive, integral and below 10^38, e.g. `1e3`
+///   or `1.0`, and to `RadonFloat` otherwise, e.g. `0.5`, `0.0` or `-1e3`
+/// - arrays map to `RadonArray`, keeping their order
+/// - objects map to `RadonMap`, whose keys are sorted
+/// - `null` cannot be converted, and fails with `RadError::Decode`
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum JsonConversion {
+    /// As in the `ParseJSON` operators: the `null` items of arrays and objects are left out, and
+    /// numbers are converted even if they lost precision when parsing them.
+    #[default]
+    Lenient,
+    /// For values given by users, which are rejected rather than silently changed: `null` fails
+    /// at any depth, and so do integral numbers too large for a float to hold exactly (above 2^53
+    /// in magnitude) with `RadError::JsonPrecisionLoss`, as they may not be the numbers written.
+    Strict,
+}
 
-    #[allow(clippy::cast_possible_truncation)]
-    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+impl RadonTypes {
+    /// Convert a JSON value following the rules of `conversion`.
+    pub fn from_json(value: JsonValue, conversion: JsonConversion) -> Result<Self, RadError> {
         match value {
             JsonValue::Null => Err(RadError::Decode {
                 from: "JsonValue::Null",
                 to: "RadonTypes",
             }),
             JsonValue::Bool(boolean) => Ok(RadonBoolean::from(boolean).into()),
-            JsonValue::Number(number) => {
-                if number.is_i64() {
-                    Ok(RadonInteger::from(i128::from(number.as_i64().expect("i64"))).into())
-                } else if number.is_u64() {
-                    Ok(RadonInteger::from(i128::from(number.as_u64().expect("u64"))).into())
-                } else {
-                    // Floats that can be safely represented as i128 are converted
-                    let floating = number.as_f64().expect("f64");
-                    if floating.is_normal() && floating.fract() == 0.0 && floating.log10() < 38.0 {
-                        Ok(RadonInteger::from(floating as i128).into())
-                    } else {
-                        Ok(RadonFloat::from(floating).into())
-                    }
-                }
-            }
+            JsonValue::Number(number) => json_number_to_radon(&number, conversion),
             JsonValue::String(string) => Ok(RadonString::from(string).into()),
-            JsonValue::Array(array) => Ok(RadonArray::from(
-                array
-                    .into_iter()
-                    // Skip null values
-                    .filter_map(|value| RadonTypes::try_from(value).ok())
-                    .collect::<Vec<_>>(),
-            )
-            .into()),
-            JsonValue::Object(object) => Ok(RadonMap::from(
-                object
+            JsonValue::Array(array) => array
+                .into_iter()
+                .filter_map(|value| json_item_to_radon(value, conversion).transpose())
+                .collect::<Result<Vec<_>, _>>()
+                .map(|items| RadonArray::from(items).into()),
+            JsonValue::Object(object) => object
+                .into_iter()
+                .filter_map(|(key, value)| {
+                    json_item_to_radon(value, conversion)
+                        .map(|value| value.map(|value| (key, value)))
+                        .transpose()
+                })
+                .collect::<Result<BTreeMap<_, _>, _>>()
+                .map(|map| RadonMap::from(map).into()),
+        }
+    }
+
+    /// Convert into plain JSON, as opposed to the JSON that `RadonTypes` are serialized into,
+    /// which is tagged with the name of the type.
+    ///
+    /// Integers that do not fit in `i64` or `u64` and floats that are not finite are written as
+    /// strings, bytes as hexadecimal strings, and errors as an object with their kind and fields
+    /// under `RadonError`. Converting the result back with `from_json` gives the same value for
+    /// all the other types, except for integral floats, which become integers.
+    pub fn to_json(&self) -> JsonValue {
+        match self {
+            RadonTypes::Array(array) => {
+                JsonValue::Array(array.value().iter().map(RadonTypes::to_json).collect())
+            }
+            RadonTypes::Boolean(boolean) => JsonValue::Bool(boolean.value()),
+            RadonTypes::Bytes(bytes) => JsonValue::String(hex::encode(bytes.value())),
+            RadonTypes::RadonError(error) => {
+                let mut object = serde_json::Map::new();
+                object.insert(
+                    "RadonError".to_string(),
+                    serde_json::to_value(error.inner()).unwrap_or(JsonValue::Null),
+                );
+
+                JsonValue::Object(object)
+            }
+            RadonTypes::Float(float) => serde_json::Number::from_f64(float.value())
+                .map(JsonValue::Number)
+                .unwrap_or_else(|| JsonValue::String(float.value().to_string())),
+            RadonTypes::Integer(integer) => {
+                let integer = integer.value();
+                i64::try_from(integer)
+                    .map(JsonValue::from)
+                    .or_else(|_| u64::try_from(integer).map(JsonValue::from))
+                    .unwrap_or_else(|_| JsonValue::String(integer.to_string()))
+            }
+            RadonTypes::Map(map) => JsonValue::Object(
+                map.value()
                     .iter()
-                    // Skip null values
-                    .filter_map(|(key, value)| {
-                        RadonTypes::try_from(value.clone())
-                            .map(|value| (key.into(), value))
-                            .ok()
-                    })
-                    .collect::<BTreeMap<_, _>>(),
-            )
-            .into()),
+                    .map(|(key, value)| (key.clone(), value.to_json()))
+                    .collect(),
+            ),
+            RadonTypes::String(string) => JsonValue::String(string.value()),
         }
     }
 }
 
+/// Allow JSON decoding of any variant of `RadonTypes`, as the `ParseJSON` operators do it. See
+/// `JsonConversion::Lenient`.
+impl TryFrom<JsonValue> for RadonTypes {
+    type Error = RadError;
+
+    fn try_from(value: JsonValue) -> Result<Self, Self::Error> {
+        RadonTypes::from_json(value, JsonConversion::Lenient)
+    }
+}
+
+impl From<RadonTypes> for JsonValue {
+    fn from(value: RadonTypes) -> Self {
+        value.to_json()
+    }
+}
+
+/// Convert an item of a JSON array or object, which is left out (`Ok(None)`) if it cannot be
+/// converted and the conversion is lenient.
+fn json_item_to_radon(
+    value: JsonValue,
+    conversion: JsonConversion,
+) -> Result<Option<RadonTypes>, RadError> {
+    match RadonTypes::from_json(value, conversion) {
+        Ok(value) => Ok(Some(value)),
+        Err(_) if conversion == JsonConversion::Lenient => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+#[allow(clippy::cast_possible_truncation)]
+fn json_number_to_radon(
+    number: &serde_json::Number,
+    conversion: JsonConversion,
+) -> Result<RadonTypes, RadError> {
+    if let Some(integer) = number.as_i64() {
+        return Ok(RadonInteger::from(i128::from(integer)).into());
+    }
+    if let Some(integer) = number.as_u64() {
+        return Ok(RadonInteger::from(i128::from(integer)).into());
+    }
+
+    let floating = number.as_f64().expect("f64");
+    let integral = floating.is_normal() && floating.fract() == 0.0;
+    if conversion == JsonConversion::Strict && integral && floating.abs() > MAX_EXACT_FLOAT_INTEGER
+    {
+        return Err(RadError::JsonPrecisionLoss {
+            number: number.to_string(),
+        });
+    }
+
+    // Floats that can be safely represented as i128 are converted
+    if integral && floating.log10() < 38.0 {
+        Ok(RadonInteger::from(floating as i128).into())
+    } else {
+        Ok(RadonFloat::from(floating).into())
+    }
+}
+
 /// Decode a vector of instances of RadonTypes from any iterator that yields `(&[u8], &T)`.
 /// The `err_action` argument allows the caller of this function to decide whether
 /// it should act in a lossy way, i.e. ignoring items that cannot be decoded or replacing them with
@@ -793,4 +901,124 @@ mod tests {
             .into()
         );
     }
+
+    #[test]
+    fn test_json_numbers_map_deterministically() {
+        let convert = |json: &str, conversion| {
+            RadonTypes::from_json(serde_json::from_str(json).unwrap(), conversion)
+        };
+        let integer = |integer: i128| Ok(RadonTypes::from(RadonInteger::from(integer)));
+        let float = |float: f64| Ok(RadonTypes::from(RadonFloat::from(float)));
+
+        for conversion in [JsonConversion::Lenient, JsonConversion::Strict] {
+            assert_eq!(convert("-42", conversion), integer(-42));
+            assert_eq!(
+                convert("18446744073709551615", conversion),
+                integer(i128::from(u64::MAX))
+            );
+            // Positive integral numbers written as floats are integers, the rest are floats
+            assert_eq!(convert("1e3", conversion), integer(1_000));
+            assert_eq!(convert("2.0", conversion), integer(2));
+            assert_eq!(convert("-1e3", conversion), float(-1_000.0));
+            assert_eq!(convert("0.0", conversion), float(0.0));
+            assert_eq!(convert("0.5", conversion), float(0.5));
+            assert_eq!(convert("9007199254740992", conversion), integer(1 << 53));
+        }
+
+        // Integers beyond `u64` are only read as floats, so their last digits may be lost
+        assert_eq!(
+            convert("123456789012345678901", JsonConversion::Lenient),
+            integer(123_456_789_012_345_683_968)
+        );
+        assert_eq!(
+            convert("123456789012345678901", JsonConversion::Strict),
+            Err(RadError::JsonPrecisionLoss {
+                number: "1.2345678901234568e20".to_string()
+            })
+        );
+        assert_eq!(convert("1e40", JsonConversion::Lenient), float(1e40));
+        assert!(matches!(
+            convert("-1e40", JsonConversion::Strict),
+            Err(RadError::JsonPrecisionLoss { .. })
+        ));
+    }
+
+    #[test]
+    fn test_nested_json_to_radon_types() {
+        let json = serde_json::json!({
+            "zeta": [1, 2.5, "three", null],
+            "alpha": { "nested": [true, { "null": null }] },
+            "null": null,
+        });
+
+        let lenient = RadonTypes::from_json(json.clone(), JsonConversion::Lenient).unwrap();
+        assert_eq!(
+            lenient,
+            RadonMap::from(BTreeMap::from([
+                (
+                    "alpha".to_string(),
+                    RadonMap::from(BTreeMap::from([(
+                        "nested".to_string(),
+                        RadonArray::from(vec![
+                            RadonBoolean::from(true).into(),
+                            RadonMap::from(BTreeMap::new()).into(),
+                        ])
+                        .into(),
+                    )]))
+                    .into(),
+                ),
+                (
+                    "zeta".to_string(),
+                    RadonArray::from(vec![
+                        RadonInteger::from(1).into(),
+                        RadonFloat::from(2.5).into(),
+                        RadonString::from("three").into(),
+                    ])
+                    .into(),
+                ),
+            ]))
+            .into()
+        );
+        // Keys are sorted whatever the order in which they were written
+        assert_eq!(
+            lenient.to_json().to_string(),
+            r#"{"alpha":{"nested":[true,{}]},"zeta":[1,2.5,"three"]}"#
+        );
+
+        assert_eq!(
+            RadonTypes::from_json(json, JsonConversion::Strict),
+            Err(RadError::Decode {
+                from: "JsonValue::Null",
+                to: "RadonTypes",
+            })
+        );
+    }
+
+    #[test]
+    fn test_radon_types_to_json() {
+        let json = |value: RadonTypes| JsonValue::from(value);
+
+        assert_eq!(
+            json(RadonInteger::from(i128::from(u64::MAX)).into()),
+            serde_json::json!(u64::MAX)
+        );
+        assert_eq!(
+            json(RadonInteger::from(i128::MIN).into()),
+            serde_json::json!(i128::MIN.to_string())
+        );
+        assert_eq!(
+            json(RadonFloat::from(f64::NAN).into()),
+            serde_json::json!("NaN")
+        );
+        assert_eq!(
+            json(RadonBytes::from(vec![0xca, 0xfe]).into()),
+            serde_json::json!("cafe")
+        );
+        assert_eq!(
+            json(RadonTypes::RadonError(RadonError::new(
+                RadError::EmptyArray
+            )))["RadonError"]["kind"],
+            serde_json::json!("empty_array")
+        );
+    }
 }
@@ -10,7 +10,7 @@ use crate::{
     error::RadError,
     operators::{identity, string as string_operators, Operable, RadonOpCodes},
     script::RadonCall,
-    types::{RadonType, RadonTypes},
+    types::{bounded, RadonType, RadonTypes},
 };
 
 const RADON_STRING_TYPE_NAME: &str = "RadonString";
@@ -20,6 +20,13 @@ pub struct RadonString {
     value: String,
 }
 
+impl RadonString {
+    /// The string, without cloning it.
+    pub fn as_str(&self) -> &str {
+        &self.value
+    }
+}
+
 impl RadonType<String> for RadonString {
     fn value(&self) -> String {
         self.value.clone()
@@ -102,6 +109,17 @@ impl Operable for RadonString {
             .as_ref()
             .map(ActiveWips::wip0024)
             .unwrap_or(true);
+        let wip0030 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0030)
+            .unwrap_or(true);
+        // After WIP-0036, `StringLength` takes an optional argument selecting the unit
+        let wip0036 = context
+            .active_wips
+            .as_ref()
+            .map(ActiveWips::wip0036)
+            .unwrap_or(true);
 
         match call {
             (RadonOpCodes::Identity, None) => identity(RadonTypes::from(self.clone())),
@@ -131,9 +149,15 @@ impl Operable for RadonString {
             (RadonOpCodes::StringMatch, Some(args)) => {
                 string_operators::string_match(self, args.as_slice()).map(RadonTypes::from)
             }
+            (RadonOpCodes::StringExtract, Some(args)) if wip0030 => {
+                string_operators::string_extract(self, args.as_slice()).map(RadonTypes::from)
+            }
             (RadonOpCodes::StringLength, None) => {
                 Ok(RadonTypes::from(string_operators::length(self)))
             }
+            (RadonOpCodes::StringLength, Some(args)) if wip0036 => {
+                string_operators::length_in_units(self, args).map(RadonTypes::from)
+            }
             (RadonOpCodes::StringToLowerCase, None) => {
                 Ok(RadonTypes::from(string_operators::to_lowercase(self)))
             }
@@ -154,7 +178,9 @@ impl Operable for RadonString {
 
 impl fmt::Display for RadonString {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, r#"{}("{}")"#, RADON_STRING_TYPE_NAME, self.value)
+        write!(f, "{}(", RADON_STRING_TYPE_NAME)?;
+        bounded::fmt_string(f, &self.value)?;
+        write!(f, ")")
     }
 }
 
@@ -190,3 +216,54 @@ fn test_serialize_radon_string() {
 
     assert_eq!(output, expected);
 }
+
+#[test]
+fn test_operate_string_extract_before_wip0030() {
+    let input = RadonString::from("price: 42");
+    let call = (
+        RadonOpCodes::StringExtract,
+        Some(vec![Value::Text(r"price: ([0-9]+)".to_string())]),
+    );
+
+    let output = input.operate(&call).unwrap();
+    assert_eq!(output, RadonTypes::from(RadonString::from("42")));
+
+    let mut context = ReportContext {
+        active_wips: Some(ActiveWips::default()),
+        ..ReportContext::default()
+    };
+    let result = input.operate_in_context(&call, &mut context);
+    assert!(matches!(result, Err(RadError::UnsupportedOperator { .. })));
+}
+
+#[test]
+fn test_operate_string_length_before_wip0036() {
+    use crate::types::integer::RadonInteger;
+    use witnet_data_structures::chain::tapi::all_wips_active;
+
+    let input = RadonString::from("👋🏽");
+    let call = (RadonOpCodes::StringLength, Some(vec![Value::Integer(2)]));
+
+    let output = input.operate(&call).unwrap();
+    assert_eq!(output, RadonTypes::from(RadonInteger::from(1)));
+
+    let mut active_wips = all_wips_active();
+    active_wips.active_wips.remove("WIP0036");
+    let mut context = ReportContext {
+        active_wips: Some(active_wips),
+        ..ReportContext::default()
+    };
+    let result = input.operate_in_context(&call, &mut context);
+    assert!(matches!(result, Err(RadError::UnsupportedOperator { .. })));
+
+    // Without argument, the length is counted in bytes both before and after WIP-0036
+    let call = (RadonOpCodes::StringLength, None);
+    assert_eq!(
+        input.operate_in_context(&call, &mut context),
+        Ok(RadonTypes::from(RadonInteger::from(8)))
+    );
+    assert_eq!(
+        input.operate(&call),
+        Ok(RadonTypes::from(RadonInteger::from(8)))
+    );
+}
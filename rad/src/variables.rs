@@ -0,0 +1,159 @@
+//! Substitution of the metadata of a data request into the URL and headers of its sources.
+//!
+//! Sources can tell which data request is asking, e.g. for auditing or for quoting per request,
+//! by referring to its metadata through a variable like `{{dr_hash}}`. Every witness fills the
+//! variables in from the `DataRequestMetadata` of its `ReportContext` right before sending the
+//! request, so only metadata that all the witnesses agree on can be referred to. Unknown variables
+//! are rejected when the data request is validated, rather than failing once it is retrieved.
+use witnet_data_structures::radon_report::DataRequestMetadata;
+
+use crate::{error::RadError, Result};
+
+/// Variables that the URL and headers of a source can refer to. The index of the witness is not
+/// one of them, as it is different for every witness.
+pub const RETRIEVAL_VARIABLES: &[&str] = &["dr_hash", "epoch"];
+
+/// Replace every `{{name}}` placeholder of a variable in `template` with its value for the given
+/// data request.
+///
+/// Anything between double braces that does not look like the name of a variable, like the
+/// `{{kind:name}}` placeholders of secrets, is left as is, and so are unknown variables, which can
+/// only be found in data requests that were validated before variables existed.
+pub fn substitute_variables(template: &str, data_request: &DataRequestMetadata) -> String {
+    let mut substituted = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some((start, name, end)) = next_placeholder(rest) {
+        let value = match name {
+            "dr_hash" => data_request.dr_hash.to_string(),
+            "epoch" => data_request.epoch.to_string(),
+            _ => rest[start..end].to_string(),
+        };
+        substituted.push_str(&rest[..start]);
+        substituted.push_str(&value);
+        rest = &rest[end..];
+    }
+    substituted.push_str(rest);
+
+    substituted
+}
+
+/// Check that every variable that `template` refers to is one of `RETRIEVAL_VARIABLES`, failing
+/// with `RadError::UnknownRetrievalVariable` otherwise.
+pub fn validate_variables(template: &str) -> Result<()> {
+    let mut rest = template;
+
+    while let Some((_, name, end)) = next_placeholder(rest) {
+        if !RETRIEVAL_VARIABLES.contains(&name) {
+            return Err(RadError::UnknownRetrievalVariable {
+                name: name.to_string(),
+            });
+        }
+        rest = &rest[end..];
+    }
+
+    Ok(())
+}
+
+/// Find the first placeholder of a variable in `template`, returning where it starts, the name of
+/// the variable, and where it ends.
+fn next_placeholder(template: &str) -> Option<(usize, &str, usize)> {
+    let mut offset = 0;
+
+    while let Some(start) = template[offset..].find("{{") {
+        let start = offset + start;
+        let after_open = &template[start + 2..];
+        let end = after_open.find("}}")?;
+        let name = &after_open[..end];
+        if is_variable_name(name) {
+            return Some((start, name, start + 2 + end + 2));
+        }
+        offset = start + 2;
+    }
+
+    None
+}
+
+/// Whether the text found between double braces names a variable, i.e. it is a lowercase
+/// identifier.
+fn is_variable_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_lowercase())
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+#[cfg(test)]
+mod tests {
+    use witnet_data_structures::chain::Hash;
+
+    use super::*;
+
+    fn data_request(epoch: u32) -> DataRequestMetadata {
+        DataRequestMetadata {
+            dr_hash: Hash::SHA256([0xab; 32]),
+            epoch,
+            simulated: false,
+        }
+    }
+
+    #[test]
+    fn test_substitute_variables_in_url() {
+        let url = "https://api.example.com/quote?dr={{dr_hash}}&epoch={{epoch}}";
+
+        assert_eq!(
+            substitute_variables(url, &data_request(1234)),
+            format!(
+                "https://api.example.com/quote?dr={}&epoch=1234",
+                "ab".repeat(32)
+            )
+        );
+    }
+
+    #[test]
+    fn test_substitute_variables_is_the_same_for_the_same_data_request() {
+        let header = "{{dr_hash}}/{{epoch}}";
+
+        assert_eq!(
+            substitute_variables(header, &data_request(7)),
+            substitute_variables(header, &data_request(7))
+        );
+        assert_ne!(
+            substitute_variables(header, &data_request(7)),
+            substitute_variables(header, &data_request(8))
+        );
+    }
+
+    #[test]
+    fn test_substitute_variables_leaves_secrets_and_other_braces_alone() {
+        let url =
+            "https://api.example.com/?key={{api_key:example}}&q={{ user { id } }}&e={{epoch}}";
+
+        assert_eq!(
+            substitute_variables(url, &data_request(3)),
+            "https://api.example.com/?key={{api_key:example}}&q={{ user { id } }}&e=3"
+        );
+        assert_eq!(
+            substitute_variables("{{witness_index}}", &data_request(3)),
+            "{{witness_index}}"
+        );
+    }
+
+    #[test]
+    fn test_validate_variables_rejects_unknown_variables() {
+        assert!(validate_variables("https://api.example.com/?dr={{dr_hash}}&e={{epoch}}").is_ok());
+        assert!(validate_variables("https://api.example.com/?key={{api_key:example}}").is_ok());
+        assert_eq!(
+            validate_variables("https://api.example.com/?w={{witness_index}}"),
+            Err(RadError::UnknownRetrievalVariable {
+                name: "witness_index".to_string()
+            })
+        );
+        assert_eq!(
+            validate_variables("{{epoch}}{{block_hash}}"),
+            Err(RadError::UnknownRetrievalVariable {
+                name: "block_hash".to_string()
+            })
+        );
+    }
+}
@@ -0,0 +1,169 @@
+use serde_cbor::Value;
+use witnet_data_structures::chain::{
+    RADAggregate, RADFilter, RADRequest, RADRetrieve, RADTally, RADType,
+};
+use witnet_rad::{
+    cbor_to_vec,
+    cost::{estimate_cost, CostBreakdown, CostTable, InputSizes, LATEST_COST_TABLE},
+    error::RadError,
+};
+
+fn call(operator: u8, args: Vec<Value>) -> Value {
+    Value::Array(
+        std::iter::once(Value::Integer(operator.into()))
+            .chain(args)
+            .collect(),
+    )
+}
+
+fn text(text: &str) -> Value {
+    Value::Text(text.to_string())
+}
+
+fn http_get(script: Value) -> RADRetrieve {
+    RADRetrieve {
+        kind: RADType::HttpGet,
+        url: "https://api.example.com/prices".to_string(),
+        script: cbor_to_vec(&script).unwrap(),
+        body: vec![],
+        headers: vec![],
+    }
+}
+
+fn deviation_standard(sigmas: f64) -> RADFilter {
+    RADFilter {
+        op: 0x05,
+        args: cbor_to_vec(&Value::Float(sigmas)).unwrap(),
+    }
+}
+
+/// A single source whose price is taken straight from a JSON map.
+fn trivial_request() -> RADRequest {
+    RADRequest {
+        time_lock: 0,
+        retrieve: vec![http_get(Value::Array(vec![
+            Value::Integer(0x77),
+            call(0x64, vec![text("price")]),
+        ]))],
+        aggregate: RADAggregate {
+            filters: vec![],
+            reducer: 0x03,
+        },
+        tally: RADTally {
+            filters: vec![],
+            reducer: 0x03,
+        },
+    }
+}
+
+/// Two sources that average the prices of every market, each of them an array of maps, and take
+/// the median of those averages.
+fn nested_request() -> RADRequest {
+    let average_price = Value::Array(vec![
+        call(
+            0x1A,
+            vec![Value::Array(vec![call(0x64, vec![text("price")])])],
+        ),
+        call(0x1B, vec![Value::Integer(0x03)]),
+    ]);
+    let script = Value::Array(vec![
+        Value::Integer(0x76),
+        call(0x1A, vec![average_price]),
+        call(0x1B, vec![Value::Integer(0x05)]),
+    ]);
+
+    RADRequest {
+        time_lock: 0,
+        retrieve: vec![http_get(script.clone()), http_get(script)],
+        aggregate: RADAggregate {
+            filters: vec![deviation_standard(1.5)],
+            reducer: 0x03,
+        },
+        tally: RADTally {
+            filters: vec![deviation_standard(2.5)],
+            reducer: 0x03,
+        },
+    }
+}
+
+#[test]
+fn test_cost_of_trivial_script() {
+    let cost = estimate_cost(&trivial_request(), &InputSizes::default()).unwrap();
+
+    // Retrieval: 100 + 1024 bytes. StringParseJSONMap: 10 + 4 * 1024 bytes. MapGetFloat: 2
+    // Aggregation of 1 source and tally of 10 reveals: ArrayReduce 5 + AverageMean 5 + 1 per item
+    assert_eq!(
+        cost,
+        CostBreakdown {
+            version: 1,
+            retrieve: vec![5_232],
+            aggregate: 11,
+            tally: 20,
+            total: 5_263,
+        }
+    );
+}
+
+#[test]
+fn test_cost_of_nested_maps_over_large_inputs() {
+    let sizes = InputSizes {
+        response_bytes: 100_000,
+        collection_items: 100,
+        reveals: 10,
+    };
+    let cost = estimate_cost(&nested_request(), &sizes).unwrap();
+
+    // Retrieval: 100_100. StringParseJSONArray: 400_010
+    // Outer ArrayMap: 105 + 100 markets * (inner ArrayMap 105 + 100 * MapGetFloat 2 + ArrayReduce
+    // 110) = 41_605. ArrayReduce with AverageMedian: 5 + 5 + 4 * 100 = 410
+    assert_eq!(
+        cost,
+        CostBreakdown {
+            version: 1,
+            retrieve: vec![542_125, 542_125],
+            aggregate: 30,
+            tally: 70,
+            total: 1_084_350,
+        }
+    );
+
+    let trivial = estimate_cost(&trivial_request(), &sizes).unwrap();
+    assert!(cost.total > 2 * trivial.total);
+    let trivial = estimate_cost(&trivial_request(), &InputSizes::default()).unwrap();
+    assert!(cost.total > 200 * trivial.total);
+}
+
+#[test]
+fn test_cost_grows_with_input_sizes() {
+    let small = estimate_cost(&nested_request(), &InputSizes::default()).unwrap();
+    let large = estimate_cost(
+        &nested_request(),
+        &InputSizes {
+            collection_items: 20,
+            ..InputSizes::default()
+        },
+    )
+    .unwrap();
+
+    assert!(large.retrieve[0] > small.retrieve[0]);
+    assert_eq!(large.aggregate, small.aggregate);
+    assert_eq!(large.tally, small.tally);
+}
+
+#[test]
+fn test_cost_of_undecodable_script() {
+    let mut request = trivial_request();
+    request.retrieve[0].script = vec![0xFF];
+
+    assert!(matches!(
+        estimate_cost(&request, &InputSizes::default()),
+        Err(RadError::BufferIsNotValue { .. })
+    ));
+}
+
+#[test]
+fn test_cost_table_versions() {
+    assert_eq!(CostTable::from_version(1), Some(LATEST_COST_TABLE));
+    assert_eq!(LATEST_COST_TABLE.version(), 1);
+    assert_eq!(CostTable::from_version(2), None);
+}
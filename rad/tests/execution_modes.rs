@@ -0,0 +1,152 @@
+use std::convert::TryFrom;
+
+use serde::Deserialize;
+use witnet_data_structures::{
+    chain::tapi::current_active_wips,
+    radon_report::{ExecutionMode, RadonReport, ReportContext, RetrievalMetadata, Stage},
+};
+use witnet_rad::{
+    request_json::{from_json, RADRequestJson},
+    run_tally_report,
+    script::{execute_radon_script, unpack_radon_script, RadonScriptExecutionSettings},
+    try_data_request,
+    types::{string::RadonString, RadonTypes},
+};
+
+/// Requests whose results and errors must not depend on the mode they are executed in, along with
+/// the response of each of their sources.
+const FIXTURES: &str = include_str!("execution_modes.json");
+
+#[derive(Deserialize)]
+struct Fixture {
+    name: String,
+    request: RADRequestJson,
+    responses: Vec<String>,
+}
+
+/// Encoding of a result as it would be committed, revealed or tallied.
+fn encoded(report: &RadonReport<RadonTypes>) -> Vec<u8> {
+    Vec::<u8>::try_from(report.result.clone()).unwrap()
+}
+
+#[test]
+fn local_and_consensus_executions_agree_byte_for_byte() {
+    let fixtures: Vec<Fixture> = serde_json::from_str(FIXTURES).unwrap();
+    let active_wips = current_active_wips();
+
+    for fixture in fixtures {
+        let request = from_json(&fixture.request).unwrap();
+        let responses: Vec<&str> = fixture.responses.iter().map(String::as_str).collect();
+        let consensus = try_data_request(
+            &request,
+            RadonScriptExecutionSettings::all_but_partial_results(),
+            Some(&responses),
+            None,
+        );
+        let local = try_data_request(
+            &request,
+            RadonScriptExecutionSettings::enable_all(),
+            Some(&responses),
+            None,
+        );
+
+        assert_eq!(
+            consensus.retrieve.iter().map(encoded).collect::<Vec<_>>(),
+            local.retrieve.iter().map(encoded).collect::<Vec<_>>(),
+            "retrievals of {}",
+            fixture.name
+        );
+        assert_eq!(
+            encoded(&consensus.aggregate),
+            encoded(&local.aggregate),
+            "aggregation of {}",
+            fixture.name
+        );
+        assert_eq!(
+            encoded(&consensus.tally),
+            encoded(&local.tally),
+            "tally of {}",
+            fixture.name
+        );
+
+        // Tallying the retrieved values themselves puts the filters of the tally to work
+        let reveals: Vec<RadonTypes> = consensus
+            .retrieve
+            .iter()
+            .map(|report| report.result.clone())
+            .collect();
+        let tally = |settings| {
+            let (result, context) = run_tally_report(
+                reveals.clone(),
+                &request.tally,
+                None,
+                None,
+                settings,
+                &active_wips,
+            );
+            result.unwrap_or_else(|error| RadonReport::from_result(Err(error), &context))
+        };
+        assert_eq!(
+            encoded(&tally(
+                RadonScriptExecutionSettings::all_but_partial_results()
+            )),
+            encoded(&tally(RadonScriptExecutionSettings::enable_all())),
+            "tally of the retrievals of {}",
+            fixture.name
+        );
+    }
+}
+
+fn first_script_with_context(mode: ExecutionMode) -> RadonReport<RadonTypes> {
+    let fixtures: Vec<Fixture> = serde_json::from_str(FIXTURES).unwrap();
+    let fixture = &fixtures[1];
+    let request = from_json(&fixture.request).unwrap();
+    let script = unpack_radon_script(&request.retrieve[0].script).unwrap();
+    let mut context = ReportContext::from_stage(Stage::Retrieval(RetrievalMetadata::default()));
+    context.active_wips = Some(current_active_wips());
+    context.mode = mode;
+
+    execute_radon_script(
+        RadonString::from(fixture.responses[0].as_str()).into(),
+        &script,
+        &mut context,
+        RadonScriptExecutionSettings::enable_all(),
+    )
+    .unwrap()
+}
+
+#[test]
+fn local_executions_record_traces() {
+    assert_eq!(
+        RadonScriptExecutionSettings::enable_all().mode(),
+        ExecutionMode::Local
+    );
+
+    let report = first_script_with_context(ExecutionMode::Local);
+    assert!(report.partial_results.is_some());
+    assert!(report.context.filter_breakdown.is_some());
+}
+
+#[cfg(debug_assertions)]
+#[test]
+#[should_panic(expected = "Local-only execution features were enabled")]
+fn consensus_executions_refuse_local_only_features() {
+    first_script_with_context(ExecutionMode::Consensus);
+}
+
+/// The settings used by nodes never enable local-only features, and no settings can enable them
+/// in a consensus execution.
+#[test]
+fn consensus_executions_only_use_consensus_settings() {
+    for settings in [
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        RadonScriptExecutionSettings::disable_all(),
+    ] {
+        assert_eq!(settings.mode(), ExecutionMode::Consensus);
+    }
+
+    let restricted =
+        RadonScriptExecutionSettings::enable_all().restricted_to(ExecutionMode::Consensus);
+    assert_eq!(restricted.mode(), ExecutionMode::Consensus);
+    assert!(restricted.timing && restricted.breakpoints);
+}
@@ -29,8 +29,7 @@ fn test_radon_types_names() {
 fn test_radon_types_display() {
     let radon_array = RadonTypes::try_from(Value::Array(vec![Value::Integer(123)])).unwrap();
     let radon_array_type_display = radon_array.to_string();
-    let radon_array_expected =
-        "RadonTypes::RadonArray([Integer(RadonInteger { value: 123 })])".to_string();
+    let radon_array_expected = "RadonTypes::RadonArray([123])".to_string();
     assert_eq!(radon_array_type_display, radon_array_expected);
 
     let radon_float = RadonTypes::try_from(Value::Float(std::f64::consts::PI)).unwrap();
@@ -45,8 +44,7 @@ fn test_radon_types_display() {
     );
     let radon_map = RadonTypes::try_from(Value::Map(map)).unwrap();
     let radon_map_type_display = radon_map.to_string();
-    let radon_map_expected =
-        r#"RadonTypes::RadonMap({"Hello": String(RadonString { value: "World" })})"#.to_string();
+    let radon_map_expected = r#"RadonTypes::RadonMap({"Hello": "World"})"#.to_string();
     assert_eq!(radon_map_type_display, radon_map_expected);
 
     let radon_bytes = RadonTypes::try_from(Value::Bytes(vec![1, 2, 3])).unwrap();
@@ -0,0 +1,13 @@
+use witnet_rad::operators::catalog::operator_catalog;
+
+/// Changes to this file are changes to the interface offered to request editors, so they must be
+/// deliberate.
+const GOLDEN_CATALOG: &str = include_str!("operator_catalog.json");
+
+#[test]
+fn test_operator_catalog_matches_golden_file() {
+    let expected: serde_json::Value = serde_json::from_str(GOLDEN_CATALOG).unwrap();
+    let catalog = serde_json::to_value(operator_catalog()).unwrap();
+
+    assert_eq!(catalog, expected);
+}
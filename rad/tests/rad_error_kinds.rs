@@ -0,0 +1,431 @@
+use std::collections::HashSet;
+
+use serde_cbor::Value;
+use serde_json::json;
+use witnet_rad::{
+    error::RadError,
+    operators::RadonOpCodes,
+    types::{array::RadonArray, integer::RadonInteger, RadonTypes},
+};
+
+/// Wallet clients decide what to do with RAD errors by their kind, so the kinds cannot change, and
+/// new ones must be added here deliberately.
+const PINNED_KINDS: &str = include_str!("rad_error_kinds.json");
+
+fn text(text: &str) -> String {
+    text.to_string()
+}
+
+fn array() -> RadonArray {
+    RadonArray::from(vec![
+        RadonTypes::from(RadonInteger::from(1)),
+        RadonTypes::from(RadonInteger::from(1)),
+    ])
+}
+
+/// An example of every variant of `RadError`, in the order they are declared.
+fn examples() -> Vec<RadError> {
+    vec![
+        RadError::Unknown,
+        RadError::Decode {
+            from: "CBOR",
+            to: "RadonTypes",
+        },
+        RadError::Encode {
+            from: "RadonTypes",
+            to: "CBOR",
+        },
+        RadError::Hash,
+        RadError::JsonParse {
+            description: text("EOF while parsing"),
+        },
+        RadError::JsonPrecisionLoss {
+            number: text("1e20"),
+        },
+        RadError::XmlParse {
+            description: text("unexpected end"),
+        },
+        RadError::XmlParseOverflow,
+        RadError::ArrayIndexOutOfBounds { index: 2 },
+        RadError::MapKeyNotFound { key: text("price") },
+        RadError::PathKeyNotFound {
+            path: text("data"),
+            key: text("price"),
+        },
+        RadError::PathIndexOutOfBounds {
+            path: text("data.items"),
+            index: 3,
+        },
+        RadError::PathTypeMismatch {
+            path: text("data"),
+            expected: "RadonMap",
+            found: "RadonArray",
+        },
+        RadError::MapKeyCollision { key: text("price") },
+        RadError::ArrayJoinTypeMismatch {
+            index: 1,
+            found: text("RadonBoolean"),
+        },
+        RadError::ArrayFilterWrongSubscript {
+            value: text("RadonInteger(1)"),
+        },
+        RadError::BufferIsNotValue {
+            description: text("invalid type"),
+        },
+        RadError::NoOperatorInCompoundCall,
+        RadError::NotIntegerOperator,
+        RadError::NotNaturalOperator { code: -1 },
+        RadError::ScriptNotArray {
+            input_type: text("Text"),
+        },
+        RadError::UnknownOperator { code: 0xFE },
+        RadError::UnknownFilter {
+            code: i128::from(u64::MAX) + 1,
+        },
+        RadError::UnknownReducer { code: 0x42 },
+        RadError::UnknownRetrieval,
+        RadError::UnsupportedHashFunction {
+            function: text("SHA2_512"),
+        },
+        RadError::UnsupportedOperator {
+            input_type: text("RadonString"),
+            operator: text("IntegerAdd"),
+            args: Some(vec![Value::Integer(1)]),
+        },
+        RadError::UnsupportedReducer {
+            array: array(),
+            reducer: text("AverageMean"),
+        },
+        RadError::UnsupportedFilter {
+            array: array(),
+            filter: text("DeviationStandard"),
+        },
+        RadError::UnsupportedSortOp { array: array() },
+        RadError::UnsupportedOpNonHomogeneous {
+            operator: text("ArraySort"),
+        },
+        RadError::UnsupportedOperatorInTally {
+            operator: RadonOpCodes::ArrayMap,
+        },
+        RadError::UnsupportedFilterInAT { operator: 0x99 },
+        RadError::UnsupportedReducerInAT { operator: 0x99 },
+        RadError::ModeTie {
+            values: array(),
+            max_count: 1,
+        },
+        RadError::EmptyArray,
+        RadError::WrongArguments {
+            input_type: "RadonFloat",
+            operator: text("FloatPower"),
+            args: vec![Value::Bytes(vec![0xde, 0xad])],
+        },
+        RadError::HttpStatus { status_code: 404 },
+        RadError::HttpOther {
+            message: text("connection refused"),
+        },
+        RadError::ParseFloat {
+            message: text("invalid float literal"),
+        },
+        RadError::ParseInt {
+            message: text("invalid digit found in string"),
+        },
+        RadError::ParseBool {
+            message: text("provided string was not `true` or `false`"),
+        },
+        RadError::Overflow {
+            operator: text("IntegerMultiply"),
+            operands: vec![Value::Integer(i128::MAX), Value::Integer(2)],
+        },
+        RadError::NonFiniteFloat {
+            operator: text("FloatPower"),
+            result: text("inf"),
+        },
+        RadError::FloatIsNaN {
+            operator: text("FloatAsInteger"),
+        },
+        RadError::FloatIsInfinite {
+            operator: text("FloatAsInteger"),
+            value: text("-inf"),
+        },
+        RadError::UndefinedPower {
+            operator: text("FloatPower"),
+            base: text("-8"),
+            exponent: text("0.5"),
+        },
+        RadError::ExponentOutOfRange {
+            operator: text("IntegerPower"),
+            exponent: text("-2000"),
+            max: 1024,
+        },
+        RadError::InvalidRegex {
+            pattern: text("("),
+            message: text("unclosed group"),
+        },
+        RadError::RegexTooComplex { length: 1000 },
+        RadError::RegexNoMatch {
+            pattern: text("a+"),
+        },
+        RadError::MismatchingTypes {
+            method: text("AverageMean"),
+            expected: "RadonFloat",
+            found: "RadonString",
+        },
+        RadError::FractionalResult {
+            reducer: text("AverageMean"),
+            value: 1.5,
+        },
+        RadError::DifferentSizeArrays {
+            method: text("AverageMean"),
+            first: 2,
+            second: 3,
+        },
+        RadError::BadSubscriptFormat {
+            value: Value::Integer(1),
+        },
+        RadError::Subscript {
+            input_type: text("RadonArray"),
+            operator: text("ArrayMap"),
+            inner: Box::new(RadError::MapKeyNotFound { key: text("price") }),
+        },
+        RadError::UrlParseError {
+            inner: url::ParseError::EmptyHost,
+            url: text("https://"),
+        },
+        RadError::RetrieveTimeout,
+        RadError::MissingSecret {
+            name: text("api_key:coinlayer"),
+        },
+        RadError::SecretHostNotAllowed {
+            name: text("api_key:coinlayer"),
+            host: text("evil.com"),
+        },
+        RadError::UnknownRetrievalVariable {
+            name: text("witness_index"),
+        },
+        RadError::HttpResponseTooLarge {
+            limit: 1_024,
+            size: 1_025,
+        },
+        RadError::HttpContentTypeMismatch {
+            content_type: text("image/png"),
+        },
+        RadError::InvalidScript {
+            value: Value::Text(text("script")),
+        },
+        RadError::EncodeRadonErrorArguments {
+            error_args: text("()"),
+        },
+        RadError::DecodeRadonErrorNotArray {
+            actual_type: text("Integer"),
+        },
+        RadError::DecodeRadonErrorEmptyArray,
+        RadError::DecodeRadonErrorBadCode {
+            actual_type: text("Text"),
+        },
+        RadError::DecodeRadonErrorUnknownCode { error_code: 0xF0 },
+        RadError::DecodeRadonErrorMissingArguments,
+        RadError::DecodeRadonErrorWrongArguments {
+            arguments: Some(Value::Array(vec![])),
+            message: text("invalid length 0"),
+        },
+        RadError::DecodeRadonErrorArgumentsRadonTypesFail {
+            arguments: None,
+            message: text("invalid type"),
+        },
+        RadError::InsufficientCommits,
+        RadError::NoReveals,
+        RadError::InsufficientConsensus {
+            achieved: 0.49,
+            required: 0.51,
+        },
+        RadError::RequestTooManySources,
+        RadError::ScriptTooManyCalls,
+        RadError::SourceScriptNotCBOR,
+        RadError::SourceScriptNotArray,
+        RadError::SourceScriptNotRADON,
+        RadError::Underflow,
+        RadError::DivisionByZero,
+        RadError::EncodeRadonErrorUnknownCode,
+        RadError::TallyExecution {
+            inner: None,
+            message: Some(text("no values")),
+        },
+        RadError::UnhandledIntercept {
+            inner: Some(Box::new(RadError::EmptyArray)),
+            message: None,
+        },
+        RadError::UnhandledInterceptV2 { inner: None },
+        RadError::MalformedReveal,
+        RadError::EncodeReveal,
+        RadError::InvalidHttpHeader {
+            name: text("Content-Type"),
+            value: text("\n"),
+            error: text("invalid HTTP header value"),
+        },
+        RadError::InconsistentSource,
+    ]
+}
+
+/// Fails to compile when a variant is added to `RadError`, as a reminder to add an example of it to
+/// `examples` and its kind to `rad_error_kinds.json`.
+#[allow(dead_code)]
+fn has_an_example(error: &RadError) {
+    match error {
+        RadError::Unknown
+        | RadError::Decode { .. }
+        | RadError::Encode { .. }
+        | RadError::Hash
+        | RadError::JsonParse { .. }
+        | RadError::JsonPrecisionLoss { .. }
+        | RadError::XmlParse { .. }
+        | RadError::XmlParseOverflow
+        | RadError::ArrayIndexOutOfBounds { .. }
+        | RadError::MapKeyNotFound { .. }
+        | RadError::PathKeyNotFound { .. }
+        | RadError::PathIndexOutOfBounds { .. }
+        | RadError::PathTypeMismatch { .. }
+        | RadError::MapKeyCollision { .. }
+        | RadError::ArrayJoinTypeMismatch { .. }
+        | RadError::ArrayFilterWrongSubscript { .. }
+        | RadError::BufferIsNotValue { .. }
+        | RadError::NoOperatorInCompoundCall
+        | RadError::NotIntegerOperator
+        | RadError::NotNaturalOperator { .. }
+        | RadError::ScriptNotArray { .. }
+        | RadError::UnknownOperator { .. }
+        | RadError::UnknownFilter { .. }
+        | RadError::UnknownReducer { .. }
+        | RadError::UnknownRetrieval
+        | RadError::UnsupportedHashFunction { .. }
+        | RadError::UnsupportedOperator { .. }
+        | RadError::UnsupportedReducer { .. }
+        | RadError::UnsupportedFilter { .. }
+        | RadError::UnsupportedSortOp { .. }
+        | RadError::UnsupportedOpNonHomogeneous { .. }
+        | RadError::UnsupportedOperatorInTally { .. }
+        | RadError::UnsupportedFilterInAT { .. }
+        | RadError::UnsupportedReducerInAT { .. }
+        | RadError::ModeTie { .. }
+        | RadError::EmptyArray
+        | RadError::WrongArguments { .. }
+        | RadError::HttpStatus { .. }
+        | RadError::HttpOther { .. }
+        | RadError::ParseFloat { .. }
+        | RadError::ParseInt { .. }
+        | RadError::ParseBool { .. }
+        | RadError::Overflow { .. }
+        | RadError::NonFiniteFloat { .. }
+        | RadError::FloatIsNaN { .. }
+        | RadError::FloatIsInfinite { .. }
+        | RadError::UndefinedPower { .. }
+        | RadError::ExponentOutOfRange { .. }
+        | RadError::InvalidRegex { .. }
+        | RadError::RegexTooComplex { .. }
+        | RadError::RegexNoMatch { .. }
+        | RadError::MismatchingTypes { .. }
+        | RadError::FractionalResult { .. }
+        | RadError::DifferentSizeArrays { .. }
+        | RadError::BadSubscriptFormat { .. }
+        | RadError::Subscript { .. }
+        | RadError::UrlParseError { .. }
+        | RadError::RetrieveTimeout
+        | RadError::MissingSecret { .. }
+        | RadError::SecretHostNotAllowed { .. }
+        | RadError::UnknownRetrievalVariable { .. }
+        | RadError::HttpResponseTooLarge { .. }
+        | RadError::HttpContentTypeMismatch { .. }
+        | RadError::InvalidScript { .. }
+        | RadError::EncodeRadonErrorArguments { .. }
+        | RadError::DecodeRadonErrorNotArray { .. }
+        | RadError::DecodeRadonErrorEmptyArray
+        | RadError::DecodeRadonErrorBadCode { .. }
+        | RadError::DecodeRadonErrorUnknownCode { .. }
+        | RadError::DecodeRadonErrorMissingArguments
+        | RadError::DecodeRadonErrorWrongArguments { .. }
+        | RadError::DecodeRadonErrorArgumentsRadonTypesFail { .. }
+        | RadError::InsufficientCommits
+        | RadError::NoReveals
+        | RadError::InsufficientConsensus { .. }
+        | RadError::RequestTooManySources
+        | RadError::ScriptTooManyCalls
+        | RadError::SourceScriptNotCBOR
+        | RadError::SourceScriptNotArray
+        | RadError::SourceScriptNotRADON
+        | RadError::Underflow
+        | RadError::DivisionByZero
+        | RadError::EncodeRadonErrorUnknownCode
+        | RadError::TallyExecution { .. }
+        | RadError::UnhandledIntercept { .. }
+        | RadError::UnhandledInterceptV2 { .. }
+        | RadError::MalformedReveal
+        | RadError::EncodeReveal
+        | RadError::InvalidHttpHeader { .. }
+        | RadError::InconsistentSource => {}
+    }
+}
+
+#[test]
+fn test_every_rad_error_has_a_distinct_pinned_kind() {
+    let pinned: Vec<String> = serde_json::from_str(PINNED_KINDS).unwrap();
+    let kinds: Vec<&str> = examples().iter().map(RadError::kind).collect();
+
+    assert_eq!(kinds, pinned);
+    assert_eq!(
+        kinds.iter().collect::<HashSet<_>>().len(),
+        kinds.len(),
+        "Every variant of RadError must have its own kind"
+    );
+}
+
+#[test]
+fn test_rad_errors_serialize_their_kind_message_and_fields() {
+    for error in examples() {
+        let json = serde_json::to_value(&error).unwrap();
+
+        assert_eq!(json["kind"], json!(error.kind()));
+        assert_eq!(json["message"], json!(error.to_string()));
+        assert!(json["fields"].is_object(), "{}", json);
+    }
+
+    let error = RadError::Subscript {
+        input_type: text("RadonArray"),
+        operator: text("ArrayMap"),
+        inner: Box::new(RadError::WrongArguments {
+            input_type: "RadonFloat",
+            operator: text("FloatPower"),
+            args: vec![
+                Value::Bytes(vec![0xde, 0xad]),
+                Value::Integer(i128::from(u64::MAX) + 1),
+            ],
+        }),
+    };
+    assert_eq!(
+        serde_json::to_value(&error).unwrap(),
+        json!({
+            "kind": "subscript",
+            "message": error.to_string(),
+            "fields": {
+                "input_type": "RadonArray",
+                "operator": "ArrayMap",
+                "inner": {
+                    "kind": "wrong_arguments",
+                    "message": "Wrong `RadonFloat::FloatPower()` arguments: `[Bytes([222, 173]), Integer(18446744073709551616)]`",
+                    "fields": {
+                        "input_type": "RadonFloat",
+                        "operator": "FloatPower",
+                        "args": [{ "bytes": "dead" }, { "integer": "18446744073709551616" }],
+                    },
+                },
+            },
+        })
+    );
+
+    let error = RadError::UnsupportedReducer {
+        array: array(),
+        reducer: text("AverageMean"),
+    };
+    assert_eq!(
+        serde_json::to_value(&error).unwrap()["fields"],
+        json!({ "array": "[1, 1]", "reducer": "AverageMean" })
+    );
+}
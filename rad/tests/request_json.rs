@@ -0,0 +1,135 @@
+use std::collections::BTreeMap;
+
+use serde_cbor::Value;
+use witnet_data_structures::chain::{
+    RADAggregate, RADFilter, RADRequest, RADRetrieve, RADTally, RADType,
+};
+use witnet_rad::{
+    cbor_to_vec,
+    request_json::{from_json, to_json, RADRequestJson},
+};
+
+/// Changes to this file are changes to the format in which request editors exchange requests, so
+/// they must be deliberate.
+const GOLDEN_REQUEST: &str = include_str!("request_json.json");
+
+fn call(operator: u8, args: Vec<Value>) -> Value {
+    Value::Array(
+        std::iter::once(Value::Integer(operator.into()))
+            .chain(args)
+            .collect(),
+    )
+}
+
+fn text(text: &str) -> Value {
+    Value::Text(text.to_string())
+}
+
+/// A request using every kind of call and argument, operators and filters without a name, and a
+/// non UTF-8 body.
+fn fixture() -> RADRequest {
+    let price_script = Value::Array(vec![
+        Value::Integer(0x77),
+        call(0x66, vec![text("bpi")]),
+        call(0x64, vec![text("rate_float")]),
+        call(0x57, vec![Value::Integer(1000)]),
+        Value::Integer(0x5B),
+    ]);
+    let mut map = BTreeMap::new();
+    map.insert(text("a"), Value::Integer(1));
+    let unusual_script = Value::Array(vec![
+        call(0x76, vec![]),
+        call(
+            0x1A,
+            vec![Value::Array(vec![
+                call(0x64, vec![text("price")]),
+                call(0x59, vec![Value::Float(0.5)]),
+            ])],
+        ),
+        call(0x11, vec![Value::Array(vec![Value::Integer(0x12)])]),
+        call(0x1D, vec![Value::Integer(1)]),
+        call(
+            0x12,
+            vec![
+                Value::Bytes(vec![0xde, 0xad]),
+                Value::Float(f64::NAN),
+                Value::Integer(-5),
+                Value::Integer(i128::from(u64::MAX) + 1),
+                Value::Null,
+                Value::Bool(true),
+                Value::Map(map),
+                Value::Tag(1, Box::new(Value::Integer(0))),
+                Value::Array(vec![Value::Integer(1), text("x")]),
+                Value::Float(2.0),
+            ],
+        ),
+    ]);
+
+    RADRequest {
+        time_lock: 1234,
+        retrieve: vec![
+            RADRetrieve {
+                kind: RADType::HttpGet,
+                url: "https://api.coindesk.com/v1/bpi/currentprice.json".to_string(),
+                script: cbor_to_vec(&price_script).unwrap(),
+                body: vec![],
+                headers: vec![],
+            },
+            RADRetrieve {
+                kind: RADType::HttpPost,
+                url: "https://example.com/graphql".to_string(),
+                script: cbor_to_vec(&unusual_script).unwrap(),
+                body: br#"{"query":"{ price }"}"#.to_vec(),
+                headers: vec![
+                    ("Content-Type".to_string(), "application/json".to_string()),
+                    ("Content-Type".to_string(), "text/plain".to_string()),
+                ],
+            },
+            RADRetrieve {
+                kind: RADType::Rng,
+                url: String::new(),
+                script: vec![],
+                body: vec![0xff, 0x00],
+                headers: vec![],
+            },
+        ],
+        aggregate: RADAggregate {
+            filters: vec![RADFilter {
+                op: 0x05,
+                args: cbor_to_vec(&Value::Float(1.5)).unwrap(),
+            }],
+            reducer: 0x03,
+        },
+        tally: RADTally {
+            filters: vec![
+                RADFilter {
+                    op: 0x08,
+                    args: vec![],
+                },
+                RADFilter {
+                    op: 0x99,
+                    args: cbor_to_vec(&Value::Null).unwrap(),
+                },
+            ],
+            reducer: 0x42,
+        },
+    }
+}
+
+#[test]
+fn test_request_json_matches_golden_file() {
+    let expected: serde_json::Value = serde_json::from_str(GOLDEN_REQUEST).unwrap();
+    let json = serde_json::to_value(to_json(&fixture()).unwrap()).unwrap();
+
+    assert_eq!(json, expected);
+}
+
+#[test]
+fn test_request_json_round_trips_losslessly() {
+    let golden: RADRequestJson = serde_json::from_str(GOLDEN_REQUEST).unwrap();
+    assert_eq!(from_json(&golden).unwrap(), fixture());
+
+    let exported = serde_json::to_string(&to_json(&fixture()).unwrap()).unwrap();
+    let imported: RADRequestJson = serde_json::from_str(&exported).unwrap();
+    assert_eq!(from_json(&imported).unwrap(), fixture());
+}
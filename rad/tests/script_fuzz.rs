@@ -0,0 +1,190 @@
+use std::{
+    convert::TryFrom,
+    panic,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use rand::{rngs::StdRng, seq::SliceRandom, Rng, SeedableRng};
+use serde_cbor::Value;
+use witnet_data_structures::{
+    chain::tapi::{all_wips_active, current_active_wips},
+    radon_report::{ExecutionMode, ReportContext, RetrievalMetadata, Stage},
+};
+use witnet_rad::{
+    operators::RadonOpCodes,
+    script::{
+        execute_contextfree_radon_script, execute_radon_script, unpack_radon_call, RadonCall,
+        RadonScriptExecutionSettings,
+    },
+    types::RadonTypes,
+};
+
+const SEED: u64 = 0x5eed;
+const ITERATIONS: usize = 5_000;
+const MAX_DEPTH: usize = 3;
+const MAX_CALLS: usize = 6;
+
+const TEXTS: &[&str] = &[
+    "",
+    "a",
+    "0",
+    "-1",
+    "1.5",
+    "1,234.5",
+    "1.234,5",
+    "NaN",
+    "true",
+    "[1,2,3]",
+    r#"{"a":{"b":[1,"c"]}}"#,
+    "<a b=\"1\">c<d>e</d></a>",
+    "(.*)",
+    "([",
+];
+
+const INTEGERS: &[i128] = &[
+    0,
+    1,
+    -1,
+    2,
+    127,
+    128,
+    255,
+    256,
+    i32::MAX as i128,
+    i32::MIN as i128,
+    u32::MAX as i128,
+    i64::MAX as i128,
+    u64::MAX as i128,
+    i128::MAX,
+    i128::MIN,
+];
+
+const FLOATS: &[f64] = &[
+    0.0,
+    -0.0,
+    0.5,
+    -1.5,
+    1e300,
+    -1e300,
+    f64::MIN_POSITIVE,
+    f64::INFINITY,
+    f64::NEG_INFINITY,
+    f64::NAN,
+];
+
+fn op_codes() -> Vec<u8> {
+    (0..=u8::MAX)
+        .filter(|code| RadonOpCodes::try_from(*code).is_ok())
+        .collect()
+}
+
+fn value(rng: &mut StdRng, codes: &[u8], depth: usize) -> Value {
+    let max_kind = if depth < MAX_DEPTH { 10 } else { 7 };
+    match rng.gen_range(0, max_kind) {
+        0 => Value::Null,
+        1 => Value::Bool(rng.gen()),
+        2 => Value::Integer(*INTEGERS.choose(rng).unwrap()),
+        3 => Value::Integer(i128::from(rng.gen::<i16>())),
+        4 => Value::Float(*FLOATS.choose(rng).unwrap()),
+        5 => Value::Text(TEXTS.choose(rng).unwrap().to_string()),
+        6 => Value::Bytes((0..rng.gen_range(0, 40)).map(|_| rng.gen()).collect()),
+        7 => Value::Array(
+            (0..rng.gen_range(0, 4))
+                .map(|_| value(rng, codes, depth + 1))
+                .collect(),
+        ),
+        8 => Value::Map(
+            (0..rng.gen_range(0, 4))
+                .map(|_| {
+                    let key = Value::Text(TEXTS.choose(rng).unwrap().to_string());
+                    (key, value(rng, codes, depth + 1))
+                })
+                .collect(),
+        ),
+        _ => Value::Array(script(rng, codes, depth + 1)),
+    }
+}
+
+fn call(rng: &mut StdRng, codes: &[u8], depth: usize) -> Value {
+    let code = if rng.gen_bool(0.95) {
+        i128::from(*codes.choose(rng).unwrap())
+    } else {
+        i128::from(rng.gen::<u8>())
+    };
+
+    if rng.gen_bool(0.2) {
+        Value::Integer(code)
+    } else {
+        let args = (0..rng.gen_range(0, 4)).map(|_| value(rng, codes, depth));
+        Value::Array(std::iter::once(Value::Integer(code)).chain(args).collect())
+    }
+}
+
+fn script(rng: &mut StdRng, codes: &[u8], depth: usize) -> Vec<Value> {
+    (0..rng.gen_range(1, MAX_CALLS))
+        .map(|_| call(rng, codes, depth))
+        .collect()
+}
+
+fn input(rng: &mut StdRng, codes: &[u8]) -> Option<RadonTypes> {
+    RadonTypes::try_from(value(rng, codes, 0)).ok()
+}
+
+fn calls(rng: &mut StdRng, codes: &[u8]) -> Vec<RadonCall> {
+    script(rng, codes, 0)
+        .iter()
+        .filter_map(|call| unpack_radon_call(call).ok())
+        .collect()
+}
+
+/// Arbitrary scripts applied on arbitrary inputs must always result in a value or an error, and
+/// never in a panic, even if it would be caught by the executor.
+#[test]
+fn arbitrary_scripts_never_panic() {
+    let panics = Arc::new(AtomicUsize::new(0));
+    let counter = panics.clone();
+    panic::set_hook(Box::new(move |_| {
+        counter.fetch_add(1, Ordering::SeqCst);
+    }));
+
+    let codes = op_codes();
+    let mut rng = StdRng::seed_from_u64(SEED);
+    let mut failures = vec![];
+    for iteration in 0..ITERATIONS {
+        let input = match input(&mut rng, &codes) {
+            Some(input) => input,
+            None => continue,
+        };
+        let script = calls(&mut rng, &codes);
+
+        let before = panics.load(Ordering::SeqCst);
+        for active_wips in [current_active_wips(), all_wips_active()] {
+            let mut context =
+                ReportContext::from_stage(Stage::Retrieval(RetrievalMetadata::default()));
+            context.active_wips = Some(active_wips);
+            context.mode = ExecutionMode::Local;
+            let _ = execute_radon_script(
+                input.clone(),
+                &script,
+                &mut context,
+                RadonScriptExecutionSettings::enable_all(),
+            );
+        }
+        let _ = execute_contextfree_radon_script(input.clone(), &script);
+
+        if panics.load(Ordering::SeqCst) != before {
+            failures.push((iteration, input, script));
+        }
+    }
+
+    let _ = panic::take_hook();
+    assert!(
+        failures.is_empty(),
+        "{} scripts panicked, the first one being {:?}",
+        failures.len(),
+        failures.first()
+    );
+}
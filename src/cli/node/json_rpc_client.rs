@@ -15,7 +15,6 @@ use itertools::Itertools;
 use num_format::{Locale, ToFormattedString};
 use prettytable::{row, Table};
 use serde::{de::DeserializeOwned, Deserialize, Serialize};
-use witnet_config::defaults::PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
 use witnet_crypto::{
     hash::calculate_sha256,
     key::{ExtendedPK, ExtendedSK},
@@ -756,8 +755,7 @@ pub fn send_dr(
     let request = r#"{"jsonrpc": "2.0","method": "getConsensusConstants", "id": "1"}"#;
     let response = send_request(&mut stream, request)?;
     let consensus_constants: ConsensusConstants = parse_response(&response)?;
-    let required_reward_collateral_ratio =
-        PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
+    let required_reward_collateral_ratio = ConsensusConstants::minimum_reward_collateral_ratio();
     let dro = deserialize_and_validate_hex_dr(
         hex_bytes,
         consensus_constants.collateral_minimum,
@@ -1353,7 +1351,7 @@ pub fn get_peers(addr: SocketAddr) -> Result<(), failure::Error> {
     let mut table = Table::new();
     table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
     table.set_titles(row!["Address", "Type"]);
-    for AddrType { address, type_ } in peers {
+    for AddrType { address, type_, .. } in peers {
         table.add_row(row![address, type_]);
     }
     table.printstd();
@@ -1374,9 +1372,17 @@ pub fn get_known_peers(addr: SocketAddr) -> Result<(), failure::Error> {
 
     let mut table = Table::new();
     table.set_format(*prettytable::format::consts::FORMAT_NO_BORDER_LINE_SEPARATOR);
-    table.set_titles(row!["Address", "Type"]);
-    for AddrType { address, type_ } in peers {
-        table.add_row(row![address, type_]);
+    table.set_titles(row!["Address", "Type", "Score"]);
+    for AddrType {
+        address,
+        type_,
+        score,
+    } in peers
+    {
+        let score = score
+            .map(|score| format!("{:.2}", score))
+            .unwrap_or_default();
+        table.add_row(row![address, type_, score]);
     }
     table.printstd();
 
@@ -11,5 +11,8 @@
 /// Utilities to securely store secrets in files
 pub mod files;
 
+/// Throttling of repeated log records
+pub mod log_throttle;
+
 /// Timestamp as UTC
 pub mod timestamp;
@@ -0,0 +1,236 @@
+//! Throttling of repeated log records.
+//!
+//! Errors are grouped by a key, such as the address of the peer that caused them, and by a
+//! category. The first records of every group are logged in full, and the rest are only counted
+//! and summarized once per period. Groups that stay quiet for a whole period are forgotten, so
+//! their next records are logged in full again.
+use std::{collections::HashMap, convert::TryFrom, fmt, hash::Hash, time::Duration};
+
+use crate::timestamp::get_timestamp;
+
+/// Records of every group that are logged in full by default.
+pub const DEFAULT_FULL_DETAIL: u32 = 5;
+
+/// Period after which the suppressed records are summarized by default.
+pub const DEFAULT_PERIOD: Duration = Duration::from_secs(60);
+
+/// Counters of a group of records.
+#[derive(Clone, Copy, Debug, Default)]
+struct Counter {
+    /// Records of the group since it was first seen
+    total: u64,
+    /// Records that were logged in full
+    logged: u32,
+    /// Records that were suppressed since the last summary
+    suppressed: u64,
+    /// Timestamp of the start of the current period
+    period_start: i64,
+    /// Timestamp of the last record
+    last_seen: i64,
+}
+
+/// Summary of the records of a group that were suppressed during a period.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Suppressed<K> {
+    /// Key of the group
+    pub key: K,
+    /// Category of the group
+    pub category: &'static str,
+    /// Number of suppressed records
+    pub count: u64,
+    /// Length of the period
+    pub period: Duration,
+}
+
+impl<K: fmt::Display> fmt::Display for Suppressed<K> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "suppressed {} similar {} errors from {} in the last {}s",
+            self.count,
+            self.category,
+            self.key,
+            self.period.as_secs()
+        )
+    }
+}
+
+/// Decides which records of every group are logged in full, and keeps the count of all of them.
+#[derive(Clone, Debug)]
+pub struct LogThrottle<K> {
+    full_detail: u32,
+    period: Duration,
+    counters: HashMap<(K, &'static str), Counter>,
+}
+
+impl<K> Default for LogThrottle<K> {
+    fn default() -> Self {
+        Self {
+            full_detail: DEFAULT_FULL_DETAIL,
+            period: DEFAULT_PERIOD,
+            counters: HashMap::new(),
+        }
+    }
+}
+
+impl<K> LogThrottle<K>
+where
+    K: Clone + Eq + Hash,
+{
+    /// Create a throttle that logs the first `full_detail` records of every group in full, and
+    /// summarizes the rest every `period`.
+    pub fn new(full_detail: u32, period: Duration) -> Self {
+        Self {
+            full_detail,
+            period,
+            counters: HashMap::new(),
+        }
+    }
+
+    /// Period after which the suppressed records are summarized.
+    pub fn period(&self) -> Duration {
+        self.period
+    }
+
+    /// Count a record of a group. Returns whether it should be logged in full.
+    pub fn record(&mut self, key: K, category: &'static str) -> bool {
+        self.record_pure(key, category, get_timestamp())
+    }
+
+    /// Count a record of a group, using `current_timestamp` as the time of the record. Returns
+    /// whether it should be logged in full.
+    pub fn record_pure(&mut self, key: K, category: &'static str, current_timestamp: i64) -> bool {
+        let full_detail = self.full_detail;
+        let counter = self
+            .counters
+            .entry((key, category))
+            .or_insert_with(|| Counter {
+                period_start: current_timestamp,
+                ..Counter::default()
+            });
+        counter.total = counter.total.saturating_add(1);
+        counter.last_seen = current_timestamp;
+
+        if counter.logged < full_detail {
+            counter.logged += 1;
+
+            true
+        } else {
+            counter.suppressed = counter.suppressed.saturating_add(1);
+
+            false
+        }
+    }
+
+    /// Number of records of a group, including the suppressed ones, since it was first seen or
+    /// last forgotten.
+    pub fn count(&self, key: &K, category: &'static str) -> u64 {
+        self.counters
+            .get(&(key.clone(), category))
+            .map_or(0, |counter| counter.total)
+    }
+
+    /// Summaries of the groups whose period has ended with suppressed records.
+    pub fn summaries(&mut self) -> Vec<Suppressed<K>> {
+        self.summaries_pure(get_timestamp())
+    }
+
+    /// Summaries of the groups whose period has ended at `current_timestamp` with suppressed
+    /// records. Their next period starts, and the groups without records during the last one are
+    /// forgotten.
+    pub fn summaries_pure(&mut self, current_timestamp: i64) -> Vec<Suppressed<K>> {
+        let period = self.period;
+        let period_secs = i64::try_from(period.as_secs()).unwrap_or(i64::MAX);
+        let mut summaries = vec![];
+
+        self.counters.retain(|(key, category), counter| {
+            if current_timestamp.saturating_sub(counter.period_start) < period_secs {
+                return true;
+            }
+            let active = counter.last_seen >= counter.period_start;
+            if counter.suppressed > 0 {
+                summaries.push(Suppressed {
+                    key: key.clone(),
+                    category,
+                    count: counter.suppressed,
+                    period,
+                });
+            }
+            counter.suppressed = 0;
+            counter.period_start = current_timestamp;
+
+            active
+        });
+
+        summaries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn throttle() -> LogThrottle<&'static str> {
+        LogThrottle::new(3, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn only_the_first_records_of_a_group_are_logged() {
+        let mut throttle = throttle();
+        let logged: Vec<_> = (0..5)
+            .map(|_| throttle.record_pure("peer", "decode", 0))
+            .collect();
+
+        assert_eq!(logged, vec![true, true, true, false, false]);
+        assert_eq!(throttle.count(&"peer", "decode"), 5);
+
+        // Other peers and other categories are throttled separately
+        assert!(throttle.record_pure("other peer", "decode", 0));
+        assert!(throttle.record_pure("peer", "storage", 0));
+        assert_eq!(throttle.count(&"peer", "storage"), 1);
+    }
+
+    #[test]
+    fn suppressed_records_are_summarized_once_per_period() {
+        let mut throttle = throttle();
+        for _ in 0..10 {
+            throttle.record_pure("peer", "decode", 0);
+        }
+
+        assert_eq!(throttle.summaries_pure(59), vec![]);
+        let summaries = throttle.summaries_pure(60);
+        assert_eq!(
+            summaries,
+            vec![Suppressed {
+                key: "peer",
+                category: "decode",
+                count: 7,
+                period: Duration::from_secs(60),
+            }]
+        );
+        assert_eq!(
+            summaries[0].to_string(),
+            "suppressed 7 similar decode errors from peer in the last 60s"
+        );
+
+        // A group that keeps failing is not logged in full again
+        assert!(!throttle.record_pure("peer", "decode", 61));
+        assert_eq!(throttle.summaries_pure(100), vec![]);
+        assert_eq!(throttle.summaries_pure(120).len(), 1);
+        assert_eq!(throttle.count(&"peer", "decode"), 11);
+    }
+
+    #[test]
+    fn quiet_groups_are_forgotten() {
+        let mut throttle = throttle();
+        for _ in 0..4 {
+            throttle.record_pure("peer", "decode", 0);
+        }
+
+        assert_eq!(throttle.summaries_pure(60).len(), 1);
+        assert_eq!(throttle.count(&"peer", "decode"), 4);
+        assert_eq!(throttle.summaries_pure(120), vec![]);
+        assert_eq!(throttle.count(&"peer", "decode"), 0);
+        assert!(throttle.record_pure("peer", "decode", 121));
+    }
+}
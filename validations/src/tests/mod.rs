@@ -33,6 +33,7 @@ use witnet_rad::{
     error::RadError,
     filters::RadonFilters,
     reducers::RadonReducers,
+    script::RadonScriptExecutionSettings,
     types::{bytes::RadonBytes, integer::RadonInteger, RadonTypes},
 };
 
@@ -2045,6 +2046,29 @@ fn data_request_no_scripts() {
     );
 }
 
+#[test]
+fn data_request_retrieval_variables() {
+    let mut data_request = example_data_request();
+    data_request.retrieve[0].url =
+        "https://blockchain.info/q/latesthash?dr={{dr_hash}}&epoch={{epoch}}".to_string();
+    data_request.retrieve[0].headers = vec![("X-Epoch".to_string(), "{{epoch}}".to_string())];
+    assert!(validate_rad_request(&data_request, &all_wips_active()).is_ok());
+
+    // The index of the witness is not a variable, as every witness would send a different request
+    data_request.retrieve[0].headers =
+        vec![("X-Witness".to_string(), "{{witness_index}}".to_string())];
+    let err = validate_rad_request(&data_request, &all_wips_active()).unwrap_err();
+    assert_eq!(
+        err.downcast::<RadError>().unwrap(),
+        RadError::UnknownRetrievalVariable {
+            name: "witness_index".to_string()
+        }
+    );
+
+    // Before WIP0040, placeholders are not substituted, so they are not validated either
+    assert!(validate_rad_request(&data_request, &current_active_wips()).is_ok());
+}
+
 #[test]
 fn data_request_empty_scripts() {
     let data_request = RADRequest {
@@ -6372,7 +6396,13 @@ fn create_tally_validation_dr_liar() {
         }],
         reducer: RadonReducers::Mode as u32,
     };
-    let report = construct_report_from_clause_result(clause_result, &script, 3, &active_wips);
+    let report = construct_report_from_clause_result(
+        clause_result,
+        &script,
+        3,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        &active_wips,
+    );
     let report = evaluate_tally_postcondition_clause(report, min_consensus, 3);
 
     // Create a TallyTransaction using the create_tally function
@@ -6461,7 +6491,13 @@ fn create_tally_validation_5_reveals_1_liar_1_error() {
         }],
         reducer: RadonReducers::Mode as u32,
     };
-    let report = construct_report_from_clause_result(clause_result, &script, 5, &active_wips);
+    let report = construct_report_from_clause_result(
+        clause_result,
+        &script,
+        5,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        &active_wips,
+    );
     let report = evaluate_tally_postcondition_clause(report, min_consensus, 5);
 
     // Create a TallyTransaction using the create_tally function
@@ -6541,7 +6577,13 @@ fn create_tally_validation_4_commits_2_reveals() {
         }],
         reducer: RadonReducers::Mode as u32,
     };
-    let report = construct_report_from_clause_result(clause_result, &script, 2, &active_wips);
+    let report = construct_report_from_clause_result(
+        clause_result,
+        &script,
+        2,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        &active_wips,
+    );
     let report = evaluate_tally_postcondition_clause(report, min_consensus, 4);
 
     // Create a TallyTransaction using the create_tally function
@@ -6577,7 +6619,13 @@ fn tally_valid_zero_commits() {
     let min_consensus = 0.0;
     let clause_result = evaluate_tally_precondition_clause(vec![], min_consensus, 0, &active_wips);
     let script = RADTally::default();
-    let report = construct_report_from_clause_result(clause_result, &script, 0, &active_wips);
+    let report = construct_report_from_clause_result(
+        clause_result,
+        &script,
+        0,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        &active_wips,
+    );
     let report = evaluate_tally_postcondition_clause(report, min_consensus, 0);
     let tally_value = report.result.encode().unwrap();
     let vt0 = ValueTransferOutput {
@@ -6612,7 +6660,13 @@ fn create_tally_validation_zero_commits() {
     let min_consensus = 0.51;
     let clause_result = evaluate_tally_precondition_clause(vec![], min_consensus, 0, &active_wips);
     let script = RADTally::default();
-    let report = construct_report_from_clause_result(clause_result, &script, 0, &active_wips);
+    let report = construct_report_from_clause_result(
+        clause_result,
+        &script,
+        0,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        &active_wips,
+    );
     let report = evaluate_tally_postcondition_clause(report, min_consensus, 0);
     let tally_transaction = create_tally(
         dr_pointer,
@@ -6642,7 +6696,13 @@ fn tally_invalid_zero_commits() {
     let min_consensus = 0.0;
     let clause_result = evaluate_tally_precondition_clause(vec![], min_consensus, 0, &active_wips);
     let script = RADTally::default();
-    let report = construct_report_from_clause_result(clause_result, &script, 0, &active_wips);
+    let report = construct_report_from_clause_result(
+        clause_result,
+        &script,
+        0,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        &active_wips,
+    );
     let report = evaluate_tally_postcondition_clause(report, min_consensus, 0);
     let tally_value = report.result.encode().unwrap();
     let vt0 = ValueTransferOutput {
@@ -6694,7 +6754,13 @@ fn tally_valid_zero_reveals() {
     let min_consensus = 0.51;
     let clause_result = evaluate_tally_precondition_clause(vec![], min_consensus, 5, &active_wips);
     let script = RADTally::default();
-    let report = construct_report_from_clause_result(clause_result, &script, 0, &active_wips);
+    let report = construct_report_from_clause_result(
+        clause_result,
+        &script,
+        0,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        &active_wips,
+    );
     let report = evaluate_tally_postcondition_clause(report, min_consensus, 5);
     let tally_value = report.result.encode().unwrap();
 
@@ -6762,7 +6828,13 @@ fn create_tally_validation_zero_reveals() {
     let min_consensus = 0.51;
     let clause_result = evaluate_tally_precondition_clause(vec![], min_consensus, 5, &active_wips);
     let script = RADTally::default();
-    let report = construct_report_from_clause_result(clause_result, &script, 0, &active_wips);
+    let report = construct_report_from_clause_result(
+        clause_result,
+        &script,
+        0,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        &active_wips,
+    );
     let report = evaluate_tally_postcondition_clause(report, min_consensus, 5);
 
     let mut committers = rewarded;
@@ -6809,7 +6881,13 @@ fn create_tally_validation_zero_reveals_zero_collateral() {
     let min_consensus = 0.51;
     let clause_result = evaluate_tally_precondition_clause(vec![], min_consensus, 5, &active_wips);
     let script = RADTally::default();
-    let report = construct_report_from_clause_result(clause_result, &script, 0, &active_wips);
+    let report = construct_report_from_clause_result(
+        clause_result,
+        &script,
+        0,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        &active_wips,
+    );
     let report = evaluate_tally_postcondition_clause(report, min_consensus, 5);
 
     let mut committers = rewarded;
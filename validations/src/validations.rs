@@ -6,10 +6,6 @@ use std::{
 };
 
 use itertools::Itertools;
-use witnet_config::defaults::{
-    PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO,
-    PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE,
-};
 use witnet_crypto::{
     hash::{calculate_sha256, Sha256},
     merkle::{merkle_tree_root as crypto_merkle_tree_root, ProgressiveMerkleTree},
@@ -40,14 +36,15 @@ use witnet_data_structures::{
     wit::NANOWITS_PER_WIT,
 };
 use witnet_rad::{
-    conditions::{
-        construct_report_from_clause_result, evaluate_tally_postcondition_clause,
-        evaluate_tally_precondition_clause, radon_report_from_error,
-    },
+    conditions::{radon_report_from_error, run_tally_with_conditions},
     error::RadError,
     operators::RadonOpCodes,
-    script::{create_radon_script_from_filters_and_reducer, unpack_radon_script},
+    script::{
+        create_radon_script_from_filters_and_reducer, unpack_radon_script,
+        RadonScriptExecutionSettings,
+    },
     types::{serial_iter_decode, RadonTypes},
+    variables::validate_variables,
 };
 
 /// Returns the fee of a value transfer transaction.
@@ -314,6 +311,14 @@ pub fn validate_rad_request(
                 }
             }
         }
+
+        // Before WIP0040, variables were not substituted, so their placeholders were valid text
+        if active_wips.wip0040() {
+            validate_variables(&path.url)?;
+            for (_, value) in &path.headers {
+                validate_variables(value)?;
+            }
+        }
     }
 
     let aggregate = &rad_request.aggregate;
@@ -788,23 +793,14 @@ pub fn run_tally(
     commits_count: usize,
     active_wips: &ActiveWips,
 ) -> RadonReport<RadonTypes> {
-    let results_len = results.len();
-    let clause_result =
-        evaluate_tally_precondition_clause(results, non_error_min, commits_count, active_wips);
-    let mut report =
-        construct_report_from_clause_result(clause_result, tally, results_len, active_wips);
-    if active_wips.wips_0009_0011_0012() {
-        report = evaluate_tally_postcondition_clause(report, non_error_min, commits_count);
-    }
-    if active_wips.wip0018() {
-        // If the result of a tally transaction is RadonError::UnhandledIntercept, this will
-        // remove the message field, as specified in WIP0018.
-        report
-            .result
-            .remove_message_from_error_unhandled_intercept();
-    }
-
-    report
+    run_tally_with_conditions(
+        results,
+        tally,
+        non_error_min,
+        commits_count,
+        RadonScriptExecutionSettings::all_but_partial_results(),
+        active_wips,
+    )
 }
 
 fn create_expected_tally_transaction(
@@ -1503,11 +1499,7 @@ pub fn validate_block_transactions(
     let mut commits_number = HashMap::new();
     let block_beacon = block.block_header.beacon;
     let mut commit_hs = HashSet::with_capacity(block.txns.commit_txns.len());
-    let collateral_age = if active_wips.wip0027() {
-        PSEUDO_CONSENSUS_CONSTANTS_WIP0027_COLLATERAL_AGE
-    } else {
-        consensus_constants.collateral_age
-    };
+    let collateral_age = consensus_constants.collateral_age_with_wips(active_wips);
     for transaction in &block.txns.commit_txns {
         let (dr_pointer, dr_witnesses, fee) = validate_commit_transaction(
             transaction,
@@ -1634,31 +1626,18 @@ pub fn validate_block_transactions(
         .into());
     }
 
-    let mut dr_weight: u32 = 0;
-    if active_wips.wip_0008() {
-        // Calculate data request not solved weight
-        let mut dr_pointers: HashSet<Hash> = dr_pool
-            .get_dr_output_pointers_by_epoch(epoch)
-            .into_iter()
-            .collect();
-        for dr in commits_number.keys() {
-            dr_pointers.remove(dr);
-        }
-        for dr in dr_pointers {
-            let unsolved_dro = dr_pool.get_dr_output(&dr);
-            if let Some(dro) = unsolved_dro {
-                dr_weight = dr_weight
-                    .saturating_add(dro.weight())
-                    .saturating_add(dro.extra_weight());
-            }
-        }
-    }
+    // Calculate data request not solved weight
+    let mut dr_weight: u32 = if active_wips.wip_0008() {
+        dr_pool.unsolved_dr_weight(epoch, commits_number.keys())
+    } else {
+        0
+    };
 
     // Validate data request transactions in a block
     let mut dr_mt = ProgressiveMerkleTree::sha256();
     for transaction in &block.txns.data_request_txns {
         let required_reward_collateral_ratio =
-            PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
+            ConsensusConstants::minimum_reward_collateral_ratio();
         let (inputs, outputs, fee) = validate_dr_transaction(
             transaction,
             &utxo_diff,
@@ -41,6 +41,7 @@ where
     Ok(WitnessingConfig {
         transports: valid,
         paranoid_threshold: config.paranoid_threshold,
+        secrets: config.secrets.clone(),
     })
 }
 
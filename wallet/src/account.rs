@@ -1,27 +1,20 @@
-use crate::{constants, types};
-use witnet_crypto::key::{ExtendedSK, KeyPath};
+use crate::{model, types};
+use witnet_crypto::key::{ExtendedPK, ExtendedSK, KeyPath};
+use witnet_data_structures::{chain::PublicKey, get_environment};
 
 /// Result type for accounts-related operations that can fail.
 pub type Result<T> = std::result::Result<T, failure::Error>;
 
-/// Root KeyPath used for wallet accounts.
-///
-/// Path levels are described here:
-/// https://github.com/aesedepece/WIPs/blob/wip-adansdpc-hdwallets/wip-adansdpc-hdwallets.md#path-levels
-#[inline]
-pub fn account_keypath(index: u32) -> KeyPath {
-    KeyPath::default()
-        .hardened(constants::KEYPATH_PURPOSE)
-        .hardened(constants::KEYPATH_COIN_TYPE)
-        .hardened(index)
-}
-
-/// Generate a new account with the given index.
+/// Generate a new account with the given index, under the derivation path of a wallet.
 ///
 /// The account index is kind of the account id and indicates in which
 /// branch the HD-Wallet derivation tree these account keys are.
-pub fn gen_account(account_index: u32, master_key: &ExtendedSK) -> Result<types::Account> {
-    let account_keypath = account_keypath(account_index);
+pub fn gen_account(
+    derivation: &model::KeyDerivation,
+    account_index: u32,
+    master_key: &ExtendedSK,
+) -> Result<types::Account> {
+    let account_keypath = derivation.account_keypath(account_index);
     let account_key = master_key.derive(&account_keypath)?;
 
     let external = {
@@ -43,3 +36,136 @@ pub fn gen_account(account_index: u32, master_key: &ExtendedSK) -> Result<types:
 
     Ok(account)
 }
+
+/// Derive the addresses and public keys found at the given key paths of a master key.
+///
+/// Nothing is persisted: this is used for previewing derivations without creating a wallet.
+pub fn derive_addresses(
+    master_key: &ExtendedSK,
+    paths: &[KeyPath],
+) -> Result<Vec<model::DerivedAddress>> {
+    paths
+        .iter()
+        .map(|path| {
+            let key = master_key.derive(path)?;
+            let ExtendedPK { key, .. } = ExtendedPK::from_secret_key(&key);
+            let pkh = PublicKey::from(key).pkh();
+
+            Ok(model::DerivedAddress {
+                address: pkh.bech32(get_environment()),
+                path: path.to_string(),
+                public_key: key.to_string(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use witnet_crypto::{
+        key::{KeySpec, MasterKeyGen},
+        mnemonic::Mnemonic,
+    };
+
+    const LEGACY: model::KeyDerivation = model::KeyDerivation::LEGACY;
+
+    fn master_key() -> ExtendedSK {
+        let phrase = "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+        let mnemonic = Mnemonic::from_phrase(phrase.into()).unwrap();
+        let seed = mnemonic.seed(&"".into());
+
+        MasterKeyGen::new(seed).generate().unwrap()
+    }
+
+    #[test]
+    fn test_derive_addresses() {
+        let paths = vec![
+            LEGACY.account_keypath(0).index(0).index(0),
+            LEGACY.account_keypath(0).index(0).index(5),
+            LEGACY.account_keypath(0).index(1).index(0),
+            "m/44'/0'/0'/0/0".parse().unwrap(),
+        ];
+
+        let derived = derive_addresses(&master_key(), &paths).unwrap();
+
+        let expected = vec![
+            (
+                "wit174la8pevl74hczcpfepgmt036zkmjen4hu8zzs",
+                "m/3'/4919'/0'/0/0",
+                "03d5761050a170c53bd09dcd1d6a69e2053197ad55bdee169c65dc580eaec6bf4c",
+            ),
+            (
+                "wit1hnevye0s0zr98ly4mu2052jqrzwp60q5m2w9l8",
+                "m/3'/4919'/0'/0/5",
+                "030e2728fc6435359ac2650c40c02bd37355f8ba479a4b1a6c80e47740538327f1",
+            ),
+            (
+                "wit13dr6kqxngduc399dw5g0khnn6yt3jpz6p2ltpm",
+                "m/3'/4919'/0'/1/0",
+                "02ef4cf3f380edeb44730eccc57d1353287d10220bb5782fb2a8832003d2a36703",
+            ),
+            // BIP-44 test vector for the same mnemonic
+            (
+                "wit15sdvjdemnp5wczky0dt34esm295a623j5zw4g3",
+                "m/44'/0'/0'/0/0",
+                "03aaeb52dd7494c361049de67cc680e83ebcbbbdbeb13637d92cd845f70308af5e",
+            ),
+        ];
+
+        assert_eq!(derived.len(), expected.len());
+        for (derived, (address, path, public_key)) in derived.iter().zip(expected) {
+            assert_eq!(derived.address, address);
+            assert_eq!(derived.path, path);
+            assert_eq!(derived.public_key, public_key);
+        }
+    }
+
+    #[test]
+    fn test_derive_addresses_matches_account_keychains() {
+        let master_key = master_key();
+        let account = gen_account(&LEGACY, 0, &master_key).unwrap();
+        let ExtendedPK { key, .. } = ExtendedPK::from_secret_key(
+            &account
+                .external
+                .derive(&KeyPath::default().index(1))
+                .unwrap(),
+        );
+
+        let derived =
+            derive_addresses(&master_key, &[LEGACY.account_keypath(0).index(0).index(1)]).unwrap();
+
+        assert_eq!(derived[0].public_key, key.to_string());
+    }
+
+    #[test]
+    fn test_gen_account_under_key_specs() {
+        let master_key = master_key();
+        let first_address = |derivation: model::KeyDerivation| {
+            let account = gen_account(&derivation, 0, &master_key).unwrap();
+            let ExtendedPK { key, .. } = ExtendedPK::from_secret_key(
+                &account
+                    .external
+                    .derive(&KeyPath::default().index(0))
+                    .unwrap(),
+            );
+
+            PublicKey::from(key).pkh().bech32(get_environment())
+        };
+
+        // Same addresses as the test vectors of `test_derive_addresses`
+        assert_eq!(
+            first_address(LEGACY),
+            "wit174la8pevl74hczcpfepgmt036zkmjen4hu8zzs"
+        );
+        let bip44 = model::KeyDerivation {
+            key_spec: KeySpec::Bip44,
+            coin_type: 0,
+        };
+        assert_eq!(bip44.base_path().to_string(), "m/44'/0'");
+        assert_eq!(
+            first_address(bip44),
+            "wit15sdvjdemnp5wczky0dt34esm295a623j5zw4g3"
+        );
+    }
+}
@@ -2,9 +2,13 @@ use failure::Fail;
 use jsonrpc_core as rpc;
 use serde_json::json;
 
+use witnet_data_structures::chain::TransactionRejection;
 use witnet_net::client::tcp;
 
-use crate::{actors, crypto, repository};
+use crate::{
+    actors, crypto, deadline, locks, model, pool, queues, repository, request_limits, simulation,
+    types,
+};
 
 #[derive(Debug, Fail)]
 pub enum Error {
@@ -24,8 +28,117 @@ pub enum Error {
     SessionsStillOpen,
     #[fail(display = "wallet not found")]
     WalletNotFound,
+    #[fail(display = "wrong wallet password")]
+    WrongPassword {
+        /// Seconds to wait before trying to unlock the wallet again
+        retry_after: u64,
+    },
+    #[fail(display = "wallet data is corrupted: {}", _0)]
+    CorruptedWallet(String),
+    #[fail(display = "wallet requires newer software: unknown key spec {}", _0)]
+    UnsupportedKeySpec(String),
+    #[fail(
+        display = "wallet requires newer software: unknown schema version {}, the latest supported is {}",
+        found, supported
+    )]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
+    #[fail(
+        display = "too many failed attempts to unlock the wallet, retry in {} seconds",
+        retry_after
+    )]
+    UnlockThrottled { retry_after: u64 },
     #[fail(display = "wallet with id {} already exists", _0)]
     WalletAlreadyExists(String),
+    #[fail(display = "{}", _0)]
+    WalletBusy(locks::Busy),
+    #[fail(display = "method {} is disabled in the wallet configuration", _0)]
+    MethodDisabled(&'static str),
+    #[fail(display = "{}", _0)]
+    ExecutionTimeout(deadline::Expired),
+    #[fail(display = "too many open sessions (at most {} allowed)", limit)]
+    TooManySessions {
+        wallet_id: Option<String>,
+        limit: usize,
+    },
+    #[fail(display = "subscriptions are only available through the WebSocket endpoint")]
+    SubscriptionsUnavailable,
+    #[fail(display = "subscription not found")]
+    SubscriptionNotFound,
+    #[fail(display = "the subscription belongs to another session")]
+    SubscriptionForbidden,
+    #[fail(
+        display = "sending {} nanowits would exceed the daily spend limit of the wallet, only {} remain",
+        requested, remaining
+    )]
+    SpendLimitExceeded {
+        limit: u64,
+        spent: u64,
+        requested: u64,
+        remaining: u64,
+    },
+    #[fail(display = "no transaction is waiting for that confirmation token, or it expired")]
+    SendConfirmationNotFound,
+    #[fail(display = "failed to decode {}: {}", path, cause)]
+    Decode {
+        path: String,
+        offset: Option<u64>,
+        cause: String,
+    },
+    /// Failure of a RAD request. Its JSON-RPC error has code 424 and carries the structured
+    /// `RadError` in the `error` field of its data. These failures used to be internal errors,
+    /// with code 500 and only the message in `cause`, which is still given.
+    #[fail(display = "RAD error: {}", _0)]
+    Rad(witnet_rad::error::RadError),
+    #[fail(
+        display = "the synchronization of the wallet is pinned to epoch {}, so its unspent outputs might have been spent since",
+        sync_target_epoch
+    )]
+    SyncPinned { sync_target_epoch: u32 },
+    #[fail(display = "the node would reject the transaction: {}", message)]
+    TransactionRejected {
+        kind: String,
+        reason: String,
+        message: String,
+    },
+    #[fail(display = "the node does not implement method {}", method)]
+    NodeCapabilityMissing { method: String },
+    #[fail(
+        display = "the session lacks the {} scope required by this method, unlock the wallet again in the session to get it",
+        required
+    )]
+    InsufficientScope { required: types::Scope },
+    #[fail(
+        display = "the payload could move funds, so it cannot be signed: {}",
+        _0
+    )]
+    PayloadMovesFunds(String),
+    #[fail(
+        display = "the witness signing policy of the wallet does not allow signing {} transactions",
+        _0
+    )]
+    WitnessSigningNotAllowed(model::WitnessTransactionType),
+    #[fail(display = "{}", _0)]
+    Overloaded(queues::Overloaded),
+    #[fail(display = "{}", _0)]
+    TooManyRequests(request_limits::TooManyRequests),
+    #[fail(
+        display = "the client was written for version {} of the API, but at least version {} is required, upgrade it to a version between {} and {}",
+        client, minimum, minimum, current
+    )]
+    ClientUpgradeRequired {
+        client: u32,
+        minimum: u32,
+        current: u32,
+    },
+    #[fail(
+        display = "the idempotency key was already used for a {} request with other params",
+        method
+    )]
+    IdempotencyKeyConflict { method: String },
+    #[fail(
+        display = "a request with this idempotency key was interrupted before being answered, so it may or may not have taken effect"
+    )]
+    IdempotentRequestInterrupted { recorded_at: i64 },
 }
 
 impl Error {
@@ -38,11 +151,43 @@ impl Error {
             ),
             Error::SessionNotFound => (401, "Unauthorized", None),
             Error::WalletNotFound => (402, "Forbidden", None),
+            Error::WrongPassword { retry_after } => (
+                406,
+                "Wrong Password",
+                Some(json!({ "cause": self.to_string(), "retry_after": retry_after })),
+            ),
+            Error::CorruptedWallet(cause) => {
+                log::error!("Corrupted Wallet: {}", cause);
+                (
+                    422,
+                    "Corrupted Wallet",
+                    Some(json!({ "cause": self.to_string() })),
+                )
+            }
+            Error::UnsupportedKeySpec(_) | Error::UnsupportedSchemaVersion { .. } => (
+                426,
+                "Unsupported Wallet",
+                Some(json!({ "cause": self.to_string() })),
+            ),
+            Error::UnlockThrottled { retry_after } => (
+                425,
+                "Unlock Throttled",
+                Some(json!({ "cause": self.to_string(), "retry_after": retry_after })),
+            ),
             Error::WalletAlreadyExists(wallet_id) => (
                 409,
                 "Wallet Conflict",
                 Some(json!({ "cause": self.to_string(), "wallet_id": wallet_id })),
             ),
+            Error::WalletBusy(busy) => (
+                409,
+                "Wallet Busy",
+                Some(json!({
+                    "cause": self.to_string(),
+                    "wallet_id": busy.wallet_id,
+                    "operation": busy.operation,
+                })),
+            ),
             Error::Node(e) => {
                 log::error!("Node Error: {}", &e);
                 (
@@ -65,6 +210,167 @@ impl Error {
                 )
             }
             Error::SessionsStillOpen => (401, "Unauthorized", None),
+            Error::MethodDisabled(method) => (
+                403,
+                "Method Disabled",
+                Some(json!({ "cause": self.to_string(), "method": method })),
+            ),
+            Error::ExecutionTimeout(expired) => (
+                504,
+                "Execution Timeout",
+                Some(json!({
+                    "cause": self.to_string(),
+                    "operation": expired.operation,
+                    "progress": expired.progress,
+                })),
+            ),
+            Error::TooManySessions { wallet_id, limit } => (
+                429,
+                "Too Many Sessions",
+                Some(json!({
+                    "cause": self.to_string(),
+                    "wallet_id": wallet_id,
+                    "limit": limit,
+                })),
+            ),
+            Error::SubscriptionsUnavailable => (
+                405,
+                "Use The WebSocket Endpoint",
+                Some(json!({ "cause": self.to_string() })),
+            ),
+            Error::SubscriptionNotFound => (
+                404,
+                "Subscription Not Found",
+                Some(json!({ "cause": self.to_string() })),
+            ),
+            Error::SubscriptionForbidden => (
+                403,
+                "Subscription Forbidden",
+                Some(json!({ "cause": self.to_string() })),
+            ),
+            Error::SpendLimitExceeded {
+                limit,
+                spent,
+                requested,
+                remaining,
+            } => (
+                412,
+                "Spend Limit Exceeded",
+                Some(json!({
+                    "cause": self.to_string(),
+                    "daily_spend_limit": limit.to_string(),
+                    "spent": spent.to_string(),
+                    "requested": requested.to_string(),
+                    "remaining_allowance": remaining.to_string(),
+                })),
+            ),
+            Error::SendConfirmationNotFound => (
+                404,
+                "Confirmation Not Found",
+                Some(json!({ "cause": self.to_string() })),
+            ),
+            Error::Decode {
+                path,
+                offset,
+                cause,
+            } => (
+                400,
+                "Decoding Error",
+                Some(json!({ "cause": cause, "path": path, "offset": offset })),
+            ),
+            Error::Rad(e) => (
+                424,
+                "RAD Error",
+                Some(json!({ "cause": self.to_string(), "error": e })),
+            ),
+            Error::SyncPinned { sync_target_epoch } => (
+                423,
+                "Sync Pinned",
+                Some(json!({
+                    "cause": self.to_string(),
+                    "sync_target_epoch": sync_target_epoch.to_string(),
+                })),
+            ),
+            Error::TransactionRejected { kind, reason, .. } => (
+                417,
+                "Transaction Rejected",
+                Some(json!({ "cause": self.to_string(), "kind": kind, "reason": reason })),
+            ),
+            Error::NodeCapabilityMissing { method } => (
+                501,
+                "Node Capability Missing",
+                Some(json!({ "cause": self.to_string(), "method": method })),
+            ),
+            Error::InsufficientScope { required } => (
+                403,
+                "Insufficient Scope",
+                Some(json!({ "cause": self.to_string(), "required_scope": required })),
+            ),
+            Error::PayloadMovesFunds(reason) => (
+                403,
+                "Payload Moves Funds",
+                Some(json!({ "cause": self.to_string(), "reason": reason })),
+            ),
+            Error::WitnessSigningNotAllowed(transaction_type) => (
+                403,
+                "Witness Signing Not Allowed",
+                Some(json!({ "cause": self.to_string(), "type": transaction_type })),
+            ),
+            Error::Overloaded(overloaded) => (
+                503,
+                "Overloaded",
+                Some(json!({
+                    "cause": self.to_string(),
+                    "class": overloaded.class,
+                    "depth": overloaded.depth,
+                    "limit": overloaded.limit,
+                })),
+            ),
+            Error::TooManyRequests(too_many) => (
+                429,
+                "Too Many Requests",
+                Some(json!({
+                    "cause": self.to_string(),
+                    "class": too_many.class,
+                    "scope": too_many.scope,
+                    "in_flight": too_many.in_flight,
+                    "limit": too_many.limit,
+                })),
+            ),
+            Error::ClientUpgradeRequired {
+                client,
+                minimum,
+                current,
+            } => (
+                426,
+                "Client Upgrade Required",
+                Some(json!({
+                    "cause": self.to_string(),
+                    "client_api_version": client,
+                    "min_client_api_version": minimum,
+                    "api_version": current,
+                })),
+            ),
+            Error::IdempotencyKeyConflict { method } => (
+                409,
+                "Idempotency Key Conflict",
+                Some(json!({ "cause": self.to_string(), "method": method })),
+            ),
+            Error::IdempotentRequestInterrupted { recorded_at } => (
+                409,
+                "Idempotent Request Interrupted",
+                Some(json!({ "cause": self.to_string(), "recorded_at": recorded_at })),
+            ),
+        }
+    }
+}
+
+impl From<TransactionRejection> for Error {
+    fn from(rejection: TransactionRejection) -> Self {
+        Error::TransactionRejected {
+            kind: rejection.kind,
+            reason: rejection.reason,
+            message: rejection.message,
         }
     }
 }
@@ -105,12 +411,22 @@ impl From<actors::worker::Error> for Error {
     fn from(err: actors::worker::Error) -> Self {
         match err {
             actors::worker::Error::WalletAlreadyExists(e) => Error::WalletAlreadyExists(e),
-            actors::worker::Error::WrongPassword => {
-                validation_error(field_error("password", "Wrong password"))
+            actors::worker::Error::WalletBusy(busy) => Error::WalletBusy(busy),
+            actors::worker::Error::WrongPassword => Error::WrongPassword { retry_after: 0 },
+            actors::worker::Error::CorruptedWallet(cause) => Error::CorruptedWallet(cause),
+            actors::worker::Error::UnsupportedKeySpec(spec) => Error::UnsupportedKeySpec(spec),
+            actors::worker::Error::UnsupportedSchemaVersion { found, supported } => {
+                Error::UnsupportedSchemaVersion { found, supported }
             }
-            actors::worker::Error::WalletNotFound => {
-                validation_error(field_error("wallet_id", "Wallet not found"))
+            actors::worker::Error::WalletNotFound => Error::WalletNotFound,
+            actors::worker::Error::Simulation(e @ simulation::Error::NoWitnesses) => {
+                validation_error(field_error("request", e))
             }
+            actors::worker::Error::Simulation(e) => validation_error(field_error("lies", e)),
+            actors::worker::Error::SeedBackupNotPending => validation_error(field_error(
+                "wallet_id",
+                "Wallet is not waiting for its seed backup to be confirmed",
+            )),
             actors::worker::Error::Node(e) => Error::Node(e),
             actors::worker::Error::KeyGen(e @ crypto::Error::InvalidKeyPath(_)) => {
                 validation_error(field_error("seedData", e.to_string()))
@@ -127,15 +443,78 @@ impl From<actors::worker::Error> for Error {
                 }},
                 "Wallet account has not enough balance",
             )),
+            actors::worker::Error::Repository(e @ repository::Error::FeeBelowMinimum { .. }) => {
+                validation_error(field_error(
+                    "fee",
+                    format!("{}. Set `allow_low_fee` to create it anyway.", e),
+                ))
+            }
+            actors::worker::Error::Repository(e @ repository::Error::DustOutput { .. }) => {
+                validation_error(field_error("outputs", e))
+            }
+            actors::worker::Error::Repository(
+                e @ repository::Error::AddressGapExceeded { .. },
+            ) => validation_error(field_error("external", e)),
+            actors::worker::Error::Repository(
+                e @ repository::Error::CursorAccountMismatch { .. },
+            ) => validation_error(field_error("cursor", e)),
+            actors::worker::Error::Repository(
+                e @ repository::Error::UnknownLocalTransaction(_)
+                | e @ repository::Error::TransactionAlreadyIncluded(_)
+                | e @ repository::Error::TransactionSuperseded { .. },
+            ) => validation_error(field_error("transaction_hash", e)),
+            actors::worker::Error::Repository(e @ repository::Error::FeeNotIncreased { .. }) => {
+                validation_error(field_error("fee", e))
+            }
+            actors::worker::Error::Repository(e @ repository::Error::FeeBumpNeedsInputs { .. }) => {
+                validation_error(field_error(
+                    "fee",
+                    format!("{}. Set `add_inputs` to add them.", e),
+                ))
+            }
+            actors::worker::Error::Repository(repository::Error::WitnessSigningNotAllowed(
+                transaction_type,
+            )) => Error::WitnessSigningNotAllowed(transaction_type),
+            actors::worker::Error::Repository(e @ repository::Error::UnknownWitnessKey(_))
+            | actors::worker::Error::Repository(e @ repository::Error::UnavailableCollateral { .. })
+            | actors::worker::Error::Repository(e @ repository::Error::CollateralAboveLimit { .. })
+            | actors::worker::Error::Repository(e @ repository::Error::AddressMismatch { .. })
+            | actors::worker::Error::Repository(e @ repository::Error::UnknownAddress(_)) => {
+                validation_error(field_error("payload", e))
+            }
+            e @ actors::worker::Error::InvalidWebhookUrl(_) => {
+                validation_error(field_error("url", e))
+            }
             actors::worker::Error::JsonRpcTimeout => Error::JsonRpcTimeout,
+            actors::worker::Error::Timeout(expired) => Error::ExecutionTimeout(expired),
+            actors::worker::Error::Rad(e) => Error::Rad(e),
+            actors::worker::Error::SyncInProgress => validation_error(field_error(
+                "epoch",
+                "Cannot pin the synchronization before the last processed block while the wallet is synchronizing",
+            )),
             _ => internal_error(err),
         }
     }
 }
 
+impl From<repository::Error> for Error {
+    fn from(err: repository::Error) -> Self {
+        internal_error(err)
+    }
+}
+
 impl From<tcp::Error> for Error {
     fn from(err: tcp::Error) -> Self {
-        node_error(err)
+        match err {
+            tcp::Error::MethodNotFound { method } => Error::NodeCapabilityMissing { method },
+            err => node_error(err),
+        }
+    }
+}
+
+impl From<pool::Exhausted> for Error {
+    fn from(_err: pool::Exhausted) -> Self {
+        Error::NodeNotConnected
     }
 }
 
@@ -0,0 +1,60 @@
+use actix::prelude::*;
+
+use crate::{
+    actors::app,
+    queues,
+    request_limits::{InFlight, RequestClass},
+    types,
+};
+
+/// Take a place in the queue of the requests of clients before handling one, and count it as in
+/// flight in its session. Both are given back when the returned admission is dropped.
+pub struct AdmitRequest {
+    pub session_id: Option<types::SessionId>,
+    pub class: RequestClass,
+}
+
+/// Places taken by a request that is being handled.
+pub struct Admission {
+    _ticket: queues::Ticket,
+    _in_flight: InFlight,
+    /// API version that the client of the session declared, if any, to make the response
+    /// compatible with it.
+    pub client_api_version: Option<u32>,
+}
+
+impl Message for AdmitRequest {
+    type Result = app::Result<Admission>;
+}
+
+impl Handler<AdmitRequest> for app::App {
+    type Result = <AdmitRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        AdmitRequest { session_id, class }: AdmitRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let ticket = self
+            .params
+            .work_queues
+            .interactive
+            .try_enter()
+            .map_err(app::Error::Overloaded)?;
+        let in_flight = self
+            .params
+            .request_limits
+            .try_enter(session_id.as_ref(), class)
+            .map_err(app::Error::TooManyRequests)?;
+
+        let client_api_version = session_id
+            .as_ref()
+            .and_then(|session_id| self.state.client_api_version(session_id));
+
+        Ok(Admission {
+            _ticket: ticket,
+            _in_flight: in_flight,
+            client_api_version,
+        })
+    }
+}
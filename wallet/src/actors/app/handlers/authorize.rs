@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::actors::app;
+use crate::types;
+
+/// Check that a session can call a method that requires `scope`, before handling the request.
+pub struct Authorize {
+    pub session_id: types::SessionId,
+    pub scope: types::Scope,
+}
+
+impl Message for Authorize {
+    type Result = app::Result<()>;
+}
+
+impl Handler<Authorize> for app::App {
+    type Result = <Authorize as Message>::Result;
+
+    fn handle(&mut self, msg: Authorize, _ctx: &mut Self::Context) -> Self::Result {
+        self.state.check_scope(&msg.session_id, msg.scope)
+    }
+}
@@ -0,0 +1,35 @@
+use actix::prelude::*;
+use futures::channel::oneshot;
+
+use crate::{actors::app, idempotency::IdempotentRequest};
+
+/// Tell whether a request made with an idempotency key is a retry, before handling it.
+pub struct BeginIdempotentRequest(pub IdempotentRequest);
+
+/// How to handle a request made with an idempotency key.
+pub enum Idempotency {
+    /// The request is the first one with its key, so it runs, and its outcome must be given to
+    /// `FinishIdempotentRequest`.
+    Run,
+    /// The request is a retry of one that was answered with this response.
+    Replay(serde_json::Value),
+    /// The request is a retry of one that is still running, so it must begin again once that one
+    /// is done.
+    Wait(oneshot::Receiver<()>),
+}
+
+impl Message for BeginIdempotentRequest {
+    type Result = app::Result<Idempotency>;
+}
+
+impl Handler<BeginIdempotentRequest> for app::App {
+    type Result = app::ResponseActFuture<Idempotency>;
+
+    fn handle(
+        &mut self,
+        BeginIdempotentRequest(request): BeginIdempotentRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.begin_idempotent_request(request)
+    }
+}
@@ -0,0 +1,36 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model};
+use witnet_data_structures::chain::RADRetrieve;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchmarkRetrievalsRequest {
+    /// Data sources to retrieve, which are not aggregated
+    pub retrievals: Vec<RADRetrieve>,
+    /// How many times to retrieve every source
+    pub iterations: u32,
+    /// Milliseconds to wait between the end of an iteration and the start of the next one
+    #[serde(default)]
+    pub spacing_ms: u64,
+    /// Keep the benchmark of every source, so that the next benchmark of the same URL is returned
+    /// along with it
+    #[serde(default)]
+    pub persist: bool,
+}
+
+impl Message for BenchmarkRetrievalsRequest {
+    type Result = app::Result<model::RetrievalBenchmark>;
+}
+
+impl Handler<BenchmarkRetrievalsRequest> for app::App {
+    type Result = app::ResponseFuture<model::RetrievalBenchmark>;
+
+    fn handle(
+        &mut self,
+        msg: BenchmarkRetrievalsRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.benchmark_retrievals(msg.retrievals, msg.iterations, msg.spacing_ms, msg.persist)
+    }
+}
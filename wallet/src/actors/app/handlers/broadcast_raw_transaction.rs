@@ -0,0 +1,211 @@
+use std::convert::TryInto;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+use witnet_crypto::signature;
+use witnet_data_structures::{
+    chain::{ConsensusConstants, Hash, Hashable, Input, KeyedSignature},
+    proto::ProtobufConvert,
+    transaction::Transaction,
+};
+
+use crate::{
+    actors::app::{
+        self,
+        handlers::decode::{decode_error, decode_payload},
+    },
+    model, types,
+};
+
+/// Request to broadcast a transaction that was created and signed by another wallet, e.g. one
+/// running on an offline machine.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BroadcastRawTransactionRequest {
+    session_id: types::SessionId,
+    /// Protocol Buffers serialization of the signed transaction, in hexadecimal or in base64, as
+    /// given in the `bytes` of `create_vtt` and `create_data_request`
+    payload: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BroadcastRawTransactionResponse {
+    pub transaction_id: String,
+    /// Wallet of the session whose outputs the transaction spends, which sent it like
+    /// `send_transaction` would
+    pub wallet_id: Option<model::WalletId>,
+    #[serde(flatten)]
+    pub sent: app::SendTransactionResponse,
+}
+
+impl Message for BroadcastRawTransactionRequest {
+    type Result = app::Result<BroadcastRawTransactionResponse>;
+}
+
+impl Handler<BroadcastRawTransactionRequest> for app::App {
+    type Result = app::ResponseActFuture<BroadcastRawTransactionResponse>;
+
+    fn handle(
+        &mut self,
+        msg: BroadcastRawTransactionRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.broadcast_raw_transaction(msg.session_id, &msg.payload)
+    }
+}
+
+/// Decode a signed transaction, and check it as far as it can be done without knowing the outputs
+/// that it spends: every input must be signed by a valid signature of the transaction, and the
+/// transaction must fit in a block.
+pub fn decode_raw_transaction(
+    payload: &str,
+    consensus_constants: &ConsensusConstants,
+) -> app::Result<Transaction> {
+    let bytes = decode_payload(payload)?;
+    if bytes.is_empty() {
+        return Err(decode_error("payload", Some(0), "the payload is empty"));
+    }
+    let transaction =
+        Transaction::from_pb_bytes(&bytes).map_err(|e| decode_error("transaction", None, e))?;
+    // Bytes that do not serialize back to themselves, e.g. because of trailing garbage, could
+    // mean different transactions to different readers
+    let canonical = transaction
+        .to_pb_bytes()
+        .map_err(|e| decode_error("transaction", None, e))?;
+    if canonical != bytes {
+        let offset = canonical
+            .iter()
+            .zip(&bytes)
+            .position(|(canonical, byte)| canonical != byte)
+            .unwrap_or_else(|| canonical.len().min(bytes.len()));
+
+        return Err(decode_error(
+            "transaction",
+            Some(offset as u64),
+            "the bytes are not the canonical serialization of a transaction",
+        ));
+    }
+
+    let (inputs, signatures, weight, max_weight) = match &transaction {
+        Transaction::ValueTransfer(vtt) => (
+            &vtt.body.inputs,
+            &vtt.signatures,
+            vtt.weight(),
+            consensus_constants.max_vt_weight,
+        ),
+        Transaction::DataRequest(drt) => (
+            &drt.body.inputs,
+            &drt.signatures,
+            drt.weight(),
+            consensus_constants.max_dr_weight,
+        ),
+        _ => {
+            return Err(decode_error(
+                "transaction",
+                None,
+                "only value transfers and data requests can be broadcast",
+            ))
+        }
+    };
+    verify_signatures(inputs, signatures, transaction.hash())?;
+    if weight > max_weight {
+        return Err(decode_error(
+            "transaction.weight",
+            None,
+            format!("{} is above the limit of {}", weight, max_weight),
+        ));
+    }
+
+    Ok(transaction)
+}
+
+/// Check that every input is signed by a valid signature of the transaction. Whether the keys are
+/// the ones of the spent outputs can only be checked by the node.
+fn verify_signatures(
+    inputs: &[Input],
+    signatures: &[KeyedSignature],
+    hash: Hash,
+) -> app::Result<()> {
+    if signatures.is_empty() {
+        return Err(decode_error(
+            "transaction.signatures",
+            None,
+            "the transaction is not signed",
+        ));
+    }
+    if signatures.len() != inputs.len() {
+        return Err(decode_error(
+            "transaction.signatures",
+            None,
+            format!(
+                "there are {} signatures for {} inputs",
+                signatures.len(),
+                inputs.len()
+            ),
+        ));
+    }
+
+    for (index, keyed_signature) in signatures.iter().enumerate() {
+        let path = format!("transaction.signatures[{}]", index);
+        let public_key = keyed_signature
+            .public_key
+            .clone()
+            .try_into()
+            .map_err(|e: failure::Error| decode_error(&path, None, e))?;
+        let signature = keyed_signature
+            .signature
+            .clone()
+            .try_into()
+            .map_err(|e: failure::Error| decode_error(&path, None, e))?;
+        signature::verify(&public_key, hash.as_ref(), &signature).map_err(|_| {
+            decode_error(&path, None, "the signature does not verify the transaction")
+        })?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use witnet_data_structures::transaction::VTTransaction;
+
+    use super::*;
+
+    fn decode_error_parts(result: app::Result<Transaction>) -> (String, Option<u64>) {
+        match result {
+            Err(app::Error::Decode { path, offset, .. }) => (path, offset),
+            other => panic!("Expected a decoding error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn payloads_that_are_not_transactions_are_rejected() {
+        let constants = ConsensusConstants::default();
+
+        assert_eq!(
+            decode_error_parts(decode_raw_transaction("0a0", &constants)),
+            ("payload".to_string(), Some(3))
+        );
+        assert_eq!(
+            decode_error_parts(decode_raw_transaction("", &constants)),
+            ("payload".to_string(), Some(0))
+        );
+        assert_eq!(
+            decode_error_parts(decode_raw_transaction("ffffffff", &constants)).0,
+            "transaction"
+        );
+    }
+
+    #[test]
+    fn unsigned_transactions_are_rejected() {
+        let transaction = Transaction::ValueTransfer(VTTransaction::default());
+        let payload = hex::encode(transaction.to_pb_bytes().unwrap());
+
+        assert_eq!(
+            decode_error_parts(decode_raw_transaction(
+                &payload,
+                &ConsensusConstants::default()
+            )),
+            ("transaction.signatures".to_string(), None)
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use std::str::FromStr;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+use witnet_data_structures::{
+    chain::Hash,
+    fee::{deserialize_fee_backwards_compatible, AbsoluteFee, Fee},
+};
+
+use crate::{
+    actors::app,
+    model,
+    types::{self, fee_compat, FeeType},
+};
+
+/// Request to replace a pending value transfer with a copy that pays a higher fee.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BumpFeeRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    /// Hash of the pending transaction to replace
+    transaction_hash: String,
+    /// New fee, or priority if `fee_type` is `weighted`
+    #[serde(deserialize_with = "deserialize_fee_backwards_compatible")]
+    fee: Fee,
+    fee_type: Option<FeeType>,
+    /// Add more inputs if the change of the transaction cannot cover the higher fee
+    #[serde(default)]
+    add_inputs: bool,
+    /// Create the replacement even if its fee is below the minimum fee policy of the wallet
+    #[serde(default)]
+    allow_low_fee: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BumpFeeResponse {
+    /// Hash of the transaction that replaces the pending one
+    pub transaction_id: String,
+    /// Effective fee of the replacement, which includes any change below the dust threshold of
+    /// the wallet
+    pub fee: AbsoluteFee,
+    #[serde(flatten)]
+    pub sent: app::SendTransactionResponse,
+}
+
+impl Message for BumpFeeRequest {
+    type Result = app::Result<BumpFeeResponse>;
+}
+
+impl Handler<BumpFeeRequest> for app::App {
+    type Result = app::ResponseActFuture<BumpFeeResponse>;
+
+    fn handle(&mut self, msg: BumpFeeRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let transaction_hash = match Hash::from_str(&msg.transaction_hash) {
+            Ok(hash) => hash,
+            Err(err) => {
+                return Box::pin(fut::err(app::validation_error(app::field_error(
+                    "transaction_hash",
+                    err.to_string(),
+                ))))
+            }
+        };
+        // For the sake of backwards compatibility, if the `fee_type` argument was provided, then we
+        // treat the `fee` argument as such type, regardless of how it was originally deserialized.
+        let fee = fee_compat(msg.fee, msg.fee_type);
+
+        self.bump_fee(
+            msg.session_id,
+            msg.wallet_id,
+            transaction_hash,
+            fee,
+            msg.add_inputs,
+            msg.allow_low_fee,
+        )
+    }
+}
@@ -0,0 +1,38 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CheckIntegrityRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    /// Repair the records that can be derived from others
+    #[serde(default)]
+    repair: bool,
+}
+
+#[derive(Serialize)]
+pub struct CheckIntegrityResponse {
+    findings: Vec<model::IntegrityFinding>,
+    needs_resync: bool,
+}
+
+impl Message for CheckIntegrityRequest {
+    type Result = app::Result<CheckIntegrityResponse>;
+}
+
+impl Handler<CheckIntegrityRequest> for app::App {
+    type Result = app::ResponseActFuture<CheckIntegrityResponse>;
+
+    fn handle(&mut self, msg: CheckIntegrityRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self
+            .check_integrity(msg.session_id, msg.wallet_id, msg.repair)
+            .map_ok(|report, _, _| CheckIntegrityResponse {
+                needs_resync: report.needs_resync(),
+                findings: report.findings,
+            });
+
+        Box::pin(f)
+    }
+}
@@ -0,0 +1,90 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use witnet_data_structures::{
+    chain::{tapi::current_active_wips, RADTally},
+    radon_report::RadonReport,
+};
+use witnet_rad::{
+    conditions::{run_tally_and_classify, RevealClassification},
+    types::{JsonConversion, RadonTypes},
+};
+
+use crate::actors::app;
+
+/// Request for running a tally on some reveals, so as to learn how consensus would classify each
+/// of them.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ClassifyRevealsRequest {
+    /// Revealed values, as JSON.
+    pub reveals: Vec<serde_json::Value>,
+    pub tally: RADTally,
+    /// Minimum percentage of the reveals that must be in consensus, as in data requests.
+    #[serde(default = "default_min_consensus_percentage")]
+    pub min_consensus_percentage: u32,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ClassifyRevealsResponse {
+    /// The tally result along with its metadata.
+    pub tally: RadonReport<RadonTypes>,
+    /// Classification of each reveal, in the same order as the reveals.
+    pub classifications: Vec<RevealClassification>,
+}
+
+fn default_min_consensus_percentage() -> u32 {
+    51
+}
+
+impl Message for ClassifyRevealsRequest {
+    type Result = app::Result<ClassifyRevealsResponse>;
+}
+
+impl Handler<ClassifyRevealsRequest> for app::App {
+    type Result = <ClassifyRevealsRequest as Message>::Result;
+
+    fn handle(&mut self, msg: ClassifyRevealsRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let non_error_min = f64::from(msg.min_consensus_percentage) / 100.0;
+        let (reveals, script) = validate_classify_reveals(msg).map_err(app::validation_error)?;
+        let (tally, classifications) =
+            run_tally_and_classify(reveals, &script, non_error_min, &current_active_wips());
+
+        Ok(ClassifyRevealsResponse {
+            tally,
+            classifications,
+        })
+    }
+}
+
+/// Validate `ClassifyRevealsRequest`.
+///
+/// To be valid it must pass these checks:
+/// - there is at least one reveal
+/// - every reveal is a valid RADON value
+/// - the minimum consensus percentage is within the range allowed in data requests
+pub fn validate_classify_reveals(
+    req: ClassifyRevealsRequest,
+) -> Result<(Vec<RadonTypes>, RADTally), app::ValidationErrors> {
+    if req.reveals.is_empty() {
+        return Err(app::field_error("reveals", "At least one reveal is needed"));
+    }
+    if !(51..100).contains(&req.min_consensus_percentage) {
+        return Err(app::field_error(
+            "min_consensus_percentage",
+            "The minimum consensus percentage must be between 51 and 99",
+        ));
+    }
+
+    let reveals = req
+        .reveals
+        .into_iter()
+        .enumerate()
+        .map(|(index, reveal)| {
+            RadonTypes::from_json(reveal, JsonConversion::Strict).map_err(|err| {
+                app::field_error("reveals", format!("Invalid reveal #{}: {}", index, err))
+            })
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((reveals, req.tally))
+}
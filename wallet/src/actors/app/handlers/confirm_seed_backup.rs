@@ -0,0 +1,77 @@
+use std::collections::HashSet;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{constants, model, types};
+
+/// A word of the mnemonic of a wallet along with its 1-based position
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SeedBackupWord {
+    pub position: usize,
+    pub word: types::Password,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmSeedBackupRequest {
+    pub session_id: types::SessionId,
+    pub wallet_id: model::WalletId,
+    pub words: Vec<SeedBackupWord>,
+}
+
+impl Message for ConfirmSeedBackupRequest {
+    type Result = app::Result<model::SeedBackupConfirmation>;
+}
+
+impl Handler<ConfirmSeedBackupRequest> for app::App {
+    type Result = app::ResponseActFuture<model::SeedBackupConfirmation>;
+
+    fn handle(&mut self, req: ConfirmSeedBackupRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let session_id = req.session_id.clone();
+        let wallet_id = req.wallet_id.clone();
+        let result = validate_confirm_seed_backup(req).map_err(app::validation_error);
+        let f = fut::result(result).and_then(move |words, slf: &mut Self, _| {
+            slf.confirm_seed_backup(session_id, wallet_id, words)
+        });
+
+        Box::pin(f)
+    }
+}
+
+/// Validate `ConfirmSeedBackupRequest`.
+///
+/// To be valid it must pass these checks:
+/// - there are at least `MIN_SEED_BACKUP_WORDS` words
+/// - positions start at 1
+/// - there are no repeated positions
+pub fn validate_confirm_seed_backup(
+    req: ConfirmSeedBackupRequest,
+) -> Result<Vec<(usize, types::Password)>, app::ValidationErrors> {
+    if req.words.len() < constants::MIN_SEED_BACKUP_WORDS {
+        return Err(app::field_error(
+            "words",
+            format!(
+                "At least {} words of the mnemonic are needed",
+                constants::MIN_SEED_BACKUP_WORDS
+            ),
+        ));
+    }
+
+    let mut positions = HashSet::new();
+    let mut words = Vec::with_capacity(req.words.len());
+    for SeedBackupWord { position, word } in req.words {
+        if position == 0 {
+            return Err(app::field_error("words", "Word positions start at 1"));
+        }
+        if !positions.insert(position) {
+            return Err(app::field_error(
+                "words",
+                format!("Repeated word position {}", position),
+            ));
+        }
+        words.push((position, word));
+    }
+
+    Ok(words)
+}
@@ -0,0 +1,24 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, types};
+
+/// Request to send a transaction that `send_transaction` kept waiting for a confirmation.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ConfirmSendRequest {
+    session_id: types::SessionId,
+    /// One-time token returned by `send_transaction`
+    token: String,
+}
+
+impl Message for ConfirmSendRequest {
+    type Result = app::Result<app::SendTransactionResponse>;
+}
+
+impl Handler<ConfirmSendRequest> for app::App {
+    type Result = app::ResponseActFuture<app::SendTransactionResponse>;
+
+    fn handle(&mut self, msg: ConfirmSendRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.confirm_send(msg.session_id, msg.token)
+    }
+}
@@ -1,8 +1,7 @@
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
-use witnet_config::defaults::PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
 use witnet_data_structures::{
-    chain::{tapi::current_active_wips, DataRequestOutput, Hashable},
+    chain::{tapi::current_active_wips, ConsensusConstants, DataRequestOutput, Hashable},
     fee::{deserialize_fee_backwards_compatible, AbsoluteFee, Fee},
     proto::ProtobufConvert,
     transaction::Transaction,
@@ -13,7 +12,7 @@ use crate::{
         app::{self, handlers::create_vtt::VttOutputParams},
         worker,
     },
-    model::TransactionMetadata,
+    model::{self, TransactionMetadata},
     types::{
         self, fee_compat, from_generic_type, from_generic_type_vec, into_generic_type,
         into_generic_type_vec, number_from_string, u32_to_string, DataRequestOutputHelper, FeeType,
@@ -24,7 +23,7 @@ use crate::{
 #[derive(Debug, Deserialize)]
 pub struct CreateDataReqRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
     #[serde(
         serialize_with = "into_generic_type::<_, DataRequestOutputHelper, _>",
         deserialize_with = "from_generic_type::<_, DataRequestOutputHelper, _>"
@@ -35,6 +34,9 @@ pub struct CreateDataReqRequest {
     fee_type: Option<FeeType>,
     #[serde(default)]
     preview: bool,
+    /// Create the transaction even if its fee is below the minimum fee policy of the wallet
+    #[serde(default)]
+    allow_low_fee: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,7 +47,10 @@ pub struct CreateDataReqResponse {
         deserialize_with = "from_generic_type::<_, TransactionHelper, _>"
     )]
     pub transaction: Transaction,
+    /// Hex encoded serialization of the signed transaction, which can be broadcast from another
+    /// wallet with `broadcast_raw_transaction`
     pub bytes: String,
+    /// Effective fee, which includes any change below the dust threshold of the wallet
     #[serde(deserialize_with = "number_from_string")]
     pub fee: AbsoluteFee,
     #[serde(
@@ -70,7 +75,7 @@ impl Handler<CreateDataReqRequest> for app::App {
     fn handle(&mut self, msg: CreateDataReqRequest, _ctx: &mut Self::Context) -> Self::Result {
         let consensus_constants = &self.params.consensus_constants;
         let required_reward_collateral_ratio =
-            PSEUDO_CONSENSUS_CONSTANTS_WIP0022_REWARD_COLLATERAL_RATIO;
+            ConsensusConstants::minimum_reward_collateral_ratio();
         let validated = validate(
             msg.request.clone(),
             consensus_constants.collateral_minimum,
@@ -87,6 +92,7 @@ impl Handler<CreateDataReqRequest> for app::App {
                 request,
                 fee,
                 preview: msg.preview,
+                allow_low_fee: msg.allow_low_fee,
             };
 
             slf.create_data_req(&msg.session_id, &msg.wallet_id, params)
@@ -0,0 +1,78 @@
+use std::path::PathBuf;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, diagnostics, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateDiagnosticsBundleRequest {
+    session_id: types::SessionId,
+    /// Only include this wallet, instead of every wallet unlocked in the session
+    #[serde(default)]
+    wallet_id: Option<model::WalletId>,
+    /// Include the addresses, balance and movements of the wallet, which needs its password
+    #[serde(default)]
+    include_sensitive: bool,
+    #[serde(default)]
+    password: Option<types::Password>,
+    /// Write the bundle to this new file instead of returning it
+    #[serde(default)]
+    output_path: Option<PathBuf>,
+}
+
+/// The bundle, or the path of the file to which it was written
+#[derive(Serialize)]
+pub struct CreateDiagnosticsBundleResponse {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    bundle: Option<diagnostics::DiagnosticsBundle>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    path: Option<PathBuf>,
+}
+
+impl Message for CreateDiagnosticsBundleRequest {
+    type Result = app::Result<CreateDiagnosticsBundleResponse>;
+}
+
+impl Handler<CreateDiagnosticsBundleRequest> for app::App {
+    type Result = app::ResponseActFuture<CreateDiagnosticsBundleResponse>;
+
+    fn handle(
+        &mut self,
+        msg: CreateDiagnosticsBundleRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let password = match (msg.include_sensitive, msg.password) {
+            (true, None) => {
+                return Box::pin(fut::err(app::validation_error(app::field_error(
+                    "password",
+                    "The password of the wallet is needed for including sensitive data",
+                ))));
+            }
+            (true, password) => password,
+            (false, _) => None,
+        };
+        let output_path = msg.output_path;
+
+        let f = self
+            .create_diagnostics_bundle(msg.session_id, msg.wallet_id, password)
+            .and_then(move |bundle, slf: &mut app::App, _| match output_path {
+                Some(path) => {
+                    let f =
+                        slf.write_diagnostics_bundle(path.clone(), bundle)
+                            .map_ok(|(), _, _| CreateDiagnosticsBundleResponse {
+                                bundle: None,
+                                path: Some(path),
+                            });
+
+                    Box::pin(f) as app::ResponseActFuture<_>
+                }
+                None => Box::pin(fut::ok(CreateDiagnosticsBundleResponse {
+                    bundle: Some(bundle),
+                    path: None,
+                })),
+            });
+
+        Box::pin(f)
+    }
+}
@@ -2,7 +2,6 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
-use futures_util::FutureExt;
 use witnet_crypto::mnemonic;
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -24,10 +23,8 @@ impl Handler<CreateMnemonicsRequest> for app::App {
 
     fn handle(&mut self, req: CreateMnemonicsRequest, _ctx: &mut Self::Context) -> Self::Result {
         let result = validate(req).map_err(app::validation_error);
-        let f = fut::result(result).and_then(|length, slf: &mut Self, _| {
-            slf.generate_mnemonics(length)
-                .map(|res| res.map(|mnemonics| CreateMnemonicsResponse { mnemonics }))
-                .into_actor(slf)
+        let f = fut::result(result).map_ok(|length, slf: &mut Self, _| CreateMnemonicsResponse {
+            mnemonics: slf.generate_mnemonics(length),
         });
 
         Box::pin(f)
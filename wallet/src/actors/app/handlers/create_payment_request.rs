@@ -0,0 +1,41 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreatePaymentRequestRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    amount: u64,
+    memo: Option<String>,
+    /// Seconds until the request expires, if it was not paid
+    expires_in: Option<u64>,
+}
+
+pub type CreatePaymentRequestResponse = model::PaymentRequest;
+
+impl Message for CreatePaymentRequestRequest {
+    type Result = app::Result<CreatePaymentRequestResponse>;
+}
+
+impl Handler<CreatePaymentRequestRequest> for app::App {
+    type Result = app::ResponseActFuture<CreatePaymentRequestResponse>;
+
+    fn handle(
+        &mut self,
+        msg: CreatePaymentRequestRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let f = self.create_payment_request(
+            msg.session_id,
+            msg.wallet_id,
+            msg.amount,
+            msg.memo,
+            msg.expires_in,
+        );
+
+        Box::pin(f)
+    }
+}
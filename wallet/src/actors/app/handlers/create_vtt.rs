@@ -3,16 +3,16 @@ use std::collections::HashSet;
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 use witnet_data_structures::{
-    chain::{Environment, Hashable, OutputPointer, PublicKeyHash, ValueTransferOutput},
+    chain::{Hashable, OutputPointer, ValueTransferOutput},
     fee::{deserialize_fee_backwards_compatible, AbsoluteFee, Fee},
     proto::ProtobufConvert,
-    transaction::Transaction,
+    transaction::{vt_weight, Transaction},
     utxo_pool::UtxoSelectionStrategy,
 };
 
 use crate::{
     actors::{app, worker},
-    model::TransactionMetadata,
+    model::{self, SpendingCheck, TransactionMetadata},
     types::{
         self, fee_compat, from_generic_type, from_generic_type_vec, into_generic_type,
         into_generic_type_vec, number_from_string, u32_to_string, FeeType, TransactionHelper,
@@ -26,6 +26,8 @@ pub struct VttOutputParams {
     pub address: String,
     pub amount: u64,
     pub time_lock: Option<u64>,
+    /// Free text kept by the wallet alongside the output, which is not part of the transaction
+    pub label: Option<String>,
 }
 
 impl From<ValueTransferOutput> for VttOutputParams {
@@ -44,6 +46,7 @@ impl From<ValueTransferOutput> for VttOutputParams {
             } else {
                 Some(time_lock)
             },
+            label: None,
         }
     }
 }
@@ -60,19 +63,26 @@ pub struct CreateVttRequest {
     )]
     outputs: Vec<VttOutputParams>,
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
     #[serde(default)]
     utxo_strategy: UtxoSelectionStrategy,
     #[serde(default)]
     selected_utxos: HashSet<OutputPointer>,
     #[serde(default)]
     preview: bool,
+    /// Create the transaction even if the synchronization of the wallet is pinned
+    #[serde(default)]
+    force: bool,
+    /// Create the transaction even if its fee is below the minimum fee policy of the wallet
+    #[serde(default)]
+    allow_low_fee: bool,
 }
 
 /// Part of CreateVttResponse struct, containing additional data to be displayed in clients
 /// (e.g. in a confirmation screen)
 #[derive(Debug, Serialize, Deserialize)]
 pub struct VttMetadata {
+    /// Effective fee, which includes any change below the dust threshold of the wallet
     #[serde(deserialize_with = "number_from_string")]
     fee: AbsoluteFee,
     #[serde(
@@ -85,6 +95,11 @@ pub struct VttMetadata {
         deserialize_with = "from_generic_type_vec::<_, VttOutputParamsHelper, _>"
     )]
     outputs: Vec<VttOutputParams>,
+    /// Addresses that more than one output pays to, which is allowed but most likely a mistake
+    duplicate_addresses: Vec<String>,
+    /// Whether the transaction has no change output, because its inputs pay for its outputs and
+    /// fee exactly or with an excess that was added to the fee
+    changeless: bool,
     #[serde(
         serialize_with = "u32_to_string",
         deserialize_with = "number_from_string"
@@ -100,8 +115,12 @@ pub struct CreateVttResponse {
         deserialize_with = "from_generic_type::<_, TransactionHelper, _>"
     )]
     pub transaction: Transaction,
+    /// Hex encoded serialization of the signed transaction, which can be broadcast from another
+    /// wallet with `broadcast_raw_transaction`
     pub bytes: String,
     pub metadata: VttMetadata,
+    /// How the spending policy of the wallet applies to sending the transaction
+    pub spending: SpendingCheck,
 }
 
 impl Message for CreateVttRequest {
@@ -113,8 +132,13 @@ impl Handler<CreateVttRequest> for app::App {
 
     fn handle(&mut self, msg: CreateVttRequest, _ctx: &mut Self::Context) -> Self::Result {
         let testnet = self.params.testnet;
-        let validated =
-            validate_output_addresses(testnet, &msg.outputs).map_err(app::validation_error);
+        let max_outputs = max_vtt_outputs(
+            self.params.max_vtt_outputs,
+            self.params.consensus_constants.max_vt_weight,
+        );
+        let validated = validate_output_count(max_outputs, msg.outputs.len())
+            .and_then(|()| validate_output_addresses(testnet, &msg.outputs))
+            .map_err(app::validation_error);
 
         // For the sake of backwards compatibility, if the `fee_type` argument was provided, then we
         // treat the `fee` argument as such type, regardless of how it was originally deserialized.
@@ -124,14 +148,26 @@ impl Handler<CreateVttRequest> for app::App {
             let params = types::VttParams {
                 fee,
                 outputs,
+                labels: msg
+                    .outputs
+                    .iter()
+                    .map(|output| output.label.clone())
+                    .collect(),
                 utxo_strategy: msg.utxo_strategy.clone(),
                 selected_utxos: msg.selected_utxos.iter().map(|x| x.into()).collect(),
                 preview: msg.preview,
+                allow_low_fee: msg.allow_low_fee,
             };
 
-            act.create_vtt(&msg.session_id, &msg.wallet_id, params)
+            act.create_vtt(&msg.session_id, &msg.wallet_id, params, msg.force)
                 .map_ok(
-                    move |worker::CreateVttResponse { fee, transaction }, _, _| {
+                    move |worker::CreateVttResponse {
+                              fee,
+                              transaction,
+                              spending,
+                          },
+                          _,
+                          _| {
                         let inputs = match transaction.metadata {
                             Some(TransactionMetadata::InputValues(inputs)) => {
                                 inputs.into_iter().map(From::from).collect_vec()
@@ -139,9 +175,16 @@ impl Handler<CreateVttRequest> for app::App {
                             _ => vec![],
                         };
                         let transaction = transaction.transaction;
+                        let changeless = match &transaction {
+                            Transaction::ValueTransfer(vtt) => {
+                                vtt.body.outputs.len() == msg.outputs.len()
+                            }
+                            _ => false,
+                        };
                         let transaction_id = hex::encode(transaction.hash().as_ref());
                         let bytes = hex::encode(transaction.to_pb_bytes().unwrap());
                         let weight = transaction.weight();
+                        let duplicate_addresses = duplicate_addresses(&msg.outputs);
 
                         CreateVttResponse {
                             transaction_id,
@@ -151,8 +194,11 @@ impl Handler<CreateVttRequest> for app::App {
                                 fee,
                                 inputs,
                                 outputs: msg.outputs,
+                                duplicate_addresses,
+                                changeless,
                                 weight,
                             },
+                            spending,
                         }
                     },
                 )
@@ -167,33 +213,75 @@ impl Handler<CreateVttRequest> for app::App {
     }
 }
 
+/// Maximum number of recipients of a value transfer transaction: the configured one, unless even
+/// a transaction with a single input and a change output would exceed the weight limit with that
+/// many.
+pub fn max_vtt_outputs(configured: usize, max_vt_weight: u32) -> usize {
+    (0..=configured)
+        .take_while(|outputs| vt_weight(1, outputs + 1) <= max_vt_weight)
+        .last()
+        .unwrap_or_default()
+}
+
+/// Validate that a transaction has at most `max_outputs` recipients
+pub fn validate_output_count(
+    max_outputs: usize,
+    outputs: usize,
+) -> Result<(), app::ValidationErrors> {
+    if outputs > max_outputs {
+        Err(app::field_error(
+            "outputs",
+            format!(
+                "Too many outputs: {}. Value transfers created by this wallet can have up to {} \
+                 outputs within the weight limit.",
+                outputs, max_outputs
+            ),
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/// Addresses that more than one output pays to, in the order in which they are first repeated
+pub fn duplicate_addresses(outputs: &[VttOutputParams]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    let mut duplicates = vec![];
+    for output in outputs {
+        if !seen.insert(&output.address) && !duplicates.contains(&output.address) {
+            duplicates.push(output.address.clone());
+        }
+    }
+
+    duplicates
+}
+
 /// Validate output addresses and transform addresses to `ValueTransferOutputs`
 ///
-/// To be valid it must pass these checks:
-/// - destination address must be in the same network (testnet/mainnet)
+/// To be valid it must pass the same checks as `validate_address`, including that the destination
+/// address must be in the same network (testnet/mainnet)
 pub fn validate_output_addresses(
     testnet: bool,
     outputs: &[VttOutputParams],
 ) -> Result<Vec<ValueTransferOutput>, app::ValidationErrors> {
-    let environment = if testnet {
-        Environment::Testnet
-    } else {
-        Environment::Mainnet
-    };
     outputs.iter().try_fold(vec![], |mut acc, output| {
-        PublicKeyHash::from_bech32(environment, &output.address)
-            .map(|pkh| {
-                acc.push(ValueTransferOutput {
-                    pkh,
-                    value: output.amount,
-                    time_lock: output.time_lock.unwrap_or_default(),
-                });
-            })
-            .map_err(|err| {
-                log::warn!("Invalid address: {}", err);
-
-                app::field_error("address", "Address failed to deserialize.")
-            })?;
+        let validation = app::inspect_address(testnet, &output.address);
+        let pkh = match (validation.pkh, validation.error) {
+            (Some(pkh), _) => pkh,
+            (None, error) => {
+                let error = error.map(|error| error.to_string()).unwrap_or_default();
+                log::warn!("Invalid address {}: {}", output.address, error);
+
+                return Err(app::field_error(
+                    "address",
+                    format!("Address failed to deserialize: {}.", error),
+                ));
+            }
+        };
+        acc.push(ValueTransferOutput {
+            pkh,
+            value: output.amount,
+            time_lock: output.time_lock.unwrap_or_default(),
+        });
 
         Ok(acc)
     })
@@ -203,7 +291,9 @@ pub fn validate_output_addresses(
 mod tests {
     use witnet_data_structures::chain::{Environment, PublicKeyHash, ValueTransferOutput};
 
-    use crate::actors::app::{validate_output_addresses, VttOutputParams};
+    use crate::actors::app::{
+        duplicate_addresses, max_vtt_outputs, validate_output_addresses, VttOutputParams,
+    };
 
     #[test]
     fn test_validate_addresses() {
@@ -211,6 +301,7 @@ mod tests {
             address: "wit18cfejmk3305y9kw5xqa59rwnpjzahr57us48vm".to_string(),
             amount: 10,
             time_lock: None,
+            label: None,
         }];
         let validation = validate_output_addresses(false, &output_mainnet).unwrap();
         assert_eq!(
@@ -230,6 +321,7 @@ mod tests {
             address: "twit1adgt8t2h3xnu358f76zxlph0urf2ev7cd78ggc".to_string(),
             amount: 10,
             time_lock: None,
+            label: None,
         }];
         let validation = validate_output_addresses(true, &output_testnet).unwrap();
         assert_eq!(
@@ -252,6 +344,7 @@ mod tests {
             address: "wit18cfejmk3305y9kw5xqa59rwnpjzahr57us48vx".to_string(),
             amount: 10,
             time_lock: None,
+            label: None,
         }];
         assert!(validate_output_addresses(false, &output_wrong_address).is_err());
 
@@ -259,6 +352,7 @@ mod tests {
             address: "wit18cfejmk3305y9kw5xqa59rwnpjzahr57us48vm".to_string(),
             amount: 10,
             time_lock: None,
+            label: None,
         }];
         assert!(validate_output_addresses(true, &output_mainnet).is_err());
 
@@ -266,7 +360,50 @@ mod tests {
             address: "twit1adgt8t2h3xnu358f76zxlph0urf2ev7cd78ggc".to_string(),
             amount: 10,
             time_lock: None,
+            label: None,
         }];
         assert!(validate_output_addresses(false, &output_testnet).is_err());
     }
+
+    #[test]
+    fn test_validate_addresses_error_message() {
+        let output_typo = [VttOutputParams {
+            address: "wit18cfejmk3305y9kw5xqa59rwnpjzahr57us48vx".to_string(),
+            amount: 10,
+            time_lock: None,
+            label: None,
+        }];
+
+        assert_eq!(
+            validate_output_addresses(false, &output_typo),
+            Err(vec![(
+                "address".to_string(),
+                "Address failed to deserialize: invalid checksum, the character at position 41 \
+                 is probably mistyped."
+                    .to_string()
+            )])
+        );
+    }
+
+    #[test]
+    fn test_max_vtt_outputs() {
+        // 1 input and 55 outputs weigh 133 + 55 * 360 = 19933
+        assert_eq!(max_vtt_outputs(50, 20_000), 50);
+        assert_eq!(max_vtt_outputs(100, 20_000), 54);
+        assert_eq!(max_vtt_outputs(100, 100), 0);
+    }
+
+    #[test]
+    fn test_duplicate_addresses() {
+        let output = |address: &str| VttOutputParams {
+            address: address.to_string(),
+            amount: 10,
+            time_lock: None,
+            label: None,
+        };
+        let outputs = ["a", "b", "a", "c", "b", "a"].map(output);
+
+        assert_eq!(duplicate_addresses(&outputs), vec!["a", "b"]);
+        assert!(duplicate_addresses(&outputs[..2]).is_empty());
+    }
 }
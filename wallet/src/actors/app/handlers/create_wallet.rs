@@ -2,8 +2,9 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 use std::str;
 
-use crate::{actors::app, types};
+use crate::{actors::app, model, types};
 use futures_util::FutureExt;
+use witnet_crypto::key::KeySpec;
 
 /// Create Wallet request, where name, description and overwrite are optional and backup_password
 /// is only needed if seed_source is xprv
@@ -15,14 +16,22 @@ pub struct CreateWalletRequest {
     seed_source: String,
     seed_data: types::Password,
     overwrite: Option<bool>,
+    /// Create the wallet even if there already is a wallet created from the same seed
+    allow_duplicate_seed: Option<bool>,
     /// only needed if seed_source is xprv
     backup_password: Option<types::Password>,
     birth_date: Option<types::BirthDate>,
+    /// Keep the wallet unusable until the user confirms having backed up its mnemonic with
+    /// `confirm_seed_backup`. Only allowed if seed_source is mnemonics
+    backup_confirmation: Option<bool>,
+    /// Key spec to derive the wallet accounts with (`wip3` or `bip44`), instead of the configured
+    /// one. It is recorded with the wallet
+    key_spec: Option<KeySpec>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CreateWalletResponse {
-    pub wallet_id: String,
+    pub wallet_id: model::WalletId,
 }
 
 impl Message for CreateWalletRequest {
@@ -33,6 +42,14 @@ impl Handler<CreateWalletRequest> for app::App {
     type Result = app::ResponseActFuture<CreateWalletResponse>;
 
     fn handle(&mut self, req: CreateWalletRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let allow_duplicate_seed = req.allow_duplicate_seed.unwrap_or(false);
+        let backup_confirmation = req.backup_confirmation.unwrap_or(false);
+        if backup_confirmation && req.seed_source != "mnemonics" {
+            return Box::pin(fut::err(app::validation_error(app::field_error(
+                "backup_confirmation",
+                "Seed backups can only be confirmed for wallets created from mnemonics",
+            ))));
+        }
         let validated_params = app::methods::validate(
             req.password,
             req.seed_data,
@@ -51,7 +68,10 @@ impl Handler<CreateWalletRequest> for app::App {
                 params.name,
                 params.description,
                 params.overwrite,
+                allow_duplicate_seed,
                 params.birth_date,
+                backup_confirmation,
+                req.key_spec,
             )
             .map(|res| res.map(|wallet_id| CreateWalletResponse { wallet_id }))
             .into_actor(slf)
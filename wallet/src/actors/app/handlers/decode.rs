@@ -0,0 +1,732 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+
+use crate::actors::app;
+use witnet_data_structures::{
+    chain::{
+        Block, DataRequestOutput, Environment, Hashable, Input, RADFilter, RADRequest, RADRetrieve,
+        ValueTransferOutput,
+    },
+    proto::ProtobufConvert,
+    transaction::Transaction,
+};
+use witnet_rad::{
+    error::RadError, filters::RadonFilters, reducers::RadonReducers, script::unpack_radon_call,
+    CborValue,
+};
+
+/// Kinds of payloads that can be decoded
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum PayloadKind {
+    Transaction,
+    Block,
+    RadRequest,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DecodeRequest {
+    /// Protocol Buffers serialization of the payload, in hexadecimal or in base64
+    pub payload: String,
+    pub kind: PayloadKind,
+}
+
+impl Message for DecodeRequest {
+    type Result = app::Result<JsonValue>;
+}
+
+impl Handler<DecodeRequest> for app::App {
+    type Result = <DecodeRequest as Message>::Result;
+
+    fn handle(&mut self, msg: DecodeRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let environment = if self.params.testnet {
+            Environment::Testnet
+        } else {
+            Environment::Mainnet
+        };
+
+        decode(&msg.payload, msg.kind, environment)
+    }
+}
+
+/// Decode a serialized transaction, block or RAD request into a readable JSON rendering.
+///
+/// Payloads made only of hexadecimal digits (optionally prefixed by `0x`) are read as hexadecimal,
+/// and any other payload as base64. Addresses are rendered for the given environment.
+///
+/// Fees are not rendered, because the value of the inputs of a transaction is not part of it.
+pub fn decode(
+    payload: &str,
+    kind: PayloadKind,
+    environment: Environment,
+) -> app::Result<JsonValue> {
+    let bytes = decode_payload(payload)?;
+
+    match kind {
+        PayloadKind::Transaction => {
+            let transaction = Transaction::from_pb_bytes(&bytes)
+                .map_err(|e| decode_error("transaction", None, e))?;
+
+            render_transaction(&transaction, environment, "transaction")
+        }
+        PayloadKind::Block => {
+            let block = Block::from_pb_bytes(&bytes).map_err(|e| decode_error("block", None, e))?;
+
+            render_block(&block, environment, "block")
+        }
+        PayloadKind::RadRequest => {
+            let rad_request = RADRequest::from_pb_bytes(&bytes)
+                .map_err(|e| decode_error("rad_request", None, e))?;
+
+            render_rad_request(&rad_request, "rad_request")
+        }
+    }
+}
+
+/// Error decoding the part of a payload at `path`.
+pub fn decode_error<E: std::fmt::Display>(path: &str, offset: Option<u64>, cause: E) -> app::Error {
+    app::Error::Decode {
+        path: path.to_string(),
+        offset,
+        cause: cause.to_string(),
+    }
+}
+
+/// Bytes of a payload given in hexadecimal or in base64.
+pub fn decode_payload(payload: &str) -> app::Result<Vec<u8>> {
+    let payload = payload.trim();
+    let hex_payload = payload.strip_prefix("0x").unwrap_or(payload);
+
+    if hex_payload.chars().all(|c| c.is_ascii_hexdigit()) {
+        hex::decode(hex_payload).map_err(|e| {
+            let offset = match e {
+                hex::FromHexError::OddLength => Some(payload.len() as u64),
+                _ => None,
+            };
+
+            decode_error("payload", offset, e)
+        })
+    } else {
+        base64::decode(payload).map_err(|e| {
+            let offset = match e {
+                base64::DecodeError::InvalidByte(offset, _)
+                | base64::DecodeError::InvalidLastSymbol(offset, _) => Some(offset as u64),
+                base64::DecodeError::InvalidLength => None,
+            };
+
+            decode_error("payload", offset, e)
+        })
+    }
+}
+
+fn render_inputs(inputs: &[Input]) -> JsonValue {
+    inputs
+        .iter()
+        .map(|input| json!({ "output_pointer": input.output_pointer().to_string() }))
+        .collect()
+}
+
+fn render_outputs(outputs: &[ValueTransferOutput], environment: Environment) -> JsonValue {
+    outputs
+        .iter()
+        .map(|output| {
+            json!({
+                "address": output.pkh.bech32(environment),
+                "value": output.value,
+                "time_lock": output.time_lock,
+            })
+        })
+        .collect()
+}
+
+fn render_transaction(
+    transaction: &Transaction,
+    environment: Environment,
+    path: &str,
+) -> app::Result<JsonValue> {
+    let hash = transaction.hash().to_string();
+
+    Ok(match transaction {
+        Transaction::ValueTransfer(vtt) => json!({
+            "type": "value_transfer",
+            "hash": hash,
+            "inputs": render_inputs(&vtt.body.inputs),
+            "outputs": render_outputs(&vtt.body.outputs, environment),
+        }),
+        Transaction::DataRequest(drt) => json!({
+            "type": "data_request",
+            "hash": hash,
+            "inputs": render_inputs(&drt.body.inputs),
+            "outputs": render_outputs(&drt.body.outputs, environment),
+            "data_request": render_data_request_output(
+                &drt.body.dr_output,
+                &format!("{}.data_request", path),
+            )?,
+        }),
+        Transaction::Commit(ct) => json!({
+            "type": "commit",
+            "hash": hash,
+            "dr_pointer": ct.body.dr_pointer.to_string(),
+            "commitment": ct.body.commitment.to_string(),
+            "collateral": render_inputs(&ct.body.collateral),
+            "outputs": render_outputs(&ct.body.outputs, environment),
+        }),
+        Transaction::Reveal(rt) => json!({
+            "type": "reveal",
+            "hash": hash,
+            "dr_pointer": rt.body.dr_pointer.to_string(),
+            "reveal": hex::encode(&rt.body.reveal),
+            "address": rt.body.pkh.bech32(environment),
+        }),
+        Transaction::Tally(tt) => json!({
+            "type": "tally",
+            "hash": hash,
+            "dr_pointer": tt.dr_pointer.to_string(),
+            "tally": hex::encode(&tt.tally),
+            "outputs": render_outputs(&tt.outputs, environment),
+            "out_of_consensus": tt
+                .out_of_consensus
+                .iter()
+                .map(|pkh| pkh.bech32(environment))
+                .collect::<Vec<_>>(),
+            "error_committers": tt
+                .error_committers
+                .iter()
+                .map(|pkh| pkh.bech32(environment))
+                .collect::<Vec<_>>(),
+        }),
+        Transaction::Mint(mt) => json!({
+            "type": "mint",
+            "hash": hash,
+            "epoch": mt.epoch,
+            "outputs": render_outputs(&mt.outputs, environment),
+        }),
+    })
+}
+
+fn render_transactions<T>(
+    transactions: &[T],
+    environment: Environment,
+    path: &str,
+) -> app::Result<JsonValue>
+where
+    T: Clone + Into<Transaction>,
+{
+    transactions
+        .iter()
+        .enumerate()
+        .map(|(i, transaction)| {
+            render_transaction(
+                &transaction.clone().into(),
+                environment,
+                &format!("{}[{}]", path, i),
+            )
+        })
+        .collect()
+}
+
+/// Render a block and its transactions. `path` locates the block in error messages.
+pub fn render_block(block: &Block, environment: Environment, path: &str) -> app::Result<JsonValue> {
+    let txns = &block.txns;
+
+    Ok(json!({
+        "hash": block.hash().to_string(),
+        "epoch": block.block_header.beacon.checkpoint,
+        "previous_block": block.block_header.beacon.hash_prev_block.to_string(),
+        "miner": block.block_sig.public_key.pkh().bech32(environment),
+        "transactions": {
+            "mint": render_transaction(
+                &txns.mint.clone().into(),
+                environment,
+                &format!("{}.mint", path),
+            )?,
+            "value_transfer": render_transactions(
+                &txns.value_transfer_txns,
+                environment,
+                &format!("{}.value_transfer", path),
+            )?,
+            "data_request": render_transactions(
+                &txns.data_request_txns,
+                environment,
+                &format!("{}.data_request", path),
+            )?,
+            "commit": render_transactions(
+                &txns.commit_txns,
+                environment,
+                &format!("{}.commit", path),
+            )?,
+            "reveal": render_transactions(
+                &txns.reveal_txns,
+                environment,
+                &format!("{}.reveal", path),
+            )?,
+            "tally": render_transactions(
+                &txns.tally_txns,
+                environment,
+                &format!("{}.tally", path),
+            )?,
+        },
+    }))
+}
+
+fn render_data_request_output(dr_output: &DataRequestOutput, path: &str) -> app::Result<JsonValue> {
+    Ok(json!({
+        "witnesses": dr_output.witnesses,
+        "witness_reward": dr_output.witness_reward,
+        "commit_and_reveal_fee": dr_output.commit_and_reveal_fee,
+        "min_consensus_percentage": dr_output.min_consensus_percentage,
+        "collateral": dr_output.collateral,
+        "rad_request": render_rad_request(
+            &dr_output.data_request,
+            &format!("{}.rad_request", path),
+        )?,
+    }))
+}
+
+fn render_rad_request(rad_request: &RADRequest, path: &str) -> app::Result<JsonValue> {
+    let retrieve = rad_request
+        .retrieve
+        .iter()
+        .enumerate()
+        .map(|(i, retrieve)| render_retrieval(retrieve, &format!("{}.retrieve[{}]", path, i)))
+        .collect::<app::Result<Vec<_>>>()?;
+
+    Ok(json!({
+        "time_lock": rad_request.time_lock,
+        "retrieve": retrieve,
+        "aggregate": render_stage(
+            &rad_request.aggregate.filters,
+            rad_request.aggregate.reducer,
+            &format!("{}.aggregate", path),
+        )?,
+        "tally": render_stage(
+            &rad_request.tally.filters,
+            rad_request.tally.reducer,
+            &format!("{}.tally", path),
+        )?,
+    }))
+}
+
+fn render_retrieval(retrieve: &RADRetrieve, path: &str) -> app::Result<JsonValue> {
+    Ok(json!({
+        "kind": retrieve.kind,
+        "url": retrieve.url,
+        "script": render_script(&retrieve.script, &format!("{}.script", path))?,
+        "body": String::from_utf8_lossy(&retrieve.body),
+        "headers": retrieve.headers,
+    }))
+}
+
+/// Render a serialized RADON script as a list of operator names and their arguments
+fn render_script(script: &[u8], path: &str) -> app::Result<JsonValue> {
+    if script.is_empty() {
+        return Ok(json!([]));
+    }
+
+    let calls = match serde_cbor::from_slice::<CborValue>(script) {
+        Ok(CborValue::Array(calls)) => calls,
+        Ok(_) => {
+            return Err(decode_error(
+                path,
+                None,
+                "a RADON script must be an array of calls",
+            ))
+        }
+        Err(e) => return Err(decode_error(path, Some(e.offset()), e)),
+    };
+
+    calls
+        .iter()
+        .enumerate()
+        .map(|(i, call)| {
+            let (operator, args) = unpack_radon_call(call)
+                .map_err(|e| decode_error(&format!("{}[{}]", path, i), None, e))?;
+
+            Ok(json!({
+                "operator": operator.to_string(),
+                "args": args.map(|args| args.iter().map(render_cbor).collect::<Vec<_>>()),
+            }))
+        })
+        .collect()
+}
+
+/// Render the filters and the reducer of the aggregation or the tally stage
+fn render_stage(filters: &[RADFilter], reducer: u32, path: &str) -> app::Result<JsonValue> {
+    let filters = filters
+        .iter()
+        .enumerate()
+        .map(|(i, filter)| {
+            let path = format!("{}.filters[{}]", path, i);
+            let name = u8::try_from(filter.op)
+                .ok()
+                .and_then(|op| RadonFilters::try_from(op).ok())
+                .ok_or_else(|| {
+                    decode_error(
+                        &path,
+                        None,
+                        RadError::UnknownFilter {
+                            code: filter.op.into(),
+                        },
+                    )
+                })?;
+            let args = if filter.args.is_empty() {
+                JsonValue::Null
+            } else {
+                serde_cbor::from_slice::<CborValue>(&filter.args)
+                    .map(|args| render_cbor(&args))
+                    .map_err(|e| decode_error(&format!("{}.args", path), Some(e.offset()), e))?
+            };
+
+            Ok(json!({ "filter": format!("{:?}", name), "args": args }))
+        })
+        .collect::<app::Result<Vec<_>>>()?;
+    let reducer = u8::try_from(reducer)
+        .ok()
+        .and_then(|op| RadonReducers::try_from(op).ok())
+        .ok_or_else(|| {
+            decode_error(
+                &format!("{}.reducer", path),
+                None,
+                RadError::UnknownReducer {
+                    code: reducer.into(),
+                },
+            )
+        })?;
+
+    Ok(json!({ "filters": filters, "reducer": format!("{:?}", reducer) }))
+}
+
+/// Render a CBOR value as JSON. Byte strings are rendered in hexadecimal.
+fn render_cbor(value: &CborValue) -> JsonValue {
+    match value {
+        CborValue::Null => JsonValue::Null,
+        CborValue::Bool(boolean) => JsonValue::from(*boolean),
+        CborValue::Integer(integer) => i64::try_from(*integer)
+            .map(JsonValue::from)
+            .unwrap_or_else(|_| JsonValue::from(integer.to_string())),
+        CborValue::Float(float) => JsonValue::from(*float),
+        CborValue::Bytes(bytes) => JsonValue::from(hex::encode(bytes)),
+        CborValue::Text(text) => JsonValue::from(text.clone()),
+        CborValue::Array(values) => values.iter().map(render_cbor).collect(),
+        CborValue::Map(map) => JsonValue::Object(
+            map.iter()
+                .map(|(key, value)| {
+                    let key = match key {
+                        CborValue::Text(text) => text.clone(),
+                        other => render_cbor(other).to_string(),
+                    };
+
+                    (key, render_cbor(value))
+                })
+                .collect(),
+        ),
+        CborValue::Tag(_, value) => render_cbor(value),
+        _ => JsonValue::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use witnet_data_structures::{
+        chain::{
+            BlockHeader, BlockMerkleRoots, BlockTransactions, Hash, KeyedSignature, OutputPointer,
+            PublicKeyHash, RADAggregate, RADTally, RADType,
+        },
+        transaction::{
+            DRTransaction, DRTransactionBody, MintTransaction, VTTransaction, VTTransactionBody,
+        },
+    };
+    use witnet_rad::cbor_to_vec;
+
+    use super::*;
+
+    const ADDRESS: &str = "wit1qqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqqwrt3a4";
+
+    fn output(value: u64, time_lock: u64) -> ValueTransferOutput {
+        ValueTransferOutput {
+            pkh: PublicKeyHash::default(),
+            value,
+            time_lock,
+        }
+    }
+
+    fn input() -> Input {
+        Input::new(OutputPointer {
+            transaction_id: Hash::SHA256([1; 32]),
+            output_index: 2,
+        })
+    }
+
+    fn cbor(value: CborValue) -> Vec<u8> {
+        cbor_to_vec(&value).unwrap()
+    }
+
+    fn text(text: &str) -> CborValue {
+        CborValue::Text(text.to_string())
+    }
+
+    /// Retrieve the USD price of a coin, average it and take the mode of the reveals
+    fn rad_request() -> RADRequest {
+        RADRequest {
+            time_lock: 0,
+            retrieve: vec![RADRetrieve {
+                kind: RADType::HttpGet,
+                url: "https://api.example.com/price".to_string(),
+                script: cbor(CborValue::Array(vec![
+                    CborValue::Integer(0x77),
+                    CborValue::Array(vec![CborValue::Integer(0x64), text("usd")]),
+                ])),
+                body: vec![],
+                headers: vec![("Accept".to_string(), "application/json".to_string())],
+            }],
+            aggregate: RADAggregate {
+                filters: vec![RADFilter {
+                    op: 0x05,
+                    args: cbor(CborValue::Float(1.5)),
+                }],
+                reducer: 0x03,
+            },
+            tally: RADTally {
+                filters: vec![RADFilter {
+                    op: 0x08,
+                    args: vec![],
+                }],
+                reducer: 0x02,
+            },
+        }
+    }
+
+    fn rad_request_json() -> JsonValue {
+        json!({
+            "time_lock": 0,
+            "retrieve": [{
+                "kind": "HTTP-GET",
+                "url": "https://api.example.com/price",
+                "script": [
+                    { "operator": "StringParseJSONMap", "args": null },
+                    { "operator": "MapGetFloat", "args": ["usd"] },
+                ],
+                "body": "",
+                "headers": [["Accept", "application/json"]],
+            }],
+            "aggregate": {
+                "filters": [{ "filter": "DeviationStandard", "args": 1.5 }],
+                "reducer": "AverageMean",
+            },
+            "tally": {
+                "filters": [{ "filter": "Mode", "args": null }],
+                "reducer": "Mode",
+            },
+        })
+    }
+
+    fn decode_error_parts(result: app::Result<JsonValue>) -> (String, Option<u64>) {
+        match result {
+            Err(app::Error::Decode { path, offset, .. }) => (path, offset),
+            other => panic!("Expected a decoding error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn decode_value_transfer_transaction() {
+        let transaction = Transaction::from(VTTransaction::new(
+            VTTransactionBody::new(vec![input()], vec![output(10, 0), output(5, 1_700_000_000)]),
+            vec![],
+        ));
+        let payload = hex::encode(transaction.to_pb_bytes().unwrap());
+
+        let decoded = decode(&payload, PayloadKind::Transaction, Environment::Mainnet).unwrap();
+
+        assert_eq!(
+            decoded,
+            json!({
+                "type": "value_transfer",
+                "hash": transaction.hash().to_string(),
+                "inputs": [{ "output_pointer": format!("{}:2", "01".repeat(32)) }],
+                "outputs": [
+                    { "address": ADDRESS, "value": 10, "time_lock": 0 },
+                    { "address": ADDRESS, "value": 5, "time_lock": 1_700_000_000 },
+                ],
+            })
+        );
+        // The same payload with a 0x prefix
+        assert_eq!(
+            decode(
+                &format!("0x{}", payload),
+                PayloadKind::Transaction,
+                Environment::Mainnet
+            )
+            .unwrap(),
+            decoded
+        );
+    }
+
+    #[test]
+    fn decode_data_request_transaction() {
+        let dr_output = DataRequestOutput {
+            data_request: rad_request(),
+            witness_reward: 1_000,
+            witnesses: 3,
+            commit_and_reveal_fee: 10,
+            min_consensus_percentage: 51,
+            collateral: 1_000_000_000,
+        };
+        let transaction = Transaction::from(DRTransaction::new(
+            DRTransactionBody::new(vec![input()], vec![], dr_output),
+            vec![],
+        ));
+        let payload = base64::encode(transaction.to_pb_bytes().unwrap());
+
+        let decoded = decode(&payload, PayloadKind::Transaction, Environment::Mainnet).unwrap();
+
+        assert_eq!(
+            decoded,
+            json!({
+                "type": "data_request",
+                "hash": transaction.hash().to_string(),
+                "inputs": [{ "output_pointer": format!("{}:2", "01".repeat(32)) }],
+                "outputs": [],
+                "data_request": {
+                    "witnesses": 3,
+                    "witness_reward": 1_000,
+                    "commit_and_reveal_fee": 10,
+                    "min_consensus_percentage": 51,
+                    "collateral": 1_000_000_000,
+                    "rad_request": rad_request_json(),
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn decode_block() {
+        let txns = BlockTransactions {
+            mint: MintTransaction::new(7, vec![output(250, 0)]),
+            ..BlockTransactions::default()
+        };
+        let block_header = BlockHeader {
+            merkle_roots: BlockMerkleRoots::from_transactions(&txns),
+            ..BlockHeader::default()
+        };
+        let block = Block::new(block_header, KeyedSignature::default(), txns);
+        let payload = hex::encode(block.to_pb_bytes().unwrap());
+
+        let decoded = decode(&payload, PayloadKind::Block, Environment::Mainnet).unwrap();
+
+        assert_eq!(
+            decoded,
+            json!({
+                "hash": block.hash().to_string(),
+                "epoch": 0,
+                "previous_block": "0".repeat(64),
+                "miner": block.block_sig.public_key.pkh().bech32(Environment::Mainnet),
+                "transactions": {
+                    "mint": {
+                        "type": "mint",
+                        "hash": block.txns.mint.hash().to_string(),
+                        "epoch": 7,
+                        "outputs": [{ "address": ADDRESS, "value": 250, "time_lock": 0 }],
+                    },
+                    "value_transfer": [],
+                    "data_request": [],
+                    "commit": [],
+                    "reveal": [],
+                    "tally": [],
+                },
+            })
+        );
+    }
+
+    #[test]
+    fn decode_rad_request() {
+        let payload = hex::encode(rad_request().to_pb_bytes().unwrap());
+
+        assert_eq!(
+            decode(&payload, PayloadKind::RadRequest, Environment::Mainnet).unwrap(),
+            rad_request_json()
+        );
+    }
+
+    #[test]
+    fn decode_addresses_for_environment() {
+        let transaction = Transaction::from(MintTransaction::new(1, vec![output(1, 0)]));
+        let payload = hex::encode(transaction.to_pb_bytes().unwrap());
+
+        let decoded = decode(&payload, PayloadKind::Transaction, Environment::Testnet).unwrap();
+
+        assert_eq!(
+            decoded["outputs"][0]["address"],
+            PublicKeyHash::default().bech32(Environment::Testnet)
+        );
+    }
+
+    #[test]
+    fn malformed_payload() {
+        // Odd number of hexadecimal digits
+        let result = decode("abc", PayloadKind::Transaction, Environment::Mainnet);
+        assert_eq!(decode_error_parts(result), ("payload".to_string(), None));
+
+        // Invalid base64 character
+        let result = decode("aGVsbG8*", PayloadKind::Transaction, Environment::Mainnet);
+        assert_eq!(decode_error_parts(result), ("payload".to_string(), Some(7)));
+
+        // Not a transaction
+        let result = decode("ffffffff", PayloadKind::Transaction, Environment::Mainnet);
+        assert_eq!(
+            decode_error_parts(result),
+            ("transaction".to_string(), None)
+        );
+    }
+
+    #[test]
+    fn malformed_script() {
+        // Truncated CBOR
+        let mut request = rad_request();
+        request.retrieve[0].script = vec![0x82, 0x18];
+        let payload = hex::encode(request.to_pb_bytes().unwrap());
+        let (path, offset) = decode_error_parts(decode(
+            &payload,
+            PayloadKind::RadRequest,
+            Environment::Mainnet,
+        ));
+        assert_eq!(path, "rad_request.retrieve[0].script");
+        assert!(offset.is_some());
+
+        // Unknown operator in the second call
+        request.retrieve[0].script = cbor(CborValue::Array(vec![
+            CborValue::Integer(0x77),
+            CborValue::Integer(0xFE),
+        ]));
+        let payload = hex::encode(request.to_pb_bytes().unwrap());
+        let result = decode(&payload, PayloadKind::RadRequest, Environment::Mainnet);
+        assert_eq!(
+            decode_error_parts(result),
+            ("rad_request.retrieve[0].script[1]".to_string(), None)
+        );
+
+        // Unknown reducer, inside a data request transaction
+        let mut request = rad_request();
+        request.tally.reducer = 0xFE;
+        let transaction = Transaction::from(DRTransaction::new(
+            DRTransactionBody::new(
+                vec![],
+                vec![],
+                DataRequestOutput {
+                    data_request: request,
+                    ..DataRequestOutput::default()
+                },
+            ),
+            vec![],
+        ));
+        let payload = hex::encode(transaction.to_pb_bytes().unwrap());
+        let result = decode(&payload, PayloadKind::Transaction, Environment::Mainnet);
+        assert_eq!(
+            decode_error_parts(result),
+            (
+                "transaction.data_request.rad_request.tally.reducer".to_string(),
+                None
+            )
+        );
+    }
+}
@@ -2,12 +2,12 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
-use crate::types;
+use crate::{model, types};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct DeleteWalletRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -0,0 +1,169 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, constants, model, types};
+use futures_util::FutureExt;
+use witnet_crypto::key::KeyPath;
+
+/// Maximum amount of addresses that can be derived in a single request.
+pub const MAX_DERIVED_ADDRESSES: u32 = 1000;
+
+/// Derive Addresses request, where either a list of explicit key paths or a range of indices
+/// under the wallet account path has to be provided
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeriveAddressesRequest {
+    seed_source: String,
+    seed_data: types::Password,
+    /// only needed if seed_source is xprv
+    backup_password: Option<types::Password>,
+    paths: Option<Vec<String>>,
+    range: Option<DerivationRange>,
+}
+
+/// Range of addresses under the `m/purpose'/coin_type'/account'/keychain/index` path, where the
+/// purpose and the coin type are the ones that new wallets are derived with
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DerivationRange {
+    #[serde(default)]
+    pub account: u32,
+    #[serde(default)]
+    pub keychain: u32,
+    #[serde(default)]
+    pub start: u32,
+    pub count: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DeriveAddressesResponse {
+    pub addresses: Vec<model::DerivedAddress>,
+}
+
+impl Message for DeriveAddressesRequest {
+    type Result = app::Result<DeriveAddressesResponse>;
+}
+
+impl Handler<DeriveAddressesRequest> for app::App {
+    type Result = app::ResponseActFuture<DeriveAddressesResponse>;
+
+    fn handle(&mut self, req: DeriveAddressesRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let validated = if self.params.enable_derive_addresses {
+            validate_derive_addresses(req, &self.params.key_derivation)
+        } else {
+            Err(app::Error::MethodDisabled("derive_addresses"))
+        };
+
+        let f = fut::result(validated).and_then(|(seed_source, paths), slf: &mut Self, _ctx| {
+            slf.derive_addresses(seed_source, paths)
+                .map(|res| res.map(|addresses| DeriveAddressesResponse { addresses }))
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+}
+
+/// Validate `DeriveAddressesRequest`.
+///
+/// To be valid it must pass these checks:
+/// - seed source and data must be valid, and double xprv keys are not supported
+/// - exactly one of `paths` or `range` must be given
+/// - no more than `MAX_DERIVED_ADDRESSES` addresses can be requested
+/// - range keychain must be either external (0) or internal (1)
+/// - range account and indices must be non-hardened values
+pub fn validate_derive_addresses(
+    req: DeriveAddressesRequest,
+    key_derivation: &model::KeyDerivation,
+) -> Result<(types::SeedSource, Vec<KeyPath>), app::Error> {
+    let seed_source =
+        app::methods::validate_seed_source(&req.seed_source, req.seed_data, req.backup_password)
+            .and_then(|seed_source| match seed_source {
+                types::SeedSource::XprvDouble(_) => Err(app::Error::Validation(app::field_error(
+                    "seed_data",
+                    "Double xprv keys are not supported.",
+                ))),
+                seed_source => Ok(seed_source),
+            });
+    let paths =
+        validate_paths(req.paths, req.range, key_derivation).map_err(app::Error::Validation);
+
+    match (seed_source, paths) {
+        (Ok(seed_source), Ok(paths)) => Ok((seed_source, paths)),
+        (Err(app::Error::Validation(mut err1)), Err(app::Error::Validation(err2))) => {
+            err1.extend(err2);
+            Err(app::Error::Validation(err1))
+        }
+        (Err(err), _) | (_, Err(err)) => Err(err),
+    }
+}
+
+/// Turn either the explicit paths or the range of a request into a list of key paths.
+fn validate_paths(
+    paths: Option<Vec<String>>,
+    range: Option<DerivationRange>,
+    key_derivation: &model::KeyDerivation,
+) -> Result<Vec<KeyPath>, app::ValidationErrors> {
+    match (paths, range) {
+        (Some(paths), None) => {
+            if paths.is_empty() {
+                return Err(app::field_error("paths", "At least one path is required."));
+            }
+            if paths.len() > MAX_DERIVED_ADDRESSES as usize {
+                return Err(app::field_error(
+                    "paths",
+                    format!("No more than {} paths are allowed.", MAX_DERIVED_ADDRESSES),
+                ));
+            }
+            paths
+                .iter()
+                .map(|path| {
+                    path.parse::<KeyPath>().map_err(|err| {
+                        app::field_error("paths", format!("Invalid path {}: {}", path, err))
+                    })
+                })
+                .collect()
+        }
+        (None, Some(range)) => {
+            if range.count == 0 || range.count > MAX_DERIVED_ADDRESSES {
+                return Err(app::field_error(
+                    "range.count",
+                    format!("Count must be between 1 and {}.", MAX_DERIVED_ADDRESSES),
+                ));
+            }
+            if range.keychain != constants::EXTERNAL_KEYCHAIN
+                && range.keychain != constants::INTERNAL_KEYCHAIN
+            {
+                return Err(app::field_error(
+                    "range.keychain",
+                    "Keychain must be either 0 (external) or 1 (internal).",
+                ));
+            }
+            if range.account >= KeyPath::HARDENED_KEY_INDEX {
+                return Err(app::field_error(
+                    "range.account",
+                    "Account index is out of range.",
+                ));
+            }
+            match range.start.checked_add(range.count) {
+                Some(end) if end <= KeyPath::HARDENED_KEY_INDEX => {}
+                _ => {
+                    return Err(app::field_error(
+                        "range.start",
+                        "Address index is out of range.",
+                    ))
+                }
+            }
+
+            let keychain_path = key_derivation
+                .account_keypath(range.account)
+                .index(range.keychain);
+
+            Ok((range.start..range.start + range.count)
+                .map(|index| keychain_path.clone().index(index))
+                .collect())
+        }
+        _ => Err(app::field_error(
+            "paths",
+            "Exactly one of paths or range must be provided.",
+        )),
+    }
+}
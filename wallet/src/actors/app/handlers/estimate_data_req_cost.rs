@@ -0,0 +1,74 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use witnet_data_structures::chain::DataRequestOutput;
+use witnet_rad::cost::{estimate_cost, CostBreakdown, InputSizes};
+
+use crate::{
+    actors::app,
+    types::{from_generic_type, into_generic_type, DataRequestOutputHelper},
+};
+
+/// Request to estimate how costly it is for witnesses to execute the scripts of a data request.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EstimateDataReqCostRequest {
+    #[serde(
+        serialize_with = "into_generic_type::<_, DataRequestOutputHelper, _>",
+        deserialize_with = "from_generic_type::<_, DataRequestOutputHelper, _>"
+    )]
+    pub request: DataRequestOutput,
+    /// Assumed bytes of the response of every data source
+    pub response_bytes: Option<u64>,
+    /// Assumed items of every array or map that the scripts operate on
+    pub collection_items: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct EstimateDataReqCostResponse {
+    pub cost: CostBreakdown,
+    /// Sizes assumed by the estimation. The tally stage is applied to a reveal per witness
+    pub sizes: InputSizes,
+    /// Total cost above which the request is reported as expensive
+    pub warning_threshold: u64,
+    /// Why the request is expensive, if its total cost exceeds `warning_threshold`
+    pub warning: Option<String>,
+}
+
+impl Message for EstimateDataReqCostRequest {
+    type Result = app::Result<EstimateDataReqCostResponse>;
+}
+
+impl Handler<EstimateDataReqCostRequest> for app::App {
+    type Result = <EstimateDataReqCostRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        msg: EstimateDataReqCostRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let defaults = InputSizes::default();
+        let sizes = InputSizes {
+            response_bytes: msg.response_bytes.unwrap_or(defaults.response_bytes),
+            collection_items: msg.collection_items.unwrap_or(defaults.collection_items),
+            reveals: u64::from(msg.request.witnesses),
+        };
+        let cost = estimate_cost(&msg.request.data_request, &sizes)
+            .map_err(|err| app::validation_error(app::field_error("request", err.to_string())))?;
+
+        let warning_threshold = self.params.data_request_cost_warning_threshold;
+        let warning = (cost.total > warning_threshold).then(|| {
+            format!(
+                "The scripts of this data request are expensive for witnesses to execute: their \
+                 estimated cost of {} exceeds {}",
+                cost.total, warning_threshold
+            )
+        });
+
+        Ok(EstimateDataReqCostResponse {
+            cost,
+            sizes,
+            warning_threshold,
+            warning,
+        })
+    }
+}
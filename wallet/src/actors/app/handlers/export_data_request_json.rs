@@ -0,0 +1,30 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use witnet_data_structures::chain::RADRequest;
+use witnet_rad::request_json::{self, RADRequestJson};
+
+/// Request to convert a data request into its canonical JSON representation, the one that
+/// `import_data_request_json` takes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ExportDataRequestJsonRequest {
+    pub data_request: RADRequest,
+}
+
+impl Message for ExportDataRequestJsonRequest {
+    type Result = app::Result<RADRequestJson>;
+}
+
+impl Handler<ExportDataRequestJsonRequest> for app::App {
+    type Result = <ExportDataRequestJsonRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        msg: ExportDataRequestJsonRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        request_json::to_json(&msg.data_request)
+            .map_err(|err| app::validation_error(app::field_error("data_request", err.to_string())))
+    }
+}
@@ -1,11 +1,11 @@
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::{actors::app, types};
+use crate::{actors::app, model, types};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ExportMasterKeyRequest {
-    pub wallet_id: String,
+    pub wallet_id: model::WalletId,
     pub password: types::Password,
     pub session_id: types::SessionId,
 }
@@ -0,0 +1,33 @@
+use actix::prelude::*;
+
+use crate::actors::app;
+
+/// Record the outcome of a request made with an idempotency key, and wake up its retries.
+pub struct FinishIdempotentRequest {
+    /// Key under which the request is recorded in its wallet
+    pub record_key: String,
+    pub outcome: IdempotentOutcome,
+}
+
+/// How a request made with an idempotency key ended.
+#[derive(Debug)]
+pub enum IdempotentOutcome {
+    /// The request was answered with this response, which answers its retries.
+    Answered(serde_json::Value),
+    /// The request failed, so its retries run it again.
+    Failed,
+    /// The request was dropped before being answered, so it may or may not have taken effect.
+    Interrupted,
+}
+
+impl Message for FinishIdempotentRequest {
+    type Result = app::Result<()>;
+}
+
+impl Handler<FinishIdempotentRequest> for app::App {
+    type Result = app::ResponseActFuture<()>;
+
+    fn handle(&mut self, msg: FinishIdempotentRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.finish_idempotent_request(msg.record_key, msg.outcome)
+    }
+}
@@ -6,7 +6,7 @@ use crate::{actors::app, model, types};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GenerateAddressRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
     external: Option<bool>,
     label: Option<String>,
 }
@@ -15,6 +15,10 @@ pub struct GenerateAddressRequest {
 pub struct GenerateAddressResponse {
     pub address: String,
     pub path: String,
+    /// Present if the address is beyond the gap limit, so that wallets recovering from the seed
+    /// could miss payments to it
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<model::AddressGapWarning>,
 }
 
 impl Message for GenerateAddressRequest {
@@ -32,12 +36,13 @@ impl Handler<GenerateAddressRequest> for app::App {
                 msg.external.unwrap_or(true),
                 msg.label,
             )
-            .map_ok(
-                |model::Address { address, path, .. }, _, _| GenerateAddressResponse {
+            .map_ok(|(model::Address { address, path, .. }, warning), _, _| {
+                GenerateAddressResponse {
                     address,
                     path,
-                },
-            );
+                    warning,
+                }
+            });
 
         Box::pin(f)
     }
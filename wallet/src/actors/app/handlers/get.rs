@@ -1,12 +1,12 @@
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::{actors::app, types};
+use crate::{actors::app, model, types};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
     key: String,
 }
 
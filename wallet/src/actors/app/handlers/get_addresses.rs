@@ -7,10 +7,19 @@ use crate::{constants, model, types};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetAddressesRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
     offset: Option<u32>,
     limit: Option<u32>,
     external: Option<bool>,
+    /// What the addresses are sorted by, their derivation index by default
+    #[serde(default)]
+    sort: model::AddressesSort,
+    /// Order of the addresses, descending by default
+    #[serde(default)]
+    order: model::SortOrder,
+    /// Cursor returned with the previous page, to go on from its last address. The offset is
+    /// counted from there.
+    cursor: Option<model::AddressesCursor>,
 }
 
 pub type GetAddressesResponse = model::Addresses;
@@ -26,7 +35,16 @@ impl Handler<GetAddressesRequest> for app::App {
         let offset = msg.offset.unwrap_or(constants::DEFAULT_PAGINATION_OFFSET);
         let limit = msg.limit.unwrap_or(constants::DEFAULT_PAGINATION_LIMIT);
         let external = msg.external.unwrap_or(true);
-        let f = self.get_addresses(msg.session_id, msg.wallet_id, offset, limit, external);
+        let f = self.get_addresses(
+            msg.session_id,
+            msg.wallet_id,
+            offset,
+            limit,
+            external,
+            msg.sort,
+            msg.order,
+            msg.cursor,
+        );
 
         Box::pin(f)
     }
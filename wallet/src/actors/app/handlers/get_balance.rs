@@ -7,7 +7,7 @@ use crate::{model, types};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetBalanceRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
 }
 
 pub type GetBalanceResponse = model::WalletBalance;
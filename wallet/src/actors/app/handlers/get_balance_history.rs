@@ -0,0 +1,39 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetBalanceHistoryRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    /// First epoch of the history, from the oldest entry if not given
+    from: Option<u32>,
+    /// Last epoch of the history, up to the newest entry if not given
+    to: Option<u32>,
+    #[serde(default)]
+    resolution: model::BalanceHistoryResolution,
+}
+
+pub type GetBalanceHistoryResponse = Vec<model::BalanceHistoryEntry>;
+
+impl Message for GetBalanceHistoryRequest {
+    type Result = app::Result<GetBalanceHistoryResponse>;
+}
+
+impl Handler<GetBalanceHistoryRequest> for app::App {
+    type Result = app::ResponseActFuture<GetBalanceHistoryResponse>;
+
+    fn handle(&mut self, msg: GetBalanceHistoryRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self.get_balance_history(
+            msg.session_id,
+            msg.wallet_id,
+            msg.from,
+            msg.to,
+            msg.resolution,
+        );
+
+        Box::pin(f)
+    }
+}
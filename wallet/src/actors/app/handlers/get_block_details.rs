@@ -0,0 +1,87 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value as JsonValue};
+use witnet_data_structures::chain::OutputPointer;
+
+use crate::{actors::app, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetBlockDetailsRequest {
+    /// Session whose unlocked wallets annotate the block, if any.
+    pub session_id: Option<types::SessionId>,
+    pub block_hash: String,
+}
+
+impl Message for GetBlockDetailsRequest {
+    type Result = app::Result<JsonValue>;
+}
+
+impl Handler<GetBlockDetailsRequest> for app::App {
+    type Result = app::ResponseActFuture<JsonValue>;
+
+    fn handle(&mut self, msg: GetBlockDetailsRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.get_block_details(msg.session_id, msg.block_hash)
+    }
+}
+
+/// Add the ids of the wallets that own the inputs, outputs and transactions of a block rendered by
+/// `render_block` to them, as an `own_wallets` field.
+pub fn annotate_block(
+    block: &mut JsonValue,
+    own_entries: &[(model::WalletId, HashSet<OutputPointer>)],
+) {
+    let mut owners: HashMap<String, Vec<model::WalletId>> = HashMap::new();
+    for (wallet_id, pointers) in own_entries {
+        for pointer in pointers {
+            owners
+                .entry(pointer.to_string())
+                .or_default()
+                .push(wallet_id.clone());
+        }
+    }
+
+    if let Some(JsonValue::Object(transactions)) = block.get_mut("transactions") {
+        for group in transactions.values_mut() {
+            match group {
+                JsonValue::Array(transactions) => transactions
+                    .iter_mut()
+                    .for_each(|transaction| annotate_transaction(transaction, &owners)),
+                mint => annotate_transaction(mint, &owners),
+            }
+        }
+    }
+}
+
+fn annotate_transaction(
+    transaction: &mut JsonValue,
+    owners: &HashMap<String, Vec<model::WalletId>>,
+) {
+    let hash = transaction["hash"].as_str().unwrap_or_default().to_string();
+    let mut transaction_owners = BTreeSet::new();
+
+    for field in ["inputs", "collateral"] {
+        if let Some(JsonValue::Array(inputs)) = transaction.get_mut(field) {
+            for input in inputs {
+                let pointer = input["output_pointer"].as_str().unwrap_or_default();
+                if let Some(wallets) = owners.get(pointer) {
+                    transaction_owners.extend(wallets.iter().cloned());
+                    input["own_wallets"] = json!(wallets);
+                }
+            }
+        }
+    }
+    if let Some(JsonValue::Array(outputs)) = transaction.get_mut("outputs") {
+        for (index, output) in outputs.iter_mut().enumerate() {
+            if let Some(wallets) = owners.get(&format!("{}:{}", hash, index)) {
+                transaction_owners.extend(wallets.iter().cloned());
+                output["own_wallets"] = json!(wallets);
+            }
+        }
+    }
+
+    if !transaction_owners.is_empty() {
+        transaction["own_wallets"] = json!(transaction_owners);
+    }
+}
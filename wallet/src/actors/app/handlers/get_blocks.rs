@@ -0,0 +1,39 @@
+use std::collections::BTreeMap;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetBlocksRequest {
+    /// Session whose unlocked wallets annotate the blocks, if any.
+    pub session_id: Option<types::SessionId>,
+    /// Range of the chain to list, as in the `getBlockChain` method of the node.
+    #[serde(flatten)]
+    pub range: types::GetBlockChainParams,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct BlockSummary {
+    pub epoch: u32,
+    pub hash: String,
+    /// Hashes of the transactions of each unlocked wallet of the session that the block includes,
+    /// by wallet id. Only present if a session with unlocked wallets was given.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub own_transactions: Option<BTreeMap<model::WalletId, Vec<String>>>,
+}
+
+pub type GetBlocksResponse = Vec<BlockSummary>;
+
+impl Message for GetBlocksRequest {
+    type Result = app::Result<GetBlocksResponse>;
+}
+
+impl Handler<GetBlocksRequest> for app::App {
+    type Result = app::ResponseActFuture<GetBlocksResponse>;
+
+    fn handle(&mut self, msg: GetBlocksRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.get_blocks(msg.session_id, msg.range)
+    }
+}
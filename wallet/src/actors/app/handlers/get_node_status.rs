@@ -0,0 +1,19 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetNodeStatusRequest;
+
+impl Message for GetNodeStatusRequest {
+    type Result = app::Result<types::NodeStatus>;
+}
+
+impl Handler<GetNodeStatusRequest> for app::App {
+    type Result = <GetNodeStatusRequest as Message>::Result;
+
+    fn handle(&mut self, _msg: GetNodeStatusRequest, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(self.get_node_status())
+    }
+}
@@ -0,0 +1,26 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use witnet_rad::operators::catalog::{operator_catalog, OperatorSpec};
+
+use crate::actors::app;
+
+/// Request for the description of every RADON operator, as used by request editors.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetOperatorCatalogRequest;
+
+impl Message for GetOperatorCatalogRequest {
+    type Result = app::Result<Vec<OperatorSpec>>;
+}
+
+impl Handler<GetOperatorCatalogRequest> for app::App {
+    type Result = <GetOperatorCatalogRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        _msg: GetOperatorCatalogRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        Ok(operator_catalog())
+    }
+}
@@ -0,0 +1,41 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, sink, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetSubscriptionStatsRequest {
+    pub session_id: types::SessionId,
+}
+
+/// Lag counters of the notifications subscription of a session, if there is one
+#[derive(Debug, Serialize)]
+pub struct GetSubscriptionStatsResponse {
+    pub subscribed: bool,
+    pub overflow_policy: Option<sink::OverflowPolicy>,
+    pub queue_size: usize,
+    pub stats: Option<sink::SinkStats>,
+}
+
+impl Message for GetSubscriptionStatsRequest {
+    type Result = app::Result<GetSubscriptionStatsResponse>;
+}
+
+impl Handler<GetSubscriptionStatsRequest> for app::App {
+    type Result = <GetSubscriptionStatsRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        msg: GetSubscriptionStatsRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let stats = self.get_subscription_stats(&msg.session_id)?;
+
+        Ok(GetSubscriptionStatsResponse {
+            subscribed: stats.is_some(),
+            overflow_policy: stats.as_ref().map(|(policy, _)| *policy),
+            queue_size: self.params.notifications_queue_size,
+            stats: stats.map(|(_, stats)| stats),
+        })
+    }
+}
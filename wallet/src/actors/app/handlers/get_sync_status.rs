@@ -0,0 +1,24 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetSyncStatusRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+}
+
+pub type GetSyncStatusResponse = model::SyncWatermark;
+
+impl Message for GetSyncStatusRequest {
+    type Result = app::Result<GetSyncStatusResponse>;
+}
+
+impl Handler<GetSyncStatusRequest> for app::App {
+    type Result = app::ResponseActFuture<GetSyncStatusResponse>;
+
+    fn handle(&mut self, msg: GetSyncStatusRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.get_sync_status(msg.session_id, msg.wallet_id)
+    }
+}
@@ -7,9 +7,18 @@ use crate::{constants, model, types};
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GetTransactionsRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
     offset: Option<u32>,
     limit: Option<u32>,
+    /// What the movements are sorted by, their id by default
+    #[serde(default)]
+    sort: model::MovementsSort,
+    /// Order of the movements, descending by default
+    #[serde(default)]
+    order: model::SortOrder,
+    /// Cursor returned with the previous page, to go on from its last movement. The offset is
+    /// counted from there.
+    cursor: Option<model::MovementsCursor>,
 }
 
 pub type GetTransactionsResponse = model::WalletTransactions;
@@ -24,7 +33,15 @@ impl Handler<GetTransactionsRequest> for app::App {
     fn handle(&mut self, msg: GetTransactionsRequest, _ctx: &mut Self::Context) -> Self::Result {
         let offset = msg.offset.unwrap_or(constants::DEFAULT_PAGINATION_OFFSET);
         let limit = msg.limit.unwrap_or(constants::DEFAULT_PAGINATION_LIMIT);
-        let f = self.get_transactions(msg.session_id, msg.wallet_id, offset, limit);
+        let f = self.get_transactions(
+            msg.session_id,
+            msg.wallet_id,
+            offset,
+            limit,
+            msg.sort,
+            msg.order,
+            msg.cursor,
+        );
 
         Box::pin(f)
     }
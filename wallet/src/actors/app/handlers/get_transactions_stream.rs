@@ -0,0 +1,47 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{constants, model, types};
+
+/// Request for the next batch of the stream of the persisted movements of a wallet.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetTransactionsStreamRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    /// Cursor returned with the previous batch, or none to start from the oldest movement
+    cursor: Option<model::TransactionsCursor>,
+    batch_size: Option<u32>,
+}
+
+pub type GetTransactionsStreamResponse = model::TransactionsBatch;
+
+impl Message for GetTransactionsStreamRequest {
+    type Result = app::Result<GetTransactionsStreamResponse>;
+}
+
+impl Handler<GetTransactionsStreamRequest> for app::App {
+    type Result = app::ResponseActFuture<GetTransactionsStreamResponse>;
+
+    fn handle(
+        &mut self,
+        msg: GetTransactionsStreamRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let batch_size = msg
+            .batch_size
+            .unwrap_or(constants::DEFAULT_TRANSACTIONS_BATCH_SIZE);
+        if batch_size == 0 || batch_size > constants::MAX_TRANSACTIONS_BATCH_SIZE {
+            return Box::pin(fut::err(app::validation_error(app::field_error(
+                "batch_size",
+                format!(
+                    "Batch size must be between 1 and {}",
+                    constants::MAX_TRANSACTIONS_BATCH_SIZE
+                ),
+            ))));
+        }
+        let f = self.get_transactions_stream(msg.session_id, msg.wallet_id, msg.cursor, batch_size);
+
+        Box::pin(f)
+    }
+}
@@ -7,7 +7,7 @@ use std::collections::HashMap;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UtxoInfoRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
 }
 
 pub type UtxoInfoResponse = HashMap<String, model::OutputInfo>;
@@ -0,0 +1,50 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, api_version};
+
+/// Request for the versions of the wallet and of its API, which needs no session so that clients
+/// can check their compatibility before unlocking a wallet.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetVersionRequest;
+
+#[derive(Debug, Serialize)]
+pub struct GetVersionResponse {
+    /// Version of the wallet software
+    pub version: String,
+    /// Version of the JSON-RPC API
+    pub api_version: u32,
+    /// Oldest API version that clients can declare when unlocking a wallet
+    pub min_client_api_version: u32,
+    /// Optional features that are enabled in the configuration of the wallet
+    pub features: Vec<String>,
+}
+
+impl Message for GetVersionRequest {
+    type Result = app::Result<GetVersionResponse>;
+}
+
+impl Handler<GetVersionRequest> for app::App {
+    type Result = <GetVersionRequest as Message>::Result;
+
+    fn handle(&mut self, _msg: GetVersionRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let params = &self.params;
+        let features = [
+            ("derive_addresses", params.enable_derive_addresses),
+            ("http_transport", params.http_server_addr.is_some()),
+            ("idle_lock", params.idle_lock_after.is_some()),
+            ("pruning", params.prune_interval.is_some()),
+        ]
+        .iter()
+        .filter(|(_, enabled)| *enabled)
+        .map(|(feature, _)| feature.to_string())
+        .collect();
+
+        Ok(GetVersionResponse {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            api_version: api_version::API_VERSION,
+            min_client_api_version: params.min_client_api_version,
+            features,
+        })
+    }
+}
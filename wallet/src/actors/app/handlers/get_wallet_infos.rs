@@ -1,18 +1,24 @@
 use actix::prelude::*;
-use futures::FutureExt;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
-use crate::model;
+use crate::{constants, model};
 
+/// Get Wallet Infos request, whose params are optional
 #[derive(Debug, Serialize, Deserialize)]
-pub struct WalletInfosRequest;
+pub struct WalletInfosRequest(pub Option<WalletInfosParams>);
 
-#[derive(Debug, Serialize)]
-pub struct WalletInfosResponse {
-    pub infos: Vec<model::Wallet>,
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct WalletInfosParams {
+    /// Also list wallets waiting for their seed backup to be confirmed
+    #[serde(default)]
+    pub include_pending: bool,
+    pub offset: Option<u32>,
+    pub limit: Option<u32>,
 }
 
+pub type WalletInfosResponse = model::WalletInfos;
+
 impl Message for WalletInfosRequest {
     type Result = app::Result<WalletInfosResponse>;
 }
@@ -20,10 +26,13 @@ impl Message for WalletInfosRequest {
 impl Handler<WalletInfosRequest> for app::App {
     type Result = app::ResponseFuture<WalletInfosResponse>;
 
-    fn handle(&mut self, _msg: WalletInfosRequest, _ctx: &mut Self::Context) -> Self::Result {
-        let f = self
-            .wallet_infos()
-            .map(|res| res.map(|infos| WalletInfosResponse { infos }));
+    fn handle(&mut self, msg: WalletInfosRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let params = msg.0.unwrap_or_default();
+        let offset = params
+            .offset
+            .unwrap_or(constants::DEFAULT_PAGINATION_OFFSET);
+        let limit = params.limit.unwrap_or(constants::DEFAULT_PAGINATION_LIMIT);
+        let f = self.wallet_infos(params.include_pending, offset, limit);
 
         Box::pin(f)
     }
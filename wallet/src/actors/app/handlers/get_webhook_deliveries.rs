@@ -0,0 +1,31 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+/// Request to list the deliveries to the webhook of a wallet that are pending or dead-lettered.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GetWebhookDeliveriesRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+}
+
+pub type GetWebhookDeliveriesResponse = model::WebhookDeliveries;
+
+impl Message for GetWebhookDeliveriesRequest {
+    type Result = app::Result<GetWebhookDeliveriesResponse>;
+}
+
+impl Handler<GetWebhookDeliveriesRequest> for app::App {
+    type Result = app::ResponseActFuture<GetWebhookDeliveriesResponse>;
+
+    fn handle(
+        &mut self,
+        msg: GetWebhookDeliveriesRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let f = self.webhook_deliveries(&msg.session_id, msg.wallet_id);
+
+        Box::pin(f)
+    }
+}
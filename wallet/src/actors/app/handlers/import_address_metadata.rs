@@ -0,0 +1,32 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, address_metadata::ImportPayload, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportAddressMetadataRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    /// Either a list of entries or CSV text with a header naming the columns
+    payload: ImportPayload,
+}
+
+pub type ImportAddressMetadataResponse = model::AddressMetadataImport;
+
+impl Message for ImportAddressMetadataRequest {
+    type Result = app::Result<ImportAddressMetadataResponse>;
+}
+
+impl Handler<ImportAddressMetadataRequest> for app::App {
+    type Result = app::ResponseActFuture<ImportAddressMetadataResponse>;
+
+    fn handle(
+        &mut self,
+        msg: ImportAddressMetadataRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let f = self.import_address_metadata(msg.session_id, msg.wallet_id, msg.payload);
+
+        Box::pin(f)
+    }
+}
@@ -0,0 +1,30 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use witnet_data_structures::chain::RADRequest;
+use witnet_rad::request_json::{self, RADRequestJson};
+
+/// Request to convert a data request from its canonical JSON representation. The response can be
+/// used as is in `create_data_request` and `run_rad_request`.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ImportDataRequestJsonRequest {
+    pub data_request: RADRequestJson,
+}
+
+impl Message for ImportDataRequestJsonRequest {
+    type Result = app::Result<RADRequest>;
+}
+
+impl Handler<ImportDataRequestJsonRequest> for app::App {
+    type Result = <ImportDataRequestJsonRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        msg: ImportDataRequestJsonRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        request_json::from_json(&msg.data_request)
+            .map_err(|err| app::validation_error(app::field_error("data_request", err.to_string())))
+    }
+}
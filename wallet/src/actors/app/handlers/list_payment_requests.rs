@@ -0,0 +1,43 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::{constants, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ListPaymentRequestsRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    /// Only list the requests with these statuses
+    status: Option<Vec<model::PaymentRequestStatus>>,
+    offset: Option<u32>,
+    limit: Option<u32>,
+}
+
+pub type ListPaymentRequestsResponse = model::PaymentRequests;
+
+impl Message for ListPaymentRequestsRequest {
+    type Result = app::Result<ListPaymentRequestsResponse>;
+}
+
+impl Handler<ListPaymentRequestsRequest> for app::App {
+    type Result = app::ResponseActFuture<ListPaymentRequestsResponse>;
+
+    fn handle(
+        &mut self,
+        msg: ListPaymentRequestsRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let offset = msg.offset.unwrap_or(constants::DEFAULT_PAGINATION_OFFSET);
+        let limit = msg.limit.unwrap_or(constants::DEFAULT_PAGINATION_LIMIT);
+        let f = self.list_payment_requests(
+            msg.session_id,
+            msg.wallet_id,
+            msg.status.unwrap_or_default(),
+            offset,
+            limit,
+        );
+
+        Box::pin(f)
+    }
+}
@@ -0,0 +1,34 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::actors::app;
+use crate::types;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockAllWalletsRequest {
+    #[serde(default)]
+    session_id: Option<types::SessionId>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct LockAllWalletsResponse {
+    pub success: bool,
+    /// How many wallets were unlocked before the request
+    pub locked_wallets: usize,
+}
+
+impl Message for LockAllWalletsRequest {
+    type Result = app::Result<LockAllWalletsResponse>;
+}
+
+impl Handler<LockAllWalletsRequest> for app::App {
+    type Result = <LockAllWalletsRequest as Message>::Result;
+
+    fn handle(&mut self, msg: LockAllWalletsRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.lock_all_wallets(msg.session_id)
+            .map(|locked_wallets| LockAllWalletsResponse {
+                success: true,
+                locked_wallets,
+            })
+    }
+}
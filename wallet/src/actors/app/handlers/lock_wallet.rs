@@ -2,11 +2,11 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
-use crate::types;
+use crate::{model, types};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct LockWalletRequest {
-    wallet_id: String,
+    wallet_id: model::WalletId,
     session_id: types::SessionId,
 }
 
@@ -1,60 +1,148 @@
+mod admit_request;
+mod authorize;
+mod begin_idempotent_request;
+mod benchmark_retrievals;
+mod broadcast_raw_transaction;
+mod bump_fee;
+mod check_integrity;
+mod classify_reveals;
 mod close_session;
+mod confirm_seed_backup;
+mod confirm_send;
 mod create_data_req;
+mod create_diagnostics_bundle;
 mod create_mnemonics;
+mod create_payment_request;
 mod create_vtt;
 mod create_wallet;
+mod decode;
 mod delete_wallet;
+mod derive_addresses;
+mod estimate_data_req_cost;
+mod export_data_request_json;
 mod export_master_key;
+mod finish_idempotent_request;
 mod forward;
 mod generate_address;
 mod get;
 mod get_addresses;
 mod get_balance;
+mod get_balance_history;
+mod get_block_details;
+mod get_blocks;
+mod get_node_status;
+mod get_operator_catalog;
+mod get_subscription_stats;
+mod get_sync_status;
 mod get_transactions;
+mod get_transactions_stream;
 mod get_utxo_info;
+mod get_version;
 mod get_wallet_infos;
+mod get_webhook_deliveries;
+mod import_address_metadata;
+mod import_data_request_json;
+mod list_payment_requests;
+mod lock_all_wallets;
 mod lock_wallet;
 mod next_subscription_id;
 mod node_notification;
+mod pin_sync;
+mod prune_wallet_data;
+mod record_request;
 mod refresh_session;
+mod requeue_webhook_deliveries;
 mod resync;
 mod run_rad_req;
 mod send_transaction;
 mod set;
 mod shutdown;
 mod sign_data;
+mod sign_witness_transaction;
+mod simulate_data_request;
 mod subscribe;
+mod switch_node;
+mod unlock_service_session;
 mod unlock_wallet;
 mod unsubscribe;
 mod update_wallet;
+mod update_wallet_policy;
+mod update_wallet_webhook;
+mod update_witness_signing_policy;
+mod validate_address;
 mod validate_mnemonics;
+mod verify_data;
 
+pub use admit_request::*;
+pub use authorize::*;
+pub use begin_idempotent_request::*;
+pub use benchmark_retrievals::*;
+pub use broadcast_raw_transaction::*;
+pub use bump_fee::*;
+pub use check_integrity::*;
+pub use classify_reveals::*;
 pub use close_session::*;
+pub use confirm_seed_backup::*;
+pub use confirm_send::*;
 pub use create_data_req::*;
+pub use create_diagnostics_bundle::*;
 pub use create_mnemonics::*;
+pub use create_payment_request::*;
 pub use create_vtt::*;
 pub use create_wallet::*;
+pub use decode::*;
 pub use delete_wallet::*;
+pub use derive_addresses::*;
+pub use estimate_data_req_cost::*;
+pub use export_data_request_json::*;
 pub use export_master_key::*;
+pub use finish_idempotent_request::*;
 pub use forward::*;
 pub use generate_address::*;
 pub use get::*;
 pub use get_addresses::*;
 pub use get_balance::*;
+pub use get_balance_history::*;
+pub use get_block_details::*;
+pub use get_blocks::*;
+pub use get_node_status::*;
+pub use get_operator_catalog::*;
+pub use get_subscription_stats::*;
+pub use get_sync_status::*;
 pub use get_transactions::*;
+pub use get_transactions_stream::*;
 pub use get_utxo_info::*;
+pub use get_version::*;
 pub use get_wallet_infos::*;
+pub use get_webhook_deliveries::*;
+pub use import_address_metadata::*;
+pub use import_data_request_json::*;
+pub use list_payment_requests::*;
+pub use lock_all_wallets::*;
 pub use lock_wallet::*;
 pub use next_subscription_id::*;
+pub use pin_sync::*;
+pub use prune_wallet_data::*;
+pub use record_request::*;
 pub use refresh_session::*;
+pub use requeue_webhook_deliveries::*;
 pub use resync::*;
 pub use run_rad_req::*;
 pub use send_transaction::*;
 pub use set::*;
 pub use shutdown::*;
 pub use sign_data::*;
+pub use sign_witness_transaction::*;
+pub use simulate_data_request::*;
 pub use subscribe::*;
+pub use switch_node::*;
+pub use unlock_service_session::*;
 pub use unlock_wallet::*;
 pub use unsubscribe::*;
 pub use update_wallet::*;
+pub use update_wallet_policy::*;
+pub use update_wallet_webhook::*;
+pub use update_witness_signing_policy::*;
+pub use validate_address::*;
 pub use validate_mnemonics::*;
+pub use verify_data::*;
@@ -10,8 +10,8 @@ impl Handler<jsonrpc::NotifySubscriptionTopic> for app::App {
     fn handle(
         &mut self,
         jsonrpc::NotifySubscriptionTopic { topic, value }: jsonrpc::NotifySubscriptionTopic,
-        _ctx: &mut Self::Context,
+        ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.handle_notification(topic, value).ok();
+        self.handle_notification(topic, value, ctx).ok();
     }
 }
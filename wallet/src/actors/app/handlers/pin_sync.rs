@@ -0,0 +1,28 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PinSyncRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    /// Epoch beyond which blocks are not processed, or `null` to unpin the synchronization and
+    /// catch up with the chain
+    #[serde(default)]
+    epoch: Option<u32>,
+}
+
+pub type PinSyncResponse = model::SyncWatermark;
+
+impl Message for PinSyncRequest {
+    type Result = app::Result<PinSyncResponse>;
+}
+
+impl Handler<PinSyncRequest> for app::App {
+    type Result = app::ResponseActFuture<PinSyncResponse>;
+
+    fn handle(&mut self, msg: PinSyncRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.pin_sync(msg.session_id, msg.wallet_id, msg.epoch)
+    }
+}
@@ -0,0 +1,29 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PruneWalletDataRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    /// Replace the retention policy of the wallet before pruning it
+    #[serde(default)]
+    retention_policy: Option<model::RetentionPolicy>,
+}
+
+pub type PruneWalletDataResponse = model::PruneReport;
+
+impl Message for PruneWalletDataRequest {
+    type Result = app::Result<PruneWalletDataResponse>;
+}
+
+impl Handler<PruneWalletDataRequest> for app::App {
+    type Result = app::ResponseActFuture<PruneWalletDataResponse>;
+
+    fn handle(&mut self, msg: PruneWalletDataRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let f = self.prune_wallet_data(msg.session_id, msg.wallet_id, msg.retention_policy);
+
+        Box::pin(f)
+    }
+}
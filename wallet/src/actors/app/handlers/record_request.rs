@@ -0,0 +1,19 @@
+use actix::prelude::*;
+
+use crate::actors::app;
+use crate::metrics;
+
+/// Record a request handled by the JSON-RPC server, once it has been answered.
+pub struct RecordRequest(pub metrics::RequestLogEntry);
+
+impl Message for RecordRequest {
+    type Result = ();
+}
+
+impl Handler<RecordRequest> for app::App {
+    type Result = <RecordRequest as Message>::Result;
+
+    fn handle(&mut self, msg: RecordRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.record_request(msg.0)
+    }
+}
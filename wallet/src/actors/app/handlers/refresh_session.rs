@@ -1,5 +1,6 @@
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
+use witnet_util::timestamp::get_timestamp;
 
 use crate::{actors::app, types};
 
@@ -26,6 +27,10 @@ impl Handler<RefreshSessionRequest> for app::App {
             .sessions
             .get_mut(&msg.session_id)
             .ok_or(app::Error::SessionNotFound)?;
+        session.last_activity = get_timestamp();
+        self.state
+            .last_authenticated_activity
+            .set(session.last_activity);
 
         if !session.session_extended {
             session.session_extended = true;
@@ -0,0 +1,40 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+/// Request to retry the dead-lettered deliveries to the webhook of a wallet that are in `ids`, or
+/// all of them if it is missing.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RequeueWebhookDeliveriesRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    #[serde(default)]
+    ids: Option<Vec<u64>>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct RequeueWebhookDeliveriesResponse {
+    /// Dead letters moved back to the outbox. Those that did not fit in it are still dead letters
+    pub requeued: usize,
+}
+
+impl Message for RequeueWebhookDeliveriesRequest {
+    type Result = app::Result<RequeueWebhookDeliveriesResponse>;
+}
+
+impl Handler<RequeueWebhookDeliveriesRequest> for app::App {
+    type Result = app::ResponseActFuture<RequeueWebhookDeliveriesResponse>;
+
+    fn handle(
+        &mut self,
+        msg: RequeueWebhookDeliveriesRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let f = self
+            .requeue_webhook_deliveries(&msg.session_id, msg.wallet_id, msg.ids)
+            .map_ok(|requeued, _, _| RequeueWebhookDeliveriesResponse { requeued });
+
+        Box::pin(f)
+    }
+}
@@ -1,12 +1,15 @@
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
-use crate::{actors::app, types};
+use crate::{actors::app, model, types};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ResyncWalletRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
+    /// Resynchronize from the birth date of the wallet (default) or from the genesis block
+    #[serde(default)]
+    from: types::ResyncFrom,
 }
 
 #[derive(Serialize)]
@@ -26,7 +29,7 @@ impl Handler<ResyncWalletRequest> for app::App {
         // resync process is successful, and it only gets mapped to `Result<ResyncWalletRequest>`
         // here.
         let f = self
-            .clear_chain_data_and_resync(msg.session_id, msg.wallet_id)
+            .clear_chain_data_and_resync(msg.session_id, msg.wallet_id, msg.from)
             .map_ok(|success, _, _| ResyncWalletResponse { success });
 
         Box::pin(f)
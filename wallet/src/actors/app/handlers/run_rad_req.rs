@@ -2,18 +2,24 @@ use actix::prelude::*;
 use futures::FutureExt;
 use serde::{Deserialize, Serialize};
 
-use crate::actors::app;
+use crate::{actors::app, model};
 use witnet_data_structures::chain::RADRequest;
 use witnet_rad::RADRequestExecutionReport;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RunRadReqRequest {
     pub rad_request: RADRequest,
+    /// Fetch all the sources even if the retrieval cache has their responses
+    #[serde(default)]
+    pub bypass_cache: bool,
 }
 
 #[derive(Debug, Serialize)]
 pub struct RunRadReqResponse {
     pub result: RADRequestExecutionReport,
+    /// How the response of each source was obtained, in the same order as the retrieval reports.
+    /// `null` if the retrieval cache was not used.
+    pub cache: Vec<Option<model::CacheStatus>>,
 }
 
 impl Message for RunRadReqRequest {
@@ -24,10 +30,14 @@ impl Handler<RunRadReqRequest> for app::App {
     type Result = app::ResponseFuture<RunRadReqResponse>;
 
     fn handle(&mut self, msg: RunRadReqRequest, _ctx: &mut Self::Context) -> Self::Result {
-        let f = self.run_rad_request(msg.rad_request).map(|res| {
-            res.map_err(app::internal_error)
-                .map(|result| RunRadReqResponse { result })
-        });
+        let f = self
+            .run_rad_request(msg.rad_request, msg.bypass_cache)
+            .map(|res| {
+                res.map(|response| RunRadReqResponse {
+                    result: response.report,
+                    cache: response.cache,
+                })
+            });
 
         Box::pin(f)
     }
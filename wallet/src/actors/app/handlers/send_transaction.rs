@@ -11,18 +11,35 @@ use witnet_data_structures::transaction::Transaction;
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SendTransactionRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
     #[serde(
         serialize_with = "into_generic_type::<_, TransactionHelper, _>",
         deserialize_with = "from_generic_type::<_, TransactionHelper, _>"
     )]
     transaction: Transaction,
+    /// Only ask the node whether it would accept the transaction, without broadcasting it
+    #[serde(default)]
+    dry_run: bool,
 }
 
 #[derive(Debug, Serialize)]
 pub struct SendTransactionResponse {
     pub jsonrpc_result: serde_json::Value,
     pub balance_movement: Option<model::BalanceMovement>,
+    /// Present if the transaction was not sent because the spending policy of the wallet asks
+    /// for it to be confirmed with `confirm_send` first
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub requires_confirmation: Option<SendConfirmation>,
+}
+
+/// What is needed for confirming the sending of a transaction.
+#[derive(Debug, Serialize)]
+pub struct SendConfirmation {
+    /// One-time token to pass to `confirm_send`
+    pub token: String,
+    /// Seconds before the transaction is discarded if its sending is not confirmed
+    pub expires_in: u64,
+    pub spending: model::SpendingCheck,
 }
 
 impl Message for SendTransactionRequest {
@@ -33,6 +50,6 @@ impl Handler<SendTransactionRequest> for app::App {
     type Result = app::ResponseActFuture<SendTransactionResponse>;
 
     fn handle(&mut self, msg: SendTransactionRequest, _ctx: &mut Self::Context) -> Self::Result {
-        self.send_transaction(msg.session_id, msg.wallet_id, msg.transaction)
+        self.send_transaction(msg.session_id, msg.wallet_id, msg.transaction, msg.dry_run)
     }
 }
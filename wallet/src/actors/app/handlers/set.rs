@@ -2,12 +2,12 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
-use crate::types;
+use crate::{model, types};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SetRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
     key: String,
     value: jsonrpc_core::Params,
 }
@@ -4,20 +4,25 @@ use serde::{Deserialize, Serialize};
 use crate::actors::app;
 use crate::{model, types};
 
-/// Request to sign strings after being hashed with SHA256.
+/// Request to sign strings after being hashed with SHA256 together with the tag of a signing
+/// domain, so that the signature cannot be reused in other contexts.
 /// If `extended_pk` flag is set to true, extended public key will be include (`chaincode`).
 /// Extended public keys can be used to derive non-hardened child public keys.
+/// Strings can only be signed without a domain if `unsafe_raw` is explicitly set to true.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct SignDataRequest {
     session_id: types::SessionId,
-    wallet_id: String,
+    wallet_id: model::WalletId,
     // Message to be signed
     data: String,
+    domain: Option<model::SigningDomain>,
+    #[serde(default)]
+    unsafe_raw: bool,
     #[serde(default)]
     extended_pk: bool,
 }
 
-pub type SignDataResponse = model::ExtendedKeyedSignature;
+pub type SignDataResponse = model::SignedData;
 
 impl Message for SignDataRequest {
     type Result = app::Result<SignDataResponse>;
@@ -27,8 +32,45 @@ impl Handler<SignDataRequest> for app::App {
     type Result = app::ResponseActFuture<SignDataResponse>;
 
     fn handle(&mut self, msg: SignDataRequest, _ctx: &mut Self::Context) -> Self::Result {
-        let f = self.sign_data(&msg.session_id, &msg.wallet_id, msg.data, msg.extended_pk);
+        let domain = match signing_domain(msg.domain, msg.unsafe_raw) {
+            Ok(domain) => domain,
+            Err(err) => return Box::pin(fut::result(Err(err))),
+        };
+        if domain.is_none() {
+            log::warn!(
+                "Signing data without a domain with the master key of wallet {}",
+                msg.wallet_id
+            );
+        }
+
+        let f = self.sign_data(
+            &msg.session_id,
+            &msg.wallet_id,
+            msg.data,
+            domain,
+            msg.extended_pk,
+        );
 
         Box::pin(f)
     }
 }
+
+/// Check that data is signed in a domain, unless it is explicitly signed raw.
+pub fn signing_domain(
+    domain: Option<model::SigningDomain>,
+    unsafe_raw: bool,
+) -> app::Result<Option<model::SigningDomain>> {
+    match (domain, unsafe_raw) {
+        (Some(domain), false) => Ok(Some(domain)),
+        (None, true) => Ok(None),
+        (Some(_), true) => Err(app::field_error(
+            "unsafe_raw",
+            "Data signed raw cannot have a domain",
+        )),
+        (None, false) => Err(app::field_error(
+            "domain",
+            "A signing domain is required unless unsafe_raw is set",
+        )),
+    }
+    .map_err(app::validation_error)
+}
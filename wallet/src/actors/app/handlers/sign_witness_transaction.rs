@@ -0,0 +1,65 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+use witnet_data_structures::chain::KeyedSignature;
+
+use crate::{
+    actors::app,
+    model, types,
+    witness_signing::{PayloadError, WitnessTransactionBody},
+};
+
+/// Request of a node to sign one of its commits or reveals with the keys of a wallet. The payload
+/// is `{ "type": "commit" | "reveal", "body": { ... } }`, where the body is that of the
+/// transaction.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignWitnessTransactionRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    payload: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SignWitnessTransactionResponse {
+    /// Signature of the hash of the body, made with the key of the address it is for
+    pub signature: KeyedSignature,
+    /// Record of the signature in the audit log of the wallet
+    pub audit: model::WitnessSigningAuditEntry,
+}
+
+impl Message for SignWitnessTransactionRequest {
+    type Result = app::Result<SignWitnessTransactionResponse>;
+}
+
+impl Handler<SignWitnessTransactionRequest> for app::App {
+    type Result = app::ResponseActFuture<SignWitnessTransactionResponse>;
+
+    fn handle(
+        &mut self,
+        msg: SignWitnessTransactionRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let body = match WitnessTransactionBody::from_payload(&msg.payload) {
+            Ok(body) => body,
+            Err(PayloadError::MovesFunds(reason)) => {
+                log::warn!(
+                    "Refused to sign a payload for wallet {} that could move funds: {}",
+                    msg.wallet_id,
+                    reason
+                );
+
+                return Box::pin(fut::result(Err(app::Error::PayloadMovesFunds(reason))));
+            }
+            Err(err) => {
+                return Box::pin(fut::result(Err(app::validation_error(app::field_error(
+                    "payload", err,
+                )))));
+            }
+        };
+
+        let f = self
+            .sign_witness_transaction(&msg.session_id, &msg.wallet_id, body)
+            .map_ok(|(signature, audit), _, _| SignWitnessTransactionResponse { signature, audit });
+
+        Box::pin(f)
+    }
+}
@@ -0,0 +1,62 @@
+use actix::prelude::*;
+use futures::FutureExt;
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    actors::app,
+    model, simulation,
+    types::{from_generic_type, into_generic_type, DataRequestOutputHelper},
+};
+use witnet_data_structures::chain::DataRequestOutput;
+use witnet_rad::RADRequestExecutionReport;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SimulateDataRequestRequest {
+    #[serde(
+        serialize_with = "into_generic_type::<_, DataRequestOutputHelper, _>",
+        deserialize_with = "from_generic_type::<_, DataRequestOutputHelper, _>"
+    )]
+    pub request: DataRequestOutput,
+    /// Values revealed by the dishonest witnesses. The rest of the witnesses reveal the result of
+    /// running the data request locally.
+    #[serde(default)]
+    pub lies: Vec<simulation::Lie>,
+    /// Fetch all the sources even if the retrieval cache has their responses
+    #[serde(default)]
+    pub bypass_cache: bool,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimulateDataRequestResponse {
+    pub result: RADRequestExecutionReport,
+    /// How the response of each source was obtained, in the same order as the retrieval reports.
+    /// `null` if the retrieval cache was not used.
+    pub cache: Vec<Option<model::CacheStatus>>,
+    pub simulation: simulation::TallySimulation,
+}
+
+impl Message for SimulateDataRequestRequest {
+    type Result = app::Result<SimulateDataRequestResponse>;
+}
+
+impl Handler<SimulateDataRequestRequest> for app::App {
+    type Result = app::ResponseFuture<SimulateDataRequestResponse>;
+
+    fn handle(
+        &mut self,
+        msg: SimulateDataRequestRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let f = self
+            .simulate_data_request(msg.request, msg.lies, msg.bypass_cache)
+            .map(|res| {
+                res.map(|response| SimulateDataRequestResponse {
+                    result: response.report,
+                    cache: response.cache,
+                    simulation: response.simulation,
+                })
+            });
+
+        Box::pin(f)
+    }
+}
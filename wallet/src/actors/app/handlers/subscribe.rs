@@ -1,18 +1,32 @@
+use std::sync::Weak;
+
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
-use crate::types;
+use crate::{sink, types};
 
 #[derive(Serialize, Deserialize)]
 pub struct SubscribeRequest {
     pub session_id: types::SessionId,
+    /// What to do with notifications when the client is not reading them fast enough
+    #[serde(default)]
+    pub overflow_policy: sink::OverflowPolicy,
+    /// Id of the last event received by a client resuming a previous subscription, so that the
+    /// events that came after it are replayed
+    #[serde(default)]
+    pub last_event_id: Option<u64>,
 }
 
+/// Attach the transport of a client to the notifications of a session. It is usually the sink of
+/// a WebSocket subscription, made through the connection that is given along with it.
 pub struct Subscribe(
     pub types::SessionId,
     pub jsonrpc_pubsub::SubscriptionId,
-    pub jsonrpc_pubsub::Sink,
+    pub Box<dyn sink::Transport>,
+    pub sink::OverflowPolicy,
+    pub Option<u64>,
+    pub Option<Weak<jsonrpc_pubsub::Session>>,
 );
 
 impl Message for Subscribe {
@@ -24,18 +38,34 @@ impl Handler<Subscribe> for app::App {
 
     fn handle(
         &mut self,
-        Subscribe(session_id, subscription_id, sink): Subscribe,
+        Subscribe(
+            session_id,
+            subscription_id,
+            sink,
+            overflow_policy,
+            last_event_id,
+            connection,
+        ): Subscribe,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.subscribe(session_id.clone(), subscription_id, sink)
-            .map(|()| log::debug!("Created subscription for session: {}", session_id))
-            .map_err(|err| {
-                log::error!(
-                    "Couldn't create subscription for session {}: {}",
-                    session_id,
-                    err
-                );
+        self.subscribe(
+            subscription_id,
+            app::state::Subscription {
+                session_id: session_id.clone(),
+                connection,
+            },
+            sink,
+            overflow_policy,
+            last_event_id,
+        )
+        .map(|()| log::debug!("Created subscription for session: {}", session_id))
+        .map_err(|err| {
+            log::error!(
+                "Couldn't create subscription for session {}: {}",
+                session_id,
                 err
-            })
+            );
+            err
+        })
     }
 }
@@ -0,0 +1,22 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, types};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SwitchNodeRequest {
+    pub session_id: types::SessionId,
+    pub url: String,
+}
+
+impl Message for SwitchNodeRequest {
+    type Result = app::Result<types::NodeStatus>;
+}
+
+impl Handler<SwitchNodeRequest> for app::App {
+    type Result = app::ResponseActFuture<types::NodeStatus>;
+
+    fn handle(&mut self, msg: SwitchNodeRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.switch_node(msg.session_id, msg.url)
+    }
+}
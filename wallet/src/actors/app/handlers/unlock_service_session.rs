@@ -0,0 +1,22 @@
+use actix::prelude::*;
+
+use crate::{actors::app, credentials, types};
+
+/// Unlock the wallets of the credentials file in the service session, when the wallet starts.
+pub struct UnlockServiceSession {
+    pub credentials: credentials::Credentials,
+    /// Key that decrypts the encrypted passwords of the credentials file.
+    pub key: Option<types::Password>,
+}
+
+impl Message for UnlockServiceSession {
+    type Result = ();
+}
+
+impl Handler<UnlockServiceSession> for app::App {
+    type Result = ();
+
+    fn handle(&mut self, msg: UnlockServiceSession, ctx: &mut Self::Context) -> Self::Result {
+        self.unlock_service_session(msg.credentials, msg.key, ctx)
+    }
+}
@@ -1,12 +1,25 @@
 use actix::prelude::*;
 use serde::{Deserialize, Serialize};
+use witnet_crypto::key::KeySpec;
 
-use crate::{actors::app, model, types};
+use crate::{actors::app, api_version, model, types};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct UnlockWalletRequest {
-    pub wallet_id: String,
+    pub wallet_id: model::WalletId,
     pub password: types::Password,
+    /// Unlock the wallet in this existing session instead of opening a new one.
+    #[serde(default)]
+    pub session_id: Option<types::SessionId>,
+    /// Scopes of the methods that the session can call. New sessions get all but
+    /// `witness_signing` if omitted, and existing sessions get these scopes in addition to the ones
+    /// they had.
+    #[serde(default)]
+    pub scopes: Option<Vec<types::Scope>>,
+    /// Version of the API that the client was written for. Clients declaring an older version than
+    /// the current one get a session in compatibility mode.
+    #[serde(default)]
+    pub client_api_version: Option<u32>,
 }
 
 #[derive(Serialize)]
@@ -19,6 +32,12 @@ pub struct UnlockWalletResponse {
     account_balance: model::WalletBalance,
     session_expiration_secs: u64,
     birth_date: types::Epoch,
+    scopes: Vec<types::Scope>,
+    key_spec: KeySpec,
+    base_path: String,
+    /// Whether the responses in the session leave out the fields that are newer than the API
+    /// version declared by the client.
+    compatibility_mode: bool,
 }
 
 impl Message for UnlockWalletRequest {
@@ -29,24 +48,44 @@ impl Handler<UnlockWalletRequest> for app::App {
     type Result = app::ResponseActFuture<UnlockWalletResponse>;
 
     fn handle(&mut self, msg: UnlockWalletRequest, _ctx: &mut Self::Context) -> Self::Result {
-        let f = self.unlock_wallet(msg.wallet_id, msg.password).map_ok(
-            |types::UnlockedWallet { data, session_id }, slf, ctx| {
-                slf.set_session_to_expire(session_id.clone())
-                    .expect("Session id should exist after wallet unlock")
-                    .spawn(ctx);
-
-                UnlockWalletResponse {
-                    session_id,
-                    name: data.name,
-                    description: data.description,
-                    current_account: data.current_account,
-                    available_accounts: data.available_accounts,
-                    account_balance: data.balance,
-                    session_expiration_secs: slf.params.session_expires_in.as_secs(),
-                    birth_date: data.birth_date.checkpoint,
-                }
-            },
-        );
+        let reused_session = msg.session_id.is_some();
+        let f = self
+            .unlock_wallet(
+                msg.wallet_id,
+                msg.password,
+                msg.session_id,
+                msg.scopes,
+                msg.client_api_version,
+            )
+            .map_ok(
+                move |types::UnlockedWallet { data, session_id }, slf, ctx| {
+                    // A reused session already has an expiration timer
+                    if !reused_session {
+                        slf.set_session_to_expire(session_id.clone())
+                            .expect("Session id should exist after wallet unlock")
+                            .spawn(ctx);
+                    }
+                    let scopes = slf.state.session_scopes(&session_id);
+                    let compatibility_mode = api_version::is_compatibility_mode(
+                        slf.state.client_api_version(&session_id),
+                    );
+
+                    UnlockWalletResponse {
+                        session_id,
+                        name: data.name,
+                        description: data.description,
+                        current_account: data.current_account,
+                        available_accounts: data.available_accounts,
+                        account_balance: data.balance,
+                        session_expiration_secs: slf.params.session_expires_in.as_secs(),
+                        birth_date: data.birth_date.checkpoint,
+                        scopes,
+                        key_spec: data.key_derivation.key_spec,
+                        base_path: data.key_derivation.base_path().to_string(),
+                        compatibility_mode,
+                    }
+                },
+            );
 
         Box::pin(f)
     }
@@ -1,8 +1,14 @@
+use std::sync::Arc;
+
 use actix::prelude::*;
 
 use crate::actors::app;
 
-pub struct UnsubscribeRequest(pub jsonrpc_pubsub::SubscriptionId);
+/// Remove a subscription, on behalf of the WebSocket connection that asks for it, if any.
+pub struct UnsubscribeRequest(
+    pub jsonrpc_pubsub::SubscriptionId,
+    pub Option<Arc<jsonrpc_pubsub::Session>>,
+);
 
 impl Message for UnsubscribeRequest {
     type Result = app::Result<()>;
@@ -13,10 +19,10 @@ impl Handler<UnsubscribeRequest> for app::App {
 
     fn handle(
         &mut self,
-        UnsubscribeRequest(id): UnsubscribeRequest,
+        UnsubscribeRequest(id, connection): UnsubscribeRequest,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.unsubscribe(&id)
+        self.unsubscribe(&id, connection)
             .map(|()| log::debug!("Subscription {:?} removed", id))
     }
 }
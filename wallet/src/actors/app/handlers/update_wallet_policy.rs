@@ -0,0 +1,36 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+/// Request to replace the spending policy of a wallet. Policies can only be changed with the
+/// password of the wallet, even from a session where it is unlocked.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWalletPolicyRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    password: types::Password,
+    policy: model::WalletPolicy,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateWalletPolicyResponse {
+    pub policy: model::WalletPolicy,
+}
+
+impl Message for UpdateWalletPolicyRequest {
+    type Result = app::Result<UpdateWalletPolicyResponse>;
+}
+
+impl Handler<UpdateWalletPolicyRequest> for app::App {
+    type Result = app::ResponseActFuture<UpdateWalletPolicyResponse>;
+
+    fn handle(&mut self, msg: UpdateWalletPolicyRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let policy = msg.policy.clone();
+        let f = self
+            .update_wallet_policy(&msg.session_id, msg.wallet_id, msg.password, msg.policy)
+            .map_ok(move |(), _, _| UpdateWalletPolicyResponse { policy });
+
+        Box::pin(f)
+    }
+}
@@ -0,0 +1,50 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+/// Request to set the endpoint that the events of a wallet are posted to, or to stop posting them
+/// if `webhook` is `null`. Like the spending policy, it can only be changed with the password of
+/// the wallet.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWalletWebhookRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    password: types::Password,
+    webhook: Option<model::WebhookConfig>,
+}
+
+/// The secret of the webhook is never sent back.
+#[derive(Debug, Serialize)]
+pub struct UpdateWalletWebhookResponse {
+    pub url: Option<String>,
+    pub topics: Vec<model::WebhookTopic>,
+}
+
+impl Message for UpdateWalletWebhookRequest {
+    type Result = app::Result<UpdateWalletWebhookResponse>;
+}
+
+impl Handler<UpdateWalletWebhookRequest> for app::App {
+    type Result = app::ResponseActFuture<UpdateWalletWebhookResponse>;
+
+    fn handle(
+        &mut self,
+        msg: UpdateWalletWebhookRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let response = UpdateWalletWebhookResponse {
+            url: msg.webhook.as_ref().map(|webhook| webhook.url.clone()),
+            topics: msg
+                .webhook
+                .as_ref()
+                .map(|webhook| webhook.topics.clone())
+                .unwrap_or_default(),
+        };
+        let f = self
+            .update_wallet_webhook(&msg.session_id, msg.wallet_id, msg.password, msg.webhook)
+            .map_ok(move |(), _, _| response);
+
+        Box::pin(f)
+    }
+}
@@ -0,0 +1,40 @@
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{actors::app, model, types};
+
+/// Request to replace which transactions nodes can have a wallet sign on their behalf. Like the
+/// spending policy, it can only be changed with the password of the wallet.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct UpdateWitnessSigningPolicyRequest {
+    session_id: types::SessionId,
+    wallet_id: model::WalletId,
+    password: types::Password,
+    policy: model::WitnessSigningPolicy,
+}
+
+#[derive(Debug, Serialize)]
+pub struct UpdateWitnessSigningPolicyResponse {
+    pub policy: model::WitnessSigningPolicy,
+}
+
+impl Message for UpdateWitnessSigningPolicyRequest {
+    type Result = app::Result<UpdateWitnessSigningPolicyResponse>;
+}
+
+impl Handler<UpdateWitnessSigningPolicyRequest> for app::App {
+    type Result = app::ResponseActFuture<UpdateWitnessSigningPolicyResponse>;
+
+    fn handle(
+        &mut self,
+        msg: UpdateWitnessSigningPolicyRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let policy = msg.policy.clone();
+        let f = self
+            .update_witness_signing_policy(&msg.session_id, msg.wallet_id, msg.password, msg.policy)
+            .map_ok(move |(), _, _| UpdateWitnessSigningPolicyResponse { policy });
+
+        Box::pin(f)
+    }
+}
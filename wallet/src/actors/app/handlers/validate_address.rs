@@ -0,0 +1,238 @@
+use std::fmt;
+
+use actix::prelude::*;
+use bech32::FromBase32;
+use serde::{Deserialize, Serialize};
+use witnet_data_structures::chain::{Environment, PublicKeyHash};
+
+use crate::{actors::app, model, types};
+
+/// Characters used by Bech32 for the data part of addresses.
+const BECH32_CHARSET: &str = "qpzry9x8gf2tvdw0s3jn54khce6mua7l";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ValidateAddressRequest {
+    pub address: String,
+    /// Session whose unlocked wallets are checked for the address, if any.
+    pub session_id: Option<types::SessionId>,
+    /// Ask the node whether the address has appeared on chain.
+    #[serde(default)]
+    pub check_chain: bool,
+}
+
+/// Why an address cannot be used as the recipient of a transaction.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum AddressError {
+    /// The address is not valid Bech32, or does not encode a public key hash.
+    Malformed { reason: String },
+    /// The checksum does not match the rest of the address. `position` is the index of the
+    /// character that is most likely mistyped, if replacing a single character fixes the checksum.
+    Checksum { position: Option<usize> },
+    /// The prefix is not the one of any Witnet environment.
+    UnknownPrefix { prefix: String },
+    /// The address belongs to an environment other than the one of the wallet.
+    WrongEnvironment { environment: Environment },
+}
+
+impl fmt::Display for AddressError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AddressError::Malformed { reason } => write!(f, "malformed address: {}", reason),
+            AddressError::Checksum {
+                position: Some(position),
+            } => write!(
+                f,
+                "invalid checksum, the character at position {} is probably mistyped",
+                position
+            ),
+            AddressError::Checksum { position: None } => write!(f, "invalid checksum"),
+            AddressError::UnknownPrefix { prefix } => write!(f, "unknown prefix \"{}\"", prefix),
+            AddressError::WrongEnvironment { environment } => {
+                write!(f, "the address belongs to {}", environment)
+            }
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+pub struct ValidateAddressResponse {
+    /// Whether the address can be used as the recipient of a transaction.
+    pub valid: bool,
+    pub checksum_valid: bool,
+    /// Environment the address belongs to, according to its prefix.
+    pub environment: Option<Environment>,
+    pub error: Option<AddressError>,
+    /// Ids of the wallets unlocked in the session that generated the address, which means that
+    /// sending to it is sending to yourself.
+    pub own_wallets: Vec<model::WalletId>,
+    /// Heuristic hint about whether the address has ever appeared on chain, only present if it
+    /// was asked for and a node is connected. It tells whether the address currently holds funds,
+    /// so addresses that spent all of them appear as never seen.
+    pub seen_on_chain_hint: Option<bool>,
+    #[serde(skip)]
+    pub pkh: Option<PublicKeyHash>,
+}
+
+impl Message for ValidateAddressRequest {
+    type Result = app::Result<ValidateAddressResponse>;
+}
+
+impl Handler<ValidateAddressRequest> for app::App {
+    type Result = app::ResponseActFuture<ValidateAddressResponse>;
+
+    fn handle(&mut self, msg: ValidateAddressRequest, _ctx: &mut Self::Context) -> Self::Result {
+        self.validate_address(msg.address, msg.session_id, msg.check_chain)
+    }
+}
+
+fn environment_from_prefix(prefix: &str) -> Option<Environment> {
+    match prefix {
+        "wit" => Some(Environment::Mainnet),
+        "twit" => Some(Environment::Testnet),
+        _ => None,
+    }
+}
+
+/// Position of the only character of the data part of a Bech32 string whose replacement makes
+/// its checksum valid, if there is exactly one such character.
+fn locate_typo(address: &str) -> Option<usize> {
+    let mut chars: Vec<char> = address.to_lowercase().chars().collect();
+    let separator = chars.iter().rposition(|c| *c == '1')?;
+
+    let mut fixable = vec![];
+    for position in separator + 1..chars.len() {
+        let original = chars[position];
+        let fixes = BECH32_CHARSET.chars().filter(|c| *c != original).any(|c| {
+            chars[position] = c;
+            bech32::decode(&chars.iter().collect::<String>()).is_ok()
+        });
+        chars[position] = original;
+        if fixes {
+            fixable.push(position);
+        }
+    }
+
+    match fixable[..] {
+        [position] => Some(position),
+        _ => None,
+    }
+}
+
+/// Check that an address is well formed and belongs to the environment of the wallet.
+///
+/// Only the fields that can be told from the address itself are filled in.
+pub fn inspect_address(testnet: bool, address: &str) -> ValidateAddressResponse {
+    let expected_environment = if testnet {
+        Environment::Testnet
+    } else {
+        Environment::Mainnet
+    };
+    let prefix = address
+        .rfind('1')
+        .map(|separator| address[..separator].to_lowercase());
+    let environment = prefix.as_deref().and_then(environment_from_prefix);
+
+    let decoded = bech32::decode(address).map_err(|err| match err {
+        bech32::Error::InvalidChecksum => AddressError::Checksum {
+            position: locate_typo(address),
+        },
+        err => AddressError::Malformed {
+            reason: err.to_string(),
+        },
+    });
+    let checksum_valid = decoded.is_ok();
+    let pkh = decoded.and_then(|(prefix, data)| {
+        let bytes = Vec::from_base32(&data).map_err(|err| AddressError::Malformed {
+            reason: err.to_string(),
+        })?;
+        let pkh = PublicKeyHash::from_bytes(&bytes).map_err(|err| AddressError::Malformed {
+            reason: err.to_string(),
+        })?;
+
+        match environment_from_prefix(&prefix) {
+            Some(environment) if environment == expected_environment => Ok(pkh),
+            Some(environment) => Err(AddressError::WrongEnvironment { environment }),
+            None => Err(AddressError::UnknownPrefix { prefix }),
+        }
+    });
+
+    ValidateAddressResponse {
+        valid: pkh.is_ok(),
+        checksum_valid,
+        environment,
+        error: pkh.as_ref().err().cloned(),
+        own_wallets: vec![],
+        seen_on_chain_hint: None,
+        pkh: pkh.ok(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAINNET_ADDRESS: &str = "wit18cfejmk3305y9kw5xqa59rwnpjzahr57us48vm";
+    const TESTNET_ADDRESS: &str = "twit1adgt8t2h3xnu358f76zxlph0urf2ev7cd78ggc";
+
+    #[test]
+    fn test_inspect_valid_address() {
+        let validation = inspect_address(false, MAINNET_ADDRESS);
+
+        assert!(validation.valid);
+        assert!(validation.checksum_valid);
+        assert_eq!(validation.environment, Some(Environment::Mainnet));
+        assert_eq!(validation.error, None);
+        assert_eq!(
+            validation.pkh,
+            Some(PublicKeyHash::from_bech32(Environment::Mainnet, MAINNET_ADDRESS).unwrap())
+        );
+        assert!(validation.own_wallets.is_empty());
+        assert_eq!(validation.seen_on_chain_hint, None);
+    }
+
+    #[test]
+    fn test_inspect_address_with_typo() {
+        // The character at position 15 should be a `y`
+        let address = MAINNET_ADDRESS.replace("5y9", "5x9");
+        let validation = inspect_address(false, &address);
+
+        assert!(!validation.valid);
+        assert!(!validation.checksum_valid);
+        assert_eq!(validation.environment, Some(Environment::Mainnet));
+        assert_eq!(
+            validation.error,
+            Some(AddressError::Checksum { position: Some(15) })
+        );
+        assert_eq!(validation.pkh, None);
+    }
+
+    #[test]
+    fn test_inspect_address_from_other_environment() {
+        let validation = inspect_address(false, TESTNET_ADDRESS);
+
+        assert!(!validation.valid);
+        assert!(validation.checksum_valid);
+        assert_eq!(validation.environment, Some(Environment::Testnet));
+        assert_eq!(
+            validation.error,
+            Some(AddressError::WrongEnvironment {
+                environment: Environment::Testnet
+            })
+        );
+
+        assert!(inspect_address(true, TESTNET_ADDRESS).valid);
+    }
+
+    #[test]
+    fn test_inspect_malformed_address() {
+        let validation = inspect_address(false, "not an address");
+
+        assert!(!validation.valid);
+        assert_eq!(validation.environment, None);
+        assert!(matches!(
+            validation.error,
+            Some(AddressError::Malformed { .. })
+        ));
+    }
+}
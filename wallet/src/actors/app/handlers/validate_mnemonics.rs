@@ -2,7 +2,7 @@ use actix::prelude::*;
 use serde::{Deserialize, Serialize};
 
 use crate::actors::app;
-use crate::types;
+use crate::{model, types};
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidateMnemonicsRequest {
@@ -14,7 +14,8 @@ pub struct ValidateMnemonicsRequest {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct ValidateMnemonicsResponse {
     pub exist: bool,
-    pub wallet_id: String,
+    /// Id of the wallet already created from the seed, if any
+    pub wallet_id: Option<model::WalletId>,
 }
 
 impl Message for ValidateMnemonicsRequest {
@@ -0,0 +1,69 @@
+use std::str::FromStr;
+
+use actix::prelude::*;
+use serde::{Deserialize, Serialize};
+use witnet_crypto::signature::{verify, PublicKey, Signature};
+
+use crate::actors::app;
+use crate::model;
+
+/// Request to verify a signature made by `sign_data`. The data must be verified in the same
+/// domain it was signed in, or raw with `unsafe_raw` if it was signed raw.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct VerifyDataRequest {
+    // Message that was signed
+    data: String,
+    domain: Option<model::SigningDomain>,
+    #[serde(default)]
+    unsafe_raw: bool,
+    /// Hex encoded public key, as returned by `sign_data`.
+    public_key: String,
+    /// Hex encoded DER signature, as returned by `sign_data`.
+    signature: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct VerifyDataResponse {
+    pub valid: bool,
+    pub domain: Option<model::SigningDomain>,
+    /// Hex encoded digest that the signature was checked against.
+    pub digest: String,
+}
+
+impl Message for VerifyDataRequest {
+    type Result = app::Result<VerifyDataResponse>;
+}
+
+impl Handler<VerifyDataRequest> for app::App {
+    type Result = <VerifyDataRequest as Message>::Result;
+
+    fn handle(&mut self, msg: VerifyDataRequest, _ctx: &mut Self::Context) -> Self::Result {
+        let domain = app::signing_domain(msg.domain, msg.unsafe_raw)?;
+        if domain.is_none() {
+            log::warn!("Verifying data signed without a domain");
+        }
+
+        verify_data(domain, &msg.data, &msg.public_key, &msg.signature)
+    }
+}
+
+/// Check a signature of some data in a domain, or raw if there is none.
+pub fn verify_data(
+    domain: Option<model::SigningDomain>,
+    data: &str,
+    public_key: &str,
+    signature: &str,
+) -> app::Result<VerifyDataResponse> {
+    let public_key = PublicKey::from_str(public_key)
+        .map_err(|err| app::validation_error(app::field_error("public_key", err.to_string())))?;
+    let signature = Signature::from_str(signature)
+        .map_err(|err| app::validation_error(app::field_error("signature", err.to_string())))?;
+
+    let digest = model::signing_digest(domain, data.as_bytes());
+
+    Ok(VerifyDataResponse {
+        valid: verify(&public_key, digest.as_ref(), &signature).is_ok(),
+        domain,
+        digest: hex::encode(digest),
+    })
+}
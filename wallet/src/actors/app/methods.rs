@@ -1,21 +1,33 @@
-use std::sync::Arc;
+use std::{collections::HashMap, path::PathBuf, str::FromStr, sync::Arc};
 
-use actix::utils::TimerFunc;
 use futures::FutureExt;
 
-use witnet_crypto::mnemonic;
+use witnet_crypto::{
+    key::{KeyPath, KeySpec},
+    mnemonic,
+};
 use witnet_data_structures::{
-    chain::{Block, InventoryItem, RADRequest, StateMachine, SyncStatus},
+    chain::{
+        Block, CheckpointBeacon, ConsensusConstants, DataRequestOutput, Environment, Hash,
+        Hashable, InventoryItem, KeyedSignature, PublicKeyHash, RADRequest, RADRetrieve,
+        StateMachine, SyncStatus, TransactionValidation,
+    },
+    fee::Fee,
     transaction::Transaction,
+    transaction_factory::NodeBalance,
 };
-use witnet_rad::RADRequestExecutionReport;
+use witnet_util::timestamp::get_timestamp;
 
 use crate::{
     actors::{
         worker::{HandleBlockRequest, HandleSuperBlockRequest, NodeStatusRequest, NotifyStatus},
         *,
     },
-    crypto, model,
+    address_metadata::ImportPayload,
+    api_version, constants, credentials, crypto,
+    deadline::Deadline,
+    diagnostics, idempotency, metrics, model, repository, schema, simulation, sink, watchdog,
+    witness_signing::WitnessTransactionBody,
 };
 
 use super::*;
@@ -35,8 +47,17 @@ impl App {
     pub fn start(params: Params) -> Addr<Self> {
         let actor = Self {
             server: None,
+            http_server: None,
+            state: state::State {
+                notifications_replay_size: params.notifications_replay_size,
+                request_log: metrics::RequestLog::new(constants::REQUEST_LOG_SIZE),
+                node_watchdog: watchdog::NodeWatchdog::new(
+                    params.node_stall_epochs,
+                    params.node_clock_skew_epochs,
+                ),
+                ..Default::default()
+            },
             params,
-            state: Default::default(),
         };
 
         actor.start()
@@ -51,6 +72,7 @@ impl App {
         std::thread::spawn(move || {
             drop(s);
         });
+        self.http_server.take();
         self.stop_worker()
             .map(|res| match res {
                 Ok(_) => {
@@ -71,64 +93,167 @@ impl App {
         session_id: &types::SessionId,
     ) -> Result<jsonrpc_pubsub::SubscriptionId> {
         if self.state.is_session_active(session_id) {
-            // We are re-using the session id as the subscription id, this is because using a number
-            // can let any client call the unsubscribe method for any other session.
-            Ok(jsonrpc_pubsub::SubscriptionId::from(session_id))
+            // Subscription ids are random rather than sequential or derived from the session, so
+            // that they cannot be guessed by other clients
+            Ok(jsonrpc_pubsub::SubscriptionId::String(hex::encode(
+                rand::random::<[u8; 16]>(),
+            )))
         } else {
             Err(Error::SessionNotFound)
         }
     }
 
     /// Try to create a subscription and store it in the session. After subscribing, events related
-    /// to wallets unlocked by this session will be sent to the client.
+    /// to wallets unlocked by this session will be sent to the client. If `last_event_id` is given,
+    /// the events that came after it are replayed first.
     pub fn subscribe(
         &mut self,
-        session_id: types::SessionId,
-        _subscription_id: jsonrpc_pubsub::SubscriptionId,
-        sink: jsonrpc_pubsub::Sink,
+        subscription_id: jsonrpc_pubsub::SubscriptionId,
+        subscription: state::Subscription,
+        sink: Box<dyn sink::Transport>,
+        overflow_policy: sink::OverflowPolicy,
+        last_event_id: Option<u64>,
     ) -> Result<()> {
-        self.state.subscribe(&session_id, sink).map(|dyn_sink| {
-            // If the subscription was successful, notify subscriber about initial status for all
-            // wallets that belong to this session.
-            let wallets = self.state.get_wallets_by_session(&session_id);
-            if let Ok(wallets) = wallets {
-                for (_, wallet) in wallets.iter() {
-                    self.params.worker.do_send(NotifyStatus(
-                        wallet.clone(),
-                        dyn_sink.clone(),
-                        None,
-                    ));
+        let session_id = subscription.session_id.clone();
+        let sink =
+            sink::BoundedSink::new(sink, self.params.notifications_queue_size, overflow_policy);
+        self.state
+            .subscribe(subscription_id, subscription, sink, last_event_id)
+            .map(|dyn_sink| {
+                // If the subscription was successful, notify subscriber about initial status for all
+                // wallets that belong to this session.
+                let wallets = self.state.get_wallets_by_session(&session_id);
+                if let Ok(wallets) = wallets {
+                    for (_, wallet) in wallets.iter() {
+                        self.params.worker.do_send(NotifyStatus(
+                            wallet.clone(),
+                            dyn_sink.clone(),
+                            None,
+                        ));
+                    }
                 }
-            }
-        })
+            })
     }
 
-    /// Remove a subscription.
-    pub fn unsubscribe(&mut self, id: &jsonrpc_pubsub::SubscriptionId) -> Result<()> {
-        // Session id and subscription id are currently the same thing. See comment in
-        // next_subscription_id method.
-        self.state.unsubscribe(id).map(|_| ())
+    /// Get the overflow policy and lag counters of the subscription of a session.
+    pub fn get_subscription_stats(
+        &self,
+        session_id: &types::SessionId,
+    ) -> Result<Option<(sink::OverflowPolicy, sink::SinkStats)>> {
+        if !self.state.is_session_active(session_id) {
+            return Err(Error::SessionNotFound);
+        }
+
+        Ok(self.state.get_sink_stats(session_id))
+    }
+
+    /// Remove a subscription on behalf of the WebSocket connection of a client, which can only
+    /// remove the subscriptions of the sessions it subscribed to. Without a connection, the
+    /// subscription is removed because the connection it was made through was closed.
+    pub fn unsubscribe(
+        &mut self,
+        id: &jsonrpc_pubsub::SubscriptionId,
+        connection: Option<Arc<jsonrpc_pubsub::Session>>,
+    ) -> Result<()> {
+        match connection {
+            Some(connection) => {
+                let caller = self
+                    .state
+                    .connection_session(&connection, id)
+                    .ok_or(Error::SubscriptionForbidden)?;
+
+                self.state.unsubscribe(id, &caller)
+            }
+            None => self.state.remove_subscription(id),
+        }
     }
 
-    /// Generate a receive address for the wallet's current account.
+    /// Generate a receive address for the wallet's current account, along with a warning if it is
+    /// beyond the gap limit.
     pub fn generate_address(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         external: bool,
         label: Option<String>,
-    ) -> ResponseActFuture<model::Address> {
+    ) -> ResponseActFuture<(model::Address, Option<model::AddressGapWarning>)> {
         let f = fut::result(
             self.state
                 .get_wallet_by_session_and_id(&session_id, &wallet_id),
         )
         .and_then(move |wallet, slf: &mut Self, _| {
+            let sink = slf.state.get_sink(&session_id);
             slf.params
                 .worker
                 .send(worker::GenAddress {
                     wallet,
                     external,
                     label,
+                    sink,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Create a payment request, which derives a fresh external address for the payment.
+    pub fn create_payment_request(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        amount: u64,
+        memo: Option<String>,
+        expires_in: Option<u64>,
+    ) -> ResponseActFuture<model::PaymentRequest> {
+        let wallet = if amount == 0 {
+            Err(validation_error(field_error(
+                "amount",
+                "Payment requests must be for a non-zero amount",
+            )))
+        } else {
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id)
+        };
+
+        let f = fut::result(wallet).and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::CreatePaymentRequest {
+                    wallet,
+                    amount,
+                    memo,
+                    expires_in,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Get a list of the payment requests of a wallet, optionally filtered by status.
+    pub fn list_payment_requests(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        statuses: Vec<model::PaymentRequestStatus>,
+        offset: u32,
+        limit: u32,
+    ) -> ResponseActFuture<model::PaymentRequests> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::GetPaymentRequests {
+                    wallet,
+                    statuses,
+                    offset,
+                    limit,
                 })
                 .flatten_err()
                 .into_actor(slf)
@@ -138,13 +263,17 @@ impl App {
     }
 
     /// Get a list of addresses generated by a wallet.
+    #[allow(clippy::too_many_arguments)]
     pub fn get_addresses(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         offset: u32,
         limit: u32,
         external: bool,
+        sort: model::AddressesSort,
+        order: model::SortOrder,
+        cursor: Option<model::AddressesCursor>,
     ) -> ResponseActFuture<model::Addresses> {
         let f = fut::result(
             self.state
@@ -158,6 +287,9 @@ impl App {
                     offset,
                     limit,
                     external,
+                    sort,
+                    order,
+                    cursor,
                 })
                 .flatten_err()
                 .into_actor(slf)
@@ -170,7 +302,7 @@ impl App {
     pub fn get_balance(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
     ) -> ResponseActFuture<model::WalletBalance> {
         let f = fut::result(
             self.state
@@ -187,11 +319,40 @@ impl App {
         Box::pin(f)
     }
 
+    /// Get the balance history of a wallet between two epochs, at a resolution
+    pub fn get_balance_history(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        from: Option<u32>,
+        to: Option<u32>,
+        resolution: model::BalanceHistoryResolution,
+    ) -> ResponseActFuture<Vec<model::BalanceHistoryEntry>> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::GetBalanceHistory {
+                    wallet,
+                    from,
+                    to,
+                    resolution,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
     /// Delete a wallet providing its WalletID and its SessionID
     pub fn delete_wallet(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
     ) -> ResponseActFuture<()> {
         let f = fut::result(
             self.state
@@ -208,13 +369,65 @@ impl App {
         Box::pin(f)
     }
 
+    /// Confirm that the user backed up the mnemonic of a wallet by checking some of its words.
+    ///
+    /// Once confirmed the wallet starts synchronizing. If the wallet is deleted after too many
+    /// failed attempts, it is also locked.
+    pub fn confirm_seed_backup(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        words: Vec<(usize, types::Password)>,
+    ) -> ResponseActFuture<model::SeedBackupConfirmation> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::ConfirmSeedBackup {
+                    wallet: wallet.clone(),
+                    wallet_id: wallet_id.clone(),
+                    words,
+                })
+                .flatten_err()
+                .into_actor(slf)
+                .map_ok(move |confirmation, act: &mut Self, ctx| {
+                    match confirmation {
+                        model::SeedBackupConfirmation::Confirmed => act.start_wallet_sync(
+                            &session_id,
+                            wallet_id,
+                            wallet,
+                            "confirm_seed_backup",
+                            ctx,
+                        ),
+                        model::SeedBackupConfirmation::WalletDeleted => {
+                            if let Err(e) = act.lock_wallet(session_id, wallet_id) {
+                                log::warn!("Deleted wallet couldn't be locked: {}", e);
+                            }
+                        }
+                        model::SeedBackupConfirmation::WrongWords { .. } => {}
+                    }
+
+                    confirmation
+                })
+        });
+
+        Box::pin(f)
+    }
+
     /// Get a list of transactions associated to a wallet account.
+    #[allow(clippy::too_many_arguments)]
     pub fn get_transactions(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         offset: u32,
         limit: u32,
+        sort: model::MovementsSort,
+        order: model::SortOrder,
+        cursor: Option<model::MovementsCursor>,
     ) -> ResponseActFuture<model::WalletTransactions> {
         let f = fut::result(
             self.state
@@ -227,6 +440,36 @@ impl App {
                     wallet,
                     offset,
                     limit,
+                    sort,
+                    order,
+                    cursor,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Get the next batch of the stream of the persisted movements of a wallet.
+    pub fn get_transactions_stream(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        cursor: Option<model::TransactionsCursor>,
+        batch_size: u32,
+    ) -> ResponseActFuture<model::TransactionsBatch> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::GetTransactionsStream {
+                    wallet,
+                    cursor,
+                    batch_size,
                 })
                 .flatten_err()
                 .into_actor(slf)
@@ -239,7 +482,7 @@ impl App {
     pub fn get_utxo_info(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
     ) -> ResponseActFuture<model::UtxoSet> {
         let f = fut::result(
             self.state
@@ -260,30 +503,100 @@ impl App {
     pub fn run_rad_request(
         &self,
         request: RADRequest,
-    ) -> ResponseFuture<RADRequestExecutionReport> {
+        bypass_cache: bool,
+    ) -> ResponseFuture<worker::RunRadRequestResponse> {
         let f = self
             .params
             .worker
-            .send(worker::RunRadRequest { request })
+            .send(worker::RunRadRequest {
+                request,
+                deadline: self
+                    .execution_deadline("run_rad_request", Some(self.params.requests_timeout)),
+                bypass_cache,
+            })
             .flatten_err();
 
         Box::pin(f)
     }
 
-    /// Generate a random BIP39 mnemonics sentence
-    pub fn generate_mnemonics(&self, length: mnemonic::Length) -> ResponseFuture<String> {
+    /// Run a data request locally and simulate its tally with the given dishonest witnesses.
+    pub fn simulate_data_request(
+        &self,
+        request: DataRequestOutput,
+        lies: Vec<simulation::Lie>,
+        bypass_cache: bool,
+    ) -> ResponseFuture<worker::SimulateDataRequestResponse> {
         let f = self
             .params
             .worker
-            .send(worker::GenMnemonic { length })
-            .map(|res| match res {
-                Ok(x) => Ok(x),
-                Err(e) => Err(e.into()),
-            });
+            .send(worker::SimulateDataRequest {
+                request,
+                lies,
+                deadline: self.execution_deadline(
+                    "simulate_data_request",
+                    Some(self.params.requests_timeout),
+                ),
+                bypass_cache,
+            })
+            .flatten_err();
+
+        Box::pin(f)
+    }
+
+    /// Retrieve some data sources repeatedly and summarize how each of them did.
+    pub fn benchmark_retrievals(
+        &self,
+        retrievals: Vec<RADRetrieve>,
+        iterations: u32,
+        spacing_ms: u64,
+        persist: bool,
+    ) -> ResponseFuture<model::RetrievalBenchmark> {
+        if retrievals.is_empty() {
+            return Box::pin(futures::future::err(validation_error(field_error(
+                "retrievals",
+                "at least one data source is needed",
+            ))));
+        }
+        if iterations == 0 || iterations > constants::MAX_BENCHMARK_ITERATIONS {
+            return Box::pin(futures::future::err(validation_error(field_error(
+                "iterations",
+                format!(
+                    "must be between 1 and {}",
+                    constants::MAX_BENCHMARK_ITERATIONS
+                ),
+            ))));
+        }
+        if spacing_ms > constants::MAX_BENCHMARK_SPACING_MS {
+            return Box::pin(futures::future::err(validation_error(field_error(
+                "spacing_ms",
+                format!("must be at most {}", constants::MAX_BENCHMARK_SPACING_MS),
+            ))));
+        }
+
+        let f = self
+            .params
+            .worker
+            .send(worker::BenchmarkRetrievals {
+                retrievals,
+                iterations,
+                spacing: std::time::Duration::from_millis(spacing_ms),
+                timeout: self.params.retrieval_timeout,
+                persist,
+                deadline: self.execution_deadline("benchmark_retrievals", None),
+            })
+            .flatten_err();
 
         Box::pin(f)
     }
 
+    /// Generate a random BIP39 mnemonics sentence. It needs no storage, so it is answered right
+    /// away even if the worker is busy.
+    pub fn generate_mnemonics(&self, length: mnemonic::Length) -> String {
+        let mnemonic = mnemonic::MnemonicGen::new().with_len(length).generate();
+
+        mnemonic.words().to_string()
+    }
+
     /// Forward a Json-RPC call to the node.
     pub fn forward(
         &mut self,
@@ -294,19 +607,281 @@ impl App {
             .timeout(self.params.requests_timeout)
             .params(params)
             .expect("params failed serialization");
-        let f = self.get_client().actor.send(req).flatten_err();
+        let f = self.get_client().send(req).flatten_err();
+
+        Box::pin(f)
+    }
+
+    /// Check whether an address can be used as the recipient of a transaction, and which of the
+    /// wallets unlocked in the session generated it. If `check_chain` is set and a node is
+    /// connected, the node is also asked whether the address holds any funds.
+    pub fn validate_address(
+        &mut self,
+        address: String,
+        session_id: Option<types::SessionId>,
+        check_chain: bool,
+    ) -> ResponseActFuture<ValidateAddressResponse> {
+        let validation = inspect_address(self.params.testnet, &address);
+        let pkh = match validation.pkh {
+            Some(pkh) => pkh,
+            None => return Box::pin(fut::result(Ok(validation))),
+        };
+        let wallets = match self.session_wallets(session_id.as_ref()) {
+            Ok(wallets) => wallets,
+            Err(err) => return Box::pin(fut::result(Err(err))),
+        };
+
+        let f = self
+            .params
+            .worker
+            .send(worker::WalletsWithAddress { wallets, pkh })
+            .flatten_err()
+            .into_actor(self)
+            .and_then(move |own_wallets, slf: &mut Self, _| {
+                let hint = if check_chain {
+                    slf.seen_on_chain_hint(address, pkh)
+                } else {
+                    Box::pin(fut::result(Ok(None)))
+                };
+
+                hint.map_ok(move |seen_on_chain_hint, _, _| ValidateAddressResponse {
+                    own_wallets,
+                    seen_on_chain_hint,
+                    ..validation
+                })
+            });
+
+        Box::pin(f)
+    }
+
+    /// Ask the node whether an address holds any funds, as a hint of whether it has ever appeared
+    /// on chain. There is no hint if the node is not connected or the request fails.
+    fn seen_on_chain_hint(
+        &mut self,
+        address: String,
+        pkh: PublicKeyHash,
+    ) -> ResponseActFuture<Option<bool>> {
+        if self.state.addresses_seen_on_chain.contains(&pkh) {
+            return Box::pin(fut::result(Ok(Some(true))));
+        }
+        if self.state.node_state.is_none() {
+            return Box::pin(fut::result(Ok(None)));
+        }
+
+        let req = jsonrpc::Request::method("getBalance")
+            .timeout(self.params.requests_timeout)
+            .value(serde_json::json!([address, true]));
+        let f = self
+            .get_client()
+            .send(req)
+            .flatten_err()
+            .into_actor(self)
+            .map(move |res: Result<serde_json::Value>, act, _ctx| {
+                let seen = match res.map(serde_json::from_value::<NodeBalance>) {
+                    Ok(Ok(NodeBalance::One { total, .. })) => total > 0,
+                    Ok(Ok(NodeBalance::Many(_))) => return Ok(None),
+                    Ok(Err(err)) => {
+                        log::warn!("Failed to deserialize the balance of an address: {}", err);
+                        return Ok(None);
+                    }
+                    Err(err) => {
+                        log::warn!("Failed to get the balance of an address: {}", err);
+                        return Ok(None);
+                    }
+                };
+                if seen {
+                    act.state.addresses_seen_on_chain.insert(pkh);
+                }
+
+                Ok(Some(seen))
+            });
+
+        Box::pin(f)
+    }
+
+    /// Wallets unlocked in a session, or none if no session is given.
+    fn session_wallets(
+        &self,
+        session_id: Option<&types::SessionId>,
+    ) -> Result<Vec<(model::WalletId, types::SessionWallet)>> {
+        let wallets = match session_id {
+            Some(session_id) => self
+                .state
+                .get_wallets_by_session(session_id)?
+                .iter()
+                .map(|(id, wallet)| (id.clone(), wallet.clone()))
+                .collect(),
+            None => vec![],
+        };
+
+        Ok(wallets)
+    }
+
+    /// List the blocks of a range of the chain, annotated with the transactions of the wallets
+    /// unlocked in the session that each of them includes.
+    ///
+    /// The annotations come from the movements indexed by the wallets, so they only cover the
+    /// blocks that the wallets have synchronized. The list is not cached, because it changes with
+    /// every new block and with every reorganization of the chain.
+    pub fn get_blocks(
+        &mut self,
+        session_id: Option<types::SessionId>,
+        range: types::GetBlockChainParams,
+    ) -> ResponseActFuture<GetBlocksResponse> {
+        let wallets = match self.session_wallets(session_id.as_ref()) {
+            Ok(wallets) => wallets,
+            Err(err) => return Box::pin(fut::result(Err(err))),
+        };
+
+        let req = jsonrpc::Request::method("getBlockChain")
+            .timeout(self.params.requests_timeout)
+            .params(range)
+            .expect("params failed serialization");
+        let f = self
+            .get_client()
+            .send(req)
+            .flatten_err()
+            .into_actor(self)
+            .and_then(
+                move |json, slf: &mut Self, _| -> ResponseActFuture<GetBlocksResponse> {
+                    let blocks = match chain_beacons(json) {
+                        Ok(blocks) => blocks,
+                        Err(err) => return Box::pin(fut::result(Err(err))),
+                    };
+                    if wallets.is_empty() {
+                        return Box::pin(fut::result(Ok(block_summaries(&blocks, None))));
+                    }
+
+                    let f = slf
+                        .params
+                        .worker
+                        .send(worker::TransactionsInBlocks {
+                            wallets,
+                            blocks: blocks.clone(),
+                        })
+                        .flatten_err()
+                        .into_actor(slf)
+                        .map_ok(move |own_transactions, _, _| {
+                            block_summaries(&blocks, Some(own_transactions.as_slice()))
+                        });
+
+                    Box::pin(f)
+                },
+            );
+
+        Box::pin(f)
+    }
+
+    /// Get a block with its transactions decoded as in the `decode` method, with the inputs,
+    /// outputs and transactions that belong to the wallets unlocked in the session marked with the
+    /// ids of those wallets.
+    ///
+    /// Blocks fetched from the node are cached by their hash, since their contents never change.
+    pub fn get_block_details(
+        &mut self,
+        session_id: Option<types::SessionId>,
+        block_hash: String,
+    ) -> ResponseActFuture<serde_json::Value> {
+        let hash = match Hash::from_str(&block_hash) {
+            Ok(hash) => hash,
+            Err(err) => {
+                return Box::pin(fut::result(Err(validation_error(field_error(
+                    "block_hash",
+                    err,
+                )))))
+            }
+        };
+        let wallets = match self.session_wallets(session_id.as_ref()) {
+            Ok(wallets) => wallets,
+            Err(err) => return Box::pin(fut::result(Err(err))),
+        };
+        let environment = if self.params.testnet {
+            Environment::Testnet
+        } else {
+            Environment::Mainnet
+        };
+
+        let block: ResponseActFuture<Arc<Block>> = match self.state.explorer_blocks.get(&hash) {
+            Some(block) => Box::pin(fut::result(Ok(block))),
+            None => {
+                let req = jsonrpc::Request::method("getBlock")
+                    .timeout(self.params.requests_timeout)
+                    .value(serde_json::json!([block_hash, false]));
+                let f = self
+                    .get_client()
+                    .send(req)
+                    .flatten_err()
+                    .into_actor(self)
+                    .map(|res: Result<serde_json::Value>, act, _ctx| {
+                        let res = res?;
+                        act.get_client()
+                            .schema
+                            .validate(&schema::GET_BLOCK, &res)
+                            .map_err(node_error)?;
+                        let block =
+                            Arc::new(serde_json::from_value::<Block>(res).map_err(node_error)?);
+                        act.state.explorer_blocks.insert(block.clone());
+
+                        Ok(block)
+                    });
+
+                Box::pin(f)
+            }
+        };
+
+        let f = block.and_then(
+            move |block, slf: &mut Self, _| -> ResponseActFuture<serde_json::Value> {
+                let mut rendered = match render_block(&block, environment, "block") {
+                    Ok(rendered) => rendered,
+                    Err(err) => return Box::pin(fut::result(Err(err))),
+                };
+                if wallets.is_empty() {
+                    return Box::pin(fut::result(Ok(rendered)));
+                }
+
+                let f = slf
+                    .params
+                    .worker
+                    .send(worker::OwnBlockEntries { wallets, block })
+                    .flatten_err()
+                    .into_actor(slf)
+                    .map_ok(move |own_entries, _, _| {
+                        annotate_block(&mut rendered, &own_entries);
+
+                        rendered
+                    });
+
+                Box::pin(f)
+            },
+        );
 
         Box::pin(f)
     }
 
-    /// Get public info of all the wallets stored in the database.
-    pub fn wallet_infos(&self) -> ResponseFuture<Vec<model::Wallet>> {
-        let f = self.params.worker.send(worker::WalletInfos).flatten_err();
+    /// Get a page of the public info of the wallets stored in the database. Wallets waiting for
+    /// their seed backup to be confirmed are only included if `include_pending` is set.
+    pub fn wallet_infos(
+        &self,
+        include_pending: bool,
+        offset: u32,
+        limit: u32,
+    ) -> ResponseFuture<model::WalletInfos> {
+        let f = metrics::timed(
+            self.state.storage_latency.clone(),
+            "wallet_infos",
+            self.params.worker.send(worker::WalletInfos {
+                include_pending,
+                offset,
+                limit,
+            }),
+        )
+        .flatten_err();
 
         Box::pin(f)
     }
 
     /// Create an empty HD Wallet.
+    #[allow(clippy::too_many_arguments)]
     pub fn create_wallet(
         &self,
         password: types::Password,
@@ -314,8 +889,11 @@ impl App {
         name: Option<String>,
         description: Option<String>,
         overwrite: bool,
+        allow_duplicate_seed: bool,
         birth_date: Option<types::BirthDate>,
-    ) -> ResponseFuture<String> {
+        backup_confirmation: bool,
+        key_spec: Option<KeySpec>,
+    ) -> ResponseFuture<model::WalletId> {
         let f = self
             .params
             .worker
@@ -325,7 +903,10 @@ impl App {
                 password,
                 seed_source,
                 overwrite,
+                allow_duplicate_seed,
                 birth_date,
+                backup_confirmation,
+                key_spec,
             })
             .flatten_err();
 
@@ -336,7 +917,7 @@ impl App {
     pub fn update_wallet(
         &self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         name: Option<String>,
         description: Option<String>,
     ) -> ResponseActFuture<()> {
@@ -374,75 +955,266 @@ impl App {
     ///
     /// This means the state of this wallet won't be updated with information received from the
     /// node.
-    pub fn lock_wallet(&mut self, session_id: types::SessionId, wallet_id: String) -> Result<()> {
+    pub fn lock_wallet(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+    ) -> Result<()> {
         self.state.remove_wallet(&session_id, &wallet_id)
     }
 
+    /// Lock every unlocked wallet of every session, e.g. because the system is going to sleep.
+    ///
+    /// If a session is given it must be active, but locking works without one so that it can be
+    /// triggered by a system hook.
+    pub fn lock_all_wallets(&mut self, session_id: Option<types::SessionId>) -> Result<usize> {
+        if let Some(session_id) = session_id {
+            if !self.state.is_session_active(&session_id) {
+                return Err(Error::SessionNotFound);
+            }
+        }
+
+        Ok(self.state.lock_all_wallets())
+    }
+
     /// Load a wallet's private information and keys in memory.
+    ///
+    /// The wallet is unlocked in `session_id` if given, so that its subscription keeps working
+    /// (e.g. after `lock_all_wallets`), or in a new session otherwise.
+    ///
+    /// Every wrong password doubles the time to wait before the wallet can be unlocked again.
+    ///
+    /// Clients declaring an API version older than the configured minimum are told to upgrade
+    /// before the password is even checked.
     pub fn unlock_wallet(
         &mut self,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         password: types::Password,
+        session_id: Option<types::SessionId>,
+        scopes: Option<Vec<types::Scope>>,
+        client_api_version: Option<u32>,
     ) -> ResponseActFuture<types::UnlockedWallet> {
-        // If a synchronization from a previous session is still running, set `stop_syncing` to
-        // `true` so as to signal that it must stop as soon as possible
+        if let Some(client) = client_api_version {
+            if client < self.params.min_client_api_version {
+                return Box::pin(fut::err(Error::ClientUpgradeRequired {
+                    client,
+                    minimum: self.params.min_client_api_version,
+                    current: api_version::API_VERSION,
+                }));
+            }
+        }
+        if let Some(session_id) = &session_id {
+            if !self.state.is_session_active(session_id) {
+                return Box::pin(fut::err(Error::SessionNotFound));
+            }
+        }
+        // Refuse early if the session limits are already reached and nothing can be evicted
+        if let Err(e) = self
+            .state
+            .sessions_to_evict(&wallet_id, &self.params.session_limits)
+        {
+            return Box::pin(fut::err(e));
+        }
+        // Slow down password guessing by refusing attempts made too soon after a wrong password,
+        // or while another attempt is running
+        if let Err(retry_after) = self.state.reserve_unlock(&wallet_id, get_timestamp()) {
+            return Box::pin(fut::err(Error::UnlockThrottled { retry_after }));
+        }
+        // If a synchronization from a previous session is still running, set `stop_syncing` to
+        // `true` so as to signal that it must stop as soon as possible
         if let Some(wallet) = self.state.get_current_wallet_session(wallet_id.clone()) {
             wallet.set_stop_syncing().expect("Lock error")
         }
+        let reused_session = session_id.is_some();
         let id = wallet_id.clone();
-        let f = self
-            .params
-            .worker
-            .send(worker::UnlockWallet { id, password })
-            .flatten_err()
-            .into_actor(self)
-            .and_then(move |res, slf: &mut Self, ctx| {
-                let types::UnlockedSessionWallet {
-                    wallet,
-                    session_id,
-                    data,
-                } = res;
+        let failed_id = wallet_id.clone();
+        let f = metrics::timed(
+            self.state.storage_latency.clone(),
+            "unlock_wallet",
+            self.params.worker.send(worker::UnlockWallet {
+                id,
+                password,
+                session_id,
+            }),
+        )
+        .flatten_err()
+        .into_actor(self)
+        .map_err(move |err, slf: &mut Self, _ctx| match err {
+            Error::WrongPassword { .. } => Error::WrongPassword {
+                retry_after: slf.state.record_failed_unlock(&failed_id, get_timestamp()),
+            },
+            err => {
+                slf.state.release_unlock(&failed_id);
+                err
+            }
+        })
+        .and_then(move |res, slf: &mut Self, ctx| {
+            let types::UnlockedSessionWallet {
+                wallet,
+                session_id,
+                data,
+            } = res;
+            slf.state.reset_failed_unlocks(&wallet_id);
+
+            // The session may have expired or been closed while the wallet was being unlocked
+            if reused_session && !slf.state.is_session_active(&session_id) {
+                return fut::err(Error::SessionNotFound);
+            }
 
-                slf.state
-                    .create_session(session_id.clone(), wallet_id.clone(), wallet.clone());
+            // Other sessions may have been opened while the wallet was being unlocked
+            let evicted = match slf
+                .state
+                .sessions_to_evict(&wallet_id, &slf.params.session_limits)
+            {
+                Ok(evicted) => evicted,
+                Err(e) => return fut::err(e),
+            };
+            // A reused session is never evicted to make room for itself
+            for evicted_id in evicted.into_iter().filter(|id| *id != session_id) {
+                if let Err(e) = slf.state.evict_session(&evicted_id) {
+                    log::warn!("Session {} couldn't be evicted: {}", evicted_id, e);
+                }
+            }
 
-                // If the node is synced start synchronization for this wallet
-                if slf.state.node_state == Some(StateMachine::Synced)
-                    || slf.state.node_state.is_none()
-                {
-                    let sink = slf.state.get_sink(&session_id);
-                    slf.params
-                        .worker
-                        .send(worker::SyncRequest {
-                            wallet_id,
-                            wallet,
-                            sink,
-                        })
-                        .flatten_err()
-                        .into_actor(slf)
-                        .map(|res: Result<()>, act: &mut Self, _ctx| {
-                            if let Err(e) = res {
-                                act.handle_sync_error(&e);
-                            }
-                        })
-                        .spawn(ctx)
-                };
+            slf.state.create_session(
+                session_id.clone(),
+                wallet_id.clone(),
+                wallet.clone(),
+                scopes.as_deref(),
+                client_api_version,
+            );
 
-                fut::ok(types::UnlockedWallet { data, session_id })
-            });
+            slf.start_wallet_sync(&session_id, wallet_id, wallet, "unlock_wallet", ctx);
+
+            fut::ok(types::UnlockedWallet { data, session_id })
+        });
 
         Box::pin(f)
     }
 
+    /// Unlock the wallets of the credentials file in the service session, which never expires.
+    ///
+    /// Wallets that cannot be unlocked are logged and reported by `get_node_status`, without
+    /// keeping the others from being unlocked. Encrypted passwords are decrypted with `key`. The
+    /// passwords are zeroed out of memory as soon as the worker is done with them.
+    pub fn unlock_service_session(
+        &mut self,
+        credentials: credentials::Credentials,
+        key: Option<types::Password>,
+        ctx: &mut <Self as Actor>::Context,
+    ) {
+        let credentials::Credentials {
+            session_id,
+            scopes,
+            wallets,
+        } = credentials;
+        self.state
+            .create_service_session(session_id.clone(), scopes.as_deref());
+
+        let mut report = credentials::StartupUnlockReport::default();
+        let mut passwords = vec![];
+        for (wallet_id, credential) in wallets {
+            match credential.into_password(key.as_ref()) {
+                Ok(password) => {
+                    report.pending.push(wallet_id.clone());
+                    passwords.push((wallet_id, password));
+                }
+                Err(e) => {
+                    log::error!("Failed to unlock wallet {} at startup: {}", wallet_id, e);
+                    report.failed.push(credentials::StartupUnlockFailure {
+                        wallet_id,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+        self.state.startup_unlock = Some(report);
+
+        for (wallet_id, password) in passwords {
+            self.unlock_wallet(
+                wallet_id.clone(),
+                password,
+                Some(session_id.clone()),
+                None,
+                None,
+            )
+            .map(move |res, act: &mut Self, _ctx| {
+                let report = act
+                    .state
+                    .startup_unlock
+                    .get_or_insert_with(Default::default);
+                report.pending.retain(|pending| *pending != wallet_id);
+                match res {
+                    Ok(_) => {
+                        log::info!("Unlocked wallet {} in the service session", wallet_id);
+                        report.unlocked.push(wallet_id);
+                    }
+                    Err(e) => {
+                        log::error!("Failed to unlock wallet {} at startup: {}", wallet_id, e);
+                        report.failed.push(credentials::StartupUnlockFailure {
+                            wallet_id,
+                            error: e.to_string(),
+                        });
+                    }
+                }
+            })
+            .spawn(ctx);
+        }
+    }
+
+    /// Start synchronizing a wallet unlocked in `session_id`, if the node is synced. The
+    /// synchronization is bounded by the execution timeout of `method`.
+    pub fn start_wallet_sync(
+        &mut self,
+        session_id: &types::SessionId,
+        wallet_id: model::WalletId,
+        wallet: types::SessionWallet,
+        method: &str,
+        ctx: &mut <Self as Actor>::Context,
+    ) {
+        if self.state.node_state == Some(StateMachine::Synced) || self.state.node_state.is_none() {
+            let sink = self.state.get_sink(session_id);
+            let deadline = self.execution_deadline(method, None);
+            let ticket = self.params.work_queues.maintenance.enter();
+            let sync = self.params.maintenance_worker.send(worker::SyncRequest {
+                wallet_id,
+                wallet,
+                sink,
+                deadline,
+            });
+            ticket
+                .hold(sync.flatten_err())
+                .into_actor(self)
+                .map(|res: Result<()>, act: &mut Self, _ctx| {
+                    if let Err(e) = res {
+                        act.handle_sync_error(&e);
+                    }
+                })
+                .spawn(ctx);
+        }
+    }
+
+    /// Create a value transfer transaction. Wallets whose synchronization is pinned are refused
+    /// unless `force` is set, because their view of their unspent outputs is stale.
     pub fn create_vtt(
         &self,
         session_id: &types::SessionId,
-        wallet_id: &str,
+        wallet_id: &model::WalletId,
         params: types::VttParams,
+        force: bool,
     ) -> ResponseActFuture<worker::CreateVttResponse> {
         let f = fut::result(
             self.state
-                .get_wallet_by_session_and_id(session_id, wallet_id),
+                .get_wallet_by_session_and_id(session_id, wallet_id)
+                .and_then(|wallet| {
+                    let sync_target_epoch = wallet.sync_target_epoch().map_err(internal_error)?;
+                    match sync_target_epoch {
+                        Some(sync_target_epoch) if !force => {
+                            Err(Error::SyncPinned { sync_target_epoch })
+                        }
+                        _ => Ok(wallet),
+                    }
+                }),
         )
         .and_then(move |wallet, slf: &mut Self, _| {
             slf.params
@@ -458,7 +1230,7 @@ impl App {
     pub fn create_data_req(
         &self,
         session_id: &types::SessionId,
-        wallet_id: &str,
+        wallet_id: &model::WalletId,
         params: types::DataReqParams,
     ) -> ResponseActFuture<worker::CreateDataReqResponse> {
         let f = fut::result(
@@ -478,21 +1250,49 @@ impl App {
 
     /// Perform all the tasks needed to properly stop the application.
     pub fn stop_worker(&self) -> ResponseFuture<()> {
-        let fut = self
-            .params
-            .worker
-            .send(worker::FlushDb)
-            .map(|res| res.map_err(internal_error))
-            .map(|res| match res {
-                Ok(result) => result.map_err(internal_error),
-                Err(e) => Err(e),
-            });
+        let latency = self.state.storage_latency.clone();
+        let block_processing = self.params.block_processing.clone();
+        let fut = metrics::timed(
+            latency.clone(),
+            "flush_db",
+            self.params.worker.send(worker::FlushDb),
+        )
+        .map(move |res| {
+            let latency = latency
+                .lock()
+                .expect("Metrics locks should only fail if poisoned");
+            log::debug!(
+                "Storage round-trips: {} (mean {}µs, max {}µs)",
+                latency.count,
+                latency.mean_micros(),
+                latency.max_micros
+            );
+            let blocks = block_processing
+                .lock()
+                .expect("Metrics locks should only fail if poisoned");
+            log::debug!(
+                "Notified blocks: {} (walked {} times, skipped {} times and found already applied {} times by wallets)",
+                blocks.blocks_scanned,
+                blocks.wallet_blocks_walked,
+                blocks.wallet_blocks_skipped,
+                blocks.wallet_blocks_duplicated
+            );
+
+            res.map_err(internal_error)
+        })
+        .map(|res| match res {
+            Ok(result) => result.map_err(internal_error),
+            Err(e) => Err(e),
+        });
 
         Box::pin(fut)
     }
 
-    /// Return a timer function that can be scheduled to expire the session after the configured time.
-    pub fn set_session_to_expire(&self, session_id: types::SessionId) -> Result<TimerFunc<Self>> {
+    /// Return a timer that can be spawned to expire the session after the configured time.
+    pub fn set_session_to_expire(
+        &self,
+        session_id: types::SessionId,
+    ) -> Result<impl ActorFuture<Self, Output = ()>> {
         if !self.state.sessions.contains_key(&session_id) {
             log::error!("Session {} does not exist.", &session_id,);
 
@@ -505,9 +1305,12 @@ impl App {
             self.params.session_expires_in.as_secs()
         );
 
-        Ok(TimerFunc::new(
-            self.params.session_expires_in,
-            move |slf: &mut Self, _ctx| {
+        Ok(self
+            .params
+            .clock
+            .sleep(self.params.session_expires_in)
+            .into_actor(self)
+            .map(move |(), slf: &mut Self, _ctx| {
                 if let Some(session) = slf.state.sessions.get_mut(&session_id) {
                     if !session.session_extended {
                         match slf.close_session(session_id.clone()) {
@@ -526,8 +1329,7 @@ impl App {
                         session_id
                     )
                 }
-            },
-        ))
+            }))
     }
 
     /// Remove a session from the list of active sessions.
@@ -539,7 +1341,7 @@ impl App {
     pub fn get(
         &self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         key: String,
     ) -> ResponseActFuture<Option<jsonrpc_core::Value>> {
         let f = fut::result(
@@ -568,7 +1370,7 @@ impl App {
     pub fn set(
         &self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         key: String,
         value: jsonrpc_core::Params,
     ) -> ResponseActFuture<()> {
@@ -592,9 +1394,22 @@ impl App {
     }
 
     /// Handle any kind of notifications received from a Witnet node.
-    pub fn handle_notification(&mut self, topic: String, value: serde_json::Value) -> Result<()> {
+    pub fn handle_notification(
+        &mut self,
+        topic: String,
+        value: serde_json::Value,
+        ctx: &mut <Self as Actor>::Context,
+    ) -> Result<()> {
+        if matches!(topic.as_str(), "blocks" | "superblocks") {
+            let alert = self
+                .state
+                .node_watchdog
+                .record_notification(get_timestamp());
+            self.notify_node_alerts(alert);
+        }
+
         match topic.as_str() {
-            "blocks" => self.handle_block_notification(value),
+            "blocks" => self.handle_block_notification(value, ctx),
             "superblocks" => self.handle_superblock_notification(value),
             _ => {
                 log::debug!("Unhandled `{}` notification", topic);
@@ -606,15 +1421,63 @@ impl App {
     }
 
     /// Handle new block notifications received from a Witnet node.
-    pub fn handle_block_notification(&mut self, value: serde_json::Value) -> Result<()> {
+    ///
+    /// Once the queue of the block worker is full, notified blocks are dropped until it drains,
+    /// as none of the blocks that follow a dropped one could be applied anyway. The wallets are
+    /// then synchronized instead of processing the next block, which catches them up with all the
+    /// blocks dropped in between.
+    pub fn handle_block_notification(
+        &mut self,
+        value: serde_json::Value,
+        ctx: &mut <Self as Actor>::Context,
+    ) -> Result<()> {
         let block = Arc::new(serde_json::from_value::<Block>(value).map_err(node_error)?);
 
         // This iterator is collected early so as to free the immutable reference to `self`.
         let wallets: Vec<types::SessionWallet> = self.state.wallets.values().cloned().collect();
+        if wallets.is_empty() {
+            return Ok(());
+        }
+
+        let queue = &self.params.work_queues.blocks;
+        if self.state.shedding_blocks || queue.is_saturated() {
+            if queue.depth() > 0 {
+                queue.record_shed();
+                if !self.state.shedding_blocks {
+                    log::warn!(
+                        "The block worker is overloaded, dropping notified blocks until it catches up"
+                    );
+                    self.state.shedding_blocks = true;
+                }
+
+                return Ok(());
+            }
+
+            log::info!(
+                "The block worker caught up, synchronizing the wallets to recover the dropped blocks"
+            );
+            self.state.shedding_blocks = false;
+            for wallet in wallets {
+                let session_id = wallet.session_id.clone();
+                let wallet_id = wallet.id.clone();
+                self.start_wallet_sync(&session_id, wallet_id, wallet, "catch_up_blocks", ctx);
+            }
+
+            return Ok(());
+        }
+
+        // The block is scanned once for every wallet, which only walks its transactions if the
+        // scan finds something that may belong to the wallet
+        let scan = Arc::new(repository::BlockScan::new(&block));
+        self.params
+            .block_processing
+            .lock()
+            .expect("Metrics locks should only fail if poisoned")
+            .blocks_scanned += 1;
 
         for wallet in &wallets {
             let sink = self.state.get_sink(&wallet.session_id);
-            self.handle_block_in_worker(block.clone(), wallet, sink.clone());
+            self.handle_block_in_worker(block.clone(), scan.clone(), wallet, sink);
         }
 
         Ok(())
@@ -622,66 +1485,94 @@ impl App {
 
     /// Handle superblock notifications received from a Witnet node.
     pub fn handle_superblock_notification(&mut self, value: serde_json::Value) -> Result<()> {
-        let superblock_notification =
-            serde_json::from_value::<types::SuperBlockNotification>(value).map_err(node_error)?;
+        let superblock_notification = Arc::new(
+            serde_json::from_value::<types::SuperBlockNotification>(value).map_err(node_error)?,
+        );
 
         // This iterator is collected early so as to free the immutable reference to `self`.
         let wallets: Vec<types::SessionWallet> = self.state.wallets.values().cloned().collect();
 
         for wallet in &wallets {
             let sink = self.state.get_sink(&wallet.session_id);
-            self.handle_superblock_in_worker(
-                superblock_notification.clone(),
-                wallet.clone(),
-                sink.clone(),
-            );
+            self.handle_superblock_in_worker(superblock_notification.clone(), wallet.clone(), sink);
         }
 
         Ok(())
     }
 
-    /// Offload block processing into a worker that operates on a different Arbiter than the main
-    /// server thread, so as not to lock the rest of the application.
+    /// Counters of the processing of the blocks notified by the node so far.
+    pub fn block_processing(&self) -> metrics::BlockProcessing {
+        self.params
+            .block_processing
+            .lock()
+            .expect("Metrics locks should only fail if poisoned")
+            .clone()
+    }
+
+    /// Depth of the queues of each class of work of the worker, and how much work they shed.
+    pub fn work_queues(&self) -> metrics::WorkQueues {
+        self.params.work_queues.metrics()
+    }
+
+    /// Requests of clients in flight, and how many were refused for going over the limits of their
+    /// sessions.
+    pub fn requests_in_flight(&self) -> metrics::RequestsInFlight {
+        self.params.request_limits.metrics()
+    }
+
+    /// Offload block processing into the block worker, which operates on a different Arbiter than
+    /// the main server thread, so as not to lock the rest of the application.
     pub fn handle_block_in_worker(
         &self,
         block: Arc<Block>,
+        scan: Arc<repository::BlockScan>,
         wallet: &types::SessionWallet,
         sink: types::DynamicSink,
     ) {
-        self.params.worker.do_send(HandleBlockRequest {
+        self.params.block_worker.do_send(HandleBlockRequest {
             block,
+            scan,
             wallet: wallet.clone(),
             sink,
+            ticket: self.params.work_queues.blocks.enter(),
         });
     }
 
-    /// Offload superblock processing into a worker that operates on a different Arbiter than the main
-    /// server thread, so as not to lock the rest of the application.
+    /// Offload superblock processing into the block worker, which operates on a different Arbiter
+    /// than the main server thread, so as not to lock the rest of the application.
     pub fn handle_superblock_in_worker(
         &self,
-        superblock_notification: types::SuperBlockNotification,
+        superblock_notification: Arc<types::SuperBlockNotification>,
         wallet: types::SessionWallet,
         sink: types::DynamicSink,
     ) {
-        self.params.worker.do_send(HandleSuperBlockRequest {
+        // Superblocks are never dropped, because the blocks that they consolidate would stay
+        // pending forever
+        self.params.block_worker.do_send(HandleSuperBlockRequest {
             superblock_notification,
             wallet,
             sink,
+            ticket: self.params.work_queues.blocks.enter(),
+            deadline: self.execution_deadline("consolidate_blocks", None),
         });
     }
 
-    /// Send a transaction to witnet network using the Inventory method
-    fn send_inventory_transaction(&self, txn: Transaction) -> ResponseActFuture<serde_json::Value> {
-        let method = "inventory".to_string();
-        let params = InventoryItem::Transaction(txn);
-
-        let req = jsonrpc::Request::method(method)
-            .timeout(self.params.requests_timeout)
-            .params(params)
-            .expect("params failed serialization");
+    /// Send a transaction to witnet network using the Inventory method, or only ask the node to
+    /// validate it with the validateTransaction method if `dry_run` is set.
+    fn send_inventory_transaction(
+        &self,
+        txn: Transaction,
+        dry_run: bool,
+    ) -> ResponseActFuture<serde_json::Value> {
+        let req = if dry_run {
+            jsonrpc::Request::method("validateTransaction").params(txn)
+        } else {
+            jsonrpc::Request::method("inventory").params(InventoryItem::Transaction(txn))
+        }
+        .expect("params failed serialization")
+        .timeout(self.params.requests_timeout);
         let f = self
             .get_client()
-            .actor
             .send(req)
             .flatten_err()
             .map(|res| {
@@ -697,124 +1588,778 @@ impl App {
         Box::pin(f)
     }
 
-    /// Send a transaction to the node as inventory item broadcast
-    /// and add a local pending balance movement to the wallet state.
+    /// Send a transaction to the node as inventory item broadcast, unless the spending policy of
+    /// its wallet forbids it, or asks for the sending to be confirmed with `confirm_send` first.
+    ///
+    /// If `dry_run` is set, the node is only asked whether it would accept the transaction, which
+    /// is neither broadcast nor added to the movements of the wallet.
     pub fn send_transaction(
         &self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         transaction: Transaction,
+        dry_run: bool,
     ) -> ResponseActFuture<SendTransactionResponse> {
+        if dry_run {
+            return match self
+                .state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id)
+            {
+                Ok(_wallet) => self.validate_transaction(transaction),
+                Err(err) => Box::pin(fut::err(err)),
+            };
+        }
+
         let f = fut::result(
             self.state
                 .get_wallet_by_session_and_id(&session_id, &wallet_id),
         )
         .and_then(move |wallet, slf: &mut Self, _| {
-            slf.send_inventory_transaction(transaction.clone())
-                .and_then(move |jsonrpc_result, act, _ctx| {
-                    match wallet.add_local_movement(&model::ExtendedTransaction {
-                        transaction,
-                        metadata: None,
-                    }) {
-                        Ok(balance_movement) => {
-                            let sink = act.state.get_sink(&session_id);
-                            if let Some(balance_movement) = balance_movement.clone() {
-                                // We send a notification to the client
-                                let events = Some(vec![types::Event::Movement(balance_movement)]);
-                                act.params
-                                    .worker
-                                    .do_send(NotifyStatus(wallet, sink, events));
-                            }
-                            actix::fut::ok(SendTransactionResponse {
-                                jsonrpc_result,
-                                balance_movement,
-                            })
-                        }
-                        Err(e) => {
-                            log::error!("Error while adding local pending movement: {}", e);
-
-                            actix::fut::err(Error::Internal(failure::Error::from(e)))
-                        }
+            slf.params
+                .worker
+                .send(worker::CheckSpending {
+                    wallet: wallet.clone(),
+                    transaction: transaction.clone(),
+                })
+                .flatten_err()
+                .into_actor(slf)
+                .and_then(move |spending, act, ctx| {
+                    if let Err(err) = check_spend_limit(&spending) {
+                        return Box::pin(fut::err(err)) as ResponseActFuture<_>;
+                    }
+                    if !spending.requires_confirmation {
+                        return act.broadcast_transaction(session_id, wallet, transaction, None);
                     }
+
+                    let confirmation = act.request_send_confirmation(
+                        state::PendingSend {
+                            session_id,
+                            wallet_id,
+                            transaction,
+                            replaces: None,
+                        },
+                        spending,
+                        ctx,
+                    );
+
+                    Box::pin(fut::ok(SendTransactionResponse {
+                        jsonrpc_result: serde_json::Value::Null,
+                        balance_movement: None,
+                        requires_confirmation: Some(confirmation),
+                    }))
                 })
         });
 
         Box::pin(f)
     }
 
-    /// Use wallet's master key to sign message data
-    pub fn sign_data(
+    /// Broadcast a transaction that was created and signed by another wallet, e.g. one running on
+    /// an offline machine.
+    ///
+    /// If the transaction spends the outputs of a wallet unlocked in the session, it is sent like
+    /// `send_transaction` would send it from that wallet, spending policy and pending movement
+    /// included. Otherwise it is only broadcast.
+    pub fn broadcast_raw_transaction(
         &self,
-        session_id: &types::SessionId,
-        wallet_id: &str,
-        data: String,
-        extended_pk: bool,
-    ) -> ResponseActFuture<model::ExtendedKeyedSignature> {
+        session_id: types::SessionId,
+        payload: &str,
+    ) -> ResponseActFuture<BroadcastRawTransactionResponse> {
+        let transaction = match decode_raw_transaction(payload, &self.params.consensus_constants) {
+            Ok(transaction) => transaction,
+            Err(err) => return Box::pin(fut::err(err)),
+        };
+        let owners = match self.session_wallets(Some(&session_id)).and_then(|wallets| {
+            wallets
+                .into_iter()
+                .filter_map(
+                    |(wallet_id, wallet)| match wallet.spends_own_outputs(&transaction) {
+                        Ok(true) => Some(Ok(wallet_id)),
+                        Ok(false) => None,
+                        Err(e) => Some(Err(internal_error(e))),
+                    },
+                )
+                .collect::<Result<Vec<_>>>()
+        }) {
+            Ok(owners) => owners,
+            Err(err) => return Box::pin(fut::err(err)),
+        };
+        let transaction_id = hex::encode(transaction.hash().as_ref());
+        log::info!("Broadcasting raw transaction {}", transaction_id);
+
+        let sent: ResponseActFuture<SendTransactionResponse> = match owners.as_slice() {
+            [] => Box::pin(self.send_inventory_transaction(transaction, false).map_ok(
+                |jsonrpc_result, _, _| SendTransactionResponse {
+                    jsonrpc_result,
+                    balance_movement: None,
+                    requires_confirmation: None,
+                },
+            )),
+            [wallet_id] => self.send_transaction(session_id, wallet_id.clone(), transaction, false),
+            _ => {
+                return Box::pin(fut::err(validation_error(field_error(
+                    "payload",
+                    "the transaction spends the outputs of more than one wallet of the session",
+                ))))
+            }
+        };
+        let wallet_id = owners.into_iter().next();
+
+        Box::pin(
+            sent.map_ok(move |sent, _, _| BroadcastRawTransactionResponse {
+                transaction_id,
+                wallet_id,
+                sent,
+            }),
+        )
+    }
+
+    /// Ask the node whether it would accept a transaction, failing with the validation error it
+    /// would reject it with.
+    fn validate_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> ResponseActFuture<SendTransactionResponse> {
+        let f = self.send_inventory_transaction(transaction, true).map(
+            |res: Result<serde_json::Value>, _act, _ctx| {
+                let jsonrpc_result = res?;
+                match serde_json::from_value(jsonrpc_result.clone()).map_err(node_error)? {
+                    TransactionValidation::Valid { .. } => Ok(SendTransactionResponse {
+                        jsonrpc_result,
+                        balance_movement: None,
+                        requires_confirmation: None,
+                    }),
+                    TransactionValidation::Invalid(rejection) => Err(rejection.into()),
+                }
+            },
+        );
+
+        Box::pin(f)
+    }
+
+    /// Keep a transaction until its sending is confirmed with the returned one-time token, or the
+    /// token expires.
+    fn request_send_confirmation(
+        &mut self,
+        pending: state::PendingSend,
+        spending: model::SpendingCheck,
+        ctx: &mut <Self as Actor>::Context,
+    ) -> SendConfirmation {
+        let token = hex::encode(rand::random::<[u8; 16]>());
+        log::info!(
+            "Sending {} nanowits from wallet {} waits for confirmation",
+            spending.outgoing,
+            pending.wallet_id
+        );
+        self.state.pending_sends.insert(token.clone(), pending);
+
+        let expires_in = constants::SEND_CONFIRMATION_TTL_SECS;
+        let expired_token = token.clone();
+        self.params
+            .clock
+            .sleep(std::time::Duration::from_secs(expires_in))
+            .into_actor(self)
+            .map(move |(), act: &mut Self, _ctx| {
+                if act.state.pending_sends.remove(&expired_token).is_some() {
+                    log::info!("A transaction was discarded because its sending was not confirmed");
+                }
+            })
+            .spawn(ctx);
+
+        SendConfirmation {
+            token,
+            expires_in,
+            spending,
+        }
+    }
+
+    /// Send a transaction that was waiting for a confirmation, as long as it still fits in the
+    /// daily spend limit of its wallet. Tokens can only be used once, and only in the session that
+    /// got them.
+    pub fn confirm_send(
+        &mut self,
+        session_id: types::SessionId,
+        token: String,
+    ) -> ResponseActFuture<SendTransactionResponse> {
+        let pending = match self.state.pending_sends.get(&token) {
+            Some(pending) if pending.session_id == session_id => {
+                self.state.pending_sends.remove(&token)
+            }
+            _ => None,
+        };
+        let state::PendingSend {
+            session_id,
+            wallet_id,
+            transaction,
+            replaces,
+        } = match pending {
+            Some(pending) => pending,
+            None => return Box::pin(fut::err(Error::SendConfirmationNotFound)),
+        };
+
         let f = fut::result(
             self.state
-                .get_wallet_by_session_and_id(session_id, wallet_id),
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
         )
         .and_then(move |wallet, slf: &mut Self, _| {
             slf.params
                 .worker
-                .send(worker::SignData {
-                    wallet,
-                    data,
-                    extended_pk,
+                .send(worker::CheckSpending {
+                    wallet: wallet.clone(),
+                    transaction: transaction.clone(),
                 })
                 .flatten_err()
                 .into_actor(slf)
+                .and_then(
+                    move |spending, act, _ctx| match check_spend_limit(&spending) {
+                        Ok(()) => {
+                            act.broadcast_transaction(session_id, wallet, transaction, replaces)
+                        }
+                        Err(err) => Box::pin(fut::err(err)),
+                    },
+                )
         });
 
         Box::pin(f)
     }
 
-    /// Shutdown system if session id is valid or there are no open sessions
-    pub fn shutdown_request(
-        &mut self,
-        session_id: Option<types::SessionId>,
-        ctx: &mut <Self as Actor>::Context,
-    ) -> Result<()> {
-        // Check if valid id or no open session(s)
-        if let Some(session_id) = session_id {
-            self.state.get_wallets_by_session(&session_id)?;
-        } else if !self.state.sessions.is_empty() {
-            return Err(app::Error::SessionsStillOpen);
-        }
-        self.stop(ctx);
+    /// Broadcast a transaction and add a local pending balance movement to the wallet state.
+    ///
+    /// If the transaction `replaces` a pending one with a higher fee, the movement of the pending
+    /// one is marked as superseded.
+    fn broadcast_transaction(
+        &self,
+        session_id: types::SessionId,
+        wallet: types::SessionWallet,
+        transaction: Transaction,
+        replaces: Option<Hash>,
+    ) -> ResponseActFuture<SendTransactionResponse> {
+        let f = self
+            .send_inventory_transaction(transaction.clone(), false)
+            .and_then(move |jsonrpc_result, act, _ctx| {
+                let txn = model::ExtendedTransaction {
+                    transaction,
+                    metadata: None,
+                };
+                let added = match replaces {
+                    Some(original) => wallet.replace_local_movement(&original, &txn),
+                    None => wallet.add_local_movement(&txn),
+                };
+                match added {
+                    Ok(balance_movement) => {
+                        let sink = act.state.get_sink(&session_id);
+                        if let Some(balance_movement) = balance_movement.clone() {
+                            // We send a notification to the client
+                            let events = Some(vec![types::Event::Movement(balance_movement)]);
+                            act.params
+                                .worker
+                                .do_send(NotifyStatus(wallet, sink, events));
+                        }
+                        actix::fut::ok(SendTransactionResponse {
+                            jsonrpc_result,
+                            balance_movement,
+                            requires_confirmation: None,
+                        })
+                    }
+                    Err(e) => {
+                        log::error!("Error while adding local pending movement: {}", e);
 
-        Ok(())
-    }
+                        actix::fut::err(Error::Internal(failure::Error::from(e)))
+                    }
+                }
+            });
 
-    /// Get the URL and address of an existing JsonRpcClient actor.
-    ///
-    /// This method exists for convenience in case that at some point we decide to allow changing
-    /// the `JsonRpcClient` address by putting `NodeClient` inside an `Arc<RwLock<_>>` or similar.
-    #[inline(always)]
-    pub fn get_client(&self) -> Arc<NodeClient> {
-        self.params.client.clone()
+        Box::pin(f)
     }
 
-    /// Subscribe to receiving real time notifications of a specific type from a Witnet node.
-    pub fn node_subscribe(&self, method: &str, ctx: &mut <Self as Actor>::Context) {
-        let recipient = ctx.address().recipient();
-
-        let request = jsonrpc::Request::method("witnet_subscribe")
-            .timeout(self.params.requests_timeout)
-            .value(serde_json::to_value([method]).expect(
-                "Any JSON-RPC method name should be serializable using `serde_json::to_value`",
-            ));
+    /// Replace a pending value transfer of a wallet with a copy that pays a higher fee, and send
+    /// the replacement like `send_transaction` would, spending policy included.
+    pub fn bump_fee(
+        &self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        transaction_hash: Hash,
+        fee: Fee,
+        add_inputs: bool,
+        allow_low_fee: bool,
+    ) -> ResponseActFuture<BumpFeeResponse> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::BumpFee {
+                    wallet: wallet.clone(),
+                    transaction_hash,
+                    fee,
+                    add_inputs,
+                    allow_low_fee,
+                })
+                .flatten_err()
+                .into_actor(slf)
+                .and_then(
+                    move |worker::BumpFeeResponse {
+                              fee,
+                              transaction,
+                              spending,
+                          },
+                          act,
+                          ctx| {
+                        if let Err(err) = check_spend_limit(&spending) {
+                            return Box::pin(fut::err(err)) as ResponseActFuture<_>;
+                        }
+                        let transaction = transaction.transaction;
+                        let transaction_id = hex::encode(transaction.hash().as_ref());
+                        if !spending.requires_confirmation {
+                            let f = act
+                                .broadcast_transaction(
+                                    session_id,
+                                    wallet,
+                                    transaction,
+                                    Some(transaction_hash),
+                                )
+                                .map_ok(move |sent, _, _| BumpFeeResponse {
+                                    transaction_id,
+                                    fee,
+                                    sent,
+                                });
+
+                            return Box::pin(f);
+                        }
 
-        log::debug!("Subscribing to {} notifications: {:?}", method, request);
+                        let confirmation = act.request_send_confirmation(
+                            state::PendingSend {
+                                session_id,
+                                wallet_id,
+                                transaction,
+                                replaces: Some(transaction_hash),
+                            },
+                            spending,
+                            ctx,
+                        );
+
+                        Box::pin(fut::ok(BumpFeeResponse {
+                            transaction_id,
+                            fee,
+                            sent: SendTransactionResponse {
+                                jsonrpc_result: serde_json::Value::Null,
+                                balance_movement: None,
+                                requires_confirmation: Some(confirmation),
+                            },
+                        }))
+                    },
+                )
+        });
 
-        self.get_client()
-            .actor
-            .do_send(jsonrpc::Subscribe(request, recipient));
+        Box::pin(f)
     }
 
-    /// Send syncStatus request to the node every 10 seconds and send
+    /// Replace the spending policy of a wallet, which requires the password of the wallet.
+    pub fn update_wallet_policy(
+        &self,
+        session_id: &types::SessionId,
+        wallet_id: model::WalletId,
+        password: types::Password,
+        policy: model::WalletPolicy,
+    ) -> ResponseActFuture<()> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::UpdateWalletPolicy {
+                    wallet_id,
+                    wallet,
+                    password,
+                    policy,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Replace which transactions nodes can have a wallet sign on their behalf, which requires the
+    /// password of the wallet.
+    pub fn update_witness_signing_policy(
+        &self,
+        session_id: &types::SessionId,
+        wallet_id: model::WalletId,
+        password: types::Password,
+        policy: model::WitnessSigningPolicy,
+    ) -> ResponseActFuture<()> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::UpdateWitnessSigningPolicy {
+                    wallet_id,
+                    wallet,
+                    password,
+                    policy,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Replace the webhook of a wallet, or remove it, which requires the password of the wallet.
+    pub fn update_wallet_webhook(
+        &self,
+        session_id: &types::SessionId,
+        wallet_id: model::WalletId,
+        password: types::Password,
+        webhook: Option<model::WebhookConfig>,
+    ) -> ResponseActFuture<()> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::UpdateWalletWebhook {
+                    wallet_id,
+                    wallet,
+                    password,
+                    webhook,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Pending deliveries and dead letters of the webhook of a wallet.
+    pub fn webhook_deliveries(
+        &self,
+        session_id: &types::SessionId,
+        wallet_id: model::WalletId,
+    ) -> ResponseActFuture<model::WebhookDeliveries> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .webhooks
+                .send(webhooks::GetDeliveries {
+                    outbox: wallet.webhook_outbox().clone(),
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Requeue dead letters of the webhook of a wallet, returning how many were requeued.
+    pub fn requeue_webhook_deliveries(
+        &self,
+        session_id: &types::SessionId,
+        wallet_id: model::WalletId,
+        ids: Option<Vec<u64>>,
+    ) -> ResponseActFuture<usize> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .webhooks
+                .send(webhooks::RequeueDeliveries {
+                    outbox: wallet.webhook_outbox().clone(),
+                    ids,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Use wallet's master key to sign message data, in the given domain or raw if there is none
+    pub fn sign_data(
+        &self,
+        session_id: &types::SessionId,
+        wallet_id: &model::WalletId,
+        data: String,
+        domain: Option<model::SigningDomain>,
+        extended_pk: bool,
+    ) -> ResponseActFuture<model::SignedData> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(session_id, wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::SignData {
+                    wallet,
+                    data,
+                    domain,
+                    extended_pk,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Sign a commit or a reveal on behalf of a node with the key of a wallet
+    pub fn sign_witness_transaction(
+        &self,
+        session_id: &types::SessionId,
+        wallet_id: &model::WalletId,
+        body: WitnessTransactionBody,
+    ) -> ResponseActFuture<(KeyedSignature, model::WitnessSigningAuditEntry)> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(session_id, wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::SignWitnessTransaction { wallet, body })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Shutdown system if session id is valid or there are no open sessions
+    pub fn shutdown_request(
+        &mut self,
+        session_id: Option<types::SessionId>,
+        ctx: &mut <Self as Actor>::Context,
+    ) -> Result<()> {
+        // Check if valid id or no open session(s)
+        if let Some(session_id) = session_id {
+            self.state.get_wallets_by_session(&session_id)?;
+        } else if !self.state.sessions.is_empty() {
+            return Err(app::Error::SessionsStillOpen);
+        }
+        self.stop(ctx);
+
+        Ok(())
+    }
+
+    /// Get the client of the node, which sends every request through the least busy of its
+    /// connections.
+    #[inline(always)]
+    pub fn get_client(&self) -> Arc<NodeClient> {
+        self.params.client.clone()
+    }
+
+    /// Subscribe to receiving real time notifications of a specific type from a Witnet node.
+    pub fn node_subscribe(&self, method: &str, ctx: &mut <Self as Actor>::Context) {
+        let recipient = ctx.address().recipient();
+
+        let request = jsonrpc::Request::method("witnet_subscribe")
+            .timeout(self.params.requests_timeout)
+            .value(serde_json::to_value([method]).expect(
+                "Any JSON-RPC method name should be serializable using `serde_json::to_value`",
+            ));
+
+        log::debug!("Subscribing to {} notifications: {:?}", method, request);
+
+        self.get_client()
+            .subscribe(jsonrpc::Subscribe(request, recipient));
+    }
+
+    /// Send syncStatus request to the node every 10 seconds and send
     /// NodeDisconnected event if error in the response
     pub fn periodic_node_request(&self, ctx: &mut <Self as Actor>::Context) {
+        self.node_status_request(ctx);
+
+        // Try to contact the node once every 15 seconds
+        let duration = std::time::Duration::from_secs(15);
+        self.params
+            .clock
+            .sleep(duration)
+            .into_actor(self)
+            .map(|(), act, ctx| act.periodic_node_request(ctx))
+            .spawn(ctx);
+    }
+
+    /// Count an epoch in the watchdog of the node once every checkpoints period, alerting clients
+    /// if the node made no progress for too long. Epochs are not counted while the node is
+    /// disconnected, which clients are already alerted about.
+    pub fn periodic_node_watchdog(&mut self, ctx: &mut <Self as Actor>::Context) {
+        if self.state.node_state.is_some() {
+            let alert = self.state.node_watchdog.tick();
+            self.notify_node_alerts(alert);
+        }
+
+        let period = u64::from(self.params.consensus_constants.checkpoints_period).max(1);
+        self.params
+            .clock
+            .sleep(std::time::Duration::from_secs(period))
+            .into_actor(self)
+            .map(|(), act, ctx| act.periodic_node_watchdog(ctx))
+            .spawn(ctx);
+    }
+
+    /// Log the changes of the liveness of the node, and notify them to the clients of all the
+    /// unlocked wallets.
+    pub fn notify_node_alerts<I>(&mut self, alerts: I)
+    where
+        I: IntoIterator<Item = watchdog::Alert>,
+    {
+        let events: Vec<types::Event> = alerts
+            .into_iter()
+            .map(|alert| match alert {
+                watchdog::Alert::Stalled(epochs) => {
+                    log::warn!("The node made no progress for {} epochs", epochs);
+                    types::Event::NodeStalled(epochs)
+                }
+                watchdog::Alert::Resumed => {
+                    log::info!("The node is making progress again");
+                    types::Event::NodeResumed
+                }
+                watchdog::Alert::ClockSkew(epochs) => {
+                    log::warn!(
+                        "The node is {} epochs ahead of the clock of the wallet, one of their clocks is wrong",
+                        epochs
+                    );
+                    types::Event::NodeClockSkew(epochs)
+                }
+            })
+            .collect();
+        if events.is_empty() {
+            return;
+        }
+
+        let wallets: Vec<types::SessionWallet> = self.state.wallets.values().cloned().collect();
+        for wallet in wallets {
+            let sink = self.state.get_sink(&wallet.session_id);
+            self.params
+                .worker
+                .do_send(NotifyStatus(wallet, sink, Some(events.clone())));
+        }
+    }
+
+    /// Expire the payment requests of all the unlocked wallets that were not paid in time, once
+    /// every minute. Wallets are skipped until the next check while the maintenance worker is
+    /// overloaded.
+    pub fn periodic_payment_requests_check(&mut self, ctx: &mut <Self as Actor>::Context) {
+        let wallets: Vec<types::SessionWallet> = self.state.wallets.values().cloned().collect();
+        for wallet in wallets {
+            let ticket = match self.params.work_queues.maintenance.try_enter() {
+                Ok(ticket) => ticket,
+                Err(e) => {
+                    log::debug!("Skipping the expiration of payment requests: {}", e);
+                    continue;
+                }
+            };
+            let sink = self.state.get_sink(&wallet.session_id);
+            let expire = self
+                .params
+                .maintenance_worker
+                .send(worker::ExpirePaymentRequests { wallet, sink });
+            ctx.spawn(ticket.hold(expire).map(drop).into_actor(self));
+        }
+
+        let duration = std::time::Duration::from_secs(60);
+        ctx.run_later(duration, |act, ctx| {
+            act.periodic_payment_requests_check(ctx)
+        });
+    }
+
+    /// Prune the metadata of the old movements of all the unlocked wallets according to their
+    /// retention policies, once every `prune_interval`. Wallets are skipped until the next turn
+    /// while the maintenance worker is overloaded.
+    pub fn periodic_prune(&mut self, ctx: &mut <Self as Actor>::Context) {
+        let prune_interval = match self.params.prune_interval {
+            Some(prune_interval) => prune_interval,
+            None => return,
+        };
+
+        let wallets: Vec<types::SessionWallet> = self.state.wallets.values().cloned().collect();
+        for wallet in wallets {
+            let ticket = match self.params.work_queues.maintenance.try_enter() {
+                Ok(ticket) => ticket,
+                Err(e) => {
+                    log::debug!("Skipping the pruning of wallet {}: {}", wallet.id, e);
+                    continue;
+                }
+            };
+            let wallet_id = wallet.id.clone();
+            let prune = self
+                .params
+                .maintenance_worker
+                .send(worker::PruneWalletData {
+                    wallet,
+                    policy: None,
+                });
+            ticket
+                .hold(prune.flatten_err())
+                .into_actor(self)
+                .map(move |res: Result<model::PruneReport>, _, _| {
+                    if let Err(e) = res {
+                        log::error!("Failed to prune the data of wallet {}: {}", wallet_id, e);
+                    }
+                })
+                .spawn(ctx);
+        }
+
+        ctx.run_later(prune_interval, |act, ctx| act.periodic_prune(ctx));
+    }
+
+    /// Deliver the notifications queued for clients that were not reading them, once every
+    /// `NOTIFICATIONS_FLUSH_INTERVAL_MS`. Otherwise they would wait for the next notification of
+    /// their session, which may never come.
+    pub fn periodic_notifications_flush(&mut self, ctx: &mut <Self as Actor>::Context) {
+        self.state.flush_sinks();
+
+        let duration = std::time::Duration::from_millis(constants::NOTIFICATIONS_FLUSH_INTERVAL_MS);
+        ctx.run_later(duration, |act, ctx| act.periodic_notifications_flush(ctx));
+    }
+
+    /// Lock every wallet once no authenticated request has been received for `idle_lock_after`,
+    /// checking again when the timer could expire next. The wallets of the service session are
+    /// kept unlocked.
+    pub fn periodic_idle_lock_check(&mut self, ctx: &mut <Self as Actor>::Context) {
+        let idle_lock_after = match self.params.idle_lock_after {
+            Some(idle_lock_after) => idle_lock_after,
+            None => return,
+        };
+
+        let idle_for = std::time::Duration::from_secs(self.state.idle_for(get_timestamp()));
+        let duration = if idle_for >= idle_lock_after {
+            if self.state.has_idle_wallets() {
+                let locked = self.state.lock_idle_wallets();
+                log::info!(
+                    "No authenticated requests for {} seconds, locked {} wallets",
+                    idle_for.as_secs(),
+                    locked
+                );
+            }
+
+            idle_lock_after
+        } else {
+            idle_lock_after - idle_for
+        };
+        ctx.run_later(duration, |act, ctx| act.periodic_idle_lock_check(ctx));
+    }
+
+    /// Send a syncStatus request through every connection to the node, which doubles as their
+    /// health check. The node only counts as failed for the failover if all of them fail.
+    ///
+    /// Wallets are synchronized if the node status changed to `Synced`.
+    pub fn node_status_request(&self, ctx: &mut <Self as Actor>::Context) {
         let wallets: Vec<types::SessionWallet> = self.state.wallets.values().cloned().collect();
 
         let wallets2 = wallets.clone();
@@ -830,10 +2375,9 @@ impl App {
 
         let f = self
             .get_client()
-            .actor
-            .send(req)
-            .flatten_err()
-            .map(|res: Result<_>| {
+            .send_to_all(req)
+            .map(|res| {
+                let res: Result<_> = res.map_err(Error::from);
                 if let Ok(res) = &res {
                     log::debug!("Periodic request result: {:?}", res);
                     let status = serde_json::from_value::<SyncStatus>(res.clone());
@@ -843,7 +2387,7 @@ impl App {
                 res
             })
             .into_actor(self)
-            .map_err(move |err, act, _ctx| {
+            .map_err(move |err, act, ctx| {
                 log::warn!("Periodic request failed: {}", &err);
                 log::error!("The node is disconnected");
                 // Update node_state
@@ -855,24 +2399,48 @@ impl App {
                         .worker
                         .do_send(NotifyStatus(wallet.clone(), sink, events.clone()))
                 }
+                // Fail over to the next node if this one has been failing for too long
+                let next_url = act.get_client().failover().record_failure(get_timestamp());
+                if let Some(url) = next_url {
+                    act.connect_to_node(url, ctx);
+                }
             })
             .map_ok(move |res, act, ctx| {
+                act.get_client().failover().record_success();
+                if let Err(e) = act.get_client().schema.validate(&schema::SYNC_STATUS, &res) {
+                    log::error!("Periodic request result rejected: {}", e);
+                    return;
+                }
                 let status = serde_json::from_value::<SyncStatus>(res);
                 // Notify if the node status is changed
                 if let Ok(status) = status {
+                    if act.state.node_state.is_none() {
+                        act.check_node_consensus_constants(ctx);
+                    }
+                    let expected_epoch = watchdog::expected_epoch(
+                        get_timestamp(),
+                        act.params.consensus_constants.checkpoint_zero_timestamp,
+                        act.params.consensus_constants.checkpoints_period,
+                    );
+                    let alerts = act
+                        .state
+                        .node_watchdog
+                        .record_epoch(status.current_epoch, expected_epoch);
+                    act.notify_node_alerts(alerts);
                     if Some(status.node_state) != act.state.node_state {
                         // Update node_state
                         act.state.node_state = Some(status.node_state);
                         for wallet in &wallets {
                             let sink = act.state.get_sink(&wallet.session_id);
-                            act.params
-                                .worker
-                                .send(NodeStatusRequest {
-                                    status: act.state.node_state.unwrap(),
-                                    wallet: wallet.clone(),
-                                    sink,
-                                })
-                                .flatten_err()
+                            let ticket = act.params.work_queues.maintenance.enter();
+                            let status = act.params.maintenance_worker.send(NodeStatusRequest {
+                                status: act.state.node_state.unwrap(),
+                                wallet: wallet.clone(),
+                                sink,
+                                deadline: act.execution_deadline("node_sync", None),
+                            });
+                            ticket
+                                .hold(status.flatten_err())
                                 .into_actor(act)
                                 .map(|res: Result<()>, act: &mut Self, _ctx| {
                                     if let Err(e) = res {
@@ -888,16 +2456,115 @@ impl App {
             })
             .map(|_res: std::result::Result<(), ()>, _act, _ctx| ());
         ctx.spawn(f);
+    }
 
-        // Try to contact the node once every 15 seconds
-        let duration = std::time::Duration::from_secs(15);
-        ctx.run_later(duration, |act, ctx| act.periodic_node_request(ctx));
+    /// Compare the consensus constants of the node with the ones of the wallet, warning about the
+    /// mismatching ones, which are also reported by `get_node_status`.
+    pub fn check_node_consensus_constants(&self, ctx: &mut <Self as Actor>::Context) {
+        let req = jsonrpc::Request::method("getConsensusConstants".to_string())
+            .timeout(self.params.requests_timeout)
+            .params(())
+            .expect("params failed serialization");
+
+        self.get_client()
+            .send(req)
+            .flatten_err()
+            .into_actor(self)
+            .map(|res: Result<serde_json::Value>, act, _ctx| {
+                let node_constants = match res.map(serde_json::from_value::<ConsensusConstants>) {
+                    Ok(Ok(node_constants)) => node_constants,
+                    Ok(Err(e)) => {
+                        log::warn!("Failed to parse the consensus constants of the node: {}", e);
+                        return;
+                    }
+                    Err(e) => {
+                        log::warn!("Failed to get the consensus constants of the node: {}", e);
+                        return;
+                    }
+                };
+
+                let mismatch = act.params.consensus_constants.differences(&node_constants);
+                if !mismatch.is_empty() {
+                    log::warn!(
+                        "The consensus constants of the node differ from the ones of the wallet: {}",
+                        mismatch.join(", ")
+                    );
+                }
+                act.state.consensus_constants_mismatch = mismatch;
+            })
+            .spawn(ctx);
+    }
+
+    /// Make the JSON-RPC client connect to another node.
+    ///
+    /// Node subscriptions are recovered by the client, and wallets are synchronized as soon as the
+    /// new node reports that it is synced, as it happens when reconnecting to the same node.
+    pub fn connect_to_node(&mut self, url: String, ctx: &mut <Self as Actor>::Context) {
+        self.get_client()
+            .switch_url(url.clone())
+            .into_actor(self)
+            .map(move |res, act, ctx| {
+                if let Err(e) = res {
+                    log::error!("Failed to connect to node {}: {}", url, e);
+                }
+                // Forget the status of the previous node, so that any status of the new one
+                // triggers the synchronization of the wallets
+                act.state.node_state = None;
+                act.node_status_request(ctx);
+            })
+            .spawn(ctx);
+    }
+
+    /// Get the configured nodes, which one is in use, the history of switches between them and the
+    /// requests in flight through every connection.
+    pub fn get_node_status(&self) -> types::NodeStatus {
+        let client = self.get_client();
+        let failover = client.failover().status();
+
+        types::NodeStatus {
+            failover,
+            compatibility: types::NodeCompatibility {
+                consensus_constants_mismatch: self.state.consensus_constants_mismatch.clone(),
+                schema_mismatches: client.schema.mismatches(),
+            },
+            startup_unlock: self.state.startup_unlock.clone(),
+            health: types::NodeHealth {
+                connections: client.pool.status(),
+                liveness: self.state.node_watchdog.status(),
+            },
+        }
+    }
+
+    /// Force the wallet to use one of the configured nodes.
+    pub fn switch_node(
+        &mut self,
+        session_id: types::SessionId,
+        url: String,
+    ) -> ResponseActFuture<types::NodeStatus> {
+        let changed = if self.state.is_session_active(&session_id) {
+            self.get_client()
+                .failover()
+                .switch_to(&url, get_timestamp())
+                .map_err(|e| validation_error(field_error("url", e.to_string())))
+        } else {
+            Err(Error::SessionNotFound)
+        };
+
+        let f = fut::result(changed).map_ok(move |changed, slf: &mut Self, ctx| {
+            if changed {
+                slf.connect_to_node(url, ctx);
+            }
+
+            slf.get_node_status()
+        });
+
+        Box::pin(f)
     }
 
     /// Validate seed (mnemonics or xprv):
     ///  - check if seed data is valid
     ///  - check if there is already a wallet created with same seed
-    ///  - return wallet id deterministically derived from seed data
+    ///  - return the id of the wallet already created with same seed, if any
     pub fn validate_seed(
         &self,
         seed_source: String,
@@ -905,16 +2572,11 @@ impl App {
         backup_password: Option<types::Password>,
     ) -> ResponseActFuture<ValidateMnemonicsResponse> {
         // Validate mnemonics source and data
-        let f = fut::result(match seed_source.as_ref() {
-            "xprv" => validate_xprv(seed_data, backup_password),
-            "mnemonics" => mnemonic::Mnemonic::from_phrase(seed_data)
-                .map_err(|err| Error::Validation(app::field_error("seed_data", format!("{}", err))))
-                .map(types::SeedSource::Mnemonics),
-            _ => Err(Error::Validation(app::field_error(
-                "seed_source",
-                "Seed source has to be mnemonics|xprv.",
-            ))),
-        })
+        let f = fut::result(validate_seed_source(
+            &seed_source,
+            seed_data,
+            backup_password,
+        ))
         // Check if seed was already used in wallet
         .and_then(|seed, slf: &mut Self, _| {
             slf.params
@@ -930,6 +2592,22 @@ impl App {
         Box::pin(f)
     }
 
+    /// Derive addresses from a seed without creating a wallet, a session or writing anything into
+    /// the database.
+    pub fn derive_addresses(
+        &self,
+        seed_source: types::SeedSource,
+        paths: Vec<KeyPath>,
+    ) -> ResponseFuture<Vec<model::DerivedAddress>> {
+        let f = self
+            .params
+            .worker
+            .send(worker::DeriveAddresses { seed_source, paths })
+            .flatten_err();
+
+        Box::pin(f)
+    }
+
     /// Clear all chain data for a wallet state.
     ///
     /// Proceed with caution, as this wipes the following data entirely:
@@ -944,7 +2622,8 @@ impl App {
     pub fn clear_chain_data_and_resync(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
+        from: types::ResyncFrom,
     ) -> ResponseActFuture<bool> {
         let f = fut::result(
             self.state
@@ -953,13 +2632,433 @@ impl App {
         .and_then(move |wallet, slf: &mut Self, _| {
             let sink = slf.state.get_sink(&session_id);
 
-            // Send `Resync` message to worker
+            // Send `Resync` message to the maintenance worker
+            let ticket = slf.params.work_queues.maintenance.enter();
+            let resync = slf.params.maintenance_worker.send(worker::Resync {
+                wallet_id,
+                wallet,
+                from,
+                sink,
+                deadline: slf.execution_deadline("resync_wallet", None),
+            });
+            ticket.hold(resync.flatten_err()).into_actor(slf).map_err(
+                |e: Error, slf: &mut Self, _| {
+                    slf.handle_sync_error(&e);
+                    e
+                },
+            )
+        });
+
+        Box::pin(f)
+    }
+
+    /// Check the integrity of the records of a wallet, repairing the ones that can be derived from
+    /// others if `repair` is true.
+    pub fn check_integrity(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        repair: bool,
+    ) -> ResponseActFuture<model::IntegrityReport> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            let sink = slf.state.get_sink(&session_id);
+
             slf.params
                 .worker
-                .send(worker::Resync {
+                .send(worker::CheckIntegrity {
+                    wallet,
+                    repair,
+                    sink,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Import the labels and account tags of the external addresses of a wallet from a mapping.
+    /// The whole mapping is rejected if any of its addresses is not the one derived at its index.
+    pub fn import_address_metadata(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        payload: ImportPayload,
+    ) -> ResponseActFuture<model::AddressMetadataImport> {
+        let entries = match payload.into_entries() {
+            Ok(entries) => entries,
+            Err(err) => {
+                return Box::pin(fut::result(Err(validation_error(field_error(
+                    "payload", err,
+                )))))
+            }
+        };
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            let sink = slf.state.get_sink(&session_id);
+
+            slf.params
+                .worker
+                .send(worker::ImportAddressMetadata {
+                    wallet,
+                    entries,
+                    sink,
+                })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Prune the metadata of the old movements of a wallet according to its retention policy,
+    /// replacing the policy first if one is given.
+    pub fn prune_wallet_data(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        policy: Option<model::RetentionPolicy>,
+    ) -> ResponseActFuture<model::PruneReport> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::PruneWalletData { wallet, policy })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Gather a diagnostics bundle of the wallets unlocked in a session, or only of one of them if
+    /// `wallet_id` is given. The sensitive data of that wallet is included if its password is
+    /// given, and otherwise everything that could identify the user is redacted.
+    pub fn create_diagnostics_bundle(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: Option<model::WalletId>,
+        password: Option<types::Password>,
+    ) -> ResponseActFuture<diagnostics::DiagnosticsBundle> {
+        let wallets = match &wallet_id {
+            Some(wallet_id) => self
+                .state
+                .get_wallet_by_session_and_id(&session_id, wallet_id)
+                .map(|wallet| vec![(wallet_id.clone(), wallet)]),
+            None => self
+                .state
+                .get_wallets_by_session(&session_id)
+                .map(|wallets| {
+                    wallets
+                        .iter()
+                        .map(|(id, wallet)| (id.clone(), wallet.clone()))
+                        .collect()
+                }),
+        };
+        let sensitive = match (wallet_id, password) {
+            (Some(wallet_id), Some(password)) => Ok(Some((wallet_id, password))),
+            (None, Some(_)) => Err(validation_error(field_error(
+                "wallet_id",
+                "Sensitive data can only be included for a single wallet",
+            ))),
+            (_, None) => Ok(None),
+        };
+        let include_sensitive = matches!(sensitive, Ok(Some(_)));
+        let requested_by = session_id.clone();
+
+        let request = wallets.and_then(|wallets| sensitive.map(|sensitive| (wallets, sensitive)));
+
+        let f = fut::result(request)
+            .and_then(|(wallets, sensitive), slf: &mut Self, _| {
+                slf.params
+                    .worker
+                    .send(worker::Diagnostics { wallets, sensitive })
+                    .flatten_err()
+                    .into_actor(slf)
+            })
+            .map_ok(move |gathered, slf: &mut Self, _| {
+                slf.diagnostics_bundle(gathered, include_sensitive, &requested_by)
+            });
+
+        Box::pin(f)
+    }
+
+    /// Write a diagnostics bundle to a new file from the worker, because the disk must never block
+    /// the actor.
+    pub fn write_diagnostics_bundle(
+        &self,
+        path: PathBuf,
+        bundle: diagnostics::DiagnosticsBundle,
+    ) -> ResponseActFuture<()> {
+        let f = self
+            .params
+            .worker
+            .send(worker::WriteDiagnosticsBundle { path, bundle })
+            .into_actor(self)
+            .map(|res, _, _| match res {
+                Ok(res) => res.map_err(|e| validation_error(field_error("output_path", e))),
+                Err(e) => Err(e.into()),
+            });
+
+        Box::pin(f)
+    }
+
+    /// Complete what the worker gathered for a diagnostics bundle with the state of the actor.
+    ///
+    /// Sensitive data is only included for the requests made in the session that asked for it.
+    fn diagnostics_bundle(
+        &self,
+        gathered: diagnostics::WorkerDiagnostics,
+        include_sensitive: bool,
+        session_id: &types::SessionId,
+    ) -> diagnostics::DiagnosticsBundle {
+        let mut requests: Vec<_> = self.state.request_log.entries().cloned().collect();
+        for request in &mut requests {
+            if !include_sensitive || request.session_id.as_ref() != Some(session_id) {
+                diagnostics::redact_request(request);
+            }
+        }
+        let latency = self
+            .state
+            .storage_latency
+            .lock()
+            .expect("Metrics locks should only fail if poisoned")
+            .clone();
+
+        diagnostics::DiagnosticsBundle {
+            created_at: get_timestamp(),
+            build: diagnostics::BuildInfo::current(),
+            config: self.params.redacted_config.clone(),
+            node: diagnostics::NodeDiagnostics {
+                status: diagnostics::redacted_node_status(&self.get_node_status()),
+                state: self.state.node_state,
+                testnet: self.params.testnet,
+            },
+            wallets: gathered.wallets,
+            storage: diagnostics::StorageDiagnostics {
+                latency,
+                queues: self.params.work_queues.metrics(),
+                ..gathered.storage
+            },
+            requests,
+            request_counters: self.state.request_log.counters().clone(),
+            requests_in_flight: self.requests_in_flight(),
+            sensitive: gathered.sensitive,
+        }
+    }
+
+    /// Record a request handled by the JSON-RPC server in the request log.
+    pub fn record_request(&mut self, entry: metrics::RequestLogEntry) {
+        self.state.request_log.record(entry);
+    }
+
+    /// Tell whether a request made with an idempotency key runs, gets the response to an earlier
+    /// request with the same key, or waits for that request to be answered. Requests that run
+    /// are recorded in their wallet before running, so that a daemon that stops midway does not
+    /// run them again.
+    pub fn begin_idempotent_request(
+        &mut self,
+        request: idempotency::IdempotentRequest,
+    ) -> ResponseActFuture<Idempotency> {
+        let wallet = match self
+            .state
+            .get_wallet_by_session_and_id(&request.session_id, &request.wallet_id)
+        {
+            Ok(wallet) => wallet,
+            Err(err) => return Box::pin(fut::err(err)),
+        };
+        let record_key = request.record_key();
+
+        // Retries begin again once the request is done, and only then are their params checked
+        if let Some(in_flight) = self.state.idempotent_in_flight.get_mut(&record_key) {
+            let (waiter, wait) = futures::channel::oneshot::channel();
+            in_flight.waiters.push(waiter);
+
+            return Box::pin(fut::ok(Idempotency::Wait(wait)));
+        }
+
+        // The request is in flight while its record is looked up, so that concurrent retries wait
+        // for it instead of running too
+        let operation = model::IdempotentOperation {
+            method: request.method.to_string(),
+            params_hash: request.params_hash.clone(),
+            recorded_at: self.params.clock.timestamp(),
+            response: None,
+        };
+        self.state.idempotent_in_flight.insert(
+            record_key.clone(),
+            state::InFlightIdempotent {
+                wallet: wallet.clone(),
+                operation: operation.clone(),
+                waiters: vec![],
+            },
+        );
+        let expired_before = self.idempotency_expired_before();
+
+        let key = record_key.clone();
+        let f = self
+            .params
+            .worker
+            .send(worker::GetIdempotentOperation {
+                wallet: wallet.clone(),
+                key: key.clone(),
+                expired_before,
+            })
+            .flatten_err()
+            .into_actor(self)
+            .and_then(move |recorded, slf: &mut Self, _| match recorded {
+                Some(recorded) => Box::pin(fut::result(replay_idempotent(recorded, &request)))
+                    as ResponseActFuture<_>,
+                None => Box::pin(
+                    slf.params
+                        .worker
+                        .send(worker::RecordIdempotentOperation {
+                            wallet,
+                            key,
+                            operation,
+                            expired_before,
+                        })
+                        .flatten_err()
+                        .into_actor(slf)
+                        .map_ok(|(), _, _| Idempotency::Run),
+                ),
+            })
+            .map(move |result, slf: &mut Self, _| {
+                // Requests that do not run are no longer in flight, and the retries waiting for
+                // them begin again
+                if !matches!(result, Ok(Idempotency::Run)) {
+                    slf.state.idempotent_in_flight.remove(&record_key);
+                }
+
+                result
+            });
+
+        Box::pin(f)
+    }
+
+    /// Record the outcome of a request made with an idempotency key, and wake up the retries
+    /// waiting for it.
+    pub fn finish_idempotent_request(
+        &mut self,
+        record_key: String,
+        outcome: IdempotentOutcome,
+    ) -> ResponseActFuture<()> {
+        let in_flight = match self.state.idempotent_in_flight.get(&record_key) {
+            Some(in_flight) => in_flight,
+            None => return Box::pin(fut::ok(())),
+        };
+        let wallet = in_flight.wallet.clone();
+        let recorded = match outcome {
+            IdempotentOutcome::Answered(response) => {
+                let operation = model::IdempotentOperation {
+                    response: Some(response.to_string()),
+                    ..in_flight.operation.clone()
+                };
+                Box::pin(
+                    self.params
+                        .worker
+                        .send(worker::RecordIdempotentOperation {
+                            wallet,
+                            key: record_key.clone(),
+                            operation,
+                            expired_before: self.idempotency_expired_before(),
+                        })
+                        .flatten_err()
+                        .into_actor(self),
+                ) as ResponseActFuture<_>
+            }
+            IdempotentOutcome::Failed => Box::pin(
+                self.params
+                    .worker
+                    .send(worker::ForgetIdempotentOperation {
+                        wallet,
+                        key: record_key.clone(),
+                    })
+                    .flatten_err()
+                    .into_actor(self),
+            ),
+            // The record without a response is kept, so that retries are refused
+            IdempotentOutcome::Interrupted => Box::pin(fut::ok(())),
+        };
+
+        let f = recorded.map(move |result, slf: &mut Self, _| {
+            if let Err(err) = &result {
+                log::error!(
+                    "Failed to record the outcome of a request made with an idempotency key: {}",
+                    err
+                );
+            }
+            // The retries are only woken up once the outcome is recorded, for them to find it
+            slf.state.idempotent_in_flight.remove(&record_key);
+
+            result
+        });
+
+        Box::pin(f)
+    }
+
+    /// Timestamp before which the requests made with an idempotency key are expired.
+    fn idempotency_expired_before(&self) -> i64 {
+        let retention =
+            i64::try_from(self.params.idempotency_key_retention.as_secs()).unwrap_or(i64::MAX);
+
+        self.params.clock.timestamp().saturating_sub(retention)
+    }
+
+    /// How far the synchronization of a wallet has got.
+    pub fn get_sync_status(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+    ) -> ResponseActFuture<model::SyncWatermark> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            slf.params
+                .worker
+                .send(worker::GetSyncStatus { wallet })
+                .flatten_err()
+                .into_actor(slf)
+        });
+
+        Box::pin(f)
+    }
+
+    /// Pin the synchronization of a wallet to an epoch, or unpin it if `epoch` is `None`, and
+    /// synchronize it up to its new target.
+    pub fn pin_sync(
+        &mut self,
+        session_id: types::SessionId,
+        wallet_id: model::WalletId,
+        epoch: Option<u32>,
+    ) -> ResponseActFuture<model::SyncWatermark> {
+        let f = fut::result(
+            self.state
+                .get_wallet_by_session_and_id(&session_id, &wallet_id),
+        )
+        .and_then(move |wallet, slf: &mut Self, _| {
+            let sink = slf.state.get_sink(&session_id);
+
+            slf.params
+                .worker
+                .send(worker::PinSync {
                     wallet_id,
                     wallet,
+                    epoch,
                     sink,
+                    deadline: slf.execution_deadline("pin_sync", None),
                 })
                 .flatten_err()
                 .into_actor(slf)
@@ -976,7 +3075,7 @@ impl App {
     pub fn export_master_key(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         password: types::Password,
     ) -> ResponseActFuture<String> {
         let f = fut::result(
@@ -986,7 +3085,12 @@ impl App {
         .and_then(move |wallet, slf: &mut Self, _| {
             slf.params
                 .worker
-                .send(worker::ExportMasterKey { wallet, password })
+                .send(worker::ExportMasterKey {
+                    wallet,
+                    password,
+                    deadline: slf
+                        .execution_deadline("export_master_key", Some(slf.params.requests_timeout)),
+                })
                 .flatten_err()
                 .into_actor(slf)
         });
@@ -994,6 +3098,22 @@ impl App {
         Box::pin(f)
     }
 
+    /// Deadline for the execution of a long-running method, using the configured timeout for that
+    /// method or else the provided default.
+    pub fn execution_deadline(
+        &self,
+        method: &str,
+        default: Option<std::time::Duration>,
+    ) -> Deadline {
+        self.params
+            .execution_timeouts
+            .get(method)
+            .copied()
+            .or(default)
+            .map(Deadline::after)
+            .unwrap_or_default()
+    }
+
     /// Handle status from sync error
     pub fn handle_sync_error(&mut self, e: &Error) {
         if let Error::JsonRpcTimeout = e {
@@ -1005,6 +3125,44 @@ impl App {
     }
 }
 
+/// Fail if sending a transaction would exceed the daily spend limit of its wallet.
+pub fn check_spend_limit(spending: &model::SpendingCheck) -> Result<()> {
+    match (spending.daily_spend_limit, spending.remaining_allowance) {
+        (Some(limit), Some(remaining)) if !spending.within_limit() => {
+            Err(Error::SpendLimitExceeded {
+                limit,
+                spent: spending.spent,
+                requested: spending.outgoing,
+                remaining,
+            })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Answer a request made with an idempotency key with the response to the request recorded under
+/// its key, failing if it is not a retry of that request but another request that reuses the key,
+/// or if that request was interrupted before being answered.
+fn replay_idempotent(
+    recorded: model::IdempotentOperation,
+    request: &idempotency::IdempotentRequest,
+) -> Result<Idempotency> {
+    if recorded.method != request.method || recorded.params_hash != request.params_hash {
+        return Err(Error::IdempotencyKeyConflict {
+            method: recorded.method,
+        });
+    }
+
+    match recorded.response {
+        Some(response) => serde_json::from_str(&response)
+            .map(Idempotency::Replay)
+            .map_err(internal_error),
+        None => Err(Error::IdempotentRequestInterrupted {
+            recorded_at: recorded.recorded_at,
+        }),
+    }
+}
+
 // Validate `CreateWalletRequest`.
 ///
 /// To be valid it must pass these checks:
@@ -1052,6 +3210,24 @@ pub fn validate(
     .map_err(validation_error)
 }
 
+/// Validate a seed source (mnemonics or xprv) and turn it into a `SeedSource`.
+pub fn validate_seed_source(
+    seed_source: &str,
+    seed_data: types::Password,
+    backup_password: Option<types::Password>,
+) -> Result<types::SeedSource> {
+    match seed_source {
+        "xprv" => validate_xprv(seed_data, backup_password),
+        "mnemonics" => mnemonic::Mnemonic::from_phrase(seed_data)
+            .map_err(|err| Error::Validation(app::field_error("seed_data", format!("{}", err))))
+            .map(types::SeedSource::Mnemonics),
+        _ => Err(Error::Validation(app::field_error(
+            "seed_source",
+            "Seed source has to be mnemonics|xprv.",
+        ))),
+    }
+}
+
 /// Validate an encrypted XPRV file, first decrypting it and then checking the key format
 /// The seed data contains the hrp||iv||salt||ciphertext
 /// hrp can be either 'xprv' or 'xprvoduble'
@@ -1118,3 +3294,44 @@ pub fn split_xprv_double(xprv_double_key: String) -> Result<(types::Password, ty
     let (internal, external) = xprv_double_key.split_at(ocurrences[1].0);
     Ok((internal.into(), external.into()))
 }
+
+/// Beacons of the blocks listed by the `getBlockChain` method of a node.
+fn chain_beacons(json: serde_json::Value) -> Result<Vec<model::Beacon>> {
+    serde_json::from_value::<Vec<types::ChainEntry>>(json)
+        .map_err(node_error)?
+        .iter()
+        .map(|entry| {
+            let beacon = CheckpointBeacon::try_from(entry).map_err(node_error)?;
+
+            Ok(model::Beacon {
+                epoch: beacon.checkpoint,
+                block_hash: beacon.hash_prev_block,
+            })
+        })
+        .collect()
+}
+
+/// Summaries of a list of blocks, with the transactions of each wallet that they include if
+/// `own_transactions` is given.
+fn block_summaries(
+    blocks: &[model::Beacon],
+    own_transactions: Option<&[(model::WalletId, HashMap<Hash, Vec<String>>)]>,
+) -> GetBlocksResponse {
+    blocks
+        .iter()
+        .map(|block| BlockSummary {
+            epoch: block.epoch,
+            hash: block.block_hash.to_string(),
+            own_transactions: own_transactions.map(|own_transactions| {
+                own_transactions
+                    .iter()
+                    .filter_map(|(wallet_id, transactions)| {
+                        let hashes = transactions.get(&block.block_hash)?;
+
+                        Some((wallet_id.clone(), hashes.clone()))
+                    })
+                    .collect()
+            }),
+        })
+        .collect()
+}
@@ -1,7 +1,7 @@
 use actix::prelude::*;
 
 use witnet_net::client::tcp::jsonrpc;
-use witnet_net::server::ws::Server;
+use witnet_net::server::{http, ws::Server};
 
 use crate::types;
 
@@ -27,6 +27,7 @@ pub type ResponseActFuture<T> = actix::ResponseActFuture<App, Result<T>>;
 
 pub struct App {
     server: Option<Server>,
+    http_server: Option<http::Server>,
     params: Params,
     state: state::State,
 }
@@ -35,10 +36,18 @@ impl Actor for App {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
+        // Database operations must only run in the worker arbiter, never in the App arbiter
+        crate::db::forbid_blocking_on_current_thread();
+
         // Subscribe to new blocks and blocks consolidation notifications from a Witnet node
         self.node_subscribe("blocks", ctx);
         self.node_subscribe("superblocks", ctx);
         self.periodic_node_request(ctx);
+        self.periodic_node_watchdog(ctx);
+        self.periodic_payment_requests_check(ctx);
+        self.periodic_idle_lock_check(ctx);
+        self.periodic_prune(ctx);
+        self.periodic_notifications_flush(ctx);
 
         let mut handler =
             jsonrpc_pubsub::PubSubHandler::new(jsonrpc_core::MetaIoHandler::default());
@@ -47,9 +56,34 @@ impl Actor for App {
         if let Ok(server) = Server::build()
             .handler(handler)
             .addr(self.params.server_addr)
+            .cors(self.params.cors.clone())
             .start()
         {
             self.server = Some(server);
         }
+
+        if let Some(http_server_addr) = self.params.http_server_addr {
+            let mut handler = jsonrpc_core::IoHandler::default();
+            connect_http_routes(&mut handler, ctx.address());
+
+            match http::Server::build()
+                .handler(handler)
+                .addr(http_server_addr)
+                .cors(self.params.cors.clone())
+                .start()
+            {
+                Ok(server) => {
+                    log::info!("JSON-RPC HTTP server listening on {}", server.local_addr());
+                    self.http_server = Some(server);
+                }
+                Err(e) => {
+                    log::error!(
+                        "Failed to start the JSON-RPC HTTP server on {}: {}",
+                        http_server_addr,
+                        e
+                    );
+                }
+            }
+        }
     }
 }
@@ -1,30 +1,217 @@
-use std::{net::SocketAddr, sync::Arc, time::Duration};
+use std::{
+    collections::HashMap,
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
 
+use futures::FutureExt;
+use witnet_config::config::SessionLimitPolicy;
 use witnet_data_structures::chain::ConsensusConstants;
-use witnet_net::client::tcp::jsonrpc::{GetCurrentNodeUrl, JsonRpcClient, Request};
+use witnet_futures_utils::TryFutureExt2;
+use witnet_net::{
+    client::tcp::{
+        self,
+        jsonrpc::{GetCurrentNodeUrl, Request, Subscribe, SwitchUrl, Value},
+    },
+    server::Cors,
+};
 
-use crate::actors;
+use crate::{actors, clock, failover, metrics, model, pool, queues, request_limits, schema};
 
 use super::*;
 
 pub struct Params {
     pub testnet: bool,
+    /// Worker threads that handle the requests of clients.
     pub worker: Addr<actors::Worker>,
+    /// Worker thread that processes the blocks and superblocks notified by the node.
+    pub block_worker: Addr<actors::Worker>,
+    /// Worker threads that run synchronizations and periodic jobs.
+    pub maintenance_worker: Addr<actors::Worker>,
+    /// Depth of the queues of each of the workers, whose limits shed the work beyond them.
+    pub work_queues: queues::WorkQueues,
+    /// Requests in flight of each session, whose limits refuse the requests beyond them.
+    pub request_limits: Arc<request_limits::RequestLimits>,
     pub client: Arc<NodeClient>,
     pub server_addr: SocketAddr,
+    /// Address of the HTTP transport, which is disabled if `None`.
+    pub http_server_addr: Option<SocketAddr>,
+    /// Browser origins allowed to call both the WebSocket and the HTTP transports.
+    pub cors: Cors,
     pub session_expires_in: Duration,
     pub requests_timeout: Duration,
     pub consensus_constants: ConsensusConstants,
+    pub enable_derive_addresses: bool,
+    /// Key spec and coin type of the ranges of addresses of `derive_addresses`.
+    pub key_derivation: model::KeyDerivation,
+    pub notifications_queue_size: usize,
+    /// How many of the last notifications of each session are kept for replaying them.
+    pub notifications_replay_size: usize,
+    /// Execution deadlines of long-running methods, by method name.
+    pub execution_timeouts: HashMap<String, Duration>,
+    pub session_limits: SessionLimits,
+    /// Lock every wallet after this long without authenticated requests. Disabled if `None`.
+    pub idle_lock_after: Option<Duration>,
+    /// Prune the wallets that have a retention policy this often. Disabled if `None`.
+    pub prune_interval: Option<Duration>,
+    /// Time source of the session timers and of the periodic checks of the node status.
+    pub clock: clock::Clock,
+    /// Epochs without progress after which the node is reported as stalled.
+    pub node_stall_epochs: u32,
+    /// Epochs that the node can be ahead of the clock of the wallet before reporting the skew.
+    pub node_clock_skew_epochs: u32,
+    /// Maximum number of recipients of a value transfer transaction.
+    pub max_vtt_outputs: usize,
+    /// Estimated cost of the scripts of a data request above which it is reported as expensive.
+    pub data_request_cost_warning_threshold: u64,
+    /// Oldest API version that clients can declare when unlocking a wallet.
+    pub min_client_api_version: u32,
+    /// How long the responses of the requests made with an idempotency key answer their retries.
+    pub idempotency_key_retention: Duration,
+    /// Timeout of every retrieval of the benchmarks of data sources, the same as for witnesses.
+    /// Disabled if `None`.
+    pub retrieval_timeout: Option<Duration>,
+    /// Counters of the processing of notified blocks, shared with the worker.
+    pub block_processing: metrics::SharedBlockProcessing,
+    /// Actor that posts the events of wallets to their webhooks.
+    pub webhooks: Addr<actors::Webhooks>,
+    /// Configuration of the wallet without its secrets, as included in diagnostics bundles.
+    pub redacted_config: serde_json::Value,
+}
+
+/// Limits on the number of concurrent sessions.
+#[derive(Clone, Copy, Debug)]
+pub struct SessionLimits {
+    /// Maximum number of sessions of the same wallet.
+    pub per_wallet: usize,
+    /// Maximum number of sessions across all wallets.
+    pub total: usize,
+    /// Whether to reject new sessions or to evict old ones when a limit is reached.
+    pub policy: SessionLimitPolicy,
+}
+
+/// A connection to the node, as the recipients of the messages that are sent through it.
+///
+/// This is usually a `JsonRpcClient`, but any actor handling the same messages can be used in its
+/// place, such as the fake node used in tests.
+#[derive(Clone)]
+pub struct NodeConnection {
+    request: Recipient<Request>,
+    subscribe: Recipient<Subscribe>,
+    switch_url: Recipient<SwitchUrl>,
+    current_url: Recipient<GetCurrentNodeUrl>,
+}
+
+impl NodeConnection {
+    /// Use an actor as a connection to the node.
+    pub fn new<A>(addr: Addr<A>) -> Self
+    where
+        A: Actor<Context = Context<A>>
+            + Handler<Request>
+            + Handler<Subscribe>
+            + Handler<SwitchUrl>
+            + Handler<GetCurrentNodeUrl>,
+    {
+        Self {
+            request: addr.clone().recipient(),
+            subscribe: addr.clone().recipient(),
+            switch_url: addr.clone().recipient(),
+            current_url: addr.recipient(),
+        }
+    }
 }
 
 pub struct NodeClient {
-    pub actor: Addr<JsonRpcClient>,
+    /// Connections to the node that requests are spread over. Subscriptions use the first one.
+    pub pool: pool::ConnectionPool<NodeConnection>,
+    /// Which of the configured nodes should be used, and how healthy it is.
+    pub failover: Mutex<failover::NodeFailover>,
+    /// Mismatches between the results of the node and the fields that the wallet expects.
+    pub schema: schema::SchemaValidator,
 }
 
 impl NodeClient {
+    /// Get a lock on the failover state of the configured nodes.
+    pub fn failover(&self) -> std::sync::MutexGuard<'_, failover::NodeFailover> {
+        self.failover
+            .lock()
+            .expect("Failover locks should only fail if poisoned")
+    }
+
+    /// Send a request through the least busy of the healthy connections.
+    ///
+    /// Fails with `pool::Exhausted` if every connection is dead.
+    pub fn send(
+        &self,
+        request: Request,
+    ) -> impl Future<Output = Result<Result<Value, tcp::Error>, pool::Exhausted>> + 'static {
+        let conn = self.pool.acquire();
+
+        async move {
+            let conn = conn?;
+
+            Ok(send_through(&conn, request).await)
+        }
+    }
+
+    /// Send a request through every connection, healthy or not, updating their health.
+    ///
+    /// Returns the first successful response, or the error of the last connection if the request
+    /// fails in all of them.
+    pub fn send_to_all(
+        &self,
+        request: Request,
+    ) -> impl Future<Output = Result<Value, tcp::Error>> + 'static {
+        let responses = self.pool.acquire_all().into_iter().map(move |conn| {
+            let request = request.clone();
+
+            async move { send_through(&conn, request).await }
+        });
+
+        futures::future::join_all(responses).map(|responses| {
+            responses
+                .into_iter()
+                .reduce(Result::or)
+                .expect("Connection pools are never empty")
+        })
+    }
+
+    /// Make every connection switch to another node.
+    pub fn switch_url(
+        &self,
+        url: String,
+    ) -> impl Future<Output = Result<(), tcp::Error>> + 'static {
+        let switches: Vec<_> = self
+            .pool
+            .connections()
+            .map(|conn| conn.switch_url.send(SwitchUrl(url.clone())).flatten_err())
+            .collect();
+        self.pool.reset_health();
+        self.schema.reset();
+
+        futures::future::join_all(switches).map(|results| {
+            results
+                .into_iter()
+                .collect::<Result<Vec<()>, tcp::Error>>()
+                .map(drop)
+        })
+    }
+
+    /// Subscribe to notifications from the node through the first connection of the pool.
+    pub fn subscribe(&self, subscribe: Subscribe) {
+        self.pool.subscriptions().subscribe.do_send(subscribe);
+    }
+
     /// Get the URL that the current client is connecting to.
     pub async fn current_url(&self) -> String {
-        self.actor.send(GetCurrentNodeUrl).await.unwrap()
+        self.pool
+            .subscriptions()
+            .current_url
+            .send(GetCurrentNodeUrl)
+            .await
+            .unwrap()
     }
 
     /// Verifies the existing connection by issuing a `syncStatus` command with a low timeout.
@@ -34,8 +221,24 @@ impl NodeClient {
         log::debug!("Validating connection to {}", url);
 
         let request = Request::method("syncStatus").timeout(Duration::from_secs(2));
-        let response = self.actor.send(request).await;
+        let response = self.send(request).await;
 
         matches!(response, Ok(Ok(_)))
     }
 }
+
+/// Send a request through a connection taken from the pool, recording whether it reached the node.
+async fn send_through(
+    conn: &pool::PooledConnection<NodeConnection>,
+    request: Request,
+) -> Result<Value, tcp::Error> {
+    let response = conn
+        .connection()
+        .request
+        .send(request)
+        .await
+        .unwrap_or_else(|err| Err(err.into()));
+    conn.record(!matches!(&response, Err(err) if err.is_connection_error()));
+
+    response
+}
@@ -1,53 +1,359 @@
 use futures::FutureExt;
-use jsonrpc_core::{Middleware, Params};
+use jsonrpc_core::{MetaIoHandler, Metadata, Middleware, Params};
 use jsonrpc_pubsub::{PubSubHandler, PubSubMetadata, Subscriber};
+use serde::Deserialize;
 use serde_json::json;
-use std::future;
+use std::{future, sync::Arc, time::Instant};
 
 use super::*;
 use futures_util::compat::{Compat, Compat01As03};
 use witnet_futures_utils::TryFutureExt2;
+use witnet_util::timestamp::get_timestamp;
+
+use crate::{api_version, idempotency, metrics, model, request_limits::RequestClass, types::Scope};
 
 /// Helper macro to add multiple JSON-RPC methods at once
+///
+/// Methods called in a session can be given the scope that the session needs for calling them.
 macro_rules! routes {
     ($io:expr, $api:expr $(,)?) => {};
+    ($io:expr, $api:expr, ($wiki:expr, $method_jsonrpc:expr, $actor_msg:ty, $scope:expr $(,)?), $($args:tt)*) => {
+        route!($io, $api, $wiki, $method_jsonrpc, $actor_msg, Some($scope));
+        routes!($io, $api, $($args)*);
+    };
     ($io:expr, $api:expr, ($wiki:expr, $method_jsonrpc:expr, $actor_msg:ty $(,)?), $($args:tt)*) => {
+        route!($io, $api, $wiki, $method_jsonrpc, $actor_msg, None);
+        routes!($io, $api, $($args)*);
+    };
+}
+
+/// Helper macro to add a JSON-RPC method, rejecting the calls from sessions that lack its scope
+macro_rules! route {
+    ($io:expr, $api:expr, $wiki:expr, $method_jsonrpc:expr, $actor_msg:ty, $scope:expr) => {
         {
             let api_addr = $api.clone();
             $io.add_method($method_jsonrpc, move |params: Params| {
                 log::debug!("Handling request for method {}: {:?}", $method_jsonrpc, params);
+                let start = Instant::now();
                 let addr = api_addr.clone();
-                // Try to parse the request params into the actor message
-                let fut03 = future::ready(params.parse::<$actor_msg>())
-                    .then(move |res| match res {
-                        Err(mut err) => {
+                let log_addr = api_addr.clone();
+                let session_id = request_session_id(&params);
+                let positional = is_positional(&params);
+                let log_session_id = session_id.clone();
+                // Try to parse the request params into the actor message, once its wallet id and
+                // its idempotency key, if any, are known to be well formed
+                let parsed = match validate_wallet_id(&params)
+                    .and_then(|()| idempotent_request(&params, $method_jsonrpc))
+                {
+                    Ok(idempotent) => params
+                        .parse::<$actor_msg>()
+                        .map(|msg| (msg, idempotent))
+                        .map_err(|mut err| {
                             err.data = Some(json!({
                                 "schema": format!("https://github.com/witnet/witnet-rust/wiki/{}", $wiki)
                             }));
 
-                            futures::future::Either::Left(future::ready(Err(err)))
-                        }
-                        Ok(msg) => {
+                            err
+                        }),
+                    Err(e) => Err(e.into()),
+                };
+                let fut03 = future::ready(parsed)
+                    .then(move |res| match res {
+                        Err(err) => futures::future::Either::Left(future::ready(Err(err))),
+                        Ok((msg, idempotent)) => {
                             log::trace!("=> Handling Request: {:?}", &msg);
-                            // Then send the parsed message to the actor
-                            let f = addr.send(msg)
-                                .flatten_err()
-                                .map(|res: Result<_>| {
-                                    res.and_then(|x| serde_json::to_value(x).map_err(internal_error))
-                                        .map_err(|e| e.into())
-                                });
+                            // Then take a place in the queue of requests and among the requests in
+                            // flight of the session, check the scope of the session and send the
+                            // parsed message to the actor, holding the places until the request is
+                            // answered. Retries of requests made with an idempotency key get the
+                            // response to the first request instead. Sessions in compatibility
+                            // mode do not get the fields newer than their client.
+                            let f = async move {
+                                match admit(addr.clone(), session_id.clone(), $method_jsonrpc).await {
+                                    Ok(admission) => {
+                                        match authorize(addr.clone(), session_id, positional, $scope).await {
+                                            Ok(()) => run_idempotent(addr.clone(), idempotent, async move {
+                                                addr.send(msg)
+                                                    .flatten_err()
+                                                    .await
+                                                    .and_then(|x| serde_json::to_value(x).map_err(internal_error))
+                                            })
+                                            .await
+                                            .map(|response| {
+                                                api_version::compatible_response(
+                                                    $method_jsonrpc,
+                                                    admission.client_api_version,
+                                                    response,
+                                                )
+                                            }),
+                                            Err(e) => Err(e),
+                                        }
+                                    }
+                                    Err(e) => Err(e),
+                                }
+                            }
+                            .map(|res: Result<serde_json::Value>| res.map_err(|e| e.into()));
 
                             futures::future::Either::Right(f)
                         }
+                    })
+                    .map(move |res| {
+                        record_request(&log_addr, $method_jsonrpc, log_session_id, start, &res);
+                        res
                     });
 
                 Compat::new(Box::pin(fut03))
             });
         }
-        routes!($io, $api, $($args)*);
     };
 }
 
+/// The session of a request, for the methods that are called in one.
+#[derive(Deserialize)]
+struct SessionParams {
+    #[serde(default)]
+    session_id: Option<types::SessionId>,
+}
+
+/// Tell the session of a request, if any. Only named params are looked at, as the position of the
+/// session id among positional params depends on the method.
+fn request_session_id(params: &Params) -> Option<types::SessionId> {
+    match params {
+        Params::Map(_) => params
+            .clone()
+            .parse::<SessionParams>()
+            .ok()
+            .and_then(|session| session.session_id),
+        _ => None,
+    }
+}
+
+/// Whether the request has positional params, whose session, if any, cannot be told.
+fn is_positional(params: &Params) -> bool {
+    matches!(params, Params::Array(values) if !values.is_empty())
+}
+
+/// The wallet of a request, for the methods that are called on one. Ids that are not even strings
+/// are left for the parsing of the request to reject.
+#[derive(Deserialize)]
+struct WalletIdParams {
+    #[serde(default)]
+    wallet_id: Option<serde_json::Value>,
+}
+
+/// Check that the wallet id of a request, if any, is well formed, reporting it as a validation
+/// error of the `wallet_id` field otherwise.
+pub fn validate_wallet_id(params: &Params) -> Result<()> {
+    let wallet_id = params
+        .clone()
+        .parse::<WalletIdParams>()
+        .ok()
+        .and_then(|wallet| wallet.wallet_id);
+
+    match wallet_id {
+        Some(serde_json::Value::String(wallet_id)) => wallet_id
+            .parse::<model::WalletId>()
+            .map(|_| ())
+            .map_err(|e| validation_error(field_error("wallet_id", e))),
+        _ => Ok(()),
+    }
+}
+
+/// Tell the idempotency key of a request, if any, checking that it is well formed and that the
+/// method accepts one, and reporting it as a validation error of the `idempotency_key` field
+/// otherwise.
+///
+/// Keys are only accepted among named params. Positional params that carry one are rejected
+/// rather than run without it, as their retries would run again.
+pub fn idempotent_request(
+    params: &Params,
+    method: &'static str,
+) -> Result<Option<idempotency::IdempotentRequest>> {
+    let mut fields = match params {
+        Params::Map(fields) => fields.clone(),
+        Params::Array(values) => {
+            let has_key = values.iter().any(|value| match value {
+                serde_json::Value::Object(fields) => fields.contains_key("idempotency_key"),
+                _ => false,
+            });
+
+            return if has_key {
+                Err(validation_error(field_error(
+                    "idempotency_key",
+                    "Idempotency keys can only be given among named params",
+                )))
+            } else {
+                Ok(None)
+            };
+        }
+        Params::None => return Ok(None),
+    };
+    let key = match fields.remove("idempotency_key") {
+        None => return Ok(None),
+        Some(serde_json::Value::String(key)) => key,
+        Some(_) => {
+            return Err(validation_error(field_error(
+                "idempotency_key",
+                "Idempotency keys must be strings",
+            )))
+        }
+    };
+    idempotency::validate_key(&key)
+        .map_err(|e| validation_error(field_error("idempotency_key", e)))?;
+    if !idempotency::IDEMPOTENT_METHODS.contains(&method) {
+        return Err(validation_error(field_error(
+            "idempotency_key",
+            format!("Method {} does not accept an idempotency key", method),
+        )));
+    }
+
+    // Requests without a well formed session or wallet are left for the parsing of the request to
+    // reject
+    let session_id = match fields.remove("session_id") {
+        Some(serde_json::Value::String(session_id)) => types::SessionId::from(session_id),
+        _ => return Ok(None),
+    };
+    let wallet_id = match fields.remove("wallet_id") {
+        Some(serde_json::Value::String(wallet_id)) => match wallet_id.parse() {
+            Ok(wallet_id) => wallet_id,
+            Err(_) => return Ok(None),
+        },
+        _ => return Ok(None),
+    };
+
+    Ok(Some(idempotency::IdempotentRequest {
+        session_id,
+        wallet_id,
+        key,
+        method,
+        params_hash: idempotency::params_hash(&fields),
+    }))
+}
+
+/// Run a request, unless it was made with an idempotency key and is a retry of an earlier request,
+/// in which case it gets the response to that request, waiting for it if it is still running.
+async fn run_idempotent<F>(
+    addr: Addr<App>,
+    request: Option<idempotency::IdempotentRequest>,
+    run: F,
+) -> Result<serde_json::Value>
+where
+    F: future::Future<Output = Result<serde_json::Value>>,
+{
+    let request = match request {
+        Some(request) => request,
+        None => return run.await,
+    };
+    loop {
+        match addr.send(BeginIdempotentRequest(request.clone())).await?? {
+            Idempotency::Run => break,
+            Idempotency::Replay(response) => return Ok(response),
+            // The request waited for was either answered or forgotten, so begin again to tell
+            Idempotency::Wait(wait) => {
+                let _ = wait.await;
+            }
+        }
+    }
+
+    let running = RunningIdempotent {
+        addr,
+        record_key: Some(request.record_key()),
+    };
+    let result = run.await;
+    running.finish(match &result {
+        Ok(response) => IdempotentOutcome::Answered(response.clone()),
+        Err(_) => IdempotentOutcome::Failed,
+    });
+
+    result
+}
+
+/// A request made with an idempotency key that is running, which is recorded as interrupted if it
+/// is dropped before finishing.
+struct RunningIdempotent {
+    addr: Addr<App>,
+    record_key: Option<String>,
+}
+
+impl RunningIdempotent {
+    fn finish(mut self, outcome: IdempotentOutcome) {
+        self.send_outcome(outcome);
+    }
+
+    fn send_outcome(&mut self, outcome: IdempotentOutcome) {
+        if let Some(record_key) = self.record_key.take() {
+            self.addr.do_send(FinishIdempotentRequest {
+                record_key,
+                outcome,
+            });
+        }
+    }
+}
+
+impl Drop for RunningIdempotent {
+    fn drop(&mut self) {
+        self.send_outcome(IdempotentOutcome::Interrupted);
+    }
+}
+
+/// Check that the session of a request, if any, has the scope required by the method. Methods that
+/// require a scope are rejected when called with positional params, as their session could not be
+/// told and so its scope could not be checked. Calls without a session are left for the method to
+/// reject if it needs one.
+async fn authorize(
+    addr: Addr<App>,
+    session_id: Option<types::SessionId>,
+    positional: bool,
+    scope: Option<Scope>,
+) -> Result<()> {
+    match (session_id, scope) {
+        (Some(session_id), Some(scope)) => addr.send(Authorize { session_id, scope }).await?,
+        (None, Some(_)) if positional => Err(validation_error(field_error(
+            "session_id",
+            "This method must be called with a session_id among named params",
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Take a place in the queue of the requests of clients and count the request as in flight in its
+/// session, failing right away with `Overloaded` if the queue is full or with `TooManyRequests` if
+/// the session already has too many requests of the class of `method` in flight.
+async fn admit(
+    addr: Addr<App>,
+    session_id: Option<types::SessionId>,
+    method: &str,
+) -> Result<Admission> {
+    addr.send(AdmitRequest {
+        session_id,
+        class: RequestClass::of(method),
+    })
+    .await?
+}
+
+/// Record an answered request in the request log of the App actor.
+fn record_request(
+    addr: &Addr<App>,
+    method: &str,
+    session_id: Option<types::SessionId>,
+    start: Instant,
+    result: &std::result::Result<serde_json::Value, jsonrpc_core::Error>,
+) {
+    let error = result.as_ref().err().map(|e| metrics::RequestError {
+        code: e.code.code(),
+        message: e.message.clone(),
+        data: e.data.clone(),
+    });
+
+    addr.do_send(RecordRequest(metrics::RequestLogEntry {
+        timestamp: get_timestamp(),
+        method: method.to_string(),
+        duration_millis: u64::try_from(start.elapsed().as_millis()).unwrap_or(u64::MAX),
+        error,
+        session_id,
+    }));
+}
+
 /// Macro to add multiple JSON-RPC methods that forward the request to the Node at once
 macro_rules! forwarded_routes {
     ($io:expr, $api:expr $(,)?) => {};
@@ -56,6 +362,8 @@ macro_rules! forwarded_routes {
             let api_addr = $api.clone();
             $io.add_method($method_wallet, move |params: Params| {
                 log::debug!("Forwarding request for method: {}", $method_wallet);
+                let start = Instant::now();
+                let log_addr = api_addr.clone();
                 let msg = ForwardRequest {
                     method: $method_node.to_string(),
                     params
@@ -65,6 +373,10 @@ macro_rules! forwarded_routes {
                     .map(|res: Result<_>| {
                         res.and_then(|x| serde_json::to_value(x).map_err(internal_error))
                             .map_err(|e| e.into())
+                    })
+                    .map(move |res| {
+                        record_request(&log_addr, $method_wallet, None, start, &res);
+                        res
                     });
                 Compat::new(Box::pin(fut03))
             });
@@ -85,7 +397,9 @@ pub fn connect_routes<T, S>(
         "notifications",
         ("rpc.on", {
             let addr = api.clone();
-            move |params: Params, _meta, subscriber: Subscriber| {
+            move |params: Params, meta: T, subscriber: Subscriber| {
+                // The connection is kept so that only itself can remove the subscription
+                let connection = meta.session().map(|session| Arc::downgrade(&session));
                 let addr_subscription_id = addr.clone();
                 let addr_subscribe = addr.clone();
                 let f = future::ready(params.parse::<SubscribeRequest>())
@@ -110,7 +424,10 @@ pub fn connect_routes<T, S>(
                                                     Subscribe(
                                                         request.session_id,
                                                         subscription_id,
-                                                        sink
+                                                        Box::new(sink),
+                                                        request.overflow_policy,
+                                                        request.last_event_id,
+                                                        connection,
                                                     )
                                                 );
                                             }))
@@ -140,8 +457,10 @@ pub fn connect_routes<T, S>(
         }),
         ("rpc.off", {
             let addr = api.clone();
-            move |subscription_id, _meta| {
-                let fut03 = addr.send(UnsubscribeRequest(subscription_id))
+            move |subscription_id, meta: Option<T>| {
+                // There is no connection when it is closed, and its subscriptions are dropped
+                let connection = meta.and_then(|meta| meta.session());
+                let fut03 = addr.send(UnsubscribeRequest(subscription_id, connection))
                     .flatten_err()
                     .map(|res| res.map(|()| json!(())).map_err(|e: Error| e.into()));
 
@@ -150,21 +469,55 @@ pub fn connect_routes<T, S>(
         }),
     );
 
+    connect_methods(handler, api);
+}
+
+/// Routes of the HTTP transport, which are the same as those of the WebSocket one except for
+/// subscriptions, as notifications cannot be pushed to HTTP clients.
+pub fn connect_http_routes<T, S>(handler: &mut MetaIoHandler<T, S>, api: Addr<App>)
+where
+    T: Metadata,
+    S: Middleware<T>,
+{
+    for method in ["rpc.on", "rpc.off"] {
+        handler.add_method(method, |_params: Params| {
+            let fut03 = future::ready(Err::<serde_json::Value, jsonrpc_core::Error>(
+                Error::SubscriptionsUnavailable.into(),
+            ));
+
+            Compat::new(Box::pin(fut03))
+        });
+    }
+
+    connect_methods(handler, api);
+}
+
+/// Routes that are available through every transport.
+fn connect_methods<T, S>(handler: &mut MetaIoHandler<T, S>, api: Addr<App>)
+where
+    T: Metadata,
+    S: Middleware<T>,
+{
     forwarded_routes!(
         handler,
         api,
         ("data_request_report", "dataRequestReport"),
         ("get_block", "getBlock"),
         ("get_block_chain", "getBlockChain"),
+        ("get_consensus_constants", "getConsensusConstants"),
         ("get_output", "getOutput"),
         ("get_transaction_by_hash", "getTransaction"),
         ("inventory", "inventory"),
         ("priority", "priority"),
+        ("validate_transaction", "validateTransaction"),
     );
 
+    // The methods called in a session are given the scope that the session needs, except for those
+    // that only manage the session itself
     routes!(
         handler,
         api,
+        ("Get-Version", "get_version", GetVersionRequest),
         ("Get-Wallet-Infos", "get_wallet_infos", WalletInfosRequest),
         (
             "Create-Mnemonics",
@@ -177,46 +530,260 @@ pub fn connect_routes<T, S>(
             ValidateMnemonicsRequest
         ),
         ("Create-Wallet", "create_wallet", CreateWalletRequest),
-        ("Delete-Wallet", "delete_wallet", DeleteWalletRequest),
-        ("Update-Wallet", "update_wallet", UpdateWalletRequest),
+        (
+            "Derive-Addresses",
+            "derive_addresses",
+            DeriveAddressesRequest
+        ),
+        (
+            "Delete-Wallet",
+            "delete_wallet",
+            DeleteWalletRequest,
+            Scope::Admin
+        ),
+        (
+            "Confirm-Seed-Backup",
+            "confirm_seed_backup",
+            ConfirmSeedBackupRequest,
+            Scope::Admin
+        ),
+        (
+            "Update-Wallet",
+            "update_wallet",
+            UpdateWalletRequest,
+            Scope::Admin
+        ),
         ("Lock-Wallet", "lock_wallet", LockWalletRequest),
+        (
+            "Lock-All-Wallets",
+            "lock_all_wallets",
+            LockAllWalletsRequest,
+            Scope::Admin
+        ),
         ("Unlock-Wallet", "unlock_wallet", UnlockWalletRequest),
-        ("Resync-Wallet", "resync_wallet", ResyncWalletRequest),
+        (
+            "Resync-Wallet",
+            "resync_wallet",
+            ResyncWalletRequest,
+            Scope::Admin
+        ),
+        (
+            "Check-Integrity",
+            "check_integrity",
+            CheckIntegrityRequest,
+            Scope::Admin
+        ),
+        (
+            "Import-Address-Metadata",
+            "import_address_metadata",
+            ImportAddressMetadataRequest,
+            Scope::Admin
+        ),
+        (
+            "Prune-Wallet-Data",
+            "prune_wallet_data",
+            PruneWalletDataRequest,
+            Scope::Admin
+        ),
+        (
+            "Get-Sync-Status",
+            "get_sync_status",
+            GetSyncStatusRequest,
+            Scope::Read
+        ),
+        ("Pin-Sync", "pin_sync", PinSyncRequest, Scope::Admin),
+        (
+            "Create-Diagnostics-Bundle",
+            "create_diagnostics_bundle",
+            CreateDiagnosticsBundleRequest,
+            Scope::Admin
+        ),
         ("Close-Session", "close_session", CloseSessionRequest),
         ("Refresh-Session", "refresh_session", RefreshSessionRequest),
-        ("Get-Balance", "get_balance", GetBalanceRequest),
-        ("Get-Utxo-Info", "get_utxo_info", UtxoInfoRequest),
+        (
+            "Get-Subscription-Stats",
+            "get_subscription_stats",
+            GetSubscriptionStatsRequest,
+            Scope::Read
+        ),
+        ("Get-Node-Status", "get_node_status", GetNodeStatusRequest),
+        (
+            "Switch-Node",
+            "switch_node",
+            SwitchNodeRequest,
+            Scope::Admin
+        ),
+        ("Get-Balance", "get_balance", GetBalanceRequest, Scope::Read),
+        (
+            "Get-Balance-History",
+            "get_balance_history",
+            GetBalanceHistoryRequest,
+            Scope::Read
+        ),
+        (
+            "Get-Utxo-Info",
+            "get_utxo_info",
+            UtxoInfoRequest,
+            Scope::Read
+        ),
         (
             "Get-Transactions",
             "get_transactions",
-            GetTransactionsRequest
+            GetTransactionsRequest,
+            Scope::Read
+        ),
+        (
+            "Get-Transactions-Stream",
+            "get_transactions_stream",
+            GetTransactionsStreamRequest,
+            Scope::Read
         ),
         (
             "Send-Transaction",
             "send_transaction",
-            SendTransactionRequest
+            SendTransactionRequest,
+            Scope::Send
+        ),
+        (
+            "Broadcast-Raw-Transaction",
+            "broadcast_raw_transaction",
+            BroadcastRawTransactionRequest,
+            Scope::Send
+        ),
+        (
+            "Confirm-Send",
+            "confirm_send",
+            ConfirmSendRequest,
+            Scope::Send
+        ),
+        ("Bump-Fee", "bump_fee", BumpFeeRequest, Scope::Send),
+        (
+            "Update-Wallet-Policy",
+            "update_wallet_policy",
+            UpdateWalletPolicyRequest,
+            Scope::Admin
+        ),
+        (
+            "Update-Witness-Signing-Policy",
+            "update_witness_signing_policy",
+            UpdateWitnessSigningPolicyRequest,
+            Scope::Admin
+        ),
+        (
+            "Update-Wallet-Webhook",
+            "update_wallet_webhook",
+            UpdateWalletWebhookRequest,
+            Scope::Admin
+        ),
+        (
+            "Get-Webhook-Deliveries",
+            "get_webhook_deliveries",
+            GetWebhookDeliveriesRequest,
+            Scope::Read
+        ),
+        (
+            "Requeue-Webhook-Deliveries",
+            "requeue_webhook_deliveries",
+            RequeueWebhookDeliveriesRequest,
+            Scope::Admin
         ),
         (
             "Generate-Address",
             "generate_address",
-            GenerateAddressRequest
+            GenerateAddressRequest,
+            Scope::Admin
+        ),
+        (
+            "Get-Addresses",
+            "get_addresses",
+            GetAddressesRequest,
+            Scope::Read
+        ),
+        (
+            "Create-Payment-Request",
+            "create_payment_request",
+            CreatePaymentRequestRequest,
+            Scope::Admin
+        ),
+        (
+            "List-Payment-Requests",
+            "list_payment_requests",
+            ListPaymentRequestsRequest,
+            Scope::Read
         ),
-        ("Get-Addresses", "get_addresses", GetAddressesRequest),
         (
             "Create-Data-Request",
             "create_data_request",
-            CreateDataReqRequest
+            CreateDataReqRequest,
+            Scope::Send
         ),
-        ("Create-Vtt", "create_vtt", CreateVttRequest),
+        ("Create-Vtt", "create_vtt", CreateVttRequest, Scope::Send),
         ("Run-Rad-Request", "run_rad_request", RunRadReqRequest),
-        ("Set", "set", SetRequest),
-        ("Get", "get", GetRequest),
-        ("Sign-Data", "sign_data", SignDataRequest),
+        (
+            "Benchmark-Retrievals",
+            "benchmark_retrievals",
+            BenchmarkRetrievalsRequest
+        ),
+        (
+            "Estimate-Data-Request-Cost",
+            "estimate_data_req_cost",
+            EstimateDataReqCostRequest
+        ),
+        (
+            "Export-Data-Request-Json",
+            "export_data_request_json",
+            ExportDataRequestJsonRequest
+        ),
+        (
+            "Import-Data-Request-Json",
+            "import_data_request_json",
+            ImportDataRequestJsonRequest
+        ),
+        (
+            "Get-Operator-Catalog",
+            "get_operator_catalog",
+            GetOperatorCatalogRequest
+        ),
+        (
+            "Simulate-Data-Request",
+            "simulate_data_request",
+            SimulateDataRequestRequest
+        ),
+        (
+            "Classify-Reveals",
+            "classify_reveals",
+            ClassifyRevealsRequest
+        ),
+        (
+            "Validate-Address",
+            "validate_address",
+            ValidateAddressRequest,
+            Scope::Read
+        ),
+        ("Decode", "decode", DecodeRequest),
+        ("Get-Blocks", "get_blocks", GetBlocksRequest, Scope::Read),
+        (
+            "Get-Block-Details",
+            "get_block_details",
+            GetBlockDetailsRequest,
+            Scope::Read
+        ),
+        ("Set", "set", SetRequest, Scope::Admin),
+        ("Get", "get", GetRequest, Scope::Read),
+        ("Sign-Data", "sign_data", SignDataRequest, Scope::Sign),
+        (
+            "Sign-Witness-Transaction",
+            "sign_witness_transaction",
+            SignWitnessTransactionRequest,
+            Scope::WitnessSigning
+        ),
+        ("Verify-Data", "verify_data", VerifyDataRequest),
         (
             "Export-Master-Key",
             "export_master_key",
-            ExportMasterKeyRequest
+            ExportMasterKeyRequest,
+            Scope::Admin
         ),
-        ("Shutdown", "shutdown", ShutdownRequest),
+        ("Shutdown", "shutdown", ShutdownRequest, Scope::Admin),
     );
 }
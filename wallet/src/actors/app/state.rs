@@ -1,13 +1,21 @@
 use std::{
-    collections::HashMap,
+    cell::Cell,
+    collections::{HashMap, HashSet, VecDeque},
     convert::TryFrom,
-    sync::{Arc, Mutex, RwLock},
+    sync::{Arc, Mutex, RwLock, Weak},
 };
 
-use witnet_data_structures::chain::StateMachine;
+use futures::channel::oneshot;
+use witnet_config::config::SessionLimitPolicy;
+use witnet_data_structures::{
+    chain::{Block, Hash, Hashable, PublicKeyHash, StateMachine},
+    transaction::Transaction,
+};
 use witnet_net::client::tcp::jsonrpc::Subscribe;
+use witnet_util::timestamp::get_timestamp;
 
 use super::*;
+use crate::{constants, credentials, metrics, model, sink, watchdog};
 
 /// Struct to manage the App actor state and its invariants.
 #[derive(Default)]
@@ -15,22 +23,159 @@ pub struct State {
     pub node_subscriptions: Arc<Mutex<HashMap<String, Subscribe>>>,
     pub node_state: Option<StateMachine>,
     pub client_subscriptions: HashMap<types::SessionId, types::DynamicSink>,
+    /// Subscriptions of clients to the notifications of the sessions, by their id. A session has
+    /// at most one, whose transport is the one attached to the sink of the session.
+    pub subscriptions: HashMap<jsonrpc_pubsub::SubscriptionId, Subscription>,
     pub sessions: HashMap<types::SessionId, Session>,
-    pub wallets: HashMap<String, types::SessionWallet>,
+    pub wallets: HashMap<model::WalletId, types::SessionWallet>,
+    pub storage_latency: metrics::SharedStorageLatency,
+    /// Consensus constants whose value in the node differs from the one in the wallet.
+    pub consensus_constants_mismatch: Vec<String>,
+    /// Timestamp of the last request that used a session, in any session. It is updated through
+    /// shared references because every authenticated method looks up its session.
+    pub last_authenticated_activity: Cell<i64>,
+    /// Failed attempts to unlock each wallet, used for slowing down password guessing. They are
+    /// only kept in memory, and forgotten as soon as the wallet is unlocked.
+    pub failed_unlocks: HashMap<model::WalletId, FailedUnlocks>,
+    /// Addresses that the node has reported as seen on chain. Negative answers are not cached,
+    /// because they may change with every new block.
+    pub addresses_seen_on_chain: HashSet<PublicKeyHash>,
+    /// How many of the last notifications of each session are kept for replaying them to clients
+    /// that resubscribe.
+    pub notifications_replay_size: usize,
+    /// Transactions above the confirmation threshold of their wallet that wait for `confirm_send`,
+    /// by their one-time token.
+    pub pending_sends: HashMap<String, PendingSend>,
+    /// Requests made with an idempotency key that are being looked up or running, by the key they
+    /// are recorded under in their wallet.
+    pub idempotent_in_flight: HashMap<String, InFlightIdempotent>,
+    /// Blocks fetched from the node by the chain explorer methods. Blocks never change, so they
+    /// are only evicted to make room for others.
+    pub explorer_blocks: BlockCache,
+    /// Outcome of unlocking the wallets of the credentials file at startup, if one is configured.
+    pub startup_unlock: Option<credentials::StartupUnlockReport>,
+    /// Last requests handled by the JSON-RPC server, and counters of their errors.
+    pub request_log: metrics::RequestLog,
+    /// Whether notified blocks are being dropped because the block worker is overloaded, so that
+    /// the wallets must be synchronized once it catches up.
+    pub shedding_blocks: bool,
+    /// Whether the node keeps making progress, and whether its clock is ahead of the wallet's.
+    pub node_watchdog: watchdog::NodeWatchdog,
 }
 
+/// Blocks by their hash, of which only the `constants::EXPLORER_BLOCK_CACHE_SIZE` most recently
+/// inserted are kept.
 #[derive(Default)]
+pub struct BlockCache {
+    blocks: HashMap<Hash, Arc<Block>>,
+    order: VecDeque<Hash>,
+}
+
+impl BlockCache {
+    pub fn get(&self, hash: &Hash) -> Option<Arc<Block>> {
+        self.blocks.get(hash).cloned()
+    }
+
+    pub fn insert(&mut self, block: Arc<Block>) {
+        let hash = block.hash();
+        if self.blocks.insert(hash, block).is_some() {
+            return;
+        }
+        self.order.push_back(hash);
+        while self.order.len() > constants::EXPLORER_BLOCK_CACHE_SIZE {
+            if let Some(evicted) = self.order.pop_front() {
+                self.blocks.remove(&evicted);
+            }
+        }
+    }
+}
+
+/// Transaction that is only sent once its sending is confirmed with a one-time token.
+#[derive(Clone, Debug)]
+pub struct PendingSend {
+    pub session_id: types::SessionId,
+    pub wallet_id: model::WalletId,
+    pub transaction: Transaction,
+    /// Pending transaction that this one replaces with a higher fee, if any
+    pub replaces: Option<Hash>,
+}
+
+/// Request made with an idempotency key that is being looked up or running.
+pub struct InFlightIdempotent {
+    /// Wallet that the request is recorded in
+    pub wallet: types::SessionWallet,
+    pub operation: model::IdempotentOperation,
+    /// Retries waiting for the request to be answered, which are woken up when these are dropped
+    pub waiters: Vec<oneshot::Sender<()>>,
+}
+
+/// Subscription of a client to the notifications of a session.
+#[derive(Clone)]
+pub struct Subscription {
+    /// Session that owns the subscription
+    pub session_id: types::SessionId,
+    /// WebSocket connection that the client subscribed through, which is the only one that can
+    /// remove the subscription
+    pub connection: Option<Weak<jsonrpc_pubsub::Session>>,
+}
+
+impl Subscription {
+    /// Whether the client subscribed through `connection`.
+    pub fn is_through(&self, connection: &Arc<jsonrpc_pubsub::Session>) -> bool {
+        self.connection
+            .as_ref()
+            .map_or(false, |own| own.ptr_eq(&Arc::downgrade(connection)))
+    }
+}
+
+/// Wrong passwords given for a wallet since it was last unlocked.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FailedUnlocks {
+    pub attempts: u32,
+    /// Timestamp of the last attempt, failed or still running.
+    pub last_attempt: i64,
+    /// Attempts whose password is still being checked, which count as failed until they are
+    /// known to be right.
+    pub in_flight: u32,
+}
+
 pub struct Session {
-    wallets: HashMap<String, types::SessionWallet>,
+    wallets: HashMap<model::WalletId, types::SessionWallet>,
     pub session_extended: bool,
+    /// Timestamp of the last time the client showed signs of life in this session.
+    pub last_activity: i64,
+    /// Scopes of the methods that the session can call.
+    pub scopes: HashSet<types::Scope>,
+    /// Whether this is the service session of the wallets unlocked at startup, which never
+    /// expires, is never evicted and is exempt from the idle lock.
+    pub service: bool,
+    /// API version that the client declared when unlocking a wallet in the session, if any.
+    pub client_api_version: Option<u32>,
+}
+
+impl Default for Session {
+    fn default() -> Self {
+        Self {
+            wallets: HashMap::default(),
+            session_extended: false,
+            last_activity: 0,
+            scopes: types::Scope::DEFAULT.iter().copied().collect(),
+            service: false,
+            client_api_version: None,
+        }
+    }
 }
 
 impl State {
     /// Get the subscription sink for a specific session
+    ///
+    /// Sinks are only kept for open sessions. Any other session gets a sink that is thrown away,
+    /// so that nothing is notified on its behalf.
     pub fn get_sink(&mut self, session_id: &types::SessionId) -> types::DynamicSink {
         match self.client_subscriptions.get(session_id) {
             Some(sink) => sink.clone(),
-            None => self.set_sink(session_id, None),
+            None if self.sessions.contains_key(session_id) => self.set_sink(session_id, None),
+            None => Arc::new(RwLock::new(sink::SessionSink::new(0))),
         }
     }
 
@@ -38,50 +183,36 @@ impl State {
     fn set_sink(
         &mut self,
         session_id: &types::SessionId,
-        new_sink: Option<jsonrpc_pubsub::Sink>,
+        new_sink: Option<sink::BoundedSink>,
     ) -> types::DynamicSink {
-        let sink = Arc::new(RwLock::new(new_sink));
+        let mut session_sink = sink::SessionSink::new(self.notifications_replay_size);
+        session_sink.set_sink(new_sink);
+        let sink = Arc::new(RwLock::new(session_sink));
         self.client_subscriptions
             .insert(session_id.clone(), sink.clone());
 
         sink
     }
 
-    /// Updates the subscription sink for a specific session
-    pub fn update_sink(
-        &mut self,
-        session_id: &types::SessionId,
-        new_sink: Option<jsonrpc_pubsub::Sink>,
-    ) -> types::DynamicSink {
-        match self.client_subscriptions.get(session_id) {
-            Some(sink) => {
-                let mut lock = sink
-                    .write()
-                    .expect("Write locks should only fail if poisoned");
-                *lock = new_sink;
-                sink.clone()
-            }
-            None => self.set_sink(session_id, new_sink),
-        }
-    }
-
     /// Get all wallets for a session
     pub fn get_wallets_by_session(
         &self,
         session_id: &types::SessionId,
-    ) -> Result<&HashMap<String, types::SessionWallet>> {
-        Ok(&self
+    ) -> Result<&HashMap<model::WalletId, types::SessionWallet>> {
+        let session = self
             .sessions
             .get(session_id)
-            .ok_or(Error::SessionNotFound)?
-            .wallets)
+            .ok_or(Error::SessionNotFound)?;
+        self.last_authenticated_activity.set(get_timestamp());
+
+        Ok(&session.wallets)
     }
 
     /// Get a reference to an unlocked wallet.
     pub fn get_wallet_by_session_and_id(
         &self,
         session_id: &types::SessionId,
-        wallet_id: &str,
+        wallet_id: &model::WalletId,
     ) -> Result<types::SessionWallet> {
         let wallets = self.get_wallets_by_session(session_id)?;
 
@@ -93,50 +224,240 @@ impl State {
         Ok(wallet)
     }
 
+    /// Check that a session can call the methods that require `scope`.
+    ///
+    /// Sessions that do not exist are not rejected here, so that methods keep failing with
+    /// `SessionNotFound` for them.
+    pub fn check_scope(&self, session_id: &types::SessionId, scope: types::Scope) -> Result<()> {
+        match self.sessions.get(session_id) {
+            Some(session) if !session.scopes.contains(&scope) => {
+                Err(Error::InsufficientScope { required: scope })
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Get the scopes of a session, in order.
+    pub fn session_scopes(&self, session_id: &types::SessionId) -> Vec<types::Scope> {
+        let mut scopes: Vec<_> = self
+            .sessions
+            .get(session_id)
+            .map(|session| session.scopes.iter().copied().collect())
+            .unwrap_or_default();
+        scopes.sort();
+
+        scopes
+    }
+
+    /// Get the API version that the client of a session declared, if any.
+    pub fn client_api_version(&self, session_id: &types::SessionId) -> Option<u32> {
+        self.sessions
+            .get(session_id)
+            .and_then(|session| session.client_api_version)
+    }
+
     /// Check if the session is still active.
     pub fn is_session_active(&self, session_id: &types::SessionId) -> bool {
         self.sessions.contains_key(session_id)
     }
 
-    /// Add a sink and subscription id to a session.
+    /// Add a sink and subscription id to a session, replacing its previous subscription if any.
+    ///
+    /// If the previous subscription of the session was terminated for lagging behind, the client
+    /// is told the reason through the new sink. Clients resuming a previous subscription give the
+    /// id of the last event they got, so that the ones they missed are replayed.
     pub fn subscribe(
         &mut self,
-        session_id: &types::SessionId,
-        sink: jsonrpc_pubsub::Sink,
+        subscription_id: jsonrpc_pubsub::SubscriptionId,
+        subscription: Subscription,
+        sink: sink::BoundedSink,
+        last_event_id: Option<u64>,
     ) -> Result<types::DynamicSink> {
-        match self.sessions.get_mut(session_id) {
-            Some(_) => Ok(self.update_sink(session_id, Some(sink))),
-            None => Err(Error::SessionNotFound),
+        let session_id = subscription.session_id.clone();
+        if !self.sessions.contains_key(&session_id) {
+            return Err(Error::SessionNotFound);
+        }
+        self.touch_session(&session_id);
+
+        let disconnect_reason = self
+            .get_sink_stats(&session_id)
+            .and_then(|(_, stats)| stats.disconnect_reason);
+        if let Some(reason) = disconnect_reason {
+            if let Err(err) = sink.notify(sink::disconnected_notification(&reason)) {
+                log::warn!("Couldn't notify subscription termination reason: {}", err);
+            }
+        }
+
+        let session_sink = self.get_sink(&session_id);
+        session_sink
+            .write()
+            .expect("Write locks should only fail if poisoned")
+            .attach(sink, last_event_id);
+        self.subscriptions
+            .retain(|_, subscription| subscription.session_id != session_id);
+        self.subscriptions.insert(subscription_id, subscription);
+
+        Ok(session_sink)
+    }
+
+    /// Try to deliver the notifications queued in the subscription sinks of every session.
+    pub fn flush_sinks(&self) {
+        for session_sink in self.client_subscriptions.values() {
+            if let Some(sink) = session_sink
+                .read()
+                .expect("Read locks should only fail if poisoned")
+                .sink()
+            {
+                sink.flush();
+            }
         }
     }
 
-    /// Remove a subscription sink from a session.
-    pub fn unsubscribe(&mut self, subscription_id: &jsonrpc_pubsub::SubscriptionId) -> Result<()> {
-        // Session id and subscription id are currently the same thing.
-        let session_id = types::SessionId::try_from(subscription_id)?;
-        match self.sessions.get_mut(&session_id) {
-            Some(_) => {
-                self.update_sink(&session_id, None);
-                log::debug!("Desubscribed subscription {}", session_id);
+    /// Get the overflow policy and lag counters of the subscription sink of a session, if any.
+    pub fn get_sink_stats(
+        &self,
+        session_id: &types::SessionId,
+    ) -> Option<(sink::OverflowPolicy, sink::SinkStats)> {
+        self.client_subscriptions.get(session_id).and_then(|sink| {
+            sink.read()
+                .expect("Read locks should only fail if poisoned")
+                .sink()
+                .map(|sink| (sink.policy(), sink.stats()))
+        })
+    }
 
-                Ok(())
+    /// Remove a subscription on behalf of the session `caller`, which has to own it.
+    pub fn unsubscribe(
+        &mut self,
+        subscription_id: &jsonrpc_pubsub::SubscriptionId,
+        caller: &types::SessionId,
+    ) -> Result<()> {
+        match self.subscriptions.get(subscription_id) {
+            Some(subscription) if &subscription.session_id == caller => {
+                self.remove_subscription(subscription_id)
             }
-            None => Err(Error::SessionNotFound),
+            Some(_) => Err(Error::SubscriptionForbidden),
+            None => Err(Error::SubscriptionNotFound),
         }
     }
 
-    /// Remove a session but keep its wallets.
+    /// Session on whose behalf a WebSocket connection removes a subscription: the one that owns
+    /// it if the connection subscribed to it, or else any other session it subscribed to.
+    pub fn connection_session(
+        &self,
+        connection: &Arc<jsonrpc_pubsub::Session>,
+        subscription_id: &jsonrpc_pubsub::SubscriptionId,
+    ) -> Option<types::SessionId> {
+        self.subscriptions
+            .get(subscription_id)
+            .filter(|subscription| subscription.is_through(connection))
+            .or_else(|| {
+                self.subscriptions
+                    .values()
+                    .find(|subscription| subscription.is_through(connection))
+            })
+            .map(|subscription| subscription.session_id.clone())
+    }
+
+    /// Remove a subscription and detach its transport from the sink of its session, which keeps
+    /// the events for a client that subscribes again.
+    pub fn remove_subscription(
+        &mut self,
+        subscription_id: &jsonrpc_pubsub::SubscriptionId,
+    ) -> Result<()> {
+        let subscription = self
+            .subscriptions
+            .remove(subscription_id)
+            .ok_or(Error::SubscriptionNotFound)?;
+        if let Some(sink) = self.client_subscriptions.get(&subscription.session_id) {
+            sink.write()
+                .expect("Write locks should only fail if poisoned")
+                .set_sink(None);
+        }
+        log::debug!(
+            "Desubscribed subscription of session {}",
+            subscription.session_id
+        );
+
+        Ok(())
+    }
+
+    /// Remove a session but keep its wallets. Its subscription goes away along with it.
     pub fn remove_session(&mut self, session_id: &types::SessionId) -> Result<()> {
-        let subscription_id = jsonrpc_pubsub::SubscriptionId::from(session_id);
-        self.unsubscribe(&subscription_id).map(|_| ())?;
+        if !self.sessions.contains_key(session_id) {
+            return Err(Error::SessionNotFound);
+        }
+        self.subscriptions
+            .retain(|_, subscription| &subscription.session_id != session_id);
+        self.client_subscriptions.remove(session_id);
+        self.pending_sends
+            .retain(|_, pending| &pending.session_id != session_id);
         self.sessions
             .remove(session_id)
             .map(|_| ())
             .ok_or(Error::SessionNotFound)
     }
 
+    /// Close a session to make room for a newer one, letting its client know through its sink.
+    pub fn evict_session(&mut self, session_id: &types::SessionId) -> Result<()> {
+        if let Some(sink) = self.client_subscriptions.get(session_id) {
+            let lock = sink
+                .read()
+                .expect("Read locks should only fail if poisoned");
+            if let Some(sink) = lock.sink() {
+                let notification = sink::session_evicted_notification(&session_id.to_string());
+                if let Err(err) = sink.notify(notification) {
+                    log::warn!(
+                        "Couldn't notify eviction of session {}: {}",
+                        session_id,
+                        err
+                    );
+                }
+            }
+        }
+        log::info!("Session {} evicted", session_id);
+
+        self.remove_session(session_id)
+    }
+
+    /// Record activity in a session, which protects it from eviction.
+    pub fn touch_session(&mut self, session_id: &types::SessionId) {
+        if let Some(session) = self.sessions.get_mut(session_id) {
+            let now = get_timestamp();
+            session.last_activity = now;
+            self.last_authenticated_activity.set(now);
+        }
+    }
+
+    /// Choose the sessions that have to be closed so that a new session of `wallet_id` fits
+    /// within `limits`. The service session neither counts towards the limits nor is evicted.
+    pub fn sessions_to_evict(
+        &self,
+        wallet_id: &model::WalletId,
+        limits: &SessionLimits,
+    ) -> Result<Vec<types::SessionId>> {
+        sessions_to_evict(
+            self.sessions
+                .iter()
+                .filter(|(_, session)| !session.service)
+                .map(|(session_id, session)| {
+                    (
+                        session_id.clone(),
+                        session.wallets.contains_key(wallet_id),
+                        session.last_activity,
+                    )
+                }),
+            wallet_id,
+            limits,
+        )
+    }
+
     /// Remove a wallet completely.
-    pub fn remove_wallet(&mut self, session_id: &types::SessionId, wallet_id: &str) -> Result<()> {
+    pub fn remove_wallet(
+        &mut self,
+        session_id: &types::SessionId,
+        wallet_id: &model::WalletId,
+    ) -> Result<()> {
         let session = self
             .sessions
             .get_mut(session_id)
@@ -148,26 +469,286 @@ impl State {
         Ok(())
     }
 
+    /// Lock every unlocked wallet at once, dropping their keys from every session.
+    ///
+    /// Sessions and their subscriptions are kept, so wallets can be unlocked again in them. Every
+    /// subscribed client is told which of its wallets were locked. Returns how many wallets were
+    /// locked.
+    pub fn lock_all_wallets(&mut self) -> usize {
+        let locked = self.lock_wallets(false);
+        log::info!("Locked all the {} unlocked wallets", locked);
+
+        locked
+    }
+
+    /// Whether any wallet would be locked by `lock_idle_wallets`.
+    pub fn has_idle_wallets(&self) -> bool {
+        let service_wallets = self.service_wallets();
+
+        self.wallets
+            .keys()
+            .any(|wallet_id| !service_wallets.contains(wallet_id))
+    }
+
+    /// Lock every unlocked wallet like `lock_all_wallets`, except the ones of the service session,
+    /// which are exempt from the idle lock. Returns how many wallets were locked.
+    pub fn lock_idle_wallets(&mut self) -> usize {
+        self.lock_wallets(true)
+    }
+
+    /// Ids of the wallets unlocked in the service session.
+    fn service_wallets(&self) -> HashSet<model::WalletId> {
+        self.sessions
+            .values()
+            .filter(|session| session.service)
+            .flat_map(|session| session.wallets.keys().cloned())
+            .collect()
+    }
+
+    fn lock_wallets(&mut self, keep_service_wallets: bool) -> usize {
+        let kept = if keep_service_wallets {
+            self.service_wallets()
+        } else {
+            HashSet::new()
+        };
+        // Running synchronizations would otherwise keep using the keys
+        let stop_syncing = |wallet: &types::SessionWallet| {
+            if let Err(e) = wallet.set_stop_syncing() {
+                log::warn!(
+                    "Couldn't stop synchronization of wallet {}: {}",
+                    wallet.id,
+                    e
+                );
+            }
+        };
+
+        let mut locked = 0;
+        self.wallets.retain(|wallet_id, wallet| {
+            let keep = kept.contains(wallet_id);
+            if !keep {
+                stop_syncing(wallet);
+                locked += 1;
+            }
+
+            keep
+        });
+
+        for (session_id, session) in self.sessions.iter_mut() {
+            if keep_service_wallets && session.service {
+                continue;
+            }
+            let mut wallet_ids: Vec<model::WalletId> = session
+                .wallets
+                .drain()
+                .map(|(wallet_id, wallet)| {
+                    if !kept.contains(&wallet_id) {
+                        stop_syncing(&wallet);
+                    }
+                    wallet_id
+                })
+                .collect();
+            wallet_ids.sort();
+
+            if let Some(sink) = self.client_subscriptions.get(session_id) {
+                let lock = sink
+                    .read()
+                    .expect("Read locks should only fail if poisoned");
+                if let Some(sink) = lock.sink() {
+                    let notification = sink::wallets_locked_notification(&wallet_ids);
+                    if let Err(err) = sink.notify(notification) {
+                        log::warn!(
+                            "Couldn't notify locking of wallets to session {}: {}",
+                            session_id,
+                            err
+                        );
+                    }
+                }
+            }
+        }
+
+        locked
+    }
+
+    /// Seconds elapsed since the last request that used a session, as of `now`.
+    pub fn idle_for(&self, now: i64) -> u64 {
+        u64::try_from(now.saturating_sub(self.last_authenticated_activity.get())).unwrap_or(0)
+    }
+
     /// Insert a new wallet into the state of the session if it is not already present.
+    ///
+    /// New sessions get the given `scopes`, or the default ones if none are given. As the wallet
+    /// password has been given again, the `scopes` are added to those of an existing session.
+    /// The API version declared by the client, if any, replaces the one the session had.
     pub fn create_session(
         &mut self,
         session_id: types::SessionId,
-        wallet_id: String,
+        wallet_id: model::WalletId,
         wallet: types::SessionWallet,
+        scopes: Option<&[types::Scope]>,
+        client_api_version: Option<u32>,
     ) {
-        let entry = self.sessions.entry(session_id);
-        let wallets = &mut entry.or_default().wallets;
-
-        wallets.insert(wallet_id.clone(), wallet.clone());
+        let now = get_timestamp();
+        let new_session = !self.sessions.contains_key(&session_id);
+        let session = self.sessions.entry(session_id).or_default();
+        if let Some(scopes) = scopes {
+            if new_session {
+                session.scopes.clear();
+            }
+            session.scopes.extend(scopes);
+        }
+        if client_api_version.is_some() {
+            session.client_api_version = client_api_version;
+        }
+        session.last_activity = now;
+        self.last_authenticated_activity.set(now);
+        session.wallets.insert(wallet_id.clone(), wallet.clone());
 
         self.wallets.insert(wallet_id, wallet);
     }
 
+    /// Open the service session in which the wallets of the credentials file are unlocked, with
+    /// the given `scopes`, or the default ones if none are given.
+    pub fn create_service_session(
+        &mut self,
+        session_id: types::SessionId,
+        scopes: Option<&[types::Scope]>,
+    ) {
+        let session = self.sessions.entry(session_id).or_default();
+        if let Some(scopes) = scopes {
+            session.scopes = scopes.iter().copied().collect();
+        }
+        session.service = true;
+        session.last_activity = get_timestamp();
+    }
+
+    /// Seconds to wait as of `now` before trying to unlock a wallet again, or `None` if it can be
+    /// unlocked right away.
+    pub fn unlock_retry_after(&self, wallet_id: &model::WalletId, now: i64) -> Option<u64> {
+        let failed = self.failed_unlocks.get(wallet_id)?;
+        let elapsed = u64::try_from(now.saturating_sub(failed.last_attempt)).unwrap_or(0);
+
+        unlock_delay(failed.attempts.saturating_add(failed.in_flight))
+            .checked_sub(elapsed)
+            .filter(|remaining| *remaining > 0)
+    }
+
+    /// Reserve an attempt to unlock a wallet at `now`, before its password is checked, so that
+    /// attempts made while others are running are throttled as if those had failed. Returns the
+    /// seconds to wait if it cannot be tried yet.
+    ///
+    /// The attempt must then be settled with `record_failed_unlock`, `reset_failed_unlocks` or
+    /// `release_unlock`.
+    pub fn reserve_unlock(&mut self, wallet_id: &model::WalletId, now: i64) -> Result<(), u64> {
+        if let Some(retry_after) = self.unlock_retry_after(wallet_id, now) {
+            return Err(retry_after);
+        }
+        let failed = self.failed_unlocks.entry(wallet_id.clone()).or_default();
+        failed.in_flight = failed.in_flight.saturating_add(1);
+        failed.last_attempt = now;
+
+        Ok(())
+    }
+
+    /// Record that a wrong password was given for a wallet at `now`. Returns the seconds to wait
+    /// before trying to unlock it again.
+    pub fn record_failed_unlock(&mut self, wallet_id: &model::WalletId, now: i64) -> u64 {
+        let failed = self.failed_unlocks.entry(wallet_id.clone()).or_default();
+        failed.in_flight = failed.in_flight.saturating_sub(1);
+        failed.attempts = failed.attempts.saturating_add(1);
+        failed.last_attempt = now;
+
+        unlock_delay(failed.attempts)
+    }
+
+    /// Give back an attempt to unlock a wallet that failed before its password could be checked.
+    pub fn release_unlock(&mut self, wallet_id: &model::WalletId) {
+        if let Some(failed) = self.failed_unlocks.get_mut(wallet_id) {
+            failed.in_flight = failed.in_flight.saturating_sub(1);
+            if failed.attempts == 0 && failed.in_flight == 0 {
+                self.failed_unlocks.remove(wallet_id);
+            }
+        }
+    }
+
+    /// Forget the failed attempts to unlock a wallet, once it has been unlocked.
+    pub fn reset_failed_unlocks(&mut self, wallet_id: &model::WalletId) {
+        self.failed_unlocks.remove(wallet_id);
+    }
+
     /// Get latest session for a wallet ID
     pub fn get_current_wallet_session(
         &mut self,
-        wallet_id: String,
+        wallet_id: model::WalletId,
     ) -> Option<&types::SessionWallet> {
         self.wallets.get(&wallet_id)
     }
 }
+
+/// Seconds to wait before trying to unlock a wallet after `attempts` wrong passwords in a row. The
+/// delay doubles with every attempt, up to `constants::MAX_UNLOCK_DELAY`.
+pub fn unlock_delay(attempts: u32) -> u64 {
+    match attempts {
+        0 => 0,
+        attempts => 1u64
+            .checked_shl(attempts - 1)
+            .unwrap_or(u64::MAX)
+            .min(constants::MAX_UNLOCK_DELAY),
+    }
+}
+
+/// Choose the sessions to close so that a new session of `wallet_id` fits within `limits`.
+///
+/// `sessions` yields every open session along with whether it has `wallet_id` unlocked and the
+/// timestamp of its last activity. Under the `Reject` policy, nothing is ever evicted and an
+/// error is returned instead.
+pub fn sessions_to_evict<I>(
+    sessions: I,
+    wallet_id: &model::WalletId,
+    limits: &SessionLimits,
+) -> Result<Vec<types::SessionId>>
+where
+    I: IntoIterator<Item = (types::SessionId, bool, i64)>,
+{
+    // Least recently active first
+    let mut sessions: Vec<_> = sessions.into_iter().collect();
+    sessions.sort_by_key(|(session_id, _, last_activity)| (*last_activity, session_id.to_string()));
+
+    let mut evicted = vec![];
+    let same_wallet = sessions.iter().filter(|(_, unlocked, _)| *unlocked).count();
+    if same_wallet >= limits.per_wallet {
+        if limits.policy == SessionLimitPolicy::Reject || limits.per_wallet == 0 {
+            return Err(Error::TooManySessions {
+                wallet_id: Some(wallet_id.to_string()),
+                limit: limits.per_wallet,
+            });
+        }
+        evicted.extend(
+            sessions
+                .iter()
+                .filter(|(_, unlocked, _)| *unlocked)
+                .take(same_wallet + 1 - limits.per_wallet)
+                .map(|(session_id, _, _)| session_id.clone()),
+        );
+    }
+
+    let remaining = sessions.len() - evicted.len();
+    if remaining >= limits.total {
+        if limits.policy == SessionLimitPolicy::Reject || limits.total == 0 {
+            return Err(Error::TooManySessions {
+                wallet_id: None,
+                limit: limits.total,
+            });
+        }
+        let already_evicted = evicted.clone();
+        evicted.extend(
+            sessions
+                .iter()
+                .map(|(session_id, _, _)| session_id)
+                .filter(|session_id| !already_evicted.contains(session_id))
+                .take(remaining + 1 - limits.total)
+                .cloned(),
+        );
+    }
+
+    Ok(evicted)
+}
@@ -0,0 +1,3027 @@
+use std::{
+    sync::atomic::Ordering,
+    time::{Duration, Instant},
+};
+
+use actix::System;
+use futures::StreamExt;
+use serde_json::{json, Value};
+use witnet_config::config::Config;
+use witnet_data_structures::{
+    chain::{
+        tapi::current_active_wips, CheckpointBeacon, Environment, Hash, Hashable, Input,
+        OutputPointer, PublicKeyHash, RADAggregate, RADRequest, RADRetrieve, RADTally, RADType,
+        StateMachine, SyncStatus, ValueTransferOutput,
+    },
+    proto::ProtobufConvert,
+    transaction::{RevealTransactionBody, Transaction, VTTransaction, VTTransactionBody},
+};
+use witnet_util::timestamp::get_timestamp;
+
+use crate::{
+    api_version, crypto, metrics,
+    testing::{
+        find_event, in_memory_db, set_storage_latency, Harness, MockEndpoint, MockResponse, TcpNode,
+    },
+    watchdog,
+};
+
+const MNEMONICS: &str = "day voice lake monkey suit bread occur own cattle visit object ordinary";
+const OTHER_MNEMONICS: &str =
+    "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about";
+const PASSWORD: &str = "12345678";
+
+#[test]
+fn test_unlocked_wallet_syncs_and_notifies_movements() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        // The node already has a block when the wallet is created
+        node.mine_block(vec![]);
+
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+
+        assert_eq!(notifications.next_event("SyncFinish").await, json!([0, 1]));
+
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        let block = node.mine_block(vec![ValueTransferOutput {
+            pkh,
+            value: 1_000,
+            time_lock: 0,
+        }]);
+
+        let beacon = notifications.next_event("Block").await;
+        assert_eq!(beacon["epoch"], json!("2"));
+        assert_eq!(
+            beacon["block_hash"],
+            serde_json::to_value(block.hash()).unwrap()
+        );
+        let movement = notifications.next_event("Movement").await;
+        assert_eq!(movement["type"], json!("POSITIVE"));
+        assert_eq!(movement["amount"], json!("1000"));
+    });
+}
+
+#[test]
+fn test_wallet_born_late_in_a_long_chain_only_syncs_the_blocks_after_its_birth() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        for _ in 0..300 {
+            node.mine_block(vec![]);
+        }
+        let epochs_of = |requests: &[Value]| -> Vec<u32> {
+            let chain = node.chain();
+            requests
+                .iter()
+                .map(|params| {
+                    chain
+                        .iter()
+                        .find(|block| params[0] == json!(block.hash().to_string()))
+                        .unwrap()
+                        .block_header
+                        .beacon
+                        .checkpoint
+                })
+                .collect()
+        };
+
+        let wallet_id = harness
+            .call(
+                "create_wallet",
+                json!({
+                    "password": PASSWORD,
+                    "seed_source": "mnemonics",
+                    "seed_data": MNEMONICS,
+                    "birth_date": { "imported": 250 },
+                }),
+            )
+            .await
+            .unwrap()["wallet_id"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let fetched = node.requests_of("getBlock").len();
+        let unlocked = harness
+            .call(
+                "unlock_wallet",
+                json!({ "wallet_id": wallet_id, "password": PASSWORD }),
+            )
+            .await
+            .unwrap();
+        // The birth date is the last block that was surely consolidated by a superblock
+        assert_eq!(unlocked["birth_date"], json!(229));
+        let session_id = unlocked["session_id"].as_str().unwrap().to_string();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+
+        // Progress is reported over the blocks after the birth date, which are the only ones
+        // fetched, and only once
+        assert_eq!(
+            notifications.next_event("SyncStart").await,
+            json!([229, 300])
+        );
+        assert_eq!(
+            notifications.next_event("SyncProgress").await[0],
+            json!(229)
+        );
+        assert_eq!(
+            notifications.next_event("SyncFinish").await,
+            json!([229, 300])
+        );
+        assert_eq!(
+            epochs_of(&node.requests_of("getBlock")[fetched..]),
+            (230..=300).collect::<Vec<_>>()
+        );
+        assert!(node
+            .requests_of("getBlockChain")
+            .iter()
+            .all(|params| params["epoch"].as_i64().unwrap() == 0
+                || params["epoch"].as_i64().unwrap() >= 229));
+
+        // Resynchronizing from genesis goes over the whole chain
+        let fetched = node.requests_of("getBlock").len();
+        harness
+            .call(
+                "resync_wallet",
+                json!({ "session_id": session_id, "wallet_id": wallet_id, "from": "genesis" }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            notifications.next_event("SyncFinish").await,
+            json!([0, 300])
+        );
+        assert_eq!(
+            epochs_of(&node.requests_of("getBlock")[fetched..]),
+            (0..=300).collect::<Vec<_>>()
+        );
+    });
+}
+
+#[test]
+fn test_malformed_wallet_ids_are_rejected() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        assert_eq!(wallet_id.len(), 64);
+
+        for malformed in &["", "../wallets", "wallet id", "wället", &"a".repeat(129)] {
+            let err = harness
+                .unlock_wallet(malformed, PASSWORD)
+                .await
+                .unwrap_err();
+            assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+            assert_eq!(err.data.unwrap()[0][0], json!("wallet_id"));
+        }
+
+        // Ids with the format of older versions are accepted, and this one is just not found
+        let err = harness
+            .unlock_wallet("Legacy_Wallet-01", PASSWORD)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(402));
+    });
+}
+
+#[test]
+fn test_transactions_are_streamed_in_batches() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let stream = |batch_size: Value| {
+            harness.call(
+                "get_transactions_stream",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "batch_size": batch_size,
+                }),
+            )
+        };
+
+        let batch = stream(Value::Null).await.unwrap();
+        assert_eq!(
+            batch,
+            json!({
+                "transactions": [],
+                "cursor": "0000000000000000",
+                "finished": true,
+            })
+        );
+
+        for batch_size in [0, 1_001] {
+            let err = stream(json!(batch_size)).await.unwrap_err();
+            assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+            assert_eq!(err.data.unwrap()[0][0], json!("batch_size"));
+        }
+    });
+}
+
+#[test]
+fn test_bump_fee_of_unknown_transaction_is_rejected() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+
+        for transaction_hash in ["not a hash", &"ab".repeat(32)] {
+            let err = harness
+                .call(
+                    "bump_fee",
+                    json!({
+                        "session_id": session_id,
+                        "wallet_id": wallet_id,
+                        "transaction_hash": transaction_hash,
+                        "fee": "100",
+                    }),
+                )
+                .await
+                .unwrap_err();
+            assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+            assert_eq!(err.data.unwrap()[0][0], json!("transaction_hash"));
+        }
+    });
+}
+
+#[test]
+fn test_session_expires_unless_refreshed() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|conf| conf.wallet.session_expires_in = 60)
+            .start()
+            .unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let session = json!({ "session_id": session_id, "wallet_id": wallet_id });
+
+        harness.advance(Duration::from_secs(59)).await;
+        assert!(harness.call("get_balance", session.clone()).await.is_ok());
+
+        // Refreshing postpones the expiration until a whole period after the refresh
+        let refreshed = harness
+            .call("refresh_session", json!({ "session_id": session_id }))
+            .await
+            .unwrap();
+        assert_eq!(refreshed, json!({ "success": true }));
+        harness.advance(Duration::from_secs(59)).await;
+        assert!(harness.call("get_balance", session.clone()).await.is_ok());
+
+        harness.advance(Duration::from_secs(1)).await;
+        let err = harness.call("get_balance", session).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(401));
+    });
+}
+
+#[test]
+fn test_node_disconnection_is_notified_and_subscriptions_recovered() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+        assert_eq!(
+            node.requests_of("witnet_subscribe"),
+            vec![json!(["blocks"]), json!(["superblocks"])]
+        );
+
+        // The next periodic check of the node status finds it disconnected
+        node.disconnect();
+        harness.advance(Duration::from_secs(15)).await;
+        notifications.next_event("NodeDisconnected").await;
+
+        // Blocks mined in the meantime are not notified
+        let missed = node.mine_block(vec![]);
+        node.reconnect();
+        let mut recovered = node.requests_of("witnet_subscribe").split_off(2);
+        recovered.sort_by_key(|params| params.to_string());
+        assert_eq!(recovered, vec![json!(["blocks"]), json!(["superblocks"])]);
+
+        // Once the node is back, the wallet synchronizes again and catches up with missed blocks
+        harness.advance(Duration::from_secs(15)).await;
+        assert_eq!(
+            notifications.next_event("NodeStatus").await,
+            json!("Synced")
+        );
+        assert_eq!(
+            notifications.next_event("SyncFinish").await,
+            json!([0, missed.block_header.beacon.checkpoint])
+        );
+
+        // And new blocks are notified through the recovered subscription
+        let block = node.mine_block(vec![]);
+        let beacon = notifications.next_event("Block").await;
+        assert_eq!(
+            beacon["block_hash"],
+            serde_json::to_value(block.hash()).unwrap()
+        );
+    });
+}
+
+#[test]
+fn test_signatures_are_only_valid_in_their_domain() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let sign = |domain: Value| {
+            harness.call(
+                "sign_data",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "data": "I own this wallet",
+                    "domain": domain,
+                }),
+            )
+        };
+
+        let proof = sign(json!("proof_of_ownership")).await.unwrap();
+        let message = sign(json!("arbitrary_message")).await.unwrap();
+        assert_eq!(proof["domain"], json!("proof_of_ownership"));
+        assert_ne!(proof["signature"], message["signature"]);
+        assert_ne!(proof["digest"], message["digest"]);
+        // Data is never signed raw unless asked explicitly
+        assert!(sign(Value::Null).await.is_err());
+
+        let verify = |domain: &str| {
+            harness.call(
+                "verify_data",
+                json!({
+                    "data": "I own this wallet",
+                    "domain": domain,
+                    "public_key": proof["public_key"],
+                    "signature": proof["signature"],
+                }),
+            )
+        };
+        let verified = verify("proof_of_ownership").await.unwrap();
+        assert_eq!(verified["valid"], json!(true));
+        assert_eq!(verified["digest"], proof["digest"]);
+        let verified = verify("arbitrary_message").await.unwrap();
+        assert_eq!(verified["valid"], json!(false));
+    });
+}
+
+#[test]
+fn test_nodes_only_get_witness_transactions_signed() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        let reveal = RevealTransactionBody::new(Hash::SHA256([1; 32]), vec![0x18, 0x2a], pkh);
+        let sign = |payload: Value| {
+            harness.call(
+                "sign_witness_transaction",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "payload": payload,
+                }),
+            )
+        };
+
+        // Sessions need to ask for the scope explicitly
+        let err = sign(json!({ "type": "reveal", "body": reveal }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.message, "Insufficient Scope");
+        assert_eq!(
+            err.data.unwrap()["required_scope"],
+            json!("witness_signing")
+        );
+        let unlocked = harness
+            .call(
+                "unlock_wallet",
+                json!({
+                    "wallet_id": wallet_id,
+                    "password": PASSWORD,
+                    "session_id": session_id,
+                    "scopes": ["witness_signing"],
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            unlocked["scopes"],
+            json!(["read", "send", "sign", "admin", "witness_signing"])
+        );
+
+        // And wallets need to allow the type of transaction
+        let err = sign(json!({ "type": "reveal", "body": reveal }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(403));
+        assert_eq!(err.message, "Witness Signing Not Allowed");
+        harness
+            .call(
+                "update_witness_signing_policy",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "password": PASSWORD,
+                    "policy": { "allowed_types": ["commit", "reveal"] },
+                }),
+            )
+            .await
+            .unwrap();
+
+        // Value transfers are never signed, even if they are disguised as a witness transaction
+        let vtt = VTTransactionBody::new(
+            vec![Input::new(OutputPointer::default())],
+            vec![ValueTransferOutput {
+                pkh: PublicKeyHash::default(),
+                value: 500,
+                time_lock: 0,
+            }],
+        );
+        for kind in ["value_transfer", "reveal"] {
+            let err = sign(json!({ "type": kind, "body": vtt }))
+                .await
+                .unwrap_err();
+            assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(403));
+            assert_eq!(err.message, "Payload Moves Funds");
+        }
+
+        let signed = sign(json!({ "type": "reveal", "body": reveal }))
+            .await
+            .unwrap();
+        assert_eq!(signed["audit"]["type"], json!("reveal"));
+        assert_eq!(signed["audit"]["hash"], json!(reveal.hash().to_string()));
+        assert_eq!(
+            signed["audit"]["dr_pointer"],
+            json!(Hash::SHA256([1; 32]).to_string())
+        );
+        assert_eq!(signed["audit"]["address"], address["address"]);
+        assert!(signed["signature"]["public_key"].is_object());
+    });
+}
+
+#[test]
+fn test_sends_above_threshold_wait_for_confirmation() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        let funds = ValueTransferOutput {
+            pkh,
+            value: 500,
+            time_lock: 0,
+        };
+        node.mine_block(vec![funds.clone(), funds]);
+        notifications.next_event("Movement").await;
+
+        let update_policy = |password: &str, daily_spend_limit: &str| {
+            harness.call(
+                "update_wallet_policy",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "password": password,
+                    "policy": {
+                        "daily_spend_limit": daily_spend_limit,
+                        "confirmation_threshold": "100",
+                    },
+                }),
+            )
+        };
+        let err = update_policy("wrong password", "500").await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(406));
+        update_policy(PASSWORD, "500").await.unwrap();
+
+        let create_vtt = || {
+            harness.call(
+                "create_vtt",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "fee": "0",
+                    "outputs": [{
+                        "address": PublicKeyHash::default().bech32(Environment::Mainnet),
+                        "amount": "200",
+                    }],
+                }),
+            )
+        };
+        let send = |transaction: Value| {
+            harness.call(
+                "send_transaction",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "transaction": transaction,
+                }),
+            )
+        };
+        let confirm = |token: Value| {
+            harness.call(
+                "confirm_send",
+                json!({ "session_id": session_id, "token": token }),
+            )
+        };
+
+        // Nothing is broadcast until the sending is confirmed, and tokens can only be used once
+        let created = create_vtt().await.unwrap();
+        assert_eq!(created["spending"]["requires_confirmation"], json!(true));
+        let sent = send(created["transaction"].clone()).await.unwrap();
+        let token = sent["requires_confirmation"]["token"].clone();
+        assert!(node.requests_of("inventory").is_empty());
+        let confirmed = confirm(token.clone()).await.unwrap();
+        assert_eq!(confirmed["balance_movement"]["amount"], json!("200"));
+        assert_eq!(node.requests_of("inventory").len(), 1);
+        let err = confirm(token).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(404));
+
+        // Tokens expire if they are not used in time
+        let transaction = create_vtt().await.unwrap()["transaction"].clone();
+        let sent = send(transaction.clone()).await.unwrap();
+        assert_eq!(
+            sent["requires_confirmation"]["spending"]["spent"],
+            json!("200")
+        );
+        harness.advance(Duration::from_secs(301)).await;
+        let err = confirm(sent["requires_confirmation"]["token"].clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(404));
+        assert_eq!(node.requests_of("inventory").len(), 1);
+
+        // Sends that would exceed the daily spend limit are rejected before asking for a token
+        update_policy(PASSWORD, "300").await.unwrap();
+        let err = send(transaction).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(412));
+        assert_eq!(err.data.unwrap()["remaining_allowance"], json!("100"));
+    });
+}
+
+#[test]
+fn test_batch_payments_keep_their_order_and_labels() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|conf| conf.wallet.max_vtt_outputs = 100)
+            .start()
+            .unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        node.mine_block(vec![ValueTransferOutput {
+            pkh,
+            value: 100_000,
+            time_lock: 0,
+        }]);
+        notifications.next_event("Movement").await;
+
+        let recipient = |i: u8| {
+            PublicKeyHash::from_bytes(&[i; 20])
+                .unwrap()
+                .bech32(Environment::Mainnet)
+        };
+        let batch = |size: u8| {
+            (1..=size)
+                .map(|i| {
+                    // The last recipient is paid twice
+                    let address = recipient(if i == size { 1 } else { i });
+                    json!({
+                        "address": address,
+                        "amount": (u64::from(i) * 10).to_string(),
+                        "label": format!("invoice #{}", i),
+                    })
+                })
+                .collect::<Vec<_>>()
+        };
+        let create_vtt = |outputs: Vec<Value>| {
+            harness.call(
+                "create_vtt",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "fee": "1",
+                    "fee_type": "weighted",
+                    "outputs": outputs,
+                }),
+            )
+        };
+
+        let outputs = batch(25);
+        let created = create_vtt(outputs.clone()).await.unwrap();
+        let metadata = &created["metadata"];
+        assert_eq!(metadata["duplicate_addresses"], json!([recipient(1)]));
+        assert_eq!(metadata["changeless"], json!(false));
+        // The fee is weighted by the whole transaction, with every recipient and the change
+        let weight: u64 = metadata["weight"].as_str().unwrap().parse().unwrap();
+        assert_eq!(metadata["fee"], json!(weight));
+
+        let sent = harness
+            .call(
+                "send_transaction",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "transaction": created["transaction"],
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(sent["balance_movement"]["type"], json!("NEGATIVE"));
+
+        let transactions = harness
+            .call(
+                "get_transactions",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let movement = &transactions["transactions"][0];
+        assert_eq!(movement["transaction"]["hash"], created["transaction_id"]);
+        let paid = movement["transaction"]["data"]["value_transfer"]["outputs"]
+            .as_array()
+            .unwrap();
+        // Recipients are paid in the order given, followed by the change
+        assert_eq!(paid.len(), outputs.len() + 1);
+        for (paid, output) in paid.iter().zip(&outputs) {
+            assert_eq!(paid["address"], output["address"]);
+            assert_eq!(paid["value"], output["amount"]);
+            assert_eq!(paid["label"], output["label"]);
+        }
+        assert_eq!(paid[25]["label"], Value::Null);
+
+        // Batches that do not fit in the weight limit of a value transfer are rejected upfront
+        let err = create_vtt(batch(60)).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        assert_eq!(err.data.unwrap()[0][0], json!("outputs"));
+    });
+}
+
+#[test]
+fn test_data_requests_are_exchanged_as_json() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let json = json!({
+            "version": 1,
+            "time_lock": 0,
+            "retrieve": [{
+                "kind": "HTTP-GET",
+                "url": "https://api.coindesk.com/v1/bpi/currentprice.json",
+                "script": [
+                    "StringParseJSONMap",
+                    ["MapGetMap", "bpi"],
+                    ["MapGetFloat", "rate_float"],
+                    ["FloatMultiply", 1000],
+                    "FloatRound"
+                ],
+                "body": "",
+                "headers": []
+            }],
+            "aggregate": { "filters": [], "reducer": "AverageMean" },
+            "tally": {
+                "filters": [{ "op": "DeviationStandard", "args": 1.5 }],
+                "reducer": "AverageMean"
+            }
+        });
+
+        let data_request = harness
+            .call("import_data_request_json", json!({ "data_request": json }))
+            .await
+            .unwrap();
+        let exported = harness
+            .call(
+                "export_data_request_json",
+                json!({ "data_request": data_request }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(exported, json);
+
+        let mut misspelled = json.clone();
+        misspelled["retrieve"][0]["script"][2][0] = json!("MapGetFlaot");
+        let err = harness
+            .call(
+                "import_data_request_json",
+                json!({ "data_request": misspelled }),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        assert_eq!(err.data.unwrap()[0][0], json!("data_request"));
+    });
+}
+
+#[test]
+fn test_pinned_wallet_ignores_later_blocks_until_unpinned() {
+    System::new().block_on(async {
+        // A single worker thread handles the blocks notified by the node in order with requests
+        let harness = Harness::builder()
+            .config(|conf| conf.wallet.concurrency = Some(1))
+            .start()
+            .unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        let pay = |value: u64| {
+            vec![ValueTransferOutput {
+                pkh,
+                value,
+                time_lock: 0,
+            }]
+        };
+        let first = node.mine_block(pay(1_000));
+        notifications.next_event("Movement").await;
+
+        let pin_sync = |epoch: Value| {
+            harness.call(
+                "pin_sync",
+                json!({ "session_id": session_id, "wallet_id": wallet_id, "epoch": epoch }),
+            )
+        };
+        let sync_status = || {
+            harness.call(
+                "get_sync_status",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+        };
+        let available = || async {
+            let balance = harness
+                .call(
+                    "get_balance",
+                    json!({ "session_id": session_id, "wallet_id": wallet_id }),
+                )
+                .await
+                .unwrap();
+
+            balance["unconfirmed"]["available"].clone()
+        };
+
+        let pinned = pin_sync(json!(1)).await.unwrap();
+        assert_eq!(pinned["last_sync"]["epoch"], json!("1"));
+        assert_eq!(
+            pinned["last_sync"]["block_hash"],
+            serde_json::to_value(first.hash()).unwrap()
+        );
+        assert_eq!(pinned["sync_target_epoch"], json!("1"));
+        assert_eq!(pinned["syncing"], json!(false));
+        notifications.received();
+
+        // Blocks beyond the pinned epoch are ignored
+        node.mine_block(pay(500));
+        node.mine_block(pay(500));
+        harness.settle().await;
+        let status = sync_status().await.unwrap();
+        assert_eq!(status["last_sync"]["epoch"], json!("1"));
+        assert_eq!(available().await, json!("1000"));
+        assert!(notifications
+            .received()
+            .iter()
+            .all(|notification| find_event(notification, "Block").is_none()));
+
+        let infos = harness.call("get_wallet_infos", json!({})).await.unwrap();
+        assert_eq!(infos["infos"][0]["sync_target_epoch"], json!("1"));
+
+        // Transactions are built from a stale view of the unspent outputs unless forced
+        let create_vtt = |force: bool| {
+            harness.call(
+                "create_vtt",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "fee": "0",
+                    "outputs": [{
+                        "address": PublicKeyHash::default().bech32(Environment::Mainnet),
+                        "amount": "200",
+                    }],
+                    "preview": true,
+                    "force": force,
+                }),
+            )
+        };
+        let err = create_vtt(false).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(423));
+        assert_eq!(err.data.unwrap()["sync_target_epoch"], json!("1"));
+        create_vtt(true).await.unwrap();
+
+        // Unpinning catches up with the chain, and later blocks are processed as usual
+        let unpinned = pin_sync(Value::Null).await.unwrap();
+        assert_eq!(unpinned["last_sync"]["epoch"], json!("3"));
+        assert_eq!(unpinned["sync_target_epoch"], Value::Null);
+        assert_eq!(available().await, json!("2000"));
+        notifications.received();
+
+        node.mine_block(pay(250));
+        let beacon = notifications.next_event("Block").await;
+        assert_eq!(beacon["epoch"], json!("4"));
+        assert_eq!(available().await, json!("2250"));
+        let infos = harness.call("get_wallet_infos", json!({})).await.unwrap();
+        assert_eq!(infos["infos"][0]["sync_target_epoch"], Value::Null);
+    });
+}
+
+#[test]
+fn test_dry_run_sends_are_validated_by_the_node_without_broadcasting() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        node.mine_block(vec![ValueTransferOutput {
+            pkh,
+            value: 1_000,
+            time_lock: 0,
+        }]);
+        notifications.next_event("Movement").await;
+
+        let created = harness
+            .call(
+                "create_vtt",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "fee": "0",
+                    "outputs": [{
+                        "address": PublicKeyHash::default().bech32(Environment::Mainnet),
+                        "amount": "200",
+                    }],
+                }),
+            )
+            .await
+            .unwrap();
+        let transaction = created["transaction"].clone();
+        let dry_run = || {
+            harness.call(
+                "send_transaction",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "transaction": transaction,
+                    "dry_run": true,
+                }),
+            )
+        };
+        let balance = || {
+            harness.call(
+                "get_balance",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+        };
+        let balance_before = balance().await.unwrap();
+        notifications.received();
+
+        // Transactions the node accepts are neither broadcast nor added to the movements
+        node.reply(
+            "validateTransaction",
+            json!({ "verdict": "valid", "fee": 0 }),
+        );
+        let validated = dry_run().await.unwrap();
+        assert_eq!(validated["jsonrpc_result"]["verdict"], json!("valid"));
+        assert_eq!(validated["balance_movement"], Value::Null);
+
+        // Rejections carry the validation error of the node
+        node.reply(
+            "validateTransaction",
+            json!({
+                "verdict": "invalid",
+                "kind": "TransactionError",
+                "reason": "NegativeFee",
+                "message": "Transaction creates value (its fee is negative)",
+            }),
+        );
+        let err = dry_run().await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(417));
+        let data = err.data.unwrap();
+        assert_eq!(data["kind"], json!("TransactionError"));
+        assert_eq!(data["reason"], json!("NegativeFee"));
+
+        assert_eq!(node.requests_of("validateTransaction").len(), 2);
+        assert!(node.requests_of("inventory").is_empty());
+        harness.settle().await;
+        assert!(notifications
+            .received()
+            .iter()
+            .all(|notification| find_event(notification, "Movement").is_none()));
+        assert_eq!(balance().await.unwrap(), balance_before);
+
+        // Nodes without the validation method are not asked to broadcast the transaction instead
+        node.remove_method("validateTransaction");
+        let err = dry_run().await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(501));
+        assert_eq!(err.data.unwrap()["method"], json!("validateTransaction"));
+        let err = harness
+            .call("validate_transaction", transaction.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(501));
+        assert!(node.requests_of("inventory").is_empty());
+    });
+}
+
+#[test]
+fn test_expensive_data_requests_are_warned_about() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|conf| conf.wallet.data_request_cost_warning_threshold = 10_000)
+            .start()
+            .unwrap();
+        let json = json!({
+            "version": 1,
+            "time_lock": 0,
+            "retrieve": [{
+                "kind": "HTTP-GET",
+                "url": "https://api.example.com/price",
+                "script": ["StringParseJSONMap", ["MapGetFloat", "price"]],
+                "body": "",
+                "headers": []
+            }],
+            "aggregate": { "filters": [], "reducer": "AverageMean" },
+            "tally": { "filters": [], "reducer": "AverageMean" }
+        });
+        let data_request = harness
+            .call("import_data_request_json", json!({ "data_request": json }))
+            .await
+            .unwrap();
+        let estimate = |response_bytes: u64| {
+            let params = json!({
+                "request": {
+                    "data_request": data_request,
+                    "witness_reward": 1000,
+                    "witnesses": 10,
+                    "commit_and_reveal_fee": 10,
+                    "min_consensus_percentage": 51,
+                    "collateral": 1_000_000_000
+                },
+                "response_bytes": response_bytes,
+            });
+
+            harness.call("estimate_data_req_cost", params)
+        };
+
+        let estimation = estimate(1024).await.unwrap();
+        assert_eq!(
+            estimation["cost"],
+            json!({
+                "version": 1,
+                "retrieve": [5_232],
+                "aggregate": 11,
+                "tally": 20,
+                "total": 5_263
+            })
+        );
+        assert_eq!(estimation["sizes"]["reveals"], json!(10));
+        assert_eq!(estimation["warning"], Value::Null);
+
+        // Parsing a larger response is more expensive
+        let estimation = estimate(4096).await.unwrap();
+        assert_eq!(estimation["cost"]["total"], json!(20_623));
+        assert!(estimation["warning"].as_str().unwrap().contains("20623"));
+    });
+}
+
+#[test]
+fn test_sessions_only_call_the_methods_of_their_scopes() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let unlock = |session_id: Option<&str>, scopes: Value| {
+            harness.call(
+                "unlock_wallet",
+                json!({
+                    "wallet_id": wallet_id,
+                    "password": PASSWORD,
+                    "session_id": session_id,
+                    "scopes": scopes,
+                }),
+            )
+        };
+
+        let unlocked = unlock(None, json!(["read"])).await.unwrap();
+        assert_eq!(unlocked["scopes"], json!(["read"]));
+        let session_id = unlocked["session_id"].as_str().unwrap().to_string();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let params = json!({ "session_id": session_id, "wallet_id": wallet_id });
+        let balance = harness.call("get_balance", params.clone()).await.unwrap();
+        assert_eq!(balance["local"], json!("0"));
+
+        let create_vtt = || {
+            harness.call(
+                "create_vtt",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "fee": "0",
+                    "outputs": [{
+                        "address": PublicKeyHash::default().bech32(Environment::Mainnet),
+                        "amount": "200",
+                    }],
+                }),
+            )
+        };
+        let err = create_vtt().await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(403));
+        assert_eq!(err.message, "Insufficient Scope");
+        assert_eq!(err.data.unwrap()["required_scope"], json!("send"));
+        // The session of positional params cannot be told, so they cannot skip the check
+        let err = harness
+            .call(
+                "create_vtt",
+                json!([
+                    "0",
+                    null,
+                    null,
+                    [{
+                        "address": PublicKeyHash::default().bech32(Environment::Mainnet),
+                        "amount": "200",
+                    }],
+                    session_id,
+                    wallet_id,
+                ]),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        assert_eq!(err.data.unwrap()[0][0], json!("session_id"));
+        let err = harness
+            .call("generate_address", params.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.data.unwrap()["required_scope"], json!("admin"));
+
+        // Unlocking the wallet again in the session, with its password, escalates the scopes
+        let unlocked = unlock(Some(&session_id), json!(["send", "admin"]))
+            .await
+            .unwrap();
+        assert_eq!(unlocked["session_id"], json!(session_id));
+        assert_eq!(unlocked["scopes"], json!(["read", "send", "admin"]));
+        notifications.next_event("SyncFinish").await;
+
+        let address = harness.call("generate_address", params).await.unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        node.mine_block(vec![ValueTransferOutput {
+            pkh,
+            value: 500,
+            time_lock: 0,
+        }]);
+        notifications.next_event("Movement").await;
+        create_vtt().await.unwrap();
+
+        // Sessions opened without scopes keep having full access
+        let unlocked = unlock(None, Value::Null).await.unwrap();
+        assert_eq!(unlocked["scopes"], json!(["read", "send", "sign", "admin"]));
+    });
+}
+
+#[test]
+fn test_methods_with_an_optional_session_are_called_without_one() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let block = node.mine_block(vec![]);
+
+        let blocks = harness
+            .call("get_blocks", json!({ "epoch": 0 }))
+            .await
+            .unwrap();
+        assert!(blocks
+            .as_array()
+            .unwrap()
+            .iter()
+            .any(|summary| summary["hash"] == json!(block.hash().to_string())));
+
+        // Shutting down without a session is refused by the method itself while sessions are open,
+        // instead of being rejected before reaching it
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let err = harness.call("shutdown", json!({})).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(401));
+        assert_eq!(err.message, "Unauthorized");
+    });
+}
+
+#[test]
+fn test_explorer_annotates_blocks_with_wallet_activity() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        node.mine_block(vec![]);
+
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        let paying = node.mine_block(vec![ValueTransferOutput {
+            pkh,
+            value: 1_000,
+            time_lock: 0,
+        }]);
+        notifications.next_event("Movement").await;
+        let other = node.mine_block(vec![ValueTransferOutput {
+            pkh: PublicKeyHash::default(),
+            value: 1_000,
+            time_lock: 0,
+        }]);
+        notifications.next_event("Block").await;
+
+        let mint_hash = paying.txns.mint.hash().to_string();
+        let blocks = harness
+            .call(
+                "get_blocks",
+                json!({ "session_id": session_id, "epoch": 2 }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            blocks,
+            json!([
+                {
+                    "epoch": 2,
+                    "hash": paying.hash().to_string(),
+                    "own_transactions": { (wallet_id.clone()): [mint_hash] },
+                },
+                {
+                    "epoch": 3,
+                    "hash": other.hash().to_string(),
+                    "own_transactions": {},
+                },
+            ])
+        );
+        // Without a session there is nothing to annotate the blocks with
+        let blocks = harness
+            .call("get_blocks", json!({ "epoch": 2 }))
+            .await
+            .unwrap();
+        assert_eq!(
+            blocks[0],
+            json!({ "epoch": 2, "hash": paying.hash().to_string() })
+        );
+
+        let fetched = node.requests_of("getBlock").len();
+        let params = json!({ "session_id": session_id, "block_hash": paying.hash().to_string() });
+        let details = harness
+            .call("get_block_details", params.clone())
+            .await
+            .unwrap();
+        let mint = &details["transactions"]["mint"];
+        assert_eq!(mint["hash"], json!(mint_hash));
+        assert_eq!(mint["own_wallets"], json!([wallet_id]));
+        assert_eq!(mint["outputs"][0]["own_wallets"], json!([wallet_id]));
+        assert_eq!(node.requests_of("getBlock").len(), fetched + 1);
+
+        // The block is taken from the cache the second time
+        let cached = harness.call("get_block_details", params).await.unwrap();
+        assert_eq!(cached, details);
+        assert_eq!(node.requests_of("getBlock").len(), fetched + 1);
+
+        let details = harness
+            .call(
+                "get_block_details",
+                json!({ "block_hash": other.hash().to_string() }),
+            )
+            .await
+            .unwrap();
+        assert!(details["transactions"]["mint"].get("own_wallets").is_none());
+    });
+}
+
+#[test]
+fn test_blocks_are_scanned_once_and_skipped_by_uninvolved_wallets() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+
+        let mut wallets = vec![];
+        for _ in 0..5 {
+            let mnemonics = harness
+                .call("create_mnemonics", json!({ "length": 12 }))
+                .await
+                .unwrap();
+            let wallet_id = harness
+                .create_wallet(mnemonics["mnemonics"].as_str().unwrap(), PASSWORD)
+                .await
+                .unwrap();
+            let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+            let mut notifications = harness.subscribe(&session_id).await.unwrap();
+            notifications.next_event("SyncFinish").await;
+            wallets.push((wallet_id, session_id, notifications));
+        }
+
+        let (wallet_id, session_id, _) = &wallets[0];
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        let irrelevant = (0..1_000u32)
+            .map(|i| {
+                VTTransaction::new(
+                    VTTransactionBody::new(
+                        vec![Input::new(OutputPointer {
+                            transaction_id: Hash::default(),
+                            output_index: i,
+                        })],
+                        vec![ValueTransferOutput {
+                            pkh: PublicKeyHash::default(),
+                            value: 1,
+                            time_lock: 0,
+                        }],
+                    ),
+                    vec![],
+                )
+            })
+            .collect();
+        node.mine_block_with_transactions(
+            vec![ValueTransferOutput {
+                pkh,
+                value: 1_000,
+                time_lock: 0,
+            }],
+            irrelevant,
+        );
+
+        let movement = wallets[0].2.next_event("Movement").await;
+        assert_eq!(movement["amount"], json!("1000"));
+        for (_, _, notifications) in &mut wallets[1..] {
+            notifications.next_event("Block").await;
+        }
+
+        assert_eq!(
+            harness.block_processing().await,
+            metrics::BlockProcessing {
+                blocks_scanned: 1,
+                wallet_blocks_walked: 1,
+                wallet_blocks_skipped: 4,
+                wallet_blocks_duplicated: 0,
+            }
+        );
+    });
+}
+
+#[test]
+fn test_movements_are_posted_to_the_webhook() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let mut endpoint = MockEndpoint::start(vec![]);
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let update_webhook = |url: &str| {
+            harness.call(
+                "update_wallet_webhook",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "password": PASSWORD,
+                    "webhook": {
+                        "url": url,
+                        "secret": "shared secret",
+                        "topics": ["movement"],
+                    },
+                }),
+            )
+        };
+        let err = update_webhook("ftp://127.0.0.1/events").await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        let webhook = update_webhook(endpoint.url()).await.unwrap();
+        assert_eq!(
+            webhook,
+            json!({ "url": endpoint.url(), "topics": ["movement"] })
+        );
+
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        node.mine_block(vec![ValueTransferOutput {
+            pkh,
+            value: 1_000,
+            time_lock: 0,
+        }]);
+
+        // Only the movement is posted, because the webhook is not subscribed to blocks
+        let request = endpoint.next().await;
+        let signature = crypto::webhook_signature(b"shared secret", request.body.as_bytes());
+        assert_eq!(
+            request.headers["x-witnet-signature"],
+            format!("sha256={}", signature)
+        );
+        let payload: Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(payload["wallet_id"], json!(wallet_id));
+        let events = payload["events"].as_array().unwrap();
+        assert_eq!(events.len(), 1);
+        assert_eq!(events[0]["Movement"]["amount"], json!("1000"));
+
+        let deliveries = json!({ "session_id": session_id, "wallet_id": wallet_id });
+        for _ in 0..100 {
+            let pending = harness
+                .call("get_webhook_deliveries", deliveries.clone())
+                .await
+                .unwrap();
+            if pending == json!({ "pending": [], "dead_letters": [] }) {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("The delivery was not removed from the outbox");
+    });
+}
+
+#[test]
+fn test_wallets_are_unlocked_at_startup_from_a_credentials_file() {
+    System::new().block_on(async {
+        // The wallets were created in a previous run of the wallet
+        let db = in_memory_db().unwrap();
+        let previous = Harness::builder().db(db.clone()).start().unwrap();
+        let good_wallet = previous.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let bad_wallet = previous
+            .create_wallet(OTHER_MNEMONICS, PASSWORD)
+            .await
+            .unwrap();
+        drop(previous);
+
+        let session_id = "0123456789abcdef0123456789abcdef";
+        let path = std::env::temp_dir().join(format!(
+            "witnet-wallet-startup-unlock-{}",
+            std::process::id()
+        ));
+        let mut wallets = serde_json::Map::new();
+        wallets.insert(good_wallet.clone(), json!({ "password": PASSWORD }));
+        wallets.insert(bad_wallet.clone(), json!({ "password": "wrong password" }));
+        let credentials = json!({
+            "session_id": session_id,
+            "scopes": ["read"],
+            "wallets": wallets,
+        });
+        std::fs::write(&path, credentials.to_string()).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o600)).unwrap();
+        }
+        let harness = Harness::builder()
+            .db(db)
+            .config(|config| config.wallet.unlock_credentials_path = Some(path.clone()))
+            .start();
+        std::fs::remove_file(&path).unwrap();
+        let harness = harness.unwrap();
+
+        let mut notifications = harness.subscribe(session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+        let mut report = Value::Null;
+        for _ in 0..100 {
+            report = harness.call("get_node_status", Value::Null).await.unwrap()["startup_unlock"]
+                .clone();
+            if report["pending"] == json!([]) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        assert_eq!(report["unlocked"], json!([good_wallet]));
+        assert_eq!(
+            report["failed"],
+            json!([{ "wallet_id": bad_wallet, "error": "wrong wallet password" }])
+        );
+
+        // The service session has the configured scopes, and does not expire
+        harness.advance(Duration::from_secs(3_600)).await;
+        let params = json!({ "session_id": session_id, "wallet_id": good_wallet });
+        let balance = harness.call("get_balance", params.clone()).await.unwrap();
+        assert_eq!(balance["local"], json!("0"));
+        let err = harness.call("generate_address", params).await.unwrap_err();
+        assert_eq!(err.data.unwrap()["required_scope"], json!("admin"));
+    });
+}
+
+#[test]
+fn test_diagnostics_bundle_leaves_out_addresses_and_keys() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let params = json!({ "session_id": session_id, "wallet_id": wallet_id });
+        let address = harness
+            .call("generate_address", params.clone())
+            .await
+            .unwrap()["address"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        let pkh = PublicKeyHash::from_bech32(Environment::Mainnet, &address).unwrap();
+        node.mine_block(vec![ValueTransferOutput {
+            pkh,
+            value: 1_000,
+            time_lock: 0,
+        }]);
+        notifications.next_event("Movement").await;
+        let master_key = harness
+            .call(
+                "export_master_key",
+                json!({ "session_id": session_id, "wallet_id": wallet_id, "password": PASSWORD }),
+            )
+            .await
+            .unwrap()["master_key"]
+            .as_str()
+            .unwrap()
+            .to_string();
+        // A failed request is logged along with its error
+        harness
+            .call(
+                "create_vtt",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "fee": "0",
+                    "outputs": [{ "address": address, "amount": "1000000" }],
+                }),
+            )
+            .await
+            .unwrap_err();
+
+        let bundle = harness
+            .call("create_diagnostics_bundle", params.clone())
+            .await
+            .unwrap()["bundle"]
+            .clone();
+        assert_eq!(bundle["wallets"].as_array().unwrap().len(), 1);
+        assert_eq!(
+            bundle["wallets"][0]["integrity"]["needs_resync"],
+            json!(false)
+        );
+        assert_eq!(bundle["storage"]["wallets"], json!(1));
+        assert_eq!(bundle["config"]["seed_password"], json!("[redacted]"));
+        assert_eq!(
+            bundle["request_counters"]["errors_by_method"]["create_vtt"],
+            json!(1)
+        );
+        assert!(bundle.get("sensitive").is_none());
+        let serialized = bundle.to_string();
+        for secret in [&address, &master_key, &wallet_id, &session_id] {
+            assert!(
+                !serialized.contains(secret.as_str()),
+                "{} is in the bundle",
+                secret
+            );
+        }
+        for prefix in ["wit1", "xprv1"] {
+            assert!(!serialized.contains(prefix), "{} is in the bundle", prefix);
+        }
+
+        // Sensitive data needs the password of the wallet
+        let sensitive_params = json!({
+            "session_id": session_id,
+            "wallet_id": wallet_id,
+            "include_sensitive": true,
+        });
+        let err = harness
+            .call("create_diagnostics_bundle", sensitive_params.clone())
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        let mut wrong_password = sensitive_params.clone();
+        wrong_password["password"] = json!("wrong password");
+        let err = harness
+            .call("create_diagnostics_bundle", wrong_password)
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(406));
+
+        // Requests whose errors quote the address, made in this session and in another one
+        let other_session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        for session_id in [&session_id, &other_session_id] {
+            harness
+                .call(
+                    "get_transactions",
+                    json!({ "session_id": session_id, "wallet_id": wallet_id, "offset": address }),
+                )
+                .await
+                .unwrap_err();
+        }
+
+        let mut with_password = sensitive_params;
+        with_password["password"] = json!(PASSWORD);
+        let bundle = harness
+            .call("create_diagnostics_bundle", with_password)
+            .await
+            .unwrap()["bundle"]
+            .clone();
+        let sensitive = &bundle["sensitive"];
+        assert_eq!(sensitive["wallet_id"], json!(wallet_id));
+        assert!(sensitive.to_string().contains(&address));
+        assert_eq!(sensitive["transactions"]["total"], json!("1"));
+        // Only the requests of the session that asked for the bundle are left unredacted
+        let quoting_address = bundle["requests"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .filter(|request| request["method"] == json!("get_transactions"))
+            .filter(|request| request.to_string().contains(&address))
+            .count();
+        assert_eq!(quoting_address, 1);
+        assert!(!bundle.to_string().contains(&other_session_id));
+
+        // The bundle can be written to a new file instead
+        let path = std::env::temp_dir().join(format!(
+            "witnet-wallet-diagnostics-{}.json",
+            std::process::id()
+        ));
+        let mut to_file = params;
+        to_file["output_path"] = json!(path);
+        let response = harness
+            .call("create_diagnostics_bundle", to_file.clone())
+            .await
+            .unwrap();
+        let written: Value = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(response, json!({ "path": path }));
+        assert_eq!(written["config"], bundle["config"]);
+        // Existing files are never overwritten
+        let err = harness
+            .call("create_diagnostics_bundle", to_file)
+            .await
+            .unwrap_err();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+    });
+}
+
+#[test]
+fn test_rad_request_sends_secret_placeholders_until_witnesses_substitute_them() {
+    System::new().block_on(async {
+        let mut endpoint = MockEndpoint::start(vec![]);
+        let path = std::env::temp_dir().join(format!(
+            "witnet-wallet-retrieval-secrets-{}",
+            std::process::id()
+        ));
+        std::fs::write(
+            &path,
+            "[api_key.coinlayer]\nvalue = \"s3cr3t\"\nhosts = [\"127.0.0.1\"]\n",
+        )
+        .unwrap();
+        let harness = Harness::builder()
+            .config(|config| config.witnessing.secrets_file = Some(path.clone()))
+            .start();
+        std::fs::remove_file(&path).unwrap();
+        let harness = harness.unwrap();
+
+        let rad_request = RADRequest {
+            time_lock: 0,
+            retrieve: vec![RADRetrieve {
+                kind: RADType::HttpGet,
+                url: endpoint.url().to_string(),
+                // An empty script returns the response as is
+                script: vec![0x80],
+                body: vec![],
+                headers: vec![("X-Api-Key".to_string(), "{{api_key:coinlayer}}".to_string())],
+            }],
+            aggregate: RADAggregate {
+                filters: vec![],
+                reducer: 0x02,
+            },
+            tally: RADTally {
+                filters: vec![],
+                reducer: 0x02,
+            },
+        };
+        harness
+            .call("run_rad_request", json!({ "rad_request": rad_request }))
+            .await
+            .unwrap();
+
+        // Witnesses send placeholders as they are until WIP0041, and so does the wallet
+        assert!(!current_active_wips().wip0041());
+        let request = endpoint.next().await;
+        assert_eq!(request.headers["x-api-key"], "{{api_key:coinlayer}}");
+
+        // Nor do placeholders for secrets that the wallet does not have fail the source
+        let mut missing = rad_request;
+        missing.retrieve[0].url = format!("{}?other", endpoint.url());
+        missing.retrieve[0].headers[0].1 = "{{api_key:other}}".to_string();
+        let response = harness
+            .call("run_rad_request", json!({ "rad_request": missing }))
+            .await
+            .unwrap();
+        assert_ne!(
+            response["result"]["retrieve"][0]["result"]["RadonError"]["kind"],
+            json!("missing_secret")
+        );
+        let request = endpoint.next().await;
+        assert_eq!(request.headers["x-api-key"], "{{api_key:other}}");
+    });
+}
+
+#[test]
+fn test_rad_request_past_its_deadline_stops_retrieving() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|config| {
+                config
+                    .wallet
+                    .execution_timeouts
+                    .insert("run_rad_request".to_string(), 200);
+            })
+            .start()
+            .unwrap();
+
+        // A source that accepts connections but never answers
+        let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let rad_request = RADRequest {
+            time_lock: 0,
+            retrieve: vec![RADRetrieve {
+                kind: RADType::HttpGet,
+                url: format!("http://{}/", silent.local_addr().unwrap()),
+                script: vec![0x80],
+                body: vec![],
+                headers: vec![],
+            }],
+            aggregate: RADAggregate {
+                filters: vec![],
+                reducer: 0x02,
+            },
+            tally: RADTally {
+                filters: vec![],
+                reducer: 0x02,
+            },
+        };
+        let err = harness
+            .call("run_rad_request", json!({ "rad_request": rad_request }))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(504));
+        assert_eq!(err.message, "Execution Timeout");
+
+        // The retrieval is not left running, so its connection is closed
+        let (mut connection, _) = silent.accept().unwrap();
+        connection
+            .set_read_timeout(Some(Duration::from_secs(5)))
+            .unwrap();
+        let mut received = vec![];
+        std::io::Read::read_to_end(&mut connection, &mut received).unwrap();
+    });
+}
+
+#[test]
+fn test_rad_request_revalidates_cached_responses_with_their_source() {
+    System::new().block_on(async {
+        let mut endpoint = MockEndpoint::start_with_responses(vec![
+            MockResponse::text("application/json", "[1, 2, 3]").with_header("ETag", "\"v1\""),
+            MockResponse::from(304),
+            // Too late for the deadline of the request
+            MockResponse::text("application/json", "[4, 5, 6]")
+                .with_header("ETag", "\"v2\"")
+                .delayed(Duration::from_secs(1)),
+            MockResponse::from(304),
+        ]);
+        let harness = Harness::builder()
+            .config(|config| {
+                config.wallet.retrieval_cache_enabled = true;
+                // Every cached response is revalidated
+                config.wallet.retrieval_cache_ttl = 0;
+                config
+                    .wallet
+                    .execution_timeouts
+                    .insert("run_rad_request".to_string(), 500);
+            })
+            .start()
+            .unwrap();
+        let rad_request = RADRequest {
+            time_lock: 0,
+            retrieve: vec![RADRetrieve {
+                kind: RADType::HttpGet,
+                url: endpoint.url().to_string(),
+                // An empty script returns the response as is
+                script: vec![0x80],
+                body: vec![],
+                headers: vec![],
+            }],
+            aggregate: RADAggregate {
+                filters: vec![],
+                reducer: 0x02,
+            },
+            tally: RADTally {
+                filters: vec![],
+                reducer: 0x02,
+            },
+        };
+        let run = || harness.call("run_rad_request", json!({ "rad_request": rad_request }));
+        let value = |response: &Value| response["result"]["retrieve"][0]["result"].clone();
+
+        let response = run().await.unwrap();
+        assert_eq!(response["cache"], json!(["miss"]));
+        assert_eq!(value(&response), json!({ "RadonString": "[1, 2, 3]" }));
+        assert!(!endpoint.next().await.headers.contains_key("if-none-match"));
+
+        // The source answers the conditional request with 304 Not Modified
+        let response = run().await.unwrap();
+        assert_eq!(response["cache"], json!(["revalidated"]));
+        assert_eq!(value(&response), json!({ "RadonString": "[1, 2, 3]" }));
+        assert_eq!(endpoint.next().await.headers["if-none-match"], "\"v1\"");
+
+        // A response that arrives after the deadline is never cached
+        let err = run().await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(504));
+        assert_eq!(endpoint.next().await.headers["if-none-match"], "\"v1\"");
+        let response = run().await.unwrap();
+        assert_eq!(response["cache"], json!(["revalidated"]));
+        assert_eq!(value(&response), json!({ "RadonString": "[1, 2, 3]" }));
+        assert_eq!(endpoint.next().await.headers["if-none-match"], "\"v1\"");
+    });
+}
+
+#[test]
+fn test_rad_request_reports_the_size_and_content_type_of_responses() {
+    System::new().block_on(async {
+        let endpoint = MockEndpoint::start_with_responses(vec![
+            MockResponse::text("application/json; charset=utf-8", "[1, 2, 3]"),
+            MockResponse::text("image/png", "[1, 2, 3]"),
+        ]);
+        let harness = Harness::builder().start().unwrap();
+        let rad_request = RADRequest {
+            time_lock: 0,
+            retrieve: vec![RADRetrieve {
+                kind: RADType::HttpGet,
+                url: endpoint.url().to_string(),
+                // An empty script returns the response as is
+                script: vec![0x80],
+                body: vec![],
+                headers: vec![],
+            }],
+            aggregate: RADAggregate {
+                filters: vec![],
+                reducer: 0x02,
+            },
+            tally: RADTally {
+                filters: vec![],
+                reducer: 0x02,
+            },
+        };
+        let run = || harness.call("run_rad_request", json!({ "rad_request": rad_request }));
+
+        // The report tells what was received
+        let response = run().await.unwrap();
+        let retrieve = &response["result"]["retrieve"][0];
+        assert_eq!(retrieve["result"]["RadonString"], json!("[1, 2, 3]"));
+        assert_eq!(
+            retrieve["context"]["stage"]["Retrieval"]["response_size"],
+            json!(9)
+        );
+        assert_eq!(
+            retrieve["context"]["stage"]["Retrieval"]["content_type"],
+            json!("application/json; charset=utf-8")
+        );
+
+        // Content types are not checked until WIP0042, as witnesses do
+        assert!(!current_active_wips().wip0042());
+        let response = run().await.unwrap();
+        let retrieve = &response["result"]["retrieve"][0];
+        assert_eq!(retrieve["result"]["RadonString"], json!("[1, 2, 3]"));
+        assert_eq!(
+            retrieve["context"]["stage"]["Retrieval"]["content_type"],
+            json!("image/png")
+        );
+    });
+}
+
+#[test]
+fn test_overwriting_a_wallet_while_unlocking_it_never_corrupts_it() {
+    System::new().block_on(async {
+        // With two worker threads both operations really run at the same time
+        let harness = Harness::builder()
+            .config(|config| config.wallet.concurrency = Some(2))
+            .start()
+            .unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let new_password = "87654321";
+
+        let overwrite = harness.call(
+            "create_wallet",
+            json!({
+                "password": new_password,
+                "seed_source": "mnemonics",
+                "seed_data": MNEMONICS,
+                "overwrite": true,
+            }),
+        );
+        let unlock = harness.unlock_wallet(&wallet_id, PASSWORD);
+        let (overwritten, unlocked) = futures::future::join(overwrite, unlock).await;
+
+        let is_busy = |err: &jsonrpc_core::Error| {
+            err.code == jsonrpc_core::ErrorCode::ServerError(409) && err.message == "Wallet Busy"
+        };
+        match &overwritten {
+            Ok(response) => assert_eq!(response["wallet_id"], json!(wallet_id)),
+            Err(err) => assert!(is_busy(err), "{:?}", err),
+        }
+        match &unlocked {
+            // The unlock ran before the wallet was overwritten
+            Ok(_) => {}
+            // The unlock ran after the wallet was overwritten, so the password is no longer valid
+            Err(err) if err.code == jsonrpc_core::ErrorCode::ServerError(406) => {
+                assert!(overwritten.is_ok());
+                let retry_after = err.data.as_ref().unwrap()["retry_after"].as_u64().unwrap();
+                tokio::time::sleep(Duration::from_secs(retry_after + 1)).await;
+            }
+            Err(err) => assert!(is_busy(err), "{:?}", err),
+        }
+
+        // The wallet is either the old one or the new one as a whole
+        let password = if overwritten.is_ok() {
+            new_password
+        } else {
+            PASSWORD
+        };
+        let session_id = harness.unlock_wallet(&wallet_id, password).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+        assert!(harness
+            .call(
+                "get_balance",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .is_ok());
+    });
+}
+
+#[test]
+fn test_node_schema_mismatches_are_reported_but_tolerated() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let block = node.mine_block(vec![]);
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        // A node that renamed `current_epoch` and dropped `confirmed`
+        node.reply_with("syncStatus", |_| {
+            json!({
+                "chain_beacon": { "checkpoint": 0, "hash_prev_block": "0".repeat(64) },
+                "epoch": 2,
+                "node_state": "WaitingConsensus",
+            })
+        });
+        let mut response = serde_json::to_value(&block).unwrap();
+        response["block_weight"] = json!(0);
+        response["miner"] = json!("twit1jqgf4rxjrgas3kdhj3t4cr3mg3n33m8zw0aglr");
+        node.reply("getBlock", response);
+
+        // Whatever could be parsed is still used
+        harness.advance(Duration::from_secs(15)).await;
+        assert_eq!(
+            notifications.next_event("NodeStatus").await,
+            json!("WaitingConsensus")
+        );
+        let details = harness
+            .call(
+                "get_block_details",
+                json!({ "block_hash": block.hash().to_string() }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            details["transactions"]["mint"]["hash"],
+            json!(block.txns.mint.hash().to_string())
+        );
+
+        let status = harness.call("get_node_status", Value::Null).await.unwrap();
+        assert_eq!(
+            status["schema_mismatches"],
+            json!([
+                {
+                    "method": "getBlock",
+                    "missing_fields": ["confirmed"],
+                    "unknown_fields": ["miner"],
+                },
+                {
+                    "method": "syncStatus",
+                    "missing_fields": ["current_epoch"],
+                    "unknown_fields": ["epoch"],
+                },
+            ])
+        );
+    });
+}
+
+#[test]
+fn test_node_schema_mismatches_fail_requests_in_strict_mode() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|config| config.wallet.strict_node_schema = true)
+            .start()
+            .unwrap();
+        let node = harness.node();
+        let block = node.mine_block(vec![]);
+        let mut response = serde_json::to_value(&block).unwrap();
+        response["confirmed"] = json!(true);
+        response["miner"] = json!("twit1jqgf4rxjrgas3kdhj3t4cr3mg3n33m8zw0aglr");
+        node.reply("getBlock", response);
+
+        let err = harness
+            .call(
+                "get_block_details",
+                json!({ "block_hash": block.hash().to_string() }),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(510));
+        assert!(err.data.unwrap()["cause"]
+            .as_str()
+            .unwrap()
+            .contains("unknown fields: [\"miner\"]"));
+    });
+}
+
+#[test]
+fn test_balance_queries_stay_fast_while_blocks_are_flooded() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|config| {
+                config.wallet.interactive_queue_limit = 4;
+                config.wallet.block_queue_limit = 2;
+            })
+            .start()
+            .unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let params = json!({ "session_id": session_id, "wallet_id": wallet_id });
+        let address = harness
+            .call("generate_address", params.clone())
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+
+        // Many more blocks than the queue of the block worker holds
+        for _ in 0..50 {
+            node.mine_block(vec![ValueTransferOutput {
+                pkh,
+                value: 10,
+                time_lock: 0,
+            }]);
+        }
+        for _ in 0..20 {
+            let start = Instant::now();
+            harness.call("get_balance", params.clone()).await.unwrap();
+            assert!(start.elapsed() < Duration::from_secs(1));
+        }
+
+        // Only the requests past the limit are refused
+        let calls = (0..10).map(|_| harness.call("get_balance", params.clone()));
+        let (answered, refused): (Vec<_>, Vec<_>) = futures::future::join_all(calls)
+            .await
+            .into_iter()
+            .partition(Result::is_ok);
+        assert_eq!(answered.len(), 4);
+        for err in refused {
+            let err = err.unwrap_err();
+            assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(503));
+            assert_eq!(err.data.unwrap()["limit"], json!(4));
+        }
+        let queues = harness.work_queues().await;
+        assert_eq!(queues.interactive.depth, 0);
+        assert_eq!(queues.interactive.shed, 6);
+
+        // Dropped blocks are recovered by synchronizing once the block worker catches up, which
+        // the next notified block triggers
+        for _ in 0..100 {
+            let balance = harness.call("get_balance", params.clone()).await.unwrap();
+            if balance["unconfirmed"]["available"] == json!("500") {
+                return;
+            }
+            node.mine_block(vec![]);
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("The wallet did not catch up with the dropped blocks");
+    });
+}
+
+#[test]
+fn test_stalled_node_is_alerted_until_it_makes_progress() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|config| config.wallet.node_stall_epochs = 3)
+            .start()
+            .unwrap();
+        let node = harness.node();
+        let epoch = Duration::from_secs(u64::from(
+            Config::default().consensus_constants.checkpoints_period,
+        ));
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        // The node keeps answering with the same epoch and notifies no blocks. The epoch in which
+        // it last made progress does not count.
+        for _ in 0..3 {
+            harness.advance(epoch).await;
+        }
+        let status = harness.call("get_node_status", Value::Null).await.unwrap();
+        assert_eq!(status["liveness"]["stalled"], json!(false));
+        assert_eq!(status["liveness"]["epochs_without_progress"], json!(2));
+
+        harness.advance(epoch).await;
+        assert_eq!(notifications.next_event("NodeStalled").await, json!(3));
+        let status = harness.call("get_node_status", Value::Null).await.unwrap();
+        assert_eq!(status["liveness"]["stalled"], json!(true));
+        assert_eq!(status["liveness"]["last_epoch"], json!(0));
+
+        // A new block clears the stall right away
+        node.mine_block(vec![]);
+        notifications.next_event("NodeResumed").await;
+        let status = harness.call("get_node_status", Value::Null).await.unwrap();
+        assert_eq!(status["liveness"]["stalled"], json!(false));
+        assert_eq!(status["liveness"]["epochs_without_progress"], json!(0));
+        assert!(status["liveness"]["last_notification"].is_i64());
+    });
+}
+
+#[test]
+fn test_node_epochs_ahead_of_the_clock_are_alerted() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let constants = Config::default().consensus_constants;
+        let expected_epoch = watchdog::expected_epoch(
+            get_timestamp(),
+            constants.checkpoint_zero_timestamp,
+            constants.checkpoints_period,
+        )
+        .unwrap();
+        let sync_status = move |current_epoch| {
+            serde_json::to_value(SyncStatus {
+                chain_beacon: CheckpointBeacon::default(),
+                current_epoch: Some(current_epoch),
+                node_state: StateMachine::Synced,
+            })
+            .unwrap()
+        };
+
+        node.reply_with("syncStatus", move |_| sync_status(expected_epoch + 100));
+        harness.advance(Duration::from_secs(15)).await;
+        let skew = notifications.next_event("NodeClockSkew").await;
+        assert!((99..=100).contains(&skew.as_u64().unwrap()), "{}", skew);
+        let status = harness.call("get_node_status", Value::Null).await.unwrap();
+        assert_eq!(status["liveness"]["clock_skew"], skew);
+
+        // The skew is cleared once the node is back in time
+        node.reply_with("syncStatus", move |_| sync_status(expected_epoch));
+        harness.advance(Duration::from_secs(15)).await;
+        let status = harness.call("get_node_status", Value::Null).await.unwrap();
+        assert_eq!(status["liveness"]["clock_skew"], Value::Null);
+    });
+}
+
+/// Run the periodic health checks of the node until `done` holds, giving the nodes reached over
+/// TCP some real time to answer every one of them.
+///
+/// Panics if it does not hold after 40 health checks.
+async fn run_health_checks_until<F>(harness: &Harness, done: F)
+where
+    F: Fn() -> bool,
+{
+    for _ in 0..40 {
+        if done() {
+            return;
+        }
+        harness.advance(Duration::from_secs(15)).await;
+        tokio::time::sleep(Duration::from_millis(100)).await;
+    }
+
+    panic!("The health checks did not get the expected outcome");
+}
+
+#[test]
+fn test_tcp_client_fails_over_to_the_next_node() {
+    System::new().block_on(async {
+        let first = TcpNode::start();
+        let second = TcpNode::start();
+        let harness = Harness::builder()
+            .config(|config| {
+                config.wallet.node_url = vec![first.url(), second.url()];
+                config.wallet.node_failover_threshold = 2;
+                config.wallet.node_failover_min_interval = 0;
+                config.wallet.requests_timeout = 500;
+            })
+            .tcp_client()
+            .start()
+            .unwrap();
+
+        // The health checks reach the first node
+        run_health_checks_until(&harness, || first.requests_of("syncStatus") > 0).await;
+        let status = harness.call("get_node_status", Value::Null).await.unwrap();
+        assert_eq!(status["active_url"], json!(first.url()));
+        assert_eq!(second.requests_of("syncStatus"), 0);
+
+        // Once it goes down, the client is switched to the second node, which gets the next
+        // health checks and the subscriptions of the wallet
+        first.stop();
+        run_health_checks_until(&harness, || second.requests_of("syncStatus") > 0).await;
+        let status = harness.call("get_node_status", Value::Null).await.unwrap();
+        assert_eq!(status["active_url"], json!(second.url()));
+        assert_eq!(status["history"].as_array().unwrap().len(), 1);
+        assert_eq!(status["history"][0]["from"], json!(first.url()));
+        assert_eq!(status["history"][0]["to"], json!(second.url()));
+        assert_eq!(status["history"][0]["reason"], json!("health-check"));
+        assert!(second.requests_of("witnet_subscribe") > 0);
+    });
+}
+
+#[test]
+fn test_derive_addresses_does_not_write_to_the_storage() {
+    System::new().block_on(async {
+        let db = in_memory_db().unwrap();
+        let harness = Harness::builder().db(db.clone()).start().unwrap();
+        harness.settle().await;
+        let writes = count_storage_writes(&db);
+
+        let derived = harness
+            .call(
+                "derive_addresses",
+                json!({
+                    "seed_source": "mnemonics",
+                    "seed_data": MNEMONICS,
+                    "range": { "count": 10 },
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(derived["addresses"].as_array().unwrap().len(), 10);
+        assert_eq!(writes.load(Ordering::SeqCst), 0);
+
+        // Whereas creating a wallet from the same seed does write to it
+        harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        assert!(writes.load(Ordering::SeqCst) > 0);
+    });
+}
+
+#[test]
+fn test_address_metadata_is_imported_and_validated_against_the_seed() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let derived = harness
+            .call(
+                "derive_addresses",
+                json!({
+                    "seed_source": "mnemonics",
+                    "seed_data": MNEMONICS,
+                    "range": { "count": 1000 },
+                }),
+            )
+            .await
+            .unwrap()["addresses"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|derived| derived["address"].as_str().unwrap().to_string())
+            .collect::<Vec<_>>();
+        // Exchanges hand out addresses far apart from each other
+        let mut entries = (0..500)
+            .map(|i| {
+                json!({
+                    "index": i * 2,
+                    "address": derived[i * 2],
+                    "label": format!("deposit {}", i),
+                    "account_tag": format!("customer-{}", i % 7),
+                })
+            })
+            .collect::<Vec<_>>();
+        let import = |entries: Vec<Value>| {
+            harness.call(
+                "import_address_metadata",
+                json!({ "session_id": session_id, "wallet_id": wallet_id, "payload": entries }),
+            )
+        };
+        let get_addresses = || {
+            harness.call(
+                "get_addresses",
+                json!({ "session_id": session_id, "wallet_id": wallet_id, "limit": 1000 }),
+            )
+        };
+
+        // A single address that is not the one derived at its index rejects the whole mapping
+        entries[250]["address"] = json!(derived[501]);
+        let err = import(entries.clone()).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        assert_eq!(
+            err.data.unwrap()[0],
+            json!([
+                "payload",
+                format!(
+                    "the address at index 500 is {}, but the wallet derives {} there",
+                    derived[501], derived[500]
+                )
+            ])
+        );
+        assert_eq!(get_addresses().await.unwrap()["total"], json!(0));
+
+        entries[250]["address"] = json!(derived[500]);
+        let report = import(entries.clone()).await.unwrap();
+        assert_eq!(
+            report,
+            json!({
+                "imported": 500,
+                "unchanged": 0,
+                "generated": 999,
+                "next_external_index": 999,
+            })
+        );
+        for written in [100, 200, 300, 400, 500] {
+            assert_eq!(
+                notifications
+                    .next_event("AddressMetadataImportProgress")
+                    .await,
+                json!([written, 500])
+            );
+        }
+
+        let addresses = get_addresses().await.unwrap();
+        assert_eq!(addresses["total"], json!(999));
+        for address in addresses["addresses"].as_array().unwrap() {
+            let index = address["index"].as_u64().unwrap() as usize;
+            assert_eq!(address["address"], json!(derived[index]));
+            if index % 2 == 0 {
+                let i = index / 2;
+                assert_eq!(address["info"]["label"], json!(format!("deposit {}", i)));
+                assert_eq!(address["account_tag"], json!(format!("customer-{}", i % 7)));
+            } else {
+                assert_eq!(address["info"]["label"], Value::Null);
+                assert!(address.get("account_tag").is_none());
+            }
+        }
+
+        // Importing the same mapping again changes nothing
+        let report = import(entries).await.unwrap();
+        assert_eq!(report["imported"], json!(0));
+        assert_eq!(report["unchanged"], json!(500));
+        assert_eq!(report["generated"], json!(0));
+        assert_eq!(get_addresses().await.unwrap()["total"], json!(999));
+
+        // Addresses can also be given without their index, as CSV
+        let csv = format!("address,label\n{},\"cold, reserve\"\n", derived[3]);
+        let report = harness
+            .call(
+                "import_address_metadata",
+                json!({ "session_id": session_id, "wallet_id": wallet_id, "payload": csv }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(report["imported"], json!(1));
+        let err = harness
+            .call(
+                "import_address_metadata",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "payload": format!("address\n{}\n", derived[999]),
+                }),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+    });
+}
+
+#[test]
+fn test_heavy_requests_of_a_session_do_not_starve_the_others() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|config| {
+                config.wallet.concurrency = Some(4);
+                config.wallet.requests_timeout = 2_000;
+                config.wallet.session_heavy_request_limit = 2;
+            })
+            .start()
+            .unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let busy_session = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let other_wallet_id = harness
+            .create_wallet(OTHER_MNEMONICS, PASSWORD)
+            .await
+            .unwrap();
+        let other_session = harness
+            .unlock_wallet(&other_wallet_id, PASSWORD)
+            .await
+            .unwrap();
+
+        // A source that accepts connections but never answers keeps the requests in flight
+        let silent = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let rad_request = RADRequest {
+            time_lock: 0,
+            retrieve: vec![RADRetrieve {
+                kind: RADType::HttpGet,
+                url: format!("http://{}/", silent.local_addr().unwrap()),
+                script: vec![0x80],
+                body: vec![],
+                headers: vec![],
+            }],
+            aggregate: RADAggregate {
+                filters: vec![],
+                reducer: 0x02,
+            },
+            tally: RADTally {
+                filters: vec![],
+                reducer: 0x02,
+            },
+        };
+        let mut heavy = (0..5)
+            .map(|_| {
+                harness.call(
+                    "run_rad_request",
+                    json!({
+                        "session_id": busy_session,
+                        "rad_request": rad_request,
+                        "bypass_cache": true,
+                    }),
+                )
+            })
+            .collect::<futures::stream::FuturesUnordered<_>>();
+
+        // The requests past the limit of the session are refused right away
+        for _ in 0..3 {
+            let err = heavy.next().await.unwrap().unwrap_err();
+            assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(429));
+            let data = err.data.unwrap();
+            assert_eq!(data["class"], json!("heavy"));
+            assert_eq!(data["scope"], json!("session"));
+            assert_eq!(data["in_flight"], json!(2));
+            assert_eq!(data["limit"], json!(2));
+        }
+        let in_flight = harness.requests_in_flight().await;
+        assert_eq!(in_flight.heavy, 2);
+        assert_eq!(in_flight.rejected, 3);
+
+        // Light requests, of the other session and of the busy one, are still answered promptly
+        for (session_id, wallet_id) in [
+            (&other_session, &other_wallet_id),
+            (&busy_session, &wallet_id),
+        ] {
+            let start = Instant::now();
+            harness
+                .call(
+                    "get_balance",
+                    json!({ "session_id": session_id, "wallet_id": wallet_id }),
+                )
+                .await
+                .unwrap();
+            assert!(start.elapsed() < Duration::from_secs(1));
+        }
+
+        // Requests stop being counted as in flight once they are answered, even if they time out
+        while heavy.next().await.is_some() {}
+        let in_flight = harness.requests_in_flight().await;
+        assert_eq!(in_flight.heavy, 0);
+        assert_eq!(in_flight.light, 0);
+        assert_eq!(in_flight.sessions, 0);
+        let response = harness
+            .call(
+                "run_rad_request",
+                json!({ "session_id": busy_session, "rad_request": rad_request }),
+            )
+            .await;
+        assert_ne!(
+            response.err().map(|err| err.code),
+            Some(jsonrpc_core::ErrorCode::ServerError(429))
+        );
+        drop(silent);
+    });
+}
+
+#[test]
+fn test_raw_transactions_are_broadcast_from_another_wallet() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+        let other_wallet_id = harness
+            .create_wallet(OTHER_MNEMONICS, PASSWORD)
+            .await
+            .unwrap();
+        let other_session = harness
+            .unlock_wallet(&other_wallet_id, PASSWORD)
+            .await
+            .unwrap();
+
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        node.mine_block(vec![ValueTransferOutput {
+            pkh,
+            value: 1_000,
+            time_lock: 0,
+        }]);
+        notifications.next_event("Movement").await;
+
+        let created = harness
+            .call(
+                "create_vtt",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "fee": "0",
+                    "outputs": [{
+                        "address": PublicKeyHash::default().bech32(Environment::Mainnet),
+                        "amount": "200",
+                    }],
+                }),
+            )
+            .await
+            .unwrap();
+        let bytes = hex::decode(created["bytes"].as_str().unwrap()).unwrap();
+        let broadcast = |session_id: &str, payload: String| {
+            harness.call(
+                "broadcast_raw_transaction",
+                json!({ "session_id": session_id, "payload": payload }),
+            )
+        };
+
+        // A wallet that does not own the inputs only broadcasts the transaction
+        let broadcast_elsewhere = broadcast(&other_session, base64::encode(&bytes))
+            .await
+            .unwrap();
+        assert_eq!(
+            broadcast_elsewhere["transaction_id"],
+            created["transaction_id"]
+        );
+        assert_eq!(broadcast_elsewhere["wallet_id"], Value::Null);
+        assert_eq!(broadcast_elsewhere["balance_movement"], Value::Null);
+        let inventory = node.requests_of("inventory");
+        assert_eq!(inventory.len(), 1);
+        assert_eq!(
+            inventory[0]["transaction"],
+            json!(Transaction::from_pb_bytes(&bytes).unwrap())
+        );
+
+        // The wallet that owns them also records the pending movement
+        let sent = broadcast(&session_id, hex::encode(&bytes)).await.unwrap();
+        assert_eq!(sent["wallet_id"], json!(wallet_id));
+        assert_eq!(sent["balance_movement"]["amount"], json!("200"));
+        assert_eq!(node.requests_of("inventory").len(), 2);
+
+        // Payloads whose signatures do not verify are rejected before reaching the node
+        let mut tampered = Transaction::from_pb_bytes(&bytes).unwrap();
+        if let Transaction::ValueTransfer(vtt) = &mut tampered {
+            vtt.body.outputs[0].value += 100;
+        }
+        let err = broadcast(&other_session, hex::encode(tampered.to_pb_bytes().unwrap()))
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        let data = err.data.unwrap();
+        assert_eq!(data["path"], json!("transaction.signatures[0]"));
+        assert_eq!(
+            data["cause"],
+            json!("the signature does not verify the transaction")
+        );
+
+        // As are the payloads that are not even a transaction, telling where decoding failed
+        let mut garbage = bytes.clone();
+        garbage.extend([0x0a, 0x00]);
+        let err = broadcast(&other_session, hex::encode(&garbage))
+            .await
+            .unwrap_err();
+        let data = err.data.unwrap();
+        assert_eq!(data["path"], json!("transaction"));
+        let err = broadcast(&other_session, format!("{}0", hex::encode(&bytes)))
+            .await
+            .unwrap_err();
+        let data = err.data.unwrap();
+        assert_eq!(data["path"], json!("payload"));
+        assert_eq!(data["offset"], json!(bytes.len() * 2 + 1));
+        assert_eq!(node.requests_of("inventory").len(), 2);
+    });
+}
+
+#[test]
+fn test_retrievals_are_benchmarked_per_source() {
+    System::new().block_on(async {
+        let json = |body: &str| MockResponse::text("application/json", body);
+        let steady = MockEndpoint::start_with_responses(
+            [100, 200, 300, 400]
+                .iter()
+                .map(|millis| json("42").delayed(Duration::from_millis(*millis)))
+                .collect(),
+        );
+        let flaky =
+            MockEndpoint::start_with_responses(vec![json("1"), 500.into(), json("2"), json("1")]);
+        let slow = MockEndpoint::start_with_responses(vec![
+            json("7"),
+            json("7"),
+            json("7"),
+            json("7").delayed(Duration::from_secs(3)),
+        ]);
+        let harness = Harness::builder()
+            .config(|config| config.mining.data_request_timeout = Duration::from_secs(1))
+            .start()
+            .unwrap();
+        let retrieve = |endpoint: &MockEndpoint| RADRetrieve {
+            kind: RADType::HttpGet,
+            url: endpoint.url().to_string(),
+            // An empty script returns the response as is
+            script: vec![0x80],
+            body: vec![],
+            headers: vec![],
+        };
+        let benchmark = |retrievals: Vec<RADRetrieve>, iterations: u32| {
+            harness.call(
+                "benchmark_retrievals",
+                json!({ "retrievals": retrievals, "iterations": iterations, "persist": true }),
+            )
+        };
+
+        let report = benchmark(
+            vec![retrieve(&steady), retrieve(&flaky), retrieve(&slow)],
+            4,
+        )
+        .await
+        .unwrap();
+        assert_eq!(report["iterations"], json!(4));
+
+        // Latencies include the delay of the responses, and nothing else is slower than a second
+        let steady_report = &report["sources"][0];
+        assert_eq!(steady_report["url"], json!(steady.url()));
+        assert_eq!(steady_report["success_rate"], json!(1.0));
+        let latency = |percentile: &str| steady_report["latency"][percentile].as_u64().unwrap();
+        assert!((100..200).contains(&latency("min_ms")));
+        assert!((200..300).contains(&latency("p50_ms")));
+        assert!((400..500).contains(&latency("p90_ms")));
+        assert_eq!(latency("p99_ms"), latency("max_ms"));
+        assert_eq!(
+            steady_report["response_size"],
+            json!({ "min": 2, "mean": 2, "max": 2 })
+        );
+        assert_eq!(
+            steady_report["results"],
+            json!([{ "value": r#"{"RadonString":"42"}"#, "count": 4 }])
+        );
+        assert_eq!(steady_report["inconsistent"], json!(false));
+        assert_eq!(steady_report["previous"], Value::Null);
+
+        // Sources that fail or return different values across iterations are told apart
+        let flaky_report = &report["sources"][1];
+        assert_eq!(flaky_report["successes"], json!(3));
+        assert_eq!(flaky_report["success_rate"], json!(0.75));
+        assert_eq!(flaky_report["errors"][0]["count"], json!(1));
+        assert_eq!(
+            flaky_report["results"],
+            json!([
+                { "value": r#"{"RadonString":"1"}"#, "count": 2 },
+                { "value": r#"{"RadonString":"2"}"#, "count": 1 },
+            ])
+        );
+        assert_eq!(flaky_report["inconsistent"], json!(true));
+
+        // Retrievals time out as they would for witnesses
+        let slow_report = &report["sources"][2];
+        assert_eq!(slow_report["timeouts"], json!(1));
+        assert_eq!(slow_report["success_rate"], json!(0.75));
+        assert!(slow_report["latency"]["max_ms"].as_u64().unwrap() < 1_000);
+
+        // The persisted benchmark of a URL is returned along with the next one
+        let next = benchmark(vec![retrieve(&steady)], 1).await.unwrap();
+        let mut persisted = steady_report.clone();
+        persisted.as_object_mut().unwrap().remove("previous");
+        assert_eq!(next["sources"][0]["previous"], persisted);
+        assert_eq!(next["sources"][0]["attempts"], json!(1));
+
+        let err = benchmark(vec![retrieve(&steady)], 1_000).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        assert_eq!(err.data.unwrap()[0][0], json!("iterations"));
+    });
+}
+
+#[test]
+fn test_get_version_needs_no_session() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|conf| conf.wallet.min_client_api_version = 2)
+            .start()
+            .unwrap();
+
+        let version = harness.call("get_version", json!({})).await.unwrap();
+        assert_eq!(version["version"], json!(env!("CARGO_PKG_VERSION")));
+        assert_eq!(version["api_version"], json!(api_version::API_VERSION));
+        assert_eq!(version["min_client_api_version"], json!(2));
+        // The HTTP transport is always disabled in the harness
+        assert!(!version["features"]
+            .as_array()
+            .unwrap()
+            .contains(&json!("http_transport")));
+    });
+}
+
+#[test]
+fn test_clients_older_than_the_minimum_api_version_are_rejected() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|conf| conf.wallet.min_client_api_version = 2)
+            .start()
+            .unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+
+        let unlock = |client_api_version: u32| {
+            harness.call(
+                "unlock_wallet",
+                json!({
+                    "wallet_id": wallet_id,
+                    "password": PASSWORD,
+                    "client_api_version": client_api_version,
+                }),
+            )
+        };
+        let err = unlock(1).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(426));
+        let data = err.data.unwrap();
+        assert_eq!(data["client_api_version"], json!(1));
+        assert_eq!(data["min_client_api_version"], json!(2));
+        assert_eq!(data["api_version"], json!(api_version::API_VERSION));
+
+        let unlocked = unlock(2).await.unwrap();
+        assert_eq!(unlocked["compatibility_mode"], json!(false));
+    });
+}
+
+#[test]
+fn test_compatibility_mode_suppresses_newer_fields() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+
+        let unlocked = harness
+            .call(
+                "unlock_wallet",
+                json!({
+                    "wallet_id": wallet_id,
+                    "password": PASSWORD,
+                    "client_api_version": 1,
+                }),
+            )
+            .await
+            .unwrap();
+        assert_eq!(unlocked["compatibility_mode"], json!(true));
+        let old_session = unlocked["session_id"].clone();
+        let new_session = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+
+        let params =
+            |session_id: &Value| json!({ "session_id": session_id, "wallet_id": wallet_id });
+        harness
+            .call("generate_address", params(&old_session))
+            .await
+            .unwrap();
+
+        // The cursor of the listings was added in version 2 of the API
+        let old_page = harness
+            .call("get_addresses", params(&old_session))
+            .await
+            .unwrap();
+        assert_eq!(old_page["addresses"].as_array().unwrap().len(), 1);
+        assert!(old_page.get("cursor").is_none());
+
+        let new_page = harness
+            .call("get_addresses", params(&json!(new_session)))
+            .await
+            .unwrap();
+        assert_eq!(new_page["addresses"], old_page["addresses"]);
+        assert!(new_page.get("cursor").is_some());
+    });
+}
+
+#[test]
+fn test_retried_sends_with_an_idempotency_key_are_broadcast_once() {
+    System::new().block_on(async {
+        let harness = Harness::builder().start().unwrap();
+        let node = harness.node();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let mut notifications = harness.subscribe(&session_id).await.unwrap();
+        notifications.next_event("SyncFinish").await;
+
+        let address = harness
+            .call(
+                "generate_address",
+                json!({ "session_id": session_id, "wallet_id": wallet_id }),
+            )
+            .await
+            .unwrap();
+        let pkh =
+            PublicKeyHash::from_bech32(Environment::Mainnet, address["address"].as_str().unwrap())
+                .unwrap();
+        node.mine_block(vec![ValueTransferOutput {
+            pkh,
+            value: 500,
+            time_lock: 0,
+        }]);
+        notifications.next_event("Movement").await;
+
+        let created = harness
+            .call(
+                "create_vtt",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "fee": "0",
+                    "outputs": [{
+                        "address": PublicKeyHash::default().bech32(Environment::Mainnet),
+                        "amount": "200",
+                    }],
+                }),
+            )
+            .await
+            .unwrap();
+        let send = |dry_run: bool| {
+            harness.call(
+                "send_transaction",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "transaction": created["transaction"],
+                    "dry_run": dry_run,
+                    "idempotency_key": "send-1",
+                }),
+            )
+        };
+
+        // Retries get the response to the first send, which is broadcast once
+        let sent = send(false).await.unwrap();
+        let retried = send(false).await.unwrap();
+        assert_eq!(retried, sent);
+        assert_eq!(node.requests_of("inventory").len(), 1);
+
+        // Reusing the key for other params is refused
+        let err = send(true).await.unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(409));
+        assert_eq!(err.message, "Idempotency Key Conflict");
+        assert_eq!(node.requests_of("inventory").len(), 1);
+    });
+}
+
+#[test]
+fn test_idempotency_keys_expire_after_the_retention_window() {
+    System::new().block_on(async {
+        let harness = Harness::builder()
+            .config(|conf| conf.wallet.idempotency_key_retention = 60)
+            .start()
+            .unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let generate_address = || {
+            harness.call(
+                "generate_address",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "idempotency_key": "address-1",
+                }),
+            )
+        };
+
+        let generated = generate_address().await.unwrap();
+        harness.advance(Duration::from_secs(59)).await;
+        assert_eq!(generate_address().await.unwrap(), generated);
+
+        // Once the key expires, it is a new request
+        harness.advance(Duration::from_secs(2)).await;
+        let regenerated = generate_address().await.unwrap();
+        assert_ne!(regenerated["address"], generated["address"]);
+
+        // Methods that do not change the wallet do not accept a key
+        let err = harness
+            .call(
+                "get_balance",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "idempotency_key": "balance-1",
+                }),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        assert_eq!(err.data.unwrap()[0][0], json!("idempotency_key"));
+    });
+}
+
+#[test]
+fn test_idempotency_keys_are_kept_across_sessions_and_restarts() {
+    System::new().block_on(async {
+        let db = in_memory_db().unwrap();
+        let generate_address = |harness: &Harness, session_id: &str, wallet_id: &str| {
+            harness.call(
+                "generate_address",
+                json!({
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "idempotency_key": "address-1",
+                }),
+            )
+        };
+
+        let harness = Harness::builder().db(db.clone()).start().unwrap();
+        let wallet_id = harness.create_wallet(MNEMONICS, PASSWORD).await.unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        let generated = generate_address(&harness, &session_id, &wallet_id)
+            .await
+            .unwrap();
+
+        // Another session of the same wallet gets the same response
+        let other_session = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        assert_eq!(
+            generate_address(&harness, &other_session, &wallet_id)
+                .await
+                .unwrap(),
+            generated
+        );
+        drop(harness);
+
+        // And so does a session of a daemon restarted on the same storage
+        let harness = Harness::builder().db(db).start().unwrap();
+        let session_id = harness.unlock_wallet(&wallet_id, PASSWORD).await.unwrap();
+        assert_eq!(
+            generate_address(&harness, &session_id, &wallet_id)
+                .await
+                .unwrap(),
+            generated
+        );
+
+        // Keys given among positional params are refused instead of being ignored
+        let err = harness
+            .call(
+                "generate_address",
+                json!([{
+                    "session_id": session_id,
+                    "wallet_id": wallet_id,
+                    "idempotency_key": "address-2",
+                }]),
+            )
+            .await
+            .unwrap_err();
+        assert_eq!(err.code, jsonrpc_core::ErrorCode::ServerError(400));
+        assert_eq!(err.data.unwrap()[0][0], json!("idempotency_key"));
+    });
+}
+
+#[test]
+fn test_mnemonics_are_generated_while_the_storage_is_slow() {
+    System::new().block_on(async {
+        let db = in_memory_db().unwrap();
+        let harness = Harness::builder()
+            .config(|config| config.wallet.concurrency = Some(1))
+            .db(db.clone())
+            .start()
+            .unwrap();
+        let latency = Duration::from_secs(2);
+        set_storage_latency(&db, latency);
+
+        // The only thread of the worker is stuck reading the wallets from the slow storage
+        let infos = async {
+            let start = Instant::now();
+            harness.call("get_wallet_infos", json!({})).await.unwrap();
+            start.elapsed()
+        };
+        let mnemonics = async {
+            let start = Instant::now();
+            let response = harness
+                .call("create_mnemonics", json!({ "length": 12 }))
+                .await
+                .unwrap();
+            (response, start.elapsed())
+        };
+        let (infos_elapsed, (response, mnemonics_elapsed)) = futures::join!(infos, mnemonics);
+
+        assert!(infos_elapsed >= latency);
+        assert!(mnemonics_elapsed < Duration::from_millis(500));
+        assert_eq!(
+            response["mnemonics"].as_str().unwrap().split(' ').count(),
+            12
+        );
+    });
+}
@@ -2,6 +2,9 @@ use crate::actors::app;
 use crate::*;
 use bech32::ToBase32;
 use std::string::ToString;
+use witnet_config::config::SessionLimitPolicy;
+
+mod harness;
 
 #[test]
 fn test_validate_mnemonics() {
@@ -212,3 +215,704 @@ fn test_split_xprv_more_than_two_xprv_ocurrences() {
 
     assert_eq!(expected, result);
 }
+
+fn derive_addresses_request(params: serde_json::Value) -> app::DeriveAddressesRequest {
+    let mut request = serde_json::json!({
+        "seed_source": "mnemonics",
+        "seed_data": "abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about",
+    });
+    request
+        .as_object_mut()
+        .unwrap()
+        .extend(params.as_object().unwrap().clone());
+
+    serde_json::from_value(request).unwrap()
+}
+
+#[test]
+fn test_validate_derive_addresses_range() {
+    let request = derive_addresses_request(serde_json::json!({
+        "range": { "account": 0, "keychain": 1, "start": 5, "count": 3 }
+    }));
+    let (_, paths) =
+        app::validate_derive_addresses(request, &model::KeyDerivation::LEGACY).unwrap();
+    let paths: Vec<String> = paths.iter().map(ToString::to_string).collect();
+
+    assert_eq!(
+        paths,
+        vec![
+            "m/3'/4919'/0'/1/5",
+            "m/3'/4919'/0'/1/6",
+            "m/3'/4919'/0'/1/7"
+        ]
+    );
+}
+
+#[test]
+fn test_validate_derive_addresses_paths() {
+    let request = derive_addresses_request(serde_json::json!({
+        "paths": ["m/3'/4919'/0'/0/0", "m/44h/0h/0h/0/0"]
+    }));
+    let (_, paths) =
+        app::validate_derive_addresses(request, &model::KeyDerivation::LEGACY).unwrap();
+    let paths: Vec<String> = paths.iter().map(ToString::to_string).collect();
+
+    assert_eq!(paths, vec!["m/3'/4919'/0'/0/0", "m/44'/0'/0'/0/0"]);
+}
+
+#[test]
+fn test_validate_derive_addresses_err() {
+    let validate_err = |params| {
+        app::validate_derive_addresses(
+            derive_addresses_request(params),
+            &model::KeyDerivation::LEGACY,
+        )
+        .map(|_| ())
+        .unwrap_err()
+        .into_parts()
+    };
+
+    let (code, _, _) = validate_err(serde_json::json!({}));
+    assert_eq!(code, 400);
+    let (code, _, _) = validate_err(serde_json::json!({
+        "paths": ["m/0"],
+        "range": { "count": 1 }
+    }));
+    assert_eq!(code, 400);
+    let (code, _, _) = validate_err(serde_json::json!({ "paths": ["3'/4919'"] }));
+    assert_eq!(code, 400);
+    let (code, _, _) = validate_err(serde_json::json!({ "range": { "count": 1001 } }));
+    assert_eq!(code, 400);
+    let (code, _, _) = validate_err(serde_json::json!({
+        "range": { "keychain": 2, "count": 1 }
+    }));
+    assert_eq!(code, 400);
+    let (code, _, _) = validate_err(serde_json::json!({
+        "range": { "start": 2147483647u32, "count": 2 }
+    }));
+    assert_eq!(code, 400);
+}
+
+#[test]
+fn test_validate_confirm_seed_backup() {
+    let validate = |words| {
+        let request = serde_json::from_value(serde_json::json!({
+            "session_id": "a-session-id",
+            "wallet_id": "a-wallet-id",
+            "words": words,
+        }))
+        .unwrap();
+
+        app::validate_confirm_seed_backup(request)
+            .map(|words| {
+                words
+                    .into_iter()
+                    .map(|(position, word)| (position, AsRef::<str>::as_ref(&word).to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .map_err(|err| app::validation_error(err).into_parts().0)
+    };
+
+    assert_eq!(
+        validate(serde_json::json!([
+            { "position": 3, "word": "abandon" },
+            { "position": 7, "word": "abandon" },
+            { "position": 12, "word": "about" },
+        ])),
+        Ok(vec![
+            (3, "abandon".to_string()),
+            (7, "abandon".to_string()),
+            (12, "about".to_string())
+        ])
+    );
+    assert_eq!(validate(serde_json::json!([])), Err(400));
+    assert_eq!(
+        validate(serde_json::json!([
+            { "position": 3, "word": "abandon" },
+            { "position": 12, "word": "about" },
+        ])),
+        Err(400)
+    );
+    assert_eq!(
+        validate(serde_json::json!([{ "position": 0, "word": "abandon" }])),
+        Err(400)
+    );
+    assert_eq!(
+        validate(serde_json::json!([
+            { "position": 1, "word": "abandon" },
+            { "position": 1, "word": "about" },
+            { "position": 2, "word": "abandon" },
+        ])),
+        Err(400)
+    );
+}
+
+fn classify_reveals_request(params: serde_json::Value) -> app::ClassifyRevealsRequest {
+    let mut request = serde_json::json!({
+        "reveals": [1.5, 2.5],
+        "tally": { "filters": [], "reducer": 3 },
+    });
+    for (key, value) in params.as_object().unwrap() {
+        request[key] = value.clone();
+    }
+
+    serde_json::from_value(request).unwrap()
+}
+
+#[test]
+fn test_validate_classify_reveals() {
+    let (reveals, _tally) =
+        app::validate_classify_reveals(classify_reveals_request(serde_json::json!({}))).unwrap();
+    assert_eq!(reveals.len(), 2);
+
+    let err = app::validate_classify_reveals(classify_reveals_request(
+        serde_json::json!({ "reveals": [] }),
+    ))
+    .unwrap_err();
+    assert_eq!(err[0].0, "reveals");
+
+    let err = app::validate_classify_reveals(classify_reveals_request(
+        serde_json::json!({ "reveals": [1.5, null] }),
+    ))
+    .unwrap_err();
+    assert_eq!(err[0].0, "reveals");
+    assert!(err[0].1.contains("#1"));
+
+    // Reveals are not silently changed, neither leaving out nested nulls nor rounding numbers
+    let err = app::validate_classify_reveals(classify_reveals_request(
+        serde_json::json!({ "reveals": [[1, null], 1e20] }),
+    ))
+    .unwrap_err();
+    assert!(err[0].1.contains("#0"));
+    let err = app::validate_classify_reveals(classify_reveals_request(
+        serde_json::json!({ "reveals": [1, 1e20] }),
+    ))
+    .unwrap_err();
+    assert!(err[0].1.contains("#1"));
+
+    let err = app::validate_classify_reveals(classify_reveals_request(
+        serde_json::json!({ "min_consensus_percentage": 50 }),
+    ))
+    .unwrap_err();
+    assert_eq!(err[0].0, "min_consensus_percentage");
+}
+
+#[test]
+fn test_wallet_infos_request_params_are_optional() {
+    let request: app::WalletInfosRequest = serde_json::from_value(serde_json::Value::Null).unwrap();
+    assert!(request.0.is_none());
+
+    let request: app::WalletInfosRequest =
+        serde_json::from_value(serde_json::json!({ "include_pending": true })).unwrap();
+    assert!(request.0.unwrap().include_pending);
+
+    let request: app::WalletInfosRequest =
+        serde_json::from_value(serde_json::json!({ "offset": 10, "limit": 5 })).unwrap();
+    let params = request.0.unwrap();
+    assert_eq!((params.offset, params.limit), (Some(10), Some(5)));
+}
+
+#[test]
+fn test_execution_timeout_error_code() {
+    let err: app::Error = actors::worker::Error::Timeout(deadline::Expired {
+        operation: "sync",
+        progress: "synced up to #10 of #20".to_string(),
+    })
+    .into();
+
+    let (code, message, data) = err.into_parts();
+    assert_eq!(code, 504);
+    assert_eq!(message, "Execution Timeout");
+    assert_eq!(
+        data.unwrap()["progress"],
+        serde_json::json!("synced up to #10 of #20")
+    );
+}
+
+#[test]
+fn test_rad_error_data_is_structured() {
+    let err: app::Error =
+        actors::worker::Error::Rad(witnet_rad::error::RadError::HttpStatus { status_code: 503 })
+            .into();
+
+    let (code, message, data) = err.into_parts();
+    assert_eq!(code, 424);
+    assert_eq!(message, "RAD Error");
+    assert_eq!(
+        data.unwrap()["error"],
+        serde_json::json!({
+            "kind": "http_status",
+            "message": "HTTP GET response was an HTTP error code: 503",
+            "fields": { "status_code": 503 },
+        })
+    );
+}
+
+#[test]
+fn test_unlock_error_codes() {
+    let codes: Vec<i64> = vec![
+        actors::worker::Error::WalletNotFound,
+        actors::worker::Error::WrongPassword,
+        actors::worker::Error::CorruptedWallet("bincode failed".to_string()),
+    ]
+    .into_iter()
+    .map(|err| app::Error::from(err).into_parts().0)
+    .collect();
+
+    assert_eq!(codes, vec![402, 406, 422]);
+    assert_eq!(
+        app::Error::UnlockThrottled { retry_after: 4 }
+            .into_parts()
+            .0,
+        425
+    );
+}
+
+#[test]
+fn test_unlock_throttling_delay_grows() {
+    let mut state = app::state::State::default();
+    let now = 1_000;
+    let wallet: model::WalletId = "wallet".parse().unwrap();
+
+    assert_eq!(state.unlock_retry_after(&wallet, now), None);
+
+    let delays: Vec<u64> = (0..5)
+        .map(|_| state.record_failed_unlock(&wallet, now))
+        .collect();
+    assert_eq!(delays, vec![1, 2, 4, 8, 16]);
+    assert_eq!(state.unlock_retry_after(&wallet, now), Some(16));
+    assert_eq!(state.unlock_retry_after(&wallet, now + 10), Some(6));
+    assert_eq!(state.unlock_retry_after(&wallet, now + 16), None);
+    // Other wallets are not throttled
+    assert_eq!(
+        state.unlock_retry_after(&"other-wallet".parse().unwrap(), now),
+        None
+    );
+
+    // The delay is capped
+    for _ in 0..100 {
+        state.record_failed_unlock(&wallet, now);
+    }
+    assert_eq!(
+        state.unlock_retry_after(&wallet, now),
+        Some(constants::MAX_UNLOCK_DELAY)
+    );
+
+    state.reset_failed_unlocks(&wallet);
+    assert_eq!(state.unlock_retry_after(&wallet, now), None);
+    assert_eq!(state.record_failed_unlock(&wallet, now), 1);
+}
+
+#[test]
+fn test_unlock_attempts_are_throttled_while_running() {
+    let mut state = app::state::State::default();
+    let now = 1_000;
+    let wallet: model::WalletId = "wallet".parse().unwrap();
+
+    // Attempts made while another one is running count it as failed
+    assert_eq!(state.reserve_unlock(&wallet, now), Ok(()));
+    assert_eq!(state.reserve_unlock(&wallet, now), Err(1));
+    assert_eq!(state.record_failed_unlock(&wallet, now), 1);
+    assert_eq!(state.reserve_unlock(&wallet, now + 1), Ok(()));
+    assert_eq!(state.reserve_unlock(&wallet, now + 1), Err(2));
+    assert_eq!(state.record_failed_unlock(&wallet, now + 1), 2);
+    assert_eq!(state.reserve_unlock(&wallet, now + 2), Err(1));
+
+    // Attempts that fail before checking the password are given back
+    assert_eq!(state.reserve_unlock(&wallet, now + 3), Ok(()));
+    state.release_unlock(&wallet);
+    assert_eq!(state.unlock_retry_after(&wallet, now + 5), None);
+    assert_eq!(state.record_failed_unlock(&wallet, now + 5), 4);
+
+    // Attempts with the right password forget every failed one
+    assert_eq!(state.reserve_unlock(&wallet, now + 9), Ok(()));
+    state.reset_failed_unlocks(&wallet);
+    assert_eq!(state.reserve_unlock(&wallet, now + 9), Ok(()));
+    state.release_unlock(&wallet);
+    assert!(state.failed_unlocks.is_empty());
+}
+
+/// Unlock the same wallet `times` times in a row, returning the open sessions and the evicted
+/// ones, or the error of the first unlock that was rejected.
+fn unlock_repeatedly(
+    limits: &app::SessionLimits,
+    times: usize,
+) -> Result<(Vec<types::SessionId>, Vec<types::SessionId>), app::Error> {
+    let mut open: Vec<(types::SessionId, bool, i64)> = vec![];
+    let mut evicted = vec![];
+    for i in 0..times {
+        let to_evict =
+            app::state::sessions_to_evict(open.clone(), &"wallet".parse().unwrap(), limits)?;
+        open.retain(|(session_id, _, _)| !to_evict.contains(session_id));
+        evicted.extend(to_evict);
+        open.push((
+            types::SessionId::from(format!("session-{}", i)),
+            true,
+            i as i64,
+        ));
+    }
+
+    Ok((open.into_iter().map(|(id, _, _)| id).collect(), evicted))
+}
+
+#[test]
+fn test_unlock_beyond_session_cap_is_rejected() {
+    let limits = app::SessionLimits {
+        per_wallet: 3,
+        total: 100,
+        policy: SessionLimitPolicy::Reject,
+    };
+
+    assert!(unlock_repeatedly(&limits, 3).is_ok());
+    let err = unlock_repeatedly(&limits, 4).unwrap_err();
+    match &err {
+        app::Error::TooManySessions { wallet_id, limit } => {
+            assert_eq!(wallet_id.as_deref(), Some("wallet"));
+            assert_eq!(*limit, 3);
+        }
+        _ => panic!("unexpected error: {}", err),
+    }
+    assert_eq!(err.into_parts().0, 429);
+}
+
+#[test]
+fn test_unlock_beyond_session_cap_evicts_least_recently_active() {
+    let limits = app::SessionLimits {
+        per_wallet: 3,
+        total: 100,
+        policy: SessionLimitPolicy::EvictLeastRecentlyActive,
+    };
+
+    let (open, evicted) = unlock_repeatedly(&limits, 5).unwrap();
+    assert_eq!(
+        open,
+        vec![
+            types::SessionId::from("session-2".to_string()),
+            types::SessionId::from("session-3".to_string()),
+            types::SessionId::from("session-4".to_string()),
+        ]
+    );
+    assert_eq!(
+        evicted,
+        vec![
+            types::SessionId::from("session-0".to_string()),
+            types::SessionId::from("session-1".to_string()),
+        ]
+    );
+}
+
+#[test]
+fn test_global_session_cap() {
+    let limits = app::SessionLimits {
+        per_wallet: 10,
+        total: 2,
+        policy: SessionLimitPolicy::EvictLeastRecentlyActive,
+    };
+    // Sessions of other wallets are evicted too when the global cap is reached
+    let sessions = vec![
+        (types::SessionId::from("old".to_string()), false, 1),
+        (types::SessionId::from("new".to_string()), false, 2),
+    ];
+    let evicted =
+        app::state::sessions_to_evict(sessions.clone(), &"wallet".parse().unwrap(), &limits)
+            .unwrap();
+    assert_eq!(evicted, vec![types::SessionId::from("old".to_string())]);
+
+    let limits = app::SessionLimits {
+        policy: SessionLimitPolicy::Reject,
+        ..limits
+    };
+    match app::state::sessions_to_evict(sessions, &"wallet".parse().unwrap(), &limits) {
+        Err(app::Error::TooManySessions {
+            wallet_id: None,
+            limit: 2,
+        }) => {}
+        other => panic!("unexpected result: {:?}", other.map_err(|e| e.to_string())),
+    }
+}
+
+#[derive(Clone, Default)]
+struct RecordingTransport(std::sync::Arc<std::sync::Mutex<Vec<jsonrpc_core::Params>>>);
+
+impl sink::Transport for RecordingTransport {
+    fn try_notify(&self, params: jsonrpc_core::Params) -> Result<(), sink::TransportError> {
+        self.0.lock().unwrap().push(params);
+        Ok(())
+    }
+}
+
+/// Transport whose client only reads the notifications once it catches up.
+#[derive(Clone, Default)]
+struct LaggingTransport {
+    caught_up: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    received: RecordingTransport,
+}
+
+impl sink::Transport for LaggingTransport {
+    fn try_notify(&self, params: jsonrpc_core::Params) -> Result<(), sink::TransportError> {
+        if self.caught_up.load(std::sync::atomic::Ordering::SeqCst) {
+            self.received.try_notify(params)
+        } else {
+            Err(sink::TransportError::Full)
+        }
+    }
+}
+
+fn subscription(session_id: &types::SessionId) -> app::state::Subscription {
+    app::state::Subscription {
+        session_id: session_id.clone(),
+        connection: None,
+    }
+}
+
+fn subscription_id(id: &str) -> jsonrpc_pubsub::SubscriptionId {
+    jsonrpc_pubsub::SubscriptionId::String(id.to_string())
+}
+
+#[test]
+fn test_evicted_session_is_notified_and_removed() {
+    let session_id = types::SessionId::from("evicted".to_string());
+    let mut state = app::state::State::default();
+    state
+        .sessions
+        .insert(session_id.clone(), app::state::Session::default());
+    let transport = RecordingTransport::default();
+    let sink = sink::BoundedSink::new(transport.clone(), 10, sink::OverflowPolicy::DropOldest);
+    state
+        .subscribe(subscription_id("a"), subscription(&session_id), sink, None)
+        .unwrap();
+
+    state.evict_session(&session_id).unwrap();
+
+    assert_eq!(
+        *transport.0.lock().unwrap(),
+        vec![sink::session_evicted_notification("evicted")]
+    );
+    assert!(!state.is_session_active(&session_id));
+    // The subscription of the evicted session does not linger
+    assert!(state.client_subscriptions.is_empty());
+    assert!(state.subscriptions.is_empty());
+}
+
+#[test]
+fn test_resubscribe_replays_missed_events() {
+    let session_id = types::SessionId::from("reconnecting".to_string());
+    let mut state = app::state::State {
+        notifications_replay_size: 100,
+        ..Default::default()
+    };
+    state
+        .sessions
+        .insert(session_id.clone(), app::state::Session::default());
+    let event_ids = |transport: &RecordingTransport| -> Vec<u64> {
+        transport
+            .0
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|params| match params {
+                jsonrpc_core::Params::Array(values) => values[0]["event_id"].as_u64().unwrap(),
+                _ => unreachable!(),
+            })
+            .collect()
+    };
+    let emit = |state: &mut app::state::State, n: u64| {
+        state
+            .get_sink(&session_id)
+            .write()
+            .unwrap()
+            .notify(serde_json::json!({ "n": n }))
+            .unwrap()
+    };
+
+    let transport = RecordingTransport::default();
+    let sink = sink::BoundedSink::new(transport.clone(), 10, sink::OverflowPolicy::DropOldest);
+    state
+        .subscribe(subscription_id("a"), subscription(&session_id), sink, None)
+        .unwrap();
+    emit(&mut state, 0);
+    emit(&mut state, 1);
+    assert_eq!(event_ids(&transport), vec![1, 2]);
+
+    // The client disconnects, and some events fire while no sink is attached
+    state
+        .unsubscribe(&subscription_id("a"), &session_id)
+        .unwrap();
+    emit(&mut state, 2);
+    emit(&mut state, 3);
+    assert_eq!(event_ids(&transport), vec![1, 2]);
+
+    // The client resubscribes from the last event it got
+    let transport = RecordingTransport::default();
+    let sink = sink::BoundedSink::new(transport.clone(), 10, sink::OverflowPolicy::DropOldest);
+    state
+        .subscribe(
+            subscription_id("b"),
+            subscription(&session_id),
+            sink,
+            Some(2),
+        )
+        .unwrap();
+    emit(&mut state, 4);
+    assert_eq!(event_ids(&transport), vec![3, 4, 5]);
+
+    // The replay buffer goes away along with the session
+    state.remove_session(&session_id).unwrap();
+    assert!(state.client_subscriptions.is_empty());
+}
+
+#[test]
+fn test_subscriptions_can_only_be_removed_by_their_session() {
+    let alice = types::SessionId::from("alice-session".to_string());
+    let bob = types::SessionId::from("bob-session".to_string());
+    let mut state = app::state::State::default();
+    let mut transports = vec![];
+    for (session_id, id) in [(&alice, "alice-subscription"), (&bob, "bob-subscription")] {
+        state
+            .sessions
+            .insert(session_id.clone(), app::state::Session::default());
+        let transport = RecordingTransport::default();
+        let sink = sink::BoundedSink::new(transport.clone(), 10, sink::OverflowPolicy::DropOldest);
+        state
+            .subscribe(subscription_id(id), subscription(session_id), sink, None)
+            .unwrap();
+        transports.push(transport);
+    }
+    let notify = |state: &mut app::state::State, session_id: &types::SessionId| {
+        state
+            .get_sink(session_id)
+            .write()
+            .unwrap()
+            .notify(serde_json::json!({}))
+            .unwrap()
+    };
+
+    // Bob cannot remove the subscription of Alice, which keeps getting notifications
+    assert!(matches!(
+        state.unsubscribe(&subscription_id("alice-subscription"), &bob),
+        Err(app::Error::SubscriptionForbidden)
+    ));
+    notify(&mut state, &alice);
+    assert_eq!(transports[0].0.lock().unwrap().len(), 1);
+
+    // Alice can remove her own subscription, only once
+    state
+        .unsubscribe(&subscription_id("alice-subscription"), &alice)
+        .unwrap();
+    notify(&mut state, &alice);
+    assert_eq!(transports[0].0.lock().unwrap().len(), 1);
+    assert!(matches!(
+        state.unsubscribe(&subscription_id("alice-subscription"), &alice),
+        Err(app::Error::SubscriptionNotFound)
+    ));
+
+    // Closing the session of Bob removes his subscription, and nothing is kept for him anymore
+    state.remove_session(&bob).unwrap();
+    assert!(state.subscriptions.is_empty());
+    assert!(!state.client_subscriptions.contains_key(&bob));
+    notify(&mut state, &bob);
+    assert!(!state.client_subscriptions.contains_key(&bob));
+    assert!(transports[1].0.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_lock_all_wallets_and_unlock_again_in_same_sessions() {
+    let path = std::env::temp_dir().join(format!("witnet-wallet-lock-all-{}", std::process::id()));
+    let rocks = std::sync::Arc::new(rocksdb::DB::open_default(&path).unwrap());
+    let unlocked_wallet = |wallet_id: &model::WalletId, session_id: &types::SessionId| {
+        std::sync::Arc::new(repository::factories::encrypted_wallet(
+            rocks.clone(),
+            wallet_id,
+            session_id.clone(),
+        ))
+    };
+    let sessions: [(types::SessionId, model::WalletId); 2] = [
+        (
+            types::SessionId::from("session-a".to_string()),
+            "wallet-a".parse().unwrap(),
+        ),
+        (
+            types::SessionId::from("session-b".to_string()),
+            "wallet-b".parse().unwrap(),
+        ),
+    ];
+
+    let mut state = app::state::State::default();
+    let mut transports = vec![];
+    let mut unlocked = vec![];
+    for (session_id, wallet_id) in &sessions {
+        let wallet = unlocked_wallet(wallet_id, session_id);
+        unlocked.push(std::sync::Arc::downgrade(&wallet));
+        state.create_session(session_id.clone(), wallet_id.clone(), wallet, None, None);
+
+        let transport = RecordingTransport::default();
+        let sink = sink::BoundedSink::new(transport.clone(), 10, sink::OverflowPolicy::DropOldest);
+        state
+            .subscribe(
+                subscription_id(wallet_id),
+                subscription(session_id),
+                sink,
+                None,
+            )
+            .unwrap();
+        transports.push(transport);
+    }
+
+    assert_eq!(state.lock_all_wallets(), 2);
+
+    // The wallets, and therefore their keys, are gone from every session
+    assert!(unlocked.iter().all(|wallet| wallet.upgrade().is_none()));
+    assert!(state.wallets.is_empty());
+    for ((session_id, wallet_id), transport) in sessions.iter().zip(&transports) {
+        assert!(state.get_wallets_by_session(session_id).unwrap().is_empty());
+        assert_eq!(
+            *transport.0.lock().unwrap(),
+            vec![sink::wallets_locked_notification(&[wallet_id.clone()])]
+        );
+    }
+
+    // Wallets can be unlocked again in the same sessions, whose subscriptions are still there
+    for (session_id, wallet_id) in &sessions {
+        let wallet = unlocked_wallet(wallet_id, session_id);
+        state.create_session(session_id.clone(), wallet_id.clone(), wallet, None, None);
+
+        assert!(state
+            .get_wallet_by_session_and_id(session_id, wallet_id)
+            .is_ok());
+        assert!(state.get_sink_stats(session_id).is_some());
+    }
+
+    drop(state);
+    drop(rocks);
+    rocksdb::DB::destroy(&rocksdb::Options::default(), &path).unwrap();
+}
+
+#[test]
+fn test_queued_notifications_are_flushed_once_the_client_catches_up() {
+    let session_id = types::SessionId::from("lagging".to_string());
+    let mut state = app::state::State::default();
+    state
+        .sessions
+        .insert(session_id.clone(), app::state::Session::default());
+    let transport = LaggingTransport::default();
+    let sink = sink::BoundedSink::new(transport.clone(), 10, sink::OverflowPolicy::DropOldest);
+    state
+        .subscribe(subscription_id("a"), subscription(&session_id), sink, None)
+        .unwrap();
+    state
+        .get_sink(&session_id)
+        .write()
+        .unwrap()
+        .notify(serde_json::json!({}))
+        .unwrap();
+    assert_eq!(state.get_sink_stats(&session_id).unwrap().1.queued, 1);
+
+    // Nothing else is notified for the session, but the client still gets what was queued
+    transport
+        .caught_up
+        .store(true, std::sync::atomic::Ordering::SeqCst);
+    state.flush_sinks();
+
+    assert_eq!(transport.received.0.lock().unwrap().len(), 1);
+    assert_eq!(state.get_sink_stats(&session_id).unwrap().1.queued, 0);
+}
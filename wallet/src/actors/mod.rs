@@ -1,5 +1,7 @@
 pub mod app;
+pub mod webhooks;
 pub mod worker;
 
 pub use app::App;
+pub use webhooks::Webhooks;
 pub use worker::Worker;
@@ -0,0 +1,92 @@
+use std::sync::Arc;
+
+use actix::prelude::*;
+
+use crate::{actors::webhooks, model, repository};
+
+/// Post the deliveries of an unlocked wallet, including the ones left in its outbox while it was
+/// locked.
+pub struct Register {
+    pub outbox: Arc<webhooks::Outbox>,
+}
+
+impl Message for Register {
+    type Result = ();
+}
+
+impl Handler<Register> for webhooks::Webhooks {
+    type Result = ();
+
+    fn handle(&mut self, msg: Register, ctx: &mut Self::Context) -> Self::Result {
+        self.register(&msg.outbox);
+        self.post_due(ctx);
+    }
+}
+
+/// Queue a signed payload for posting it to the webhook of a wallet.
+pub struct Enqueue {
+    pub outbox: Arc<webhooks::Outbox>,
+    pub url: String,
+    pub payload: String,
+    pub signature: String,
+}
+
+impl Message for Enqueue {
+    type Result = repository::Result<model::WebhookDelivery>;
+}
+
+impl Handler<Enqueue> for webhooks::Webhooks {
+    type Result = <Enqueue as Message>::Result;
+
+    fn handle(&mut self, msg: Enqueue, ctx: &mut Self::Context) -> Self::Result {
+        let delivery =
+            msg.outbox
+                .enqueue(&msg.url, msg.payload, msg.signature, webhooks::now_millis())?;
+        self.register(&msg.outbox);
+        self.post_due(ctx);
+
+        Ok(delivery)
+    }
+}
+
+pub struct GetDeliveries {
+    pub outbox: Arc<webhooks::Outbox>,
+}
+
+impl Message for GetDeliveries {
+    type Result = repository::Result<model::WebhookDeliveries>;
+}
+
+impl Handler<GetDeliveries> for webhooks::Webhooks {
+    type Result = <GetDeliveries as Message>::Result;
+
+    fn handle(&mut self, msg: GetDeliveries, _ctx: &mut Self::Context) -> Self::Result {
+        msg.outbox.deliveries()
+    }
+}
+
+pub struct RequeueDeliveries {
+    pub outbox: Arc<webhooks::Outbox>,
+    /// Dead letters to requeue, or all the ones of the wallet if `None`
+    pub ids: Option<Vec<u64>>,
+}
+
+impl Message for RequeueDeliveries {
+    type Result = repository::Result<usize>;
+}
+
+impl Handler<RequeueDeliveries> for webhooks::Webhooks {
+    type Result = <RequeueDeliveries as Message>::Result;
+
+    fn handle(&mut self, msg: RequeueDeliveries, ctx: &mut Self::Context) -> Self::Result {
+        let requeued = msg
+            .outbox
+            .requeue(msg.ids.as_deref(), webhooks::now_millis())?;
+        if requeued > 0 {
+            self.register(&msg.outbox);
+            self.post_due(ctx);
+        }
+
+        Ok(requeued)
+    }
+}
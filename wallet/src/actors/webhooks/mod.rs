@@ -0,0 +1,237 @@
+//! Delivery of the events of wallets to their webhooks.
+//!
+//! The worker signs the events that a webhook is subscribed to while it processes blocks, and has
+//! this actor persist them in the `WebhookOutbox` of their wallet, which lives in its encrypted
+//! database. Deliveries are posted from the tasks of this actor, so processing blocks never waits
+//! on an endpoint. When nothing is due, the actor sleeps until the next retry is due or new
+//! deliveries are queued.
+//!
+//! Every change to an outbox is made by this actor, so that sessions that unlocked the same wallet
+//! never race on it. Outboxes are only reachable while their wallets are unlocked: the deliveries
+//! of a locked wallet wait in its database until it is unlocked again.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Weak},
+    time::Duration,
+};
+
+use actix::prelude::*;
+use witnet_net::client::http::{WitnetHttpBody, WitnetHttpClient, WitnetHttpRequest};
+
+use crate::{db, model, repository};
+
+pub mod handlers;
+#[cfg(test)]
+mod tests;
+
+pub use handlers::*;
+
+/// Header with the identifier of a delivery, which is the same in every attempt of it.
+pub const DELIVERY_HEADER: &str = "X-Witnet-Delivery";
+
+/// Header with the signature of the payload of a delivery, as `sha256=<hex-encoded HMAC>`.
+pub const SIGNATURE_HEADER: &str = "X-Witnet-Signature";
+
+/// Deliveries posted at the same time.
+const BATCH_SIZE: usize = 16;
+
+/// Time that an endpoint has for accepting a delivery before the attempt is considered failed.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub type Outbox = repository::WebhookOutbox<db::EncryptedDb>;
+
+pub struct Webhooks {
+    /// Outboxes of the unlocked wallets, which are forgotten once their wallets are dropped.
+    outboxes: HashMap<model::WalletId, Weak<Outbox>>,
+    client: Arc<WitnetHttpClient>,
+    /// Whether a batch of deliveries is being posted.
+    posting: bool,
+    /// Timer that wakes the actor up when the next retry is due.
+    timer: Option<SpawnHandle>,
+}
+
+impl Actor for Webhooks {
+    type Context = Context<Self>;
+}
+
+impl Webhooks {
+    /// Start the actor in an arbiter of its own, because the database operations of the outboxes
+    /// must never run in the arbiter of the `App` actor.
+    pub fn start(arbiter: &ArbiterHandle) -> Result<Addr<Self>, failure::Error> {
+        let client = Arc::new(WitnetHttpClient::new(None, false)?);
+
+        Ok(Self::start_in_arbiter(arbiter, move |_ctx| Self {
+            outboxes: Default::default(),
+            client,
+            posting: false,
+            timer: None,
+        }))
+    }
+
+    /// Post the deliveries of `outbox` from now on, instead of the ones of any other instance of
+    /// the same wallet.
+    fn register(&mut self, outbox: &Arc<Outbox>) {
+        self.outboxes
+            .insert(outbox.wallet_id().clone(), Arc::downgrade(outbox));
+    }
+
+    /// Outboxes of the wallets that are still unlocked, forgetting the other ones.
+    fn live_outboxes(&mut self) -> Vec<Arc<Outbox>> {
+        let mut live = Vec::with_capacity(self.outboxes.len());
+        self.outboxes
+            .retain(|_wallet_id, outbox| match outbox.upgrade() {
+                Some(outbox) => {
+                    live.push(outbox);
+
+                    true
+                }
+                None => false,
+            });
+
+        live
+    }
+
+    /// Post the deliveries that are due, unless a batch is already being posted, in which case they
+    /// are posted as soon as it is done.
+    fn post_due(&mut self, ctx: &mut Context<Self>) {
+        if self.posting {
+            return;
+        }
+        if let Some(timer) = self.timer.take() {
+            ctx.cancel_future(timer);
+        }
+
+        let now = now_millis();
+        let mut due = Vec::new();
+        for outbox in self.live_outboxes() {
+            match outbox.due(now, BATCH_SIZE - due.len()) {
+                Ok(deliveries) => due.extend(
+                    deliveries
+                        .into_iter()
+                        .map(|delivery| (Arc::downgrade(&outbox), delivery)),
+                ),
+                Err(e) => log::error!(
+                    "Failed to read the webhook outbox of wallet {}: {}",
+                    outbox.wallet_id(),
+                    e
+                ),
+            }
+            if due.len() == BATCH_SIZE {
+                break;
+            }
+        }
+        if due.is_empty() {
+            self.schedule_next(ctx);
+
+            return;
+        }
+
+        self.posting = true;
+        let client = self.client.clone();
+        let posts = due.into_iter().map(move |(outbox, delivery)| {
+            let client = client.clone();
+
+            async move {
+                let result = post(&client, &delivery).await;
+
+                (outbox, delivery, result)
+            }
+        });
+        let f = futures::future::join_all(posts)
+            .into_actor(self)
+            .map(|results, act, ctx| {
+                let now = now_millis();
+                for (outbox, delivery, result) in results {
+                    // The attempt is recorded once the wallet is unlocked again otherwise, as it
+                    // is still in its outbox
+                    let outbox = match outbox.upgrade() {
+                        Some(outbox) => outbox,
+                        None => continue,
+                    };
+                    let recorded = match result {
+                        Ok(()) => {
+                            log::debug!(
+                                "Delivered {} to the webhook of wallet {}",
+                                delivery.id,
+                                delivery.wallet_id
+                            );
+                            outbox.delivered(delivery.id)
+                        }
+                        Err(error) => {
+                            log::warn!(
+                                "Failed to deliver {} to the webhook of wallet {}: {}",
+                                delivery.id,
+                                delivery.wallet_id,
+                                error
+                            );
+                            outbox.failed(delivery.id, error, now).map(drop)
+                        }
+                    };
+                    if let Err(e) = recorded {
+                        log::error!(
+                            "Failed to update the webhook outbox of wallet {}: {}",
+                            delivery.wallet_id,
+                            e
+                        );
+                    }
+                }
+
+                act.posting = false;
+                act.post_due(ctx);
+            });
+        ctx.spawn(f);
+    }
+
+    /// Wake up when the next retry is due, if there is any.
+    fn schedule_next(&mut self, ctx: &mut Context<Self>) {
+        let mut next = None;
+        for outbox in self.live_outboxes() {
+            match outbox.next_attempt_at() {
+                Ok(next_attempt_at) => next = next.into_iter().chain(next_attempt_at).min(),
+                Err(e) => log::error!(
+                    "Failed to read the webhook outbox of wallet {}: {}",
+                    outbox.wallet_id(),
+                    e
+                ),
+            }
+        }
+
+        if let Some(next_attempt_at) = next {
+            let delay = u64::try_from(next_attempt_at - now_millis()).unwrap_or(0);
+            self.timer = Some(ctx.run_later(Duration::from_millis(delay), |act, ctx| {
+                act.timer = None;
+                act.post_due(ctx);
+            }));
+        }
+    }
+}
+
+/// Post a delivery to its endpoint, which must accept it with a successful status.
+async fn post(client: &WitnetHttpClient, delivery: &model::WebhookDelivery) -> Result<(), String> {
+    let request = WitnetHttpRequest::build(|builder| {
+        builder
+            .method("POST")
+            .uri(delivery.url.as_str())
+            .header("Content-Type", "application/json")
+            .header(DELIVERY_HEADER, delivery.id.to_string())
+            .header(SIGNATURE_HEADER, format!("sha256={}", delivery.signature))
+            .body(WitnetHttpBody::from(delivery.payload.clone()))
+    })
+    .map_err(|e| e.to_string())?;
+
+    let response = tokio::time::timeout(REQUEST_TIMEOUT, client.send(request))
+        .await
+        .map_err(|_| "request timed out".to_string())?
+        .map_err(|e| e.to_string())?;
+    let status = response.inner().status();
+    if status.is_success() {
+        Ok(())
+    } else {
+        Err(format!("endpoint answered with status {}", status))
+    }
+}
+
+/// Current timestamp in milliseconds, which is the unit of the timestamps of the outbox.
+pub fn now_millis() -> i64 {
+    chrono::Utc::now().timestamp_millis()
+}
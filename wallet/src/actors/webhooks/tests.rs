@@ -0,0 +1,229 @@
+use std::future::Future;
+
+use hmac::{Hmac, Mac};
+
+use super::*;
+use crate::{
+    crypto,
+    testing::{in_memory_db, MockEndpoint},
+};
+
+const SECRET: &str = "shared secret";
+const PAYLOAD: &str = r#"{"wallet_id":"wallet","events":[{"SyncFinish":[0,10]}]}"#;
+
+fn wallet_id() -> model::WalletId {
+    "wallet".parse().unwrap()
+}
+
+/// Outbox in the encrypted database of the wallet.
+fn outbox(db: &Arc<rocksdb::DB>, max_attempts: u32, retry_delay: i64) -> Arc<Outbox> {
+    let key = crypto::key_from_password(b"password", b"salt", 1);
+    let wallet_db = db::EncryptedDb::new(db.clone(), b"wallet".to_vec(), key, vec![0; 16]);
+
+    Arc::new(repository::WebhookOutbox::new(
+        wallet_id(),
+        wallet_db,
+        10,
+        max_attempts,
+        retry_delay,
+    ))
+}
+
+async fn enqueue(webhooks: &Addr<Webhooks>, outbox: &Arc<Outbox>, url: &str) -> u64 {
+    let signature = crypto::webhook_signature(SECRET.as_bytes(), PAYLOAD.as_bytes());
+
+    webhooks
+        .send(Enqueue {
+            outbox: outbox.clone(),
+            url: url.to_string(),
+            payload: PAYLOAD.to_string(),
+            signature,
+        })
+        .await
+        .unwrap()
+        .unwrap()
+        .id
+}
+
+/// Wait in real time until `condition` holds, panicking after 10 seconds.
+async fn wait_until<F, Fut>(mut condition: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = bool>,
+{
+    for _ in 0..1_000 {
+        if condition().await {
+            return;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+
+    panic!("Timed out waiting for a condition");
+}
+
+async fn delivered(outbox: &Outbox) -> bool {
+    let deliveries = outbox.deliveries().unwrap();
+
+    deliveries.pending.is_empty() && deliveries.dead_letters.is_empty()
+}
+
+#[test]
+fn test_signature() {
+    // Test case 2 of RFC 4231
+    assert_eq!(
+        crypto::webhook_signature(b"Jefe", b"what do ya want for nothing?"),
+        "5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+    );
+}
+
+#[test]
+fn test_delivery_is_signed() {
+    System::new().block_on(async {
+        let mut endpoint = MockEndpoint::start(vec![]);
+        let db = in_memory_db().unwrap();
+        let outbox = outbox(&db, 3, 10);
+        let webhooks = Webhooks::start(&Arbiter::new().handle()).unwrap();
+        let id = enqueue(&webhooks, &outbox, endpoint.url()).await;
+
+        let request = endpoint.next().await;
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.body, PAYLOAD);
+        assert_eq!(request.headers["content-type"], "application/json");
+        assert_eq!(request.headers["x-witnet-delivery"], id.to_string());
+
+        // The endpoint can authenticate the payload with the shared secret
+        let signature = request.headers["x-witnet-signature"]
+            .strip_prefix("sha256=")
+            .unwrap();
+        let mut mac = Hmac::<sha2::Sha256>::new_varkey(SECRET.as_bytes()).unwrap();
+        mac.input(request.body.as_bytes());
+        mac.verify(&hex::decode(signature).unwrap()).unwrap();
+
+        wait_until(|| delivered(&outbox)).await;
+    });
+}
+
+#[test]
+fn test_retry_after_server_error() {
+    System::new().block_on(async {
+        let mut endpoint = MockEndpoint::start(vec![500]);
+        let db = in_memory_db().unwrap();
+        let outbox = outbox(&db, 3, 10);
+        let webhooks = Webhooks::start(&Arbiter::new().handle()).unwrap();
+        let id = enqueue(&webhooks, &outbox, endpoint.url()).await;
+
+        let failed = endpoint.next().await;
+        let retried = endpoint.next().await;
+        assert_eq!(failed.headers["x-witnet-delivery"], id.to_string());
+        assert_eq!(retried.headers["x-witnet-delivery"], id.to_string());
+        assert_eq!(retried.body, failed.body);
+        assert_eq!(
+            retried.headers["x-witnet-signature"],
+            failed.headers["x-witnet-signature"]
+        );
+
+        wait_until(|| delivered(&outbox)).await;
+    });
+}
+
+#[test]
+fn test_outbox_survives_restart() {
+    System::new().block_on(async {
+        let mut endpoint = MockEndpoint::start(vec![500]);
+        let db = in_memory_db().unwrap();
+        let outbox_before = outbox(&db, 3, 500);
+        let arbiter = Arbiter::new();
+        let webhooks = Webhooks::start(&arbiter.handle()).unwrap();
+        let id = enqueue(&webhooks, &outbox_before, endpoint.url()).await;
+
+        // The wallet stops after the first attempt failed, and before retrying it
+        endpoint.next().await;
+        wait_until(|| async { outbox_before.deliveries().unwrap().pending[0].attempts == 1 }).await;
+        arbiter.stop();
+        arbiter.join().unwrap();
+        drop(outbox_before);
+
+        // Once the wallet is unlocked again, the delivery is retried from its database
+        let outbox_after = outbox(&db, 3, 500);
+        let webhooks = Webhooks::start(&Arbiter::new().handle()).unwrap();
+        webhooks
+            .send(Register {
+                outbox: outbox_after.clone(),
+            })
+            .await
+            .unwrap();
+        let retried = endpoint.next().await;
+        assert_eq!(retried.headers["x-witnet-delivery"], id.to_string());
+        assert_eq!(retried.body, PAYLOAD);
+
+        wait_until(|| delivered(&outbox_after)).await;
+    });
+}
+
+#[test]
+fn test_outbox_is_encrypted() {
+    System::new().block_on(async {
+        let db = in_memory_db().unwrap();
+        let outbox = outbox(&db, 3, 500);
+        let webhooks = Webhooks::start(&Arbiter::new().handle()).unwrap();
+        // Nothing listens on the port of the URL, so the delivery stays in the outbox
+        enqueue(&webhooks, &outbox, "http://127.0.0.1:1/events").await;
+
+        let persisted = db
+            .iterator(rocksdb::IteratorMode::Start)
+            .map(|record| {
+                let (key, value) = record.unwrap();
+
+                [key, value].concat()
+            })
+            .collect::<Vec<_>>();
+        assert!(!persisted.is_empty());
+        // Neither the events, nor the URL, nor the names of the keys are persisted in cleartext
+        for record in persisted {
+            for leaked in [PAYLOAD.as_bytes(), &b"127.0.0.1:1"[..], &b"webhook"[..]] {
+                assert!(!record.windows(leaked.len()).any(|window| window == leaked));
+            }
+        }
+    });
+}
+
+#[test]
+fn test_dead_letters_are_requeued() {
+    System::new().block_on(async {
+        let mut endpoint = MockEndpoint::start(vec![503]);
+        let db = in_memory_db().unwrap();
+        let outbox = outbox(&db, 1, 10);
+        let webhooks = Webhooks::start(&Arbiter::new().handle()).unwrap();
+        let id = enqueue(&webhooks, &outbox, endpoint.url()).await;
+
+        endpoint.next().await;
+        wait_until(|| async { !outbox.deliveries().unwrap().dead_letters.is_empty() }).await;
+        let deliveries = webhooks
+            .send(GetDeliveries {
+                outbox: outbox.clone(),
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(deliveries.pending.is_empty());
+        assert_eq!(deliveries.dead_letters[0].id, id);
+        assert_eq!(
+            deliveries.dead_letters[0].last_error,
+            Some("endpoint answered with status 503 Service Unavailable".to_string())
+        );
+
+        let requeued = webhooks
+            .send(RequeueDeliveries {
+                outbox: outbox.clone(),
+                ids: None,
+            })
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(requeued, 1);
+        let retried = endpoint.next().await;
+        assert_eq!(retried.headers["x-witnet-delivery"], id.to_string());
+
+        wait_until(|| delivered(&outbox)).await;
+    });
+}
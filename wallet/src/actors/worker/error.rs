@@ -1,6 +1,6 @@
 use failure::Fail;
 
-use crate::{crypto, db, repository};
+use crate::{crypto, db, deadline, locks, pool, repository, simulation, sink};
 use witnet_data_structures::chain::Hash;
 use witnet_net::client::tcp;
 
@@ -23,8 +23,19 @@ pub enum Error {
     WrongPassword,
     #[fail(display = "wallet not found")]
     WalletNotFound,
+    #[fail(display = "wallet data is corrupted: {}", _0)]
+    CorruptedWallet(String),
+    #[fail(display = "wallet requires newer software: unknown key spec {}", _0)]
+    UnsupportedKeySpec(String),
+    #[fail(
+        display = "wallet requires newer software: unknown schema version {}, the latest supported is {}",
+        found, supported
+    )]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
     #[fail(display = "send error: {}", _0)]
     Send(#[cause] futures01::sync::mpsc::SendError<std::string::String>),
+    #[fail(display = "notification error: {}", _0)]
+    Sink(#[cause] sink::Error),
     #[fail(display = "node error: {}", _0)]
     Node(#[cause] failure::Error),
     #[fail(display = "JsonRPC timeout error")]
@@ -39,6 +50,8 @@ pub enum Error {
     EpochCalculation(#[cause] witnet_data_structures::error::EpochCalculationError),
     #[fail(display = "wallet already exists: {}", _0)]
     WalletAlreadyExists(String),
+    #[fail(display = "wallet is not waiting for its seed backup to be confirmed")]
+    SeedBackupNotPending,
     #[fail(
         display = "error while syncing: node is behind our local tip (#{} < #{})",
         _0, _1
@@ -49,6 +62,16 @@ pub enum Error {
         _0, _1
     )]
     InvalidBirthDate(u32, u32),
+    #[fail(display = "{}", _0)]
+    Timeout(#[cause] deadline::Expired),
+    #[fail(display = "tally simulation failed: {}", _0)]
+    Simulation(#[cause] simulation::Error),
+    #[fail(display = "wallet is already synchronizing")]
+    SyncInProgress,
+    #[fail(display = "invalid webhook URL: {}", _0)]
+    InvalidWebhookUrl(String),
+    #[fail(display = "{}", _0)]
+    WalletBusy(#[cause] locks::Busy),
 }
 
 #[derive(Debug, Fail)]
@@ -121,6 +144,24 @@ impl From<futures01::sync::mpsc::SendError<std::string::String>> for Error {
     }
 }
 
+impl From<deadline::Expired> for Error {
+    fn from(err: deadline::Expired) -> Self {
+        Error::Timeout(err)
+    }
+}
+
+impl From<locks::Busy> for Error {
+    fn from(err: locks::Busy) -> Self {
+        Error::WalletBusy(err)
+    }
+}
+
+impl From<sink::Error> for Error {
+    fn from(err: sink::Error) -> Self {
+        Error::Sink(err)
+    }
+}
+
 impl From<tcp::Error> for Error {
     fn from(err: tcp::Error) -> Self {
         match err {
@@ -130,6 +171,12 @@ impl From<tcp::Error> for Error {
     }
 }
 
+impl From<pool::Exhausted> for Error {
+    fn from(err: pool::Exhausted) -> Self {
+        node_error(err)
+    }
+}
+
 impl From<witnet_data_structures::chain::HashParseError> for Error {
     fn from(err: witnet_data_structures::chain::HashParseError) -> Self {
         block_error(err)
@@ -0,0 +1,48 @@
+use std::time::Duration;
+
+use actix::prelude::*;
+
+use crate::{actors::worker, deadline::Deadline, model};
+use witnet_data_structures::chain::RADRetrieve;
+
+/// Retrieve some data sources repeatedly and summarize how each of them did.
+pub struct BenchmarkRetrievals {
+    pub retrievals: Vec<RADRetrieve>,
+    pub iterations: u32,
+    /// Time between the start of an iteration and the end of the previous one.
+    pub spacing: Duration,
+    /// Each retrieval is abandoned if it does not finish within this timeout.
+    pub timeout: Option<Duration>,
+    /// Keep the benchmark of each source, replacing the last one of the same URL.
+    pub persist: bool,
+    /// The benchmark is abandoned if it does not finish before this deadline.
+    pub deadline: Deadline,
+}
+
+impl Message for BenchmarkRetrievals {
+    type Result = worker::Result<model::RetrievalBenchmark>;
+}
+
+impl Handler<BenchmarkRetrievals> for worker::Worker {
+    type Result = <BenchmarkRetrievals as Message>::Result;
+
+    fn handle(
+        &mut self,
+        BenchmarkRetrievals {
+            retrievals,
+            iterations,
+            spacing,
+            timeout,
+            persist,
+            deadline,
+        }: BenchmarkRetrievals,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        log::debug!(
+            "Benchmarking {} retrievals over {} iterations",
+            retrievals.len(),
+            iterations
+        );
+        self.benchmark_retrievals(retrievals, iterations, spacing, timeout, persist, deadline)
+    }
+}
@@ -0,0 +1,59 @@
+use actix::prelude::*;
+
+use crate::{
+    actors::worker,
+    model::{ExtendedTransaction, SpendingCheck},
+    types,
+};
+use witnet_data_structures::{
+    chain::Hash,
+    fee::{AbsoluteFee, Fee},
+};
+
+pub struct BumpFee {
+    pub wallet: types::SessionWallet,
+    /// Pending transaction to replace
+    pub transaction_hash: Hash,
+    pub fee: Fee,
+    /// Add more inputs if the change of the transaction cannot cover the higher fee
+    pub add_inputs: bool,
+    pub allow_low_fee: bool,
+}
+
+pub struct BumpFeeResponse {
+    pub fee: AbsoluteFee,
+    /// Transaction that replaces the pending one
+    pub transaction: ExtendedTransaction,
+    /// How the spending policy of the wallet applies to sending the replacement
+    pub spending: SpendingCheck,
+}
+
+impl Message for BumpFee {
+    type Result = worker::Result<BumpFeeResponse>;
+}
+
+impl Handler<BumpFee> for worker::Worker {
+    type Result = <BumpFee as Message>::Result;
+
+    fn handle(
+        &mut self,
+        BumpFee {
+            wallet,
+            transaction_hash,
+            fee,
+            add_inputs,
+            allow_low_fee,
+        }: BumpFee,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (transaction, fee) =
+            self.bump_fee(&wallet, &transaction_hash, fee, add_inputs, allow_low_fee)?;
+        let spending = self.spending_check(&wallet, &transaction.transaction)?;
+
+        Ok(BumpFeeResponse {
+            fee,
+            transaction,
+            spending,
+        })
+    }
+}
@@ -0,0 +1,31 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct CheckIntegrity {
+    pub wallet: types::SessionWallet,
+    /// Whether to repair the records that can be derived from others
+    pub repair: bool,
+    pub sink: types::DynamicSink,
+}
+
+impl Message for CheckIntegrity {
+    type Result = worker::Result<model::IntegrityReport>;
+}
+
+impl Handler<CheckIntegrity> for worker::Worker {
+    type Result = <CheckIntegrity as Message>::Result;
+
+    fn handle(
+        &mut self,
+        CheckIntegrity {
+            wallet,
+            repair,
+            sink,
+        }: CheckIntegrity,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.check_integrity(&wallet, repair, sink)
+    }
+}
@@ -1,6 +1,6 @@
 use actix::prelude::*;
 
-use crate::{actors::worker, types};
+use crate::{actors::worker, model, types};
 
 pub struct CheckWalletSeedRequest {
     /// Wallet seed source (mnemonics or xprv)
@@ -8,7 +8,7 @@ pub struct CheckWalletSeedRequest {
 }
 
 impl Message for CheckWalletSeedRequest {
-    type Result = worker::Result<(bool, String)>;
+    type Result = worker::Result<(bool, Option<model::WalletId>)>;
 }
 
 impl Handler<CheckWalletSeedRequest> for worker::Worker {
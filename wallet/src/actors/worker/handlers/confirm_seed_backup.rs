@@ -0,0 +1,31 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct ConfirmSeedBackup {
+    pub wallet: types::SessionWallet,
+    pub wallet_id: model::WalletId,
+    /// Words of the mnemonic along with their 1-based positions
+    pub words: Vec<(usize, types::Password)>,
+}
+
+impl Message for ConfirmSeedBackup {
+    type Result = worker::Result<model::SeedBackupConfirmation>;
+}
+
+impl Handler<ConfirmSeedBackup> for worker::Worker {
+    type Result = <ConfirmSeedBackup as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ConfirmSeedBackup {
+            wallet,
+            wallet_id,
+            words,
+        }: ConfirmSeedBackup,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.confirm_seed_backup(&wallet, wallet_id, &words)
+    }
+}
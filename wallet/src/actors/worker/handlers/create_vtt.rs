@@ -1,6 +1,10 @@
 use actix::prelude::*;
 
-use crate::{actors::worker, model::ExtendedTransaction, types};
+use crate::{
+    actors::worker,
+    model::{ExtendedTransaction, SpendingCheck},
+    types,
+};
 use witnet_data_structures::fee::AbsoluteFee;
 
 pub struct CreateVtt {
@@ -11,6 +15,8 @@ pub struct CreateVtt {
 pub struct CreateVttResponse {
     pub fee: AbsoluteFee,
     pub transaction: ExtendedTransaction,
+    /// How the spending policy of the wallet applies to sending the transaction
+    pub spending: SpendingCheck,
 }
 
 impl Message for CreateVtt {
@@ -25,7 +31,13 @@ impl Handler<CreateVtt> for worker::Worker {
         CreateVtt { wallet, params }: CreateVtt,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.create_vtt(&wallet, params)
-            .map(|(transaction, fee)| CreateVttResponse { fee, transaction })
+        let (transaction, fee) = self.create_vtt(&wallet, params)?;
+        let spending = self.spending_check(&wallet, &transaction.transaction)?;
+
+        Ok(CreateVttResponse {
+            fee,
+            transaction,
+            spending,
+        })
     }
 }
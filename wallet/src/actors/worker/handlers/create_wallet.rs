@@ -1,7 +1,8 @@
 use actix::prelude::*;
+use witnet_crypto::key::KeySpec;
 
 use crate::actors::worker;
-use crate::types;
+use crate::{model, types};
 
 pub struct CreateWallet {
     /// Wallet name
@@ -14,12 +15,18 @@ pub struct CreateWallet {
     pub seed_source: types::SeedSource,
     /// Overwrite flag
     pub overwrite: bool,
+    /// Allow creating another wallet from a seed that already has one
+    pub allow_duplicate_seed: bool,
     /// Protocol epoch in which a wallet was created (won't synchronize blocks prior to this epoch)
     pub birth_date: Option<types::BirthDate>,
+    /// Keep the wallet unusable until the user confirms having backed up its mnemonic
+    pub backup_confirmation: bool,
+    /// Key spec to derive the wallet accounts with, instead of the configured one
+    pub key_spec: Option<KeySpec>,
 }
 
 impl Message for CreateWallet {
-    type Result = worker::Result<String>;
+    type Result = worker::Result<model::WalletId>;
 }
 
 impl Handler<CreateWallet> for worker::Worker {
@@ -33,7 +40,10 @@ impl Handler<CreateWallet> for worker::Worker {
             password,
             seed_source,
             overwrite,
+            allow_duplicate_seed,
             birth_date,
+            backup_confirmation,
+            key_spec,
         }: CreateWallet,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
@@ -43,7 +53,10 @@ impl Handler<CreateWallet> for worker::Worker {
             password.as_ref(),
             &seed_source,
             overwrite,
+            allow_duplicate_seed,
             birth_date,
+            backup_confirmation,
+            key_spec,
         )
     }
 }
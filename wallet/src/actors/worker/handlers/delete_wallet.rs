@@ -1,11 +1,11 @@
 use actix::prelude::*;
 
 use crate::actors::worker;
-use crate::types;
+use crate::{model, types};
 
 pub struct DeleteWallet {
     pub wallet: types::SessionWallet,
-    pub wallet_id: String,
+    pub wallet_id: model::WalletId,
 }
 
 impl Message for DeleteWallet {
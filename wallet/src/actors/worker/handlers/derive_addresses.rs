@@ -0,0 +1,28 @@
+use actix::prelude::*;
+use witnet_crypto::key::KeyPath;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct DeriveAddresses {
+    /// Seed data (mnemonics or xprv)
+    pub seed_source: types::SeedSource,
+    /// Key paths to derive, starting at the master key
+    pub paths: Vec<KeyPath>,
+}
+
+impl Message for DeriveAddresses {
+    type Result = worker::Result<Vec<model::DerivedAddress>>;
+}
+
+impl Handler<DeriveAddresses> for worker::Worker {
+    type Result = <DeriveAddresses as Message>::Result;
+
+    fn handle(
+        &mut self,
+        DeriveAddresses { seed_source, paths }: DeriveAddresses,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.derive_addresses(&seed_source, &paths)
+    }
+}
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{diagnostics, model, types};
+
+pub struct Diagnostics {
+    /// Wallets to diagnose, by id
+    pub wallets: Vec<(model::WalletId, types::SessionWallet)>,
+    /// Id and password of the wallet whose sensitive data is included, if any
+    pub sensitive: Option<(model::WalletId, types::Password)>,
+}
+
+impl Message for Diagnostics {
+    type Result = worker::Result<diagnostics::WorkerDiagnostics>;
+}
+
+impl Handler<Diagnostics> for worker::Worker {
+    type Result = <Diagnostics as Message>::Result;
+
+    fn handle(
+        &mut self,
+        Diagnostics { wallets, sensitive }: Diagnostics,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let sensitive = sensitive
+            .as_ref()
+            .map(|(wallet_id, password)| (wallet_id, AsRef::<[u8]>::as_ref(password)));
+
+        self.diagnostics(&wallets, sensitive)
+    }
+}
+
+/// Write a diagnostics bundle to a new file, which may block on the disk.
+pub struct WriteDiagnosticsBundle {
+    pub path: PathBuf,
+    pub bundle: diagnostics::DiagnosticsBundle,
+}
+
+impl Message for WriteDiagnosticsBundle {
+    type Result = std::io::Result<()>;
+}
+
+impl Handler<WriteDiagnosticsBundle> for worker::Worker {
+    type Result = <WriteDiagnosticsBundle as Message>::Result;
+
+    fn handle(
+        &mut self,
+        WriteDiagnosticsBundle { path, bundle }: WriteDiagnosticsBundle,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        diagnostics::write_bundle(&path, &bundle)
+    }
+}
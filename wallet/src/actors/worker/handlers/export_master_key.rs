@@ -1,11 +1,14 @@
 use actix::prelude::*;
 
 use crate::actors::worker;
-use crate::types;
+use crate::{deadline::Deadline, types};
 
 pub struct ExportMasterKey {
     pub wallet: types::SessionWallet,
     pub password: types::Password,
+    /// The export is refused if it has not started before this deadline, e.g. because the
+    /// worker was busy.
+    pub deadline: Deadline,
 }
 
 impl Message for ExportMasterKey {
@@ -17,9 +20,13 @@ impl Handler<ExportMasterKey> for worker::Worker {
 
     fn handle(
         &mut self,
-        ExportMasterKey { wallet, password }: ExportMasterKey,
+        ExportMasterKey {
+            wallet,
+            password,
+            deadline,
+        }: ExportMasterKey,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.export_master_key(&wallet, password)
+        self.export_master_key(&wallet, password, deadline)
     }
 }
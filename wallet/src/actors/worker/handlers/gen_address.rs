@@ -7,10 +7,12 @@ pub struct GenAddress {
     pub wallet: types::SessionWallet,
     pub external: bool,
     pub label: Option<String>,
+    /// Sink of the session, notified about the first address beyond the gap limit
+    pub sink: types::DynamicSink,
 }
 
 impl Message for GenAddress {
-    type Result = worker::Result<model::Address>;
+    type Result = worker::Result<(model::Address, Option<model::AddressGapWarning>)>;
 }
 
 impl Handler<GenAddress> for worker::Worker {
@@ -22,10 +24,11 @@ impl Handler<GenAddress> for worker::Worker {
             wallet,
             external,
             label,
+            sink,
         }: GenAddress,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.gen_address(&wallet, external, label)
-            .map(|address| (*address).clone())
+        self.gen_address(&wallet, external, label, sink)
+            .map(|(address, warning)| ((*address).clone(), warning))
     }
 }
@@ -11,6 +11,10 @@ pub struct GetAddresses {
     pub limit: u32,
     /// External
     pub external: bool,
+    pub sort: model::AddressesSort,
+    pub order: model::SortOrder,
+    /// Cursor returned with the previous page, if any
+    pub cursor: Option<model::AddressesCursor>,
 }
 
 impl Message for GetAddresses {
@@ -27,9 +31,12 @@ impl Handler<GetAddresses> for worker::Worker {
             offset,
             limit,
             external,
+            sort,
+            order,
+            cursor,
         }: GetAddresses,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.addresses(&wallet, offset, limit, external)
+        self.addresses(&wallet, offset, limit, external, sort, order, cursor)
     }
 }
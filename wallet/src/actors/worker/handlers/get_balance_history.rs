@@ -0,0 +1,32 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetBalanceHistory {
+    pub wallet: types::SessionWallet,
+    pub from: Option<u32>,
+    pub to: Option<u32>,
+    pub resolution: model::BalanceHistoryResolution,
+}
+
+impl Message for GetBalanceHistory {
+    type Result = worker::Result<Vec<model::BalanceHistoryEntry>>;
+}
+
+impl Handler<GetBalanceHistory> for worker::Worker {
+    type Result = <GetBalanceHistory as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetBalanceHistory {
+            wallet,
+            from,
+            to,
+            resolution,
+        }: GetBalanceHistory,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.balance_history(&wallet, from, to, resolution)
+    }
+}
@@ -9,6 +9,10 @@ pub struct GetTransactions {
     pub offset: u32,
     /// Limit
     pub limit: u32,
+    pub sort: model::MovementsSort,
+    pub order: model::SortOrder,
+    /// Cursor returned with the previous page, if any
+    pub cursor: Option<model::MovementsCursor>,
 }
 
 impl Message for GetTransactions {
@@ -24,9 +28,40 @@ impl Handler<GetTransactions> for worker::Worker {
             wallet,
             offset,
             limit,
+            sort,
+            order,
+            cursor,
         }: GetTransactions,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.transactions(&wallet, offset, limit)
+        self.transactions(&wallet, offset, limit, sort, order, cursor)
+    }
+}
+
+pub struct GetTransactionsStream {
+    pub wallet: types::SessionWallet,
+    /// Position of the stream, or none to start from the oldest movement
+    pub cursor: Option<model::TransactionsCursor>,
+    /// Maximum number of movements of the batch
+    pub batch_size: u32,
+}
+
+impl Message for GetTransactionsStream {
+    type Result = worker::Result<model::TransactionsBatch>;
+}
+
+impl Handler<GetTransactionsStream> for worker::Worker {
+    type Result = <GetTransactionsStream as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetTransactionsStream {
+            wallet,
+            cursor,
+            batch_size,
+        }: GetTransactionsStream,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.transactions_stream(&wallet, cursor, batch_size)
     }
 }
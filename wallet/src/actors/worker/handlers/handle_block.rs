@@ -1,13 +1,17 @@
 use actix::{Handler, Message};
 use std::sync::Arc;
 
-use crate::{actors::worker, types};
+use crate::{actors::worker, queues, repository, types};
 use witnet_data_structures::chain::Block;
 
 pub struct HandleBlockRequest {
     pub block: Arc<Block>,
+    /// Scan of the block, shared by every wallet that the block is handed to.
+    pub scan: Arc<repository::BlockScan>,
     pub wallet: types::SessionWallet,
     pub sink: types::DynamicSink,
+    /// Place of the block in the queue of the block worker, given back once it is processed.
+    pub ticket: queues::Ticket,
 }
 
 impl Message for HandleBlockRequest {
@@ -18,6 +22,6 @@ impl Handler<HandleBlockRequest> for worker::Worker {
     type Result = <HandleBlockRequest as Message>::Result;
 
     fn handle(&mut self, msg: HandleBlockRequest, _ctx: &mut Self::Context) -> Self::Result {
-        self.handle_block(msg.block, false, msg.wallet, msg.sink)
+        self.handle_block(msg.block, Some(&msg.scan), false, msg.wallet, msg.sink)
     }
 }
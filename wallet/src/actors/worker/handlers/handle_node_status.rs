@@ -1,12 +1,15 @@
 use actix::{Handler, Message};
 
-use crate::{actors::worker, types};
+use crate::{actors::worker, deadline::Deadline, types};
 use witnet_data_structures::chain::StateMachine;
 
 pub struct NodeStatusRequest {
     pub status: StateMachine,
     pub wallet: types::SessionWallet,
     pub sink: types::DynamicSink,
+    /// The synchronization started once the node is synced stops, keeping its progress, if it
+    /// does not finish before this deadline.
+    pub deadline: Deadline,
 }
 
 impl Message for NodeStatusRequest {
@@ -17,6 +20,6 @@ impl Handler<NodeStatusRequest> for worker::Worker {
     type Result = <NodeStatusRequest as Message>::Result;
 
     fn handle(&mut self, msg: NodeStatusRequest, _ctx: &mut Self::Context) -> Self::Result {
-        self.handle_node_status(msg.status, msg.wallet, msg.sink)
+        self.handle_node_status(msg.status, msg.wallet, msg.sink, msg.deadline)
     }
 }
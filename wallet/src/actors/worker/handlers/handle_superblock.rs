@@ -1,12 +1,18 @@
 use actix::{Handler, Message};
+use std::sync::Arc;
 
 use crate::actors::worker;
-use crate::types;
+use crate::{deadline::Deadline, queues, types};
 
 pub struct HandleSuperBlockRequest {
-    pub superblock_notification: types::SuperBlockNotification,
+    pub superblock_notification: Arc<types::SuperBlockNotification>,
     pub wallet: types::SessionWallet,
     pub sink: types::DynamicSink,
+    /// Place of the superblock in the queue of the block worker, given back once it is processed.
+    pub ticket: queues::Ticket,
+    /// The consolidation stops, keeping the blocks already consolidated, if it does not finish
+    /// before this deadline.
+    pub deadline: Deadline,
 }
 
 impl Message for HandleSuperBlockRequest {
@@ -17,6 +23,11 @@ impl Handler<HandleSuperBlockRequest> for worker::Worker {
     type Result = <HandleSuperBlockRequest as Message>::Result;
 
     fn handle(&mut self, msg: HandleSuperBlockRequest, _ctx: &mut Self::Context) -> Self::Result {
-        self.handle_superblock(msg.superblock_notification, msg.wallet, msg.sink)
+        self.handle_superblock(
+            &msg.superblock_notification,
+            msg.wallet,
+            msg.sink,
+            msg.deadline,
+        )
     }
 }
@@ -0,0 +1,64 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct GetIdempotentOperation {
+    pub wallet: types::SessionWallet,
+    pub key: String,
+    /// Requests recorded before this timestamp are expired
+    pub expired_before: i64,
+}
+
+impl Message for GetIdempotentOperation {
+    type Result = worker::Result<Option<model::IdempotentOperation>>;
+}
+
+impl Handler<GetIdempotentOperation> for worker::Worker {
+    type Result = <GetIdempotentOperation as Message>::Result;
+
+    fn handle(&mut self, msg: GetIdempotentOperation, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(msg
+            .wallet
+            .idempotent_operation(&msg.key, msg.expired_before)?)
+    }
+}
+
+pub struct RecordIdempotentOperation {
+    pub wallet: types::SessionWallet,
+    pub key: String,
+    pub operation: model::IdempotentOperation,
+    /// Requests recorded before this timestamp are expired, and deleted
+    pub expired_before: i64,
+}
+
+impl Message for RecordIdempotentOperation {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<RecordIdempotentOperation> for worker::Worker {
+    type Result = <RecordIdempotentOperation as Message>::Result;
+
+    fn handle(&mut self, msg: RecordIdempotentOperation, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(msg
+            .wallet
+            .record_idempotent_operation(&msg.key, &msg.operation, msg.expired_before)?)
+    }
+}
+
+pub struct ForgetIdempotentOperation {
+    pub wallet: types::SessionWallet,
+    pub key: String,
+}
+
+impl Message for ForgetIdempotentOperation {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<ForgetIdempotentOperation> for worker::Worker {
+    type Result = <ForgetIdempotentOperation as Message>::Result;
+
+    fn handle(&mut self, msg: ForgetIdempotentOperation, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(msg.wallet.forget_idempotent_operation(&msg.key)?)
+    }
+}
@@ -0,0 +1,30 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{address_metadata::AddressMetadata, model, types};
+
+pub struct ImportAddressMetadata {
+    pub wallet: types::SessionWallet,
+    pub entries: Vec<AddressMetadata>,
+    pub sink: types::DynamicSink,
+}
+
+impl Message for ImportAddressMetadata {
+    type Result = worker::Result<model::AddressMetadataImport>;
+}
+
+impl Handler<ImportAddressMetadata> for worker::Worker {
+    type Result = <ImportAddressMetadata as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ImportAddressMetadata {
+            wallet,
+            entries,
+            sink,
+        }: ImportAddressMetadata,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.import_address_metadata(&wallet, &entries, sink)
+    }
+}
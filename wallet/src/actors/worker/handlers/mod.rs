@@ -1,56 +1,92 @@
+pub mod benchmark_retrievals;
+pub mod bump_fee;
+pub mod check_integrity;
 pub mod check_wallet_seed;
+pub mod confirm_seed_backup;
 pub mod create_data_req;
 pub mod create_vtt;
 pub mod create_wallet;
 pub mod delete_wallet;
+pub mod derive_addresses;
+pub mod diagnostics;
 pub mod export_master_key;
 pub mod flush_db;
 pub mod gen_address;
-pub mod gen_mnemonic;
 pub mod get;
 pub mod get_addresses;
 pub mod get_balance;
+pub mod get_balance_history;
 pub mod get_transaction;
 pub mod get_transactions;
 pub mod get_utxo_info;
 pub mod handle_block;
 pub mod handle_node_status;
 pub mod handle_superblock;
+pub mod idempotent_operations;
+pub mod import_address_metadata;
 pub mod notify_status;
+pub mod own_block_entries;
+pub mod payment_requests;
+pub mod prune_wallet_data;
 pub mod resync;
 pub mod run_rad_request;
 pub mod set;
 pub mod sign_data;
+pub mod simulate_data_request;
+pub mod spending_policy;
 pub mod sync;
+pub mod sync_status;
+pub mod transactions_in_blocks;
 pub mod unlock_wallet;
 pub mod update_wallet;
 pub mod update_wallet_info;
 pub mod wallet_infos;
+pub mod wallets_with_address;
+pub mod webhook;
+pub mod witness_signing;
 
+pub use benchmark_retrievals::*;
+pub use bump_fee::*;
+pub use check_integrity::*;
 pub use check_wallet_seed::*;
+pub use confirm_seed_backup::*;
 pub use create_data_req::*;
 pub use create_vtt::*;
 pub use create_wallet::*;
 pub use delete_wallet::*;
+pub use derive_addresses::*;
+pub use diagnostics::*;
 pub use export_master_key::*;
 pub use flush_db::*;
 pub use gen_address::*;
-pub use gen_mnemonic::*;
 pub use get::*;
 pub use get_addresses::*;
 pub use get_balance::*;
+pub use get_balance_history::*;
 pub use get_transactions::*;
 pub use get_utxo_info::*;
 pub use handle_block::*;
 pub use handle_node_status::*;
 pub use handle_superblock::*;
+pub use idempotent_operations::*;
+pub use import_address_metadata::*;
 pub use notify_status::*;
+pub use own_block_entries::*;
+pub use payment_requests::*;
+pub use prune_wallet_data::*;
 pub use resync::*;
 pub use run_rad_request::*;
 pub use set::*;
 pub use sign_data::*;
+pub use simulate_data_request::*;
+pub use spending_policy::*;
 pub use sync::*;
+pub use sync_status::*;
+pub use transactions_in_blocks::*;
 pub use unlock_wallet::*;
 pub use update_wallet::*;
 pub use update_wallet_info::*;
 pub use wallet_infos::*;
+pub use wallets_with_address::*;
+pub use webhook::*;
+pub use witness_signing::*;
@@ -0,0 +1,27 @@
+use std::{collections::HashSet, sync::Arc};
+
+use actix::prelude::*;
+use witnet_data_structures::chain::{Block, OutputPointer};
+
+use crate::{actors::worker, model, types};
+
+pub struct OwnBlockEntries {
+    pub wallets: Vec<(model::WalletId, types::SessionWallet)>,
+    pub block: Arc<Block>,
+}
+
+impl Message for OwnBlockEntries {
+    type Result = worker::Result<Vec<(model::WalletId, HashSet<OutputPointer>)>>;
+}
+
+impl Handler<OwnBlockEntries> for worker::Worker {
+    type Result = <OwnBlockEntries as Message>::Result;
+
+    fn handle(
+        &mut self,
+        OwnBlockEntries { wallets, block }: OwnBlockEntries,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.own_block_entries(&wallets, &block)
+    }
+}
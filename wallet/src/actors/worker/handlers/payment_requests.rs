@@ -0,0 +1,87 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct CreatePaymentRequest {
+    pub wallet: types::SessionWallet,
+    /// Expected amount
+    pub amount: u64,
+    /// Memo, also used as the label of the address
+    pub memo: Option<String>,
+    /// Seconds until the request expires
+    pub expires_in: Option<u64>,
+}
+
+impl Message for CreatePaymentRequest {
+    type Result = worker::Result<model::PaymentRequest>;
+}
+
+impl Handler<CreatePaymentRequest> for worker::Worker {
+    type Result = <CreatePaymentRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        CreatePaymentRequest {
+            wallet,
+            amount,
+            memo,
+            expires_in,
+        }: CreatePaymentRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.create_payment_request(&wallet, amount, memo, expires_in)
+    }
+}
+
+pub struct GetPaymentRequests {
+    pub wallet: types::SessionWallet,
+    /// Statuses to filter by, or all of them if empty
+    pub statuses: Vec<model::PaymentRequestStatus>,
+    /// Offset
+    pub offset: u32,
+    /// Limit
+    pub limit: u32,
+}
+
+impl Message for GetPaymentRequests {
+    type Result = worker::Result<model::PaymentRequests>;
+}
+
+impl Handler<GetPaymentRequests> for worker::Worker {
+    type Result = <GetPaymentRequests as Message>::Result;
+
+    fn handle(
+        &mut self,
+        GetPaymentRequests {
+            wallet,
+            statuses,
+            offset,
+            limit,
+        }: GetPaymentRequests,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.payment_requests(&wallet, &statuses, offset, limit)
+    }
+}
+
+pub struct ExpirePaymentRequests {
+    pub wallet: types::SessionWallet,
+    pub sink: types::DynamicSink,
+}
+
+impl Message for ExpirePaymentRequests {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<ExpirePaymentRequests> for worker::Worker {
+    type Result = <ExpirePaymentRequests as Message>::Result;
+
+    fn handle(
+        &mut self,
+        ExpirePaymentRequests { wallet, sink }: ExpirePaymentRequests,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.expire_payment_requests(&wallet, sink)
+    }
+}
@@ -0,0 +1,26 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct PruneWalletData {
+    pub wallet: types::SessionWallet,
+    /// Retention policy that replaces the one of the wallet before pruning, if any
+    pub policy: Option<model::RetentionPolicy>,
+}
+
+impl Message for PruneWalletData {
+    type Result = worker::Result<model::PruneReport>;
+}
+
+impl Handler<PruneWalletData> for worker::Worker {
+    type Result = <PruneWalletData as Message>::Result;
+
+    fn handle(
+        &mut self,
+        PruneWalletData { wallet, policy }: PruneWalletData,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.prune_wallet_data(&wallet, policy)
+    }
+}
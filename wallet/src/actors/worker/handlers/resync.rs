@@ -1,12 +1,17 @@
 use actix::{Handler, Message};
 
 use crate::actors::worker;
-use crate::types;
+use crate::{deadline::Deadline, model, types};
 
 pub struct Resync {
-    pub wallet_id: String,
+    pub wallet_id: model::WalletId,
     pub wallet: types::SessionWallet,
+    /// Whether to start again at the birth date of the wallet or at the genesis block
+    pub from: types::ResyncFrom,
     pub sink: types::DynamicSink,
+    /// The synchronization stops, keeping its progress, if it does not finish before this
+    /// deadline.
+    pub deadline: Deadline,
 }
 
 impl Message for Resync {
@@ -17,6 +22,12 @@ impl Handler<Resync> for worker::Worker {
     type Result = <Resync as Message>::Result;
 
     fn handle(&mut self, msg: Resync, _ctx: &mut Self::Context) -> Self::Result {
-        self.clear_chain_data_and_resync(&msg.wallet_id, msg.wallet, msg.sink)
+        self.clear_chain_data_and_resync(
+            &msg.wallet_id,
+            msg.wallet,
+            msg.from,
+            msg.sink,
+            msg.deadline,
+        )
     }
 }
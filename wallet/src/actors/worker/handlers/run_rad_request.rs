@@ -1,16 +1,27 @@
 use actix::prelude::*;
 
-use crate::actors::worker;
+use crate::{actors::worker, deadline::Deadline, model};
 use witnet_data_structures::chain::RADRequest;
 use witnet_rad::RADRequestExecutionReport;
 
 /// Execute the containing RAD-request.
 pub struct RunRadRequest {
     pub request: RADRequest,
+    /// The execution is abandoned if it does not finish before this deadline.
+    pub deadline: Deadline,
+    /// Fetch all the sources even if the retrieval cache has their responses.
+    pub bypass_cache: bool,
+}
+
+pub struct RunRadRequestResponse {
+    pub report: RADRequestExecutionReport,
+    /// How the response of each source was obtained, or `None` if the retrieval cache was not
+    /// used.
+    pub cache: Vec<Option<model::CacheStatus>>,
 }
 
 impl Message for RunRadRequest {
-    type Result = worker::Result<RADRequestExecutionReport>;
+    type Result = worker::Result<RunRadRequestResponse>;
 }
 
 impl Handler<RunRadRequest> for worker::Worker {
@@ -18,10 +29,14 @@ impl Handler<RunRadRequest> for worker::Worker {
 
     fn handle(
         &mut self,
-        RunRadRequest { request }: RunRadRequest,
+        RunRadRequest {
+            request,
+            deadline,
+            bypass_cache,
+        }: RunRadRequest,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         log::debug!("Executing RAD request");
-        Ok(self.run_rad_request(request))
+        self.run_rad_request(request, deadline, bypass_cache)
     }
 }
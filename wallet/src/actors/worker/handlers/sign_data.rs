@@ -6,11 +6,12 @@ use crate::{model, types};
 pub struct SignData {
     pub wallet: types::SessionWallet,
     pub data: String,
+    pub domain: Option<model::SigningDomain>,
     pub extended_pk: bool,
 }
 
 impl Message for SignData {
-    type Result = worker::Result<model::ExtendedKeyedSignature>;
+    type Result = worker::Result<model::SignedData>;
 }
 
 impl Handler<SignData> for worker::Worker {
@@ -21,10 +22,11 @@ impl Handler<SignData> for worker::Worker {
         SignData {
             wallet,
             data,
+            domain,
             extended_pk,
         }: SignData,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.sign_data(&wallet, &data, extended_pk)
+        self.sign_data(&wallet, &data, domain, extended_pk)
     }
 }
@@ -0,0 +1,46 @@
+use actix::prelude::*;
+
+use crate::{actors::worker, deadline::Deadline, model, simulation};
+use witnet_data_structures::chain::DataRequestOutput;
+use witnet_rad::RADRequestExecutionReport;
+
+/// Execute the containing data request and simulate its tally with dishonest witnesses.
+pub struct SimulateDataRequest {
+    pub request: DataRequestOutput,
+    /// Values revealed by the dishonest witnesses, one per witness.
+    pub lies: Vec<simulation::Lie>,
+    /// The execution is abandoned if it does not finish before this deadline.
+    pub deadline: Deadline,
+    /// Fetch all the sources even if the retrieval cache has their responses.
+    pub bypass_cache: bool,
+}
+
+pub struct SimulateDataRequestResponse {
+    pub report: RADRequestExecutionReport,
+    /// How the response of each source was obtained, or `None` if the retrieval cache was not
+    /// used.
+    pub cache: Vec<Option<model::CacheStatus>>,
+    pub simulation: simulation::TallySimulation,
+}
+
+impl Message for SimulateDataRequest {
+    type Result = worker::Result<SimulateDataRequestResponse>;
+}
+
+impl Handler<SimulateDataRequest> for worker::Worker {
+    type Result = <SimulateDataRequest as Message>::Result;
+
+    fn handle(
+        &mut self,
+        SimulateDataRequest {
+            request,
+            lies,
+            deadline,
+            bypass_cache,
+        }: SimulateDataRequest,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        log::debug!("Simulating data request with {} lies", lies.len());
+        self.simulate_data_request(request, lies, deadline, bypass_cache)
+    }
+}
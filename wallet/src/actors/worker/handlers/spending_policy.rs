@@ -0,0 +1,58 @@
+use actix::prelude::*;
+use witnet_data_structures::transaction::Transaction;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct UpdateWalletPolicy {
+    pub wallet_id: model::WalletId,
+    pub wallet: types::SessionWallet,
+    /// Password of the wallet, which must be the right one for the policy to change
+    pub password: types::Password,
+    pub policy: model::WalletPolicy,
+}
+
+impl Message for UpdateWalletPolicy {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<UpdateWalletPolicy> for worker::Worker {
+    type Result = <UpdateWalletPolicy as Message>::Result;
+
+    fn handle(
+        &mut self,
+        UpdateWalletPolicy {
+            wallet_id,
+            wallet,
+            password,
+            policy,
+        }: UpdateWalletPolicy,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.update_wallet_policy(&wallet_id, &wallet, password.as_ref(), policy)
+    }
+}
+
+pub struct CheckSpending {
+    pub wallet: types::SessionWallet,
+    pub transaction: Transaction,
+}
+
+impl Message for CheckSpending {
+    type Result = worker::Result<model::SpendingCheck>;
+}
+
+impl Handler<CheckSpending> for worker::Worker {
+    type Result = <CheckSpending as Message>::Result;
+
+    fn handle(
+        &mut self,
+        CheckSpending {
+            wallet,
+            transaction,
+        }: CheckSpending,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.spending_check(&wallet, &transaction)
+    }
+}
@@ -1,12 +1,15 @@
 use actix::{Handler, Message};
 
 use crate::actors::worker;
-use crate::types;
+use crate::{deadline::Deadline, model, types};
 
 pub struct SyncRequest {
-    pub wallet_id: String,
+    pub wallet_id: model::WalletId,
     pub wallet: types::SessionWallet,
     pub sink: types::DynamicSink,
+    /// The synchronization stops, keeping its progress, if it does not finish before this
+    /// deadline.
+    pub deadline: Deadline,
 }
 
 impl Message for SyncRequest {
@@ -17,6 +20,6 @@ impl Handler<SyncRequest> for worker::Worker {
     type Result = <SyncRequest as Message>::Result;
 
     fn handle(&mut self, msg: SyncRequest, _ctx: &mut Self::Context) -> Self::Result {
-        self.sync(&msg.wallet_id, &msg.wallet, msg.sink)
+        self.sync(&msg.wallet_id, &msg.wallet, msg.sink, msg.deadline)
     }
 }
@@ -0,0 +1,49 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{deadline::Deadline, model, types};
+
+pub struct GetSyncStatus {
+    pub wallet: types::SessionWallet,
+}
+
+impl Message for GetSyncStatus {
+    type Result = worker::Result<model::SyncWatermark>;
+}
+
+impl Handler<GetSyncStatus> for worker::Worker {
+    type Result = <GetSyncStatus as Message>::Result;
+
+    fn handle(&mut self, msg: GetSyncStatus, _ctx: &mut Self::Context) -> Self::Result {
+        Ok(msg.wallet.sync_watermark()?)
+    }
+}
+
+pub struct PinSync {
+    pub wallet_id: model::WalletId,
+    pub wallet: types::SessionWallet,
+    /// Epoch beyond which blocks are not processed, or `None` to unpin the synchronization
+    pub epoch: Option<u32>,
+    pub sink: types::DynamicSink,
+    /// The synchronization stops, keeping its progress, if it does not finish before this
+    /// deadline.
+    pub deadline: Deadline,
+}
+
+impl Message for PinSync {
+    type Result = worker::Result<model::SyncWatermark>;
+}
+
+impl Handler<PinSync> for worker::Worker {
+    type Result = <PinSync as Message>::Result;
+
+    fn handle(&mut self, msg: PinSync, _ctx: &mut Self::Context) -> Self::Result {
+        self.pin_sync(
+            &msg.wallet_id,
+            msg.wallet,
+            msg.epoch,
+            msg.sink,
+            msg.deadline,
+        )
+    }
+}
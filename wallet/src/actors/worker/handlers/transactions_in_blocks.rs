@@ -0,0 +1,27 @@
+use std::collections::HashMap;
+
+use actix::prelude::*;
+use witnet_data_structures::chain::Hash;
+
+use crate::{actors::worker, model, types};
+
+pub struct TransactionsInBlocks {
+    pub wallets: Vec<(model::WalletId, types::SessionWallet)>,
+    pub blocks: Vec<model::Beacon>,
+}
+
+impl Message for TransactionsInBlocks {
+    type Result = worker::Result<Vec<(model::WalletId, HashMap<Hash, Vec<String>>)>>;
+}
+
+impl Handler<TransactionsInBlocks> for worker::Worker {
+    type Result = <TransactionsInBlocks as Message>::Result;
+
+    fn handle(
+        &mut self,
+        TransactionsInBlocks { wallets, blocks }: TransactionsInBlocks,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.transactions_in_blocks(&wallets, &blocks)
+    }
+}
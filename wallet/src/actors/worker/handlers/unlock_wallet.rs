@@ -1,13 +1,15 @@
 use actix::prelude::*;
 
 use crate::actors::worker;
-use crate::types;
+use crate::{model, types};
 
 pub struct UnlockWallet {
     /// Wallet id
-    pub id: String,
+    pub id: model::WalletId,
     /// Wallet password
     pub password: types::Password,
+    /// Existing session to unlock the wallet in, instead of a new one
+    pub session_id: Option<types::SessionId>,
 }
 
 impl Message for UnlockWallet {
@@ -19,9 +21,13 @@ impl Handler<UnlockWallet> for worker::Worker {
 
     fn handle(
         &mut self,
-        UnlockWallet { id, password }: UnlockWallet,
+        UnlockWallet {
+            id,
+            password,
+            session_id,
+        }: UnlockWallet,
         _ctx: &mut Self::Context,
     ) -> Self::Result {
-        self.unlock_wallet(&id, password.as_ref())
+        self.unlock_wallet(&id, password.as_ref(), session_id)
     }
 }
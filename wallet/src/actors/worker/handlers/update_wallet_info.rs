@@ -1,10 +1,10 @@
 use actix::prelude::*;
 
-use crate::actors::worker;
+use crate::{actors::worker, model};
 
 pub struct UpdateWalletInfo {
     /// Wallet id
-    pub wallet_id: String,
+    pub wallet_id: model::WalletId,
     /// Wallet name
     pub name: Option<String>,
 }
@@ -3,16 +3,21 @@ use actix::prelude::*;
 use crate::actors::worker;
 use crate::model;
 
-pub struct WalletInfos;
+pub struct WalletInfos {
+    /// Include wallets waiting for their seed backup to be confirmed
+    pub include_pending: bool,
+    pub offset: u32,
+    pub limit: u32,
+}
 
 impl Message for WalletInfos {
-    type Result = worker::Result<Vec<model::Wallet>>;
+    type Result = worker::Result<model::WalletInfos>;
 }
 
 impl Handler<WalletInfos> for worker::Worker {
     type Result = <WalletInfos as Message>::Result;
 
-    fn handle(&mut self, _msg: WalletInfos, _ctx: &mut Self::Context) -> Self::Result {
-        self.wallet_infos()
+    fn handle(&mut self, msg: WalletInfos, _ctx: &mut Self::Context) -> Self::Result {
+        self.wallet_infos(msg.include_pending, msg.offset, msg.limit)
     }
 }
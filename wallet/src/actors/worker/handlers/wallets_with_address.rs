@@ -0,0 +1,25 @@
+use actix::prelude::*;
+use witnet_data_structures::chain::PublicKeyHash;
+
+use crate::{actors::worker, model, types};
+
+pub struct WalletsWithAddress {
+    pub wallets: Vec<(model::WalletId, types::SessionWallet)>,
+    pub pkh: PublicKeyHash,
+}
+
+impl Message for WalletsWithAddress {
+    type Result = worker::Result<Vec<model::WalletId>>;
+}
+
+impl Handler<WalletsWithAddress> for worker::Worker {
+    type Result = <WalletsWithAddress as Message>::Result;
+
+    fn handle(
+        &mut self,
+        WalletsWithAddress { wallets, pkh }: WalletsWithAddress,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.wallets_with_address(&wallets, &pkh)
+    }
+}
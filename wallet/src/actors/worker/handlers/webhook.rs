@@ -0,0 +1,33 @@
+use actix::prelude::*;
+
+use crate::actors::worker;
+use crate::{model, types};
+
+pub struct UpdateWalletWebhook {
+    pub wallet_id: model::WalletId,
+    pub wallet: types::SessionWallet,
+    /// Password of the wallet, which must be the right one for the webhook to change
+    pub password: types::Password,
+    pub webhook: Option<model::WebhookConfig>,
+}
+
+impl Message for UpdateWalletWebhook {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<UpdateWalletWebhook> for worker::Worker {
+    type Result = <UpdateWalletWebhook as Message>::Result;
+
+    fn handle(
+        &mut self,
+        UpdateWalletWebhook {
+            wallet_id,
+            wallet,
+            password,
+            webhook,
+        }: UpdateWalletWebhook,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.update_wallet_webhook(&wallet_id, &wallet, password.as_ref(), webhook)
+    }
+}
@@ -0,0 +1,55 @@
+use actix::prelude::*;
+use witnet_data_structures::chain::KeyedSignature;
+
+use crate::actors::worker;
+use crate::{model, types, witness_signing::WitnessTransactionBody};
+
+pub struct UpdateWitnessSigningPolicy {
+    pub wallet_id: model::WalletId,
+    pub wallet: types::SessionWallet,
+    /// Password of the wallet, which must be the right one for the policy to change
+    pub password: types::Password,
+    pub policy: model::WitnessSigningPolicy,
+}
+
+impl Message for UpdateWitnessSigningPolicy {
+    type Result = worker::Result<()>;
+}
+
+impl Handler<UpdateWitnessSigningPolicy> for worker::Worker {
+    type Result = <UpdateWitnessSigningPolicy as Message>::Result;
+
+    fn handle(
+        &mut self,
+        UpdateWitnessSigningPolicy {
+            wallet_id,
+            wallet,
+            password,
+            policy,
+        }: UpdateWitnessSigningPolicy,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.update_witness_signing_policy(&wallet_id, &wallet, password.as_ref(), policy)
+    }
+}
+
+pub struct SignWitnessTransaction {
+    pub wallet: types::SessionWallet,
+    pub body: WitnessTransactionBody,
+}
+
+impl Message for SignWitnessTransaction {
+    type Result = worker::Result<(KeyedSignature, model::WitnessSigningAuditEntry)>;
+}
+
+impl Handler<SignWitnessTransaction> for worker::Worker {
+    type Result = <SignWitnessTransaction as Message>::Result;
+
+    fn handle(
+        &mut self,
+        SignWitnessTransaction { wallet, body }: SignWitnessTransaction,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        self.sign_witness_transaction(&wallet, &body)
+    }
+}
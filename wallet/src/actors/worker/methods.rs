@@ -1,30 +1,51 @@
-use std::convert::{TryFrom, TryInto};
+use std::{
+    collections::{HashMap, HashSet},
+    convert::{TryFrom, TryInto},
+    time::Duration,
+};
 
-use jsonrpc_core as rpc;
 use serde_json::{json, Value};
 
 use crate::{
-    account, constants, crypto,
+    account,
+    actors::webhooks,
+    address_metadata::AddressMetadata,
+    constants, crypto,
     db::Database as _,
-    model, params,
+    deadline::{self, Deadline},
+    diagnostics, locks, metrics, model, params, retrieval_benchmark, schema, simulation,
     types::{ChainEntry, DynamicSink, GetBlockChainParams},
+    witness_signing::WitnessTransactionBody,
 };
-use witnet_crypto::{key::ExtendedSK, mnemonic};
+use witnet_crypto::key::{ExtendedSK, KeyPath, KeySpec};
 use witnet_data_structures::{
     chain::{
-        Block, CheckpointBeacon, DataRequestInfo, Hashable, OutputPointer, RADRequest,
+        header::{verify_block_header, BlockTransactionHashes},
+        tapi::current_active_wips,
+        Block, CheckpointBeacon, ConsensusConstants, DataRequestInfo, DataRequestOutput, Hash,
+        Hashable, KeyedSignature, OutputPointer, PublicKeyHash, RADRequest, RADRetrieve, RADType,
         StateMachine, ValueTransferOutput,
     },
-    fee::AbsoluteFee,
+    fee::{AbsoluteFee, Fee},
+    radon_report::DataRequestMetadata,
     transaction::Transaction,
+    witnessing::{RetrievalSecrets, WitnessingConfig},
 };
 use witnet_futures_utils::TryFutureExt2;
-use witnet_net::client::tcp::jsonrpc;
-use witnet_rad::{script::RadonScriptExecutionSettings, RADRequestExecutionReport};
+use witnet_net::client::{
+    http::{WitnetHttpBody, WitnetHttpClient, WitnetHttpRequest},
+    tcp::jsonrpc,
+};
+use witnet_rad::{
+    error::RadError,
+    response::read_response,
+    script::RadonScriptExecutionSettings,
+    secrets::{substitute_secrets, substitute_url_secrets},
+    variables::substitute_variables,
+};
 use witnet_util::timestamp::get_timestamp;
 
 use super::*;
-use futures_util::compat::Compat01As03;
 
 pub enum IndexTransactionQuery {
     InputTransactions(Vec<OutputPointer>),
@@ -37,32 +58,205 @@ impl Worker {
         db: Arc<rocksdb::DB>,
         node: params::NodeParams,
         params: params::Params,
-    ) -> Addr<Self> {
+        block_processing: metrics::SharedBlockProcessing,
+        webhooks: Addr<Webhooks>,
+    ) -> Workers {
         let wallets = Arc::new(repository::Wallets::new(db::PlainDb::new(db.clone())));
+        let wallet_locks = Arc::new(locks::WalletLocks::new(Duration::from_secs(
+            constants::WALLET_LOCK_TIMEOUT_SECS,
+        )));
+        let retrieval_cache = if params.retrieval_cache_enabled {
+            Some(Arc::new(repository::RetrievalCache::new(
+                db::PlainDb::new(db.clone()),
+                params.retrieval_cache_ttl,
+                params.retrieval_cache_max_size,
+            )))
+        } else {
+            None
+        };
 
-        SyncArbiter::start(concurrency, move || Self {
+        let worker = move || Self {
             db: db.clone(),
             wallets: wallets.clone(),
+            wallet_locks: wallet_locks.clone(),
+            retrieval_cache: retrieval_cache.clone(),
             node: node.clone(),
             params: params.clone(),
+            block_processing: block_processing.clone(),
+            webhooks: webhooks.clone(),
             rng: rand::rngs::OsRng,
-        })
+        };
+
+        // Blocks are processed by a single thread, so that they are applied in the order in which
+        // they were notified
+        Workers {
+            interactive: SyncArbiter::start(concurrency, worker.clone()),
+            blocks: SyncArbiter::start(1, worker.clone()),
+            maintenance: SyncArbiter::start(concurrency, worker),
+        }
     }
 
-    pub fn run_rad_request(&self, request: RADRequest) -> RADRequestExecutionReport {
-        witnet_rad::try_data_request(
+    /// Run a data request locally.
+    ///
+    /// If the retrieval cache is enabled and all the sources of the request are HTTP sources, their
+    /// responses are taken from the cache when possible, unless `bypass_cache` is set. Otherwise,
+    /// or if any source cannot be fetched, the request is run as witnesses would run it.
+    ///
+    /// The deadline is checked while the sources are being retrieved, which are abandoned as soon
+    /// as it expires.
+    pub fn run_rad_request(
+        &self,
+        request: RADRequest,
+        deadline: Deadline,
+        bypass_cache: bool,
+    ) -> Result<RunRadRequestResponse> {
+        let witnessing = self.params.witnessing.clone();
+        let retrieval_cache = self.retrieval_cache.as_ref().filter(|_| {
+            request
+                .retrieve
+                .iter()
+                .all(|retrieve| retrieve.kind.is_http())
+        });
+
+        let cached = match retrieval_cache {
+            Some(cache) => {
+                match fetch_sources_with_cache(cache, &request, bypass_cache, &witnessing, deadline)
+                {
+                    Ok(responses) => Some(responses),
+                    Err(e @ Error::Timeout(_)) => return Err(e),
+                    Err(e) => {
+                        log::debug!("Running data request without retrieval cache: {}", e);
+                        None
+                    }
+                }
+            }
+            None => None,
+        };
+
+        match cached {
+            Some(responses) => {
+                let (bodies, cache): (Vec<String>, Vec<_>) = responses.into_iter().unzip();
+                let inputs: Vec<&str> = bodies.iter().map(String::as_str).collect();
+                let report = witnet_rad::try_data_request(
+                    &request,
+                    RadonScriptExecutionSettings::enable_all(),
+                    Some(&inputs),
+                    Some(witnessing),
+                );
+
+                Ok(RunRadRequestResponse {
+                    report,
+                    cache: cache.into_iter().map(Some).collect(),
+                })
+            }
+            None => Ok(RunRadRequestResponse {
+                cache: vec![None; request.retrieve.len()],
+                report: deadline::block_on_with_deadline(
+                    deadline,
+                    "run_rad_request",
+                    witnet_rad::try_data_request_async(
+                        &request,
+                        RadonScriptExecutionSettings::enable_all(),
+                        None,
+                        Some(witnessing),
+                    ),
+                )?,
+            }),
+        }
+    }
+
+    /// Run the retrieval and aggregation of a data request locally, and then simulate its tally
+    /// with witnesses that reveal the aggregated result and others that reveal the given lies.
+    pub fn simulate_data_request(
+        &self,
+        request: DataRequestOutput,
+        lies: Vec<simulation::Lie>,
+        deadline: Deadline,
+        bypass_cache: bool,
+    ) -> Result<SimulateDataRequestResponse> {
+        let RunRadRequestResponse { report, cache } =
+            self.run_rad_request(request.data_request.clone(), deadline, bypass_cache)?;
+        let simulation = simulation::simulate_tally(
             &request,
-            RadonScriptExecutionSettings::enable_all(),
-            None,
-            Some(self.params.witnessing.clone()),
+            report.aggregate.result.clone(),
+            &lies,
+            self.params.consensus_constants.collateral_minimum,
+            &current_active_wips(),
         )
+        .map_err(Error::Simulation)?;
+
+        Ok(SimulateDataRequestResponse {
+            report,
+            cache,
+            simulation,
+        })
     }
 
-    pub fn gen_mnemonic(&self, length: mnemonic::Length) -> String {
-        let mnemonic = mnemonic::MnemonicGen::new().with_len(length).generate();
-        let words = mnemonic.words();
+    /// Retrieve some data sources `iterations` times, all of them at once in every iteration, and
+    /// summarize how each of them did. Every summary comes along with the last one that was kept
+    /// for the same URL, and replaces it if `persist` is set.
+    pub fn benchmark_retrievals(
+        &self,
+        retrievals: Vec<RADRetrieve>,
+        iterations: u32,
+        spacing: Duration,
+        timeout: Option<Duration>,
+        persist: bool,
+        deadline: Deadline,
+    ) -> Result<model::RetrievalBenchmark> {
+        let mut attempts: Vec<Vec<retrieval_benchmark::Attempt>> =
+            retrievals.iter().map(|_| vec![]).collect();
+        for iteration in 0..iterations {
+            if iteration > 0 {
+                std::thread::sleep(spacing);
+            }
+            deadline.check("benchmark_retrievals", || {
+                format!("completed {} of {} iterations", iteration, iterations)
+            })?;
+
+            let running: Vec<_> = retrievals
+                .iter()
+                .map(|retrieve| {
+                    let retrieve = retrieve.clone();
+                    let witnessing = self.params.witnessing.clone();
+
+                    std::thread::spawn(move || {
+                        retrieval_benchmark::attempt(&retrieve, timeout, &witnessing)
+                    })
+                })
+                .collect();
+            for (source, running) in attempts.iter_mut().zip(running) {
+                source.push(running.join().map_err(|_| {
+                    Error::Rad(RadError::HttpOther {
+                        message: "the retrieval thread panicked".to_string(),
+                    })
+                })?);
+            }
+        }
 
-        words.to_string()
+        let benchmarked_at = get_timestamp();
+        let sources = retrievals
+            .iter()
+            .zip(attempts)
+            .map(|(retrieve, attempts)| {
+                let benchmark =
+                    retrieval_benchmark::summarize(&retrieve.url, &attempts, benchmarked_at);
+                let previous = self.wallets.retrieval_benchmark(&retrieve.url)?;
+                if persist {
+                    self.wallets.set_retrieval_benchmark(&benchmark)?;
+                }
+
+                Ok(model::BenchmarkedSource {
+                    benchmark,
+                    previous,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(model::RetrievalBenchmark {
+            iterations,
+            sources,
+        })
     }
 
     pub fn flush_db(&self) -> Result<()> {
@@ -71,12 +265,18 @@ impl Worker {
         Ok(())
     }
 
-    pub fn wallet_infos(&self) -> Result<Vec<model::Wallet>> {
-        let wallets = self.wallets.infos()?;
+    pub fn wallet_infos(
+        &self,
+        include_pending: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<model::WalletInfos> {
+        let wallets = self.wallets.infos(include_pending, offset, limit)?;
 
         Ok(wallets)
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn create_wallet(
         &mut self,
         name: Option<String>,
@@ -84,9 +284,23 @@ impl Worker {
         password: &[u8],
         source: &types::SeedSource,
         overwrite: bool,
+        allow_duplicate_seed: bool,
         birth_date: Option<types::BirthDate>,
-    ) -> Result<String> {
-        let (id, default_account, master_key) = match source {
+        backup_confirmation: bool,
+        key_spec: Option<KeySpec>,
+    ) -> Result<model::WalletId> {
+        let key_derivation = model::KeyDerivation {
+            key_spec: key_spec.unwrap_or(self.params.key_derivation.key_spec),
+            ..self.params.key_derivation
+        };
+        // Keep the mnemonic words so that the user can prove having backed them up
+        let seed_backup = match source {
+            types::SeedSource::Mnemonics(mnemonic) if backup_confirmation => {
+                Some(types::Secret::new(mnemonic.words()))
+            }
+            _ => None,
+        };
+        let (seed_id, default_account, master_key) = match source {
             types::SeedSource::XprvDouble((internal, external)) => {
                 let (external_key, external_path) = ExtendedSK::from_slip32(external.as_ref())
                     .map_err(|e| Error::KeyGen(crypto::Error::Deserialization(e)))?;
@@ -133,7 +347,8 @@ impl Worker {
                     self.params.id_hash_iterations,
                 );
                 let default_account_index = 0;
-                let default_account = account::gen_account(default_account_index, &master_key)?;
+                let default_account =
+                    account::gen_account(&key_derivation, default_account_index, &master_key)?;
                 (id, default_account, Some(master_key))
             }
         };
@@ -193,16 +408,24 @@ impl Worker {
             }
         };
 
-        // Return error if `overwrite=false` and wallet already exists
-        if !overwrite
-            && self
-                .wallets
-                .infos()?
-                .into_iter()
-                .any(|wallet| wallet.id == id)
-        {
-            return Err(Error::WalletAlreadyExists(id));
-        }
+        // Wallet ids are random, the id derived from the seed is only used for its fingerprint.
+        // Return error if `overwrite=false` and a wallet already exists for the same seed, unless
+        // creating another wallet from it is explicitly allowed
+        let seed_fingerprint = self.wallets.seed_fingerprint(&seed_id)?;
+        let id = self
+            .wallets
+            .new_wallet_id(
+                &mut self.rng,
+                &seed_fingerprint,
+                overwrite,
+                allow_duplicate_seed,
+            )
+            .map_err(|err| match err {
+                repository::Error::WalletAlreadyExists(id) => Error::WalletAlreadyExists(id),
+                err => Error::Repository(err),
+            })?;
+        // Overwriting a wallet must not interleave with anything else done on it
+        let _lock = self.wallet_locks.exclusive(&id, "create_wallet")?;
 
         // This is for storage encryption
         let prefix = id.as_bytes().to_vec();
@@ -223,9 +446,12 @@ impl Worker {
                 iv,
                 salt,
                 id: &id,
+                seed_fingerprint,
                 account: &default_account,
                 master_key,
                 birth_date,
+                seed_backup,
+                key_derivation,
             },
         )?;
 
@@ -233,15 +459,84 @@ impl Worker {
     }
 
     /// Delete a wallet providing its WalletID and its SessionID
-    pub fn delete_wallet(&mut self, _wallet: &types::Wallet, wallet_id: String) -> Result<()> {
+    pub fn delete_wallet(
+        &mut self,
+        _wallet: &types::Wallet,
+        wallet_id: model::WalletId,
+    ) -> Result<()> {
+        let _lock = self.wallet_locks.exclusive(&wallet_id, "delete_wallet")?;
         self.wallets.delete(wallet_id)?;
 
         Ok(())
     }
 
-    /// Check if wallet with given seed source already exists
-    pub fn check_wallet_seed(&self, seed: types::SeedSource) -> Result<(bool, String)> {
-        let id = match seed {
+    /// Check some words of the mnemonic of a wallet waiting for its seed backup to be confirmed.
+    ///
+    /// If they are right the wallet can be used from then on. Otherwise the failed attempt is
+    /// counted, and the wallet is deleted once there are no attempts left.
+    pub fn confirm_seed_backup(
+        &mut self,
+        wallet: &types::Wallet,
+        wallet_id: model::WalletId,
+        words: &[(usize, types::Password)],
+    ) -> Result<model::SeedBackupConfirmation> {
+        // Running out of attempts deletes the wallet
+        let _lock = self
+            .wallet_locks
+            .exclusive(&wallet_id, "confirm_seed_backup")?;
+        if !wallet.is_pending_confirmation()? {
+            return Err(Error::SeedBackupNotPending);
+        }
+
+        if wallet.check_seed_backup(words)? {
+            wallet.forget_seed_backup()?;
+            self.wallets.confirm(&wallet_id)?;
+
+            return Ok(model::SeedBackupConfirmation::Confirmed);
+        }
+
+        let failed = self.wallets.record_failed_confirmation(&wallet_id)?;
+        let max_attempts = self.params.seed_backup_confirmation_attempts;
+        if failed >= max_attempts {
+            log::warn!(
+                "Deleting wallet {} after {} failed seed backup confirmations",
+                wallet_id,
+                failed
+            );
+            wallet.forget_seed_backup()?;
+            self.wallets.delete(wallet_id)?;
+
+            Ok(model::SeedBackupConfirmation::WalletDeleted)
+        } else {
+            Ok(model::SeedBackupConfirmation::WrongWords {
+                remaining_attempts: max_attempts - failed,
+            })
+        }
+    }
+
+    /// Derive addresses from a seed source without creating a wallet or writing to the database.
+    pub fn derive_addresses(
+        &self,
+        source: &types::SeedSource,
+        paths: &[KeyPath],
+    ) -> Result<Vec<model::DerivedAddress>> {
+        let master_key = crypto::gen_master_key(
+            self.params.seed_password.as_ref(),
+            self.params.master_key_salt.as_ref(),
+            source,
+        )?;
+        let addresses = account::derive_addresses(&master_key, paths)?;
+
+        Ok(addresses)
+    }
+
+    /// Check if wallet with given seed source already exists, returning the id of the first wallet
+    /// created from it
+    pub fn check_wallet_seed(
+        &self,
+        seed: types::SeedSource,
+    ) -> Result<(bool, Option<model::WalletId>)> {
+        let seed_id = match seed {
             types::SeedSource::XprvDouble((_, external)) => {
                 let (external_key, _) = ExtendedSK::from_slip32(external.as_ref())
                     .map_err(|e| Error::KeyGen(crypto::Error::Deserialization(e)))?;
@@ -267,13 +562,14 @@ impl Worker {
                 )
             }
         };
-        Ok((
-            self.wallets
-                .infos()?
-                .into_iter()
-                .any(|wallet| wallet.id == id),
-            id,
-        ))
+        let seed_fingerprint = self.wallets.seed_fingerprint(&seed_id)?;
+        let wallet_id = self
+            .wallets
+            .wallets_with_seed(&seed_fingerprint)?
+            .into_iter()
+            .next();
+
+        Ok((wallet_id.is_some(), wallet_id))
     }
 
     /// Update a wallet details.
@@ -289,7 +585,11 @@ impl Worker {
     }
 
     /// Update the wallet information in the infos database.
-    pub fn update_wallet_info(&self, wallet_id: &str, name: Option<String>) -> Result<()> {
+    pub fn update_wallet_info(
+        &self,
+        wallet_id: &model::WalletId,
+        name: Option<String>,
+    ) -> Result<()> {
         self.wallets.update_info(wallet_id, name)?;
 
         Ok(())
@@ -297,79 +597,119 @@ impl Worker {
 
     pub fn unlock_wallet(
         &mut self,
-        wallet_id: &str,
+        wallet_id: &model::WalletId,
         password: &[u8],
+        session_id: Option<types::SessionId>,
     ) -> Result<types::UnlockedSessionWallet> {
+        let _lock = self.wallet_locks.shared(wallet_id, "unlock_wallet")?;
         let (salt, iv) = self
             .wallets
             .wallet_salt_and_iv(wallet_id)
             .map_err(|err| match err {
                 repository::Error::Db(db::Error::DbKeyNotFound { .. })
                 | repository::Error::WalletNotFound => Error::WalletNotFound,
-                err => Error::Repository(err),
+                err => corrupted_wallet_error(err),
             })?;
         let key = crypto::key_from_password(password, &salt, self.params.db_hash_iterations);
-        let session_id: types::SessionId = From::from(crypto::gen_session_id(
-            &mut self.rng,
-            &self.params.id_hash_function,
-            &key,
-            &salt,
-            self.params.id_hash_iterations,
-        ));
+        let session_id: types::SessionId = match session_id {
+            Some(session_id) => session_id,
+            None => From::from(crypto::gen_session_id(
+                &mut self.rng,
+                &self.params.id_hash_function,
+                &key,
+                &salt,
+                self.params.id_hash_iterations,
+            )),
+        };
         let prefix = wallet_id.as_bytes().to_vec();
         let wallet_db = db::EncryptedDb::new(self.db.clone(), prefix, key, iv);
+        let unlocked = open_wallet(wallet_db, wallet_id, session_id, self.params.clone())?;
+        unlocked
+            .wallet
+            .set_sync_target_epoch(self.wallets.sync_target_epoch(wallet_id)?)?;
 
-        // Check if password-derived key is able to read the special stored value
-        wallet_db
-            .get(&constants::ENCRYPTION_CHECK_KEY)
+        if self.params.check_integrity_on_unlock {
+            repair_on_unlock(&unlocked.wallet, wallet_id);
+        }
+        // Deliveries left in the outbox while the wallet was locked are posted now
+        self.webhooks.do_send(webhooks::Register {
+            outbox: unlocked.wallet.webhook_outbox().clone(),
+        });
+
+        Ok(unlocked)
+    }
+
+    /// Check that a password is the one of a wallet, without unlocking it.
+    pub fn check_password(&self, wallet_id: &model::WalletId, password: &[u8]) -> Result<()> {
+        let (salt, iv) = self
+            .wallets
+            .wallet_salt_and_iv(wallet_id)
             .map_err(|err| match err {
-                db::Error::DbKeyNotFound { .. } => Error::WrongPassword,
-                err => Error::Db(err),
+                repository::Error::Db(db::Error::DbKeyNotFound { .. })
+                | repository::Error::WalletNotFound => Error::WalletNotFound,
+                err => corrupted_wallet_error(err),
             })?;
+        let key = crypto::key_from_password(password, &salt, self.params.db_hash_iterations);
+        let prefix = wallet_id.as_bytes().to_vec();
+        let wallet_db = db::EncryptedDb::new(self.db.clone(), prefix, key, iv);
 
-        let wallet = Arc::new(repository::Wallet::unlock(
-            wallet_id,
-            session_id.clone(),
-            wallet_db,
-            self.params.clone(),
-        )?);
-        let data = wallet.public_data()?;
-
-        Ok(types::UnlockedSessionWallet {
-            wallet,
-            data,
-            session_id,
-        })
+        match wallet_db.get(&constants::ENCRYPTION_CHECK_KEY) {
+            Ok(()) => Ok(()),
+            Err(db::Error::DbKeyNotFound { .. }) => Err(Error::WrongPassword),
+            Err(err) => Err(corrupted_wallet_error(err.into())),
+        }
     }
 
-    /// Generate a wallet's address specifying if it should be external or internal
+    /// Generate a wallet's address specifying if it should be external or internal, along with a
+    /// warning if an external address is beyond the gap limit. The client is notified about the
+    /// first address beyond the limit.
     pub fn gen_address(
         &mut self,
         wallet: &types::Wallet,
         external: bool,
         label: Option<String>,
-    ) -> Result<Arc<model::Address>> {
-        let address = if external {
-            wallet.gen_external_address(label)?
-        } else {
-            wallet.gen_internal_address(label, false)?
-        };
+        sink: types::DynamicSink,
+    ) -> Result<(Arc<model::Address>, Option<model::AddressGapWarning>)> {
+        if !external {
+            return Ok((wallet.gen_internal_address(label, false)?, None));
+        }
 
-        Ok(address)
+        let (address, warning) = wallet.gen_external_address_within_gap(label)?;
+        let events: Vec<_> = wallet
+            .take_address_gap_warnings()?
+            .into_iter()
+            .map(types::Event::AddressGapExceeded)
+            .collect();
+        if !events.is_empty() {
+            if let Err(e) = self.notify_client(wallet, sink, Some(events)) {
+                log::error!(
+                    "Failed to notify the client of wallet {} about the address gap: {}",
+                    wallet.id,
+                    e
+                );
+            }
+        }
+
+        Ok((address, warning))
     }
 
+    #[allow(clippy::too_many_arguments)]
     pub fn addresses(
         &mut self,
         wallet: &types::Wallet,
         offset: u32,
         limit: u32,
         external: bool,
+        sort: model::AddressesSort,
+        order: model::SortOrder,
+        cursor: Option<model::AddressesCursor>,
     ) -> Result<model::Addresses> {
-        let addresses = if external {
-            wallet.external_addresses(offset, limit)?
+        let keychain = if external {
+            constants::EXTERNAL_KEYCHAIN
         } else {
-            wallet.internal_addresses(offset, limit)?
+            constants::INTERNAL_KEYCHAIN
         };
+        let addresses = wallet.addresses(keychain, sort, order, cursor, offset, limit)?;
 
         Ok(addresses)
     }
@@ -380,23 +720,349 @@ impl Worker {
         Ok(balance)
     }
 
+    pub fn balance_history(
+        &mut self,
+        wallet: &types::Wallet,
+        from: Option<u32>,
+        to: Option<u32>,
+        resolution: model::BalanceHistoryResolution,
+    ) -> Result<Vec<model::BalanceHistoryEntry>> {
+        let history = wallet.balance_history(from, to, resolution)?;
+
+        Ok(history)
+    }
+
+    /// Prune the metadata of the old movements of a wallet according to its retention policy,
+    /// replacing the policy first if one is given. The pruned details are archived to the
+    /// `prune_archive_path` directory.
+    pub fn prune_wallet_data(
+        &self,
+        wallet: &types::Wallet,
+        policy: Option<model::RetentionPolicy>,
+    ) -> Result<model::PruneReport> {
+        if let Some(policy) = policy {
+            log::info!(
+                "Updating the retention policy of wallet {}: {:?}",
+                wallet.id,
+                policy
+            );
+            wallet.set_retention_policy(policy)?;
+        }
+        let report = wallet.prune_movements(&self.params.prune_archive_path)?;
+
+        Ok(report)
+    }
+
+    /// Check the integrity of the records of a wallet, repairing the ones that can be derived
+    /// from others if `repair` is true. Clients are notified of the progress of the check.
+    pub fn check_integrity(
+        &self,
+        wallet: &types::Wallet,
+        repair: bool,
+        sink: DynamicSink,
+    ) -> Result<model::IntegrityReport> {
+        let report = wallet.check_integrity(repair, |checked, total| {
+            let events = Some(vec![types::Event::IntegrityCheckProgress(checked, total)]);
+            self.notify_client(wallet, sink.clone(), events).ok();
+        })?;
+
+        Ok(report)
+    }
+
+    /// Import the labels and account tags of the external addresses of a wallet.
+    pub fn import_address_metadata(
+        &self,
+        wallet: &types::Wallet,
+        entries: &[AddressMetadata],
+        sink: DynamicSink,
+    ) -> Result<model::AddressMetadataImport> {
+        let report = wallet.import_address_metadata(entries, |imported, total| {
+            let events = Some(vec![types::Event::AddressMetadataImportProgress(
+                imported, total,
+            )]);
+            self.notify_client(wallet, sink.clone(), events).ok();
+        })?;
+        log::info!(
+            "Imported the metadata of {} addresses of wallet {} ({} unchanged)",
+            report.imported,
+            wallet.id,
+            report.unchanged
+        );
+
+        Ok(report)
+    }
+
+    /// Synchronization, integrity and address index usage of the given wallets and stats of the
+    /// database, for a diagnostics bundle. The sensitive data of one of the wallets is included if
+    /// its id and the right password are given.
+    pub fn diagnostics(
+        &self,
+        wallets: &[(model::WalletId, types::SessionWallet)],
+        sensitive: Option<(&model::WalletId, &[u8])>,
+    ) -> Result<diagnostics::WorkerDiagnostics> {
+        let sensitive = match sensitive {
+            Some((wallet_id, password)) => {
+                let wallet = wallets
+                    .iter()
+                    .find(|(id, _)| id == wallet_id)
+                    .map(|(_, wallet)| wallet)
+                    .ok_or(Error::WalletNotFound)?;
+                self.check_password(wallet_id, password)?;
+                let data = wallet.public_data()?;
+
+                Some(diagnostics::SensitiveDiagnostics {
+                    wallet_id: wallet_id.clone(),
+                    name: data.name,
+                    description: data.description,
+                    balance: wallet.balance()?,
+                    addresses: wallet
+                        .external_addresses(0, constants::DIAGNOSTICS_SENSITIVE_ITEMS)?,
+                    transactions: wallet.transactions(0, constants::DIAGNOSTICS_SENSITIVE_ITEMS)?,
+                })
+            }
+            None => None,
+        };
+
+        let mut sorted: Vec<_> = wallets.iter().collect();
+        sorted.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let mut diagnosed = Vec::with_capacity(sorted.len());
+        for (_, wallet) in sorted {
+            let report = wallet.check_integrity(false, |_, _| {})?;
+            diagnosed.push(diagnostics::WalletDiagnostics {
+                sync: wallet.sync_watermark()?,
+                integrity: diagnostics::IntegritySummary::from(&report),
+                address_index: wallet.address_index_usage()?,
+            });
+        }
+
+        let property = |name: &str| self.db.property_int_value(name).ok().flatten();
+        let storage = diagnostics::StorageDiagnostics {
+            wallets: self.wallets.infos(true, 0, 0)?.total,
+            estimated_keys: property("rocksdb.estimate-num-keys"),
+            live_data_bytes: property("rocksdb.estimate-live-data-size"),
+            sst_files_bytes: property("rocksdb.total-sst-files-size"),
+            latency: metrics::StorageLatency::default(),
+            queues: metrics::WorkQueues::default(),
+        };
+
+        Ok(diagnostics::WorkerDiagnostics {
+            wallets: diagnosed,
+            storage,
+            sensitive,
+        })
+    }
+
     pub fn get_utxo_info(&mut self, wallet: &types::Wallet) -> Result<model::UtxoSet> {
         let utxo_info = wallet.get_utxo_info()?;
 
         Ok(utxo_info)
     }
 
+    /// Ids of the given wallets that generated the address.
+    pub fn wallets_with_address(
+        &mut self,
+        wallets: &[(model::WalletId, types::SessionWallet)],
+        pkh: &PublicKeyHash,
+    ) -> Result<Vec<model::WalletId>> {
+        let mut ids = vec![];
+        for (id, wallet) in wallets {
+            if wallet.contains_address(pkh)? {
+                ids.push(id.clone());
+            }
+        }
+        ids.sort();
+
+        Ok(ids)
+    }
+
+    /// Hashes of the transactions of each of the given wallets included in each of `blocks`, for
+    /// the wallets and blocks that have any.
+    pub fn transactions_in_blocks(
+        &mut self,
+        wallets: &[(model::WalletId, types::SessionWallet)],
+        blocks: &[model::Beacon],
+    ) -> Result<Vec<(model::WalletId, HashMap<Hash, Vec<String>>)>> {
+        let mut transactions = vec![];
+        for (id, wallet) in wallets {
+            let wallet_transactions = wallet.transactions_in_blocks(blocks)?;
+            if !wallet_transactions.is_empty() {
+                transactions.push((id.clone(), wallet_transactions));
+            }
+        }
+        transactions.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(transactions)
+    }
+
+    /// Output pointers of the inputs and outputs of a block that belong to each of the given
+    /// wallets, for the wallets that own any.
+    pub fn own_block_entries(
+        &mut self,
+        wallets: &[(model::WalletId, types::SessionWallet)],
+        block: &Block,
+    ) -> Result<Vec<(model::WalletId, HashSet<OutputPointer>)>> {
+        let block_txns: Vec<Transaction> = balance_txns(block).collect();
+
+        let mut entries = vec![];
+        for (id, wallet) in wallets {
+            let wallet_entries = wallet.own_entries(&block_txns)?;
+            if !wallet_entries.is_empty() {
+                entries.push((id.clone(), wallet_entries));
+            }
+        }
+        entries.sort_by(|(a, _), (b, _)| a.cmp(b));
+
+        Ok(entries)
+    }
+
     pub fn transactions(
         &mut self,
         wallet: &types::Wallet,
         offset: u32,
         limit: u32,
+        sort: model::MovementsSort,
+        order: model::SortOrder,
+        cursor: Option<model::MovementsCursor>,
     ) -> Result<model::WalletTransactions> {
-        let transactions = wallet.transactions(offset, limit)?;
+        let transactions = wallet.sorted_transactions(sort, order, cursor, offset, limit)?;
 
         Ok(transactions)
     }
 
+    /// Next batch of the stream of the persisted movements of a wallet.
+    pub fn transactions_stream(
+        &mut self,
+        wallet: &types::Wallet,
+        cursor: Option<model::TransactionsCursor>,
+        batch_size: u32,
+    ) -> Result<model::TransactionsBatch> {
+        let batch = wallet.transactions_stream(cursor, batch_size)?;
+
+        Ok(batch)
+    }
+
+    /// Replace the spending policy of a wallet, if the password of the wallet is the right one.
+    pub fn update_wallet_policy(
+        &self,
+        wallet_id: &model::WalletId,
+        wallet: &types::Wallet,
+        password: &[u8],
+        policy: model::WalletPolicy,
+    ) -> Result<()> {
+        self.check_password(wallet_id, password)?;
+        log::info!(
+            "Updating the spending policy of wallet {}: {:?}",
+            wallet_id,
+            policy
+        );
+        wallet.set_policy(policy)?;
+
+        Ok(())
+    }
+
+    /// Replace which transactions nodes can have a wallet sign on their behalf, if the password of
+    /// the wallet is the right one.
+    pub fn update_witness_signing_policy(
+        &self,
+        wallet_id: &model::WalletId,
+        wallet: &types::Wallet,
+        password: &[u8],
+        policy: model::WitnessSigningPolicy,
+    ) -> Result<()> {
+        self.check_password(wallet_id, password)?;
+        log::info!(
+            "Updating the witness signing policy of wallet {}: {:?}",
+            wallet_id,
+            policy
+        );
+        wallet.set_witness_signing_policy(policy)?;
+
+        Ok(())
+    }
+
+    /// Replace the webhook of a wallet, or remove it if `webhook` is `None`, if the password of the
+    /// wallet is the right one.
+    pub fn update_wallet_webhook(
+        &self,
+        wallet_id: &model::WalletId,
+        wallet: &types::Wallet,
+        password: &[u8],
+        webhook: Option<model::WebhookConfig>,
+    ) -> Result<()> {
+        self.check_password(wallet_id, password)?;
+        if let Some(webhook) = &webhook {
+            let uri = webhook
+                .url
+                .parse::<witnet_net::Uri>()
+                .map_err(|e| Error::InvalidWebhookUrl(e.to_string()))?;
+            if !matches!(uri.scheme_str(), Some("http") | Some("https")) {
+                return Err(Error::InvalidWebhookUrl(
+                    "only http and https URLs are supported".to_string(),
+                ));
+            }
+        }
+        log::info!(
+            "Updating the webhook of wallet {}: {:?}",
+            wallet_id,
+            webhook
+        );
+        wallet.set_webhook(webhook)?;
+
+        Ok(())
+    }
+
+    /// Check how the spending policy of a wallet applies to sending a transaction now.
+    pub fn spending_check(
+        &self,
+        wallet: &types::Wallet,
+        transaction: &Transaction,
+    ) -> Result<model::SpendingCheck> {
+        let now = u64::try_from(get_timestamp()).unwrap_or_default();
+
+        Ok(wallet.spending_check(transaction, now)?)
+    }
+
+    pub fn create_payment_request(
+        &self,
+        wallet: &types::Wallet,
+        amount: u64,
+        memo: Option<String>,
+        expires_in: Option<u64>,
+    ) -> Result<model::PaymentRequest> {
+        let now = u64::try_from(get_timestamp()).unwrap_or_default();
+        let payment_request = wallet.create_payment_request(amount, memo, expires_in, now)?;
+
+        Ok(payment_request)
+    }
+
+    pub fn payment_requests(
+        &self,
+        wallet: &types::Wallet,
+        statuses: &[model::PaymentRequestStatus],
+        offset: u32,
+        limit: u32,
+    ) -> Result<model::PaymentRequests> {
+        let payment_requests = wallet.payment_requests(statuses, offset, limit)?;
+
+        Ok(payment_requests)
+    }
+
+    /// Expire the payment requests of a wallet that were not paid in time, and notify clients.
+    pub fn expire_payment_requests(
+        &self,
+        wallet: &types::SessionWallet,
+        sink: types::DynamicSink,
+    ) -> Result<()> {
+        let now = u64::try_from(get_timestamp()).unwrap_or_default();
+        wallet.expire_payment_requests(now)?;
+        let events = payment_request_events(wallet)?;
+        if !events.is_empty() {
+            self.notify_client(wallet, sink, Some(events)).ok();
+        }
+
+        Ok(())
+    }
+
     pub fn get(&self, wallet: &types::Wallet, key: &str) -> Result<Option<String>> {
         let value = wallet.kv_get(key)?;
 
@@ -415,7 +1081,7 @@ impl Worker {
         block_info: &model::Beacon,
         txns: impl Iterator<Item = Transaction> + Clone,
         confirmed: bool,
-    ) -> Result<Vec<model::BalanceMovement>> {
+    ) -> Result<Option<Vec<model::BalanceMovement>>> {
         // If syncing, then re-generate transient addresses if needed
         // Note: this code can be further refactored by only updating the transient addresses
         wallet._sync_address_generation(txns.clone())?;
@@ -431,7 +1097,7 @@ impl Worker {
         // Extending transactions with metadata queried from the node
         let extended_txns = self.extend_transactions_data(filtered_txns)?;
         let balance_movements =
-            wallet.index_block_transactions(block_info, &extended_txns, confirmed)?;
+            wallet.index_new_block_transactions(block_info, &extended_txns, confirmed)?;
 
         Ok(balance_movements)
     }
@@ -442,20 +1108,30 @@ impl Worker {
         sink: types::DynamicSink,
         events: Option<Vec<types::Event>>,
     ) -> Result<()> {
-        // Make a grab of the sink, cloning immediately so as to drop the read lock ASAP.
-        let sink = sink
+        // Webhooks get their events whether or not a client is subscribed
+        if let Some(events) = &events {
+            if let Err(e) = self.queue_webhook_events(wallet, events) {
+                log::error!(
+                    "Failed to queue the events of wallet {} for its webhook: {}",
+                    wallet.id,
+                    e
+                );
+            }
+        }
+
+        let wanted = sink
             .read()
             .expect("Read locks should only fail if poisoned")
-            .clone();
+            .wants_notifications();
 
-        if let Some(sink) = sink.as_ref() {
+        if wanted {
             log::debug!("Notifying status of wallet {}", wallet.id);
 
             let balance = wallet.balance()?;
             let wallet_data = wallet.public_data()?;
             let client = self.node.get_client();
             let f = async {
-                let payload = json!({
+                json!({
                     "events": events.unwrap_or_default(),
                     "status": {
                         "account": {
@@ -473,12 +1149,16 @@ impl Worker {
                             "last_sync": wallet_data.last_sync,
                         },
                     },
-                });
-                let send = sink.notify(rpc::Params::Array(vec![payload]));
-                Compat01As03::new(send).await
+                })
             };
-
-            futures::executor::block_on(f)?;
+            let payload = futures::executor::block_on(f);
+
+            // Queueing never blocks: if the client is not reading, the overflow policy of its
+            // subscription applies. The notification is also kept for replaying it to a client
+            // that resubscribes after a disconnection.
+            sink.write()
+                .expect("Write locks should only fail if poisoned")
+                .notify(payload)?;
         } else {
             log::debug!("No sinks need to be notified for wallet {}", wallet.id);
         }
@@ -486,6 +1166,43 @@ impl Worker {
         Ok(())
     }
 
+    /// Sign the events that the webhook of a wallet is subscribed to, and have the actor that posts
+    /// them persist them in the outbox of the wallet. Posting them never blocks the processing of
+    /// blocks.
+    fn queue_webhook_events(&self, wallet: &types::Wallet, events: &[types::Event]) -> Result<()> {
+        let webhook = match wallet.webhook()? {
+            Some(webhook) => webhook,
+            None => return Ok(()),
+        };
+        let events: Vec<&types::Event> = events
+            .iter()
+            .filter(|event| {
+                event
+                    .webhook_topic()
+                    .map_or(false, |topic| webhook.wants(topic))
+            })
+            .collect();
+        if events.is_empty() {
+            return Ok(());
+        }
+
+        let payload = json!({
+            "wallet_id": wallet.id,
+            "events": events,
+        })
+        .to_string();
+        let signature = crypto::webhook_signature(webhook.secret.as_bytes(), payload.as_bytes());
+        // The events are persisted before the block is considered processed
+        futures::executor::block_on(self.webhooks.send(webhooks::Enqueue {
+            outbox: wallet.webhook_outbox().clone(),
+            url: webhook.url,
+            payload,
+            signature,
+        }))??;
+
+        Ok(())
+    }
+
     pub fn create_vtt(
         &self,
         wallet: &types::Wallet,
@@ -494,6 +1211,17 @@ impl Worker {
         Ok(wallet.create_vtt(params)?)
     }
 
+    pub fn bump_fee(
+        &self,
+        wallet: &types::Wallet,
+        transaction_hash: &Hash,
+        fee: Fee,
+        add_inputs: bool,
+        allow_low_fee: bool,
+    ) -> Result<(model::ExtendedTransaction, AbsoluteFee)> {
+        Ok(wallet.bump_fee(transaction_hash, fee, add_inputs, allow_low_fee)?)
+    }
+
     pub fn get_transaction(
         &self,
         wallet: &types::Wallet,
@@ -516,13 +1244,24 @@ impl Worker {
         &self,
         wallet: &types::Wallet,
         data: &str,
+        domain: Option<model::SigningDomain>,
         extended_pk: bool,
-    ) -> Result<model::ExtendedKeyedSignature> {
-        let signed_data = wallet.sign_data(data, extended_pk)?;
+    ) -> Result<model::SignedData> {
+        let signed_data = wallet.sign_data(data, domain, extended_pk)?;
 
         Ok(signed_data)
     }
 
+    pub fn sign_witness_transaction(
+        &self,
+        wallet: &types::Wallet,
+        body: &WitnessTransactionBody,
+    ) -> Result<(KeyedSignature, model::WitnessSigningAuditEntry)> {
+        let signed = wallet.sign_witness_transaction(body)?;
+
+        Ok(signed)
+    }
+
     /// Extend transactions with metadata requested to the node through JSON-RPC queries.
     pub fn extend_transactions_data(
         &self,
@@ -669,7 +1408,7 @@ impl Worker {
             .timeout(self.node.requests_timeout)
             .params(params)
             .expect("params failed serialization");
-        let res = self.node.get_client().actor.send(req).flatten_err().await;
+        let res = self.node.get_client().send(req).flatten_err().await;
 
         match res {
             Ok(json) => serde_json::from_value::<types::GetTransactionResponse>(json)
@@ -695,11 +1434,16 @@ impl Worker {
             .timeout(self.node.requests_timeout)
             .params(params)
             .expect("params failed serialization");
-        let res = self.node.get_client().actor.send(req).flatten_err().await;
+        let res = self.node.get_client().send(req).flatten_err().await;
 
         match res {
             Ok(json) => {
                 log::trace!("dataRequestReport request result: {:?}", json);
+                self.node
+                    .get_client()
+                    .schema
+                    .validate(&schema::DATA_REQUEST_REPORT, &json)
+                    .map_err(node_error)?;
                 serde_json::from_value::<DataRequestInfo>(json).map_err(node_error)
             }
             Err(err) => {
@@ -712,10 +1456,22 @@ impl Worker {
     /// Sync wrapper in order to clear transient addresses in case of errors
     pub fn sync(
         &self,
-        wallet_id: &str,
+        wallet_id: &model::WalletId,
         wallet: &types::SessionWallet,
         sink: types::DynamicSink,
+        deadline: Deadline,
     ) -> Result<()> {
+        let _lock = self.wallet_locks.shared(wallet_id, "sync")?;
+        // Wallets waiting for their seed backup to be confirmed are synchronized after confirmation
+        if wallet.is_pending_confirmation()? {
+            log::debug!(
+                "Not synchronizing wallet {} until its seed backup is confirmed",
+                wallet_id
+            );
+
+            return Ok(());
+        }
+
         let sync_start = wallet.lock_and_read_state(|state| state.last_sync.checkpoint)?;
 
         // Generate transient addresses for sync purposes
@@ -724,7 +1480,7 @@ impl Worker {
             self.params.sync_address_batch_length,
         )?;
 
-        let sync_result = self.sync_inner(wallet_id, wallet, sink.clone());
+        let sync_result = self.sync_inner(wallet_id, wallet, sink.clone(), deadline);
 
         // Clear transient created addresses
         wallet.clear_transient_addresses()?;
@@ -750,12 +1506,16 @@ impl Worker {
     }
 
     /// Try to synchronize the information for a wallet to whatever the world state is in a Witnet
-    /// chain.
+    /// chain, or up to the epoch its synchronization is pinned to.
+    ///
+    /// The deadline is checked after processing every block, so the blocks processed before it
+    /// expires are kept.
     pub fn sync_inner(
         &self,
-        wallet_id: &str,
+        wallet_id: &model::WalletId,
         wallet: &types::SessionWallet,
         sink: types::DynamicSink,
+        deadline: Deadline,
     ) -> Result<()> {
         let limit = i64::from(self.params.node_sync_batch_size);
 
@@ -786,7 +1546,13 @@ impl Worker {
             let block_arc = Arc::new(block);
 
             // Process genesis block (transactions indexed as confirmed)
-            self.handle_block(block_arc, true, wallet.clone(), DynamicSink::default())?;
+            self.handle_block(
+                block_arc,
+                None,
+                true,
+                wallet.clone(),
+                DynamicSink::default(),
+            )?;
         }
 
         // Query the node for the latest block in the chain
@@ -826,6 +1592,7 @@ impl Worker {
             tip.hash_prev_block
         );
 
+        let mut reached_target = false;
         loop {
             // Ask a Witnet node for epochs and ids for all the blocks that happened AFTER the last
             // one we processed — hence `since_beacon.checkpoint + 1`
@@ -838,7 +1605,12 @@ impl Worker {
             log::debug!("[SU] Received chain: {:?}", block_chain);
 
             // For each of the blocks we have been informed about, ask a Witnet node for its contents
-            for ChainEntry(_epoch, id) in block_chain {
+            for ChainEntry(epoch, id) in block_chain {
+                if wallet.is_beyond_sync_target(epoch)? {
+                    reached_target = true;
+                    break;
+                }
+
                 let get_block_future = self.get_block(id.clone());
                 let (block, confirmed) = futures::executor::block_on(get_block_future)?;
 
@@ -848,11 +1620,19 @@ impl Worker {
                 // Process each block and update latest beacon
                 self.handle_block(
                     block_arc.clone(),
+                    None,
                     confirmed,
                     wallet.clone(),
                     DynamicSink::default(),
                 )?;
                 latest_beacon = block_arc.block_header.beacon;
+
+                deadline.check("sync", || {
+                    format!(
+                        "synced up to #{} of #{}",
+                        latest_beacon.checkpoint, tip.checkpoint
+                    )
+                })?;
             }
 
             let events = Some(vec![types::Event::SyncProgress(
@@ -865,6 +1645,7 @@ impl Worker {
             // Keep asking for new batches of blocks until we get less than expected, which signals
             // that there are no more blocks to process.
             if batch_size < i128::from(limit)
+                || reached_target
                 || wallet.lock_and_read_state(|state| state.stop_syncing)?
             {
                 break;
@@ -913,7 +1694,7 @@ impl Worker {
             .timeout(self.node.requests_timeout)
             .params(params)
             .expect("params failed serialization");
-        let res = self.node.get_client().actor.send(req).flatten_err().await;
+        let res = self.node.get_client().send(req).flatten_err().await;
 
         match res {
             Ok(json) => {
@@ -940,11 +1721,16 @@ impl Worker {
             .timeout(self.node.requests_timeout)
             .params(params)
             .expect("params failed serialization");
-        let res = self.node.get_client().actor.send(req).flatten_err().await;
+        let res = self.node.get_client().send(req).flatten_err().await;
 
         match res {
             Ok(json) => {
                 log::trace!("getBlock request result: {:?}", json);
+                self.node
+                    .get_client()
+                    .schema
+                    .validate(&schema::GET_BLOCK, &json)
+                    .map_err(node_error)?;
                 // Set confirmed to true if the result contains {"confirmed": true}
                 let mut confirmed = false;
                 if let Some(obj) = json.as_object() {
@@ -968,10 +1754,52 @@ impl Worker {
     pub fn handle_block(
         &self,
         block: Arc<Block>,
+        scan: Option<&repository::BlockScan>,
         confirmed: bool,
         wallet: types::SessionWallet,
         sink: types::DynamicSink,
     ) -> Result<()> {
+        if wallet.is_pending_confirmation()? {
+            return Ok(());
+        }
+
+        // Blocks can be delivered again by the node, e.g. when replaying notifications after a
+        // reconnection, and they must not be applied twice
+        let block_info = model::Beacon {
+            block_hash: block.hash(),
+            epoch: block.block_header.beacon.checkpoint,
+        };
+        if wallet.is_block_applied(&block_info)? {
+            self.skip_applied_block(&wallet, &block_info);
+
+            return Ok(());
+        }
+
+        // Blocks beyond the epoch the wallet is pinned to are processed by the synchronization
+        // that follows unpinning it
+        let epoch = block.block_header.beacon.checkpoint;
+        if wallet.is_beyond_sync_target(epoch)? {
+            log::debug!(
+                "Ignoring block #{} because the synchronization of wallet {} is pinned to an earlier epoch",
+                epoch,
+                wallet.id,
+            );
+
+            return Ok(());
+        }
+
+        // How the block connects to our chain is checked below, so the previous beacon is not
+        // needed here
+        if self.params.verify_block_headers {
+            verify_block_header(
+                &block.block_header,
+                &block.block_sig,
+                &BlockTransactionHashes::from(&block.txns),
+                None,
+            )
+            .map_err(block_error)?;
+        }
+
         let block_beacon = block.block_header.beacon;
         let wallet_data = wallet.public_data()?;
         let last_sync = wallet_data.last_sync;
@@ -1030,23 +1858,48 @@ impl Worker {
         }
 
         if needs_indexing {
-            // Index incoming block and its transactions
-            let new_last_sync = self.index_block(block, confirmed, &wallet, sink)?;
-
-            // Update wallet state with the last indexed epoch and block hash
-            wallet.update_sync_state(new_last_sync, confirmed)?;
+            // Index incoming block and its transactions, unless it was applied in the meantime
+            match self.index_block(block, scan, confirmed, &wallet, sink)? {
+                Some(new_last_sync) => {
+                    // Update wallet state with the last indexed epoch and block hash
+                    wallet.update_sync_state(new_last_sync, confirmed)?;
+                }
+                None => self.skip_applied_block(&wallet, &block_info),
+            }
         }
 
         Ok(())
     }
 
+    /// Count a block that was not processed because it had already been applied to a wallet.
+    fn skip_applied_block(&self, wallet: &types::SessionWallet, block_info: &model::Beacon) {
+        log::debug!(
+            "Ignoring block #{} ({}) because it was already applied to wallet {}",
+            block_info.epoch,
+            block_info.block_hash,
+            wallet.id
+        );
+        self.block_processing
+            .lock()
+            .expect("Metrics locks should only fail if poisoned")
+            .wallet_blocks_duplicated += 1;
+    }
+
     /// Handle superblock notification by confirming the transactions of the consolidated blocks
+    ///
+    /// The deadline is checked before every block, and also bounds the synchronization that
+    /// follows if the blocks cannot be consolidated.
     pub fn handle_superblock(
         &self,
-        notification: types::SuperBlockNotification,
+        notification: &types::SuperBlockNotification,
         wallet: types::SessionWallet,
         sink: types::DynamicSink,
+        deadline: Deadline,
     ) -> Result<()> {
+        if wallet.is_pending_confirmation()? {
+            return Ok(());
+        }
+
         log::info!(
             "Superblock #{} notification received. Consolidating {} pending blocks...",
             notification.superblock.index,
@@ -1068,21 +1921,26 @@ impl Worker {
             return Ok(());
         }
 
-        let consolidated = wallet
-            .handle_superblock(&notification.consolidated_block_hashes)
-            .map_err(Error::from);
+        let block_hashes = &notification.consolidated_block_hashes;
+        let mut consolidated = 0;
+        let result = wallet.handle_superblock(block_hashes, |blocks| {
+            consolidated = blocks;
+            deadline.check("consolidate_blocks", || {
+                format!("consolidated {} of {} blocks", blocks, block_hashes.len())
+            })?;
+
+            Ok::<_, Error>(())
+        });
 
-        match consolidated {
+        match result {
             Ok(_) => {
-                // Notify consolidation of the persisted blocks
-                self.notify_client(
-                    &wallet,
-                    sink,
-                    Some(vec![types::Event::BlocksConsolidate(
-                        notification.consolidated_block_hashes,
-                    )]),
-                )
-                .ok();
+                // Notify consolidation of the persisted blocks, and the payment requests paid by
+                // their transactions
+                let mut events = vec![types::Event::BlocksConsolidate(
+                    notification.consolidated_block_hashes.clone(),
+                )];
+                events.extend(payment_request_events(&wallet)?);
+                self.notify_client(&wallet, sink, Some(events)).ok();
             }
             Err(e) => {
                 log::error!(
@@ -1096,23 +1954,26 @@ impl Worker {
                     &wallet,
                     sink.clone(),
                     Some(vec![types::Event::BlocksOrphan(
-                        notification.consolidated_block_hashes,
+                        block_hashes[consolidated..].to_vec(),
                     )]),
                 )
                 .ok();
 
-                self.sync(&wallet.id, &wallet, sink)?
+                self.sync(&wallet.id, &wallet, sink, deadline)?
             }
         }
 
         Ok(())
     }
 
+    /// Notify a node status change, synchronizing the wallet once the node is synced. The
+    /// synchronization stops, keeping its progress, if it does not finish before the deadline.
     pub fn handle_node_status(
         &self,
         status: StateMachine,
         wallet: types::SessionWallet,
         sink: types::DynamicSink,
+        deadline: Deadline,
     ) -> Result<()> {
         log::debug!("The current node status is {:?}", status);
         // Notify about the changed node status.
@@ -1121,19 +1982,22 @@ impl Worker {
 
         if status == StateMachine::Synced && !wallet.is_syncing()? {
             wallet.clear_pending_state().ok();
-            self.sync(&wallet.id, &wallet, sink)?;
+            self.sync(&wallet.id, &wallet, sink, deadline)?;
         }
 
         Ok(())
     }
 
+    /// Index a block and notify about it. If the block comes with its scan, its transactions are
+    /// only walked if the scan finds something that may belong to the wallet.
     pub fn index_block(
         &self,
         block: Arc<Block>,
+        scan: Option<&repository::BlockScan>,
         confirmed: bool,
         wallet: &types::SessionWallet,
         sink: types::DynamicSink,
-    ) -> Result<CheckpointBeacon> {
+    ) -> Result<Option<CheckpointBeacon>> {
         let block_hash = block.hash();
 
         // Immediately update the local reference to the node's last beacon
@@ -1143,49 +2007,58 @@ impl Worker {
         };
         self.node.update_last_beacon(block_own_beacon);
 
-        // Block transactions to be indexed.
-        // Note: reveal transactions do not change wallet balances
-        let vtt_txns = block
-            .txns
-            .value_transfer_txns
-            .iter()
-            .cloned()
-            .map(Transaction::from);
-        let dr_txns = block
-            .txns
-            .data_request_txns
-            .iter()
-            .cloned()
-            .map(Transaction::from);
-        let commit_txns = block
-            .txns
-            .commit_txns
-            .iter()
-            .cloned()
-            .map(Transaction::from);
-        let tally_txns = block.txns.tally_txns.iter().cloned().map(Transaction::from);
-
-        let block_txns = vtt_txns
-            .chain(dr_txns)
-            .chain(commit_txns)
-            .chain(tally_txns)
-            .chain(std::iter::once(Transaction::Mint(block.txns.mint.clone())));
-
         let block_info = model::Beacon {
             block_hash,
             epoch: block.block_header.beacon.checkpoint,
         };
-        let balance_movements =
-            self.index_txns(wallet.as_ref(), &block_info, block_txns, confirmed)?;
+        let walk = match scan {
+            Some(scan) => {
+                let involved = wallet.is_involved_in(scan)?;
+                let mut counters = self
+                    .block_processing
+                    .lock()
+                    .expect("Metrics locks should only fail if poisoned");
+                if involved {
+                    counters.wallet_blocks_walked += 1;
+                } else {
+                    counters.wallet_blocks_skipped += 1;
+                }
+
+                involved
+            }
+            None => true,
+        };
+        let balance_movements = if walk {
+            self.index_txns(
+                wallet.as_ref(),
+                &block_info,
+                balance_txns(&block),
+                confirmed,
+            )?
+        } else {
+            log::debug!(
+                "Block #{} ({}) has no transactions of wallet {}",
+                block_info.epoch,
+                block_info.block_hash,
+                wallet.id
+            );
+            wallet.index_new_block_transactions(&block_info, &[], confirmed)?
+        };
+        let balance_movements = match balance_movements {
+            Some(balance_movements) => balance_movements,
+            None => return Ok(None),
+        };
 
         // Notify about the new block and every single balance movement found within.
         let mut events = vec![types::Event::Block(block_info)];
         for balance_movement in balance_movements {
             events.push(types::Event::Movement(balance_movement));
         }
+        // Payment requests are only updated by confirmed blocks
+        events.extend(payment_request_events(wallet)?);
         self.notify_client(wallet, sink, Some(events)).ok();
 
-        Ok(block_own_beacon)
+        Ok(Some(block_own_beacon))
     }
 
     /// Clear all chain data for a wallet state.
@@ -1196,34 +2069,363 @@ impl Worker {
     /// - Movements
     /// - Addresses and their metadata
     ///
+    /// The synchronization starts again at the birth date of the wallet or at the genesis block,
+    /// depending on `from`.
+    ///
     /// In order to prevent data race conditions, resyncing is not allowed while a sync or resync
     /// process is already in progress. Accordingly, this function returns whether chain data has
     /// been cleared or not.
     pub fn clear_chain_data_and_resync(
         &self,
-        wallet_id: &str,
+        wallet_id: &model::WalletId,
         wallet: types::SessionWallet,
+        from: types::ResyncFrom,
         sink: DynamicSink,
+        deadline: Deadline,
     ) -> Result<bool> {
         // Do not try to clear chain data and resync if a resynchronization is already in progress
         if !wallet.is_syncing()? {
-            wallet.clear_chain_data()?;
+            wallet.clear_chain_data(from)?;
 
-            self.sync(wallet_id, &wallet, sink).map(|_| true)
+            self.sync(wallet_id, &wallet, sink, deadline).map(|_| true)
         } else {
             Ok(false)
         }
     }
 
+    /// Pin the synchronization of a wallet to an epoch, or unpin it if `epoch` is `None`, and
+    /// synchronize it up to its new target.
+    ///
+    /// Blocks beyond the target are not processed, so the movements and balance of the wallet stay
+    /// as of the target until it is unpinned. Pinning it before the last processed block clears
+    /// its chain data and synchronizes it again from its birth date, which cannot be done while it
+    /// is already synchronizing.
+    pub fn pin_sync(
+        &self,
+        wallet_id: &model::WalletId,
+        wallet: types::SessionWallet,
+        epoch: Option<u32>,
+        sink: DynamicSink,
+        deadline: Deadline,
+    ) -> Result<model::SyncWatermark> {
+        let last_sync = wallet.public_data()?.last_sync.checkpoint;
+        let rewind = epoch.map_or(false, |epoch| epoch < last_sync);
+        if rewind && wallet.is_syncing()? {
+            return Err(Error::SyncInProgress);
+        }
+
+        log::info!(
+            "Pinning the synchronization of wallet {} to epoch {:?}",
+            wallet_id,
+            epoch
+        );
+        self.wallets.set_sync_target_epoch(wallet_id, epoch)?;
+        wallet.set_sync_target_epoch(epoch)?;
+
+        if rewind {
+            let resynced = self.clear_chain_data_and_resync(
+                wallet_id,
+                wallet.clone(),
+                types::ResyncFrom::BirthDate,
+                sink,
+                deadline,
+            )?;
+            if !resynced {
+                return Err(Error::SyncInProgress);
+            }
+        } else if !wallet.is_syncing()? {
+            self.sync(wallet_id, &wallet, sink, deadline)?;
+        }
+
+        Ok(wallet.sync_watermark()?)
+    }
+
+    /// Export the master key of a wallet, encrypted with its password, unless the deadline
+    /// expired while the export was waiting for the worker.
     pub fn export_master_key(
         &self,
         wallet: &types::Wallet,
         password: types::Password,
+        deadline: Deadline,
     ) -> Result<String> {
+        deadline.check("export_master_key", || "not started".to_string())?;
+
         wallet.export_master_key(password).map_err(Error::from)
     }
 }
 
+/// Unlock a wallet from its encrypted database, telling a key derived from a wrong password apart
+/// from corrupted wallet data.
+///
+/// Keys are encrypted as well as values, so a wrong key cannot find the encryption check value.
+/// Once the check value is found, the key is the right one, and any record that is missing or
+/// cannot be decrypted or decoded means that the wallet data is corrupted.
+pub fn open_wallet(
+    wallet_db: db::EncryptedDb,
+    wallet_id: &model::WalletId,
+    session_id: types::SessionId,
+    params: params::Params,
+) -> Result<types::UnlockedSessionWallet> {
+    match wallet_db.get(&constants::ENCRYPTION_CHECK_KEY) {
+        Ok(()) => {}
+        Err(db::Error::DbKeyNotFound { .. }) => return Err(Error::WrongPassword),
+        Err(err) => return Err(corrupted_wallet_error(err.into())),
+    }
+
+    let wallet = repository::Wallet::unlock(wallet_id, session_id.clone(), wallet_db, params)
+        .map(Arc::new)
+        .map_err(corrupted_wallet_error)?;
+    let data = wallet.public_data().map_err(corrupted_wallet_error)?;
+
+    Ok(types::UnlockedSessionWallet {
+        wallet,
+        data,
+        session_id,
+    })
+}
+
+/// Check the integrity of a wallet that has just been unlocked and repair whatever can be
+/// derived, logging the findings. A failed check does not prevent using the wallet.
+fn repair_on_unlock(wallet: &types::Wallet, wallet_id: &model::WalletId) {
+    match wallet.check_integrity(true, |checked, total| {
+        log::debug!(
+            "Checked the integrity of {} of {} records of wallet {}",
+            checked,
+            total,
+            wallet_id
+        )
+    }) {
+        Ok(report) => {
+            for finding in &report.findings {
+                log::warn!(
+                    "Integrity issue in wallet {}: {:?} ({:?})",
+                    wallet_id,
+                    finding.issue,
+                    finding.resolution
+                );
+            }
+            if report.needs_resync() {
+                log::warn!(
+                    "Wallet {} needs to be resynchronized to recover missing records",
+                    wallet_id
+                );
+            }
+        }
+        Err(err) => log::error!(
+            "Failed to check the integrity of wallet {}: {}",
+            wallet_id,
+            err
+        ),
+    }
+}
+
+/// Turn errors caused by wallet records that are missing or cannot be decoded into
+/// `Error::CorruptedWallet`, and those of wallets written by newer versions into
+/// `Error::UnsupportedKeySpec` or `Error::UnsupportedSchemaVersion`.
+fn corrupted_wallet_error(err: repository::Error) -> Error {
+    match err {
+        repository::Error::UnsupportedKeySpec(spec) => Error::UnsupportedKeySpec(spec),
+        repository::Error::UnsupportedSchemaVersion { found, supported } => {
+            Error::UnsupportedSchemaVersion { found, supported }
+        }
+        repository::Error::Db(
+            err @ (db::Error::DbKeyNotFound { .. } | db::Error::Bincode(_) | db::Error::Cipher(_)),
+        ) => Error::CorruptedWallet(err.to_string()),
+        err => Error::Repository(err),
+    }
+}
+
+/// Get the responses of all the sources of a data request from the retrieval cache, fetching or
+/// revalidating them as needed.
+///
+/// Cache keys are built from the templates of the sources, never from their substituted secrets.
+fn fetch_sources_with_cache(
+    cache: &repository::RetrievalCache<db::PlainDb>,
+    request: &RADRequest,
+    bypass: bool,
+    witnessing: &WitnessingConfig<witnet_net::Uri>,
+    deadline: Deadline,
+) -> Result<Vec<(String, model::CacheStatus)>> {
+    request
+        .retrieve
+        .iter()
+        .map(|retrieve| {
+            let method = match retrieve.kind {
+                RADType::HttpPost => "POST",
+                _ => "GET",
+            };
+            let key = repository::retrieval_cache::cache_key(method, &retrieve.url, &retrieve.body);
+
+            cache.retrieve(&key, get_timestamp(), bypass, |cached| {
+                deadline::block_on_with_deadline(
+                    deadline,
+                    "run_rad_request",
+                    fetch_source(retrieve, method, cached, &witnessing.secrets),
+                )?
+            })
+        })
+        .collect()
+}
+
+/// Fetch the response of an HTTP source, as a conditional request if there is a cached response.
+///
+/// Responses are held to the same limits as in retrievals run by witnesses.
+async fn fetch_source(
+    retrieve: &RADRetrieve,
+    method: &str,
+    cached: Option<&model::CachedRetrieval>,
+    secrets: &RetrievalSecrets,
+) -> Result<repository::retrieval_cache::Fetched> {
+    let client = WitnetHttpClient::new(None, true).map_err(|e| {
+        Error::Rad(RadError::HttpOther {
+            message: e.to_string(),
+        })
+    })?;
+    // The data request has not been broadcast, so its variables get the same placeholders as in
+    // `try_data_request`. Variables and secrets are only substituted once witnesses do.
+    let active_wips = current_active_wips();
+    let secrets = Some(secrets).filter(|_| active_wips.wip0041());
+    let max_response_size = ConsensusConstants::max_http_response_size_with_wips(&active_wips);
+    let with_variables = |template: &str| {
+        if active_wips.wip0040() {
+            substitute_variables(template, &DataRequestMetadata::simulated())
+        } else {
+            template.to_string()
+        }
+    };
+    let (url, host) = match secrets {
+        Some(secrets) => {
+            substitute_url_secrets(&with_variables(&retrieve.url), secrets).map_err(Error::Rad)?
+        }
+        None => (with_variables(&retrieve.url), None),
+    };
+    let headers = retrieve
+        .headers
+        .iter()
+        .map(|(name, value)| {
+            let value = with_variables(value);
+            match secrets {
+                Some(secrets) => Ok((name, substitute_secrets(&value, secrets, host.as_deref())?)),
+                None => Ok((name, value)),
+            }
+        })
+        .collect::<std::result::Result<Vec<_>, RadError>>()
+        .map_err(Error::Rad)?;
+    let request = WitnetHttpRequest::build(|builder| {
+        let mut builder = builder.method(method).uri(&url);
+        for (name, value) in &headers {
+            builder = builder.header(*name, value);
+        }
+        if let Some(etag) = cached.and_then(|cached| cached.etag.as_ref()) {
+            builder = builder.header("If-None-Match", etag);
+        }
+        if let Some(last_modified) = cached.and_then(|cached| cached.last_modified.as_ref()) {
+            builder = builder.header("If-Modified-Since", last_modified);
+        }
+
+        let body = match retrieve.kind {
+            RADType::HttpPost => WitnetHttpBody::from(retrieve.body.clone()),
+            _ => WitnetHttpBody::empty(),
+        };
+
+        builder.body(body)
+    })
+    .map_err(|e| {
+        Error::Rad(RadError::HttpOther {
+            message: e.to_string(),
+        })
+    })?;
+
+    let response = client
+        .send(request)
+        .await
+        .map_err(|e| {
+            Error::Rad(RadError::HttpOther {
+                message: e.to_string().replace(&url, &retrieve.url),
+            })
+        })?
+        .inner();
+    let status_code = response.status().as_u16();
+
+    if status_code == 304 && cached.is_some() {
+        return Ok(repository::retrieval_cache::Fetched::NotModified);
+    }
+    if !response.status().is_success() {
+        return Err(Error::Rad(RadError::HttpStatus { status_code }));
+    }
+
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from)
+    };
+    let etag = header("ETag");
+    let last_modified = header("Last-Modified");
+
+    let body = read_response(&retrieve.kind, response, max_response_size)
+        .await
+        .map_err(Error::Rad)?;
+
+    Ok(repository::retrieval_cache::Fetched::Modified(
+        model::CachedRetrieval {
+            body: body.text,
+            etag,
+            last_modified,
+            fetched_at: get_timestamp(),
+        },
+    ))
+}
+
+/// Transactions of a block that can change the balance of a wallet, which are all of them but
+/// reveals.
+fn balance_txns(block: &Block) -> impl Iterator<Item = Transaction> + Clone + '_ {
+    let vtt_txns = block
+        .txns
+        .value_transfer_txns
+        .iter()
+        .cloned()
+        .map(Transaction::from);
+    let dr_txns = block
+        .txns
+        .data_request_txns
+        .iter()
+        .cloned()
+        .map(Transaction::from);
+    let commit_txns = block
+        .txns
+        .commit_txns
+        .iter()
+        .cloned()
+        .map(Transaction::from);
+    let tally_txns = block.txns.tally_txns.iter().cloned().map(Transaction::from);
+
+    vtt_txns
+        .chain(dr_txns)
+        .chain(commit_txns)
+        .chain(tally_txns)
+        .chain(std::iter::once(Transaction::Mint(block.txns.mint.clone())))
+}
+
+/// Take the payment requests of a wallet that changed status, and the reuses of their addresses,
+/// and turn them into events.
+fn payment_request_events(wallet: &types::Wallet) -> Result<Vec<types::Event>> {
+    let events = wallet
+        .take_payment_request_updates()?
+        .into_iter()
+        .map(types::Event::PaymentRequest)
+        .chain(
+            wallet
+                .take_address_reuses()?
+                .into_iter()
+                .map(types::Event::AddressReuse),
+        )
+        .collect();
+
+    Ok(events)
+}
+
 fn validate_birth_date(
     birth_date: u32,
     checkpoint_zero_timestamp: i64,
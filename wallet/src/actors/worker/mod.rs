@@ -3,11 +3,13 @@ use std::sync::Arc;
 
 use actix::prelude::*;
 
-use crate::{db, params, repository, types};
+use crate::{actors::Webhooks, db, locks, metrics, params, repository, types};
 
 pub mod error;
 pub mod handlers;
 pub mod methods;
+#[cfg(test)]
+mod tests;
 
 pub use error::*;
 pub use handlers::*;
@@ -17,11 +19,28 @@ pub type Result<T> = result::Result<T, Error>;
 pub struct Worker {
     db: Arc<rocksdb::DB>,
     wallets: Arc<repository::Wallets<db::PlainDb>>,
+    /// Locks of the wallets being operated on, shared by all the threads of the worker.
+    wallet_locks: Arc<locks::WalletLocks>,
+    /// Cache of data source responses for `RunRadRequest`, if enabled.
+    retrieval_cache: Option<Arc<repository::RetrievalCache<db::PlainDb>>>,
     node: params::NodeParams,
     params: params::Params,
+    /// Counters of the processing of notified blocks, shared with the `App` actor.
+    block_processing: metrics::SharedBlockProcessing,
+    /// Actor that persists and posts the events of the wallets to their webhooks.
+    webhooks: Addr<Webhooks>,
     rng: rand::rngs::OsRng,
 }
 
 impl Actor for Worker {
     type Context = SyncContext<Self>;
 }
+
+/// Addresses of the pools of threads of the worker, one for each class of work, which share the
+/// same storage.
+#[derive(Clone)]
+pub struct Workers {
+    pub interactive: Addr<Worker>,
+    pub blocks: Addr<Worker>,
+    pub maintenance: Addr<Worker>,
+}
@@ -0,0 +1,64 @@
+use super::*;
+use crate::{
+    constants, crypto,
+    db::Database as _,
+    model,
+    repository::{factories, keys},
+};
+
+#[test]
+fn test_open_wallet_tells_wrong_password_from_corrupted_data() {
+    let path = std::env::temp_dir().join(format!("witnet-wallet-unlock-{}", std::process::id()));
+    let rocks = Arc::new(rocksdb::DB::open_default(&path).unwrap());
+    let wallet_id: &model::WalletId = &"a-wallet-id".parse().unwrap();
+    let session_id = types::SessionId::from("a-session-id".to_string());
+    let params = factories::default_params();
+
+    // The factory encrypts the wallet with "password"
+    drop(factories::encrypted_wallet(
+        rocks.clone(),
+        wallet_id,
+        session_id.clone(),
+    ));
+    let (salt, iv) = repository::Wallets::new(db::PlainDb::new(rocks.clone()))
+        .wallet_salt_and_iv(wallet_id)
+        .unwrap();
+    let wallet_db = |password: &[u8]| {
+        let key = crypto::key_from_password(password, &salt, params.db_hash_iterations);
+        db::EncryptedDb::new(
+            rocks.clone(),
+            wallet_id.as_bytes().to_vec(),
+            key,
+            iv.clone(),
+        )
+    };
+    let open = |password: &[u8]| {
+        methods::open_wallet(
+            wallet_db(password),
+            wallet_id,
+            session_id.clone(),
+            params.clone(),
+        )
+    };
+    // The check value is stored by the worker when creating wallets
+    wallet_db(b"password")
+        .put(
+            &constants::ENCRYPTION_CHECK_KEY,
+            constants::ENCRYPTION_CHECK_VALUE,
+        )
+        .unwrap();
+
+    assert!(open(b"password").is_ok());
+    assert!(matches!(open(b"wrong"), Err(Error::WrongPassword)));
+
+    // Overwrite the default account with a value that cannot be decoded
+    let account_key: keys::Key<Vec<u8>, ()> =
+        keys::Key::new(keys::wallet_default_account().as_ref().to_vec());
+    wallet_db(b"password").put(&account_key, ()).unwrap();
+
+    assert!(matches!(open(b"password"), Err(Error::CorruptedWallet(_))));
+    assert!(matches!(open(b"wrong"), Err(Error::WrongPassword)));
+
+    drop(rocks);
+    rocksdb::DB::destroy(&rocksdb::Options::default(), &path).unwrap();
+}
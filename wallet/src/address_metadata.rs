@@ -0,0 +1,240 @@
+//! Labels and account tags of the addresses of a wallet, as imported from another system.
+//!
+//! Exchanges that migrate their seed to this wallet keep the labels of their deposit addresses by
+//! importing them along with the index at which each address is derived, its address, or both.
+//! Mappings can be given as a JSON array of entries, or as CSV text with a header naming the
+//! columns, e.g. `index,address,label,account_tag`.
+use failure::Fail;
+use serde::{Deserialize, Serialize};
+
+/// Columns of the CSV mappings, in the same order as the fields of `AddressMetadata`.
+const CSV_COLUMNS: &[&str] = &["index", "address", "label", "account_tag"];
+
+/// Metadata to attach to one of the external addresses of a wallet.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Eq, Serialize)]
+pub struct AddressMetadata {
+    /// Index at which the address is derived in the external keychain of the account
+    #[serde(default)]
+    pub index: Option<u32>,
+    /// The address, which must be the one derived at `index` if both are given
+    #[serde(default)]
+    pub address: Option<String>,
+    #[serde(default)]
+    pub label: Option<String>,
+    /// Account of the other system that the address belongs to, e.g. a customer id
+    #[serde(default)]
+    pub account_tag: Option<String>,
+}
+
+/// A mapping of addresses to their metadata, as sent by clients.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum ImportPayload {
+    Entries(Vec<AddressMetadata>),
+    Csv(String),
+}
+
+/// A mapping that cannot be imported.
+#[derive(Debug, PartialEq, Eq, Fail)]
+pub enum PayloadError {
+    #[fail(display = "line {} of the CSV mapping is invalid: {}", line, reason)]
+    Csv { line: usize, reason: String },
+    #[fail(display = "entry {} has neither an index nor an address", _0)]
+    Unaddressed(usize),
+}
+
+impl ImportPayload {
+    /// The entries of the mapping, in order.
+    pub fn into_entries(self) -> Result<Vec<AddressMetadata>, PayloadError> {
+        let entries = match self {
+            ImportPayload::Entries(entries) => entries,
+            ImportPayload::Csv(text) => parse_csv(&text)?,
+        };
+        if let Some(position) = entries
+            .iter()
+            .position(|entry| entry.index.is_none() && entry.address.is_none())
+        {
+            return Err(PayloadError::Unaddressed(position));
+        }
+
+        Ok(entries)
+    }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<AddressMetadata>, PayloadError> {
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line.trim_end_matches('\r')))
+        .filter(|(_, line)| !line.trim().is_empty());
+
+    let (line, header) = lines.next().ok_or(PayloadError::Csv {
+        line: 1,
+        reason: "missing header".to_string(),
+    })?;
+    let columns = split_csv_line(header)
+        .map_err(|reason| PayloadError::Csv { line, reason })?
+        .into_iter()
+        .map(|name| {
+            let name = name.trim().to_lowercase();
+            CSV_COLUMNS
+                .iter()
+                .position(|column| *column == name)
+                .ok_or_else(|| PayloadError::Csv {
+                    line,
+                    reason: format!("unknown column {}", name),
+                })
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+
+    lines
+        .map(|(line, text)| {
+            let fields =
+                split_csv_line(text).map_err(|reason| PayloadError::Csv { line, reason })?;
+            if fields.len() != columns.len() {
+                return Err(PayloadError::Csv {
+                    line,
+                    reason: format!("expected {} fields, found {}", columns.len(), fields.len()),
+                });
+            }
+
+            let mut entry = AddressMetadata::default();
+            for (column, field) in columns.iter().zip(fields) {
+                // Empty fields leave the value out
+                let value = Some(field).filter(|field| !field.is_empty());
+                match column {
+                    0 => {
+                        entry.index = value
+                            .map(|value| value.trim().parse())
+                            .transpose()
+                            .map_err(|_| PayloadError::Csv {
+                                line,
+                                reason: "the index is not a number".to_string(),
+                            })?
+                    }
+                    1 => entry.address = value.map(|value| value.trim().to_string()),
+                    2 => entry.label = value,
+                    _ => entry.account_tag = value,
+                }
+            }
+
+            Ok(entry)
+        })
+        .collect()
+}
+
+/// Split a line of CSV into its fields. Fields can be quoted with `"` to contain commas, and
+/// quotes are escaped inside them by doubling them.
+fn split_csv_line(line: &str) -> Result<Vec<String>, String> {
+    let mut fields = vec![];
+    let mut field = String::new();
+    let mut chars = line.chars().peekable();
+    let mut quoted = false;
+
+    while let Some(c) = chars.next() {
+        match (c, quoted) {
+            ('"', true) if chars.peek() == Some(&'"') => {
+                chars.next();
+                field.push('"');
+            }
+            ('"', true) => quoted = false,
+            ('"', false) if field.is_empty() => quoted = true,
+            (',', false) => fields.push(std::mem::take(&mut field)),
+            (c, _) => field.push(c),
+        }
+    }
+    if quoted {
+        return Err("unterminated quoted field".to_string());
+    }
+    fields.push(field);
+
+    Ok(fields)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_mappings_are_parsed() {
+        let csv = "Index,Label,Account_Tag\r\n0,\"Deposit, main\",customer-1\r\n\r\n7,,\"say \"\"hi\"\"\"\r\n";
+        let entries = ImportPayload::Csv(csv.to_string()).into_entries().unwrap();
+
+        assert_eq!(
+            entries,
+            vec![
+                AddressMetadata {
+                    index: Some(0),
+                    label: Some("Deposit, main".to_string()),
+                    account_tag: Some("customer-1".to_string()),
+                    ..Default::default()
+                },
+                AddressMetadata {
+                    index: Some(7),
+                    account_tag: Some("say \"hi\"".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_csv_mappings_are_rejected() {
+        let invalid = |csv: &str| ImportPayload::Csv(csv.to_string()).into_entries();
+
+        assert_eq!(
+            invalid("index,colour\n1,red"),
+            Err(PayloadError::Csv {
+                line: 1,
+                reason: "unknown column colour".to_string(),
+            })
+        );
+        assert_eq!(
+            invalid("index,label\n1,a\nx,b"),
+            Err(PayloadError::Csv {
+                line: 3,
+                reason: "the index is not a number".to_string(),
+            })
+        );
+        assert_eq!(
+            invalid("index,label\n1"),
+            Err(PayloadError::Csv {
+                line: 2,
+                reason: "expected 2 fields, found 1".to_string(),
+            })
+        );
+        assert_eq!(
+            invalid("index,label\n1,\"open"),
+            Err(PayloadError::Csv {
+                line: 2,
+                reason: "unterminated quoted field".to_string(),
+            })
+        );
+        assert_eq!(invalid("label\nmain"), Err(PayloadError::Unaddressed(0)));
+    }
+
+    #[test]
+    fn json_mappings_are_parsed() {
+        let payload: ImportPayload = serde_json::from_value(serde_json::json!([
+            { "index": 3, "label": "cold" },
+            { "address": "wit1abc", "account_tag": "customer-2" },
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            payload.into_entries().unwrap(),
+            vec![
+                AddressMetadata {
+                    index: Some(3),
+                    label: Some("cold".to_string()),
+                    ..Default::default()
+                },
+                AddressMetadata {
+                    address: Some("wit1abc".to_string()),
+                    account_tag: Some("customer-2".to_string()),
+                    ..Default::default()
+                },
+            ]
+        );
+    }
+}
@@ -0,0 +1,191 @@
+//! Version of the JSON-RPC API of the wallet, and compatibility with older clients.
+//!
+//! Clients can declare the API version that they were written for when unlocking a wallet. Those
+//! declaring a version older than the configured minimum are told to upgrade, and those declaring
+//! an older version that is still supported get a session in compatibility mode, whose responses
+//! leave out the fields that were added after their version, so that strict clients are not
+//! confused by them. Clients that declare no version get every field.
+//!
+//! Every change to the methods or to the types of their requests and responses must bump
+//! `API_VERSION`, which is guarded by a test that hashes them.
+use serde_json::Value;
+
+/// Current version of the JSON-RPC API.
+pub const API_VERSION: u32 = 4;
+
+/// A field of the responses of a method that was added in a later version of the API.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VersionedField {
+    /// API version that added the field.
+    pub since: u32,
+    pub method: &'static str,
+    pub field: &'static str,
+}
+
+/// Fields of the responses that clients of older API versions do not get in compatibility mode.
+pub const VERSIONED_FIELDS: &[VersionedField] = &[
+    VersionedField {
+        since: 2,
+        method: "generate_address",
+        field: "warning",
+    },
+    VersionedField {
+        since: 2,
+        method: "get_addresses",
+        field: "cursor",
+    },
+    VersionedField {
+        since: 2,
+        method: "get_transactions",
+        field: "cursor",
+    },
+];
+
+/// Whether a session whose client declared `client_api_version` is in compatibility mode.
+pub fn is_compatibility_mode(client_api_version: Option<u32>) -> bool {
+    matches!(client_api_version, Some(version) if version < API_VERSION)
+}
+
+/// Remove from the response to `method` the fields that were added after the API version of the
+/// client, if it declared one.
+pub fn compatible_response(
+    method: &str,
+    client_api_version: Option<u32>,
+    mut response: Value,
+) -> Value {
+    let client_api_version = match client_api_version {
+        Some(version) if is_compatibility_mode(Some(version)) => version,
+        _ => return response,
+    };
+    if let Value::Object(fields) = &mut response {
+        for versioned in VERSIONED_FIELDS
+            .iter()
+            .filter(|versioned| versioned.method == method && versioned.since > client_api_version)
+        {
+            fields.remove(versioned.field);
+        }
+    }
+
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::Path};
+
+    use serde_json::json;
+    use witnet_crypto::hash::calculate_sha256;
+
+    use super::*;
+
+    /// Hash of the API that `API_VERSION` describes.
+    const API_HASH: &str = "5240a3336bf1292e1a038cb54b2652d93b160ec3dfec4b57e9345bcb0ff56f3a";
+
+    /// Lines of a source file that describe the API: those of the `pub struct`, `pub enum` and
+    /// `pub type` items, without comments.
+    fn api_items(source: &str) -> Vec<String> {
+        let mut items = vec![];
+        let mut in_item = false;
+        for line in source.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            if in_item {
+                items.push(line.to_string());
+                in_item = line != "}";
+            } else if line.starts_with("pub struct ")
+                || line.starts_with("pub enum ")
+                || line.starts_with("pub type ")
+            {
+                items.push(line.to_string());
+                in_item = line.ends_with('{');
+            }
+        }
+
+        items
+    }
+
+    /// Description of the API: the methods, the types of their requests and responses, and the
+    /// fields that were added in every version.
+    fn api_description() -> String {
+        let src = Path::new(env!("CARGO_MANIFEST_DIR")).join("src");
+        let mut description = String::new();
+
+        let routes = fs::read_to_string(src.join("actors/app/routes.rs")).unwrap();
+        let methods = &routes[routes.find("fn connect_methods").unwrap()..];
+        description.push_str("== routes\n");
+        for line in methods.lines().map(str::trim) {
+            if !line.is_empty() && !line.starts_with("//") {
+                description.push_str(line);
+                description.push('\n');
+            }
+        }
+
+        let mut handlers: Vec<_> = fs::read_dir(src.join("actors/app/handlers"))
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+            .collect();
+        handlers.sort();
+        let files = handlers
+            .into_iter()
+            .map(|name| format!("actors/app/handlers/{}", name))
+            .chain(std::iter::once("model.rs".to_string()));
+        for file in files {
+            description.push_str(&format!("== {}\n", file));
+            for line in api_items(&fs::read_to_string(src.join(&file)).unwrap()) {
+                description.push_str(&line);
+                description.push('\n');
+            }
+        }
+
+        description.push_str("== versioned fields\n");
+        for versioned in VERSIONED_FIELDS {
+            description.push_str(&format!(
+                "{} {}.{}\n",
+                versioned.since, versioned.method, versioned.field
+            ));
+        }
+
+        description
+    }
+
+    #[test]
+    fn the_api_version_is_bumped_when_the_api_changes() {
+        let hash = hex::encode(calculate_sha256(api_description().as_bytes()));
+
+        assert_eq!(
+            (API_VERSION, hash.as_str()),
+            (4, API_HASH),
+            "The JSON-RPC API changed: bump API_VERSION, list the fields added to responses in \
+             VERSIONED_FIELDS, and update the expected version and API_HASH"
+        );
+    }
+
+    #[test]
+    fn versioned_fields_are_not_newer_than_the_api() {
+        for versioned in VERSIONED_FIELDS {
+            assert!(versioned.since > 1 && versioned.since <= API_VERSION);
+        }
+    }
+
+    #[test]
+    fn newer_fields_are_only_suppressed_in_compatibility_mode() {
+        let response = json!({ "address": "wit1", "path": "m/0", "warning": {} });
+
+        assert_eq!(
+            compatible_response("generate_address", Some(1), response.clone()),
+            json!({ "address": "wit1", "path": "m/0" })
+        );
+        for client_api_version in [None, Some(API_VERSION), Some(API_VERSION + 1)] {
+            assert_eq!(
+                compatible_response("generate_address", client_api_version, response.clone()),
+                response
+            );
+        }
+        // Fields of other methods are left alone
+        assert_eq!(
+            compatible_response("get_balance", Some(1), response.clone()),
+            response
+        );
+    }
+}
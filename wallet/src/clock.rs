@@ -0,0 +1,149 @@
+//! Time source of the timers of the App actor.
+//!
+//! Session expiration and the periodic checks of the node status wait on a `Clock`, which is the
+//! system clock except in tests, where a `VirtualClock` only moves forward when told to, so that
+//! timers fire at known points. Timers that compare wall-clock timestamps, such as the idle lock,
+//! keep using the system clock. Persisted records that expire, such as the responses kept for
+//! idempotency keys, are stamped with `Clock::timestamp` instead.
+use std::{
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use futures::{channel::oneshot, future::BoxFuture, FutureExt};
+use witnet_util::timestamp::get_timestamp;
+
+/// Time source of the timers of the App actor.
+#[derive(Clone, Debug, Default)]
+pub enum Clock {
+    /// Timers wait for real time to pass.
+    #[default]
+    System,
+    /// Timers wait for the virtual clock to be advanced.
+    Virtual(VirtualClock),
+}
+
+impl Clock {
+    /// Future that completes once `duration` has passed according to this clock.
+    pub fn sleep(&self, duration: Duration) -> BoxFuture<'static, ()> {
+        match self {
+            Clock::System => tokio::time::sleep(duration).boxed(),
+            Clock::Virtual(clock) => clock.sleep(duration).boxed(),
+        }
+    }
+
+    /// Current timestamp in seconds according to this clock. Virtual clocks are ahead of the
+    /// system clock by the time they have been advanced.
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            Clock::System => get_timestamp(),
+            Clock::Virtual(clock) => get_timestamp()
+                .saturating_add(i64::try_from(clock.elapsed().as_secs()).unwrap_or(i64::MAX)),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Timers {
+    elapsed: Duration,
+    pending: Vec<(Duration, oneshot::Sender<()>)>,
+}
+
+/// A clock that only moves forward when it is advanced. Clones share the same time.
+#[derive(Clone, Debug, Default)]
+pub struct VirtualClock {
+    timers: Arc<Mutex<Timers>>,
+}
+
+impl VirtualClock {
+    /// Create a clock whose time starts at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Timers> {
+        self.timers
+            .lock()
+            .expect("Clock locks should only fail if poisoned")
+    }
+
+    /// Time the clock has been advanced since it was created.
+    pub fn elapsed(&self) -> Duration {
+        self.lock().elapsed
+    }
+
+    /// Number of timers that have not fired yet.
+    pub fn pending(&self) -> usize {
+        self.lock().pending.len()
+    }
+
+    /// Future that completes once the clock has been advanced by `duration` from now.
+    pub fn sleep(&self, duration: Duration) -> impl std::future::Future<Output = ()> {
+        let (sender, receiver) = oneshot::channel();
+        {
+            let mut timers = self.lock();
+            let deadline = timers.elapsed + duration;
+            if duration == Duration::from_secs(0) {
+                sender.send(()).ok();
+            } else {
+                timers.pending.push((deadline, sender));
+            }
+        }
+
+        // Timers of a dropped clock fire right away rather than never
+        receiver.map(drop)
+    }
+
+    /// Move the clock forward, firing every timer that expires in the meantime, in order.
+    pub fn advance(&self, duration: Duration) {
+        let mut timers = self.lock();
+        timers.elapsed += duration;
+        let now = timers.elapsed;
+
+        let (mut expired, pending): (Vec<_>, Vec<_>) = timers
+            .pending
+            .drain(..)
+            .partition(|(deadline, _)| *deadline <= now);
+        timers.pending = pending;
+        drop(timers);
+
+        expired.sort_by_key(|(deadline, _)| *deadline);
+        for (_, sender) in expired {
+            sender.send(()).ok();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_virtual_timers_fire_when_advanced_past_them() {
+        let clock = VirtualClock::new();
+        let mut short = Box::pin(clock.sleep(Duration::from_secs(10)));
+        let mut long = Box::pin(clock.sleep(Duration::from_secs(60)));
+        assert_eq!(clock.pending(), 2);
+
+        futures::executor::block_on(async {
+            clock.advance(Duration::from_secs(9));
+            assert!(futures::poll!(&mut short).is_pending());
+
+            clock.advance(Duration::from_secs(1));
+            assert!(futures::poll!(&mut short).is_ready());
+            assert!(futures::poll!(&mut long).is_pending());
+
+            // Timers are relative to the time at which they are set
+            let mut later = Box::pin(clock.sleep(Duration::from_secs(10)));
+            clock.advance(Duration::from_secs(15));
+            assert!(futures::poll!(&mut later).is_ready());
+            assert!(futures::poll!(&mut long).is_pending());
+
+            clock.advance(Duration::from_secs(35));
+            assert!(futures::poll!(&mut long).is_ready());
+        });
+
+        assert_eq!(clock.elapsed(), Duration::from_secs(60));
+        assert_eq!(clock.pending(), 0);
+    }
+}
@@ -0,0 +1,173 @@
+//! Selection of inputs that pay for a transaction without leaving any change.
+//!
+//! A transaction without a change output is smaller, and does not tell observers which of its
+//! outputs goes back to the sender. Before the usual selection runs, the wallet looks for a subset
+//! of its outputs whose value is exactly what the transaction needs, or exceeds it by no more than
+//! a tolerance that is then added to the fee. The search is a branch and bound over the outputs
+//! sorted by value, which can take exponential time on unlucky sets, so it gives up after visiting
+//! a bounded number of nodes and the wallet falls back to the usual selection.
+
+/// Result of a search for a selection without change.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Search {
+    /// Indices of the selected candidates, if a selection was found.
+    pub selection: Option<Vec<usize>>,
+    /// Nodes of the search tree that were visited.
+    pub nodes: u32,
+}
+
+/// Look for a subset of `values` that sums up to `target(n)` plus at most `tolerance`, where `n`
+/// is the number of values in the subset. `target` must not decrease as `n` grows, and returns
+/// `None` if a transaction cannot have `n` inputs, e.g. because it would be too heavy.
+///
+/// Subsets that go over the tolerance are not extended, even though adding an input worth less
+/// than the fee it costs could bring them back within it, as such inputs are not worth spending.
+/// At most `budget` nodes are visited.
+pub fn find_changeless<F>(values: &[u64], target: F, tolerance: u64, budget: u32) -> Search
+where
+    F: Fn(usize) -> Option<u64>,
+{
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    // Trying the largest values first finds the selections with fewer inputs first
+    order.sort_by(|a, b| values[*b].cmp(&values[*a]));
+    let sorted: Vec<u64> = order.iter().map(|index| values[*index]).collect();
+
+    // Value of the candidates from each position to the end, to stop looking as soon as they
+    // cannot reach the target anymore
+    let mut remaining = vec![0; sorted.len() + 1];
+    for position in (0..sorted.len()).rev() {
+        remaining[position] = remaining[position + 1].saturating_add(sorted[position]);
+    }
+
+    let mut search = BranchAndBound {
+        sorted: &sorted,
+        remaining: &remaining,
+        target: &target,
+        tolerance,
+        budget,
+        nodes: 0,
+        selected: vec![],
+    };
+    let found = search.extend(0, 0);
+
+    Search {
+        selection: found.then(|| {
+            let mut selection: Vec<usize> = search
+                .selected
+                .iter()
+                .map(|position| order[*position])
+                .collect();
+            selection.sort_unstable();
+
+            selection
+        }),
+        nodes: search.nodes,
+    }
+}
+
+struct BranchAndBound<'a, F> {
+    sorted: &'a [u64],
+    remaining: &'a [u64],
+    target: &'a F,
+    tolerance: u64,
+    budget: u32,
+    nodes: u32,
+    /// Positions in `sorted` of the values in the current subset
+    selected: Vec<usize>,
+}
+
+impl<'a, F> BranchAndBound<'a, F>
+where
+    F: Fn(usize) -> Option<u64>,
+{
+    /// Try to complete the current subset, worth `sum`, with the values from `start` on.
+    fn extend(&mut self, start: usize, sum: u64) -> bool {
+        for position in start..self.sorted.len() {
+            if self.nodes >= self.budget {
+                return false;
+            }
+            // Subsets with the same values as one already tried would fail the same way
+            if position > start && self.sorted[position] == self.sorted[position - 1] {
+                continue;
+            }
+            self.nodes += 1;
+
+            let need = match (self.target)(self.selected.len() + 1) {
+                Some(need) => need,
+                None => return false,
+            };
+            if sum.saturating_add(self.remaining[position]) < need {
+                return false;
+            }
+            let with = sum.saturating_add(self.sorted[position]);
+            if with > need.saturating_add(self.tolerance) {
+                continue;
+            }
+
+            self.selected.push(position);
+            if with >= need || self.extend(position + 1, with) {
+                return true;
+            }
+            self.selected.pop();
+        }
+
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn exact_matches_are_found() {
+        let values = [700, 50, 300, 1_000, 250];
+        let search = find_changeless(&values, |_| Some(1_000), 0, 1_000);
+        assert_eq!(search.selection, Some(vec![3]));
+
+        let search = find_changeless(&values, |_| Some(550), 0, 1_000);
+        assert_eq!(search.selection, Some(vec![2, 4]));
+
+        // The target grows with the fee of every input
+        let search = find_changeless(&values, |inputs| Some(750 + 100 * inputs as u64), 0, 1_000);
+        assert_eq!(search.selection, Some(vec![0, 4]));
+        assert_eq!(
+            find_changeless(&values, |_| Some(850), 0, 1_000).selection,
+            None
+        );
+    }
+
+    #[test]
+    fn the_tolerance_allows_inexact_matches() {
+        let values = [700, 320];
+        assert_eq!(
+            find_changeless(&values, |_| Some(1_000), 0, 1_000).selection,
+            None
+        );
+        assert_eq!(
+            find_changeless(&values, |_| Some(1_000), 20, 1_000).selection,
+            Some(vec![0, 1])
+        );
+    }
+
+    #[test]
+    fn selections_cannot_exceed_the_input_limit() {
+        let values = [500, 500, 500];
+        let two_inputs = |inputs: usize| if inputs <= 2 { Some(1_500) } else { None };
+
+        assert_eq!(
+            find_changeless(&values, two_inputs, 0, 1_000).selection,
+            None
+        );
+    }
+
+    #[test]
+    fn the_search_respects_its_budget() {
+        // Even values can never add up to an odd target, but there is no quick way to tell
+        let values: Vec<u64> = (0..10_000).map(|i| 2 * (1_000 + i % 977)).collect();
+        let search = find_changeless(&values, |_| Some(1_000_001), 0, 5_000);
+
+        assert_eq!(search.selection, None);
+        assert_eq!(search.nodes, 5_000);
+    }
+}
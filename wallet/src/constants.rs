@@ -11,11 +11,11 @@ pub static DEFAULT_PAGINATION_OFFSET: u32 = 0;
 /// Default limit/page-size used when returning paginated results.
 pub static DEFAULT_PAGINATION_LIMIT: u32 = u32::MAX;
 
-/// Purpose section for an account keypath.
-pub static KEYPATH_PURPOSE: u32 = 3;
+/// Default number of movements of every batch of a stream of transactions.
+pub static DEFAULT_TRANSACTIONS_BATCH_SIZE: u32 = 100;
 
-/// Coin-type section for an account keypath.
-pub static KEYPATH_COIN_TYPE: u32 = 4919;
+/// Maximum number of movements of every batch of a stream of transactions.
+pub static MAX_TRANSACTIONS_BATCH_SIZE: u32 = 1_000;
 
 /// The value for the 'change' path in an account keypath intended for
 /// receiving payments.
@@ -31,3 +31,64 @@ pub static ENCRYPTION_CHECK_KEY: Key<&'static str, ()> = Key::new_const("ENC_KEY
 
 /// Special value stored with `ENCRYPTION_CHECK_KEY`.
 pub static ENCRYPTION_CHECK_VALUE: () = ();
+
+/// Maximum number of seconds to wait before trying to unlock a wallet again after giving a wrong
+/// password.
+pub static MAX_UNLOCK_DELAY: u64 = 300;
+
+/// Length of the salt of the seed fingerprints of wallets.
+pub static SEED_FINGERPRINT_SALT_LENGTH: usize = 32;
+
+/// Length in seconds of the rolling window of the daily spend limit of wallets.
+pub static SPEND_LIMIT_WINDOW_SECS: u64 = 24 * 60 * 60;
+
+/// Seconds that a transaction above the confirmation threshold of a wallet waits for
+/// `confirm_send` before it is discarded.
+pub static SEND_CONFIRMATION_TTL_SECS: u64 = 300;
+
+/// Epochs before the last block applied to a wallet for which the hashes of the applied blocks are
+/// remembered, so that blocks delivered again by the node are not applied twice. Older blocks do
+/// not connect to the tip of the wallet anyway.
+pub static APPLIED_BLOCKS_WINDOW: u32 = 256;
+
+/// Transactions signed on behalf of nodes that are kept in the audit log of a wallet.
+pub static WITNESS_SIGNING_AUDIT_LENGTH: usize = 1_000;
+
+/// Blocks of the chain explorer methods that are kept in memory, by their hash.
+pub static EXPLORER_BLOCK_CACHE_SIZE: usize = 100;
+
+/// Environment variable with the key that decrypts the encrypted passwords of the credentials file
+/// used for unlocking wallets at startup.
+pub static CREDENTIALS_KEY_ENV_VAR: &str = "WITNET_WALLET_CREDENTIALS_KEY";
+
+/// Minimum length of the id of the service session, which grants access to the wallets unlocked
+/// at startup to whoever knows it.
+pub static MIN_SERVICE_SESSION_ID_LENGTH: usize = 32;
+
+/// Last requests handled by the JSON-RPC server that are kept for diagnostics bundles.
+pub static REQUEST_LOG_SIZE: usize = 100;
+
+/// Addresses and movements of a wallet that are included in a diagnostics bundle, if sensitive data
+/// is asked for.
+pub static DIAGNOSTICS_SENSITIVE_ITEMS: u32 = 20;
+
+/// Random ids generated for a new wallet before giving up if all of them are already taken.
+pub static MAX_WALLET_ID_ATTEMPTS: usize = 8;
+
+/// Seconds that an operation on a wallet waits for other operations on the same wallet to finish
+/// before failing with `WalletBusy`.
+pub static WALLET_LOCK_TIMEOUT_SECS: u64 = 30;
+
+/// Maximum number of times that the sources of a retrieval benchmark are retrieved.
+pub static MAX_BENCHMARK_ITERATIONS: u32 = 100;
+
+/// Maximum time in milliseconds between the iterations of a retrieval benchmark.
+pub static MAX_BENCHMARK_SPACING_MS: u64 = 60_000;
+
+/// Distinct words of the mnemonic of a wallet that have to be given for confirming its seed
+/// backup, or all of them if the mnemonic is shorter.
+pub static MIN_SEED_BACKUP_WORDS: usize = 3;
+
+/// Milliseconds between two attempts to deliver the notifications queued for clients that were
+/// not reading them, so that they get them once they catch up even if nothing else is notified.
+pub static NOTIFICATIONS_FLUSH_INTERVAL_MS: u64 = 500;
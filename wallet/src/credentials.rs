@@ -0,0 +1,255 @@
+//! Credentials file of the wallets that are unlocked when the wallet starts.
+//!
+//! Server deployments list in a JSON file, readable only by the user running the wallet, the
+//! wallets to unlock at startup along with the id and the scopes of the service session in which
+//! they are unlocked:
+//! ```json
+//! {
+//!     "session_id": "a secret token with at least 32 characters",
+//!     "scopes": ["read", "send"],
+//!     "wallets": {
+//!         "<wallet id>": { "password": "<password>" },
+//!         "<another wallet id>": { "encrypted_password": "<hex-encoded IV|SALT|CIPHERTEXT>" }
+//!     }
+//! }
+//! ```
+//! Encrypted passwords are decrypted with the key in the `WITNET_WALLET_CREDENTIALS_KEY`
+//! environment variable. The contents of the file are zeroed out of memory once the wallets are
+//! unlocked.
+use std::{collections::BTreeMap, fs, path::Path};
+
+use failure::Fail;
+use serde::{Deserialize, Serialize};
+use witnet_protected::Protected;
+
+use crate::{constants, crypto, model, types};
+
+/// Errors of reading the credentials file or the password of one of its wallets.
+#[derive(Debug, Fail)]
+pub enum Error {
+    #[fail(display = "Failed to read the credentials file: {}", _0)]
+    Io(#[cause] std::io::Error),
+    #[fail(
+        display = "The credentials file is accessible by other users (mode {:o}), it must only be accessible by its owner",
+        mode
+    )]
+    Permissions { mode: u32 },
+    #[fail(display = "Failed to parse the credentials file: {}", _0)]
+    Parse(#[cause] serde_json::Error),
+    #[fail(
+        display = "The id of the service session must have at least {} characters",
+        _0
+    )]
+    SessionIdTooShort(usize),
+    #[fail(
+        display = "The password is encrypted but the {} environment variable is not set",
+        _0
+    )]
+    MissingKey(&'static str),
+    #[fail(display = "Failed to decrypt the password: {}", _0)]
+    Decryption(String),
+}
+
+/// Contents of the credentials file.
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Credentials {
+    /// Id of the service session, which clients use for calling the wallet.
+    pub session_id: types::SessionId,
+    /// Scopes of the service session, all but `witness_signing` if omitted.
+    #[serde(default)]
+    pub scopes: Option<Vec<types::Scope>>,
+    /// Credential of every wallet to unlock, by wallet id.
+    pub wallets: BTreeMap<model::WalletId, WalletCredential>,
+}
+
+/// Password of a wallet, either in plain text or encrypted.
+#[derive(Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WalletCredential {
+    Password(types::Password),
+    /// Hex-encoded output of encrypting the password with the key from the environment.
+    EncryptedPassword(String),
+}
+
+/// Outcome of unlocking the wallets of the credentials file, as shown to clients.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StartupUnlockReport {
+    /// Wallets that are being unlocked.
+    pub pending: Vec<model::WalletId>,
+    /// Wallets that were unlocked in the service session.
+    pub unlocked: Vec<model::WalletId>,
+    /// Wallets that could not be unlocked.
+    pub failed: Vec<StartupUnlockFailure>,
+}
+
+/// A wallet of the credentials file that could not be unlocked.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct StartupUnlockFailure {
+    pub wallet_id: model::WalletId,
+    pub error: String,
+}
+
+/// Read the credentials file, refusing it if other users can access it.
+pub fn load(path: &Path) -> Result<Credentials, Error> {
+    check_permissions(&fs::metadata(path).map_err(Error::Io)?)?;
+
+    // The contents are zeroed out when dropped, as are the passwords parsed from them
+    let contents = Protected::new(fs::read(path).map_err(Error::Io)?);
+    let credentials: Credentials = serde_json::from_slice(&contents).map_err(Error::Parse)?;
+
+    let session_id_length = String::from(credentials.session_id.clone()).len();
+    if session_id_length < constants::MIN_SERVICE_SESSION_ID_LENGTH {
+        return Err(Error::SessionIdTooShort(
+            constants::MIN_SERVICE_SESSION_ID_LENGTH,
+        ));
+    }
+
+    Ok(credentials)
+}
+
+#[cfg(unix)]
+fn check_permissions(metadata: &fs::Metadata) -> Result<(), Error> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = metadata.permissions().mode() & 0o777;
+    if mode & 0o077 != 0 {
+        return Err(Error::Permissions { mode });
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn check_permissions(_metadata: &fs::Metadata) -> Result<(), Error> {
+    Ok(())
+}
+
+impl WalletCredential {
+    /// Get the password in plain text, decrypting it with `key` if it is encrypted.
+    pub fn into_password(self, key: Option<&types::Password>) -> Result<types::Password, Error> {
+        match self {
+            WalletCredential::Password(password) => Ok(password),
+            WalletCredential::EncryptedPassword(ciphertext) => {
+                let key = key.ok_or(Error::MissingKey(constants::CREDENTIALS_KEY_ENV_VAR))?;
+                let ciphertext =
+                    hex::decode(ciphertext).map_err(|e| Error::Decryption(e.to_string()))?;
+                let password = Protected::new(
+                    crypto::decrypt_cbc(&ciphertext, key.as_ref())
+                        .map_err(|e| Error::Decryption(e.to_string()))?,
+                );
+                let password =
+                    std::str::from_utf8(&password).map_err(|e| Error::Decryption(e.to_string()))?;
+
+                Ok(types::Password::new(password))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    const SESSION_ID: &str = "0123456789abcdef0123456789abcdef";
+
+    /// Write a credentials file with the given mode to a path that is unique to the test.
+    fn credentials_file(name: &str, contents: &str, mode: u32) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "witnet-wallet-credentials-{}-{}",
+            name,
+            std::process::id()
+        ));
+        fs::write(&path, contents).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+
+        path
+    }
+
+    #[test]
+    fn test_load_credentials() {
+        let path = credentials_file(
+            "load",
+            &format!(
+                r#"{{
+                    "session_id": "{}",
+                    "scopes": ["read"],
+                    "wallets": {{ "wallet": {{ "password": "12345678" }} }}
+                }}"#,
+                SESSION_ID
+            ),
+            0o600,
+        );
+
+        let credentials = load(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+
+        assert_eq!(String::from(credentials.session_id), SESSION_ID);
+        assert_eq!(credentials.scopes, Some(vec![types::Scope::Read]));
+        let password = credentials
+            .wallets
+            .into_iter()
+            .map(|(wallet_id, credential)| (wallet_id, credential.into_password(None).unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(password[0].0.as_str(), "wallet");
+        assert_eq!(AsRef::<str>::as_ref(&password[0].1), "12345678");
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_over_permissive_file_is_refused() {
+        let contents = format!(r#"{{ "session_id": "{}", "wallets": {{}} }}"#, SESSION_ID);
+
+        for mode in [0o644, 0o640, 0o604] {
+            let path = credentials_file("permissions", &contents, mode);
+            let result = load(&path);
+            fs::remove_file(&path).unwrap();
+
+            assert!(
+                matches!(result, Err(Error::Permissions { mode: refused }) if refused == mode),
+                "mode {:o} should be refused",
+                mode
+            );
+        }
+    }
+
+    #[test]
+    fn test_short_session_id_is_refused() {
+        let path = credentials_file(
+            "session",
+            r#"{ "session_id": "short", "wallets": {} }"#,
+            0o600,
+        );
+        let result = load(&path);
+        fs::remove_file(&path).unwrap();
+
+        assert!(matches!(result, Err(Error::SessionIdTooShort(_))));
+    }
+
+    #[test]
+    fn test_encrypted_password() {
+        let key = types::Password::new("credentials key");
+        let ciphertext = crypto::encrypt_cbc(b"12345678", b"credentials key").unwrap();
+        let credential = || WalletCredential::EncryptedPassword(hex::encode(&ciphertext));
+
+        let password = credential().into_password(Some(&key)).unwrap();
+        assert_eq!(AsRef::<str>::as_ref(&password), "12345678");
+
+        assert!(matches!(
+            credential().into_password(None),
+            Err(Error::MissingKey(_))
+        ));
+        assert!(matches!(
+            credential().into_password(Some(&types::Password::new("wrong key"))),
+            Err(Error::Decryption(_))
+        ));
+    }
+}
@@ -1,13 +1,14 @@
 use failure::Fail;
+use hmac::{Hmac, Mac};
 
 use witnet_crypto::{
     cipher,
-    hash::HashFunction,
+    hash::{calculate_sha256, HashFunction},
     key::{ExtendedSK, KeyError, MasterKeyGen, MasterKeyGenError},
     pbkdf2::pbkdf2_sha256,
 };
 
-use crate::types;
+use crate::{model, types};
 
 const IV_LENGTH: usize = 16;
 const SALT_LENGTH: usize = 32;
@@ -85,6 +86,34 @@ pub fn gen_wallet_id(
     }
 }
 
+/// Generate a random wallet id, not linked in any way to the seed of the wallet.
+pub fn gen_random_wallet_id<Rng>(rng: &mut Rng) -> model::WalletId
+where
+    Rng: rand::Rng + rand::CryptoRng,
+{
+    model::WalletId::from_random_bytes(rng.gen())
+}
+
+/// Generate the fingerprint used for detecting wallets created from the same seed.
+///
+/// `seed_id` is the id deterministically derived from the seed with `gen_wallet_id`, which is
+/// never exposed, and `salt` is specific to the wallets database.
+pub fn gen_seed_fingerprint(seed_id: &str, salt: &[u8]) -> String {
+    let fingerprint = calculate_sha256(&[salt, seed_id.as_bytes()].concat());
+
+    hex::encode(fingerprint)
+}
+
+/// Sign the payload of a webhook delivery: the hex-encoded HMAC-SHA256 of the payload with the
+/// secret shared with the endpoint.
+pub fn webhook_signature(secret: &[u8], payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<sha2::Sha256>::new_varkey(secret).expect("HMAC can take keys of any length");
+    mac.input(payload);
+
+    hex::encode(mac.result().code())
+}
+
 /// Generate a cryptographic session id.
 pub fn gen_session_id<Rng>(
     rng: &mut Rng,
@@ -31,4 +31,29 @@ impl CryptoEngine {
 
         Ok(value)
     }
+
+    /// Encrypt a value with a random IV, which is prepended to the ciphertext, so that values that
+    /// are kept outside of the database never share one.
+    pub fn seal<T>(&self, value: &T) -> Result<Vec<u8>>
+    where
+        T: serde::Serialize + ?Sized,
+    {
+        let bytes = bincode::serialize(value)?;
+        let mut sealed = cipher::generate_random(self.iv.len())?;
+        let encrypted = cipher::encrypt_aes_cbc(self.key.as_ref(), &bytes, &sealed)?;
+        sealed.extend(encrypted);
+
+        Ok(sealed)
+    }
+
+    pub fn unseal<T>(&self, bytes: &[u8]) -> Result<T>
+    where
+        T: serde::de::DeserializeOwned,
+    {
+        let (iv, encrypted) = bytes.split_at(self.iv.len().min(bytes.len()));
+        let decrypted = cipher::decrypt_aes_cbc(self.key.as_ref(), encrypted, iv)?;
+        let value = bincode::deserialize(&decrypted)?;
+
+        Ok(value)
+    }
 }
@@ -30,6 +30,9 @@ impl EncryptedDb {
 
 impl AsRef<rocksdb::DB> for EncryptedDb {
     fn as_ref(&self) -> &rocksdb::DB {
+        debug_assert_blocking_allowed();
+        #[cfg(any(test, feature = "test-utils"))]
+        simulate_storage_latency(&self.db);
         self.db.as_ref()
     }
 }
@@ -80,12 +83,16 @@ impl Database for EncryptedDb {
         let enc_key = self.engine.encrypt(&prefix_key)?;
         let enc_val = self.engine.encrypt(value.borrow())?;
 
+        #[cfg(any(test, feature = "test-utils"))]
+        record_storage_write(&self.db);
         self.as_ref().put(enc_key, enc_val)?;
 
         Ok(())
     }
 
     fn write(&self, batch: Self::WriteBatch) -> Result<()> {
+        #[cfg(any(test, feature = "test-utils"))]
+        record_storage_write(&self.db);
         self.as_ref().write(batch.into())?;
 
         Ok(())
@@ -97,6 +104,20 @@ impl Database for EncryptedDb {
         Ok(())
     }
 
+    fn seal<V>(&self, value: &V) -> Result<Vec<u8>>
+    where
+        V: serde::Serialize + ?Sized,
+    {
+        self.engine.seal(value)
+    }
+
+    fn unseal<V>(&self, bytes: &[u8]) -> Result<V>
+    where
+        V: serde::de::DeserializeOwned,
+    {
+        self.engine.unseal(bytes)
+    }
+
     fn batch(&self) -> Self::WriteBatch {
         EncryptedWriteBatch::new(self.prefixer.clone(), self.engine.clone())
     }
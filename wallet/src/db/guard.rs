@@ -0,0 +1,99 @@
+//! Guard against running blocking database operations on the thread of the `App` actor.
+//!
+//! All the RocksDB operations are meant to run in the worker arbiter. A slow disk would otherwise
+//! stall every JSON-RPC method handled by the `App` actor, not only the ones touching storage.
+use std::cell::Cell;
+#[cfg(any(test, feature = "test-utils"))]
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex, Weak,
+    },
+    time::Duration,
+};
+
+thread_local! {
+    static BLOCKING_FORBIDDEN: Cell<bool> = Cell::new(false);
+}
+
+/// Mark the current thread as one that must never perform blocking database operations.
+pub fn forbid_blocking_on_current_thread() {
+    BLOCKING_FORBIDDEN.with(|forbidden| forbidden.set(true));
+}
+
+/// Whether blocking database operations are allowed on the current thread.
+pub fn is_blocking_allowed() -> bool {
+    BLOCKING_FORBIDDEN.with(|forbidden| !forbidden.get())
+}
+
+/// Panic in debug builds if a blocking database operation is performed on a thread marked with
+/// `forbid_blocking_on_current_thread`.
+#[inline]
+pub fn debug_assert_blocking_allowed() {
+    debug_assert!(
+        is_blocking_allowed(),
+        "RocksDB operation performed on a thread where blocking is forbidden"
+    );
+}
+
+/// Latency added to every operation on some databases, which simulates a slow disk in tests.
+#[cfg(any(test, feature = "test-utils"))]
+static STORAGE_LATENCIES: Mutex<Vec<(Weak<rocksdb::DB>, Duration)>> = Mutex::new(Vec::new());
+
+/// Make every operation on `db` take at least `latency`, as if it was stored in a slow disk.
+#[cfg(any(test, feature = "test-utils"))]
+pub fn set_storage_latency(db: &Arc<rocksdb::DB>, latency: Duration) {
+    let mut latencies = STORAGE_LATENCIES
+        .lock()
+        .expect("Storage latency lock should only fail if poisoned");
+    latencies.retain(|(other, _)| other.strong_count() > 0 && !is_same_db(other, db));
+    latencies.push((Arc::downgrade(db), latency));
+}
+
+/// Wait for the latency set for `db` with `set_storage_latency`, if any.
+#[cfg(any(test, feature = "test-utils"))]
+pub fn simulate_storage_latency(db: &Arc<rocksdb::DB>) {
+    let latency = STORAGE_LATENCIES
+        .lock()
+        .expect("Storage latency lock should only fail if poisoned")
+        .iter()
+        .find(|(other, _)| is_same_db(other, db))
+        .map(|(_, latency)| *latency);
+    if let Some(latency) = latency {
+        std::thread::sleep(latency);
+    }
+}
+
+/// Counters of the writes to some databases, which tests use to check that nothing is written.
+#[cfg(any(test, feature = "test-utils"))]
+static STORAGE_WRITES: Mutex<Vec<(Weak<rocksdb::DB>, Arc<AtomicUsize>)>> = Mutex::new(Vec::new());
+
+/// Count the writes to `db` from now on, i.e. its puts and written batches, returning the counter.
+#[cfg(any(test, feature = "test-utils"))]
+pub fn count_storage_writes(db: &Arc<rocksdb::DB>) -> Arc<AtomicUsize> {
+    let counter = Arc::new(AtomicUsize::new(0));
+    let mut writes = STORAGE_WRITES
+        .lock()
+        .expect("Storage writes lock should only fail if poisoned");
+    writes.retain(|(other, _)| other.strong_count() > 0 && !is_same_db(other, db));
+    writes.push((Arc::downgrade(db), counter.clone()));
+
+    counter
+}
+
+/// Count a write to `db`, if `count_storage_writes` was called for it.
+#[cfg(any(test, feature = "test-utils"))]
+pub fn record_storage_write(db: &Arc<rocksdb::DB>) {
+    let writes = STORAGE_WRITES
+        .lock()
+        .expect("Storage writes lock should only fail if poisoned");
+    if let Some((_, counter)) = writes.iter().find(|(other, _)| is_same_db(other, db)) {
+        counter.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+/// Whether `other` is still alive and is the same database as `db`.
+#[cfg(any(test, feature = "test-utils"))]
+fn is_same_db(other: &Weak<rocksdb::DB>, db: &Arc<rocksdb::DB>) -> bool {
+    other.strong_count() > 0 && std::ptr::eq(other.as_ptr(), Arc::as_ptr(db))
+}
@@ -1,5 +1,6 @@
 mod encrypted;
 mod error;
+mod guard;
 mod plain;
 #[cfg(test)]
 mod tests;
@@ -10,6 +11,7 @@ use crate::repository::keys::Key;
 
 pub use encrypted::*;
 pub use error::Error;
+pub use guard::*;
 pub use plain::*;
 pub use std::borrow::Borrow;
 #[cfg(test)]
@@ -61,6 +63,17 @@ pub trait Database {
 
     fn flush(&self) -> Result<()>;
 
+    /// Serialize a value to be kept outside of the database, encrypted with the key of the
+    /// database and a random IV if it has a key.
+    fn seal<V>(&self, value: &V) -> Result<Vec<u8>>
+    where
+        V: serde::Serialize + ?Sized;
+
+    /// Deserialize a value sealed by this database.
+    fn unseal<V>(&self, bytes: &[u8]) -> Result<V>
+    where
+        V: serde::de::DeserializeOwned;
+
     fn batch(&self) -> Self::WriteBatch;
 }
 
@@ -70,4 +83,9 @@ pub trait WriteBatch {
         K: AsRef<[u8]>,
         V: serde::Serialize + ?Sized,
         Vref: Borrow<V>;
+
+    fn delete<K, V>(&mut self, key: &Key<K, V>) -> Result<()>
+    where
+        K: AsRef<[u8]>,
+        V: ?Sized;
 }
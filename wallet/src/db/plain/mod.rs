@@ -19,6 +19,9 @@ impl PlainDb {
 
 impl AsRef<rocksdb::DB> for PlainDb {
     fn as_ref(&self) -> &rocksdb::DB {
+        debug_assert_blocking_allowed();
+        #[cfg(any(test, feature = "test-utils"))]
+        simulate_storage_latency(&self.db);
         self.db.as_ref()
     }
 }
@@ -60,12 +63,16 @@ impl Database for PlainDb {
     {
         let bytes = bincode::serialize(value.borrow())?;
 
+        #[cfg(any(test, feature = "test-utils"))]
+        record_storage_write(&self.db);
         self.as_ref().put(key, bytes)?;
 
         Ok(())
     }
 
     fn write(&self, batch: Self::WriteBatch) -> Result<()> {
+        #[cfg(any(test, feature = "test-utils"))]
+        record_storage_write(&self.db);
         self.as_ref().write(batch.into())?;
 
         Ok(())
@@ -77,6 +84,20 @@ impl Database for PlainDb {
         Ok(())
     }
 
+    fn seal<V>(&self, value: &V) -> Result<Vec<u8>>
+    where
+        V: serde::Serialize + ?Sized,
+    {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn unseal<V>(&self, bytes: &[u8]) -> Result<V>
+    where
+        V: serde::de::DeserializeOwned,
+    {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
     fn batch(&self) -> Self::WriteBatch {
         PlainWriteBatch::default()
     }
@@ -1,4 +1,8 @@
-use std::{cell::RefCell, collections::HashMap, rc::Rc};
+use std::{
+    cell::{Cell, RefCell},
+    collections::HashMap,
+    rc::Rc,
+};
 
 use super::*;
 
@@ -7,11 +11,21 @@ type Bytes = Vec<u8>;
 #[derive(Default, Clone)]
 pub struct HashMapDb {
     rc: Rc<RefCell<HashMap<Bytes, Bytes>>>,
+    /// Values read so far, shared by all the clones of the database
+    reads: Rc<Cell<usize>>,
 }
 
 impl HashMapDb {
     pub fn new(rc: Rc<RefCell<HashMap<Bytes, Bytes>>>) -> Self {
-        Self { rc }
+        Self {
+            rc,
+            reads: Rc::default(),
+        }
+    }
+
+    /// Number of values that were read from the database so far.
+    pub fn reads(&self) -> usize {
+        self.reads.get()
     }
 
     #[allow(dead_code)]
@@ -33,9 +47,7 @@ impl HashMapDb {
             hashmap.insert(key, value);
         }
 
-        Ok(Self {
-            rc: Rc::new(RefCell::new(hashmap)),
-        })
+        Ok(Self::new(Rc::new(RefCell::new(hashmap))))
     }
 }
 
@@ -54,6 +66,7 @@ impl Database for HashMapDb {
         V: serde::de::DeserializeOwned,
     {
         let k = key.as_ref().to_vec();
+        self.reads.set(self.reads.get() + 1);
         let res = match RefCell::borrow(&self.rc).get(&k) {
             Some(value) => Some(bincode::deserialize(value.as_ref())?),
             None => None,
@@ -67,6 +80,7 @@ impl Database for HashMapDb {
         K: AsRef<[u8]>,
     {
         let k = key.as_ref().to_vec();
+        self.reads.set(self.reads.get() + 1);
         let res = RefCell::borrow(&self.rc).contains_key(&k);
 
         Ok(res)
@@ -90,7 +104,10 @@ impl Database for HashMapDb {
         let mut map = self.rc.borrow_mut();
 
         for (k, v) in batch {
-            map.insert(k, v);
+            match v {
+                Some(v) => map.insert(k, v),
+                None => map.remove(&k),
+            };
         }
 
         Ok(())
@@ -100,14 +117,29 @@ impl Database for HashMapDb {
         Ok(())
     }
 
+    fn seal<V>(&self, value: &V) -> Result<Vec<u8>>
+    where
+        V: serde::Serialize + ?Sized,
+    {
+        Ok(bincode::serialize(value)?)
+    }
+
+    fn unseal<V>(&self, bytes: &[u8]) -> Result<V>
+    where
+        V: serde::de::DeserializeOwned,
+    {
+        Ok(bincode::deserialize(bytes)?)
+    }
+
     fn batch(&self) -> Self::WriteBatch {
         Default::default()
     }
 }
 
+/// Pending writes, where `None` values are deletions.
 #[derive(Default)]
 pub struct HashMapWriteBatch {
-    data: HashMap<Bytes, Bytes>,
+    data: HashMap<Bytes, Option<Bytes>>,
 }
 
 impl WriteBatch for HashMapWriteBatch {
@@ -120,16 +152,26 @@ impl WriteBatch for HashMapWriteBatch {
         let k = key.as_ref().to_vec();
         let v = bincode::serialize(value.borrow())?;
 
-        self.data.insert(k, v);
+        self.data.insert(k, Some(v));
+
+        Ok(())
+    }
+
+    fn delete<K, V>(&mut self, key: &Key<K, V>) -> Result<()>
+    where
+        K: AsRef<[u8]>,
+        V: ?Sized,
+    {
+        self.data.insert(key.as_ref().to_vec(), None);
 
         Ok(())
     }
 }
 
-type IntoIter = std::collections::hash_map::IntoIter<Bytes, Bytes>;
+type IntoIter = std::collections::hash_map::IntoIter<Bytes, Option<Bytes>>;
 
 impl IntoIterator for HashMapWriteBatch {
-    type Item = (Bytes, Bytes);
+    type Item = (Bytes, Option<Bytes>);
     type IntoIter = IntoIter;
 
     fn into_iter(self) -> IntoIter {
@@ -168,4 +210,58 @@ fn test_hashmap_writebatch() {
 
     assert_eq!(value1, db.get(&key1).unwrap());
     assert_eq!(value2, db.get(&key2).unwrap());
+
+    let mut batch = db.batch();
+    batch.delete(&key1).unwrap();
+    db.write(batch).unwrap();
+
+    assert!(!db.contains(&key1).unwrap());
+    assert_eq!(value2, db.get(&key2).unwrap());
+}
+
+#[test]
+fn test_encrypted_db_seals_values_with_its_key() {
+    let rocks = crate::testing::in_memory_db().unwrap();
+    let db = |password: &[u8]| {
+        let key = crate::crypto::key_from_password(password, b"salt", 1);
+
+        EncryptedDb::new(rocks.clone(), b"wallet".to_vec(), key, vec![0; 16])
+    };
+    let value = "archived details".to_string();
+
+    // Every sealed copy has an IV of its own
+    let sealed = db(b"password").seal(&value).unwrap();
+    assert_ne!(sealed, db(b"password").seal(&value).unwrap());
+    assert!(!sealed
+        .windows(value.len())
+        .any(|window| window == value.as_bytes()));
+
+    assert_eq!(db(b"password").unseal::<String>(&sealed).unwrap(), value);
+    assert!(db(b"wrong").unseal::<String>(&sealed).is_err());
+    assert!(db(b"password").unseal::<String>(&sealed[..8]).is_err());
+}
+
+#[test]
+fn test_blocking_guard_is_per_thread() {
+    let forbidden = std::thread::spawn(|| {
+        forbid_blocking_on_current_thread();
+        is_blocking_allowed()
+    })
+    .join()
+    .unwrap();
+
+    assert!(!forbidden);
+    assert!(is_blocking_allowed());
+}
+
+#[test]
+#[cfg(debug_assertions)]
+fn test_blocking_guard_panics_on_forbidden_thread() {
+    let res = std::thread::spawn(|| {
+        forbid_blocking_on_current_thread();
+        debug_assert_blocking_allowed();
+    })
+    .join();
+
+    assert!(res.is_err());
 }
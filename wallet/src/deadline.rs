@@ -0,0 +1,215 @@
+//! Execution deadlines for long-running worker jobs.
+//!
+//! Mailbox timeouts only bound the time it takes to deliver a message to the worker, not the time
+//! it takes to handle it. Long-running jobs (running a data request, synchronizing a wallet,
+//! consolidating blocks) take a `Deadline` instead, which they check at natural yield points so as
+//! to stop cleanly.
+use std::{
+    future::Future,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    task::{Context, Poll},
+    thread,
+    time::{Duration, Instant},
+};
+
+use failure::Fail;
+
+/// The deadline of a job expired before the job was completed.
+#[derive(Clone, Debug, PartialEq, Eq, Fail)]
+#[fail(display = "{} timed out ({})", operation, progress)]
+pub struct Expired {
+    /// Name of the operation that timed out.
+    pub operation: &'static str,
+    /// How far the operation got before timing out.
+    pub progress: String,
+}
+
+/// Instant after which a job must stop, if any.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Deadline(Option<Instant>);
+
+impl Deadline {
+    /// A deadline that never expires.
+    pub fn none() -> Self {
+        Self(None)
+    }
+
+    /// A deadline that expires after `timeout` from now.
+    pub fn after(timeout: Duration) -> Self {
+        Self(Instant::now().checked_add(timeout))
+    }
+
+    /// Time left until the deadline expires, if there is a deadline.
+    pub fn remaining(&self) -> Option<Duration> {
+        self.0
+            .map(|instant| instant.saturating_duration_since(Instant::now()))
+    }
+
+    /// Whether the deadline has already expired.
+    pub fn is_expired(&self) -> bool {
+        self.remaining() == Some(Duration::from_secs(0))
+    }
+
+    /// Fail if the deadline has expired. `progress` is only called in that case, and describes
+    /// how far the operation got.
+    pub fn check<F>(&self, operation: &'static str, progress: F) -> Result<(), Expired>
+    where
+        F: FnOnce() -> String,
+    {
+        if self.is_expired() {
+            Err(Expired {
+                operation,
+                progress: progress(),
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// Run an asynchronous job on the current thread until it completes or the deadline expires.
+///
+/// The deadline is checked whenever the job yields, e.g. while it waits for a response. Once it
+/// expires the job is dropped, which cancels whatever it was waiting for, so that nothing keeps
+/// running in the background after a timeout.
+pub fn block_on_with_deadline<F>(
+    deadline: Deadline,
+    operation: &'static str,
+    job: F,
+) -> Result<F::Output, Expired>
+where
+    F: Future,
+{
+    if deadline.remaining().is_none() {
+        return Ok(futures::executor::block_on(job));
+    }
+
+    let wake = Arc::new(ThreadWake {
+        thread: thread::current(),
+        woken: AtomicBool::new(false),
+    });
+    let waker = futures::task::waker(wake.clone());
+    let mut context = Context::from_waker(&waker);
+    futures::pin_mut!(job);
+    loop {
+        if let Poll::Ready(output) = job.as_mut().poll(&mut context) {
+            return Ok(output);
+        }
+        // The job may have been woken while it was being polled, and parking would miss it
+        while !wake.woken.swap(false, Ordering::Acquire) {
+            match deadline.remaining() {
+                Some(remaining) if remaining > Duration::from_secs(0) => {
+                    thread::park_timeout(remaining)
+                }
+                _ => {
+                    return Err(Expired {
+                        operation,
+                        progress: "not completed".to_string(),
+                    })
+                }
+            }
+        }
+    }
+}
+
+/// Wakes up the thread that runs a job with a deadline.
+struct ThreadWake {
+    thread: thread::Thread,
+    woken: AtomicBool,
+}
+
+impl futures::task::ArcWake for ThreadWake {
+    fn wake_by_ref(arc_self: &Arc<Self>) {
+        arc_self.woken.store(true, Ordering::Release);
+        arc_self.thread.unpark();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn no_deadline_never_expires() {
+        let deadline = Deadline::none();
+
+        assert!(!deadline.is_expired());
+        assert_eq!(deadline.remaining(), None);
+        assert_eq!(deadline.check("sync", || unreachable!()), Ok(()));
+    }
+
+    #[test]
+    fn expired_deadline_reports_progress() {
+        let deadline = Deadline::after(Duration::from_secs(0));
+
+        assert!(deadline.is_expired());
+        assert_eq!(
+            deadline.check("sync", || "synced up to #10 of #20".to_string()),
+            Err(Expired {
+                operation: "sync",
+                progress: "synced up to #10 of #20".to_string(),
+            })
+        );
+    }
+
+    #[test]
+    fn slow_job_is_dropped_when_it_times_out() {
+        struct Dropped(Arc<AtomicBool>);
+        impl Drop for Dropped {
+            fn drop(&mut self) {
+                self.0.store(true, Ordering::SeqCst);
+            }
+        }
+
+        let dropped = Arc::new(AtomicBool::new(false));
+        let guard = Dropped(dropped.clone());
+        let slow = block_on_with_deadline(
+            Deadline::after(Duration::from_millis(10)),
+            "slow job",
+            async move {
+                let _guard = guard;
+                futures::future::pending::<()>().await;
+                1
+            },
+        );
+        assert_eq!(
+            slow,
+            Err(Expired {
+                operation: "slow job",
+                progress: "not completed".to_string(),
+            })
+        );
+        // The job is not left running after the timeout
+        assert!(dropped.load(Ordering::SeqCst));
+
+        let fast = block_on_with_deadline(
+            Deadline::after(Duration::from_secs(10)),
+            "fast job",
+            async { 2 },
+        );
+        assert_eq!(fast, Ok(2));
+
+        let unbounded = block_on_with_deadline(Deadline::none(), "unbounded job", async { 3 });
+        assert_eq!(unbounded, Ok(3));
+    }
+
+    #[test]
+    fn job_woken_from_another_thread_completes() {
+        let (sender, receiver) = futures::channel::oneshot::channel();
+        let sending = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            sender.send(4).unwrap();
+        });
+
+        let received = block_on_with_deadline(
+            Deadline::after(Duration::from_secs(10)),
+            "waiting job",
+            receiver,
+        );
+        sending.join().unwrap();
+        assert_eq!(received, Ok(Ok(4)));
+    }
+}
@@ -0,0 +1,271 @@
+//! Health tracking and failover between the Witnet nodes the wallet can connect to.
+//!
+//! The wallet is configured with an ordered list of node JSON-RPC endpoints, and only talks to one
+//! of them at a time. The periodic `syncStatus` request acts as a health check: after a number of
+//! consecutive failures, the wallet fails over to the next endpoint in the list. To avoid flapping
+//! between nodes, no automatic failover happens until some time has passed since the last switch.
+use std::collections::VecDeque;
+
+use failure::Fail;
+use serde::Serialize;
+
+/// How many failover events are remembered.
+const HISTORY_LENGTH: usize = 32;
+
+#[derive(Debug, PartialEq, Eq, Fail)]
+pub enum Error {
+    #[fail(display = "{} is not one of the configured node URLs", _0)]
+    UnknownUrl(String),
+}
+
+/// Why the wallet switched from a node to another.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FailoverReason {
+    /// The active node failed too many health checks in a row.
+    HealthCheck,
+    /// An operator asked for the switch.
+    Manual,
+}
+
+/// A switch from a node to another.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FailoverEvent {
+    pub timestamp: i64,
+    pub from: String,
+    pub to: String,
+    pub reason: FailoverReason,
+}
+
+/// Snapshot of the failover state, as shown to clients as part of the status of the node.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct FailoverStatus {
+    /// URL of the node the wallet is connected to.
+    pub active_url: String,
+    /// All the configured node URLs, in failover order.
+    pub urls: Vec<String>,
+    /// Health checks failed in a row by the active node.
+    pub consecutive_failures: u32,
+    /// Most recent switches between nodes, oldest first.
+    pub history: Vec<FailoverEvent>,
+}
+
+/// Failover state machine for an ordered list of node URLs.
+#[derive(Debug)]
+pub struct NodeFailover {
+    urls: Vec<String>,
+    active: usize,
+    failure_threshold: u32,
+    min_switch_interval: i64,
+    consecutive_failures: u32,
+    last_switch: Option<i64>,
+    history: VecDeque<FailoverEvent>,
+}
+
+impl NodeFailover {
+    /// Create the failover state for a non-empty list of URLs, starting with the first one.
+    ///
+    /// `failure_threshold` is the number of health checks that must fail in a row before failing
+    /// over, and `min_switch_interval` the seconds that must pass after a switch before failing
+    /// over again.
+    pub fn new(urls: Vec<String>, failure_threshold: u32, min_switch_interval: i64) -> Self {
+        assert!(!urls.is_empty(), "At least one node URL is needed");
+
+        Self {
+            urls,
+            active: 0,
+            failure_threshold: failure_threshold.max(1),
+            min_switch_interval,
+            consecutive_failures: 0,
+            last_switch: None,
+            history: VecDeque::with_capacity(HISTORY_LENGTH),
+        }
+    }
+
+    /// URL of the node the wallet should be connected to.
+    pub fn active_url(&self) -> &str {
+        &self.urls[self.active]
+    }
+
+    /// Record a successful health check of the active node.
+    pub fn record_success(&mut self) {
+        self.consecutive_failures = 0;
+    }
+
+    /// Record a failed health check of the active node.
+    ///
+    /// Returns the URL to fail over to, if it is time to do so.
+    pub fn record_failure(&mut self, now: i64) -> Option<String> {
+        self.consecutive_failures = self.consecutive_failures.saturating_add(1);
+
+        if self.urls.len() < 2 || self.consecutive_failures < self.failure_threshold {
+            return None;
+        }
+        if matches!(self.last_switch, Some(last) if now - last < self.min_switch_interval) {
+            return None;
+        }
+
+        let next = (self.active + 1) % self.urls.len();
+        self.switch(next, FailoverReason::HealthCheck, now);
+
+        Some(self.active_url().to_string())
+    }
+
+    /// Switch to a specific node on request of an operator.
+    ///
+    /// Returns whether the active node changed.
+    pub fn switch_to(&mut self, url: &str, now: i64) -> Result<bool, Error> {
+        let index = self
+            .urls
+            .iter()
+            .position(|x| x == url)
+            .ok_or_else(|| Error::UnknownUrl(url.to_string()))?;
+
+        if index == self.active {
+            return Ok(false);
+        }
+        self.switch(index, FailoverReason::Manual, now);
+
+        Ok(true)
+    }
+
+    /// Get a snapshot of the failover state.
+    pub fn status(&self) -> FailoverStatus {
+        FailoverStatus {
+            active_url: self.active_url().to_string(),
+            urls: self.urls.clone(),
+            consecutive_failures: self.consecutive_failures,
+            history: self.history.iter().cloned().collect(),
+        }
+    }
+
+    fn switch(&mut self, index: usize, reason: FailoverReason, now: i64) {
+        let from = self.active_url().to_string();
+        self.active = index;
+        self.consecutive_failures = 0;
+        self.last_switch = Some(now);
+
+        let to = self.active_url().to_string();
+        log::warn!("Switching from node {} to node {} ({:?})", from, to, reason);
+
+        if self.history.len() == HISTORY_LENGTH {
+            self.history.pop_front();
+        }
+        self.history.push_back(FailoverEvent {
+            timestamp: now,
+            from,
+            to,
+            reason,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls() -> Vec<String> {
+        vec!["127.0.0.1:21338".to_string(), "127.0.0.1:21339".to_string()]
+    }
+
+    #[test]
+    fn test_starts_with_first_url() {
+        let failover = NodeFailover::new(urls(), 3, 60);
+
+        assert_eq!(failover.active_url(), "127.0.0.1:21338");
+        assert!(failover.status().history.is_empty());
+    }
+
+    #[test]
+    fn test_failover_after_threshold() {
+        let mut failover = NodeFailover::new(urls(), 3, 60);
+
+        assert_eq!(failover.record_failure(0), None);
+        assert_eq!(failover.record_failure(1), None);
+        assert_eq!(
+            failover.record_failure(2),
+            Some("127.0.0.1:21339".to_string())
+        );
+
+        let status = failover.status();
+        assert_eq!(status.active_url, "127.0.0.1:21339");
+        assert_eq!(status.consecutive_failures, 0);
+        assert_eq!(
+            status.history,
+            vec![FailoverEvent {
+                timestamp: 2,
+                from: "127.0.0.1:21338".to_string(),
+                to: "127.0.0.1:21339".to_string(),
+                reason: FailoverReason::HealthCheck,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_success_resets_failures() {
+        let mut failover = NodeFailover::new(urls(), 3, 60);
+
+        for now in 0..10 {
+            assert_eq!(failover.record_failure(now), None);
+            assert_eq!(failover.record_failure(now), None);
+            failover.record_success();
+        }
+
+        assert_eq!(failover.active_url(), "127.0.0.1:21338");
+    }
+
+    #[test]
+    fn test_no_flapping_between_nodes() {
+        let mut failover = NodeFailover::new(urls(), 1, 60);
+
+        assert!(failover.record_failure(0).is_some());
+        // The new node is failing too, but the last switch was too recent
+        for now in 1..60 {
+            assert_eq!(failover.record_failure(now), None);
+        }
+        assert_eq!(
+            failover.record_failure(60),
+            Some("127.0.0.1:21338".to_string())
+        );
+    }
+
+    #[test]
+    fn test_single_url_never_fails_over() {
+        let mut failover = NodeFailover::new(vec!["127.0.0.1:21338".to_string()], 1, 0);
+
+        for now in 0..10 {
+            assert_eq!(failover.record_failure(now), None);
+        }
+        assert_eq!(failover.status().consecutive_failures, 10);
+    }
+
+    #[test]
+    fn test_manual_switch() {
+        let mut failover = NodeFailover::new(urls(), 3, 60);
+
+        assert_eq!(failover.switch_to("127.0.0.1:21339", 5), Ok(true));
+        assert_eq!(failover.switch_to("127.0.0.1:21339", 6), Ok(false));
+        assert_eq!(
+            failover.switch_to("127.0.0.1:1", 7),
+            Err(Error::UnknownUrl("127.0.0.1:1".to_string()))
+        );
+
+        let status = failover.status();
+        assert_eq!(status.active_url, "127.0.0.1:21339");
+        assert_eq!(status.history.len(), 1);
+        assert_eq!(status.history[0].reason, FailoverReason::Manual);
+    }
+
+    #[test]
+    fn test_history_is_bounded() {
+        let mut failover = NodeFailover::new(urls(), 1, 0);
+
+        for now in 0..100 {
+            failover.record_failure(now);
+        }
+
+        let history = failover.status().history;
+        assert_eq!(history.len(), HISTORY_LENGTH);
+        assert_eq!(history.last().unwrap().timestamp, 99);
+    }
+}
@@ -0,0 +1,152 @@
+//! Idempotency keys of the requests that change the state of a wallet.
+//!
+//! A client that loses the connection while sending a transaction cannot tell whether it was sent,
+//! and retrying it could send it twice. Clients can therefore give these requests a key of their
+//! own. The first request with a key runs as usual, and its response is recorded in the wallet for
+//! a retention window, during which retries with the same key on the same wallet get the same
+//! response without running again, even from another session or after the daemon restarts. Retries that arrive while the first request is still running
+//! wait for its response. Retries whose params are not those of the first request are refused, as
+//! they are rather new requests that reuse a key by mistake.
+//!
+//! Requests that fail are forgotten, so that retrying them runs them again. Requests that were
+//! interrupted midway, e.g. because the daemon stopped, may or may not have taken effect, so their
+//! retries are refused until the key expires.
+use serde_json::{Map, Value};
+use witnet_crypto::hash::calculate_sha256;
+
+use crate::{model, types::SessionId};
+
+/// Methods that accept an idempotency key.
+pub const IDEMPOTENT_METHODS: &[&str] = &[
+    "bump_fee",
+    "create_data_request",
+    "create_payment_request",
+    "create_vtt",
+    "generate_address",
+    "send_transaction",
+];
+
+/// Maximum length of an idempotency key.
+pub const MAX_KEY_LENGTH: usize = 128;
+
+/// A request made with an idempotency key.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct IdempotentRequest {
+    pub session_id: SessionId,
+    pub wallet_id: model::WalletId,
+    pub key: String,
+    pub method: &'static str,
+    /// Hash of the params of the request other than its session, wallet and key
+    pub params_hash: String,
+}
+
+impl IdempotentRequest {
+    /// Key under which the request is recorded in its wallet, which only the requests to the same
+    /// wallet with the same idempotency key share, whatever their session.
+    pub fn record_key(&self) -> String {
+        let id = format!("{}\n{}", self.wallet_id, self.key);
+
+        hex::encode(calculate_sha256(id.as_bytes()))
+    }
+}
+
+/// Check that an idempotency key is not empty, not too long, and only made of printable ASCII
+/// characters.
+pub fn validate_key(key: &str) -> Result<(), String> {
+    if key.is_empty() || key.len() > MAX_KEY_LENGTH {
+        Err(format!(
+            "Idempotency keys must have between 1 and {} characters",
+            MAX_KEY_LENGTH
+        ))
+    } else if !key.bytes().all(|byte| byte.is_ascii_graphic()) {
+        Err("Idempotency keys can only have printable ASCII characters".to_string())
+    } else {
+        Ok(())
+    }
+}
+
+/// Hash of the params of a request, which does not depend on the order of their fields.
+pub fn params_hash(params: &Map<String, Value>) -> String {
+    let canonical = canonical(&Value::Object(params.clone())).to_string();
+
+    hex::encode(calculate_sha256(canonical.as_bytes()))
+}
+
+/// Copy of a JSON value whose objects have their fields sorted.
+fn canonical(value: &Value) -> Value {
+    match value {
+        Value::Object(fields) => {
+            let mut sorted: Vec<_> = fields.iter().collect();
+            sorted.sort_by(|a, b| a.0.cmp(b.0));
+
+            Value::Object(
+                sorted
+                    .into_iter()
+                    .map(|(name, value)| (name.clone(), canonical(value)))
+                    .collect(),
+            )
+        }
+        Value::Array(values) => Value::Array(values.iter().map(canonical).collect()),
+        value => value.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn object(value: Value) -> Map<String, Value> {
+        match value {
+            Value::Object(fields) => fields,
+            _ => panic!("Expected an object"),
+        }
+    }
+
+    #[test]
+    fn params_hashes_ignore_the_order_of_fields() {
+        let params =
+            object(json!({ "fee": "1", "outputs": [{ "address": "wit1", "amount": "2" }] }));
+        let reordered =
+            object(json!({ "outputs": [{ "amount": "2", "address": "wit1" }], "fee": "1" }));
+        let mutated =
+            object(json!({ "fee": "1", "outputs": [{ "address": "wit1", "amount": "3" }] }));
+
+        assert_eq!(params_hash(&params), params_hash(&reordered));
+        assert_ne!(params_hash(&params), params_hash(&mutated));
+    }
+
+    #[test]
+    fn keys_are_recorded_per_wallet() {
+        let request = |session_id: &str, wallet: char, key: &str| IdempotentRequest {
+            session_id: SessionId::from(session_id.to_string()),
+            wallet_id: wallet.to_string().repeat(64).parse().unwrap(),
+            key: key.to_string(),
+            method: "send_transaction",
+            params_hash: String::new(),
+        };
+
+        assert_eq!(
+            request("alice", '0', "key").record_key(),
+            request("bob", '0', "key").record_key()
+        );
+        assert_ne!(
+            request("alice", '0', "key").record_key(),
+            request("alice", '1', "key").record_key()
+        );
+        assert_ne!(
+            request("alice", '0', "key").record_key(),
+            request("alice", '0', "other key").record_key()
+        );
+    }
+
+    #[test]
+    fn malformed_keys_are_rejected() {
+        assert!(validate_key("4f8b2c8e-retry-1").is_ok());
+        assert!(validate_key("").is_err());
+        assert!(validate_key(&"a".repeat(MAX_KEY_LENGTH + 1)).is_err());
+        assert!(validate_key("with space").is_err());
+        assert!(validate_key("wället").is_err());
+    }
+}
@@ -6,6 +6,9 @@
 //! var sock= (() => { let s = new WebSocket('ws://localhost:3030');s.addEventListener('message', (e) => {  console.log('Rcv =>', e.data) });return s; })();
 //! sock.send('{"jsonrpc":"2.0","method":"getBlockChain","id":"1"}');
 //! ```
+//!
+//! Clients that cannot use WebSockets can POST the same JSON-RPC requests to the HTTP server, if
+//! `wallet.http_server_addr` is configured. Subscriptions are only available through WebSockets.
 
 #![deny(rust_2018_idioms)]
 #![deny(non_upper_case_globals)]
@@ -14,6 +17,7 @@
 #![deny(unused_mut)]
 #![deny(missing_docs)]
 
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex, RwLock};
 use std::time::Duration;
 
@@ -22,31 +26,162 @@ use failure::Error;
 
 use witnet_config::config::Config;
 use witnet_data_structures::chain::{CheckpointBeacon, EpochConstants};
-use witnet_net::client::tcp::JsonRpcClient;
+use witnet_net::client::tcp::{jsonrpc::Subscribe, JsonRpcClient};
 use witnet_validations::witnessing::validate_witnessing_config;
 
 use crate::actors::app;
 
 mod account;
 mod actors;
+mod address_metadata;
+mod api_version;
+mod clock;
+mod coin_selection;
 mod constants;
+mod credentials;
 mod crypto;
 mod db;
+mod deadline;
+mod diagnostics;
+mod failover;
+mod idempotency;
+mod locks;
+mod metrics;
 mod model;
 mod params;
+mod pool;
+mod queues;
 mod repository;
+mod request_limits;
+mod retrieval_benchmark;
+mod schema;
 mod signal;
+mod simulation;
+mod sink;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
 mod types;
+mod watchdog;
+mod witness_signing;
 
 /// Run the Witnet wallet application.
 pub fn run(conf: Config) -> Result<(), Error> {
+    let rocksdb_opts = conf.rocksdb.to_rocksdb_options();
+
+    let system = System::new();
+
+    let db = Arc::new(
+        ::rocksdb::DB::open(
+            &rocksdb_opts,
+            conf.wallet.db_path.join(&conf.wallet.db_file_name),
+        )
+        .map_err(|e| failure::format_err!("{}", e))?,
+    );
+    // Databases written by newer versions of the wallet are never opened
+    repository::Wallets::new(db::PlainDb::new(db.clone())).migrate_schema()?;
+
+    // Initialize actors inside system context
+    system.block_on(async {
+        let node_subscriptions = Arc::new(Mutex::new(Default::default()));
+        let node_client = connect_to_nodes(&conf, node_subscriptions.clone())?;
+
+        // Trigger connection validation
+        node_client.valid_connection().await;
+
+        let app = start(
+            &conf,
+            db.clone(),
+            node_client,
+            node_subscriptions,
+            clock::Clock::System,
+        )?;
+
+        // Intercept SIGTERM signal to gracefully close the wallet
+        signal::ctrl_c(move || {
+            app.do_send(actors::app::Shutdown);
+        });
+
+        Result::<(), Error>::Ok(())
+    })?;
+    system.run()?;
+
+    log::info!("Waiting for db to shut down...");
+    while Arc::strong_count(&db) > 1 {
+        std::thread::sleep(Duration::from_millis(500));
+    }
+    log::info!("Db shut down finished.");
+
+    Ok(())
+}
+
+/// Connect to the first of the configured nodes through a pool of JSON-RPC clients, which fail
+/// over to the next ones when the App actor tells them to.
+///
+/// This must be called from inside a running actix system.
+fn connect_to_nodes(
+    conf: &Config,
+    node_subscriptions: Arc<Mutex<HashMap<String, Subscribe>>>,
+) -> Result<Arc<app::NodeClient>, Error> {
+    let node_urls = conf.wallet.node_url.clone();
+    if node_urls.is_empty() {
+        return Err(app::Error::NodeNotConnected.into());
+    }
+
+    // Connections to the node that forwarded requests are spread over
+    let node_pool_size = conf.wallet.node_pool_size.max(1);
+
+    // Hysteresis of the failover between nodes
+    let failover = failover::NodeFailover::new(
+        node_urls,
+        conf.wallet.node_failover_threshold,
+        i64::try_from(conf.wallet.node_failover_min_interval).unwrap_or(i64::MAX),
+    );
+
+    // The JSON-RPC clients are only given the active URL, so they never switch to another node by
+    // themselves: failing over is decided by the health checks of the App actor. Only the first
+    // one is given the subscriptions, so that notifications are not received twice.
+    let connections = (0..node_pool_size)
+        .map(|index| {
+            let subscriptions = if index == 0 {
+                node_subscriptions.clone()
+            } else {
+                Default::default()
+            };
+
+            JsonRpcClient::start_with_subscriptions(
+                vec![failover.active_url().to_string()],
+                subscriptions,
+            )
+            .map(app::NodeConnection::new)
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| app::Error::NodeNotConnected)?;
+
+    Ok(Arc::new(app::NodeClient {
+        pool: pool::ConnectionPool::new(connections),
+        failover: Mutex::new(failover),
+        schema: schema::SchemaValidator::new(conf.wallet.strict_node_schema),
+    }))
+}
+
+/// Start the worker and the App actor on top of an open database and a client of the node.
+///
+/// This must be called from inside a running actix system.
+fn start(
+    conf: &Config,
+    db: Arc<::rocksdb::DB>,
+    node_client: Arc<app::NodeClient>,
+    node_subscriptions: Arc<Mutex<HashMap<String, Subscribe>>>,
+    clock: clock::Clock,
+) -> Result<Addr<actors::App>, Error> {
     let session_expires_in = Duration::from_secs(conf.wallet.session_expires_in);
     let requests_timeout = Duration::from_millis(conf.wallet.requests_timeout);
     let server_addr = conf.wallet.server_addr;
-    let db_path = conf.wallet.db_path;
-    let db_file_name = conf.wallet.db_file_name;
-    let node_urls = conf.wallet.node_url;
-    let rocksdb_opts = conf.rocksdb.to_rocksdb_options();
+    let http_server_addr = conf.wallet.http_server_addr;
+    let cors = witnet_net::server::Cors {
+        allowed_origins: conf.wallet.cors_allowed_origins.clone(),
+        allow_credentials: conf.wallet.cors_allow_credentials,
+    };
     let epoch_constants = EpochConstants {
         checkpoint_zero_timestamp: conf.consensus_constants.checkpoint_zero_timestamp,
         checkpoints_period: conf.consensus_constants.checkpoints_period,
@@ -63,14 +198,10 @@ pub fn run(conf: Config) -> Result<(), Error> {
     let testnet = conf.wallet.testnet;
 
     // Master-key generation params
-    let seed_password = conf.wallet.seed_password;
-    let master_key_salt = conf.wallet.master_key_salt;
+    let seed_password = conf.wallet.seed_password.clone();
+    let master_key_salt = conf.wallet.master_key_salt.clone();
     let id_hash_iterations = conf.wallet.id_hash_iterations;
-    let id_hash_function = conf.wallet.id_hash_function;
-
-    // Weights
-    let max_vt_weight = conf.consensus_constants.max_vt_weight;
-    let max_dr_weight = conf.consensus_constants.max_dr_weight;
+    let id_hash_function = conf.wallet.id_hash_function.clone();
 
     // Wallet concurrency
     let concurrency = conf.wallet.concurrency.unwrap_or_else(num_cpus::get);
@@ -84,96 +215,237 @@ pub fn run(conf: Config) -> Result<(), Error> {
     // Allow to use outputs that have not been confirmed by a superblock in new transactions
     let use_unconfirmed_utxos = conf.wallet.use_unconfirmed_utxos;
 
-    let system = System::new();
-
-    let consensus_constants = conf.consensus_constants;
+    let consensus_constants = conf.consensus_constants.clone();
 
     let pending_transactions_timeout_seconds = conf.wallet.pending_transactions_timeout_seconds;
 
-    let db = Arc::new(
-        ::rocksdb::DB::open(&rocksdb_opts, db_path.join(db_file_name))
-            .map_err(|e| failure::format_err!("{}", e))?,
+    // Whether the stateless key derivation method is exposed
+    let enable_derive_addresses = conf.wallet.enable_derive_addresses;
+
+    // Bound of the notifications queue of every client subscription
+    let notifications_queue_size = conf.wallet.notifications_queue_size;
+
+    // Notifications kept per session for clients that resubscribe after a disconnection
+    let notifications_replay_size = conf.wallet.notifications_replay_size;
+
+    // Local cache of data source responses for `run_rad_request`
+    let retrieval_cache_enabled = conf.wallet.retrieval_cache_enabled;
+    let retrieval_cache_ttl = i64::try_from(conf.wallet.retrieval_cache_ttl).unwrap_or(i64::MAX);
+    let retrieval_cache_max_size = conf.wallet.retrieval_cache_max_size;
+    let verify_block_headers = conf.wallet.verify_block_headers;
+    let seed_backup_confirmation_attempts = conf.wallet.seed_backup_confirmation_attempts;
+    let check_integrity_on_unlock = conf.wallet.check_integrity_on_unlock;
+    let address_index_max_entries =
+        usize::try_from(conf.wallet.address_index_max_entries).unwrap_or(usize::MAX);
+
+    // Fee policy of the transactions created by the wallet
+    let minimum_fee = conf.wallet.minimum_fee;
+    let minimum_fee_per_kilo_weight = conf.wallet.minimum_fee_per_kilo_weight;
+    let dust_threshold = conf.wallet.dust_threshold;
+    let changeless_tolerance = conf.wallet.changeless_tolerance;
+    let changeless_search_budget = conf.wallet.changeless_search_budget;
+    let refuse_addresses_beyond_gap_limit = conf.wallet.refuse_addresses_beyond_gap_limit;
+
+    // Resolution of the balance history of the wallets
+    let balance_history_granularity = conf.wallet.balance_history_granularity.max(1);
+    let balance_history_daily_after =
+        u64::from(conf.wallet.balance_history_daily_after) * 24 * 60 * 60;
+
+    // Pruning of the old movement metadata of the wallets
+    let prune_interval = conf.wallet.prune_interval.map(Duration::from_secs);
+    let prune_archive_path = conf
+        .wallet
+        .prune_archive_path
+        .clone()
+        .unwrap_or_else(|| conf.wallet.db_path.join("pruned"));
+
+    // Limits of the work queued for each pool of worker threads
+    let work_queues = queues::WorkQueues::new(
+        conf.wallet.interactive_queue_limit,
+        conf.wallet.block_queue_limit,
+        conf.wallet.maintenance_queue_limit,
+    );
+    let request_limits = request_limits::RequestLimits::new(
+        conf.wallet.session_request_limit,
+        conf.wallet.session_heavy_request_limit,
+        conf.wallet.heavy_request_limit,
     );
 
+    // Derivation of the accounts of new wallets, unless overridden when creating them
+    let key_derivation = model::KeyDerivation {
+        key_spec: conf.wallet.key_spec,
+        coin_type: conf.wallet.coin_type,
+    };
+
+    // Outboxes of the events posted to the webhooks of the wallets
+    let webhook_outbox_size =
+        usize::try_from(conf.wallet.webhook_outbox_size).unwrap_or(usize::MAX);
+    let webhook_max_attempts = conf.wallet.webhook_max_attempts;
+    let webhook_retry_delay = i64::try_from(conf.wallet.webhook_retry_delay).unwrap_or(i64::MAX);
+
+    // Limits on the number of concurrent sessions
+    let session_limits = actors::app::SessionLimits {
+        per_wallet: conf.wallet.max_sessions_per_wallet,
+        total: conf.wallet.max_sessions,
+        policy: conf.wallet.session_limit_policy,
+    };
+
+    // Recipients of a single value transfer transaction
+    let max_vtt_outputs = usize::try_from(conf.wallet.max_vtt_outputs).unwrap_or(usize::MAX);
+
+    // Cost of the scripts of a data request from which it is reported as expensive
+    let data_request_cost_warning_threshold = conf.wallet.data_request_cost_warning_threshold;
+
+    // Clients declaring an older API version are told to upgrade
+    let min_client_api_version = conf.wallet.min_client_api_version;
+
+    // Retries of requests made with an idempotency key are answered for this long
+    let idempotency_key_retention = Duration::from_secs(conf.wallet.idempotency_key_retention);
+
+    // Retrievals of the benchmarks of data sources time out as those of witnesses do
+    let retrieval_timeout =
+        Some(conf.mining.data_request_timeout).filter(|timeout| !timeout.is_zero());
+
+    // Epochs without progress or ahead of the clock after which the node is reported
+    let node_stall_epochs = conf.wallet.node_stall_epochs;
+    let node_clock_skew_epochs = conf.wallet.node_clock_skew_epochs;
+
+    // Global idle timer that locks every wallet
+    let idle_lock_after = conf.wallet.idle_lock_after.map(Duration::from_secs);
+
+    // Execution deadlines of long-running methods
+    let execution_timeouts = conf
+        .wallet
+        .execution_timeouts
+        .iter()
+        .map(|(method, millis)| (method.clone(), Duration::from_millis(*millis)))
+        .collect();
+
+    // Configuration included in diagnostics bundles, which never carries its secrets
+    let redacted_config = diagnostics::redacted_config(conf)?;
+
+    // Wallets to unlock at startup, and the key that decrypts their encrypted passwords
+    let credentials = conf
+        .wallet
+        .unlock_credentials_path
+        .as_deref()
+        .map(credentials::load)
+        .transpose()?;
+    let credentials_key = credentials.as_ref().and_then(|_| {
+        std::env::var(constants::CREDENTIALS_KEY_ENV_VAR)
+            .ok()
+            .map(types::Password::new)
+    });
+
     // Run setup logic for smart retrievals, aka paranoid witnessing.
-    let witnessing_config = conf.witnessing.into_config();
+    let witnessing_config = conf.witnessing.clone().into_config();
     let witnessing_config = validate_witnessing_config(&witnessing_config)?;
 
-    // Initialize actors inside system context
-    system.block_on(async {
-        let node_subscriptions = Arc::new(Mutex::new(Default::default()));
-        let node_client_actor =
-            JsonRpcClient::start_with_subscriptions(node_urls.clone(), node_subscriptions.clone())
-                .map_err(|_| app::Error::NodeNotConnected)?;
-        let node_client = Arc::new(app::NodeClient {
-            actor: node_client_actor,
-        });
+    let params = params::Params {
+        testnet,
+        seed_password,
+        master_key_salt,
+        id_hash_iterations,
+        id_hash_function,
+        db_hash_iterations,
+        db_iv_length,
+        db_salt_length,
+        epoch_constants,
+        node_sync_batch_size,
+        genesis_hash,
+        genesis_prev_hash,
+        sync_address_batch_length,
+        consensus_constants: consensus_constants.clone(),
+        use_unconfirmed_utxos,
+        pending_transactions_timeout_seconds,
+        witnessing: witnessing_config,
+        retrieval_cache_enabled,
+        retrieval_cache_ttl,
+        retrieval_cache_max_size,
+        verify_block_headers,
+        seed_backup_confirmation_attempts,
+        check_integrity_on_unlock,
+        address_index_max_entries,
+        minimum_fee,
+        minimum_fee_per_kilo_weight,
+        dust_threshold,
+        changeless_tolerance,
+        changeless_search_budget,
+        refuse_addresses_beyond_gap_limit,
+        balance_history_granularity,
+        balance_history_daily_after,
+        prune_archive_path,
+        key_derivation,
+        webhook_outbox_size,
+        webhook_max_attempts,
+        webhook_retry_delay,
+    };
 
-        // Trigger connection validation. Due to how the JSON-RPC client works, it will keep
-        // retrying connection using a different URL each time.
-        node_client.valid_connection().await;
+    let last_beacon = Arc::new(RwLock::new(CheckpointBeacon {
+        checkpoint: 0,
+        hash_prev_block: genesis_prev_hash,
+    }));
+    let network = String::from(if testnet { "Testnet" } else { "Mainnet" });
+    let node_params = params::NodeParams {
+        client: node_client.clone(),
+        last_beacon,
+        network,
+        requests_timeout,
+        subscriptions: node_subscriptions,
+    };
 
-        let params = params::Params {
-            testnet,
-            seed_password,
-            master_key_salt,
-            id_hash_iterations,
-            id_hash_function,
-            db_hash_iterations,
-            db_iv_length,
-            db_salt_length,
-            epoch_constants,
-            node_sync_batch_size,
-            genesis_hash,
-            genesis_prev_hash,
-            sync_address_batch_length,
-            max_vt_weight,
-            max_dr_weight,
-            consensus_constants: consensus_constants.clone(),
-            use_unconfirmed_utxos,
-            pending_transactions_timeout_seconds,
-            witnessing: witnessing_config,
-        };
-
-        let last_beacon = Arc::new(RwLock::new(CheckpointBeacon {
-            checkpoint: 0,
-            hash_prev_block: genesis_prev_hash,
-        }));
-        let network = String::from(if testnet { "Testnet" } else { "Mainnet" });
-        let node_params = params::NodeParams {
-            client: node_client.clone(),
-            last_beacon,
-            network,
-            requests_timeout,
-            subscriptions: node_subscriptions,
-        };
-
-        // Start wallet actors
-        let worker = actors::Worker::start(concurrency, db.clone(), node_params, params);
-        let app = actors::App::start(actors::app::Params {
-            testnet,
-            worker,
-            client: node_client,
-            server_addr,
-            session_expires_in,
-            requests_timeout,
-            consensus_constants,
-        });
+    // Start wallet actors
+    let block_processing = metrics::SharedBlockProcessing::default();
+    let webhooks = actors::Webhooks::start(&Arbiter::new().handle())?;
+    let workers = actors::Worker::start(
+        concurrency,
+        db,
+        node_params,
+        params,
+        block_processing.clone(),
+        webhooks.clone(),
+    );
+    let app = actors::App::start(actors::app::Params {
+        testnet,
+        worker: workers.interactive,
+        block_worker: workers.blocks,
+        maintenance_worker: workers.maintenance,
+        work_queues,
+        request_limits,
+        client: node_client,
+        server_addr,
+        http_server_addr,
+        cors,
+        session_expires_in,
+        requests_timeout,
+        consensus_constants,
+        enable_derive_addresses,
+        key_derivation,
+        notifications_queue_size,
+        notifications_replay_size,
+        execution_timeouts,
+        session_limits,
+        idle_lock_after,
+        prune_interval,
+        clock,
+        node_stall_epochs,
+        node_clock_skew_epochs,
+        max_vtt_outputs,
+        data_request_cost_warning_threshold,
+        min_client_api_version,
+        idempotency_key_retention,
+        retrieval_timeout,
+        block_processing,
+        webhooks,
+        redacted_config,
+    });
 
-        // Intercept SIGTERM signal to gracefully close the wallet
-        signal::ctrl_c(move || {
-            app.do_send(actors::app::Shutdown);
+    if let Some(credentials) = credentials {
+        app.do_send(actors::app::UnlockServiceSession {
+            credentials,
+            key: credentials_key,
         });
-
-        Result::<(), Error>::Ok(())
-    })?;
-    system.run()?;
-
-    log::info!("Waiting for db to shut down...");
-    while Arc::strong_count(&db) > 1 {
-        std::thread::sleep(Duration::from_millis(500));
     }
-    log::info!("Db shut down finished.");
 
-    Ok(())
+    Ok(app)
 }
@@ -0,0 +1,256 @@
+//! Locks that serialize the operations on the same wallet across the threads of the worker.
+//!
+//! Operations that rewrite the storage of a wallet (creating it over an existing one, deleting it)
+//! take an exclusive lock on its id, while operations that only read it or add to it (unlocking,
+//! synchronizing) take a shared one, so that they never observe a half-written wallet. Locks are
+//! only waited for up to a timeout, after which the operation fails naming the one holding it.
+use std::{
+    collections::HashMap,
+    sync::{Arc, Condvar, Mutex},
+    time::{Duration, Instant},
+};
+
+use failure::Fail;
+
+use crate::model;
+
+/// A wallet stayed locked by another operation for longer than the lock timeout.
+#[derive(Clone, Debug, PartialEq, Eq, Fail)]
+#[fail(display = "wallet {} is busy with {}", wallet_id, operation)]
+pub struct Busy {
+    pub wallet_id: String,
+    /// Name of the operation holding the lock.
+    pub operation: &'static str,
+}
+
+/// Operations holding the lock of a wallet.
+#[derive(Debug, Default)]
+struct Holders {
+    exclusive: Option<&'static str>,
+    shared: Vec<&'static str>,
+}
+
+impl Holders {
+    fn is_empty(&self) -> bool {
+        self.exclusive.is_none() && self.shared.is_empty()
+    }
+
+    /// Operation that prevents taking the lock, if any.
+    fn conflict(&self, exclusive: bool) -> Option<&'static str> {
+        match self.exclusive {
+            Some(operation) => Some(operation),
+            None if exclusive => self.shared.first().copied(),
+            None => None,
+        }
+    }
+}
+
+/// Locks of the wallets that are being operated on, by wallet id.
+///
+/// Entries only exist while some operation holds the lock of their wallet.
+#[derive(Debug)]
+pub struct WalletLocks {
+    holders: Mutex<HashMap<model::WalletId, Holders>>,
+    released: Condvar,
+    timeout: Duration,
+}
+
+impl WalletLocks {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            holders: Mutex::new(HashMap::new()),
+            released: Condvar::new(),
+            timeout,
+        }
+    }
+
+    /// Lock a wallet for an operation that no other operation can run along with.
+    pub fn exclusive(
+        self: &Arc<Self>,
+        wallet_id: &model::WalletId,
+        operation: &'static str,
+    ) -> Result<WalletLockGuard, Busy> {
+        self.acquire(wallet_id, operation, true)
+    }
+
+    /// Lock a wallet for an operation that can run along with other shared ones.
+    pub fn shared(
+        self: &Arc<Self>,
+        wallet_id: &model::WalletId,
+        operation: &'static str,
+    ) -> Result<WalletLockGuard, Busy> {
+        self.acquire(wallet_id, operation, false)
+    }
+
+    fn acquire(
+        self: &Arc<Self>,
+        wallet_id: &model::WalletId,
+        operation: &'static str,
+        exclusive: bool,
+    ) -> Result<WalletLockGuard, Busy> {
+        let deadline = Instant::now() + self.timeout;
+        let mut holders = self.holders.lock().expect("wallet locks poisoned");
+
+        loop {
+            let conflict = holders
+                .get(wallet_id)
+                .and_then(|wallet| wallet.conflict(exclusive));
+            let conflict = match conflict {
+                Some(conflict) => conflict,
+                None => break,
+            };
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining == Duration::from_secs(0) {
+                log::warn!(
+                    "Gave up on locking wallet {} for {}, it is busy with {}",
+                    wallet_id,
+                    operation,
+                    conflict
+                );
+
+                return Err(Busy {
+                    wallet_id: wallet_id.to_string(),
+                    operation: conflict,
+                });
+            }
+            holders = self
+                .released
+                .wait_timeout(holders, remaining)
+                .expect("wallet locks poisoned")
+                .0;
+        }
+
+        let wallet = holders.entry(wallet_id.clone()).or_default();
+        if exclusive {
+            wallet.exclusive = Some(operation);
+        } else {
+            wallet.shared.push(operation);
+        }
+
+        Ok(WalletLockGuard {
+            locks: self.clone(),
+            wallet_id: wallet_id.clone(),
+            operation,
+            exclusive,
+        })
+    }
+
+    fn release(&self, wallet_id: &model::WalletId, operation: &'static str, exclusive: bool) {
+        let mut holders = self.holders.lock().expect("wallet locks poisoned");
+        if let Some(wallet) = holders.get_mut(wallet_id) {
+            if exclusive {
+                wallet.exclusive = None;
+            } else if let Some(index) = wallet.shared.iter().position(|held| *held == operation) {
+                wallet.shared.swap_remove(index);
+            }
+            if wallet.is_empty() {
+                holders.remove(wallet_id);
+            }
+        }
+        drop(holders);
+
+        self.released.notify_all();
+    }
+}
+
+/// Lock of a wallet, which is released when dropped.
+#[derive(Debug)]
+pub struct WalletLockGuard {
+    locks: Arc<WalletLocks>,
+    wallet_id: model::WalletId,
+    operation: &'static str,
+    exclusive: bool,
+}
+
+impl Drop for WalletLockGuard {
+    fn drop(&mut self) {
+        self.locks
+            .release(&self.wallet_id, self.operation, self.exclusive);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use super::*;
+
+    fn locked_wallets(locks: &WalletLocks) -> usize {
+        locks.holders.lock().unwrap().len()
+    }
+
+    fn wallet_id() -> model::WalletId {
+        "0123456789abcdef".parse().unwrap()
+    }
+
+    #[test]
+    fn shared_locks_run_together() {
+        let locks = Arc::new(WalletLocks::new(Duration::from_millis(10)));
+
+        let unlock = locks.shared(&wallet_id(), "unlock_wallet").unwrap();
+        let sync = locks.shared(&wallet_id(), "sync").unwrap();
+
+        assert_eq!(
+            locks.exclusive(&wallet_id(), "create_wallet").unwrap_err(),
+            Busy {
+                wallet_id: wallet_id().to_string(),
+                operation: "unlock_wallet",
+            }
+        );
+        drop(unlock);
+        drop(sync);
+        assert!(locks.exclusive(&wallet_id(), "create_wallet").is_ok());
+    }
+
+    #[test]
+    fn exclusive_lock_names_the_operation_holding_it() {
+        let locks = Arc::new(WalletLocks::new(Duration::from_millis(10)));
+
+        let _create = locks.exclusive(&wallet_id(), "create_wallet").unwrap();
+
+        assert_eq!(
+            locks.shared(&wallet_id(), "unlock_wallet").unwrap_err(),
+            Busy {
+                wallet_id: wallet_id().to_string(),
+                operation: "create_wallet",
+            }
+        );
+        // Other wallets are not affected
+        assert!(locks
+            .exclusive(&"fedcba9876543210".parse().unwrap(), "delete_wallet")
+            .is_ok());
+    }
+
+    #[test]
+    fn waiting_operation_runs_once_the_lock_is_released() {
+        let locks = Arc::new(WalletLocks::new(Duration::from_secs(10)));
+        let create = locks.exclusive(&wallet_id(), "create_wallet").unwrap();
+
+        let waiting = {
+            let locks = locks.clone();
+            thread::spawn(move || locks.shared(&wallet_id(), "unlock_wallet").map(|_| ()))
+        };
+        thread::sleep(Duration::from_millis(50));
+        drop(create);
+
+        assert_eq!(waiting.join().unwrap(), Ok(()));
+    }
+
+    #[test]
+    fn released_locks_leave_no_entries() {
+        let locks = Arc::new(WalletLocks::new(Duration::from_millis(10)));
+
+        {
+            let _unlock = locks.shared(&wallet_id(), "unlock_wallet").unwrap();
+            let _other = locks
+                .exclusive(&"fedcba9876543210".parse().unwrap(), "delete_wallet")
+                .unwrap();
+            assert_eq!(locked_wallets(&locks), 2);
+        }
+        assert_eq!(locked_wallets(&locks), 0);
+
+        locks.exclusive(&wallet_id(), "create_wallet").unwrap();
+        assert_eq!(locked_wallets(&locks), 0);
+    }
+}
@@ -0,0 +1,300 @@
+//! Latency metrics of the round-trips between the `App` actor and the storage worker, usage of the
+//! in-memory indexes kept by the worker, counters of the processing of notified blocks, depth of the
+//! queues of work of the worker, requests in flight, and the log of the last requests handled by the
+//! JSON-RPC server.
+use std::{
+    collections::{BTreeMap, VecDeque},
+    future::Future,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use futures::FutureExt;
+use serde::Serialize;
+
+use crate::types;
+
+/// Round-trips slower than this are logged as warnings.
+const SLOW_ROUND_TRIP: Duration = Duration::from_secs(1);
+
+/// Aggregated latency of storage round-trips.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct StorageLatency {
+    /// Number of round-trips recorded.
+    pub count: u64,
+    /// Sum of the latencies of all the recorded round-trips, in microseconds.
+    pub total_micros: u64,
+    /// Latency of the slowest recorded round-trip, in microseconds.
+    pub max_micros: u64,
+    /// Latency of the last recorded round-trip, in microseconds.
+    pub last_micros: u64,
+}
+
+impl StorageLatency {
+    /// Record the latency of a round-trip.
+    pub fn record(&mut self, elapsed: Duration) {
+        let micros = u64::try_from(elapsed.as_micros()).unwrap_or(u64::MAX);
+        self.count = self.count.saturating_add(1);
+        self.total_micros = self.total_micros.saturating_add(micros);
+        self.max_micros = self.max_micros.max(micros);
+        self.last_micros = micros;
+    }
+
+    /// Mean latency of the recorded round-trips, in microseconds.
+    pub fn mean_micros(&self) -> u64 {
+        self.total_micros.checked_div(self.count).unwrap_or(0)
+    }
+}
+
+/// Usage of the in-memory index of the addresses of an unlocked wallet.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct AddressIndexUsage {
+    /// Number of addresses held by the index.
+    pub addresses: usize,
+    /// Maximum number of addresses that the index can hold.
+    pub max_addresses: usize,
+    /// Estimated memory taken by the index, in bytes.
+    pub approx_bytes: usize,
+    /// Whether the index holds every address of the wallet. If not, the addresses missing from it
+    /// are looked up in the database.
+    pub complete: bool,
+}
+
+/// Shareable handle to the storage latency metrics.
+pub type SharedStorageLatency = Arc<Mutex<StorageLatency>>;
+
+/// Counters of the processing of the blocks notified by the node.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct BlockProcessing {
+    /// Blocks deserialized and scanned, once for all the unlocked wallets.
+    pub blocks_scanned: u64,
+    /// Times that a wallet walked the transactions of a block, because its scan found something
+    /// that may belong to the wallet.
+    pub wallet_blocks_walked: u64,
+    /// Times that a wallet indexed a block without walking its transactions, because its scan
+    /// found nothing that belongs to the wallet.
+    pub wallet_blocks_skipped: u64,
+    /// Times that a block was not processed by a wallet because it had already been applied to it,
+    /// e.g. when the node delivered it again.
+    pub wallet_blocks_duplicated: u64,
+}
+
+/// Shareable handle to the block processing counters.
+pub type SharedBlockProcessing = Arc<Mutex<BlockProcessing>>;
+
+/// Depth of the queue of a class of work of the worker.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct WorkQueue {
+    /// Work queued or running.
+    pub depth: usize,
+    /// Depth past which work is refused.
+    pub limit: usize,
+    /// Work refused or dropped because the queue was full.
+    pub shed: u64,
+}
+
+/// Depth of the queues of each class of work of the worker.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct WorkQueues {
+    /// Requests of clients.
+    pub interactive: WorkQueue,
+    /// Blocks and superblocks notified by the node.
+    pub blocks: WorkQueue,
+    /// Synchronizations and periodic jobs.
+    pub maintenance: WorkQueue,
+}
+
+/// Requests of clients in flight, as limited for each session.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RequestsInFlight {
+    /// Heavy requests in flight in all the sessions.
+    pub heavy: usize,
+    /// Light requests in flight in all the sessions.
+    pub light: usize,
+    /// Heavy requests past which all the sessions are refused more.
+    pub heavy_limit: usize,
+    /// Sessions with requests in flight.
+    pub sessions: usize,
+    /// Requests refused because a limit was reached.
+    pub rejected: u64,
+}
+
+/// A request handled by the JSON-RPC server.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RequestLogEntry {
+    /// Seconds since the Unix epoch when the request was answered.
+    pub timestamp: i64,
+    pub method: String,
+    /// Time taken to answer the request, in milliseconds.
+    pub duration_millis: u64,
+    /// Error with which the request was answered, if it failed.
+    pub error: Option<RequestError>,
+    /// Session in which the request was made, which is never written to diagnostics bundles.
+    #[serde(skip)]
+    pub session_id: Option<types::SessionId>,
+}
+
+/// Error with which a request was answered.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct RequestError {
+    pub code: i64,
+    pub message: String,
+    pub data: Option<serde_json::Value>,
+}
+
+/// Counters of the requests handled by the JSON-RPC server since the wallet started.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct RequestCounters {
+    /// Requests handled.
+    pub requests: u64,
+    /// Requests answered with an error.
+    pub errors: u64,
+    /// Requests answered with an error, by error code.
+    pub errors_by_code: BTreeMap<i64, u64>,
+    /// Requests answered with an error, by method.
+    pub errors_by_method: BTreeMap<String, u64>,
+}
+
+/// The last requests handled by the JSON-RPC server, of which only the `capacity` most recent are
+/// kept, along with counters of all of them.
+#[derive(Debug, Default)]
+pub struct RequestLog {
+    entries: VecDeque<RequestLogEntry>,
+    capacity: usize,
+    counters: RequestCounters,
+}
+
+impl RequestLog {
+    /// Create a log that keeps the last `capacity` requests.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+            counters: RequestCounters::default(),
+        }
+    }
+
+    /// Record a handled request, evicting the oldest one if the log is full.
+    pub fn record(&mut self, entry: RequestLogEntry) {
+        self.counters.requests = self.counters.requests.saturating_add(1);
+        if let Some(error) = &entry.error {
+            self.counters.errors = self.counters.errors.saturating_add(1);
+            *self.counters.errors_by_code.entry(error.code).or_default() += 1;
+            *self
+                .counters
+                .errors_by_method
+                .entry(entry.method.clone())
+                .or_default() += 1;
+        }
+
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(entry);
+    }
+
+    /// The requests kept in the log, oldest first.
+    pub fn entries(&self) -> impl Iterator<Item = &RequestLogEntry> {
+        self.entries.iter()
+    }
+
+    /// Counters of all the requests recorded so far.
+    pub fn counters(&self) -> &RequestCounters {
+        &self.counters
+    }
+}
+
+/// Wrap a future of a storage round-trip so that its latency is recorded when it resolves.
+pub fn timed<F>(
+    metrics: SharedStorageLatency,
+    operation: &'static str,
+    fut: F,
+) -> impl Future<Output = F::Output>
+where
+    F: Future,
+{
+    let start = Instant::now();
+
+    fut.map(move |output| {
+        let elapsed = start.elapsed();
+        if elapsed >= SLOW_ROUND_TRIP {
+            log::warn!("Storage operation {} took {:?}", operation, elapsed);
+        } else {
+            log::trace!("Storage operation {} took {:?}", operation, elapsed);
+        }
+        metrics
+            .lock()
+            .expect("Metrics locks should only fail if poisoned")
+            .record(elapsed);
+
+        output
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_latencies() {
+        let mut latency = StorageLatency::default();
+        assert_eq!(latency.mean_micros(), 0);
+
+        latency.record(Duration::from_micros(10));
+        latency.record(Duration::from_micros(30));
+
+        assert_eq!(
+            latency,
+            StorageLatency {
+                count: 2,
+                total_micros: 40,
+                max_micros: 30,
+                last_micros: 30,
+            }
+        );
+        assert_eq!(latency.mean_micros(), 20);
+    }
+
+    #[test]
+    fn timed_future_records_latency() {
+        let metrics = SharedStorageLatency::default();
+        let output =
+            futures::executor::block_on(timed(metrics.clone(), "test", futures::future::ready(7)));
+
+        assert_eq!(output, 7);
+        assert_eq!(metrics.lock().unwrap().count, 1);
+    }
+
+    #[test]
+    fn request_log_is_bounded() {
+        let entry = |method: &str, code: Option<i64>| RequestLogEntry {
+            timestamp: 0,
+            method: method.to_string(),
+            duration_millis: 1,
+            error: code.map(|code| RequestError {
+                code,
+                message: "Validation Error".to_string(),
+                data: None,
+            }),
+            session_id: None,
+        };
+        let mut log = RequestLog::new(2);
+
+        log.record(entry("get_balance", None));
+        log.record(entry("create_vtt", Some(400)));
+        log.record(entry("create_vtt", Some(400)));
+        log.record(entry("unlock_wallet", Some(406)));
+
+        let methods: Vec<_> = log.entries().map(|entry| entry.method.as_str()).collect();
+        assert_eq!(methods, vec!["create_vtt", "unlock_wallet"]);
+        let counters = log.counters();
+        assert_eq!(counters.requests, 4);
+        assert_eq!(counters.errors, 3);
+        assert_eq!(counters.errors_by_code[&400], 2);
+        assert_eq!(counters.errors_by_code[&406], 1);
+        assert_eq!(counters.errors_by_method["create_vtt"], 2);
+    }
+}
@@ -1,12 +1,20 @@
 //! Types that are serializable and can be returned as a response.
-use std::{collections::HashMap, fmt};
+use std::{
+    borrow::Borrow, collections::HashMap, convert::TryFrom, fmt, ops::Deref, path::PathBuf,
+    str::FromStr,
+};
 
-use failure::_core::fmt::Formatter;
+use failure::Fail;
 use serde::{Deserialize, Serialize};
 
-use crate::{
-    account,
-    types::{number_from_string, u32_to_string, u64_to_string},
+use crate::types::{
+    human_readable_only, human_readable_only_or_default, i64_to_string, number_from_string,
+    option_number_from_string, option_u32_to_string, option_u64_to_string, u32_to_string,
+    u64_to_string,
+};
+use witnet_crypto::{
+    hash::{calculate_sha256, Sha256},
+    key::{KeyPath, KeySpec, UnknownKeySpec},
 };
 use witnet_data_structures::{
     chain::{DataRequestInfo, Hash, OutputPointer, PublicKeyHash, ValueTransferOutput},
@@ -14,10 +22,152 @@ use witnet_data_structures::{
 };
 use witnet_util::timestamp::get_timestamp;
 
+/// Number of hexadecimal digits of the ids of new wallets.
+pub const WALLET_ID_LENGTH: usize = 64;
+
+/// Maximum length of the ids of wallets created by older versions.
+pub const MAX_LEGACY_WALLET_ID_LENGTH: usize = 128;
+
+/// Id of a wallet, which is also the prefix of the keys of its records in the database.
+///
+/// New wallets get ids of `WALLET_ID_LENGTH` lowercase hexadecimal digits. Ids of wallets created
+/// by older versions can be any other combination of ASCII letters, digits, `-` and `_`, so that
+/// those wallets can still be opened, but path separators, whitespace and control characters are
+/// always rejected.
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct WalletId(String);
+
+impl WalletId {
+    /// Id of a new wallet, made of the given random bytes.
+    pub fn from_random_bytes(bytes: [u8; WALLET_ID_LENGTH / 2]) -> Self {
+        WalletId(hex::encode(bytes))
+    }
+
+    /// The id as a string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Whether the id does not follow the format of the ids of new wallets.
+    pub fn is_legacy(&self) -> bool {
+        self.0.len() != WALLET_ID_LENGTH
+            || !self
+                .0
+                .bytes()
+                .all(|byte| byte.is_ascii_digit() || (b'a'..=b'f').contains(&byte))
+    }
+}
+
+/// Error of an id that is not a valid wallet id.
+#[derive(Debug, Eq, Fail, PartialEq)]
+pub enum InvalidWalletId {
+    #[fail(display = "Wallet id is empty")]
+    Empty,
+    #[fail(
+        display = "Wallet id is longer than {} characters",
+        MAX_LEGACY_WALLET_ID_LENGTH
+    )]
+    TooLong,
+    #[fail(display = "Wallet id contains the invalid character {:?}", _0)]
+    InvalidCharacter(char),
+}
+
+impl FromStr for WalletId {
+    type Err = InvalidWalletId;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.is_empty() {
+            return Err(InvalidWalletId::Empty);
+        }
+        if s.len() > MAX_LEGACY_WALLET_ID_LENGTH {
+            return Err(InvalidWalletId::TooLong);
+        }
+        if let Some(c) = s
+            .chars()
+            .find(|c| !(c.is_ascii_alphanumeric() || *c == '-' || *c == '_'))
+        {
+            return Err(InvalidWalletId::InvalidCharacter(c));
+        }
+
+        Ok(WalletId(s.to_string()))
+    }
+}
+
+impl TryFrom<String> for WalletId {
+    type Error = InvalidWalletId;
+
+    fn try_from(id: String) -> Result<Self, Self::Error> {
+        id.parse()
+    }
+}
+
+impl From<WalletId> for String {
+    fn from(id: WalletId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for WalletId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl Deref for WalletId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for WalletId {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for WalletId {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct Wallet {
-    pub id: String,
+    pub id: WalletId,
     pub name: Option<String>,
+    pub pending_confirmation: bool,
+    /// Epoch beyond which the wallet does not process blocks, if its synchronization is pinned
+    #[serde(serialize_with = "option_u32_to_string")]
+    pub sync_target_epoch: Option<u32>,
+    /// Key spec that the addresses of the wallet are derived with
+    pub key_spec: KeySpec,
+    /// Keypath that the accounts of the wallet are derived from
+    pub base_path: String,
+}
+
+/// A page of the public info of the wallets stored in the database
+#[derive(Debug, Clone, Serialize)]
+pub struct WalletInfos {
+    pub infos: Vec<Wallet>,
+    /// Number of wallets, including those not in this page
+    pub total: u32,
+    /// Wallets in this page whose info could not be decoded, and were left out
+    pub warnings: Vec<String>,
+}
+
+/// Outcome of an attempt to confirm the seed backup of a wallet
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum SeedBackupConfirmation {
+    /// The words were right, the wallet can now be used
+    Confirmed,
+    /// Some of the words were wrong
+    WrongWords { remaining_attempts: u32 },
+    /// Some of the words were wrong and there were no attempts left, so the wallet was deleted
+    WalletDeleted,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -37,14 +187,101 @@ pub struct Address {
     pub account: u32,
     pub path: String,
     pub info: AddressInfo,
+    /// Account of another system that the address belongs to, if it was imported with one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub account_tag: Option<String>,
+    /// Last payment to the address after it was done with, if it was ever reused
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reuse: Option<AddressReuse>,
     #[serde(skip)]
     pub pkh: PublicKeyHash,
 }
 
+/// An address derived on demand from a seed, which is not tracked by any wallet.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct DerivedAddress {
+    pub address: String,
+    pub path: String,
+    pub public_key: String,
+}
+
 #[derive(Debug, Serialize)]
 pub struct Addresses {
     pub addresses: Vec<Address>,
     pub total: u32,
+    /// Cursor to get the next page with, if this one is not empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<AddressesCursor>,
+}
+
+/// Order of the items of a listing.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    Asc,
+    #[default]
+    Desc,
+}
+
+/// What the addresses of a keychain are listed by. Ties are broken by derivation index, so the
+/// order is always the same.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AddressesSort {
+    /// Derivation index
+    #[default]
+    Index,
+    /// Order in which the addresses were generated, which is the one of their derivation indices
+    Creation,
+    /// Label, with the addresses without a label first
+    Label,
+}
+
+/// Position in a listing of the addresses of a keychain, right after the last address that was
+/// returned. It is the derivation index of that address, which clients get as an opaque string of
+/// 8 hexadecimal digits.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct AddressesCursor {
+    pub index: u32,
+}
+
+/// Error of a string that is not a cursor returned by a listing.
+#[derive(Debug, Eq, Fail, PartialEq)]
+#[fail(display = "Cursor is not one returned by a previous page of the listing")]
+pub struct InvalidListingCursor;
+
+impl FromStr for AddressesCursor {
+    type Err = InvalidListingCursor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s.len() != 8 {
+            return Err(InvalidListingCursor);
+        }
+        let index = u32::from_str_radix(s, 16).map_err(|_| InvalidListingCursor)?;
+
+        Ok(AddressesCursor { index })
+    }
+}
+
+impl TryFrom<String> for AddressesCursor {
+    type Error = InvalidListingCursor;
+
+    fn try_from(cursor: String) -> Result<Self, Self::Error> {
+        cursor.parse()
+    }
+}
+
+impl From<AddressesCursor> for String {
+    fn from(cursor: AddressesCursor) -> Self {
+        cursor.to_string()
+    }
+}
+
+impl fmt::Display for AddressesCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}", self.index)
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
@@ -90,6 +327,48 @@ pub struct WalletBalance {
     pub local: u64,
     /// Total amount of wallet's funds after last block
     pub unconfirmed: BalanceInfo,
+    /// Funds locked as collateral of commits whose data requests have not been resolved yet
+    #[serde(
+        default,
+        serialize_with = "u64_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub collateralized: u64,
+}
+
+/// Balance of a wallet after a block that changed it, as recorded in its balance history
+#[derive(Copy, Clone, Debug, Eq, Default, Deserialize, PartialEq, Serialize)]
+pub struct BalanceHistoryEntry {
+    /// Epoch of the block
+    pub epoch: u32,
+    /// Timestamp of the epoch of the block, in seconds
+    pub timestamp: u64,
+    /// Total amount of wallet's funds (expendable or time-locked) confirmed by superblocks
+    #[serde(
+        serialize_with = "u64_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub confirmed: u64,
+    /// Change of the total amount of wallet's funds after the block, if it is not confirmed by a
+    /// superblock yet
+    #[serde(
+        serialize_with = "i64_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub pending_delta: i64,
+}
+
+/// How many entries of a balance history are kept over a period of time
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BalanceHistoryResolution {
+    /// Every block that changed the balance
+    #[default]
+    Block,
+    /// The last entry of every hour
+    Hourly,
+    /// The last entry of every day
+    Daily,
 }
 
 #[derive(Debug, Serialize)]
@@ -100,6 +379,182 @@ pub struct ExtendedKeyedSignature {
     pub chaincode: String,
 }
 
+/// Context in which data is signed with the master key of a wallet.
+///
+/// The tag of the domain is hashed together with the data, so that a signature made in one
+/// domain is never valid in another one, nor as the signature of a transaction or of any other
+/// protocol message.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SigningDomain {
+    /// Proving the ownership of the wallet or of its addresses to a third party.
+    ProofOfOwnership,
+    /// Signing a message chosen by the user.
+    ArbitraryMessage,
+}
+
+impl SigningDomain {
+    /// Bytes prepended to the data before hashing it. No tag is a prefix of another one.
+    pub fn tag(self) -> &'static [u8] {
+        match self {
+            SigningDomain::ProofOfOwnership => b"Witnet Signed Data/proof_of_ownership:",
+            SigningDomain::ArbitraryMessage => b"Witnet Signed Data/arbitrary_message:",
+        }
+    }
+}
+
+/// Digest that is signed for some data: the SHA256 of the domain tag followed by the length of the
+/// data as 8 big endian bytes and the data itself, or just the SHA256 of the data if there is no
+/// domain.
+pub fn signing_digest(domain: Option<SigningDomain>, data: &[u8]) -> Sha256 {
+    match domain {
+        Some(domain) => {
+            let tag = domain.tag();
+            let mut preimage = Vec::with_capacity(tag.len() + 8 + data.len());
+            preimage.extend_from_slice(tag);
+            preimage.extend_from_slice(&(data.len() as u64).to_be_bytes());
+            preimage.extend_from_slice(data);
+
+            calculate_sha256(&preimage)
+        }
+        None => calculate_sha256(data),
+    }
+}
+
+/// Signature of some data, along with what was actually signed.
+#[derive(Debug, Serialize)]
+pub struct SignedData {
+    #[serde(flatten)]
+    pub signature: ExtendedKeyedSignature,
+    /// Domain the data was signed in, or `null` if it was signed raw.
+    pub domain: Option<SigningDomain>,
+    /// Hex encoded digest that was signed.
+    pub digest: String,
+}
+
+/// Guardrails on the funds that can be sent out of a wallet.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WalletPolicy {
+    /// Nanowits that can leave the wallet within any 24 hours, counting confirmed and pending
+    /// outgoing movements
+    #[serde(
+        default,
+        serialize_with = "option_u64_to_string",
+        deserialize_with = "option_number_from_string"
+    )]
+    pub daily_spend_limit: Option<u64>,
+    /// Transactions sending more nanowits than this out of the wallet must be confirmed with
+    /// `confirm_send` before they are broadcast
+    #[serde(
+        default,
+        serialize_with = "option_u64_to_string",
+        deserialize_with = "option_number_from_string"
+    )]
+    pub confirmation_threshold: Option<u64>,
+}
+
+/// Types of the transactions that a node can have the wallet sign on its behalf.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WitnessTransactionType {
+    Commit,
+    Reveal,
+}
+
+impl fmt::Display for WitnessTransactionType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let transaction_type = match self {
+            WitnessTransactionType::Commit => "commit",
+            WitnessTransactionType::Reveal => "reveal",
+        };
+
+        f.write_str(transaction_type)
+    }
+}
+
+/// Which transactions nodes can have the wallet sign through `sign_witness_transaction`.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WitnessSigningPolicy {
+    /// Types of the transactions that can be signed, none unless explicitly allowed
+    #[serde(default)]
+    pub allowed_types: Vec<WitnessTransactionType>,
+    /// Nanowits that a single commit can lock as collateral, none unless explicitly allowed
+    #[serde(
+        default,
+        serialize_with = "u64_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub max_collateral: u64,
+}
+
+/// `WitnessSigningPolicy` as persisted by wallets that did not store its `max_collateral`
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LegacyWitnessSigningPolicy {
+    pub allowed_types: Vec<WitnessTransactionType>,
+}
+
+impl From<LegacyWitnessSigningPolicy> for WitnessSigningPolicy {
+    fn from(policy: LegacyWitnessSigningPolicy) -> Self {
+        WitnessSigningPolicy {
+            allowed_types: policy.allowed_types,
+            max_collateral: 0,
+        }
+    }
+}
+
+/// Record of a transaction signed on behalf of a node.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WitnessSigningAuditEntry {
+    /// Seconds since the Unix epoch when the transaction was signed
+    pub timestamp: i64,
+    #[serde(rename = "type")]
+    pub transaction_type: WitnessTransactionType,
+    /// Hex encoded hash of the body of the transaction, which is what was signed
+    pub hash: String,
+    /// Hex encoded hash of the data request the transaction is for
+    pub dr_pointer: String,
+    /// Address whose key signed the transaction
+    pub address: String,
+}
+
+/// How the spending policy of a wallet applies to a transaction.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SpendingCheck {
+    /// Nanowits that the transaction sends out of the wallet, including the fee
+    #[serde(
+        serialize_with = "u64_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub outgoing: u64,
+    /// Nanowits sent out of the wallet within the last 24 hours
+    #[serde(
+        serialize_with = "u64_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub spent: u64,
+    #[serde(
+        serialize_with = "option_u64_to_string",
+        deserialize_with = "option_number_from_string"
+    )]
+    pub daily_spend_limit: Option<u64>,
+    /// Nanowits that can still be sent before the transaction, if there is a daily spend limit
+    #[serde(
+        serialize_with = "option_u64_to_string",
+        deserialize_with = "option_number_from_string"
+    )]
+    pub remaining_allowance: Option<u64>,
+    /// Whether sending the transaction must be confirmed with `confirm_send`
+    pub requires_confirmation: bool,
+}
+
+impl SpendingCheck {
+    /// Whether the transaction fits in what remains of the daily spend limit.
+    pub fn within_limit(&self) -> bool {
+        self.remaining_allowance
+            .map_or(true, |remaining| self.outgoing <= remaining)
+    }
+}
+
 #[cfg(test)]
 impl Addresses {
     /// Number of addresses contained in the internal buffer.
@@ -134,7 +589,11 @@ pub struct BalanceMovement {
         deserialize_with = "number_from_string"
     )]
     pub amount: u64,
+    #[serde(serialize_with = "serialize_movement_transaction")]
     pub transaction: WalletTransaction,
+    /// Mining activity behind the movement, if any
+    #[serde(default)]
+    pub category: Option<MovementCategory>,
 }
 
 #[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -154,6 +613,23 @@ impl fmt::Display for MovementType {
     }
 }
 
+/// Mining activity of a wallet that committed to solve a data request
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum MovementCategory {
+    /// A commit transaction locked funds of the wallet as collateral
+    #[serde(rename = "collateral_locked")]
+    CollateralLocked,
+    /// A tally transaction returned the collateral of the wallet together with its reward
+    #[serde(rename = "mining_reward")]
+    MiningReward,
+    /// A tally transaction did not return the collateral of the wallet (e.g. it did not reveal
+    /// or its reveal was out of consensus). The collateral already left the balance with the
+    /// movement of the commit that locked it, so this movement has no amount of its own and only
+    /// affects the collateralized balance.
+    #[serde(rename = "collateral_lost")]
+    CollateralLost,
+}
+
 /// Transaction linked to a balance movement in a wallet
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 pub struct WalletTransaction {
@@ -173,6 +649,253 @@ pub struct WalletTransaction {
     pub miner_fee: u64,
     /// Date when transaction was included a block (same as block date)
     pub timestamp: u64,
+    /// Fee paid by the wallet, only known for transactions funded exclusively by the wallet
+    #[serde(
+        default,
+        serialize_with = "option_u64_to_string",
+        deserialize_with = "option_number_from_string"
+    )]
+    pub fee_paid: Option<u64>,
+    /// Addresses on the other side of the movement: the recipients of a sent transaction, or
+    /// the owners of the inputs of a received transaction (if known)
+    #[serde(default)]
+    pub counterparties: Option<Vec<String>>,
+    /// Hash of the data request that commit and tally transactions refer to
+    #[serde(default)]
+    pub data_request_hash: Option<String>,
+    /// Whether the metadata of the movement was pruned, leaving only its hash, amount, direction
+    /// and epoch. Pruned movements are persisted apart, so this is never stored.
+    #[serde(
+        default,
+        serialize_with = "human_readable_only",
+        deserialize_with = "human_readable_only_or_default"
+    )]
+    pub pruned: bool,
+    /// Hash of the transaction that replaced this one with a higher fee. Only local movements are
+    /// superseded, and they are never persisted, so this is never stored either.
+    #[serde(
+        default,
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "human_readable_only",
+        deserialize_with = "human_readable_only_or_default"
+    )]
+    pub superseded_by: Option<String>,
+}
+
+/// `WalletTransaction` of a pruned movement as shown in responses, with nulls for the fields that
+/// were removed.
+#[derive(Serialize)]
+struct PrunedWalletTransaction<'a> {
+    block: PrunedBeacon,
+    confirmed: bool,
+    data: Option<()>,
+    hash: &'a str,
+    miner_fee: Option<()>,
+    timestamp: Option<()>,
+    fee_paid: Option<()>,
+    counterparties: Option<()>,
+    data_request_hash: Option<()>,
+    pruned: bool,
+}
+
+/// `Beacon` of a pruned movement, which only keeps its epoch
+#[derive(Serialize)]
+struct PrunedBeacon {
+    #[serde(serialize_with = "u32_to_string")]
+    epoch: u32,
+    block_hash: Option<()>,
+}
+
+/// Serialize the transaction of a balance movement, showing the fields removed from pruned
+/// movements as nulls in human-readable formats.
+fn serialize_movement_transaction<S>(
+    transaction: &WalletTransaction,
+    serializer: S,
+) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    if !transaction.pruned || !serializer.is_human_readable() {
+        return transaction.serialize(serializer);
+    }
+
+    PrunedWalletTransaction {
+        block: PrunedBeacon {
+            epoch: transaction.block.as_ref().map_or(0, |block| block.epoch),
+            block_hash: None,
+        },
+        confirmed: transaction.confirmed,
+        data: None,
+        hash: &transaction.hash,
+        miner_fee: None,
+        timestamp: None,
+        fee_paid: None,
+        counterparties: None,
+        data_request_hash: None,
+        pruned: true,
+    }
+    .serialize(serializer)
+}
+
+/// What is kept of a balance movement once its metadata is pruned
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PrunedMovement {
+    pub kind: MovementType,
+    pub amount: u64,
+    /// Hash of the transaction of the movement
+    pub hash: String,
+    /// Epoch of the block in which the transaction is included
+    pub epoch: u32,
+}
+
+impl From<PrunedMovement> for BalanceMovement {
+    fn from(movement: PrunedMovement) -> Self {
+        BalanceMovement {
+            db_key: 0,
+            kind: movement.kind,
+            amount: movement.amount,
+            transaction: WalletTransaction {
+                block: Some(Beacon {
+                    epoch: movement.epoch,
+                    block_hash: Hash::default(),
+                }),
+                confirmed: true,
+                data: TransactionData::ValueTransfer(VtData {
+                    inputs: vec![],
+                    outputs: vec![],
+                }),
+                hash: movement.hash,
+                miner_fee: 0,
+                timestamp: 0,
+                fee_paid: None,
+                counterparties: None,
+                data_request_hash: None,
+                pruned: true,
+                superseded_by: None,
+            },
+            category: None,
+        }
+    }
+}
+
+/// How much of the metadata of its movements a wallet keeps
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Epochs before the last confirmed one whose movements keep their full metadata. Older
+    /// movements are pruned down to their hash, amount, direction and epoch. Movements are never
+    /// pruned if not set.
+    #[serde(default)]
+    pub keep_epochs: Option<u32>,
+}
+
+/// Movement removed by pruning, with the full metadata that it had, as written to the archive
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedMovement {
+    /// Id of the movement in its account
+    pub id: u32,
+    pub movement: BalanceMovement,
+}
+
+/// Details removed from the movements of a wallet by one pruning run
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MovementArchive {
+    pub wallet_id: WalletId,
+    pub account: u32,
+    /// Movements of blocks before this epoch were pruned
+    pub pruned_before: u32,
+    pub movements: Vec<ArchivedMovement>,
+}
+
+/// Outcome of pruning the movements of a wallet
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PruneReport {
+    /// Retention policy that was applied
+    pub policy: RetentionPolicy,
+    /// Movements pruned by this run
+    pub pruned: u32,
+    /// File to which the details of the pruned movements were archived, if any was pruned
+    pub archive_path: Option<PathBuf>,
+}
+
+/// `BalanceMovement` as persisted by wallets that did not store the transaction context fields
+/// of `WalletTransaction` (`fee_paid`, `counterparties` and `data_request_hash`).
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LegacyBalanceMovement {
+    #[serde(skip)]
+    pub db_key: u32,
+    pub kind: MovementType,
+    pub amount: u64,
+    pub transaction: LegacyWalletTransaction,
+}
+
+/// `WalletTransaction` as persisted by wallets that did not store the transaction context fields
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LegacyWalletTransaction {
+    pub block: Option<Beacon>,
+    pub confirmed: bool,
+    pub data: TransactionData,
+    pub hash: String,
+    pub miner_fee: u64,
+    pub timestamp: u64,
+}
+
+impl From<LegacyBalanceMovement> for BalanceMovement {
+    fn from(movement: LegacyBalanceMovement) -> Self {
+        let LegacyWalletTransaction {
+            block,
+            confirmed,
+            data,
+            hash,
+            miner_fee,
+            timestamp,
+        } = movement.transaction;
+
+        BalanceMovement {
+            db_key: movement.db_key,
+            kind: movement.kind,
+            amount: movement.amount,
+            transaction: WalletTransaction {
+                block,
+                confirmed,
+                data,
+                hash,
+                miner_fee,
+                timestamp,
+                fee_paid: None,
+                counterparties: None,
+                data_request_hash: None,
+                pruned: false,
+                superseded_by: None,
+            },
+            category: None,
+        }
+    }
+}
+
+/// `BalanceMovement` as persisted by wallets that did not store its `category`
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct UncategorizedBalanceMovement {
+    #[serde(skip)]
+    pub db_key: u32,
+    pub kind: MovementType,
+    #[serde(
+        serialize_with = "u64_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub amount: u64,
+    pub transaction: WalletTransaction,
+}
+
+impl From<UncategorizedBalanceMovement> for BalanceMovement {
+    fn from(movement: UncategorizedBalanceMovement) -> Self {
+        BalanceMovement {
+            db_key: movement.db_key,
+            kind: movement.kind,
+            amount: movement.amount,
+            transaction: movement.transaction,
+            category: None,
+        }
+    }
 }
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
@@ -234,6 +957,14 @@ pub struct Output {
     )]
     pub value: u64,
     pub output_type: OutputType,
+    /// Label given to the output when the wallet created the transaction. Labels are stored
+    /// apart from movements, which are persisted without them.
+    #[serde(
+        default,
+        serialize_with = "human_readable_only",
+        deserialize_with = "human_readable_only_or_default"
+    )]
+    pub label: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -256,6 +987,301 @@ pub struct WalletTransactions {
         deserialize_with = "number_from_string"
     )]
     pub total: u32,
+    /// Cursor to get the next page with, if this one is not empty
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cursor: Option<MovementsCursor>,
+}
+
+/// What the movements of a wallet are listed by. Ties are broken by movement id, so the order is
+/// always the same.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MovementsSort {
+    /// Movement id, which grows monotonically as movements are indexed. Local movements, which
+    /// are not in a block yet, come after the rest.
+    #[default]
+    Id,
+    /// Signed amount, which is positive for the received movements and negative for the sent ones
+    Amount,
+    /// Epoch of the block of the movement, with local movements last
+    Epoch,
+}
+
+/// Key that the movements of a wallet are sorted by.
+///
+/// The primary value depends on the sort, and ties are broken by whether the movement is local and
+/// by its id. Local movements have the id that the next indexed movement will get, so the hash of
+/// their transaction breaks the ties between them. It is left empty for the rest of movements.
+#[derive(Clone, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub struct MovementSortKey {
+    pub primary: i128,
+    pub local: bool,
+    pub id: u32,
+    pub hash: String,
+}
+
+/// Position in a listing of the movements of a wallet, right after the last movement that was
+/// returned.
+///
+/// The cursor holds the sort key of that movement rather than its position, so that a listing
+/// goes on from the same place even if movements were indexed in between. Clients get it as an
+/// opaque string of hexadecimal digits.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct MovementsCursor {
+    pub key: MovementSortKey,
+}
+
+impl FromStr for MovementsCursor {
+    type Err = InvalidListingCursor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if !(s.len() == 42 || s.len() == 106) || !s.is_ascii() {
+            return Err(InvalidListingCursor);
+        }
+        let primary = u128::from_str_radix(&s[..32], 16).map_err(|_| InvalidListingCursor)?;
+        let local = match &s[32..34] {
+            "00" => false,
+            "01" => true,
+            _ => return Err(InvalidListingCursor),
+        };
+        let id = u32::from_str_radix(&s[34..42], 16).map_err(|_| InvalidListingCursor)?;
+        let hash = &s[42..];
+        if hash.chars().any(|c| !matches!(c, '0'..='9' | 'a'..='f')) {
+            return Err(InvalidListingCursor);
+        }
+
+        Ok(MovementsCursor {
+            key: MovementSortKey {
+                primary: primary as i128,
+                local,
+                id,
+                hash: hash.to_string(),
+            },
+        })
+    }
+}
+
+impl TryFrom<String> for MovementsCursor {
+    type Error = InvalidListingCursor;
+
+    fn try_from(cursor: String) -> Result<Self, Self::Error> {
+        cursor.parse()
+    }
+}
+
+impl From<MovementsCursor> for String {
+    fn from(cursor: MovementsCursor) -> Self {
+        cursor.to_string()
+    }
+}
+
+impl fmt::Display for MovementsCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:032x}{:02x}{:08x}{}",
+            self.key.primary as u128,
+            u8::from(self.key.local),
+            self.key.id,
+            self.key.hash
+        )
+    }
+}
+
+/// Position in the stream of the persisted movements of a wallet account, right after the last
+/// movement that was returned.
+///
+/// Movement ids grow monotonically with the blocks, so the cursor only needs the account and the id
+/// of the next movement, which together make its storage key. Clients get it as an opaque string of
+/// 16 hexadecimal digits.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct TransactionsCursor {
+    pub account: u32,
+    pub next_id: u32,
+}
+
+/// Error of a string that is not a cursor returned by `get_transactions_stream`.
+#[derive(Debug, Eq, Fail, PartialEq)]
+#[fail(display = "Cursor is not one returned by get_transactions_stream")]
+pub struct InvalidTransactionsCursor;
+
+impl FromStr for TransactionsCursor {
+    type Err = InvalidTransactionsCursor;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let bytes = hex::decode(s).map_err(|_| InvalidTransactionsCursor)?;
+        if bytes.len() != 8 {
+            return Err(InvalidTransactionsCursor);
+        }
+        let mut account = [0; 4];
+        let mut next_id = [0; 4];
+        account.copy_from_slice(&bytes[..4]);
+        next_id.copy_from_slice(&bytes[4..]);
+
+        Ok(TransactionsCursor {
+            account: u32::from_be_bytes(account),
+            next_id: u32::from_be_bytes(next_id),
+        })
+    }
+}
+
+impl TryFrom<String> for TransactionsCursor {
+    type Error = InvalidTransactionsCursor;
+
+    fn try_from(cursor: String) -> Result<Self, Self::Error> {
+        cursor.parse()
+    }
+}
+
+impl From<TransactionsCursor> for String {
+    fn from(cursor: TransactionsCursor) -> Self {
+        cursor.to_string()
+    }
+}
+
+impl fmt::Display for TransactionsCursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:08x}{:08x}", self.account, self.next_id)
+    }
+}
+
+/// Batch of the stream of the persisted movements of a wallet account, from the oldest one.
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct TransactionsBatch {
+    pub transactions: Vec<BalanceMovement>,
+    /// Cursor to get the next batch with. Movements persisted later are returned after it.
+    pub cursor: TransactionsCursor,
+    /// Whether the batch reached the latest persisted movement.
+    pub finished: bool,
+}
+
+/// Status of a payment request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentRequestStatus {
+    /// Nothing has been received yet.
+    Pending,
+    /// Some confirmed payments were received, but they do not add up to the requested amount.
+    PartiallyPaid,
+    /// Confirmed payments add up to at least the requested amount.
+    Paid,
+    /// The request expired before being fully paid.
+    Expired,
+}
+
+impl PaymentRequestStatus {
+    /// Whether payments to the address of the request are still being tracked.
+    pub fn is_open(self) -> bool {
+        matches!(
+            self,
+            PaymentRequestStatus::Pending | PaymentRequestStatus::PartiallyPaid
+        )
+    }
+}
+
+/// A request for an amount to be paid to a fresh external address of the wallet.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct PaymentRequest {
+    pub id: u32,
+    /// Address that the payment is expected at
+    pub address: String,
+    /// Expected amount
+    #[serde(
+        serialize_with = "u64_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub amount: u64,
+    pub memo: Option<String>,
+    /// Timestamp when the request was created
+    pub created_at: u64,
+    /// Timestamp after which the request expires if it was not paid
+    pub expires_at: Option<u64>,
+    pub status: PaymentRequestStatus,
+    /// Total amount of the confirmed payments to the address of the request
+    #[serde(
+        serialize_with = "u64_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub received_amount: u64,
+    /// Hashes of the transactions paying to the address of the request
+    pub payments: Vec<String>,
+}
+
+impl PaymentRequest {
+    /// Account for a confirmed transaction paying `value` to the address of the request.
+    ///
+    /// Returns whether the request changed, which is never the case for requests that are not
+    /// open, or for transactions that were already accounted for.
+    pub fn record_payment(&mut self, transaction_hash: &str, value: u64) -> bool {
+        if !self.status.is_open() || self.payments.iter().any(|x| x == transaction_hash) {
+            return false;
+        }
+
+        self.payments.push(transaction_hash.to_string());
+        self.received_amount = self.received_amount.saturating_add(value);
+        self.status = if self.received_amount >= self.amount {
+            PaymentRequestStatus::Paid
+        } else {
+            PaymentRequestStatus::PartiallyPaid
+        };
+
+        true
+    }
+
+    /// Mark the request as expired if it is still open after its expiration timestamp.
+    ///
+    /// Returns whether the request changed.
+    pub fn expire(&mut self, now: u64) -> bool {
+        match self.expires_at {
+            Some(expires_at) if self.status.is_open() && now >= expires_at => {
+                self.status = PaymentRequestStatus::Expired;
+
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// A confirmed payment to the address of a payment request that was not expecting it anymore,
+/// either because the request was already paid or expired, or because the address had already
+/// received a payment that has nothing to do with the request.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AddressReuse {
+    pub address: String,
+    /// Payment request that was already paid or expired when the payment arrived
+    pub payment_request_id: Option<u32>,
+    /// Hash of an earlier transaction paying to the address outside of its payment request
+    pub previous_transaction: Option<String>,
+    /// Hash of the transaction reusing the address
+    pub transaction: String,
+}
+
+/// An external address generated further ahead of the last one that received a payment than the
+/// gap limit, i.e. the number of addresses in a row without payments after which wallets
+/// recovering from the seed stop looking for them.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct AddressGapWarning {
+    /// Index of the generated address
+    pub index: u32,
+    /// Index of the last external address that received a payment, if any did
+    pub last_used_index: Option<u32>,
+    /// Addresses from the last one that received a payment up to the generated one
+    pub gap: u32,
+    pub gap_limit: u16,
+    pub message: String,
+}
+
+#[derive(Debug, Eq, PartialEq, Serialize)]
+pub struct PaymentRequests {
+    pub payment_requests: Vec<PaymentRequest>,
+    #[serde(
+        serialize_with = "u32_to_string",
+        deserialize_with = "number_from_string"
+    )]
+    pub total: u32,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq, Hash)]
@@ -368,8 +1394,30 @@ impl fmt::Display for Beacon {
     }
 }
 
+/// How far the synchronization of a wallet has got.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SyncWatermark {
+    /// Last block processed, which may not be consolidated by a superblock yet
+    pub last_sync: Beacon,
+    /// Last block consolidated by a superblock
+    pub last_confirmed: Beacon,
+    /// Whether a synchronization is running
+    pub syncing: bool,
+    /// Epoch beyond which the wallet does not process blocks, if its synchronization is pinned
+    #[serde(
+        default,
+        serialize_with = "option_u32_to_string",
+        deserialize_with = "option_number_from_string"
+    )]
+    pub sync_target_epoch: Option<u32>,
+}
+
 pub type UtxoSet = HashMap<OutPtr, OutputInfo>;
 
+/// Map of data request hash to the collateral locked by the commits of the wallet to that data
+/// request, until it is resolved by a tally transaction.
+pub type Collaterals = HashMap<String, u64>;
+
 /// Map of output pointer to timestamp.
 /// Used to mark outputs that have been recently used in a transaction.
 /// They will not be used again until this timestamp.
@@ -390,22 +1438,73 @@ pub fn clean_used_outputs(used_outputs: &UsedOutputs, utxo_set: &UtxoSet) -> Use
     new_hm
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct Path {
     pub account: u32,
     pub keychain: u32,
     pub index: u32,
 }
 
-impl fmt::Display for Path {
-    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
-        write!(
-            f,
-            "{}/{}/{}",
-            account::account_keypath(self.account),
-            self.keychain,
-            self.index
-        )
+/// How the accounts of a wallet are derived from its master key, under
+/// `m/purpose'/coin_type'/account'` where the purpose is given by the key spec
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KeyDerivation {
+    pub key_spec: KeySpec,
+    pub coin_type: u32,
+}
+
+impl KeyDerivation {
+    /// Derivation of the wallets created before it was recorded along with them
+    pub const LEGACY: KeyDerivation = KeyDerivation {
+        key_spec: KeySpec::Wip3,
+        coin_type: 4919,
+    };
+
+    /// Path under which all the accounts are derived.
+    pub fn base_path(&self) -> KeyPath {
+        KeyPath::default()
+            .hardened(self.key_spec.purpose())
+            .hardened(self.coin_type)
+    }
+
+    /// Path of an account.
+    pub fn account_keypath(&self, account: u32) -> KeyPath {
+        self.base_path().hardened(account)
+    }
+
+    /// Path of an address.
+    pub fn keypath(&self, path: &Path) -> KeyPath {
+        self.account_keypath(path.account)
+            .index(path.keychain)
+            .index(path.index)
+    }
+}
+
+/// `KeyDerivation` as persisted, with the name of its key spec so that wallets created by newer
+/// versions with specs unknown to this one can be told apart from corrupted ones
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct KeyDerivationRecord {
+    pub key_spec: String,
+    pub coin_type: u32,
+}
+
+impl From<KeyDerivation> for KeyDerivationRecord {
+    fn from(derivation: KeyDerivation) -> Self {
+        KeyDerivationRecord {
+            key_spec: derivation.key_spec.to_string(),
+            coin_type: derivation.coin_type,
+        }
+    }
+}
+
+impl TryFrom<KeyDerivationRecord> for KeyDerivation {
+    type Error = UnknownKeySpec;
+
+    fn try_from(record: KeyDerivationRecord) -> Result<Self, Self::Error> {
+        Ok(KeyDerivation {
+            key_spec: record.key_spec.parse()?,
+            coin_type: record.coin_type,
+        })
     }
 }
 
@@ -421,10 +1520,374 @@ pub enum TransactionMetadata {
     Tally(Box<DataRequestInfo>),
 }
 
+/// How the response of a data source was obtained when running a data request locally.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CacheStatus {
+    /// The cached response was fresh enough to be used without contacting the source.
+    Hit,
+    /// The source confirmed that the cached response had not changed.
+    Revalidated,
+    /// The response was fetched from the source.
+    Miss,
+}
+
+/// Response of a data source stored in the retrieval cache.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CachedRetrieval {
+    pub body: String,
+    /// Value of the `ETag` header of the response
+    pub etag: Option<String>,
+    /// Value of the `Last-Modified` header of the response
+    pub last_modified: Option<String>,
+    /// Timestamp when the response was last fetched or revalidated
+    pub fetched_at: i64,
+}
+
+impl CachedRetrieval {
+    /// Approximate number of bytes taken by the entry in the retrieval cache.
+    pub fn size(&self) -> u64 {
+        let len = self.body.len()
+            + self.etag.as_ref().map(String::len).unwrap_or(0)
+            + self.last_modified.as_ref().map(String::len).unwrap_or(0);
+
+        u64::try_from(len).unwrap_or(u64::MAX)
+    }
+}
+
+/// Entry of the retrieval cache, as listed in its index.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalCacheIndexEntry {
+    pub key: String,
+    pub size: u64,
+}
+
+/// Request made with an idempotency key, as recorded to answer its retries.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IdempotentOperation {
+    pub method: String,
+    /// Hash of the params of the request, which retries must repeat
+    pub params_hash: String,
+    /// Timestamp when the request was first made
+    pub recorded_at: i64,
+    /// Response to the request, as JSON, or `None` if it has not been answered
+    pub response: Option<String>,
+}
+
+/// Request made with an idempotency key, as listed in the index of the recorded ones.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct IdempotencyIndexEntry {
+    pub key: String,
+    pub recorded_at: i64,
+}
+
+/// How a data source did across the iterations of a retrieval benchmark.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SourceBenchmark {
+    /// URL of the source, with the placeholders of its secrets
+    pub url: String,
+    pub attempts: u32,
+    pub successes: u32,
+    /// Attempts that did not finish within the retrieval timeout
+    pub timeouts: u32,
+    /// Proportion of the attempts that succeeded, from 0 to 1
+    pub success_rate: f64,
+    /// Latencies of the attempts that finished, whether they succeeded or not. `None` if every
+    /// attempt timed out.
+    pub latency: Option<LatencyStats>,
+    /// Sizes in bytes of the bodies of the responses, if the source was retrieved over HTTP
+    pub response_size: Option<SizeStats>,
+    /// Distinct values returned by the successful attempts, the most frequent first
+    pub results: Vec<ValueCount>,
+    /// Distinct errors of the failed attempts, the most frequent first
+    pub errors: Vec<ValueCount>,
+    /// Whether the successful attempts returned more than one distinct value
+    pub inconsistent: bool,
+    /// Timestamp when the benchmark finished
+    pub benchmarked_at: i64,
+}
+
+/// Percentiles of the latencies of a data source, in milliseconds.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct LatencyStats {
+    pub min_ms: u64,
+    pub p50_ms: u64,
+    pub p90_ms: u64,
+    pub p99_ms: u64,
+    pub max_ms: u64,
+    pub mean_ms: u64,
+}
+
+/// Statistics of the sizes of the responses of a data source, in bytes.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct SizeStats {
+    pub min: u64,
+    pub mean: u64,
+    pub max: u64,
+}
+
+/// A value and how many times it was seen.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct ValueCount {
+    /// JSON of a value returned by a source, or the message of an error
+    pub value: String,
+    pub count: u32,
+}
+
+/// Result of a retrieval benchmark.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RetrievalBenchmark {
+    pub iterations: u32,
+    /// The sources, in the same order as they were given
+    pub sources: Vec<BenchmarkedSource>,
+}
+
+/// A data source benchmark, along with the last one of the same URL that was kept.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct BenchmarkedSource {
+    #[serde(flatten)]
+    pub benchmark: SourceBenchmark,
+    pub previous: Option<SourceBenchmark>,
+}
+
+/// Kinds of events of a wallet that can be delivered to its webhook.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WebhookTopic {
+    /// A new movement affecting the balance of the wallet
+    Movement,
+    /// A payment request of the wallet was fully paid
+    PaymentRequestPaid,
+    /// The address of a payment request of the wallet received a payment it was not expecting
+    AddressReuse,
+    /// A synchronization of the wallet finished
+    SyncFinish,
+}
+
+/// Endpoint that the events of a wallet are posted to.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// URL that the events are posted to
+    pub url: String,
+    /// Shared secret that the payloads are signed with, so that the endpoint can authenticate them
+    pub secret: String,
+    /// Topics of the events that are delivered, or every topic if empty
+    #[serde(default)]
+    pub topics: Vec<WebhookTopic>,
+}
+
+impl WebhookConfig {
+    /// Whether the events of a topic are delivered to this endpoint.
+    pub fn wants(&self, topic: WebhookTopic) -> bool {
+        self.topics.is_empty() || self.topics.contains(&topic)
+    }
+}
+
+impl fmt::Debug for WebhookConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("WebhookConfig")
+            .field("url", &self.url)
+            .field("secret", &"[redacted]")
+            .field("topics", &self.topics)
+            .finish()
+    }
+}
+
+/// Events of a wallet waiting to be posted to its webhook, or that could not be.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WebhookDelivery {
+    /// Identifier of the delivery, sent along with it so that endpoints can ignore repeated ones
+    pub id: u64,
+    pub wallet_id: WalletId,
+    pub url: String,
+    /// JSON payload, exactly as it is signed and posted
+    pub payload: String,
+    /// Hex-encoded HMAC-SHA256 of the payload with the secret of the webhook
+    pub signature: String,
+    /// Timestamp in milliseconds when the events were queued
+    pub created_at: i64,
+    /// Failed attempts to post the payload since it was queued or requeued
+    pub attempts: u32,
+    /// Timestamp in milliseconds before which the payload is not posted again
+    pub next_attempt_at: i64,
+    /// Why the last attempt failed
+    pub last_error: Option<String>,
+}
+
+/// Delivery waiting in the outbox of a wallet, as listed in its index.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct WebhookOutboxEntry {
+    pub id: u64,
+    /// Timestamp in milliseconds before which the delivery is not posted again
+    pub next_attempt_at: i64,
+}
+
+/// Deliveries to the webhook of a wallet.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct WebhookDeliveries {
+    /// Deliveries waiting to be posted, including the ones waiting to be retried
+    pub pending: Vec<WebhookDelivery>,
+    /// Deliveries that failed too many times, or did not fit in the outbox, and will not be
+    /// retried unless they are requeued
+    pub dead_letters: Vec<WebhookDelivery>,
+}
+
+/// An inconsistency between the records of a wallet database, found by checking its integrity.
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum IntegrityIssue {
+    /// Addresses of a keychain were persisted at and beyond its next index, so they would be
+    /// derived again
+    AddressIndexBehind {
+        keychain: u32,
+        next_index: u32,
+        persisted_until: u32,
+    },
+    /// An address below the next index of its keychain is missing
+    MissingAddress { keychain: u32, index: u32 },
+    /// The entry that finds an address from its public key hash is missing or points to another
+    /// address
+    BrokenAddressIndexEntry { keychain: u32, index: u32 },
+    /// Movements were persisted at and beyond the next transaction id, so they would be overwritten
+    TransactionIdBehind { next_id: u32, persisted_until: u32 },
+    /// A movement below the next transaction id is missing
+    MissingMovement { id: u32 },
+    /// The entry that finds a movement from the hash of its transaction is missing or points to
+    /// another movement
+    BrokenTransactionIndexEntry { id: u32, transaction_hash: String },
+    /// The persisted balance does not add up to the UTXO set
+    BalanceMismatch {
+        #[serde(serialize_with = "u64_to_string")]
+        persisted: u64,
+        #[serde(serialize_with = "u64_to_string")]
+        utxo_set: u64,
+    },
+    /// An unspent output was created by a transaction that has no movement
+    UtxoWithoutMovement { output_pointer: String },
+    /// An output reserved by a pending transaction is not in the UTXO set
+    StaleReservation { output_pointer: String },
+}
+
+impl IntegrityIssue {
+    /// Name of the kind of issue, as it is serialized.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            IntegrityIssue::AddressIndexBehind { .. } => "address_index_behind",
+            IntegrityIssue::MissingAddress { .. } => "missing_address",
+            IntegrityIssue::BrokenAddressIndexEntry { .. } => "broken_address_index_entry",
+            IntegrityIssue::TransactionIdBehind { .. } => "transaction_id_behind",
+            IntegrityIssue::MissingMovement { .. } => "missing_movement",
+            IntegrityIssue::BrokenTransactionIndexEntry { .. } => "broken_transaction_index_entry",
+            IntegrityIssue::BalanceMismatch { .. } => "balance_mismatch",
+            IntegrityIssue::UtxoWithoutMovement { .. } => "utxo_without_movement",
+            IntegrityIssue::StaleReservation { .. } => "stale_reservation",
+        }
+    }
+}
+
+/// What can be done about an integrity issue.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityResolution {
+    /// The broken records can be derived from other records
+    Repairable,
+    /// The broken records were derived again from other records
+    Repaired,
+    /// Only synchronizing the wallet again recovers the missing records
+    NeedsResync,
+}
+
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
+pub struct IntegrityFinding {
+    #[serde(flatten)]
+    pub issue: IntegrityIssue,
+    pub resolution: IntegrityResolution,
+}
+
+/// Outcome of an import of the metadata of the external addresses of a wallet.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct AddressMetadataImport {
+    /// Entries that changed the label or account tag of their address
+    pub imported: u32,
+    /// Entries that the address already had
+    pub unchanged: u32,
+    /// Addresses generated to cover the highest imported index
+    pub generated: u32,
+    /// Next index of the external keychain after the import
+    pub next_external_index: u32,
+}
+
+/// Findings of an integrity check of a wallet database, which are none for a healthy wallet.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+pub struct IntegrityReport {
+    pub findings: Vec<IntegrityFinding>,
+}
+
+impl IntegrityReport {
+    /// Whether some findings can only be recovered from by synchronizing the wallet again.
+    pub fn needs_resync(&self) -> bool {
+        self.findings
+            .iter()
+            .any(|finding| finding.resolution == IntegrityResolution::NeedsResync)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_wallet_id_format() {
+        let new_id = WalletId::from_random_bytes([0xab; WALLET_ID_LENGTH / 2]);
+        assert!(!new_id.is_legacy());
+        assert_eq!(new_id, new_id.as_str().parse().unwrap());
+
+        // Ids of wallets created by older versions are still accepted
+        let legacy_id: WalletId = "example-wallet_1".parse().unwrap();
+        assert!(legacy_id.is_legacy());
+        assert_eq!(legacy_id.to_string(), "example-wallet_1");
+
+        assert_eq!("".parse::<WalletId>(), Err(InvalidWalletId::Empty));
+        assert_eq!(
+            "a".repeat(MAX_LEGACY_WALLET_ID_LENGTH + 1)
+                .parse::<WalletId>(),
+            Err(InvalidWalletId::TooLong)
+        );
+        for (id, invalid) in [
+            ("a/b", '/'),
+            ("a\nb", '\n'),
+            ("a b", ' '),
+            ("wallet\0", '\0'),
+        ] {
+            assert_eq!(
+                id.parse::<WalletId>(),
+                Err(InvalidWalletId::InvalidCharacter(invalid))
+            );
+        }
+        assert!(serde_json::from_str::<WalletId>("\"../wallet\"").is_err());
+    }
+
+    #[test]
+    fn test_transactions_cursor_format() {
+        let cursor = TransactionsCursor {
+            account: 1,
+            next_id: 50_000,
+        };
+        assert_eq!(cursor.to_string(), "000000010000c350");
+        assert_eq!("000000010000c350".parse(), Ok(cursor));
+        assert_eq!(
+            serde_json::to_value(cursor).unwrap(),
+            serde_json::json!("000000010000c350")
+        );
+
+        for invalid in ["", "00000001", "000000010000c35z", "000000010000c3500"] {
+            assert_eq!(
+                invalid.parse::<TransactionsCursor>(),
+                Err(InvalidTransactionsCursor)
+            );
+        }
+    }
+
     #[test]
     fn test_out_ptr_transaction_id() {
         let txn_hash = vec![0; 32];
@@ -438,4 +1901,47 @@ mod tests {
 
         assert_eq!(&txn.as_slice(), &id.as_ref());
     }
+
+    #[test]
+    fn test_integrity_issue_kind_matches_serialization() {
+        let issues = vec![
+            IntegrityIssue::AddressIndexBehind {
+                keychain: 0,
+                next_index: 1,
+                persisted_until: 2,
+            },
+            IntegrityIssue::MissingAddress {
+                keychain: 0,
+                index: 0,
+            },
+            IntegrityIssue::BrokenAddressIndexEntry {
+                keychain: 0,
+                index: 0,
+            },
+            IntegrityIssue::TransactionIdBehind {
+                next_id: 1,
+                persisted_until: 2,
+            },
+            IntegrityIssue::MissingMovement { id: 0 },
+            IntegrityIssue::BrokenTransactionIndexEntry {
+                id: 0,
+                transaction_hash: String::new(),
+            },
+            IntegrityIssue::BalanceMismatch {
+                persisted: 1,
+                utxo_set: 2,
+            },
+            IntegrityIssue::UtxoWithoutMovement {
+                output_pointer: String::new(),
+            },
+            IntegrityIssue::StaleReservation {
+                output_pointer: String::new(),
+            },
+        ];
+
+        for issue in issues {
+            let serialized = serde_json::to_value(&issue).unwrap();
+            assert_eq!(serialized["kind"], issue.kind());
+        }
+    }
 }
@@ -1,5 +1,6 @@
 use std::{
     collections::HashMap,
+    path::PathBuf,
     sync::{Arc, Mutex, RwLock},
     time::Duration,
 };
@@ -11,7 +12,7 @@ use witnet_data_structures::{
 };
 use witnet_net::client::tcp::jsonrpc::Subscribe;
 
-use crate::{actors::app::NodeClient, types};
+use crate::{actors::app::NodeClient, model, types};
 
 /// Initialization parameters that can be specific for each wallet.
 #[derive(Clone)]
@@ -29,12 +30,48 @@ pub struct Params {
     pub genesis_hash: Hash,
     pub genesis_prev_hash: Hash,
     pub sync_address_batch_length: u16,
-    pub max_vt_weight: u32,
-    pub max_dr_weight: u32,
     pub consensus_constants: ConsensusConstants,
     pub use_unconfirmed_utxos: bool,
     pub pending_transactions_timeout_seconds: u64,
     pub witnessing: WitnessingConfig<witnet_net::Uri>,
+    pub retrieval_cache_enabled: bool,
+    /// Seconds after which a cached data source response is revalidated.
+    pub retrieval_cache_ttl: i64,
+    pub retrieval_cache_max_size: u64,
+    /// Check the internal consistency of blocks received from the node before applying them.
+    pub verify_block_headers: bool,
+    /// Failed seed backup confirmations after which a wallet waiting for one is deleted.
+    pub seed_backup_confirmation_attempts: u32,
+    /// Check the integrity of wallets when they are unlocked, repairing what can be derived.
+    pub check_integrity_on_unlock: bool,
+    /// Maximum number of addresses of a wallet kept in its in-memory index.
+    pub address_index_max_entries: usize,
+    /// Minimum fee in nanowits of the transactions created by the wallet.
+    pub minimum_fee: u64,
+    /// Minimum fee in nanowits per thousand weight units of the transactions created by the wallet.
+    pub minimum_fee_per_kilo_weight: u64,
+    /// Value in nanowits below which outputs are not created.
+    pub dust_threshold: u64,
+    /// Excess in nanowits over the outputs and fee that inputs needing no change may have.
+    pub changeless_tolerance: u64,
+    /// Nodes visited at most when looking for inputs that need no change, or zero to not look.
+    pub changeless_search_budget: u32,
+    /// Refuse to generate external addresses beyond the gap limit instead of warning about them.
+    pub refuse_addresses_beyond_gap_limit: bool,
+    /// Epochs grouped into every entry of the balance history of a wallet.
+    pub balance_history_granularity: u32,
+    /// Seconds after which the entries of the balance history are downsampled to one per day.
+    pub balance_history_daily_after: u64,
+    /// Directory to which the details removed from pruned movements are archived.
+    pub prune_archive_path: PathBuf,
+    /// Key spec and coin type that the accounts of new wallets are derived with.
+    pub key_derivation: model::KeyDerivation,
+    /// Maximum number of deliveries in the webhook outbox of a wallet, and also of dead letters.
+    pub webhook_outbox_size: usize,
+    /// Failed attempts after which a webhook delivery becomes a dead letter.
+    pub webhook_max_attempts: u32,
+    /// Milliseconds to wait before retrying a failed webhook delivery for the first time.
+    pub webhook_retry_delay: i64,
 }
 
 #[derive(Clone)]
@@ -72,10 +109,8 @@ impl NodeParams {
         }
     }
 
-    /// Get an existing JsonRpcClient actor.
-    ///
-    /// This method exists for convenience in case that at some point we decide to allow changing
-    /// the `JsonRpcClient` address by putting `NodeClient` inside an `Arc<RwLock<_>>` or similar.
+    /// Get the client of the node, which sends every request through the least busy of its
+    /// connections.
     #[inline(always)]
     pub fn get_client(&self) -> Arc<NodeClient> {
         self.client.clone()
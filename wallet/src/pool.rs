@@ -0,0 +1,277 @@
+//! Pool of connections to the same Witnet node.
+//!
+//! Requests forwarded to the node are spread over several connections, so that slow requests such
+//! as `getBlockChain` over large ranges do not hold back the rest. Every request goes through the
+//! healthy connection with the fewest requests in flight, and ties are broken in round-robin
+//! order. Subscriptions are always made through the first connection of the pool, so that
+//! notifications are not received more than once.
+use std::sync::{
+    atomic::{AtomicBool, AtomicUsize, Ordering},
+    Arc, Mutex,
+};
+
+use failure::Fail;
+use serde::Serialize;
+
+/// Every connection of the pool is dead.
+#[derive(Debug, PartialEq, Eq, Fail)]
+#[fail(display = "wallet is not connected to a node")]
+pub struct Exhausted;
+
+/// Snapshot of the state of a connection, as shown to clients.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct ConnectionStatus {
+    /// Whether the last request sent through this connection reached the node.
+    pub healthy: bool,
+    /// Requests sent through this connection that are still waiting for a response.
+    pub in_flight: usize,
+}
+
+#[derive(Debug)]
+struct Member<T> {
+    connection: T,
+    healthy: AtomicBool,
+    in_flight: AtomicUsize,
+}
+
+/// A non-empty pool of connections.
+#[derive(Debug)]
+pub struct ConnectionPool<T> {
+    members: Vec<Arc<Member<T>>>,
+    /// Where the search for the least busy connection starts next time.
+    next: Mutex<usize>,
+}
+
+impl<T> ConnectionPool<T> {
+    /// Create a pool for a non-empty list of connections, which are assumed to be healthy.
+    pub fn new(connections: Vec<T>) -> Self {
+        assert!(!connections.is_empty(), "At least one connection is needed");
+
+        Self {
+            members: connections
+                .into_iter()
+                .map(|connection| {
+                    Arc::new(Member {
+                        connection,
+                        healthy: AtomicBool::new(true),
+                        in_flight: AtomicUsize::new(0),
+                    })
+                })
+                .collect(),
+            next: Mutex::new(0),
+        }
+    }
+
+    /// The connection that subscriptions are made through.
+    pub fn subscriptions(&self) -> &T {
+        &self.members[0].connection
+    }
+
+    /// All the connections of the pool, in order.
+    pub fn connections(&self) -> impl Iterator<Item = &T> {
+        self.members.iter().map(|member| &member.connection)
+    }
+
+    /// Take the healthy connection with the fewest requests in flight for sending a request.
+    pub fn acquire(&self) -> Result<PooledConnection<T>, Exhausted> {
+        let mut next = self
+            .next
+            .lock()
+            .expect("Pool locks should only fail if poisoned");
+
+        let len = self.members.len();
+        let index = (0..len)
+            .map(|offset| (*next + offset) % len)
+            .filter(|index| self.members[*index].healthy.load(Ordering::SeqCst))
+            .min_by_key(|index| self.members[*index].in_flight.load(Ordering::SeqCst))
+            .ok_or(Exhausted)?;
+        *next = (index + 1) % len;
+
+        Ok(PooledConnection::new(self.members[index].clone()))
+    }
+
+    /// Take every connection, healthy or not, so as to check all of them.
+    pub fn acquire_all(&self) -> Vec<PooledConnection<T>> {
+        self.members
+            .iter()
+            .cloned()
+            .map(PooledConnection::new)
+            .collect()
+    }
+
+    /// Consider every connection healthy again, e.g. after connecting them to another node.
+    pub fn reset_health(&self) {
+        for member in &self.members {
+            member.healthy.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Get a snapshot of the state of every connection, in order.
+    pub fn status(&self) -> Vec<ConnectionStatus> {
+        self.members
+            .iter()
+            .map(|member| ConnectionStatus {
+                healthy: member.healthy.load(Ordering::SeqCst),
+                in_flight: member.in_flight.load(Ordering::SeqCst),
+            })
+            .collect()
+    }
+}
+
+/// A connection taken from the pool for a single request, which counts as in flight until this
+/// is dropped.
+#[derive(Debug)]
+pub struct PooledConnection<T> {
+    member: Arc<Member<T>>,
+}
+
+impl<T> PooledConnection<T> {
+    fn new(member: Arc<Member<T>>) -> Self {
+        member.in_flight.fetch_add(1, Ordering::SeqCst);
+
+        Self { member }
+    }
+
+    pub fn connection(&self) -> &T {
+        &self.member.connection
+    }
+
+    /// Record whether the request reached the node. Connections that fail are not used again
+    /// until they succeed in a health check or their health is reset.
+    pub fn record(&self, healthy: bool) {
+        self.member.healthy.store(healthy, Ordering::SeqCst);
+    }
+}
+
+impl<T> Drop for PooledConnection<T> {
+    fn drop(&mut self) {
+        self.member.in_flight.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures::{
+        channel::oneshot,
+        future::{self, Either},
+        FutureExt,
+    };
+
+    use super::*;
+
+    /// A connection to a mock node, which answers with the index of the connection. Requests sent
+    /// through the slow connection are only answered once the returned sender is used.
+    struct MockConnection {
+        index: usize,
+        delay: Option<future::Shared<oneshot::Receiver<()>>>,
+    }
+
+    fn mock_pool(
+        size: usize,
+        slow: Option<usize>,
+    ) -> (ConnectionPool<MockConnection>, oneshot::Sender<()>) {
+        let (sender, receiver) = oneshot::channel();
+        let receiver = receiver.shared();
+        let connections = (0..size)
+            .map(|index| MockConnection {
+                index,
+                delay: if Some(index) == slow {
+                    Some(receiver.clone())
+                } else {
+                    None
+                },
+            })
+            .collect();
+
+        (ConnectionPool::new(connections), sender)
+    }
+
+    async fn request(pool: &ConnectionPool<MockConnection>) -> Result<usize, Exhausted> {
+        let conn = pool.acquire()?;
+        if let Some(delay) = conn.connection().delay.clone() {
+            delay.await.ok();
+        }
+        conn.record(true);
+
+        Ok(conn.connection().index)
+    }
+
+    #[test]
+    fn test_least_busy_connection_is_used() {
+        let (pool, _sender) = mock_pool(3, None);
+
+        let first = pool.acquire().unwrap();
+        let second = pool.acquire().unwrap();
+        assert_eq!(first.connection().index, 0);
+        assert_eq!(second.connection().index, 1);
+        drop(second);
+        // The second connection is idle again, and the third one was never used
+        assert_eq!(pool.acquire().unwrap().connection().index, 2);
+        assert_eq!(pool.acquire().unwrap().connection().index, 1);
+
+        assert_eq!(
+            pool.status(),
+            vec![
+                ConnectionStatus {
+                    healthy: true,
+                    in_flight: 1
+                },
+                ConnectionStatus {
+                    healthy: true,
+                    in_flight: 0
+                },
+                ConnectionStatus {
+                    healthy: true,
+                    in_flight: 0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_slow_connection_does_not_block_the_rest() {
+        let (pool, sender) = mock_pool(3, Some(0));
+
+        futures::executor::block_on(async {
+            let mut slow = Box::pin(request(&pool));
+            assert!(futures::poll!(&mut slow).is_pending());
+            let rest = Box::pin(future::join_all((0..4).map(|_| request(&pool))));
+
+            // The other requests complete while the first one is still waiting for the node
+            let (answered, slow) = match future::select(rest, slow).await {
+                Either::Left(left) => left,
+                Either::Right(_) => panic!("The slow request completed first"),
+            };
+            let answered: Vec<usize> = answered.into_iter().map(Result::unwrap).collect();
+            assert_eq!(answered, vec![1, 2, 1, 2]);
+            assert_eq!(pool.status()[0].in_flight, 1);
+
+            sender.send(()).unwrap();
+            assert_eq!(slow.await, Ok(0));
+        });
+
+        assert!(pool.status().iter().all(|status| status.in_flight == 0));
+    }
+
+    #[test]
+    fn test_dead_connections_are_skipped() {
+        let (pool, _sender) = mock_pool(2, None);
+
+        pool.acquire().unwrap().record(false);
+        for _ in 0..3 {
+            assert_eq!(pool.acquire().unwrap().connection().index, 1);
+        }
+
+        pool.acquire().unwrap().record(false);
+        assert_eq!(pool.acquire().err(), Some(Exhausted));
+
+        // Health checks go through every connection anyway
+        let all = pool.acquire_all();
+        assert_eq!(all.len(), 2);
+        all[1].record(true);
+        assert_eq!(pool.acquire().unwrap().connection().index, 1);
+
+        pool.reset_health();
+        assert!(pool.status().iter().all(|status| status.healthy));
+    }
+}
@@ -0,0 +1,242 @@
+//! Bounded queues of the work handed over to the storage worker.
+//!
+//! The worker runs one pool of threads for each class of work, so that the requests of clients
+//! never wait behind the processing of notified blocks or behind maintenance jobs. Each class
+//! counts the work that is queued or running in its pool, and refuses more once it reaches its
+//! limit: requests of clients fail right away with `Overloaded`, notified blocks are dropped and
+//! caught up with later by synchronizing the wallets, and periodic jobs wait for their next turn.
+use std::{
+    fmt,
+    future::Future,
+    sync::{
+        atomic::{AtomicU64, AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use failure::Fail;
+use futures::FutureExt;
+use serde::Serialize;
+
+use crate::metrics;
+
+/// Classes of the work handed over to the worker, each of them with its own pool of threads.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WorkClass {
+    /// Requests of clients, which are waiting for their answer.
+    Interactive,
+    /// Blocks and superblocks notified by the node.
+    Blocks,
+    /// Synchronizations and periodic jobs.
+    Maintenance,
+}
+
+impl fmt::Display for WorkClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorkClass::Interactive => write!(f, "interactive"),
+            WorkClass::Blocks => write!(f, "blocks"),
+            WorkClass::Maintenance => write!(f, "maintenance"),
+        }
+    }
+}
+
+/// A queue was at its limit when more work was offered to it.
+#[derive(Clone, Debug, PartialEq, Eq, Fail)]
+#[fail(
+    display = "the wallet is overloaded with {} work ({} of at most {} queued)",
+    class, depth, limit
+)]
+pub struct Overloaded {
+    pub class: WorkClass,
+    pub depth: usize,
+    pub limit: usize,
+}
+
+/// Count of the work of a class that is queued or running, and of the work that it refused.
+#[derive(Debug)]
+pub struct WorkQueue {
+    class: WorkClass,
+    limit: usize,
+    depth: AtomicUsize,
+    shed: AtomicU64,
+}
+
+impl WorkQueue {
+    pub fn new(class: WorkClass, limit: usize) -> Arc<Self> {
+        Arc::new(Self {
+            class,
+            limit,
+            depth: AtomicUsize::new(0),
+            shed: AtomicU64::new(0),
+        })
+    }
+
+    /// Take a place in the queue, unless it is full. Refused work is counted as shed.
+    pub fn try_enter(self: &Arc<Self>) -> Result<Ticket, Overloaded> {
+        let entered = self
+            .depth
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |depth| {
+                Some(depth + 1).filter(|depth| *depth <= self.limit)
+            });
+
+        match entered {
+            Ok(_) => Ok(Ticket(self.clone())),
+            Err(depth) => {
+                self.record_shed();
+
+                Err(Overloaded {
+                    class: self.class,
+                    depth,
+                    limit: self.limit,
+                })
+            }
+        }
+    }
+
+    /// Take a place in the queue even if it is full, for work that cannot be refused.
+    pub fn enter(self: &Arc<Self>) -> Ticket {
+        self.depth.fetch_add(1, Ordering::AcqRel);
+
+        Ticket(self.clone())
+    }
+
+    /// Work queued or running.
+    pub fn depth(&self) -> usize {
+        self.depth.load(Ordering::Acquire)
+    }
+
+    /// Whether the queue has reached its limit.
+    pub fn is_saturated(&self) -> bool {
+        self.depth() >= self.limit
+    }
+
+    /// Count work that was dropped without offering it to the queue.
+    pub fn record_shed(&self) {
+        self.shed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn metrics(&self) -> metrics::WorkQueue {
+        metrics::WorkQueue {
+            depth: self.depth(),
+            limit: self.limit,
+            shed: self.shed.load(Ordering::Relaxed),
+        }
+    }
+}
+
+/// A place taken in a queue, which is given back when the ticket is dropped. Tickets travel along
+/// with the work, so that they are only dropped once the worker is done with it.
+#[derive(Debug)]
+pub struct Ticket(Arc<WorkQueue>);
+
+impl Ticket {
+    /// Keep the place in the queue until `fut` resolves.
+    pub fn hold<F>(self, fut: F) -> impl Future<Output = F::Output>
+    where
+        F: Future,
+    {
+        fut.map(move |output| {
+            drop(self);
+
+            output
+        })
+    }
+}
+
+impl Drop for Ticket {
+    fn drop(&mut self) {
+        self.0.depth.fetch_sub(1, Ordering::AcqRel);
+    }
+}
+
+/// The queues of all the classes of work, shared by the `App` actor and the worker.
+#[derive(Clone, Debug)]
+pub struct WorkQueues {
+    pub interactive: Arc<WorkQueue>,
+    pub blocks: Arc<WorkQueue>,
+    pub maintenance: Arc<WorkQueue>,
+}
+
+impl WorkQueues {
+    pub fn new(interactive_limit: usize, blocks_limit: usize, maintenance_limit: usize) -> Self {
+        Self {
+            interactive: WorkQueue::new(WorkClass::Interactive, interactive_limit),
+            blocks: WorkQueue::new(WorkClass::Blocks, blocks_limit),
+            maintenance: WorkQueue::new(WorkClass::Maintenance, maintenance_limit),
+        }
+    }
+
+    pub fn metrics(&self) -> metrics::WorkQueues {
+        metrics::WorkQueues {
+            interactive: self.interactive.metrics(),
+            blocks: self.blocks.metrics(),
+            maintenance: self.maintenance.metrics(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn work_is_refused_only_past_the_limit() {
+        let queue = WorkQueue::new(WorkClass::Interactive, 2);
+
+        let first = queue.try_enter().unwrap();
+        let second = queue.try_enter().unwrap();
+        assert!(queue.is_saturated());
+        assert_eq!(
+            queue.try_enter().unwrap_err(),
+            Overloaded {
+                class: WorkClass::Interactive,
+                depth: 2,
+                limit: 2,
+            }
+        );
+        assert_eq!(
+            queue.metrics(),
+            metrics::WorkQueue {
+                depth: 2,
+                limit: 2,
+                shed: 1,
+            }
+        );
+
+        drop(first);
+        assert!(!queue.is_saturated());
+        let third = queue.try_enter().unwrap();
+        drop(second);
+        drop(third);
+        assert_eq!(queue.metrics().depth, 0);
+        assert_eq!(queue.metrics().shed, 1);
+    }
+
+    #[test]
+    fn work_that_cannot_be_refused_goes_past_the_limit() {
+        let queue = WorkQueue::new(WorkClass::Maintenance, 1);
+
+        let first = queue.enter();
+        let second = queue.enter();
+        assert_eq!(queue.metrics().depth, 2);
+        assert!(queue.try_enter().is_err());
+
+        drop(first);
+        assert!(queue.is_saturated());
+        drop(second);
+        assert!(queue.try_enter().is_ok());
+        assert_eq!(queue.metrics().depth, 0);
+    }
+
+    #[test]
+    fn tickets_are_held_until_the_work_is_done() {
+        let queue = WorkQueue::new(WorkClass::Blocks, 1);
+
+        let work = queue.enter().hold(async { 42 });
+        assert!(queue.is_saturated());
+        assert_eq!(futures::executor::block_on(work), 42);
+        assert_eq!(queue.metrics().depth, 0);
+    }
+}
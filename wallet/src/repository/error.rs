@@ -1,6 +1,6 @@
 use failure::Fail;
 
-use crate::{crypto, db};
+use crate::{crypto, db, model};
 use witnet_crypto::key::KeyDerivationError;
 use witnet_data_structures::{
     chain::{DataRequestOutput, HashParseError, PublicKeyHashParseError},
@@ -31,6 +31,8 @@ pub enum Error {
     },
     #[fail(display = "maximum transaction id reached for account")]
     TransactionIdOverflow,
+    #[fail(display = "maximum payment request id reached for account")]
+    PaymentRequestIdOverflow,
     #[fail(display = "mutex poison error")]
     MutexPoison,
     #[fail(display = "database failed: {}", _0)]
@@ -75,12 +77,111 @@ pub enum Error {
     MaximumDRWeightReached(Box<DataRequestOutput>),
     #[fail(display = "The chosen fee seems too large")]
     FeeTooLarge,
+    #[fail(
+        display = "The fee of {} nanoWits is below the minimum of {} nanoWits for a transaction of weight {}",
+        fee, minimum, weight
+    )]
+    FeeBelowMinimum { fee: u64, minimum: u64, weight: u32 },
+    #[fail(
+        display = "Output {} of {} nanoWits is below the dust threshold of {} nanoWits",
+        index, value, threshold
+    )]
+    DustOutput {
+        index: usize,
+        value: u64,
+        threshold: u64,
+    },
+    #[fail(
+        display = "Address {} would be {} addresses ahead of the last one that received a payment, beyond the gap limit of {}",
+        index, gap, gap_limit
+    )]
+    AddressGapExceeded {
+        index: u32,
+        gap: u32,
+        gap_limit: u16,
+    },
     #[fail(display = "Unknown Fee Type specified")]
     UnknownFeeType,
     #[fail(display = "Wallet not found")]
     WalletNotFound,
+    #[fail(display = "Wallet {} already exists", _0)]
+    WalletAlreadyExists(String),
+    #[fail(display = "No free wallet id found after {} attempts", _0)]
+    WalletIdCollision(usize),
     #[fail(display = "Secp256k1 error: {}", _0)]
     Secp256k1(#[cause] witnet_crypto::secp256k1::Error),
+    #[fail(display = "retrieval cache error: {}", _0)]
+    RetrievalCache(String),
+    #[fail(display = "archiving pruned movements failed: {}", _0)]
+    PruneArchive(#[cause] std::io::Error),
+    #[fail(display = "wallet requires newer software: unknown key spec {}", _0)]
+    UnsupportedKeySpec(String),
+    #[fail(
+        display = "cursor of account {} cannot be used with account {}",
+        cursor, account
+    )]
+    CursorAccountMismatch { cursor: u32, account: u32 },
+    #[fail(
+        display = "database schema version {} is newer than version {} supported by this software, upgrade the wallet to open it",
+        found, supported
+    )]
+    UnsupportedSchemaVersion { found: u32, supported: u32 },
+    #[fail(
+        display = "transaction {} is not a pending value transfer sent from this wallet",
+        _0
+    )]
+    UnknownLocalTransaction(String),
+    #[fail(display = "transaction {} has already been included in a block", _0)]
+    TransactionAlreadyIncluded(String),
+    #[fail(
+        display = "transaction {} has already been replaced by transaction {}",
+        transaction, replacement
+    )]
+    TransactionSuperseded {
+        transaction: String,
+        replacement: String,
+    },
+    #[fail(
+        display = "The new fee of {} nanoWits must be higher than the previous fee of {} nanoWits",
+        fee, previous
+    )]
+    FeeNotIncreased { fee: u64, previous: u64 },
+    #[fail(
+        display = "The change of {} nanoWits cannot cover a fee increase of {} nanoWits, more inputs need to be added",
+        change, increase
+    )]
+    FeeBumpNeedsInputs { change: u64, increase: u64 },
+    #[fail(
+        display = "the policy of the wallet does not allow signing {} transactions on behalf of nodes",
+        _0
+    )]
+    WitnessSigningNotAllowed(model::WitnessTransactionType),
+    #[fail(display = "address {} does not belong to the wallet", _0)]
+    UnknownWitnessKey(String),
+    #[fail(
+        display = "collateral input {} is not an available unspent output of address {}",
+        output, address
+    )]
+    UnavailableCollateral { output: String, address: String },
+    #[fail(
+        display = "the commit locks {} nanoWits as collateral, above the limit of {} nanoWits of the witness signing policy of the wallet",
+        collateral, limit
+    )]
+    CollateralAboveLimit { collateral: u64, limit: u64 },
+    #[fail(
+        display = "the address at index {} is {}, but the wallet derives {} there",
+        index, expected, derived
+    )]
+    AddressMismatch {
+        index: u32,
+        expected: String,
+        derived: String,
+    },
+    #[fail(
+        display = "address {} is not one of the external addresses of the account",
+        _0
+    )]
+    UnknownAddress(String),
 }
 
 impl From<failure::Error> for Error {
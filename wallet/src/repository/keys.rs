@@ -1,8 +1,8 @@
-use crate::model;
-use std::{fmt, marker::PhantomData};
+use crate::{model, types};
+use std::{collections::BTreeMap, fmt, marker::PhantomData};
 use witnet_crypto::key::ExtendedSK;
 use witnet_data_structures::{
-    chain::{CheckpointBeacon, PublicKeyHash},
+    chain::{CheckpointBeacon, Hash, PublicKeyHash},
     transaction::Transaction,
 };
 
@@ -80,10 +80,16 @@ where
 
 /// The list of wallet ids stored in the database.
 #[inline]
-pub fn wallet_ids() -> Key<&'static str, Vec<String>> {
+pub fn wallet_ids() -> Key<&'static str, Vec<model::WalletId>> {
     Key::new("wallets")
 }
 
+/// Version of the schema of the records shared by all the wallets.
+#[inline]
+pub fn wallets_schema_version() -> Key<&'static str, u32> {
+    Key::new("wallets-schema-version")
+}
+
 /// A wallet's name.
 #[inline]
 pub fn wallet_name() -> Key<&'static str, String> {
@@ -96,21 +102,107 @@ pub fn wallet_description() -> Key<&'static str, String> {
     Key::new("description")
 }
 
+/// A wallet's spending policy.
+#[inline]
+pub fn wallet_policy() -> Key<&'static str, model::WalletPolicy> {
+    Key::new("policy")
+}
+
+/// Which transactions nodes can have the wallet sign on their behalf.
+#[inline]
+pub fn wallet_witness_signing_policy() -> Key<&'static str, model::WitnessSigningPolicy> {
+    Key::new("witness-signing-policy")
+}
+
+/// `wallet_witness_signing_policy` as persisted before it had a maximum collateral.
+///
+/// This is the same database key as `wallet_witness_signing_policy`.
+#[inline]
+pub fn legacy_wallet_witness_signing_policy() -> Key<&'static str, model::LegacyWitnessSigningPolicy>
+{
+    Key::new("witness-signing-policy")
+}
+
+/// Last transactions signed on behalf of nodes, oldest first.
+#[inline]
+pub fn wallet_witness_signing_audit() -> Key<&'static str, Vec<model::WitnessSigningAuditEntry>> {
+    Key::new("witness-signing-audit")
+}
+
+/// How much of the metadata of its movements a wallet keeps.
+#[inline]
+pub fn wallet_retention_policy() -> Key<&'static str, model::RetentionPolicy> {
+    Key::new("retention-policy")
+}
+
+/// Version of the schema of the records of a wallet.
+#[inline]
+pub fn wallet_schema_version() -> Key<&'static str, u32> {
+    Key::new("schema-version")
+}
+
+/// Key spec and coin type that the accounts of the wallet are derived with.
+#[inline]
+pub fn wallet_key_derivation() -> Key<&'static str, model::KeyDerivationRecord> {
+    Key::new("key-derivation")
+}
+
+/// Endpoint that the events of a wallet are posted to, if it has one.
+#[inline]
+pub fn wallet_webhook() -> Key<&'static str, model::WebhookConfig> {
+    Key::new("webhook")
+}
+
 /// A wallet's name.
 #[inline]
-pub fn wallet_id_name(id: &str) -> Key<String, String> {
+pub fn wallet_id_name(id: &model::WalletId) -> Key<String, String> {
     Key::new(format!("{}name", id))
 }
 
+/// A wallet's seed fingerprint, used for detecting wallets created from the same seed.
+#[inline]
+pub fn wallet_id_seed_fingerprint(wallet_id: &model::WalletId) -> Key<String, String> {
+    Key::new(format!("{}seed-fingerprint", wallet_id))
+}
+
+/// Failed confirmations of the seed backup of a wallet. Only present while the wallet is waiting
+/// for its seed backup to be confirmed.
+#[inline]
+pub fn wallet_id_pending_confirmation(wallet_id: &model::WalletId) -> Key<String, u32> {
+    Key::new(format!("{}pending-confirmation", wallet_id))
+}
+
+/// Epoch beyond which the blocks are not processed by a wallet. Only present while the
+/// synchronization of the wallet is pinned.
+#[inline]
+pub fn wallet_id_sync_target_epoch(wallet_id: &model::WalletId) -> Key<String, u32> {
+    Key::new(format!("{}sync-target-epoch", wallet_id))
+}
+
+/// Key spec and coin type that the accounts of a wallet are derived with, readable without
+/// unlocking the wallet.
+#[inline]
+pub fn wallet_id_key_derivation(
+    wallet_id: &model::WalletId,
+) -> Key<String, model::KeyDerivationRecord> {
+    Key::new(format!("{}key-derivation", wallet_id))
+}
+
+/// Salt of the seed fingerprints of all the wallets.
+#[inline]
+pub fn seed_fingerprint_salt() -> Key<&'static str, Vec<u8>> {
+    Key::new("seed-fingerprint-salt")
+}
+
 /// A wallet's encryption salt.
 #[inline]
-pub fn wallet_id_salt(wallet_id: &str) -> Key<String, Vec<u8>> {
+pub fn wallet_id_salt(wallet_id: &model::WalletId) -> Key<String, Vec<u8>> {
     Key::new(format!("{}salt", wallet_id))
 }
 
 /// A wallet's encryption iv.
 #[inline]
-pub fn wallet_id_iv(wallet_id: &str) -> Key<String, Vec<u8>> {
+pub fn wallet_id_iv(wallet_id: &model::WalletId) -> Key<String, Vec<u8>> {
     Key::new(format!("{}iv", wallet_id))
 }
 
@@ -132,6 +224,12 @@ pub fn wallet_last_sync() -> Key<&'static str, CheckpointBeacon> {
     Key::new("last-sync")
 }
 
+/// Hashes of the last confirmed blocks applied to the wallet, by epoch.
+#[inline]
+pub fn wallet_applied_blocks() -> Key<&'static str, BTreeMap<u32, Hash>> {
+    Key::new("applied-blocks")
+}
+
 /// An account's external key.
 #[inline]
 pub fn account_key(account_index: u32, keychain: u32) -> Key<String, ExtendedSK> {
@@ -144,18 +242,49 @@ pub fn account_balance(account_index: u32) -> Key<String, model::BalanceInfo> {
     Key::new(format!("account-{}-balance", account_index))
 }
 
+/// Balances of an account after the blocks that changed it, from the oldest.
+#[inline]
+pub fn account_balance_history(account_index: u32) -> Key<String, Vec<model::BalanceHistoryEntry>> {
+    Key::new(format!("account-{}-balance-history", account_index))
+}
+
 /// An account's UTXO set.
 #[inline]
 pub fn account_utxo_set(account_index: u32) -> Key<String, model::UtxoSet> {
     Key::new(format!("account-{}-utxo-set", account_index))
 }
 
+/// An account's collaterals of unresolved data requests.
+#[inline]
+pub fn account_collaterals(account_index: u32) -> Key<String, model::Collaterals> {
+    Key::new(format!("account-{}-collaterals", account_index))
+}
+
 /// An account's next index to use for generating an address.
 #[inline]
 pub fn account_next_index(account_index: u32, keychain: u32) -> Key<String, u32> {
     Key::new(format!("account-{}-{}-next-index", account_index, keychain))
 }
 
+/// Highest index of an address of an account's keychain that received a payment.
+#[inline]
+pub fn account_last_used_index(account_index: u32, keychain: u32) -> Key<String, u32> {
+    Key::new(format!(
+        "account-{}-{}-last-used-index",
+        account_index, keychain
+    ))
+}
+
+/// Highest next index that an account's keychain ever reached. Unlike the next index, it is kept
+/// when the chain data is cleared, so that resynchronizations look at least that far.
+#[inline]
+pub fn account_generation_watermark(account_index: u32, keychain: u32) -> Key<String, u32> {
+    Key::new(format!(
+        "account-{}-{}-generation-watermark",
+        account_index, keychain
+    ))
+}
+
 /// A wallet's account address.
 #[inline]
 pub fn address(account_index: u32, keychain: u32, key_index: u32) -> Key<String, String> {
@@ -200,6 +329,32 @@ pub fn address_info(
     ))
 }
 
+/// Account of another system that an address belongs to, as imported with its metadata.
+#[inline]
+pub fn address_account_tag(
+    account_index: u32,
+    keychain: u32,
+    key_index: u32,
+) -> Key<String, String> {
+    Key::new(format!(
+        "account-{}-key-{}-{}-address-account-tag",
+        account_index, keychain, key_index
+    ))
+}
+
+/// Last payment that reused an address after its payment request was done with.
+#[inline]
+pub fn address_reuse(
+    account_index: u32,
+    keychain: u32,
+    key_index: u32,
+) -> Key<String, model::AddressReuse> {
+    Key::new(format!(
+        "account-{}-key-{}-{}-address-reuse",
+        account_index, keychain, key_index
+    ))
+}
+
 /// Master key
 #[inline]
 pub fn master_key() -> Key<&'static str, ExtendedSK> {
@@ -224,6 +379,12 @@ pub fn transaction(transaction_hash: &str) -> Key<String, Transaction> {
     Key::new(format!("transaction-{}", transaction_hash))
 }
 
+/// Local labels of the outputs of a transaction created by the wallet.
+#[inline]
+pub fn transaction_output_labels(transaction_hash: &str) -> Key<String, Vec<Option<String>>> {
+    Key::new(format!("transaction-{}-output-labels", transaction_hash))
+}
+
 /// An index of transaction hashes.
 #[inline]
 pub fn transactions_index(transaction_hash: &[u8]) -> Key<Vec<u8>, u32> {
@@ -257,6 +418,137 @@ pub fn transaction_movement(
     ))
 }
 
+/// Transaction movement, as persisted before movements were categorized.
+///
+/// This is the same database key as `transaction_movement`.
+#[inline]
+pub fn uncategorized_transaction_movement(
+    account_index: u32,
+    transaction_id: u32,
+) -> Key<String, model::UncategorizedBalanceMovement> {
+    Key::new(format!(
+        "account-{}-transaction-{}-movement",
+        account_index, transaction_id
+    ))
+}
+
+/// Transaction movement, as persisted before movements included the full transaction context.
+///
+/// This is the same database key as `transaction_movement`.
+#[inline]
+pub fn legacy_transaction_movement(
+    account_index: u32,
+    transaction_id: u32,
+) -> Key<String, model::LegacyBalanceMovement> {
+    Key::new(format!(
+        "account-{}-transaction-{}-movement",
+        account_index, transaction_id
+    ))
+}
+
+/// Transaction movement whose metadata was pruned, which replaces its `transaction_movement`.
+#[inline]
+pub fn transaction_pruned_movement(
+    account_index: u32,
+    transaction_id: u32,
+) -> Key<String, model::PrunedMovement> {
+    Key::new(format!(
+        "account-{}-transaction-{}-pruned-movement",
+        account_index, transaction_id
+    ))
+}
+
+/// Id of the first transaction movement that has not been pruned.
+#[inline]
+pub fn transactions_pruned_until(account_index: u32) -> Key<String, u32> {
+    Key::new(format!(
+        "account-{}-transactions-pruned-until",
+        account_index
+    ))
+}
+
+/// Next payment request id.
+#[inline]
+pub fn payment_request_next_id(account_index: u32) -> Key<String, u32> {
+    Key::new(format!(
+        "account-{}-payment-requests-next-id",
+        account_index
+    ))
+}
+
+/// A payment request.
+#[inline]
+pub fn payment_request(
+    account_index: u32,
+    payment_request_id: u32,
+) -> Key<String, model::PaymentRequest> {
+    Key::new(format!(
+        "account-{}-payment-request-{}",
+        account_index, payment_request_id
+    ))
+}
+
+/// Entries of the retrieval cache of the request editor, from least to most recently used.
+#[inline]
+pub fn retrieval_cache_index() -> Key<&'static str, Vec<model::RetrievalCacheIndexEntry>> {
+    Key::new("retrieval-cache-index")
+}
+
+/// A response stored in the retrieval cache of the request editor.
+#[inline]
+pub fn retrieval_cache_entry(key: &str) -> Key<String, model::CachedRetrieval> {
+    Key::new(format!("retrieval-cache-entry-{}", key))
+}
+
+/// Requests made with an idempotency key that are recorded for a wallet, from the oldest.
+#[inline]
+pub fn idempotency_index() -> Key<&'static str, Vec<model::IdempotencyIndexEntry>> {
+    Key::new("idempotency-index")
+}
+
+/// A request made with an idempotency key.
+#[inline]
+pub fn idempotent_operation(key: &str) -> Key<String, model::IdempotentOperation> {
+    Key::new(format!("idempotent-operation-{}", key))
+}
+
+/// Last benchmark kept of the data source with this URL.
+#[inline]
+pub fn retrieval_benchmark(url: &str) -> Key<String, model::SourceBenchmark> {
+    Key::new(format!("retrieval-benchmark-{}", url))
+}
+
+/// Deliveries waiting to be posted to the webhook of a wallet, from the oldest.
+#[inline]
+pub fn webhook_outbox() -> Key<&'static str, Vec<model::WebhookOutboxEntry>> {
+    Key::new("webhook-outbox")
+}
+
+/// Identifiers of the deliveries to the webhook of a wallet that will not be retried unless they
+/// are requeued, from the oldest.
+#[inline]
+pub fn webhook_dead_letters() -> Key<&'static str, Vec<u64>> {
+    Key::new("webhook-dead-letters")
+}
+
+/// A delivery to the webhook of a wallet, either pending or dead-lettered.
+#[inline]
+pub fn webhook_delivery(id: u64) -> Key<String, model::WebhookDelivery> {
+    Key::new(format!("webhook-delivery-{}", id))
+}
+
+/// Identifier of the next delivery queued for the webhook of a wallet.
+#[inline]
+pub fn webhook_next_delivery_id() -> Key<&'static str, u64> {
+    Key::new("webhook-next-delivery-id")
+}
+
+/// Mnemonic words of a wallet, kept until the user confirms having backed them up.
+#[inline]
+pub fn seed_backup() -> Key<&'static str, types::Secret> {
+    Key::new("seed-backup")
+}
+
 /// Protocol epoch in which a wallet was created (won't synchronize blocks prior to this epoch)
 #[inline]
 pub fn birth_date() -> Key<&'static str, CheckpointBeacon> {
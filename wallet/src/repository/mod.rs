@@ -1,10 +1,18 @@
 mod error;
 pub mod keys;
+pub mod retrieval_cache;
+pub mod schema;
 mod wallet;
 mod wallets;
+pub mod webhook_outbox;
 
 pub use error::Error;
-pub use wallet::Wallet;
+pub use retrieval_cache::RetrievalCache;
+pub use wallet::{BlockScan, Wallet};
 pub use wallets::Wallets;
+pub use webhook_outbox::WebhookOutbox;
+
+#[cfg(test)]
+pub(crate) use wallet::tests::factories;
 
 pub type Result<T> = std::result::Result<T, Error>;
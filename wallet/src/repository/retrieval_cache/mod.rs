@@ -0,0 +1,149 @@
+//! Local cache of the responses of data sources, only used when running data requests from the
+//! request editor of the wallet. Never used for anything consensus-related.
+use std::sync::Mutex;
+
+use witnet_crypto::hash::calculate_sha256;
+
+use super::*;
+use crate::{
+    db::{Database, WriteBatch as _},
+    model,
+};
+
+#[cfg(test)]
+mod tests;
+
+/// Result of asking a data source for its current response.
+pub enum Fetched {
+    /// The source answered that the cached response passed to the fetch function is still valid.
+    NotModified,
+    /// A new response.
+    Modified(model::CachedRetrieval),
+}
+
+pub struct RetrievalCache<T> {
+    db: T,
+    /// Seconds after which a cached response must be revalidated.
+    ttl: i64,
+    /// Maximum total size in bytes of the cached responses.
+    max_size: u64,
+    index_mutex: Mutex<()>,
+}
+
+/// Key of the cached response of a request.
+pub fn cache_key(method: &str, url: &str, body: &[u8]) -> String {
+    let request = [method.as_bytes(), b" ", url.as_bytes(), b"\n", body].concat();
+
+    hex::encode(calculate_sha256(&request).as_ref())
+}
+
+impl<T: Database> RetrievalCache<T> {
+    pub fn new(db: T, ttl: i64, max_size: u64) -> Self {
+        Self {
+            db,
+            ttl,
+            max_size,
+            index_mutex: Default::default(),
+        }
+    }
+
+    /// Get the response of a request, from the cache if possible.
+    ///
+    /// Cached responses younger than the TTL are used as is. Older ones are passed to `fetch` so
+    /// that it can revalidate them with a conditional request. If `bypass` is set, the cached
+    /// response is ignored, but the fetched response is still cached.
+    pub fn retrieve<F, E>(
+        &self,
+        key: &str,
+        now: i64,
+        bypass: bool,
+        fetch: F,
+    ) -> std::result::Result<(String, model::CacheStatus), E>
+    where
+        F: FnOnce(Option<&model::CachedRetrieval>) -> std::result::Result<Fetched, E>,
+        E: From<Error>,
+    {
+        let cached = if bypass {
+            None
+        } else {
+            self.db
+                .get_opt(&keys::retrieval_cache_entry(key))
+                .map_err(Error::from)?
+        };
+
+        if let Some(cached) = &cached {
+            if now.saturating_sub(cached.fetched_at) < self.ttl {
+                self.touch(key)?;
+
+                return Ok((cached.body.clone(), model::CacheStatus::Hit));
+            }
+        }
+
+        let (entry, status) = match (fetch(cached.as_ref())?, cached) {
+            (Fetched::NotModified, Some(cached)) => (
+                model::CachedRetrieval {
+                    fetched_at: now,
+                    ..cached
+                },
+                model::CacheStatus::Revalidated,
+            ),
+            (Fetched::NotModified, None) => {
+                return Err(Error::RetrievalCache(
+                    "source answered not modified to an unconditional request".to_string(),
+                )
+                .into());
+            }
+            (Fetched::Modified(entry), _) => (entry, model::CacheStatus::Miss),
+        };
+        let body = entry.body.clone();
+        self.store(key, entry)?;
+
+        Ok((body, status))
+    }
+
+    /// Mark a cached response as the most recently used.
+    fn touch(&self, key: &str) -> Result<()> {
+        let _lock = self.index_mutex.lock()?;
+        let mut index = self.db.get_or_default(&keys::retrieval_cache_index())?;
+
+        if let Some(position) = index.iter().position(|entry| entry.key == key) {
+            let entry = index.remove(position);
+            index.push(entry);
+            self.db.put(&keys::retrieval_cache_index(), index)?;
+        }
+
+        Ok(())
+    }
+
+    /// Cache a response, evicting the least recently used ones if the cache gets too big.
+    /// Responses bigger than the whole cache are not stored.
+    fn store(&self, key: &str, entry: model::CachedRetrieval) -> Result<()> {
+        let _lock = self.index_mutex.lock()?;
+        let mut index = self.db.get_or_default(&keys::retrieval_cache_index())?;
+        let mut batch = self.db.batch();
+
+        index.retain(|indexed| indexed.key != key);
+        let size = entry.size();
+        if size <= self.max_size {
+            batch.put(&keys::retrieval_cache_entry(key), &entry)?;
+            index.push(model::RetrievalCacheIndexEntry {
+                key: key.to_string(),
+                size,
+            });
+        } else {
+            batch.delete(&keys::retrieval_cache_entry(key))?;
+        }
+
+        let mut total_size: u64 = index.iter().map(|indexed| indexed.size).sum();
+        while total_size > self.max_size && !index.is_empty() {
+            let evicted = index.remove(0);
+            batch.delete(&keys::retrieval_cache_entry(&evicted.key))?;
+            total_size -= evicted.size;
+        }
+
+        batch.put(&keys::retrieval_cache_index(), index)?;
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use std::cell::Cell;
+
+use super::*;
+use crate::db::HashMapDb;
+
+const TTL: i64 = 60;
+
+/// A data source honoring `If-None-Match`, that counts how many times it is contacted.
+struct MockServer {
+    body: String,
+    etag: String,
+    requests: Cell<u32>,
+    not_modified: Cell<u32>,
+}
+
+impl MockServer {
+    fn new(body: &str, etag: &str) -> Self {
+        Self {
+            body: body.to_string(),
+            etag: etag.to_string(),
+            requests: Cell::new(0),
+            not_modified: Cell::new(0),
+        }
+    }
+
+    fn fetch(
+        &self,
+        now: i64,
+        cached: Option<&model::CachedRetrieval>,
+    ) -> std::result::Result<Fetched, Error> {
+        self.requests.set(self.requests.get() + 1);
+
+        let if_none_match = cached.and_then(|cached| cached.etag.as_ref());
+        if if_none_match == Some(&self.etag) {
+            self.not_modified.set(self.not_modified.get() + 1);
+
+            Ok(Fetched::NotModified)
+        } else {
+            Ok(Fetched::Modified(model::CachedRetrieval {
+                body: self.body.clone(),
+                etag: Some(self.etag.clone()),
+                last_modified: None,
+                fetched_at: now,
+            }))
+        }
+    }
+}
+
+fn retrieve(
+    cache: &RetrievalCache<HashMapDb>,
+    server: &MockServer,
+    key: &str,
+    now: i64,
+    bypass: bool,
+) -> (String, model::CacheStatus) {
+    cache
+        .retrieve(key, now, bypass, |cached| server.fetch(now, cached))
+        .unwrap()
+}
+
+#[test]
+fn test_hit_and_revalidation() {
+    let cache = RetrievalCache::new(HashMapDb::default(), TTL, 1_000);
+    let server = MockServer::new(r#"{"price":1}"#, "v1");
+    let key = cache_key("GET", "https://example.com/price", b"");
+
+    let (body, status) = retrieve(&cache, &server, &key, 0, false);
+    assert_eq!(body, r#"{"price":1}"#);
+    assert_eq!(status, model::CacheStatus::Miss);
+
+    // Fresh entries are served without contacting the source
+    let (body, status) = retrieve(&cache, &server, &key, TTL - 1, false);
+    assert_eq!(body, r#"{"price":1}"#);
+    assert_eq!(status, model::CacheStatus::Hit);
+    assert_eq!(server.requests.get(), 1);
+
+    // Stale entries are revalidated with a conditional request
+    let (body, status) = retrieve(&cache, &server, &key, TTL, false);
+    assert_eq!(body, r#"{"price":1}"#);
+    assert_eq!(status, model::CacheStatus::Revalidated);
+    assert_eq!(server.requests.get(), 2);
+    assert_eq!(server.not_modified.get(), 1);
+
+    // Revalidation makes the entry fresh again
+    let (_body, status) = retrieve(&cache, &server, &key, TTL + 1, false);
+    assert_eq!(status, model::CacheStatus::Hit);
+}
+
+#[test]
+fn test_changed_source_is_fetched_again() {
+    let cache = RetrievalCache::new(HashMapDb::default(), TTL, 1_000);
+    let key = cache_key("GET", "https://example.com/price", b"");
+
+    let server = MockServer::new(r#"{"price":1}"#, "v1");
+    retrieve(&cache, &server, &key, 0, false);
+
+    let server = MockServer::new(r#"{"price":2}"#, "v2");
+    let (body, status) = retrieve(&cache, &server, &key, TTL, false);
+    assert_eq!(body, r#"{"price":2}"#);
+    assert_eq!(status, model::CacheStatus::Miss);
+    assert_eq!(server.not_modified.get(), 0);
+}
+
+#[test]
+fn test_bypass() {
+    let cache = RetrievalCache::new(HashMapDb::default(), TTL, 1_000);
+    let server = MockServer::new("body", "v1");
+    let key = cache_key("GET", "https://example.com", b"");
+
+    retrieve(&cache, &server, &key, 0, false);
+    let (_body, status) = retrieve(&cache, &server, &key, 1, true);
+
+    // Bypassing the cache sends an unconditional request
+    assert_eq!(status, model::CacheStatus::Miss);
+    assert_eq!(server.requests.get(), 2);
+    assert_eq!(server.not_modified.get(), 0);
+}
+
+#[test]
+fn test_lru_eviction() {
+    let db = HashMapDb::default();
+    // Room for two responses of 10 bytes (body and etag)
+    let cache = RetrievalCache::new(db.clone(), TTL, 20);
+    let server_a = MockServer::new("aaaaaaaa", "v1");
+    let server_b = MockServer::new("bbbbbbbb", "v1");
+    let server_c = MockServer::new("cccccccc", "v1");
+
+    retrieve(&cache, &server_a, "a", 0, false);
+    retrieve(&cache, &server_b, "b", 0, false);
+    // Using `a` makes `b` the least recently used entry
+    retrieve(&cache, &server_a, "a", 1, false);
+    retrieve(&cache, &server_c, "c", 2, false);
+
+    assert!(db.contains(&keys::retrieval_cache_entry("a")).unwrap());
+    assert!(!db.contains(&keys::retrieval_cache_entry("b")).unwrap());
+    assert!(db.contains(&keys::retrieval_cache_entry("c")).unwrap());
+    let index: Vec<String> = db
+        .get(&keys::retrieval_cache_index())
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.key)
+        .collect();
+    assert_eq!(index, vec!["a".to_string(), "c".to_string()]);
+
+    // Responses bigger than the whole cache are not stored
+    let server_big = MockServer::new(&"x".repeat(30), "v1");
+    let (_body, status) = retrieve(&cache, &server_big, "big", 3, false);
+    assert_eq!(status, model::CacheStatus::Miss);
+    assert!(!db.contains(&keys::retrieval_cache_entry("big")).unwrap());
+}
+
+#[test]
+fn test_cache_key() {
+    let get = cache_key("GET", "https://example.com", b"");
+    let post = cache_key("POST", "https://example.com", b"");
+    let post_body = cache_key("POST", "https://example.com", b"{}");
+
+    assert_ne!(get, post);
+    assert_ne!(post, post_body);
+    assert_eq!(get, cache_key("GET", "https://example.com", b""));
+}
@@ -0,0 +1,69 @@
+//! Versions of the format of the records of the wallet database.
+//!
+//! Databases record the version of the schema of their records when they are created. Opening one
+//! with an older version runs the migrations of every newer version in order, recording each
+//! version as soon as its migration finishes, so an interrupted upgrade resumes where it stopped.
+//! Databases with a newer version than the latest migration are never opened, because this
+//! software would misread the records it does not know and could corrupt them.
+use crate::{db::Database, repository::keys::Key};
+
+use super::{Error, Result};
+
+/// Records migrated between two writes of a migration, and between two progress logs.
+pub const MIGRATION_BATCH_SIZE: u32 = 1_000;
+
+/// Upgrade of the records of a database to a new version of its schema.
+pub struct Migration<C> {
+    /// Version of the schema after the migration
+    pub version: u32,
+    /// What the migration changes, for the logs
+    pub description: &'static str,
+    /// Rewrite the records of the database, in batches of at most `MIGRATION_BATCH_SIZE` records
+    pub run: fn(&C) -> Result<()>,
+}
+
+/// Version of the schema that the records of a new database are written in.
+pub fn latest_version<C>(migrations: &[Migration<C>]) -> u32 {
+    migrations.last().map_or(0, |migration| migration.version)
+}
+
+/// Bring the records of `db` up to the latest version of `migrations`, which must be sorted by
+/// version, and return the version that the database had. Databases without a version predate
+/// versioning, so all the migrations are run on them.
+pub fn migrate<T, C>(
+    db: &T,
+    version_key: &Key<&'static str, u32>,
+    context: &C,
+    name: &str,
+    migrations: &[Migration<C>],
+) -> Result<u32>
+where
+    T: Database,
+{
+    let found = db.get_or_default(version_key)?;
+    let supported = latest_version(migrations);
+    if found > supported {
+        return Err(Error::UnsupportedSchemaVersion { found, supported });
+    }
+
+    for migration in migrations
+        .iter()
+        .filter(|migration| migration.version > found)
+    {
+        log::info!(
+            "Migrating {} to schema version {}: {}",
+            name,
+            migration.version,
+            migration.description
+        );
+        (migration.run)(context)?;
+        db.put(version_key, migration.version)?;
+    }
+
+    Ok(found)
+}
+
+/// Log the progress of a migration that went through `done` of `total` records.
+pub fn log_progress(name: &str, done: u32, total: u32) {
+    log::info!("Migrated {} of {} records of {}", done, total, name);
+}
@@ -0,0 +1,138 @@
+//! In-memory index of the addresses of an unlocked wallet.
+//!
+//! Telling whether an output of a block belongs to the wallet is a lookup in this index rather
+//! than a read of the encrypted database. The index is loaded from the persisted public key hashes
+//! of the addresses when the wallet is unlocked, so no keys are derived for it, and it is updated
+//! as new addresses are generated.
+use std::mem;
+
+use super::*;
+
+/// Derivation of the public key hashes of the addresses of a keychain.
+pub trait Deriver: Send + Sync {
+    /// Public key hash of the address at `index` of the keychain whose key is `parent_key`.
+    fn derive_pkh(&self, parent_key: &ExtendedSK, index: u32) -> Result<PublicKeyHash>;
+}
+
+/// Derivation of addresses as the children of the keychain keys (WIP-0001).
+#[derive(Debug, Default)]
+pub struct Bip32Deriver;
+
+impl Deriver for Bip32Deriver {
+    fn derive_pkh(&self, parent_key: &ExtendedSK, index: u32) -> Result<PublicKeyHash> {
+        let extended_sk = parent_key.derive(&KeyPath::default().index(index))?;
+        let ExtendedPK { key, .. } = ExtendedPK::from_secret_key(&extended_sk);
+
+        Ok(witnet_data_structures::chain::PublicKey::from(key).pkh())
+    }
+}
+
+/// Outcome of looking up a public key hash in an `AddressIndex`.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Lookup {
+    /// The address belongs to the wallet and was derived at this path
+    Owned(model::Path),
+    /// The address does not belong to the wallet
+    NotOwned,
+    /// The index does not hold every address of the wallet, so the database has to be checked
+    Unknown,
+}
+
+/// Index of the addresses generated by a wallet, by public key hash.
+#[derive(Debug)]
+pub struct AddressIndex {
+    paths: HashMap<PublicKeyHash, model::Path>,
+    max_entries: usize,
+    complete: bool,
+}
+
+impl AddressIndex {
+    /// Create an empty index that holds at most `max_entries` addresses.
+    pub fn new(max_entries: usize) -> Self {
+        Self {
+            paths: HashMap::new(),
+            max_entries,
+            complete: true,
+        }
+    }
+
+    /// Add an address to the index. Returns `false` if it did not fit, in which case the lookups of
+    /// the addresses missing from the index fall back to the database from then on.
+    pub fn insert(&mut self, pkh: PublicKeyHash, path: model::Path) -> bool {
+        if self.paths.len() >= self.max_entries && !self.paths.contains_key(&pkh) {
+            self.complete = false;
+
+            return false;
+        }
+        self.paths.insert(pkh, path);
+
+        true
+    }
+
+    /// Tell that some address of the wallet is missing from the index.
+    pub fn set_incomplete(&mut self) {
+        self.complete = false;
+    }
+
+    /// Look up whether an address belongs to the wallet.
+    pub fn lookup(&self, pkh: &PublicKeyHash) -> Lookup {
+        match self.paths.get(pkh) {
+            Some(path) => Lookup::Owned(*path),
+            None if self.complete => Lookup::NotOwned,
+            None => Lookup::Unknown,
+        }
+    }
+
+    /// Remove every address from the index.
+    pub fn clear(&mut self) {
+        self.paths = HashMap::new();
+        self.complete = true;
+    }
+
+    /// Size of the index and an estimate of the memory that it takes.
+    pub fn usage(&self) -> metrics::AddressIndexUsage {
+        // Every bucket of the hash map holds an entry and a control byte
+        let bucket_bytes = mem::size_of::<(PublicKeyHash, model::Path)>() + 1;
+
+        metrics::AddressIndexUsage {
+            addresses: self.paths.len(),
+            max_addresses: self.max_entries,
+            approx_bytes: self.paths.capacity().saturating_mul(bucket_bytes),
+            complete: self.complete,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn path(index: u32) -> model::Path {
+        model::Path {
+            account: 0,
+            keychain: constants::EXTERNAL_KEYCHAIN,
+            index,
+        }
+    }
+
+    #[test]
+    fn lookup_falls_back_to_database_once_full() {
+        let mut index = AddressIndex::new(1);
+        let first = PublicKeyHash::from_bytes(&[1; 20]).unwrap();
+        let second = PublicKeyHash::from_bytes(&[2; 20]).unwrap();
+
+        assert!(index.insert(first, path(0)));
+        assert_eq!(index.lookup(&first), Lookup::Owned(path(0)));
+        assert_eq!(index.lookup(&second), Lookup::NotOwned);
+
+        assert!(!index.insert(second, path(1)));
+        assert_eq!(index.lookup(&first), Lookup::Owned(path(0)));
+        assert_eq!(index.lookup(&second), Lookup::Unknown);
+        assert!(!index.usage().complete);
+
+        index.clear();
+        assert_eq!(index.lookup(&first), Lookup::NotOwned);
+        assert_eq!(index.usage().addresses, 0);
+        assert!(index.usage().complete);
+    }
+}
@@ -0,0 +1,142 @@
+//! Import of the labels and account tags of the external addresses of a wallet.
+//!
+//! Every entry is checked against the address that the wallet derives at its index before
+//! anything is written, so a mapping made for another seed or another derivation is rejected as a
+//! whole. The external keychain is then extended up to the highest imported index, so that
+//! synchronizing finds the payments to all the imported addresses however far apart they are.
+use super::*;
+use crate::address_metadata::AddressMetadata;
+
+/// Entries written between progress updates.
+const PROGRESS_INTERVAL: u32 = 100;
+
+impl<T> Wallet<T>
+where
+    T: Database,
+{
+    /// Attach the labels and account tags of `entries` to the external addresses of the current
+    /// account. Entries that match what the addresses already have are left as they are, so
+    /// importing the same mapping again changes nothing.
+    ///
+    /// `progress` is called with how many entries have been written out of the total every
+    /// `PROGRESS_INTERVAL` of them and at the end.
+    pub fn import_address_metadata<F>(
+        &self,
+        entries: &[AddressMetadata],
+        mut progress: F,
+    ) -> Result<model::AddressMetadataImport>
+    where
+        F: FnMut(u32, u32),
+    {
+        let mut state = self.state.write()?;
+        let account = state.account;
+        let keychain = constants::EXTERNAL_KEYCHAIN;
+        let parent_key = state.keychains[keychain as usize].clone();
+
+        let mut indices = Vec::with_capacity(entries.len());
+        for entry in entries {
+            let index = match entry.index {
+                Some(index) => {
+                    let derived = self
+                        .deriver
+                        .derive_pkh(&parent_key, index)?
+                        .bech32(get_environment());
+                    if let Some(expected) = entry
+                        .address
+                        .as_ref()
+                        .filter(|expected| **expected != derived)
+                    {
+                        return Err(Error::AddressMismatch {
+                            index,
+                            expected: expected.clone(),
+                            derived,
+                        });
+                    }
+
+                    index
+                }
+                None => {
+                    let address = entry.address.clone().unwrap_or_default();
+                    PublicKeyHash::from_bech32(get_environment(), &address)
+                        .ok()
+                        .map(|pkh| self._address_path(&state, &pkh))
+                        .transpose()?
+                        .flatten()
+                        .filter(|path| path.account == account && path.keychain == keychain)
+                        .ok_or(Error::UnknownAddress(address))?
+                        .index
+                }
+            };
+            indices.push(index);
+        }
+
+        let previous_next_index = state.next_external_index;
+        if let Some(highest) = indices.iter().max() {
+            let next_index = highest.checked_add(1).ok_or(Error::IndexOverflow)?;
+            if next_index > state.next_external_index {
+                log::info!(
+                    "Extending the external addresses of wallet {} from index {} to {} to cover the imported metadata",
+                    self.id,
+                    state.next_external_index,
+                    next_index
+                );
+            }
+            while state.next_external_index < next_index {
+                let address = self._gen_external_address(&mut state, None)?;
+                state.transient_external_addresses.remove(&address.pkh);
+            }
+        }
+
+        let total = u32::try_from(entries.len()).unwrap_or(u32::MAX);
+        let mut report = model::AddressMetadataImport {
+            imported: 0,
+            unchanged: 0,
+            generated: state.next_external_index - previous_next_index,
+            next_external_index: state.next_external_index,
+        };
+        let mut batch = self.db.batch();
+        for (done, (entry, index)) in entries.iter().zip(indices).enumerate() {
+            let address = self._get_address(&state, account, keychain, index)?;
+            let label = entry.label.clone().or_else(|| address.info.label.clone());
+            let account_tag = entry
+                .account_tag
+                .clone()
+                .or_else(|| address.account_tag.clone());
+
+            if label == address.info.label && account_tag == address.account_tag {
+                report.unchanged += 1;
+            } else {
+                let mut info: model::AddressInfo =
+                    self.db.get(&keys::address_info(account, keychain, index))?;
+                info.label = label.clone();
+                batch.put(&keys::address_info(account, keychain, index), &info)?;
+                if let Some(account_tag) = &account_tag {
+                    batch.put(
+                        &keys::address_account_tag(account, keychain, index),
+                        account_tag,
+                    )?;
+                }
+                // Addresses that are waiting for their block to be confirmed would otherwise
+                // overwrite the new metadata once it is
+                if let Some(pending) = state.pending_addresses_by_path.get_mut(&address.path) {
+                    let mut updated = (**pending).clone();
+                    updated.info.label = label;
+                    updated.account_tag = account_tag;
+                    *pending = Arc::new(updated);
+                }
+                report.imported += 1;
+            }
+
+            let done = u32::try_from(done + 1).unwrap_or(u32::MAX);
+            if done % PROGRESS_INTERVAL == 0 && done < total {
+                self.db
+                    .write(std::mem::replace(&mut batch, self.db.batch()))?;
+                progress(done, total);
+            }
+        }
+        self.db.write(batch)?;
+        progress(total, total);
+
+        Ok(report)
+    }
+}
@@ -0,0 +1,164 @@
+//! History of the balance of a wallet.
+//!
+//! Every persisted block that changes the total balance of the wallet appends an entry to the
+//! history, or replaces the last one if both blocks fall in the same group of
+//! `balance_history_granularity` epochs. Entries older than a day are then downsampled to the last
+//! one of every hour, and entries older than `balance_history_daily_after` to the last one of every
+//! day. Ages are measured from the newest entry rather than from the current time, so the history
+//! does not depend on when the blocks were processed and a resynchronization rebuilds the same one.
+//!
+//! Blocks that are not confirmed by a superblock yet are only added to the history when it is
+//! queried, so rolling them back on a reorganization leaves nothing behind.
+use super::*;
+
+const SECONDS_PER_HOUR: u64 = 60 * 60;
+const SECONDS_PER_DAY: u64 = 24 * SECONDS_PER_HOUR;
+
+/// Total amount of funds of a balance, whether they are expendable or time-locked.
+fn total(balance: &model::BalanceInfo) -> u64 {
+    balance.available.saturating_add(balance.locked)
+}
+
+/// Difference between two amounts of funds, saturated to the range of `i64`.
+fn delta(from: u64, to: u64) -> i64 {
+    let delta = i128::from(to) - i128::from(from);
+
+    i64::try_from(delta).unwrap_or(if delta > 0 { i64::MAX } else { i64::MIN })
+}
+
+/// Period of time that an entry falls into at a resolution, or none if every entry is kept.
+fn bucket(
+    entry: &model::BalanceHistoryEntry,
+    resolution: model::BalanceHistoryResolution,
+) -> Option<u64> {
+    match resolution {
+        model::BalanceHistoryResolution::Block => None,
+        model::BalanceHistoryResolution::Hourly => Some(entry.timestamp / SECONDS_PER_HOUR),
+        model::BalanceHistoryResolution::Daily => Some(entry.timestamp / SECONDS_PER_DAY),
+    }
+}
+
+/// Keep the last of the consecutive entries that fall into the same period of time, at the
+/// resolution that `resolution_of` tells for each entry.
+fn keep_last_per_bucket<I, F>(entries: I, resolution_of: F) -> Vec<model::BalanceHistoryEntry>
+where
+    I: IntoIterator<Item = model::BalanceHistoryEntry>,
+    F: Fn(&model::BalanceHistoryEntry) -> model::BalanceHistoryResolution,
+{
+    let mut kept: Vec<model::BalanceHistoryEntry> = Vec::new();
+    for entry in entries {
+        let resolution = resolution_of(&entry);
+        match kept.last_mut() {
+            Some(last)
+                if bucket(&entry, resolution).is_some()
+                    && bucket(last, resolution) == bucket(&entry, resolution) =>
+            {
+                *last = entry
+            }
+            _ => kept.push(entry),
+        }
+    }
+
+    kept
+}
+
+/// Downsample the entries of a history that are older than a day to one per hour, and the ones
+/// older than `daily_after` seconds to one per day.
+fn downsample(
+    history: Vec<model::BalanceHistoryEntry>,
+    daily_after: u64,
+) -> Vec<model::BalanceHistoryEntry> {
+    let newest = match history.last() {
+        Some(entry) => entry.timestamp,
+        None => return history,
+    };
+    let daily_before = newest.saturating_sub(daily_after);
+    let hourly_before = newest.saturating_sub(SECONDS_PER_DAY);
+
+    keep_last_per_bucket(history, |entry| {
+        if entry.timestamp < daily_before {
+            model::BalanceHistoryResolution::Daily
+        } else if entry.timestamp < hourly_before {
+            model::BalanceHistoryResolution::Hourly
+        } else {
+            model::BalanceHistoryResolution::Block
+        }
+    })
+}
+
+/// History after recording the balance of a block that is about to be persisted, or none if the
+/// block did not change the balance.
+pub(super) fn record(
+    history: &[model::BalanceHistoryEntry],
+    epoch_constants: EpochConstants,
+    epoch: Epoch,
+    balance: &model::BalanceInfo,
+    params: &Params,
+) -> Option<Vec<model::BalanceHistoryEntry>> {
+    let confirmed = total(balance);
+    if history.last().map_or(0, |last| last.confirmed) == confirmed {
+        return None;
+    }
+
+    let entry = model::BalanceHistoryEntry {
+        epoch,
+        timestamp: convert_block_epoch_to_timestamp(epoch_constants, epoch),
+        confirmed,
+        pending_delta: 0,
+    };
+    let granularity = params.balance_history_granularity.max(1);
+    let mut history = history.to_vec();
+    match history.last_mut() {
+        Some(last) if last.epoch / granularity == epoch / granularity => *last = entry,
+        _ => history.push(entry),
+    }
+
+    Some(downsample(history, params.balance_history_daily_after))
+}
+
+impl<T> Wallet<T>
+where
+    T: Database,
+{
+    /// Retrieve the balance history of the current wallet account between two epochs (both
+    /// included), keeping the last entry of every period of time of `resolution`.
+    ///
+    /// The blocks that are not confirmed by a superblock yet come after the persisted entries,
+    /// with the change they make to the confirmed balance as their `pending_delta`.
+    pub fn balance_history(
+        &self,
+        from: Option<Epoch>,
+        to: Option<Epoch>,
+        resolution: model::BalanceHistoryResolution,
+    ) -> Result<Vec<model::BalanceHistoryEntry>> {
+        let state = self.state.read()?;
+        let confirmed = total(&state.balance.confirmed);
+
+        let mut pending_blocks: Vec<_> = state.pending_blocks.values().collect();
+        pending_blocks.sort_by_key(|block_state| block_state.beacon.epoch);
+
+        let mut entries = state.balance_history.clone();
+        let mut last_balance = confirmed;
+        for block_state in pending_blocks {
+            let balance = total(&block_state.balance);
+            if balance != last_balance {
+                entries.push(model::BalanceHistoryEntry {
+                    epoch: block_state.beacon.epoch,
+                    timestamp: convert_block_epoch_to_timestamp(
+                        state.epoch_constants,
+                        block_state.beacon.epoch,
+                    ),
+                    confirmed,
+                    pending_delta: delta(confirmed, balance),
+                });
+                last_balance = balance;
+            }
+        }
+
+        let in_range = entries.into_iter().filter(|entry| {
+            from.map_or(true, |from| entry.epoch >= from) && to.map_or(true, |to| entry.epoch <= to)
+        });
+
+        Ok(keep_last_per_bucket(in_range, |_| resolution))
+    }
+}
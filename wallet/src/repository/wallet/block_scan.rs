@@ -0,0 +1,50 @@
+//! Summary of what the transactions of a block involve.
+//!
+//! A block notified by the node is scanned once, and every unlocked wallet checks the scan against
+//! its address index and its unspent outputs. Only the wallets that find something of theirs in
+//! it walk the transactions of the block.
+use super::*;
+
+/// Addresses, outputs and data requests involved in the transactions of a block that can change
+/// the balance of a wallet.
+#[derive(Debug, Default)]
+pub struct BlockScan {
+    /// Addresses paid by the outputs of the block.
+    pub output_pkhs: HashSet<PublicKeyHash>,
+    /// Outputs spent by the inputs and the collateral of the block.
+    pub spent_outputs: HashSet<OutputPointer>,
+    /// Data requests resolved by the tallies of the block.
+    pub tallied_requests: HashSet<Hash>,
+}
+
+impl BlockScan {
+    /// Scan the transactions of a block. Reveals are left out, because they neither pay nor spend.
+    pub fn new(block: &Block) -> Self {
+        let txns = &block.txns;
+        let mut scan = Self::default();
+
+        for vtt in &txns.value_transfer_txns {
+            scan.add(&vtt.body.inputs, &vtt.body.outputs);
+        }
+        for drt in &txns.data_request_txns {
+            scan.add(&drt.body.inputs, &drt.body.outputs);
+        }
+        for commit in &txns.commit_txns {
+            scan.add(&commit.body.collateral, &commit.body.outputs);
+        }
+        for tally in &txns.tally_txns {
+            scan.add(&[], &tally.outputs);
+            scan.tallied_requests.insert(tally.dr_pointer);
+        }
+        scan.add(&[], &txns.mint.outputs);
+
+        scan
+    }
+
+    fn add(&mut self, inputs: &[Input], outputs: &[ValueTransferOutput]) {
+        self.spent_outputs
+            .extend(inputs.iter().map(|input| *input.output_pointer()));
+        self.output_pkhs
+            .extend(outputs.iter().map(|output| output.pkh));
+    }
+}
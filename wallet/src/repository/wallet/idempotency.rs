@@ -0,0 +1,70 @@
+//! Requests made to a wallet with an idempotency key, recorded to answer their retries.
+//!
+//! A request is recorded without a response as soon as it starts, and with its response once it
+//! is answered, so that a record without a response left behind by a daemon that stopped midway
+//! tells that the request may or may not have taken effect. Records expire after a retention
+//! window. They are listed in an index from the oldest, so that the expired ones are deleted as
+//! new ones are recorded.
+use super::*;
+
+impl<T> Wallet<T>
+where
+    T: Database,
+{
+    /// Get the request recorded under `key`, unless it was recorded before `expired_before`.
+    pub fn idempotent_operation(
+        &self,
+        key: &str,
+        expired_before: i64,
+    ) -> Result<Option<model::IdempotentOperation>> {
+        let operation = self.db.get_opt(&keys::idempotent_operation(key))?;
+
+        Ok(operation.filter(|operation| operation.recorded_at >= expired_before))
+    }
+
+    /// Record a request under `key`, replacing the one recorded under it if any, and delete the
+    /// requests recorded before `expired_before`.
+    pub fn record_idempotent_operation(
+        &self,
+        key: &str,
+        operation: &model::IdempotentOperation,
+        expired_before: i64,
+    ) -> Result<()> {
+        // The index is only updated while holding the lock of the state, so that concurrent
+        // updates are not lost
+        let _state = self.state.write()?;
+        let index = self.db.get_or_default(&keys::idempotency_index())?;
+        let mut batch = self.db.batch();
+
+        let (expired, mut index): (Vec<_>, Vec<_>) = index
+            .into_iter()
+            .filter(|indexed| indexed.key != key)
+            .partition(|indexed| indexed.recorded_at < expired_before);
+        for expired in expired {
+            batch.delete(&keys::idempotent_operation(&expired.key))?;
+        }
+        index.push(model::IdempotencyIndexEntry {
+            key: key.to_string(),
+            recorded_at: operation.recorded_at,
+        });
+        batch.put(&keys::idempotent_operation(key), operation)?;
+        batch.put(&keys::idempotency_index(), index)?;
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Forget the request recorded under `key`, so that a retry runs it again.
+    pub fn forget_idempotent_operation(&self, key: &str) -> Result<()> {
+        let _state = self.state.write()?;
+        let mut index = self.db.get_or_default(&keys::idempotency_index())?;
+        let mut batch = self.db.batch();
+
+        index.retain(|indexed| indexed.key != key);
+        batch.delete(&keys::idempotent_operation(key))?;
+        batch.put(&keys::idempotency_index(), index)?;
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+}
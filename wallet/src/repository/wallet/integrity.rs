@@ -0,0 +1,319 @@
+//! Integrity check of the records of a wallet database.
+//!
+//! Some records are derived from others: the next indices from the persisted addresses and
+//! movements, the index entries from the records they point to, and the balance from the UTXO set.
+//! If writing to the database gets interrupted, e.g. by a power loss, they can get out of step.
+//! Those can be derived again, while records that are missing altogether can only be recovered by
+//! synchronizing the wallet again.
+use super::*;
+
+/// Records checked between progress updates.
+const PROGRESS_INTERVAL: u32 = 1_000;
+
+/// Counter of checked records that reports on them every `PROGRESS_INTERVAL` records.
+struct Progress<F> {
+    checked: u32,
+    total: u32,
+    report: F,
+}
+
+impl<F> Progress<F>
+where
+    F: FnMut(u32, u32),
+{
+    fn tick(&mut self) {
+        self.checked = self.checked.saturating_add(1);
+        if self.checked % PROGRESS_INTERVAL == 0 {
+            (self.report)(self.checked, self.total);
+        }
+    }
+
+    fn finish(&mut self) {
+        (self.report)(self.total, self.total);
+    }
+}
+
+/// Index entry to be written again, pointing to the record it belongs to.
+enum Repair {
+    Address(PublicKeyHash, model::Path),
+    Transaction(Vec<u8>, u32),
+}
+
+impl<T> Wallet<T>
+where
+    T: Database,
+{
+    /// Check that the records of the wallet are consistent with each other, and derive again the
+    /// inconsistent ones that can be derived from others if `repair` is true.
+    ///
+    /// Addresses, movements and unspent outputs are checked without locking the wallet state, and
+    /// `progress` is called with how many of them have been checked out of the total every
+    /// `PROGRESS_INTERVAL` of them and at the end. The rest of the check and the repairs take a
+    /// write lock, so that blocks are not persisted in the meantime.
+    pub fn check_integrity<F>(&self, repair: bool, progress: F) -> Result<model::IntegrityReport>
+    where
+        F: FnMut(u32, u32),
+    {
+        let account = self.state.read()?.account;
+        let keychains = [constants::EXTERNAL_KEYCHAIN, constants::INTERNAL_KEYCHAIN];
+        let mut next_indices = [0; 2];
+        for (next_index, keychain) in next_indices.iter_mut().zip(keychains) {
+            *next_index = self
+                .db
+                .get_or_default(&keys::account_next_index(account, keychain))?;
+        }
+        let transaction_next_id = self
+            .db
+            .get_or_default(&keys::transaction_next_id(account))?;
+        // Movements persisted beyond the next id also count as the movements of their transactions
+        let movements_until = self.persisted_until(transaction_next_id, |id| {
+            keys::transaction_movement(account, id)
+        })?;
+        let utxo_set: model::UtxoSet = self.db.get_or_default(&keys::account_utxo_set(account))?;
+        let utxo_count = u32::try_from(utxo_set.len()).unwrap_or(u32::MAX);
+
+        let mut progress = Progress {
+            checked: 0,
+            total: next_indices
+                .iter()
+                .fold(movements_until, |acc, index| acc.saturating_add(*index))
+                .saturating_add(utxo_count),
+            report: progress,
+        };
+        let mut findings = vec![];
+        let mut repairs = vec![];
+        let mut movement_hashes = HashSet::new();
+
+        // Every address below the next index of its keychain can be found from its pkh
+        for (next_index, keychain) in next_indices.iter().zip(keychains) {
+            for index in 0..*next_index {
+                progress.tick();
+                let pkh = match self
+                    .db
+                    .get_opt(&keys::address_pkh(account, keychain, index))?
+                {
+                    Some(pkh) if self.db.contains(&keys::address(account, keychain, index))? => pkh,
+                    _ => {
+                        findings.push(model::IntegrityFinding {
+                            issue: model::IntegrityIssue::MissingAddress { keychain, index },
+                            resolution: model::IntegrityResolution::NeedsResync,
+                        });
+                        continue;
+                    }
+                };
+                let path: Option<model::Path> = self.db.get_opt(&keys::pkh(&pkh))?;
+                let points_to_address = path.map_or(false, |path| {
+                    path.account == account && path.keychain == keychain && path.index == index
+                });
+                if !points_to_address {
+                    findings.push(model::IntegrityFinding {
+                        issue: model::IntegrityIssue::BrokenAddressIndexEntry { keychain, index },
+                        resolution: model::IntegrityResolution::Repairable,
+                    });
+                    repairs.push(Repair::Address(
+                        pkh,
+                        model::Path {
+                            account,
+                            keychain,
+                            index,
+                        },
+                    ));
+                }
+            }
+        }
+
+        // Every movement below the next transaction id can be found from its transaction hash,
+        // whether its metadata was pruned or not
+        for id in 0..movements_until {
+            progress.tick();
+            let hash = match self.db.get_opt(&keys::transaction_hash(account, id))? {
+                Some(hash)
+                    if self.db.contains(&keys::transaction_movement(account, id))?
+                        || self
+                            .db
+                            .contains(&keys::transaction_pruned_movement(account, id))? =>
+                {
+                    hash
+                }
+                _ => {
+                    findings.push(model::IntegrityFinding {
+                        issue: model::IntegrityIssue::MissingMovement { id },
+                        resolution: model::IntegrityResolution::NeedsResync,
+                    });
+                    continue;
+                }
+            };
+            movement_hashes.insert(hash.clone());
+            if self.db.get_opt(&keys::transactions_index(&hash))? != Some(id) {
+                findings.push(model::IntegrityFinding {
+                    issue: model::IntegrityIssue::BrokenTransactionIndexEntry {
+                        id,
+                        transaction_hash: hex::encode(&hash),
+                    },
+                    resolution: model::IntegrityResolution::Repairable,
+                });
+                repairs.push(Repair::Transaction(hash, id));
+            }
+        }
+
+        // Every unspent output was created by a transaction with a movement
+        for out_ptr in utxo_set.keys() {
+            progress.tick();
+            if !movement_hashes.contains(&out_ptr.txn_hash) {
+                findings.push(model::IntegrityFinding {
+                    issue: model::IntegrityIssue::UtxoWithoutMovement {
+                        output_pointer: out_ptr.to_string(),
+                    },
+                    resolution: model::IntegrityResolution::NeedsResync,
+                });
+            }
+        }
+
+        {
+            let mut state = self.state.write()?;
+            let mut batch = self.db.batch();
+
+            // No addresses nor movements were persisted beyond the next index or id
+            for keychain in keychains {
+                let next_index = self
+                    .db
+                    .get_or_default(&keys::account_next_index(account, keychain))?;
+                let persisted_until = self
+                    .persisted_until(next_index, |index| keys::address(account, keychain, index))?;
+                if persisted_until > next_index {
+                    findings.push(model::IntegrityFinding {
+                        issue: model::IntegrityIssue::AddressIndexBehind {
+                            keychain,
+                            next_index,
+                            persisted_until,
+                        },
+                        resolution: model::IntegrityResolution::Repairable,
+                    });
+                    if repair {
+                        batch.put(
+                            &keys::account_next_index(account, keychain),
+                            persisted_until,
+                        )?;
+                        let state_index = if keychain == constants::EXTERNAL_KEYCHAIN {
+                            &mut state.next_external_index
+                        } else {
+                            &mut state.next_internal_index
+                        };
+                        *state_index = (*state_index).max(persisted_until);
+                    }
+                }
+            }
+            if repair {
+                // The addresses persisted beyond the old next indices are indexed as well
+                self._rebuild_address_index(&mut state)?;
+            }
+            let next_id = self
+                .db
+                .get_or_default(&keys::transaction_next_id(account))?;
+            let persisted_until =
+                self.persisted_until(next_id, |id| keys::transaction_movement(account, id))?;
+            if persisted_until > next_id {
+                findings.push(model::IntegrityFinding {
+                    issue: model::IntegrityIssue::TransactionIdBehind {
+                        next_id,
+                        persisted_until,
+                    },
+                    resolution: model::IntegrityResolution::Repairable,
+                });
+                if repair {
+                    batch.put(&keys::transaction_next_id(account), persisted_until)?;
+                    state.transaction_next_id = state.transaction_next_id.max(persisted_until);
+                }
+            }
+
+            // The persisted balance adds up to the persisted UTXO set
+            let utxo_set: model::UtxoSet =
+                self.db.get_or_default(&keys::account_utxo_set(account))?;
+            if let Some(balance) = self.db.get_opt(&keys::account_balance(account))? {
+                let persisted = balance
+                    .available
+                    .checked_add(balance.locked)
+                    .ok_or(Error::TransactionBalanceOverflow)?;
+                let utxo_total = utxo_set
+                    .values()
+                    .try_fold(0u64, |acc, output| acc.checked_add(output.amount))
+                    .ok_or(Error::TransactionBalanceOverflow)?;
+                if persisted != utxo_total {
+                    findings.push(model::IntegrityFinding {
+                        issue: model::IntegrityIssue::BalanceMismatch {
+                            persisted,
+                            utxo_set: utxo_total,
+                        },
+                        resolution: model::IntegrityResolution::Repairable,
+                    });
+                    if repair {
+                        let timestamp = u64::try_from(get_timestamp())
+                            .expect("Get timestamp should return a positive value");
+                        let balance = utxo_set_balance(&utxo_set, timestamp);
+                        batch.put(&keys::account_balance(account), balance)?;
+                        state.balance.confirmed = balance;
+                        // Blocks pending consolidation recompute the unconfirmed balance from the
+                        // UTXO set in memory, which is not affected
+                        if state.pending_blocks.is_empty() {
+                            state.balance.unconfirmed = balance;
+                        }
+                    }
+                }
+            }
+
+            // Outputs reserved by pending transactions are unspent
+            let stale_reservations: Vec<model::OutPtr> = state
+                .used_outputs
+                .keys()
+                .filter(|out_ptr| !state.utxo_set.contains_key(out_ptr))
+                .cloned()
+                .collect();
+            for out_ptr in stale_reservations {
+                findings.push(model::IntegrityFinding {
+                    issue: model::IntegrityIssue::StaleReservation {
+                        output_pointer: out_ptr.to_string(),
+                    },
+                    resolution: model::IntegrityResolution::Repairable,
+                });
+                if repair {
+                    state.used_outputs.remove(&out_ptr);
+                }
+            }
+
+            if repair {
+                for repair in repairs {
+                    match repair {
+                        Repair::Address(pkh, path) => batch.put(&keys::pkh(&pkh), path)?,
+                        Repair::Transaction(hash, id) => {
+                            batch.put(&keys::transactions_index(&hash), id)?
+                        }
+                    }
+                }
+                self.db.write(batch)?;
+
+                for finding in &mut findings {
+                    if finding.resolution == model::IntegrityResolution::Repairable {
+                        finding.resolution = model::IntegrityResolution::Repaired;
+                    }
+                }
+            }
+        }
+        progress.finish();
+
+        Ok(model::IntegrityReport { findings })
+    }
+
+    /// The index following the last of the consecutive records that are persisted from `start`.
+    fn persisted_until<K, V, Q>(&self, start: u32, key: Q) -> Result<u32>
+    where
+        K: AsRef<[u8]>,
+        Q: Fn(u32) -> keys::Key<K, V>,
+    {
+        let mut index = start;
+        while self.db.contains(&key(index))? {
+            index = index.checked_add(1).ok_or(Error::IndexOverflow)?;
+        }
+
+        Ok(index)
+    }
+}
@@ -0,0 +1,90 @@
+//! Migrations of the records of a wallet to newer versions of their schema.
+//!
+//! Wallets created before their schema was versioned have no version record, and are migrated from
+//! version 0.
+use std::mem;
+
+use super::*;
+use crate::repository::schema::{self, Migration};
+
+/// Version of the schema of the records of new wallets, which is the one of their last migration.
+pub const WALLET_SCHEMA_VERSION: u32 = 2;
+
+/// Migrations of the records of a wallet, sorted by version.
+pub fn migrations<T: Database>() -> Vec<Migration<T>> {
+    vec![
+        Migration {
+            version: 1,
+            description:
+                "rewrite the movements stored without their transaction context or category",
+            run: migrate_movements,
+        },
+        Migration {
+            version: 2,
+            description: "rewrite the witness signing policy stored without a maximum collateral",
+            run: migrate_witness_signing_policy,
+        },
+    ]
+}
+
+/// Bring the records of the wallet stored in `db` up to `WALLET_SCHEMA_VERSION`.
+pub fn migrate<T: Database>(db: &T, id: &model::WalletId) -> Result<u32> {
+    schema::migrate(
+        db,
+        &keys::wallet_schema_version(),
+        db,
+        &format!("wallet {}", id),
+        &migrations(),
+    )
+}
+
+/// Rewrite in the current format the movements that are stored in the formats from before the
+/// transaction context and the category of movements were recorded.
+fn migrate_movements<T: Database>(db: &T) -> Result<()> {
+    let default_account = db.get_or_default(&keys::wallet_default_account())?;
+    let accounts = db
+        .get_opt(&keys::wallet_accounts())?
+        .unwrap_or_else(|| vec![default_account]);
+
+    for account in accounts {
+        let total = db.get_or_default(&keys::transaction_next_id(account))?;
+        let name = format!("the movements of account {}", account);
+        let mut batch = db.batch();
+        for id in 0..total {
+            match db.get(&keys::transaction_movement(account, id)) {
+                Err(db::Error::Bincode(_)) => {
+                    let movement = get_movement(db, account, id)?;
+                    batch.put(&keys::transaction_movement(account, id), movement)?;
+                }
+                // Movements whose metadata was pruned have no full record
+                Ok(_) | Err(db::Error::DbKeyNotFound { .. }) => {}
+                Err(err) => return Err(err.into()),
+            }
+
+            let done = id + 1;
+            if done % schema::MIGRATION_BATCH_SIZE == 0 || done == total {
+                db.write(mem::replace(&mut batch, db.batch()))?;
+                schema::log_progress(&name, done, total);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Rewrite in the current format the witness signing policy stored before it had a maximum
+/// collateral, which is none, so that nodes cannot lock any funds until the owner allows it.
+fn migrate_witness_signing_policy<T: Database>(db: &T) -> Result<()> {
+    match db.get(&keys::wallet_witness_signing_policy()) {
+        Err(db::Error::Bincode(_)) => {
+            let policy: model::WitnessSigningPolicy = db
+                .get(&keys::legacy_wallet_witness_signing_policy())?
+                .into();
+            db.put(&keys::wallet_witness_signing_policy(), policy)?;
+        }
+        Ok(_) | Err(db::Error::DbKeyNotFound { .. }) => {}
+        Err(err) => return Err(err.into()),
+    }
+
+    Ok(())
+}
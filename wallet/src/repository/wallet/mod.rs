@@ -1,34 +1,35 @@
 use itertools::Itertools;
 use std::{
-    cmp::min,
-    collections::{hash_map::Entry, HashMap, HashSet},
+    cmp::max,
+    collections::{hash_map::Entry, BTreeMap, HashMap, HashSet},
     convert::TryFrom,
     ops::Range,
     str::FromStr,
     sync::{Arc, RwLock, RwLockReadGuard},
 };
 
+use address_index::{AddressIndex, Lookup};
 use bech32::ToBase32;
 use state::State;
 use witnet_crypto::{
-    hash::calculate_sha256,
     key::{ExtendedPK, ExtendedSK, KeyPath, PK},
     signature,
 };
 use witnet_data_structures::{
     chain::{
-        CheckpointBeacon, DataRequestOutput, Environment, Epoch, EpochConstants, Hash, Hashable,
-        Input, KeyedSignature, OutputPointer, PublicKeyHash, ValueTransferOutput,
+        Block, CheckpointBeacon, DataRequestOutput, Environment, Epoch, EpochConstants, Hash,
+        Hashable, Input, KeyedSignature, OutputPointer, PublicKeyHash, ValueTransferOutput,
     },
     fee::{AbsoluteFee, Fee},
     get_environment,
     radon_error::RadonError,
     transaction::{
-        DRTransaction, DRTransactionBody, TallyTransaction, Transaction, VTTransaction,
-        VTTransactionBody,
+        dr_weight, vt_weight, DRTransaction, DRTransactionBody, TallyTransaction, Transaction,
+        VTTransaction, VTTransactionBody,
     },
     transaction_factory::{
-        insert_change_output, CollectedOutputs, OutputsCollection, TransactionInfo,
+        calculate_weight, insert_change_output, transaction_outputs_sum, CollectedOutputs,
+        OutputsCollection, TransactionInfo,
     },
     utxo_pool::UtxoSelectionStrategy,
 };
@@ -36,24 +37,45 @@ use witnet_rad::{error::RadError, types::RadonTypes};
 use witnet_util::timestamp::get_timestamp;
 
 use crate::{
-    constants, crypto,
-    db::{Database, WriteBatch as _},
-    model,
+    coin_selection, constants, crypto,
+    db::{self, Database, WriteBatch as _},
+    metrics, model,
     params::Params,
     types,
+    witness_signing::WitnessTransactionBody,
 };
 
 use super::*;
 
+pub use address_index::{Bip32Deriver, Deriver};
+pub use block_scan::BlockScan;
+
+mod address_index;
+mod address_metadata;
+mod balance_history;
+mod block_scan;
+mod idempotency;
+mod integrity;
+pub mod migrations;
+mod pruning;
 mod state;
 #[cfg(test)]
-mod tests;
+pub(crate) mod tests;
 
 /// Internal structure used to gather state mutations while indexing block transactions
 struct AccountMutation {
     balance_movement: model::BalanceMovement,
     utxo_inserts: Vec<(model::OutPtr, model::OutputInfo)>,
     utxo_removals: Vec<model::OutPtr>,
+    collateral_change: Option<CollateralChange>,
+}
+
+/// Change in the collaterals of the wallet caused by a commit or tally transaction
+enum CollateralChange {
+    /// A commit locked this amount as collateral for a data request
+    Locked(String, u64),
+    /// The tally of a data request resolved the collateral locked for it
+    Released(String),
 }
 
 /// Struct that keep the unspent outputs pool and the own unspent outputs pool
@@ -180,11 +202,15 @@ pub struct TransactionComponents {
 }
 
 pub struct Wallet<T> {
-    pub id: String,
+    pub id: model::WalletId,
     pub session_id: types::SessionId,
     db: T,
     params: Params,
     state: RwLock<State>,
+    deriver: Arc<dyn Deriver>,
+    key_derivation: model::KeyDerivation,
+    /// Events waiting to be posted to the webhook of the wallet, in its own database.
+    webhook_outbox: Arc<WebhookOutbox<T>>,
 }
 
 impl<T> Wallet<T>
@@ -200,8 +226,11 @@ where
     ) -> Result<()> {
         let mut state = self.state.write()?;
 
-        let external_range =
-            state.next_external_index..state.next_external_index + u32::from(external_addresses);
+        // Addresses generated before the chain data was cleared are looked at even if they are
+        // beyond the gap limit
+        let external_range = state.next_external_index
+            ..(state.next_external_index + u32::from(external_addresses))
+                .max(state.external_generation_watermark);
         let internal_range =
             state.next_internal_index..state.next_internal_index + u32::from(internal_addresses);
 
@@ -277,6 +306,8 @@ where
         state.pending_addresses_by_path.clear();
         state.pending_addresses_by_block.clear();
         state.local_movements.clear();
+        state.local_transactions.clear();
+        state.fee_bumps.clear();
         state.db_movements_to_update.clear();
 
         // Restore state from database
@@ -285,23 +316,67 @@ where
             .get_or_default(&keys::transaction_next_id(account))?;
         state.utxo_set = self.db.get_or_default(&keys::account_utxo_set(account))?;
         state.used_outputs = model::clean_used_outputs(&state.used_outputs, &state.utxo_set);
+        state.collaterals = self
+            .db
+            .get_or_default(&keys::account_collaterals(account))?;
         state.balance.confirmed = self.db.get_or_default(&keys::account_balance(account))?;
         state.balance.unconfirmed = state.balance.confirmed;
+        state.balance.collateralized = collateralized_amount(&state.collaterals)?;
+        state.balance_history = self
+            .db
+            .get_or_default(&keys::account_balance_history(account))?;
 
         Ok(())
     }
 
-    pub fn unlock(id: &str, session_id: types::SessionId, db: T, params: Params) -> Result<Self> {
-        let id = id.to_owned();
+    pub fn unlock(
+        id: &model::WalletId,
+        session_id: types::SessionId,
+        db: T,
+        params: Params,
+    ) -> Result<Self>
+    where
+        T: Clone,
+    {
+        Self::unlock_with_deriver(id, session_id, db, params, Arc::new(Bip32Deriver))
+    }
+
+    /// Unlock a wallet whose addresses are derived by `deriver`.
+    pub fn unlock_with_deriver(
+        id: &model::WalletId,
+        session_id: types::SessionId,
+        db: T,
+        params: Params,
+        deriver: Arc<dyn Deriver>,
+    ) -> Result<Self>
+    where
+        T: Clone,
+    {
+        let id = id.clone();
+        migrations::migrate(&db, &id)?;
+        // Wallets created before the key derivation was recorded use the legacy one
+        let key_derivation = match db.get_opt(&keys::wallet_key_derivation())? {
+            Some(record) => model::KeyDerivation::try_from(record)
+                .map_err(|err| Error::UnsupportedKeySpec(err.0))?,
+            None => model::KeyDerivation::LEGACY,
+        };
         let name = db.get_opt(&keys::wallet_name())?;
         let description = db.get_opt(&keys::wallet_description())?;
+        let policy = db.get_or_default(&keys::wallet_policy())?;
+        let witness_signing_policy = db.get_or_default(&keys::wallet_witness_signing_policy())?;
+        let webhook = db.get_opt(&keys::wallet_webhook())?;
         let account = db.get_or_default(&keys::wallet_default_account())?;
         let available_accounts = db
             .get_opt(&keys::wallet_accounts())?
             .unwrap_or_else(|| vec![account]);
 
         let transaction_next_id = db.get_or_default(&keys::transaction_next_id(account))?;
+        let payment_request_next_id = db.get_or_default(&keys::payment_request_next_id(account))?;
+        let payment_requests = (0..payment_request_next_id)
+            .map(|id| db.get(&keys::payment_request(account, id)))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
         let utxo_set: model::UtxoSet = db.get_or_default(&keys::account_utxo_set(account))?;
+        let collaterals = db.get_or_default(&keys::account_collaterals(account))?;
         let timestamp =
             u64::try_from(get_timestamp()).expect("Get timestamp should return a positive value");
         let balance_info = db
@@ -310,29 +385,16 @@ where
                 // compute balance from utxo set if is not cached in the
                 // database, this is mostly used for testing where overflow
                 // checks are enabled
-                utxo_set
-                    .values()
-                    .map(|balance| (balance.amount, balance.time_lock))
-                    .fold(
-                        model::BalanceInfo::default(),
-                        |mut acc, (amount, time_lock)| {
-                            if timestamp >= time_lock {
-                                acc.available =
-                                    acc.available.checked_add(amount).expect("balance overflow");
-                            } else {
-                                acc.locked =
-                                    acc.locked.checked_add(amount).expect("balance overflow");
-                            }
-
-                            acc
-                        },
-                    )
+                utxo_set_balance(&utxo_set, timestamp)
             });
         let balance = model::WalletBalance {
             local: 0,
             unconfirmed: balance_info,
             confirmed: balance_info,
+            collateralized: collateralized_amount(&collaterals)?,
         };
+        let balance_history = db.get_or_default(&keys::account_balance_history(account))?;
+        let applied_blocks = db.get_or_default(&keys::wallet_applied_blocks())?;
 
         let last_sync = db
             .get(&keys::wallet_last_sync())
@@ -348,6 +410,25 @@ where
             account,
             constants::EXTERNAL_KEYCHAIN,
         ))?;
+        let last_used_external_index = match db.get_opt(&keys::account_last_used_index(
+            account,
+            constants::EXTERNAL_KEYCHAIN,
+        ))? {
+            Some(index) => Some(index),
+            // Wallets created before the last used index was stored look for it
+            None => last_used_address_index(
+                &db,
+                account,
+                constants::EXTERNAL_KEYCHAIN,
+                next_external_index,
+            )?,
+        };
+        let external_generation_watermark = db
+            .get_or_default(&keys::account_generation_watermark(
+                account,
+                constants::EXTERNAL_KEYCHAIN,
+            ))?
+            .max(next_external_index);
         let internal_key = db.get(&keys::account_key(account, constants::INTERNAL_KEYCHAIN))?;
         let next_internal_index = db.get_or_default(&keys::account_next_index(
             account,
@@ -361,41 +442,139 @@ where
         });
 
         let state = RwLock::new(State {
+            applied_blocks,
             name,
             description,
+            policy,
+            witness_signing_policy,
+            webhook,
             account,
             keychains,
             next_external_index,
             next_internal_index,
+            last_used_external_index,
+            external_generation_watermark,
+            address_gap_exceeded: false,
             available_accounts,
             balance,
+            balance_history,
             transaction_next_id,
             utxo_set,
+            collaterals,
             used_outputs: Default::default(),
             epoch_constants,
             last_sync,
             last_confirmed,
             local_movements: Default::default(),
+            local_transactions: Default::default(),
+            fee_bumps: Default::default(),
             pending_movements: Default::default(),
             pending_transactions: Default::default(),
             pending_addresses_by_block: Default::default(),
             pending_addresses_by_path: Default::default(),
+            payment_requests,
+            payment_request_updates: Default::default(),
+            address_reuses: Default::default(),
+            address_gap_warnings: Default::default(),
             pending_blocks: Default::default(),
             pending_dr_movements: Default::default(),
             db_movements_to_update: Default::default(),
             transient_external_addresses: Default::default(),
             transient_internal_addresses: Default::default(),
+            address_index: AddressIndex::new(params.address_index_max_entries),
             stop_syncing: false,
+            sync_target_epoch: None,
             birth_date,
         });
 
-        Ok(Self {
+        let webhook_outbox = Arc::new(WebhookOutbox::new(
+            id.clone(),
+            db.clone(),
+            params.webhook_outbox_size,
+            params.webhook_max_attempts,
+            params.webhook_retry_delay,
+        ));
+
+        let wallet = Self {
             id,
             session_id,
             db,
             params,
             state,
-        })
+            deriver,
+            key_derivation,
+            webhook_outbox,
+        };
+        let usage = {
+            let mut state = wallet.state.write()?;
+            wallet._rebuild_address_index(&mut state)?;
+
+            state.address_index.usage()
+        };
+        log::debug!(
+            "Indexed {} addresses of wallet {} (~{} bytes)",
+            usage.addresses,
+            wallet.id,
+            usage.approx_bytes
+        );
+        if !usage.complete {
+            log::warn!(
+                "Wallet {} has more than {} addresses, the rest of them will be looked up in the database",
+                wallet.id,
+                usage.max_addresses
+            );
+        }
+
+        Ok(wallet)
+    }
+
+    /// Load the in-memory index of addresses from the public key hashes persisted for them, without
+    /// deriving any key.
+    fn _rebuild_address_index(&self, state: &mut State) -> Result<()> {
+        let account = state.account;
+        state.address_index.clear();
+
+        for (keychain, next_index) in [
+            (constants::EXTERNAL_KEYCHAIN, state.next_external_index),
+            (constants::INTERNAL_KEYCHAIN, state.next_internal_index),
+        ] {
+            for index in 0..next_index {
+                match self
+                    .db
+                    .get_opt(&keys::address_pkh(account, keychain, index))?
+                {
+                    Some(pkh) => {
+                        let path = model::Path {
+                            account,
+                            keychain,
+                            index,
+                        };
+                        if !state.address_index.insert(pkh, path) {
+                            return Ok(());
+                        }
+                    }
+                    None => state.address_index.set_incomplete(),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Path of an address of the wallet, or `None` if it does not belong to the wallet.
+    fn _address_path(&self, state: &State, pkh: &PublicKeyHash) -> Result<Option<model::Path>> {
+        match state.address_index.lookup(pkh) {
+            Lookup::Owned(path) => Ok(Some(path)),
+            Lookup::NotOwned => Ok(None),
+            Lookup::Unknown => Ok(self.db.get_opt(&keys::pkh(pkh))?),
+        }
+    }
+
+    /// Usage of the in-memory index of the addresses of the wallet.
+    pub fn address_index_usage(&self) -> Result<metrics::AddressIndexUsage> {
+        let state = self.state.read()?;
+
+        Ok(state.address_index.usage())
     }
 
     /// Return all non-sensitive data regarding the wallet.
@@ -417,6 +596,7 @@ where
             last_sync,
             last_confirmed,
             birth_date,
+            key_derivation: self.key_derivation,
         })
     }
 
@@ -430,17 +610,16 @@ where
         index: u32,
         persist_db: bool,
     ) -> Result<(Arc<model::Address>, u32)> {
-        let extended_sk = parent_key.derive(&KeyPath::default().index(index))?;
-        let ExtendedPK { key, .. } = ExtendedPK::from_secret_key(&extended_sk);
-
-        let pkh = witnet_data_structures::chain::PublicKey::from(key).pkh();
+        let pkh = self.deriver.derive_pkh(parent_key, index)?;
         let address = pkh.bech32(get_environment());
-        let path = model::Path {
-            account,
-            keychain,
-            index,
-        }
-        .to_string();
+        let path = self
+            .key_derivation
+            .keypath(&model::Path {
+                account,
+                keychain,
+                index,
+            })
+            .to_string();
         let info = model::AddressInfo {
             label,
             received_payments: vec![],
@@ -479,6 +658,8 @@ where
             account,
             path,
             info,
+            account_tag: None,
+            reuse: None,
             pkh,
         };
 
@@ -500,6 +681,68 @@ where
         self._gen_external_address(&mut state, label)
     }
 
+    /// Generate an address in the external keychain for a client, checking how far ahead it is of
+    /// the last one that received a payment.
+    ///
+    /// Wallets recovering from the seed stop looking for payments after `sync_address_batch_length`
+    /// addresses in a row without any, so they would miss the payments to addresses beyond that
+    /// gap. A warning is returned along with those addresses, unless the wallet is configured to
+    /// refuse generating them.
+    pub fn gen_external_address_within_gap(
+        &self,
+        label: Option<String>,
+    ) -> Result<(Arc<model::Address>, Option<model::AddressGapWarning>)> {
+        let mut state = self.state.write()?;
+
+        let index = state.next_external_index;
+        let last_used_index = state.last_used_external_index;
+        let gap = match last_used_index {
+            Some(last_used_index) => index.saturating_sub(last_used_index),
+            None => index.saturating_add(1),
+        };
+        let gap_limit = self.params.sync_address_batch_length;
+        if gap <= u32::from(gap_limit) {
+            state.address_gap_exceeded = false;
+
+            return Ok((self._gen_external_address(&mut state, label)?, None));
+        }
+        if self.params.refuse_addresses_beyond_gap_limit {
+            return Err(Error::AddressGapExceeded {
+                index,
+                gap,
+                gap_limit,
+            });
+        }
+
+        let address = self._gen_external_address(&mut state, label)?;
+        let warning = model::AddressGapWarning {
+            index,
+            last_used_index,
+            gap,
+            gap_limit,
+            message: format!(
+                "Address {} is {} addresses ahead of the last one that received a payment. \
+                 Wallets recovering from the seed only look {} addresses ahead, so they would \
+                 miss payments to it until the addresses before it receive some.",
+                index, gap, gap_limit
+            ),
+        };
+        if !state.address_gap_exceeded {
+            state.address_gap_exceeded = true;
+            state.address_gap_warnings.push(warning.clone());
+        }
+
+        Ok((address, Some(warning)))
+    }
+
+    /// Take the warnings about addresses generated beyond the gap limit since the last time they
+    /// were taken.
+    pub fn take_address_gap_warnings(&self) -> Result<Vec<model::AddressGapWarning>> {
+        let mut state = self.state.write()?;
+
+        Ok(std::mem::take(&mut state.address_gap_warnings))
+    }
+
     /// Generate an address in the internal keychain (WIP-0001).
     pub fn gen_internal_address(
         &self,
@@ -511,18 +754,45 @@ where
         self._gen_internal_address(&mut state, label, preview)
     }
 
-    /// Return a list of the generated external addresses that.
+    /// Return a list of the generated external addresses, from the newest.
     pub fn external_addresses(&self, offset: u32, limit: u32) -> Result<model::Addresses> {
-        self.addresses(constants::EXTERNAL_KEYCHAIN, offset, limit)
+        self.addresses(
+            constants::EXTERNAL_KEYCHAIN,
+            model::AddressesSort::Index,
+            model::SortOrder::Desc,
+            None,
+            offset,
+            limit,
+        )
     }
 
-    /// Return a list of the generated internal addresses that.
+    /// Return a list of the generated internal addresses, from the newest.
     pub fn internal_addresses(&self, offset: u32, limit: u32) -> Result<model::Addresses> {
-        self.addresses(constants::INTERNAL_KEYCHAIN, offset, limit)
+        self.addresses(
+            constants::INTERNAL_KEYCHAIN,
+            model::AddressesSort::Index,
+            model::SortOrder::Desc,
+            None,
+            offset,
+            limit,
+        )
     }
 
-    /// Return a list of internal or external addresses.
-    fn addresses(&self, keychain: u32, offset: u32, limit: u32) -> Result<model::Addresses> {
+    /// Return a page of the internal or external addresses sorted by `sort` in `order`: up to
+    /// `limit` of them, after skipping `offset` of them from `cursor` or from the first one.
+    ///
+    /// Addresses are only ever added, so pages that go on from the cursor of the previous one
+    /// neither repeat nor miss any address that already existed, even if addresses are generated
+    /// in between.
+    pub fn addresses(
+        &self,
+        keychain: u32,
+        sort: model::AddressesSort,
+        order: model::SortOrder,
+        cursor: Option<model::AddressesCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<model::Addresses> {
         let state = self.state.read()?;
         let account = state.account;
         let total = if keychain == constants::EXTERNAL_KEYCHAIN {
@@ -531,127 +801,285 @@ where
             state.next_internal_index
         };
 
-        let end = total.saturating_sub(offset);
-        let start = end.saturating_sub(limit);
-        let range = start..end;
-        let mut addresses = Vec::with_capacity(range.len());
+        // Sort keys of the addresses, which are only read for the page unless sorting by label
+        let labels = match sort {
+            model::AddressesSort::Index | model::AddressesSort::Creation => None,
+            model::AddressesSort::Label => Some(
+                (0..total)
+                    .map(|index| {
+                        self._get_address(&state, account, keychain, index)
+                            .map(|address| address.info.label.clone())
+                    })
+                    .collect::<Result<Vec<_>>>()?,
+            ),
+        };
+        let key = |index: u32| {
+            let label = labels
+                .as_ref()
+                .and_then(|labels| labels.get(index as usize).cloned().flatten());
+
+            (label, index)
+        };
+        let mut sorted: Vec<u32> = (0..total).collect();
+        if labels.is_some() {
+            sorted.sort_by_cached_key(|index| key(*index));
+        }
+        if order == model::SortOrder::Desc {
+            sorted.reverse();
+        }
+
+        let start = match cursor {
+            Some(cursor) => {
+                let after = key(cursor.index);
+                sorted.partition_point(|index| match order {
+                    model::SortOrder::Asc => key(*index) <= after,
+                    model::SortOrder::Desc => key(*index) >= after,
+                })
+            }
+            None => 0,
+        };
+        let page = sorted
+            .into_iter()
+            .skip(start)
+            .skip(offset as usize)
+            .take(limit as usize);
 
         log::debug!(
-            "Retrieving external addresses in range {:?}. Start({}), End({}), Total({})",
-            range,
-            start,
-            end,
+            "Retrieving addresses of keychain {} sorted by {:?} in {:?} order from position {}. \
+             Total({})",
+            keychain,
+            sort,
+            order,
+            start.saturating_add(offset as usize),
             total
         );
-        for index in range.rev() {
+        let mut addresses = vec![];
+        let mut last = None;
+        for index in page {
             let address = self._get_address(&state, account, keychain, index)?;
             addresses.push((*address).clone());
+            last = Some(index);
         }
 
-        Ok(model::Addresses { addresses, total })
+        Ok(model::Addresses {
+            addresses,
+            total,
+            cursor: last.map(|index| model::AddressesCursor { index }),
+        })
     }
 
-    /// Return a list of the transactions.
+    /// Return a list of the transactions, from the newest.
     pub fn transactions(&self, offset: u32, limit: u32) -> Result<model::WalletTransactions> {
+        self.sorted_transactions(
+            model::MovementsSort::Id,
+            model::SortOrder::Desc,
+            None,
+            offset,
+            limit,
+        )
+    }
+
+    /// Return a page of the movements of the current account sorted by `sort` in `order`: up to
+    /// `limit` of them, after skipping `offset` of them from `cursor` or from the first one.
+    ///
+    /// The cursor holds the sort key of the last movement of the previous page, so pages that go
+    /// on from it neither repeat nor miss any movement that already existed, even if movements are
+    /// indexed in between. Local movements are the exception, as they get a new id when they are
+    /// included in a block.
+    ///
+    /// When sorting by id, which is the order in which movements are persisted, only the persisted
+    /// movements of the page are read. Other sorts read every movement.
+    pub fn sorted_transactions(
+        &self,
+        sort: model::MovementsSort,
+        order: model::SortOrder,
+        cursor: Option<model::MovementsCursor>,
+        offset: u32,
+        limit: u32,
+    ) -> Result<model::WalletTransactions> {
         let state = self.state.read()?;
         let account = state.account;
 
         // Total amount of state and db transactions
         let total = state.transaction_next_id + u32::try_from(state.local_movements.len()).unwrap();
-        let mut transactions: Vec<model::BalanceMovement> = Vec::new();
 
         // Query database `transaction_next_id` to compute total amount of transactions
         let db_total = self
             .db
             .get_or_default(&keys::transaction_next_id(account))?;
 
-        // get number of non-repated pending movements.
-        let pending_length = state
-            .pending_movements
-            .values()
-            .fold(0, |acc: usize, x| acc.saturating_add(x.len()));
-        let total_local = state.local_movements.len();
-
-        // Lets get the ranges for pending and db transactions
-        let (range_local, range_pending, range_db) = calculate_transaction_ranges(
-            offset as usize,
-            limit as usize,
-            total_local,
-            pending_length,
-            db_total as usize,
-        );
-
-        // Append local movements if any
-        if let Some(range_local) = range_local {
-            // Append local pending balance movements (not yet included in blocks)
-            let mut local_movements: Vec<model::BalanceMovement> =
-                state.local_movements.values().cloned().collect();
-            local_movements.sort_by(|a, b| a.db_key.cmp(&b.db_key));
-            transactions.extend_from_slice(local_movements.drain(range_local).as_slice());
-        }
-
-        // Append balance movements of pending blocks
-        if let Some(range_pending) = range_pending {
-            // We need to order transaction by beacon
-            let mut beacon_list: Vec<model::Beacon> = state
-                .pending_blocks
-                .values()
-                .map(|state| state.beacon.clone())
-                .collect();
-            beacon_list.sort_by(|a, b| a.epoch.cmp(&b.epoch));
-
-            // Get all pending movements in a vec
-            let mut all_pending_movements: Vec<model::BalanceMovement> = vec![];
-            beacon_list.iter().for_each(|beacon| {
-                all_pending_movements.extend_from_slice(
-                    state
-                        .pending_movements
-                        .get(&beacon.block_hash.to_string())
-                        .unwrap_or(&vec![]),
-                );
-            });
-
-            transactions.extend_from_slice(&all_pending_movements[range_pending]);
-        }
-
         // Build a HashMap<transaction_index, balance_movement>
         let mut db_movements_to_update: HashMap<u32, model::BalanceMovement> = HashMap::new();
         state.db_movements_to_update.values().for_each(|movements| {
             db_movements_to_update.extend(movements.iter().map(|x| (x.db_key, x.clone())))
         });
+        let get_stored = |id: u32| -> Option<model::BalanceMovement> {
+            // Check if there is a pending update for the queried balance movement, otherwise
+            // query the database
+            if let Some(transaction) = db_movements_to_update.get(&id) {
+                log::debug!(
+                    "Updating transaction {:?} with pending tally found",
+                    transaction.transaction.hash
+                );
 
-        if let Some(range_db) = range_db {
-            for index in range_db.rev() {
-                let index = u32::try_from(index).unwrap();
+                return Some(transaction.clone());
+            }
+            match get_movement(&self.db, account, id) {
+                Ok(mut transaction) => {
+                    transaction.db_key = id;
 
-                // Check if there is a pending update for the queried balance movement,
-                // otherwise query the database
-                if let Some(transaction) = db_movements_to_update.get(&index) {
-                    log::debug!(
-                        "Updating transaction {:?} with pending tally found",
-                        transaction.transaction.hash
+                    Some(transaction)
+                }
+                Err(e) => {
+                    log::error!(
+                        "Error while retrieving transaction with index {}: {}",
+                        id,
+                        e
                     );
-                    transactions.push(transaction.clone());
+
+                    None
+                }
+            }
+        };
+
+        // Movements of pending blocks, and local pending movements (not yet included in blocks)
+        let in_memory = state
+            .pending_movements
+            .values()
+            .flatten()
+            .map(|movement| (movement_sort_key(sort, movement, false), movement.clone()))
+            .chain(
+                state
+                    .local_movements
+                    .values()
+                    .map(|movement| (movement_sort_key(sort, movement, true), movement.clone())),
+            );
+
+        // In ascending order, the listing is made of `stored` persisted movements, by id, followed
+        // by the movements in `listed`. Persisted movements are only read for the page when
+        // sorting by id, as every other movement has a greater id.
+        let (stored, mut listed): (u32, Vec<_>) = match sort {
+            model::MovementsSort::Id => (db_total, in_memory.collect()),
+            _ => (
+                0,
+                (0..db_total)
+                    .filter_map(&get_stored)
+                    .map(|movement| (movement_sort_key(sort, &movement, false), movement))
+                    .chain(in_memory)
+                    .collect(),
+            ),
+        };
+        listed.sort_by(|(a, _), (b, _)| a.cmp(b));
+        let stored_key = |id: u32| model::MovementSortKey {
+            primary: 0,
+            local: false,
+            id,
+            hash: String::new(),
+        };
+        let len = stored as usize + listed.len();
+        // Movements whose key is below `key`, or not above it if `inclusive`
+        let count_below = |key: &model::MovementSortKey, inclusive: bool| {
+            let below = |other: &model::MovementSortKey| {
+                if inclusive {
+                    other <= key
                 } else {
-                    match self.get_transaction(account, index) {
-                        Ok(transaction) => {
-                            transactions.push(transaction);
-                        }
-                        Err(e) => {
-                            log::error!(
-                                "Error while retrieving transaction with index {}: {}",
-                                index,
-                                e
-                            );
-                        }
-                    }
+                    other < key
                 }
+            };
+            let stored_below = partition_point_u32(stored, |id| below(&stored_key(id)));
+
+            stored_below as usize + listed.partition_point(|(other, _)| below(other))
+        };
+
+        let start = match (&cursor, order) {
+            (None, _) => 0,
+            (Some(cursor), model::SortOrder::Asc) => count_below(&cursor.key, true),
+            (Some(cursor), model::SortOrder::Desc) => len - count_below(&cursor.key, false),
+        };
+        let from = start.saturating_add(offset as usize).min(len);
+        let to = from.saturating_add(limit as usize).min(len);
+
+        let mut transactions: Vec<model::BalanceMovement> = Vec::new();
+        let mut last = None;
+        for position in from..to {
+            let ascending = match order {
+                model::SortOrder::Asc => position,
+                model::SortOrder::Desc => len - 1 - position,
+            };
+            if ascending < stored as usize {
+                let id = u32::try_from(ascending).unwrap();
+                transactions.extend(get_stored(id));
+                last = Some(stored_key(id));
+            } else {
+                let (key, movement) = &listed[ascending - stored as usize];
+                transactions.push(movement.clone());
+                last = Some(key.clone());
             }
         }
 
+        for transaction in transactions.iter_mut() {
+            attach_output_labels(&self.db, transaction)?;
+        }
+
         Ok(model::WalletTransactions {
             transactions,
             total,
+            cursor: last.map(|key| model::MovementsCursor { key }),
+        })
+    }
+
+    /// Get the next batch of up to `batch_size` persisted movements of the current wallet account,
+    /// in the order of their ids, starting from `cursor` or from the oldest one.
+    ///
+    /// Every movement is looked up by its id, so a batch only reads its own movements no matter how
+    /// far into the stream the cursor is. Movements of blocks that are not confirmed by a superblock
+    /// yet have no final id, so they are only returned by `transactions`.
+    pub fn transactions_stream(
+        &self,
+        cursor: Option<model::TransactionsCursor>,
+        batch_size: u32,
+    ) -> Result<model::TransactionsBatch> {
+        let state = self.state.read()?;
+        let account = state.account;
+        let from = match cursor {
+            Some(cursor) if cursor.account != account => {
+                return Err(Error::CursorAccountMismatch {
+                    cursor: cursor.account,
+                    account,
+                });
+            }
+            Some(cursor) => cursor.next_id,
+            None => 0,
+        };
+
+        let persisted = self
+            .db
+            .get_or_default(&keys::transaction_next_id(account))?;
+        let until = from.saturating_add(batch_size).min(persisted).max(from);
+
+        let mut transactions = vec![];
+        for id in from..until {
+            // Movements waiting for the tally of their data request are returned already updated
+            let pending_update = state
+                .db_movements_to_update
+                .values()
+                .flatten()
+                .find(|movement| movement.db_key == id);
+            let mut movement = match pending_update {
+                Some(movement) => movement.clone(),
+                None => get_movement(&self.db, account, id)?,
+            };
+            attach_output_labels(&self.db, &mut movement)?;
+            transactions.push(movement);
+        }
+
+        Ok(model::TransactionsBatch {
+            transactions,
+            cursor: model::TransactionsCursor {
+                account,
+                next_id: until,
+            },
+            finished: until >= persisted,
         })
     }
 
@@ -665,91 +1093,648 @@ where
     ) -> Result<Arc<model::Address>> {
         let state = self.state.read()?;
 
-        self._get_address(&state, account, keychain, index)
+        self._get_address(&state, account, keychain, index)
+    }
+
+    /// Non-locking version of `get_address` (requires a reference to `State` to be passed as
+    /// argument instead of taking a read lock on `self.state`, so as to avoid deadlocks).
+    pub fn _get_address(
+        &self,
+        state: &State,
+        account: u32,
+        keychain: u32,
+        index: u32,
+    ) -> Result<Arc<model::Address>> {
+        let path = self
+            .key_derivation
+            .keypath(&model::Path {
+                account,
+                keychain,
+                index,
+            })
+            .to_string();
+
+        if let Some(address) = state.pending_addresses_by_path.get(&path) {
+            log::trace!("Address {} found in memory", path);
+
+            Ok(address.clone())
+        } else {
+            log::trace!(
+                "Address {} not found in memory, looking for it in storage...",
+                path,
+            );
+            let address = self.db.get(&keys::address(account, keychain, index))?;
+            let pkh = self.db.get(&keys::address_pkh(account, keychain, index))?;
+            let info = self.db.get(&keys::address_info(account, keychain, index))?;
+            let account_tag = self
+                .db
+                .get_opt(&keys::address_account_tag(account, keychain, index))?;
+            let reuse = self
+                .db
+                .get_opt(&keys::address_reuse(account, keychain, index))?;
+
+            Ok(Arc::new(model::Address {
+                address,
+                index,
+                keychain,
+                account,
+                path,
+                info,
+                account_tag,
+                reuse,
+                pkh,
+            }))
+        }
+    }
+
+    /// Whether an address has been generated by this wallet, be it external or internal.
+    pub fn contains_address(&self, pkh: &PublicKeyHash) -> Result<bool> {
+        let state = self.state.read()?;
+        let pending = state
+            .pending_addresses_by_path
+            .values()
+            .any(|address| address.pkh == *pkh);
+
+        Ok(pending || self.db.contains(&keys::pkh(pkh))?)
+    }
+
+    /// Hashes of the transactions of the wallet included in each of `blocks`, for the blocks that
+    /// include any.
+    ///
+    /// Movements are persisted in the order of their blocks, so only those of the blocks since the
+    /// earliest of `blocks` are read.
+    pub fn transactions_in_blocks(
+        &self,
+        blocks: &[model::Beacon],
+    ) -> Result<HashMap<Hash, Vec<String>>> {
+        let state = self.state.read()?;
+        let account = state.account;
+        let wanted: HashSet<Hash> = blocks.iter().map(|block| block.block_hash).collect();
+        let since = match blocks.iter().map(|block| block.epoch).min() {
+            Some(since) => since,
+            None => return Ok(HashMap::new()),
+        };
+
+        let mut transactions: HashMap<Hash, Vec<String>> = HashMap::new();
+        let persisted = self
+            .db
+            .get_or_default(&keys::transaction_next_id(account))?;
+        for id in (0..persisted).rev() {
+            let movement = get_movement(&self.db, account, id)?;
+            match movement.transaction.block {
+                Some(block) if block.epoch < since => break,
+                Some(block) if wanted.contains(&block.block_hash) => transactions
+                    .entry(block.block_hash)
+                    .or_default()
+                    .push(movement.transaction.hash),
+                _ => {}
+            }
+        }
+        // Persisted movements were read from the latest one
+        for hashes in transactions.values_mut() {
+            hashes.reverse();
+        }
+
+        for movement in state.pending_movements.values().flatten() {
+            if let Some(block) = &movement.transaction.block {
+                if wanted.contains(&block.block_hash) {
+                    transactions
+                        .entry(block.block_hash)
+                        .or_default()
+                        .push(movement.transaction.hash.clone());
+                }
+            }
+        }
+
+        Ok(transactions)
+    }
+
+    /// Output pointers of the inputs and outputs of `transactions` that belong to the wallet.
+    ///
+    /// Outputs belong to the wallet if they pay one of its addresses, and inputs if they spend an
+    /// output of the wallet, which is looked up in the movement of the transaction that created it.
+    pub fn own_entries(&self, transactions: &[Transaction]) -> Result<HashSet<OutputPointer>> {
+        let state = self.state.read()?;
+
+        let mut own = HashSet::new();
+        let mut spent = vec![];
+        for transaction in transactions {
+            // Reveals have neither inputs nor outputs
+            let (inputs, outputs) = match extract_inputs_and_outputs(transaction) {
+                Ok(entries) => entries,
+                Err(_) => continue,
+            };
+            let transaction_id = transaction.hash();
+            for (index, output) in outputs.iter().enumerate() {
+                if self._address_path(&state, &output.pkh)?.is_some() {
+                    own.insert(OutputPointer {
+                        transaction_id,
+                        output_index: u32::try_from(index).unwrap(),
+                    });
+                }
+            }
+            spent.extend(inputs.iter().map(|input| *input.output_pointer()));
+        }
+        for pointer in spent {
+            if !own.contains(&pointer) && self._is_own_output(&state, &pointer)? {
+                own.insert(pointer);
+            }
+        }
+
+        Ok(own)
+    }
+
+    /// Whether an output paid one of the addresses of the wallet, according to the movement of the
+    /// transaction that created it.
+    fn _is_own_output(&self, state: &State, pointer: &OutputPointer) -> Result<bool> {
+        if state.utxo_set.contains_key(&model::OutPtr::from(pointer)) {
+            return Ok(true);
+        }
+
+        let movement = match self
+            .db
+            .get_opt(&keys::transactions_index(pointer.transaction_id.as_ref()))?
+        {
+            Some(id) => Some(get_movement(&self.db, state.account, id)?),
+            None => {
+                let hash = pointer.transaction_id.to_string();
+                state
+                    .pending_movements
+                    .values()
+                    .flatten()
+                    .find(|movement| movement.transaction.hash == hash)
+                    .cloned()
+            }
+        };
+        let output_type = movement.and_then(|movement| {
+            movement_outputs(&movement.transaction.data)
+                .get(pointer.output_index as usize)
+                .map(|output| output.output_type)
+        });
+
+        Ok(matches!(
+            output_type,
+            Some(model::OutputType::External) | Some(model::OutputType::Internal)
+        ))
+    }
+
+    /// Get a transaction if exists.
+    pub fn get_transaction(&self, account: u32, index: u32) -> Result<model::BalanceMovement> {
+        let mut movement = get_movement(&self.db, account, index)?;
+        attach_output_labels(&self.db, &mut movement)?;
+
+        Ok(movement)
+    }
+
+    /// Get a previously put serialized value.
+    ///
+    /// See `kv_set`.
+    pub fn kv_get(&self, key: &str) -> Result<Option<String>> {
+        let value = self.db.get_opt(&keys::custom(key))?;
+
+        Ok(value)
+    }
+
+    /// Set an arbitrary string value under a custom key.
+    ///
+    /// See `kv_get`.
+    pub fn kv_set(&self, key: &str, value: &str) -> Result<()> {
+        self.db.put(&keys::custom(key), value.to_string())?;
+
+        Ok(())
+    }
+
+    /// Update a wallet's name and/or description
+    pub fn update(&self, name: Option<String>, description: Option<String>) -> Result<()> {
+        let mut batch = self.db.batch();
+        let mut state = self.state.write()?;
+
+        state.name = name;
+        if let Some(ref name) = state.name {
+            batch.put(&keys::wallet_name(), name)?;
+        }
+
+        state.description = description;
+        if let Some(ref description) = state.description {
+            batch.put(&keys::wallet_description(), description)?;
+        }
+
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Spending policy of the wallet.
+    pub fn policy(&self) -> Result<model::WalletPolicy> {
+        let state = self.state.read()?;
+
+        Ok(state.policy.clone())
+    }
+
+    /// Replace the spending policy of the wallet.
+    pub fn set_policy(&self, policy: model::WalletPolicy) -> Result<()> {
+        let mut state = self.state.write()?;
+        self.db.put(&keys::wallet_policy(), &policy)?;
+        state.policy = policy;
+
+        Ok(())
+    }
+
+    /// Which transactions nodes can have the wallet sign on their behalf.
+    pub fn witness_signing_policy(&self) -> Result<model::WitnessSigningPolicy> {
+        let state = self.state.read()?;
+
+        Ok(state.witness_signing_policy.clone())
+    }
+
+    /// Replace which transactions nodes can have the wallet sign on their behalf.
+    pub fn set_witness_signing_policy(&self, policy: model::WitnessSigningPolicy) -> Result<()> {
+        let mut state = self.state.write()?;
+        self.db
+            .put(&keys::wallet_witness_signing_policy(), &policy)?;
+        state.witness_signing_policy = policy;
+
+        Ok(())
+    }
+
+    /// Sign a commit or a reveal on behalf of a node, with the key of the address of the wallet
+    /// that the transaction is for, and record it in the audit log of the wallet.
+    ///
+    /// The transaction must be of a type allowed by the witness signing policy of the wallet. The
+    /// collateral of a commit must be made of available unspent outputs of the committer, which
+    /// are then marked as used, and lock no more than the policy allows.
+    pub fn sign_witness_transaction(
+        &self,
+        body: &WitnessTransactionBody,
+    ) -> Result<(KeyedSignature, model::WitnessSigningAuditEntry)> {
+        // The write lock keeps concurrent signatures from losing entries of the audit log, and
+        // from using the same outputs as collateral
+        let mut state = self.state.write()?;
+        let transaction_type = body.transaction_type();
+        if !state
+            .witness_signing_policy
+            .allowed_types
+            .contains(&transaction_type)
+        {
+            return Err(Error::WitnessSigningNotAllowed(transaction_type));
+        }
+
+        let signer = body.signer();
+        let address = signer.bech32(get_environment());
+        let path = match self.db.get_opt(&keys::pkh(&signer))? {
+            Some(path) if path.account == state.account => path,
+            _ => return Err(Error::UnknownWitnessKey(address)),
+        };
+        let parent_key = state
+            .keychains
+            .get(path.keychain as usize)
+            .ok_or_else(|| Error::UnknownWitnessKey(address.clone()))?;
+        let sign_key = parent_key
+            .derive(&KeyPath::default().index(path.index))?
+            .into();
+        let collateral = body.collateral();
+        self.check_collateral(&state, collateral, signer, &address, body.change())?;
+
+        let hash = body.hash();
+        let public_key = From::from(PK::from_secret_key_global(&sign_key));
+        let signature = From::from(signature::sign(sign_key, hash.as_ref())?);
+        let entry = model::WitnessSigningAuditEntry {
+            timestamp: get_timestamp(),
+            transaction_type,
+            hash: hash.to_string(),
+            dr_pointer: body.dr_pointer().to_string(),
+            address,
+        };
+        log::info!(
+            "Signed {} transaction {} for data request {} with address {} of wallet {} on behalf of a node",
+            entry.transaction_type,
+            entry.hash,
+            entry.dr_pointer,
+            entry.address,
+            self.id
+        );
+
+        let mut audit = self
+            .db
+            .get_or_default(&keys::wallet_witness_signing_audit())?;
+        audit.push(entry.clone());
+        let excess = audit
+            .len()
+            .saturating_sub(constants::WITNESS_SIGNING_AUDIT_LENGTH);
+        audit.drain(..excess);
+        self.db.put(&keys::wallet_witness_signing_audit(), audit)?;
+
+        let used_until = u64::try_from(get_timestamp()).unwrap_or_default()
+            + self.params.pending_transactions_timeout_seconds;
+        for input in collateral {
+            state
+                .used_outputs
+                .insert(input.output_pointer().into(), used_until);
+        }
+
+        Ok((
+            KeyedSignature {
+                signature,
+                public_key,
+            },
+            entry,
+        ))
+    }
+
+    /// Check that the collateral of a commit signed on behalf of a node is made of unspent outputs
+    /// of `signer` that are not being used by other transactions, unless their usage timed out,
+    /// and that it locks no more nanowits than the witness signing policy of the wallet allows,
+    /// once `change` is returned.
+    fn check_collateral(
+        &self,
+        state: &State,
+        collateral: &[Input],
+        signer: PublicKeyHash,
+        address: &str,
+        change: u64,
+    ) -> Result<()> {
+        let timestamp = u64::try_from(get_timestamp()).unwrap_or_default();
+        let mut total: u64 = 0;
+        for input in collateral {
+            let pointer = model::OutPtr::from(input.output_pointer());
+            let available = state
+                .used_outputs
+                .get(&pointer)
+                .map_or(true, |until| *until <= timestamp);
+            match state.utxo_set.get(&pointer) {
+                Some(output) if output.pkh == signer && available => {
+                    total = total.saturating_add(output.amount);
+                }
+                _ => {
+                    return Err(Error::UnavailableCollateral {
+                        output: input.output_pointer().to_string(),
+                        address: address.to_string(),
+                    })
+                }
+            }
+        }
+
+        let locked = total.saturating_sub(change);
+        let limit = state.witness_signing_policy.max_collateral;
+        if locked > limit {
+            return Err(Error::CollateralAboveLimit {
+                collateral: locked,
+                limit,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Outbox of the events waiting to be posted to the webhook of the wallet.
+    pub fn webhook_outbox(&self) -> &Arc<WebhookOutbox<T>> {
+        &self.webhook_outbox
+    }
+
+    /// Endpoint that the events of the wallet are posted to, if it has one.
+    pub fn webhook(&self) -> Result<Option<model::WebhookConfig>> {
+        let state = self.state.read()?;
+
+        Ok(state.webhook.clone())
+    }
+
+    /// Replace the endpoint that the events of the wallet are posted to, or stop posting them.
+    pub fn set_webhook(&self, webhook: Option<model::WebhookConfig>) -> Result<()> {
+        let mut state = self.state.write()?;
+        let mut batch = self.db.batch();
+        match &webhook {
+            Some(webhook) => batch.put(&keys::wallet_webhook(), webhook)?,
+            None => batch.delete(&keys::wallet_webhook())?,
+        }
+        self.db.write(batch)?;
+        state.webhook = webhook;
+
+        Ok(())
+    }
+
+    /// Nanowits sent out of the wallet since a timestamp, according to its confirmed, pending and
+    /// local outgoing movements.
+    ///
+    /// Movements are visited from the newest, so the ones before `since` are only read up to the
+    /// first page that is entirely older than it.
+    pub fn spent_since(&self, since: u64) -> Result<u64> {
+        const PAGE_SIZE: u32 = 100;
+
+        let mut spent = 0u64;
+        let mut offset = 0;
+        loop {
+            let page = self.transactions(offset, PAGE_SIZE)?.transactions;
+            for movement in &page {
+                if movement.kind == model::MovementType::Negative
+                    && movement.transaction.superseded_by.is_none()
+                    && movement.transaction.timestamp >= since
+                {
+                    spent = spent.saturating_add(movement.amount);
+                }
+            }
+
+            let exhausted = page.len() < PAGE_SIZE as usize;
+            let older = page
+                .last()
+                .map_or(true, |movement| movement.transaction.timestamp < since);
+            if exhausted || older {
+                return Ok(spent);
+            }
+            offset += PAGE_SIZE;
+        }
+    }
+
+    /// Check how the spending policy of the wallet applies to sending a transaction at `now`.
+    pub fn spending_check(
+        &self,
+        transaction: &Transaction,
+        now: u64,
+    ) -> Result<model::SpendingCheck> {
+        let policy = self.policy()?;
+        let outgoing = {
+            let state = self.state.read()?;
+            let txn = model::ExtendedTransaction {
+                transaction: transaction.clone(),
+                metadata: None,
+            };
+
+            match self._get_account_mutation(&state, &txn, &model::Beacon::default(), false)? {
+                Some(mutation)
+                    if mutation.balance_movement.kind == model::MovementType::Negative =>
+                {
+                    mutation.balance_movement.amount
+                }
+                _ => 0,
+            }
+        };
+        let spent = self.spent_since(now.saturating_sub(constants::SPEND_LIMIT_WINDOW_SECS))?;
+
+        Ok(model::SpendingCheck {
+            outgoing,
+            spent,
+            daily_spend_limit: policy.daily_spend_limit,
+            remaining_allowance: policy
+                .daily_spend_limit
+                .map(|limit| limit.saturating_sub(spent)),
+            requires_confirmation: policy
+                .confirmation_threshold
+                .map_or(false, |threshold| outgoing > threshold),
+        })
+    }
+
+    /// Create a payment request for an amount to be paid to a fresh external address.
+    pub fn create_payment_request(
+        &self,
+        amount: u64,
+        memo: Option<String>,
+        expires_in: Option<u64>,
+        now: u64,
+    ) -> Result<model::PaymentRequest> {
+        let mut state = self.state.write()?;
+        let account = state.account;
+        let id = u32::try_from(state.payment_requests.len())
+            .map_err(|_| Error::PaymentRequestIdOverflow)?;
+        let next_id = id.checked_add(1).ok_or(Error::PaymentRequestIdOverflow)?;
+
+        let address = self._gen_external_address(&mut state, memo.clone())?;
+        let payment_request = model::PaymentRequest {
+            id,
+            address: address.address.clone(),
+            amount,
+            memo,
+            created_at: now,
+            expires_at: expires_in.map(|expires_in| now.saturating_add(expires_in)),
+            status: model::PaymentRequestStatus::Pending,
+            received_amount: 0,
+            payments: vec![],
+        };
+
+        let mut batch = self.db.batch();
+        batch.put(&keys::payment_request(account, id), &payment_request)?;
+        batch.put(&keys::payment_request_next_id(account), next_id)?;
+        self.db.write(batch)?;
+
+        state.payment_requests.push(payment_request.clone());
+
+        Ok(payment_request)
     }
 
-    /// Non-locking version of `get_address` (requires a reference to `State` to be passed as
-    /// argument instead of taking a read lock on `self.state`, so as to avoid deadlocks).
-    pub fn _get_address(
+    /// Return a list of the payment requests, newest first, optionally only those with some
+    /// specific statuses.
+    pub fn payment_requests(
         &self,
-        state: &State,
-        account: u32,
-        keychain: u32,
-        index: u32,
-    ) -> Result<Arc<model::Address>> {
-        let path = model::Path {
-            account,
-            keychain,
-            index,
-        }
-        .to_string();
+        statuses: &[model::PaymentRequestStatus],
+        offset: u32,
+        limit: u32,
+    ) -> Result<model::PaymentRequests> {
+        let state = self.state.read()?;
 
-        if let Some(address) = state.pending_addresses_by_path.get(&path) {
-            log::trace!("Address {} found in memory", path);
+        let filtered: Vec<_> = state
+            .payment_requests
+            .iter()
+            .rev()
+            .filter(|request| statuses.is_empty() || statuses.contains(&request.status))
+            .collect();
+        let total = u32::try_from(filtered.len()).unwrap();
+        let payment_requests = filtered
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .cloned()
+            .collect();
 
-            Ok(address.clone())
-        } else {
-            log::trace!(
-                "Address {} not found in memory, looking for it in storage...",
-                path,
-            );
-            let address = self.db.get(&keys::address(account, keychain, index))?;
-            let pkh = self.db.get(&keys::address_pkh(account, keychain, index))?;
-            let info = self.db.get(&keys::address_info(account, keychain, index))?;
+        Ok(model::PaymentRequests {
+            payment_requests,
+            total,
+        })
+    }
 
-            Ok(Arc::new(model::Address {
-                address,
-                index,
-                keychain,
-                account,
-                path,
-                info,
-                pkh,
-            }))
+    /// Mark as expired the open payment requests whose expiration timestamp has passed.
+    ///
+    /// The expired requests are returned, and also queued for notifying clients.
+    pub fn expire_payment_requests(&self, now: u64) -> Result<Vec<model::PaymentRequest>> {
+        let mut state = self.state.write()?;
+        let account = state.account;
+
+        let expired: Vec<_> = state
+            .payment_requests
+            .iter()
+            .filter_map(|request| {
+                let mut request = request.clone();
+                if request.expire(now) {
+                    Some(request)
+                } else {
+                    None
+                }
+            })
+            .collect();
+        if expired.is_empty() {
+            return Ok(expired);
         }
-    }
 
-    /// Get a transaction if exists.
-    pub fn get_transaction(&self, account: u32, index: u32) -> Result<model::BalanceMovement> {
-        Ok(self.db.get(&keys::transaction_movement(account, index))?)
-    }
+        let mut batch = self.db.batch();
+        for request in &expired {
+            batch.put(&keys::payment_request(account, request.id), request)?;
+        }
+        self.db.write(batch)?;
 
-    /// Get a previously put serialized value.
-    ///
-    /// See `kv_set`.
-    pub fn kv_get(&self, key: &str) -> Result<Option<String>> {
-        let value = self.db.get_opt(&keys::custom(key))?;
+        apply_payment_request_updates(&mut state, &expired);
 
-        Ok(value)
+        Ok(expired)
     }
 
-    /// Set an arbitrary string value under a custom key.
-    ///
-    /// See `kv_get`.
-    pub fn kv_set(&self, key: &str, value: &str) -> Result<()> {
-        self.db.put(&keys::custom(key), value.to_string())?;
+    /// Take the payment requests that changed status since this method was last called.
+    pub fn take_payment_request_updates(&self) -> Result<Vec<model::PaymentRequest>> {
+        let mut state = self.state.write()?;
 
-        Ok(())
+        Ok(std::mem::take(&mut state.payment_request_updates))
     }
 
-    /// Update a wallet's name and/or description
-    pub fn update(&self, name: Option<String>, description: Option<String>) -> Result<()> {
-        let mut batch = self.db.batch();
+    /// Take the reuses of the addresses of payment requests detected since this method was last
+    /// called.
+    pub fn take_address_reuses(&self) -> Result<Vec<model::AddressReuse>> {
         let mut state = self.state.write()?;
 
-        state.name = name;
-        if let Some(ref name) = state.name {
-            batch.put(&keys::wallet_name(), name)?;
-        }
+        Ok(std::mem::take(&mut state.address_reuses))
+    }
 
-        state.description = description;
-        if let Some(ref description) = state.description {
-            batch.put(&keys::wallet_description(), description)?;
-        }
+    /// Whether a block scanned by `BlockScan` may include transactions of the wallet, checking the
+    /// same as `filter_wallet_transactions` does for every transaction of the block. Blocks are
+    /// always of interest while addresses are being generated for a synchronization.
+    pub fn is_involved_in(&self, scan: &BlockScan) -> Result<bool> {
+        let state = self.state.read()?;
 
-        self.db.write(batch)?;
+        if !state.transient_external_addresses.is_empty()
+            || !state.transient_internal_addresses.is_empty()
+        {
+            return Ok(true);
+        }
+        if scan
+            .spent_outputs
+            .iter()
+            .any(|pointer| state.utxo_set.contains_key(&model::OutPtr::from(pointer)))
+        {
+            return Ok(true);
+        }
+        for dr_pointer in &scan.tallied_requests {
+            if state
+                .pending_dr_movements
+                .contains_key(&dr_pointer.to_string())
+                || self
+                    .db
+                    .get(&keys::transactions_index(dr_pointer.as_ref()))
+                    .is_ok()
+            {
+                return Ok(true);
+            }
+        }
+        for pkh in &scan.output_pkhs {
+            if self._address_path(&state, pkh)?.is_some() {
+                return Ok(true);
+            }
+        }
 
-        Ok(())
+        Ok(false)
     }
 
     /// Filter transactions in a block (received from a node) if they belong to wallet accounts.
@@ -789,7 +1774,7 @@ where
             }
 
             let check_db_and_transient = |output: &ValueTransferOutput| {
-                self.db.get(&keys::pkh(&output.pkh)).is_ok()
+                matches!(self._address_path(&state, &output.pkh), Ok(Some(_)))
                     || state.transient_external_addresses.contains_key(&output.pkh)
                     || state.transient_internal_addresses.contains_key(&output.pkh)
             };
@@ -814,6 +1799,46 @@ where
         confirmed: bool,
     ) -> Result<Vec<model::BalanceMovement>> {
         let mut state = self.state.write()?;
+
+        self._index_block_transactions(&mut state, block_info, txns, confirmed)
+    }
+
+    /// Whether a block has already been applied to the wallet, either as a confirmed block or as a
+    /// pending one.
+    pub fn is_block_applied(&self, block_info: &model::Beacon) -> Result<bool> {
+        let state = self.state.read()?;
+
+        Ok(is_applied_block(&state, block_info))
+    }
+
+    /// Index the transactions of a block like `index_block_transactions`, unless the block has
+    /// already been applied to the wallet, in which case `None` is returned.
+    ///
+    /// The block is checked while holding the lock on the state of the wallet, and it is recorded as
+    /// applied in the same write batch as its changes, so blocks are never applied twice, even if
+    /// they are delivered again after a crash.
+    pub fn index_new_block_transactions(
+        &self,
+        block_info: &model::Beacon,
+        txns: &[model::ExtendedTransaction],
+        confirmed: bool,
+    ) -> Result<Option<Vec<model::BalanceMovement>>> {
+        let mut state = self.state.write()?;
+        if is_applied_block(&state, block_info) {
+            return Ok(None);
+        }
+
+        self._index_block_transactions(&mut state, block_info, txns, confirmed)
+            .map(Some)
+    }
+
+    fn _index_block_transactions(
+        &self,
+        state: &mut State,
+        block_info: &model::Beacon,
+        txns: &[model::ExtendedTransaction],
+        confirmed: bool,
+    ) -> Result<Vec<model::BalanceMovement>> {
         let mut addresses = HashMap::new();
         let mut block_balance_movements = Vec::new();
         let mut dr_balance_movements = HashMap::new();
@@ -822,7 +1847,7 @@ where
         // Index all transactions
         for txn in txns {
             // Transactions are only indexed if they do not exist in database, or if resynchronizing.
-            match self._index_transaction(&mut state, &mut addresses, txn, block_info, confirmed) {
+            match self._index_transaction(state, &mut addresses, txn, block_info, confirmed) {
                 Ok(Some(balance_movement)) => {
                     if let Transaction::DataRequest(dr_tx) = &txn.transaction {
                         dr_balance_movements.insert(
@@ -870,8 +1895,7 @@ where
                     .db
                     .get(&keys::transactions_index(tally.dr_pointer.as_ref()))
                     .and_then(|txn_id| {
-                        self.db
-                            .get(&keys::transaction_movement(state.account, txn_id))
+                        get_movement(&self.db, state.account, txn_id)
                             .map(|dr_movement| (dr_movement, txn_id))
                     })
                 {
@@ -912,22 +1936,47 @@ where
                     acc
                 },
             );
+        state.balance.collateralized = collateralized_amount(&state.collaterals)?;
 
-        let addresses = addresses.into_values().collect();
+        let addresses: Vec<_> = addresses.into_values().collect();
 
         // Persist into database
         if confirmed {
             let mut balance_movements_to_persist = block_balance_movements.clone();
             balance_movements_to_persist.extend_from_slice(&db_movements_to_update);
+            let (payment_requests, reuses) = match_payment_requests(
+                &state.payment_requests,
+                &balance_movements_to_persist,
+                &addresses,
+            );
+            let addresses = mark_reused_addresses(addresses, &reuses);
+            let balance_history = balance_history::record(
+                &state.balance_history,
+                state.epoch_constants,
+                block_info.epoch,
+                &state.balance.unconfirmed,
+                &self.params,
+            );
+            let applied_blocks = record_applied_block(&state.applied_blocks, block_info);
 
             self._persist_block_txns(
                 balance_movements_to_persist.clone(),
                 addresses,
+                &payment_requests,
                 state.transaction_next_id,
                 state.utxo_set.clone(),
+                &state.collaterals,
                 &state.balance.unconfirmed,
+                balance_history.as_deref(),
+                &applied_blocks,
                 block_info,
             )?;
+            apply_payment_request_updates(state, &payment_requests);
+            state.address_reuses.extend(reuses);
+            if let Some(balance_history) = balance_history {
+                state.balance_history = balance_history;
+            }
+            state.applied_blocks = applied_blocks;
             // At this point state.utxo_set will only have confirmed utxos, so we can clear the
             // pending transactions
             state.pending_transactions.clear();
@@ -958,6 +2007,7 @@ where
                 beacon: block_info.clone(),
                 transaction_next_id: state.transaction_next_id,
                 utxo_set: state.utxo_set.clone(),
+                collaterals: state.collaterals.clone(),
             };
 
             state
@@ -986,13 +2036,18 @@ where
         Ok(block_balance_movements)
     }
 
+    #[allow(clippy::too_many_arguments)]
     fn _persist_block_txns(
         &self,
         balance_movements: Vec<model::BalanceMovement>,
         addresses: Vec<Arc<model::Address>>,
+        payment_requests: &[model::PaymentRequest],
         transaction_next_id: u32,
         utxo_set: model::UtxoSet,
+        collaterals: &model::Collaterals,
         balance: &model::BalanceInfo,
+        balance_history: Option<&[model::BalanceHistoryEntry]>,
+        applied_blocks: &BTreeMap<u32, Hash>,
         block_info: &model::Beacon,
     ) -> Result<()> {
         log::debug!(
@@ -1026,9 +2081,27 @@ where
         // Write account state
         batch.put(&keys::transaction_next_id(account), transaction_next_id)?;
         batch.put(&keys::account_utxo_set(account), utxo_set)?;
+        batch.put(&keys::account_collaterals(account), collaterals)?;
         batch.put(&keys::account_balance(account), balance)?;
+        if let Some(balance_history) = balance_history {
+            batch.put(&keys::account_balance_history(account), balance_history)?;
+        }
 
         // Persist addresses
+        let last_used_external_index = addresses
+            .iter()
+            .filter(|address| {
+                address.keychain == constants::EXTERNAL_KEYCHAIN
+                    && !address.info.received_payments.is_empty()
+            })
+            .map(|address| address.index)
+            .max();
+        if let Some(index) = last_used_external_index {
+            let key = keys::account_last_used_index(account, constants::EXTERNAL_KEYCHAIN);
+            if self.db.get_opt(&key)?.map_or(true, |stored| index > stored) {
+                batch.put(&key, index)?;
+            }
+        }
         for address in addresses {
             batch.put(
                 &keys::address_info(account, address.keychain, address.index),
@@ -1046,6 +2119,20 @@ where
                 &keys::address_pkh(account, address.keychain, address.index),
                 address.pkh,
             )?;
+            if let Some(reuse) = &address.reuse {
+                batch.put(
+                    &keys::address_reuse(account, address.keychain, address.index),
+                    reuse,
+                )?;
+            }
+        }
+
+        // Persist payment requests paid by the block transactions
+        for payment_request in payment_requests {
+            batch.put(
+                &keys::payment_request(account, payment_request.id),
+                payment_request,
+            )?;
         }
 
         // Update the last_sync in the database (which corresponds with the last_confirmed in the state)
@@ -1056,6 +2143,16 @@ where
                 hash_prev_block: block_info.block_hash,
             },
         )?;
+        // Record the block as applied along with its changes
+        batch.put(&keys::wallet_applied_blocks(), applied_blocks)?;
+
+        #[cfg(test)]
+        if tests::CRASH_BEFORE_BLOCK_WRITE.with(|crash| crash.replace(false)) {
+            return Err(Error::Failure(failure::format_err!(
+                "simulated crash before writing block #{}",
+                block_info.epoch
+            )));
+        }
 
         self.db.write(batch)?;
 
@@ -1084,11 +2181,26 @@ where
         types::VttParams {
             fee,
             outputs,
+            labels,
             utxo_strategy,
             selected_utxos,
             preview,
+            allow_low_fee,
         }: types::VttParams,
     ) -> Result<(model::ExtendedTransaction, AbsoluteFee)> {
+        let dust_threshold = self.params.dust_threshold;
+        if let Some((index, output)) = outputs
+            .iter()
+            .enumerate()
+            .find(|(_, output)| output.value < dust_threshold)
+        {
+            return Err(Error::DustOutput {
+                index,
+                value: output.value,
+                threshold: dust_threshold,
+            });
+        }
+
         let mut state = self.state.write()?;
         let TransactionComponents {
             fee,
@@ -1102,6 +2214,9 @@ where
             selected_utxos,
             preview,
         )?;
+        if !allow_low_fee {
+            self.check_minimum_fee(fee, vt_weight(inputs.pointers.len(), outputs.len()))?;
+        }
 
         let pointers_as_inputs = inputs
             .pointers
@@ -1114,6 +2229,15 @@ where
         let signatures =
             self.create_signatures_from_inputs(pointers_as_inputs, sign_data, &mut state);
         let transaction = VTTransaction::new(body, signatures?);
+
+        // Labels are kept locally, for attaching them to the movement of the transaction
+        if !preview && labels.iter().any(Option::is_some) {
+            self.db.put(
+                &keys::transaction_output_labels(&hex::encode(transaction.hash())),
+                &labels,
+            )?;
+        }
+
         let extended = model::ExtendedTransaction {
             transaction: Transaction::ValueTransfer(transaction),
             metadata: Some(model::TransactionMetadata::InputValues(inputs.resolved)),
@@ -1128,6 +2252,7 @@ where
             fee,
             request,
             preview,
+            allow_low_fee,
         }: types::DataReqParams,
     ) -> Result<(model::ExtendedTransaction, AbsoluteFee)> {
         let mut state = self.state.write()?;
@@ -1136,6 +2261,12 @@ where
             inputs,
             outputs,
         } = self.create_dr_transaction_components(&mut state, request.clone(), fee, preview)?;
+        if !allow_low_fee {
+            self.check_minimum_fee(
+                fee,
+                dr_weight(inputs.pointers.len(), outputs.len(), &request),
+            )?;
+        }
 
         let pointers_as_inputs = inputs
             .pointers
@@ -1157,6 +2288,228 @@ where
         Ok((extended, fee))
     }
 
+    /// Rebuild a pending value transfer sent from this wallet so that it pays a higher fee.
+    ///
+    /// The replacement spends the same inputs and makes the same payments, and the fee increase
+    /// is taken from the change output. If the change cannot cover it, more inputs are added as
+    /// long as `add_inputs` is set.
+    pub fn bump_fee(
+        &self,
+        transaction_hash: &Hash,
+        fee: Fee,
+        add_inputs: bool,
+        allow_low_fee: bool,
+    ) -> Result<(model::ExtendedTransaction, AbsoluteFee)> {
+        let mut state = self.state.write()?;
+        let state = &mut *state;
+
+        if let Some(replacement) = state.fee_bumps.get(transaction_hash) {
+            return Err(Error::TransactionSuperseded {
+                transaction: transaction_hash.to_string(),
+                replacement: replacement.to_string(),
+            });
+        }
+        let original = match state.local_transactions.get(transaction_hash) {
+            Some(vtt) => vtt.clone(),
+            None => {
+                let hex_hash = transaction_hash.to_string();
+                let included = state
+                    .pending_movements
+                    .values()
+                    .flatten()
+                    .any(|movement| movement.transaction.hash == hex_hash)
+                    || self
+                        .db
+                        .get_opt(&keys::transactions_index(transaction_hash.as_ref()))?
+                        .is_some();
+
+                return Err(if included {
+                    Error::TransactionAlreadyIncluded(hex_hash)
+                } else {
+                    Error::UnknownLocalTransaction(hex_hash)
+                });
+            }
+        };
+
+        // The inputs of the original transaction are only missing from the UTXO set if a block
+        // has already spent them
+        let mut pointers = original
+            .body
+            .inputs
+            .iter()
+            .map(|input| input.output_pointer().clone())
+            .collect_vec();
+        let mut resolved = Vec::with_capacity(pointers.len());
+        for pointer in &pointers {
+            let info = state
+                .utxo_set
+                .get(&pointer.into())
+                .ok_or_else(|| Error::TransactionAlreadyIncluded(transaction_hash.to_string()))?;
+            resolved.push(ValueTransferOutput::from(info.clone()));
+        }
+        let mut input_value = resolved.iter().map(|vto| vto.value).sum::<u64>();
+
+        // The change is the last output, as long as it goes to the internal keychain
+        let mut outputs = original.body.outputs.clone();
+        let has_change = match outputs.last() {
+            Some(last) => {
+                last.time_lock == 0
+                    && self
+                        ._address_path(state, &last.pkh)?
+                        .map_or(false, |path| path.keychain == constants::INTERNAL_KEYCHAIN)
+            }
+            None => false,
+        };
+        let change_output = if has_change { outputs.pop() } else { None };
+        let previous_change = change_output.as_ref().map_or(0, |change| change.value);
+        let change_pkh = change_output.map(|change| change.pkh);
+        let payments = outputs
+            .iter()
+            .try_fold(0u64, |total, vto| total.checked_add(vto.value))
+            .ok_or(Error::TransactionValueOverflow)?;
+        let previous_fee = input_value
+            .checked_sub(payments)
+            .and_then(|value| value.checked_sub(previous_change))
+            .ok_or(Error::TransactionValueOverflow)?;
+
+        let timestamp = u64::try_from(get_timestamp()).unwrap();
+        let mut extra_inputs = if add_inputs {
+            sort_utxo_set(&state.utxo_set, true, None)
+                .filter(|out_ptr| {
+                    let pointer: OutputPointer = (*out_ptr).into();
+                    let info = &state.utxo_set[*out_ptr];
+
+                    !pointers.contains(&pointer)
+                        && info.time_lock <= timestamp
+                        && state
+                            .used_outputs
+                            .get(*out_ptr)
+                            .map_or(true, |until| *until <= timestamp)
+                        && (self.params.use_unconfirmed_utxos
+                            || !state.pending_transactions.contains(&pointer.transaction_id))
+                })
+                .cloned()
+                .collect_vec()
+                .into_iter()
+        } else {
+            Vec::new().into_iter()
+        };
+        let mut added = vec![];
+
+        let (fee, change) = loop {
+            let weight = vt_weight(pointers.len(), outputs.len() + 1);
+            if weight > self.params.consensus_constants.max_vt_weight {
+                return Err(Error::MaximumVTTWeightReached(u64::from(weight)));
+            }
+            let fee = match fee {
+                Fee::Absolute(fee) => fee,
+                Fee::Relative(priority) => priority.into_absolute(weight),
+            };
+            if fee.as_nanowits() <= previous_fee {
+                return Err(Error::FeeNotIncreased {
+                    fee: fee.as_nanowits(),
+                    previous: previous_fee,
+                });
+            }
+            if let Some(change) = (input_value - payments).checked_sub(fee.as_nanowits()) {
+                break (fee, change);
+            }
+            if !add_inputs {
+                return Err(Error::FeeBumpNeedsInputs {
+                    change: previous_change,
+                    increase: fee.as_nanowits() - previous_fee,
+                });
+            }
+
+            let out_ptr = extra_inputs.next().ok_or(Error::InsufficientBalance {
+                total_balance: state
+                    .balance
+                    .unconfirmed
+                    .available
+                    .saturating_add(state.balance.unconfirmed.locked),
+                available_balance: input_value,
+                transaction_value: payments.saturating_add(fee.as_nanowits()),
+            })?;
+            let info = state.utxo_set[&out_ptr].clone();
+            input_value = input_value
+                .checked_add(info.amount)
+                .ok_or(Error::TransactionValueOverflow)?;
+            pointers.push((&out_ptr).into());
+            resolved.push(info.into());
+            added.push(out_ptr);
+        };
+
+        // Change below the dust threshold is not worth an output, so it is added to the fee
+        let fee = if change < self.params.dust_threshold {
+            fee + AbsoluteFee::from_nanowits(change)
+        } else {
+            let change_pkh = match change_pkh {
+                Some(pkh) => pkh,
+                None => self.calculate_change_address(state, None, false)?,
+            };
+            insert_change_output(&mut outputs, change_pkh, change);
+
+            fee
+        };
+        if !allow_low_fee {
+            self.check_minimum_fee(fee, vt_weight(pointers.len(), outputs.len()))?;
+        }
+
+        let tx_pending_timeout = self.params.pending_transactions_timeout_seconds;
+        for out_ptr in added {
+            state
+                .used_outputs
+                .insert(out_ptr, timestamp + tx_pending_timeout);
+        }
+
+        let pointers_as_inputs = pointers.into_iter().map(Input::new).collect_vec();
+        let body = VTTransactionBody::new(pointers_as_inputs.clone(), outputs);
+        let sign_data = body.hash();
+        let signatures = self.create_signatures_from_inputs(pointers_as_inputs, sign_data, state);
+        let transaction = VTTransaction::new(body, signatures?);
+
+        // The payments are kept in place, so their labels still apply to the replacement
+        if let Some(labels) = self
+            .db
+            .get_opt(&keys::transaction_output_labels(&hex::encode(
+                transaction_hash,
+            )))?
+        {
+            self.db.put(
+                &keys::transaction_output_labels(&hex::encode(transaction.hash())),
+                &labels,
+            )?;
+        }
+
+        let extended = model::ExtendedTransaction {
+            transaction: Transaction::ValueTransfer(transaction),
+            metadata: Some(model::TransactionMetadata::InputValues(resolved)),
+        };
+
+        Ok((extended, fee))
+    }
+
+    /// Check that a fee is not below the minimum fee policy of the wallet for a transaction of
+    /// the given weight.
+    fn check_minimum_fee(&self, fee: AbsoluteFee, weight: u32) -> Result<()> {
+        let per_weight = u64::from(weight)
+            .saturating_mul(self.params.minimum_fee_per_kilo_weight)
+            .saturating_add(999)
+            / 1000;
+        let minimum = max(self.params.minimum_fee, per_weight);
+        let fee = fee.as_nanowits();
+
+        if fee < minimum {
+            Err(Error::FeeBelowMinimum {
+                fee,
+                minimum,
+                weight,
+            })
+        } else {
+            Ok(())
+        }
+    }
+
     /// Create signatures from inputs
     fn create_signatures_from_inputs(
         &self,
@@ -1212,7 +2565,7 @@ where
             timestamp,
             None,
             utxo_strategy,
-            self.params.max_vt_weight,
+            self.params.consensus_constants.max_vt_weight,
             selected_utxos,
             preview,
         )
@@ -1236,7 +2589,7 @@ where
             timestamp,
             None,
             &utxo_strategy,
-            self.params.max_dr_weight,
+            self.params.consensus_constants.max_dr_weight,
             HashSet::default(),
             preview,
         )
@@ -1294,6 +2647,21 @@ where
             selected_utxos,
         };
 
+        // Outputs are not filtered by block number in the search for inputs needing no change
+        if block_number_limit.is_none() {
+            if let Some(components) = self.select_changeless(
+                &wallet_utxos,
+                &outputs,
+                dr_output,
+                fee,
+                timestamp,
+                utxo_strategy,
+                max_weight,
+            ) {
+                return Ok(components);
+            }
+        }
+
         let TransactionInfo {
             fee,
             inputs,
@@ -1309,6 +2677,16 @@ where
             max_weight,
         )?;
 
+        // Change below the dust threshold is not worth an output, so it is added to the fee
+        let change = inputs.total_value - output_value - fee.as_nanowits();
+        if change < self.params.dust_threshold {
+            return Ok(TransactionComponents {
+                fee: fee + AbsoluteFee::from_nanowits(change),
+                inputs,
+                outputs,
+            });
+        }
+
         // For data requests, this will set the change address to the one from the first input.
         // For any other transaction type, a fresh address is generated in the internal keychain.
         let change_pkh = self.calculate_change_address(
@@ -1318,16 +2696,99 @@ where
         )?;
 
         let mut outputs = outputs;
-        insert_change_output(
-            &mut outputs,
-            change_pkh,
-            inputs.total_value - output_value - fee.as_nanowits(),
+        insert_change_output(&mut outputs, change_pkh, change);
+
+        Ok(TransactionComponents {
+            fee,
+            inputs,
+            outputs,
+        })
+    }
+
+    /// Select inputs that pay for the outputs and the fee without any change, or with an excess
+    /// of up to the changeless tolerance, which is added to the fee. Returns `None` if there are
+    /// no such inputs, or if the search gives up before finding them.
+    #[allow(clippy::too_many_arguments)]
+    fn select_changeless(
+        &self,
+        wallet_utxos: &WalletUtxos<'_>,
+        outputs: &[ValueTransferOutput],
+        dr_output: Option<&DataRequestOutput>,
+        fee: Fee,
+        timestamp: u64,
+        utxo_strategy: &UtxoSelectionStrategy,
+        max_weight: u32,
+    ) -> Option<TransactionComponents> {
+        if self.params.changeless_search_budget == 0 {
+            return None;
+        }
+        let output_value = transaction_outputs_sum(outputs).ok()?.checked_add(
+            dr_output
+                .map_or(Ok(0), DataRequestOutput::checked_total_value)
+                .ok()?,
+        )?;
+        // Transactions that pay nothing are rejected by the usual selection
+        if output_value == 0 {
+            return None;
+        }
+
+        let candidates: Vec<(OutputPointer, ValueTransferOutput)> = wallet_utxos
+            .sort_by(utxo_strategy)
+            .into_iter()
+            .filter(|pointer| {
+                wallet_utxos
+                    .get_usage_timeout(pointer)
+                    .map_or(true, |usage_timeout| usage_timeout <= timestamp)
+            })
+            .filter_map(|pointer| Some((pointer, wallet_utxos.get(&pointer)?)))
+            .collect();
+        let values = candidates
+            .iter()
+            .map(|(_, output)| output.value)
+            .collect_vec();
+        let target = |inputs: usize| {
+            let weight = calculate_weight(inputs, outputs.len(), dr_output, max_weight).ok()?;
+            let fee = match fee {
+                Fee::Absolute(fee) => fee,
+                Fee::Relative(priority) => priority.into_absolute(weight),
+            };
+
+            output_value.checked_add(fee.as_nanowits())
+        };
+
+        let search = coin_selection::find_changeless(
+            &values,
+            target,
+            self.params.changeless_tolerance,
+            self.params.changeless_search_budget,
         );
+        let selection = match search.selection {
+            Some(selection) => selection,
+            None => {
+                log::debug!(
+                    "No inputs needing no change were found among {} outputs after visiting {} nodes",
+                    values.len(),
+                    search.nodes
+                );
 
-        Ok(TransactionComponents {
-            fee,
-            inputs,
-            outputs,
+                return None;
+            }
+        };
+
+        let (pointers, resolved): (Vec<_>, Vec<_>) = selection
+            .into_iter()
+            .map(|index| candidates[index].clone())
+            .unzip();
+        let total_value = resolved.iter().map(|output| output.value).sum::<u64>();
+
+        Some(TransactionComponents {
+            fee: AbsoluteFee::from_nanowits(total_value - output_value),
+            inputs: CollectedOutputs {
+                pointers,
+                resolved,
+                total_value,
+            },
+            outputs: outputs.to_vec(),
         })
     }
 
@@ -1346,9 +2807,17 @@ where
         let (address, next_index) =
             self.derive_and_persist_address(label, parent_key, account, keychain, index, !preview)?;
 
-        // Don't advance the internal index if we are simply previewing
+        // Don't advance the internal index nor index the address if we are simply previewing
         if !preview {
             state.next_internal_index = next_index;
+            state.address_index.insert(
+                address.pkh,
+                model::Path {
+                    account,
+                    keychain,
+                    index,
+                },
+            );
             log::debug!("Internal keychain advanced to index #{next_index}");
         }
 
@@ -1379,11 +2848,18 @@ where
                 txn_hash,
                 block_info.epoch,
             );
-            state.balance.local = state
-                .balance
-                .local
-                .checked_sub(local_movement.amount)
-                .ok_or(Error::TransactionValueOverflow)?;
+            // Superseded movements no longer count towards the local balance
+            if local_movement.transaction.superseded_by.is_none() {
+                state.balance.local = state
+                    .balance
+                    .local
+                    .checked_sub(local_movement.amount)
+                    .ok_or(Error::TransactionValueOverflow)?;
+            }
+        }
+        state.local_transactions.remove(&txn_hash);
+        if let Transaction::ValueTransfer(vtt) = &txn.transaction {
+            self._resolve_fee_bumps(state, vtt)?;
         }
 
         // Update memory state: `utxo_set`
@@ -1395,6 +2871,20 @@ where
             state.utxo_set.insert(pointer.clone(), key_balance.clone());
         }
 
+        // Update memory state: `collaterals`
+        match account_mutation.collateral_change {
+            Some(CollateralChange::Locked(dr_hash, amount)) => {
+                let collateral = state.collaterals.entry(dr_hash).or_default();
+                *collateral = collateral
+                    .checked_add(amount)
+                    .ok_or(Error::TransactionBalanceOverflow)?;
+            }
+            Some(CollateralChange::Released(dr_hash)) => {
+                state.collaterals.remove(&dr_hash);
+            }
+            None => {}
+        }
+
         // Update `transaction_next_id`
         state.transaction_next_id = state
             .transaction_next_id
@@ -1413,7 +2903,10 @@ where
                     let old_address = match addresses.entry(key_balance.pkh) {
                         Entry::Occupied(e) => e.into_mut(),
                         Entry::Vacant(e) => {
-                            let path = self.db.get(&keys::pkh(&key_balance.pkh))?;
+                            let path = match self._address_path(state, &key_balance.pkh)? {
+                                Some(path) => path,
+                                None => self.db.get(&keys::pkh(&key_balance.pkh))?,
+                            };
                             // Get address from memory or DB
                             let old_address =
                                 self._get_address(state, path.account, path.keychain, path.index)?;
@@ -1445,6 +2938,13 @@ where
                         old_address,
                         updated_address
                     );
+                    if updated_address.keychain == constants::EXTERNAL_KEYCHAIN
+                        && state
+                            .last_used_external_index
+                            .map_or(true, |index| updated_address.index > index)
+                    {
+                        state.last_used_external_index = Some(updated_address.index);
+                    }
 
                     *old_address = Arc::new(updated_address);
                 }
@@ -1455,6 +2955,80 @@ where
         Ok(Some(account_mutation.balance_movement))
     }
 
+    /// Once any version of a transaction whose fee was bumped is included in a block, the other
+    /// versions can no longer be, so their local movements are discarded and the inputs that
+    /// only they were spending can be used again.
+    fn _resolve_fee_bumps(&self, state: &mut State, vtt: &VTTransaction) -> Result<()> {
+        if state.fee_bumps.is_empty() {
+            return Ok(());
+        }
+        let included = vtt.hash();
+
+        let mut first = included;
+        while let Some(original) = state
+            .fee_bumps
+            .iter()
+            .find_map(|(original, replacement)| (*replacement == first).then_some(*original))
+        {
+            first = original;
+        }
+        let mut discarded = vec![];
+        let mut version = Some(first);
+        while let Some(hash) = version {
+            version = state.fee_bumps.remove(&hash);
+            if hash != included {
+                discarded.push(hash);
+            }
+        }
+
+        let spent: HashSet<model::OutPtr> = vtt
+            .body
+            .inputs
+            .iter()
+            .map(|input| input.output_pointer().into())
+            .collect();
+        for hash in discarded {
+            if let Some(movement) = state.local_movements.remove(&hash) {
+                log::debug!(
+                    "Discarding local pending movement (txn id: {}) because transaction {} was included instead",
+                    hash,
+                    included,
+                );
+                if movement.transaction.superseded_by.is_none() {
+                    state.balance.local = state
+                        .balance
+                        .local
+                        .checked_sub(movement.amount)
+                        .ok_or(Error::TransactionValueOverflow)?;
+                }
+            }
+            if let Some(discarded) = state.local_transactions.remove(&hash) {
+                for input in &discarded.body.inputs {
+                    let out_ptr: model::OutPtr = input.output_pointer().into();
+                    if !spent.contains(&out_ptr) {
+                        state.used_outputs.remove(&out_ptr);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Whether a transaction spends any of the unspent outputs of the wallet.
+    pub fn spends_own_outputs(&self, transaction: &Transaction) -> Result<bool> {
+        let state = self.state.read()?;
+        let inputs = match transaction {
+            Transaction::ValueTransfer(tx) => &tx.body.inputs,
+            Transaction::DataRequest(tx) => &tx.body.inputs,
+            _ => return Ok(false),
+        };
+
+        Ok(inputs
+            .iter()
+            .any(|input| state.utxo_set.contains_key(&input.output_pointer().into())))
+    }
+
     // TODO: notify client of new local pending transaction
     /// Add local pending balance movement submitted by wallet client
     pub fn add_local_movement(
@@ -1519,6 +3093,9 @@ where
             state
                 .local_movements
                 .insert(txn_hash, account_mutation.balance_movement.clone());
+            if let Transaction::ValueTransfer(vtt) = &txn.transaction {
+                state.local_transactions.insert(txn_hash, vtt.clone());
+            }
             log::debug!(
                 "Local pending movement added for transaction id: {})",
                 txn_hash
@@ -1535,6 +3112,38 @@ where
         Ok(None)
     }
 
+    /// Add the local pending balance movement of a transaction that replaces a previous one with
+    /// a higher fee, and mark the movement of the previous one as superseded.
+    pub fn replace_local_movement(
+        &self,
+        original: &Hash,
+        txn: &model::ExtendedTransaction,
+    ) -> Result<Option<model::BalanceMovement>> {
+        let balance_movement = self.add_local_movement(txn)?;
+
+        let mut state = self.state.write()?;
+        let state = &mut *state;
+        let replacement = txn.transaction.hash();
+        if let Some(superseded) = state.local_movements.get_mut(original) {
+            if superseded.transaction.superseded_by.is_none() {
+                superseded.transaction.superseded_by = Some(replacement.to_string());
+                state.balance.local = state
+                    .balance
+                    .local
+                    .checked_sub(superseded.amount)
+                    .ok_or(Error::TransactionValueOverflow)?;
+            }
+        }
+        state.fee_bumps.insert(*original, replacement);
+        log::debug!(
+            "Local pending movement for transaction id {} superseded by transaction id {}",
+            original,
+            replacement
+        );
+
+        Ok(balance_movement)
+    }
+
     // During wallet synchronization, generate external and internal addresses
     // if transaction outputs are pointing to transient addresses
     pub fn _sync_address_generation(&self, txns: impl Iterator<Item = Transaction>) -> Result<()> {
@@ -1664,7 +3273,7 @@ where
         let mut output_amount: u64 = 0;
         let mut own_outputs: HashMap<PublicKeyHash, model::OutputType> = HashMap::new();
         for (index, output) in outputs.iter().enumerate() {
-            if let Some(path) = self.db.get_opt(&keys::pkh(&output.pkh))? {
+            if let Some(path) = self._address_path(state, &output.pkh)? {
                 match path.keychain {
                     x if x == constants::EXTERNAL_KEYCHAIN => {
                         own_outputs.insert(output.pkh, model::OutputType::External);
@@ -1708,15 +3317,48 @@ where
             }
         }
 
+        // Commits spending our outputs lock the difference as collateral until the tally of their
+        // data request, which returns it together with the reward, unless it was slashed
+        let (collateral_change, category) = match &txn.transaction {
+            Transaction::Commit(commit) if !utxo_removals.is_empty() => (
+                Some(CollateralChange::Locked(
+                    commit.body.dr_pointer.to_string(),
+                    input_amount.saturating_sub(output_amount),
+                )),
+                Some(model::MovementCategory::CollateralLocked),
+            ),
+            Transaction::Tally(tally) => {
+                let dr_hash = tally.dr_pointer.to_string();
+                match state.collaterals.get(&dr_hash) {
+                    Some(_) if utxo_inserts.is_empty() => (
+                        Some(CollateralChange::Released(dr_hash)),
+                        Some(model::MovementCategory::CollateralLost),
+                    ),
+                    Some(_) => (
+                        Some(CollateralChange::Released(dr_hash)),
+                        Some(model::MovementCategory::MiningReward),
+                    ),
+                    None => (None, None),
+                }
+            }
+            _ => (None, None),
+        };
+
         // If UTXO set has not changed, then there is no balance movement derived from the transaction being processed
-        if utxo_inserts.is_empty() && utxo_removals.is_empty() {
+        if utxo_inserts.is_empty() && utxo_removals.is_empty() && collateral_change.is_none() {
             return Ok(None);
         }
 
-        let (amount, kind) = if output_amount >= input_amount {
-            (output_amount - input_amount, model::MovementType::Positive)
-        } else {
-            (input_amount - output_amount, model::MovementType::Negative)
+        let (amount, kind) = match (&txn.transaction, category) {
+            // A slashed collateral was already accounted for by the movement of the commit that
+            // spent it, so the tally only records that it is not coming back
+            (Transaction::Tally(_), Some(model::MovementCategory::CollateralLost)) => {
+                (0, model::MovementType::Negative)
+            }
+            _ if output_amount >= input_amount => {
+                (output_amount - input_amount, model::MovementType::Positive)
+            }
+            _ => (input_amount - output_amount, model::MovementType::Negative),
         };
 
         // Build the balance movement, first computing the miner fee
@@ -1742,6 +3384,16 @@ where
             _ => 0,
         };
 
+        // The fee paid by the wallet is only known if it funded the whole transaction, so that the
+        // value of every input is known
+        let fee_paid = if !resolved_inputs.is_empty() && resolved_inputs.len() == inputs.len() {
+            transaction_fee(&txn.transaction, input_amount)
+        } else {
+            None
+        };
+        let own_inputs: HashSet<PublicKeyHash> =
+            resolved_inputs.iter().map(|input| input.pkh).collect();
+
         // If no metadata is present in the extended transaction, insert resolved inputs so that
         // they can be added to the balance movement.
         // Tally transactions are not affected here because they already contain metadata.
@@ -1765,12 +3417,16 @@ where
             convert_block_epoch_to_timestamp(state.epoch_constants, block_info.epoch),
             confirmed,
             own_outputs,
+            own_inputs,
+            fee_paid,
+            category,
         )?;
 
         Ok(Some(AccountMutation {
             balance_movement,
             utxo_inserts,
             utxo_removals,
+            collateral_change,
         }))
     }
 
@@ -1787,6 +3443,21 @@ where
         let (address, next_index) =
             self.derive_and_persist_address(label, &parent_key, account, keychain, index, true)?;
         state.next_external_index = next_index;
+        if next_index > state.external_generation_watermark {
+            state.external_generation_watermark = next_index;
+            self.db.put(
+                &keys::account_generation_watermark(account, keychain),
+                next_index,
+            )?;
+        }
+        state.address_index.insert(
+            address.pkh,
+            model::Path {
+                account,
+                keychain,
+                index,
+            },
+        );
 
         Ok(address)
     }
@@ -1798,12 +3469,13 @@ where
         Ok(txn)
     }
 
-    /// Sign data using the wallet master key.
+    /// Sign data using the wallet master key, in the given domain or raw if there is none.
     pub fn sign_data(
         &self,
         data: &str,
+        domain: Option<model::SigningDomain>,
         extended_pk: bool,
-    ) -> Result<model::ExtendedKeyedSignature> {
+    ) -> Result<model::SignedData> {
         let state = self.state.read()?;
         let keychain = constants::EXTERNAL_KEYCHAIN;
         let parent_key = &state.keychains[keychain as usize];
@@ -1815,13 +3487,17 @@ where
         };
         let public_key = ExtendedPK::from_secret_key(parent_key).key.to_string();
 
-        let hashed_data = calculate_sha256(data.as_bytes());
-        let signature = signature::sign(parent_key.secret_key, hashed_data.as_ref())?.to_string();
+        let digest = model::signing_digest(domain, data.as_bytes());
+        let signature = signature::sign(parent_key.secret_key, digest.as_ref())?.to_string();
 
-        Ok(model::ExtendedKeyedSignature {
-            signature,
-            public_key,
-            chaincode,
+        Ok(model::SignedData {
+            signature: model::ExtendedKeyedSignature {
+                signature,
+                public_key,
+                chaincode,
+            },
+            domain,
+            digest: hex::encode(digest),
         })
     }
 
@@ -1845,9 +3521,20 @@ where
     }
 
     /// Handle superblock in wallet by confirming pending block changes
-    pub fn handle_superblock(&self, block_hashes: &[String]) -> Result<()> {
+    ///
+    /// `before_block` is called with how many blocks have been consolidated before every block,
+    /// and stops the consolidation if it fails, keeping the blocks already consolidated.
+    pub fn handle_superblock<F, E>(
+        &self,
+        block_hashes: &[String],
+        mut before_block: F,
+    ) -> std::result::Result<(), E>
+    where
+        F: FnMut(usize) -> std::result::Result<(), E>,
+        E: From<Error>,
+    {
         if let Some(last_confirmed_hash) = block_hashes.last() {
-            let state = self.state.read()?;
+            let state = self.state.read().map_err(Error::from)?;
             if last_confirmed_hash == &state.last_confirmed.hash_prev_block.to_string() {
                 log::debug!(
                     "Superblock notification was previously handled (Block #{}: {} is already confirmed)",
@@ -1859,14 +3546,18 @@ where
             }
         }
 
-        block_hashes.iter().try_for_each(|block_hash| {
-            // Genesis block is always confirmed
-            if block_hash == &self.params.genesis_hash.to_string() {
-                Ok(())
-            } else {
-                self.try_consolidate_block(block_hash)
-            }
-        })
+        block_hashes
+            .iter()
+            .enumerate()
+            .try_for_each(|(consolidated, block_hash)| {
+                before_block(consolidated)?;
+                // Genesis block is always confirmed
+                if block_hash == &self.params.genesis_hash.to_string() {
+                    Ok(())
+                } else {
+                    Ok(self.try_consolidate_block(block_hash)?)
+                }
+            })
     }
 
     /// Try to consolidate a block by persisting all changes into the database.
@@ -1906,14 +3597,45 @@ where
             })?;
 
         // Try to persist block transaction changes
+        let (payment_requests, reuses) =
+            match_payment_requests(&state.payment_requests, &movements, &addresses);
+        let addresses = mark_reused_addresses(addresses, &reuses);
+        // Later pending blocks and address lookups start from the in-memory addresses
+        for address in &addresses {
+            if address.reuse.is_some() {
+                if let Some(pending) = state.pending_addresses_by_path.get_mut(&address.path) {
+                    let mut updated = (**pending).clone();
+                    updated.reuse = address.reuse.clone();
+                    *pending = Arc::new(updated);
+                }
+            }
+        }
+        let balance_history = balance_history::record(
+            &state.balance_history,
+            state.epoch_constants,
+            block_state.beacon.epoch,
+            &block_state.balance,
+            &self.params,
+        );
+        let applied_blocks = record_applied_block(&state.applied_blocks, &block_state.beacon);
         self._persist_block_txns(
             movements.clone(),
             addresses,
+            &payment_requests,
             block_state.transaction_next_id,
             block_state.utxo_set.clone(),
+            &block_state.collaterals,
             &block_state.balance,
+            balance_history.as_deref(),
+            &applied_blocks,
             &block_state.beacon,
         )?;
+        apply_payment_request_updates(&mut state, &payment_requests);
+        state.address_reuses.extend(reuses);
+        if let Some(balance_history) = balance_history {
+            state.balance_history = balance_history;
+        }
+        state.applied_blocks = applied_blocks;
 
         // Update pending DR movements if they were persisted
         // balance_movements_to_persist.
@@ -1944,21 +3666,37 @@ where
     ///
     /// Proceed with caution, as this wipes the following data entirely on memory:
     /// - Synchronization status
-    /// - Balances
+    /// - Balances and their history
     /// - Movements
     /// - Addresses and their metadata
     ///
     /// Resets synchronization status in database:
-    /// - Last synchronization status set to the genesis block
+    /// - Last synchronization status set to the birth date of the wallet or to the genesis block,
+    ///   depending on `from`
     /// - Transaction index set to zero
     /// - External and internal address indices set to zero
-    pub fn clear_chain_data(&self) -> Result<()> {
+    /// - Balance history emptied
+    pub fn clear_chain_data(&self, from: types::ResyncFrom) -> Result<()> {
         let mut state = self.state.write()?;
-        state.clear_chain_data();
+        let since = match from {
+            types::ResyncFrom::BirthDate => state.birth_date,
+            types::ResyncFrom::Genesis => CheckpointBeacon {
+                checkpoint: 0,
+                hash_prev_block: self.params.genesis_prev_hash,
+            },
+        };
+        state.clear_chain_data(since);
 
         let mut batch = self.db.batch();
-        batch.put(&keys::wallet_last_sync(), state.birth_date)?;
+        batch.put(&keys::wallet_last_sync(), since)?;
+        batch.put(&keys::wallet_applied_blocks(), BTreeMap::new())?;
         batch.put(&keys::transaction_next_id(0), 0)?;
+        batch.put(&keys::transactions_pruned_until(0), 0)?;
+        batch.put(&keys::account_collaterals(0), model::Collaterals::default())?;
+        batch.put(
+            &keys::account_balance_history(0),
+            Vec::<model::BalanceHistoryEntry>::new(),
+        )?;
         batch.put(
             &keys::account_next_index(0, constants::EXTERNAL_KEYCHAIN),
             0,
@@ -1967,6 +3705,10 @@ where
             &keys::account_next_index(0, constants::INTERNAL_KEYCHAIN),
             0,
         )?;
+        batch.delete(&keys::account_last_used_index(
+            0,
+            constants::EXTERNAL_KEYCHAIN,
+        ))?;
         self.db.write(batch)?;
 
         Ok(())
@@ -1988,6 +3730,87 @@ where
             && state.transient_external_addresses.is_empty()))
     }
 
+    /// Epoch beyond which blocks are not processed, or `None` if the synchronization of the wallet
+    /// is not pinned.
+    pub fn sync_target_epoch(&self) -> Result<Option<u32>> {
+        Ok(self.state.read()?.sync_target_epoch)
+    }
+
+    /// Pin the synchronization of the wallet to an epoch, or unpin it if `epoch` is `None`.
+    ///
+    /// The pin is only kept in memory, storing it is up to the caller.
+    pub fn set_sync_target_epoch(&self, epoch: Option<u32>) -> Result<()> {
+        self.state.write()?.sync_target_epoch = epoch;
+
+        Ok(())
+    }
+
+    /// Tell whether a block of some epoch is beyond the epoch the synchronization of the wallet
+    /// is pinned to.
+    pub fn is_beyond_sync_target(&self, epoch: u32) -> Result<bool> {
+        Ok(self
+            .sync_target_epoch()?
+            .map_or(false, |target_epoch| epoch > target_epoch))
+    }
+
+    /// How far the synchronization of the wallet has got.
+    pub fn sync_watermark(&self) -> Result<model::SyncWatermark> {
+        let syncing = self.is_syncing()?;
+        let state = self.state.read()?;
+        let beacon = |beacon: CheckpointBeacon| model::Beacon {
+            epoch: beacon.checkpoint,
+            block_hash: beacon.hash_prev_block,
+        };
+
+        Ok(model::SyncWatermark {
+            last_sync: beacon(state.last_sync),
+            last_confirmed: beacon(state.last_confirmed),
+            syncing,
+            sync_target_epoch: state.sync_target_epoch,
+        })
+    }
+
+    /// Tell whether the user still has to confirm having backed up the seed of this wallet.
+    pub fn is_pending_confirmation(&self) -> Result<bool> {
+        Ok(self.db.contains(&keys::seed_backup())?)
+    }
+
+    /// Check some words of the seed backup of this wallet, given their 1-based positions in the
+    /// mnemonic. Returns `false` if the wallet has no seed backup, if any word is wrong or if there
+    /// are less than `MIN_SEED_BACKUP_WORDS` distinct positions.
+    pub fn check_seed_backup(&self, words: &[(usize, types::Password)]) -> Result<bool> {
+        let seed_backup = match self.db.get_opt(&keys::seed_backup())? {
+            Some(seed_backup) => seed_backup,
+            None => return Ok(false),
+        };
+        let mnemonic: Vec<&[u8]> = seed_backup
+            .split(|byte| byte.is_ascii_whitespace())
+            .filter(|word| !word.is_empty())
+            .collect();
+
+        let positions: HashSet<usize> = words.iter().map(|(position, _)| *position).collect();
+        let required = constants::MIN_SEED_BACKUP_WORDS.min(mnemonic.len()).max(1);
+
+        Ok(positions.len() >= required
+            && words.iter().all(|(position, word)| {
+                position
+                    .checked_sub(1)
+                    .and_then(|index| mnemonic.get(index))
+                    .map(|expected| *expected == AsRef::<[u8]>::as_ref(word))
+                    .unwrap_or(false)
+            }))
+    }
+
+    /// Remove the seed backup of this wallet, once it has been confirmed or the wallet is about to
+    /// be deleted.
+    pub fn forget_seed_backup(&self) -> Result<()> {
+        let mut batch = self.db.batch();
+        batch.delete(&keys::seed_backup())?;
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
     pub fn export_master_key(&self, password: types::Password) -> Result<String> {
         let state = self.state.read()?;
         let (tag, key) = if let Some(master_key) = self.db.get_opt(&keys::master_key())? {
@@ -2050,6 +3873,183 @@ fn extract_inputs_and_outputs(
     Ok((inputs, outputs))
 }
 
+/// Find the open payment requests that are paid by confirmed value transfers, and return them with
+/// those payments accounted for, along with the payments that reuse the address of a request.
+///
+/// An address is reused by a payment that arrives after its request was paid or expired, or by a
+/// payment to an open request when `addresses`, with the address information after the payments,
+/// show that the address already received a payment that is not one of the request. Payments that
+/// only complete an open request, like the second of two partial payments, are not reuses.
+fn match_payment_requests(
+    payment_requests: &[model::PaymentRequest],
+    movements: &[model::BalanceMovement],
+    addresses: &[Arc<model::Address>],
+) -> (Vec<model::PaymentRequest>, Vec<model::AddressReuse>) {
+    let mut requests: HashMap<&str, model::PaymentRequest> = payment_requests
+        .iter()
+        .map(|request| (request.address.as_str(), request.clone()))
+        .collect();
+    if requests.is_empty() {
+        return (vec![], vec![]);
+    }
+    let received: HashMap<&str, &model::AddressInfo> = addresses
+        .iter()
+        .map(|address| (address.address.as_str(), &address.info))
+        .collect();
+    // Payments of the same transactions are not unrelated, even if they come after another one
+    let transactions: HashSet<&str> = movements
+        .iter()
+        .map(|movement| movement.transaction.hash.as_str())
+        .collect();
+
+    let mut updated = HashSet::new();
+    let mut reuses = vec![];
+    for movement in movements {
+        let outputs = match &movement.transaction.data {
+            model::TransactionData::ValueTransfer(vt_data) => &vt_data.outputs,
+            _ => continue,
+        };
+
+        // A single transaction may pay several times to the same address
+        let mut paid: HashMap<&str, u64> = HashMap::new();
+        for output in outputs {
+            if requests.contains_key(output.address.as_str()) {
+                let value = paid.entry(output.address.as_str()).or_default();
+                *value = value.saturating_add(output.value);
+            }
+        }
+
+        let hash = &movement.transaction.hash;
+        for (address, value) in paid.into_iter().sorted() {
+            let request = requests.get_mut(address).unwrap();
+            if request.payments.contains(hash) {
+                continue;
+            }
+            if !request.record_payment(hash, value) {
+                log::warn!(
+                    "Address {} of payment request #{} ({:?}) was reused by transaction {}",
+                    address,
+                    request.id,
+                    request.status,
+                    hash,
+                );
+                reuses.push(model::AddressReuse {
+                    address: address.to_string(),
+                    payment_request_id: Some(request.id),
+                    previous_transaction: None,
+                    transaction: hash.clone(),
+                });
+                continue;
+            }
+            log::info!(
+                "Payment request #{} received {} nanowits in transaction {} ({:?})",
+                request.id,
+                value,
+                hash,
+                request.status,
+            );
+            updated.insert(request.id);
+
+            // Received payments are output pointers, i.e. `<transaction hash>:<output index>`
+            let previous_transaction = received.get(address).and_then(|info| {
+                info.received_payments
+                    .iter()
+                    .filter_map(|pointer| pointer.split(':').next())
+                    .find(|previous| {
+                        !transactions.contains(previous)
+                            && !request.payments.iter().any(|x| x == previous)
+                    })
+            });
+            if let Some(previous_transaction) = previous_transaction {
+                log::warn!(
+                    "Address {} of payment request #{} was reused by transaction {}, as it had received transaction {}",
+                    address,
+                    request.id,
+                    hash,
+                    previous_transaction,
+                );
+                reuses.push(model::AddressReuse {
+                    address: address.to_string(),
+                    payment_request_id: None,
+                    previous_transaction: Some(previous_transaction.to_string()),
+                    transaction: hash.clone(),
+                });
+            }
+        }
+    }
+
+    let payment_requests = requests
+        .into_values()
+        .filter(|request| updated.contains(&request.id))
+        .sorted_by_key(|request| request.id)
+        .collect();
+
+    (payment_requests, reuses)
+}
+
+/// Flag the addresses that were reused with their last reuse.
+fn mark_reused_addresses(
+    addresses: Vec<Arc<model::Address>>,
+    reuses: &[model::AddressReuse],
+) -> Vec<Arc<model::Address>> {
+    if reuses.is_empty() {
+        return addresses;
+    }
+
+    addresses
+        .into_iter()
+        .map(|address| {
+            match reuses
+                .iter()
+                .rev()
+                .find(|reuse| reuse.address == address.address)
+            {
+                Some(reuse) => Arc::new(model::Address {
+                    reuse: Some(reuse.clone()),
+                    ..(*address).clone()
+                }),
+                None => address,
+            }
+        })
+        .collect()
+}
+
+/// Replace the in-memory payment requests with their updated versions, and queue them for
+/// notifying clients.
+fn apply_payment_request_updates(state: &mut State, payment_requests: &[model::PaymentRequest]) {
+    for request in payment_requests {
+        state.payment_requests[request.id as usize] = request.clone();
+        state.payment_request_updates.push(request.clone());
+    }
+}
+
+/// Whether a block is one of the confirmed blocks applied to the wallet or one of its pending
+/// blocks.
+fn is_applied_block(state: &State, block_info: &model::Beacon) -> bool {
+    state.applied_blocks.get(&block_info.epoch) == Some(&block_info.block_hash)
+        || state
+            .pending_blocks
+            .contains_key(&block_info.block_hash.to_string())
+}
+
+/// Add a block to the confirmed blocks applied to a wallet, forgetting the ones that are more than
+/// `APPLIED_BLOCKS_WINDOW` epochs older than the newest one.
+fn record_applied_block(
+    applied_blocks: &BTreeMap<u32, Hash>,
+    block_info: &model::Beacon,
+) -> BTreeMap<u32, Hash> {
+    let mut applied_blocks = applied_blocks.clone();
+    applied_blocks.insert(block_info.epoch, block_info.block_hash);
+
+    let newest = *applied_blocks
+        .keys()
+        .next_back()
+        .unwrap_or(&block_info.epoch);
+    let oldest = newest.saturating_sub(constants::APPLIED_BLOCKS_WINDOW);
+
+    applied_blocks.split_off(&oldest)
+}
+
 // Balance Movement Factory
 #[allow(clippy::too_many_arguments)]
 fn build_balance_movement(
@@ -2062,6 +4062,9 @@ fn build_balance_movement(
     timestamp: u64,
     confirmed: bool,
     own_outputs: HashMap<PublicKeyHash, model::OutputType>,
+    own_inputs: HashSet<PublicKeyHash>,
+    fee_paid: Option<u64>,
+    category: Option<model::MovementCategory>,
 ) -> Result<model::BalanceMovement> {
     // Input values with their ValueTransferOutput data
     let transaction_inputs = match &txn.metadata {
@@ -2075,6 +4078,31 @@ fn build_balance_movement(
         _ => vec![],
     };
 
+    // Counterparties: recipients of the outputs for movements out of the wallet, and owners of
+    // the inputs for movements into the wallet
+    let counterparties = match kind {
+        model::MovementType::Negative => extract_inputs_and_outputs(&txn.transaction)?
+            .1
+            .iter()
+            .map(|output| output.pkh)
+            .filter(|pkh| !own_outputs.contains_key(pkh))
+            .collect::<Vec<PublicKeyHash>>(),
+        model::MovementType::Positive => transaction_inputs_pkhs(&txn.metadata)
+            .filter(|pkh| !own_inputs.contains(pkh) && !own_outputs.contains_key(pkh))
+            .collect::<Vec<PublicKeyHash>>(),
+    }
+    .into_iter()
+    .unique()
+    .map(|pkh| pkh.to_string())
+    .collect();
+
+    // Data request that commit and tally transactions refer to
+    let data_request_hash = match &txn.transaction {
+        Transaction::Commit(commit) => Some(commit.body.dr_pointer.to_string()),
+        Transaction::Tally(tally) => Some(tally.dr_pointer.to_string()),
+        _ => None,
+    };
+
     // Transaction Data
     let transaction_data = match &txn.transaction {
         Transaction::ValueTransfer(vtt) => model::TransactionData::ValueTransfer(model::VtData {
@@ -2117,10 +4145,28 @@ fn build_balance_movement(
             hash: hex::encode(txn.transaction.hash()),
             miner_fee,
             timestamp,
+            fee_paid,
+            counterparties: Some(counterparties),
+            data_request_hash,
+            pruned: false,
+            superseded_by: None,
         },
+        category,
     })
 }
 
+/// Owners of the inputs of a transaction, as found in its metadata
+fn transaction_inputs_pkhs(
+    metadata: &Option<model::TransactionMetadata>,
+) -> impl Iterator<Item = PublicKeyHash> + '_ {
+    let inputs = match metadata {
+        Some(model::TransactionMetadata::InputValues(inputs)) => inputs.as_slice(),
+        _ => &[],
+    };
+
+    inputs.iter().map(|input| input.pkh)
+}
+
 fn build_tally_report(
     tally: &TallyTransaction,
     metadata: &Option<model::TransactionMetadata>,
@@ -2191,69 +4237,172 @@ fn build_updated_dr_transaction_data(
     }))
 }
 
-#[allow(clippy::type_complexity)]
-fn calculate_transaction_ranges(
-    offset: usize,
-    limit: usize,
-    total_local: usize,
-    total_pending: usize,
-    total_db: usize,
-) -> (
-    Option<Range<usize>>,
-    Option<Range<usize>>,
-    Option<Range<usize>>,
-) {
-    let mut limit = limit;
-
-    let max_local = std::cmp::min(limit, total_local);
-    let local = std::cmp::min(total_local.saturating_sub(offset), max_local);
-
-    limit = limit.saturating_sub(local);
-
-    let max_pending = min(limit, total_pending);
-    let total_local_pending = total_local + total_pending;
-    let pending = min(total_local_pending.saturating_sub(offset), max_pending);
-    limit = limit.saturating_sub(pending);
-
-    let max_db = min(limit, total_db);
-    let total = total_local + total_pending + total_db;
-    let db = min(total.saturating_sub(offset), max_db);
-
-    log::debug!(
-        "Will retrieve {} from local, {} from pending and {} from DB",
+/// Key that a movement is sorted by when listing the movements of a wallet.
+fn movement_sort_key(
+    sort: model::MovementsSort,
+    movement: &model::BalanceMovement,
+    local: bool,
+) -> model::MovementSortKey {
+    let primary = match sort {
+        model::MovementsSort::Id => 0,
+        model::MovementsSort::Amount => match movement.kind {
+            model::MovementType::Positive => i128::from(movement.amount),
+            model::MovementType::Negative => -i128::from(movement.amount),
+        },
+        // Local movements are not in a block yet, so they go after every block
+        model::MovementsSort::Epoch if local => i128::from(u32::MAX) + 1,
+        model::MovementsSort::Epoch => movement
+            .transaction
+            .block
+            .as_ref()
+            .map_or(0, |block| i128::from(block.epoch)),
+    };
+
+    model::MovementSortKey {
+        primary,
         local,
-        pending,
-        db
-    );
+        id: movement.db_key,
+        hash: if local {
+            movement.transaction.hash.clone()
+        } else {
+            String::new()
+        },
+    }
+}
 
-    let local_range = if local > 0 {
-        let init = total_local - offset;
-        let end = init - local;
+/// Number of values in `0..len` for which `pred` holds, assuming that it holds for a prefix of them.
+fn partition_point_u32<F>(len: u32, pred: F) -> u32
+where
+    F: Fn(u32) -> bool,
+{
+    let (mut low, mut high) = (0, len);
+    while low < high {
+        let middle = low + (high - low) / 2;
+        if pred(middle) {
+            low = middle + 1;
+        } else {
+            high = middle;
+        }
+    }
 
-        Some(end..init)
-    } else {
-        None
-    };
+    low
+}
 
-    let pending_range = if pending > 0 {
-        let init = total_local_pending - local - offset;
-        let end = init - pending;
+/// Outputs of the transaction of a movement, in the order of the transaction.
+fn movement_outputs(data: &model::TransactionData) -> &[model::Output] {
+    match data {
+        model::TransactionData::ValueTransfer(data) | model::TransactionData::Commit(data) => {
+            &data.outputs
+        }
+        model::TransactionData::DataRequest(data) => &data.outputs,
+        model::TransactionData::Tally(data) => &data.outputs,
+        model::TransactionData::Mint(data) => &data.outputs,
+    }
+}
 
-        Some(end..init)
-    } else {
-        None
-    };
+/// Get a balance movement from the database, supporting movements persisted before they
+/// included the full transaction context or a category, and movements whose metadata was pruned.
+/// The missing fields of those are left empty.
+fn get_movement<T: Database>(
+    db: &T,
+    account: u32,
+    index: u32,
+) -> db::Result<model::BalanceMovement> {
+    match db.get(&keys::transaction_movement(account, index)) {
+        Err(err @ db::Error::DbKeyNotFound { .. }) => {
+            match db.get_opt(&keys::transaction_pruned_movement(account, index))? {
+                Some(pruned) => Ok(model::BalanceMovement::from(pruned)),
+                None => Err(err),
+            }
+        }
+        Err(db::Error::Bincode(_)) => {
+            match db.get(&keys::uncategorized_transaction_movement(account, index)) {
+                Err(db::Error::Bincode(_)) => db
+                    .get(&keys::legacy_transaction_movement(account, index))
+                    .map(model::BalanceMovement::from),
+                result => result.map(model::BalanceMovement::from),
+            }
+        }
+        result => result,
+    }
+}
+
+/// Highest index below `next_index` of an address of a keychain that received a payment.
+fn last_used_address_index<T: Database>(
+    db: &T,
+    account: u32,
+    keychain: u32,
+    next_index: u32,
+) -> db::Result<Option<u32>> {
+    for index in (0..next_index).rev() {
+        let info = db.get_opt(&keys::address_info(account, keychain, index))?;
+        if info.map_or(false, |info| !info.received_payments.is_empty()) {
+            return Ok(Some(index));
+        }
+    }
 
-    let db_range = if db > 0 {
-        let init = total - local - pending - offset;
-        let end = init - db;
+    Ok(None)
+}
 
-        Some(end..init)
-    } else {
-        None
+/// Attach to the outputs of a value transfer movement the labels that they were given when the
+/// wallet created its transaction, if any.
+fn attach_output_labels<T: Database>(
+    db: &T,
+    movement: &mut model::BalanceMovement,
+) -> db::Result<()> {
+    if let model::TransactionData::ValueTransfer(data) = &mut movement.transaction.data {
+        let labels = db.get_opt(&keys::transaction_output_labels(&movement.transaction.hash))?;
+        for (output, label) in data.outputs.iter_mut().zip(labels.unwrap_or_default()) {
+            output.label = label;
+        }
+    }
+
+    Ok(())
+}
+
+/// Fee paid by a value transfer or data request transaction whose inputs add up to
+/// `input_amount`.
+fn transaction_fee(transaction: &Transaction, input_amount: u64) -> Option<u64> {
+    let outputs_sum = |outputs: &[ValueTransferOutput]| {
+        outputs
+            .iter()
+            .try_fold(0u64, |acc, output| acc.checked_add(output.value))
     };
+    let output_amount = match transaction {
+        Transaction::ValueTransfer(vtt) => outputs_sum(&vtt.body.outputs)?,
+        Transaction::DataRequest(dr) => outputs_sum(&dr.body.outputs)?
+            .checked_add(dr.body.dr_output.checked_total_value().ok()?)?,
+        _ => return None,
+    };
+
+    input_amount.checked_sub(output_amount)
+}
+
+/// Balance of a UTXO set, telling apart the outputs that are time-locked at `timestamp`
+fn utxo_set_balance(utxo_set: &model::UtxoSet, timestamp: u64) -> model::BalanceInfo {
+    utxo_set
+        .values()
+        .map(|balance| (balance.amount, balance.time_lock))
+        .fold(
+            model::BalanceInfo::default(),
+            |mut acc, (amount, time_lock)| {
+                if timestamp >= time_lock {
+                    acc.available = acc.available.checked_add(amount).expect("balance overflow");
+                } else {
+                    acc.locked = acc.locked.checked_add(amount).expect("balance overflow");
+                }
+
+                acc
+            },
+        )
+}
 
-    (local_range, pending_range, db_range)
+/// Total amount locked as collateral of unresolved data requests
+fn collateralized_amount(collaterals: &model::Collaterals) -> Result<u64> {
+    collaterals
+        .values()
+        .try_fold(0u64, |acc, amount| acc.checked_add(*amount))
+        .ok_or(Error::TransactionBalanceOverflow)
 }
 
 // Map vtt to output vec
@@ -2269,6 +4418,7 @@ fn vtt_to_outputs(
             output_type: *own_outputs
                 .get(&output.pkh)
                 .unwrap_or(&model::OutputType::Other),
+            label: None,
         })
         .collect::<Vec<model::Output>>()
 }
@@ -2286,76 +4436,12 @@ where
 }
 
 #[test]
-fn test_get_tx_ranges_inner_range() {
-    let local_total = 10;
-    let pending_total = 10;
-    let db_total = 10;
-
-    let (local_range, pending_range, db_range) =
-        calculate_transaction_ranges(5, 4, local_total, pending_total, db_total);
-    assert_eq!(local_range, Some(1..5));
-    assert_eq!(pending_range, None);
-    assert_eq!(db_range, None);
-
-    let (local_range, pending_range, db_range) =
-        calculate_transaction_ranges(15, 4, local_total, pending_total, db_total);
-    assert_eq!(local_range, None);
-    assert_eq!(pending_range, Some(1..5));
-    assert_eq!(db_range, None);
-
-    let (local_range, pending_range, db_range) =
-        calculate_transaction_ranges(25, 4, local_total, pending_total, db_total);
-    assert_eq!(local_range, None);
-    assert_eq!(pending_range, None);
-    assert_eq!(db_range, Some(1..5));
-}
-
-#[test]
-fn test_get_tx_ranges_overlap() {
-    let local_total = 10;
-    let pending_total = 10;
-    let db_total = 10;
-
-    let (local_range, pending_range, db_range) =
-        calculate_transaction_ranges(5, 10, local_total, pending_total, db_total);
-    assert_eq!(local_range, Some(0..5));
-    assert_eq!(pending_range, Some(5..10));
-    assert_eq!(db_range, None);
-
-    let (local_range, pending_range, db_range) =
-        calculate_transaction_ranges(15, 10, local_total, pending_total, db_total);
-    assert_eq!(local_range, None);
-    assert_eq!(pending_range, Some(0..5));
-    assert_eq!(db_range, Some(5..10));
-
-    let (local_range, pending_range, db_range) =
-        calculate_transaction_ranges(5, 20, local_total, pending_total, db_total);
-    assert_eq!(local_range, Some(0..5));
-    assert_eq!(pending_range, Some(0..10));
-    assert_eq!(db_range, Some(5..10));
-}
-
-#[test]
-fn test_get_tx_ranges_exceed() {
-    let local_total = 10;
-    let pending_total = 10;
-    let db_total = 10;
-
-    let (local_range, pending_range, db_range) =
-        calculate_transaction_ranges(5, 40, local_total, pending_total, db_total);
-    assert_eq!(local_range, Some(0..5));
-    assert_eq!(pending_range, Some(0..10));
-    assert_eq!(db_range, Some(0..10));
-
-    let (local_range, pending_range, db_range) =
-        calculate_transaction_ranges(15, 40, local_total, pending_total, db_total);
-    assert_eq!(local_range, None);
-    assert_eq!(pending_range, Some(0..5));
-    assert_eq!(db_range, Some(0..10));
-
-    let (local_range, pending_range, db_range) =
-        calculate_transaction_ranges(25, 40, local_total, pending_total, db_total);
-    assert_eq!(local_range, None);
-    assert_eq!(pending_range, None);
-    assert_eq!(db_range, Some(0..5));
+fn test_partition_point_u32() {
+    assert_eq!(partition_point_u32(0, |_| true), 0);
+    assert_eq!(partition_point_u32(10, |value| value < 4), 4);
+    assert_eq!(partition_point_u32(10, |_| true), 10);
+    assert_eq!(
+        partition_point_u32(u32::MAX, |value| value <= 1 << 31),
+        (1 << 31) + 1
+    );
 }
@@ -0,0 +1,186 @@
+//! Pruning of the metadata of old movements.
+//!
+//! Wallets with a retention policy keep the full metadata of the movements of the last
+//! `keep_epochs` confirmed epochs. Older movements are pruned down to their hash, amount, direction
+//! and epoch, which is all that balances and histories need. Everything removed from them,
+//! including the labels of their outputs, is first archived to a new file, so nothing is lost. The
+//! archive is JSON sealed with the key of the wallet database, so it is as private as the wallet.
+//!
+//! Movement ids follow the order of the blocks, so the pruned movements are always the first ones
+//! of an account, up to its `transactions_pruned_until` id.
+use std::{fs, io::Write as _, path::Path};
+
+use super::*;
+
+/// Write an archive to a new file that only its owner can access, making sure that it reached the
+/// disk before the details in it are deleted. Existing files are never overwritten.
+fn write_archive(path: &Path, sealed: &[u8]) -> std::io::Result<()> {
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut file = options.open(path)?;
+    file.write_all(sealed)?;
+    file.flush()?;
+    file.sync_all()
+}
+
+impl<T> Wallet<T>
+where
+    T: Database,
+{
+    /// Retention policy of the movements of the wallet.
+    pub fn retention_policy(&self) -> Result<model::RetentionPolicy> {
+        let policy = self.db.get_or_default(&keys::wallet_retention_policy())?;
+
+        Ok(policy)
+    }
+
+    /// Replace the retention policy of the movements of the wallet. Nothing is pruned until
+    /// `prune_movements` is called.
+    pub fn set_retention_policy(&self, policy: model::RetentionPolicy) -> Result<()> {
+        self.db.put(&keys::wallet_retention_policy(), policy)?;
+
+        Ok(())
+    }
+
+    /// Prune the metadata of the movements that are older than the retention policy of the wallet
+    /// allows, after archiving it to a new file in `archive_dir`.
+    pub fn prune_movements(&self, archive_dir: &Path) -> Result<model::PruneReport> {
+        let policy = self.retention_policy()?;
+        let mut report = model::PruneReport {
+            policy,
+            ..Default::default()
+        };
+        let keep_epochs = match policy.keep_epochs {
+            Some(keep_epochs) => keep_epochs,
+            None => return Ok(report),
+        };
+
+        // The movements are read while no block can be persisted, but the archive is written
+        // after releasing the state, so that files are never written while holding it
+        let state = self.state.read()?;
+        let account = state.account;
+        let pruned_before = state.last_confirmed.checkpoint.saturating_sub(keep_epochs);
+        let pruned_until = self
+            .db
+            .get_or_default(&keys::transactions_pruned_until(account))?;
+        let next_id = self
+            .db
+            .get_or_default(&keys::transaction_next_id(account))?;
+        // Movements waiting for the tally of their data request are still to be rewritten
+        let to_update = movements_to_update(&state);
+
+        let mut movements = vec![];
+        for id in pruned_until..next_id {
+            let mut movement = get_movement(&self.db, account, id)?;
+            match &movement.transaction.block {
+                Some(block) if block.epoch < pruned_before && !to_update.contains(&id) => {}
+                _ => break,
+            }
+            if movement.transaction.pruned {
+                continue;
+            }
+            attach_output_labels(&self.db, &mut movement)?;
+            movements.push(model::ArchivedMovement { id, movement });
+        }
+        drop(state);
+        let last_id = match movements.last() {
+            Some(archived) => archived.id,
+            None => return Ok(report),
+        };
+
+        let archive = model::MovementArchive {
+            wallet_id: self.id.clone(),
+            account,
+            pruned_before,
+            movements,
+        };
+        let json = serde_json::to_vec_pretty(&archive)
+            .map_err(|err| Error::PruneArchive(std::io::Error::from(err)))?;
+        let sealed = self.db.seal(&json)?;
+        let archive_path = archive_dir.join(format!(
+            "{}-movements-{}-{}-{}.json.sealed",
+            self.id,
+            pruned_until,
+            last_id,
+            get_timestamp()
+        ));
+        fs::create_dir_all(archive_dir).map_err(Error::PruneArchive)?;
+        write_archive(&archive_path, &sealed).map_err(Error::PruneArchive)?;
+
+        // Confirmed movements do not change, unless a tally made them wait for an update or
+        // another run pruned them meanwhile, in which case the next run archives them again
+        let state = self.state.write()?;
+        let raced = state.account != account
+            || self
+                .db
+                .get_or_default(&keys::transactions_pruned_until(account))?
+                != pruned_until
+            || movements_to_update(&state)
+                .iter()
+                .any(|id| (pruned_until..=last_id).contains(id));
+        if raced {
+            drop(state);
+            log::warn!(
+                "Movements of wallet {} changed while they were being pruned, they will be pruned by the next run",
+                self.id
+            );
+            if let Err(e) = fs::remove_file(&archive_path) {
+                log::warn!(
+                    "Failed to remove the unused archive {}: {}",
+                    archive_path.display(),
+                    e
+                );
+            }
+
+            return Ok(report);
+        }
+
+        let mut batch = self.db.batch();
+        for model::ArchivedMovement { id, movement } in &archive.movements {
+            let pruned = model::PrunedMovement {
+                kind: movement.kind,
+                amount: movement.amount,
+                hash: movement.transaction.hash.clone(),
+                epoch: movement
+                    .transaction
+                    .block
+                    .as_ref()
+                    .map_or(0, |block| block.epoch),
+            };
+            batch.put(&keys::transaction_pruned_movement(account, *id), pruned)?;
+            batch.delete(&keys::transaction_movement(account, *id))?;
+            batch.delete(&keys::transaction_output_labels(&movement.transaction.hash))?;
+        }
+        batch.put(&keys::transactions_pruned_until(account), last_id + 1)?;
+        self.db.write(batch)?;
+        drop(state);
+
+        log::info!(
+            "Pruned the metadata of {} movements of wallet {} before epoch {}, archived to {}",
+            archive.movements.len(),
+            self.id,
+            pruned_before,
+            archive_path.display()
+        );
+        report.pruned = u32::try_from(archive.movements.len()).unwrap_or(u32::MAX);
+        report.archive_path = Some(archive_path);
+
+        Ok(report)
+    }
+}
+
+/// Ids of the movements waiting for the tally of their data request to be rewritten.
+fn movements_to_update(state: &State) -> HashSet<u32> {
+    state
+        .db_movements_to_update
+        .values()
+        .flatten()
+        .map(|movement| movement.db_key)
+        .collect()
+}
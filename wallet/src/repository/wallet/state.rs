@@ -14,6 +14,8 @@ pub struct StateSnapshot {
     pub transaction_next_id: u32,
     /// Current UTXO set (including pending movements)
     pub utxo_set: model::UtxoSet,
+    /// Current collaterals of unresolved data requests (including pending movements)
+    pub collaterals: model::Collaterals,
 }
 
 /// A single wallet state. It includes:
@@ -27,10 +29,15 @@ pub struct StateSnapshot {
 pub struct State {
     /// Current account index
     pub account: u32,
+    /// Hashes of the last confirmed blocks applied to the wallet, by epoch, within
+    /// `APPLIED_BLOCKS_WINDOW` epochs of the newest one
+    pub applied_blocks: BTreeMap<u32, Hash>,
     /// Available account indices
     pub available_accounts: Vec<u32>,
     /// Current wallet balance (including pending movements)
     pub balance: model::WalletBalance,
+    /// Balances after the confirmed blocks that changed them, from the oldest
+    pub balance_history: Vec<model::BalanceHistoryEntry>,
     /// Wallet description
     pub description: Option<String>,
     /// List of already existing DB balance movements that need to be updated upon superblock
@@ -47,17 +54,45 @@ pub struct State {
     /// List of local pending balance movements derived from transaction submissions by wallet clients
     /// (they have not yet been indexed in blocks)
     pub local_movements: HashMap<Hash, model::BalanceMovement>,
+    /// Value transfer transactions of the local movements, which can be replaced with a higher fee
+    pub local_transactions: HashMap<Hash, VTTransaction>,
+    /// Local transactions replaced with a higher fee, and the transactions that replaced them
+    pub fee_bumps: HashMap<Hash, Hash>,
     /// Wallet name
     pub name: Option<String>,
+    /// Guardrails on the funds that can be sent out of the wallet
+    pub policy: model::WalletPolicy,
+    /// Which transactions nodes can have the wallet sign on their behalf
+    pub witness_signing_policy: model::WitnessSigningPolicy,
+    /// Endpoint that the events of the wallet are posted to
+    pub webhook: Option<model::WebhookConfig>,
     /// Next external index used to derive addresses
     pub next_external_index: u32,
     /// Next internal index used to derive addresses
     pub next_internal_index: u32,
+    /// Highest index of an external address that received a payment, if any did
+    pub last_used_external_index: Option<u32>,
+    /// Highest next external index ever reached, which is kept when clearing the chain data so
+    /// that resynchronizations look at least that far
+    pub external_generation_watermark: u32,
+    /// Whether the last external address generated for a client was beyond the gap limit
+    pub address_gap_exceeded: bool,
     /// List of pending address infos indexed by block hash, waiting to be confirmed with a superblock
     ///  This is a hashmap from pending_block_hash to Vec<addresses>.
     pub pending_addresses_by_block: HashMap<String, Vec<Arc<model::Address>>>,
     /// List of pending address infos indexed by key path, waiting to be confirmed with a superblock
     pub pending_addresses_by_path: HashMap<String, Arc<model::Address>>,
+    /// Payment requests of the current account, indexed by their id
+    pub payment_requests: Vec<model::PaymentRequest>,
+    /// Payment requests that changed status since the last time they were taken for notifying
+    /// clients
+    pub payment_request_updates: Vec<model::PaymentRequest>,
+    /// Reuses of the addresses of payment requests detected since the last time they were taken
+    /// for notifying clients
+    pub address_reuses: Vec<model::AddressReuse>,
+    /// Warnings about external addresses generated beyond the gap limit since the last time they
+    /// were taken for notifying clients
+    pub address_gap_warnings: Vec<model::AddressGapWarning>,
     /// List of pending blocks with state snapshots waiting to be confirmed
     ///  This is a hashmap from pending_block_hash to StateSnapshot.
     pub pending_blocks: HashMap<String, StateSnapshot>,
@@ -73,6 +108,9 @@ pub struct State {
     pub transaction_next_id: u32,
     /// Current UTXO set (including pending movements)
     pub utxo_set: model::UtxoSet,
+    /// Collateral locked by the commits of the wallet, by data request hash (including pending
+    /// movements)
+    pub collaterals: model::Collaterals,
     /// Outputs that have been locally tagged as used. This prevents double spending
     /// an output while a first spending transaction is pending.
     pub used_outputs: model::UsedOutputs,
@@ -80,8 +118,12 @@ pub struct State {
     pub transient_internal_addresses: HashMap<PublicKeyHash, model::Address>,
     /// Transient external addresses
     pub transient_external_addresses: HashMap<PublicKeyHash, model::Address>,
+    /// In-memory index of the generated addresses, used to tell which outputs belong to the wallet
+    pub address_index: AddressIndex,
     /// Flag that determines whether to stop syncing an ongoing synchronization for a wallet session
     pub stop_syncing: bool,
+    /// Epoch beyond which blocks are not processed, while the synchronization is pinned
+    pub sync_target_epoch: Option<u32>,
     /// Protocol epoch in which a wallet was created (won't synchronize blocks prior to this epoch)
     pub birth_date: CheckpointBeacon,
 }
@@ -91,17 +133,25 @@ impl State {
     ///
     /// Proceed with caution, as this wipes the following data entirely:
     /// - Synchronization status
-    /// - Balances
+    /// - Balances and their history
     /// - Movements
     /// - Addresses and their metadata
     ///
-    pub fn clear_chain_data(&mut self) {
+    /// The synchronization status is reset to `since`, so the blocks up to it are not scanned again.
+    /// The generation watermark is kept, so that the addresses generated before are scanned again.
+    pub fn clear_chain_data(&mut self, since: CheckpointBeacon) {
+        self.applied_blocks.clear();
         self.balance = Default::default();
-        self.last_confirmed = self.birth_date;
-        self.last_sync = self.birth_date;
+        self.balance_history.clear();
+        self.last_confirmed = since;
+        self.last_sync = since;
         self.local_movements.clear();
+        self.local_transactions.clear();
+        self.fee_bumps.clear();
         self.next_internal_index = Default::default();
         self.next_external_index = Default::default();
+        self.last_used_external_index = None;
+        self.address_gap_exceeded = false;
         self.pending_addresses_by_block.clear();
         self.pending_addresses_by_path.clear();
         self.pending_blocks.clear();
@@ -110,8 +160,10 @@ impl State {
         self.pending_transactions.clear();
         self.transaction_next_id = Default::default();
         self.utxo_set.clear();
+        self.collaterals.clear();
         self.used_outputs.clear();
         self.transient_internal_addresses.clear();
         self.transient_external_addresses.clear();
+        self.address_index.clear();
     }
 }
@@ -8,25 +8,106 @@ use crate::db::HashMapDb;
 use witnet_crypto::{hash::HashFunction, mnemonic};
 
 pub fn wallet(data: Option<HashMapDb>) -> (Wallet<db::HashMapDb>, db::HashMapDb) {
-    wallet_inner(data, true)
+    wallet_inner(data, true, None, default_params())
+}
+
+/// Create a wallet with the given parameters instead of the default ones.
+pub fn wallet_with_params(
+    data: Option<HashMapDb>,
+    params: params::Params,
+) -> (Wallet<db::HashMapDb>, db::HashMapDb) {
+    wallet_inner(data, true, None, params)
 }
 
 pub fn wallet_with_args(
     data: Option<HashMapDb>,
     store_master_key: bool,
 ) -> (Wallet<db::HashMapDb>, db::HashMapDb) {
-    wallet_inner(data, store_master_key)
+    wallet_inner(data, store_master_key, None, default_params())
+}
+
+/// Create a wallet from the given mnemonic phrase, still waiting for its seed backup to be
+/// confirmed.
+pub fn wallet_with_seed_backup(phrase: &str) -> (Wallet<db::HashMapDb>, db::HashMapDb) {
+    let mnemonic = mnemonic::Mnemonic::from_phrase_ref(phrase).unwrap();
+
+    wallet_inner(None, true, Some(mnemonic), default_params())
 }
 
 fn wallet_inner(
     data: Option<HashMapDb>,
     store_master_key: bool,
+    seed_backup: Option<mnemonic::Mnemonic>,
+    params: params::Params,
 ) -> (Wallet<db::HashMapDb>, db::HashMapDb) {
-    let id = "example-wallet";
+    let id: model::WalletId = "example-wallet".parse().unwrap();
+    let mut rng = rand::rngs::OsRng;
+    let salt = crypto::salt(&mut rng, params.db_salt_length);
+    let iv = crypto::salt(&mut rng, params.db_iv_length);
+
+    let db = data.unwrap_or_default();
+    let wallets = Wallets::new(db.clone());
+    let session_id = types::SessionId::from(id.to_string());
+    let wallet = create_and_unlock(
+        &wallets,
+        db.clone(),
+        (&id, session_id),
+        (salt, iv),
+        store_master_key,
+        seed_backup,
+        params,
+    );
+
+    (wallet, db)
+}
+
+/// Create a wallet in a RocksDB database and unlock it in `session_id`, as the worker does.
+pub fn encrypted_wallet(
+    rocks: Arc<rocksdb::DB>,
+    id: &model::WalletId,
+    session_id: types::SessionId,
+) -> Wallet<db::EncryptedDb> {
     let params = default_params();
-    let mnemonic = mnemonic::MnemonicGen::new()
-        .with_len(mnemonic::Length::Words12)
-        .generate();
+    let mut rng = rand::rngs::OsRng;
+    let salt = crypto::salt(&mut rng, params.db_salt_length);
+    let iv = crypto::salt(&mut rng, params.db_iv_length);
+    let key = crypto::key_from_password(b"password", &salt, params.db_hash_iterations);
+
+    let wallets = Wallets::new(db::PlainDb::new(rocks.clone()));
+    let wallet_db = db::EncryptedDb::new(rocks, id.as_bytes().to_vec(), key, iv.clone());
+
+    create_and_unlock(
+        &wallets,
+        wallet_db,
+        (id, session_id),
+        (salt, iv),
+        true,
+        None,
+        params,
+    )
+}
+
+fn create_and_unlock<P, D>(
+    wallets: &Wallets<P>,
+    db: D,
+    (id, session_id): (&model::WalletId, types::SessionId),
+    (salt, iv): (Vec<u8>, Vec<u8>),
+    store_master_key: bool,
+    seed_backup: Option<mnemonic::Mnemonic>,
+    params: params::Params,
+) -> Wallet<D>
+where
+    P: Database,
+    D: Database + Clone,
+{
+    let backup = seed_backup
+        .as_ref()
+        .map(|mnemonic| types::Secret::new(mnemonic.words()));
+    let mnemonic = seed_backup.unwrap_or_else(|| {
+        mnemonic::MnemonicGen::new()
+            .with_len(mnemonic::Length::Words12)
+            .generate()
+    });
     let source = types::SeedSource::Mnemonics(mnemonic);
     let master_key = crypto::gen_master_key(
         params.seed_password.as_ref(),
@@ -35,14 +116,8 @@ fn wallet_inner(
     )
     .unwrap();
     let default_account_index = 0;
-    let default_account = account::gen_account(default_account_index, &master_key).unwrap();
-
-    let mut rng = rand::rngs::OsRng;
-    let salt = crypto::salt(&mut rng, params.db_salt_length);
-    let iv = crypto::salt(&mut rng, params.db_iv_length);
-
-    let db = data.unwrap_or_default();
-    let wallets = Wallets::new(db.clone());
+    let default_account =
+        account::gen_account(&params.key_derivation, default_account_index, &master_key).unwrap();
 
     let master_key_to_store = if store_master_key {
         Some(master_key)
@@ -57,6 +132,7 @@ fn wallet_inner(
                 iv,
                 salt,
                 id,
+                seed_fingerprint: wallets.seed_fingerprint(id).unwrap(),
                 name: None,
                 description: None,
                 account: &default_account,
@@ -65,14 +141,13 @@ fn wallet_inner(
                     checkpoint: 0,
                     hash_prev_block: params.genesis_prev_hash,
                 },
+                seed_backup: backup,
+                key_derivation: params.key_derivation,
             },
         )
         .unwrap();
 
-    let session_id = types::SessionId::from(String::from(id));
-    let wallet = Wallet::unlock(id, session_id, db.clone(), params).unwrap();
-
-    (wallet, db)
+    Wallet::unlock(id, session_id, db, params).unwrap()
 }
 
 pub fn pkh() -> PublicKeyHash {
@@ -129,15 +204,13 @@ pub fn default_params() -> params::Params {
         genesis_hash: Hash::default(),
         genesis_prev_hash: Hash::default(),
         sync_address_batch_length: 10,
-        max_vt_weight: 20_000,
-        max_dr_weight: 80_000,
         consensus_constants: ConsensusConstants {
             checkpoint_zero_timestamp: 0,
             checkpoints_period: 0,
             bootstrap_hash: Hash::SHA256([3; 32]),
             genesis_hash: Hash::SHA256([4; 32]),
-            max_vt_weight: 0,
-            max_dr_weight: 0,
+            max_vt_weight: 20_000,
+            max_dr_weight: 80_000,
             activity_period: 0,
             reputation_expire_alpha_diff: 0,
             reputation_issuance: 0,
@@ -161,5 +234,33 @@ pub fn default_params() -> params::Params {
         use_unconfirmed_utxos: true,
         pending_transactions_timeout_seconds: 10 * 45,
         witnessing: WitnessingConfig::default(),
+        retrieval_cache_enabled: false,
+        retrieval_cache_ttl: 60,
+        retrieval_cache_max_size: 1024,
+        verify_block_headers: false,
+        seed_backup_confirmation_attempts: 3,
+        check_integrity_on_unlock: false,
+        address_index_max_entries: 1_000_000,
+        minimum_fee: 0,
+        minimum_fee_per_kilo_weight: 0,
+        dust_threshold: 0,
+        changeless_tolerance: 0,
+        changeless_search_budget: 0,
+        refuse_addresses_beyond_gap_limit: false,
+        balance_history_granularity: 1,
+        balance_history_daily_after: 30 * 24 * 60 * 60,
+        prune_archive_path: std::env::temp_dir(),
+        key_derivation: model::KeyDerivation::LEGACY,
+        webhook_outbox_size: 10,
+        webhook_max_attempts: 3,
+        webhook_retry_delay: 1_000,
     }
 }
+
+/// Database of a wallet created before its schema was versioned, with no movements.
+pub fn unversioned_wallet_db() -> HashMapDb {
+    // db created using:
+    // let (wallet, db) = factories::wallet(None);
+    // println!("{}", db.export_to_json().unwrap());
+    HashMapDb::import_from_json("[[[101,120,97,109,112,108,101,45,119,97,108,108,101,116,105,118],[16,0,0,0,0,0,0,0,253,220,27,197,124,58,52,48,149,209,246,175,226,167,24,235]],[[100,101,102,97,117,108,116,45,97,99,99,111,117,110,116],[0,0,0,0]],[[98,105,114,116,104,45,100,97,116,101],[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]],[[97,99,99,111,117,110,116,45,48,45,48,45,107,101,121],[32,0,0,0,0,0,0,0,138,147,131,114,62,143,164,77,65,3,128,60,53,66,230,108,34,29,118,222,26,170,237,96,102,215,94,227,107,132,164,179,32,0,0,0,0,0,0,0,31,132,33,245,90,11,143,28,106,239,4,135,237,90,168,51,103,119,71,41,85,64,211,24,187,167,46,148,194,32,181,25]],[[109,97,115,116,101,114,45,107,101,121],[32,0,0,0,0,0,0,0,104,215,222,1,179,103,48,156,129,116,214,126,65,8,186,43,227,75,42,174,149,13,59,190,61,147,106,113,156,234,112,103,32,0,0,0,0,0,0,0,233,91,131,28,53,151,204,57,207,6,225,50,113,67,70,16,120,151,29,93,114,9,217,245,212,145,169,196,138,206,81,232]],[[97,99,99,111,117,110,116,45,48,45,49,45,107,101,121],[32,0,0,0,0,0,0,0,159,251,203,85,170,0,252,117,89,207,127,183,202,138,136,177,9,166,37,188,2,117,143,179,121,90,111,70,108,156,252,7,32,0,0,0,0,0,0,0,117,94,110,127,110,158,134,8,141,217,209,199,209,173,223,91,131,92,167,133,147,7,79,157,249,183,18,136,1,230,8,53]],[[108,97,115,116,45,115,121,110,99],[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]],[[119,97,108,108,101,116,115],[1,0,0,0,0,0,0,0,14,0,0,0,0,0,0,0,101,120,97,109,112,108,101,45,119,97,108,108,101,116]],[[101,120,97,109,112,108,101,45,119,97,108,108,101,116,115,97,108,116],[32,0,0,0,0,0,0,0,26,124,168,170,31,187,78,102,12,152,23,213,241,242,206,201,58,116,200,79,251,130,103,254,13,178,54,228,142,39,208,161]]]").unwrap()
+}
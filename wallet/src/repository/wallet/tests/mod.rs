@@ -1,14 +1,27 @@
 use std::{collections::HashMap, iter::FromIterator as _, mem};
 
 use witnet_data_structures::{
-    chain::Hashable, transaction::VTTransaction, transaction_factory::calculate_weight,
+    chain::{Hashable, PublicKey},
+    transaction::{CommitTransactionBody, VTTransaction},
+    transaction_factory::calculate_weight,
+    vrf::DataRequestEligibilityClaim,
 };
 
-use crate::{db::HashMapDb, repository::wallet::tests::factories::vtt_from_body, *};
+use crate::{
+    db::{HashMapDb, WriteBatch as _},
+    repository::wallet::tests::factories::vtt_from_body,
+    *,
+};
 
 use super::*;
 
-mod factories;
+pub(crate) mod factories;
+
+thread_local! {
+    /// Fault injection: make the next write of the changes of a confirmed block fail, as if the
+    /// process crashed right before it.
+    pub(crate) static CRASH_BEFORE_BLOCK_WRITE: std::cell::Cell<bool> = std::cell::Cell::new(false);
+}
 
 #[test]
 fn test_wallet_public_data() {
@@ -41,6 +54,82 @@ fn test_gen_external_address() {
     assert_eq!(None, address_no_label.info.label);
 }
 
+#[test]
+fn test_key_derivation_of_wallet() {
+    let (wallet, _db) = factories::wallet(None);
+
+    assert_eq!(
+        model::KeyDerivation::LEGACY,
+        wallet.public_data().unwrap().key_derivation
+    );
+
+    let key_derivation = model::KeyDerivation {
+        key_spec: witnet_crypto::key::KeySpec::Bip44,
+        coin_type: 0,
+    };
+    let params = params::Params {
+        key_derivation,
+        ..factories::default_params()
+    };
+    let (wallet, db) = factories::wallet_with_params(None, params);
+
+    assert_eq!(key_derivation, wallet.public_data().unwrap().key_derivation);
+    assert_eq!(
+        "m/44'/0'/0'/0/0",
+        &wallet.gen_external_address(None).unwrap().path
+    );
+    assert_eq!(
+        "m/44'/0'/0'/1/0",
+        &wallet.gen_internal_address(None, false).unwrap().path
+    );
+
+    // The derivation is read back from the wallet rather than from the parameters
+    assert_eq!(
+        key_derivation,
+        reopen(&db).public_data().unwrap().key_derivation
+    );
+}
+
+#[test]
+fn test_unlock_wallet_with_unknown_key_spec() {
+    let (_wallet, db) = factories::wallet(None);
+    db.put(
+        &keys::wallet_key_derivation(),
+        model::KeyDerivationRecord {
+            key_spec: "wip99".to_string(),
+            coin_type: 4919,
+        },
+    )
+    .unwrap();
+
+    let err = Wallet::unlock(
+        &"example-wallet".parse().unwrap(),
+        types::SessionId::from(String::from("example-wallet")),
+        db,
+        factories::default_params(),
+    )
+    .err()
+    .unwrap();
+
+    assert!(matches!(&err, Error::UnsupportedKeySpec(spec) if spec == "wip99"));
+    assert_eq!(
+        "wallet requires newer software: unknown key spec wip99",
+        err.to_string()
+    );
+}
+
+#[test]
+fn test_contains_address() {
+    let (wallet, _db) = factories::wallet(None);
+    let external = wallet.gen_external_address(None).unwrap();
+    let internal = wallet.gen_internal_address(None, false).unwrap();
+    let foreign = PublicKeyHash::from_bytes(&[0x01; 20]).unwrap();
+
+    assert!(wallet.contains_address(&external.pkh).unwrap());
+    assert!(wallet.contains_address(&internal.pkh).unwrap());
+    assert!(!wallet.contains_address(&foreign).unwrap());
+}
+
 #[test]
 fn test_gen_external_address_creates_different_addresses() {
     let (wallet, _db) = factories::wallet(None);
@@ -595,9 +684,11 @@ fn test_create_vtt_does_not_spend_utxos() {
                 value,
                 time_lock,
             }],
+            labels: vec![],
             utxo_strategy,
             selected_utxos: HashSet::default(),
             preview: false,
+            allow_low_fee: false,
         })
         .unwrap();
 
@@ -687,6 +778,7 @@ fn test_create_data_request_does_not_spend_utxos() {
             fee,
             request,
             preview: false,
+            allow_low_fee: false,
         })
         .unwrap();
 
@@ -727,6 +819,249 @@ fn test_create_data_request_does_not_spend_utxos() {
         .is_err());
 }
 
+/// Wallet with a single UTXO of 1000 nanoWits and the given fee policy.
+fn wallet_with_fee_policy(
+    minimum_fee: u64,
+    minimum_fee_per_kilo_weight: u64,
+    dust_threshold: u64,
+) -> Wallet<HashMapDb> {
+    let pkh = factories::pkh();
+    let utxo_set: HashMap<model::OutPtr, model::OutputInfo> = HashMap::from_iter(vec![(
+        model::OutPtr {
+            txn_hash: vec![0; 32],
+            output_index: 0,
+        },
+        model::OutputInfo {
+            pkh,
+            amount: 1000,
+            time_lock: 0,
+        },
+    )]);
+    let path = model::Path {
+        account: 0,
+        keychain: constants::EXTERNAL_KEYCHAIN,
+        index: 0,
+    };
+
+    let db = HashMapDb::default();
+    db.put(&keys::account_utxo_set(0), utxo_set).unwrap();
+    db.put(&keys::pkh(&pkh), path).unwrap();
+    let params = params::Params {
+        minimum_fee,
+        minimum_fee_per_kilo_weight,
+        dust_threshold,
+        ..factories::default_params()
+    };
+
+    factories::wallet_with_params(Some(db), params).0
+}
+
+fn vtt_params(value: u64, fee: u64, allow_low_fee: bool) -> types::VttParams {
+    types::VttParams {
+        fee: Fee::absolute_from_nanowits(fee),
+        outputs: vec![ValueTransferOutput {
+            pkh: factories::pkh(),
+            value,
+            time_lock: 0,
+        }],
+        labels: vec![],
+        utxo_strategy: UtxoSelectionStrategy::Random { from: None },
+        selected_utxos: HashSet::default(),
+        preview: true,
+        allow_low_fee,
+    }
+}
+
+fn vtt_outputs(extended: model::ExtendedTransaction) -> Vec<ValueTransferOutput> {
+    match extended.transaction {
+        Transaction::ValueTransfer(vtt) => vtt.body.outputs,
+        transaction => panic!("expected a value transfer, got: {:?}", transaction),
+    }
+}
+
+#[test]
+fn test_create_vtt_folds_dust_change_into_fee() {
+    let wallet = wallet_with_fee_policy(0, 0, 100);
+
+    // 1000 - 800 - 150 leaves a change of 50, which is below the dust threshold
+    let (extended, fee) = wallet.create_vtt(vtt_params(800, 150, false)).unwrap();
+    assert_eq!(fee.as_nanowits(), 200);
+    assert_eq!(vtt_outputs(extended).len(), 1);
+
+    // A change of exactly the dust threshold is kept
+    let (extended, fee) = wallet.create_vtt(vtt_params(750, 150, false)).unwrap();
+    assert_eq!(fee.as_nanowits(), 150);
+    let outputs = vtt_outputs(extended);
+    assert_eq!(outputs.len(), 2);
+    assert_eq!(outputs[1].value, 100);
+}
+
+/// Wallet with UTXOs of the given values, which looks for inputs needing no change.
+fn wallet_with_changeless_search(values: &[u64], tolerance: u64) -> Wallet<HashMapDb> {
+    let pkh = factories::pkh();
+    let utxo_set: HashMap<model::OutPtr, model::OutputInfo> = values
+        .iter()
+        .enumerate()
+        .map(|(index, value)| {
+            (
+                model::OutPtr {
+                    txn_hash: vec![0; 32],
+                    output_index: index as u32,
+                },
+                model::OutputInfo {
+                    pkh,
+                    amount: *value,
+                    time_lock: 0,
+                },
+            )
+        })
+        .collect();
+    let path = model::Path {
+        account: 0,
+        keychain: constants::EXTERNAL_KEYCHAIN,
+        index: 0,
+    };
+
+    let db = HashMapDb::default();
+    db.put(&keys::account_utxo_set(0), utxo_set).unwrap();
+    db.put(&keys::pkh(&pkh), path).unwrap();
+    let params = params::Params {
+        changeless_tolerance: tolerance,
+        changeless_search_budget: 1_000,
+        ..factories::default_params()
+    };
+
+    factories::wallet_with_params(Some(db), params).0
+}
+
+fn vtt_input_values(extended: &model::ExtendedTransaction) -> Vec<u64> {
+    match &extended.metadata {
+        Some(model::TransactionMetadata::InputValues(inputs)) => {
+            inputs.iter().map(|input| input.value).sorted().collect()
+        }
+        metadata => panic!("expected the values of the inputs, got: {:?}", metadata),
+    }
+}
+
+#[test]
+fn test_create_vtt_selects_inputs_needing_no_change() {
+    let wallet = wallet_with_changeless_search(&[600, 2_000, 400], 0);
+
+    let (extended, fee) = wallet.create_vtt(vtt_params(950, 50, false)).unwrap();
+    assert_eq!(fee.as_nanowits(), 50);
+    assert_eq!(vtt_input_values(&extended), vec![400, 600]);
+    assert_eq!(vtt_outputs(extended).len(), 1);
+
+    let (extended, fee) = wallet
+        .create_vtt(vtt_params(2_000 - 50, 50, false))
+        .unwrap();
+    assert_eq!(fee.as_nanowits(), 50);
+    assert_eq!(vtt_input_values(&extended), vec![2_000]);
+    assert_eq!(vtt_outputs(extended).len(), 1);
+}
+
+#[test]
+fn test_create_vtt_adds_the_changeless_tolerance_to_the_fee() {
+    let wallet = wallet_with_changeless_search(&[600, 2_000, 400], 20);
+
+    let (extended, fee) = wallet.create_vtt(vtt_params(950, 40, false)).unwrap();
+    assert_eq!(fee.as_nanowits(), 50);
+    assert_eq!(vtt_outputs(extended).len(), 1);
+
+    // No inputs are within the tolerance, so the usual selection leaves some change
+    let (extended, fee) = wallet.create_vtt(vtt_params(900, 50, false)).unwrap();
+    assert_eq!(fee.as_nanowits(), 50);
+    let outputs = vtt_outputs(extended);
+    assert_eq!(outputs.len(), 2);
+    assert_ne!(outputs[1].value, 0);
+}
+
+#[test]
+fn test_create_vtt_rejects_dust_recipients_even_if_low_fee_is_allowed() {
+    let wallet = wallet_with_fee_policy(50, 0, 100);
+
+    for allow_low_fee in [false, true] {
+        let err = wallet
+            .create_vtt(vtt_params(99, 150, allow_low_fee))
+            .unwrap_err();
+        assert!(
+            matches!(
+                err,
+                Error::DustOutput {
+                    index: 0,
+                    value: 99,
+                    threshold: 100,
+                }
+            ),
+            "{:?}",
+            err
+        );
+    }
+
+    wallet.create_vtt(vtt_params(100, 150, false)).unwrap();
+}
+
+#[test]
+fn test_create_vtt_enforces_minimum_fee_unless_overridden() {
+    let wallet = wallet_with_fee_policy(50, 100, 100);
+
+    // With a change output, the weight of 853 requires a fee of at least 86 nanoWits
+    let weight = vt_weight(1, 2);
+    assert_eq!(weight, 853);
+    let err = wallet.create_vtt(vtt_params(700, 85, false)).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::FeeBelowMinimum {
+                fee: 85,
+                minimum: 86,
+                weight: 853,
+            }
+        ),
+        "{:?}",
+        err
+    );
+    let (_, fee) = wallet.create_vtt(vtt_params(700, 85, true)).unwrap();
+    assert_eq!(fee.as_nanowits(), 85);
+    wallet.create_vtt(vtt_params(700, 86, false)).unwrap();
+
+    // The dust change that is added to the fee counts towards the minimum
+    let (extended, fee) = wallet.create_vtt(vtt_params(900, 40, false)).unwrap();
+    assert_eq!(fee.as_nanowits(), 100);
+    assert_eq!(vtt_outputs(extended).len(), 1);
+}
+
+#[test]
+fn test_create_data_request_enforces_minimum_fee_unless_overridden() {
+    let wallet = wallet_with_fee_policy(150, 0, 100);
+    let data_req_params = |allow_low_fee| types::DataReqParams {
+        fee: Fee::absolute_from_nanowits(123),
+        request: DataRequestOutput {
+            witness_reward: 5,
+            witnesses: 99,
+            ..DataRequestOutput::default()
+        },
+        preview: true,
+        allow_low_fee,
+    };
+
+    let err = wallet.create_data_req(data_req_params(false)).unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::FeeBelowMinimum {
+                fee: 123,
+                minimum: 150,
+                ..
+            }
+        ),
+        "{:?}",
+        err
+    );
+    let (_, fee) = wallet.create_data_req(data_req_params(true)).unwrap();
+    assert_eq!(fee.as_nanowits(), 123);
+}
+
 #[test]
 fn test_index_transaction_output_affects_balance() {
     let (wallet, db) = factories::wallet(None);
@@ -1026,9 +1361,11 @@ fn test_index_transaction_vtt_created_by_wallet() {
                 value: 1,
                 time_lock: 0,
             }],
+            labels: vec![],
             utxo_strategy: UtxoSelectionStrategy::Random { from: None },
             selected_utxos: HashSet::default(),
             preview: false,
+            allow_low_fee: false,
         })
         .unwrap();
 
@@ -1061,6 +1398,249 @@ fn test_index_transaction_vtt_created_by_wallet() {
     assert_eq!(Some(a_block), block_after);
 }
 
+/// Fund a wallet with outputs of 1_000 and 5_000 nanoWits, and send 500 nanoWits paying a fee of
+/// 10 nanoWits out of the smaller one, which leaves a change of 490 nanoWits.
+fn wallet_with_pending_vtt() -> (Wallet<db::HashMapDb>, VTTransaction) {
+    let (wallet, _db) = factories::wallet(None);
+    let our_address = wallet.gen_external_address(None).unwrap();
+    wallet
+        .index_block_transactions(
+            &factories::BlockInfo::default().create(),
+            &[factories::vtt_from_body(VTTransactionBody::new(
+                vec![Input::default()],
+                vec![
+                    ValueTransferOutput {
+                        pkh: our_address.pkh,
+                        value: 1_000,
+                        time_lock: 0,
+                    },
+                    ValueTransferOutput {
+                        pkh: our_address.pkh,
+                        value: 5_000,
+                        time_lock: 0,
+                    },
+                ],
+            ))],
+            true,
+        )
+        .unwrap();
+
+    let (extended, _) = wallet
+        .create_vtt(types::VttParams {
+            fee: Fee::absolute_from_nanowits(10),
+            outputs: vec![ValueTransferOutput {
+                pkh: factories::pkh(),
+                value: 500,
+                time_lock: 0,
+            }],
+            labels: vec![],
+            utxo_strategy: UtxoSelectionStrategy::SmallFirst { from: None },
+            selected_utxos: HashSet::default(),
+            preview: false,
+            allow_low_fee: false,
+        })
+        .unwrap();
+    wallet.add_local_movement(&extended).unwrap();
+
+    match extended.transaction {
+        Transaction::ValueTransfer(vtt) => (wallet, vtt),
+        transaction => panic!("expected a value transfer, got: {:?}", transaction),
+    }
+}
+
+fn bumped_vtt(extended: &model::ExtendedTransaction) -> VTTransaction {
+    match &extended.transaction {
+        Transaction::ValueTransfer(vtt) => vtt.clone(),
+        transaction => panic!("expected a value transfer, got: {:?}", transaction),
+    }
+}
+
+#[test]
+fn test_bump_fee_replaces_pending_vtt() {
+    let (wallet, original) = wallet_with_pending_vtt();
+    let original_hash = original.hash();
+    assert_eq!(original.body.outputs[1].value, 490);
+
+    let (extended, fee) = wallet
+        .bump_fee(
+            &original_hash,
+            Fee::absolute_from_nanowits(100),
+            false,
+            false,
+        )
+        .unwrap();
+    let replacement = bumped_vtt(&extended);
+    let replacement_hash = replacement.hash();
+
+    // Same inputs and payment, the higher fee comes out of the change
+    assert_eq!(fee.as_nanowits(), 100);
+    assert_eq!(replacement.body.inputs, original.body.inputs);
+    assert_eq!(replacement.body.inputs.len(), replacement.signatures.len());
+    assert_eq!(replacement.body.outputs.len(), 2);
+    assert_eq!(replacement.body.outputs[0], original.body.outputs[0]);
+    assert_eq!(
+        replacement.body.outputs[1].pkh,
+        original.body.outputs[1].pkh
+    );
+    assert_eq!(replacement.body.outputs[1].value, 400);
+
+    wallet
+        .replace_local_movement(&original_hash, &extended)
+        .unwrap();
+    let movements = wallet.transactions(0, 10).unwrap().transactions;
+    let superseded = movements
+        .iter()
+        .find(|movement| movement.transaction.hash == original_hash.to_string())
+        .unwrap();
+    assert_eq!(
+        superseded.transaction.superseded_by,
+        Some(replacement_hash.to_string())
+    );
+    assert!(wallet.balance().unwrap().local > 0);
+
+    let err = wallet
+        .bump_fee(
+            &original_hash,
+            Fee::absolute_from_nanowits(200),
+            false,
+            false,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::TransactionSuperseded { ref replacement, .. } if *replacement == replacement_hash.to_string()),
+        "{:?}",
+        err
+    );
+
+    // Once the replacement is included in a block, the superseded original is gone
+    wallet
+        .index_block_transactions(
+            &factories::BlockInfo::default().create(),
+            &[Transaction::ValueTransfer(replacement)],
+            true,
+        )
+        .unwrap();
+    {
+        let state = wallet.state.read().unwrap();
+        assert!(state.local_movements.is_empty());
+        assert!(state.local_transactions.is_empty());
+        assert!(state.fee_bumps.is_empty());
+    }
+    assert_eq!(wallet.balance().unwrap().local, 0);
+
+    let err = wallet
+        .bump_fee(
+            &replacement_hash,
+            Fee::absolute_from_nanowits(200),
+            false,
+            false,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::TransactionAlreadyIncluded(ref hash) if *hash == replacement_hash.to_string()),
+        "{:?}",
+        err
+    );
+    let err = wallet
+        .bump_fee(
+            &Hash::default(),
+            Fee::absolute_from_nanowits(200),
+            false,
+            false,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(err, Error::UnknownLocalTransaction(_)),
+        "{:?}",
+        err
+    );
+}
+
+#[test]
+fn test_bump_fee_adds_inputs_only_if_allowed() {
+    let (wallet, original) = wallet_with_pending_vtt();
+    let original_hash = original.hash();
+
+    let err = wallet
+        .bump_fee(
+            &original_hash,
+            Fee::absolute_from_nanowits(10),
+            false,
+            false,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::FeeNotIncreased {
+                fee: 10,
+                previous: 10
+            }
+        ),
+        "{:?}",
+        err
+    );
+    let err = wallet
+        .bump_fee(
+            &original_hash,
+            Fee::absolute_from_nanowits(600),
+            false,
+            false,
+        )
+        .unwrap_err();
+    assert!(
+        matches!(
+            err,
+            Error::FeeBumpNeedsInputs {
+                change: 490,
+                increase: 590
+            }
+        ),
+        "{:?}",
+        err
+    );
+
+    let (extended, fee) = wallet
+        .bump_fee(
+            &original_hash,
+            Fee::absolute_from_nanowits(600),
+            true,
+            false,
+        )
+        .unwrap();
+    let replacement = bumped_vtt(&extended);
+    assert_eq!(fee.as_nanowits(), 600);
+    assert_eq!(replacement.body.inputs.len(), 2);
+    assert_eq!(replacement.body.inputs[0], original.body.inputs[0]);
+    assert_eq!(replacement.body.outputs[1].value, 1_000 + 5_000 - 500 - 600);
+    let added: model::OutPtr = replacement.body.inputs[1].output_pointer().into();
+    wallet
+        .replace_local_movement(&original_hash, &extended)
+        .unwrap();
+    assert!(wallet
+        .state
+        .read()
+        .unwrap()
+        .used_outputs
+        .contains_key(&added));
+
+    // If the original is included in a block after all, the replacement is discarded and the
+    // input that only the replacement spent can be used again
+    wallet
+        .index_block_transactions(
+            &factories::BlockInfo::default().create(),
+            &[Transaction::ValueTransfer(original)],
+            true,
+        )
+        .unwrap();
+    let state = wallet.state.read().unwrap();
+    assert!(state.local_movements.is_empty());
+    assert!(state.fee_bumps.is_empty());
+    assert!(!state.used_outputs.contains_key(&added));
+    assert!(state.utxo_set.contains_key(&added));
+    assert_eq!(state.balance.local, 0);
+}
+
 #[test]
 fn test_update_wallet_with_empty_values() {
     let (wallet, db) = factories::wallet(None);
@@ -1150,9 +1730,11 @@ fn test_get_transaction() {
                 value: 1,
                 time_lock: 0,
             }],
+            labels: vec![],
             utxo_strategy: UtxoSelectionStrategy::Random { from: None },
             selected_utxos: HashSet::default(),
             preview: false,
+            allow_low_fee: false,
         })
         .unwrap();
 
@@ -1178,16 +1760,85 @@ fn test_get_transaction() {
 }
 
 #[test]
-fn test_get_transactions() {
+fn test_output_labels_are_attached_to_persisted_movements() {
     let (wallet, _db) = factories::wallet(None);
-
-    let no_transactions = crate::model::WalletTransactions {
-        transactions: vec![],
-        total: 0,
-    };
-    assert_eq!(wallet.transactions(0, 0).unwrap(), no_transactions);
-    assert_eq!(wallet.transactions(0, 1).unwrap(), no_transactions);
-    assert_eq!(wallet.transactions(1, 0).unwrap(), no_transactions);
+    let our_address = wallet.gen_external_address(None).unwrap();
+    let (alice, bob) = (factories::pkh(), factories::pkh());
+    wallet
+        .index_block_transactions(
+            &factories::BlockInfo::default().create(),
+            &[vtt_from_body(VTTransactionBody::new(
+                vec![Input::default()],
+                vec![ValueTransferOutput {
+                    pkh: our_address.pkh,
+                    value: 10,
+                    time_lock: 0,
+                }],
+            ))],
+            true,
+        )
+        .unwrap();
+
+    let output = |pkh, value| ValueTransferOutput {
+        pkh,
+        value,
+        time_lock: 0,
+    };
+    let (extended, ..) = wallet
+        .create_vtt(types::VttParams {
+            fee: Fee::default(),
+            outputs: vec![output(alice, 3), output(bob, 2), output(alice, 1)],
+            labels: vec![Some("rent".to_string()), None, Some("tip".to_string())],
+            utxo_strategy: UtxoSelectionStrategy::Random { from: None },
+            selected_utxos: HashSet::default(),
+            preview: false,
+            allow_low_fee: false,
+        })
+        .unwrap();
+    let vtt = match extended.transaction {
+        Transaction::ValueTransfer(vtt) => vtt,
+        _ => panic!("expected a value transfer transaction"),
+    };
+    wallet
+        .index_block_transactions(
+            &factories::BlockInfo::default().create(),
+            &[vtt_from_body(vtt.body)],
+            true,
+        )
+        .unwrap();
+
+    // The movement is persisted without labels, which are attached again when reading it
+    let movement = wallet.get_transaction(0, 1).unwrap();
+    let outputs = match &movement.transaction.data {
+        model::TransactionData::ValueTransfer(data) => &data.outputs,
+        data => panic!("expected value transfer data, got {:?}", data),
+    };
+    let itemized = outputs
+        .iter()
+        .map(|output| (output.value, output.label.as_deref()))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        itemized,
+        vec![(3, Some("rent")), (2, None), (1, Some("tip")), (4, None)]
+    );
+    assert_eq!(
+        wallet.transactions(0, 10).unwrap().transactions[0],
+        movement
+    );
+}
+
+#[test]
+fn test_get_transactions() {
+    let (wallet, _db) = factories::wallet(None);
+
+    let no_transactions = crate::model::WalletTransactions {
+        transactions: vec![],
+        total: 0,
+        cursor: None,
+    };
+    assert_eq!(wallet.transactions(0, 0).unwrap(), no_transactions);
+    assert_eq!(wallet.transactions(0, 1).unwrap(), no_transactions);
+    assert_eq!(wallet.transactions(1, 0).unwrap(), no_transactions);
     assert_eq!(wallet.transactions(1, 1).unwrap(), no_transactions);
 
     let a_block = factories::BlockInfo::default().create();
@@ -1215,6 +1866,7 @@ fn test_get_transactions() {
     let no_transactions = crate::model::WalletTransactions {
         transactions: vec![],
         total: 1,
+        cursor: None,
     };
     assert_eq!(wallet.transactions(0, 0).unwrap(), no_transactions);
     let x = wallet.transactions(0, 1).unwrap();
@@ -1233,9 +1885,11 @@ fn test_get_transactions() {
                 value: 1,
                 time_lock: 0,
             }],
+            labels: vec![],
             utxo_strategy: UtxoSelectionStrategy::Random { from: None },
             selected_utxos: HashSet::default(),
             preview: false,
+            allow_low_fee: false,
         })
         .unwrap();
 
@@ -1315,9 +1969,11 @@ fn test_create_vtt_with_locked_balance() {
                 value: 1,
                 time_lock: 0,
             }],
+            labels: vec![],
             utxo_strategy: UtxoSelectionStrategy::Random { from: None },
             selected_utxos: HashSet::default(),
             preview: false,
+            allow_low_fee: false,
         })
         .unwrap_err();
 
@@ -1379,9 +2035,11 @@ fn test_create_vtt_with_multiple_outputs() {
                     time_lock: 0,
                 },
             ],
+            labels: vec![],
             utxo_strategy: UtxoSelectionStrategy::Random { from: None },
             selected_utxos: HashSet::default(),
             preview: false,
+            allow_low_fee: false,
         })
         .unwrap();
 
@@ -3005,13 +3663,2444 @@ fn test_create_transaction_components_does_not_use_unconfirmed_utxos_and_selecti
 
 #[test]
 fn test_unlock_wallet_backwards_compatible() {
-    // db created using:
-    // let (wallet, db) = factories::wallet(None);
-    // println!("{}", db.export_to_json().unwrap());
+    let db = factories::unversioned_wallet_db();
+    let id: model::WalletId = "example-wallet".parse().unwrap();
+    assert!(id.is_legacy());
+    let session_id = types::SessionId::from(id.to_string());
+    let params = factories::default_params();
+    let wallet = Wallet::unlock(&id, session_id, db, params).unwrap();
+    assert_eq!(wallet.id, id);
+}
+
+#[test]
+fn test_payment_request_partially_paid_then_paid() {
+    let (wallet, db) = factories::wallet(None);
+
+    let request = wallet
+        .create_payment_request(10, Some("invoice #1".to_string()), None, 1_000)
+        .unwrap();
+    assert_eq!(request.id, 0);
+    assert_eq!(request.status, model::PaymentRequestStatus::Pending);
+    assert_eq!(db.get(&keys::payment_request(0, 0)).unwrap(), request);
+    let pkh = PublicKeyHash::from_bech32(get_environment(), &request.address).unwrap();
+
+    let pay = |value| {
+        let body = VTTransactionBody::new(
+            vec![Input::default()],
+            vec![ValueTransferOutput {
+                pkh,
+                value,
+                time_lock: 0,
+            }],
+        );
+        let block = factories::BlockInfo::default().create();
+        wallet
+            .index_block_transactions(&block, &[vtt_from_body(body.clone())], true)
+            .unwrap();
+
+        body.hash().to_string()
+    };
+
+    let first_payment = pay(4);
+    let updates = wallet.take_payment_request_updates().unwrap();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].id, 0);
+    assert_eq!(
+        updates[0].status,
+        model::PaymentRequestStatus::PartiallyPaid
+    );
+    assert_eq!(updates[0].received_amount, 4);
+    assert_eq!(updates[0].payments, vec![first_payment.clone()]);
+
+    let second_payment = pay(6);
+    let updates = wallet.take_payment_request_updates().unwrap();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].status, model::PaymentRequestStatus::Paid);
+    assert_eq!(updates[0].received_amount, 10);
+    assert_eq!(updates[0].payments, vec![first_payment, second_payment]);
+    assert_eq!(db.get(&keys::payment_request(0, 0)).unwrap(), updates[0]);
+
+    // Paid requests are not tracked anymore
+    pay(1);
+    assert!(wallet.take_payment_request_updates().unwrap().is_empty());
+
+    let paid = wallet
+        .payment_requests(&[model::PaymentRequestStatus::Paid], 0, 10)
+        .unwrap();
+    assert_eq!(paid.total, 1);
+    assert_eq!(paid.payment_requests[0].received_amount, 10);
+}
+
+#[test]
+fn test_payment_request_is_only_paid_on_consolidation() {
+    let (wallet, _db) = factories::wallet(None);
+
+    let request = wallet.create_payment_request(10, None, None, 0).unwrap();
+    let pkh = PublicKeyHash::from_bech32(get_environment(), &request.address).unwrap();
+    let body = VTTransactionBody::new(
+        vec![Input::default()],
+        vec![ValueTransferOutput {
+            pkh,
+            value: 10,
+            time_lock: 0,
+        }],
+    );
+    let block = factories::BlockInfo::default().create();
+
+    wallet
+        .index_block_transactions(&block, &[vtt_from_body(body)], false)
+        .unwrap();
+    assert!(wallet.take_payment_request_updates().unwrap().is_empty());
+
+    wallet
+        .try_consolidate_block(&block.block_hash.to_string())
+        .unwrap();
+    let updates = wallet.take_payment_request_updates().unwrap();
+    assert_eq!(updates.len(), 1);
+    assert_eq!(updates[0].status, model::PaymentRequestStatus::Paid);
+}
+
+#[test]
+fn test_superblock_consolidation_can_stop_before_any_block() {
+    let (wallet, _db) = factories::wallet(None);
+    let blocks: Vec<_> = (0..3)
+        .map(|_| factories::BlockInfo::default().create())
+        .collect();
+    for block in &blocks {
+        wallet.index_block_transactions(block, &[], false).unwrap();
+    }
+    let block_hashes: Vec<_> = blocks
+        .iter()
+        .map(|block| block.block_hash.to_string())
+        .collect();
+
+    let mut checked = vec![];
+    let err = wallet
+        .handle_superblock(&block_hashes, |consolidated| {
+            checked.push(consolidated);
+            if consolidated == 2 {
+                Err(repository::Error::BlockConsolidation("stopped".to_string()))
+            } else {
+                Ok(())
+            }
+        })
+        .unwrap_err();
+    assert!(matches!(err, repository::Error::BlockConsolidation(_)));
+    assert_eq!(checked, vec![0, 1, 2]);
+
+    // The blocks before the one it stopped at are kept consolidated
+    let state = wallet.state.read().unwrap();
+    assert_eq!(
+        state.pending_blocks.keys().collect::<Vec<_>>(),
+        vec![&block_hashes[2]]
+    );
+    assert_eq!(state.last_confirmed.hash_prev_block, blocks[1].block_hash);
+}
+
+#[test]
+fn test_payment_request_address_reused_after_being_paid() {
+    let (wallet, db) = factories::wallet(None);
+
+    let request = wallet.create_payment_request(10, None, None, 0).unwrap();
+    let pkh = PublicKeyHash::from_bech32(get_environment(), &request.address).unwrap();
+    let pay = |value| {
+        let body = VTTransactionBody::new(
+            vec![Input::default()],
+            vec![ValueTransferOutput {
+                pkh,
+                value,
+                time_lock: 0,
+            }],
+        );
+        let block = factories::BlockInfo::default().create();
+        wallet
+            .index_block_transactions(&block, &[vtt_from_body(body.clone())], true)
+            .unwrap();
+
+        body.hash().to_string()
+    };
+
+    // Partial payments complete the request instead of reusing its address
+    pay(4);
+    assert!(wallet.take_address_reuses().unwrap().is_empty());
+    pay(6);
+    assert!(wallet.take_address_reuses().unwrap().is_empty());
+    let address = wallet
+        .get_address(0, constants::EXTERNAL_KEYCHAIN, 0)
+        .unwrap();
+    assert_eq!(address.address, request.address);
+    assert_eq!(address.reuse, None);
+
+    let reusing = pay(1);
+    let expected = model::AddressReuse {
+        address: request.address.clone(),
+        payment_request_id: Some(request.id),
+        previous_transaction: None,
+        transaction: reusing,
+    };
+    assert_eq!(
+        wallet.take_address_reuses().unwrap(),
+        vec![expected.clone()]
+    );
+    assert_eq!(wallet.take_payment_request_updates().unwrap().len(), 2);
+    assert_eq!(
+        db.get(&keys::address_reuse(0, constants::EXTERNAL_KEYCHAIN, 0))
+            .unwrap(),
+        expected
+    );
+    let address = wallet
+        .get_address(0, constants::EXTERNAL_KEYCHAIN, 0)
+        .unwrap();
+    assert_eq!(address.reuse, Some(expected));
+}
+
+#[test]
+fn test_payment_request_address_reused_after_expiring_is_reported_on_consolidation() {
+    let (wallet, _db) = factories::wallet(None);
+
+    let request = wallet
+        .create_payment_request(10, None, Some(60), 0)
+        .unwrap();
+    wallet.expire_payment_requests(60).unwrap();
+    let pkh = PublicKeyHash::from_bech32(get_environment(), &request.address).unwrap();
+    let body = VTTransactionBody::new(
+        vec![Input::default()],
+        vec![ValueTransferOutput {
+            pkh,
+            value: 10,
+            time_lock: 0,
+        }],
+    );
+    let block = factories::BlockInfo::default().create();
+
+    wallet
+        .index_block_transactions(&block, &[vtt_from_body(body.clone())], false)
+        .unwrap();
+    assert!(wallet.take_address_reuses().unwrap().is_empty());
+
+    wallet
+        .try_consolidate_block(&block.block_hash.to_string())
+        .unwrap();
+    let reuses = wallet.take_address_reuses().unwrap();
+    assert_eq!(reuses.len(), 1);
+    assert_eq!(reuses[0].payment_request_id, Some(request.id));
+    assert_eq!(reuses[0].transaction, body.hash().to_string());
+    // The address waiting in memory is flagged as well
+    let address = wallet
+        .get_address(0, constants::EXTERNAL_KEYCHAIN, 0)
+        .unwrap();
+    assert_eq!(address.reuse, Some(reuses[0].clone()));
+}
+
+#[test]
+fn test_payment_request_expiration() {
+    let (wallet, db) = factories::wallet(None);
+
+    let expiring = wallet
+        .create_payment_request(10, None, Some(60), 1_000)
+        .unwrap();
+    let not_expiring = wallet
+        .create_payment_request(10, None, None, 1_000)
+        .unwrap();
+    assert_eq!(expiring.expires_at, Some(1_060));
+    assert_ne!(expiring.address, not_expiring.address);
+
+    assert!(wallet.expire_payment_requests(1_059).unwrap().is_empty());
+
+    let expired = wallet.expire_payment_requests(1_060).unwrap();
+    assert_eq!(expired.len(), 1);
+    assert_eq!(expired[0].id, expiring.id);
+    assert_eq!(expired[0].status, model::PaymentRequestStatus::Expired);
+    assert_eq!(wallet.take_payment_request_updates().unwrap(), expired);
+    assert_eq!(db.get(&keys::payment_request(0, 0)).unwrap(), expired[0]);
+
+    // Expired requests are only expired once
+    assert!(wallet.expire_payment_requests(2_000).unwrap().is_empty());
+
+    let pending = wallet
+        .payment_requests(&[model::PaymentRequestStatus::Pending], 0, 10)
+        .unwrap();
+    assert_eq!(pending.total, 1);
+    assert_eq!(pending.payment_requests[0].id, not_expiring.id);
+    assert_eq!(wallet.payment_requests(&[], 0, 10).unwrap().total, 2);
+}
+
+#[test]
+fn test_index_block_transactions_records_transaction_context() {
+    use witnet_data_structures::chain::DataRequestInfo;
+    use witnet_rad::types::integer::RadonInteger;
+
+    let (wallet, _db) = factories::wallet(None);
+    let external = wallet.gen_external_address(None).unwrap();
+    let change = wallet.gen_internal_address(None, false).unwrap();
+    let sender = factories::pkh();
+    let recipient = factories::pkh();
+
+    // A first block funds the wallet
+    let funding = model::ExtendedTransaction {
+        metadata: Some(model::TransactionMetadata::InputValues(vec![
+            ValueTransferOutput {
+                pkh: sender,
+                value: 11,
+                time_lock: 0,
+            },
+        ])),
+        ..vtt_from_body(VTTransactionBody::new(
+            vec![Input::default()],
+            vec![ValueTransferOutput {
+                pkh: external.pkh,
+                value: 10,
+                time_lock: 0,
+            }],
+        ))
+    };
+    wallet
+        .index_block_transactions(
+            &factories::BlockInfo::default().create(),
+            &[funding.clone()],
+            true,
+        )
+        .unwrap();
+
+    // A second block with a send, a receive and a tally reward
+    let send = vtt_from_body(VTTransactionBody::new(
+        vec![Input::new(OutputPointer {
+            transaction_id: funding.transaction.hash(),
+            output_index: 0,
+        })],
+        vec![
+            ValueTransferOutput {
+                pkh: recipient,
+                value: 6,
+                time_lock: 0,
+            },
+            ValueTransferOutput {
+                pkh: change.pkh,
+                value: 3,
+                time_lock: 0,
+            },
+        ],
+    ));
+    let receive = model::ExtendedTransaction {
+        metadata: Some(model::TransactionMetadata::InputValues(vec![
+            ValueTransferOutput {
+                pkh: sender,
+                value: 5,
+                time_lock: 0,
+            },
+        ])),
+        ..vtt_from_body(VTTransactionBody::new(
+            vec![Input::default()],
+            vec![ValueTransferOutput {
+                pkh: external.pkh,
+                value: 4,
+                time_lock: 0,
+            }],
+        ))
+    };
+    let dr_pointer = factories::transaction_id();
+    let tally = model::ExtendedTransaction {
+        transaction: Transaction::Tally(TallyTransaction::new(
+            dr_pointer,
+            Vec::try_from(RadonTypes::from(RadonInteger::from(1))).unwrap(),
+            vec![ValueTransferOutput {
+                pkh: external.pkh,
+                value: 2,
+                time_lock: 0,
+            }],
+            vec![],
+            vec![],
+        )),
+        metadata: Some(model::TransactionMetadata::Tally(Box::new(
+            DataRequestInfo::default(),
+        ))),
+    };
+    let block = factories::BlockInfo::default().create();
+    wallet
+        .index_block_transactions(
+            &block,
+            &[send.clone(), receive.clone(), tally.clone()],
+            true,
+        )
+        .unwrap();
+    let timestamp = convert_block_epoch_to_timestamp(EpochConstants::default(), block.epoch);
+
+    let send_movement = wallet.get_transaction(0, 1).unwrap();
+    assert_eq!(send_movement.kind, model::MovementType::Negative);
+    assert_eq!(send_movement.amount, 7);
+    let txn = send_movement.transaction;
+    assert_eq!(txn.hash, send.transaction.hash().to_string());
+    assert!(matches!(txn.data, model::TransactionData::ValueTransfer(_)));
+    assert_eq!(txn.block, Some(block.clone()));
+    assert_eq!(txn.timestamp, timestamp);
+    assert_eq!(txn.fee_paid, Some(1));
+    assert_eq!(txn.counterparties, Some(vec![recipient.to_string()]));
+    assert_eq!(txn.data_request_hash, None);
+
+    let receive_movement = wallet.get_transaction(0, 2).unwrap();
+    assert_eq!(receive_movement.kind, model::MovementType::Positive);
+    assert_eq!(receive_movement.amount, 4);
+    let txn = receive_movement.transaction;
+    assert_eq!(txn.hash, receive.transaction.hash().to_string());
+    assert!(matches!(txn.data, model::TransactionData::ValueTransfer(_)));
+    assert_eq!(txn.block, Some(block.clone()));
+    assert_eq!(txn.timestamp, timestamp);
+    assert_eq!(txn.miner_fee, 1);
+    assert_eq!(txn.fee_paid, None);
+    assert_eq!(txn.counterparties, Some(vec![sender.to_string()]));
+    assert_eq!(txn.data_request_hash, None);
+
+    let tally_movement = wallet.get_transaction(0, 3).unwrap();
+    assert_eq!(tally_movement.kind, model::MovementType::Positive);
+    assert_eq!(tally_movement.amount, 2);
+    let txn = tally_movement.transaction;
+    assert_eq!(txn.hash, tally.transaction.hash().to_string());
+    assert!(matches!(txn.data, model::TransactionData::Tally(_)));
+    assert_eq!(txn.block, Some(block));
+    assert_eq!(txn.timestamp, timestamp);
+    assert_eq!(txn.fee_paid, None);
+    assert_eq!(txn.counterparties, Some(vec![]));
+    assert_eq!(txn.data_request_hash, Some(dr_pointer.to_string()));
+}
+
+#[test]
+fn test_get_transaction_persisted_without_transaction_context() {
+    let (wallet, db) = factories::wallet(None);
+    let block = factories::BlockInfo::default().create();
+    let legacy = model::LegacyBalanceMovement {
+        db_key: 0,
+        kind: model::MovementType::Positive,
+        amount: 1,
+        transaction: model::LegacyWalletTransaction {
+            block: Some(block.clone()),
+            confirmed: true,
+            data: model::TransactionData::Mint(model::MintData { outputs: vec![] }),
+            hash: factories::transaction_id().to_string(),
+            miner_fee: 0,
+            timestamp: 1,
+        },
+    };
+    db.put(&keys::legacy_transaction_movement(0, 0), &legacy)
+        .unwrap();
 
-    let db = HashMapDb::import_from_json("[[[101,120,97,109,112,108,101,45,119,97,108,108,101,116,105,118],[16,0,0,0,0,0,0,0,253,220,27,197,124,58,52,48,149,209,246,175,226,167,24,235]],[[100,101,102,97,117,108,116,45,97,99,99,111,117,110,116],[0,0,0,0]],[[98,105,114,116,104,45,100,97,116,101],[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]],[[97,99,99,111,117,110,116,45,48,45,48,45,107,101,121],[32,0,0,0,0,0,0,0,138,147,131,114,62,143,164,77,65,3,128,60,53,66,230,108,34,29,118,222,26,170,237,96,102,215,94,227,107,132,164,179,32,0,0,0,0,0,0,0,31,132,33,245,90,11,143,28,106,239,4,135,237,90,168,51,103,119,71,41,85,64,211,24,187,167,46,148,194,32,181,25]],[[109,97,115,116,101,114,45,107,101,121],[32,0,0,0,0,0,0,0,104,215,222,1,179,103,48,156,129,116,214,126,65,8,186,43,227,75,42,174,149,13,59,190,61,147,106,113,156,234,112,103,32,0,0,0,0,0,0,0,233,91,131,28,53,151,204,57,207,6,225,50,113,67,70,16,120,151,29,93,114,9,217,245,212,145,169,196,138,206,81,232]],[[97,99,99,111,117,110,116,45,48,45,49,45,107,101,121],[32,0,0,0,0,0,0,0,159,251,203,85,170,0,252,117,89,207,127,183,202,138,136,177,9,166,37,188,2,117,143,179,121,90,111,70,108,156,252,7,32,0,0,0,0,0,0,0,117,94,110,127,110,158,134,8,141,217,209,199,209,173,223,91,131,92,167,133,147,7,79,157,249,183,18,136,1,230,8,53]],[[108,97,115,116,45,115,121,110,99],[0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0,0]],[[119,97,108,108,101,116,115],[1,0,0,0,0,0,0,0,14,0,0,0,0,0,0,0,101,120,97,109,112,108,101,45,119,97,108,108,101,116]],[[101,120,97,109,112,108,101,45,119,97,108,108,101,116,115,97,108,116],[32,0,0,0,0,0,0,0,26,124,168,170,31,187,78,102,12,152,23,213,241,242,206,201,58,116,200,79,251,130,103,254,13,178,54,228,142,39,208,161]]]").unwrap();
-    let id = "example-wallet";
-    let session_id = types::SessionId::from(String::from(id));
+    let movement = wallet.get_transaction(0, 0).unwrap();
+
+    assert_eq!(movement, model::BalanceMovement::from(legacy));
+    assert_eq!(movement.transaction.block, Some(block));
+    assert_eq!(movement.transaction.fee_paid, None);
+    assert_eq!(movement.transaction.counterparties, None);
+    assert_eq!(movement.transaction.data_request_hash, None);
+}
+
+#[test]
+fn test_clear_chain_data_from_birth_date_or_genesis() {
+    let (wallet, db) = factories::wallet(None);
     let params = factories::default_params();
-    let _wallet = Wallet::unlock(id, session_id, db, params).unwrap();
+    let birth_date = CheckpointBeacon {
+        checkpoint: 1_000,
+        hash_prev_block: factories::transaction_id(),
+    };
+    db.put(&keys::birth_date(), birth_date).unwrap();
+    db.put(&keys::wallet_last_sync(), birth_date).unwrap();
+    let wallet = Wallet::unlock(&wallet.id, wallet.session_id.clone(), db.clone(), params).unwrap();
+
+    // A new wallet starts synchronizing at its birth date
+    assert_eq!(wallet.public_data().unwrap().last_confirmed, birth_date);
+
+    let synced = CheckpointBeacon {
+        checkpoint: 1_200,
+        hash_prev_block: factories::transaction_id(),
+    };
+    wallet.update_sync_state(synced, true).unwrap();
+
+    wallet
+        .clear_chain_data(types::ResyncFrom::BirthDate)
+        .unwrap();
+    let data = wallet.public_data().unwrap();
+    assert_eq!(data.last_sync, birth_date);
+    assert_eq!(data.last_confirmed, birth_date);
+    assert_eq!(db.get(&keys::wallet_last_sync()).unwrap(), birth_date);
+
+    wallet.update_sync_state(synced, true).unwrap();
+
+    // Resynchronizing from genesis does not change the birth date
+    wallet.clear_chain_data(types::ResyncFrom::Genesis).unwrap();
+    let genesis = CheckpointBeacon {
+        checkpoint: 0,
+        hash_prev_block: wallet.get_bootstrap_hash(),
+    };
+    let data = wallet.public_data().unwrap();
+    assert_eq!(data.last_sync, genesis);
+    assert_eq!(data.last_confirmed, genesis);
+    assert_eq!(data.birth_date, birth_date);
+    assert_eq!(db.get(&keys::wallet_last_sync()).unwrap(), genesis);
+}
+
+/// Fund a wallet with 100 nanowits and commit to a data request, using them as collateral with a
+/// change of 20 nanowits. Returns the data request hash and the address of the wallet.
+fn commit_collateral(wallet: &Wallet<HashMapDb>) -> (Hash, Arc<model::Address>) {
+    use witnet_data_structures::transaction::{CommitTransaction, CommitTransactionBody};
+
+    let external = wallet.gen_external_address(None).unwrap();
+    let funding = vtt_from_body(VTTransactionBody::new(
+        vec![Input::default()],
+        vec![ValueTransferOutput {
+            pkh: external.pkh,
+            value: 100,
+            time_lock: 0,
+        }],
+    ));
+    wallet
+        .index_block_transactions(
+            &factories::BlockInfo::default().create(),
+            &[funding.clone()],
+            true,
+        )
+        .unwrap();
+
+    let dr_pointer = factories::transaction_id();
+    let commit = model::ExtendedTransaction {
+        transaction: Transaction::Commit(CommitTransaction::new(
+            CommitTransactionBody::new(
+                dr_pointer,
+                Hash::default(),
+                Default::default(),
+                vec![Input::new(OutputPointer {
+                    transaction_id: funding.transaction.hash(),
+                    output_index: 0,
+                })],
+                vec![ValueTransferOutput {
+                    pkh: external.pkh,
+                    value: 20,
+                    time_lock: 0,
+                }],
+                None,
+            ),
+            vec![],
+        )),
+        metadata: None,
+    };
+    wallet
+        .index_block_transactions(&factories::BlockInfo::default().create(), &[commit], true)
+        .unwrap();
+
+    (dr_pointer, external)
+}
+
+fn tally_paying(dr_pointer: Hash, outputs: Vec<ValueTransferOutput>) -> model::ExtendedTransaction {
+    use witnet_data_structures::chain::DataRequestInfo;
+    use witnet_rad::types::integer::RadonInteger;
+
+    model::ExtendedTransaction {
+        transaction: Transaction::Tally(TallyTransaction::new(
+            dr_pointer,
+            Vec::try_from(RadonTypes::from(RadonInteger::from(1))).unwrap(),
+            outputs,
+            vec![],
+            vec![],
+        )),
+        metadata: Some(model::TransactionMetadata::Tally(Box::new(
+            DataRequestInfo::default(),
+        ))),
+    }
+}
+
+#[test]
+fn test_commit_reveal_tally_cycle() {
+    let (wallet, db) = factories::wallet(None);
+    let (dr_pointer, external) = commit_collateral(&wallet);
+
+    // The collateral is not spendable until the data request is resolved
+    let balance = wallet.balance().unwrap();
+    assert_eq!(balance.unconfirmed.available, 20);
+    assert_eq!(balance.collateralized, 80);
+    let commit_movement = wallet.get_transaction(0, 1).unwrap();
+    assert_eq!(commit_movement.kind, model::MovementType::Negative);
+    assert_eq!(commit_movement.amount, 80);
+    assert_eq!(
+        commit_movement.category,
+        Some(model::MovementCategory::CollateralLocked)
+    );
+    assert_eq!(
+        commit_movement.transaction.data_request_hash,
+        Some(dr_pointer.to_string())
+    );
+
+    // Collaterals survive unlocking the wallet again
+    let unlocked = Wallet::unlock(
+        &wallet.id,
+        wallet.session_id.clone(),
+        db.clone(),
+        factories::default_params(),
+    )
+    .unwrap();
+    assert_eq!(unlocked.balance().unwrap().collateralized, 80);
+
+    // Reveal transactions do not move funds, so the block revealing does not change the balance
+    wallet
+        .index_block_transactions(&factories::BlockInfo::default().create(), &[], true)
+        .unwrap();
+    assert_eq!(wallet.balance().unwrap().collateralized, 80);
+
+    // The tally returns the collateral together with the reward
+    let tally = tally_paying(
+        dr_pointer,
+        vec![
+            ValueTransferOutput {
+                pkh: external.pkh,
+                value: 85,
+                time_lock: 0,
+            },
+            ValueTransferOutput {
+                pkh: factories::pkh(),
+                value: 85,
+                time_lock: 0,
+            },
+        ],
+    );
+    wallet
+        .index_block_transactions(&factories::BlockInfo::default().create(), &[tally], true)
+        .unwrap();
+
+    let balance = wallet.balance().unwrap();
+    assert_eq!(balance.unconfirmed.available, 105);
+    assert_eq!(balance.confirmed.available, 105);
+    assert_eq!(balance.collateralized, 0);
+    let tally_movement = wallet.get_transaction(0, 2).unwrap();
+    assert_eq!(tally_movement.kind, model::MovementType::Positive);
+    assert_eq!(tally_movement.amount, 85);
+    assert_eq!(
+        tally_movement.category,
+        Some(model::MovementCategory::MiningReward)
+    );
+    assert_eq!(
+        tally_movement.transaction.data_request_hash,
+        Some(dr_pointer.to_string())
+    );
+    assert_eq!(
+        db.get(&keys::account_collaterals(0)).unwrap(),
+        model::Collaterals::default()
+    );
+}
+
+#[test]
+fn test_slashed_commit() {
+    let (wallet, _db) = factories::wallet(None);
+    let (dr_pointer, _external) = commit_collateral(&wallet);
+
+    // The tally only pays the other witnesses
+    let tally = tally_paying(
+        dr_pointer,
+        vec![ValueTransferOutput {
+            pkh: factories::pkh(),
+            value: 170,
+            time_lock: 0,
+        }],
+    );
+    wallet
+        .index_block_transactions(&factories::BlockInfo::default().create(), &[tally], true)
+        .unwrap();
+
+    let balance = wallet.balance().unwrap();
+    assert_eq!(balance.unconfirmed.available, 20);
+    assert_eq!(balance.collateralized, 0);
+    let lost_movement = wallet.get_transaction(0, 2).unwrap();
+    assert_eq!(lost_movement.kind, model::MovementType::Negative);
+    assert_eq!(lost_movement.amount, 0);
+    assert_eq!(
+        lost_movement.category,
+        Some(model::MovementCategory::CollateralLost)
+    );
+    assert_eq!(
+        lost_movement.transaction.data_request_hash,
+        Some(dr_pointer.to_string())
+    );
+
+    // The collateral is only lost once: the movements add up to the balance of the wallet
+    let net = wallet
+        .transactions(0, 10)
+        .unwrap()
+        .transactions
+        .iter()
+        .fold(0i128, |acc, movement| match movement.kind {
+            model::MovementType::Positive => acc + i128::from(movement.amount),
+            model::MovementType::Negative => acc - i128::from(movement.amount),
+        });
+    assert_eq!(net, 20);
+    assert_eq!(wallet.spent_since(0).unwrap(), 80);
+}
+
+#[test]
+fn test_tally_refund_without_collateral_is_not_categorized() {
+    let (wallet, _db) = factories::wallet(None);
+    let external = wallet.gen_external_address(None).unwrap();
+    let tally = tally_paying(
+        factories::transaction_id(),
+        vec![ValueTransferOutput {
+            pkh: external.pkh,
+            value: 3,
+            time_lock: 0,
+        }],
+    );
+    wallet
+        .index_block_transactions(&factories::BlockInfo::default().create(), &[tally], true)
+        .unwrap();
+
+    let movement = wallet.get_transaction(0, 0).unwrap();
+    assert_eq!(movement.kind, model::MovementType::Positive);
+    assert_eq!(movement.category, None);
+}
+
+#[test]
+fn test_get_transaction_persisted_without_category() {
+    let (wallet, db) = factories::wallet(None);
+    let uncategorized = model::UncategorizedBalanceMovement {
+        db_key: 0,
+        kind: model::MovementType::Positive,
+        amount: 1,
+        transaction: model::WalletTransaction {
+            block: Some(factories::BlockInfo::default().create()),
+            confirmed: true,
+            data: model::TransactionData::Mint(model::MintData { outputs: vec![] }),
+            hash: factories::transaction_id().to_string(),
+            miner_fee: 0,
+            timestamp: 1,
+            fee_paid: None,
+            counterparties: Some(vec![]),
+            data_request_hash: None,
+            pruned: false,
+            superseded_by: None,
+        },
+    };
+    db.put(
+        &keys::uncategorized_transaction_movement(0, 0),
+        &uncategorized,
+    )
+    .unwrap();
+
+    let movement = wallet.get_transaction(0, 0).unwrap();
+
+    assert_eq!(movement, model::BalanceMovement::from(uncategorized));
+    assert_eq!(movement.transaction.counterparties, Some(vec![]));
+    assert_eq!(movement.category, None);
+}
+
+#[test]
+fn test_check_seed_backup() {
+    let (wallet, _db) = factories::wallet_with_seed_backup(
+        "legal winner thank year wave sausage worth useful legal winner thank yellow",
+    );
+    let words = |words: &[(usize, &str)]| -> Vec<(usize, types::Password)> {
+        words
+            .iter()
+            .map(|(position, word)| (*position, types::Password::new(*word)))
+            .collect()
+    };
+
+    assert!(wallet.is_pending_confirmation().unwrap());
+    assert!(wallet
+        .check_seed_backup(&words(&[(1, "legal"), (4, "year"), (12, "yellow")]))
+        .unwrap());
+    assert!(!wallet
+        .check_seed_backup(&words(&[(1, "legal"), (4, "year"), (12, "thank")]))
+        .unwrap());
+    // A few right words are not enough, nor repeating the same one
+    assert!(!wallet.check_seed_backup(&words(&[(1, "legal")])).unwrap());
+    assert!(!wallet
+        .check_seed_backup(&words(&[(1, "legal"), (4, "year")]))
+        .unwrap());
+    assert!(!wallet
+        .check_seed_backup(&words(&[(1, "legal"), (1, "legal"), (1, "legal")]))
+        .unwrap());
+    assert!(!wallet.check_seed_backup(&words(&[(13, "legal")])).unwrap());
+    assert!(!wallet.check_seed_backup(&words(&[(0, "legal")])).unwrap());
+    assert!(!wallet.check_seed_backup(&words(&[])).unwrap());
+
+    wallet.forget_seed_backup().unwrap();
+
+    assert!(!wallet.is_pending_confirmation().unwrap());
+    assert!(!wallet
+        .check_seed_backup(&words(&[(1, "legal"), (4, "year"), (12, "yellow")]))
+        .unwrap());
+}
+
+#[test]
+fn test_wallet_without_seed_backup_is_not_pending_confirmation() {
+    let (wallet, _db) = factories::wallet(None);
+
+    assert!(!wallet.is_pending_confirmation().unwrap());
+}
+
+#[test]
+fn test_sign_data_in_different_domains() {
+    let (wallet, _db) = factories::wallet(None);
+    let data = "I own this wallet";
+
+    let proof = wallet
+        .sign_data(data, Some(model::SigningDomain::ProofOfOwnership), false)
+        .unwrap();
+    let message = wallet
+        .sign_data(data, Some(model::SigningDomain::ArbitraryMessage), false)
+        .unwrap();
+    let raw = wallet.sign_data(data, None, false).unwrap();
+
+    assert_eq!(proof.signature.public_key, message.signature.public_key);
+    assert_ne!(proof.signature.signature, message.signature.signature);
+    assert_ne!(proof.signature.signature, raw.signature.signature);
+    assert_eq!(
+        proof.digest,
+        hex::encode(model::signing_digest(
+            Some(model::SigningDomain::ProofOfOwnership),
+            data.as_bytes()
+        ))
+    );
+    assert_ne!(proof.digest, message.digest);
+    // Raw signatures are made over the plain SHA256 of the data
+    assert_eq!(
+        raw.digest,
+        hex::encode(witnet_crypto::hash::calculate_sha256(data.as_bytes()))
+    );
+    assert_eq!(raw.domain, None);
+}
+
+#[test]
+fn test_spending_check_counts_outgoing_movements_in_a_rolling_window() {
+    let (wallet, db) = factories::wallet(None);
+    let address = wallet.gen_external_address(None).unwrap();
+    let block = factories::BlockInfo::default().create();
+
+    // txn1 gives two credits of 3 and 7 to our pkh, and txn2 sends the first one away
+    let txn1 = VTTransactionBody::new(
+        vec![Input::default()],
+        vec![
+            ValueTransferOutput {
+                pkh: address.pkh,
+                value: 3,
+                time_lock: 0,
+            },
+            ValueTransferOutput {
+                pkh: address.pkh,
+                value: 7,
+                time_lock: 0,
+            },
+        ],
+    );
+    let spend = |output_index, value| {
+        VTTransactionBody::new(
+            vec![Input::new(OutputPointer {
+                transaction_id: txn1.hash(),
+                output_index,
+            })],
+            vec![ValueTransferOutput {
+                pkh: factories::pkh(),
+                value,
+                time_lock: 0,
+            }],
+        )
+    };
+    let txn2 = spend(0, 3);
+    let txn3 = vtt_from_body(spend(1, 7)).transaction;
+
+    wallet
+        .index_block_transactions(&block, &[vtt_from_body(txn1.clone())], true)
+        .unwrap();
+    wallet
+        .index_block_transactions(&block, &[vtt_from_body(txn2)], true)
+        .unwrap();
+    let sent_at = wallet.transactions(0, 1).unwrap().transactions[0]
+        .transaction
+        .timestamp;
+
+    let policy = model::WalletPolicy {
+        daily_spend_limit: Some(5),
+        confirmation_threshold: Some(6),
+    };
+    wallet.set_policy(policy.clone()).unwrap();
+    assert_eq!(policy, db.get(&keys::wallet_policy()).unwrap());
+
+    let check = wallet.spending_check(&txn3, sent_at).unwrap();
+    assert_eq!(check.outgoing, 7);
+    assert_eq!(check.spent, 3);
+    assert_eq!(check.remaining_allowance, Some(2));
+    assert!(check.requires_confirmation);
+    assert!(!check.within_limit());
+
+    // Movements count for a whole day after they happen
+    let day = constants::SPEND_LIMIT_WINDOW_SECS;
+    let check = wallet.spending_check(&txn3, sent_at + day).unwrap();
+    assert_eq!(check.spent, 3);
+    let check = wallet.spending_check(&txn3, sent_at + day + 1).unwrap();
+    assert_eq!(check.spent, 0);
+    assert_eq!(check.remaining_allowance, Some(5));
+
+    wallet
+        .set_policy(model::WalletPolicy {
+            daily_spend_limit: Some(10),
+            confirmation_threshold: None,
+        })
+        .unwrap();
+    let check = wallet.spending_check(&txn3, sent_at).unwrap();
+    assert!(check.within_limit());
+    assert!(!check.requires_confirmation);
+}
+
+/// A wallet with a few addresses and a confirmed movement, as found in a healthy database.
+fn wallet_with_history() -> (
+    Wallet<db::HashMapDb>,
+    db::HashMapDb,
+    Arc<model::Address>,
+    Hash,
+) {
+    let (wallet, db) = factories::wallet(None);
+    let address = wallet.gen_external_address(None).unwrap();
+    wallet.gen_external_address(None).unwrap();
+    wallet.gen_internal_address(None, false).unwrap();
+    let body = VTTransactionBody::new(
+        vec![Input::default()],
+        vec![ValueTransferOutput {
+            pkh: address.pkh,
+            value: 3,
+            time_lock: 0,
+        }],
+    );
+    let hash = body.hash();
+    wallet
+        .index_block_transactions(
+            &factories::BlockInfo::default().create(),
+            &[vtt_from_body(body)],
+            true,
+        )
+        .unwrap();
+
+    (wallet, db, address, hash)
+}
+
+/// Unlock a wallet again from its database, as after restarting.
+fn reopen(db: &db::HashMapDb) -> Wallet<db::HashMapDb> {
+    let session_id = types::SessionId::from(String::from("example-wallet"));
+
+    Wallet::unlock(
+        &"example-wallet".parse().unwrap(),
+        session_id,
+        db.clone(),
+        factories::default_params(),
+    )
+    .unwrap()
+}
+
+fn db_contents(db: &db::HashMapDb) -> HashMap<Vec<u8>, Vec<u8>> {
+    let contents: Vec<(Vec<u8>, Vec<u8>)> =
+        serde_json::from_str(&db.export_to_json().unwrap()).unwrap();
+
+    contents.into_iter().collect()
+}
+
+fn issues(
+    report: &model::IntegrityReport,
+) -> Vec<(model::IntegrityIssue, model::IntegrityResolution)> {
+    report
+        .findings
+        .iter()
+        .map(|finding| (finding.issue.clone(), finding.resolution))
+        .collect()
+}
+
+#[test]
+fn test_check_integrity_of_healthy_wallet() {
+    let (wallet, db, _address, _hash) = wallet_with_history();
+    let contents = db_contents(&db);
+    let mut updates = vec![];
+
+    let report = wallet
+        .check_integrity(true, |checked, total| updates.push((checked, total)))
+        .unwrap();
+
+    assert_eq!(report, model::IntegrityReport::default());
+    assert!(!report.needs_resync());
+    // 3 addresses, 1 movement and 1 unspent output
+    assert_eq!(updates, vec![(5, 5)]);
+    assert_eq!(db_contents(&db), contents);
+}
+
+#[test]
+fn test_check_integrity_advances_address_index() {
+    let (_wallet, db, _address, _hash) = wallet_with_history();
+    let keychain = constants::EXTERNAL_KEYCHAIN;
+    db.put(&keys::account_next_index(0, keychain), 1).unwrap();
+    let wallet = reopen(&db);
+    let expected = model::IntegrityIssue::AddressIndexBehind {
+        keychain,
+        next_index: 1,
+        persisted_until: 2,
+    };
+
+    let report = wallet.check_integrity(false, |_, _| {}).unwrap();
+    assert_eq!(
+        issues(&report),
+        vec![(expected.clone(), model::IntegrityResolution::Repairable)]
+    );
+    assert_eq!(1, db.get(&keys::account_next_index(0, keychain)).unwrap());
+
+    let report = wallet.check_integrity(true, |_, _| {}).unwrap();
+    assert_eq!(
+        issues(&report),
+        vec![(expected, model::IntegrityResolution::Repaired)]
+    );
+    assert_eq!(2, db.get(&keys::account_next_index(0, keychain)).unwrap());
+    // The next address is not derived again
+    assert_eq!(2, wallet.gen_external_address(None).unwrap().index);
+    assert!(wallet
+        .check_integrity(false, |_, _| {})
+        .unwrap()
+        .findings
+        .is_empty());
+}
+
+#[test]
+fn test_check_integrity_recomputes_balance() {
+    let (_wallet, db, _address, _hash) = wallet_with_history();
+    db.put(
+        &keys::account_balance(0),
+        model::BalanceInfo {
+            available: 1,
+            locked: 0,
+        },
+    )
+    .unwrap();
+    let wallet = reopen(&db);
+    let contents = db_contents(&db);
+
+    let report = wallet.check_integrity(true, |_, _| {}).unwrap();
+
+    assert_eq!(
+        issues(&report),
+        vec![(
+            model::IntegrityIssue::BalanceMismatch {
+                persisted: 1,
+                utxo_set: 3,
+            },
+            model::IntegrityResolution::Repaired
+        )]
+    );
+    let balance = model::BalanceInfo {
+        available: 3,
+        locked: 0,
+    };
+    assert_eq!(balance, db.get(&keys::account_balance(0)).unwrap());
+    assert_eq!(balance, wallet.balance().unwrap().confirmed);
+    assert_eq!(balance, wallet.balance().unwrap().unconfirmed);
+    // Nothing else is written
+    let mut repaired = db_contents(&db);
+    repaired.remove(keys::account_balance(0).as_ref());
+    let mut contents = contents;
+    contents.remove(keys::account_balance(0).as_ref());
+    assert_eq!(repaired, contents);
+}
+
+#[test]
+fn test_check_integrity_restores_index_entries() {
+    let (wallet, db, address, hash) = wallet_with_history();
+    let mut batch = db.batch();
+    batch.delete(&keys::pkh(&address.pkh)).unwrap();
+    batch
+        .put(&keys::transactions_index(hash.as_ref()), 7u32)
+        .unwrap();
+    db.write(batch).unwrap();
+
+    let report = wallet.check_integrity(true, |_, _| {}).unwrap();
+
+    assert_eq!(
+        issues(&report),
+        vec![
+            (
+                model::IntegrityIssue::BrokenAddressIndexEntry {
+                    keychain: constants::EXTERNAL_KEYCHAIN,
+                    index: 0,
+                },
+                model::IntegrityResolution::Repaired
+            ),
+            (
+                model::IntegrityIssue::BrokenTransactionIndexEntry {
+                    id: 0,
+                    transaction_hash: hash.to_string(),
+                },
+                model::IntegrityResolution::Repaired
+            ),
+        ]
+    );
+    let path = db.get(&keys::pkh(&address.pkh)).unwrap();
+    assert_eq!((0, 0, 0), (path.account, path.keychain, path.index));
+    assert_eq!(0, db.get(&keys::transactions_index(hash.as_ref())).unwrap());
+}
+
+#[test]
+fn test_check_integrity_advances_transaction_id() {
+    let (_wallet, db, _address, _hash) = wallet_with_history();
+    db.put(&keys::transaction_next_id(0), 0).unwrap();
+    let wallet = reopen(&db);
+
+    let report = wallet.check_integrity(true, |_, _| {}).unwrap();
+
+    assert_eq!(
+        issues(&report),
+        vec![(
+            model::IntegrityIssue::TransactionIdBehind {
+                next_id: 0,
+                persisted_until: 1,
+            },
+            model::IntegrityResolution::Repaired
+        )]
+    );
+    assert_eq!(1, db.get(&keys::transaction_next_id(0)).unwrap());
+    assert_eq!(1, wallet.state.read().unwrap().transaction_next_id);
+}
+
+#[test]
+fn test_check_integrity_flags_missing_records_for_resync() {
+    let (wallet, db, _address, hash) = wallet_with_history();
+    let mut batch = db.batch();
+    batch.delete(&keys::transaction_movement(0, 0)).unwrap();
+    batch
+        .delete(&keys::address_pkh(0, constants::INTERNAL_KEYCHAIN, 0))
+        .unwrap();
+    db.write(batch).unwrap();
+    let contents = db_contents(&db);
+
+    let report = wallet.check_integrity(true, |_, _| {}).unwrap();
+
+    assert_eq!(
+        issues(&report),
+        vec![
+            (
+                model::IntegrityIssue::MissingAddress {
+                    keychain: constants::INTERNAL_KEYCHAIN,
+                    index: 0,
+                },
+                model::IntegrityResolution::NeedsResync
+            ),
+            (
+                model::IntegrityIssue::MissingMovement { id: 0 },
+                model::IntegrityResolution::NeedsResync
+            ),
+            (
+                model::IntegrityIssue::UtxoWithoutMovement {
+                    output_pointer: format!("{}:0", hash),
+                },
+                model::IntegrityResolution::NeedsResync
+            ),
+        ]
+    );
+    assert!(report.needs_resync());
+    assert_eq!(db_contents(&db), contents);
+}
+
+#[test]
+fn test_check_integrity_releases_stale_reservations() {
+    let (wallet, _db, _address, _hash) = wallet_with_history();
+    let stale = model::OutPtr {
+        txn_hash: vec![0; 32],
+        output_index: 1,
+    };
+    wallet
+        .state
+        .write()
+        .unwrap()
+        .used_outputs
+        .insert(stale.clone(), 0);
+
+    let report = wallet.check_integrity(false, |_, _| {}).unwrap();
+    assert_eq!(
+        issues(&report),
+        vec![(
+            model::IntegrityIssue::StaleReservation {
+                output_pointer: stale.to_string(),
+            },
+            model::IntegrityResolution::Repairable
+        )]
+    );
+    assert!(wallet
+        .state
+        .read()
+        .unwrap()
+        .used_outputs
+        .contains_key(&stale));
+
+    wallet.check_integrity(true, |_, _| {}).unwrap();
+    assert!(wallet.state.read().unwrap().used_outputs.is_empty());
+}
+
+/// Deriver of addresses that counts how many of them it derives.
+struct CountingDeriver(Arc<std::sync::atomic::AtomicUsize>);
+
+impl Deriver for CountingDeriver {
+    fn derive_pkh(&self, parent_key: &ExtendedSK, index: u32) -> Result<PublicKeyHash> {
+        self.0.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+
+        Bip32Deriver.derive_pkh(parent_key, index)
+    }
+}
+
+/// Unlock a wallet again with a deriver that counts the addresses derived from then on.
+fn unlock_counting_derivations(
+    wallet: Wallet<HashMapDb>,
+    db: HashMapDb,
+) -> (Wallet<HashMapDb>, Arc<std::sync::atomic::AtomicUsize>) {
+    let derivations = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+    let wallet = Wallet::unlock_with_deriver(
+        &wallet.id,
+        wallet.session_id.clone(),
+        db,
+        factories::default_params(),
+        Arc::new(CountingDeriver(derivations.clone())),
+    )
+    .unwrap();
+
+    (wallet, derivations)
+}
+
+fn payments_to(pkhs: &[PublicKeyHash]) -> model::ExtendedTransaction {
+    let outputs = pkhs
+        .iter()
+        .map(|pkh| ValueTransferOutput {
+            pkh: *pkh,
+            value: 1,
+            time_lock: 0,
+        })
+        .collect();
+
+    vtt_from_body(VTTransactionBody::new(vec![Input::default()], outputs))
+}
+
+#[test]
+fn test_address_index_block_processing_derives_no_addresses() {
+    let (wallet, db) = factories::wallet(None);
+    let external = (0..3)
+        .map(|_| wallet.gen_external_address(None).unwrap().pkh)
+        .collect_vec();
+    let internal = wallet.gen_internal_address(None, false).unwrap().pkh;
+    let (wallet, derivations) = unlock_counting_derivations(wallet, db);
+
+    let usage = wallet.address_index_usage().unwrap();
+    assert_eq!(usage.addresses, 4);
+    assert!(usage.complete);
+    assert!(usage.approx_bytes > 0);
+
+    let foreign = PublicKeyHash::from_bytes(&[0xFF; 20]).unwrap();
+    let txns = vec![
+        payments_to(&[external[2], internal, foreign]),
+        payments_to(&[foreign]),
+    ];
+    let filtered = wallet
+        .filter_wallet_transactions(txns.iter().map(|txn| txn.transaction.clone()))
+        .unwrap();
+    assert_eq!(filtered, vec![txns[0].transaction.clone()]);
+
+    let block = factories::BlockInfo::default().create();
+    wallet
+        .index_block_transactions(&block, &txns[..1], true)
+        .unwrap();
+
+    assert_eq!(wallet.balance().unwrap().confirmed.available, 2);
+    assert_eq!(derivations.load(std::sync::atomic::Ordering::SeqCst), 0);
+}
+
+#[test]
+fn test_address_index_matches_new_address_in_next_block() {
+    let (wallet, db) = factories::wallet(None);
+    let (wallet, derivations) = unlock_counting_derivations(wallet, db);
+    assert_eq!(wallet.address_index_usage().unwrap().addresses, 0);
+
+    let address = wallet.gen_external_address(None).unwrap();
+    assert_eq!(derivations.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    let txns = vec![payments_to(&[address.pkh])];
+    let filtered = wallet
+        .filter_wallet_transactions(txns.iter().map(|txn| txn.transaction.clone()))
+        .unwrap();
+    assert_eq!(filtered.len(), 1);
+
+    let block = factories::BlockInfo::default().create();
+    wallet
+        .index_block_transactions(&block, &txns, true)
+        .unwrap();
+
+    assert_eq!(wallet.balance().unwrap().confirmed.available, 1);
+    assert_eq!(derivations.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+    // Resynchronizing forgets the addresses until they are generated again
+    wallet.clear_chain_data(types::ResyncFrom::Genesis).unwrap();
+    assert_eq!(wallet.address_index_usage().unwrap().addresses, 0);
+}
+
+fn block_with(txns: &[model::ExtendedTransaction]) -> Block {
+    let value_transfer_txns = txns
+        .iter()
+        .map(|txn| match &txn.transaction {
+            Transaction::ValueTransfer(vtt) => vtt.clone(),
+            _ => unreachable!("Only value transfers are put in test blocks"),
+        })
+        .collect();
+
+    Block::new(
+        Default::default(),
+        Default::default(),
+        witnet_data_structures::chain::BlockTransactions {
+            value_transfer_txns,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn test_block_scan_involves_wallet_only_in_its_outputs_and_utxos() {
+    let (wallet, _db) = factories::wallet(None);
+    let address = wallet.gen_external_address(None).unwrap();
+    let foreign = PublicKeyHash::from_bytes(&[0xFF; 20]).unwrap();
+
+    let unrelated = block_with(&[payments_to(&[foreign]), payments_to(&[foreign])]);
+    assert!(!wallet.is_involved_in(&BlockScan::new(&unrelated)).unwrap());
+
+    let paying = block_with(&[payments_to(&[address.pkh])]);
+    assert!(wallet.is_involved_in(&BlockScan::new(&paying)).unwrap());
+    let payment = payments_to(&[address.pkh]);
+    let payment_hash = payment.transaction.hash();
+    wallet
+        .index_block_transactions(&factories::BlockInfo::default().create(), &[payment], true)
+        .unwrap();
+
+    // Spending the output of the payment involves the wallet, even if nothing is paid to it
+    let spend = vtt_from_body(VTTransactionBody::new(
+        vec![Input::new(OutputPointer {
+            transaction_id: payment_hash,
+            output_index: 0,
+        })],
+        vec![ValueTransferOutput {
+            pkh: foreign,
+            value: 1,
+            time_lock: 0,
+        }],
+    ));
+    assert!(wallet
+        .is_involved_in(&BlockScan::new(&block_with(&[spend])))
+        .unwrap());
+    assert!(!wallet.is_involved_in(&BlockScan::new(&unrelated)).unwrap());
+}
+
+fn balance_history_params(granularity: u32, daily_after_days: u64) -> params::Params {
+    params::Params {
+        balance_history_granularity: granularity,
+        balance_history_daily_after: daily_after_days * 24 * 60 * 60,
+        ..factories::default_params()
+    }
+}
+
+fn index_payment(
+    wallet: &Wallet<HashMapDb>,
+    pkh: PublicKeyHash,
+    value: u64,
+    epoch: u32,
+    confirmed: bool,
+) {
+    let payment = vtt_from_body(VTTransactionBody::new(
+        vec![Input::default()],
+        vec![ValueTransferOutput {
+            pkh,
+            value,
+            time_lock: 0,
+        }],
+    ));
+    let block = model::Beacon {
+        epoch,
+        block_hash: factories::transaction_id(),
+    };
+
+    wallet
+        .index_block_transactions(&block, &[payment], confirmed)
+        .unwrap();
+}
+
+fn history_points(history: &[model::BalanceHistoryEntry]) -> Vec<(u32, u64, i64)> {
+    history
+        .iter()
+        .map(|entry| (entry.epoch, entry.confirmed, entry.pending_delta))
+        .collect()
+}
+
+#[test]
+fn test_balance_history_is_downsampled_and_queried_at_resolutions() {
+    // Default epoch constants make epochs last one second
+    const HOUR: u32 = 60 * 60;
+    const DAY: u32 = 24 * HOUR;
+
+    let (wallet, db) = factories::wallet_with_params(None, balance_history_params(1, 2));
+    let address = wallet.gen_external_address(None).unwrap();
+
+    let epochs = [
+        100,
+        200,
+        3 * DAY + 100,
+        3 * DAY + 200,
+        3 * DAY + 2 * HOUR,
+        5 * DAY + 10,
+        5 * DAY + 20,
+    ];
+    for (value, epoch) in (1..).zip(epochs) {
+        index_payment(&wallet, address.pkh, value, epoch, true);
+    }
+
+    // Entries older than 2 days are kept one per day, and entries older than a day one per hour
+    let expected = vec![
+        (200, 3, 0),
+        (3 * DAY + 200, 10, 0),
+        (3 * DAY + 2 * HOUR, 15, 0),
+        (5 * DAY + 10, 21, 0),
+        (5 * DAY + 20, 28, 0),
+    ];
+    let history = wallet
+        .balance_history(None, None, model::BalanceHistoryResolution::Block)
+        .unwrap();
+    assert_eq!(history_points(&history), expected);
+    assert_eq!(db.get(&keys::account_balance_history(0)).unwrap(), history);
+
+    let daily = wallet
+        .balance_history(None, None, model::BalanceHistoryResolution::Daily)
+        .unwrap();
+    assert_eq!(
+        history_points(&daily),
+        vec![
+            (200, 3, 0),
+            (3 * DAY + 2 * HOUR, 15, 0),
+            (5 * DAY + 20, 28, 0)
+        ]
+    );
+
+    let hourly = wallet
+        .balance_history(
+            Some(3 * DAY),
+            Some(5 * DAY + 20),
+            model::BalanceHistoryResolution::Hourly,
+        )
+        .unwrap();
+    assert_eq!(
+        history_points(&hourly),
+        vec![
+            (3 * DAY + 200, 10, 0),
+            (3 * DAY + 2 * HOUR, 15, 0),
+            (5 * DAY + 20, 28, 0)
+        ]
+    );
+
+    // Blocks pending confirmation are only reported along with the change to the balance
+    index_payment(&wallet, address.pkh, 8, 5 * DAY + 30, false);
+    let history = wallet
+        .balance_history(
+            Some(5 * DAY + 20),
+            None,
+            model::BalanceHistoryResolution::Block,
+        )
+        .unwrap();
+    assert_eq!(
+        history_points(&history),
+        vec![(5 * DAY + 20, 28, 0), (5 * DAY + 30, 28, 8)]
+    );
+
+    // Rolling back the pending blocks truncates the history to the last confirmed block
+    wallet.clear_pending_state().unwrap();
+    let history = wallet
+        .balance_history(None, None, model::BalanceHistoryResolution::Block)
+        .unwrap();
+    assert_eq!(history_points(&history), expected);
+
+    // Resynchronizing rebuilds the history from scratch
+    wallet.clear_chain_data(types::ResyncFrom::Genesis).unwrap();
+    assert!(wallet
+        .balance_history(None, None, model::BalanceHistoryResolution::Block)
+        .unwrap()
+        .is_empty());
+    assert!(db
+        .get(&keys::account_balance_history(0))
+        .unwrap()
+        .is_empty());
+}
+
+#[test]
+fn test_balance_history_groups_epochs_and_skips_unchanged_balances() {
+    let (wallet, _db) = factories::wallet_with_params(None, balance_history_params(10, 30));
+    let address = wallet.gen_external_address(None).unwrap();
+    let foreign = PublicKeyHash::from_bytes(&[0xFF; 20]).unwrap();
+
+    index_payment(&wallet, address.pkh, 1, 100, true);
+    index_payment(&wallet, address.pkh, 2, 105, true);
+    index_payment(&wallet, foreign, 3, 110, true);
+    index_payment(&wallet, address.pkh, 4, 120, true);
+
+    let history = wallet
+        .balance_history(None, None, model::BalanceHistoryResolution::Block)
+        .unwrap();
+    assert_eq!(history_points(&history), vec![(105, 3, 0), (120, 7, 0)]);
+}
+
+#[test]
+fn test_prune_movements_keeps_recent_metadata_and_archives_the_rest() {
+    let archive_dir =
+        std::env::temp_dir().join(format!("witnet-wallet-prune-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&archive_dir);
+    let (wallet, db) = factories::wallet(None);
+    let address = wallet.gen_external_address(None).unwrap();
+    for (value, epoch) in [(1, 100), (2, 200), (3, 300), (4, 1000)] {
+        index_payment(&wallet, address.pkh, value, epoch, true);
+    }
+    let old_hash = wallet.get_transaction(0, 0).unwrap().transaction.hash;
+    db.put(
+        &keys::transaction_output_labels(&old_hash),
+        vec![Some("rent".to_string())],
+    )
+    .unwrap();
+
+    // Nothing is pruned without a retention policy
+    let report = wallet.prune_movements(&archive_dir).unwrap();
+    assert_eq!((report.pruned, report.archive_path), (0, None));
+
+    // Movements of blocks more than 750 epochs older than the last confirmed one are pruned
+    let policy = model::RetentionPolicy {
+        keep_epochs: Some(750),
+    };
+    wallet.set_retention_policy(policy).unwrap();
+    let report = wallet.prune_movements(&archive_dir).unwrap();
+    assert_eq!(report.policy, policy);
+    assert_eq!(report.pruned, 2);
+    for id in 0..2 {
+        assert!(!db.contains(&keys::transaction_movement(0, id)).unwrap());
+        assert!(db
+            .contains(&keys::transaction_pruned_movement(0, id))
+            .unwrap());
+    }
+    for id in 2..4 {
+        assert!(db.contains(&keys::transaction_movement(0, id)).unwrap());
+    }
+    assert_eq!(
+        db.get_opt(&keys::transaction_output_labels(&old_hash))
+            .unwrap(),
+        None
+    );
+
+    // The archive has everything that was removed from the pruned movements
+    let sealed = std::fs::read(report.archive_path.unwrap()).unwrap();
+    let json: Vec<u8> = db.unseal(&sealed).unwrap();
+    let archive: serde_json::Value = serde_json::from_slice(&json).unwrap();
+    assert_eq!(archive["pruned_before"], 250);
+    let archived = archive["movements"].as_array().unwrap();
+    assert_eq!(archived.len(), 2);
+    assert_eq!(archived[0]["id"], 0);
+    let transaction = &archived[0]["movement"]["transaction"];
+    assert_eq!(transaction["hash"], old_hash.as_str());
+    assert_eq!(transaction["block"]["epoch"], "100");
+    assert!(transaction["block"]["block_hash"].is_string());
+    assert_eq!(
+        transaction["data"]["value_transfer"]["outputs"][0]["label"],
+        "rent"
+    );
+    assert!(transaction["timestamp"].is_u64());
+    assert_eq!(
+        archived[1]["movement"]["transaction"]["block"]["epoch"],
+        "200"
+    );
+
+    // Pruned movements keep their hash, amount, direction and epoch, with nulls for the rest
+    let transactions = serde_json::to_value(wallet.transactions(0, 10).unwrap()).unwrap();
+    let transactions = transactions["transactions"].as_array().unwrap();
+    assert_eq!(transactions.len(), 4);
+    let pruned = &transactions[3];
+    assert_eq!(pruned["type"], "POSITIVE");
+    assert_eq!(pruned["amount"], "1");
+    assert_eq!(pruned["transaction"]["hash"], old_hash.as_str());
+    assert_eq!(pruned["transaction"]["block"]["epoch"], "100");
+    assert_eq!(pruned["transaction"]["pruned"], true);
+    for field in [
+        "data",
+        "miner_fee",
+        "timestamp",
+        "fee_paid",
+        "counterparties",
+        "data_request_hash",
+    ] {
+        assert!(pruned["transaction"][field].is_null(), "{}", field);
+    }
+    assert!(pruned["transaction"]["block"]["block_hash"].is_null());
+    let retained = &transactions[1];
+    assert_eq!(retained["amount"], "3");
+    assert_eq!(retained["transaction"]["pruned"], false);
+    assert!(retained["transaction"]["data"]["value_transfer"].is_object());
+    assert!(retained["transaction"]["block"]["block_hash"].is_string());
+
+    // Pruned movements still count as present, and are not pruned twice
+    let report = wallet.check_integrity(false, |_, _| {}).unwrap();
+    assert!(!report
+        .findings
+        .iter()
+        .any(|finding| matches!(finding.issue, model::IntegrityIssue::MissingMovement { .. })));
+    let report = wallet.prune_movements(&archive_dir).unwrap();
+    assert_eq!((report.pruned, report.archive_path), (0, None));
+
+    std::fs::remove_dir_all(&archive_dir).unwrap();
+}
+
+#[test]
+fn test_transactions_stream_reads_every_movement_once_and_only_its_batch() {
+    const MOVEMENTS: u32 = 50_000;
+    const BATCH_SIZE: u32 = 700;
+
+    let (wallet, db) = factories::wallet(None);
+    let address = wallet.gen_external_address(None).unwrap();
+    index_payment(&wallet, address.pkh, 1, 100, true);
+    let template = wallet.get_transaction(0, 0).unwrap();
+
+    // Synthetic movements are told apart by their amount, which is their id
+    let movement = |id: u32| model::BalanceMovement {
+        amount: u64::from(id),
+        ..template.clone()
+    };
+    let mut batch = db.batch();
+    for id in 0..MOVEMENTS {
+        batch
+            .put(&keys::transaction_movement(0, id), movement(id))
+            .unwrap();
+    }
+    batch.put(&keys::transaction_next_id(0), MOVEMENTS).unwrap();
+    db.write(batch).unwrap();
+
+    let mut amounts = vec![];
+    let mut cursor = None;
+    loop {
+        let reads = db.reads();
+        let batch = wallet.transactions_stream(cursor, BATCH_SIZE).unwrap();
+        // Every movement is read along with the labels of its outputs, and nothing else but the
+        // id of the next movement
+        assert!(batch.transactions.len() <= BATCH_SIZE as usize);
+        assert!(db.reads() - reads <= 2 * batch.transactions.len() + 1);
+        amounts.extend(batch.transactions.iter().map(|movement| movement.amount));
+        cursor = Some(batch.cursor);
+        if batch.finished {
+            break;
+        }
+    }
+    assert_eq!(amounts, (0..u64::from(MOVEMENTS)).collect::<Vec<_>>());
+
+    // Movements persisted later are returned after the cursor
+    db.put(
+        &keys::transaction_movement(0, MOVEMENTS),
+        movement(MOVEMENTS),
+    )
+    .unwrap();
+    db.put(&keys::transaction_next_id(0), MOVEMENTS + 1)
+        .unwrap();
+    let batch = wallet.transactions_stream(cursor, BATCH_SIZE).unwrap();
+    assert_eq!(batch.transactions, vec![movement(MOVEMENTS)]);
+    assert!(batch.finished);
+    let batch = wallet
+        .transactions_stream(Some(batch.cursor), BATCH_SIZE)
+        .unwrap();
+    assert!(batch.transactions.is_empty());
+    assert_eq!(
+        batch.cursor,
+        model::TransactionsCursor {
+            account: 0,
+            next_id: MOVEMENTS + 1,
+        }
+    );
+
+    // Cursors of other accounts are rejected
+    let other_account = model::TransactionsCursor {
+        account: 1,
+        next_id: 0,
+    };
+    assert!(matches!(
+        wallet.transactions_stream(Some(other_account), BATCH_SIZE),
+        Err(Error::CursorAccountMismatch {
+            cursor: 1,
+            account: 0,
+        })
+    ));
+}
+
+#[test]
+fn test_new_wallets_record_the_latest_schema_version() {
+    let (_wallet, db) = factories::wallet(None);
+
+    assert_eq!(
+        db.get(&keys::wallet_schema_version()).unwrap(),
+        migrations::WALLET_SCHEMA_VERSION
+    );
+    assert_eq!(
+        migrations::WALLET_SCHEMA_VERSION,
+        schema::latest_version(&migrations::migrations::<HashMapDb>())
+    );
+}
+
+#[test]
+fn test_unlock_migrates_movements_of_unversioned_wallets() {
+    let db = factories::unversioned_wallet_db();
+    let legacy = model::LegacyBalanceMovement {
+        db_key: 0,
+        kind: model::MovementType::Positive,
+        amount: 1,
+        transaction: model::LegacyWalletTransaction {
+            block: Some(factories::BlockInfo::default().create()),
+            confirmed: true,
+            data: model::TransactionData::Mint(model::MintData { outputs: vec![] }),
+            hash: factories::transaction_id().to_string(),
+            miner_fee: 0,
+            timestamp: 1,
+        },
+    };
+    db.put(&keys::legacy_transaction_movement(0, 0), &legacy)
+        .unwrap();
+    // The movement with id 1 was pruned
+    db.put(&keys::transaction_next_id(0), 2).unwrap();
+    assert!(db.get(&keys::transaction_movement(0, 0)).is_err());
+
+    let id: model::WalletId = "example-wallet".parse().unwrap();
+    let session_id = types::SessionId::from(id.to_string());
+    let params = factories::default_params();
+    let wallet = Wallet::unlock(&id, session_id, db.clone(), params).unwrap();
+
+    // The movement is stored in the current format, with the same data
+    assert_eq!(
+        db.get(&keys::wallet_schema_version()).unwrap(),
+        migrations::WALLET_SCHEMA_VERSION
+    );
+    let migrated = db.get(&keys::transaction_movement(0, 0)).unwrap();
+    assert_eq!(migrated, model::BalanceMovement::from(legacy));
+    assert_eq!(wallet.get_transaction(0, 0).unwrap(), migrated);
+    assert_eq!(db.get_opt(&keys::transaction_movement(0, 1)).unwrap(), None);
+}
+
+#[test]
+fn test_unlock_refuses_wallets_of_newer_schema_versions() {
+    let (wallet, db) = factories::wallet(None);
+    let newer = migrations::WALLET_SCHEMA_VERSION + 1;
+    db.put(&keys::wallet_schema_version(), newer).unwrap();
+    let snapshot = db.export_to_json().unwrap();
+
+    let params = factories::default_params();
+    let err =
+        Wallet::unlock(&wallet.id, wallet.session_id.clone(), db.clone(), params).unwrap_err();
+
+    assert!(matches!(
+        err,
+        Error::UnsupportedSchemaVersion { found, supported }
+            if found == newer && supported == migrations::WALLET_SCHEMA_VERSION
+    ));
+    assert_eq!(db.export_to_json().unwrap(), snapshot);
+}
+
+/// A confirmed block paying 3 to an address of the wallet.
+fn paying_block(wallet: &Wallet<db::HashMapDb>) -> (model::Beacon, model::ExtendedTransaction) {
+    let address = wallet.gen_external_address(None).unwrap();
+    let block = model::Beacon {
+        epoch: 10,
+        block_hash: factories::transaction_id(),
+    };
+    let vtt = VTTransactionBody::new(
+        vec![Input::default()],
+        vec![ValueTransferOutput {
+            pkh: address.pkh,
+            value: 3,
+            time_lock: 0,
+        }],
+    );
+
+    (block, vtt_from_body(vtt))
+}
+
+#[test]
+fn test_redelivered_blocks_are_applied_once() {
+    let (wallet, db) = factories::wallet(None);
+    let (block, txn) = paying_block(&wallet);
+
+    let movements = wallet
+        .index_new_block_transactions(&block, &[txn.clone()], true)
+        .unwrap();
+    assert_eq!(movements.map(|movements| movements.len()), Some(1));
+    assert!(wallet.is_block_applied(&block).unwrap());
+    assert_eq!(
+        wallet
+            .index_new_block_transactions(&block, &[txn.clone()], true)
+            .unwrap(),
+        None
+    );
+
+    // The applied blocks survive a restart
+    let wallet = reopen(&db);
+    assert_eq!(
+        wallet
+            .index_new_block_transactions(&block, &[txn], true)
+            .unwrap(),
+        None
+    );
+    assert_eq!(wallet.balance().unwrap().confirmed.available, 3);
+    assert_eq!(wallet.transactions(0, 10).unwrap().total, 1);
+
+    // Pending blocks count as applied until the pending state is cleared
+    let pending = model::Beacon {
+        epoch: 11,
+        block_hash: factories::transaction_id(),
+    };
+    wallet
+        .index_new_block_transactions(&pending, &[], false)
+        .unwrap();
+    assert!(wallet.is_block_applied(&pending).unwrap());
+    wallet.clear_pending_state().unwrap();
+    assert!(!wallet.is_block_applied(&pending).unwrap());
+}
+
+#[test]
+fn test_block_is_applied_once_after_crashing_before_writing_it() {
+    let (wallet, db) = factories::wallet(None);
+    let (block, txn) = paying_block(&wallet);
+
+    CRASH_BEFORE_BLOCK_WRITE.with(|crash| crash.set(true));
+    assert!(wallet
+        .index_new_block_transactions(&block, &[txn.clone()], true)
+        .is_err());
+
+    // Nothing of the block was written, so it is applied again after restarting
+    let wallet = reopen(&db);
+    assert!(!wallet.is_block_applied(&block).unwrap());
+    assert!(wallet
+        .index_new_block_transactions(&block, &[txn.clone()], true)
+        .unwrap()
+        .is_some());
+    assert_eq!(
+        wallet
+            .index_new_block_transactions(&block, &[txn], true)
+            .unwrap(),
+        None
+    );
+
+    let wallet = reopen(&db);
+    assert_eq!(wallet.balance().unwrap().confirmed.available, 3);
+    assert_eq!(wallet.transactions(0, 10).unwrap().total, 1);
+}
+
+#[test]
+fn test_applied_blocks_are_forgotten_on_resync_and_out_of_the_window() {
+    let (wallet, db) = factories::wallet(None);
+    let block = |epoch| model::Beacon {
+        epoch,
+        block_hash: factories::transaction_id(),
+    };
+    let old = block(1);
+    let new = block(1 + constants::APPLIED_BLOCKS_WINDOW + 1);
+
+    for block in &[&old, &new] {
+        wallet
+            .index_new_block_transactions(block, &[], true)
+            .unwrap();
+    }
+    assert!(!wallet.is_block_applied(&old).unwrap());
+    assert!(wallet.is_block_applied(&new).unwrap());
+
+    wallet.clear_chain_data(types::ResyncFrom::Genesis).unwrap();
+    assert!(!wallet.is_block_applied(&new).unwrap());
+    assert!(reopen(&db)
+        .index_new_block_transactions(&new, &[], true)
+        .unwrap()
+        .is_some());
+}
+
+#[test]
+fn test_witness_transactions_are_signed_as_allowed_by_the_policy() {
+    let (wallet, db) = factories::wallet(None);
+    let address = wallet.gen_external_address(None).unwrap();
+    let public_key: PublicKey = {
+        let state = wallet.state.read().unwrap();
+        let sign_key = state.keychains[constants::EXTERNAL_KEYCHAIN as usize]
+            .derive(&KeyPath::default().index(address.index))
+            .unwrap()
+            .into();
+        From::from(PK::from_secret_key_global(&sign_key))
+    };
+    let commit_with_collateral = |public_key: &PublicKey, collateral: Vec<Input>| {
+        let proof = serde_json::from_value(serde_json::json!({
+            "proof": vec![0u8; 81],
+            "public_key": public_key,
+        }))
+        .unwrap();
+        let body = CommitTransactionBody {
+            dr_pointer: Hash::SHA256([1; 32]),
+            proof: DataRequestEligibilityClaim { proof },
+            collateral,
+            outputs: vec![ValueTransferOutput {
+                pkh: PublicKeyHash::from_public_key(public_key),
+                value: 1,
+                time_lock: 0,
+            }],
+            ..Default::default()
+        };
+
+        WitnessTransactionBody::from_payload(&serde_json::json!({
+            "type": "commit",
+            "body": body,
+        }))
+        .unwrap()
+    };
+    let commit = |public_key: &PublicKey| commit_with_collateral(public_key, vec![]);
+
+    // Nothing is signed for nodes unless the policy allows it
+    assert!(matches!(
+        wallet.sign_witness_transaction(&commit(&public_key)),
+        Err(Error::WitnessSigningNotAllowed(
+            model::WitnessTransactionType::Commit
+        ))
+    ));
+    wallet
+        .set_witness_signing_policy(model::WitnessSigningPolicy {
+            allowed_types: vec![model::WitnessTransactionType::Commit],
+            max_collateral: 1_000,
+        })
+        .unwrap();
+    let wallet = reopen(&db);
+
+    let body = commit(&public_key);
+    let (signature, entry) = wallet.sign_witness_transaction(&body).unwrap();
+    assert_eq!(signature.public_key, public_key);
+    signature::verify(
+        &public_key.clone().try_into().unwrap(),
+        body.hash().as_ref(),
+        &signature.signature.clone().try_into().unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        entry.transaction_type,
+        model::WitnessTransactionType::Commit
+    );
+    assert_eq!(entry.hash, body.hash().to_string());
+    assert_eq!(entry.dr_pointer, Hash::SHA256([1; 32]).to_string());
+    assert_eq!(entry.address, address.address);
+    assert_eq!(
+        db.get(&keys::wallet_witness_signing_audit()).unwrap(),
+        vec![entry]
+    );
+
+    // Keys that are not of the wallet cannot be used
+    let stranger = PublicKey {
+        compressed: 2,
+        bytes: [7; 32],
+    };
+    assert!(matches!(
+        wallet.sign_witness_transaction(&commit(&stranger)),
+        Err(Error::UnknownWitnessKey(_))
+    ));
+
+    // Collateral can only be taken from available outputs of the committer, up to the limit
+    let output = |index: u32, amount: u64, pkh: PublicKeyHash| {
+        let pointer = OutputPointer {
+            transaction_id: Hash::SHA256([2; 32]),
+            output_index: index,
+        };
+        wallet.state.write().unwrap().utxo_set.insert(
+            (&pointer).into(),
+            model::OutputInfo {
+                amount,
+                pkh,
+                time_lock: 0,
+            },
+        );
+
+        Input::new(pointer)
+    };
+    let other_address = wallet.gen_external_address(None).unwrap();
+    let other_pkh = PublicKeyHash::from_bech32(get_environment(), &other_address.address).unwrap();
+    let of_other_address = output(0, 500, other_pkh);
+    assert!(matches!(
+        wallet
+            .sign_witness_transaction(&commit_with_collateral(&public_key, vec![of_other_address])),
+        Err(Error::UnavailableCollateral { .. })
+    ));
+    let unknown = Input::new(OutputPointer {
+        transaction_id: Hash::SHA256([3; 32]),
+        output_index: 0,
+    });
+    assert!(matches!(
+        wallet.sign_witness_transaction(&commit_with_collateral(&public_key, vec![unknown])),
+        Err(Error::UnavailableCollateral { .. })
+    ));
+    let signer = PublicKeyHash::from_public_key(&public_key);
+    let large = output(1, 2_000, signer);
+    assert!(matches!(
+        wallet.sign_witness_transaction(&commit_with_collateral(&public_key, vec![large])),
+        Err(Error::CollateralAboveLimit {
+            collateral: 1_999,
+            limit: 1_000
+        })
+    ));
+    let small = output(2, 1_001, signer);
+    wallet
+        .sign_witness_transaction(&commit_with_collateral(&public_key, vec![small.clone()]))
+        .unwrap();
+    assert!(wallet
+        .state
+        .read()
+        .unwrap()
+        .used_outputs
+        .contains_key(&small.output_pointer().into()));
+    // Outputs locked by a commit cannot be used by another one
+    assert!(matches!(
+        wallet.sign_witness_transaction(&commit_with_collateral(&public_key, vec![small.clone()])),
+        Err(Error::UnavailableCollateral { .. })
+    ));
+    // Until their usage times out
+    wallet
+        .state
+        .write()
+        .unwrap()
+        .used_outputs
+        .insert(small.output_pointer().into(), 0);
+    wallet
+        .sign_witness_transaction(&commit_with_collateral(&public_key, vec![small]))
+        .unwrap();
+}
+
+#[test]
+fn test_unlock_migrates_the_witness_signing_policy() {
+    let db = factories::unversioned_wallet_db();
+    let legacy = model::LegacyWitnessSigningPolicy {
+        allowed_types: vec![model::WitnessTransactionType::Reveal],
+    };
+    db.put(&keys::legacy_wallet_witness_signing_policy(), &legacy)
+        .unwrap();
+    assert!(db.get(&keys::wallet_witness_signing_policy()).is_err());
+
+    let id: model::WalletId = "example-wallet".parse().unwrap();
+    let session_id = types::SessionId::from(id.to_string());
+    let params = factories::default_params();
+    let wallet = Wallet::unlock(&id, session_id, db.clone(), params).unwrap();
+
+    // Nodes cannot lock any collateral until the owner allows it
+    let migrated = model::WitnessSigningPolicy {
+        allowed_types: vec![model::WitnessTransactionType::Reveal],
+        max_collateral: 0,
+    };
+    assert_eq!(
+        db.get(&keys::wallet_witness_signing_policy()).unwrap(),
+        migrated
+    );
+    assert_eq!(wallet.witness_signing_policy().unwrap(), migrated);
+}
+
+/// Generate external addresses for a client, returning the indices of those with a warning.
+fn gen_addresses_within_gap(wallet: &Wallet<HashMapDb>, count: u32) -> Vec<u32> {
+    (0..count)
+        .filter_map(|_| wallet.gen_external_address_within_gap(None).unwrap().1)
+        .map(|warning| warning.index)
+        .collect()
+}
+
+#[test]
+fn test_gen_external_address_warns_beyond_the_gap_limit() {
+    let (wallet, db) = factories::wallet(None);
+    let gap_limit = factories::default_params().sync_address_batch_length;
+    assert_eq!(gap_limit, 10);
+
+    // Recovering wallets look at addresses 0 to 9 when none has been used
+    assert_eq!(gen_addresses_within_gap(&wallet, 10), Vec::<u32>::new());
+    let (_, warning) = wallet.gen_external_address_within_gap(None).unwrap();
+    assert_eq!(
+        warning.map(|warning| (warning.index, warning.last_used_index, warning.gap)),
+        Some((10, None, 11))
+    );
+    assert_eq!(gen_addresses_within_gap(&wallet, 2), vec![11, 12]);
+    // Only the first address beyond the limit is notified
+    let warnings = wallet.take_address_gap_warnings().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(warnings[0].index, 10);
+    assert_eq!(warnings[0].gap_limit, gap_limit);
+
+    // A payment to an address closes the gap behind it
+    let used = wallet.gen_external_address(None).unwrap();
+    assert_eq!(used.index, 13);
+    wallet
+        .index_block_transactions(
+            &factories::BlockInfo::default().create(),
+            &[payments_to(&[used.pkh])],
+            true,
+        )
+        .unwrap();
+    assert_eq!(
+        db.get(&keys::account_last_used_index(
+            0,
+            constants::EXTERNAL_KEYCHAIN
+        ))
+        .unwrap(),
+        13
+    );
+    assert_eq!(gen_addresses_within_gap(&wallet, 10), Vec::<u32>::new());
+    assert!(wallet.take_address_gap_warnings().unwrap().is_empty());
+
+    // Going beyond the limit again is notified again
+    assert_eq!(gen_addresses_within_gap(&wallet, 2), vec![24, 25]);
+    let warnings = wallet.take_address_gap_warnings().unwrap();
+    assert_eq!(warnings.len(), 1);
+    assert_eq!(
+        (warnings[0].index, warnings[0].last_used_index),
+        (24, Some(13))
+    );
+}
+
+#[test]
+fn test_gen_external_address_refuses_addresses_beyond_the_gap_limit() {
+    let params = params::Params {
+        refuse_addresses_beyond_gap_limit: true,
+        ..factories::default_params()
+    };
+    let (wallet, db) = factories::wallet_with_params(None, params);
+
+    assert_eq!(gen_addresses_within_gap(&wallet, 10), Vec::<u32>::new());
+    assert!(matches!(
+        wallet.gen_external_address_within_gap(None),
+        Err(Error::AddressGapExceeded {
+            index: 10,
+            gap: 11,
+            gap_limit: 10,
+        })
+    ));
+
+    // Nothing was generated
+    assert_eq!(
+        db.get(&keys::account_next_index(0, constants::EXTERNAL_KEYCHAIN))
+            .unwrap(),
+        10
+    );
+    assert!(wallet.take_address_gap_warnings().unwrap().is_empty());
+}
+
+#[test]
+fn test_resync_looks_at_every_address_generated_beyond_the_gap_limit() {
+    let (wallet, db) = factories::wallet(None);
+    let addresses = (0..30)
+        .map(|_| wallet.gen_external_address(None).unwrap())
+        .collect_vec();
+    assert_eq!(
+        db.get(&keys::account_generation_watermark(
+            0,
+            constants::EXTERNAL_KEYCHAIN
+        ))
+        .unwrap(),
+        30
+    );
+
+    wallet.clear_chain_data(types::ResyncFrom::Genesis).unwrap();
+    wallet.initialize_transient_addresses(10, 10).unwrap();
+
+    // The last address is far beyond the gap limit, but it was generated before
+    let last = addresses.last().unwrap().pkh;
+    assert!(wallet
+        .lock_and_read_state(|state| {
+            state.transient_external_addresses.len() == 30
+                && state.transient_external_addresses.contains_key(&last)
+        })
+        .unwrap());
+    // The watermark survives the resynchronization
+    assert_eq!(
+        db.get(&keys::account_generation_watermark(
+            0,
+            constants::EXTERNAL_KEYCHAIN
+        ))
+        .unwrap(),
+        30
+    );
+}
+
+fn sorted_label(index: u32) -> Option<String> {
+    match index % 3 {
+        0 => None,
+        1 => Some("b".to_string()),
+        _ => Some("a".to_string()),
+    }
+}
+
+#[test]
+fn test_addresses_pages_are_consistent_while_addresses_are_generated() {
+    let sorts = [
+        model::AddressesSort::Index,
+        model::AddressesSort::Creation,
+        model::AddressesSort::Label,
+    ];
+    for sort in sorts {
+        for order in [model::SortOrder::Asc, model::SortOrder::Desc] {
+            let (wallet, _db) = factories::wallet(None);
+            for index in 0..8 {
+                wallet.gen_external_address(sorted_label(index)).unwrap();
+            }
+
+            let mut listed = vec![];
+            let mut cursor = None;
+            loop {
+                let page = wallet
+                    .addresses(constants::EXTERNAL_KEYCHAIN, sort, order, cursor, 0, 3)
+                    .unwrap();
+                if page.addresses.is_empty() {
+                    assert_eq!(page.cursor, None);
+                    break;
+                }
+                assert_eq!(
+                    page.cursor.map(|cursor| cursor.index),
+                    page.addresses.last().map(|address| address.index)
+                );
+                listed.extend(page.addresses);
+                cursor = page.cursor;
+
+                // Addresses generated between pages cannot break the listing
+                let index = wallet.gen_external_address(None).unwrap().index;
+                wallet
+                    .gen_external_address(sorted_label(index + 1))
+                    .unwrap();
+            }
+
+            let keys = listed
+                .iter()
+                .map(|address| match sort {
+                    model::AddressesSort::Label => (address.info.label.clone(), address.index),
+                    _ => (None, address.index),
+                })
+                .collect_vec();
+            let mut expected = keys.clone();
+            expected.sort();
+            if order == model::SortOrder::Desc {
+                expected.reverse();
+            }
+            expected.dedup();
+            // Sorted, and without repeating any item
+            assert_eq!(keys, expected, "{:?} {:?}", sort, order);
+            // Every address that existed when the listing started was listed
+            for index in 0..8 {
+                assert!(
+                    listed.iter().any(|address| address.index == index),
+                    "{:?} {:?}",
+                    sort,
+                    order
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_addresses_are_sorted_by_label_and_index() {
+    let (wallet, _db) = factories::wallet(None);
+    for index in 0..6 {
+        wallet.gen_external_address(sorted_label(index)).unwrap();
+    }
+
+    let page = wallet
+        .addresses(
+            constants::EXTERNAL_KEYCHAIN,
+            model::AddressesSort::Label,
+            model::SortOrder::Asc,
+            None,
+            1,
+            4,
+        )
+        .unwrap();
+    let indices = page
+        .addresses
+        .iter()
+        .map(|address| address.index)
+        .collect_vec();
+    // Unlabeled 0 and 3, then "a" for 2 and 5, then "b" for 1 and 4
+    assert_eq!(indices, vec![3, 2, 5, 1]);
+    assert_eq!(page.total, 6);
+
+    // The default listing goes from the newest address
+    let page = wallet.external_addresses(0, 2).unwrap();
+    assert_eq!(
+        page.addresses
+            .iter()
+            .map(|address| address.index)
+            .collect_vec(),
+        vec![5, 4]
+    );
+}
+
+/// Payment of `value` to `pkh`, made unique by `nonce`.
+fn unique_payment(pkh: PublicKeyHash, value: u64, nonce: u64) -> model::ExtendedTransaction {
+    vtt_from_body(VTTransactionBody::new(
+        vec![Input::default()],
+        vec![
+            ValueTransferOutput {
+                pkh,
+                value,
+                time_lock: 0,
+            },
+            ValueTransferOutput {
+                pkh: PublicKeyHash::from_bytes(&[0xFF; 20]).unwrap(),
+                value: nonce + 1,
+                time_lock: 0,
+            },
+        ],
+    ))
+}
+
+#[test]
+fn test_movements_pages_are_consistent_while_movements_are_indexed() {
+    let sorts = [
+        model::MovementsSort::Id,
+        model::MovementsSort::Amount,
+        model::MovementsSort::Epoch,
+    ];
+    for sort in sorts {
+        for order in [model::SortOrder::Asc, model::SortOrder::Desc] {
+            let (wallet, _db) = factories::wallet(None);
+            let pkh = wallet.gen_external_address(None).unwrap().pkh;
+            let mut nonce = 0;
+            let mut index_block = |epoch: u32, values: &[u64]| {
+                let txns = values
+                    .iter()
+                    .map(|value| {
+                        nonce += 1;
+                        unique_payment(pkh, *value, nonce)
+                    })
+                    .collect_vec();
+                let block = model::Beacon {
+                    epoch,
+                    block_hash: factories::transaction_id(),
+                };
+                wallet
+                    .index_block_transactions(&block, &txns, true)
+                    .unwrap();
+            };
+            index_block(10, &[5, 1, 5]);
+            index_block(20, &[3]);
+            index_block(30, &[1, 7]);
+            let existing = wallet.transactions(0, 100).unwrap();
+            assert_eq!(existing.total, 6);
+
+            let mut listed = vec![];
+            let mut cursor = None;
+            let mut epoch = 40;
+            loop {
+                let page = wallet
+                    .sorted_transactions(sort, order, cursor, 0, 2)
+                    .unwrap();
+                if page.transactions.is_empty() {
+                    assert_eq!(page.cursor, None);
+                    break;
+                }
+                listed.extend(page.transactions);
+                cursor = page.cursor;
+
+                // Movements indexed between pages cannot break the listing
+                index_block(epoch, &[u64::from(epoch) % 7 + 1]);
+                epoch += 10;
+            }
+
+            let keys = listed
+                .iter()
+                .map(|movement| movement_sort_key(sort, movement, false))
+                .collect_vec();
+            let mut expected = keys.clone();
+            expected.sort();
+            if order == model::SortOrder::Desc {
+                expected.reverse();
+            }
+            expected.dedup();
+            // Sorted, and without repeating any item
+            assert_eq!(keys, expected, "{:?} {:?}", sort, order);
+            for movement in &existing.transactions {
+                assert!(
+                    listed
+                        .iter()
+                        .any(|listed| listed.transaction.hash == movement.transaction.hash),
+                    "{:?} {:?}",
+                    sort,
+                    order
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_movements_are_sorted_by_amount_and_id() {
+    let (wallet, _db) = factories::wallet(None);
+    let pkh = wallet.gen_external_address(None).unwrap().pkh;
+    let txns = [5, 1, 5, 3]
+        .iter()
+        .enumerate()
+        .map(|(nonce, value)| unique_payment(pkh, *value, nonce as u64))
+        .collect_vec();
+    wallet
+        .index_block_transactions(&factories::BlockInfo::default().create(), &txns, true)
+        .unwrap();
+
+    let page = wallet
+        .sorted_transactions(
+            model::MovementsSort::Amount,
+            model::SortOrder::Desc,
+            None,
+            0,
+            10,
+        )
+        .unwrap();
+    let listed = page
+        .transactions
+        .iter()
+        .map(|movement| (movement.amount, movement.db_key))
+        .collect_vec();
+    assert_eq!(listed, vec![(5, 2), (5, 0), (3, 3), (1, 1)]);
+
+    // The cursor of a page holds the key of its last movement
+    let cursor: model::MovementsCursor = page.cursor.unwrap().to_string().parse().unwrap();
+    assert_eq!(cursor.key.primary, 1);
+    assert_eq!(cursor.key.id, 1);
+    let next = wallet
+        .sorted_transactions(
+            model::MovementsSort::Amount,
+            model::SortOrder::Desc,
+            Some(cursor),
+            0,
+            10,
+        )
+        .unwrap();
+    assert!(next.transactions.is_empty());
+    assert!("not a cursor".parse::<model::MovementsCursor>().is_err());
+}
+
+#[test]
+fn test_idempotent_operations_expire_and_are_pruned() {
+    let (wallet, db) = factories::wallet(None);
+    let operation = |recorded_at: i64| model::IdempotentOperation {
+        method: "send_transaction".to_string(),
+        params_hash: "hash".to_string(),
+        recorded_at,
+        response: Some("{}".to_string()),
+    };
+
+    wallet
+        .record_idempotent_operation("first", &operation(100), 0)
+        .unwrap();
+    assert_eq!(
+        wallet.idempotent_operation("first", 100).unwrap(),
+        Some(operation(100))
+    );
+    assert_eq!(wallet.idempotent_operation("first", 101).unwrap(), None);
+
+    // Recording an operation deletes the expired ones
+    wallet
+        .record_idempotent_operation("second", &operation(200), 150)
+        .unwrap();
+    assert_eq!(wallet.idempotent_operation("first", 0).unwrap(), None);
+    let index = db.get(&keys::idempotency_index()).unwrap();
+    assert_eq!(
+        index,
+        vec![model::IdempotencyIndexEntry {
+            key: "second".to_string(),
+            recorded_at: 200,
+        }]
+    );
+
+    wallet.forget_idempotent_operation("second").unwrap();
+    assert_eq!(wallet.idempotent_operation("second", 0).unwrap(), None);
+    assert!(db.get(&keys::idempotency_index()).unwrap().is_empty());
 }
@@ -1,18 +1,32 @@
-use std::sync::Mutex;
+use std::{collections::HashMap, convert::TryFrom, sync::Mutex};
 
-use super::*;
+use super::{
+    schema::{self, Migration},
+    wallet::migrations::WALLET_SCHEMA_VERSION,
+    *,
+};
 use crate::{
-    constants,
-    db::{Database, WriteBatch as _},
+    constants, crypto,
+    db::{self, Database, WriteBatch as _},
     model, types,
 };
 
 #[cfg(test)]
 mod tests;
 
+/// Version of the schema of the records shared by all the wallets, which is the one of their last
+/// migration.
+pub const WALLETS_SCHEMA_VERSION: u32 = 1;
+
 pub struct Wallets<T> {
     db: T,
     wallets_mutex: Mutex<()>,
+    /// Decoded names of the wallets that have already been listed, so that listing them again
+    /// does not hit the database. Entries are removed whenever the wallet info changes
+    names_cache: Mutex<HashMap<model::WalletId, Option<String>>>,
+    /// Whether the wallets that have already been listed are waiting for their seed backup to be
+    /// confirmed, so that filtering them out does not read the database for every wallet
+    pending_cache: Mutex<HashMap<model::WalletId, bool>>,
 }
 
 impl<T: Database> Wallets<T> {
@@ -20,7 +34,44 @@ impl<T: Database> Wallets<T> {
         Self {
             db,
             wallets_mutex: Default::default(),
+            names_cache: Default::default(),
+            pending_cache: Default::default(),
+        }
+    }
+
+    /// Migrations of the records shared by all the wallets, sorted by version.
+    fn migrations() -> Vec<Migration<Self>> {
+        vec![Migration {
+            version: 1,
+            description: "store the seed fingerprint of the wallets whose id was derived from it",
+            run: |wallets| {
+                let migrated = wallets.migrate_seed_fingerprints()?;
+                log::info!("Stored the seed fingerprint of {} wallets", migrated);
+
+                Ok(())
+            },
+        }]
+    }
+
+    /// Bring the records shared by all the wallets up to `WALLETS_SCHEMA_VERSION`, or record that
+    /// version if the database is new. Returns the version that the database had.
+    ///
+    /// Databases written by newer software are refused with `Error::UnsupportedSchemaVersion`.
+    pub fn migrate_schema(&self) -> Result<u32> {
+        let version_key = keys::wallets_schema_version();
+        if !self.db.contains(&version_key)? && !self.db.contains(&keys::wallet_ids())? {
+            self.db.put(&version_key, WALLETS_SCHEMA_VERSION)?;
+
+            return Ok(WALLETS_SCHEMA_VERSION);
         }
+
+        schema::migrate(
+            &self.db,
+            &version_key,
+            self,
+            "the wallets database",
+            &Self::migrations(),
+        )
     }
 
     pub fn flush_db(&self) -> Result<()> {
@@ -29,22 +80,114 @@ impl<T: Database> Wallets<T> {
         Ok(())
     }
 
-    /// Retrieve public information of wallets stored in the wallets DB
-    pub fn infos(&self) -> Result<Vec<model::Wallet>> {
-        let ids: Vec<String> = self.db.get_or_default(&keys::wallet_ids())?;
-        let mut wallets = Vec::with_capacity(ids.len());
+    /// Retrieve a page of the public information of wallets stored in the wallets DB. Wallets
+    /// waiting for their seed backup to be confirmed are only included if `include_pending` is set.
+    ///
+    /// Only the wallets in the requested page are decoded. Wallets whose information cannot be
+    /// decoded are left out of the page and reported as warnings. The other wallets are only
+    /// checked for a pending seed backup if those are left out, which is cached.
+    pub fn infos(
+        &self,
+        include_pending: bool,
+        offset: u32,
+        limit: u32,
+    ) -> Result<model::WalletInfos> {
+        let ids: Vec<model::WalletId> = self.db.get_or_default(&keys::wallet_ids())?;
+        let limit = limit as usize;
+        let mut total = 0;
+        let mut infos = Vec::with_capacity(limit.min(ids.len()));
+        let mut warnings = vec![];
 
         for id in ids {
-            let name = self.db.get_opt(&keys::wallet_id_name(&id))?;
+            if !include_pending && self.is_pending(&id)? {
+                continue;
+            }
+            total += 1;
+            if total <= offset || infos.len() + warnings.len() >= limit {
+                continue;
+            }
 
-            wallets.push(model::Wallet { id, name })
+            let pending_confirmation = include_pending && self.is_pending(&id)?;
+            let info = self.name(&id).and_then(|name| {
+                Ok((
+                    name,
+                    self.sync_target_epoch(&id)?,
+                    self.key_derivation(&id)?,
+                ))
+            });
+            match info {
+                Ok((name, sync_target_epoch, key_derivation)) => infos.push(model::Wallet {
+                    id,
+                    name,
+                    pending_confirmation,
+                    sync_target_epoch,
+                    key_spec: key_derivation.key_spec,
+                    base_path: key_derivation.base_path().to_string(),
+                }),
+                Err(Error::Db(db::Error::Bincode(e))) => {
+                    log::warn!("Skipping wallet {} with corrupted info: {}", id, e);
+                    warnings.push(format!("wallet {} has corrupted info: {}", id, e));
+                }
+                Err(e @ Error::UnsupportedKeySpec(_)) => {
+                    log::warn!("Skipping wallet {}: {}", id, e);
+                    warnings.push(format!("wallet {}: {}", id, e));
+                }
+                Err(e) => return Err(e),
+            }
         }
 
-        Ok(wallets)
+        Ok(model::WalletInfos {
+            infos,
+            total,
+            warnings,
+        })
+    }
+
+    /// Name of a wallet, taken from the cache if the wallet has already been listed
+    fn name(&self, id: &model::WalletId) -> Result<Option<String>> {
+        if let Some(name) = self.names_cache.lock()?.get(id) {
+            return Ok(name.clone());
+        }
+        let name = self.db.get_opt(&keys::wallet_id_name(id))?;
+        self.names_cache.lock()?.insert(id.clone(), name.clone());
+
+        Ok(name)
+    }
+
+    /// Whether a wallet is waiting for its seed backup to be confirmed, taken from the cache if
+    /// the wallet has already been listed
+    fn is_pending(&self, id: &model::WalletId) -> Result<bool> {
+        if let Some(pending) = self.pending_cache.lock()?.get(id) {
+            return Ok(*pending);
+        }
+        let pending = self
+            .db
+            .contains(&keys::wallet_id_pending_confirmation(id))?;
+        self.pending_cache.lock()?.insert(id.clone(), pending);
+
+        Ok(pending)
+    }
+
+    /// Key derivation of a wallet, as recorded in the wallets DB when it was created. Wallets
+    /// created before it was recorded use the legacy one
+    pub fn key_derivation(&self, id: &model::WalletId) -> Result<model::KeyDerivation> {
+        match self.db.get_opt(&keys::wallet_id_key_derivation(id))? {
+            Some(record) => model::KeyDerivation::try_from(record)
+                .map_err(|err| Error::UnsupportedKeySpec(err.0)),
+            None => Ok(model::KeyDerivation::LEGACY),
+        }
+    }
+
+    /// Remove the cached info of a wallet, to be called whenever it changes
+    fn invalidate_info(&self, id: &model::WalletId) -> Result<()> {
+        self.names_cache.lock()?.remove(id);
+        self.pending_cache.lock()?.remove(id);
+
+        Ok(())
     }
 
     /// Update a wallet's public info in the wallets db .
-    pub fn update_info(&self, id: &str, name: Option<String>) -> Result<()> {
+    pub fn update_info(&self, id: &model::WalletId, name: Option<String>) -> Result<()> {
         let mut batch = self.db.batch();
 
         if let Some(name) = name {
@@ -52,6 +195,7 @@ impl<T: Database> Wallets<T> {
         }
 
         self.db.write(batch)?;
+        self.invalidate_info(id)?;
 
         Ok(())
     }
@@ -65,6 +209,7 @@ impl<T: Database> Wallets<T> {
     ) -> Result<()> {
         let types::CreateWalletData {
             id,
+            seed_fingerprint,
             name,
             description,
             iv,
@@ -72,6 +217,8 @@ impl<T: Database> Wallets<T> {
             account,
             master_key,
             birth_date,
+            seed_backup,
+            key_derivation,
         } = wallet_data;
         let mut batch = self.db.batch();
         let mut wbatch = wallet_db.batch();
@@ -90,6 +237,12 @@ impl<T: Database> Wallets<T> {
             wbatch.put(&keys::wallet_description(), description)?;
         }
 
+        // The key derivation is also public, so wallets can be listed without unlocking them
+        let key_derivation = model::KeyDerivationRecord::from(key_derivation);
+        wbatch.put(&keys::wallet_key_derivation(), &key_derivation)?;
+        batch.put(&keys::wallet_id_key_derivation(id), &key_derivation)?;
+
+        wbatch.put(&keys::wallet_schema_version(), WALLET_SCHEMA_VERSION)?;
         wbatch.put(&keys::wallet_default_account(), account.index)?;
         wbatch.put(
             &keys::account_key(account.index, constants::EXTERNAL_KEYCHAIN),
@@ -103,47 +256,224 @@ impl<T: Database> Wallets<T> {
         wbatch.put(&keys::birth_date(), birth_date)?;
         wbatch.put(&keys::wallet_last_sync(), birth_date)?;
 
+        // The wallet cannot be used until the user confirms having backed up its seed
+        if let Some(seed_backup) = seed_backup {
+            wbatch.put(&keys::seed_backup(), seed_backup)?;
+            batch.put(&keys::wallet_id_pending_confirmation(id), 0u32)?;
+        }
+
         wallet_db.write(wbatch)?;
 
         batch.put(&keys::wallet_id_salt(id), &salt)?;
         batch.put(&keys::wallet_id_iv(id), &iv)?;
+        batch.put(&keys::wallet_id_seed_fingerprint(id), seed_fingerprint)?;
 
         // FIXME: Use merge operator or a transaction when available in rocksdb crate
-        let wallet_id = id.to_string();
         let lock = self.wallets_mutex.lock()?;
-        let mut ids: Vec<String> = self.db.get_or_default(&keys::wallet_ids())?;
-        if !ids.contains(&wallet_id) {
-            ids.push(wallet_id);
+        let mut ids: Vec<model::WalletId> = self.db.get_or_default(&keys::wallet_ids())?;
+        if !ids.contains(id) {
+            ids.push(id.clone());
             batch.put(&keys::wallet_ids(), ids)?;
         }
         self.db.write(batch)?;
         drop(lock);
+        self.invalidate_info(id)?;
 
         Ok(())
     }
 
     /// Delete a wallet by its ID
-    pub fn delete(&self, wallet_id: String) -> Result<()> {
+    pub fn delete(&self, wallet_id: model::WalletId) -> Result<()> {
         let mut batch = self.db.batch();
         let lock = self.wallets_mutex.lock()?;
-        let mut ids: Vec<String> = self.db.get_or_default(&keys::wallet_ids())?;
+        let mut ids: Vec<model::WalletId> = self.db.get_or_default(&keys::wallet_ids())?;
 
         if let Some(index) = ids.iter().position(|x| x == &wallet_id) {
             ids.remove(index);
             batch.put(&keys::wallet_ids(), ids)?;
         }
+        batch.delete(&keys::wallet_id_pending_confirmation(&wallet_id))?;
+        batch.delete(&keys::wallet_id_sync_target_epoch(&wallet_id))?;
 
         self.db.write(batch)?;
         drop(lock);
+        self.invalidate_info(&wallet_id)?;
+
+        Ok(())
+    }
+
+    /// Number of failed confirmations of the seed backup of a wallet, or `None` if the wallet is
+    /// not waiting for its seed backup to be confirmed
+    pub fn pending_confirmation(&self, wallet_id: &model::WalletId) -> Result<Option<u32>> {
+        Ok(self
+            .db
+            .get_opt(&keys::wallet_id_pending_confirmation(wallet_id))?)
+    }
+
+    /// Count a failed confirmation of the seed backup of a wallet. Returns the number of failed
+    /// confirmations so far
+    pub fn record_failed_confirmation(&self, wallet_id: &model::WalletId) -> Result<u32> {
+        let key = keys::wallet_id_pending_confirmation(wallet_id);
+        let lock = self.wallets_mutex.lock()?;
+        let failed = self.db.get_or_default(&key)?.saturating_add(1);
+        self.db.put(&key, failed)?;
+        drop(lock);
+
+        Ok(failed)
+    }
+
+    /// Mark the seed backup of a wallet as confirmed, so that the wallet can be used
+    pub fn confirm(&self, wallet_id: &model::WalletId) -> Result<()> {
+        let mut batch = self.db.batch();
+        batch.delete(&keys::wallet_id_pending_confirmation(wallet_id))?;
+        self.db.write(batch)?;
+        self.invalidate_info(wallet_id)?;
+
+        Ok(())
+    }
+
+    /// Epoch beyond which the blocks are not processed by a wallet, or `None` if the
+    /// synchronization of the wallet is not pinned
+    pub fn sync_target_epoch(&self, wallet_id: &model::WalletId) -> Result<Option<u32>> {
+        Ok(self
+            .db
+            .get_opt(&keys::wallet_id_sync_target_epoch(wallet_id))?)
+    }
+
+    /// Pin the synchronization of a wallet to an epoch, or unpin it if `epoch` is `None`
+    pub fn set_sync_target_epoch(
+        &self,
+        wallet_id: &model::WalletId,
+        epoch: Option<u32>,
+    ) -> Result<()> {
+        let key = keys::wallet_id_sync_target_epoch(wallet_id);
+        let mut batch = self.db.batch();
+        match epoch {
+            Some(epoch) => batch.put(&key, epoch)?,
+            None => batch.delete(&key)?,
+        }
+        self.db.write(batch)?;
 
         Ok(())
     }
 
+    /// Last benchmark kept of the data source with this URL
+    pub fn retrieval_benchmark(&self, url: &str) -> Result<Option<model::SourceBenchmark>> {
+        Ok(self.db.get_opt(&keys::retrieval_benchmark(url))?)
+    }
+
+    /// Keep the benchmark of a data source, replacing the last one of the same URL
+    pub fn set_retrieval_benchmark(&self, benchmark: &model::SourceBenchmark) -> Result<()> {
+        let mut batch = self.db.batch();
+        batch.put(&keys::retrieval_benchmark(&benchmark.url), benchmark)?;
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Get the fingerprint of a seed given the id deterministically derived from it.
+    ///
+    /// The salt of the fingerprints is generated the first time it is needed.
+    pub fn seed_fingerprint(&self, seed_id: &str) -> Result<String> {
+        let lock = self.wallets_mutex.lock()?;
+        let salt = match self.db.get_opt(&keys::seed_fingerprint_salt())? {
+            Some(salt) => salt,
+            None => {
+                let salt = crypto::salt(
+                    &mut rand::rngs::OsRng,
+                    constants::SEED_FINGERPRINT_SALT_LENGTH,
+                );
+                self.db.put(&keys::seed_fingerprint_salt(), &salt)?;
+
+                salt
+            }
+        };
+        drop(lock);
+
+        Ok(crypto::gen_seed_fingerprint(seed_id, &salt))
+    }
+
+    /// Store the seed fingerprint of wallets created when their id was derived from their seed.
+    ///
+    /// The id of those wallets is the id derived from their seed, so their fingerprint can be
+    /// computed without unlocking them. Returns the number of migrated wallets.
+    pub fn migrate_seed_fingerprints(&self) -> Result<usize> {
+        let ids: Vec<model::WalletId> = self.db.get_or_default(&keys::wallet_ids())?;
+        let mut batch = self.db.batch();
+        let mut migrated = 0;
+
+        for id in ids {
+            if !self.db.contains(&keys::wallet_id_seed_fingerprint(&id))? {
+                batch.put(
+                    &keys::wallet_id_seed_fingerprint(&id),
+                    self.seed_fingerprint(&id)?,
+                )?;
+                migrated += 1;
+            }
+        }
+        self.db.write(batch)?;
+
+        Ok(migrated)
+    }
+
+    /// Ids of the wallets created from the seed with the given fingerprint
+    pub fn wallets_with_seed(&self, seed_fingerprint: &str) -> Result<Vec<model::WalletId>> {
+        let ids: Vec<model::WalletId> = self.db.get_or_default(&keys::wallet_ids())?;
+        let mut wallets = vec![];
+
+        for id in ids {
+            let fingerprint = self.db.get_opt(&keys::wallet_id_seed_fingerprint(&id))?;
+            if fingerprint.as_deref() == Some(seed_fingerprint) {
+                wallets.push(id);
+            }
+        }
+
+        Ok(wallets)
+    }
+
+    /// Choose the id of a new wallet created from the seed with the given fingerprint.
+    ///
+    /// Ids are random, unless `overwrite` is set and there already is a wallet created from the
+    /// same seed, whose id is then reused. Creating another wallet from the same seed fails unless
+    /// `allow_duplicate_seed` is set. Random ids that are already taken are generated again, so an
+    /// existing wallet is never overwritten by accident.
+    pub fn new_wallet_id<R>(
+        &self,
+        rng: &mut R,
+        seed_fingerprint: &str,
+        overwrite: bool,
+        allow_duplicate_seed: bool,
+    ) -> Result<model::WalletId>
+    where
+        R: rand::Rng + rand::CryptoRng,
+    {
+        if !allow_duplicate_seed {
+            if let Some(id) = self.wallets_with_seed(seed_fingerprint)?.into_iter().next() {
+                return if overwrite {
+                    Ok(id)
+                } else {
+                    Err(Error::WalletAlreadyExists(id.into()))
+                };
+            }
+        }
+
+        let ids: Vec<model::WalletId> = self.db.get_or_default(&keys::wallet_ids())?;
+        for _ in 0..constants::MAX_WALLET_ID_ATTEMPTS {
+            let id = crypto::gen_random_wallet_id(rng);
+            if !ids.contains(&id) {
+                return Ok(id);
+            }
+            log::warn!("Generated wallet id {} is already taken, trying again", id);
+        }
+
+        Err(Error::WalletIdCollision(constants::MAX_WALLET_ID_ATTEMPTS))
+    }
+
     /// Get a wallet's salt and IV based on its provided ID
-    pub fn wallet_salt_and_iv(&self, id: &str) -> Result<(Vec<u8>, Vec<u8>)> {
-        let ids: Vec<String> = self.db.get_or_default(&keys::wallet_ids())?;
+    pub fn wallet_salt_and_iv(&self, id: &model::WalletId) -> Result<(Vec<u8>, Vec<u8>)> {
+        let ids: Vec<model::WalletId> = self.db.get_or_default(&keys::wallet_ids())?;
         // This check is necessary because without it, a deleted wallet could be unlocked
-        if !ids.contains(&id.to_string()) {
+        if !ids.contains(id) {
             Err(Error::WalletNotFound)
         } else {
             let salt = self.db.get(&keys::wallet_id_salt(id))?;
@@ -1,5 +1,8 @@
 use std::{cell::RefCell, rc::Rc};
 
+use witnet_crypto::mnemonic;
+use witnet_data_structures::chain::CheckpointBeacon;
+
 use super::*;
 
 pub fn wallets(data: Option<HashMap<Vec<u8>, Vec<u8>>>) -> (Wallets<db::HashMapDb>, db::HashMapDb) {
@@ -9,3 +12,66 @@ pub fn wallets(data: Option<HashMap<Vec<u8>, Vec<u8>>>) -> (Wallets<db::HashMapD
 
     (wallets, db)
 }
+
+pub fn wallet_id(id: &str) -> model::WalletId {
+    id.parse().unwrap()
+}
+
+/// Create a wallet from a new random seed, but with the given seed fingerprint
+pub fn create_wallet(
+    wallets: &Wallets<db::HashMapDb>,
+    db: &db::HashMapDb,
+    id: &model::WalletId,
+    seed_fingerprint: &str,
+) {
+    create_wallet_inner(wallets, db, id, seed_fingerprint, false)
+}
+
+/// Create a wallet from a new random seed, waiting for its seed backup to be confirmed
+pub fn create_pending_wallet(
+    wallets: &Wallets<db::HashMapDb>,
+    db: &db::HashMapDb,
+    id: &model::WalletId,
+) {
+    create_wallet_inner(wallets, db, id, "a-seed-fingerprint", true)
+}
+
+fn create_wallet_inner(
+    wallets: &Wallets<db::HashMapDb>,
+    db: &db::HashMapDb,
+    id: &model::WalletId,
+    seed_fingerprint: &str,
+    backup_confirmation: bool,
+) {
+    let mnemonic = mnemonic::MnemonicGen::new()
+        .with_len(mnemonic::Length::Words12)
+        .generate();
+    let seed_backup = if backup_confirmation {
+        Some(types::Secret::new(mnemonic.words()))
+    } else {
+        None
+    };
+    let master_key =
+        crypto::gen_master_key("", b"Bitcoin seed", &types::SeedSource::Mnemonics(mnemonic))
+            .unwrap();
+    let account = account::gen_account(&model::KeyDerivation::LEGACY, 0, &master_key).unwrap();
+
+    wallets
+        .create(
+            db,
+            types::CreateWalletData {
+                id,
+                seed_fingerprint: seed_fingerprint.to_string(),
+                name: None,
+                description: None,
+                iv: vec![],
+                salt: vec![],
+                account: &account,
+                master_key: None,
+                birth_date: CheckpointBeacon::default(),
+                seed_backup,
+                key_derivation: model::KeyDerivation::LEGACY,
+            },
+        )
+        .unwrap();
+}
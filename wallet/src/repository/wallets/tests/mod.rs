@@ -9,7 +9,7 @@ mod factories;
 fn test_wallet_infos_when_no_wallets() {
     let (wallets, _db) = factories::wallets(None);
 
-    let infos = wallets.infos().unwrap();
+    let infos = wallets.infos(false, 0, u32::MAX).unwrap().infos;
 
     assert!(infos.is_empty());
 }
@@ -17,21 +17,24 @@ fn test_wallet_infos_when_no_wallets() {
 #[test]
 fn test_wallet_infos_when_wallets() {
     let (wallets, db) = factories::wallets(Some(HashMap::new()));
-    db.put(&keys::wallet_ids(), vec!["a-wallet-id".to_string()])
-        .unwrap();
+    db.put(
+        &keys::wallet_ids(),
+        vec![factories::wallet_id("a-wallet-id")],
+    )
+    .unwrap();
 
-    let infos = wallets.infos().unwrap();
+    let infos = wallets.infos(false, 0, u32::MAX).unwrap().infos;
 
     assert_eq!(1, infos.len());
 }
 
 #[test]
 fn test_update_wallet_info() {
-    let id = "a-wallet-id".to_string();
+    let id = factories::wallet_id("a-wallet-id");
     let (wallets, db) = factories::wallets(Some(HashMap::new()));
     db.put(&keys::wallet_ids(), vec![id.clone()]).unwrap();
 
-    let wallet_info = &wallets.infos().unwrap()[0];
+    let wallet_info = &wallets.infos(false, 0, u32::MAX).unwrap().infos[0];
 
     assert!(wallet_info.name.is_none());
     assert!(!db.contains(&keys::wallet_id_name(&id)).unwrap());
@@ -40,8 +43,437 @@ fn test_update_wallet_info() {
 
     wallets.update_info(&id, name.clone()).unwrap();
 
-    let wallet_info = &wallets.infos().unwrap()[0];
+    let wallet_info = &wallets.infos(false, 0, u32::MAX).unwrap().infos[0];
 
     assert_eq!(name, wallet_info.name);
     assert_eq!(name, db.get_opt(&keys::wallet_id_name(&id)).unwrap());
 }
+
+/// Wallets DB with 25 named wallets, the name of `wallet-12` being corrupted
+fn wallets_with_corrupted_info() -> (Wallets<db::HashMapDb>, db::HashMapDb) {
+    let ids: Vec<model::WalletId> = (0..25)
+        .map(|i| factories::wallet_id(&format!("wallet-{:02}", i)))
+        .collect();
+    let mut data = HashMap::new();
+    data.insert(
+        keys::wallet_ids().as_ref().to_vec(),
+        bincode::serialize(&ids).unwrap(),
+    );
+    for id in &ids {
+        let name = if id.as_str() == "wallet-12" {
+            vec![0xff]
+        } else {
+            bincode::serialize(&format!("Name of {}", id)).unwrap()
+        };
+        data.insert(keys::wallet_id_name(id).as_ref().to_vec(), name);
+    }
+
+    factories::wallets(Some(data))
+}
+
+#[test]
+fn test_wallet_infos_pagination() {
+    let (wallets, _db) = wallets_with_corrupted_info();
+
+    let page = wallets.infos(false, 20, 10).unwrap();
+
+    assert_eq!(25, page.total);
+    let ids: Vec<_> = page.infos.iter().map(|info| info.id.as_str()).collect();
+    assert_eq!(
+        vec![
+            "wallet-20",
+            "wallet-21",
+            "wallet-22",
+            "wallet-23",
+            "wallet-24"
+        ],
+        ids
+    );
+    assert_eq!(Some("Name of wallet-20".to_string()), page.infos[0].name);
+    assert!(page.warnings.is_empty());
+}
+
+#[test]
+fn test_wallet_infos_skip_corrupted_records() {
+    let (wallets, _db) = wallets_with_corrupted_info();
+
+    let page = wallets.infos(false, 10, 5).unwrap();
+
+    assert_eq!(25, page.total);
+    let ids: Vec<_> = page.infos.iter().map(|info| info.id.as_str()).collect();
+    assert_eq!(
+        vec!["wallet-10", "wallet-11", "wallet-13", "wallet-14"],
+        ids
+    );
+    assert_eq!(1, page.warnings.len());
+    assert!(page.warnings[0].contains("wallet-12"));
+}
+
+#[test]
+fn test_wallet_infos_only_decode_requested_page() {
+    let (wallets, _db) = wallets_with_corrupted_info();
+
+    // The corrupted record is not in the page, so it is never decoded
+    let page = wallets.infos(false, 0, 10).unwrap();
+
+    assert_eq!(10, page.infos.len());
+    assert!(page.warnings.is_empty());
+}
+
+#[test]
+fn test_wallet_infos_reads_are_bounded_by_the_page() {
+    let (wallets, db) = wallets_with_corrupted_info();
+    let reads_of = |include_pending, offset| {
+        let reads = db.reads();
+        let page = wallets.infos(include_pending, offset, 5).unwrap();
+        assert_eq!(25, page.total);
+
+        db.reads() - reads
+    };
+
+    // The list of ids, and the records of the wallets in the page
+    assert!(reads_of(true, 20) <= 1 + 4 * 5);
+    // Leaving out the pending wallets checks all of them, but only the first time
+    assert!(reads_of(false, 0) <= 1 + 25 + 3 * 5);
+    assert!(reads_of(false, 0) <= 1 + 2 * 5);
+}
+
+#[test]
+fn test_wallet_infos_cache_is_invalidated_on_update() {
+    let id = &factories::wallet_id("a-wallet-id");
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    factories::create_wallet(&wallets, &db, id, "a-seed-fingerprint");
+    let name = |wallets: &Wallets<db::HashMapDb>| {
+        wallets.infos(false, 0, u32::MAX).unwrap().infos[0]
+            .name
+            .clone()
+    };
+    assert_eq!(None, name(&wallets));
+
+    // Listing again does not read the database, which is only written through `update_info`
+    db.put(&keys::wallet_id_name(id), "Outdated".to_string())
+        .unwrap();
+    assert_eq!(None, name(&wallets));
+
+    wallets
+        .update_info(id, Some("Testing".to_string()))
+        .unwrap();
+    assert_eq!(Some("Testing".to_string()), name(&wallets));
+}
+
+#[test]
+fn test_seed_fingerprint_is_salted() {
+    let (wallets, _db) = factories::wallets(None);
+    let (other_wallets, _other_db) = factories::wallets(None);
+
+    let fingerprint = wallets.seed_fingerprint("a-seed-id").unwrap();
+
+    assert_eq!(fingerprint, wallets.seed_fingerprint("a-seed-id").unwrap());
+    assert_ne!(
+        fingerprint,
+        wallets.seed_fingerprint("another-seed-id").unwrap()
+    );
+    assert_ne!(
+        fingerprint,
+        other_wallets.seed_fingerprint("a-seed-id").unwrap()
+    );
+}
+
+#[test]
+fn test_duplicate_seed_detected_after_migration() {
+    // Wallets created before seed fingerprints existed have the id derived from their seed
+    let legacy_id = factories::wallet_id("a-legacy-wallet-id");
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    db.put(&keys::wallet_ids(), vec![legacy_id.clone()])
+        .unwrap();
+    let fingerprint = wallets.seed_fingerprint(&legacy_id).unwrap();
+    assert!(wallets.wallets_with_seed(&fingerprint).unwrap().is_empty());
+
+    assert_eq!(wallets.migrate_seed_fingerprints().unwrap(), 1);
+    assert_eq!(wallets.migrate_seed_fingerprints().unwrap(), 0);
+
+    assert_eq!(
+        Some(fingerprint.clone()),
+        db.get_opt(&keys::wallet_id_seed_fingerprint(&legacy_id))
+            .unwrap()
+    );
+    assert_eq!(
+        vec![legacy_id.clone()],
+        wallets.wallets_with_seed(&fingerprint).unwrap()
+    );
+    let mut rng = rand::rngs::OsRng;
+    match wallets.new_wallet_id(&mut rng, &fingerprint, false, false) {
+        Err(Error::WalletAlreadyExists(id)) => assert_eq!(id, legacy_id.as_str()),
+        other => panic!("Expected WalletAlreadyExists, got {:?}", other),
+    }
+    assert_eq!(
+        legacy_id,
+        wallets
+            .new_wallet_id(&mut rng, &fingerprint, true, false)
+            .unwrap()
+    );
+}
+
+#[test]
+fn test_two_wallets_from_one_seed() {
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    let mut rng = rand::rngs::OsRng;
+    let fingerprint = wallets.seed_fingerprint("a-seed-id").unwrap();
+
+    let first_id = wallets
+        .new_wallet_id(&mut rng, &fingerprint, false, false)
+        .unwrap();
+    assert_ne!(first_id.as_str(), "a-seed-id");
+    assert!(!first_id.is_legacy());
+    factories::create_wallet(&wallets, &db, &first_id, &fingerprint);
+
+    assert!(matches!(
+        wallets.new_wallet_id(&mut rng, &fingerprint, false, false),
+        Err(Error::WalletAlreadyExists(_))
+    ));
+    let second_id = wallets
+        .new_wallet_id(&mut rng, &fingerprint, false, true)
+        .unwrap();
+    assert_ne!(first_id, second_id);
+    factories::create_wallet(&wallets, &db, &second_id, &fingerprint);
+
+    assert_eq!(2, wallets.infos(false, 0, u32::MAX).unwrap().infos.len());
+    assert_eq!(
+        vec![first_id, second_id],
+        wallets.wallets_with_seed(&fingerprint).unwrap()
+    );
+}
+
+#[test]
+fn test_pending_wallet_excluded_from_infos() {
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    factories::create_wallet(
+        &wallets,
+        &db,
+        &factories::wallet_id("a-wallet-id"),
+        "a-seed-fingerprint",
+    );
+    factories::create_pending_wallet(&wallets, &db, &factories::wallet_id("a-pending-wallet-id"));
+
+    let infos = wallets.infos(false, 0, u32::MAX).unwrap().infos;
+    assert_eq!(1, infos.len());
+    assert_eq!("a-wallet-id", infos[0].id.as_str());
+    assert!(!infos[0].pending_confirmation);
+
+    let infos = wallets.infos(true, 0, u32::MAX).unwrap().infos;
+    assert_eq!(2, infos.len());
+    assert_eq!("a-pending-wallet-id", infos[1].id.as_str());
+    assert!(infos[1].pending_confirmation);
+}
+
+#[test]
+fn test_failed_seed_backup_confirmations() {
+    let id = &factories::wallet_id("a-pending-wallet-id");
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    factories::create_pending_wallet(&wallets, &db, id);
+
+    assert_eq!(Some(0), wallets.pending_confirmation(id).unwrap());
+    assert_eq!(1, wallets.record_failed_confirmation(id).unwrap());
+    assert_eq!(2, wallets.record_failed_confirmation(id).unwrap());
+    assert_eq!(Some(2), wallets.pending_confirmation(id).unwrap());
+
+    wallets.delete(id.clone()).unwrap();
+
+    assert_eq!(None, wallets.pending_confirmation(id).unwrap());
+    assert!(wallets.infos(true, 0, u32::MAX).unwrap().infos.is_empty());
+}
+
+#[test]
+fn test_confirmed_wallet_included_in_infos() {
+    let id = &factories::wallet_id("a-pending-wallet-id");
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    factories::create_pending_wallet(&wallets, &db, id);
+    wallets.record_failed_confirmation(id).unwrap();
+
+    wallets.confirm(id).unwrap();
+
+    assert_eq!(None, wallets.pending_confirmation(id).unwrap());
+    let infos = wallets.infos(false, 0, u32::MAX).unwrap().infos;
+    assert_eq!(1, infos.len());
+    assert!(!infos[0].pending_confirmation);
+}
+
+#[test]
+fn test_pinned_wallet_reported_in_infos() {
+    let id = &factories::wallet_id("a-wallet-id");
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    factories::create_wallet(&wallets, &db, id, "a-seed-fingerprint");
+    let pinned_to = |wallets: &Wallets<db::HashMapDb>| {
+        wallets.infos(false, 0, u32::MAX).unwrap().infos[0].sync_target_epoch
+    };
+
+    assert_eq!(None, pinned_to(&wallets));
+
+    wallets.set_sync_target_epoch(id, Some(42)).unwrap();
+    assert_eq!(Some(42), wallets.sync_target_epoch(id).unwrap());
+    assert_eq!(Some(42), pinned_to(&wallets));
+
+    wallets.set_sync_target_epoch(id, None).unwrap();
+    assert_eq!(None, wallets.sync_target_epoch(id).unwrap());
+    assert_eq!(None, pinned_to(&wallets));
+
+    // The pin is removed along with the wallet
+    wallets.set_sync_target_epoch(id, Some(42)).unwrap();
+    wallets.delete(id.clone()).unwrap();
+    assert_eq!(None, wallets.sync_target_epoch(id).unwrap());
+}
+
+#[test]
+fn test_key_derivation_reported_in_infos() {
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    factories::create_wallet(
+        &wallets,
+        &db,
+        &factories::wallet_id("a-wallet-id"),
+        "a-seed-fingerprint",
+    );
+    db.put(
+        &keys::wallet_ids(),
+        vec![
+            factories::wallet_id("a-wallet-id"),
+            factories::wallet_id("legacy-wallet-id"),
+        ],
+    )
+    .unwrap();
+
+    let page = wallets.infos(false, 0, u32::MAX).unwrap();
+
+    // Wallets created before the key derivation was recorded use the legacy one
+    for info in page.infos {
+        assert_eq!(witnet_crypto::key::KeySpec::Wip3, info.key_spec);
+        assert_eq!("m/3'/4919'", info.base_path);
+    }
+}
+
+#[test]
+fn test_wallet_infos_skip_unknown_key_spec() {
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    factories::create_wallet(
+        &wallets,
+        &db,
+        &factories::wallet_id("a-wallet-id"),
+        "a-seed-fingerprint",
+    );
+    db.put(
+        &keys::wallet_id_key_derivation(&factories::wallet_id("a-wallet-id")),
+        model::KeyDerivationRecord {
+            key_spec: "wip99".to_string(),
+            coin_type: 4919,
+        },
+    )
+    .unwrap();
+
+    let page = wallets.infos(false, 0, u32::MAX).unwrap();
+
+    assert!(page.infos.is_empty());
+    assert_eq!(1, page.warnings.len());
+    assert!(page.warnings[0].contains("requires newer software"));
+    assert!(matches!(
+        wallets.key_derivation(&factories::wallet_id("a-wallet-id")),
+        Err(Error::UnsupportedKeySpec(spec)) if spec == "wip99"
+    ));
+}
+
+#[test]
+fn test_new_wallet_id_retries_on_collision() {
+    use rand::SeedableRng as _;
+
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    let fingerprint = wallets.seed_fingerprint("a-seed-id").unwrap();
+    let rng = rand::rngs::StdRng::seed_from_u64(42);
+
+    // The first id that the generator yields is already taken by another wallet
+    let taken_id = crypto::gen_random_wallet_id(&mut rng.clone());
+    factories::create_wallet(&wallets, &db, &taken_id, "another-seed-fingerprint");
+
+    let id = wallets
+        .new_wallet_id(&mut rng.clone(), &fingerprint, false, false)
+        .unwrap();
+
+    assert_ne!(taken_id, id);
+    assert!(!id.is_legacy());
+    assert_eq!(1, wallets.infos(false, 0, u32::MAX).unwrap().infos.len());
+}
+
+#[test]
+fn test_legacy_wallet_ids_are_listed() {
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    let legacy_ids: Vec<model::WalletId> = ["a-wallet-id", "Legacy_Wallet_01"]
+        .iter()
+        .map(|id| factories::wallet_id(id))
+        .collect();
+    db.put(&keys::wallet_ids(), &legacy_ids).unwrap();
+
+    let ids: Vec<_> = wallets
+        .infos(false, 0, u32::MAX)
+        .unwrap()
+        .infos
+        .into_iter()
+        .map(|info| info.id)
+        .collect();
+
+    assert_eq!(legacy_ids, ids);
+    assert!(ids.iter().all(model::WalletId::is_legacy));
+}
+
+#[test]
+fn test_migrate_schema_of_new_database_records_latest_version() {
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+
+    assert_eq!(wallets.migrate_schema().unwrap(), WALLETS_SCHEMA_VERSION);
+    assert_eq!(
+        db.get(&keys::wallets_schema_version()).unwrap(),
+        WALLETS_SCHEMA_VERSION
+    );
+    assert_eq!(
+        WALLETS_SCHEMA_VERSION,
+        schema::latest_version(&Wallets::<db::HashMapDb>::migrations())
+    );
+}
+
+#[test]
+fn test_migrate_schema_of_unversioned_database() {
+    // Databases written before the schema was versioned have wallets but no version
+    let legacy_id = factories::wallet_id("a-legacy-wallet-id");
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    db.put(&keys::wallet_ids(), vec![legacy_id.clone()])
+        .unwrap();
+
+    assert_eq!(wallets.migrate_schema().unwrap(), 0);
+    assert_eq!(
+        db.get(&keys::wallets_schema_version()).unwrap(),
+        WALLETS_SCHEMA_VERSION
+    );
+    let fingerprint = wallets.seed_fingerprint(&legacy_id).unwrap();
+    assert_eq!(
+        vec![legacy_id],
+        wallets.wallets_with_seed(&fingerprint).unwrap()
+    );
+
+    // Migrations are only run once
+    assert_eq!(wallets.migrate_schema().unwrap(), WALLETS_SCHEMA_VERSION);
+    assert_eq!(wallets.migrate_seed_fingerprints().unwrap(), 0);
+}
+
+#[test]
+fn test_migrate_schema_refuses_newer_versions() {
+    let (wallets, db) = factories::wallets(Some(HashMap::new()));
+    db.put(&keys::wallets_schema_version(), WALLETS_SCHEMA_VERSION + 1)
+        .unwrap();
+
+    match wallets.migrate_schema() {
+        Err(err @ Error::UnsupportedSchemaVersion { .. }) => {
+            assert!(err.to_string().contains("upgrade the wallet"))
+        }
+        other => panic!("Expected UnsupportedSchemaVersion, got {:?}", other),
+    }
+    assert_eq!(
+        db.get(&keys::wallets_schema_version()).unwrap(),
+        WALLETS_SCHEMA_VERSION + 1
+    );
+}
@@ -0,0 +1,268 @@
+//! Persistent outbox of the events of a wallet that are posted to its webhook.
+//!
+//! Events are delivered at least once: a delivery stays in the outbox until its endpoint accepts
+//! it, and failed ones are retried with an exponential backoff until they run out of attempts and
+//! become dead letters, which are kept until they are requeued or evicted by newer ones.
+//!
+//! The outbox lives in the encrypted database of its wallet, with every delivery under a key of
+//! its own, and only the identifiers of the pending deliveries and dead letters in the indexes
+//! that are rewritten as they change.
+use std::sync::Mutex;
+
+use super::*;
+use crate::{
+    db::{Database, WriteBatch as _},
+    model,
+};
+
+#[cfg(test)]
+mod tests;
+
+pub struct WebhookOutbox<T> {
+    wallet_id: model::WalletId,
+    db: T,
+    /// Maximum number of deliveries in the outbox, and also of dead letters.
+    capacity: usize,
+    /// Failed attempts after which a delivery becomes a dead letter.
+    max_attempts: u32,
+    /// Milliseconds to wait before the first retry, which doubles after every failed attempt.
+    retry_delay: i64,
+    mutex: Mutex<()>,
+}
+
+impl<T: Database> WebhookOutbox<T> {
+    pub fn new(
+        wallet_id: model::WalletId,
+        db: T,
+        capacity: usize,
+        max_attempts: u32,
+        retry_delay: i64,
+    ) -> Self {
+        Self {
+            wallet_id,
+            db,
+            capacity,
+            max_attempts: max_attempts.max(1),
+            retry_delay,
+            mutex: Default::default(),
+        }
+    }
+
+    /// Wallet whose webhook the deliveries are posted to.
+    pub fn wallet_id(&self) -> &model::WalletId {
+        &self.wallet_id
+    }
+
+    /// Queue a signed payload for posting it to the webhook of the wallet as soon as possible.
+    ///
+    /// If the outbox is full, the delivery becomes a dead letter right away, so that it can still
+    /// be requeued once the endpoint catches up.
+    pub fn enqueue(
+        &self,
+        url: &str,
+        payload: String,
+        signature: String,
+        now: i64,
+    ) -> Result<model::WebhookDelivery> {
+        let _lock = self.mutex.lock()?;
+        let id = self.db.get_or_default(&keys::webhook_next_delivery_id())?;
+        let mut outbox = self.db.get_or_default(&keys::webhook_outbox())?;
+        let mut batch = self.db.batch();
+
+        let mut delivery = model::WebhookDelivery {
+            id,
+            wallet_id: self.wallet_id.clone(),
+            url: url.to_string(),
+            payload,
+            signature,
+            created_at: now,
+            attempts: 0,
+            next_attempt_at: now,
+            last_error: None,
+        };
+        if outbox.len() < self.capacity {
+            outbox.push(model::WebhookOutboxEntry {
+                id,
+                next_attempt_at: now,
+            });
+            batch.put(&keys::webhook_delivery(id), &delivery)?;
+            batch.put(&keys::webhook_outbox(), outbox)?;
+        } else {
+            log::warn!(
+                "Webhook outbox is full, delivery {} of wallet {} is dead-lettered",
+                id,
+                self.wallet_id
+            );
+            delivery.last_error = Some("webhook outbox is full".to_string());
+            batch.put(&keys::webhook_delivery(id), &delivery)?;
+            let mut dead_letters = self.db.get_or_default(&keys::webhook_dead_letters())?;
+            self.push_dead_letter(&mut batch, &mut dead_letters, id)?;
+            batch.put(&keys::webhook_dead_letters(), dead_letters)?;
+        }
+        batch.put(&keys::webhook_next_delivery_id(), id + 1)?;
+        self.db.write(batch)?;
+
+        Ok(delivery)
+    }
+
+    /// Up to `limit` deliveries whose next attempt is due at `now`, from the oldest.
+    pub fn due(&self, now: i64, limit: usize) -> Result<Vec<model::WebhookDelivery>> {
+        let _lock = self.mutex.lock()?;
+        let outbox = self.db.get_or_default(&keys::webhook_outbox())?;
+
+        outbox
+            .into_iter()
+            .filter(|entry| entry.next_attempt_at <= now)
+            .take(limit)
+            .map(|entry| Ok(self.db.get(&keys::webhook_delivery(entry.id))?))
+            .collect()
+    }
+
+    /// Timestamp in milliseconds of the next attempt that is due, if there are deliveries left.
+    pub fn next_attempt_at(&self) -> Result<Option<i64>> {
+        let _lock = self.mutex.lock()?;
+        let outbox = self.db.get_or_default(&keys::webhook_outbox())?;
+
+        Ok(outbox.iter().map(|entry| entry.next_attempt_at).min())
+    }
+
+    /// Remove a delivery that its endpoint accepted.
+    pub fn delivered(&self, id: u64) -> Result<()> {
+        let _lock = self.mutex.lock()?;
+        let mut outbox = self.db.get_or_default(&keys::webhook_outbox())?;
+        outbox.retain(|entry| entry.id != id);
+        let mut batch = self.db.batch();
+        batch.put(&keys::webhook_outbox(), outbox)?;
+        batch.delete(&keys::webhook_delivery(id))?;
+        self.db.write(batch)?;
+
+        Ok(())
+    }
+
+    /// Record a failed attempt of a delivery, scheduling its retry or turning it into a dead letter
+    /// if it ran out of attempts. Returns whether it was dead-lettered.
+    pub fn failed(&self, id: u64, error: String, now: i64) -> Result<bool> {
+        let _lock = self.mutex.lock()?;
+        let mut outbox = self.db.get_or_default(&keys::webhook_outbox())?;
+        let position = match outbox.iter().position(|entry| entry.id == id) {
+            Some(position) => position,
+            None => return Ok(false),
+        };
+        let mut delivery = self.db.get(&keys::webhook_delivery(id))?;
+        let mut batch = self.db.batch();
+
+        delivery.attempts += 1;
+        delivery.last_error = Some(error);
+        if delivery.attempts < self.max_attempts {
+            let backoff = 1i64
+                .checked_shl(delivery.attempts - 1)
+                .unwrap_or(i64::MAX)
+                .saturating_mul(self.retry_delay);
+            delivery.next_attempt_at = now.saturating_add(backoff);
+            outbox[position].next_attempt_at = delivery.next_attempt_at;
+            batch.put(&keys::webhook_delivery(id), &delivery)?;
+            batch.put(&keys::webhook_outbox(), outbox)?;
+            self.db.write(batch)?;
+
+            return Ok(false);
+        }
+
+        log::warn!(
+            "Delivery {} to the webhook of wallet {} failed {} times, it is dead-lettered",
+            delivery.id,
+            delivery.wallet_id,
+            delivery.attempts
+        );
+        outbox.remove(position);
+        batch.put(&keys::webhook_delivery(id), &delivery)?;
+        let mut dead_letters = self.db.get_or_default(&keys::webhook_dead_letters())?;
+        self.push_dead_letter(&mut batch, &mut dead_letters, id)?;
+        batch.put(&keys::webhook_outbox(), outbox)?;
+        batch.put(&keys::webhook_dead_letters(), dead_letters)?;
+        self.db.write(batch)?;
+
+        Ok(true)
+    }
+
+    /// Pending deliveries and dead letters of the wallet.
+    pub fn deliveries(&self) -> Result<model::WebhookDeliveries> {
+        let _lock = self.mutex.lock()?;
+        let outbox = self.db.get_or_default(&keys::webhook_outbox())?;
+        let dead_letters = self.db.get_or_default(&keys::webhook_dead_letters())?;
+
+        Ok(model::WebhookDeliveries {
+            pending: outbox
+                .iter()
+                .map(|entry| self.db.get(&keys::webhook_delivery(entry.id)))
+                .collect::<std::result::Result<_, _>>()?,
+            dead_letters: dead_letters
+                .iter()
+                .map(|id| self.db.get(&keys::webhook_delivery(*id)))
+                .collect::<std::result::Result<_, _>>()?,
+        })
+    }
+
+    /// Move dead letters back to the outbox, with all of their attempts left. Only the ones in
+    /// `ids` are requeued, or all of them if it is `None`, as long as they fit in the outbox.
+    /// Returns how many were requeued.
+    pub fn requeue(&self, ids: Option<&[u64]>, now: i64) -> Result<usize> {
+        let _lock = self.mutex.lock()?;
+        let mut outbox = self.db.get_or_default(&keys::webhook_outbox())?;
+        let dead_letters = self.db.get_or_default(&keys::webhook_dead_letters())?;
+        let mut batch = self.db.batch();
+
+        let mut requeued = 0;
+        let mut remaining = Vec::with_capacity(dead_letters.len());
+        for id in dead_letters {
+            let selected = ids.map_or(true, |ids| ids.contains(&id));
+            if selected && outbox.len() < self.capacity {
+                let mut delivery = self.db.get(&keys::webhook_delivery(id))?;
+                delivery.attempts = 0;
+                delivery.next_attempt_at = now;
+                batch.put(&keys::webhook_delivery(id), &delivery)?;
+                outbox.push(model::WebhookOutboxEntry {
+                    id,
+                    next_attempt_at: now,
+                });
+                requeued += 1;
+            } else {
+                remaining.push(id);
+            }
+        }
+
+        if requeued > 0 {
+            // Keep the outbox sorted by identifier, so that the oldest events are posted first
+            outbox.sort_by_key(|entry| entry.id);
+            batch.put(&keys::webhook_outbox(), outbox)?;
+            batch.put(&keys::webhook_dead_letters(), remaining)?;
+            self.db.write(batch)?;
+        }
+
+        Ok(requeued)
+    }
+
+    /// Add a dead letter, evicting the oldest ones and deleting their deliveries if there are too
+    /// many.
+    fn push_dead_letter(
+        &self,
+        batch: &mut T::WriteBatch,
+        dead_letters: &mut Vec<u64>,
+        id: u64,
+    ) -> Result<()> {
+        dead_letters.push(id);
+        dead_letters.sort_unstable();
+        if dead_letters.len() > self.capacity {
+            let evicted = dead_letters.len() - self.capacity;
+            log::warn!(
+                "Evicting {} webhook dead letters of wallet {}",
+                evicted,
+                self.wallet_id
+            );
+            for id in dead_letters.drain(..evicted) {
+                batch.delete(&keys::webhook_delivery(id))?;
+            }
+        }
+
+        Ok(())
+    }
+}
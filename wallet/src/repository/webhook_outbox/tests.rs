@@ -0,0 +1,135 @@
+use super::*;
+use crate::db::HashMapDb;
+
+const RETRY_DELAY: i64 = 1_000;
+
+fn outbox(db: HashMapDb, capacity: usize, max_attempts: u32) -> WebhookOutbox<HashMapDb> {
+    WebhookOutbox::new(
+        "wallet".parse().unwrap(),
+        db,
+        capacity,
+        max_attempts,
+        RETRY_DELAY,
+    )
+}
+
+fn enqueue(outbox: &WebhookOutbox<HashMapDb>, now: i64) -> u64 {
+    outbox
+        .enqueue(
+            "http://localhost/hook",
+            "{}".to_string(),
+            "signature".to_string(),
+            now,
+        )
+        .unwrap()
+        .id
+}
+
+fn due_ids(outbox: &WebhookOutbox<HashMapDb>, now: i64) -> Vec<u64> {
+    outbox
+        .due(now, usize::MAX)
+        .unwrap()
+        .into_iter()
+        .map(|delivery| delivery.id)
+        .collect()
+}
+
+#[test]
+fn test_retries_back_off_until_dead_letter() {
+    let outbox = outbox(HashMapDb::default(), 10, 3);
+    let id = enqueue(&outbox, 0);
+    assert_eq!(due_ids(&outbox, 0), vec![id]);
+
+    // The delay doubles after every failed attempt
+    assert!(!outbox.failed(id, "status 500".to_string(), 0).unwrap());
+    assert_eq!(due_ids(&outbox, RETRY_DELAY - 1), Vec::<u64>::new());
+    assert_eq!(outbox.next_attempt_at().unwrap(), Some(RETRY_DELAY));
+    assert!(!outbox
+        .failed(id, "status 500".to_string(), RETRY_DELAY)
+        .unwrap());
+    assert_eq!(outbox.next_attempt_at().unwrap(), Some(3 * RETRY_DELAY));
+
+    // The last attempt turns the delivery into a dead letter
+    assert!(outbox
+        .failed(id, "status 503".to_string(), 3 * RETRY_DELAY)
+        .unwrap());
+    assert_eq!(outbox.next_attempt_at().unwrap(), None);
+    let deliveries = outbox.deliveries().unwrap();
+    assert!(deliveries.pending.is_empty());
+    assert_eq!(deliveries.dead_letters.len(), 1);
+    assert_eq!(deliveries.dead_letters[0].attempts, 3);
+    assert_eq!(
+        deliveries.dead_letters[0].last_error,
+        Some("status 503".to_string())
+    );
+}
+
+#[test]
+fn test_requeue_dead_letters() {
+    let outbox = outbox(HashMapDb::default(), 10, 1);
+    let first = enqueue(&outbox, 0);
+    let second = enqueue(&outbox, 0);
+    for id in [first, second] {
+        assert!(outbox.failed(id, "timed out".to_string(), 0).unwrap());
+    }
+
+    // Only the selected dead letters are requeued
+    assert_eq!(outbox.requeue(Some(&[second, 100][..]), 5).unwrap(), 1);
+    assert_eq!(due_ids(&outbox, 5), vec![second]);
+    assert_eq!(outbox.deliveries().unwrap().pending[0].attempts, 0);
+
+    assert_eq!(outbox.requeue(None, 5).unwrap(), 1);
+    assert_eq!(due_ids(&outbox, 5), vec![first, second]);
+    assert!(outbox.deliveries().unwrap().dead_letters.is_empty());
+}
+
+#[test]
+fn test_full_outbox_dead_letters_new_deliveries() {
+    let db = HashMapDb::default();
+    let outbox = outbox(db.clone(), 1, 3);
+    let first = enqueue(&outbox, 0);
+    let second = enqueue(&outbox, 0);
+
+    let deliveries = outbox.deliveries().unwrap();
+    assert_eq!(deliveries.pending[0].id, first);
+    assert_eq!(deliveries.dead_letters[0].id, second);
+
+    // Requeueing does not overflow the outbox either
+    assert_eq!(outbox.requeue(None, 0).unwrap(), 0);
+    outbox.delivered(first).unwrap();
+    assert_eq!(outbox.requeue(None, 0).unwrap(), 1);
+
+    // Deliveries outlive the outbox, and their identifiers are never reused
+    let outbox = self::outbox(db, 1, 3);
+    assert_eq!(due_ids(&outbox, 0), vec![second]);
+    outbox.delivered(second).unwrap();
+    assert!(enqueue(&outbox, 0) > second);
+}
+
+#[test]
+fn test_deliveries_are_kept_under_keys_of_their_own() {
+    let db = HashMapDb::default();
+    let outbox = outbox(db.clone(), 1, 1);
+    let delivered = enqueue(&outbox, 0);
+    let evicted = enqueue(&outbox, 0);
+    outbox.delivered(delivered).unwrap();
+    let dead_letter = enqueue(&outbox, 0);
+    assert!(outbox
+        .failed(dead_letter, "timed out".to_string(), 0)
+        .unwrap());
+
+    // Only the identifiers are indexed, and the deliveries that left the outbox are deleted
+    assert!(db.get(&keys::webhook_outbox()).unwrap().is_empty());
+    assert_eq!(
+        db.get(&keys::webhook_dead_letters()).unwrap(),
+        vec![dead_letter]
+    );
+    assert!(!db.contains(&keys::webhook_delivery(delivered)).unwrap());
+    assert!(!db.contains(&keys::webhook_delivery(evicted)).unwrap());
+    assert_eq!(
+        db.get(&keys::webhook_delivery(dead_letter))
+            .unwrap()
+            .attempts,
+        1
+    );
+}
@@ -0,0 +1,331 @@
+//! Limits on the requests that each session can have in flight at the same time.
+//!
+//! The queue of the requests of clients is shared by every session, so a single client issuing
+//! many concurrent calls could take all of it and starve the other sessions. Each session can
+//! therefore only have so many requests in flight, with a lower limit for the heavy methods
+//! (running data requests, exporting transactions, resynchronizing) than for the rest of them.
+//! Heavy requests are also limited across all the sessions, as a second line of defense against
+//! many sessions being heavy at once. Requests past any limit fail right away with
+//! `TooManyRequests`.
+use std::{
+    collections::HashMap,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use failure::Fail;
+use serde::Serialize;
+
+use crate::{metrics, types::SessionId};
+
+/// Methods that are classified as heavy, because they can keep the worker busy for long.
+pub const HEAVY_METHODS: &[&str] = &[
+    "benchmark_retrievals",
+    "check_integrity",
+    "create_diagnostics_bundle",
+    "get_balance_history",
+    "get_transactions_stream",
+    "import_address_metadata",
+    "prune_wallet_data",
+    "resync_wallet",
+    "run_rad_request",
+    "simulate_data_request",
+];
+
+/// Classes of the requests of clients, each of them with its own limits.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RequestClass {
+    Heavy,
+    Light,
+}
+
+impl RequestClass {
+    /// Class of the requests to `method`.
+    pub fn of(method: &str) -> Self {
+        if HEAVY_METHODS.contains(&method) {
+            RequestClass::Heavy
+        } else {
+            RequestClass::Light
+        }
+    }
+}
+
+impl fmt::Display for RequestClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RequestClass::Heavy => write!(f, "heavy"),
+            RequestClass::Light => write!(f, "light"),
+        }
+    }
+}
+
+/// Whether a limit applies to the requests of a session or to those of all the sessions.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitScope {
+    Session,
+    Global,
+}
+
+impl fmt::Display for LimitScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitScope::Session => write!(f, "in the session"),
+            LimitScope::Global => write!(f, "in all the sessions"),
+        }
+    }
+}
+
+/// A request was refused because too many others of its class were in flight.
+#[derive(Clone, Debug, PartialEq, Eq, Fail)]
+#[fail(
+    display = "too many {} requests in flight {} ({} of at most {})",
+    class, scope, in_flight, limit
+)]
+pub struct TooManyRequests {
+    pub class: RequestClass,
+    pub scope: LimitScope,
+    pub in_flight: usize,
+    pub limit: usize,
+}
+
+/// Requests of a session that are in flight.
+#[derive(Debug, Default)]
+struct SessionRequests {
+    heavy: usize,
+    light: usize,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    sessions: HashMap<SessionId, SessionRequests>,
+    heavy: usize,
+    light: usize,
+    rejected: u64,
+}
+
+/// Count of the requests in flight of every session.
+#[derive(Debug)]
+pub struct RequestLimits {
+    session_limit: usize,
+    session_heavy_limit: usize,
+    heavy_limit: usize,
+    state: Mutex<State>,
+}
+
+impl RequestLimits {
+    /// Limits of `session_limit` light and `session_heavy_limit` heavy requests in flight for
+    /// each session, and of `heavy_limit` heavy requests for all of them.
+    pub fn new(session_limit: usize, session_heavy_limit: usize, heavy_limit: usize) -> Arc<Self> {
+        Arc::new(Self {
+            session_limit,
+            session_heavy_limit,
+            heavy_limit,
+            state: Mutex::default(),
+        })
+    }
+
+    /// Count a request in flight, unless it would go over a limit. Requests that are not made in a
+    /// session are only subject to the global limit.
+    pub fn try_enter(
+        self: &Arc<Self>,
+        session_id: Option<&SessionId>,
+        class: RequestClass,
+    ) -> Result<InFlight, TooManyRequests> {
+        let mut state = self.lock();
+
+        let session_in_flight = session_id
+            .and_then(|session_id| state.sessions.get(session_id))
+            .map_or(0, |session| match class {
+                RequestClass::Heavy => session.heavy,
+                RequestClass::Light => session.light,
+            });
+        let session_limit = match class {
+            RequestClass::Heavy => self.session_heavy_limit,
+            RequestClass::Light => self.session_limit,
+        };
+        let refused = if session_id.is_some() && session_in_flight >= session_limit {
+            Some(TooManyRequests {
+                class,
+                scope: LimitScope::Session,
+                in_flight: session_in_flight,
+                limit: session_limit,
+            })
+        } else if class == RequestClass::Heavy && state.heavy >= self.heavy_limit {
+            Some(TooManyRequests {
+                class,
+                scope: LimitScope::Global,
+                in_flight: state.heavy,
+                limit: self.heavy_limit,
+            })
+        } else {
+            None
+        };
+        if let Some(refused) = refused {
+            state.rejected += 1;
+
+            return Err(refused);
+        }
+
+        if let Some(session_id) = session_id {
+            let session = state.sessions.entry(session_id.clone()).or_default();
+            match class {
+                RequestClass::Heavy => session.heavy += 1,
+                RequestClass::Light => session.light += 1,
+            }
+        }
+        match class {
+            RequestClass::Heavy => state.heavy += 1,
+            RequestClass::Light => state.light += 1,
+        }
+
+        Ok(InFlight {
+            limits: self.clone(),
+            session_id: session_id.cloned(),
+            class,
+        })
+    }
+
+    pub fn metrics(&self) -> metrics::RequestsInFlight {
+        let state = self.lock();
+
+        metrics::RequestsInFlight {
+            heavy: state.heavy,
+            light: state.light,
+            heavy_limit: self.heavy_limit,
+            sessions: state.sessions.len(),
+            rejected: state.rejected,
+        }
+    }
+
+    fn leave(&self, session_id: Option<&SessionId>, class: RequestClass) {
+        let mut state = self.lock();
+
+        match class {
+            RequestClass::Heavy => state.heavy -= 1,
+            RequestClass::Light => state.light -= 1,
+        }
+        if let Some(session_id) = session_id {
+            if let Some(session) = state.sessions.get_mut(session_id) {
+                match class {
+                    RequestClass::Heavy => session.heavy -= 1,
+                    RequestClass::Light => session.light -= 1,
+                }
+                // Sessions are only tracked while they have requests in flight
+                if session.heavy == 0 && session.light == 0 {
+                    state.sessions.remove(session_id);
+                }
+            }
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("Request limits locks should only fail if poisoned")
+    }
+}
+
+/// A request counted as in flight, which stops being counted when this is dropped, whether the
+/// request was answered, failed or timed out.
+#[derive(Debug)]
+pub struct InFlight {
+    limits: Arc<RequestLimits>,
+    session_id: Option<SessionId>,
+    class: RequestClass,
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.limits.leave(self.session_id.as_ref(), self.class);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str) -> SessionId {
+        SessionId::from(id.to_string())
+    }
+
+    #[test]
+    fn sessions_are_limited_separately_by_class() {
+        let limits = RequestLimits::new(2, 1, 10);
+        let alice = session("alice-session");
+        let bob = session("bob-session");
+
+        let heavy = limits.try_enter(Some(&alice), RequestClass::Heavy).unwrap();
+        assert_eq!(
+            limits
+                .try_enter(Some(&alice), RequestClass::Heavy)
+                .unwrap_err(),
+            TooManyRequests {
+                class: RequestClass::Heavy,
+                scope: LimitScope::Session,
+                in_flight: 1,
+                limit: 1,
+            }
+        );
+        // Neither the light requests of the session nor the other sessions are affected
+        let light = limits.try_enter(Some(&alice), RequestClass::Light).unwrap();
+        let other = limits.try_enter(Some(&bob), RequestClass::Heavy).unwrap();
+        assert_eq!(
+            limits.metrics(),
+            metrics::RequestsInFlight {
+                heavy: 2,
+                light: 1,
+                heavy_limit: 10,
+                sessions: 2,
+                rejected: 1,
+            }
+        );
+
+        drop(heavy);
+        assert!(limits.try_enter(Some(&alice), RequestClass::Heavy).is_ok());
+        drop(light);
+        drop(other);
+        assert_eq!(limits.metrics().heavy, 0);
+        assert_eq!(limits.metrics().light, 0);
+        assert_eq!(limits.metrics().sessions, 0);
+    }
+
+    #[test]
+    fn heavy_requests_are_limited_across_sessions() {
+        let limits = RequestLimits::new(2, 2, 2);
+
+        let _first = limits
+            .try_enter(Some(&session("alice-session")), RequestClass::Heavy)
+            .unwrap();
+        let _second = limits.try_enter(None, RequestClass::Heavy).unwrap();
+        assert_eq!(
+            limits
+                .try_enter(Some(&session("bob-session")), RequestClass::Heavy)
+                .unwrap_err(),
+            TooManyRequests {
+                class: RequestClass::Heavy,
+                scope: LimitScope::Global,
+                in_flight: 2,
+                limit: 2,
+            }
+        );
+
+        // Light requests outside of sessions are not limited here
+        let light = (0..5)
+            .map(|_| limits.try_enter(None, RequestClass::Light))
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(limits.metrics().light, 5);
+        drop(light);
+        assert_eq!(limits.metrics().light, 0);
+    }
+
+    #[test]
+    fn methods_are_classified_explicitly() {
+        assert_eq!(RequestClass::of("run_rad_request"), RequestClass::Heavy);
+        assert_eq!(RequestClass::of("resync_wallet"), RequestClass::Heavy);
+        assert_eq!(RequestClass::of("get_balance"), RequestClass::Light);
+        assert_eq!(RequestClass::of("unknown_method"), RequestClass::Light);
+    }
+}
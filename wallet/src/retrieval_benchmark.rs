@@ -0,0 +1,289 @@
+//! Benchmarks of the data sources of data requests.
+//!
+//! Authors choosing the sources of a data request want to know which of them are fast and reliable
+//! from the point of view of a witness. A benchmark retrieves the sources several times, as
+//! witnesses would retrieve them but without aggregating their values, and summarizes how each of
+//! them did: how long it took, how often it failed, how large its responses were, and how many
+//! different values it returned, as a source that disagrees with itself also disagrees with the
+//! other witnesses.
+use std::time::{Duration, Instant};
+
+use witnet_data_structures::{
+    chain::{tapi::current_active_wips, RADRetrieve},
+    radon_report::{DataRequestMetadata, Stage},
+    witnessing::WitnessingConfig,
+};
+use witnet_rad::{run_retrieval_report, script::RadonScriptExecutionSettings, types::RadonTypes};
+
+use crate::{
+    deadline::{self, Deadline},
+    model,
+};
+
+/// A single retrieval of a data source.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Attempt {
+    /// Time until the retrieval finished or timed out
+    pub latency: Duration,
+    pub outcome: Outcome,
+}
+
+/// How a retrieval ended.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Outcome {
+    /// The source was retrieved and its script returned this value, as JSON.
+    Value {
+        value: String,
+        response_size: Option<usize>,
+    },
+    /// The source or its script failed with this error.
+    Failed(String),
+    /// The retrieval did not finish within the timeout.
+    TimedOut,
+}
+
+/// Retrieve a data source once, with the limits of the retrievals run by witnesses.
+///
+/// A retrieval that times out is dropped as soon as it does, which closes its connection.
+pub fn attempt(
+    retrieve: &RADRetrieve,
+    timeout: Option<Duration>,
+    witnessing: &WitnessingConfig<witnet_net::Uri>,
+) -> Attempt {
+    let started = Instant::now();
+
+    let finished = deadline::block_on_with_deadline(
+        timeout.map(Deadline::after).unwrap_or_default(),
+        "retrieval",
+        run_retrieval_report(
+            retrieve,
+            RadonScriptExecutionSettings::disable_all(),
+            current_active_wips(),
+            None,
+            &witnessing.secrets,
+            Some(DataRequestMetadata::simulated()),
+        ),
+    );
+    let outcome = match finished {
+        Err(_) => Outcome::TimedOut,
+        Ok(Err(error)) => Outcome::Failed(error.to_string()),
+        Ok(Ok(report)) => {
+            let response_size = match &report.context.stage {
+                Stage::Retrieval(metadata) => metadata.response_size,
+                _ => None,
+            };
+            match report.result {
+                RadonTypes::RadonError(error) => Outcome::Failed(error.to_string()),
+                value => match serde_json::to_string(&value) {
+                    Ok(value) => Outcome::Value {
+                        value,
+                        response_size,
+                    },
+                    Err(error) => Outcome::Failed(error.to_string()),
+                },
+            }
+        }
+    };
+
+    Attempt {
+        latency: started.elapsed(),
+        outcome,
+    }
+}
+
+/// Summarize the attempts to retrieve the data source with this URL.
+pub fn summarize(url: &str, attempts: &[Attempt], benchmarked_at: i64) -> model::SourceBenchmark {
+    let mut latencies = vec![];
+    let mut sizes = vec![];
+    let mut results = vec![];
+    let mut errors = vec![];
+    let mut timeouts = 0;
+
+    for attempt in attempts {
+        match &attempt.outcome {
+            Outcome::Value {
+                value,
+                response_size,
+            } => {
+                results.push(value.clone());
+                sizes.extend(response_size.map(|size| size as u64));
+            }
+            Outcome::Failed(error) => errors.push(error.clone()),
+            Outcome::TimedOut => {
+                timeouts += 1;
+                continue;
+            }
+        }
+        latencies.push(u64::try_from(attempt.latency.as_millis()).unwrap_or(u64::MAX));
+    }
+
+    let attempts = u32::try_from(attempts.len()).unwrap_or(u32::MAX);
+    let successes = u32::try_from(results.len()).unwrap_or(u32::MAX);
+    let results = count_distinct(results);
+
+    model::SourceBenchmark {
+        url: url.to_string(),
+        attempts,
+        successes,
+        timeouts,
+        success_rate: if attempts == 0 {
+            0.0
+        } else {
+            f64::from(successes) / f64::from(attempts)
+        },
+        latency: latency_stats(latencies),
+        response_size: size_stats(sizes),
+        inconsistent: results.len() > 1,
+        results,
+        errors: count_distinct(errors),
+        benchmarked_at,
+    }
+}
+
+fn latency_stats(mut latencies: Vec<u64>) -> Option<model::LatencyStats> {
+    latencies.sort_unstable();
+
+    Some(model::LatencyStats {
+        min_ms: *latencies.first()?,
+        p50_ms: percentile(&latencies, 50),
+        p90_ms: percentile(&latencies, 90),
+        p99_ms: percentile(&latencies, 99),
+        max_ms: *latencies.last()?,
+        mean_ms: mean(&latencies),
+    })
+}
+
+fn size_stats(sizes: Vec<u64>) -> Option<model::SizeStats> {
+    Some(model::SizeStats {
+        min: *sizes.iter().min()?,
+        mean: mean(&sizes),
+        max: *sizes.iter().max()?,
+    })
+}
+
+/// Nearest-rank percentile of some sorted samples, which must not be empty.
+fn percentile(sorted: &[u64], percentile: usize) -> u64 {
+    let rank = (percentile * sorted.len() + 99) / 100;
+
+    sorted[rank.max(1) - 1]
+}
+
+fn mean(samples: &[u64]) -> u64 {
+    let sum: u128 = samples.iter().map(|sample| u128::from(*sample)).sum();
+
+    u64::try_from(sum / samples.len().max(1) as u128).unwrap_or(u64::MAX)
+}
+
+/// Count how many times each distinct value was seen, the most frequent first and then in the
+/// order in which they were first seen.
+fn count_distinct(values: Vec<String>) -> Vec<model::ValueCount> {
+    let mut counts: Vec<model::ValueCount> = vec![];
+    for value in values {
+        match counts.iter_mut().find(|count| count.value == value) {
+            Some(count) => count.count += 1,
+            None => counts.push(model::ValueCount { value, count: 1 }),
+        }
+    }
+    // Sorting is stable, so ties keep the order in which they were first seen
+    counts.sort_by(|a, b| b.count.cmp(&a.count));
+
+    counts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn value(value: &str, millis: u64) -> Attempt {
+        Attempt {
+            latency: Duration::from_millis(millis),
+            outcome: Outcome::Value {
+                value: value.to_string(),
+                response_size: Some(10),
+            },
+        }
+    }
+
+    #[test]
+    fn latency_percentiles_are_nearest_rank() {
+        let attempts: Vec<_> = (1..=10).rev().map(|i| value("1", i * 10)).collect();
+        let benchmark = summarize("https://example.com", &attempts, 0);
+
+        assert_eq!(
+            benchmark.latency,
+            Some(model::LatencyStats {
+                min_ms: 10,
+                p50_ms: 50,
+                p90_ms: 90,
+                p99_ms: 100,
+                max_ms: 100,
+                mean_ms: 55,
+            })
+        );
+        assert_eq!(benchmark.success_rate, 1.0);
+        assert!(!benchmark.inconsistent);
+
+        let single = summarize("https://example.com", &[value("1", 7)], 0);
+        assert_eq!(single.latency.unwrap().p99_ms, 7);
+    }
+
+    #[test]
+    fn failures_and_timeouts_lower_the_success_rate() {
+        let attempts = vec![
+            value("1", 10),
+            Attempt {
+                latency: Duration::from_millis(20),
+                outcome: Outcome::Failed("HTTP status 500".to_string()),
+            },
+            Attempt {
+                latency: Duration::from_millis(2_000),
+                outcome: Outcome::TimedOut,
+            },
+            value("1", 30),
+        ];
+        let benchmark = summarize("https://example.com", &attempts, 0);
+
+        assert_eq!(benchmark.attempts, 4);
+        assert_eq!(benchmark.successes, 2);
+        assert_eq!(benchmark.timeouts, 1);
+        assert_eq!(benchmark.success_rate, 0.5);
+        // Timed out attempts do not count for the latencies
+        assert_eq!(benchmark.latency.unwrap().max_ms, 30);
+        assert_eq!(
+            benchmark.errors,
+            vec![model::ValueCount {
+                value: "HTTP status 500".to_string(),
+                count: 1,
+            }]
+        );
+
+        let timed_out = summarize("https://example.com", &attempts[2..3], 0);
+        assert_eq!(timed_out.latency, None);
+        assert_eq!(timed_out.response_size, None);
+    }
+
+    #[test]
+    fn sources_that_disagree_with_themselves_are_detected() {
+        let attempts = vec![
+            value(r#"{"RadonInteger":"1"}"#, 10),
+            value(r#"{"RadonInteger":"2"}"#, 10),
+            value(r#"{"RadonInteger":"2"}"#, 10),
+        ];
+        let benchmark = summarize("https://example.com", &attempts, 0);
+
+        assert!(benchmark.inconsistent);
+        assert_eq!(
+            benchmark.results,
+            vec![
+                model::ValueCount {
+                    value: r#"{"RadonInteger":"2"}"#.to_string(),
+                    count: 2,
+                },
+                model::ValueCount {
+                    value: r#"{"RadonInteger":"1"}"#.to_string(),
+                    count: 1,
+                },
+            ]
+        );
+    }
+}
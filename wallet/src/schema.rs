@@ -0,0 +1,246 @@
+//! Validation of the responses of the node against the fields that the wallet expects in them.
+//!
+//! When the node is running an older or newer release than the wallet, the results of the methods
+//! that the wallet consumes may have renamed or new fields. Responses are still deserialized
+//! leniently, so that whatever can be parsed is used, but the fields that were missing or unknown
+//! are reported as a `NodeSchemaMismatch`, both in the logs and in `get_node_status`. In strict mode
+//! mismatches fail the request instead, which is meant for catching version skew in CI.
+use std::{
+    collections::BTreeMap,
+    sync::{Mutex, MutexGuard},
+};
+
+use failure::Fail;
+use serde::Serialize;
+use serde_json::Value;
+
+/// Top-level fields of the result of a node method.
+#[derive(Debug)]
+pub struct ResponseSchema {
+    /// Name of the JSON-RPC method.
+    pub method: &'static str,
+    /// Fields that the wallet reads, which are reported if missing.
+    pub expected: &'static [&'static str],
+    /// Fields that the node is known to add and the wallet ignores.
+    pub ignored: &'static [&'static str],
+}
+
+/// `syncStatus`, which is used as the health check of the node.
+pub const SYNC_STATUS: ResponseSchema = ResponseSchema {
+    method: "syncStatus",
+    expected: &["chain_beacon", "current_epoch", "node_state"],
+    ignored: &[],
+};
+
+/// `getBlock`, which is a serialized `Block` with some additional fields.
+pub const GET_BLOCK: ResponseSchema = ResponseSchema {
+    method: "getBlock",
+    expected: &["block_header", "block_sig", "txns", "confirmed"],
+    ignored: &[
+        "block_weight",
+        "dr_weight",
+        "txns_hashes",
+        "txns_weights",
+        "vt_weight",
+    ],
+};
+
+/// `dataRequestReport`, which is a serialized `DataRequestInfo`.
+pub const DATA_REQUEST_REPORT: ResponseSchema = ResponseSchema {
+    method: "dataRequestReport",
+    expected: &[
+        "block_hash_dr_tx",
+        "block_hash_tally_tx",
+        "commits",
+        "current_commit_round",
+        "current_reveal_round",
+        "current_stage",
+        "reveals",
+        "tally",
+    ],
+    ignored: &[],
+};
+
+/// The result of a node method does not have the fields that the wallet expects.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Fail)]
+#[fail(
+    display = "unexpected result of {} (missing fields: {:?}, unknown fields: {:?}), the node and the wallet may be running different versions",
+    method, missing_fields, unknown_fields
+)]
+pub struct NodeSchemaMismatch {
+    pub method: String,
+    /// Expected fields that were not in the result.
+    pub missing_fields: Vec<String>,
+    /// Fields of the result that the wallet does not know about.
+    pub unknown_fields: Vec<String>,
+}
+
+impl ResponseSchema {
+    /// Compare the fields of a result with the schema of the method.
+    ///
+    /// Only objects are checked, as other results (e.g. `null` for a missing block) are handled by
+    /// their deserialization.
+    pub fn check(&self, result: &Value) -> Option<NodeSchemaMismatch> {
+        let fields = result.as_object()?;
+
+        let missing_fields: Vec<String> = self
+            .expected
+            .iter()
+            .filter(|field| !fields.contains_key(**field))
+            .map(|field| field.to_string())
+            .collect();
+        let unknown_fields: Vec<String> = fields
+            .keys()
+            .filter(|field| {
+                !self.expected.contains(&field.as_str()) && !self.ignored.contains(&field.as_str())
+            })
+            .cloned()
+            .collect();
+
+        if missing_fields.is_empty() && unknown_fields.is_empty() {
+            None
+        } else {
+            Some(NodeSchemaMismatch {
+                method: self.method.to_string(),
+                missing_fields,
+                unknown_fields,
+            })
+        }
+    }
+}
+
+/// Mismatches found in the results of the node the wallet is connected to.
+///
+/// Every mismatch is only logged the first time it is found for a method, until the wallet
+/// connects to another node, so that the periodic requests do not flood the logs.
+#[derive(Debug)]
+pub struct SchemaValidator {
+    strict: bool,
+    mismatches: Mutex<BTreeMap<&'static str, NodeSchemaMismatch>>,
+}
+
+impl SchemaValidator {
+    pub fn new(strict: bool) -> Self {
+        Self {
+            strict,
+            mismatches: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Check the result of a node method, recording any mismatch.
+    ///
+    /// Mismatches are only an error in strict mode, and otherwise the result should be used as
+    /// far as it can be deserialized.
+    pub fn validate(
+        &self,
+        schema: &ResponseSchema,
+        result: &Value,
+    ) -> Result<(), NodeSchemaMismatch> {
+        let mismatch = match schema.check(result) {
+            Some(mismatch) => mismatch,
+            None => return Ok(()),
+        };
+
+        let mut mismatches = self.lock();
+        if mismatches.get(schema.method) != Some(&mismatch) {
+            log::warn!("{}", mismatch);
+            mismatches.insert(schema.method, mismatch.clone());
+        }
+
+        if self.strict {
+            Err(mismatch)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// The last mismatch found for every method, sorted by method.
+    pub fn mismatches(&self) -> Vec<NodeSchemaMismatch> {
+        self.lock().values().cloned().collect()
+    }
+
+    /// Forget the mismatches, e.g. when connecting to another node.
+    pub fn reset(&self) {
+        self.lock().clear();
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<&'static str, NodeSchemaMismatch>> {
+        self.mismatches
+            .lock()
+            .expect("Schema mismatch locks should only fail if poisoned")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn sync_status() -> Value {
+        json!({
+            "chain_beacon": { "checkpoint": 1, "hash_prev_block": "0".repeat(64) },
+            "current_epoch": 2,
+            "node_state": "Synced",
+        })
+    }
+
+    #[test]
+    fn matching_results_are_valid() {
+        let validator = SchemaValidator::new(true);
+
+        assert_eq!(validator.validate(&SYNC_STATUS, &sync_status()), Ok(()));
+        assert_eq!(validator.validate(&GET_BLOCK, &Value::Null), Ok(()));
+        assert!(validator.mismatches().is_empty());
+    }
+
+    #[test]
+    fn missing_and_unknown_fields_are_recorded() {
+        let validator = SchemaValidator::new(false);
+        let mut status = sync_status();
+        status.as_object_mut().unwrap().remove("current_epoch");
+        status["epoch"] = json!(2);
+
+        assert_eq!(validator.validate(&SYNC_STATUS, &status), Ok(()));
+        assert_eq!(
+            validator.mismatches(),
+            vec![NodeSchemaMismatch {
+                method: "syncStatus".to_string(),
+                missing_fields: vec!["current_epoch".to_string()],
+                unknown_fields: vec!["epoch".to_string()],
+            }]
+        );
+
+        // Only the last mismatch of every method is kept
+        status["current_epoch"] = json!(2);
+        validator.validate(&SYNC_STATUS, &status).unwrap();
+        assert_eq!(
+            validator.mismatches()[0].missing_fields,
+            Vec::<String>::new()
+        );
+
+        validator.reset();
+        assert!(validator.mismatches().is_empty());
+    }
+
+    #[test]
+    fn mismatches_are_errors_in_strict_mode() {
+        let validator = SchemaValidator::new(true);
+        let block = json!({
+            "block_header": {},
+            "block_sig": {},
+            "txns": {},
+            "block_weight": 0,
+        });
+
+        assert_eq!(
+            validator.validate(&GET_BLOCK, &block),
+            Err(NodeSchemaMismatch {
+                method: "getBlock".to_string(),
+                missing_fields: vec!["confirmed".to_string()],
+                unknown_fields: vec![],
+            })
+        );
+        assert_eq!(validator.mismatches().len(), 1);
+    }
+}
@@ -0,0 +1,338 @@
+//! Simulation of the tally stage of a data request.
+//!
+//! Before committing funds to a data request, its creator may want to know how the tally behaves
+//! if some of the witnesses do not reveal the honest result. A simulation reveals the honest result
+//! for some of the witnesses and made-up values for the rest, and then runs the tally exactly as
+//! the node would: the tally script with its precondition and postcondition clauses, followed by
+//! the construction of the tally transaction that pays the witnesses.
+use std::collections::HashSet;
+
+use failure::Fail;
+use serde::{Deserialize, Serialize};
+
+use witnet_data_structures::{
+    chain::{tapi::ActiveWips, DataRequestOutput, Hash, PublicKeyHash},
+    data_request::create_tally,
+    radon_report::{RadonReport, ReportContext, Stage},
+};
+use witnet_rad::{
+    conditions::run_tally_with_conditions,
+    error::RadError,
+    script::RadonScriptExecutionSettings,
+    types::{float::RadonFloat, integer::RadonInteger, JsonConversion, RadonType, RadonTypes},
+};
+use witnet_validations::validations::tally_bytes_on_encode_error;
+
+#[derive(Debug, PartialEq, Fail)]
+pub enum Error {
+    #[fail(display = "a data request needs at least one witness")]
+    NoWitnesses,
+    #[fail(display = "there are {} lies but only {} witnesses", lies, witnesses)]
+    TooManyLies { lies: usize, witnesses: usize },
+    #[fail(display = "lie #{} is not a valid RADON value: {}", index, error)]
+    InvalidLie { index: usize, error: RadError },
+    #[fail(
+        display = "outliers can only be generated from integer or float results, not {}",
+        _0
+    )]
+    NonNumericResult(String),
+}
+
+/// A value revealed by a simulated dishonest witness.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Lie {
+    /// Reveal this value, given as JSON.
+    Value { value: serde_json::Value },
+    /// Reveal the honest result multiplied by this factor. The honest result must be an integer
+    /// or a float.
+    Outlier { factor: f64 },
+}
+
+impl Lie {
+    /// The value revealed by a witness telling this lie.
+    fn reveal(&self, index: usize, honest_result: &RadonTypes) -> Result<RadonTypes, Error> {
+        match self {
+            Lie::Value { value } => RadonTypes::from_json(value.clone(), JsonConversion::Strict)
+                .map_err(|error| Error::InvalidLie { index, error }),
+            Lie::Outlier { factor } => match honest_result {
+                RadonTypes::Float(float) => Ok(RadonFloat::from(float.value() * factor).into()),
+                RadonTypes::Integer(integer) => {
+                    Ok(RadonInteger::from((integer.value() as f64 * factor) as i128).into())
+                }
+                other => Err(Error::NonNumericResult(other.radon_type_name().to_string())),
+            },
+        }
+    }
+}
+
+/// What a simulated witness revealed and how the tally treated it.
+#[derive(Clone, Debug, Serialize)]
+pub struct SimulatedWitness {
+    /// Whether the witness revealed the honest result.
+    pub honest: bool,
+    pub reveal: RadonTypes,
+    /// Whether the tally classified the reveal as out of consensus.
+    pub liar: bool,
+    /// Whether the reveal is an error, or was considered as such.
+    pub error: bool,
+    /// Nanowits paid to the witness by the tally transaction. This is the reward plus the
+    /// collateral for witnesses in consensus, and nothing for those whose collateral is slashed.
+    pub payout: u64,
+}
+
+/// Outcome of a simulated tally.
+#[derive(Clone, Debug, Serialize)]
+pub struct TallySimulation {
+    /// The tally result along with its metadata, including which reveals were removed by each
+    /// filter.
+    pub tally: RadonReport<RadonTypes>,
+    /// The simulated witnesses, honest ones first.
+    pub witnesses: Vec<SimulatedWitness>,
+    /// Nanowits returned to the creator of the data request, because of witnesses that were not
+    /// rewarded.
+    pub tally_change: u64,
+}
+
+/// Made-up address of a simulated participant. The creator of the data request is number 0, and
+/// witnesses follow.
+fn simulated_pkh(participant: usize) -> PublicKeyHash {
+    let mut bytes = [0; 20];
+    bytes[12..].copy_from_slice(&(participant as u64).to_be_bytes());
+
+    PublicKeyHash::from_bytes(&bytes).expect("PKHs are 20 bytes long")
+}
+
+/// Simulate the tally of a data request in which every witness commits and reveals, the first of
+/// them revealing `honest_result` and the last `lies.len()` revealing the given lies.
+pub fn simulate_tally(
+    dr_output: &DataRequestOutput,
+    honest_result: RadonTypes,
+    lies: &[Lie],
+    collateral_minimum: u64,
+    active_wips: &ActiveWips,
+) -> Result<TallySimulation, Error> {
+    let witnesses = usize::from(dr_output.witnesses);
+    if witnesses == 0 {
+        return Err(Error::NoWitnesses);
+    }
+    if lies.len() > witnesses {
+        return Err(Error::TooManyLies {
+            lies: lies.len(),
+            witnesses,
+        });
+    }
+
+    let honest_count = witnesses - lies.len();
+    let mut reveals = vec![honest_result.clone(); honest_count];
+    for (index, lie) in lies.iter().enumerate() {
+        reveals.push(lie.reveal(index, &honest_result)?);
+    }
+
+    let reports = reveals
+        .iter()
+        .map(|reveal| RadonReport::from_result(Ok(reveal.clone()), &ReportContext::default()))
+        .collect();
+    let non_error_min = f64::from(dr_output.min_consensus_percentage) / 100.0;
+    // Same settings as consensus, plus the breakdown of the reveals removed by each filter
+    let settings = RadonScriptExecutionSettings {
+        filter_breakdown: true,
+        ..RadonScriptExecutionSettings::all_but_partial_results()
+    };
+    let tally = run_tally_with_conditions(
+        reports,
+        &dr_output.data_request.tally,
+        non_error_min,
+        witnesses,
+        settings,
+        active_wips,
+    );
+
+    let creator = simulated_pkh(0);
+    let revealers: Vec<PublicKeyHash> = (1..=witnesses).map(simulated_pkh).collect();
+    let committers: HashSet<PublicKeyHash> = revealers.iter().copied().collect();
+    let tally_transaction = create_tally(
+        Hash::default(),
+        dr_output,
+        creator,
+        &tally,
+        revealers.clone(),
+        committers,
+        collateral_minimum,
+        tally_bytes_on_encode_error(),
+        active_wips,
+    );
+    let paid_to = |pkh: &PublicKeyHash| -> u64 {
+        tally_transaction
+            .outputs
+            .iter()
+            .filter(|output| output.pkh == *pkh)
+            .map(|output| output.value)
+            .sum()
+    };
+
+    let (liars, errors) = match &tally.context.stage {
+        Stage::Tally(metadata) => (metadata.liars.clone(), metadata.errors.clone()),
+        _ => (vec![false; witnesses], vec![false; witnesses]),
+    };
+    let witnesses = reveals
+        .into_iter()
+        .zip(revealers.iter())
+        .enumerate()
+        .map(|(index, (reveal, pkh))| SimulatedWitness {
+            honest: index < honest_count,
+            reveal,
+            liar: liars.get(index).copied().unwrap_or_default(),
+            error: errors.get(index).copied().unwrap_or_default(),
+            payout: paid_to(pkh),
+        })
+        .collect();
+
+    Ok(TallySimulation {
+        tally_change: paid_to(&creator),
+        tally,
+        witnesses,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use witnet_data_structures::{
+        chain::{tapi::current_active_wips, RADFilter, RADRequest, RADTally},
+        radon_report::{RemovalReason, RemovedElement},
+    };
+    use witnet_rad::{filters::RadonFilters, reducers::RadonReducers, types::string::RadonString};
+
+    use super::*;
+
+    const WITNESS_REWARD: u64 = 1_000;
+    const COLLATERAL: u64 = 1_000_000_000;
+
+    /// Data request with 5 witnesses whose tally is the mean of the values within one standard
+    /// deviation
+    fn dr_output() -> DataRequestOutput {
+        DataRequestOutput {
+            data_request: RADRequest {
+                tally: RADTally {
+                    filters: vec![RADFilter {
+                        op: RadonFilters::DeviationStandard as u32,
+                        args: vec![249, 60, 0],
+                    }],
+                    reducer: RadonReducers::AverageMean as u32,
+                },
+                ..Default::default()
+            },
+            witness_reward: WITNESS_REWARD,
+            witnesses: 5,
+            min_consensus_percentage: 51,
+            collateral: COLLATERAL,
+            ..Default::default()
+        }
+    }
+
+    fn simulate(lies: &[Lie]) -> TallySimulation {
+        simulate_tally(
+            &dr_output(),
+            RadonFloat::from(100.0).into(),
+            lies,
+            COLLATERAL,
+            &current_active_wips(),
+        )
+        .unwrap()
+    }
+
+    fn liars(simulation: &TallySimulation) -> Vec<bool> {
+        simulation.witnesses.iter().map(|w| w.liar).collect()
+    }
+
+    fn payouts(simulation: &TallySimulation) -> Vec<u64> {
+        simulation.witnesses.iter().map(|w| w.payout).collect()
+    }
+
+    #[test]
+    fn one_outlier_is_filtered_out() {
+        let simulation = simulate(&[Lie::Outlier { factor: 10.0 }]);
+
+        assert_eq!(simulation.tally.result, RadonFloat::from(100.0).into());
+        assert_eq!(liars(&simulation), vec![false, false, false, false, true]);
+        assert!(simulation.witnesses.iter().all(|w| !w.error));
+        assert_eq!(
+            simulation.witnesses[4].reveal,
+            RadonTypes::from(RadonFloat::from(1000.0))
+        );
+        let honest_payout = WITNESS_REWARD + COLLATERAL;
+        assert_eq!(
+            payouts(&simulation),
+            vec![
+                honest_payout,
+                honest_payout,
+                honest_payout,
+                honest_payout,
+                0
+            ]
+        );
+        assert_eq!(simulation.tally_change, WITNESS_REWARD);
+        let breakdown = simulation.tally.context.filter_breakdown.unwrap();
+        assert_eq!(
+            breakdown.stages[0].removed,
+            vec![RemovedElement {
+                index: 4,
+                reason: RemovalReason::DeviationExceeded
+            }]
+        );
+    }
+
+    #[test]
+    fn three_outliers_outvote_the_honest_witnesses() {
+        let lie = Lie::Value {
+            value: serde_json::json!(1000.5),
+        };
+        let simulation = simulate(&[lie.clone(), lie.clone(), lie]);
+
+        assert_eq!(simulation.tally.result, RadonFloat::from(1000.5).into());
+        assert_eq!(liars(&simulation), vec![true, true, false, false, false]);
+        assert!(simulation.witnesses[..2].iter().all(|w| w.honest));
+        let liar_payout = WITNESS_REWARD + COLLATERAL;
+        assert_eq!(
+            payouts(&simulation),
+            vec![0, 0, liar_payout, liar_payout, liar_payout]
+        );
+        assert_eq!(simulation.tally_change, 2 * WITNESS_REWARD);
+    }
+
+    #[test]
+    fn invalid_simulations() {
+        let simulate = |honest_result: RadonTypes, lies: &[Lie]| {
+            simulate_tally(
+                &dr_output(),
+                honest_result,
+                lies,
+                COLLATERAL,
+                &current_active_wips(),
+            )
+            .map(|_| ())
+        };
+        let outlier = Lie::Outlier { factor: 2.0 };
+
+        assert_eq!(
+            simulate(RadonFloat::from(1.0).into(), &vec![outlier.clone(); 6]),
+            Err(Error::TooManyLies {
+                lies: 6,
+                witnesses: 5
+            })
+        );
+        assert!(matches!(
+            simulate(
+                RadonFloat::from(1.0).into(),
+                &[Lie::Value {
+                    value: serde_json::Value::Null
+                }]
+            ),
+            Err(Error::InvalidLie { index: 0, .. })
+        ));
+        assert!(matches!(
+            simulate(RadonString::from("foo").into(), &[outlier]),
+            Err(Error::NonNumericResult(_))
+        ));
+    }
+}
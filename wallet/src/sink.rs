@@ -0,0 +1,654 @@
+//! Bounded notification sinks for client subscriptions.
+//!
+//! Notifications for a subscribed client are buffered in a bounded queue instead of being pushed
+//! straight into the websockets transport. If the client stops reading and the queue fills up, the
+//! overflow policy of its subscription decides what is done with the extra notifications.
+//!
+//! The last notifications of every session are also kept in a replay buffer, so that a client that
+//! reconnects after a brief disconnection can resubscribe from the last event it got and receive
+//! the ones it missed in between.
+use std::{
+    collections::VecDeque,
+    fmt,
+    sync::{Arc, Mutex},
+};
+
+use failure::Fail;
+use futures::FutureExt;
+use futures_util::compat::Compat01As03;
+use jsonrpc_core as rpc;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use witnet_util::timestamp::get_timestamp;
+
+use crate::model;
+
+/// What to do with new notifications when the queue of a subscription is full.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverflowPolicy {
+    /// Discard the oldest queued notification to make room for the new one.
+    DropOldest,
+    /// Discard the new notification, keeping the ones already queued.
+    DropNewest,
+    /// Terminate the subscription, recording why it was terminated.
+    Disconnect,
+}
+
+impl Default for OverflowPolicy {
+    fn default() -> Self {
+        OverflowPolicy::DropOldest
+    }
+}
+
+impl fmt::Display for OverflowPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            OverflowPolicy::DropOldest => write!(f, "drop-oldest"),
+            OverflowPolicy::DropNewest => write!(f, "drop-newest"),
+            OverflowPolicy::Disconnect => write!(f, "disconnect"),
+        }
+    }
+}
+
+/// Errors returned by a notification transport that is not accepting notifications.
+#[derive(Debug, PartialEq, Eq)]
+pub enum TransportError {
+    /// The transport is alive but cannot take more notifications right now.
+    Full,
+    /// The transport is closed and will never take notifications again.
+    Closed,
+}
+
+#[derive(Debug, PartialEq, Eq, Fail)]
+pub enum Error {
+    #[fail(display = "subscription was disconnected: {}", _0)]
+    Disconnected(String),
+}
+
+/// Something that can deliver notifications to a client without blocking.
+pub trait Transport: Send {
+    fn try_notify(&self, params: rpc::Params) -> Result<(), TransportError>;
+}
+
+impl Transport for jsonrpc_pubsub::Sink {
+    fn try_notify(&self, params: rpc::Params) -> Result<(), TransportError> {
+        // Poll the send only once: if the client is not reading, the transport channel stays full
+        // and the notification is kept in our own bounded queue instead.
+        match Compat01As03::new(self.notify(params)).now_or_never() {
+            Some(Ok(_)) => Ok(()),
+            Some(Err(_)) => Err(TransportError::Closed),
+            None => Err(TransportError::Full),
+        }
+    }
+}
+
+impl<T: Transport + ?Sized> Transport for Box<T> {
+    fn try_notify(&self, params: rpc::Params) -> Result<(), TransportError> {
+        (**self).try_notify(params)
+    }
+}
+
+/// Lag counters of a subscription.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct SinkStats {
+    /// Notifications waiting in the queue for the client to read them.
+    pub queued: usize,
+    /// Notifications delivered to the client.
+    pub sent: u64,
+    /// Notifications discarded because the queue was full.
+    pub dropped: u64,
+    /// Notifications discarded since the client last caught up.
+    pub missed: u64,
+    /// Timestamp of the first notification discarded since the client last caught up.
+    pub missed_since: Option<i64>,
+    /// Why the subscription was terminated, if it was.
+    pub disconnect_reason: Option<String>,
+}
+
+struct Inner {
+    transport: Option<Box<dyn Transport>>,
+    queue: VecDeque<rpc::Params>,
+    capacity: usize,
+    policy: OverflowPolicy,
+    stats: SinkStats,
+}
+
+/// A notification sink with a bounded queue and an overflow policy.
+///
+/// Cloning it is cheap, and all the clones share the same queue and counters.
+#[derive(Clone)]
+pub struct BoundedSink {
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl BoundedSink {
+    pub fn new<T>(transport: T, capacity: usize, policy: OverflowPolicy) -> Self
+    where
+        T: Transport + 'static,
+    {
+        Self {
+            inner: Arc::new(Mutex::new(Inner {
+                transport: Some(Box::new(transport)),
+                queue: VecDeque::with_capacity(capacity),
+                capacity: capacity.max(1),
+                policy,
+                stats: SinkStats::default(),
+            })),
+        }
+    }
+
+    /// Queue a notification and try to deliver as many queued notifications as possible.
+    pub fn notify(&self, params: rpc::Params) -> Result<(), Error> {
+        let mut inner = self.lock();
+        inner.check_connected()?;
+        inner.flush();
+
+        if inner.queue.len() >= inner.capacity {
+            match inner.policy {
+                OverflowPolicy::DropOldest => {
+                    inner.queue.pop_front();
+                    inner.queue.push_back(params);
+                    inner.record_drop();
+                }
+                OverflowPolicy::DropNewest => inner.record_drop(),
+                OverflowPolicy::Disconnect => {
+                    let reason = format!(
+                        "client lagged behind more than {} notifications",
+                        inner.capacity
+                    );
+                    inner.disconnect(reason);
+                }
+            }
+        } else {
+            inner.queue.push_back(params);
+        }
+
+        inner.flush();
+        inner.check_connected()
+    }
+
+    /// Try to deliver the queued notifications without queueing a new one.
+    pub fn flush(&self) {
+        self.lock().flush();
+    }
+
+    /// Get the current lag counters of this sink.
+    pub fn stats(&self) -> SinkStats {
+        let inner = self.lock();
+        let mut stats = inner.stats.clone();
+        stats.queued = inner.queue.len();
+
+        stats
+    }
+
+    /// Get the overflow policy of this sink.
+    pub fn policy(&self) -> OverflowPolicy {
+        self.lock().policy
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Inner> {
+        self.inner
+            .lock()
+            .expect("Sink locks should only fail if poisoned")
+    }
+}
+
+impl Inner {
+    fn check_connected(&self) -> Result<(), Error> {
+        match &self.stats.disconnect_reason {
+            Some(reason) => Err(Error::Disconnected(reason.clone())),
+            None => Ok(()),
+        }
+    }
+
+    fn record_drop(&mut self) {
+        self.stats.dropped += 1;
+        self.stats.missed += 1;
+        self.stats.missed_since.get_or_insert_with(get_timestamp);
+    }
+
+    fn disconnect(&mut self, reason: String) {
+        log::warn!("Terminating client subscription: {}", reason);
+        self.transport = None;
+        self.queue.clear();
+        self.stats.disconnect_reason = Some(reason);
+    }
+
+    /// Send a notification, returning whether the transport took it.
+    fn send(&mut self, params: rpc::Params) -> bool {
+        let result = match &self.transport {
+            Some(transport) => transport.try_notify(params),
+            None => return false,
+        };
+
+        match result {
+            Ok(()) => true,
+            Err(TransportError::Full) => false,
+            Err(TransportError::Closed) => {
+                self.disconnect("client closed the connection".to_string());
+                false
+            }
+        }
+    }
+
+    fn flush(&mut self) {
+        // Before anything else, let a client that is catching up know about what it missed
+        if self.stats.missed > 0 {
+            let notification = missed_events_notification(
+                self.stats.missed,
+                self.stats.missed_since.unwrap_or_default(),
+            );
+            if !self.send(notification) {
+                return;
+            }
+            self.stats.missed = 0;
+            self.stats.missed_since = None;
+        }
+
+        while let Some(params) = self.queue.front().cloned() {
+            if !self.send(params) {
+                break;
+            }
+            self.queue.pop_front();
+            self.stats.sent += 1;
+        }
+    }
+}
+
+/// Subscription of a session: the sink of the current subscriber, if any, and the replay buffer
+/// with the last notifications of the session, which outlives subscribers.
+#[derive(Default)]
+pub struct SessionSink {
+    sink: Option<BoundedSink>,
+    replay: VecDeque<(u64, rpc::Params)>,
+    replay_capacity: usize,
+    last_event_id: u64,
+}
+
+impl SessionSink {
+    /// Create a session sink without subscriber that keeps the last `replay_capacity`
+    /// notifications.
+    pub fn new(replay_capacity: usize) -> Self {
+        Self {
+            replay_capacity,
+            ..Self::default()
+        }
+    }
+
+    /// Sink of the current subscriber, if any.
+    pub fn sink(&self) -> Option<&BoundedSink> {
+        self.sink.as_ref()
+    }
+
+    /// Replace the sink of the current subscriber, without replaying anything.
+    pub fn set_sink(&mut self, sink: Option<BoundedSink>) {
+        self.sink = sink;
+    }
+
+    /// Whether notifications are either delivered to a subscriber or kept for replaying them.
+    pub fn wants_notifications(&self) -> bool {
+        self.sink.is_some() || self.replay_capacity > 0
+    }
+
+    /// Id of the last notification of the session, or 0 if there were none.
+    pub fn last_event_id(&self) -> u64 {
+        self.last_event_id
+    }
+
+    /// Give the next event id to a notification, keep it in the replay buffer and deliver it to
+    /// the current subscriber, if any. The payload gets the id in its `event_id` field.
+    pub fn notify(&mut self, mut payload: serde_json::Value) -> Result<u64, Error> {
+        self.last_event_id += 1;
+        let event_id = self.last_event_id;
+        if let Some(payload) = payload.as_object_mut() {
+            payload.insert("event_id".to_string(), json!(event_id));
+        }
+        let params = rpc::Params::Array(vec![payload]);
+
+        if self.replay_capacity > 0 {
+            if self.replay.len() >= self.replay_capacity {
+                self.replay.pop_front();
+            }
+            self.replay.push_back((event_id, params.clone()));
+        }
+
+        match &self.sink {
+            Some(sink) => sink.notify(params).map(|()| event_id),
+            None => Ok(event_id),
+        }
+    }
+
+    /// Attach the sink of a new subscriber. A client resuming a previous subscription gives the id
+    /// of the last event it got, and the notifications that came after it are delivered in order
+    /// before any new one. If some of them are no longer buffered, a `replay_unavailable`
+    /// notification is delivered instead, so that the client refreshes its state.
+    pub fn attach(&mut self, sink: BoundedSink, last_event_id: Option<u64>) {
+        if let Some(last_event_id) = last_event_id {
+            let result = match self.events_after(last_event_id) {
+                Some(events) => events
+                    .into_iter()
+                    .try_for_each(|params| sink.notify(params)),
+                None => sink.notify(replay_unavailable_notification(
+                    last_event_id,
+                    self.last_event_id,
+                )),
+            };
+            if let Err(err) = result {
+                log::warn!("Couldn't replay notifications to a new subscriber: {}", err);
+            }
+        }
+
+        self.sink = Some(sink);
+    }
+
+    /// Buffered notifications that came after an event, or `None` if some of them are missing
+    /// from the buffer.
+    fn events_after(&self, event_id: u64) -> Option<Vec<rpc::Params>> {
+        if event_id > self.last_event_id {
+            return None;
+        }
+        if event_id == self.last_event_id {
+            return Some(vec![]);
+        }
+        match self.replay.front() {
+            Some((oldest, _)) if *oldest <= event_id + 1 => Some(
+                self.replay
+                    .iter()
+                    .filter(|(id, _)| *id > event_id)
+                    .map(|(_, params)| params.clone())
+                    .collect(),
+            ),
+            _ => None,
+        }
+    }
+}
+
+/// Notification telling a client how many events it missed while it was not reading, so it can
+/// refresh its state.
+pub fn missed_events_notification(count: u64, since: i64) -> rpc::Params {
+    rpc::Params::Array(vec![json!({
+        "missed_events": {
+            "count": count,
+            "since": since,
+        }
+    })])
+}
+
+/// Notification telling a client that resubscribed from an event that the notifications it missed
+/// can no longer be replayed, so it must refresh its state.
+pub fn replay_unavailable_notification(last_event_id: u64, current_event_id: u64) -> rpc::Params {
+    rpc::Params::Array(vec![json!({
+        "replay_unavailable": {
+            "last_event_id": last_event_id,
+            "current_event_id": current_event_id,
+        }
+    })])
+}
+
+/// Notification telling a client that its session was closed to make room for a newer one.
+pub fn session_evicted_notification(session_id: &str) -> rpc::Params {
+    rpc::Params::Array(vec![json!({
+        "session_evicted": {
+            "session_id": session_id,
+        }
+    })])
+}
+
+/// Notification telling a client that every wallet was locked, along with the ones that were
+/// unlocked in its session. The session stays open, so they can be unlocked in it again.
+pub fn wallets_locked_notification(wallet_ids: &[model::WalletId]) -> rpc::Params {
+    rpc::Params::Array(vec![json!({
+        "wallets_locked": {
+            "wallet_ids": wallet_ids,
+        }
+    })])
+}
+
+/// Notification telling a client that its previous subscription was terminated.
+pub fn disconnected_notification(reason: &str) -> rpc::Params {
+    rpc::Params::Array(vec![json!({
+        "disconnected": {
+            "reason": reason,
+        }
+    })])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    use super::*;
+
+    #[derive(Clone, Default)]
+    struct TestTransport {
+        stalled: Arc<AtomicBool>,
+        closed: Arc<AtomicBool>,
+        received: Arc<Mutex<Vec<rpc::Params>>>,
+    }
+
+    impl TestTransport {
+        fn stall(&self, stalled: bool) {
+            self.stalled.store(stalled, Ordering::SeqCst);
+        }
+
+        fn received(&self) -> Vec<serde_json::Value> {
+            self.received
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|params| match params {
+                    rpc::Params::Array(values) => values[0].clone(),
+                    _ => unreachable!(),
+                })
+                .collect()
+        }
+    }
+
+    impl Transport for TestTransport {
+        fn try_notify(&self, params: rpc::Params) -> Result<(), TransportError> {
+            if self.closed.load(Ordering::SeqCst) {
+                Err(TransportError::Closed)
+            } else if self.stalled.load(Ordering::SeqCst) {
+                Err(TransportError::Full)
+            } else {
+                self.received.lock().unwrap().push(params);
+                Ok(())
+            }
+        }
+    }
+
+    fn event(n: u64) -> rpc::Params {
+        rpc::Params::Array(vec![json!(n)])
+    }
+
+    fn stalled_sink(policy: OverflowPolicy) -> (BoundedSink, TestTransport) {
+        let transport = TestTransport::default();
+        let sink = BoundedSink::new(transport.clone(), 3, policy);
+        transport.stall(true);
+        for n in 0..5 {
+            let _ = sink.notify(event(n));
+        }
+
+        (sink, transport)
+    }
+
+    #[test]
+    fn test_notify_delivers_when_transport_accepts() {
+        let transport = TestTransport::default();
+        let sink = BoundedSink::new(transport.clone(), 3, OverflowPolicy::default());
+
+        for n in 0..10 {
+            sink.notify(event(n)).unwrap();
+        }
+
+        assert_eq!(
+            transport.received(),
+            (0..10).map(|n| json!(n)).collect::<Vec<_>>()
+        );
+        assert_eq!(sink.stats().sent, 10);
+        assert_eq!(sink.stats().dropped, 0);
+    }
+
+    #[test]
+    fn test_drop_oldest() {
+        let (sink, transport) = stalled_sink(OverflowPolicy::DropOldest);
+
+        let stats = sink.stats();
+        assert_eq!(stats.queued, 3);
+        assert_eq!(stats.dropped, 2);
+        assert_eq!(stats.missed, 2);
+        assert!(stats.missed_since.is_some());
+        assert!(transport.received().is_empty());
+
+        transport.stall(false);
+        sink.flush();
+
+        let received = transport.received();
+        assert_eq!(received[0]["missed_events"]["count"], json!(2));
+        assert_eq!(&received[1..], &[json!(2), json!(3), json!(4)]);
+        assert_eq!(sink.stats().missed, 0);
+
+        // The missed events notification is only sent once
+        sink.notify(event(5)).unwrap();
+        assert_eq!(transport.received().len(), 5);
+    }
+
+    #[test]
+    fn test_drop_newest() {
+        let (sink, transport) = stalled_sink(OverflowPolicy::DropNewest);
+
+        assert_eq!(sink.stats().queued, 3);
+        assert_eq!(sink.stats().dropped, 2);
+
+        transport.stall(false);
+        sink.flush();
+
+        let received = transport.received();
+        assert_eq!(received[0]["missed_events"]["count"], json!(2));
+        assert_eq!(&received[1..], &[json!(0), json!(1), json!(2)]);
+    }
+
+    #[test]
+    fn test_disconnect() {
+        let (sink, transport) = stalled_sink(OverflowPolicy::Disconnect);
+
+        let stats = sink.stats();
+        assert_eq!(stats.queued, 0);
+        assert_eq!(stats.dropped, 0);
+        assert_eq!(
+            stats.disconnect_reason,
+            Some("client lagged behind more than 3 notifications".to_string())
+        );
+
+        transport.stall(false);
+        assert!(sink.notify(event(5)).is_err());
+        assert!(transport.received().is_empty());
+    }
+
+    #[test]
+    fn test_closed_transport_disconnects() {
+        let transport = TestTransport::default();
+        let sink = BoundedSink::new(transport.clone(), 3, OverflowPolicy::DropOldest);
+        transport.closed.store(true, Ordering::SeqCst);
+
+        assert_eq!(
+            sink.notify(event(0)),
+            Err(Error::Disconnected(
+                "client closed the connection".to_string()
+            ))
+        );
+    }
+
+    fn session_event(n: u64) -> serde_json::Value {
+        json!({ "n": n })
+    }
+
+    fn received_numbers(transport: &TestTransport) -> Vec<serde_json::Value> {
+        transport
+            .received()
+            .into_iter()
+            .map(|value| value.get("n").cloned().unwrap_or(value))
+            .collect()
+    }
+
+    #[test]
+    fn test_session_sink_assigns_event_ids() {
+        let transport = TestTransport::default();
+        let mut session_sink = SessionSink::new(10);
+        session_sink.attach(
+            BoundedSink::new(transport.clone(), 10, OverflowPolicy::default()),
+            None,
+        );
+
+        assert_eq!(session_sink.notify(session_event(0)), Ok(1));
+        assert_eq!(session_sink.notify(session_event(1)), Ok(2));
+        assert_eq!(
+            transport.received(),
+            vec![
+                json!({ "n": 0, "event_id": 1 }),
+                json!({ "n": 1, "event_id": 2 })
+            ]
+        );
+    }
+
+    #[test]
+    fn test_session_sink_replay_buffer_is_bounded() {
+        let mut session_sink = SessionSink::new(3);
+        for n in 0..5 {
+            session_sink.notify(session_event(n)).unwrap();
+        }
+
+        assert_eq!(session_sink.last_event_id(), 5);
+        assert_eq!(session_sink.replay.len(), 3);
+
+        // Events 3, 4 and 5 are buffered, so a client that got event 2 can catch up
+        let transport = TestTransport::default();
+        session_sink.attach(
+            BoundedSink::new(transport.clone(), 10, OverflowPolicy::default()),
+            Some(2),
+        );
+        assert_eq!(
+            received_numbers(&transport),
+            vec![json!(2), json!(3), json!(4)]
+        );
+
+        // Event 2 is not buffered anymore
+        let transport = TestTransport::default();
+        session_sink.attach(
+            BoundedSink::new(transport.clone(), 10, OverflowPolicy::default()),
+            Some(1),
+        );
+        assert_eq!(
+            transport.received(),
+            vec![json!({
+                "replay_unavailable": { "last_event_id": 1, "current_event_id": 5 }
+            })]
+        );
+
+        // Neither are events from the future
+        let transport = TestTransport::default();
+        session_sink.attach(
+            BoundedSink::new(transport.clone(), 10, OverflowPolicy::default()),
+            Some(6),
+        );
+        assert!(transport.received()[0].get("replay_unavailable").is_some());
+    }
+
+    #[test]
+    fn test_session_sink_without_replay() {
+        let mut session_sink = SessionSink::default();
+        assert!(!session_sink.wants_notifications());
+
+        session_sink.notify(session_event(0)).unwrap();
+        assert!(session_sink.replay.is_empty());
+
+        let transport = TestTransport::default();
+        session_sink.attach(
+            BoundedSink::new(transport.clone(), 10, OverflowPolicy::default()),
+            Some(0),
+        );
+        assert!(session_sink.wants_notifications());
+        assert!(transport.received()[0].get("replay_unavailable").is_some());
+    }
+}
@@ -0,0 +1,186 @@
+//! A local HTTP endpoint for the wallet to post the events of its webhooks to, or to retrieve
+//! data sources from.
+//!
+//! It answers every request with the next of its scripted responses, or with an empty `200 OK` once
+//! they run out, and hands the requests over to the test in the order they arrived.
+use std::{
+    collections::HashMap,
+    io::{BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    thread,
+    time::Duration,
+};
+
+use futures::{channel::mpsc, StreamExt};
+
+use super::NOTIFICATION_TIMEOUT;
+
+/// A request received by a `MockEndpoint`.
+#[derive(Debug)]
+pub struct ReceivedRequest {
+    pub method: String,
+    /// Path and query of the request
+    pub path: String,
+    /// Headers of the request, by their lowercase name
+    pub headers: HashMap<String, String>,
+    pub body: String,
+}
+
+/// A scripted response of a `MockEndpoint`.
+#[derive(Clone, Debug)]
+pub struct MockResponse {
+    pub status: u16,
+    pub content_type: Option<String>,
+    pub body: String,
+    /// Other headers of the response
+    pub headers: Vec<(String, String)>,
+    /// Time to wait before answering
+    pub delay: Duration,
+}
+
+impl MockResponse {
+    /// A response with a text body.
+    pub fn text(content_type: &str, body: &str) -> Self {
+        Self {
+            status: 200,
+            content_type: Some(content_type.to_string()),
+            body: body.to_string(),
+            headers: vec![],
+            delay: Duration::default(),
+        }
+    }
+
+    /// The same response, sent after waiting for `delay`.
+    pub fn delayed(self, delay: Duration) -> Self {
+        Self { delay, ..self }
+    }
+
+    /// The same response, with another header.
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+
+        self
+    }
+}
+
+impl From<u16> for MockResponse {
+    fn from(status: u16) -> Self {
+        Self {
+            status,
+            content_type: None,
+            body: String::new(),
+            headers: vec![],
+            delay: Duration::default(),
+        }
+    }
+}
+
+/// An HTTP endpoint listening on a random local port.
+pub struct MockEndpoint {
+    url: String,
+    receiver: mpsc::UnboundedReceiver<ReceivedRequest>,
+}
+
+impl MockEndpoint {
+    /// Start listening, answering the first requests with `statuses`.
+    pub fn start(statuses: Vec<u16>) -> Self {
+        Self::start_with_responses(statuses.into_iter().map(MockResponse::from).collect())
+    }
+
+    /// Start listening, answering the first requests with `responses`.
+    pub fn start_with_responses(responses: Vec<MockResponse>) -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind the mock endpoint");
+        let url = format!("http://{}/events", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::unbounded();
+
+        thread::spawn(move || {
+            let mut responses = responses.into_iter();
+            for stream in listener.incoming() {
+                let mut stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => break,
+                };
+                let request = match read_request(&mut stream) {
+                    Some(request) => request,
+                    None => continue,
+                };
+                let response = responses.next().unwrap_or_else(|| MockResponse::from(200));
+                write_response(&mut stream, &response);
+                if sender.unbounded_send(request).is_err() {
+                    break;
+                }
+            }
+        });
+
+        Self { url, receiver }
+    }
+
+    /// URL to post to.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+
+    /// Wait for the next request.
+    ///
+    /// Panics if none arrives within 10 seconds of real time.
+    pub async fn next(&mut self) -> ReceivedRequest {
+        tokio::time::timeout(NOTIFICATION_TIMEOUT, self.receiver.next())
+            .await
+            .expect("Timed out waiting for a request to the mock endpoint")
+            .expect("The mock endpoint stopped")
+    }
+}
+
+fn write_response(stream: &mut TcpStream, response: &MockResponse) {
+    thread::sleep(response.delay);
+    let mut head = format!("HTTP/1.1 {} Mock\r\nConnection: close\r\n", response.status);
+    if let Some(content_type) = &response.content_type {
+        head.push_str(&format!("Content-Type: {}\r\n", content_type));
+    }
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+
+    head.push_str(&format!("Content-Length: {}\r\n\r\n", response.body.len()));
+    stream.write_all(head.as_bytes()).ok();
+    stream.write_all(response.body.as_bytes()).ok();
+}
+
+fn read_request(stream: &mut TcpStream) -> Option<ReceivedRequest> {
+    let mut reader = BufReader::new(stream.try_clone().ok()?);
+
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).ok()?;
+    let mut request_line = request_line.split_whitespace();
+    let method = request_line.next()?.to_string();
+    let path = request_line.next()?.to_string();
+
+    let mut headers = HashMap::new();
+    loop {
+        let mut line = String::new();
+        reader.read_line(&mut line).ok()?;
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        let (name, value) = line.split_once(':')?;
+        headers.insert(name.trim().to_lowercase(), value.trim().to_string());
+    }
+
+    if headers.get("expect").map(String::as_str) == Some("100-continue") {
+        stream.write_all(b"HTTP/1.1 100 Continue\r\n\r\n").ok()?;
+    }
+    let length = headers
+        .get("content-length")
+        .and_then(|length| length.parse().ok())
+        .unwrap_or(0);
+    let mut body = vec![0; length];
+    reader.read_exact(&mut body).ok()?;
+
+    Some(ReceivedRequest {
+        method,
+        path,
+        headers,
+        body: String::from_utf8(body).ok()?,
+    })
+}
@@ -0,0 +1,502 @@
+//! Support for testing the wallet without a Witnet node or a database on disk.
+//!
+//! A `Harness` runs the same worker and App actor as the wallet application, wired to a database
+//! that lives in memory, to a scripted `FakeNode` and to a `VirtualClock` that drives the session
+//! timers and the periodic checks of the node status. Clients are played through the same
+//! JSON-RPC methods as the HTTP transport, and notifications are received through subscriptions
+//! attached directly to the App actor. Webhooks can be pointed at a local `MockEndpoint`, and
+//! `set_storage_latency` makes the database as slow as a struggling disk, while
+//! `count_storage_writes` tells whether anything was written to it. Tests of the connection
+//! to the node itself can use the real JSON-RPC client instead, connected to `TcpNode`s.
+//!
+//! This module is only available to the tests of this crate, or with the `test-utils` feature.
+//! Harnesses must be started and used from inside a running actix system:
+//! ```ignore
+//! System::new().block_on(async {
+//!     let harness = Harness::builder().start().unwrap();
+//!     let wallet_id = harness.create_wallet(MNEMONICS, "password").await.unwrap();
+//!     let session_id = harness.unlock_wallet(&wallet_id, "password").await.unwrap();
+//!     let mut notifications = harness.subscribe(&session_id).await.unwrap();
+//!     harness.advance(Duration::from_secs(60)).await;
+//! });
+//! ```
+use std::{
+    collections::HashMap,
+    net::{IpAddr, Ipv4Addr, SocketAddr},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+    time::Duration,
+};
+
+use actix::prelude::*;
+use futures::{channel::mpsc, StreamExt};
+use futures_util::compat::Compat01As03;
+use jsonrpc_core as rpc;
+use serde_json::{json, Value};
+use witnet_config::config::Config;
+use witnet_data_structures::chain::Block;
+
+use crate::{
+    actors::{app, App},
+    clock, failover, metrics, pool, schema, sink, types,
+};
+
+mod endpoint;
+mod node;
+mod tcp_node;
+
+pub use crate::clock::VirtualClock;
+pub use crate::db::{count_storage_writes, set_storage_latency};
+pub use endpoint::{MockEndpoint, MockResponse, ReceivedRequest};
+pub use node::{FakeConnection, FakeNode};
+pub use tcp_node::TcpNode;
+
+/// How long to wait in real time for a notification before giving up.
+const NOTIFICATION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Round trips to the App actor that `Harness::settle` waits for.
+const SETTLE_ROUND_TRIPS: usize = 8;
+
+/// Open a RocksDB database that lives in memory, so that every call gets a blank one.
+pub fn in_memory_db() -> Result<Arc<rocksdb::DB>, failure::Error> {
+    let env = rocksdb::Env::mem_env().map_err(|e| failure::format_err!("{}", e))?;
+    let mut opts = rocksdb::Options::default();
+    opts.create_if_missing(true);
+    opts.set_env(&env);
+
+    rocksdb::DB::open(&opts, "/witnet-wallet")
+        .map(Arc::new)
+        .map_err(|e| failure::format_err!("{}", e))
+}
+
+/// Builder of a `Harness`.
+pub struct HarnessBuilder {
+    config: Config,
+    pool_size: usize,
+    db: Option<Arc<rocksdb::DB>>,
+    tcp_client: bool,
+}
+
+impl Default for HarnessBuilder {
+    fn default() -> Self {
+        let mut config = Config::default();
+        // Hashing passwords as many times as in production only makes tests slow
+        config.wallet.node_url = vec!["127.0.0.1:21338".to_string()];
+        config.wallet.concurrency = Some(1);
+        config.wallet.id_hash_iterations = 1;
+        config.wallet.db_encrypt_hash_iterations = 1;
+        // Tests that care about the fee policy configure it
+        config.wallet.minimum_fee = 0;
+        config.wallet.minimum_fee_per_kilo_weight = 0;
+        config.wallet.dust_threshold = 0;
+        config.wallet.changeless_search_budget = 0;
+
+        Self {
+            config,
+            pool_size: 1,
+            db: None,
+            tcp_client: false,
+        }
+    }
+}
+
+impl HarnessBuilder {
+    /// Change the configuration of the wallet. Whatever is configured, the WebSocket server only
+    /// listens on a random local port, the HTTP one is disabled, and the first node URL is the one
+    /// reported by the fake node.
+    pub fn config<F>(mut self, f: F) -> Self
+    where
+        F: FnOnce(&mut Config),
+    {
+        f(&mut self.config);
+
+        self
+    }
+
+    /// Number of connections to the fake node that requests are spread over.
+    pub fn pool_size(mut self, pool_size: usize) -> Self {
+        self.pool_size = pool_size.max(1);
+
+        self
+    }
+
+    /// Use an existing database instead of a blank one, e.g. that of another harness, so as to
+    /// start a wallet whose wallets already exist.
+    pub fn db(mut self, db: Arc<rocksdb::DB>) -> Self {
+        self.db = Some(db);
+
+        self
+    }
+
+    /// Connect to the configured node URLs through `JsonRpcClient`s over TCP, as the wallet
+    /// application does, instead of to the fake node, which is then left unused.
+    pub fn tcp_client(mut self) -> Self {
+        self.tcp_client = true;
+
+        self
+    }
+
+    /// Start the worker and the App actor, connected to a fake node whose chain only has the
+    /// genesis block.
+    ///
+    /// This must be called from inside a running actix system.
+    pub fn start(mut self) -> Result<Harness, failure::Error> {
+        self.config.wallet.server_addr = SocketAddr::new(IpAddr::V4(Ipv4Addr::LOCALHOST), 0);
+        self.config.wallet.http_server_addr = None;
+        let node_url = self
+            .config
+            .wallet
+            .node_url
+            .first()
+            .cloned()
+            .ok_or(app::Error::NodeNotConnected)?;
+
+        let clock = VirtualClock::new();
+        let node = FakeNode::new(&node_url, clock.clone());
+        node.push_block(Block::genesis(
+            self.config.consensus_constants.bootstrap_hash,
+            vec![],
+        ));
+        let consensus_constants = serde_json::to_value(&self.config.consensus_constants)?;
+        node.reply_with("getConsensusConstants", move |_| {
+            consensus_constants.clone()
+        });
+
+        let node_subscriptions = Arc::new(Mutex::new(HashMap::new()));
+        let node_client = if self.tcp_client {
+            self.config.wallet.node_pool_size = self.pool_size;
+            crate::connect_to_nodes(&self.config, node_subscriptions.clone())?
+        } else {
+            let connections = (0..self.pool_size)
+                .map(|_| app::NodeConnection::new(node.connect()))
+                .collect();
+
+            Arc::new(app::NodeClient {
+                pool: pool::ConnectionPool::new(connections),
+                failover: Mutex::new(failover::NodeFailover::new(
+                    self.config.wallet.node_url.clone(),
+                    self.config.wallet.node_failover_threshold,
+                    i64::try_from(self.config.wallet.node_failover_min_interval)
+                        .unwrap_or(i64::MAX),
+                )),
+                schema: schema::SchemaValidator::new(self.config.wallet.strict_node_schema),
+            })
+        };
+
+        let db = match self.db {
+            Some(db) => db,
+            None => in_memory_db()?,
+        };
+        let app = crate::start(
+            &self.config,
+            db,
+            node_client,
+            node_subscriptions,
+            clock::Clock::Virtual(clock.clone()),
+        )?;
+        let mut handler = rpc::IoHandler::default();
+        app::connect_http_routes(&mut handler, app.clone());
+
+        Ok(Harness {
+            app,
+            handler,
+            node,
+            clock,
+            next_id: AtomicU64::new(0),
+        })
+    }
+}
+
+/// A running wallet connected to a fake node, whose timers follow a virtual clock.
+pub struct Harness {
+    app: Addr<App>,
+    handler: rpc::IoHandler,
+    node: FakeNode,
+    clock: VirtualClock,
+    next_id: AtomicU64,
+}
+
+impl Harness {
+    /// Create a builder with a configuration suitable for tests.
+    pub fn builder() -> HarnessBuilder {
+        HarnessBuilder::default()
+    }
+
+    /// The node that the wallet is connected to.
+    pub fn node(&self) -> &FakeNode {
+        &self.node
+    }
+
+    /// The clock of the timers of the wallet and of the delays of the node.
+    pub fn clock(&self) -> &VirtualClock {
+        &self.clock
+    }
+
+    /// Call a JSON-RPC method of the wallet, as a client of the HTTP transport would.
+    pub async fn call(&self, method: &str, params: Value) -> Result<Value, rpc::Error> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "method": method,
+            "params": params,
+            "id": self.next_id.fetch_add(1, Ordering::SeqCst),
+        });
+
+        let response = Compat01As03::new(self.handler.handle_request(&request.to_string()))
+            .await
+            .expect("JSON-RPC handlers never fail")
+            .expect("JSON-RPC calls with an id always have a response");
+
+        match serde_json::from_str(&response).expect("JSON-RPC responses are valid JSON") {
+            rpc::Output::Success(success) => Ok(success.result),
+            rpc::Output::Failure(failure) => Err(failure.error),
+        }
+    }
+
+    /// Create a wallet from mnemonics, returning its id.
+    pub async fn create_wallet(
+        &self,
+        mnemonics: &str,
+        password: &str,
+    ) -> Result<String, rpc::Error> {
+        let response = self
+            .call(
+                "create_wallet",
+                json!({
+                    "password": password,
+                    "seed_source": "mnemonics",
+                    "seed_data": mnemonics,
+                }),
+            )
+            .await?;
+
+        Ok(response["wallet_id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// Unlock a wallet in a new session, returning the id of the session.
+    pub async fn unlock_wallet(
+        &self,
+        wallet_id: &str,
+        password: &str,
+    ) -> Result<String, rpc::Error> {
+        let response = self
+            .call(
+                "unlock_wallet",
+                json!({
+                    "wallet_id": wallet_id,
+                    "password": password,
+                }),
+            )
+            .await?;
+
+        Ok(response["session_id"]
+            .as_str()
+            .unwrap_or_default()
+            .to_string())
+    }
+
+    /// Subscribe to the notifications of a session, as a WebSocket client would with `rpc.on`.
+    ///
+    /// The notifications that the session got before subscribing are replayed first, so that
+    /// nothing is missed because of subscribing after the work that a method started in the
+    /// background, such as the synchronization started by `unlock_wallet`.
+    pub async fn subscribe(&self, session_id: &str) -> Result<Notifications, rpc::Error> {
+        let session_id = types::SessionId::from(session_id.to_string());
+        let subscription_id = self
+            .app
+            .send(app::NextSubscriptionId(session_id.clone()))
+            .await
+            .map_err(app::Error::from)??;
+
+        let (sender, receiver) = mpsc::unbounded();
+        self.app
+            .send(app::Subscribe(
+                session_id,
+                subscription_id,
+                Box::new(ChannelTransport(sender)),
+                sink::OverflowPolicy::default(),
+                Some(0),
+                None,
+            ))
+            .await
+            .map_err(app::Error::from)??;
+
+        Ok(Notifications { receiver })
+    }
+
+    /// Advance the virtual clock, and let the wallet react to the timers that fire.
+    pub async fn advance(&self, duration: Duration) {
+        self.clock.advance(duration);
+        self.settle().await;
+    }
+
+    /// Counters of the processing of the blocks notified by the node so far.
+    pub async fn block_processing(&self) -> metrics::BlockProcessing {
+        self.app
+            .send(GetBlockProcessing)
+            .await
+            .expect("The App actor stops only when the harness is dropped")
+    }
+
+    /// Depth of the queues of the worker, and how much work they shed so far.
+    pub async fn work_queues(&self) -> metrics::WorkQueues {
+        self.app
+            .send(GetWorkQueues)
+            .await
+            .expect("The App actor stops only when the harness is dropped")
+    }
+
+    /// Requests of clients in flight, and how many were refused so far for going over the limits
+    /// of their sessions.
+    pub async fn requests_in_flight(&self) -> metrics::RequestsInFlight {
+        self.app
+            .send(GetRequestsInFlight)
+            .await
+            .expect("The App actor stops only when the harness is dropped")
+    }
+
+    /// Give the App actor a few turns to handle its pending messages and timers, and the fake
+    /// node to answer the requests that they make.
+    ///
+    /// Work handed to the worker runs in other threads, so its effects must be waited for through
+    /// notifications instead.
+    pub async fn settle(&self) {
+        for _ in 0..SETTLE_ROUND_TRIPS {
+            self.app
+                .send(Ping)
+                .await
+                .expect("The App actor stops only when the harness is dropped");
+        }
+    }
+}
+
+/// Message that the App actor answers with its block processing counters.
+struct GetBlockProcessing;
+
+impl Message for GetBlockProcessing {
+    type Result = metrics::BlockProcessing;
+}
+
+impl Handler<GetBlockProcessing> for App {
+    type Result = MessageResult<GetBlockProcessing>;
+
+    fn handle(&mut self, _msg: GetBlockProcessing, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.block_processing())
+    }
+}
+
+/// Message that the App actor answers with the depth of the queues of the worker.
+struct GetWorkQueues;
+
+impl Message for GetWorkQueues {
+    type Result = metrics::WorkQueues;
+}
+
+impl Handler<GetWorkQueues> for App {
+    type Result = MessageResult<GetWorkQueues>;
+
+    fn handle(&mut self, _msg: GetWorkQueues, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.work_queues())
+    }
+}
+
+/// Message that the App actor answers with the requests of clients in flight.
+struct GetRequestsInFlight;
+
+impl Message for GetRequestsInFlight {
+    type Result = metrics::RequestsInFlight;
+}
+
+impl Handler<GetRequestsInFlight> for App {
+    type Result = MessageResult<GetRequestsInFlight>;
+
+    fn handle(&mut self, _msg: GetRequestsInFlight, _ctx: &mut Self::Context) -> Self::Result {
+        MessageResult(self.requests_in_flight())
+    }
+}
+
+/// Message that the App actor answers right away, for waiting until it has had a turn.
+struct Ping;
+
+impl Message for Ping {
+    type Result = ();
+}
+
+impl Handler<Ping> for App {
+    type Result = ();
+
+    fn handle(&mut self, _msg: Ping, _ctx: &mut Self::Context) {}
+}
+
+/// Transport of a subscription that forwards notifications to a channel.
+struct ChannelTransport(mpsc::UnboundedSender<Value>);
+
+impl sink::Transport for ChannelTransport {
+    fn try_notify(&self, params: rpc::Params) -> Result<(), sink::TransportError> {
+        let payload = match params {
+            rpc::Params::Array(mut values) if values.len() == 1 => values.remove(0),
+            params => json!(params),
+        };
+
+        self.0
+            .unbounded_send(payload)
+            .map_err(|_| sink::TransportError::Closed)
+    }
+}
+
+/// Notifications received by a subscription, in order.
+pub struct Notifications {
+    receiver: mpsc::UnboundedReceiver<Value>,
+}
+
+impl Notifications {
+    /// Wait for the next notification.
+    ///
+    /// Panics if none arrives within 10 seconds of real time.
+    pub async fn next(&mut self) -> Value {
+        tokio::time::timeout(NOTIFICATION_TIMEOUT, self.receiver.next())
+            .await
+            .expect("Timed out waiting for a notification")
+            .expect("The subscription was closed")
+    }
+
+    /// Wait for a notification with an event of the given kind, e.g. `Movement`, skipping the
+    /// notifications before it, and return the content of that event.
+    ///
+    /// Panics if none arrives within 10 seconds of real time.
+    pub async fn next_event(&mut self, kind: &str) -> Value {
+        loop {
+            let notification = self.next().await;
+            if let Some(event) = find_event(&notification, kind) {
+                return event;
+            }
+        }
+    }
+
+    /// Notifications that have already arrived, without waiting for more.
+    pub fn received(&mut self) -> Vec<Value> {
+        let mut received = vec![];
+        while let Ok(Some(notification)) = self.receiver.try_next() {
+            received.push(notification);
+        }
+
+        received
+    }
+}
+
+/// Content of the first event of the given kind in a notification. Events without content, such
+/// as `NodeDisconnected`, have `null` as content.
+pub fn find_event(notification: &Value, kind: &str) -> Option<Value> {
+    notification["events"]
+        .as_array()?
+        .iter()
+        .find_map(|event| match event {
+            Value::String(name) if name == kind => Some(Value::Null),
+            Value::Object(event) => event.get(kind).cloned(),
+            _ => None,
+        })
+}
@@ -0,0 +1,444 @@
+//! A scripted Witnet node for the wallet to connect to instead of a real one.
+//!
+//! Every connection to the node is an actor handling the same messages as `JsonRpcClient`, so it
+//! can be put in the connection pool of the wallet. The state of the node is shared by all of its
+//! connections and by every clone of the `FakeNode` handle, which is what tests use to script it.
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use actix::prelude::*;
+use async_jsonrpc_client::ErrorKind as TransportErrorKind;
+use serde_json::json;
+use witnet_data_structures::{
+    chain::{
+        Block, BlockHeader, BlockTransactions, CheckpointBeacon, Hashable, KeyedSignature,
+        StateMachine, SyncStatus, ValueTransferOutput,
+    },
+    transaction::{MintTransaction, VTTransaction},
+};
+use witnet_net::client::tcp::{
+    self,
+    jsonrpc::{GetCurrentNodeUrl, NotifySubscriptionTopic, Request, Subscribe, SwitchUrl, Value},
+};
+
+use crate::{clock::VirtualClock, types};
+
+type Responder = Box<dyn Fn(&Value) -> Value + Send>;
+
+/// A scripted response to a request, sent after some time of the virtual clock has passed.
+struct Reply {
+    delay: Duration,
+    result: Value,
+}
+
+struct State {
+    url: String,
+    connected: bool,
+    node_state: StateMachine,
+    chain: Vec<Block>,
+    replies: HashMap<String, VecDeque<Reply>>,
+    responders: HashMap<String, Responder>,
+    /// Methods that the node answers as if it did not implement them.
+    unimplemented: HashSet<String>,
+    requests: Vec<(String, Value)>,
+    /// Subscriptions by topic that notifications are delivered to.
+    active: HashMap<String, Recipient<NotifySubscriptionTopic>>,
+    /// Subscriptions that the node lost when disconnecting, which clients recover on reconnection.
+    lost: HashMap<String, Recipient<NotifySubscriptionTopic>>,
+}
+
+/// Handle to a scripted Witnet node. Clones share the same node.
+///
+/// Requests are answered, in this order of preference:
+/// - with a method not found error, for the methods removed by `remove_method`,
+/// - with the replies queued for their method by `reply` and `reply_after`, which are used once,
+/// - by the responder set for their method by `reply_with`,
+/// - from the chain and status of the node, for `getBlockChain`, `getBlock` and `syncStatus`,
+/// - with `null`, which the wallet fails to parse as anything it expects.
+///
+/// While the node is disconnected, every request fails with a connection error and notifications
+/// are not delivered.
+#[derive(Clone)]
+pub struct FakeNode {
+    state: Arc<Mutex<State>>,
+    clock: VirtualClock,
+}
+
+impl FakeNode {
+    /// Create a synced node with an empty chain, whose scripted delays are measured with `clock`.
+    pub fn new(url: &str, clock: VirtualClock) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(State {
+                url: url.to_string(),
+                connected: true,
+                node_state: StateMachine::Synced,
+                chain: vec![],
+                replies: Default::default(),
+                responders: Default::default(),
+                unimplemented: Default::default(),
+                requests: vec![],
+                active: Default::default(),
+                lost: Default::default(),
+            })),
+            clock,
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("Fake node locks should only fail if poisoned")
+    }
+
+    /// Start a new connection to the node.
+    pub fn connect(&self) -> Addr<FakeConnection> {
+        FakeConnection { node: self.clone() }.start()
+    }
+
+    /// URL that the connections to the node report.
+    pub fn url(&self) -> String {
+        self.lock().url.clone()
+    }
+
+    /// Queue a response to the next request of `method` that has no response queued yet.
+    pub fn reply(&self, method: &str, result: Value) {
+        self.reply_after(method, Duration::from_secs(0), result);
+    }
+
+    /// Queue a response like `reply`, which is only sent once the virtual clock has been advanced
+    /// by `delay` after the request arrives.
+    pub fn reply_after(&self, method: &str, delay: Duration, result: Value) {
+        self.lock()
+            .replies
+            .entry(method.to_string())
+            .or_default()
+            .push_back(Reply { delay, result });
+    }
+
+    /// Answer the requests of `method` that have no response queued by calling `responder` with
+    /// their params.
+    pub fn reply_with<F>(&self, method: &str, responder: F)
+    where
+        F: Fn(&Value) -> Value + Send + 'static,
+    {
+        self.lock()
+            .responders
+            .insert(method.to_string(), Box::new(responder));
+    }
+
+    /// Answer the requests of `method` as a node that does not implement it.
+    pub fn remove_method(&self, method: &str) {
+        self.lock().unimplemented.insert(method.to_string());
+    }
+
+    /// Change the state reported by `syncStatus`.
+    pub fn set_node_state(&self, node_state: StateMachine) {
+        self.lock().node_state = node_state;
+    }
+
+    /// Blocks of the chain of the node, in order.
+    pub fn chain(&self) -> Vec<Block> {
+        self.lock().chain.clone()
+    }
+
+    /// Append a block to the chain of the node, without notifying it.
+    pub fn push_block(&self, block: Block) {
+        self.lock().chain.push(block);
+    }
+
+    /// Mine a block on top of the chain of the node whose mint transaction pays `outputs`, append
+    /// it to the chain and notify it to the `blocks` subscribers.
+    pub fn mine_block(&self, outputs: Vec<ValueTransferOutput>) -> Block {
+        self.mine_block_with_transactions(outputs, vec![])
+    }
+
+    /// Mine a block like `mine_block` does, that also includes some value transfer transactions.
+    pub fn mine_block_with_transactions(
+        &self,
+        outputs: Vec<ValueTransferOutput>,
+        value_transfer_txns: Vec<VTTransaction>,
+    ) -> Block {
+        let beacon = {
+            let state = self.lock();
+            let tip = state
+                .chain
+                .last()
+                .expect("A chain must have a genesis block before mining on top of it");
+
+            CheckpointBeacon {
+                checkpoint: tip.block_header.beacon.checkpoint + 1,
+                hash_prev_block: tip.hash(),
+            }
+        };
+        let block = Block::new(
+            BlockHeader {
+                beacon,
+                ..Default::default()
+            },
+            KeyedSignature::default(),
+            BlockTransactions {
+                mint: MintTransaction::new(beacon.checkpoint, outputs),
+                value_transfer_txns,
+                ..Default::default()
+            },
+        );
+
+        self.push_block(block.clone());
+        self.notify("blocks", serde_json::to_value(&block).unwrap());
+
+        block
+    }
+
+    /// Push a notification to the subscribers of `topic`. Returns whether anyone received it.
+    pub fn notify(&self, topic: &str, value: Value) -> bool {
+        let state = self.lock();
+        match state.active.get(topic) {
+            Some(recipient) if state.connected => {
+                recipient.do_send(NotifySubscriptionTopic {
+                    topic: topic.to_string(),
+                    value,
+                });
+
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Push a notification like `notify` once the virtual clock has been advanced by `delay`.
+    ///
+    /// This must be called from inside a running actix system.
+    pub fn notify_after(&self, delay: Duration, topic: &str, value: Value) {
+        let node = self.clone();
+        let topic = topic.to_string();
+        let sleep = self.clock.sleep(delay);
+        actix::spawn(async move {
+            sleep.await;
+            node.notify(&topic, value);
+        });
+    }
+
+    /// Drop every connection to the node: requests fail with a connection error and the
+    /// subscriptions are lost.
+    pub fn disconnect(&self) {
+        let mut state = self.lock();
+        state.connected = false;
+        let active = std::mem::take(&mut state.active);
+        state.lost.extend(active);
+    }
+
+    /// Accept connections again. Clients recover their lost subscriptions, as `JsonRpcClient`
+    /// does after reconnecting, so a `witnet_subscribe` request is recorded for each of them.
+    pub fn reconnect(&self) {
+        let mut state = self.lock();
+        state.connected = true;
+        let lost = std::mem::take(&mut state.lost);
+        for (topic, recipient) in lost {
+            state
+                .requests
+                .push(("witnet_subscribe".to_string(), json!([topic])));
+            state.active.insert(topic, recipient);
+        }
+    }
+
+    /// Whether the node accepts connections.
+    pub fn is_connected(&self) -> bool {
+        self.lock().connected
+    }
+
+    /// Method and params of every request received by the node, in order.
+    pub fn requests(&self) -> Vec<(String, Value)> {
+        self.lock().requests.clone()
+    }
+
+    /// Params of every request of `method` received by the node, in order.
+    pub fn requests_of(&self, method: &str) -> Vec<Value> {
+        self.lock()
+            .requests
+            .iter()
+            .filter(|(request_method, _)| request_method == method)
+            .map(|(_, params)| params.clone())
+            .collect()
+    }
+
+    /// Record a request and decide how to answer it.
+    fn answer(&self, method: &str, params: &Value) -> (Duration, Result<Value, tcp::Error>) {
+        let mut state = self.lock();
+        state.requests.push((method.to_string(), params.clone()));
+
+        if !state.connected {
+            return (
+                Duration::from_secs(0),
+                Err(tcp::Error::RequestFailed {
+                    error_kind: TransportErrorKind::Unreachable,
+                    message: format!("fake node at {} is disconnected", state.url),
+                }),
+            );
+        }
+
+        if state.unimplemented.contains(method) {
+            return (
+                Duration::from_secs(0),
+                Err(tcp::Error::MethodNotFound {
+                    method: method.to_string(),
+                }),
+            );
+        }
+
+        if let Some(reply) = state
+            .replies
+            .get_mut(method)
+            .and_then(|replies| replies.pop_front())
+        {
+            return (reply.delay, Ok(reply.result));
+        }
+
+        let result = match (state.responders.get(method), method) {
+            (Some(responder), _) => responder(params),
+            (None, "syncStatus") => serde_json::to_value(SyncStatus {
+                chain_beacon: state
+                    .chain
+                    .last()
+                    .map(|block| CheckpointBeacon {
+                        checkpoint: block.block_header.beacon.checkpoint,
+                        hash_prev_block: block.hash(),
+                    })
+                    .unwrap_or_default(),
+                current_epoch: state
+                    .chain
+                    .last()
+                    .map(|block| block.block_header.beacon.checkpoint),
+                node_state: state.node_state,
+            })
+            .unwrap(),
+            (None, "getBlockChain") => get_block_chain(&state.chain, params),
+            (None, "getBlock") => get_block(&state.chain, params),
+            (None, _) => Value::Null,
+        };
+
+        (Duration::from_secs(0), Ok(result))
+    }
+
+    fn subscribe(
+        &self,
+        topic: String,
+        params: Value,
+        recipient: Recipient<NotifySubscriptionTopic>,
+    ) {
+        let mut state = self.lock();
+        state
+            .requests
+            .push(("witnet_subscribe".to_string(), params));
+        if state.connected {
+            state.active.insert(topic, recipient);
+        } else {
+            state.lost.insert(topic, recipient);
+        }
+    }
+}
+
+/// Answer `getBlockChain` with the epoch and hash of the blocks of `chain` in the requested range.
+fn get_block_chain(chain: &[Block], params: &Value) -> Value {
+    let types::GetBlockChainParams { epoch, limit } =
+        serde_json::from_value(params.clone()).unwrap_or_default();
+    let tip = chain
+        .last()
+        .map(|block| i64::from(block.block_header.beacon.checkpoint))
+        .unwrap_or_default();
+    let since = if epoch < 0 { tip + 1 + epoch } else { epoch };
+
+    let entries: Vec<_> = chain
+        .iter()
+        .filter(|block| i64::from(block.block_header.beacon.checkpoint) >= since)
+        .map(|block| {
+            json!([
+                block.block_header.beacon.checkpoint,
+                block.hash().to_string()
+            ])
+        })
+        .collect();
+    let count = usize::try_from(limit.unsigned_abs()).unwrap_or(usize::MAX);
+    let entries = match limit {
+        0 => entries,
+        limit if limit > 0 => entries.into_iter().take(count).collect(),
+        _ => entries[entries.len().saturating_sub(count)..].to_vec(),
+    };
+
+    Value::Array(entries)
+}
+
+/// Answer `getBlock` with the block of `chain` that has the requested hash, as pending.
+fn get_block(chain: &[Block], params: &Value) -> Value {
+    let hash = params.get(0).and_then(Value::as_str).unwrap_or_default();
+
+    chain
+        .iter()
+        .find(|block| block.hash().to_string() == hash)
+        .map(|block| {
+            let mut value = serde_json::to_value(block).unwrap();
+            value["confirmed"] = json!(false);
+
+            value
+        })
+        .unwrap_or(Value::Null)
+}
+
+/// A connection to a `FakeNode`.
+pub struct FakeConnection {
+    node: FakeNode,
+}
+
+impl Actor for FakeConnection {
+    type Context = Context<Self>;
+}
+
+impl Handler<Request> for FakeConnection {
+    type Result = ResponseFuture<Result<Value, tcp::Error>>;
+
+    fn handle(&mut self, request: Request, _ctx: &mut Self::Context) -> Self::Result {
+        let (delay, result) = self.node.answer(request.get_method(), request.get_params());
+        let sleep = self.node.clock.sleep(delay);
+
+        Box::pin(async move {
+            sleep.await;
+
+            result
+        })
+    }
+}
+
+impl Handler<Subscribe> for FakeConnection {
+    type Result = ();
+
+    fn handle(&mut self, Subscribe(request, recipient): Subscribe, _ctx: &mut Self::Context) {
+        let params = request.get_params().clone();
+        let topic = params
+            .get(0)
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string();
+
+        self.node.subscribe(topic, params, recipient);
+    }
+}
+
+impl Handler<GetCurrentNodeUrl> for FakeConnection {
+    type Result = String;
+
+    fn handle(&mut self, _msg: GetCurrentNodeUrl, _ctx: &mut Self::Context) -> Self::Result {
+        self.node.url()
+    }
+}
+
+impl Handler<SwitchUrl> for FakeConnection {
+    type Result = Result<(), tcp::Error>;
+
+    fn handle(&mut self, SwitchUrl(url): SwitchUrl, _ctx: &mut Self::Context) -> Self::Result {
+        self.node.lock().url = url;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,144 @@
+//! A Witnet node reachable over TCP, for the wallet to connect to with its real JSON-RPC client.
+//!
+//! It speaks the newline-delimited JSON-RPC of the node, answering `syncStatus` as a synced node
+//! with an empty chain, `witnet_subscribe` with a new subscription id, and every other method with
+//! `null`. Once stopped, it drops its connections and every new one, as a node that went down.
+use std::{
+    io::{BufRead, BufReader, Write},
+    net::{Shutdown, TcpListener, TcpStream},
+    sync::{Arc, Mutex},
+    thread,
+};
+
+use serde_json::{json, Value};
+use witnet_data_structures::chain::{StateMachine, SyncStatus};
+
+#[derive(Default)]
+struct State {
+    stopped: bool,
+    /// Method of every request received, in order.
+    requests: Vec<String>,
+    /// Subscriptions made so far, which gives the id of the next one.
+    subscriptions: u64,
+    connections: Vec<TcpStream>,
+}
+
+/// A node listening on a random local port. Clones share the same node.
+#[derive(Clone)]
+pub struct TcpNode {
+    url: String,
+    state: Arc<Mutex<State>>,
+}
+
+impl TcpNode {
+    /// Start listening.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("Failed to bind the TCP node");
+        let node = Self {
+            url: listener.local_addr().unwrap().to_string(),
+            state: Default::default(),
+        };
+
+        let accepting = node.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(_) => break,
+                };
+                {
+                    let mut state = accepting.lock();
+                    // Connections to a stopped node are closed right away
+                    if state.stopped {
+                        continue;
+                    }
+                    match stream.try_clone() {
+                        Ok(connection) => state.connections.push(connection),
+                        Err(_) => continue,
+                    }
+                }
+
+                let serving = accepting.clone();
+                thread::spawn(move || serving.serve(stream));
+            }
+        });
+
+        node
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .expect("TCP node locks should only fail if poisoned")
+    }
+
+    /// Address to connect to, as configured in `wallet.node_url`.
+    pub fn url(&self) -> String {
+        self.url.clone()
+    }
+
+    /// Drop every connection and refuse to answer any more requests.
+    pub fn stop(&self) {
+        let mut state = self.lock();
+        state.stopped = true;
+        for connection in state.connections.drain(..) {
+            connection.shutdown(Shutdown::Both).ok();
+        }
+    }
+
+    /// How many requests of `method` the node answered.
+    pub fn requests_of(&self, method: &str) -> usize {
+        self.lock()
+            .requests
+            .iter()
+            .filter(|request| *request == method)
+            .count()
+    }
+
+    /// Answer the requests of a connection until it is closed or the node is stopped.
+    fn serve(&self, stream: TcpStream) {
+        let mut writer = match stream.try_clone() {
+            Ok(writer) => writer,
+            Err(_) => return,
+        };
+
+        for line in BufReader::new(stream).lines() {
+            let request: Value = match line.map(|line| serde_json::from_str(&line)) {
+                Ok(Ok(request)) => request,
+                Ok(Err(_)) => continue,
+                Err(_) => break,
+            };
+            let method = request["method"].as_str().unwrap_or_default();
+            let result = {
+                let mut state = self.lock();
+                if state.stopped {
+                    break;
+                }
+                state.requests.push(method.to_string());
+
+                match method {
+                    "syncStatus" => serde_json::to_value(SyncStatus {
+                        chain_beacon: Default::default(),
+                        current_epoch: None,
+                        node_state: StateMachine::Synced,
+                    })
+                    .unwrap(),
+                    "witnet_subscribe" => {
+                        state.subscriptions += 1;
+                        json!(format!("0x{:x}", state.subscriptions))
+                    }
+                    _ => Value::Null,
+                }
+            };
+
+            let response = json!({
+                "jsonrpc": "2.0",
+                "result": result,
+                "id": request["id"],
+            });
+            if writeln!(writer, "{}", response).is_err() {
+                break;
+            }
+        }
+    }
+}
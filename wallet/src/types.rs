@@ -7,7 +7,6 @@ use std::{
 };
 
 use core::fmt::Display;
-use failure::Fail;
 use serde::{Deserialize, Deserializer, Serialize};
 use witnet_crypto::{
     key::{ExtendedSK, SK},
@@ -28,7 +27,7 @@ use witnet_data_structures::{
 };
 use witnet_protected::{Protected, ProtectedString};
 
-use crate::{app::VttOutputParams, model};
+use crate::{app::VttOutputParams, credentials, failover, model, pool, schema, sink, watchdog};
 
 use super::{db, repository};
 
@@ -43,35 +42,6 @@ pub type Wallet = repository::Wallet<db::EncryptedDb>;
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
 pub struct SessionId(String);
 
-#[derive(Debug, Fail)]
-pub enum Errors {
-    #[fail(
-        display = "Tried to construct a `SessionId` from a `SubscriptionId` that is not a `String`"
-    )]
-    SubscriptionIdIsNotValidSessionId,
-}
-
-/// Convenient conversion from `SessionId` to `SubscriptionId`
-impl From<&SessionId> for jsonrpc_pubsub::SubscriptionId {
-    fn from(id: &SessionId) -> Self {
-        jsonrpc_pubsub::SubscriptionId::String(String::from(id.clone()))
-    }
-}
-
-/// Convenient conversion from `SubscriptionId` to `SessionId`
-impl TryFrom<&jsonrpc_pubsub::SubscriptionId> for SessionId {
-    type Error = crate::actors::app::Error;
-
-    fn try_from(id: &jsonrpc_pubsub::SubscriptionId) -> Result<Self, Self::Error> {
-        match id {
-            jsonrpc_pubsub::SubscriptionId::String(string) => Ok(SessionId::from(string.clone())),
-            _ => Err(crate::actors::app::error::internal_error(
-                Errors::SubscriptionIdIsNotValidSessionId,
-            )),
-        }
-    }
-}
-
 impl fmt::Display for SessionId {
     #[inline]
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
@@ -122,7 +92,7 @@ pub struct Account {
 }
 
 pub struct WalletData {
-    pub id: String,
+    pub id: model::WalletId,
     pub name: Option<String>,
     pub description: Option<String>,
     pub balance: model::WalletBalance,
@@ -131,10 +101,12 @@ pub struct WalletData {
     pub last_sync: CheckpointBeacon,
     pub last_confirmed: CheckpointBeacon,
     pub birth_date: CheckpointBeacon,
+    pub key_derivation: model::KeyDerivation,
 }
 
 pub struct CreateWalletData<'a> {
-    pub id: &'a str,
+    pub id: &'a model::WalletId,
+    pub seed_fingerprint: String,
     pub name: Option<String>,
     pub description: Option<String>,
     pub iv: Vec<u8>,
@@ -142,20 +114,28 @@ pub struct CreateWalletData<'a> {
     pub account: &'a Account,
     pub master_key: Option<ExtendedSK>,
     pub birth_date: CheckpointBeacon,
+    pub seed_backup: Option<Secret>,
+    pub key_derivation: model::KeyDerivation,
 }
 
 pub struct VttParams {
     pub fee: Fee,
     pub outputs: Vec<ValueTransferOutput>,
+    /// Local labels of the outputs, in the same order. Missing labels are `None`.
+    pub labels: Vec<Option<String>>,
     pub utxo_strategy: UtxoSelectionStrategy,
     pub selected_utxos: HashSet<model::OutPtr>,
     pub preview: bool,
+    /// Skip the minimum fee checks, but not the validity checks of the outputs.
+    pub allow_low_fee: bool,
 }
 
 pub struct DataReqParams {
     pub fee: Fee,
     pub request: DataRequestOutput,
     pub preview: bool,
+    /// Skip the minimum fee checks.
+    pub allow_low_fee: bool,
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -210,6 +190,57 @@ pub enum BirthDate {
     Imported(Epoch),
 }
 
+/// Where to start scanning the chain when resynchronizing a wallet
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, Serialize, Deserialize)]
+pub enum ResyncFrom {
+    /// Start at the birth date of the wallet, skipping the blocks prior to its creation
+    #[default]
+    #[serde(rename = "birth_date")]
+    BirthDate,
+    /// Start at the genesis block, for wallets whose birth date may be wrong
+    #[serde(rename = "genesis")]
+    Genesis,
+}
+
+/// Capability of a session, which decides which methods it can call. Scopes are independent of
+/// each other, e.g. a session with the `send` scope cannot read balances unless it also has the
+/// `read` one.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Query wallets without modifying them
+    Read,
+    /// Create and send transactions
+    Send,
+    /// Sign arbitrary data with the keys of the wallets
+    Sign,
+    /// Change wallets, their configuration or that of the wallet server
+    Admin,
+    /// Sign commits and reveals on behalf of a node, as allowed by the policy of the wallets
+    #[serde(rename = "witness_signing")]
+    WitnessSigning,
+}
+
+impl Scope {
+    /// Scopes of the sessions that request none when opening them, which are all but
+    /// `witness_signing`, as that one is only meant for the sessions of nodes.
+    pub const DEFAULT: [Scope; 4] = [Scope::Read, Scope::Send, Scope::Sign, Scope::Admin];
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let scope = match self {
+            Scope::Read => "read",
+            Scope::Send => "send",
+            Scope::Sign => "sign",
+            Scope::Admin => "admin",
+            Scope::WitnessSigning => "witness_signing",
+        };
+
+        f.write_str(scope)
+    }
+}
+
 impl TryFrom<&ChainEntry> for CheckpointBeacon {
     type Error = hex::FromHexError;
 
@@ -224,10 +255,10 @@ impl TryFrom<&ChainEntry> for CheckpointBeacon {
     }
 }
 
-/// A reference-counted atomic read/write lock over the `Option` of a `Sink`.
+/// A reference-counted atomic read/write lock over the `SessionSink` of a session.
 /// Allows swapping, adding and removing sinks in runtime through interior mutability of any
 /// structures that may include this type.
-pub type DynamicSink = Arc<RwLock<Option<jsonrpc_pubsub::Sink>>>;
+pub type DynamicSink = Arc<RwLock<sink::SessionSink>>;
 
 /// Friendly events that can be sent to subscribed clients to let them now about significant
 /// activity related to their wallets.
@@ -236,16 +267,35 @@ pub enum Event {
     /// The basic information of a new block that has already been processed but is pending
     /// consolidation (anchoring into a future superblock).
     Block(model::Beacon),
+    /// The address of a payment request received a payment after it was done with.
+    AddressReuse(model::AddressReuse),
+    /// An external address was generated beyond the gap limit, which is only notified for the
+    /// first of them until the gap closes again.
+    AddressGapExceeded(model::AddressGapWarning),
+    /// An update on the progress of an import of address metadata: entries written and total
+    /// entries.
+    AddressMetadataImportProgress(u32, u32),
     /// A list of hashes of blocks that are now considered final.
     BlocksConsolidate(Vec<String>),
     /// A list of hashes of blocks that are now considered orphaned.
     BlocksOrphan(Vec<String>),
+    /// An update on the progress of an integrity check: records checked and total records.
+    IntegrityCheckProgress(u32, u32),
     /// A new movement (transaction) affecting balance.
     Movement(model::BalanceMovement),
     /// Node status has changed
     NodeStatus(StateMachine),
     /// Node disconnected
     NodeDisconnected,
+    /// The node made no progress for this many epochs.
+    NodeStalled(u32),
+    /// The node made progress again after having stalled.
+    NodeResumed,
+    /// The node reports an epoch this many epochs ahead of the one given by the clock of the
+    /// wallet.
+    NodeClockSkew(u32),
+    /// A payment request changed status, because of a confirmed payment or its expiration.
+    PaymentRequest(model::PaymentRequest),
     /// The end of a synchronization progress.
     SyncFinish(u32, u32),
     /// An update on the progress of a the synchronization progress.
@@ -256,6 +306,23 @@ pub enum Event {
     SyncError(u32, u32),
 }
 
+impl Event {
+    /// Topic under which the event is delivered to webhooks, if it is delivered to them at all.
+    pub fn webhook_topic(&self) -> Option<model::WebhookTopic> {
+        match self {
+            Event::Movement(_) => Some(model::WebhookTopic::Movement),
+            Event::PaymentRequest(request)
+                if request.status == model::PaymentRequestStatus::Paid =>
+            {
+                Some(model::WebhookTopic::PaymentRequestPaid)
+            }
+            Event::AddressReuse(_) => Some(model::WebhookTopic::AddressReuse),
+            Event::SyncFinish(..) => Some(model::WebhookTopic::SyncFinish),
+            _ => None,
+        }
+    }
+}
+
 /// Format of the output of getTransaction
 #[derive(Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -267,6 +334,42 @@ pub struct GetTransactionResponse {
     pub block_hash: String,
 }
 
+/// Status of the nodes that the wallet connects to, as answered by `get_node_status`.
+///
+/// Its parts are flattened, so that clients get a single object with all their fields.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeStatus {
+    /// Which of the configured nodes is in use, and the switches between them.
+    #[serde(flatten)]
+    pub failover: failover::FailoverStatus,
+    /// How the active node differs from what the wallet expects of it.
+    #[serde(flatten)]
+    pub compatibility: NodeCompatibility,
+    /// Outcome of unlocking the wallets of the credentials file at startup, if one is configured.
+    pub startup_unlock: Option<credentials::StartupUnlockReport>,
+    /// How the connections to the active node and the node itself are doing.
+    #[serde(flatten)]
+    pub health: NodeHealth,
+}
+
+/// Differences between the active node and what the wallet expects of it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeCompatibility {
+    /// Consensus constants whose value in the active node differs from the one in the wallet.
+    pub consensus_constants_mismatch: Vec<String>,
+    /// Results of the active node that do not have the fields that the wallet expects.
+    pub schema_mismatches: Vec<schema::NodeSchemaMismatch>,
+}
+
+/// Health of the active node and of the connections to it.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeHealth {
+    /// State of every connection to the active node.
+    pub connections: Vec<pool::ConnectionStatus>,
+    /// Whether the node keeps making progress, and whether its clock is ahead of the wallet's.
+    pub liveness: watchdog::NodeLiveness,
+}
+
 /// Notification signaling that a superblock has been consolidated.
 ///
 /// As per current consensus algorithm, "consolidated blocks" implies that there exists at least one
@@ -577,6 +680,8 @@ pub struct VttOutputParamsHelper {
     )]
     pub amount: u64,
     pub time_lock: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
 }
 
 impl From<VttOutputParams> for VttOutputParamsHelper {
@@ -585,6 +690,7 @@ impl From<VttOutputParams> for VttOutputParamsHelper {
             address: x.address.parse().unwrap(),
             amount: x.amount,
             time_lock: x.time_lock,
+            label: x.label,
         }
     }
 }
@@ -595,6 +701,7 @@ impl From<VttOutputParamsHelper> for VttOutputParams {
             address: x.address.to_string(),
             amount: x.amount,
             time_lock: x.time_lock,
+            label: x.label,
         }
     }
 }
@@ -675,6 +782,17 @@ where
     }
 }
 
+pub fn i64_to_string<S>(val: &i64, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    if serializer.is_human_readable() {
+        serializer.serialize_str(&val.to_string())
+    } else {
+        serializer.serialize_i64(*val)
+    }
+}
+
 pub fn number_from_string<'de, T, D>(deserializer: D) -> Result<T, D::Error>
 where
     D: Deserializer<'de>,
@@ -697,6 +815,71 @@ where
     }
 }
 
+pub fn option_u64_to_string<S>(val: &Option<u64>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match val {
+        Some(val) if serializer.is_human_readable() => serializer.serialize_some(&val.to_string()),
+        Some(val) => serializer.serialize_some(val),
+        None => serializer.serialize_none(),
+    }
+}
+
+pub fn option_u32_to_string<S>(val: &Option<u32>, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+{
+    match val {
+        Some(val) if serializer.is_human_readable() => serializer.serialize_some(&val.to_string()),
+        Some(val) => serializer.serialize_some(val),
+        None => serializer.serialize_none(),
+    }
+}
+
+/// Serialize a value in human-readable formats only, such as the JSON of responses. Binary formats
+/// get nothing instead, so that adding such a value to a type does not change how it is persisted.
+pub fn human_readable_only<S, T>(val: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: serde::Serializer,
+    T: Serialize,
+{
+    if serializer.is_human_readable() {
+        val.serialize(serializer)
+    } else {
+        serializer.serialize_unit()
+    }
+}
+
+/// Deserialize a value serialized with `human_readable_only`, which is the default one in binary
+/// formats.
+pub fn human_readable_only_or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    if deserializer.is_human_readable() {
+        T::deserialize(deserializer)
+    } else {
+        <()>::deserialize(deserializer).map(|()| T::default())
+    }
+}
+
+pub fn option_number_from_string<'de, T, D>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr + serde::Deserialize<'de>,
+    <T as FromStr>::Err: Display,
+{
+    #[derive(Deserialize)]
+    #[serde(bound(
+        deserialize = "T: FromStr + serde::Deserialize<'de>, <T as FromStr>::Err: Display"
+    ))]
+    struct Wrapper<T>(#[serde(deserialize_with = "number_from_string")] T);
+
+    Option::<Wrapper<T>>::deserialize(deserializer).map(|wrapper| wrapper.map(|Wrapper(x)| x))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,228 @@
+//! Detection of a node that stops making progress.
+//!
+//! A node can stay connected and answer every health check while being stuck, e.g. because it
+//! lost all its peers. The watchdog counts the epochs that pass without the node notifying blocks
+//! or superblocks, or reporting a newer current epoch, and considers it stalled after too many of
+//! them. It also compares the epoch reported by the node with the one expected from the clock of
+//! the wallet, as a node whose clock runs ahead reports epochs that do not exist yet.
+use serde::Serialize;
+
+/// Changes of the liveness of the node that clients are alerted about.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Alert {
+    /// No progress for this many epochs.
+    Stalled(u32),
+    /// Progress after having stalled.
+    Resumed,
+    /// The node reports an epoch this many epochs ahead of the expected one.
+    ClockSkew(u32),
+}
+
+/// Snapshot of the liveness of the node, as shown to clients.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct NodeLiveness {
+    /// Whether the node made no progress for too many epochs.
+    pub stalled: bool,
+    /// Epochs that passed since the node last made progress.
+    pub epochs_without_progress: u32,
+    /// Last current epoch reported by the node.
+    pub last_epoch: Option<u32>,
+    /// Timestamp of the last block or superblock notified by the node.
+    pub last_notification: Option<i64>,
+    /// Epochs by which the current epoch of the node is ahead of the expected one, if that is more
+    /// than allowed.
+    pub clock_skew: Option<u32>,
+}
+
+/// Liveness state machine of the node the wallet is connected to.
+#[derive(Debug, Default)]
+pub struct NodeWatchdog {
+    stall_epochs: u32,
+    clock_skew_epochs: u32,
+    progressed: bool,
+    status: NodeLiveness,
+}
+
+impl NodeWatchdog {
+    /// Create a watchdog that considers the node stalled after `stall_epochs` epochs without
+    /// progress, and its clock skewed if it is more than `clock_skew_epochs` epochs ahead.
+    pub fn new(stall_epochs: u32, clock_skew_epochs: u32) -> Self {
+        Self {
+            stall_epochs,
+            clock_skew_epochs,
+            ..Self::default()
+        }
+    }
+
+    /// Record a block or superblock notified by the node at `timestamp`.
+    pub fn record_notification(&mut self, timestamp: i64) -> Option<Alert> {
+        self.status.last_notification = Some(timestamp);
+
+        self.record_progress()
+    }
+
+    /// Record the current epoch reported by the node, which is progress if it is newer than the
+    /// last one, and check it against `expected_epoch`, the one given by the clock of the wallet.
+    ///
+    /// The skew is only alerted about when it is first detected.
+    pub fn record_epoch(
+        &mut self,
+        current_epoch: Option<u32>,
+        expected_epoch: Option<u32>,
+    ) -> Vec<Alert> {
+        let mut alerts = vec![];
+        let current_epoch = match current_epoch {
+            Some(current_epoch) => current_epoch,
+            None => return alerts,
+        };
+
+        if self
+            .status
+            .last_epoch
+            .map_or(true, |last_epoch| current_epoch > last_epoch)
+        {
+            alerts.extend(self.record_progress());
+        }
+        self.status.last_epoch = Some(current_epoch);
+
+        // Epoch 0 being in the future means that the node is ahead by at least one epoch
+        let ahead = match expected_epoch {
+            Some(expected_epoch) => current_epoch.saturating_sub(expected_epoch),
+            None => current_epoch.saturating_add(1),
+        };
+        let clock_skew = Some(ahead).filter(|ahead| *ahead > self.clock_skew_epochs);
+        if clock_skew.is_some() && self.status.clock_skew.is_none() {
+            alerts.push(Alert::ClockSkew(ahead));
+        }
+        self.status.clock_skew = clock_skew;
+
+        alerts
+    }
+
+    /// Count an epoch that passed, considering the node stalled if it made no progress for too
+    /// many of them. The stall is only alerted about when it starts.
+    pub fn tick(&mut self) -> Option<Alert> {
+        if std::mem::take(&mut self.progressed) {
+            self.status.epochs_without_progress = 0;
+
+            return None;
+        }
+
+        self.status.epochs_without_progress += 1;
+        if self.status.stalled || self.status.epochs_without_progress < self.stall_epochs {
+            return None;
+        }
+        self.status.stalled = true;
+
+        Some(Alert::Stalled(self.status.epochs_without_progress))
+    }
+
+    /// Snapshot of the liveness of the node.
+    pub fn status(&self) -> NodeLiveness {
+        self.status.clone()
+    }
+
+    fn record_progress(&mut self) -> Option<Alert> {
+        self.progressed = true;
+        self.status.epochs_without_progress = 0;
+        if !std::mem::take(&mut self.status.stalled) {
+            return None;
+        }
+
+        Some(Alert::Resumed)
+    }
+}
+
+/// Epoch that should be current at `timestamp`, or `None` if epoch 0 has not started yet.
+pub fn expected_epoch(timestamp: i64, checkpoint_zero_timestamp: i64, period: u16) -> Option<u32> {
+    let seconds_from_genesis = timestamp.checked_sub(checkpoint_zero_timestamp)?;
+    if seconds_from_genesis < 0 || period == 0 {
+        return None;
+    }
+
+    Some(u32::try_from(seconds_from_genesis / i64::from(period)).unwrap_or(u32::MAX))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn node_stalls_after_epochs_without_progress() {
+        let mut watchdog = NodeWatchdog::new(3, 2);
+        assert_eq!(watchdog.record_epoch(Some(10), Some(10)), vec![]);
+
+        // The epoch in which progress was made does not count
+        assert_eq!(watchdog.tick(), None);
+        assert_eq!(watchdog.tick(), None);
+        assert_eq!(watchdog.tick(), None);
+        assert_eq!(watchdog.tick(), Some(Alert::Stalled(3)));
+        assert_eq!(watchdog.tick(), None);
+        assert!(watchdog.status().stalled);
+        assert_eq!(watchdog.status().epochs_without_progress, 4);
+
+        // Reporting the same epoch again is no progress
+        assert_eq!(watchdog.record_epoch(Some(10), Some(14)), vec![]);
+        assert!(watchdog.status().stalled);
+
+        assert_eq!(watchdog.record_notification(1_000), Some(Alert::Resumed));
+        assert_eq!(
+            watchdog.status(),
+            NodeLiveness {
+                stalled: false,
+                epochs_without_progress: 0,
+                last_epoch: Some(10),
+                last_notification: Some(1_000),
+                clock_skew: None,
+            }
+        );
+        assert_eq!(watchdog.tick(), None);
+        assert_eq!(watchdog.tick(), None);
+    }
+
+    #[test]
+    fn newer_epochs_are_progress() {
+        let mut watchdog = NodeWatchdog::new(1, 2);
+        assert_eq!(watchdog.tick(), Some(Alert::Stalled(1)));
+
+        assert_eq!(
+            watchdog.record_epoch(Some(5), Some(5)),
+            vec![Alert::Resumed]
+        );
+        assert_eq!(watchdog.tick(), None);
+        assert_eq!(watchdog.record_epoch(Some(6), Some(6)), vec![]);
+        assert_eq!(watchdog.tick(), None);
+        assert_eq!(watchdog.tick(), Some(Alert::Stalled(1)));
+    }
+
+    #[test]
+    fn epochs_ahead_of_the_clock_are_skewed() {
+        let mut watchdog = NodeWatchdog::new(3, 2);
+
+        assert_eq!(watchdog.record_epoch(Some(102), Some(100)), vec![]);
+        assert_eq!(watchdog.status().clock_skew, None);
+        assert_eq!(
+            watchdog.record_epoch(Some(110), Some(100)),
+            vec![Alert::ClockSkew(10)]
+        );
+        assert_eq!(watchdog.record_epoch(Some(111), Some(100)), vec![]);
+        assert_eq!(watchdog.status().clock_skew, Some(11));
+
+        // Epochs behind the clock are a node that is syncing, not a skewed one
+        assert_eq!(watchdog.record_epoch(Some(90), Some(100)), vec![]);
+        assert_eq!(watchdog.status().clock_skew, None);
+
+        assert_eq!(
+            watchdog.record_epoch(Some(5), None),
+            vec![Alert::ClockSkew(6)]
+        );
+    }
+
+    #[test]
+    fn expected_epochs_follow_the_clock() {
+        assert_eq!(expected_epoch(1_000, 1_000, 45), Some(0));
+        assert_eq!(expected_epoch(1_089, 1_000, 45), Some(1));
+        assert_eq!(expected_epoch(1_090, 1_000, 45), Some(2));
+        assert_eq!(expected_epoch(999, 1_000, 45), None);
+    }
+}
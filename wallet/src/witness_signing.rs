@@ -0,0 +1,267 @@
+//! Validation of the transactions that a node asks the wallet to sign on its behalf.
+//!
+//! Nodes that keep the funds of their identity in the wallet do not hold its keys, so they ask the
+//! wallet to sign their commits and reveals instead. As the node is trusted less than the owner of
+//! the wallet, the payloads are checked to be a commit or a reveal for a data request whose value
+//! can only go back to the signer, and anything that could move funds elsewhere is rejected.
+use failure::Fail;
+use serde_json::Value;
+
+use witnet_data_structures::{
+    chain::{Hash, Hashable, Input, PublicKeyHash, ValueTransferOutput},
+    transaction::{CommitTransactionBody, RevealTransactionBody},
+};
+
+use crate::model::WitnessTransactionType;
+
+/// Fields of the body of a commit transaction.
+const COMMIT_FIELDS: &[&str] = &[
+    "dr_pointer",
+    "commitment",
+    "proof",
+    "collateral",
+    "outputs",
+    "bn256_public_key",
+];
+
+/// Fields of the body of a reveal transaction.
+const REVEAL_FIELDS: &[&str] = &["dr_pointer", "reveal", "pkh"];
+
+/// Types of the transactions that spend or create value, which are never signed for a node.
+const FUNDS_MOVING_TYPES: &[&str] = &["value_transfer", "data_request", "tally", "mint"];
+
+/// A payload that the wallet refuses to sign for a node.
+#[derive(Debug, PartialEq, Eq, Fail)]
+pub enum PayloadError {
+    #[fail(display = "the payload could move funds: {}", _0)]
+    MovesFunds(String),
+    #[fail(display = "the payload is not a commit or reveal transaction: {}", _0)]
+    Invalid(String),
+}
+
+/// Body of a transaction of a witness, as sent by the node in a payload of the form
+/// `{ "type": "commit" | "reveal", "body": { ... } }`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum WitnessTransactionBody {
+    Commit(CommitTransactionBody),
+    Reveal(RevealTransactionBody),
+}
+
+impl WitnessTransactionBody {
+    /// Parse and check a payload sent by a node.
+    pub fn from_payload(payload: &Value) -> Result<Self, PayloadError> {
+        let kind = payload
+            .get("type")
+            .and_then(Value::as_str)
+            .ok_or_else(|| PayloadError::Invalid("missing type".to_string()))?;
+        let body = payload
+            .get("body")
+            .and_then(Value::as_object)
+            .ok_or_else(|| PayloadError::Invalid("missing body".to_string()))?;
+
+        let fields = match kind {
+            "commit" => COMMIT_FIELDS,
+            "reveal" => REVEAL_FIELDS,
+            kind if FUNDS_MOVING_TYPES.contains(&kind) => {
+                return Err(PayloadError::MovesFunds(format!(
+                    "{} transactions cannot be signed remotely",
+                    kind
+                )));
+            }
+            kind => {
+                return Err(PayloadError::Invalid(format!(
+                    "unknown transaction type {}",
+                    kind
+                )));
+            }
+        };
+        // Value transfers disguised as witness transactions have inputs
+        if body.contains_key("inputs") {
+            return Err(PayloadError::MovesFunds(
+                "the body has the inputs of a value transfer".to_string(),
+            ));
+        }
+        if let Some(field) = body.keys().find(|field| !fields.contains(&field.as_str())) {
+            return Err(PayloadError::Invalid(format!(
+                "unknown field {} in a {} body",
+                field, kind
+            )));
+        }
+
+        let body = Value::Object(body.clone());
+        let body = match kind {
+            "commit" => serde_json::from_value(body).map(WitnessTransactionBody::Commit),
+            _ => serde_json::from_value(body).map(WitnessTransactionBody::Reveal),
+        }
+        .map_err(|err| PayloadError::Invalid(err.to_string()))?;
+
+        if body.dr_pointer() == Hash::default() {
+            return Err(PayloadError::Invalid(
+                "the transaction does not reference a data request".to_string(),
+            ));
+        }
+        // The only output of a commit is the change of its collateral, which goes back to the
+        // committer
+        let signer = body.signer();
+        if body.outputs().len() > 1 {
+            return Err(PayloadError::MovesFunds(
+                "a commit can only have one output".to_string(),
+            ));
+        }
+        if body.outputs().iter().any(|output| output.pkh != signer) {
+            return Err(PayloadError::MovesFunds(
+                "the output of a commit must pay back to the committer".to_string(),
+            ));
+        }
+
+        Ok(body)
+    }
+
+    pub fn transaction_type(&self) -> WitnessTransactionType {
+        match self {
+            WitnessTransactionBody::Commit(_) => WitnessTransactionType::Commit,
+            WitnessTransactionBody::Reveal(_) => WitnessTransactionType::Reveal,
+        }
+    }
+
+    /// Hash of the body, which is what gets signed.
+    pub fn hash(&self) -> Hash {
+        match self {
+            WitnessTransactionBody::Commit(body) => body.hash(),
+            WitnessTransactionBody::Reveal(body) => body.hash(),
+        }
+    }
+
+    /// Hash of the data request the transaction is for.
+    pub fn dr_pointer(&self) -> Hash {
+        match self {
+            WitnessTransactionBody::Commit(body) => body.dr_pointer,
+            WitnessTransactionBody::Reveal(body) => body.dr_pointer,
+        }
+    }
+
+    /// Address whose key must sign the transaction: the one of the eligibility proof of a commit,
+    /// or the one receiving the reward of a reveal.
+    pub fn signer(&self) -> PublicKeyHash {
+        match self {
+            WitnessTransactionBody::Commit(body) => body.proof.proof.pkh(),
+            WitnessTransactionBody::Reveal(body) => body.pkh,
+        }
+    }
+
+    /// Inputs locked as collateral by a commit.
+    pub fn collateral(&self) -> &[Input] {
+        match self {
+            WitnessTransactionBody::Commit(body) => &body.collateral,
+            WitnessTransactionBody::Reveal(_) => &[],
+        }
+    }
+
+    /// Nanowits of the collateral that a commit returns to the committer.
+    pub fn change(&self) -> u64 {
+        self.outputs().iter().map(|output| output.value).sum()
+    }
+
+    fn outputs(&self) -> &[ValueTransferOutput] {
+        match self {
+            WitnessTransactionBody::Commit(body) => &body.outputs,
+            WitnessTransactionBody::Reveal(_) => &[],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn dr_pointer() -> Hash {
+        Hash::SHA256([1; 32])
+    }
+
+    fn reveal_payload(pkh: PublicKeyHash) -> Value {
+        json!({
+            "type": "reveal",
+            "body": RevealTransactionBody::new(dr_pointer(), vec![0x18, 0x2a], pkh),
+        })
+    }
+
+    #[test]
+    fn witness_transactions_are_parsed() {
+        let pkh = PublicKeyHash::from_bytes(&[2; 20]).unwrap();
+        let body = WitnessTransactionBody::from_payload(&reveal_payload(pkh)).unwrap();
+
+        assert_eq!(body.transaction_type(), WitnessTransactionType::Reveal);
+        assert_eq!(body.signer(), pkh);
+        assert_eq!(body.dr_pointer(), dr_pointer());
+
+        let commit = CommitTransactionBody {
+            dr_pointer: dr_pointer(),
+            ..Default::default()
+        };
+        let payload = json!({ "type": "commit", "body": commit });
+        let body = WitnessTransactionBody::from_payload(&payload).unwrap();
+        assert_eq!(body, WitnessTransactionBody::Commit(commit));
+    }
+
+    #[test]
+    fn payloads_that_move_funds_are_rejected() {
+        let pkh = PublicKeyHash::from_bytes(&[2; 20]).unwrap();
+        let thief = PublicKeyHash::from_bytes(&[3; 20]).unwrap();
+        let output = |pkh| ValueTransferOutput {
+            pkh,
+            value: 1_000,
+            time_lock: 0,
+        };
+        let moves_funds = |payload: Value| {
+            matches!(
+                WitnessTransactionBody::from_payload(&payload),
+                Err(PayloadError::MovesFunds(_))
+            )
+        };
+
+        assert!(moves_funds(json!({
+            "type": "value_transfer",
+            "body": { "inputs": [], "outputs": [output(thief)] },
+        })));
+        assert!(moves_funds(json!({
+            "type": "commit",
+            "body": { "dr_pointer": dr_pointer(), "inputs": [], "outputs": [output(thief)] },
+        })));
+        let commit = CommitTransactionBody {
+            dr_pointer: dr_pointer(),
+            outputs: vec![output(thief)],
+            ..Default::default()
+        };
+        assert!(moves_funds(json!({ "type": "commit", "body": commit })));
+        let commit = CommitTransactionBody {
+            dr_pointer: dr_pointer(),
+            outputs: vec![output(pkh), output(pkh)],
+            ..Default::default()
+        };
+        assert!(moves_funds(json!({ "type": "commit", "body": commit })));
+    }
+
+    #[test]
+    fn malformed_payloads_are_rejected() {
+        let pkh = PublicKeyHash::from_bytes(&[2; 20]).unwrap();
+        let invalid = |payload: Value| {
+            matches!(
+                WitnessTransactionBody::from_payload(&payload),
+                Err(PayloadError::Invalid(_))
+            )
+        };
+
+        assert!(invalid(json!({ "body": {} })));
+        assert!(invalid(json!({ "type": "stake", "body": {} })));
+        let mut payload = reveal_payload(pkh);
+        payload["body"]["fee"] = json!(1);
+        assert!(invalid(payload));
+        let payload = json!({
+            "type": "reveal",
+            "body": RevealTransactionBody::new(Hash::default(), vec![], pkh),
+        });
+        assert!(invalid(payload));
+    }
+}
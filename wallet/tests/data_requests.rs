@@ -55,7 +55,8 @@ fn test_radon_types_json_serialization() {
 #[test]
 fn test_radon_error_json_serialization() {
     let radon_error = RadonTypes::RadonError(RadonError::new(RadError::default()));
-    let expected_json = r#"{"RadonError":"Unknown error"}"#;
+    let expected_json =
+        r#"{"RadonError":{"kind":"unknown","message":"Unknown error","fields":{}}}"#;
     assert_eq!(serde_json::to_string(&radon_error).unwrap(), expected_json);
 }
 